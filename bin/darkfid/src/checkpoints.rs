@@ -0,0 +1,213 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2025 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use std::{collections::BTreeMap, str::FromStr};
+
+use darkfi::{
+    blockchain::{Blockchain, HeaderHash},
+    Error, Result,
+};
+use log::info;
+
+/// Release-embedded localnet checkpoints, as `(height, hash)`
+const LOCALNET_CHECKPOINTS: &[(u32, &str)] = &[];
+
+/// Release-embedded testnet checkpoints, as `(height, hash)`.
+/// Updated on each release to pin blocks finalized at release time.
+const TESTNET_CHECKPOINTS: &[(u32, &str)] = &[];
+
+/// Release-embedded mainnet checkpoints, as `(height, hash)`.
+/// Updated on each release to pin blocks finalized at release time.
+const MAINNET_CHECKPOINTS: &[(u32, &str)] = &[];
+
+/// Auxiliary function to retrieve the release-embedded checkpoints of given network.
+pub fn embedded_checkpoints(network: &str) -> Result<Vec<(u32, HeaderHash)>> {
+    let checkpoints = match network {
+        "localnet" => LOCALNET_CHECKPOINTS,
+        "testnet" => TESTNET_CHECKPOINTS,
+        "mainnet" => MAINNET_CHECKPOINTS,
+        _ => return Err(Error::UnsupportedChain),
+    };
+
+    let mut ret = Vec::with_capacity(checkpoints.len());
+    for (height, hash) in checkpoints {
+        ret.push((*height, HeaderHash::from_str(hash)?));
+    }
+
+    Ok(ret)
+}
+
+/// Auxiliary function to parse a checkpoint in the `height:hash` format.
+pub fn parse_checkpoint(checkpoint: &str) -> Result<(u32, HeaderHash)> {
+    let Some((height, hash)) = checkpoint.split_once(':') else {
+        return Err(Error::ParseFailed("Invalid checkpoint, expected `height:hash`"))
+    };
+
+    let Ok(height) = height.trim().parse::<u32>() else {
+        return Err(Error::ParseFailed("Invalid checkpoint height"))
+    };
+
+    Ok((height, HeaderHash::from_str(hash.trim())?))
+}
+
+/// Origin of a pinned checkpoint
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CheckpointSource {
+    /// Embedded in the release
+    Embedded,
+    /// Configured by the node operator
+    Configured,
+    /// Automatically pinned finalized block
+    Finalized,
+}
+
+impl CheckpointSource {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Embedded => "embedded",
+            Self::Configured => "configured",
+            Self::Finalized => "finalized",
+        }
+    }
+}
+
+/// Block checkpoints the node enforces during sync. Peers and headers
+/// contradicting any of them get rejected, protecting the node from
+/// long-range forks fed by its peers.
+#[derive(Debug, Default)]
+pub struct Checkpoints {
+    /// Pinned checkpoints, keyed by block height
+    pinned: BTreeMap<u32, (HeaderHash, CheckpointSource)>,
+    /// Depth below our last block at which finalized blocks get pinned.
+    /// Zero disables automatic pinning.
+    depth: u32,
+}
+
+impl Checkpoints {
+    pub fn new(depth: u32) -> Self {
+        Self { pinned: BTreeMap::new(), depth }
+    }
+
+    /// Pin a checkpoint. Fails if it contradicts an already pinned one.
+    pub fn pin(&mut self, height: u32, hash: HeaderHash, source: CheckpointSource) -> Result<()> {
+        if let Some((pinned, _)) = self.pinned.get(&height) {
+            if pinned != &hash {
+                return Err(Error::Custom(format!(
+                    "Checkpoint {height} - {hash} contradicts pinned checkpoint {pinned}"
+                )))
+            }
+            return Ok(())
+        }
+
+        self.pinned.insert(height, (hash, source));
+        Ok(())
+    }
+
+    /// Check that the given block doesn't contradict any pinned checkpoint.
+    pub fn verify(&self, height: u32, hash: &HeaderHash) -> bool {
+        match self.pinned.get(&height) {
+            Some((pinned, _)) => pinned == hash,
+            None => true,
+        }
+    }
+
+    /// Retrieve the highest pinned checkpoint, if any.
+    pub fn highest(&self) -> Option<(u32, HeaderHash)> {
+        self.pinned.last_key_value().map(|(height, (hash, _))| (*height, *hash))
+    }
+
+    /// Iterate over the pinned checkpoints, ordered by height.
+    pub fn iter(&self) -> impl Iterator<Item = (&u32, &(HeaderHash, CheckpointSource))> {
+        self.pinned.iter()
+    }
+
+    /// Pin the canonical block `depth` blocks below our last one,
+    /// replacing the previous automatically pinned block.
+    pub fn pin_finalized(&mut self, blockchain: &Blockchain) -> Result<()> {
+        if self.depth == 0 {
+            return Ok(())
+        }
+
+        let (last, _) = blockchain.last()?;
+        if last < self.depth {
+            return Ok(())
+        }
+
+        let height = last - self.depth;
+        let hash = blockchain.blocks.get_order(&[height], true)?[0].unwrap();
+        self.pinned.retain(|_, (_, source)| *source != CheckpointSource::Finalized);
+        self.pin(height, hash, CheckpointSource::Finalized)?;
+        info!(target: "darkfid::checkpoints::pin_finalized", "Pinned finalized block: {height} - {hash}");
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn checkpoints_parse() {
+        let hash = HeaderHash::new(blake3::hash(b"foo").into());
+
+        assert_eq!(parse_checkpoint(&format!("42:{hash}")).unwrap(), (42, hash));
+        assert_eq!(parse_checkpoint(&format!(" 42 : {hash} ")).unwrap(), (42, hash));
+        assert!(parse_checkpoint(&hash.to_string()).is_err());
+        assert!(parse_checkpoint(&format!("-1:{hash}")).is_err());
+        assert!(parse_checkpoint(&format!("foo:{hash}")).is_err());
+        assert!(parse_checkpoint("42:foo").is_err());
+
+        // Release-embedded checkpoints must always parse
+        for network in ["localnet", "testnet", "mainnet"] {
+            embedded_checkpoints(network).unwrap();
+        }
+        assert!(embedded_checkpoints("devnet").is_err());
+    }
+
+    #[test]
+    fn checkpoints_pin() {
+        let foo = HeaderHash::new(blake3::hash(b"foo").into());
+        let bar = HeaderHash::new(blake3::hash(b"bar").into());
+
+        let mut checkpoints = Checkpoints::new(0);
+        assert!(checkpoints.highest().is_none());
+        assert!(checkpoints.verify(1, &foo));
+
+        checkpoints.pin(1, foo, CheckpointSource::Embedded).unwrap();
+        checkpoints.pin(5, bar, CheckpointSource::Configured).unwrap();
+        assert_eq!(checkpoints.highest(), Some((5, bar)));
+
+        // Blocks contradicting a pinned checkpoint are rejected,
+        // unpinned heights are accepted
+        assert!(checkpoints.verify(1, &foo));
+        assert!(!checkpoints.verify(1, &bar));
+        assert!(!checkpoints.verify(5, &foo));
+        assert!(checkpoints.verify(3, &foo));
+
+        // Pinning a conflicting checkpoint fails, while pinning the same
+        // one again keeps its original source
+        assert!(checkpoints.pin(1, bar, CheckpointSource::Configured).is_err());
+        checkpoints.pin(1, foo, CheckpointSource::Finalized).unwrap();
+        let pinned: Vec<_> = checkpoints.iter().map(|(h, (_, s))| (*h, *s)).collect();
+        assert_eq!(
+            pinned,
+            vec![(1, CheckpointSource::Embedded), (5, CheckpointSource::Configured)]
+        );
+    }
+}
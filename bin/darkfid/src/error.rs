@@ -16,54 +16,36 @@
  * along with this program.  If not, see <https://www.gnu.org/licenses/>.
  */
 
-use darkfi::rpc::jsonrpc::{ErrorCode::ServerError, JsonError, JsonResult};
+use darkfi::{rpc::error_code::DARKFID, rpc_error_codes};
 
-/// Custom RPC errors available for darkfid.
-/// Please sort them sensefully.
-pub enum RpcError {
-    // Transaction-related errors
-    TxSimulationFail = -32110,
-    TxGasCalculationFail = -32111,
-
-    // State-related errors,
-    NotSynced = -32120,
-    UnknownBlockHeight = -32121,
+rpc_error_codes! {
+    /// Custom RPC errors available for darkfid.
+    /// Please sort them sensefully.
+    pub enum RpcError in DARKFID {
+        // Transaction-related errors
+        TxSimulationFail = 10 => "Failed simulating transaction state change",
+        TxGasCalculationFail = 11 => "Failed to calculate transaction's gas",
+        TxAdmissionRejected = 12 => "Transaction rejected by mempool admission policy",
+        TxNotFound = 13 => "Did not find transaction",
 
-    // Parsing errors
-    ParseError = -32190,
+        // State-related errors
+        NotSynced = 20 => "Blockchain is not synced",
+        UnknownBlockHeight = 21 => "Did not find block height",
 
-    // Contract-related errors
-    ContractZkasDbNotFound = -32200,
+        // Development mode errors
+        DevnetOnly = 30 => "Method is only available on localnet",
+        BlockGenerationFail = 31 => "Failed generating blocks",
+        BlockInvalidationFail = 32 => "Failed invalidating block",
+        BlockReconsiderationFail = 33 => "Failed reconsidering block",
 
-    // Misc errors
-    PingFailed = -32300,
-}
-
-fn to_tuple(e: RpcError) -> (i32, String) {
-    let msg = match e {
-        // Transaction-related errors
-        RpcError::TxSimulationFail => "Failed simulating transaction state change",
-        RpcError::TxGasCalculationFail => "Failed to calculate transaction's gas",
-        // State-related errors
-        RpcError::NotSynced => "Blockchain is not synced",
-        RpcError::UnknownBlockHeight => "Did not find block height",
         // Parsing errors
-        RpcError::ParseError => "Parse error",
-        // Contract-related errors
-        RpcError::ContractZkasDbNotFound => "zkas database not found for given contract",
-        // Misc errors
-        RpcError::PingFailed => "Miner daemon ping error",
-    };
-
-    (e as i32, msg.to_string())
-}
+        ParseError = 90 => "Parse error",
 
-pub fn server_error(e: RpcError, id: u16, msg: Option<&str>) -> JsonResult {
-    let (code, default_msg) = to_tuple(e);
+        // Contract-related errors
+        ContractZkasDbNotFound = 100 => "zkas database not found for given contract",
+        ContractStateNotFound = 101 => "State tree not found for given contract",
 
-    if let Some(message) = msg {
-        return JsonError::new(ServerError(code), Some(message.to_string()), id).into()
+        // Misc errors
+        PingFailed = 200 => "Miner daemon ping error",
     }
-
-    JsonError::new(ServerError(code), Some(default_msg), id).into()
 }
@@ -0,0 +1,280 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2025 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Server-side filters for `blockchain.subscribe_blocks` subscriptions.
+//!
+//! Special-purpose clients, like DAO dashboards, usually only care about
+//! calls to specific contracts or contract functions. They can register a
+//! [`BlockFilter`] when subscribing, so we only push them the blocks that
+//! contain matching transactions, trimmed down to just those transactions.
+
+use std::{collections::HashMap, str::FromStr, sync::Arc};
+
+use log::error;
+use tinyjson::JsonValue;
+
+use darkfi::{
+    blockchain::BlockInfo,
+    rpc::jsonrpc::{JsonNotification, JsonNotificationFilter},
+    tx::Transaction,
+    util::encoding::base64,
+};
+use darkfi_sdk::crypto::{ContractId, FuncId, FuncRef};
+use darkfi_serial::{deserialize, serialize};
+
+/// Filter of the transactions a blocks subscription is interested in
+#[derive(Clone, Debug, Default)]
+pub struct BlockFilter {
+    /// Contract IDs whose calls are of interest
+    contracts: Vec<ContractId>,
+    /// Function IDs whose calls are of interest
+    functions: Vec<FuncId>,
+}
+
+impl BlockFilter {
+    /// Parse a filter from a JSON object of the form:
+    /// `{"contracts": ["contract_id", ...], "functions": ["func_id", ...]}`,
+    /// where both fields are optional, base58-encoded identifiers.
+    /// Returns `None` if the object is malformed or matches nothing.
+    pub fn from_json(value: &JsonValue) -> Option<Self> {
+        let map = value.get::<HashMap<String, JsonValue>>()?;
+
+        let mut filter = Self::default();
+        for (key, ids) in map {
+            let ids = ids.get::<Vec<JsonValue>>()?;
+            for id in ids {
+                let id = id.get::<String>()?;
+                match key.as_str() {
+                    "contracts" => filter.contracts.push(ContractId::from_str(id).ok()?),
+                    "functions" => filter.functions.push(FuncId::from_str(id).ok()?),
+                    _ => return None,
+                }
+            }
+        }
+
+        if filter.contracts.is_empty() && filter.functions.is_empty() {
+            return None
+        }
+
+        Some(filter)
+    }
+
+    /// Check if any of the transaction calls matches the filter
+    pub fn matches(&self, tx: &Transaction) -> bool {
+        tx.calls.iter().any(|call| {
+            let contract_id = call.data.contract_id;
+            if self.contracts.contains(&contract_id) {
+                return true
+            }
+
+            let Some(func_code) = call.data.data.first() else { return false };
+            let func_id = FuncRef { contract_id, func_code: *func_code }.to_func_id();
+            self.functions.contains(&func_id)
+        })
+    }
+
+    /// Trim a `blockchain.subscribe_blocks` notification down to the blocks
+    /// containing matching transactions, keeping only those transactions.
+    /// Returns `None` if no block matched.
+    pub fn apply(&self, notification: &JsonNotification) -> Option<JsonNotification> {
+        let blocks = notification.params.get::<Vec<JsonValue>>()?;
+
+        let mut matched = vec![];
+        for block in blocks {
+            let Some(bytes) = base64::decode(block.get::<String>()?) else {
+                error!(target: "darkfid::filter", "Failed decoding notified block");
+                continue
+            };
+            let Ok(mut block) = deserialize::<BlockInfo>(&bytes) else {
+                error!(target: "darkfid::filter", "Failed deserializing notified block");
+                continue
+            };
+
+            block.txs.retain(|tx| self.matches(tx));
+            if block.txs.is_empty() {
+                continue
+            }
+
+            matched.push(JsonValue::String(base64::encode(&serialize(&block))));
+        }
+
+        if matched.is_empty() {
+            return None
+        }
+
+        Some(JsonNotification::new(&notification.method, JsonValue::Array(matched)))
+    }
+
+    /// Convert the filter into a [`JsonNotificationFilter`]
+    pub fn into_notification_filter(self) -> JsonNotificationFilter {
+        Arc::new(move |notification| self.apply(notification))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use darkfi_sdk::{
+        crypto::contract_id::{DAO_CONTRACT_ID, MONEY_CONTRACT_ID},
+        dark_tree::DarkLeaf,
+        tx::ContractCall,
+    };
+
+    use super::*;
+
+    fn ids(fields: &[(&str, &[String])]) -> JsonValue {
+        let fields = fields
+            .iter()
+            .map(|(key, ids)| {
+                let ids = ids.iter().map(|id| JsonValue::String(id.clone())).collect();
+                (key.to_string(), JsonValue::Array(ids))
+            })
+            .collect();
+        JsonValue::Object(fields)
+    }
+
+    fn func_id(contract_id: ContractId, func_code: u8) -> FuncId {
+        FuncRef { contract_id, func_code }.to_func_id()
+    }
+
+    /// Create a transaction with a call per given contract call data
+    fn tx(calls: &[(ContractId, Vec<u8>)]) -> Transaction {
+        let calls = calls
+            .iter()
+            .map(|(contract_id, data)| DarkLeaf {
+                data: ContractCall { contract_id: *contract_id, data: data.clone() },
+                parent_index: None,
+                children_indexes: vec![],
+            })
+            .collect();
+        Transaction { calls, ..Default::default() }
+    }
+
+    fn notification(blocks: &[BlockInfo]) -> JsonNotification {
+        let blocks = blocks
+            .iter()
+            .map(|block| JsonValue::String(base64::encode(&serialize(block))))
+            .collect();
+        JsonNotification::new("blockchain.subscribe_blocks", JsonValue::Array(blocks))
+    }
+
+    fn notified_blocks(notification: &JsonNotification) -> Vec<BlockInfo> {
+        notification
+            .params
+            .get::<Vec<JsonValue>>()
+            .unwrap()
+            .iter()
+            .map(|b| deserialize(&base64::decode(b.get::<String>().unwrap()).unwrap()).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn block_filter_from_json() {
+        let money = MONEY_CONTRACT_ID.to_string();
+        let dao_func = func_id(*DAO_CONTRACT_ID, 3).to_string();
+
+        let filter = BlockFilter::from_json(&ids(&[("contracts", &[money.clone()])])).unwrap();
+        assert_eq!(filter.contracts, vec![*MONEY_CONTRACT_ID]);
+        assert!(filter.functions.is_empty());
+
+        let filter = BlockFilter::from_json(&ids(&[("functions", &[dao_func.clone()])])).unwrap();
+        assert_eq!(filter.functions, vec![func_id(*DAO_CONTRACT_ID, 3)]);
+        assert!(filter.contracts.is_empty());
+
+        let both = ids(&[("contracts", &[money.clone()]), ("functions", &[dao_func])]);
+        let filter = BlockFilter::from_json(&both).unwrap();
+        assert_eq!((filter.contracts.len(), filter.functions.len()), (1, 1));
+
+        // Filters matching nothing are rejected
+        assert!(BlockFilter::from_json(&JsonValue::Object(HashMap::new())).is_none());
+        assert!(BlockFilter::from_json(&ids(&[("contracts", &[])])).is_none());
+
+        // So are malformed ones
+        assert!(BlockFilter::from_json(&JsonValue::Array(vec![])).is_none());
+        assert!(BlockFilter::from_json(&ids(&[("tokens", &[money.clone()])])).is_none());
+        assert!(BlockFilter::from_json(&ids(&[("contracts", &["foo".to_string()])])).is_none());
+        let invalid = ids(&[("contracts", &[money.clone(), "foo".to_string()])]);
+        assert!(BlockFilter::from_json(&invalid).is_none());
+        let not_array = JsonValue::Object(HashMap::from([(
+            "contracts".to_string(),
+            JsonValue::String(money.clone()),
+        )]));
+        assert!(BlockFilter::from_json(&not_array).is_none());
+        let not_string = JsonValue::Object(HashMap::from([(
+            "contracts".to_string(),
+            JsonValue::Array(vec![JsonValue::Number(42.0)]),
+        )]));
+        assert!(BlockFilter::from_json(&not_string).is_none());
+    }
+
+    #[test]
+    fn block_filter_matches() {
+        let by_contract = BlockFilter { contracts: vec![*MONEY_CONTRACT_ID], functions: vec![] };
+        let by_function =
+            BlockFilter { contracts: vec![], functions: vec![func_id(*DAO_CONTRACT_ID, 3)] };
+
+        // Any call to the contract matches
+        let money = tx(&[(*MONEY_CONTRACT_ID, vec![0, 1, 2])]);
+        assert!(by_contract.matches(&money));
+        assert!(!by_function.matches(&money));
+        assert!(by_contract.matches(&tx(&[(*MONEY_CONTRACT_ID, vec![])])));
+
+        // Only calls to the exact contract function match
+        let dao_exec = tx(&[(*DAO_CONTRACT_ID, vec![3, 4])]);
+        assert!(by_function.matches(&dao_exec));
+        assert!(!by_contract.matches(&dao_exec));
+        assert!(!by_function.matches(&tx(&[(*DAO_CONTRACT_ID, vec![2, 3])])));
+        assert!(!by_function.matches(&tx(&[(*MONEY_CONTRACT_ID, vec![3])])));
+        assert!(!by_function.matches(&tx(&[(*DAO_CONTRACT_ID, vec![])])));
+
+        // A single matching call is enough
+        let both = tx(&[(*DAO_CONTRACT_ID, vec![2]), (*MONEY_CONTRACT_ID, vec![0])]);
+        assert!(by_contract.matches(&both));
+        assert!(!by_function.matches(&both));
+        assert!(!by_contract.matches(&Transaction::default()));
+    }
+
+    #[test]
+    fn block_filter_apply() {
+        let filter = BlockFilter { contracts: vec![*MONEY_CONTRACT_ID], functions: vec![] };
+        let money = tx(&[(*MONEY_CONTRACT_ID, vec![0])]);
+        let dao = tx(&[(*DAO_CONTRACT_ID, vec![0])]);
+
+        let mixed =
+            BlockInfo { txs: vec![dao.clone(), money.clone(), dao.clone()], ..Default::default() };
+        let unrelated = BlockInfo { txs: vec![dao], ..Default::default() };
+
+        // Unmatched blocks get dropped, and matched ones trimmed down
+        let trimmed = filter.apply(&notification(&[unrelated.clone(), mixed.clone()])).unwrap();
+        assert_eq!(trimmed.method, "blockchain.subscribe_blocks");
+        let blocks = notified_blocks(&trimmed);
+        assert_eq!(blocks.len(), 1);
+        assert_eq!(blocks[0].hash(), mixed.hash());
+        assert!(blocks[0].txs == vec![money]);
+
+        // Nothing gets notified when no block matches
+        assert!(filter.apply(&notification(&[unrelated.clone()])).is_none());
+        assert!(filter.apply(&notification(&[])).is_none());
+
+        // Malformed blocks are skipped
+        let mut malformed = notification(&[mixed]);
+        let JsonValue::Array(ref mut blocks) = malformed.params else { unreachable!() };
+        blocks.insert(0, JsonValue::String("foo".to_string()));
+        blocks.push(JsonValue::String(base64::encode(b"bar")));
+        assert_eq!(notified_blocks(&filter.apply(&malformed).unwrap()).len(), 1);
+    }
+}
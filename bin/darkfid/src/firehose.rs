@@ -0,0 +1,223 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2025 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! High-throughput streaming exporter of finalized blocks and mempool
+//! transactions, intended for indexers and analytics pipelines.
+//!
+//! Clients connect over websocket and receive binary frames, where the
+//! first byte tags the payload type, followed by the serialized object:
+//! * [`FIREHOSE_BLOCK`]: a finalized `BlockInfo`
+//! * [`FIREHOSE_TX`]: a `Transaction` that entered our mempool
+//!
+//! Each connection keeps its own block cursor and reads blocks directly
+//! from the blockchain database, so slow consumers simply fall behind
+//! without missing any blocks or affecting JSON-RPC subscribers. Clients
+//! can resume from a specific height by connecting with a `from` query
+//! parameter, e.g. `ws://127.0.0.1:8242/?from=1000`, otherwise streaming
+//! starts from the next finalized block.
+
+use std::{sync::Arc, time::Duration};
+
+use async_tungstenite::tungstenite::{
+    handshake::server::{ErrorResponse, Request, Response},
+    http::StatusCode,
+    Message,
+};
+use futures::{Sink, SinkExt, StreamExt};
+use log::{debug, error, info};
+use smol::{
+    future,
+    net::{TcpListener, TcpStream},
+    Executor, Task, Timer,
+};
+use tinyjson::JsonValue;
+use url::Url;
+
+use darkfi::{
+    rpc::jsonrpc::JsonNotification, system::Subscription, util::encoding::base64, Error, Result,
+};
+use darkfi_serial::serialize_async;
+
+use crate::DarkfiNode;
+
+/// Frame tag of a serialized finalized block
+pub const FIREHOSE_BLOCK: u8 = 0x00;
+/// Frame tag of a serialized mempool transaction
+pub const FIREHOSE_TX: u8 = 0x01;
+
+/// Interval to poll the blockchain for new finalized blocks
+const BLOCK_POLL_INTERVAL: Duration = Duration::from_secs(1);
+/// Maximum amount of blocks to retrieve from the database at once
+const BLOCK_BATCH_SIZE: u32 = 100;
+
+/// Accept firehose connections on `listen` and spawn a streamer
+/// for each of them. The streamer tasks are owned by this task, so
+/// stopping it also closes all the firehose connections.
+pub async fn firehose_task(
+    node: Arc<DarkfiNode>,
+    listen: Url,
+    executor: Arc<Executor<'_>>,
+) -> Result<()> {
+    let (Some(host), Some(port)) = (listen.host_str(), listen.port()) else {
+        return Err(Error::ParseFailed("Invalid firehose listen URL"))
+    };
+
+    let listener = TcpListener::bind((host, port)).await?;
+    info!(target: "darkfid::firehose", "Firehose exporter listening on {}", listen);
+
+    // Streamer tasks of the open connections, cancelled once dropped
+    let mut connections: Vec<Task<()>> = vec![];
+    loop {
+        let (stream, peer_addr) = match listener.accept().await {
+            Ok(v) => v,
+            Err(e) => {
+                error!(target: "darkfid::firehose", "Failed accepting firehose connection: {}", e);
+                continue
+            }
+        };
+
+        debug!(target: "darkfid::firehose", "Accepted firehose connection from {}", peer_addr);
+        connections.retain(|task| !task.is_finished());
+        let node_ = node.clone();
+        connections.push(executor.spawn(async move {
+            if let Err(e) = handle_connection(&node_, stream).await {
+                debug!(
+                    target: "darkfid::firehose",
+                    "Firehose connection {} closed: {}", peer_addr, e,
+                );
+            }
+        }));
+    }
+}
+
+/// Auxiliary function to parse the optional `from` height of a
+/// websocket handshake request query.
+fn parse_from(query: Option<&str>) -> std::result::Result<Option<u32>, ()> {
+    let Some(query) = query else { return Ok(None) };
+    for pair in query.split('&') {
+        if let Some(height) = pair.strip_prefix("from=") {
+            return height.parse().map(Some).map_err(|_| ())
+        }
+    }
+
+    Ok(None)
+}
+
+/// Auxiliary function to generate a tagged firehose frame.
+fn frame(tag: u8, payload: &[u8]) -> Message {
+    let mut data = Vec::with_capacity(payload.len() + 1);
+    data.push(tag);
+    data.extend_from_slice(payload);
+    Message::Binary(data)
+}
+
+/// Perform the websocket handshake and stream blocks and transactions
+/// until the client disconnects.
+async fn handle_connection(node: &DarkfiNode, stream: TcpStream) -> Result<()> {
+    let mut from = None;
+    let callback = |request: &Request, response: Response| match parse_from(request.uri().query()) {
+        Ok(height) => {
+            from = height;
+            Ok(response)
+        }
+        Err(()) => {
+            let mut response = ErrorResponse::new(Some("Invalid from height".to_string()));
+            *response.status_mut() = StatusCode::BAD_REQUEST;
+            Err(response)
+        }
+    };
+    let ws = match async_tungstenite::accept_hdr_async(stream, callback).await {
+        Ok(v) => v,
+        Err(e) => return Err(Error::Custom(format!("Websocket handshake failed: {e}"))),
+    };
+    let (mut sink, mut stream) = ws.split();
+
+    // Subscribe to mempool transactions first, so we don't miss any
+    let txs_sub = node.subscribers.get("txs").unwrap().publisher.clone().subscribe().await;
+
+    // Grab the next block height to stream
+    let next = match from {
+        Some(height) => height,
+        None => node.validator.blockchain.last()?.0 + 1,
+    };
+
+    // We don't expect anything from the client, so we just drain
+    // incoming frames until it disconnects.
+    let reader = async {
+        while let Some(msg) = stream.next().await {
+            if matches!(msg, Ok(Message::Close(_)) | Err(_)) {
+                break
+            }
+        }
+        Err(Error::Custom("Client disconnected".to_string()))
+    };
+
+    let result = future::or(reader, stream_events(node, &mut sink, next, &txs_sub)).await;
+    txs_sub.unsubscribe().await;
+    result
+}
+
+/// Stream finalized blocks starting from height `next`, along with
+/// mempool transactions, into provided sink.
+async fn stream_events<S>(
+    node: &DarkfiNode,
+    sink: &mut S,
+    mut next: u32,
+    txs_sub: &Subscription<JsonNotification>,
+) -> Result<()>
+where
+    S: Sink<Message> + Unpin,
+    S::Error: std::fmt::Display,
+{
+    let blockchain = &node.validator.blockchain;
+    loop {
+        // Stream any new finalized blocks
+        let (last, _) = blockchain.last()?;
+        while next <= last {
+            let end = last.min(next.saturating_add(BLOCK_BATCH_SIZE - 1));
+            let heights: Vec<u32> = (next..=end).collect();
+            for block in blockchain.get_blocks_by_heights(&heights)? {
+                let payload = serialize_async(&block).await;
+                if let Err(e) = sink.send(frame(FIREHOSE_BLOCK, &payload)).await {
+                    return Err(Error::Custom(e.to_string()))
+                }
+            }
+            next = end + 1;
+        }
+
+        // Forward mempool transactions until its time to poll for new blocks.
+        // The transactions subscriber notifies base64 encoded serialized
+        // transactions, so we simply decode them.
+        let mut poll = Timer::after(BLOCK_POLL_INTERVAL);
+        while let Some(notification) = future::or(async { Some(txs_sub.receive().await) }, async {
+            (&mut poll).await;
+            None
+        })
+        .await
+        {
+            let JsonValue::Array(params) = notification.params else { continue };
+            for param in params {
+                let JsonValue::String(encoded_tx) = param else { continue };
+                let Some(payload) = base64::decode(&encoded_tx) else { continue };
+                if let Err(e) = sink.send(frame(FIREHOSE_TX, &payload)).await {
+                    return Err(Error::Custom(e.to_string()))
+                }
+            }
+        }
+    }
+}
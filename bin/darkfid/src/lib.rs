@@ -19,17 +19,23 @@
 use std::{
     collections::{HashMap, HashSet},
     sync::Arc,
+    time::Duration,
 };
 
 use log::{debug, error, info};
-use smol::lock::Mutex;
+use smol::lock::{Mutex, RwLock};
 use url::Url;
 
 use darkfi::{
     net::settings::Settings,
     rpc::{
+        drain::RpcDrain,
         jsonrpc::JsonSubscriber,
-        server::{listen_and_serve, RequestHandler},
+        log_method::log_events_task,
+        server::{listen_and_serve, listen_and_serve_with, RequestHandler},
+        session::RpcSessions,
+        settings::RpcSettings,
+        stats::RpcStats,
     },
     system::{ExecutorPtr, StoppableTask, StoppableTaskPtr},
     validator::{Validator, ValidatorConfig, ValidatorPtr},
@@ -40,18 +46,36 @@ use darkfi::{
 mod tests;
 
 mod error;
-use error::{server_error, RpcError};
+use error::RpcError;
 
 /// JSON-RPC requests handler and methods
 mod rpc;
-use rpc::{DefaultRpcHandler, MinerRpcClient, MmRpcHandler};
+use rpc::{DefaultRpcHandler, ManagementRpcHandler, MinerRpcClient, MmRpcHandler};
 mod rpc_blockchain;
+mod rpc_devtools;
 mod rpc_tx;
 mod rpc_xmr;
 
 /// Validator async tasks
 pub mod task;
-use task::{consensus::ConsensusInitTaskConfig, consensus_init_task};
+use task::{consensus::ConsensusInitTaskConfig, consensus_init_task, watchdog::TipHealth};
+
+/// Block checkpoints enforced during sync
+pub mod checkpoints;
+use checkpoints::Checkpoints;
+
+/// Streaming exporter of finalized blocks and mempool transactions
+pub mod firehose;
+use firehose::firehose_task;
+
+/// Server-side filters for blocks subscriptions
+mod filter;
+
+/// Money contract nullifiers extraction, for double-spend detection
+pub mod spends;
+
+/// Cache of proposals received before their parent
+pub mod orphans;
 
 /// P2P net protocols
 mod proto;
@@ -76,6 +100,22 @@ pub struct DarkfiNode {
     rpc_client: Option<Mutex<MinerRpcClient>>,
     /// HTTP JSON-RPC connection tracker
     mm_rpc_connections: Mutex<HashSet<StoppableTaskPtr>>,
+    /// Management JSON-RPC connection tracker
+    management_rpc_connections: Mutex<HashSet<StoppableTaskPtr>>,
+    /// JSON-RPC request statistics, shared by all RPC endpoints
+    rpc_stats: RpcStats,
+    /// JSON-RPC client sessions, shared by all RPC endpoints
+    rpc_sessions: RpcSessions,
+    /// JSON-RPC graceful shutdown state, shared by all RPC endpoints
+    rpc_drain: RpcDrain,
+    /// Flag indicating JSON-RPC requests get traced with correlation IDs
+    rpc_tracing: bool,
+    /// Flag indicating the node runs the localnet development mode
+    devnet: bool,
+    /// Block checkpoints enforced during sync
+    checkpoints: RwLock<Checkpoints>,
+    /// Best chain tip liveness state, maintained by the stale tip watchdog
+    tip_health: RwLock<TipHealth>,
 }
 
 impl DarkfiNode {
@@ -85,6 +125,10 @@ impl DarkfiNode {
         txs_batch_size: usize,
         subscribers: HashMap<&'static str, JsonSubscriber>,
         rpc_client: Option<Mutex<MinerRpcClient>>,
+        rpc_tracing: bool,
+        rpc_session_rate_limit: Option<u32>,
+        rpc_drain_timeout: u64,
+        devnet: bool,
     ) -> DarkfiNodePtr {
         Arc::new(Self {
             p2p_handler,
@@ -94,6 +138,14 @@ impl DarkfiNode {
             rpc_connections: Mutex::new(HashSet::new()),
             rpc_client,
             mm_rpc_connections: Mutex::new(HashSet::new()),
+            management_rpc_connections: Mutex::new(HashSet::new()),
+            rpc_stats: RpcStats::new(),
+            rpc_sessions: RpcSessions::new(rpc_session_rate_limit),
+            rpc_drain: RpcDrain::new(Duration::from_secs(rpc_drain_timeout)),
+            rpc_tracing,
+            devnet,
+            checkpoints: RwLock::new(Checkpoints::default()),
+            tip_health: RwLock::new(TipHealth::default()),
         })
     }
 }
@@ -107,12 +159,18 @@ pub struct Darkfid {
     node: DarkfiNodePtr,
     /// `dnet` background task
     dnet_task: StoppableTaskPtr,
-    /// JSON-RPC background task
-    rpc_task: StoppableTaskPtr,
+    /// Log events background task
+    log_task: StoppableTaskPtr,
+    /// JSON-RPC background tasks, one per configured listener
+    rpc_tasks: Mutex<Vec<StoppableTaskPtr>>,
     /// HTTP JSON-RPC background task
     mm_rpc_task: StoppableTaskPtr,
+    /// Management JSON-RPC background task
+    management_rpc_task: StoppableTaskPtr,
     /// Consensus protocol background task
     consensus_task: StoppableTaskPtr,
+    /// Firehose exporter background task
+    firehose_task: StoppableTaskPtr,
 }
 
 impl Darkfid {
@@ -126,6 +184,10 @@ impl Darkfid {
         net_settings: &Settings,
         minerd_endpoint: &Option<Url>,
         txs_batch_size: &Option<usize>,
+        rpc_tracing: bool,
+        rpc_session_rate_limit: Option<u32>,
+        rpc_drain_timeout: u64,
+        devnet: bool,
         ex: &ExecutorPtr,
     ) -> Result<DarkfidPtr> {
         info!(target: "darkfid::Darkfid::init", "Initializing a Darkfi daemon...");
@@ -152,7 +214,9 @@ impl Darkfid {
         subscribers.insert("blocks", JsonSubscriber::new("blockchain.subscribe_blocks"));
         subscribers.insert("txs", JsonSubscriber::new("blockchain.subscribe_txs"));
         subscribers.insert("proposals", JsonSubscriber::new("blockchain.subscribe_proposals"));
+        subscribers.insert("tips", JsonSubscriber::new("blockchain.subscribe_tips"));
         subscribers.insert("dnet", JsonSubscriber::new("dnet.subscribe_events"));
+        subscribers.insert("log", JsonSubscriber::new("log.subscribe_events"));
 
         // Initialize JSON-RPC client to perform requests to minerd
         let rpc_client = match minerd_endpoint {
@@ -167,27 +231,55 @@ impl Darkfid {
         };
 
         // Initialize node
-        let node =
-            DarkfiNode::new(p2p_handler, validator, txs_batch_size, subscribers, rpc_client).await;
+        let node = DarkfiNode::new(
+            p2p_handler,
+            validator,
+            txs_batch_size,
+            subscribers,
+            rpc_client,
+            rpc_tracing,
+            rpc_session_rate_limit,
+            rpc_drain_timeout,
+            devnet,
+        )
+        .await;
 
         // Generate the background tasks
         let dnet_task = StoppableTask::new();
-        let rpc_task = StoppableTask::new();
+        let log_task = StoppableTask::new();
         let mm_rpc_task = StoppableTask::new();
+        let management_rpc_task = StoppableTask::new();
         let consensus_task = StoppableTask::new();
+        let firehose_task = StoppableTask::new();
 
         info!(target: "darkfid::Darkfid::init", "Darkfi daemon initialized successfully!");
 
-        Ok(Arc::new(Self { node, dnet_task, rpc_task, mm_rpc_task, consensus_task }))
+        Ok(Arc::new(Self {
+            node,
+            dnet_task,
+            log_task,
+            rpc_tasks: Mutex::new(vec![]),
+            mm_rpc_task,
+            management_rpc_task,
+            consensus_task,
+            firehose_task,
+        }))
+    }
+
+    /// Grab the node live data subscriber registered under `name`,
+    /// e.g. `blocks` for the confirmed blocks notifications.
+    pub fn subscriber(&self, name: &str) -> Option<JsonSubscriber> {
+        self.node.subscribers.get(name).cloned()
     }
 
-    /// Start the DarkFi daemon in the given executor, using the provided JSON-RPC listen url
-    /// and consensus initialization configuration.
+    /// Start the DarkFi daemon in the given executor, using the provided JSON-RPC listeners
+    /// settings, optional firehose exporter listen url and consensus initialization configuration.
     pub async fn start(
         &self,
         executor: &ExecutorPtr,
-        rpc_listen: &Url,
+        rpc_settings: &RpcSettings,
         mm_rpc_listen: &Option<Url>,
+        firehose_listen: &Option<Url>,
         config: &ConsensusInitTaskConfig,
     ) -> Result<()> {
         info!(target: "darkfid::Darkfid::start", "Starting Darkfi daemon...");
@@ -223,21 +315,72 @@ impl Darkfid {
             executor.clone(),
         );
 
-        // Start the JSON-RPC task
-        info!(target: "darkfid::Darkfid::start", "Starting JSON-RPC server");
-        let node_ = self.node.clone();
-        self.rpc_task.clone().start(
-            listen_and_serve::<DefaultRpcHandler>(rpc_listen.clone(), self.node.clone(), None, executor.clone()),
-            |res| async move {
+        // Start the log events task
+        info!(target: "darkfid::Darkfid::start", "Starting log events task");
+        self.log_task.clone().start(
+            log_events_task(self.node.subscribers.get("log").unwrap().clone()),
+            |res| async {
                 match res {
-                    Ok(()) | Err(Error::RpcServerStopped) => <DarkfiNode as RequestHandler<DefaultRpcHandler>>::stop_connections(&node_).await,
-                    Err(e) => error!(target: "darkfid::Darkfid::start", "Failed starting JSON-RPC server: {}", e),
+                    Ok(()) | Err(Error::DetachedTaskStopped) => { /* Do nothing */ }
+                    Err(e) => error!(target: "darkfid::Darkfid::start", "Failed starting log events task: {}", e),
                 }
             },
-            Error::RpcServerStopped,
+            Error::DetachedTaskStopped,
             executor.clone(),
         );
 
+        // Start a JSON-RPC task for each configured listener
+        rpc_settings.validate()?;
+        let mut rpc_tasks = self.rpc_tasks.lock().await;
+        for listener in &rpc_settings.listeners {
+            info!(
+                target: "darkfid::Darkfid::start",
+                "Starting JSON-RPC server on {} ({})",
+                listener.listen,
+                if listener.auth_token.is_some() { "authenticated" } else { "unauthenticated" },
+            );
+            let node_ = self.node.clone();
+            let rpc_task = StoppableTask::new();
+            rpc_task.clone().start(
+                listen_and_serve_with::<DefaultRpcHandler>(listener.clone(), self.node.clone(), executor.clone()),
+                |res| async move {
+                    match res {
+                        Ok(()) | Err(Error::RpcServerStopped) => <DarkfiNode as RequestHandler<DefaultRpcHandler>>::stop_connections(&node_).await,
+                        Err(e) => error!(target: "darkfid::Darkfid::start", "Failed starting JSON-RPC server: {}", e),
+                    }
+                },
+                Error::RpcServerStopped,
+                executor.clone(),
+            );
+            rpc_tasks.push(rpc_task);
+        }
+        drop(rpc_tasks);
+
+        // Start the management JSON-RPC task
+        if let Some(listener) = &rpc_settings.management {
+            info!(target: "darkfid::Darkfid::start", "Starting management JSON-RPC server on {}", listener.listen);
+            let node_ = self.node.clone();
+            self.management_rpc_task.clone().start(
+                listen_and_serve_with::<ManagementRpcHandler>(listener.clone(), self.node.clone(), executor.clone()),
+                |res| async move {
+                    match res {
+                        Ok(()) | Err(Error::RpcServerStopped) => <DarkfiNode as RequestHandler<ManagementRpcHandler>>::stop_connections(&node_).await,
+                        Err(e) => error!(target: "darkfid::Darkfid::start", "Failed starting management JSON-RPC server: {}", e),
+                    }
+                },
+                Error::RpcServerStopped,
+                executor.clone(),
+            );
+        } else {
+            // Create a dummy task
+            self.management_rpc_task.clone().start(
+                async { Ok(()) },
+                |_| async { /* Do nothing */ },
+                Error::RpcServerStopped,
+                executor.clone(),
+            );
+        }
+
         // Start the HTTP JSON-RPC task
         if let Some(url) = mm_rpc_listen {
             info!(target: "darkfid::Darkfid::start", "Starting HTTP JSON-RPC server");
@@ -263,6 +406,30 @@ impl Darkfid {
             );
         }
 
+        // Start the firehose exporter task
+        if let Some(url) = firehose_listen {
+            info!(target: "darkfid::Darkfid::start", "Starting firehose exporter");
+            self.firehose_task.clone().start(
+                firehose_task(self.node.clone(), url.clone(), executor.clone()),
+                |res| async {
+                    match res {
+                        Ok(()) | Err(Error::DetachedTaskStopped) => { /* Do nothing */ }
+                        Err(e) => error!(target: "darkfid::Darkfid::start", "Failed starting firehose exporter: {}", e),
+                    }
+                },
+                Error::DetachedTaskStopped,
+                executor.clone(),
+            );
+        } else {
+            // Create a dummy task
+            self.firehose_task.clone().start(
+                async { Ok(()) },
+                |_| async { /* Do nothing */ },
+                Error::DetachedTaskStopped,
+                executor.clone(),
+            );
+        }
+
         // Start the P2P network
         info!(target: "darkfid::Darkfid::start", "Starting P2P network");
         self.node
@@ -301,13 +468,27 @@ impl Darkfid {
         info!(target: "darkfid::Darkfid::stop", "Stopping dnet subs task...");
         self.dnet_task.stop().await;
 
-        // Stop the JSON-RPC task
-        info!(target: "darkfid::Darkfid::stop", "Stopping JSON-RPC server...");
-        self.rpc_task.stop().await;
+        // Stop the log events task
+        info!(target: "darkfid::Darkfid::stop", "Stopping log events task...");
+        self.log_task.stop().await;
+
+        // Stop the JSON-RPC tasks
+        info!(target: "darkfid::Darkfid::stop", "Stopping JSON-RPC servers...");
+        for rpc_task in self.rpc_tasks.lock().await.drain(..) {
+            rpc_task.stop().await;
+        }
 
         // Stop the HTTP JSON-RPC task
         info!(target: "darkfid::Darkfid::stop", "Stopping HTTP JSON-RPC server...");
-        self.rpc_task.stop().await;
+        self.mm_rpc_task.stop().await;
+
+        // Stop the management JSON-RPC task
+        info!(target: "darkfid::Darkfid::stop", "Stopping management JSON-RPC server...");
+        self.management_rpc_task.stop().await;
+
+        // Stop the firehose exporter task
+        info!(target: "darkfid::Darkfid::stop", "Stopping firehose exporter...");
+        self.firehose_task.stop().await;
 
         // Stop the P2P network
         info!(target: "darkfid::Darkfid::stop", "Stopping P2P network protocols handler...");
@@ -16,7 +16,7 @@
  * along with this program.  If not, see <https://www.gnu.org/licenses/>.
  */
 
-use std::sync::Arc;
+use std::{str::FromStr, sync::Arc, time::Duration};
 
 use log::{debug, error, info};
 use smol::{fs::read_to_string, stream::StreamExt};
@@ -25,19 +25,29 @@ use url::Url;
 
 use darkfi::{
     async_daemonize,
-    blockchain::BlockInfo,
+    blockchain::{BlockInfo, HeaderHash},
     cli_desc,
     net::settings::SettingsOpt,
+    rpc::settings::{RpcListenerSettings, RpcSettings},
     util::{
         encoding::base64,
         path::{expand_path, get_config_path},
     },
-    validator::{Validator, ValidatorConfig},
+    validator::{
+        admission::{AdmissionPolicy, ContractAllowlist, FeeFloor, RateLimit, SizeLimit},
+        Validator, ValidatorConfig,
+    },
     Error, Result,
 };
+use darkfi_sdk::crypto::ContractId;
 use darkfi_serial::deserialize_async;
 
-use darkfid::{task::consensus::ConsensusInitTaskConfig, Darkfid};
+use darkfid::{
+    checkpoints::{embedded_checkpoints, parse_checkpoint, CheckpointSource},
+    spends::MoneyNullifiers,
+    task::consensus::ConsensusInitTaskConfig,
+    Darkfid,
+};
 
 const CONFIG_FILE: &str = "darkfid_config.toml";
 const CONFIG_FILE_CONTENTS: &str = include_str!("../darkfid_config.toml");
@@ -64,6 +74,14 @@ struct Args {
     /// Reset validator state to given block height
     reset: Option<u32>,
 
+    #[structopt(long)]
+    /// Replay blocks from given height and verify their stored state diffs
+    replay: Option<u32>,
+
+    #[structopt(long)]
+    /// Optional last block height to replay, defaults to last block
+    replay_end: Option<u32>,
+
     #[structopt(short, long)]
     /// Set log file to ouput into
     log: Option<String>,
@@ -82,6 +100,28 @@ pub struct BlockchainNetwork {
     /// JSON-RPC listen URL
     rpc_listen: Url,
 
+    #[structopt(long)]
+    /// Assign correlation IDs to JSON-RPC requests and tag downstream logs with them
+    rpc_tracing: bool,
+
+    #[structopt(long)]
+    /// Maximum JSON-RPC requests per minute of every client session (unlimited if unset)
+    rpc_session_rate_limit: Option<u32>,
+
+    #[structopt(long, default_value = "10")]
+    /// Seconds to wait for in-flight JSON-RPC requests to finish on shutdown
+    rpc_drain_timeout: u64,
+
+    #[serde(default)]
+    #[structopt(skip)]
+    /// Additional JSON-RPC listeners, each with its own settings
+    rpc_listeners: Vec<RpcListenerSettings>,
+
+    #[serde(default)]
+    #[structopt(skip)]
+    /// Optional authenticated JSON-RPC listener serving the management methods
+    management_rpc: Option<RpcListenerSettings>,
+
     #[structopt(long, default_value = "~/.local/share/darkfi/darkfid/localnet")]
     /// Path to blockchain database
     database: String,
@@ -98,6 +138,10 @@ pub struct BlockchainNetwork {
     /// Optional HTTP JSON-RPC listen URL to serve handlers for p2pool merge mining requests
     mm_rpc_listen: Option<Url>,
 
+    #[structopt(long)]
+    /// Optional websocket listen URL to stream finalized blocks and mempool transactions
+    firehose_listen: Option<Url>,
+
     #[structopt(long, default_value = "10")]
     /// PoW block production target, in seconds
     pow_target: u32,
@@ -134,6 +178,30 @@ pub struct BlockchainNetwork {
     /// Optional sync checkpoint hash
     checkpoint: Option<String>,
 
+    #[structopt(long)]
+    /// Additional checkpoints to enforce, in `height:hash` format (repeatable flag)
+    checkpoints: Vec<String>,
+
+    #[structopt(long, default_value = "100")]
+    /// Depth below last block at which finalized blocks get pinned as checkpoints (0 disables)
+    checkpoint_depth: u32,
+
+    #[structopt(long, default_value = "10")]
+    /// Resync when the chain tip hasn't changed for this many target block times while connected (0 disables)
+    stale_tip_multiplier: u32,
+
+    #[structopt(long)]
+    /// Bootstrap a fresh node from a blockchain snapshot served by peers, retrieving its blocks in bulk
+    snapshot_sync: bool,
+
+    #[structopt(long, default_value = "0")]
+    /// Interval in blocks between blockchain snapshots served to peers (0 disables)
+    snapshot_interval: u32,
+
+    #[structopt(long, default_value = "67108864")]
+    /// Maximum amount of state bytes a non-native contract can hold
+    contract_state_quota: u64,
+
     #[structopt(long)]
     /// Optional bootstrap timestamp
     bootstrap: Option<u64>,
@@ -142,11 +210,68 @@ pub struct BlockchainNetwork {
     /// Garbage collection task transactions batch size
     txs_batch_size: Option<usize>,
 
+    #[structopt(long)]
+    /// Mempool admission: maximum serialized transaction size, in bytes
+    mempool_max_tx_size: Option<usize>,
+
+    #[structopt(long)]
+    /// Mempool admission: maximum contract calls per transaction
+    mempool_max_tx_calls: Option<usize>,
+
+    #[structopt(long)]
+    /// Mempool admission: minimum fee a transaction must pay
+    mempool_min_fee: Option<u64>,
+
+    #[structopt(long)]
+    /// Mempool admission: allowed contract IDs (repeatable flag, empty allows all)
+    mempool_allowed_contracts: Vec<String>,
+
+    #[structopt(long)]
+    /// Mempool admission: maximum transactions accepted per peer, per minute
+    mempool_peer_rate_limit: Option<u32>,
+
     /// P2P network settings
     #[structopt(flatten)]
     net: SettingsOpt,
 }
 
+/// Build the mempool admission policies pipeline from the configured limits.
+fn admission_policies(config: &BlockchainNetwork) -> Result<Vec<Arc<dyn AdmissionPolicy>>> {
+    let mut policies: Vec<Arc<dyn AdmissionPolicy>> = vec![];
+
+    if config.mempool_max_tx_size.is_some() || config.mempool_max_tx_calls.is_some() {
+        let max_bytes = config.mempool_max_tx_size.unwrap_or(usize::MAX);
+        let max_calls = config.mempool_max_tx_calls;
+        policies.push(Arc::new(SizeLimit { max_bytes, max_calls }));
+    }
+
+    if !config.mempool_allowed_contracts.is_empty() {
+        let mut contracts = Vec::with_capacity(config.mempool_allowed_contracts.len());
+        for contract in &config.mempool_allowed_contracts {
+            let Ok(contract_id) = ContractId::from_str(contract) else {
+                error!(target: "darkfid", "Invalid mempool allowed contract ID: {}", contract);
+                return Err(Error::ParseFailed("Invalid mempool allowed contract ID"))
+            };
+            contracts.push(contract_id);
+        }
+        policies.push(Arc::new(ContractAllowlist::new(&contracts)));
+    }
+
+    if let Some(max_txs) = config.mempool_peer_rate_limit {
+        policies.push(Arc::new(RateLimit::new(max_txs, Duration::from_secs(60))));
+    }
+
+    if let Some(min_fee) = config.mempool_min_fee {
+        policies.push(Arc::new(FeeFloor { min_fee }));
+    }
+
+    if !policies.is_empty() {
+        info!(target: "darkfid", "Node is configured with {} mempool admission policies", policies.len());
+    }
+
+    Ok(policies)
+}
+
 async_daemonize!(realmain);
 async fn realmain(args: Args, ex: Arc<smol::Executor<'static>>) -> Result<()> {
     info!(target: "darkfid", "Initializing DarkFi node...");
@@ -196,8 +321,22 @@ async fn realmain(args: Args, ex: Arc<smol::Executor<'static>>) -> Result<()> {
         pow_fixed_difficulty,
         genesis_block,
         verify_fees: !blockchain_config.skip_fees,
+        admission_policies: admission_policies(&blockchain_config)?,
+        spend_extractor: Some(Arc::new(MoneyNullifiers)),
+        snapshot_interval: blockchain_config.snapshot_interval,
+        contract_state_quota: blockchain_config.contract_state_quota,
     };
 
+    // Grab the release-embedded and configured checkpoints
+    let mut checkpoints = vec![];
+    for (height, hash) in embedded_checkpoints(&args.network)? {
+        checkpoints.push((height, hash, CheckpointSource::Embedded));
+    }
+    for checkpoint in &blockchain_config.checkpoints {
+        let (height, hash) = parse_checkpoint(checkpoint)?;
+        checkpoints.push((height, hash, CheckpointSource::Configured));
+    }
+
     // Check if reset was requested
     if let Some(height) = args.reset {
         info!(target: "darkfid", "Node will reset validator state to height: {}", height);
@@ -207,6 +346,34 @@ async fn realmain(args: Args, ex: Arc<smol::Executor<'static>>) -> Result<()> {
         return Ok(())
     }
 
+    // Check if replay was requested
+    if let Some(start) = args.replay {
+        let validator = Validator::new(&sled_db, &config).await?;
+        let end = match args.replay_end {
+            Some(end) => end,
+            None => validator.blockchain.last()?.0,
+        };
+        let mut replay_checkpoints: Vec<(u32, HeaderHash)> =
+            checkpoints.iter().map(|(height, hash, _)| (*height, *hash)).collect();
+        if let Some(height) = blockchain_config.checkpoint_height {
+            let Some(hash) = &blockchain_config.checkpoint else {
+                return Err(Error::ParseFailed("Blockchain configured checkpoint hash missing"))
+            };
+            replay_checkpoints.push((height, HeaderHash::from_str(hash)?));
+        }
+        info!(target: "darkfid", "Node will replay blocks {}..={}", start, end);
+        let mismatches = validator.replay_range(start, end, &replay_checkpoints).await?;
+        if !mismatches.is_empty() {
+            error!(target: "darkfid", "State diff mismatches found at heights: {:?}", mismatches);
+            return Err(Error::Custom(format!(
+                "Replay found {} state diff mismatches",
+                mismatches.len()
+            )))
+        }
+        info!(target: "darkfid", "Blocks replayed successfully, no state diff mismatches found!");
+        return Ok(())
+    }
+
     // Generate the daemon
     let daemon = Darkfid::init(
         &sled_db,
@@ -214,6 +381,10 @@ async fn realmain(args: Args, ex: Arc<smol::Executor<'static>>) -> Result<()> {
         &blockchain_config.net.into(),
         &blockchain_config.minerd_endpoint,
         &blockchain_config.txs_batch_size,
+        blockchain_config.rpc_tracing,
+        blockchain_config.rpc_session_rate_limit,
+        blockchain_config.rpc_drain_timeout,
+        args.network == "localnet",
         &ex,
     )
     .await?;
@@ -223,14 +394,27 @@ async fn realmain(args: Args, ex: Arc<smol::Executor<'static>>) -> Result<()> {
         skip_sync: blockchain_config.skip_sync,
         checkpoint_height: blockchain_config.checkpoint_height,
         checkpoint: blockchain_config.checkpoint,
+        checkpoints,
+        checkpoint_depth: blockchain_config.checkpoint_depth,
+        stale_tip_multiplier: blockchain_config.stale_tip_multiplier,
+        snapshot_sync: blockchain_config.snapshot_sync,
         miner: blockchain_config.minerd_endpoint.is_some(),
         recipient: blockchain_config.recipient,
         spend_hook: blockchain_config.spend_hook,
         user_data: blockchain_config.user_data,
         bootstrap,
     };
+    let rpc_settings = RpcSettings::new(blockchain_config.rpc_listen)
+        .with_listeners(blockchain_config.rpc_listeners)
+        .with_management(blockchain_config.management_rpc);
     daemon
-        .start(&ex, &blockchain_config.rpc_listen, &blockchain_config.mm_rpc_listen, &config)
+        .start(
+            &ex,
+            &rpc_settings,
+            &blockchain_config.mm_rpc_listen,
+            &blockchain_config.firehose_listen,
+            &config,
+        )
         .await?;
 
     // Signal handling for graceful termination.
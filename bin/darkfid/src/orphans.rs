@@ -0,0 +1,288 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2025 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::Arc,
+    time::Duration,
+};
+
+use log::{debug, info};
+use smol::{
+    channel::{bounded, Receiver, Sender},
+    lock::Mutex,
+};
+use tinyjson::JsonValue;
+
+use darkfi::{
+    blockchain::HeaderHash,
+    net::P2pPtr,
+    rpc::jsonrpc::JsonSubscriber,
+    system::timeout::timeout,
+    util::encoding::base64,
+    validator::{consensus::Proposal, ValidatorPtr},
+    Error, Result,
+};
+use darkfi_serial::serialize_async;
+
+use crate::{proto::ProposalMessage, task::handle_unknown_proposal};
+
+/// Maximum number of orphan proposals kept in the pool
+pub const ORPHANS_MAX: usize = 64;
+
+/// Maximum number of orphan proposals kept in the pool per channel
+pub const ORPHANS_PER_CHANNEL_MAX: usize = 8;
+
+/// Seconds an orphan proposal waits for its parent to arrive, before
+/// its fork sequence gets requested from the peer that sent it
+pub const ORPHAN_GRACE_PERIOD: u64 = 5;
+
+/// Pooled orphan proposal, along with the channel ID it was received
+/// from and the sender used to signal its handler it got evicted.
+type Orphan = (Proposal, u32, Sender<()>);
+
+/// Bounded pool of proposals received before their parent, keyed by
+/// the missing parent hash, so they can be re-validated once it arrives
+/// instead of being dropped and re-requested.
+pub struct OrphanPool {
+    /// Orphan proposals grouped by their missing parent hash
+    orphans: Mutex<HashMap<HeaderHash, Vec<Orphan>>>,
+    /// Orphan proposals insertion order, as `(parent, hash, channel)`
+    /// tuples, used to evict the oldest one when the pool is full
+    order: Mutex<VecDeque<(HeaderHash, HeaderHash, u32)>>,
+    /// Maximum number of orphan proposals kept
+    max_size: usize,
+    /// Maximum number of orphan proposals kept per channel
+    max_per_channel: usize,
+}
+
+impl OrphanPool {
+    pub fn new(max_size: usize, max_per_channel: usize) -> Self {
+        Self {
+            orphans: Mutex::new(HashMap::new()),
+            order: Mutex::new(VecDeque::new()),
+            max_size,
+            max_per_channel,
+        }
+    }
+
+    /// Insert an orphan proposal received from given channel. When the
+    /// channel has reached its quota, its oldest orphan gets evicted, so a
+    /// single peer can't flush the rest ones out of the pool. Otherwise, if
+    /// the pool is full, its oldest orphan gets evicted. Returns `None` if
+    /// the proposal was already in the pool, or the receiver getting
+    /// signalled when it gets evicted. The sender side is dropped once the
+    /// proposal leaves the pool any other way.
+    pub async fn insert(&self, proposal: Proposal, channel: u32) -> Option<Receiver<()>> {
+        let parent = proposal.block.header.previous;
+        let mut orphans = self.orphans.lock().await;
+        let mut order = self.order.lock().await;
+
+        let siblings = orphans.entry(parent).or_default();
+        if siblings.iter().any(|(p, _, _)| p.hash == proposal.hash) {
+            return None
+        }
+        let (evict_tx, evict_rx) = bounded(1);
+        order.push_back((parent, proposal.hash, channel));
+        siblings.push((proposal, channel, evict_tx));
+
+        loop {
+            let index =
+                if order.iter().filter(|(_, _, c)| *c == channel).count() > self.max_per_channel {
+                    order.iter().position(|(_, _, c)| *c == channel).unwrap()
+                } else if order.len() > self.max_size {
+                    0
+                } else {
+                    break
+                };
+
+            let (parent, hash, _) = order.remove(index).unwrap();
+            debug!(target: "darkfid::orphans::insert", "Evicting orphan proposal {hash}");
+            let Some(siblings) = orphans.get_mut(&parent) else { continue };
+            if let Some(index) = siblings.iter().position(|(p, _, _)| p.hash == hash) {
+                let (_, _, evict_tx) = siblings.remove(index);
+                let _ = evict_tx.try_send(());
+            }
+            if siblings.is_empty() {
+                orphans.remove(&parent);
+            }
+        }
+
+        Some(evict_rx)
+    }
+
+    /// Remove an orphan proposal from the pool, returning it along with
+    /// the channel ID it was received from, if it was still there.
+    pub async fn remove(&self, parent: &HeaderHash, hash: &HeaderHash) -> Option<(Proposal, u32)> {
+        let mut orphans = self.orphans.lock().await;
+        let siblings = orphans.get_mut(parent)?;
+        let index = siblings.iter().position(|(p, _, _)| p.hash == *hash)?;
+        let (proposal, channel, _) = siblings.remove(index);
+        if siblings.is_empty() {
+            orphans.remove(parent);
+        }
+        drop(orphans);
+
+        self.order.lock().await.retain(|(_, h, _)| h != hash);
+        Some((proposal, channel))
+    }
+
+    /// Remove and return all the orphan proposals extending given parent
+    pub async fn take_children(&self, parent: &HeaderHash) -> Vec<(Proposal, u32)> {
+        let Some(children) = self.orphans.lock().await.remove(parent) else { return vec![] };
+        self.order.lock().await.retain(|(p, _, _)| p != parent);
+        children.into_iter().map(|(proposal, channel, _)| (proposal, channel)).collect()
+    }
+
+    /// Number of orphan proposals in the pool
+    pub async fn len(&self) -> usize {
+        self.order.lock().await.len()
+    }
+}
+
+/// Cheap sanity checks an orphan proposal must pass before it gets pooled,
+/// so peers can't fill the pool with junk. Its hash must match its block,
+/// it must extend past our canonical tip, its timestamp must not be in the
+/// future and its PoW must satisfy the canonical next mine target. Its
+/// transactions can't be verified until its parent arrives.
+pub async fn verify_orphan(validator: &ValidatorPtr, proposal: &Proposal) -> Result<()> {
+    if proposal.hash != proposal.block.hash() {
+        return Err(Error::ProposalHashesMissmatchError)
+    }
+
+    let (last_height, _) = validator.blockchain.last()?;
+    if proposal.block.header.height <= last_height {
+        return Err(Error::BlockIsInvalid(proposal.hash.as_string()))
+    }
+
+    let module = validator.consensus.module.read().await;
+    if !module.verify_current_timestamp(proposal.block.header.timestamp)? {
+        return Err(Error::PoWInvalidTimestamp)
+    }
+    module.verify_block_hash(&proposal.block)
+}
+
+/// Re-validate the orphan proposals extending the given freshly appended
+/// proposal, and recursively the ones extending them. Appended orphans are
+/// broadcasted to the network and notified to the proposals subscriber.
+pub async fn append_orphans(
+    orphans: &OrphanPool,
+    validator: &ValidatorPtr,
+    p2p: &P2pPtr,
+    proposals_sub: &JsonSubscriber,
+    parent: HeaderHash,
+) {
+    let mut parents = vec![parent];
+    while let Some(parent) = parents.pop() {
+        for (proposal, channel) in orphans.take_children(&parent).await {
+            if let Err(e) = validator.append_proposal(&proposal).await {
+                debug!(
+                    target: "darkfid::orphans::append_orphans",
+                    "Orphan proposal {} re-validation failed: {e}", proposal.hash,
+                );
+                continue
+            }
+            info!(
+                target: "darkfid::orphans::append_orphans",
+                "Appended orphan proposal {} - {}", proposal.hash, proposal.block.header.height,
+            );
+
+            // Broadcast proposal to rest nodes
+            let exclude: Vec<_> =
+                p2p.get_channel(channel).map(|c| c.address().clone()).into_iter().collect();
+            let message = ProposalMessage(proposal.clone());
+            p2p.broadcast_with_exclude(&message, &exclude).await;
+
+            // Notify proposals subscriber
+            let enc_prop = JsonValue::String(base64::encode(&serialize_async(&proposal).await));
+            proposals_sub.notify(vec![enc_prop].into()).await;
+
+            parents.push(proposal.hash);
+        }
+    }
+}
+
+/// Background task to handle an orphan proposal. When only its direct
+/// parent is missing from our best fork, the proposal waits in the pool
+/// for it to arrive during the grace period, otherwise it wouldn't arrive
+/// on its own, so we don't wait. If it is still orphaned afterwards, or it
+/// got evicted from the pool meanwhile, its fork sequence is requested from
+/// the peer that sent it.
+#[allow(clippy::too_many_arguments)]
+pub async fn handle_orphan_proposal(
+    orphans: Arc<OrphanPool>,
+    validator: ValidatorPtr,
+    p2p: P2pPtr,
+    proposals_sub: JsonSubscriber,
+    blocks_sub: JsonSubscriber,
+    channel: u32,
+    proposal: Proposal,
+    evicted: Receiver<()>,
+) -> Result<()> {
+    let (parent, hash) = (proposal.block.header.previous, proposal.hash);
+
+    let (best_height, _) = validator.consensus.best_fork_last_header().await?;
+    if proposal.block.header.height == best_height + 2 {
+        match timeout(Duration::from_secs(ORPHAN_GRACE_PERIOD), evicted.recv()).await {
+            // Grace period expired
+            Err(_) => {}
+            // Proposal got evicted from the pool
+            Ok(Ok(())) => {
+                debug!(target: "darkfid::orphans::handle_orphan_proposal", "Orphan proposal {hash} evicted, requesting its fork sequence");
+                return handle_unknown_proposal(
+                    validator,
+                    p2p,
+                    proposals_sub,
+                    blocks_sub,
+                    channel,
+                    proposal,
+                )
+                .await
+            }
+            // Proposal left the pool once its parent arrived
+            Ok(Err(_)) => {
+                debug!(target: "darkfid::orphans::handle_orphan_proposal", "Orphan proposal {hash} no longer pending");
+                return Ok(())
+            }
+        }
+    }
+
+    if orphans.remove(&parent, &hash).await.is_none() {
+        // Check if the proposal got evicted rather than appended
+        if evicted.try_recv().is_err() {
+            debug!(target: "darkfid::orphans::handle_orphan_proposal", "Orphan proposal {hash} no longer pending");
+            return Ok(())
+        }
+    }
+
+    debug!(target: "darkfid::orphans::handle_orphan_proposal", "Orphan proposal {hash} parent didn't arrive, requesting its fork sequence");
+    handle_unknown_proposal(
+        validator.clone(),
+        p2p.clone(),
+        proposals_sub.clone(),
+        blocks_sub,
+        channel,
+        proposal,
+    )
+    .await?;
+
+    // Orphans extending the retrieved proposal can now be appended
+    append_orphans(&orphans, &validator, &p2p, &proposals_sub, hash).await;
+
+    Ok(())
+}
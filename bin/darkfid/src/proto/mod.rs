@@ -22,7 +22,7 @@ use darkfi::{
     net::{P2p, P2pPtr, Settings},
     rpc::jsonrpc::JsonSubscriber,
     system::ExecutorPtr,
-    validator::ValidatorPtr,
+    validator::{admission::TxSource, ValidatorPtr},
     Result,
 };
 use log::info;
@@ -42,6 +42,13 @@ pub use protocol_sync::{
     SyncRequest, SyncResponse, TipRequest, TipResponse, BATCH,
 };
 
+/// Blockchain snapshot sync protocol
+mod protocol_snapshot;
+pub use protocol_snapshot::{
+    ProtocolSnapshotHandler, ProtocolSnapshotHandlerPtr, SnapshotChunkRequest,
+    SnapshotChunkResponse, SnapshotManifestRequest, SnapshotManifestResponse,
+};
+
 /// Transaction broadcast protocol
 mod protocol_tx;
 pub use protocol_tx::{ProtocolTxHandler, ProtocolTxHandlerPtr};
@@ -57,6 +64,8 @@ pub struct DarkfidP2pHandler {
     proposals: ProtocolProposalHandlerPtr,
     /// `ProtocolSync` messages handler
     sync: ProtocolSyncHandlerPtr,
+    /// `ProtocolSnapshot` messages handler
+    snapshots: ProtocolSnapshotHandlerPtr,
     /// `ProtocolTx` messages handler
     txs: ProtocolTxHandlerPtr,
 }
@@ -81,6 +90,9 @@ impl DarkfidP2pHandler {
         // Generate a new `ProtocolSync` messages handler
         let sync = ProtocolSyncHandler::init(&p2p).await;
 
+        // Generate a new `ProtocolSnapshot` messages handler
+        let snapshots = ProtocolSnapshotHandler::init(&p2p).await;
+
         // Generate a new `ProtocolTx` messages handler
         let txs = ProtocolTxHandler::init(&p2p).await;
 
@@ -89,7 +101,7 @@ impl DarkfidP2pHandler {
             "Darkfid P2P handler generated successfully!"
         );
 
-        Ok(Arc::new(Self { p2p, proposals, sync, txs }))
+        Ok(Arc::new(Self { p2p, proposals, sync, snapshots, txs }))
     }
 
     /// Start the Darkfid P2P protocols handler for provided validator.
@@ -112,9 +124,12 @@ impl DarkfidP2pHandler {
         // Start the `ProtocolSync` messages handler
         self.sync.start(executor, validator).await?;
 
+        // Start the `ProtocolSnapshot` messages handler
+        self.snapshots.start(executor, validator).await?;
+
         // Start the `ProtocolTx` messages handler
         let subscriber = subscribers.get("txs").unwrap().clone();
-        self.txs.start(executor, validator, subscriber).await?;
+        self.txs.start(executor, validator, &self.p2p, subscriber).await?;
 
         // Start the P2P instance
         self.p2p.clone().start().await?;
@@ -137,6 +152,9 @@ impl DarkfidP2pHandler {
         // Start the `ProtocolTx` messages handler
         self.txs.stop().await;
 
+        // Start the `ProtocolSnapshot` messages handler
+        self.snapshots.stop().await;
+
         // Start the `ProtocolSync` messages handler
         self.sync.stop().await;
 
@@ -146,3 +164,13 @@ impl DarkfidP2pHandler {
         info!(target: "darkfid::proto::mod::DarkfidP2pHandler::stop", "Darkfid P2P handler terminated successfully!");
     }
 }
+
+/// Auxiliary function to identify the peer behind provided channel by
+/// its host, so reconnecting doesn't reset its admission rate limits or
+/// double-spend penalties.
+pub fn peer_source(p2p: &P2pPtr, channel: u32) -> TxSource {
+    match p2p.get_channel(channel) {
+        Some(c) => TxSource::Peer(c.address().host_str().unwrap_or_default().to_string()),
+        None => TxSource::Peer(format!("channel-{channel}")),
+    }
+}
@@ -19,7 +19,7 @@
 use std::{collections::HashSet, sync::Arc};
 
 use async_trait::async_trait;
-use log::{debug, error};
+use log::{debug, error, warn};
 use smol::lock::RwLock;
 use tinyjson::JsonValue;
 
@@ -40,7 +40,11 @@ use darkfi::{
 };
 use darkfi_serial::{serialize_async, SerialDecodable, SerialEncodable};
 
-use crate::task::handle_unknown_proposal;
+use super::peer_source;
+use crate::orphans::{
+    append_orphans, handle_orphan_proposal, verify_orphan, OrphanPool, ORPHANS_MAX,
+    ORPHANS_PER_CHANNEL_MAX,
+};
 
 /// Auxiliary [`Proposal`] wrapper structure used for messaging.
 #[derive(Clone, Debug, SerialEncodable, SerialDecodable)]
@@ -57,6 +61,8 @@ pub struct ProtocolProposalHandler {
     handler: ProtocolGenericHandlerPtr<ProposalMessage, ProposalMessage>,
     /// Background tasks invoked by the handler.
     tasks: Arc<RwLock<HashSet<StoppableTaskPtr>>>,
+    /// Proposals received before their parent, pending re-validation.
+    orphans: Arc<OrphanPool>,
 }
 
 impl ProtocolProposalHandler {
@@ -70,8 +76,9 @@ impl ProtocolProposalHandler {
 
         let handler = ProtocolGenericHandler::new(p2p, "ProtocolProposal", SESSION_DEFAULT).await;
         let tasks = Arc::new(RwLock::new(HashSet::new()));
+        let orphans = Arc::new(OrphanPool::new(ORPHANS_MAX, ORPHANS_PER_CHANNEL_MAX));
 
-        Arc::new(Self { handler, tasks })
+        Arc::new(Self { handler, tasks, orphans })
     }
 
     /// Start the `ProtocolProposal` background task.
@@ -89,7 +96,7 @@ impl ProtocolProposalHandler {
         );
 
         self.handler.task.clone().start(
-            handle_receive_proposal(self.handler.clone(), self.tasks.clone(), validator.clone(), p2p.clone(), proposals_sub, blocks_sub, executor.clone()),
+            handle_receive_proposal(self.handler.clone(), self.tasks.clone(), self.orphans.clone(), validator.clone(), p2p.clone(), proposals_sub, blocks_sub, executor.clone()),
             |res| async move {
                 match res {
                     Ok(()) | Err(Error::DetachedTaskStopped) => { /* Do nothing */ }
@@ -123,9 +130,11 @@ impl ProtocolProposalHandler {
 }
 
 /// Background handler function for ProtocolProposal.
+#[allow(clippy::too_many_arguments)]
 async fn handle_receive_proposal(
     handler: ProtocolGenericHandlerPtr<ProposalMessage, ProposalMessage>,
     tasks: Arc<RwLock<HashSet<StoppableTaskPtr>>>,
+    orphans: Arc<OrphanPool>,
     validator: ValidatorPtr,
     p2p: P2pPtr,
     proposals_sub: JsonSubscriber,
@@ -159,6 +168,20 @@ async fn handle_receive_proposal(
         // Append proposal
         match validator.append_proposal(&proposal.0).await {
             Ok(()) => {
+                // Record any double-spends the proposal transactions introduce
+                let source = peer_source(&p2p, channel);
+                for tx in &proposal.0.block.txs {
+                    let proofs =
+                        validator.double_spends.observe(tx, &source, Some(proposal.0.hash));
+                    for proof in proofs {
+                        warn!(
+                            target: "darkfid::proto::protocol_proposal::handle_receive_proposal",
+                            "Proposal {} from {source} contains transaction {} double-spending a nullifier of {}",
+                            proposal.0.hash, proof.second_tx, proof.first_tx,
+                        );
+                    }
+                }
+
                 // Signal handler to broadcast the valid proposal to rest nodes
                 handler.send_action(channel, ProtocolGenericAction::Broadcast).await;
 
@@ -166,6 +189,9 @@ async fn handle_receive_proposal(
                 let enc_prop = JsonValue::String(base64::encode(&serialize_async(&proposal).await));
                 proposals_sub.notify(vec![enc_prop].into()).await;
 
+                // Re-validate any orphans that were waiting for this proposal
+                append_orphans(&orphans, &validator, &p2p, &proposals_sub, proposal.0.hash).await;
+
                 continue
             }
             Err(e) => {
@@ -183,12 +209,25 @@ async fn handle_receive_proposal(
             }
         };
 
-        // Handle unknown proposal in the background
+        // Verify the orphan proposal header, so junk doesn't get pooled
+        if let Err(e) = verify_orphan(&validator, &proposal.0).await {
+            debug!(
+                target: "darkfid::proto::protocol_proposal::handle_receive_proposal",
+                "Orphan proposal {} verification failed: {e}", proposal.0.hash,
+            );
+            continue
+        }
+
+        // Keep the orphan proposal around, so it gets appended if its parent
+        // arrives shortly, skipping it if it's already pending.
+        let Some(evicted) = orphans.insert(proposal.0.clone(), channel).await else { continue };
+
+        // Handle orphan proposal in the background
         let task = StoppableTask::new();
         let _tasks = tasks.clone();
         let _task = task.clone();
         task.clone().start(
-            handle_unknown_proposal(validator.clone(), p2p.clone(), proposals_sub.clone(), blocks_sub.clone(), channel, proposal.0),
+            handle_orphan_proposal(orphans.clone(), validator.clone(), p2p.clone(), proposals_sub.clone(), blocks_sub.clone(), channel, proposal.0, evicted),
             |res| async move {
                 match res {
                     Ok(()) | Err(Error::DetachedTaskStopped) => { _tasks.write().await.remove(&_task); }
@@ -0,0 +1,261 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2025 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use log::{debug, error};
+
+use darkfi::{
+    impl_p2p_message,
+    net::{
+        protocol::protocol_generic::{
+            ProtocolGenericAction, ProtocolGenericHandler, ProtocolGenericHandlerPtr,
+        },
+        session::SESSION_DEFAULT,
+        Message, P2pPtr,
+    },
+    system::ExecutorPtr,
+    validator::{snapshot::SnapshotManifest, ValidatorPtr},
+    Error, Result,
+};
+use darkfi_serial::{SerialDecodable, SerialEncodable};
+
+/// Structure represening a request to ask a node for a blockchain snapshot
+/// manifest. If no height is provided, their latest one is returned.
+#[derive(Clone, Debug, SerialEncodable, SerialDecodable)]
+pub struct SnapshotManifestRequest {
+    /// Optional snapshot block height
+    pub height: Option<u32>,
+}
+
+impl_p2p_message!(SnapshotManifestRequest, "snapshotmanifestrequest");
+
+/// Structure representing the response to `SnapshotManifestRequest`,
+/// containing the requested snapshot manifest, if it was found.
+#[derive(Clone, Debug, SerialEncodable, SerialDecodable)]
+pub struct SnapshotManifestResponse {
+    /// Response snapshot manifest
+    pub manifest: Option<SnapshotManifest>,
+}
+
+impl_p2p_message!(SnapshotManifestResponse, "snapshotmanifestresponse");
+
+/// Structure represening a request to ask a node for a blockchain snapshot chunk.
+#[derive(Clone, Debug, SerialEncodable, SerialDecodable)]
+pub struct SnapshotChunkRequest {
+    /// Chunk hash
+    pub chunk: blake3::Hash,
+}
+
+impl_p2p_message!(SnapshotChunkRequest, "snapshotchunkrequest");
+
+/// Structure representing the response to `SnapshotChunkRequest`,
+/// containing the requested chunk, if it was found.
+#[derive(Clone, Debug, SerialEncodable, SerialDecodable)]
+pub struct SnapshotChunkResponse {
+    /// Requested chunk hash
+    pub chunk: blake3::Hash,
+    /// Response chunk data
+    pub data: Option<Vec<u8>>,
+}
+
+impl_p2p_message!(SnapshotChunkResponse, "snapshotchunkresponse");
+
+/// Atomic pointer to the `ProtocolSnapshot` handler.
+pub type ProtocolSnapshotHandlerPtr = Arc<ProtocolSnapshotHandler>;
+
+/// Handler managing all `ProtocolSnapshot` messages, over generic P2P protocols.
+pub struct ProtocolSnapshotHandler {
+    /// The generic handler for `SnapshotManifestRequest` messages.
+    manifest_handler: ProtocolGenericHandlerPtr<SnapshotManifestRequest, SnapshotManifestResponse>,
+    /// The generic handler for `SnapshotChunkRequest` messages.
+    chunk_handler: ProtocolGenericHandlerPtr<SnapshotChunkRequest, SnapshotChunkResponse>,
+}
+
+impl ProtocolSnapshotHandler {
+    /// Initialize the generic prototocol handlers for all `ProtocolSnapshot` messages
+    /// and register them to the provided P2P network, using the default session flag.
+    pub async fn init(p2p: &P2pPtr) -> ProtocolSnapshotHandlerPtr {
+        debug!(
+            target: "darkfid::proto::protocol_snapshot::init",
+            "Adding all snapshot protocols to the protocol registry"
+        );
+
+        let manifest_handler =
+            ProtocolGenericHandler::new(p2p, "ProtocolSnapshotManifest", SESSION_DEFAULT).await;
+        let chunk_handler =
+            ProtocolGenericHandler::new(p2p, "ProtocolSnapshotChunk", SESSION_DEFAULT).await;
+
+        Arc::new(Self { manifest_handler, chunk_handler })
+    }
+
+    /// Start all `ProtocolSnapshot` background tasks.
+    pub async fn start(&self, executor: &ExecutorPtr, validator: &ValidatorPtr) -> Result<()> {
+        debug!(
+            target: "darkfid::proto::protocol_snapshot::start",
+            "Starting snapshot protocols handlers tasks..."
+        );
+
+        self.manifest_handler.task.clone().start(
+            handle_receive_manifest_request(self.manifest_handler.clone(), validator.clone()),
+            |res| async move {
+                match res {
+                    Ok(()) | Err(Error::DetachedTaskStopped) => { /* Do nothing */ }
+                    Err(e) => error!(target: "darkfid::proto::protocol_snapshot::start", "Failed starting ProtocolSnapshotManifest handler task: {e}"),
+                }
+            },
+            Error::DetachedTaskStopped,
+            executor.clone(),
+        );
+
+        self.chunk_handler.task.clone().start(
+            handle_receive_chunk_request(self.chunk_handler.clone(), validator.clone()),
+            |res| async move {
+                match res {
+                    Ok(()) | Err(Error::DetachedTaskStopped) => { /* Do nothing */ }
+                    Err(e) => error!(target: "darkfid::proto::protocol_snapshot::start", "Failed starting ProtocolSnapshotChunk handler task: {e}"),
+                }
+            },
+            Error::DetachedTaskStopped,
+            executor.clone(),
+        );
+
+        debug!(
+            target: "darkfid::proto::protocol_snapshot::start",
+            "Snapshot protocols handlers tasks started!"
+        );
+
+        Ok(())
+    }
+
+    /// Stop all `ProtocolSnapshot` background tasks.
+    pub async fn stop(&self) {
+        debug!(target: "darkfid::proto::protocol_snapshot::stop", "Terminating snapshot protocols handlers tasks...");
+        self.manifest_handler.task.stop().await;
+        self.chunk_handler.task.stop().await;
+        debug!(target: "darkfid::proto::protocol_snapshot::stop", "Snapshot protocols handlers tasks terminated!");
+    }
+}
+
+/// Background handler function for ProtocolSnapshotManifest.
+async fn handle_receive_manifest_request(
+    handler: ProtocolGenericHandlerPtr<SnapshotManifestRequest, SnapshotManifestResponse>,
+    validator: ValidatorPtr,
+) -> Result<()> {
+    debug!(target: "darkfid::proto::protocol_snapshot::handle_receive_manifest_request", "START");
+    loop {
+        // Wait for a new manifest request message
+        let (channel, request) = match handler.receiver.recv().await {
+            Ok(r) => r,
+            Err(e) => {
+                debug!(
+                    target: "darkfid::proto::protocol_snapshot::handle_receive_manifest_request",
+                    "recv fail: {e}"
+                );
+                continue
+            }
+        };
+
+        // Check if node has finished syncing its blockchain
+        if !*validator.synced.read().await {
+            debug!(
+                target: "darkfid::proto::protocol_snapshot::handle_receive_manifest_request",
+                "Node still syncing blockchain, skipping..."
+            );
+            handler.send_action(channel, ProtocolGenericAction::Skip).await;
+            continue
+        }
+
+        debug!(target: "darkfid::proto::protocol_snapshot::handle_receive_manifest_request", "Received request: {request:?}");
+
+        // Grab the requested manifest
+        let manifest = match request.height {
+            Some(height) => validator
+                .snapshots
+                .get_all()
+                .map(|manifests| manifests.into_iter().find(|m| m.height == height)),
+            None => validator.snapshots.latest(),
+        };
+        let manifest = match manifest {
+            Ok(m) => m,
+            Err(e) => {
+                error!(
+                    target: "darkfid::proto::protocol_snapshot::handle_receive_manifest_request",
+                    "Retrieving snapshot manifest failed: {e}"
+                );
+                handler.send_action(channel, ProtocolGenericAction::Skip).await;
+                continue
+            }
+        };
+
+        // Send response
+        handler
+            .send_action(
+                channel,
+                ProtocolGenericAction::Response(SnapshotManifestResponse { manifest }),
+            )
+            .await;
+    }
+}
+
+/// Background handler function for ProtocolSnapshotChunk.
+async fn handle_receive_chunk_request(
+    handler: ProtocolGenericHandlerPtr<SnapshotChunkRequest, SnapshotChunkResponse>,
+    validator: ValidatorPtr,
+) -> Result<()> {
+    debug!(target: "darkfid::proto::protocol_snapshot::handle_receive_chunk_request", "START");
+    loop {
+        // Wait for a new chunk request message
+        let (channel, request) = match handler.receiver.recv().await {
+            Ok(r) => r,
+            Err(e) => {
+                debug!(
+                    target: "darkfid::proto::protocol_snapshot::handle_receive_chunk_request",
+                    "recv fail: {e}"
+                );
+                continue
+            }
+        };
+
+        debug!(target: "darkfid::proto::protocol_snapshot::handle_receive_chunk_request", "Received request: {request:?}");
+
+        // Grab the requested chunk
+        let data = match validator.snapshots.get_chunk(&request.chunk) {
+            Ok(d) => d,
+            Err(e) => {
+                error!(
+                    target: "darkfid::proto::protocol_snapshot::handle_receive_chunk_request",
+                    "snapshots.get_chunk fail: {e}"
+                );
+                handler.send_action(channel, ProtocolGenericAction::Skip).await;
+                continue
+            }
+        };
+
+        // Send response
+        handler
+            .send_action(
+                channel,
+                ProtocolGenericAction::Response(SnapshotChunkResponse {
+                    chunk: request.chunk,
+                    data,
+                }),
+            )
+            .await;
+    }
+}
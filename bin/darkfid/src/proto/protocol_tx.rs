@@ -38,6 +38,8 @@ use darkfi::{
 };
 use darkfi_serial::serialize_async;
 
+use super::peer_source;
+
 /// Atomic pointer to the `ProtocolTx` handler.
 pub type ProtocolTxHandlerPtr = Arc<ProtocolTxHandler>;
 
@@ -66,6 +68,7 @@ impl ProtocolTxHandler {
         &self,
         executor: &ExecutorPtr,
         validator: &ValidatorPtr,
+        p2p: &P2pPtr,
         subscriber: JsonSubscriber,
     ) -> Result<()> {
         debug!(
@@ -74,7 +77,7 @@ impl ProtocolTxHandler {
         );
 
         self.handler.task.clone().start(
-            handle_receive_tx(self.handler.clone(), validator.clone(), subscriber),
+            handle_receive_tx(self.handler.clone(), validator.clone(), p2p.clone(), subscriber),
             |res| async move {
                 match res {
                     Ok(()) | Err(Error::DetachedTaskStopped) => { /* Do nothing */ }
@@ -105,6 +108,7 @@ impl ProtocolTxHandler {
 async fn handle_receive_tx(
     handler: ProtocolGenericHandlerPtr<Transaction, Transaction>,
     validator: ValidatorPtr,
+    p2p: P2pPtr,
     subscriber: JsonSubscriber,
 ) -> Result<()> {
     debug!(target: "darkfid::proto::protocol_tx::handle_receive_tx", "START");
@@ -131,8 +135,13 @@ async fn handle_receive_tx(
             continue
         }
 
+        // Identify the peer by its host, so reconnecting doesn't reset
+        // its admission rate limits or double-spend penalties. Conflicting
+        // transactions get rejected by `append_tx()` itself.
+        let source = peer_source(&p2p, channel);
+
         // Append transaction
-        if let Err(e) = validator.append_tx(&tx, true).await {
+        if let Err(e) = validator.append_tx(&tx, true, &source).await {
             debug!(
                 target: "darkfid::proto::protocol_tx::handle_receive_tx",
                 "append_tx fail: {e}"
@@ -16,7 +16,10 @@
  * along with this program.  If not, see <https://www.gnu.org/licenses/>.
  */
 
-use std::{collections::HashSet, time::Instant};
+use std::{
+    collections::{HashMap, HashSet},
+    time::Instant,
+};
 
 use async_trait::async_trait;
 use log::{debug, error, info};
@@ -28,24 +31,28 @@ use darkfi::{
     net::P2pPtr,
     rpc::{
         client::RpcChadClient,
-        jsonrpc::{ErrorCode, JsonError, JsonRequest, JsonResponse, JsonResult},
+        drain::RpcDrain,
+        jsonrpc::{ErrorCode, JsonError, JsonRequest, JsonResponse, JsonResult, JsonSubscriber},
+        log_method::HandlerLog,
         p2p_method::HandlerP2p,
         server::RequestHandler,
+        session::{HandlerSession, RpcSessions},
+        stats::{HandlerStats, RpcStats},
     },
+    rpc_error,
     system::{sleep, ExecutorPtr, StoppableTaskPtr},
     util::time::Timestamp,
     Error, Result,
 };
 
-use crate::{
-    error::{server_error, RpcError},
-    DarkfiNode,
-};
+use crate::{error::RpcError, DarkfiNode};
 
 /// Default JSON-RPC `RequestHandler` type
 pub struct DefaultRpcHandler;
 /// HTTP JSON-RPC `RequestHandler` type for p2pool
 pub struct MmRpcHandler;
+/// Authenticated management JSON-RPC `RequestHandler` type
+pub struct ManagementRpcHandler;
 
 /// Structure to hold a JSON-RPC client and its config,
 /// so we can recreate it in case of an error.
@@ -79,11 +86,18 @@ impl RequestHandler<DefaultRpcHandler> for DarkfiNode {
             // =====================
             "ping" => <DarkfiNode as RequestHandler<DefaultRpcHandler>>::pong(self, req.id, req.params).await,
             "clock" => self.clock(req.id, req.params).await,
+            "health.status" => self.health_status(req.id, req.params).await,
             "ping_miner" => self.ping_miner(req.id, req.params).await,
             "dnet.switch" => self.dnet_switch(req.id, req.params).await,
             "dnet.subscribe_events" => self.dnet_subscribe_events(req.id, req.params).await,
+            "dnet.get_recent" => self.dnet_get_recent(req.id, req.params).await,
             // TODO: Make this optional
             "p2p.get_info" => self.p2p_get_info(req.id, req.params).await,
+            "p2p.get_transport_stats" => self.p2p_get_transport_stats(req.id, req.params).await,
+            "p2p.check_reachability" => self.p2p_check_reachability(req.id, req.params).await,
+            "rpc.get_stats" => self.rpc_get_stats(req.id, req.params).await,
+            "rpc.session" => self.rpc_session(req.id, req.params).await,
+            "rpc.set_session_label" => self.rpc_set_session_label(req.id, req.params).await,
 
             // ==================
             // Blockchain methods
@@ -91,12 +105,18 @@ impl RequestHandler<DefaultRpcHandler> for DarkfiNode {
             "blockchain.get_block" => self.blockchain_get_block(req.id, req.params).await,
             "blockchain.get_tx" => self.blockchain_get_tx(req.id, req.params).await,
             "blockchain.last_confirmed_block" => self.blockchain_last_confirmed_block(req.id, req.params).await,
+            "blockchain.checkpoints" => self.blockchain_checkpoints(req.id, req.params).await,
+            "blockchain.deployments" => self.blockchain_deployments(req.id, req.params).await,
             "blockchain.best_fork_next_block_height" => self.blockchain_best_fork_next_block_height(req.id, req.params).await,
             "blockchain.block_target" => self.blockchain_block_target(req.id, req.params).await,
+            "blockchain.get_difficulty_history" => self.blockchain_get_difficulty_history(req.id, req.params).await,
             "blockchain.lookup_zkas" => self.blockchain_lookup_zkas(req.id, req.params).await,
+            "blockchain.get_contract_state_key" => self.blockchain_get_contract_state_key(req.id, req.params).await,
             "blockchain.subscribe_blocks" => self.blockchain_subscribe_blocks(req.id, req.params).await,
             "blockchain.subscribe_txs" =>  self.blockchain_subscribe_txs(req.id, req.params).await,
             "blockchain.subscribe_proposals" => self.blockchain_subscribe_proposals(req.id, req.params).await,
+            "blockchain.subscribe_tips" => self.blockchain_subscribe_tips(req.id, req.params).await,
+            "blockchain.generate_blocks" => self.blockchain_generate_blocks(req.id, req.params).await,
 
             // ===================
             // Transaction methods
@@ -106,6 +126,14 @@ impl RequestHandler<DefaultRpcHandler> for DarkfiNode {
             "tx.pending" => self.tx_pending(req.id, req.params).await,
             "tx.clean_pending" => self.tx_pending(req.id, req.params).await,
             "tx.calculate_gas" => self.tx_calculate_gas(req.id, req.params).await,
+            "tx.decode" => self.tx_decode(req.id, req.params).await,
+            "tx.double_spends" => self.tx_double_spends(req.id, req.params).await,
+
+            // ================
+            // Devtools methods
+            // ================
+            "devtools.invalidate_block" => self.devtools_invalidate_block(req.id, req.params).await,
+            "devtools.reconsider_block" => self.devtools_reconsider_block(req.id, req.params).await,
 
             // ==============
             // Invalid method
@@ -114,6 +142,22 @@ impl RequestHandler<DefaultRpcHandler> for DarkfiNode {
         }
     }
 
+    fn stats(&self) -> Option<&RpcStats> {
+        Some(&self.rpc_stats)
+    }
+
+    fn tracing(&self) -> bool {
+        self.rpc_tracing
+    }
+
+    fn sessions(&self) -> Option<&RpcSessions> {
+        Some(&self.rpc_sessions)
+    }
+
+    fn drain(&self) -> Option<&RpcDrain> {
+        Some(&self.rpc_drain)
+    }
+
     async fn connections_mut(&self) -> MutexGuard<'life0, HashSet<StoppableTaskPtr>> {
         self.rpc_connections.lock().await
     }
@@ -138,11 +182,84 @@ impl RequestHandler<MmRpcHandler> for DarkfiNode {
         }
     }
 
+    fn stats(&self) -> Option<&RpcStats> {
+        Some(&self.rpc_stats)
+    }
+
+    fn tracing(&self) -> bool {
+        self.rpc_tracing
+    }
+
+    fn sessions(&self) -> Option<&RpcSessions> {
+        Some(&self.rpc_sessions)
+    }
+
+    fn drain(&self) -> Option<&RpcDrain> {
+        Some(&self.rpc_drain)
+    }
+
     async fn connections_mut(&self) -> MutexGuard<'life0, HashSet<StoppableTaskPtr>> {
         self.mm_rpc_connections.lock().await
     }
 }
 
+#[async_trait]
+#[rustfmt::skip]
+impl RequestHandler<ManagementRpcHandler> for DarkfiNode {
+    async fn handle_request(&self, req: JsonRequest) -> JsonResult {
+        debug!(target: "darkfid::management_rpc", "--> {}", req.stringify().unwrap());
+
+        match req.method.as_str() {
+            // =====================
+            // Miscellaneous methods
+            // =====================
+            "ping" => <DarkfiNode as RequestHandler<ManagementRpcHandler>>::pong(self, req.id, req.params).await,
+
+            // ===================
+            // Log control methods
+            // ===================
+            "log.get_levels" => self.log_get_levels(req.id, req.params).await,
+            "log.set_level" => self.log_set_level(req.id, req.params).await,
+            "log.reset_level" => self.log_reset_level(req.id, req.params).await,
+            "log.switch" => self.log_switch(req.id, req.params).await,
+            "log.subscribe_events" => self.log_subscribe_events(req.id, req.params).await,
+
+            // ======================
+            // P2P management methods
+            // ======================
+            "p2p.export_hosts" => self.p2p_export_hosts(req.id, req.params).await,
+            "p2p.import_hosts" => self.p2p_import_hosts(req.id, req.params).await,
+            "p2p.add_peer" => self.p2p_add_peer(req.id, req.params).await,
+            "p2p.remove_peer" => self.p2p_remove_peer(req.id, req.params).await,
+
+            // ==============
+            // Invalid method
+            // ==============
+            _ => JsonError::new(ErrorCode::MethodNotFound, None, req.id).into(),
+        }
+    }
+
+    fn stats(&self) -> Option<&RpcStats> {
+        Some(&self.rpc_stats)
+    }
+
+    fn tracing(&self) -> bool {
+        self.rpc_tracing
+    }
+
+    fn sessions(&self) -> Option<&RpcSessions> {
+        Some(&self.rpc_sessions)
+    }
+
+    fn drain(&self) -> Option<&RpcDrain> {
+        Some(&self.rpc_drain)
+    }
+
+    async fn connections_mut(&self) -> MutexGuard<'life0, HashSet<StoppableTaskPtr>> {
+        self.management_rpc_connections.lock().await
+    }
+}
+
 impl DarkfiNode {
     // RPCAPI:
     // Returns current system clock as `u64` (String) timestamp.
@@ -154,6 +271,40 @@ impl DarkfiNode {
             .into()
     }
 
+    // RPCAPI:
+    // Returns the node health status: sync state, connected peers count,
+    // and the best chain tip liveness as tracked by the stale tip watchdog.
+    // `last_tip_change` is a `u64` (String) timestamp, `tip_hash` is `null`
+    // until the watchdog has observed a tip.
+    //
+    // --> {"jsonrpc": "2.0", "method": "health.status", "params": [], "id": 1}
+    // <-- {"jsonrpc": "2.0", "result": {"synced": true, "peers": 8, "tip_height": 1234, "tip_hash": "...", "last_tip_change": "1234", "stale": false, "resyncs": 0}, "id": 1}
+    async fn health_status(&self, id: u16, _params: JsonValue) -> JsonResult {
+        let synced = *self.validator.synced.read().await;
+        let peers = self.p2p_handler.p2p.peers_count();
+        let health = self.tip_health.read().await.clone();
+
+        let tip_hash = match health.tip {
+            Some(hash) => JsonValue::String(hash.to_string()),
+            None => JsonValue::Null,
+        };
+
+        let result = JsonValue::Object(HashMap::from([
+            ("synced".to_string(), JsonValue::Boolean(synced)),
+            ("peers".to_string(), JsonValue::Number(peers as f64)),
+            ("tip_height".to_string(), JsonValue::Number(health.tip_height as f64)),
+            ("tip_hash".to_string(), tip_hash),
+            (
+                "last_tip_change".to_string(),
+                JsonValue::String(health.last_change.inner().to_string()),
+            ),
+            ("stale".to_string(), JsonValue::Boolean(health.stale)),
+            ("resyncs".to_string(), JsonValue::Number(health.resyncs as f64)),
+        ]));
+
+        JsonResponse::new(result, id).into()
+    }
+
     // RPCAPI:
     // Activate or deactivate dnet in the P2P stack.
     // By sending `true`, dnet will be activated, and by sending `false` dnet
@@ -203,7 +354,7 @@ impl DarkfiNode {
     async fn ping_miner(&self, id: u16, _params: JsonValue) -> JsonResult {
         if let Err(e) = self.ping_miner_daemon().await {
             error!(target: "darkfid::rpc::ping_miner", "Failed to ping miner daemon: {}", e);
-            return server_error(RpcError::PingFailed, id, None)
+            return rpc_error!(RpcError::PingFailed, id)
         }
         JsonResponse::new(JsonValue::Boolean(true), id).into()
     }
@@ -276,3 +427,25 @@ impl HandlerP2p for DarkfiNode {
         self.p2p_handler.p2p.clone()
     }
 }
+
+impl HandlerStats for DarkfiNode {
+    fn rpc_stats(&self) -> &RpcStats {
+        &self.rpc_stats
+    }
+
+    fn rpc_stats_sessions(&self) -> Option<&RpcSessions> {
+        Some(&self.rpc_sessions)
+    }
+}
+
+impl HandlerSession for DarkfiNode {
+    fn rpc_session_registry(&self) -> &RpcSessions {
+        &self.rpc_sessions
+    }
+}
+
+impl HandlerLog for DarkfiNode {
+    fn log_subscriber(&self) -> Option<JsonSubscriber> {
+        self.subscribers.get("log").cloned()
+    }
+}
@@ -16,9 +16,12 @@
  * along with this program.  If not, see <https://www.gnu.org/licenses/>.
  */
 
-use std::str::FromStr;
+use std::{collections::HashMap, str::FromStr};
 
-use darkfi_sdk::{crypto::ContractId, tx::TransactionHash};
+use darkfi_sdk::{
+    crypto::{ContractId, PublicKey},
+    tx::TransactionHash,
+};
 use darkfi_serial::{deserialize_async, serialize_async};
 use log::{debug, error};
 use tinyjson::JsonValue;
@@ -29,10 +32,16 @@ use darkfi::{
         ErrorCode::{InternalError, InvalidParams, ParseError},
         JsonError, JsonResponse, JsonResult,
     },
+    rpc_error,
     util::encoding::base64,
+    Error,
 };
 
-use crate::{server_error, DarkfiNode, RpcError};
+use crate::{
+    filter::BlockFilter,
+    task::miner::{generate_blocks, MinerRewardsRecipientConfig},
+    DarkfiNode, RpcError,
+};
 
 impl DarkfiNode {
     // RPCAPI:
@@ -68,7 +77,7 @@ impl DarkfiNode {
         };
 
         if blocks.is_empty() {
-            return server_error(RpcError::UnknownBlockHeight, id, None)
+            return rpc_error!(RpcError::UnknownBlockHeight, id)
         }
 
         let block = base64::encode(&serialize_async(&blocks[0]).await);
@@ -102,6 +111,7 @@ impl DarkfiNode {
 
         let txs = match self.validator.blockchain.transactions.get(&[tx_hash], true) {
             Ok(txs) => txs,
+            Err(Error::TransactionNotFound(_)) => return rpc_error!(RpcError::TxNotFound, id),
             Err(e) => {
                 error!(target: "darkfid::rpc::blockchain_get_tx", "Failed fetching tx by hash: {}", e);
                 return JsonError::new(InternalError, None, id).into()
@@ -148,6 +158,90 @@ impl DarkfiNode {
         .into()
     }
 
+    // RPCAPI:
+    // Queries the node for its active checkpoints, enforced during sync.
+    // Returns an array of `[height, hash, source]` entries ordered by height,
+    // where source is one of `embedded`, `configured` or `finalized`.
+    //
+    // **Params:**
+    // * `None`
+    //
+    // **Returns:**
+    // * `array`: Active checkpoints
+    //
+    // --> {"jsonrpc": "2.0", "method": "blockchain.checkpoints", "params": [], "id": 1}
+    // <-- {"jsonrpc": "2.0", "result": [[1234, "HeaderHash", "finalized"]], "id": 1}
+    pub async fn blockchain_checkpoints(&self, id: u16, params: JsonValue) -> JsonResult {
+        let params = params.get::<Vec<JsonValue>>().unwrap();
+        if !params.is_empty() {
+            return JsonError::new(InvalidParams, None, id).into()
+        }
+
+        let checkpoints = self
+            .checkpoints
+            .read()
+            .await
+            .iter()
+            .map(|(height, (hash, source))| {
+                JsonValue::Array(vec![
+                    JsonValue::Number(*height as f64),
+                    JsonValue::String(hash.to_string()),
+                    JsonValue::String(source.name().to_string()),
+                ])
+            })
+            .collect();
+
+        JsonResponse::new(JsonValue::Array(checkpoints), id).into()
+    }
+
+    // RPCAPI:
+    // Queries the validator for the known soft-fork deployments, along with
+    // their state for the current best fork next block height. State is one
+    // of `defined`, `started`, `locked_in`, `active` or `failed`.
+    //
+    // **Params:**
+    // * `None`
+    //
+    // **Returns:**
+    // * `height`: `f64` Height the states were computed for
+    // * `deployments`: Array of `name`, `bit`, `start_height`, `timeout_height` and `state`
+    //
+    // --> {"jsonrpc": "2.0", "method": "blockchain.deployments", "params": [], "id": 1}
+    // <-- {"jsonrpc": "2.0", "result": {"height": 1234, "deployments": [{"name": "foo", "bit": 0, "start_height": 1000, "timeout_height": 5000, "state": "started"}]}, "id": 1}
+    pub async fn blockchain_deployments(&self, id: u16, params: JsonValue) -> JsonResult {
+        let params = params.get::<Vec<JsonValue>>().unwrap();
+        if !params.is_empty() {
+            return JsonError::new(InvalidParams, None, id).into()
+        }
+
+        let Ok((height, states)) = self.validator.deployment_states().await else {
+            return JsonError::new(InternalError, None, id).into()
+        };
+
+        let deployments = states
+            .iter()
+            .map(|(deployment, state)| {
+                JsonValue::Object(HashMap::from([
+                    ("name".to_string(), JsonValue::String(deployment.name.to_string())),
+                    ("bit".to_string(), JsonValue::Number(deployment.bit as f64)),
+                    ("start_height".to_string(), JsonValue::Number(deployment.start_height as f64)),
+                    (
+                        "timeout_height".to_string(),
+                        JsonValue::Number(deployment.timeout_height as f64),
+                    ),
+                    ("state".to_string(), JsonValue::String(state.name().to_string())),
+                ]))
+            })
+            .collect();
+
+        let result = JsonValue::Object(HashMap::from([
+            ("height".to_string(), JsonValue::Number(height as f64)),
+            ("deployments".to_string(), JsonValue::Array(deployments)),
+        ]));
+
+        JsonResponse::new(result, id).into()
+    }
+
     // RPCAPI:
     // Queries the validator to find the current best fork next block height.
     //
@@ -198,20 +292,99 @@ impl DarkfiNode {
         JsonResponse::new(JsonValue::Number(block_target as f64), id).into()
     }
 
+    // RPCAPI:
+    // Queries the validator for the difficulty history of the last confirmed
+    // blocks, along with a network hashrate estimate over them. The window is
+    // capped at 720 blocks.
+    //
+    // **Params:**
+    // * `array[0]`: `f64` Amount of latest blocks to include
+    //
+    // **Returns:**
+    // * `samples`: Array of per-block `height`, `timestamp`, `difficulty` (string)
+    //   and `solve_time` in seconds (`null` for the genesis block)
+    // * `hashrate`: `f64` Estimated network hashes per second over the window
+    //
+    // --> {"jsonrpc": "2.0", "method": "blockchain.get_difficulty_history", "params": [60], "id": 1}
+    // <-- {"jsonrpc": "2.0", "result": {"samples": [{"height": 1234, "timestamp": 1734000000, "difficulty": "4096", "solve_time": 94}, ...], "hashrate": 41.2}, "id": 1}
+    pub async fn blockchain_get_difficulty_history(
+        &self,
+        id: u16,
+        params: JsonValue,
+    ) -> JsonResult {
+        let params = params.get::<Vec<JsonValue>>().unwrap();
+        if params.len() != 1 || !params[0].is_number() {
+            return JsonError::new(InvalidParams, None, id).into()
+        }
+
+        let window = *params[0].get::<f64>().unwrap();
+        if window < 1.0 {
+            return JsonError::new(InvalidParams, None, id).into()
+        }
+
+        let history = match self.validator.difficulty_history(window as usize) {
+            Ok(v) => v,
+            Err(e) => {
+                error!(target: "darkfid::rpc::blockchain_get_difficulty_history", "Failed fetching difficulty history: {}", e);
+                return JsonError::new(InternalError, None, id).into()
+            }
+        };
+
+        let samples = history
+            .samples
+            .into_iter()
+            .map(|s| {
+                let solve_time = match s.solve_time {
+                    Some(t) => JsonValue::Number(t as f64),
+                    None => JsonValue::Null,
+                };
+                JsonValue::Object(HashMap::from([
+                    ("height".to_string(), JsonValue::Number(s.height as f64)),
+                    ("timestamp".to_string(), JsonValue::Number(s.timestamp.inner() as f64)),
+                    ("difficulty".to_string(), JsonValue::String(s.difficulty.to_string())),
+                    ("solve_time".to_string(), solve_time),
+                ]))
+            })
+            .collect();
+
+        let result = JsonValue::Object(HashMap::from([
+            ("samples".to_string(), JsonValue::Array(samples)),
+            ("hashrate".to_string(), JsonValue::Number(history.hashrate)),
+        ]));
+
+        JsonResponse::new(result, id).into()
+    }
+
     // RPCAPI:
     // Initializes a subscription to new incoming blocks.
     // Once a subscription is established, `darkfid` will send JSON-RPC notifications of
     // new incoming blocks to the subscriber.
     //
+    // An optional filter object can be provided, containing base58-encoded contract
+    // and/or function IDs. In that case, only blocks containing transactions calling
+    // them are sent, trimmed down to just the matching transactions.
+    //
+    // **Params:**
+    // * `array[0]`: Optional filter object `{"contracts": [...], "functions": [...]}`
+    //
     // --> {"jsonrpc": "2.0", "method": "blockchain.subscribe_blocks", "params": [], "id": 1}
+    // --> {"jsonrpc": "2.0", "method": "blockchain.subscribe_blocks", "params": [{"contracts": [`contract_id`]}], "id": 1}
     // <-- {"jsonrpc": "2.0", "method": "blockchain.subscribe_blocks", "params": [`blockinfo`]}
     pub async fn blockchain_subscribe_blocks(&self, id: u16, params: JsonValue) -> JsonResult {
         let params = params.get::<Vec<JsonValue>>().unwrap();
-        if !params.is_empty() {
-            return JsonError::new(InvalidParams, None, id).into()
-        }
+        let subscriber = self.subscribers.get("blocks").unwrap();
+
+        match params.len() {
+            0 => subscriber.clone().into(),
+            1 => {
+                let Some(filter) = BlockFilter::from_json(&params[0]) else {
+                    return JsonError::new(InvalidParams, None, id).into()
+                };
 
-        self.subscribers.get("blocks").unwrap().clone().into()
+                subscriber.with_filter(filter.into_notification_filter()).into()
+            }
+            _ => JsonError::new(InvalidParams, None, id).into(),
+        }
     }
 
     // RPCAPI:
@@ -245,6 +418,69 @@ impl DarkfiNode {
         self.subscribers.get("proposals").unwrap().clone().into()
     }
 
+    // RPCAPI:
+    // Initializes a subscription to best fork tip changes while the node is mining.
+    // Once a subscription is established, `darkfid` will send JSON-RPC notifications
+    // with the new tip hash and height, so miner daemons can drop stale jobs immediately.
+    //
+    // --> {"jsonrpc": "2.0", "method": "blockchain.subscribe_tips", "params": [], "id": 1}
+    // <-- {"jsonrpc": "2.0", "method": "blockchain.subscribe_tips", "params": [`hash`, `height`]}
+    pub async fn blockchain_subscribe_tips(&self, id: u16, params: JsonValue) -> JsonResult {
+        let params = params.get::<Vec<JsonValue>>().unwrap();
+        if !params.is_empty() {
+            return JsonError::new(InvalidParams, None, id).into()
+        }
+
+        self.subscribers.get("tips").unwrap().clone().into()
+    }
+
+    // RPCAPI:
+    // Instantly produces the given number of blocks on top of the best fork, containing
+    // the current mempool transactions, and rewards them to the given address.
+    // Only available when the node runs on localnet, so developers can iterate without
+    // running minerd.
+    // Returns the produced blocks hashes upon success.
+    //
+    // **Params:**
+    // * `array[0]`: `u32` Number of blocks to produce (as string)
+    // * `array[1]`: base58-encoded rewards recipient address
+    //
+    // --> {"jsonrpc": "2.0", "method": "blockchain.generate_blocks", "params": ["3", "2W7e..."], "id": 1}
+    // <-- {"jsonrpc": "2.0", "result": ["8b61...", ...], "id": 1}
+    pub async fn blockchain_generate_blocks(&self, id: u16, params: JsonValue) -> JsonResult {
+        if !self.devnet {
+            return rpc_error!(RpcError::DevnetOnly, id)
+        }
+
+        let params = params.get::<Vec<JsonValue>>().unwrap();
+        if params.len() != 2 || !params[0].is_string() || !params[1].is_string() {
+            return JsonError::new(InvalidParams, None, id).into()
+        }
+
+        let count = match params[0].get::<String>().unwrap().parse::<u32>() {
+            Ok(v) => v,
+            Err(_) => return JsonError::new(ParseError, None, id).into(),
+        };
+
+        let recipient = match PublicKey::from_str(params[1].get::<String>().unwrap()) {
+            Ok(v) => v,
+            Err(_) => return JsonError::new(ParseError, None, id).into(),
+        };
+
+        let recipient_config =
+            MinerRewardsRecipientConfig { recipient, spend_hook: None, user_data: None };
+        let hashes = match generate_blocks(self, &recipient_config, count as usize).await {
+            Ok(v) => v,
+            Err(e) => {
+                error!(target: "darkfid::rpc::blockchain_generate_blocks", "Failed generating blocks: {e}");
+                return rpc_error!(RpcError::BlockGenerationFail, id)
+            }
+        };
+
+        let hashes = hashes.iter().map(|h| JsonValue::String(h.to_string())).collect();
+        JsonResponse::new(JsonValue::Array(hashes), id).into()
+    }
+
     // RPCAPI:
     // Performs a lookup of zkas bincodes for a given contract ID and returns all of
     // them, including their namespace.
@@ -283,7 +519,7 @@ impl DarkfiNode {
                 target: "darkfid::rpc::blockchain_lookup_zkas", "Did not find zkas db for ContractId: {}",
                 contract_id
             );
-            return server_error(RpcError::ContractZkasDbNotFound, id, None)
+            return rpc_error!(RpcError::ContractZkasDbNotFound, id)
         };
 
         let mut ret = vec![];
@@ -313,4 +549,72 @@ impl DarkfiNode {
 
         JsonResponse::new(JsonValue::Array(ret), id).into()
     }
+
+    // RPCAPI:
+    // Queries a single key of a contract's state tree and returns its value.
+    // Returns `null` if the key does not exist in the tree.
+    //
+    // **Params:**
+    // * `array[0]`: base58-encoded contract ID string
+    // * `array[1]`: Tree name string
+    // * `array[2]`: base64-encoded key bytes
+    //
+    // **Returns:**
+    // * base64-encoded value bytes, or `null`
+    //
+    // --> {"jsonrpc": "2.0", "method": "blockchain.get_contract_state_key", "params": ["BZHKGQ26bzmBithTQYTJtjo2QdCqpkR9tjSBopT4yf4o", "coin_roots", "ABCD..."], "id": 1}
+    // <-- {"jsonrpc": "2.0", "result": "", "id": 1}
+    pub async fn blockchain_get_contract_state_key(
+        &self,
+        id: u16,
+        params: JsonValue,
+    ) -> JsonResult {
+        let params = params.get::<Vec<JsonValue>>().unwrap();
+        if params.len() != 3 ||
+            !params[0].is_string() ||
+            !params[1].is_string() ||
+            !params[2].is_string()
+        {
+            return JsonError::new(InvalidParams, None, id).into()
+        }
+
+        let contract_id = params[0].get::<String>().unwrap();
+        let contract_id = match ContractId::from_str(contract_id) {
+            Ok(v) => v,
+            Err(e) => {
+                error!(target: "darkfid::rpc::blockchain_get_contract_state_key", "Error decoding string to ContractId: {}", e);
+                return JsonError::new(InvalidParams, None, id).into()
+            }
+        };
+
+        let tree_name = params[1].get::<String>().unwrap();
+
+        let Some(key) = base64::decode(params[2].get::<String>().unwrap()) else {
+            error!(target: "darkfid::rpc::blockchain_get_contract_state_key", "Error decoding base64 key");
+            return JsonError::new(InvalidParams, None, id).into()
+        };
+
+        let Ok(tree) = self.validator.blockchain.contracts.lookup(
+            &self.validator.blockchain.sled_db,
+            &contract_id,
+            tree_name,
+        ) else {
+            error!(
+                target: "darkfid::rpc::blockchain_get_contract_state_key", "Did not find state tree {} for ContractId: {}",
+                tree_name, contract_id
+            );
+            return rpc_error!(RpcError::ContractStateNotFound, id)
+        };
+
+        match tree.get(key) {
+            Ok(Some(value)) => {
+                JsonResponse::new(JsonValue::String(base64::encode(&value)), id).into()
+            }
+            Ok(None) => JsonResponse::new(JsonValue::Null, id).into(),
+            Err(e) => {
+                error!(target: "darkfid::rpc::blockchain_get_contract_state_key", "Internal sled error: {}", e);
+                JsonError::new(InternalError, None, id).into()
+            }
+        }
+    }
 }
@@ -0,0 +1,110 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2025 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+use std::str::FromStr;
+
+use log::error;
+use tinyjson::JsonValue;
+
+use darkfi::{
+    blockchain::HeaderHash,
+    rpc::jsonrpc::{ErrorCode::InvalidParams, JsonError, JsonResponse, JsonResult},
+    rpc_error,
+};
+
+use crate::{DarkfiNode, RpcError};
+
+impl DarkfiNode {
+    // RPCAPI:
+    // Forces a reorg by marking the given block as invalid, analogous to
+    // Bitcoin Core's `invalidateblock`. A confirmed block gets rolled back
+    // along with all blocks on top of it, and their transactions return to
+    // the mempool. An unconfirmed block gets dropped from the forks along
+    // with its descendants. The block is rejected until reconsidered.
+    // Only available when the node runs on localnet.
+    // Returns the dropped blocks hashes upon success.
+    //
+    // **Params:**
+    // * `array[0]`: Hex-encoded block hash
+    //
+    // --> {"jsonrpc": "2.0", "method": "devtools.invalidate_block", "params": ["8b61..."], "id": 1}
+    // <-- {"jsonrpc": "2.0", "result": ["8b61...", ...], "id": 1}
+    pub async fn devtools_invalidate_block(&self, id: u16, params: JsonValue) -> JsonResult {
+        if !self.devnet {
+            return rpc_error!(RpcError::DevnetOnly, id)
+        }
+
+        let params = params.get::<Vec<JsonValue>>().unwrap();
+        if params.len() != 1 || !params[0].is_string() {
+            return JsonError::new(InvalidParams, None, id).into()
+        }
+
+        let Ok(hash) = HeaderHash::from_str(params[0].get::<String>().unwrap()) else {
+            return rpc_error!(RpcError::ParseError, id)
+        };
+
+        let hashes = match self.validator.invalidate_block(&hash).await {
+            Ok(v) => v,
+            Err(e) => {
+                error!(target: "darkfid::rpc::devtools_invalidate_block", "Failed invalidating block {hash}: {e}");
+                return rpc_error!(RpcError::BlockInvalidationFail, id)
+            }
+        };
+
+        let hashes = hashes.iter().map(|h| JsonValue::String(h.to_string())).collect();
+        JsonResponse::new(JsonValue::Array(hashes), id).into()
+    }
+
+    // RPCAPI:
+    // Removes the invalid mark of a block previously invalidated with
+    // `devtools.invalidate_block`, re-appending it and the blocks dropped
+    // along with it as proposals, analogous to Bitcoin Core's
+    // `reconsiderblock`. They become the best chain again if they outrank
+    // the current one. Only available when the node runs on localnet.
+    // Returns the re-appended blocks hashes upon success.
+    //
+    // **Params:**
+    // * `array[0]`: Hex-encoded block hash
+    //
+    // --> {"jsonrpc": "2.0", "method": "devtools.reconsider_block", "params": ["8b61..."], "id": 1}
+    // <-- {"jsonrpc": "2.0", "result": ["8b61...", ...], "id": 1}
+    pub async fn devtools_reconsider_block(&self, id: u16, params: JsonValue) -> JsonResult {
+        if !self.devnet {
+            return rpc_error!(RpcError::DevnetOnly, id)
+        }
+
+        let params = params.get::<Vec<JsonValue>>().unwrap();
+        if params.len() != 1 || !params[0].is_string() {
+            return JsonError::new(InvalidParams, None, id).into()
+        }
+
+        let Ok(hash) = HeaderHash::from_str(params[0].get::<String>().unwrap()) else {
+            return rpc_error!(RpcError::ParseError, id)
+        };
+
+        let hashes = match self.validator.reconsider_block(&hash).await {
+            Ok(v) => v,
+            Err(e) => {
+                error!(target: "darkfid::rpc::devtools_reconsider_block", "Failed reconsidering block {hash}: {e}");
+                return rpc_error!(RpcError::BlockReconsiderationFail, id)
+            }
+        };
+
+        let hashes = hashes.iter().map(|h| JsonValue::String(h.to_string())).collect();
+        JsonResponse::new(JsonValue::Array(hashes), id).into()
+    }
+}
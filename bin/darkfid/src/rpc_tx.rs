@@ -16,21 +16,40 @@
  * along with this program.  If not, see <https://www.gnu.org/licenses/>.
  */
 
+use std::collections::HashMap;
+
+use darkfi_auction_contract::AuctionFunction;
+use darkfi_dao_contract::DaoFunction;
+use darkfi_darkname_contract::DarknameFunction;
+use darkfi_deployooor_contract::DeployFunction;
+use darkfi_money_contract::MoneyFunction;
+use darkfi_oracle_contract::OracleFunction;
+use darkfi_sdk::{
+    crypto::{
+        ContractId, AUCTION_CONTRACT_ID, DAO_CONTRACT_ID, DARKNAME_CONTRACT_ID,
+        DEPLOYOOOR_CONTRACT_ID, MONEY_CONTRACT_ID, ORACLE_CONTRACT_ID,
+    },
+    hex::{decode_hex, AsHex},
+};
 use darkfi_serial::deserialize_async;
 use log::{error, warn};
 use tinyjson::JsonValue;
 
 use darkfi::{
+    error::TxVerifyFailed,
     rpc::jsonrpc::{
         ErrorCode::{InternalError, InvalidParams},
         JsonError, JsonResponse, JsonResult,
     },
+    rpc_error,
     tx::Transaction,
     util::encoding::base64,
+    validator::{admission::TxSource, double_spend::EQUIVOCATION_THRESHOLD},
+    Error,
 };
 
 use super::DarkfiNode;
-use crate::{server_error, RpcError};
+use crate::RpcError;
 
 impl DarkfiNode {
     // RPCAPI:
@@ -48,7 +67,7 @@ impl DarkfiNode {
 
         if !*self.validator.synced.read().await {
             error!(target: "darkfid::rpc::tx_simulate", "Blockchain is not synced");
-            return server_error(RpcError::NotSynced, id, None)
+            return rpc_error!(RpcError::NotSynced, id)
         }
 
         // Try to deserialize the transaction
@@ -57,7 +76,7 @@ impl DarkfiNode {
             Some(v) => v,
             None => {
                 error!(target: "darkfid::rpc::tx_simulate", "Failed decoding base64 transaction");
-                return server_error(RpcError::ParseError, id, None)
+                return rpc_error!(RpcError::ParseError, id)
             }
         };
 
@@ -65,18 +84,17 @@ impl DarkfiNode {
             Ok(v) => v,
             Err(e) => {
                 error!(target: "darkfid::rpc::tx_simulate", "Failed deserializing bytes into Transaction: {}", e);
-                return server_error(RpcError::ParseError, id, None)
+                return rpc_error!(RpcError::ParseError, id)
             }
         };
 
         // Simulate state transition
-        let result = self.validator.append_tx(&tx, false).await;
-        if result.is_err() {
-            error!(
-                target: "darkfid::rpc::tx_simulate", "Failed to validate state transition: {}",
-                result.err().unwrap()
-            );
-            return server_error(RpcError::TxSimulationFail, id, None)
+        if let Err(e) = self.validator.append_tx(&tx, false, &TxSource::Local).await {
+            error!(target: "darkfid::rpc::tx_simulate", "Failed to validate state transition: {}", e);
+            if let Error::TxVerifyFailed(TxVerifyFailed::AdmissionRejected(_)) = e {
+                return rpc_error!(RpcError::TxAdmissionRejected, id)
+            }
+            return rpc_error!(RpcError::TxSimulationFail, id)
         };
 
         JsonResponse::new(JsonValue::Boolean(true), id).into()
@@ -98,7 +116,7 @@ impl DarkfiNode {
 
         if !*self.validator.synced.read().await {
             error!(target: "darkfid::rpc::tx_broadcast", "Blockchain is not synced");
-            return server_error(RpcError::NotSynced, id, None)
+            return rpc_error!(RpcError::NotSynced, id)
         }
 
         // Try to deserialize the transaction
@@ -107,7 +125,7 @@ impl DarkfiNode {
             Some(v) => v,
             None => {
                 error!(target: "darkfid::rpc::tx_broadcast", "Failed decoding base64 transaction");
-                return server_error(RpcError::ParseError, id, None)
+                return rpc_error!(RpcError::ParseError, id)
             }
         };
 
@@ -115,7 +133,7 @@ impl DarkfiNode {
             Ok(v) => v,
             Err(e) => {
                 error!(target: "darkfid::rpc::tx_broadcast", "Failed deserializing bytes into Transaction: {}", e);
-                return server_error(RpcError::ParseError, id, None)
+                return rpc_error!(RpcError::ParseError, id)
             }
         };
 
@@ -128,9 +146,14 @@ impl DarkfiNode {
             "Failed to validate state transition"
         };
         // We'll perform the state transition check here.
-        if let Err(e) = self.validator.append_tx(&tx, self.rpc_client.is_some()).await {
+        if let Err(e) =
+            self.validator.append_tx(&tx, self.rpc_client.is_some(), &TxSource::Local).await
+        {
             error!(target: "darkfid::rpc::tx_broadcast", "{}: {}", error_message, e);
-            return server_error(RpcError::TxSimulationFail, id, None)
+            if let Error::TxVerifyFailed(TxVerifyFailed::AdmissionRejected(_)) = e {
+                return rpc_error!(RpcError::TxAdmissionRejected, id)
+            }
+            return rpc_error!(RpcError::TxSimulationFail, id)
         };
 
         self.p2p_handler.p2p.broadcast(&tx).await;
@@ -156,7 +179,7 @@ impl DarkfiNode {
 
         if !*self.validator.synced.read().await {
             error!(target: "darkfid::rpc::tx_pending", "Blockchain is not synced");
-            return server_error(RpcError::NotSynced, id, None)
+            return rpc_error!(RpcError::NotSynced, id)
         }
 
         let pending_txs = match self.validator.blockchain.get_pending_txs() {
@@ -187,7 +210,7 @@ impl DarkfiNode {
 
         if !*self.validator.synced.read().await {
             error!(target: "darkfid::rpc::tx_clean_pending", "Blockchain is not synced");
-            return server_error(RpcError::NotSynced, id, None)
+            return rpc_error!(RpcError::NotSynced, id)
         }
 
         let pending_txs = match self.validator.blockchain.get_pending_txs() {
@@ -224,7 +247,7 @@ impl DarkfiNode {
 
         if !*self.validator.synced.read().await {
             error!(target: "darkfid::rpc::tx_calculate_gas", "Blockchain is not synced");
-            return server_error(RpcError::NotSynced, id, None)
+            return rpc_error!(RpcError::NotSynced, id)
         }
 
         // Try to deserialize the transaction
@@ -233,7 +256,7 @@ impl DarkfiNode {
             Some(v) => v,
             None => {
                 error!(target: "darkfid::rpc::tx_calculate_gas", "Failed decoding base64 transaction");
-                return server_error(RpcError::ParseError, id, None)
+                return rpc_error!(RpcError::ParseError, id)
             }
         };
 
@@ -241,7 +264,7 @@ impl DarkfiNode {
             Ok(v) => v,
             Err(e) => {
                 error!(target: "darkfid::rpc::tx_calculate_gas", "Failed deserializing bytes into Transaction: {}", e);
-                return server_error(RpcError::ParseError, id, None)
+                return rpc_error!(RpcError::ParseError, id)
             }
         };
 
@@ -255,9 +278,249 @@ impl DarkfiNode {
                 target: "darkfid::rpc::tx_calculate_gas", "Failed to validate state transition: {}",
                 result.err().unwrap()
             );
-            return server_error(RpcError::TxGasCalculationFail, id, None)
+            return rpc_error!(RpcError::TxGasCalculationFail, id)
         };
 
         JsonResponse::new(JsonValue::Number(result.unwrap().total_gas_used() as f64), id).into()
     }
+
+    // RPCAPI:
+    // Decode a raw transaction into a structured breakdown, for debugging
+    // and explorer use. The transaction can be given either base58 or hex
+    // encoded, selected by the optional second parameter. When it is omitted,
+    // base58 is tried first and hex second. Calls to the native contracts get
+    // their contract and function names resolved, and the paid fee is returned
+    // if the transaction has a `Money::FeeV1` call. The transaction is not
+    // verified in any way.
+    //
+    // --> {"jsonrpc": "2.0", "method": "tx.decode", "params": ["base58EncodedTX"], "id": 1}
+    // --> {"jsonrpc": "2.0", "method": "tx.decode", "params": ["hexEncodedTX", "hex"], "id": 1}
+    // <-- {"jsonrpc": "2.0", "result": {"hash": "txID...", "calls": [{"index": 0, "contract_id": "...", "contract": "Money", "function_id": 0, "function": "FeeV1", ...}, ...], "proofs": 2, "signatures": 2, "fee": 1234}, "id": 1}
+    pub async fn tx_decode(&self, id: u16, params: JsonValue) -> JsonResult {
+        let params = params.get::<Vec<JsonValue>>().unwrap();
+        if params.is_empty() || params.len() > 2 || !params.iter().all(|p| p.is_string()) {
+            return JsonError::new(InvalidParams, None, id).into()
+        }
+
+        // Parse the requested encoding, if any
+        let encoding = match params.get(1).map(|p| p.get::<String>().unwrap().as_str()) {
+            None => None,
+            Some(v @ ("base58" | "hex")) => Some(v),
+            Some(_) => return JsonError::new(InvalidParams, None, id).into(),
+        };
+
+        // Decode the transaction bytes using the requested encoding,
+        // otherwise try base58 first and then hex.
+        let tx_enc = params[0].get::<String>().unwrap().trim();
+        let base58 = || bs58::decode(tx_enc).into_vec().map_err(|e| e.to_string());
+        let hex = || decode_hex(tx_enc).collect::<Result<Vec<u8>, _>>().map_err(|e| e.to_string());
+        let tx_bytes = match encoding {
+            Some("base58") => base58(),
+            Some(_) => hex(),
+            None => base58().or_else(|_| hex()),
+        };
+        let tx_bytes = match tx_bytes {
+            Ok(v) => v,
+            Err(e) => {
+                error!(target: "darkfid::rpc::tx_decode", "Failed decoding base58/hex transaction: {}", e);
+                return rpc_error!(RpcError::ParseError, id)
+            }
+        };
+
+        let tx: Transaction = match deserialize_async(&tx_bytes).await {
+            Ok(v) => v,
+            Err(e) => {
+                error!(target: "darkfid::rpc::tx_decode", "Failed deserializing bytes into Transaction: {}", e);
+                return rpc_error!(RpcError::ParseError, id)
+            }
+        };
+
+        let mut calls = Vec::with_capacity(tx.calls.len());
+        for (i, call) in tx.calls.iter().enumerate() {
+            let contract_id = call.data.contract_id;
+            let function_id = call.data.data.first().copied();
+            let (contract, function) = native_call_names(&contract_id, function_id);
+            let contract = contract.map_or(JsonValue::Null, |x| JsonValue::String(x.to_string()));
+            let function = function.map_or(JsonValue::Null, |x| JsonValue::String(x.to_string()));
+            let function_id = function_id.map_or(JsonValue::Null, |x| JsonValue::Number(x as f64));
+
+            let proofs = tx.proofs.get(i).map(|p| p.len()).unwrap_or(0);
+            let signatures = tx.signatures.get(i).map(|s| s.len()).unwrap_or(0);
+
+            let parent_index = match call.parent_index {
+                Some(v) => JsonValue::Number(v as f64),
+                None => JsonValue::Null,
+            };
+            let children_indexes =
+                call.children_indexes.iter().map(|x| JsonValue::Number(*x as f64)).collect();
+
+            calls.push(JsonValue::Object(HashMap::from([
+                ("index".to_string(), JsonValue::Number(i as f64)),
+                ("contract_id".to_string(), JsonValue::String(contract_id.to_string())),
+                ("contract".to_string(), contract),
+                ("function_id".to_string(), function_id),
+                ("function".to_string(), function),
+                ("data_len".to_string(), JsonValue::Number(call.data.data.len() as f64)),
+                ("parent_index".to_string(), parent_index),
+                ("children_indexes".to_string(), JsonValue::Array(children_indexes)),
+                ("proofs".to_string(), JsonValue::Number(proofs as f64)),
+                ("signatures".to_string(), JsonValue::Number(signatures as f64)),
+            ])));
+        }
+
+        // Find the fee call the same way the validator does, as the
+        // first `Money::FeeV1` call in the transaction.
+        let mut fee = JsonValue::Null;
+        if let Some(call) = tx.calls.iter().find(|call| call.data.is_money_fee()) {
+            if call.data.data.len() >= 9 {
+                if let Ok(v) = deserialize_async::<u64>(&call.data.data[1..9]).await {
+                    fee = JsonValue::Number(v as f64);
+                }
+            }
+        }
+
+        let proofs: usize = tx.proofs.iter().map(|p| p.len()).sum();
+        let signatures: usize = tx.signatures.iter().map(|s| s.len()).sum();
+
+        let result = JsonValue::Object(HashMap::from([
+            ("hash".to_string(), JsonValue::String(tx.hash().to_string())),
+            ("calls".to_string(), JsonValue::Array(calls)),
+            ("proofs".to_string(), JsonValue::Number(proofs as f64)),
+            ("signatures".to_string(), JsonValue::Number(signatures as f64)),
+            ("fee".to_string(), fee),
+        ]));
+
+        JsonResponse::new(result, id).into()
+    }
+
+    // RPCAPI:
+    // Returns the recorded double-spend proofs, oldest first, along with the
+    // decaying number of proofs attributed to each peer. Each proof references
+    // two distinct valid transactions spending the same nullifier, the sources
+    // they were received from, and the block proposal the conflicting one
+    // was found in, if any. Peers only get proofs attributed when they relayed
+    // both transactions, and reaching the `threshold` marks them equivocating.
+    //
+    // --> {"jsonrpc": "2.0", "method": "tx.double_spends", "params": [], "id": 1}
+    // <-- {"jsonrpc": "2.0", "result": {"proofs": [{"nullifier": "...", "first_tx": "txID...", "first_source": "local", "second_tx": "txID...", "second_source": "1.2.3.4", "block": null, "timestamp": 1234}, ...], "peers": {"1.2.3.4": 1}, "threshold": 3}, "id": 1}
+    pub async fn tx_double_spends(&self, id: u16, params: JsonValue) -> JsonResult {
+        let params = params.get::<Vec<JsonValue>>().unwrap();
+        if !params.is_empty() {
+            return JsonError::new(InvalidParams, None, id).into()
+        }
+
+        let proofs = self
+            .validator
+            .double_spends
+            .proofs()
+            .iter()
+            .map(|proof| {
+                let block = match proof.block {
+                    Some(hash) => JsonValue::String(hash.to_string()),
+                    None => JsonValue::Null,
+                };
+                JsonValue::Object(HashMap::from([
+                    ("nullifier".to_string(), JsonValue::String(proof.nullifier.hex())),
+                    ("first_tx".to_string(), JsonValue::String(proof.first_tx.to_string())),
+                    ("first_source".to_string(), JsonValue::String(proof.first_source.to_string())),
+                    ("second_tx".to_string(), JsonValue::String(proof.second_tx.to_string())),
+                    (
+                        "second_source".to_string(),
+                        JsonValue::String(proof.second_source.to_string()),
+                    ),
+                    ("block".to_string(), block),
+                    ("timestamp".to_string(), JsonValue::Number(proof.timestamp.inner() as f64)),
+                ]))
+            })
+            .collect();
+
+        let peers = self
+            .validator
+            .double_spends
+            .scores()
+            .into_iter()
+            .map(|(peer, score)| (peer, JsonValue::Number(score as f64)))
+            .collect();
+
+        let result = JsonValue::Object(HashMap::from([
+            ("proofs".to_string(), JsonValue::Array(proofs)),
+            ("peers".to_string(), JsonValue::Object(peers)),
+            ("threshold".to_string(), JsonValue::Number(EQUIVOCATION_THRESHOLD as f64)),
+        ]));
+
+        JsonResponse::new(result, id).into()
+    }
+}
+
+/// Auxiliary function to resolve the contract and function names of a
+/// call, if it targets one of the native contracts.
+fn native_call_names(
+    contract_id: &ContractId,
+    function_id: Option<u8>,
+) -> (Option<&'static str>, Option<&'static str>) {
+    let Some(function_id) = function_id else { return (None, None) };
+
+    if *contract_id == *MONEY_CONTRACT_ID {
+        let function = MoneyFunction::try_from(function_id).ok().map(|f| match f {
+            MoneyFunction::FeeV1 => "FeeV1",
+            MoneyFunction::GenesisMintV1 => "GenesisMintV1",
+            MoneyFunction::PoWRewardV1 => "PoWRewardV1",
+            MoneyFunction::TransferV1 => "TransferV1",
+            MoneyFunction::OtcSwapV1 => "OtcSwapV1",
+            MoneyFunction::AuthTokenMintV1 => "AuthTokenMintV1",
+            MoneyFunction::AuthTokenFreezeV1 => "AuthTokenFreezeV1",
+            MoneyFunction::TokenMintV1 => "TokenMintV1",
+        });
+        return (Some("Money"), function)
+    }
+
+    if *contract_id == *DAO_CONTRACT_ID {
+        let function = DaoFunction::try_from(function_id).ok().map(|f| match f {
+            DaoFunction::Mint => "Mint",
+            DaoFunction::Propose => "Propose",
+            DaoFunction::Vote => "Vote",
+            DaoFunction::Exec => "Exec",
+            DaoFunction::AuthMoneyTransfer => "AuthMoneyTransfer",
+        });
+        return (Some("DAO"), function)
+    }
+
+    if *contract_id == *DEPLOYOOOR_CONTRACT_ID {
+        let function = DeployFunction::try_from(function_id).ok().map(|f| match f {
+            DeployFunction::DeployV1 => "DeployV1",
+            DeployFunction::LockV1 => "LockV1",
+        });
+        return (Some("Deployooor"), function)
+    }
+
+    if *contract_id == *DARKNAME_CONTRACT_ID {
+        let function = DarknameFunction::try_from(function_id).ok().map(|f| match f {
+            DarknameFunction::RegisterV1 => "RegisterV1",
+            DarknameFunction::RenewV1 => "RenewV1",
+            DarknameFunction::TransferV1 => "TransferV1",
+            DarknameFunction::CommitV1 => "CommitV1",
+        });
+        return (Some("Darkname"), function)
+    }
+
+    if *contract_id == *AUCTION_CONTRACT_ID {
+        let function = AuctionFunction::try_from(function_id).ok().map(|f| match f {
+            AuctionFunction::CreateV1 => "CreateV1",
+            AuctionFunction::BidV1 => "BidV1",
+            AuctionFunction::RevealV1 => "RevealV1",
+            AuctionFunction::ClaimV1 => "ClaimV1",
+        });
+        return (Some("Auction"), function)
+    }
+
+    if *contract_id == *ORACLE_CONTRACT_ID {
+        let function = OracleFunction::try_from(function_id).ok().map(|f| match f {
+            OracleFunction::RegisterV1 => "RegisterV1",
+            OracleFunction::RotateV1 => "RotateV1",
+            OracleFunction::SubmitV1 => "SubmitV1",
+        });
+        return (Some("Oracle"), function)
+    }
+
+    (None, None)
 }
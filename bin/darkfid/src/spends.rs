@@ -0,0 +1,58 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2025 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use darkfi::{tx::Transaction, validator::double_spend::SpendExtractor};
+use darkfi_money_contract::{
+    model::{MoneyFeeParamsV1, MoneyTransferParamsV1},
+    MoneyFunction,
+};
+use darkfi_sdk::crypto::MONEY_CONTRACT_ID;
+use darkfi_serial::deserialize;
+
+/// Extracts the nullifiers revealed by native Money contract calls
+pub struct MoneyNullifiers;
+
+impl SpendExtractor for MoneyNullifiers {
+    fn nullifiers(&self, tx: &Transaction) -> Vec<[u8; 32]> {
+        let mut nullifiers = vec![];
+        for call in &tx.calls {
+            if call.data.contract_id != *MONEY_CONTRACT_ID {
+                continue
+            }
+
+            let Some((function, data)) = call.data.data.split_first() else { continue };
+            match MoneyFunction::try_from(*function) {
+                Ok(MoneyFunction::FeeV1) => {
+                    // Fee call parameters are prefixed with the paid fee
+                    let Some(data) = data.get(8..) else { continue };
+                    if let Ok(params) = deserialize::<MoneyFeeParamsV1>(data) {
+                        nullifiers.push(params.input.nullifier.to_bytes());
+                    }
+                }
+                Ok(MoneyFunction::TransferV1) | Ok(MoneyFunction::OtcSwapV1) => {
+                    if let Ok(params) = deserialize::<MoneyTransferParamsV1>(data) {
+                        nullifiers.extend(params.inputs.iter().map(|i| i.nullifier.to_bytes()));
+                    }
+                }
+                _ => continue,
+            }
+        }
+
+        nullifiers
+    }
+}
@@ -16,7 +16,7 @@
  * along with this program.  If not, see <https://www.gnu.org/licenses/>.
  */
 
-use std::str::FromStr;
+use std::{future::Future, str::FromStr};
 
 use darkfi::{
     blockchain::HeaderHash,
@@ -30,10 +30,14 @@ use darkfi_sdk::{
     pasta::{group::ff::PrimeField, pallas},
 };
 use darkfi_serial::serialize_async;
-use log::{error, info};
+use log::{error, info, warn};
 
 use crate::{
-    task::{garbage_collect_task, miner::MinerRewardsRecipientConfig, miner_task, sync_task},
+    checkpoints::{CheckpointSource, Checkpoints},
+    task::{
+        garbage_collect_task, miner::MinerRewardsRecipientConfig, miner_task, stale_tip_watchdog,
+        sync_task,
+    },
     DarkfiNodePtr,
 };
 
@@ -43,6 +47,10 @@ pub struct ConsensusInitTaskConfig {
     pub skip_sync: bool,
     pub checkpoint_height: Option<u32>,
     pub checkpoint: Option<String>,
+    pub checkpoints: Vec<(u32, HeaderHash, CheckpointSource)>,
+    pub checkpoint_depth: u32,
+    pub stale_tip_multiplier: u32,
+    pub snapshot_sync: bool,
     pub miner: bool,
     pub recipient: Option<String>,
     pub spend_hook: Option<String>,
@@ -70,6 +78,13 @@ pub async fn consensus_init_task(
     info!(target: "darkfid::task::consensus_init_task", "Generating new empty fork...");
     node.validator.consensus.generate_empty_fork().await?;
 
+    // Pin configured checkpoints
+    let mut checkpoints = Checkpoints::new(config.checkpoint_depth);
+    for (height, hash, source) in &config.checkpoints {
+        checkpoints.pin(*height, *hash, *source)?;
+    }
+    *node.checkpoints.write().await = checkpoints;
+
     // Sync blockchain
     let checkpoint = if !config.skip_sync {
         // Parse configured checkpoint
@@ -78,12 +93,14 @@ pub async fn consensus_init_task(
         }
 
         let checkpoint = if let Some(height) = config.checkpoint_height {
-            Some((height, HeaderHash::from_str(config.checkpoint.as_ref().unwrap())?))
+            let hash = HeaderHash::from_str(config.checkpoint.as_ref().unwrap())?;
+            node.checkpoints.write().await.pin(height, hash, CheckpointSource::Configured)?;
+            Some((height, hash))
         } else {
             None
         };
 
-        sync_task(&node, checkpoint).await?;
+        sync_task(&node, checkpoint, config.snapshot_sync).await?;
         checkpoint
     } else {
         *node.validator.synced.write().await = true;
@@ -129,53 +146,83 @@ pub async fn consensus_init_task(
         None
     };
 
-    // Gracefully handle network disconnections
+    // Gracefully handle network disconnections and stale chain tips
     loop {
-        let result = if config.miner {
-            miner_task(&node, recipient_config.as_ref().unwrap(), config.skip_sync, &ex).await
-        } else {
-            replicator_task(&node, &ex).await
-        };
+        let miner_config = recipient_config.as_ref().map(|r| (r, config.skip_sync));
+        let watchdog = stale_tip_watchdog(&node, config.stale_tip_multiplier);
+        let result = watched_consensus_task(&node, watchdog, miner_config, &ex).await;
 
         match result {
             Ok(_) => return Ok(()),
-            Err(Error::NetworkNotConnected) => {
+            Err(e @ (Error::NetworkNotConnected | Error::StaleChainTip)) => {
+                warn!(target: "darkfid::task::consensus_init_task", "{e}, resyncing node...");
                 // Sync node again
                 *node.validator.synced.write().await = false;
                 node.validator.consensus.purge_forks().await?;
                 if !config.skip_sync {
-                    sync_task(&node, checkpoint).await?;
+                    sync_task(&node, checkpoint, config.snapshot_sync).await?;
                 } else {
                     *node.validator.synced.write().await = true;
                 }
+                node.tip_health.write().await.reset();
             }
             Err(e) => return Err(e),
         }
     }
 }
 
-/// Async task to start the consensus task, while monitoring for a network disconnections.
-async fn replicator_task(node: &DarkfiNodePtr, ex: &ExecutorPtr) -> Result<()> {
+/// Run the node's miner or replicator task, racing it against the provided
+/// stale tip watchdog. The task subscriptions are created here and terminated
+/// whichever future finishes first, since the other one gets dropped mid-flight.
+pub async fn watched_consensus_task(
+    node: &DarkfiNodePtr,
+    watchdog: impl Future<Output = Result<()>>,
+    miner_config: Option<(&MinerRewardsRecipientConfig, bool)>,
+    ex: &ExecutorPtr,
+) -> Result<()> {
     // Grab proposals subscriber and subscribe to it
     let proposals_sub = node.subscribers.get("proposals").unwrap();
     let prop_subscription = proposals_sub.publisher.clone().subscribe().await;
 
-    // Subscribe to the network disconnect subscriber
-    let net_subscription = node.p2p_handler.p2p.hosts().subscribe_disconnect().await;
+    // Subscribe to the network disconnect subscriber, if we are a replicator
+    let net_subscription = match miner_config {
+        Some(_) => None,
+        None => Some(node.p2p_handler.p2p.hosts().subscribe_disconnect().await),
+    };
 
-    let result = smol::future::or(
-        monitor_network(&net_subscription),
-        consensus_task(node, &prop_subscription, ex),
-    )
-    .await;
+    let task = async {
+        match (miner_config, &net_subscription) {
+            (Some((recipient_config, skip_sync)), _) => {
+                miner_task(node, recipient_config, skip_sync, &prop_subscription, ex).await
+            }
+            (None, Some(net_subscription)) => {
+                replicator_task(node, &prop_subscription, net_subscription, ex).await
+            }
+            (None, None) => unreachable!("Replicators always subscribe to disconnections"),
+        }
+    };
+    let result = smol::future::or(watchdog, task).await;
 
     // Terminate the subscriptions
     prop_subscription.unsubscribe().await;
-    net_subscription.unsubscribe().await;
+    if let Some(net_subscription) = net_subscription {
+        net_subscription.unsubscribe().await;
+    }
 
     result
 }
 
+/// Async task to start the consensus task, while monitoring for a network disconnections.
+async fn replicator_task(
+    node: &DarkfiNodePtr,
+    prop_subscription: &Subscription<JsonNotification>,
+    net_subscription: &Subscription<Error>,
+    ex: &ExecutorPtr,
+) -> Result<()> {
+    smol::future::or(monitor_network(net_subscription), consensus_task(node, prop_subscription, ex))
+        .await
+}
+
 /// Async task to monitor network disconnections.
 async fn monitor_network(subscription: &Subscription<Error>) -> Result<()> {
     Err(subscription.receive().await)
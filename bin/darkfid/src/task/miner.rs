@@ -17,13 +17,14 @@
  */
 
 use darkfi::{
-    blockchain::{BlockInfo, Header},
+    blockchain::{BlockInfo, Header, HeaderHash},
     rpc::{jsonrpc::JsonNotification, util::JsonValue},
     system::{ExecutorPtr, StoppableTask, Subscription},
     tx::{ContractCallLeaf, Transaction, TransactionBuilder},
     util::{encoding::base64, time::Timestamp},
     validator::{
         consensus::{Fork, Proposal},
+        pow::mine_block,
         utils::best_fork_index,
     },
     zk::{empty_witnesses, ProvingKey, ZkCircuit},
@@ -44,7 +45,7 @@ use num_bigint::BigUint;
 use rand::rngs::OsRng;
 use smol::channel::{Receiver, Sender};
 
-use crate::{proto::ProposalMessage, task::garbage_collect_task, DarkfiNodePtr};
+use crate::{proto::ProposalMessage, task::garbage_collect_task, DarkfiNode, DarkfiNodePtr};
 
 /// Auxiliary structure representing node miner rewards recipient configuration
 pub struct MinerRewardsRecipientConfig {
@@ -68,6 +69,7 @@ pub async fn miner_task(
     node: &DarkfiNodePtr,
     recipient_config: &MinerRewardsRecipientConfig,
     skip_sync: bool,
+    subscription: &Subscription<JsonNotification>,
     ex: &ExecutorPtr,
 ) -> Result<()> {
     // Initialize miner configuration
@@ -93,10 +95,6 @@ pub async fn miner_task(
     // Grab blocks subscriber
     let block_sub = node.subscribers.get("blocks").unwrap();
 
-    // Grab proposals subscriber and subscribe to it
-    let proposals_sub = node.subscribers.get("proposals").unwrap();
-    let subscription = proposals_sub.publisher.clone().subscribe().await;
-
     // Listen for blocks until next confirmation, for optimal conditions
     if !skip_sync {
         info!(target: "darkfid::task::miner_task", "Waiting for next confirmation...");
@@ -161,25 +159,34 @@ pub async fn miner_task(
         drop(forks);
 
         // Start listenning for network proposals and mining next block for best fork.
+        // When the listener finishes first, the pending mining request has been
+        // dropped, so we can immediately preempt the miner daemon job.
         match smol::future::or(
-            listen_to_network(node, &extended_fork, &subscription, &sender),
-            mine(
-                node,
-                &extended_fork,
-                &mut secret,
-                recipient_config,
-                &zkbin,
-                &pk,
-                &stop_signal,
-                skip_sync,
-            ),
+            async {
+                let tip = listen_to_network(node, &extended_fork, subscription, &sender).await?;
+                Ok::<Option<(HeaderHash, u32)>, Error>(Some(tip))
+            },
+            async {
+                mine(
+                    node,
+                    &extended_fork,
+                    &mut secret,
+                    recipient_config,
+                    &zkbin,
+                    &pk,
+                    &stop_signal,
+                    skip_sync,
+                )
+                .await?;
+                Ok::<Option<(HeaderHash, u32)>, Error>(None)
+            },
         )
         .await
         {
-            Ok(_) => { /* Do nothing */ }
+            Ok(Some(tip)) => preempt_miner_daemon(node, tip).await,
+            Ok(None) => { /* Do nothing */ }
             Err(Error::NetworkNotConnected) => {
                 error!(target: "darkfid::task::miner_task", "Node disconnected from the network");
-                subscription.unsubscribe().await;
                 return Err(Error::NetworkNotConnected)
             }
             Err(e) => {
@@ -232,15 +239,16 @@ pub async fn miner_task(
 }
 
 /// Async task to listen for incoming proposals and check if the best fork has changed.
+/// Returns the hash and height of the new best fork tip.
 async fn listen_to_network(
     node: &DarkfiNodePtr,
     extended_fork: &Fork,
     subscription: &Subscription<JsonNotification>,
     sender: &Sender<()>,
-) -> Result<()> {
+) -> Result<(HeaderHash, u32)> {
     // Grab extended fork last proposal hash
     let last_proposal_hash = extended_fork.last_proposal()?.hash;
-    loop {
+    let tip = loop {
         // Wait until a new proposal has been received
         subscription.receive().await;
 
@@ -251,21 +259,32 @@ async fn listen_to_network(
         let index = best_fork_index(&forks)?;
 
         // Verify if proposals sequence has changed
-        if forks[index].last_proposal()?.hash != last_proposal_hash {
-            drop(forks);
-            break
-        }
-
+        let last_proposal = forks[index].last_proposal()?;
         drop(forks);
-    }
+        if last_proposal.hash != last_proposal_hash {
+            break (last_proposal.hash, last_proposal.block.header.height)
+        }
+    };
 
     // Signal miner to abort mining
     sender.send(()).await?;
+
+    Ok(tip)
+}
+
+/// Auxiliary function to preempt the miner daemon pending job right
+/// after the best fork changed, so it doesn't keep hashing stale work
+/// while we are generating the new block template. Miner daemons
+/// subscribed to `blockchain.subscribe_tips` get the new tip pushed and
+/// drop their stale job on their own, while the abort request covers
+/// the ones that are not subscribed.
+async fn preempt_miner_daemon(node: &DarkfiNodePtr, tip: (HeaderHash, u32)) {
+    info!(target: "darkfid::task::miner::preempt_miner_daemon", "Best fork changed, preempting miner daemon job");
+    let params = vec![JsonValue::String(tip.0.to_string()), JsonValue::Number(tip.1 as f64)];
+    node.subscribers.get("tips").unwrap().notify(JsonValue::Array(params)).await;
     if let Err(e) = node.miner_daemon_request("abort", &JsonValue::Array(vec![])).await {
-        error!(target: "darkfid::task::miner::listen_to_network", "Failed to execute miner daemon abort request: {}", e);
+        error!(target: "darkfid::task::miner::preempt_miner_daemon", "Failed to execute miner daemon abort request: {}", e);
     }
-
-    Ok(())
 }
 
 /// Async task to generate and mine provided fork index next block,
@@ -323,11 +342,20 @@ async fn mine_next_block(
     )
     .await?;
 
+    // Signal readiness for the soft-fork deployments we know of
+    next_block.header.version = node
+        .validator
+        .consensus
+        .versionbits
+        .block_version(&extended_fork.overlay, next_block.header.height)?;
+
     // Execute request to minerd and parse response
-    let target = JsonValue::String(next_target.to_string());
+    let target = JsonValue::String(base64::encode(&serialize_async(&next_target).await));
     let block = JsonValue::String(base64::encode(&serialize_async(&next_block).await));
-    let response =
-        node.miner_daemon_request_with_retry("mine", &JsonValue::Array(vec![target, block])).await;
+    let recipient = JsonValue::String(recipient_config.recipient.to_string());
+    let response = node
+        .miner_daemon_request_with_retry("mine", &JsonValue::Array(vec![target, block, recipient]))
+        .await;
     next_block.header.nonce = *response.get::<f64>().unwrap() as u64;
 
     // Sign the mined block
@@ -352,6 +380,87 @@ async fn mine_next_block(
     Ok(())
 }
 
+/// Instantly produce `count` blocks extending the best fork, containing
+/// its unproposed transactions, and confirm them. Used by the localnet
+/// development mode, where blocks have the lowest difficulty, so we can
+/// skip mining them through minerd. Returns the produced blocks hashes.
+pub async fn generate_blocks(
+    node: &DarkfiNode,
+    recipient_config: &MinerRewardsRecipientConfig,
+    count: usize,
+) -> Result<Vec<HeaderHash>> {
+    info!(target: "darkfid::task::miner::generate_blocks", "Generating {count} blocks...");
+    let (zkbin, _) = node.validator.blockchain.contracts.get_zkas(
+        &node.validator.blockchain.sled_db,
+        &MONEY_CONTRACT_ID,
+        MONEY_CONTRACT_ZKAS_MINT_NS_V1,
+    )?;
+    let circuit = ZkCircuit::new(empty_witnesses(&zkbin)?, &zkbin);
+    let pk = ProvingKey::build(zkbin.k, &circuit);
+    let mut secret = SecretKey::random(&mut OsRng);
+
+    let mut hashes = Vec::with_capacity(count);
+    for _ in 0..count {
+        // Grab best current fork
+        let forks = node.validator.consensus.forks.read().await;
+        let index = best_fork_index(&forks)?;
+        let extended_fork = forks[index].full_clone()?;
+        drop(forks);
+
+        // Generate its next block
+        let (next_target, mut next_block) = generate_next_block(
+            &extended_fork,
+            &mut secret,
+            recipient_config,
+            &zkbin,
+            &pk,
+            node.validator.consensus.module.read().await.target,
+            node.validator.verify_fees,
+        )
+        .await?;
+        next_block.header.version = node
+            .validator
+            .consensus
+            .versionbits
+            .block_version(&extended_fork.overlay, next_block.header.height)?;
+
+        // With the lowest difficulty every nonce is valid, otherwise we
+        // have to mine the block ourselves.
+        if extended_fork.module.next_difficulty()? > BigUint::from(1_u8) {
+            let threads = std::thread::available_parallelism().map(|n| n.get()).unwrap_or(1);
+            next_block = smol::unblock(move || {
+                let (_, stop_signal) = smol::channel::bounded(1);
+                mine_block(&next_target, &mut next_block, threads, &stop_signal)?;
+                Ok::<BlockInfo, Error>(next_block)
+            })
+            .await?;
+        }
+
+        // Sign and verify the block
+        next_block.sign(&secret);
+        extended_fork.module.verify_current_block(&next_block)?;
+
+        // Append the block as a proposal and broadcast it to the network
+        let proposal = Proposal::new(next_block);
+        node.validator.append_proposal(&proposal).await?;
+        hashes.push(proposal.hash);
+        let message = ProposalMessage(proposal);
+        node.p2p_handler.p2p.broadcast(&message).await;
+    }
+
+    // Check if we can confirm anything and broadcast them
+    let confirmed = node.validator.confirmation().await?;
+    if !confirmed.is_empty() {
+        let mut notif_blocks = Vec::with_capacity(confirmed.len());
+        for block in confirmed {
+            notif_blocks.push(JsonValue::String(base64::encode(&serialize_async(&block).await)));
+        }
+        node.subscribers.get("blocks").unwrap().notify(JsonValue::Array(notif_blocks)).await;
+    }
+
+    Ok(hashes)
+}
+
 /// Auxiliary function to generate next block in an atomic manner.
 async fn generate_next_block(
     extended_fork: &Fork,
@@ -25,6 +25,9 @@ pub use miner::miner_task;
 pub mod sync;
 pub use sync::sync_task;
 
+pub mod watchdog;
+pub use watchdog::stale_tip_watchdog;
+
 pub mod unknown_proposal;
 pub use unknown_proposal::handle_unknown_proposal;
 
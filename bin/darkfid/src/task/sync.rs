@@ -19,18 +19,28 @@
 use std::collections::HashMap;
 
 use darkfi::{
-    blockchain::HeaderHash, net::ChannelPtr, rpc::jsonrpc::JsonSubscriber, system::sleep,
-    util::encoding::base64, validator::consensus::Proposal, Error, Result,
+    blockchain::HeaderHash,
+    net::ChannelPtr,
+    rpc::jsonrpc::JsonSubscriber,
+    system::sleep,
+    util::encoding::base64,
+    validator::{
+        consensus::Proposal,
+        snapshot::{decode_snapshot, SnapshotManifest, SLED_SNAPSHOT_CHUNK_TREE},
+    },
+    Error, Result,
 };
 use darkfi_serial::serialize_async;
 use log::{debug, info, warn};
 use rand::{prelude::SliceRandom, rngs::OsRng};
+use sled_overlay::sled;
 use tinyjson::JsonValue;
 
 use crate::{
     proto::{
-        ForkSyncRequest, ForkSyncResponse, HeaderSyncRequest, HeaderSyncResponse, SyncRequest,
-        SyncResponse, TipRequest, TipResponse, BATCH,
+        ForkSyncRequest, ForkSyncResponse, HeaderSyncRequest, HeaderSyncResponse,
+        SnapshotChunkRequest, SnapshotChunkResponse, SnapshotManifestRequest,
+        SnapshotManifestResponse, SyncRequest, SyncResponse, TipRequest, TipResponse, BATCH,
     },
     DarkfiNodePtr,
 };
@@ -39,7 +49,14 @@ use crate::{
 //       We can also make them be like torrents, where we retrieve chunks not in order.
 /// async task used for block syncing.
 /// A checkpoint can be provided to ensure node syncs the correct sequence.
-pub async fn sync_task(node: &DarkfiNodePtr, checkpoint: Option<(u32, HeaderHash)>) -> Result<()> {
+/// The node pinned checkpoints are always enforced.
+/// If snapshot sync is enabled and the node only has the genesis block,
+/// it first bootstraps its state from a snapshot served by its peers.
+pub async fn sync_task(
+    node: &DarkfiNodePtr,
+    checkpoint: Option<(u32, HeaderHash)>,
+    snapshot_sync: bool,
+) -> Result<()> {
     info!(target: "darkfid::task::sync_task", "Starting blockchain sync...");
 
     // Grab blocks subscriber
@@ -48,6 +65,18 @@ pub async fn sync_task(node: &DarkfiNodePtr, checkpoint: Option<(u32, HeaderHash
     // Grab last known block header, including existing pending sync ones
     let mut last = node.validator.blockchain.last()?;
 
+    // Pin our finalized blocks, so we don't follow peers forking below them,
+    // and use the highest pinned checkpoint if it's after the provided one.
+    let checkpoint = {
+        let mut checkpoints = node.checkpoints.write().await;
+        checkpoints.pin_finalized(&node.validator.blockchain)?;
+        match (checkpoint, checkpoints.highest()) {
+            (Some(c), Some(h)) if h.0 > c.0 => Some(h),
+            (None, h) => h,
+            (c, _) => c,
+        }
+    };
+
     // If checkpoint is not reached, purge headers and start syncing from scratch
     if let Some(checkpoint) = checkpoint {
         if checkpoint.0 > last.0 {
@@ -73,6 +102,17 @@ pub async fn sync_task(node: &DarkfiNodePtr, checkpoint: Option<(u32, HeaderHash
     let (mut common_tip_height, mut common_tip_peers) =
         most_common_tip(node, &last.1, checkpoint).await;
 
+    // Bootstrap from a blockchain snapshot, retrieving its blocks in bulk
+    if snapshot_sync && node.validator.blockchain.last()?.0 == 0 {
+        if let Some(snapshot_tip) = retrieve_snapshot(node, &common_tip_peers).await? {
+            last = snapshot_tip;
+            info!(target: "darkfid::task::sync_task", "Last known block: {} - {}", last.0, last.1);
+
+            // Grab synced peers most common tip again
+            (common_tip_height, common_tip_peers) = most_common_tip(node, &last.1, None).await;
+        }
+    }
+
     // If last known block header is before the checkpoint, we sync until that first.
     if let Some(checkpoint) = checkpoint {
         if checkpoint.0 > last.0 {
@@ -251,6 +291,139 @@ async fn most_common_tip(
     (common_tip.0, common_tip.2)
 }
 
+/// Auxiliary function to retrieve the most common blockchain snapshot served
+/// by provided peers and replay its blocks. Manifests contradicting a pinned
+/// checkpoint are ignored, and each chunk gets verified against the manifest
+/// as it's received, rotating over the peers serving it. Chunks are stored
+/// in a scratch database, and all the snapshot blocks are fully verified
+/// while replaying them, so nothing served by the peers is trusted. Returns
+/// our new last block, or `None` if no usable snapshot was found.
+async fn retrieve_snapshot(
+    node: &DarkfiNodePtr,
+    peers: &[ChannelPtr],
+) -> Result<Option<(u32, HeaderHash)>> {
+    info!(target: "darkfid::task::sync::retrieve_snapshot", "Retrieving blockchain snapshot manifests from peers...");
+    let comms_timeout = node.p2p_handler.p2p.settings().read().await.outbound_connect_timeout;
+
+    // Ask each peer for their latest snapshot manifest
+    let mut manifests: HashMap<blake3::Hash, (SnapshotManifest, Vec<ChannelPtr>)> = HashMap::new();
+    for peer in peers {
+        // Communication setup
+        let Ok(response_sub) = peer.subscribe_msg::<SnapshotManifestResponse>().await else {
+            debug!(target: "darkfid::task::sync::retrieve_snapshot", "Failure during `SnapshotManifestResponse` communication setup with peer: {peer:?}");
+            continue
+        };
+
+        // Node creates a `SnapshotManifestRequest` and sends it
+        let request = SnapshotManifestRequest { height: None };
+        if let Err(e) = peer.send(&request).await {
+            debug!(target: "darkfid::task::sync::retrieve_snapshot", "Failure during `SnapshotManifestRequest` send to peer {peer:?}: {e}");
+            continue
+        };
+
+        // Node waits for response
+        let Ok(response) = response_sub.receive_with_timeout(comms_timeout).await else {
+            debug!(target: "darkfid::task::sync::retrieve_snapshot", "Timeout while waiting for `SnapshotManifestResponse` from peer: {peer:?}");
+            continue
+        };
+
+        // Handle response
+        let Some(manifest) = response.manifest.clone() else { continue };
+        if !node.checkpoints.read().await.verify(manifest.height, &manifest.block) {
+            debug!(target: "darkfid::task::sync::retrieve_snapshot", "Peer {peer:?} snapshot {} - {} contradicts pinned checkpoint", manifest.height, manifest.block);
+            continue
+        }
+        manifests.entry(manifest.hash()).or_insert_with(|| (manifest, vec![])).1.push(peer.clone());
+    }
+
+    // Grab the highest manifest with the most peers
+    let Some((manifest, manifest_peers)) = manifests
+        .into_values()
+        .max_by(|a, b| a.1.len().cmp(&b.1.len()).then(a.0.height.cmp(&b.0.height)))
+    else {
+        info!(target: "darkfid::task::sync::retrieve_snapshot", "No blockchain snapshot found, syncing from genesis");
+        return Ok(None)
+    };
+    info!(target: "darkfid::task::sync::retrieve_snapshot", "Most common snapshot: {} - {} ({} chunks)", manifest.height, manifest.block, manifest.chunks.len());
+
+    // Communication setup
+    let mut peer_subs = vec![];
+    for peer in &manifest_peers {
+        match peer.subscribe_msg::<SnapshotChunkResponse>().await {
+            Ok(response_sub) => peer_subs.push(Some(response_sub)),
+            Err(e) => {
+                debug!(target: "darkfid::task::sync::retrieve_snapshot", "Failure during `SnapshotChunkResponse` communication setup with peer {peer:?}: {e}");
+                peer_subs.push(None)
+            }
+        }
+    }
+
+    // Chunks are stored in a scratch database, removed once dropped,
+    // so the snapshot is never held in memory.
+    let scratch_path = std::env::temp_dir().join(format!("darkfid_snapshot_{}", manifest.hash()));
+    let scratch_db = sled::Config::new().path(scratch_path).temporary(true).open()?;
+    let chunks = scratch_db.open_tree(SLED_SNAPSHOT_CHUNK_TREE)?;
+
+    // Retrieve the chunks, rotating over the peers
+    let total = manifest.chunks.len();
+    let mut peer_index = 0;
+    for (index, chunk) in manifest.chunks.iter().enumerate() {
+        let mut attempts = 0;
+        loop {
+            // Give up if no peer could serve the chunk
+            if attempts >= manifest_peers.len() {
+                warn!(target: "darkfid::task::sync::retrieve_snapshot", "Failed retrieving snapshot chunk {index}, syncing from genesis");
+                return Ok(None)
+            }
+            attempts += 1;
+
+            let peer = &manifest_peers[peer_index % manifest_peers.len()];
+            let response_sub = &peer_subs[peer_index % manifest_peers.len()];
+            peer_index += 1;
+            let Some(response_sub) = response_sub else { continue };
+
+            // Node creates a `SnapshotChunkRequest` and sends it
+            let request = SnapshotChunkRequest { chunk: *chunk };
+            if let Err(e) = peer.send(&request).await {
+                debug!(target: "darkfid::task::sync::retrieve_snapshot", "Failure during `SnapshotChunkRequest` send to peer {peer:?}: {e}");
+                continue
+            };
+
+            // Node waits for response
+            let Ok(response) = response_sub.receive_with_timeout(comms_timeout).await else {
+                debug!(target: "darkfid::task::sync::retrieve_snapshot", "Timeout while waiting for `SnapshotChunkResponse` from peer: {peer:?}");
+                continue
+            };
+
+            // Verify the chunk
+            let Some(ref data) = response.data else { continue };
+            if response.chunk != *chunk || !manifest.verify_chunk(index, data) {
+                debug!(target: "darkfid::task::sync::retrieve_snapshot", "Invalid `SnapshotChunkResponse` from peer: {peer:?}");
+                continue
+            }
+
+            chunks.insert(chunk.as_bytes(), &data[..])?;
+            break
+        }
+        info!(target: "darkfid::task::sync::retrieve_snapshot", "Snapshot chunks received: {}/{}", index + 1, total);
+    }
+
+    // Decode the snapshot and replay its blocks. If any of them is invalid,
+    // we keep the ones replayed before it and sync the rest normally.
+    let snapshot = match decode_snapshot(&manifest, &chunks, &scratch_db) {
+        Ok(s) => s,
+        Err(e) => {
+            warn!(target: "darkfid::task::sync::retrieve_snapshot", "Failed decoding snapshot, syncing from genesis: {e}");
+            return Ok(None)
+        }
+    };
+    if let Err(e) = node.validator.replay_snapshot(&snapshot).await {
+        warn!(target: "darkfid::task::sync::retrieve_snapshot", "Failed replaying snapshot: {e}");
+    }
+
+    Ok(Some(node.validator.blockchain.last()?))
+}
+
 /// Auxiliary function to retrieve headers backwards until our last known one and verify them.
 async fn retrieve_headers(
     node: &DarkfiNodePtr,
@@ -299,6 +472,16 @@ async fn retrieve_headers(
             let mut response_headers = response.headers.to_vec();
             response_headers.retain(|h| h.height > last_known);
 
+            // Reject peers serving headers that contradict our checkpoints
+            let checkpoints = node.checkpoints.read().await;
+            if let Some(h) =
+                response_headers.iter().find(|h| !checkpoints.verify(h.height, &h.hash()))
+            {
+                debug!(target: "darkfid::task::sync::retrieve_headers", "Peer {peer:?} header {} - {} contradicts pinned checkpoint", h.height, h.hash());
+                continue
+            }
+            drop(checkpoints);
+
             if response_headers.is_empty() {
                 break 'headers_loop
             }
@@ -423,8 +423,14 @@ async fn handle_reorg(
             }
 
             // Verify proposal
-            if let Err(e) =
-                verify_fork_proposal(&peer_fork, peer_proposal, validator.verify_fees).await
+            if let Err(e) = verify_fork_proposal(
+                &peer_fork,
+                peer_proposal,
+                &validator.consensus.scheduler,
+                &validator.consensus.versionbits,
+                validator.verify_fees,
+            )
+            .await
             {
                 error!(target: "darkfid::task::handle_reorg", "Verify fork proposal failed: {e}");
                 return Ok(())
@@ -445,7 +451,15 @@ async fn handle_reorg(
     }
 
     // Verify trigger proposal
-    if let Err(e) = verify_fork_proposal(&peer_fork, &proposal, validator.verify_fees).await {
+    if let Err(e) = verify_fork_proposal(
+        &peer_fork,
+        &proposal,
+        &validator.consensus.scheduler,
+        &validator.consensus.versionbits,
+        validator.verify_fees,
+    )
+    .await
+    {
         error!(target: "darkfid::task::handle_reorg", "Verify proposal failed: {e}");
         return Ok(())
     }
@@ -0,0 +1,104 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2025 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use darkfi::{
+    blockchain::HeaderHash, system::sleep, util::time::Timestamp,
+    validator::utils::best_fork_index, Error, Result,
+};
+use log::{debug, warn};
+
+use crate::DarkfiNodePtr;
+
+/// Auxiliary structure tracking the liveness of the node's best chain tip
+#[derive(Clone, Default)]
+pub struct TipHealth {
+    /// Last observed best tip hash
+    pub tip: Option<HeaderHash>,
+    /// Last observed best tip height
+    pub tip_height: u32,
+    /// Timestamp of the last observed tip change
+    pub last_change: Timestamp,
+    /// Flag indicating the tip is considered stale
+    pub stale: bool,
+    /// Number of resyncs the watchdog has triggered
+    pub resyncs: u64,
+}
+
+impl TipHealth {
+    /// Reset the tracked state after a resync, keeping the counters.
+    pub fn reset(&mut self) {
+        self.tip = None;
+        self.last_change = Timestamp::current_time();
+        self.stale = false;
+    }
+}
+
+/// Grab the node's current best tip, from its best fork or the canonical
+/// chain if no forks exist.
+async fn best_tip(node: &DarkfiNodePtr) -> Result<(u32, HeaderHash)> {
+    let forks = node.validator.consensus.forks.read().await;
+    if forks.is_empty() {
+        drop(forks);
+        return node.validator.blockchain.last()
+    }
+    let index = best_fork_index(&forks)?;
+    let proposal = forks[index].last_proposal()?;
+    Ok((proposal.block.header.height, proposal.hash))
+}
+
+/// Async task monitoring the node's best chain tip. When no new tip has been
+/// observed for `multiplier` times the target block time while we are
+/// connected to peers, the tip is flagged as stale and the task exits with
+/// [`Error::StaleChainTip`], so the caller can trigger a resync.
+/// A zero `multiplier` disables the watchdog.
+pub async fn stale_tip_watchdog(node: &DarkfiNodePtr, multiplier: u32) -> Result<()> {
+    if multiplier == 0 {
+        return smol::future::pending().await
+    }
+
+    node.tip_health.write().await.reset();
+    loop {
+        let target = node.validator.consensus.module.read().await.target as u64;
+        sleep(target).await;
+
+        let (height, hash) = best_tip(node).await?;
+        let mut health = node.tip_health.write().await;
+        if health.tip != Some(hash) {
+            debug!(target: "darkfid::task::stale_tip_watchdog",
+                "Best tip changed: {height} - {hash}");
+            health.tip = Some(hash);
+            health.tip_height = height;
+            health.last_change = Timestamp::current_time();
+            health.stale = false;
+            continue
+        }
+
+        let elapsed = health.last_change.elapsed()?.inner();
+        if elapsed < target * multiplier as u64 || !node.p2p_handler.p2p.is_connected() {
+            continue
+        }
+
+        warn!(
+            target: "darkfid::task::stale_tip_watchdog",
+            "Best tip {height} - {hash} has not changed for {elapsed} seconds, triggering resync..."
+        );
+        health.stale = true;
+        health.resyncs += 1;
+        return Err(Error::StaleChainTip)
+    }
+}
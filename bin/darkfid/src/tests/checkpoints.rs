@@ -0,0 +1,115 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2025 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use std::{sync::Arc, time::Duration};
+
+use darkfi::{net::Settings, system::timeout::timeout, Result};
+use darkfi_contract_test_harness::init_logger;
+use darkfi_sdk::num_traits::One;
+use num_bigint::BigUint;
+use smol::Executor;
+use url::Url;
+
+use crate::{
+    checkpoints::{CheckpointSource, Checkpoints},
+    task::sync_task,
+    tests::{generate_node, Harness, HarnessConfig},
+};
+
+async fn sync_checkpoints_real(ex: Arc<Executor<'static>>) -> Result<()> {
+    init_logger();
+
+    // Initialize harness in testing mode
+    let config = HarnessConfig {
+        pow_target: 90,
+        pow_fixed_difficulty: Some(BigUint::one()),
+        confirmation_threshold: 3,
+        alice_url: "tcp+tls://127.0.0.1:19240".to_string(),
+        bob_url: "tcp+tls://127.0.0.1:19241".to_string(),
+    };
+    let th = Harness::new(config, false, &ex).await?;
+
+    // Generate some blocks, confirming the first two
+    let genesis = th.alice.validator.blockchain.last_block()?;
+    let block1 = th.generate_next_block(&genesis).await?;
+    let block2 = th.generate_next_block(&block1).await?;
+    let block3 = th.generate_next_block(&block2).await?;
+    let block4 = th.generate_next_block(&block3).await?;
+    th.add_blocks(&vec![block1.clone(), block2.clone(), block3, block4]).await?;
+    th.validate_fork_chains(1, vec![2]).await;
+
+    // Create a third node, connected to Bob, without syncing it yet
+    let mut settings = Settings { localnet: true, inbound_connections: 3, ..Default::default() };
+    settings.inbound_addrs = vec![Url::parse("tcp+tls://127.0.0.1:19242")?];
+    settings.peers = vec![th.bob.p2p_handler.p2p.settings().read().await.inbound_addrs[0].clone()];
+    let charlie = generate_node(&th.vks, &th.validator_config, &settings, &ex, true, None).await?;
+
+    // A pinned checkpoint contradicting the peers chain makes us reject
+    // them, so nothing gets synced
+    let mut checkpoints = Checkpoints::new(0);
+    checkpoints.pin(block1.header.height, block2.hash(), CheckpointSource::Configured)?;
+    *charlie.checkpoints.write().await = checkpoints;
+    assert!(timeout(Duration::from_secs(10), sync_task(&charlie, None, false)).await.is_err());
+    assert_eq!(charlie.validator.blockchain.len(), 1);
+    assert!(charlie.validator.blockchain.headers.is_empty_sync());
+
+    // With the correct checkpoint pinned, we follow the peers chain
+    let mut checkpoints = Checkpoints::new(1);
+    checkpoints.pin(block2.header.height, block2.hash(), CheckpointSource::Configured)?;
+    *charlie.checkpoints.write().await = checkpoints;
+    sync_task(&charlie, None, false).await?;
+    let alice = &th.alice.validator;
+    assert_eq!(charlie.validator.blockchain.len(), alice.blockchain.len());
+    assert_eq!(charlie.validator.blockchain.last()?, alice.blockchain.last()?);
+
+    // Our finalized blocks get pinned one block below our last one
+    let mut checkpoints = charlie.checkpoints.write().await;
+    checkpoints.pin_finalized(&charlie.validator.blockchain)?;
+    assert_eq!(checkpoints.highest(), Some((block2.header.height, block2.hash())));
+    assert!(checkpoints.verify(block1.header.height, &block1.hash()));
+    assert!(!checkpoints.verify(block1.header.height, &block2.hash()));
+    let pinned: Vec<_> = checkpoints.iter().map(|(h, (_, s))| (*h, *s)).collect();
+    assert_eq!(
+        pinned,
+        vec![
+            (block1.header.height, CheckpointSource::Finalized),
+            (block2.header.height, CheckpointSource::Configured),
+        ]
+    );
+    drop(checkpoints);
+
+    // Thanks for reading
+    Ok(())
+}
+
+#[test]
+fn sync_checkpoints() -> Result<()> {
+    let ex = Arc::new(Executor::new());
+    let (signal, shutdown) = smol::channel::unbounded::<()>();
+
+    easy_parallel::Parallel::new().each(0..4, |_| smol::block_on(ex.run(shutdown.recv()))).finish(
+        || {
+            smol::block_on(async {
+                sync_checkpoints_real(ex.clone()).await.unwrap();
+                drop(signal);
+            })
+        },
+    );
+
+    Ok(())
+}
@@ -0,0 +1,110 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2025 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use std::{sync::Arc, time::Duration};
+
+use async_tungstenite::tungstenite::Message;
+use darkfi::{
+    system::{msleep, timeout::timeout, StoppableTask},
+    Error, Result,
+};
+use darkfi_contract_test_harness::init_logger;
+use darkfi_sdk::num_traits::One;
+use futures::StreamExt;
+use num_bigint::BigUint;
+use smol::{net::TcpStream, Executor};
+use url::Url;
+
+use crate::{
+    firehose::{firehose_task, FIREHOSE_BLOCK},
+    tests::{Harness, HarnessConfig},
+};
+
+async fn firehose_stop_real(ex: Arc<Executor<'static>>) -> Result<()> {
+    init_logger();
+
+    // Initialize harness in testing mode
+    let config = HarnessConfig {
+        pow_target: 90,
+        pow_fixed_difficulty: Some(BigUint::one()),
+        confirmation_threshold: 3,
+        alice_url: "tcp+tls://127.0.0.1:19140".to_string(),
+        bob_url: "tcp+tls://127.0.0.1:19141".to_string(),
+    };
+    let th = Harness::new(config, false, &ex).await?;
+
+    // Start the firehose exporter
+    let listen = Url::parse("tcp://127.0.0.1:19142")?;
+    let task = StoppableTask::new();
+    task.clone().start(
+        firehose_task(th.alice.clone(), listen, ex.clone()),
+        |_| async { /* Do nothing */ },
+        Error::DetachedTaskStopped,
+        ex.clone(),
+    );
+
+    // Connect a client streaming from genesis
+    let stream = loop {
+        match TcpStream::connect("127.0.0.1:19142").await {
+            Ok(stream) => break stream,
+            Err(_) => msleep(100).await,
+        }
+    };
+    let Ok((mut ws, _)) =
+        async_tungstenite::client_async("ws://127.0.0.1:19142/?from=0", stream).await
+    else {
+        panic!("Firehose handshake failed")
+    };
+
+    // We receive the genesis block
+    let Some(Ok(Message::Binary(data))) = ws.next().await else { panic!("No genesis frame") };
+    assert_eq!(data[0], FIREHOSE_BLOCK);
+
+    // Stopping the exporter closes the open connections too
+    task.stop().await;
+    let closed = timeout(Duration::from_secs(5), async {
+        while let Some(Ok(msg)) = ws.next().await {
+            if matches!(msg, Message::Close(_)) {
+                break
+            }
+        }
+    })
+    .await;
+    assert!(closed.is_ok());
+    assert!(TcpStream::connect("127.0.0.1:19142").await.is_err());
+
+    // Thanks for reading
+    Ok(())
+}
+
+#[test]
+fn firehose_stop() -> Result<()> {
+    let ex = Arc::new(Executor::new());
+    let (signal, shutdown) = smol::channel::unbounded::<()>();
+
+    easy_parallel::Parallel::new().each(0..4, |_| smol::block_on(ex.run(shutdown.recv()))).finish(
+        || {
+            smol::block_on(async {
+                firehose_stop_real(ex.clone()).await.unwrap();
+                drop(signal);
+            })
+        },
+    );
+
+    Ok(())
+}
@@ -19,7 +19,7 @@
 use std::{collections::HashMap, sync::Arc};
 
 use darkfi::{
-    blockchain::{BlockInfo, Header, HeaderHash},
+    blockchain::{BlockInfo, Header, HeaderHash, CONTRACT_STATE_QUOTA},
     net::Settings,
     rpc::jsonrpc::JsonSubscriber,
     system::sleep,
@@ -87,6 +87,10 @@ impl Harness {
             pow_fixed_difficulty: config.pow_fixed_difficulty.clone(),
             genesis_block,
             verify_fees,
+            admission_policies: vec![],
+            spend_extractor: None,
+            snapshot_interval: 0,
+            contract_state_quota: CONTRACT_STATE_QUOTA,
         };
 
         // Generate validators using pregenerated vks
@@ -241,19 +245,29 @@ pub async fn generate_node(
     subscribers.insert("blocks", JsonSubscriber::new("blockchain.subscribe_blocks"));
     subscribers.insert("txs", JsonSubscriber::new("blockchain.subscribe_txs"));
     subscribers.insert("proposals", JsonSubscriber::new("blockchain.subscribe_proposals"));
+    subscribers.insert("tips", JsonSubscriber::new("blockchain.subscribe_tips"));
     subscribers.insert("dnet", JsonSubscriber::new("dnet.subscribe_events"));
 
     let p2p_handler = DarkfidP2pHandler::init(settings, ex).await?;
-    let node =
-        DarkfiNode::new(p2p_handler.clone(), validator.clone(), 50, subscribers.clone(), None)
-            .await;
+    let node = DarkfiNode::new(
+        p2p_handler.clone(),
+        validator.clone(),
+        50,
+        subscribers.clone(),
+        None,
+        false,
+        None,
+        10,
+        false,
+    )
+    .await;
 
     p2p_handler.clone().start(ex, &validator, &subscribers).await?;
 
     node.validator.consensus.generate_empty_fork().await?;
 
     if !skip_sync {
-        sync_task(&node, checkpoint).await?;
+        sync_task(&node, checkpoint, false).await?;
     } else {
         *node.validator.synced.write().await = true;
     }
@@ -0,0 +1,98 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2025 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+use std::sync::Arc;
+
+use darkfi::{
+    validator::{consensus::Proposal, utils::best_fork_index},
+    Result,
+};
+use darkfi_contract_test_harness::init_logger;
+use darkfi_sdk::num_traits::One;
+use num_bigint::BigUint;
+use smol::Executor;
+
+use crate::tests::{Harness, HarnessConfig};
+
+async fn invalidate_block_real(ex: Arc<Executor<'static>>) -> Result<()> {
+    init_logger();
+
+    // Initialize harness in testing mode
+    let config = HarnessConfig {
+        pow_target: 90,
+        pow_fixed_difficulty: Some(BigUint::one()),
+        confirmation_threshold: 6,
+        alice_url: "tcp+tls://127.0.0.1:18742".to_string(),
+        bob_url: "tcp+tls://127.0.0.1:18743".to_string(),
+    };
+    let th = Harness::new(config, false, &ex).await?;
+    let validator = &th.alice.validator;
+
+    // Append a sequence of proposals
+    let genesis = validator.blockchain.last_block()?;
+    let block1 = th.generate_next_block(&genesis).await?;
+    let block2 = th.generate_next_block(&block1).await?;
+    let block3 = th.generate_next_block(&block2).await?;
+    let (proposal1, proposal2, proposal3) =
+        (Proposal::new(block1), Proposal::new(block2), Proposal::new(block3));
+    validator.append_proposal(&proposal1).await?;
+    validator.append_proposal(&proposal2).await?;
+    validator.append_proposal(&proposal3).await?;
+
+    // Invalidating a proposal drops it along with its descendants
+    let dropped = validator.invalidate_block(&proposal2.hash).await?;
+    assert_eq!(dropped, vec![proposal2.hash, proposal3.hash]);
+
+    let forks = validator.consensus.forks.read().await;
+    let best = &forks[best_fork_index(&forks)?];
+    assert_eq!(best.proposals, vec![proposal1.hash]);
+    drop(forks);
+
+    // Invalidated proposals get rejected
+    assert!(validator.append_proposal(&proposal2).await.is_err());
+
+    // Reconsidering it re-appends the dropped proposals
+    let appended = validator.reconsider_block(&proposal2.hash).await?;
+    assert_eq!(appended, vec![proposal2.hash, proposal3.hash]);
+
+    let forks = validator.consensus.forks.read().await;
+    let best = &forks[best_fork_index(&forks)?];
+    assert_eq!(best.proposals, vec![proposal1.hash, proposal2.hash, proposal3.hash]);
+    drop(forks);
+
+    // Only invalidated blocks can be reconsidered
+    assert!(validator.reconsider_block(&proposal2.hash).await.is_err());
+
+    Ok(())
+}
+
+#[test]
+fn invalidate_block() -> Result<()> {
+    let ex = Arc::new(Executor::new());
+    let (signal, shutdown) = smol::channel::unbounded::<()>();
+
+    easy_parallel::Parallel::new().each(0..4, |_| smol::block_on(ex.run(shutdown.recv()))).finish(
+        || {
+            smol::block_on(async {
+                invalidate_block_real(ex.clone()).await.unwrap();
+                drop(signal);
+            })
+        },
+    );
+
+    Ok(())
+}
@@ -18,7 +18,9 @@
 
 use std::sync::Arc;
 
-use darkfi::{net::Settings, validator::utils::best_fork_index, Result};
+use darkfi::{
+    net::Settings, rpc::settings::RpcSettings, validator::utils::best_fork_index, Result,
+};
 use darkfi_contract_test_harness::init_logger;
 use darkfi_sdk::num_traits::One;
 use num_bigint::BigUint;
@@ -34,6 +36,20 @@ mod sync_forks;
 
 mod unproposed_txs;
 
+mod orphans;
+
+mod invalidate;
+
+mod watchdog;
+
+mod replay;
+
+mod tx_decode;
+
+mod firehose;
+
+mod checkpoints;
+
 async fn sync_blocks_real(ex: Arc<Executor<'static>>) -> Result<()> {
     init_logger();
 
@@ -235,11 +251,19 @@ fn darkfid_programmatic_control() -> Result<()> {
         pow_fixed_difficulty: Some(BigUint::one()),
         genesis_block,
         verify_fees: false,
+        admission_policies: vec![],
+        spend_extractor: None,
+        snapshot_interval: 0,
+        contract_state_quota: darkfi::blockchain::CONTRACT_STATE_QUOTA,
     };
     let consensus_config = crate::ConsensusInitTaskConfig {
         skip_sync: true,
         checkpoint_height: None,
         checkpoint: None,
+        checkpoints: vec![],
+        checkpoint_depth: 0,
+        stale_tip_multiplier: 0,
+        snapshot_sync: false,
         miner: false,
         recipient: None,
         spend_hook: None,
@@ -249,7 +273,7 @@ fn darkfid_programmatic_control() -> Result<()> {
     let sled_db = sled_overlay::sled::Config::new().temporary(true).open()?;
     let (_, vks) = darkfi_contract_test_harness::vks::get_cached_pks_and_vks()?;
     darkfi_contract_test_harness::vks::inject(&sled_db, &vks)?;
-    let rpc_listen = Url::parse("tcp://127.0.0.1:8240")?;
+    let rpc_settings = RpcSettings::new(Url::parse("tcp://127.0.0.1:8240")?);
 
     // Create an executor and communication signals
     let ex = Arc::new(smol::Executor::new());
@@ -265,19 +289,23 @@ fn darkfid_programmatic_control() -> Result<()> {
                     &darkfi::net::Settings::default(),
                     &None,
                     &None,
+                    false,
+                    None,
+                    10,
+                    false,
                     &ex,
                 )
                 .await
                 .unwrap();
 
                 // Start it
-                daemon.start(&ex, &rpc_listen, &None, &consensus_config).await.unwrap();
+                daemon.start(&ex, &rpc_settings, &None, &None, &consensus_config).await.unwrap();
 
                 // Stop it
                 daemon.stop().await.unwrap();
 
                 // Start it again
-                daemon.start(&ex, &rpc_listen, &None, &consensus_config).await.unwrap();
+                daemon.start(&ex, &rpc_settings, &None, &None, &consensus_config).await.unwrap();
 
                 // Stop it
                 daemon.stop().await.unwrap();
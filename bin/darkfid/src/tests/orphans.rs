@@ -0,0 +1,135 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2025 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use std::sync::Arc;
+
+use darkfi::{
+    rpc::jsonrpc::JsonSubscriber,
+    validator::{consensus::Proposal, utils::best_fork_index},
+    Result,
+};
+use darkfi_contract_test_harness::init_logger;
+use darkfi_sdk::num_traits::One;
+use num_bigint::BigUint;
+use smol::Executor;
+
+use crate::{
+    orphans::{append_orphans, verify_orphan, OrphanPool},
+    tests::{Harness, HarnessConfig},
+};
+
+async fn orphan_proposals_real(ex: Arc<Executor<'static>>) -> Result<()> {
+    init_logger();
+
+    // Initialize harness in testing mode
+    let config = HarnessConfig {
+        pow_target: 90,
+        pow_fixed_difficulty: Some(BigUint::one()),
+        confirmation_threshold: 6,
+        alice_url: "tcp+tls://127.0.0.1:18740".to_string(),
+        bob_url: "tcp+tls://127.0.0.1:18741".to_string(),
+    };
+    let th = Harness::new(config, false, &ex).await?;
+    let validator = &th.alice.validator;
+    let p2p = &th.alice.p2p_handler.p2p;
+    let proposals_sub = JsonSubscriber::new("blockchain.subscribe_proposals");
+
+    // Generate a sequence of blocks
+    let genesis = validator.blockchain.last_block()?;
+    let block1 = th.generate_next_block(&genesis).await?;
+    let block2 = th.generate_next_block(&block1).await?;
+    let block3 = th.generate_next_block(&block2).await?;
+    let (proposal1, proposal2, proposal3) =
+        (Proposal::new(block1), Proposal::new(block2), Proposal::new(block3));
+
+    // Proposals arriving before their parent can't be appended
+    assert!(validator.append_proposal(&proposal3).await.is_err());
+    assert!(validator.append_proposal(&proposal2).await.is_err());
+
+    // Their headers are sane, so they can be pooled
+    verify_orphan(validator, &proposal2).await?;
+    verify_orphan(validator, &proposal3).await?;
+
+    // Proposals with mismatched hashes or not extending our canonical
+    // tip are rejected
+    let mut forged = proposal3.clone();
+    forged.hash = proposal2.hash;
+    assert!(verify_orphan(validator, &forged).await.is_err());
+    assert!(verify_orphan(validator, &Proposal::new(genesis.clone())).await.is_err());
+
+    // Keep them in the pool, in reverse order
+    let orphans = OrphanPool::new(2, 2);
+    let evicted3 = orphans.insert(proposal3.clone(), 0).await.unwrap();
+    let evicted2 = orphans.insert(proposal2.clone(), 0).await.unwrap();
+    assert!(orphans.insert(proposal2.clone(), 0).await.is_none());
+    assert_eq!(orphans.len().await, 2);
+
+    // Once the first proposal arrives, the whole sequence gets appended
+    validator.append_proposal(&proposal1).await?;
+    append_orphans(&orphans, validator, p2p, &proposals_sub, proposal1.hash).await;
+    assert_eq!(orphans.len().await, 0);
+
+    // Appended orphans are not signalled as evicted
+    assert!(evicted2.try_recv().is_err() && evicted2.is_closed());
+    assert!(evicted3.try_recv().is_err() && evicted3.is_closed());
+
+    let forks = validator.consensus.forks.read().await;
+    let best = &forks[best_fork_index(&forks)?];
+    assert_eq!(best.proposals, vec![proposal1.hash, proposal2.hash, proposal3.hash]);
+    drop(forks);
+
+    // The pool is bounded, evicting and signalling the oldest orphans
+    let orphans = OrphanPool::new(1, 1);
+    let evicted2 = orphans.insert(proposal2.clone(), 0).await.unwrap();
+    let evicted3 = orphans.insert(proposal3.clone(), 1).await.unwrap();
+    assert_eq!(orphans.len().await, 1);
+    assert!(evicted2.try_recv().is_ok());
+    assert!(orphans.remove(&proposal2.block.header.previous, &proposal2.hash).await.is_none());
+    assert!(orphans.remove(&proposal3.block.header.previous, &proposal3.hash).await.is_some());
+    assert!(evicted3.try_recv().is_err());
+
+    // A channel over its quota evicts its own orphans, not the rest ones
+    let orphans = OrphanPool::new(3, 1);
+    let evicted1 = orphans.insert(proposal1.clone(), 0).await.unwrap();
+    let evicted2 = orphans.insert(proposal2.clone(), 1).await.unwrap();
+    let evicted3 = orphans.insert(proposal3.clone(), 1).await.unwrap();
+    assert_eq!(orphans.len().await, 2);
+    assert!(evicted1.try_recv().is_err());
+    assert!(evicted2.try_recv().is_ok());
+    assert!(evicted3.try_recv().is_err());
+    assert!(orphans.remove(&proposal1.block.header.previous, &proposal1.hash).await.is_some());
+
+    Ok(())
+}
+
+#[test]
+fn orphan_proposals() -> Result<()> {
+    let ex = Arc::new(Executor::new());
+    let (signal, shutdown) = smol::channel::unbounded::<()>();
+
+    easy_parallel::Parallel::new().each(0..4, |_| smol::block_on(ex.run(shutdown.recv()))).finish(
+        || {
+            smol::block_on(async {
+                orphan_proposals_real(ex.clone()).await.unwrap();
+                drop(signal);
+            })
+        },
+    );
+
+    Ok(())
+}
@@ -0,0 +1,92 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2025 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use std::sync::Arc;
+
+use darkfi::Result;
+use darkfi_contract_test_harness::init_logger;
+use darkfi_sdk::num_traits::One;
+use num_bigint::BigUint;
+use smol::Executor;
+
+use crate::tests::{Harness, HarnessConfig};
+
+async fn replay_range_real(ex: Arc<Executor<'static>>) -> Result<()> {
+    init_logger();
+
+    // Initialize harness in testing mode
+    let config = HarnessConfig {
+        pow_target: 90,
+        pow_fixed_difficulty: Some(BigUint::one()),
+        confirmation_threshold: 3,
+        alice_url: "tcp+tls://127.0.0.1:18940".to_string(),
+        bob_url: "tcp+tls://127.0.0.1:18941".to_string(),
+    };
+    let th = Harness::new(config, false, &ex).await?;
+    let validator = &th.alice.validator;
+
+    // Generate a sequence of blocks and add them to nodes,
+    // so some of them get confirmed.
+    let mut previous = validator.blockchain.last_block()?;
+    for _ in 0..6 {
+        let block = th.generate_next_block(&previous).await?;
+        th.add_blocks(&[block.clone()]).await?;
+        previous = block;
+    }
+    let (last, last_hash) = validator.blockchain.last()?;
+    assert!(last >= 3);
+
+    // Replaying the whole chain must reproduce the stored diffs
+    assert!(validator.replay_range(1, last, &[]).await?.is_empty());
+
+    // Same when bootstrapping from the stored diffs, enforcing a checkpoint
+    assert!(validator.replay_range(2, last, &[(last, last_hash)]).await?.is_empty());
+
+    // A checkpoint not matching the replayed blocks must fail
+    let wrong = validator.blockchain.blocks.get_order(&[1], true)?[0].unwrap();
+    assert!(validator.replay_range(1, last, &[(last, wrong)]).await.is_err());
+
+    // Invalid ranges must fail
+    assert!(validator.replay_range(last, last - 1, &[]).await.is_err());
+    assert!(validator.replay_range(1, last + 1, &[]).await.is_err());
+
+    // Corrupting the stored diff of the last block must be reported
+    let diff = validator.blockchain.blocks.get_state_diff(&[1], true)?.remove(0).unwrap();
+    validator.blockchain.blocks.insert_state_diff(&[last], &[diff])?;
+    assert_eq!(validator.replay_range(1, last, &[]).await?, vec![last]);
+
+    // Thanks for reading
+    Ok(())
+}
+
+#[test]
+fn replay_range() -> Result<()> {
+    let ex = Arc::new(Executor::new());
+    let (signal, shutdown) = smol::channel::unbounded::<()>();
+
+    easy_parallel::Parallel::new().each(0..4, |_| smol::block_on(ex.run(shutdown.recv()))).finish(
+        || {
+            smol::block_on(async {
+                replay_range_real(ex.clone()).await.unwrap();
+                drop(signal);
+            })
+        },
+    );
+
+    Ok(())
+}
@@ -0,0 +1,140 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2025 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use std::sync::Arc;
+
+use darkfi::{
+    rpc::{
+        error_code::RpcErrorCode,
+        jsonrpc::{ErrorCode, JsonResult},
+    },
+    tx::Transaction,
+    Result,
+};
+use darkfi_contract_test_harness::init_logger;
+use darkfi_money_contract::MoneyFunction;
+use darkfi_sdk::{
+    crypto::MONEY_CONTRACT_ID, dark_tree::DarkLeaf, hex::AsHex, num_traits::One, ContractCall,
+};
+use darkfi_serial::serialize;
+use num_bigint::BigUint;
+use smol::Executor;
+use tinyjson::JsonValue;
+
+use crate::{
+    tests::{Harness, HarnessConfig},
+    RpcError,
+};
+
+/// Auxiliary function to build a `JsonValue` array of string params
+fn params(params: &[&str]) -> JsonValue {
+    JsonValue::Array(params.iter().map(|p| JsonValue::String(p.to_string())).collect())
+}
+
+/// Auxiliary function to build an unverified Money call leaf
+fn money_call(data: Vec<u8>) -> DarkLeaf<ContractCall> {
+    DarkLeaf {
+        data: ContractCall { contract_id: *MONEY_CONTRACT_ID, data },
+        parent_index: None,
+        children_indexes: vec![],
+    }
+}
+
+async fn tx_decode_real(ex: Arc<Executor<'static>>) -> Result<()> {
+    init_logger();
+
+    // Initialize harness in testing mode
+    let config = HarnessConfig {
+        pow_target: 90,
+        pow_fixed_difficulty: Some(BigUint::one()),
+        confirmation_threshold: 3,
+        alice_url: "tcp+tls://127.0.0.1:19040".to_string(),
+        bob_url: "tcp+tls://127.0.0.1:19041".to_string(),
+    };
+    let th = Harness::new(config, false, &ex).await?;
+
+    // Grab the producer transaction of a fresh block, which has no fee
+    let genesis = th.alice.validator.blockchain.last_block()?;
+    let block = th.generate_next_block(&genesis).await?;
+    let tx = block.txs.last().unwrap();
+    let tx_bytes = serialize(tx);
+    let base58 = bs58::encode(&tx_bytes).into_string();
+    let base58 = base58.as_str();
+    let hex = tx_bytes.hex();
+    let hex = hex.as_str();
+
+    // Both encodings decode to the same transaction, with or without
+    // the encoding being explicitly requested.
+    for p in [vec![base58], vec![base58, "base58"], vec![hex], vec![hex, "hex"]] {
+        let JsonResult::Response(rep) = th.alice.tx_decode(1, params(&p)).await else {
+            panic!("Failed decoding transaction")
+        };
+        assert_eq!(rep.result["hash"], JsonValue::String(tx.hash().to_string()));
+        assert_eq!(rep.result["fee"], JsonValue::Null);
+        let JsonValue::Array(calls) = &rep.result["calls"] else { panic!() };
+        assert_eq!(calls.len(), tx.calls.len());
+    }
+
+    // Mismatching or unknown encodings are rejected
+    let JsonResult::Error(e) = th.alice.tx_decode(1, params(&[base58, "hex"])).await else {
+        panic!("Decoded base58 transaction as hex")
+    };
+    assert_eq!(e.error.code, RpcError::ParseError.code());
+    let JsonResult::Error(e) = th.alice.tx_decode(1, params(&[hex, "base64"])).await else {
+        panic!("Accepted unknown encoding")
+    };
+    assert_eq!(e.error.code, ErrorCode::InvalidParams.code());
+
+    // The fee call is found at any index, not just the last one
+    let mut fee_data = vec![MoneyFunction::FeeV1 as u8];
+    fee_data.extend(serialize(&1234_u64));
+    let tx = Transaction {
+        calls: vec![money_call(fee_data), money_call(vec![MoneyFunction::TransferV1 as u8])],
+        proofs: vec![vec![], vec![]],
+        signatures: vec![vec![], vec![]],
+        ..Default::default()
+    };
+    let base58 = bs58::encode(serialize(&tx)).into_string();
+    let JsonResult::Response(rep) = th.alice.tx_decode(1, params(&[&base58])).await else {
+        panic!("Failed decoding transaction")
+    };
+    assert_eq!(rep.result["fee"], JsonValue::Number(1234.0));
+    let JsonValue::Array(calls) = &rep.result["calls"] else { panic!() };
+    assert_eq!(calls[0]["function"], JsonValue::String("FeeV1".to_string()));
+    assert_eq!(calls[1]["function"], JsonValue::String("TransferV1".to_string()));
+
+    // Thanks for reading
+    Ok(())
+}
+
+#[test]
+fn tx_decode() -> Result<()> {
+    let ex = Arc::new(Executor::new());
+    let (signal, shutdown) = smol::channel::unbounded::<()>();
+
+    easy_parallel::Parallel::new().each(0..4, |_| smol::block_on(ex.run(shutdown.recv()))).finish(
+        || {
+            smol::block_on(async {
+                tx_decode_real(ex.clone()).await.unwrap();
+                drop(signal);
+            })
+        },
+    );
+
+    Ok(())
+}
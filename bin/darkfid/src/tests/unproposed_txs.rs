@@ -32,7 +32,9 @@ use darkfi::Result;
 use std::sync::Arc;
 
 use crate::tests::{Harness, HarnessConfig};
-use darkfi::validator::{consensus::GAS_LIMIT_UNPROPOSED_TXS, utils::best_fork_index};
+use darkfi::validator::{
+    admission::TxSource, consensus::GAS_LIMIT_UNPROPOSED_TXS, utils::best_fork_index,
+};
 use darkfi_contract_test_harness::{init_logger, Holder, TestHarness};
 use darkfi_sdk::{crypto::BaseBlind, num_traits::One};
 use num_bigint::BigUint;
@@ -87,7 +89,7 @@ async fn simulate_unproposed_txs(
                 current_block_height,
             )
             .await?;
-        validator.append_tx(&tx, true).await?;
+        validator.append_tx(&tx, true, &TxSource::Local).await?;
     }
 
     // Obtain fork
@@ -0,0 +1,91 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2025 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use std::sync::Arc;
+
+use darkfi::{system::sleep, Error, Result};
+use darkfi_contract_test_harness::init_logger;
+use darkfi_sdk::{crypto::Keypair, num_traits::One};
+use num_bigint::BigUint;
+use rand::rngs::OsRng;
+use smol::Executor;
+
+use crate::{
+    task::{consensus::watched_consensus_task, miner::MinerRewardsRecipientConfig},
+    tests::{Harness, HarnessConfig},
+};
+
+async fn watchdog_unsubscribes_real(ex: Arc<Executor<'static>>) -> Result<()> {
+    init_logger();
+
+    // Initialize harness in testing mode
+    let config = HarnessConfig {
+        pow_target: 90,
+        pow_fixed_difficulty: Some(BigUint::one()),
+        confirmation_threshold: 6,
+        alice_url: "tcp+tls://127.0.0.1:18840".to_string(),
+        bob_url: "tcp+tls://127.0.0.1:18841".to_string(),
+    };
+    let th = Harness::new(config, false, &ex).await?;
+    let proposals = th.alice.subscribers.get("proposals").unwrap().publisher.clone();
+    let subscriptions = proposals.subscriptions().await;
+
+    // The watchdog fires while the replicator task waits for proposals
+    let watchdog = async {
+        sleep(1).await;
+        Err(Error::StaleChainTip)
+    };
+    let result = watched_consensus_task(&th.alice, watchdog, None, &ex).await;
+    assert!(matches!(result, Err(Error::StaleChainTip)));
+    assert_eq!(proposals.subscriptions().await, subscriptions);
+
+    // The same holds for the miner task, waiting for the next confirmation
+    let recipient_config = MinerRewardsRecipientConfig {
+        recipient: Keypair::random(&mut OsRng).public,
+        spend_hook: None,
+        user_data: None,
+    };
+    let watchdog = async {
+        sleep(1).await;
+        Err(Error::StaleChainTip)
+    };
+    let result =
+        watched_consensus_task(&th.alice, watchdog, Some((&recipient_config, false)), &ex).await;
+    assert!(matches!(result, Err(Error::StaleChainTip)));
+    assert_eq!(proposals.subscriptions().await, subscriptions);
+
+    // Thanks for reading
+    Ok(())
+}
+
+#[test]
+fn watchdog_unsubscribes() -> Result<()> {
+    let ex = Arc::new(Executor::new());
+    let (signal, shutdown) = smol::channel::unbounded::<()>();
+
+    easy_parallel::Parallel::new().each(0..4, |_| smol::block_on(ex.run(shutdown.recv()))).finish(
+        || {
+            smol::block_on(async {
+                watchdog_unsubscribes_real(ex.clone()).await.unwrap();
+                drop(signal);
+            })
+        },
+    );
+
+    Ok(())
+}
@@ -23,3 +23,6 @@ pub mod saltbox;
 
 /// bcrypt utilities
 pub mod bcrypt;
+
+/// Direct message key rotation
+pub mod ratchet;
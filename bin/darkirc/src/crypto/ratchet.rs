@@ -0,0 +1,288 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2025 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Periodic re-keying of direct message encryption.
+//!
+//! Every contact's messages are by default encrypted using a `ChaChaBox`
+//! derived from both sides' long-term keys, so compromising one of them
+//! allows decrypting all the history found in the DAG. To provide forward
+//! secrecy, each side periodically generates an ephemeral keypair and
+//! announces its public key to the contact using a handshake message,
+//! encrypted and authenticated with the long-term `ChaChaBox`. Once we
+//! know the contact's latest ephemeral key, messages get encrypted using
+//! a `ChaChaBox` derived from both sides' latest ephemeral keys instead.
+//!
+//! Each handshake also acknowledges the latest ephemeral key received from
+//! the contact. Our messages are only encrypted with our latest key the
+//! contact acknowledged, so they can always decrypt them, and our older
+//! keys are retired once a newer one is acknowledged. We don't rotate again
+//! before our current key gets acknowledged, so a contact staying offline
+//! never misses more than one of our keys. Only the last [`MAX_EPOCH_KEYS`]
+//! ephemeral keys of the contact are kept. Messages encrypted with retired
+//! keys can't be decrypted anymore, even when a long-term key gets
+//! compromised.
+//!
+//! Handshakes are formatted as CTCP messages, so contacts running older
+//! versions will just see an unknown CTCP request and keep using the
+//! long-term key, since we only switch to ephemeral keys after receiving
+//! their handshake.
+
+use std::{sync::Arc, time::Duration};
+
+use crypto_box::{ChaChaBox, PublicKey, SecretKey};
+use darkfi::{system::sleep, util::time::Timestamp, Result};
+use darkfi_serial::{async_trait, SerialDecodable, SerialEncodable};
+use log::error;
+use rand::rngs::OsRng;
+
+use crate::irc::server::IrcServer;
+
+/// Sled tree holding the ratchet state of each contact, keyed by name
+pub const DM_RATCHETS_TREE: &str = "darkirc_dm_ratchets";
+
+/// Prefix of a handshake message, which is followed by the key epoch,
+/// the base58 encoded ephemeral public key and the epoch of the latest
+/// contact's key we acknowledge, if any
+const HANDSHAKE_PREFIX: &str = "\x01DHREKEY ";
+
+/// Amount of the contact's ephemeral keys kept
+pub const MAX_EPOCH_KEYS: usize = 2;
+
+/// Interval of checking whether our ephemeral keys must be rotated, in seconds
+const REKEY_CHECK_INTERVAL: u64 = 60;
+
+/// Ephemeral key state of a single contact
+#[derive(Clone, SerialEncodable, SerialDecodable)]
+pub struct DmRatchet {
+    /// Long-term public key of the contact this state belongs to
+    pub contact: [u8; 32],
+    /// Our ephemeral secret keys along with their epochs, oldest first
+    ours: Vec<(u64, [u8; 32])>,
+    /// The contact's ephemeral public keys along with their epochs, oldest first
+    theirs: Vec<(u64, [u8; 32])>,
+    /// Epoch of our latest ephemeral key the contact acknowledged
+    acked: Option<u64>,
+}
+
+impl DmRatchet {
+    /// Create an empty ratchet state for the given contact public key
+    pub fn new(contact: &PublicKey) -> Self {
+        Self { contact: *contact.as_bytes(), ours: vec![], theirs: vec![], acked: None }
+    }
+
+    /// Epoch of our current ephemeral key, if any
+    pub fn current_epoch(&self) -> Option<u64> {
+        self.ours.last().map(|(epoch, _)| *epoch)
+    }
+
+    /// Check if our keys can be rotated, which is when we have none
+    /// yet or the contact acknowledged our current one.
+    pub fn can_rotate(&self) -> bool {
+        self.current_epoch().is_none_or(|epoch| self.acked == Some(epoch))
+    }
+
+    /// Generate a new ephemeral keypair for the given epoch. Returns the
+    /// handshake message announcing the new public key, acknowledging
+    /// the contact's latest one. Our current key is kept until the
+    /// contact acknowledges the new one.
+    pub fn rotate(&mut self, epoch: u64) -> String {
+        let secret = SecretKey::generate(&mut OsRng);
+        let public = secret.public_key();
+        self.ours.push((epoch, secret.to_bytes()));
+
+        let public = bs58::encode(public.as_bytes()).into_string();
+        match self.theirs.last() {
+            Some((ack, _)) => format!("{HANDSHAKE_PREFIX}{epoch} {public} {ack}\x01"),
+            None => format!("{HANDSHAKE_PREFIX}{epoch} {public}\x01"),
+        }
+    }
+
+    /// Apply a handshake received from the contact, retiring our keys
+    /// older than the one it acknowledges.
+    /// Returns `false` if its key was already known or outdated.
+    pub fn receive(&mut self, epoch: u64, public: &PublicKey, ack: Option<u64>) -> bool {
+        if let Some(ack) = ack {
+            if self.ours.iter().any(|(e, _)| *e == ack) && self.acked.is_none_or(|a| a < ack) {
+                self.acked = Some(ack);
+                while self.ours.first().is_some_and(|(e, _)| *e < ack) {
+                    let (_, mut key) = self.ours.remove(0);
+                    key.fill(0);
+                }
+            }
+        }
+
+        if self.theirs.last().is_some_and(|(e, _)| *e >= epoch) {
+            return false
+        }
+
+        self.theirs.push((epoch, *public.as_bytes()));
+        while self.theirs.len() > MAX_EPOCH_KEYS {
+            self.theirs.remove(0);
+        }
+        true
+    }
+
+    /// `ChaChaBox` to encrypt new messages with, available once both
+    /// sides have announced an ephemeral key and the contact acknowledged
+    /// one of ours.
+    pub fn send_box(&self) -> Option<ChaChaBox> {
+        let acked = self.acked?;
+        let (_, secret) = self.ours.iter().find(|(e, _)| *e == acked)?;
+        let (_, public) = self.theirs.last()?;
+        Some(ChaChaBox::new(&PublicKey::from(*public), &SecretKey::from(*secret)))
+    }
+
+    /// All the `ChaChaBox` combinations of the kept ephemeral keys,
+    /// newest first, used to decrypt messages.
+    pub fn recv_boxes(&self) -> Vec<ChaChaBox> {
+        let mut ret = Vec::with_capacity(self.ours.len() * self.theirs.len());
+        for (_, secret) in self.ours.iter().rev() {
+            let secret = SecretKey::from(*secret);
+            for (_, public) in self.theirs.iter().rev() {
+                ret.push(ChaChaBox::new(&PublicKey::from(*public), &secret));
+            }
+        }
+        ret
+    }
+}
+
+impl Drop for DmRatchet {
+    fn drop(&mut self) {
+        for (_, key) in self.ours.iter_mut() {
+            key.fill(0);
+        }
+    }
+}
+
+/// Parse a handshake message into its epoch, ephemeral public key
+/// and acknowledged epoch
+pub fn parse_handshake(msg: &str) -> Option<(u64, PublicKey, Option<u64>)> {
+    let payload = msg.strip_prefix(HANDSHAKE_PREFIX)?.strip_suffix('\x01')?;
+    let mut parts = payload.split(' ');
+    let epoch = parts.next()?.parse().ok()?;
+    let public: [u8; 32] = bs58::decode(parts.next()?).into_vec().ok()?.try_into().ok()?;
+    let ack = match parts.next() {
+        Some(ack) => Some(ack.parse().ok()?),
+        None => None,
+    };
+    if parts.next().is_some() {
+        return None
+    }
+    Some((epoch, PublicKey::from(public), ack))
+}
+
+/// Background task rotating our ephemeral keys of each contact every
+/// `interval` seconds and publishing the handshakes over the DAG.
+pub async fn rekey_task(server: Arc<IrcServer>, interval: u64) -> Result<()> {
+    loop {
+        let now = Timestamp::current_time().inner();
+        if let Err(e) = server.rotate_dm_keys(now, interval).await {
+            error!(target: "darkirc::crypto::ratchet", "Failed rotating DM keys: {}", e);
+        }
+
+        sleep(REKEY_CHECK_INTERVAL.min(interval)).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crypto::saltbox;
+
+    /// Encrypt a message with `from`'s send box, and check `to` decrypts it
+    fn round_trip(from: &DmRatchet, to: &DmRatchet) -> bool {
+        let ciphertext = saltbox::encrypt(&from.send_box().unwrap(), b"hello");
+        let ciphertext = bs58::decode(ciphertext).into_vec().unwrap();
+        to.recv_boxes().iter().any(|b| saltbox::try_decrypt(b, &ciphertext).is_some())
+    }
+
+    /// Deliver a handshake to the contact
+    fn deliver(handshake: &str, to: &mut DmRatchet) -> bool {
+        let (epoch, public, ack) = parse_handshake(handshake).unwrap();
+        to.receive(epoch, &public, ack)
+    }
+
+    #[test]
+    fn dm_ratchet_rotation() {
+        let alice_key = SecretKey::generate(&mut OsRng);
+        let bob_key = SecretKey::generate(&mut OsRng);
+        let mut alice = DmRatchet::new(&bob_key.public_key());
+        let mut bob = DmRatchet::new(&alice_key.public_key());
+
+        // Nothing is encrypted with ephemeral keys until they're acknowledged
+        assert!(alice.can_rotate());
+        let a1 = alice.rotate(1);
+        assert!(!alice.can_rotate());
+        assert!(deliver(&a1, &mut bob));
+        assert!(!deliver(&a1, &mut bob));
+        assert!(alice.send_box().is_none() && bob.send_box().is_none());
+
+        let b1 = bob.rotate(2);
+        assert!(deliver(&b1, &mut alice));
+        assert!(alice.can_rotate() && !bob.can_rotate());
+        assert!(round_trip(&alice, &bob));
+        assert!(bob.send_box().is_none());
+
+        // Alice rotates, but keeps using her acknowledged key until Bob
+        // acknowledges the new one, so he can still decrypt
+        let a2 = alice.rotate(3);
+        assert!(!alice.can_rotate());
+        assert_eq!(alice.ours.len(), 2);
+        assert!(round_trip(&alice, &bob));
+
+        // A staled handshake is ignored
+        assert!(!deliver(&a1, &mut bob));
+
+        // Bob learns the new key, acknowledging Alice's first one,
+        // which is how he knows his own is acknowledged
+        assert!(deliver(&a2, &mut bob));
+        assert!(bob.can_rotate());
+        assert!(round_trip(&alice, &bob) && round_trip(&bob, &alice));
+
+        // Once Bob acknowledges the new key, the old one is retired
+        let b2 = bob.rotate(4);
+        assert!(deliver(&b2, &mut alice));
+        assert_eq!(alice.ours.len(), 1);
+        assert_eq!(alice.current_epoch(), Some(3));
+        assert!(round_trip(&alice, &bob) && round_trip(&bob, &alice));
+
+        // Only the last contact's keys are kept
+        let a3 = alice.rotate(5);
+        assert!(deliver(&a3, &mut bob));
+        assert_eq!(bob.theirs.len(), MAX_EPOCH_KEYS);
+        assert!(round_trip(&alice, &bob) && round_trip(&bob, &alice));
+    }
+
+    #[test]
+    fn dm_ratchet_handshakes() {
+        let public = SecretKey::generate(&mut OsRng).public_key();
+        let encoded = bs58::encode(public.as_bytes()).into_string();
+
+        let parsed = parse_handshake(&format!("{HANDSHAKE_PREFIX}42 {encoded} 41\x01")).unwrap();
+        assert_eq!((parsed.0, parsed.1.as_bytes(), parsed.2), (42, public.as_bytes(), Some(41)));
+        let parsed = parse_handshake(&format!("{HANDSHAKE_PREFIX}42 {encoded}\x01")).unwrap();
+        assert_eq!(parsed.2, None);
+
+        assert!(parse_handshake(&format!("{HANDSHAKE_PREFIX}42 {encoded} 41")).is_none());
+        assert!(parse_handshake(&format!("{HANDSHAKE_PREFIX}x {encoded}\x01")).is_none());
+        assert!(parse_handshake(&format!("{HANDSHAKE_PREFIX}42 {encoded} x\x01")).is_none());
+        assert!(parse_handshake(&format!("{HANDSHAKE_PREFIX}42 {encoded} 41 40\x01")).is_none());
+        assert!(parse_handshake(&format!("{HANDSHAKE_PREFIX}42 abc\x01")).is_none());
+        assert!(parse_handshake("hello").is_none());
+    }
+}
@@ -0,0 +1,239 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2025 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Out-of-band file sharing through a local fud daemon.
+//!
+//! Files shared in a channel are `put` into fud over its JSON-RPC, and
+//! a `fud://` link to them is posted instead of the file contents. On
+//! the receiving side, linked files up to a configured size can be
+//! queued for download in fud automatically. Since link sizes are set
+//! by their sender, the limit is enforced by fud itself against the
+//! file metadata, before any of its chunks get fetched.
+
+use std::{path::Path, sync::Arc};
+
+use darkfi::{
+    event_graph::{Event, EventGraphPtr},
+    rpc::{client::RpcClient, jsonrpc::JsonRequest, util::JsonValue},
+    Error, Result,
+};
+use log::{error, info};
+use smol::{fs, Executor};
+use url::Url;
+
+use crate::{
+    irc::{server::IrcServer, Msg},
+    search::SELF_NICK,
+};
+
+/// URL scheme of shared file links
+const FUD_SCHEME: &str = "fud";
+
+/// Link to a file shared through fud
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FudLink {
+    /// fud file hash
+    pub hash: blake3::Hash,
+    /// File name
+    pub name: String,
+    /// File size in bytes, as claimed by the sender
+    pub size: u64,
+}
+
+impl FudLink {
+    /// Build the `fud://<hash>?name=<name>&size=<size>` link URL
+    pub fn to_url(&self) -> Url {
+        let mut url = Url::parse(&format!("{}://{}", FUD_SCHEME, self.hash)).unwrap();
+        url.query_pairs_mut()
+            .append_pair("name", &self.name)
+            .append_pair("size", &self.size.to_string());
+        url
+    }
+
+    /// Parse a single link URL
+    pub fn parse(link: &str) -> Option<Self> {
+        let url = Url::parse(link).ok()?;
+        if url.scheme() != FUD_SCHEME {
+            return None
+        }
+
+        let hash = blake3::Hash::from_hex(url.host_str()?).ok()?;
+        let mut name = String::new();
+        let mut size = 0;
+        for (key, value) in url.query_pairs() {
+            match key.as_ref() {
+                "name" => name = value.to_string(),
+                "size" => size = value.parse().ok()?,
+                _ => {}
+            }
+        }
+
+        Some(Self { hash, name, size })
+    }
+
+    /// Find all the links contained in a message
+    pub fn find(msg: &str) -> Vec<Self> {
+        let prefix = format!("{}://", FUD_SCHEME);
+        msg.split_whitespace().filter(|w| w.starts_with(&prefix)).filter_map(Self::parse).collect()
+    }
+}
+
+/// JSON-RPC client of a local fud daemon
+pub struct FudClient {
+    /// fud JSON-RPC endpoint
+    endpoint: Url,
+    /// Maximum size in bytes of linked files downloaded automatically
+    pub autofetch_size: u64,
+    executor: Arc<Executor<'static>>,
+}
+
+impl FudClient {
+    pub fn new(endpoint: Url, autofetch_size: u64, executor: Arc<Executor<'static>>) -> Self {
+        Self { endpoint, autofetch_size, executor }
+    }
+
+    /// Perform a request to fud. A connection is only held for the
+    /// duration of the request, since sharing is occasional.
+    async fn request(&self, method: &str, params: Vec<JsonValue>) -> Result<JsonValue> {
+        let client = RpcClient::new(self.endpoint.clone(), self.executor.clone()).await?;
+        let rep = client.request(JsonRequest::new(method, JsonValue::Array(params))).await;
+        client.stop().await;
+        rep
+    }
+
+    /// Put a local file into fud, returning the link to share
+    pub async fn share(&self, path: &Path) -> Result<FudLink> {
+        let metadata = fs::metadata(path).await?;
+        if !metadata.is_file() {
+            return Err(Error::Custom(format!("{} is not a file", path.display())))
+        }
+
+        let name = path.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+        let path = JsonValue::String(path.to_string_lossy().to_string());
+
+        let rep = self.request("put", vec![path]).await?;
+        let Some(hash) = rep.get::<String>() else {
+            return Err(Error::ParseFailed("Invalid fud put reply"))
+        };
+        let Ok(hash) = blake3::Hash::from_hex(hash) else {
+            return Err(Error::ParseFailed("Invalid fud put reply"))
+        };
+
+        Ok(FudLink { hash, name, size: metadata.len() })
+    }
+
+    /// Queue a linked file for download in fud, optionally refusing it if
+    /// it exceeds the given size in bytes. Returns `false` if fud is
+    /// already downloading it.
+    pub async fn download(&self, link: &FudLink, max_size: Option<u64>) -> Result<bool> {
+        let mut params = vec![JsonValue::String(link.hash.to_hex().to_string())];
+        if let Some(max_size) = max_size {
+            params.push(JsonValue::String("normal".to_string()));
+            params.push(JsonValue::Number(max_size as f64));
+        }
+        let rep = self.request("download", params).await?;
+        rep.get::<bool>().copied().ok_or(Error::ParseFailed("Invalid fud download reply"))
+    }
+}
+
+/// Queue the small files linked in an event for download
+async fn autofetch_event(fud: &FudClient, server: &IrcServer, event: &Event) {
+    let mut privmsg = match Msg::deserialize(event.content()).await {
+        Ok(Msg::V1(old_msg)) => old_msg.into_new(),
+        Ok(Msg::V2(new_msg)) => new_msg,
+        Err(_) => return,
+    };
+
+    server.try_decrypt(&mut privmsg, SELF_NICK).await;
+
+    // Only fetch from our channels and contacts
+    if !server.channels.read().await.contains_key(&privmsg.channel) &&
+        !server.contacts.read().await.contains_key(&privmsg.channel)
+    {
+        return
+    }
+
+    for link in FudLink::find(&privmsg.msg) {
+        // Skip links claiming to be large early, the real size is
+        // enforced by fud.
+        if link.size > fud.autofetch_size {
+            continue
+        }
+
+        match fud.download(&link, Some(fud.autofetch_size)).await {
+            Ok(true) => info!(
+                target: "darkirc::fud",
+                "Fetching {} ({} bytes) shared by {} in {}",
+                link.name, link.size, privmsg.nick, privmsg.channel,
+            ),
+            Ok(false) => {}
+            Err(e) => error!(target: "darkirc::fud", "Failed queuing {}: {}", link.hash, e),
+        }
+    }
+}
+
+/// Background task automatically fetching the small files shared in
+/// the messages inserted into the DAG.
+pub async fn autofetch_task(
+    fud: Arc<FudClient>,
+    server: Arc<IrcServer>,
+    event_graph: EventGraphPtr,
+) -> Result<()> {
+    let incoming = event_graph.event_pub.clone().subscribe().await;
+
+    loop {
+        let event = incoming.receive().await;
+        autofetch_event(&fud, &server, &event).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fud_links() {
+        let link = FudLink {
+            hash: blake3::hash(b"foo"),
+            name: "my file & co.txt".to_string(),
+            size: 1024,
+        };
+
+        // Links round-trip through their URL, even with odd file names
+        let url = link.to_url();
+        assert_eq!(url.scheme(), FUD_SCHEME);
+        assert_eq!(FudLink::parse(url.as_str()), Some(link.clone()));
+
+        // Links are found among the words of a message
+        let other = FudLink { hash: blake3::hash(b"bar"), name: "bar".to_string(), size: 0 };
+        let msg = format!("look at {} and {} :)", url, other.to_url());
+        assert_eq!(FudLink::find(&msg), vec![link.clone(), other]);
+        assert!(FudLink::find("no links here, https://dark.fi").is_empty());
+
+        // Invalid links are rejected
+        let hash = link.hash.to_hex();
+        assert!(FudLink::parse(&format!("https://{hash}?name=foo&size=1")).is_none());
+        assert!(FudLink::parse("fud://deadbeef?name=foo&size=1").is_none());
+        assert!(FudLink::parse(&format!("fud://{hash}?name=foo&size=-1")).is_none());
+        assert!(FudLink::parse(&format!("fud://{hash}?name=foo&size=big")).is_none());
+
+        // Missing query pairs default to empty values
+        let bare = FudLink::parse(&format!("fud://{hash}")).unwrap();
+        assert_eq!((bare.hash, bare.name.as_str(), bare.size), (link.hash, "", 0));
+    }
+}
@@ -0,0 +1,463 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2025 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+//! Read-only public web gateway.
+//!
+//! Serves the recent history of selected channels over plain HTTP as JSON
+//! and Atom feeds, so community websites can embed recent activity without
+//! every visitor running a node. Nicknames are never exposed, only a short
+//! hash of them.
+//!
+//! Only plaintext messages of the explicitly listed channels get published.
+//! Nothing is ever decrypted for the gateway, and listed channels we have
+//! configured as encrypted are skipped altogether.
+
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Arc,
+    time::Duration,
+};
+
+use darkfi::{
+    event_graph::{Event, EventGraphPtr},
+    rpc::util::{json_map, JsonValue},
+    system::{timeout::timeout, Semaphore},
+    util::time::DateTime,
+    Error, Result,
+};
+use log::{debug, error, info};
+use smol::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    lock::RwLock,
+    net::{TcpListener, TcpStream},
+    Executor,
+};
+use url::Url;
+
+use crate::irc::{server::IrcServer, Msg};
+
+/// Default amount of messages kept per channel
+pub const DEFAULT_GATEWAY_LIMIT: usize = 50;
+/// Maximum size of an HTTP request we're willing to read
+const MAX_REQUEST_SIZE: usize = 8192;
+/// Deadline for reading an HTTP request and writing its response
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+/// Maximum amount of concurrently served connections
+const MAX_CONNECTIONS: usize = 64;
+/// Amount of bytes of the nickname hash exposed in the feeds
+const NICK_HASH_LEN: usize = 8;
+
+/// A public channel message served by the gateway
+#[derive(Clone, Debug)]
+pub struct FeedMsg {
+    /// Event ID
+    pub id: blake3::Hash,
+    /// Event timestamp in milliseconds
+    pub timestamp: u64,
+    /// Truncated hex-encoded hash of the sender nickname
+    pub nick_hash: String,
+    /// Message text
+    pub msg: String,
+}
+
+impl FeedMsg {
+    /// RFC 3339 representation of the message timestamp
+    fn date(&self) -> String {
+        format!("{}Z", DateTime::from_timestamp(self.timestamp / 1000, 0))
+    }
+}
+
+impl From<&FeedMsg> for JsonValue {
+    fn from(m: &FeedMsg) -> JsonValue {
+        json_map([
+            ("id", JsonValue::String(m.id.to_hex().to_string())),
+            ("timestamp", JsonValue::Number(m.timestamp as f64)),
+            ("nick", JsonValue::String(m.nick_hash.clone())),
+            ("msg", JsonValue::String(m.msg.clone())),
+        ])
+    }
+}
+
+/// Hash a nickname so it can't be read directly from the feeds, while
+/// still allowing readers to tell senders apart.
+fn nick_hash(nick: &str) -> String {
+    let hash = blake3::hash(nick.as_bytes());
+    hash.to_hex()[..NICK_HASH_LEN * 2].to_string()
+}
+
+/// Escape a string for inclusion in XML text or attributes
+fn xml_escape(s: &str) -> String {
+    let mut ret = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '&' => ret.push_str("&amp;"),
+            '<' => ret.push_str("&lt;"),
+            '>' => ret.push_str("&gt;"),
+            '"' => ret.push_str("&quot;"),
+            '\'' => ret.push_str("&apos;"),
+            c if c.is_control() => {}
+            c => ret.push(c),
+        }
+    }
+    ret
+}
+
+/// In-memory recent history of the channels exposed by the gateway
+pub struct Gateway {
+    /// Channels exposed by the gateway
+    channels: HashSet<String>,
+    /// Maximum amount of messages kept per channel
+    limit: usize,
+    /// Recent messages per channel, oldest first
+    msgs: RwLock<HashMap<String, Vec<FeedMsg>>>,
+}
+
+impl Gateway {
+    /// Create a new gateway exposing the given channels
+    pub fn new(channels: &[String], limit: usize) -> Self {
+        let channels: HashSet<String> = channels
+            .iter()
+            .map(|c| if c.starts_with('#') { c.clone() } else { format!("#{c}") })
+            .collect();
+
+        let msgs = channels.iter().map(|c| (c.clone(), vec![])).collect();
+        Self { channels, limit: limit.max(1), msgs: RwLock::new(msgs) }
+    }
+
+    /// Record a message for the given channel, if it's exposed
+    pub async fn insert(&self, channel: &str, msg: FeedMsg) {
+        let mut msgs = self.msgs.write().await;
+        let Some(chan_msgs) = msgs.get_mut(channel) else { return };
+
+        if chan_msgs.iter().any(|m| m.id == msg.id) {
+            return
+        }
+
+        chan_msgs.push(msg);
+        chan_msgs.sort_by_key(|m| m.timestamp);
+        if chan_msgs.len() > self.limit {
+            let excess = chan_msgs.len() - self.limit;
+            chan_msgs.drain(..excess);
+        }
+    }
+
+    /// Grab the recent messages of a channel, newest first
+    pub async fn recent(&self, channel: &str) -> Option<Vec<FeedMsg>> {
+        let msgs = self.msgs.read().await;
+        msgs.get(channel).map(|m| m.iter().rev().cloned().collect())
+    }
+
+    /// Render the JSON list of exposed channels
+    fn channels_json(&self) -> String {
+        let mut channels: Vec<&String> = self.channels.iter().collect();
+        channels.sort();
+        let channels = channels.into_iter().map(|c| JsonValue::String(c.clone())).collect();
+        JsonValue::Array(channels).stringify().unwrap()
+    }
+
+    /// Render the JSON feed of a channel
+    async fn channel_json(&self, channel: &str) -> Option<String> {
+        let msgs = self.recent(channel).await?;
+        let feed = json_map([
+            ("channel", JsonValue::String(channel.to_string())),
+            ("messages", JsonValue::Array(msgs.iter().map(|m| m.into()).collect())),
+        ]);
+        Some(feed.stringify().unwrap())
+    }
+
+    /// Render the Atom feed of a channel
+    async fn channel_atom(&self, channel: &str) -> Option<String> {
+        let msgs = self.recent(channel).await?;
+        let updated = match msgs.first() {
+            Some(m) => m.date(),
+            None => format!("{}Z", DateTime::from_timestamp(0, 0)),
+        };
+
+        let mut feed = String::from("<?xml version=\"1.0\" encoding=\"utf-8\"?>\n");
+        feed.push_str("<feed xmlns=\"http://www.w3.org/2005/Atom\">\n");
+        feed.push_str(&format!("  <id>urn:darkirc:{}</id>\n", xml_escape(channel)));
+        feed.push_str(&format!("  <title>{}</title>\n", xml_escape(channel)));
+        feed.push_str(&format!("  <updated>{updated}</updated>\n"));
+        for m in msgs.iter() {
+            feed.push_str("  <entry>\n");
+            feed.push_str(&format!("    <id>urn:darkirc:event:{}</id>\n", m.id.to_hex()));
+            feed.push_str(&format!("    <title>{}</title>\n", m.nick_hash));
+            feed.push_str(&format!("    <author><name>{}</name></author>\n", m.nick_hash));
+            feed.push_str(&format!("    <updated>{}</updated>\n", m.date()));
+            feed.push_str(&format!(
+                "    <content type=\"text\">{}</content>\n",
+                xml_escape(&m.msg)
+            ));
+            feed.push_str("  </entry>\n");
+        }
+        feed.push_str("</feed>\n");
+
+        Some(feed)
+    }
+
+    /// Route a request path to its response status, content type and body
+    async fn route(&self, path: &str) -> (&'static str, &'static str, String) {
+        let path = path.split('?').next().unwrap_or_default();
+
+        if path == "/" || path == "/channels.json" {
+            return ("200 OK", "application/json", self.channels_json())
+        }
+
+        let name = path.trim_start_matches('/');
+        let response = if let Some(name) = name.strip_suffix(".json") {
+            self.channel_json(&format!("#{name}")).await.map(|b| ("application/json", b))
+        } else if let Some(name) = name.strip_suffix(".atom") {
+            self.channel_atom(&format!("#{name}")).await.map(|b| ("application/atom+xml", b))
+        } else {
+            None
+        };
+
+        match response {
+            Some((content_type, body)) => ("200 OK", content_type, body),
+            None => ("404 Not Found", "text/plain", String::from("Not found\n")),
+        }
+    }
+
+    /// Handle a single HTTP connection, which has [`REQUEST_TIMEOUT`] to
+    /// send its request and receive the response as a whole, so slow
+    /// clients can't hold on to connection slots.
+    async fn handle_connection(&self, stream: TcpStream) -> Result<()> {
+        match timeout(REQUEST_TIMEOUT, self.serve_request(stream)).await {
+            Ok(res) => res,
+            Err(_) => Err(Error::ConnectTimeout),
+        }
+    }
+
+    /// Read a single HTTP request from the stream and write its response.
+    async fn serve_request(&self, mut stream: TcpStream) -> Result<()> {
+        // Read until the end of the request headers. We only serve GET
+        // requests, so we don't care about a body.
+        let mut buf = vec![];
+        let mut chunk = [0u8; 1024];
+        loop {
+            let n = stream.read(&mut chunk).await?;
+            if n == 0 {
+                return Ok(())
+            }
+            buf.extend_from_slice(&chunk[..n]);
+
+            if buf.windows(4).any(|w| w == b"\r\n\r\n") {
+                break
+            }
+
+            if buf.len() > MAX_REQUEST_SIZE {
+                return Err(Error::MalformedPacket)
+            }
+        }
+
+        let request = String::from_utf8_lossy(&buf);
+        let mut parts = request.lines().next().unwrap_or_default().split_whitespace();
+        let (status, content_type, body) = match (parts.next(), parts.next()) {
+            (Some("GET"), Some(path)) => self.route(path).await,
+            _ => ("405 Method Not Allowed", "text/plain", String::from("Method not allowed\n")),
+        };
+
+        let response = format!(
+            "HTTP/1.1 {status}\r\n\
+             Content-Type: {content_type}; charset=utf-8\r\n\
+             Content-Length: {}\r\n\
+             Access-Control-Allow-Origin: *\r\n\
+             Connection: close\r\n\r\n{body}",
+            body.len(),
+        );
+
+        stream.write_all(response.as_bytes()).await?;
+        stream.flush().await?;
+        Ok(())
+    }
+}
+
+/// Decode and record a single event, skipping anything that isn't a
+/// readable message on an exposed channel
+async fn feed_event(gateway: &Gateway, server: &IrcServer, event: &Event) {
+    let mut privmsg = match Msg::deserialize(event.content()).await {
+        Ok(Msg::V1(old_msg)) => old_msg.into_new(),
+        Ok(Msg::V2(new_msg)) => new_msg,
+        Err(_) => return,
+    };
+
+    // Never decrypt anything for the gateway, and skip the listed channels
+    // we have configured as encrypted, in case plaintext is sent to them
+    if !gateway.channels.contains(&privmsg.channel) {
+        return
+    }
+    if server.channels.read().await.get(&privmsg.channel).is_some_and(|c| c.saltbox.is_some()) {
+        return
+    }
+
+    let msg = FeedMsg {
+        id: event.id(),
+        timestamp: event.timestamp,
+        nick_hash: nick_hash(&privmsg.nick),
+        msg: privmsg.msg,
+    };
+
+    gateway.insert(&privmsg.channel, msg).await;
+}
+
+/// Background task feeding the gateway history. It first goes through
+/// the current DAG contents, and then every new event inserted into the DAG.
+pub async fn feed_task(
+    gateway: Arc<Gateway>,
+    server: Arc<IrcServer>,
+    event_graph: EventGraphPtr,
+) -> Result<()> {
+    // Subscribe first so we don't miss anything while backfilling
+    let incoming = event_graph.event_pub.clone().subscribe().await;
+
+    info!(target: "darkirc::gateway", "Loading existing DAG events");
+    for event in event_graph.order_events().await {
+        feed_event(&gateway, &server, &event).await;
+    }
+
+    loop {
+        let event = incoming.receive().await;
+        feed_event(&gateway, &server, &event).await;
+    }
+}
+
+/// Serve the gateway feeds over HTTP on the given URL
+pub async fn listen_and_serve(
+    gateway: Arc<Gateway>,
+    listen: Url,
+    ex: Arc<Executor<'static>>,
+) -> Result<()> {
+    let slots = Semaphore::new(MAX_CONNECTIONS);
+    let Some(addr) = listen.socket_addrs(|| None)?.into_iter().next() else {
+        return Err(Error::UrlParse(listen.to_string()))
+    };
+
+    let listener = TcpListener::bind(addr).await?;
+    info!(target: "darkirc::gateway", "Web gateway listening on {}", listen);
+
+    loop {
+        let (stream, peer) = match listener.accept().await {
+            Ok(v) => v,
+            Err(e) => {
+                error!(target: "darkirc::gateway", "Failed accepting connection: {}", e);
+                continue
+            }
+        };
+
+        // Refuse connections over the limit right away
+        let Some(slot) = slots.try_acquire() else {
+            debug!(target: "darkirc::gateway", "Refusing connection from {}, too many", peer);
+            continue
+        };
+
+        debug!(target: "darkirc::gateway", "Accepted connection from {}", peer);
+        let gateway = gateway.clone();
+        ex.spawn(async move {
+            if let Err(e) = gateway.handle_connection(stream).await {
+                debug!(target: "darkirc::gateway", "Connection {} failed: {}", peer, e);
+            }
+            drop(slot);
+        })
+        .detach();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn feed_msg(n: u8, timestamp: u64) -> FeedMsg {
+        FeedMsg {
+            id: blake3::hash(&[n]),
+            timestamp,
+            nick_hash: nick_hash("anon"),
+            msg: format!("<msg {n}>"),
+        }
+    }
+
+    #[test]
+    fn gateway_history() {
+        smol::block_on(async {
+            let gateway = Gateway::new(&["dev".to_string(), "#random".to_string()], 2);
+
+            // Unlisted channels are never recorded
+            gateway.insert("#secret", feed_msg(0, 1000)).await;
+            assert!(gateway.recent("#secret").await.is_none());
+
+            // Messages are deduplicated, ordered and trimmed to the limit
+            gateway.insert("#dev", feed_msg(2, 2000)).await;
+            gateway.insert("#dev", feed_msg(1, 1000)).await;
+            gateway.insert("#dev", feed_msg(1, 1000)).await;
+            gateway.insert("#dev", feed_msg(3, 3000)).await;
+            let recent = gateway.recent("#dev").await.unwrap();
+            let timestamps: Vec<u64> = recent.iter().map(|m| m.timestamp).collect();
+            assert_eq!(timestamps, vec![3000, 2000]);
+            assert!(gateway.recent("#random").await.unwrap().is_empty());
+        })
+    }
+
+    #[test]
+    fn gateway_routes() {
+        smol::block_on(async {
+            let gateway = Gateway::new(&["#dev".to_string()], 10);
+            gateway.insert("#dev", feed_msg(1, 1000)).await;
+
+            let (status, _, body) = gateway.route("/channels.json").await;
+            assert_eq!(status, "200 OK");
+            assert_eq!(body, "[\"#dev\"]");
+
+            let (status, content_type, body) = gateway.route("/dev.json?limit=1").await;
+            assert_eq!((status, content_type), ("200 OK", "application/json"));
+            assert!(body.contains(&nick_hash("anon")));
+            assert!(!body.contains("anon"));
+
+            // Message text gets escaped in the Atom feed
+            let (status, content_type, body) = gateway.route("/dev.atom").await;
+            assert_eq!((status, content_type), ("200 OK", "application/atom+xml"));
+            assert!(body.contains("&lt;msg 1&gt;"));
+            assert!(!body.contains("<msg 1>"));
+
+            assert_eq!(gateway.route("/secret.json").await.0, "404 Not Found");
+            assert_eq!(gateway.route("/dev").await.0, "404 Not Found");
+        })
+    }
+
+    #[test]
+    fn gateway_connection() -> Result<()> {
+        smol::block_on(async {
+            let gateway = Gateway::new(&["#dev".to_string()], 10);
+            let listener = TcpListener::bind("127.0.0.1:0").await?;
+            let addr = listener.local_addr()?;
+
+            let client = smol::spawn(async move {
+                let mut stream = TcpStream::connect(addr).await?;
+                stream.write_all(b"GET /dev.json HTTP/1.1\r\nHost: localhost\r\n\r\n").await?;
+                let mut response = String::new();
+                stream.read_to_string(&mut response).await?;
+                Ok::<String, std::io::Error>(response)
+            });
+
+            let (stream, _) = listener.accept().await?;
+            gateway.handle_connection(stream).await?;
+
+            let response = client.await?;
+            assert!(response.starts_with("HTTP/1.1 200 OK\r\n"));
+            assert!(response.contains("\"messages\":[]"));
+            Ok(())
+        })
+    }
+}
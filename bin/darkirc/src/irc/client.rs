@@ -42,7 +42,12 @@ use smol::{
 
 use super::{
     server::{IrcServer, MAX_MSG_LEN},
-    Msg, NickServ, OldPrivmsg, SERVER_NAME,
+    services::{fileserv::FILESERV_NICK, search::SEARCHSERV_NICK},
+    FileServ, Msg, NickServ, OldPrivmsg, SearchServ, SERVER_NAME,
+};
+use crate::{
+    notify::{escape_tag_value, HIGHLIGHT_TAG},
+    pow,
 };
 
 const PENALTY_LIMIT: usize = 5;
@@ -59,6 +64,8 @@ pub enum ReplyType {
     Cap(String),
     /// NOTICE reply (from, to, what)
     Notice((String, String, String)),
+    /// Client reply carrying IRCv3 message tags (tags, nick, msg)
+    Tagged((String, String, String)),
 }
 
 /// Stateful IRC client handler, used for each client connection
@@ -96,6 +103,10 @@ pub struct Client {
     pub seen: OnceCell<sled::Tree>,
     /// NickServ instance
     pub nickserv: Arc<NickServ>,
+    /// Search service instance
+    pub searchserv: SearchServ,
+    /// File sharing service instance
+    pub fileserv: FileServ,
 }
 
 impl Client {
@@ -105,8 +116,11 @@ impl Client {
         incoming: Subscription<Event>,
         addr: SocketAddr,
     ) -> Result<Self> {
-        let caps =
-            HashMap::from([("no-history".to_string(), false), ("no-autojoin".to_string(), false)]);
+        let caps = HashMap::from([
+            ("no-history".to_string(), false),
+            ("no-autojoin".to_string(), false),
+            ("message-tags".to_string(), false),
+        ]);
 
         let username = Arc::new(RwLock::new(String::from("*")));
         let nickname = Arc::new(RwLock::new(String::from("*")));
@@ -130,6 +144,8 @@ impl Client {
             nickserv: Arc::new(
                 NickServ::new(username.clone(), nickname.clone(), server.clone()).await?,
             ),
+            searchserv: SearchServ::new(server.clone()),
+            fileserv: FileServ::new(server.clone()),
         })
     }
 
@@ -241,7 +257,7 @@ impl Client {
                     self.server.try_decrypt(&mut privmsg, self.nickname.read().await.as_ref()).await;
 
                     // We should skip any attempts to contact services from the network.
-                    if ["nickserv", "chanserv"].contains(&privmsg.nick.to_lowercase().as_str()) {
+                    if ["nickserv", "chanserv", SEARCHSERV_NICK, FILESERV_NICK].contains(&privmsg.nick.to_lowercase().as_str()) {
                         continue
                     }
 
@@ -271,11 +287,8 @@ impl Client {
                             continue
                         }
 
-                        // Format the message
-                        let msg = format!("PRIVMSG {} :{}", privmsg.channel, line);
-
                         // Send it to the client
-                        let reply = ReplyType::Client((privmsg.nick.clone(), msg));
+                        let reply = self.privmsg_reply(&privmsg.nick, &privmsg.channel, line).await;
                         if let Err(e) = self.reply(&mut writer, &reply).await {
                             error!("[IRC CLIENT] Failed writing PRIVMSG to client: {}", e);
                             continue
@@ -292,6 +305,21 @@ impl Client {
         }
     }
 
+    /// Create the reply relaying a PRIVMSG line to the IRC client, tagging
+    /// it with the matched highlight keyword if the client supports tags.
+    pub async fn privmsg_reply(&self, nick: &str, channel: &str, line: &str) -> ReplyType {
+        let msg = format!("PRIVMSG {} :{}", channel, line);
+
+        if *self.caps.read().await.get("message-tags").unwrap() {
+            if let Some(keyword) = self.server.highlight(channel, line) {
+                let tags = format!("{}={}", HIGHLIGHT_TAG, escape_tag_value(&keyword));
+                return ReplyType::Tagged((tags, nick.to_string(), msg))
+            }
+        }
+
+        ReplyType::Client((nick.to_string(), msg))
+    }
+
     /// Send a reply to the IRC client. Matches on the reply type.
     async fn reply<W>(&self, writer: &mut W, reply: &ReplyType) -> Result<()>
     where
@@ -305,6 +333,9 @@ impl Client {
             ReplyType::Notice((src, dst, msg)) => {
                 format!(":{}!~anon@darkirc NOTICE {} :{}", src, dst, msg)
             }
+            ReplyType::Tagged((tags, nick, msg)) => {
+                format!("@{} :{}!~anon@darkirc {}", tags, nick, msg)
+            }
         };
 
         debug!("[{}] <-- {}", self.addr, r);
@@ -343,6 +374,15 @@ impl Client {
             return Err(Error::ParseFailed("Line doesn't end with CR/LF"))
         }
 
+        // Drop any IRCv3 message tags, we don't relay them
+        if line.starts_with('@') {
+            let Some((_, rest)) = line.split_once(' ') else { return Ok(None) };
+            line = rest.trim_start().to_string();
+            if line.is_empty() {
+                return Ok(None)
+            }
+        }
+
         // Prefix the message part of PRIVMSG with ':' if is not already.
         // Or realname part of USER command.
         let mut words: Vec<String> = line.split_whitespace().map(|s| s.to_string()).collect();
@@ -432,6 +472,23 @@ impl Client {
             return Ok(Some(vec![event]))
         }
 
+        // Links to files shared through the fud service are posted as
+        // PRIVMSGs to their target.
+        let shared = self.fileserv.take_shared().await;
+        if !shared.is_empty() {
+            if !*self.server.darkirc.event_graph.synced.read().await {
+                debug!("DAG is still syncing, queuing shared links...");
+                args_queue.extend(shared);
+                return Ok(None)
+            }
+
+            let mut events = vec![];
+            for args in shared {
+                events.push(self.privmsg_to_event(args).await);
+            }
+            return Ok(Some(events))
+        }
+
         Ok(None)
     }
 
@@ -456,8 +513,15 @@ impl Client {
         // Encrypt the Privmsg if an encryption method is available.
         self.server.try_encrypt(&mut privmsg).await;
 
-        // Build a DAG event and return it.
-        Event::new(serialize_async(&privmsg).await, &self.server.darkirc.event_graph).await
+        // Build a DAG event, stamping it if the channel requires so.
+        let mut event =
+            Event::new(serialize_async(&privmsg).await, &self.server.darkirc.event_graph).await;
+        let difficulty = self.server.pow_difficulty(&privmsg.channel).await;
+        if difficulty > 0 {
+            pow::stamp(&mut event, difficulty).await;
+        }
+
+        event
     }
 
     /// Atomically mark a message as seen for this client.
@@ -49,16 +49,21 @@
 //! Some of the above commands could actually be implemented and could
 //! work in respect to the P2P network.
 
-use std::{collections::HashSet, sync::atomic::Ordering::SeqCst};
+use std::{
+    collections::{HashMap, HashSet},
+    sync::atomic::Ordering::SeqCst,
+    time::UNIX_EPOCH,
+};
 
-use darkfi::Result;
+use darkfi::{util::time::Timestamp, Result};
 use log::{error, info};
 
 use super::{
     client::{Client, ReplyType},
     rpl::*,
     server::MAX_NICK_LEN,
-    IrcChannel, Msg, SERVER_NAME,
+    services::{fileserv::FILESERV_NICK, search::SEARCHSERV_NICK},
+    DroppedHistory, HistoryRetention, IrcChannel, Msg, SERVER_NAME,
 };
 use crate::crypto::bcrypt::bcrypt_hash_password;
 
@@ -308,6 +313,9 @@ impl Client {
                     topic: String::new(),
                     nicks: HashSet::from([nick.clone()]),
                     saltbox: None,
+                    pow: 0,
+                    retention: HistoryRetention::default(),
+                    highlights: vec![],
                 };
                 server_channels.insert(channel.clone(), chan);
             }
@@ -709,6 +717,18 @@ impl Client {
             return self.nickserv.handle_query(message.strip_prefix(':').unwrap()).await
         }
 
+        // Handle queries to the file sharing service
+        if target.to_lowercase().as_str() == FILESERV_NICK {
+            let query = &args[args.find(':').unwrap() + 1..];
+            return self.fileserv.handle_query(&nick, query).await
+        }
+
+        // Handle queries to the search service
+        if target.to_lowercase().as_str() == SEARCHSERV_NICK {
+            let query = &args[args.find(':').unwrap() + 1..];
+            return self.searchserv.handle_query(&nick, query).await
+        }
+
         // If it's a DM and we don't have an encryption key, we will
         // refuse to send it. Send ERR_NORECIPIENT to the client.
         if !target.starts_with('#') && !self.server.contacts.read().await.contains_key(target) {
@@ -959,6 +979,8 @@ impl Client {
 
     /// Internal function that scans the DAG and returns events for
     /// given channels. Will return empty if no_history CAP is requested.
+    /// Channel events over their retention quota are dropped, and
+    /// summarized in a notice instead.
     async fn get_history(&self, channels: &HashSet<String>) -> Result<Vec<ReplyType>> {
         if channels.is_empty() || *self.caps.read().await.get("no-history").unwrap() {
             return Ok(vec![])
@@ -967,8 +989,8 @@ impl Client {
         // Fetch and order all the events from the DAG
         let dag_events = self.server.darkirc.event_graph.order_events().await;
 
-        // Here we'll hold the events in order we'll push to the client
-        let mut replies = vec![];
+        // Here we'll hold the events we'll push to the client, in order
+        let mut history = vec![];
 
         for event in dag_events.iter() {
             let event_id = event.id();
@@ -1003,19 +1025,70 @@ impl Client {
                 continue
             }
 
-            // Insert nicks into channels
-            if let Some(chan) = self.server.channels.write().await.get_mut(&privmsg.channel) {
-                chan.nicks.insert(privmsg.nick.clone());
+            history.push((event_id, event.timestamp, privmsg));
+        }
+
+        // Apply the channels retention policies
+        let retentions: HashMap<String, HistoryRetention> = self
+            .server
+            .channels
+            .read()
+            .await
+            .iter()
+            .filter(|(_, chan)| !chan.retention.is_unlimited())
+            .map(|(name, chan)| (name.clone(), chan.retention))
+            .collect();
+
+        let now = UNIX_EPOCH.elapsed().unwrap().as_millis() as u64;
+        let mut dropped = vec![false; history.len()];
+        for (name, retention) in &retentions {
+            let indexes: Vec<usize> =
+                (0..history.len()).filter(|i| &history[*i].2.channel == name).collect();
+            let timestamps: Vec<u64> = indexes.iter().map(|i| history[*i].1).collect();
+            for (i, over) in indexes.iter().zip(retention.over_quota(&timestamps, now)) {
+                dropped[*i] = over;
+            }
+        }
+
+        let mut replies = vec![];
+        let mut summaries: HashMap<String, DroppedHistory> = HashMap::new();
+        for ((event_id, timestamp, privmsg), dropped) in history.into_iter().zip(dropped) {
+            if dropped {
+                summaries
+                    .entry(privmsg.channel.clone())
+                    .and_modify(|s| s.add(timestamp))
+                    .or_insert(DroppedHistory::new(timestamp));
+            } else {
+                // Insert nicks into channels
+                if let Some(chan) = self.server.channels.write().await.get_mut(&privmsg.channel) {
+                    chan.nicks.insert(privmsg.nick.clone());
+                }
+
+                replies
+                    .push(self.privmsg_reply(&privmsg.nick, &privmsg.channel, &privmsg.msg).await);
             }
 
-            let msg = format!("PRIVMSG {} :{}", privmsg.channel, privmsg.msg);
-            replies.push(ReplyType::Client((privmsg.nick, msg)));
+            // Dropped events are marked seen as well, so they
+            // only get summarized once.
             if let Err(e) = self.mark_seen(&event_id).await {
                 error!("[IRC CLIENT] (get_history) self.mark_seen({}) failed: {}", event_id, e);
                 return Err(e)
             }
         }
 
-        Ok(replies)
+        // Let the client know history was truncated, before replaying it
+        let mut notices = vec![];
+        for (channel, summary) in summaries {
+            let msg = format!(
+                "History truncated by retention policy: {} messages from {} to {} dropped",
+                summary.count,
+                Timestamp::from_u64(summary.first / 1000),
+                Timestamp::from_u64(summary.last / 1000),
+            );
+            notices.push(ReplyType::Notice((SERVER_NAME.to_string(), channel, msg)));
+        }
+        notices.extend(replies);
+
+        Ok(notices)
     }
 }
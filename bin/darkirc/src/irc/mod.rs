@@ -18,7 +18,7 @@
 
 use std::{collections::HashSet, sync::Arc};
 
-use crypto_box::ChaChaBox;
+use crypto_box::{ChaChaBox, PublicKey};
 use darkfi::{Error, Result};
 use darkfi_serial::{async_trait, deserialize_async_partial, SerialDecodable, SerialEncodable};
 
@@ -33,7 +33,7 @@ pub(crate) mod command;
 
 /// Services implementations
 pub(crate) mod services;
-pub(crate) use services::nickserv::NickServ;
+pub(crate) use services::{fileserv::FileServ, nickserv::NickServ, search::SearchServ};
 
 /// IRC numerics and server replies
 pub(crate) mod rpl;
@@ -131,10 +131,133 @@ pub struct IrcChannel {
     pub topic: String,
     pub nicks: HashSet<String>,
     pub saltbox: Option<Arc<ChaChaBox>>,
+    /// Required message stamp difficulty, in leading zero bits
+    pub pow: u8,
+    /// Local history retention policy
+    pub retention: HistoryRetention,
+    /// Configured highlight keywords, lowercased
+    pub highlights: Vec<String>,
+}
+
+/// Local history retention policy of an IRC channel.
+/// Events over the quota are not deleted from the DAG, they are
+/// only dropped from the history replayed to clients.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct HistoryRetention {
+    /// Maximum amount of events replayed (unlimited if zero)
+    pub max_events: usize,
+    /// Maximum age of events replayed, in seconds (unlimited if zero)
+    pub max_age: u64,
+}
+
+impl HistoryRetention {
+    /// Check if the policy doesn't restrict history at all
+    pub fn is_unlimited(&self) -> bool {
+        self.max_events == 0 && self.max_age == 0
+    }
+
+    /// Given the millisecond timestamps of a channel's history events,
+    /// in replay order, return which of them are over the quota.
+    /// Events older than `max_age` are dropped first, and then the
+    /// oldest of the remaining ones until `max_events` are left.
+    pub fn over_quota(&self, timestamps: &[u64], now: u64) -> Vec<bool> {
+        let cutoff = now.saturating_sub(self.max_age.saturating_mul(1000));
+        let mut dropped: Vec<bool> =
+            timestamps.iter().map(|ts| self.max_age > 0 && *ts < cutoff).collect();
+
+        if self.max_events > 0 {
+            let mut kept = dropped.iter().filter(|d| !**d).count();
+            for d in dropped.iter_mut() {
+                if kept <= self.max_events {
+                    break
+                }
+                if !*d {
+                    *d = true;
+                    kept -= 1;
+                }
+            }
+        }
+
+        dropped
+    }
+}
+
+/// Summary of the history events dropped by a retention policy
+#[derive(Clone, Copy, Debug)]
+pub struct DroppedHistory {
+    /// Amount of events dropped
+    pub count: usize,
+    /// Timestamp of the oldest dropped event, in milliseconds
+    pub first: u64,
+    /// Timestamp of the newest dropped event, in milliseconds
+    pub last: u64,
+}
+
+impl DroppedHistory {
+    pub fn new(timestamp: u64) -> Self {
+        Self { count: 1, first: timestamp, last: timestamp }
+    }
+
+    /// Account another dropped event
+    pub fn add(&mut self, timestamp: u64) {
+        self.count += 1;
+        self.first = self.first.min(timestamp);
+        self.last = self.last.max(timestamp);
+    }
 }
 
 /// IRC contact definition
 #[derive(Clone)]
 pub struct IrcContact {
+    pub public: PublicKey,
     pub saltbox: Option<Arc<ChaChaBox>>,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn history_retention_over_quota() {
+        let now = 100_000;
+        let timestamps = [10_000, 50_000, 80_000, 90_000, 99_000];
+
+        // Unlimited policies drop nothing
+        let unlimited = HistoryRetention::default();
+        assert!(unlimited.is_unlimited());
+        assert_eq!(unlimited.over_quota(&timestamps, now), vec![false; 5]);
+
+        // Only the newest events are kept
+        let by_count = HistoryRetention { max_events: 2, max_age: 0 };
+        assert!(!by_count.is_unlimited());
+        assert_eq!(by_count.over_quota(&timestamps, now), vec![true, true, true, false, false]);
+        let roomy = HistoryRetention { max_events: 10, max_age: 0 };
+        assert_eq!(roomy.over_quota(&timestamps, now), vec![false; 5]);
+
+        // Events older than the maximum age are dropped
+        let by_age = HistoryRetention { max_events: 0, max_age: 30 };
+        assert_eq!(by_age.over_quota(&timestamps, now), vec![true, true, false, false, false]);
+
+        // Both limits combine, the age one being applied first
+        let both = HistoryRetention { max_events: 2, max_age: 60 };
+        assert_eq!(both.over_quota(&timestamps, now), vec![true, true, true, false, false]);
+        let both = HistoryRetention { max_events: 4, max_age: 60 };
+        assert_eq!(both.over_quota(&timestamps, now), vec![true, false, false, false, false]);
+
+        // Ages over the clock don't underflow, and events from the
+        // future are kept
+        let ancient = HistoryRetention { max_events: 0, max_age: u64::MAX };
+        assert_eq!(ancient.over_quota(&timestamps, now), vec![false; 5]);
+        assert_eq!(by_age.over_quota(&[now + 1], now), vec![false]);
+        assert!(by_count.over_quota(&[], now).is_empty());
+    }
+
+    #[test]
+    fn dropped_history_add() {
+        let mut dropped = DroppedHistory::new(50);
+        dropped.add(20);
+        dropped.add(70);
+        dropped.add(30);
+        assert_eq!((dropped.count, dropped.first, dropped.last), (4, 20, 70));
+    }
+}
@@ -19,11 +19,12 @@
 use std::{collections::HashMap, fs::File, io::BufReader, path::PathBuf, sync::Arc};
 
 use darkfi::{
-    event_graph::Event,
+    event_graph::{proto::EventPut, Event},
     system::{StoppableTask, StoppableTaskPtr, Subscription},
     util::path::expand_path,
     Error, Result,
 };
+use darkfi_serial::{deserialize, serialize, serialize_async};
 use futures_rustls::{
     rustls::{self, pki_types::PrivateKeyDer},
     TlsAcceptor,
@@ -38,9 +39,15 @@ use smol::{
 };
 use url::Url;
 
-use super::{client::Client, ChaChaBox, IrcChannel, IrcContact, Priv, Privmsg};
+use super::{client::Client, ChaChaBox, IrcChannel, IrcContact, OldPrivmsg, Priv, Privmsg};
 use crate::{
-    crypto::saltbox,
+    crypto::{
+        ratchet::{parse_handshake, DmRatchet, DM_RATCHETS_TREE},
+        saltbox,
+    },
+    fud::FudClient,
+    notify::match_keyword,
+    pow,
     settings::{parse_autojoin_channels, parse_configured_channels, parse_configured_contacts},
     DarkIrc,
 };
@@ -69,10 +76,14 @@ pub struct IrcServer {
     pub contacts: RwLock<HashMap<String, IrcContact>>,
     /// Saltbox used to encrypt our nick in direct messages
     saltbox: RwLock<Option<Arc<ChaChaBox>>>,
+    /// Ephemeral DM key state of each configured contact
+    ratchets: RwLock<HashMap<String, DmRatchet>>,
     /// Active client connections
     clients: Mutex<HashMap<u16, StoppableTaskPtr>>,
     /// IRC server Password
     pub password: String,
+    /// Client of the local fud daemon used to share files, if enabled
+    pub fud: Option<Arc<FudClient>>,
 }
 
 impl IrcServer {
@@ -86,6 +97,7 @@ impl IrcServer {
         tls_secret: Option<String>,
         config_path: PathBuf,
         password: String,
+        fud: Option<Arc<FudClient>>,
     ) -> Result<Arc<Self>> {
         let scheme = listen.scheme();
         if scheme != "tcp" && scheme != "tcp+tls" {
@@ -136,8 +148,10 @@ impl IrcServer {
             channels: RwLock::new(HashMap::new()),
             contacts: RwLock::new(HashMap::new()),
             saltbox: RwLock::new(None),
+            ratchets: RwLock::new(HashMap::new()),
             clients: Mutex::new(HashMap::new()),
             password,
+            fud,
         });
 
         // Load any channel/contact configuration.
@@ -166,16 +180,133 @@ impl IrcServer {
         // Parse configured contacts
         let (contacts, saltbox) = parse_configured_contacts(&contents)?;
 
+        // Load the contacts ephemeral DM keys, dropping them if the
+        // contact's long-term key was changed.
+        let tree = self.darkirc.sled.open_tree(DM_RATCHETS_TREE)?;
+        let mut ratchets = HashMap::new();
+        for (name, contact) in contacts.iter() {
+            let ratchet = match tree.get(name.as_bytes())? {
+                Some(bytes) => match deserialize::<DmRatchet>(&bytes) {
+                    Ok(ratchet) if &ratchet.contact == contact.public.as_bytes() => ratchet,
+                    _ => DmRatchet::new(&contact.public),
+                },
+                None => DmRatchet::new(&contact.public),
+            };
+            ratchets.insert(name.clone(), ratchet);
+        }
+
         // FIXME: This will remove clients' joined channels. They need to stay.
         // Only if everything is fine, replace.
         *self.autojoin.write().await = autojoin;
+        self.darkirc.event_graph.set_event_filter(pow::event_filter(&channels)).await;
+        self.darkirc.highlights.set_configured(
+            channels
+                .iter()
+                .filter(|(_, chan)| !chan.highlights.is_empty())
+                .map(|(name, chan)| (name.clone(), chan.highlights.clone()))
+                .collect(),
+        );
         *self.channels.write().await = channels;
         *self.contacts.write().await = contacts;
         *self.saltbox.write().await = saltbox;
+        *self.ratchets.write().await = ratchets;
+
+        Ok(())
+    }
+
+    /// Return the stamp difficulty required for messages sent to
+    /// provided channel, as seen on the wire. Encrypted channels
+    /// never require stamps.
+    pub async fn pow_difficulty(&self, channel: &str) -> u8 {
+        match self.channels.read().await.get(channel) {
+            Some(chan) if chan.saltbox.is_none() => chan.pow,
+            _ => 0,
+        }
+    }
 
+    /// Return the highlight keyword matched by the given message text,
+    /// out of the keywords configured for provided channel or contact
+    /// and the ones set through JSON-RPC.
+    pub fn highlight(&self, target: &str, text: &str) -> Option<String> {
+        let keywords = self.darkirc.highlights.keywords(target);
+        match_keyword(&keywords, text).map(|k| k.to_string())
+    }
+
+    /// Persist the ephemeral DM key state of the given contact.
+    fn store_dm_ratchet(&self, name: &str, ratchet: &DmRatchet) -> Result<()> {
+        let tree = self.darkirc.sled.open_tree(DM_RATCHETS_TREE)?;
+        tree.insert(name.as_bytes(), serialize(ratchet))?;
         Ok(())
     }
 
+    /// Rotate our ephemeral DM keys which are older than `interval` seconds,
+    /// and publish the handshakes announcing the new keys over the DAG.
+    pub async fn rotate_dm_keys(&self, now: u64, interval: u64) -> Result<()> {
+        // Handshakes must be published on top of a synced DAG
+        if !*self.darkirc.event_graph.synced.read().await {
+            return Ok(())
+        }
+
+        let mut handshakes = vec![];
+        let mut ratchets = self.ratchets.write().await;
+        for (name, ratchet) in ratchets.iter_mut() {
+            // Our current key must be acknowledged before rotating it
+            if !ratchet.can_rotate() ||
+                ratchet.current_epoch().is_some_and(|epoch| now < epoch + interval)
+            {
+                continue
+            }
+
+            let handshake = ratchet.rotate(now);
+            self.store_dm_ratchet(name, ratchet)?;
+            handshakes.push((name.clone(), handshake));
+        }
+        drop(ratchets);
+
+        for (name, handshake) in handshakes {
+            let Some(contact) = self.contacts.read().await.get(&name).cloned() else { continue };
+            let Some(saltbox) = &contact.saltbox else { continue };
+
+            // Handshakes are always encrypted using the long-term keys,
+            // so the contact can authenticate them.
+            let mut privmsg =
+                OldPrivmsg { channel: name.clone(), nick: String::new(), msg: handshake };
+            self.encrypt_dm(saltbox, &mut privmsg).await;
+
+            let event =
+                Event::new(serialize_async(&privmsg).await, &self.darkirc.event_graph).await;
+            if let Err(e) = self.darkirc.event_graph.dag_insert(&[event.clone()]).await {
+                error!("[IRC SERVER] Failed inserting DM handshake to DAG: {}", e);
+                continue
+            }
+            self.darkirc.p2p.broadcast(&EventPut(event)).await;
+            info!("[IRC SERVER] Rotated DM encryption key for contact \"{}\"", name);
+        }
+
+        Ok(())
+    }
+
+    /// Apply a DM key handshake received from the given contact.
+    async fn receive_dm_handshake(
+        &self,
+        name: &str,
+        epoch: u64,
+        public: &crypto_box::PublicKey,
+        ack: Option<u64>,
+    ) {
+        let mut ratchets = self.ratchets.write().await;
+        let Some(ratchet) = ratchets.get_mut(name) else { return };
+        let received = ratchet.receive(epoch, public, ack);
+        if let Err(e) = self.store_dm_ratchet(name, ratchet) {
+            error!("[IRC SERVER] Failed storing DM key state for contact {}: {}", name, e);
+        }
+        if !received {
+            return
+        }
+
+        info!("[IRC SERVER] Received new DM encryption key from contact \"{}\"", name);
+    }
+
     /// Start accepting new IRC connections.
     pub async fn listen(self: Arc<Self>, ex: Arc<Executor<'_>>) -> Result<()> {
         loop {
@@ -302,22 +433,30 @@ impl IrcServer {
 
         if let Some((name, contact)) = self.contacts.read().await.get_key_value(privmsg.channel()) {
             if let Some(saltbox) = &contact.saltbox {
-                // We will use dummy channel and nick values of MAX_NICK_LEN,
-                // since they are not used, so all encrypted messages look the same.
-                *privmsg.channel() = saltbox::encrypt(saltbox, &[0x00; MAX_NICK_LEN]);
-                // We will encrypt the dummy nick value using our own self saltbox,
-                // so we can identify our messages. We can safely unwrap here since
-                // we know that if contacts exist, our self saltbox does as well.
-                *privmsg.nick() = saltbox::encrypt(
-                    self.saltbox.read().await.as_ref().unwrap(),
-                    &[0x00; MAX_NICK_LEN],
-                );
-                *privmsg.msg() = saltbox::encrypt(saltbox, privmsg.msg().as_bytes());
+                // Use the ephemeral keys if both sides have announced one
+                let ephemeral = match self.ratchets.read().await.get(name) {
+                    Some(ratchet) => ratchet.send_box(),
+                    None => None,
+                };
+                self.encrypt_dm(ephemeral.as_ref().unwrap_or(saltbox.as_ref()), privmsg).await;
                 debug!("Successfully encrypted message for {}", name);
             }
         };
     }
 
+    /// Encrypt a direct message `Privmsg` using the given `ChaChaBox`.
+    async fn encrypt_dm<T: Priv>(&self, saltbox: &ChaChaBox, privmsg: &mut T) {
+        // We will use dummy channel and nick values of MAX_NICK_LEN,
+        // since they are not used, so all encrypted messages look the same.
+        *privmsg.channel() = saltbox::encrypt(saltbox, &[0x00; MAX_NICK_LEN]);
+        // We will encrypt the dummy nick value using our own self saltbox,
+        // so we can identify our messages. We can safely unwrap here since
+        // we know that if contacts exist, our self saltbox does as well.
+        *privmsg.nick() =
+            saltbox::encrypt(self.saltbox.read().await.as_ref().unwrap(), &[0x00; MAX_NICK_LEN]);
+        *privmsg.msg() = saltbox::encrypt(saltbox, privmsg.msg().as_bytes());
+    }
+
     /// Try decrypting a given potentially encrypted `Privmsg` object.
     pub async fn try_decrypt(&self, privmsg: &mut Privmsg, self_nickname: &str) {
         // If all fields have base58, then we can consider decrypting.
@@ -368,32 +507,50 @@ impl IrcServer {
         for (name, contact) in self.contacts.read().await.iter() {
             let Some(saltbox) = &contact.saltbox else { continue };
 
-            if saltbox::try_decrypt(saltbox, &channel_ciphertext).is_none() {
+            // The message is either encrypted using the long-term keys,
+            // or using the ephemeral keys of any of the kept epochs.
+            let ephemeral = match self.ratchets.read().await.get(name) {
+                Some(ratchet) => ratchet.recv_boxes(),
+                None => vec![],
+            };
+            let (saltbox, is_ephemeral) = if saltbox::try_decrypt(saltbox, &channel_ciphertext)
+                .is_some()
+            {
+                (saltbox.as_ref(), false)
+            } else if let Some(ephemeral_box) =
+                ephemeral.iter().find(|b| saltbox::try_decrypt(b, &channel_ciphertext).is_some())
+            {
+                (ephemeral_box, true)
+            } else {
                 continue
             };
 
             // Since everyone encrypts the dummy nick value with their self saltbox,
             // we try to decrypt using our, to identify our messages. We can safely
             // unwrap here since we know that if contacts exist, our self saltbox does as well.
-            let nick = if saltbox::try_decrypt(
-                self.saltbox.read().await.as_ref().unwrap(),
-                &nick_ciphertext,
-            )
-            .is_some()
-            {
-                String::from(self_nickname)
-            } else {
-                name.to_string()
-            };
+            let from_self =
+                saltbox::try_decrypt(self.saltbox.read().await.as_ref().unwrap(), &nick_ciphertext)
+                    .is_some();
+            let nick = if from_self { String::from(self_nickname) } else { name.to_string() };
 
             let Some(msg_dec) = saltbox::try_decrypt(saltbox, &msg_ciphertext) else {
                 warn!(target: "darkirc::irc::server::try_decrypt", "Could not decrypt message ciphertext for contact: {name}");
                 continue
             };
+            let msg = String::from_utf8_lossy(&msg_dec);
+
+            // Key handshakes are consumed here and never shown. They are only
+            // accepted when authenticated by the contact's long-term key.
+            if let Some((epoch, public, ack)) = parse_handshake(&msg) {
+                if !from_self && !is_ephemeral {
+                    self.receive_dm_handshake(name, epoch, &public, ack).await;
+                }
+                return
+            }
 
             privmsg.channel = name.to_string();
             privmsg.nick = nick;
-            privmsg.msg = String::from_utf8_lossy(&msg_dec).into();
+            privmsg.msg = msg.into();
             debug!("Successfully decrypted message from {}", name);
             return
         }
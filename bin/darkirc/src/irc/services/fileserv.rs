@@ -0,0 +1,168 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2025 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use std::sync::Arc;
+
+use darkfi::{util::path::expand_path, Result};
+use log::error;
+use smol::lock::Mutex;
+
+use super::super::{client::ReplyType, rpl::*};
+use crate::{fud::FudLink, IrcServer};
+
+/// Nickname the file sharing service answers to
+pub const FILESERV_NICK: &str = "fud";
+
+const FILESERV_USAGE: &str = r#"***** fud Help *****
+
+fud shares files through a local fud daemon, posting links to them.
+
+The following commands are available:
+
+  SHARE <target> <path> [comment]   Put a local file into fud and post
+                                    its link to a channel or contact.
+  GET <link>                        Download a shared file with fud.
+
+***** End of Help *****
+"#;
+
+/// Service used to share files over fud from IRC
+pub struct FileServ {
+    /// Pointer to parent `IrcServer`
+    pub server: Arc<IrcServer>,
+    /// `PRIVMSG` arguments posting shared links, waiting to be broadcast
+    shared: Mutex<Vec<String>>,
+}
+
+impl FileServ {
+    /// Instantiate a new `FileServ` for a client.
+    pub fn new(server: Arc<IrcServer>) -> Self {
+        Self { server, shared: Mutex::new(vec![]) }
+    }
+
+    fn notice(nick: &str, msg: String) -> ReplyType {
+        ReplyType::Notice((FILESERV_NICK.to_string(), nick.to_string(), msg))
+    }
+
+    /// Take the `PRIVMSG` arguments of the links shared since the last
+    /// call, to be broadcast by the client.
+    pub async fn take_shared(&self) -> Vec<String> {
+        std::mem::take(&mut *self.shared.lock().await)
+    }
+
+    /// Handle a `fud` service query. This is the main command handler.
+    /// Called from `command::handle_cmd_privmsg`.
+    pub async fn handle_query(&self, nick: &str, query: &str) -> Result<Vec<ReplyType>> {
+        let query = query.trim();
+        let (command, rest) = query.split_once(' ').unwrap_or((query, ""));
+
+        if command.is_empty() {
+            return Ok(vec![ReplyType::Server((
+                ERR_NOTEXTTOSEND,
+                format!("{} :No text to send", nick),
+            ))])
+        }
+
+        match command.to_uppercase().as_str() {
+            "SHARE" => self.handle_share(nick, rest.trim()).await,
+            "GET" => self.handle_get(nick, rest.trim()).await,
+            "HELP" => {
+                Ok(FILESERV_USAGE.lines().map(|x| Self::notice(nick, x.to_string())).collect())
+            }
+            _ => Ok(vec![
+                Self::notice(nick, "Invalid command.".to_string()),
+                Self::notice(
+                    nick,
+                    format!("Use /msg {} HELP for a command listing.", FILESERV_NICK),
+                ),
+            ]),
+        }
+    }
+
+    /// Handle the SHARE command
+    async fn handle_share(&self, nick: &str, args: &str) -> Result<Vec<ReplyType>> {
+        let Some(fud) = &self.server.fud else {
+            return Ok(vec![Self::notice(
+                nick,
+                "fud integration is disabled. Set `fud_rpc` in the config.".to_string(),
+            )])
+        };
+
+        let mut tokens = args.splitn(3, ' ');
+        let (Some(target), Some(path)) = (tokens.next(), tokens.next()) else {
+            return Ok(vec![Self::notice(
+                nick,
+                "Use `SHARE <target> <path> [comment]`.".to_string(),
+            )])
+        };
+        let comment = tokens.next().unwrap_or("").trim();
+
+        if !target.starts_with('#') && !self.server.contacts.read().await.contains_key(target) {
+            return Ok(vec![Self::notice(nick, format!("Unknown channel or contact {}", target))])
+        }
+
+        let Ok(path) = expand_path(path) else {
+            return Ok(vec![Self::notice(nick, format!("Invalid path {}", path))])
+        };
+
+        let link = match fud.share(&path).await {
+            Ok(v) => v,
+            Err(e) => {
+                error!(target: "darkirc::irc::services::fileserv", "Failed sharing {:?}: {}", path, e);
+                return Ok(vec![Self::notice(nick, format!("Failed sharing file: {}", e))])
+            }
+        };
+
+        let mut msg = format!("Shared {} ({} bytes): {}", link.name, link.size, link.to_url());
+        if !comment.is_empty() {
+            msg = format!("{} - {}", msg, comment);
+        }
+
+        self.shared.lock().await.push(format!("{} :{}", target, msg));
+
+        Ok(vec![
+            ReplyType::Client((nick.to_string(), format!("PRIVMSG {} :{}", target, msg))),
+            Self::notice(nick, format!("Shared {} as {}", path.display(), link.hash)),
+        ])
+    }
+
+    /// Handle the GET command
+    async fn handle_get(&self, nick: &str, args: &str) -> Result<Vec<ReplyType>> {
+        let Some(fud) = &self.server.fud else {
+            return Ok(vec![Self::notice(
+                nick,
+                "fud integration is disabled. Set `fud_rpc` in the config.".to_string(),
+            )])
+        };
+
+        let Some(link) = FudLink::parse(args) else {
+            return Ok(vec![Self::notice(nick, "Use `GET <fud link>`.".to_string())])
+        };
+
+        let msg = match fud.download(&link, None).await {
+            Ok(true) => format!("Downloading {} with fud", link.name),
+            Ok(false) => format!("{} is already being downloaded", link.name),
+            Err(e) => {
+                error!(target: "darkirc::irc::services::fileserv", "Failed queuing {}: {}", link.hash, e);
+                format!("Failed queuing download: {}", e)
+            }
+        };
+
+        Ok(vec![Self::notice(nick, msg)])
+    }
+}
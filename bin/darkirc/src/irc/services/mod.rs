@@ -21,3 +21,9 @@ pub mod rln;
 
 /// NickServ implementation, used for account management
 pub mod nickserv;
+
+/// Local message history search service
+pub mod search;
+
+/// File sharing service over fud
+pub mod fileserv;
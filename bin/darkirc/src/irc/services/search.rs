@@ -0,0 +1,133 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2025 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use std::sync::Arc;
+
+use darkfi::{util::time::DateTime, Result};
+use log::error;
+
+use super::super::{client::ReplyType, rpl::*};
+use crate::{
+    search::{IndexedMsg, DEFAULT_SEARCH_LIMIT},
+    IrcServer,
+};
+
+/// Nickname the search service answers to
+pub const SEARCHSERV_NICK: &str = "darkirc";
+
+const SEARCHSERV_USAGE: &str = r#"***** darkirc Help *****
+
+darkirc allows a client to search the locally indexed message history.
+
+The following commands are available:
+
+  SEARCH <query>    Find messages containing all the words in <query>.
+
+***** End of Help *****
+"#;
+
+/// Service used to query the local search index over IRC
+pub struct SearchServ {
+    /// Pointer to parent `IrcServer`
+    pub server: Arc<IrcServer>,
+}
+
+impl SearchServ {
+    /// Instantiate a new `SearchServ` for a client.
+    pub fn new(server: Arc<IrcServer>) -> Self {
+        Self { server }
+    }
+
+    fn notice(nick: &str, msg: String) -> ReplyType {
+        ReplyType::Notice((SEARCHSERV_NICK.to_string(), nick.to_string(), msg))
+    }
+
+    fn format_msg(msg: &IndexedMsg) -> String {
+        let date = DateTime::from_timestamp(msg.timestamp / 1000, 0);
+        format!("[{}] {} <{}> {}", date, msg.channel, msg.nick, msg.msg)
+    }
+
+    /// Handle a `darkirc` service query. This is the main command handler.
+    /// Called from `command::handle_cmd_privmsg`.
+    pub async fn handle_query(&self, nick: &str, query: &str) -> Result<Vec<ReplyType>> {
+        let query = query.trim();
+        let (command, rest) = query.split_once(' ').unwrap_or((query, ""));
+
+        if command.is_empty() {
+            return Ok(vec![ReplyType::Server((
+                ERR_NOTEXTTOSEND,
+                format!("{} :No text to send", nick),
+            ))])
+        }
+
+        match command.to_uppercase().as_str() {
+            "SEARCH" => self.handle_search(nick, rest.trim()).await,
+            "HELP" => {
+                Ok(SEARCHSERV_USAGE.lines().map(|x| Self::notice(nick, x.to_string())).collect())
+            }
+            _ => Ok(vec![
+                Self::notice(nick, "Invalid command.".to_string()),
+                Self::notice(
+                    nick,
+                    format!("Use /msg {} HELP for a command listing.", SEARCHSERV_NICK),
+                ),
+            ]),
+        }
+    }
+
+    /// Handle the SEARCH command
+    async fn handle_search(&self, nick: &str, query: &str) -> Result<Vec<ReplyType>> {
+        let Some(index) = &self.server.darkirc.search_index else {
+            return Ok(vec![Self::notice(
+                nick,
+                "Search index is disabled. Enable `search_index` in the config.".to_string(),
+            )])
+        };
+
+        if query.is_empty() {
+            return Ok(vec![Self::notice(nick, "Use `SEARCH <query>`.".to_string())])
+        }
+
+        let results = match index.search(query, DEFAULT_SEARCH_LIMIT) {
+            Ok(v) => v,
+            Err(e) => {
+                error!(target: "darkirc::irc::services::search", "Search failed: {}", e);
+                return Ok(vec![Self::notice(nick, "Search failed.".to_string())])
+            }
+        };
+
+        if results.is_empty() {
+            return Ok(vec![Self::notice(nick, format!("No results for \"{}\"", query))])
+        }
+
+        let mut replies =
+            vec![Self::notice(nick, format!("{} results for \"{}\":", results.len(), query))];
+        for result in results.iter() {
+            if let Some(before) = &result.before {
+                replies.push(Self::notice(nick, format!("   {}", Self::format_msg(before))));
+            }
+            replies.push(Self::notice(nick, format!(">> {}", Self::format_msg(&result.msg))));
+            if let Some(after) = &result.after {
+                replies.push(Self::notice(nick, format!("   {}", Self::format_msg(after))));
+            }
+            replies.push(Self::notice(nick, "--".to_string()));
+        }
+
+        Ok(replies)
+    }
+}
@@ -20,7 +20,7 @@ use std::{collections::HashSet, io::Write, path::PathBuf, sync::Arc};
 
 use darkfi::{
     async_daemonize, cli_desc,
-    event_graph::{proto::ProtocolEventGraph, EventGraph, EventGraphPtr},
+    event_graph::{policy::EventRateLimit, proto::ProtocolEventGraph, EventGraph, EventGraphPtr},
     net::{session::SESSION_DEFAULT, settings::SettingsOpt, P2p, P2pPtr},
     rpc::{
         jsonrpc::JsonSubscriber,
@@ -59,6 +59,29 @@ mod rpc;
 /// Settings utilities
 mod settings;
 
+/// Proof-of-work anti-spam message stamps
+mod pow;
+
+/// Local message history search index
+mod search;
+use search::SearchIndex;
+
+/// Local usage statistics
+mod stats;
+use stats::UsageStats;
+
+/// Read-only public web gateway
+mod gateway;
+use gateway::{Gateway, DEFAULT_GATEWAY_LIMIT};
+
+/// File sharing through a local fud daemon
+mod fud;
+use fud::FudClient;
+
+/// Keyword highlight notifications
+mod notify;
+use notify::Highlights;
+
 fn panic_hook(panic_info: &std::panic::PanicHookInfo) {
     error!("panic occurred: {panic_info}");
     error!("{}", std::backtrace::Backtrace::force_capture().to_string());
@@ -123,6 +146,10 @@ struct Args {
     #[structopt(long)]
     skip_dag_sync: bool,
 
+    /// Bootstrap the DAG from a snapshot file before syncing it
+    #[structopt(long)]
+    import_snapshot: Option<String>,
+
     /// IRC Password (Encrypted with bcrypt-2b)
     #[structopt(long)]
     pub password: Option<String>,
@@ -135,6 +162,42 @@ struct Args {
     #[structopt(long)]
     list_contacts: bool,
 
+    /// Maintain a local full-text search index of decrypted channel messages
+    #[structopt(long)]
+    search_index: bool,
+
+    /// Record local usage statistics, queryable over JSON-RPC
+    #[structopt(long)]
+    usage_stats: bool,
+
+    /// Max new events per second accepted from a peer before greylisting it (0 to disable)
+    #[structopt(long, default_value = "0")]
+    event_rate_limit: f64,
+
+    /// Interval in seconds of rotating the ephemeral DM encryption keys (0 to disable)
+    #[structopt(long, default_value = "21600")]
+    dm_rekey_interval: u64,
+
+    /// Serve selected channels as read-only JSON/Atom feeds on this HTTP address
+    #[structopt(long)]
+    gateway_listen: Option<Url>,
+
+    /// Channels exposed through the web gateway
+    #[structopt(long)]
+    gateway_channels: Vec<String>,
+
+    /// Amount of recent messages served per channel by the web gateway
+    #[structopt(long)]
+    gateway_limit: Option<usize>,
+
+    /// JSON-RPC endpoint of a local fud daemon used to share files
+    #[structopt(long)]
+    fud_rpc: Option<Url>,
+
+    /// Automatically download shared files up to this size in KiB (0 to disable)
+    #[structopt(long, default_value = "0")]
+    fud_autofetch_size: u64,
+
     /// P2P network settings
     #[structopt(flatten)]
     net: SettingsOpt,
@@ -153,18 +216,34 @@ pub struct DarkIrc {
     dnet_sub: JsonSubscriber,
     /// deg JSON-RPC subscriber
     deg_sub: JsonSubscriber,
+    /// Highlight notifications JSON-RPC subscriber
+    notify_sub: JsonSubscriber,
+    /// Highlight keywords set through JSON-RPC
+    highlights: Highlights,
     /// Replay logs (DB) path
     replay_datastore: PathBuf,
+    /// Directory DAG snapshots get exported into
+    snapshot_dir: PathBuf,
+    /// Optional local search index over message history
+    search_index: Option<Arc<SearchIndex>>,
+    /// Optional local usage statistics
+    usage_stats: Option<Arc<UsageStats>>,
 }
 
 impl DarkIrc {
+    #[allow(clippy::too_many_arguments)]
     fn new(
         p2p: P2pPtr,
         sled: sled::Db,
         event_graph: EventGraphPtr,
         dnet_sub: JsonSubscriber,
         deg_sub: JsonSubscriber,
+        notify_sub: JsonSubscriber,
+        highlights: Highlights,
         replay_datastore: PathBuf,
+        snapshot_dir: PathBuf,
+        search_index: Option<Arc<SearchIndex>>,
+        usage_stats: Option<Arc<UsageStats>>,
     ) -> Self {
         Self {
             p2p,
@@ -173,7 +252,12 @@ impl DarkIrc {
             rpc_connections: Mutex::new(HashSet::new()),
             dnet_sub,
             deg_sub,
+            notify_sub,
+            highlights,
             replay_datastore,
+            snapshot_dir,
+            search_index,
+            usage_stats,
         }
     }
 }
@@ -273,7 +357,7 @@ async fn realmain(args: Args, ex: Arc<Executor<'static>>) -> Result<()> {
     let replay_mode = args.replay_mode;
 
     info!("Instantiating event DAG");
-    let sled_db = sled::open(datastore)?;
+    let sled_db = sled::open(&datastore)?;
     let mut p2p_settings: darkfi::net::Settings = args.net.into();
     p2p_settings.app_version = semver::Version::parse(env!("CARGO_PKG_VERSION")).unwrap();
     let p2p = P2p::new(p2p_settings, ex.clone()).await?;
@@ -290,6 +374,17 @@ async fn realmain(args: Args, ex: Arc<Executor<'static>>) -> Result<()> {
 
     let prune_task = event_graph.prune_task.get().unwrap();
 
+    if args.event_rate_limit > 0.0 {
+        info!("Greylisting peers relaying more than {} events/sec", args.event_rate_limit);
+        let rate_limit = EventRateLimit { max_rate: args.event_rate_limit, ..Default::default() };
+        event_graph.set_rate_limit(Some(rate_limit)).await;
+    }
+
+    if let Some(path) = &args.import_snapshot {
+        info!("Importing event DAG snapshot from {path}");
+        event_graph.import_snapshot(&expand_path(path)?).await?;
+    }
+
     info!("Registering EventGraph P2P protocol");
     let event_graph_ = Arc::clone(&event_graph);
     let registry = p2p.protocol_registry();
@@ -348,6 +443,23 @@ async fn realmain(args: Args, ex: Arc<Executor<'static>>) -> Result<()> {
         ex.clone(),
     );
 
+    let search_index = if args.search_index {
+        info!("Opening message search index");
+        Some(Arc::new(SearchIndex::new(&sled_db)?))
+    } else {
+        None
+    };
+
+    let usage_stats = if args.usage_stats {
+        info!("Opening usage statistics");
+        Some(Arc::new(UsageStats::new(&sled_db)?))
+    } else {
+        None
+    };
+
+    let notify_sub = JsonSubscriber::new("notify.subscribe_events");
+    let highlights = Highlights::new(&sled_db)?;
+
     info!("Starting JSON-RPC server");
     let darkirc = Arc::new(DarkIrc::new(
         p2p.clone(),
@@ -355,7 +467,12 @@ async fn realmain(args: Args, ex: Arc<Executor<'static>>) -> Result<()> {
         event_graph.clone(),
         dnet_sub,
         deg_sub,
+        notify_sub.clone(),
+        highlights,
         replay_datastore.clone(),
+        datastore.join("snapshots"),
+        search_index.clone(),
+        usage_stats.clone(),
     ));
     let darkirc_ = Arc::clone(&darkirc);
     let rpc_task = StoppableTask::new();
@@ -374,6 +491,9 @@ async fn realmain(args: Args, ex: Arc<Executor<'static>>) -> Result<()> {
     info!("Starting IRC server");
     let password = args.password.unwrap_or_default();
     let config_path = get_config_path(args.config, CONFIG_FILE)?;
+    let fud = args.fud_rpc.map(|endpoint| {
+        Arc::new(FudClient::new(endpoint, args.fud_autofetch_size * 1024, ex.clone()))
+    });
     let irc_server = IrcServer::new(
         darkirc.clone(),
         args.irc_listen,
@@ -381,6 +501,7 @@ async fn realmain(args: Args, ex: Arc<Executor<'static>>) -> Result<()> {
         args.irc_tls_secret,
         config_path,
         password,
+        fud.clone(),
     )
     .await?;
 
@@ -398,6 +519,118 @@ async fn realmain(args: Args, ex: Arc<Executor<'static>>) -> Result<()> {
         ex.clone(),
     );
 
+    info!("Starting highlight notifications task");
+    let notify_task = StoppableTask::new();
+    notify_task.clone().start(
+        notify::notify_task(notify_sub, irc_server.clone(), event_graph.clone()),
+        |res| async move {
+            match res {
+                Ok(()) | Err(Error::DetachedTaskStopped) => { /* Do nothing */ }
+                Err(e) => error!("Failed highlight notifications task: {}", e),
+            }
+        },
+        Error::DetachedTaskStopped,
+        ex.clone(),
+    );
+
+    let search_task = StoppableTask::new();
+    if let Some(search_index) = search_index {
+        info!("Starting search indexing task");
+        search_task.clone().start(
+            search::index_task(search_index, irc_server.clone(), event_graph.clone()),
+            |res| async move {
+                match res {
+                    Ok(()) | Err(Error::DetachedTaskStopped) => { /* Do nothing */ }
+                    Err(e) => error!("Failed search indexing task: {}", e),
+                }
+            },
+            Error::DetachedTaskStopped,
+            ex.clone(),
+        );
+    }
+
+    let stats_task = StoppableTask::new();
+    if let Some(usage_stats) = usage_stats {
+        info!("Starting usage statistics task");
+        stats_task.clone().start(
+            stats::stats_task(usage_stats, irc_server.clone(), event_graph.clone()),
+            |res| async move {
+                match res {
+                    Ok(()) | Err(Error::DetachedTaskStopped) => { /* Do nothing */ }
+                    Err(e) => error!("Failed usage statistics task: {}", e),
+                }
+            },
+            Error::DetachedTaskStopped,
+            ex.clone(),
+        );
+    }
+
+    let fud_task = StoppableTask::new();
+    if let Some(fud) = fud.filter(|f| f.autofetch_size > 0) {
+        info!("Starting fud auto-fetch task");
+        fud_task.clone().start(
+            fud::autofetch_task(fud, irc_server.clone(), event_graph.clone()),
+            |res| async move {
+                match res {
+                    Ok(()) | Err(Error::DetachedTaskStopped) => { /* Do nothing */ }
+                    Err(e) => error!("Failed fud auto-fetch task: {}", e),
+                }
+            },
+            Error::DetachedTaskStopped,
+            ex.clone(),
+        );
+    }
+
+    let rekey_task = StoppableTask::new();
+    if args.dm_rekey_interval > 0 {
+        info!("Starting DM key rotation task");
+        rekey_task.clone().start(
+            crypto::ratchet::rekey_task(irc_server.clone(), args.dm_rekey_interval),
+            |res| async move {
+                match res {
+                    Ok(()) | Err(Error::DetachedTaskStopped) => { /* Do nothing */ }
+                    Err(e) => error!("Failed DM key rotation task: {}", e),
+                }
+            },
+            Error::DetachedTaskStopped,
+            ex.clone(),
+        );
+    }
+
+    let gateway_feed_task = StoppableTask::new();
+    let gateway_task = StoppableTask::new();
+    if let Some(gateway_listen) = args.gateway_listen {
+        info!("Starting web gateway");
+        let gateway = Arc::new(Gateway::new(
+            &args.gateway_channels,
+            args.gateway_limit.unwrap_or(DEFAULT_GATEWAY_LIMIT),
+        ));
+
+        gateway_feed_task.clone().start(
+            gateway::feed_task(gateway.clone(), irc_server.clone(), event_graph.clone()),
+            |res| async move {
+                match res {
+                    Ok(()) | Err(Error::DetachedTaskStopped) => { /* Do nothing */ }
+                    Err(e) => error!("Failed web gateway feed task: {}", e),
+                }
+            },
+            Error::DetachedTaskStopped,
+            ex.clone(),
+        );
+
+        gateway_task.clone().start(
+            gateway::listen_and_serve(gateway, gateway_listen, ex.clone()),
+            |res| async move {
+                match res {
+                    Ok(()) | Err(Error::DetachedTaskStopped) => { /* Do nothing */ }
+                    Err(e) => error!("Failed web gateway: {}", e),
+                }
+            },
+            Error::DetachedTaskStopped,
+            ex.clone(),
+        );
+    }
+
     info!("Starting P2P network");
     p2p.clone().start().await?;
 
@@ -433,8 +666,17 @@ async fn realmain(args: Args, ex: Arc<Executor<'static>>) -> Result<()> {
 
     info!("Stopping IRC server");
     irc_task.stop().await;
+    notify_task.stop().await;
+    search_task.stop().await;
+    stats_task.stop().await;
+    fud_task.stop().await;
+    rekey_task.stop().await;
     prune_task.stop().await;
 
+    info!("Stopping web gateway");
+    gateway_task.stop().await;
+    gateway_feed_task.stop().await;
+
     info!("Flushing sled database...");
     let flushed_bytes = sled_db.flush_async().await?;
     info!("Flushed {} bytes", flushed_bytes);
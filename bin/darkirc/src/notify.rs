@@ -0,0 +1,277 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2025 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Keyword highlight notifications.
+//!
+//! Message contents of encrypted channels only exist in plaintext inside
+//! the daemon, so clients that aren't attached, or can't match keywords
+//! themselves, would otherwise miss mentions. Every channel can have a
+//! set of highlight keywords, configured in TOML or through JSON-RPC.
+//! Messages matching them are tagged with [`HIGHLIGHT_TAG`] for IRC
+//! clients that negotiated the `message-tags` capability, and published
+//! to `notify.subscribe_events` JSON-RPC subscribers.
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    sync::{Arc, RwLock},
+};
+
+use darkfi::{
+    event_graph::{Event, EventGraphPtr},
+    rpc::{
+        jsonrpc::JsonSubscriber,
+        util::{json_map, JsonValue},
+    },
+    Result,
+};
+use darkfi_serial::{deserialize, serialize};
+use log::info;
+use sled_overlay::sled;
+
+use crate::irc::{server::IrcServer, Msg};
+
+/// IRCv3 message tag set on lines matching a highlight keyword
+pub const HIGHLIGHT_TAG: &str = "dark.fi/highlight";
+/// Sled tree holding the keywords set through JSON-RPC, keyed by target
+const HIGHLIGHTS_TREE: &str = "darkirc_highlights";
+
+/// Highlight keywords of every channel or contact. Keywords set
+/// through JSON-RPC are persisted in sled, while the ones configured
+/// in TOML are replaced on every configuration reload.
+pub struct Highlights {
+    tree: sled::Tree,
+    configured: RwLock<HashMap<String, Vec<String>>>,
+    custom: RwLock<HashMap<String, Vec<String>>>,
+}
+
+impl Highlights {
+    /// Open the highlights tree and load the stored keywords
+    pub fn new(sled_db: &sled::Db) -> Result<Self> {
+        let tree = sled_db.open_tree(HIGHLIGHTS_TREE)?;
+
+        let mut custom = HashMap::new();
+        for record in tree.iter() {
+            let (target, value) = record?;
+            let target = String::from_utf8_lossy(&target).to_string();
+            custom.insert(target, deserialize(&value)?);
+        }
+
+        Ok(Self { tree, configured: RwLock::new(HashMap::new()), custom: RwLock::new(custom) })
+    }
+
+    /// All the keywords of the given channel or contact
+    pub fn keywords(&self, target: &str) -> Vec<String> {
+        let mut keywords = self.configured.read().unwrap().get(target).cloned().unwrap_or_default();
+        if let Some(custom) = self.custom.read().unwrap().get(target) {
+            keywords.extend(custom.iter().cloned());
+        }
+        keywords
+    }
+
+    /// Replace the keywords configured in TOML
+    pub fn set_configured(&self, configured: HashMap<String, Vec<String>>) {
+        *self.configured.write().unwrap() = configured;
+    }
+
+    /// Replace the keywords of the given channel or contact set
+    /// through JSON-RPC. An empty set removes them.
+    pub fn set(&self, target: &str, keywords: &[String]) -> Result<()> {
+        let keywords = normalize_keywords(keywords);
+        let mut custom = self.custom.write().unwrap();
+        if keywords.is_empty() {
+            self.tree.remove(target.as_bytes())?;
+            custom.remove(target);
+        } else {
+            self.tree.insert(target.as_bytes(), serialize(&keywords))?;
+            custom.insert(target.to_string(), keywords);
+        }
+        self.tree.flush()?;
+
+        Ok(())
+    }
+
+    /// JSON representation of the configured and custom keywords
+    /// of every channel or contact having any
+    pub fn json(&self) -> JsonValue {
+        let mut targets: BTreeMap<String, (Vec<String>, Vec<String>)> = BTreeMap::new();
+        for (target, keywords) in self.configured.read().unwrap().iter() {
+            targets.entry(target.clone()).or_default().0 = keywords.clone();
+        }
+        for (target, keywords) in self.custom.read().unwrap().iter() {
+            targets.entry(target.clone()).or_default().1 = keywords.clone();
+        }
+
+        let to_json = |keywords: Vec<String>| {
+            JsonValue::Array(keywords.into_iter().map(JsonValue::String).collect())
+        };
+
+        let mut ret = HashMap::new();
+        for (target, (configured, custom)) in targets {
+            if configured.is_empty() && custom.is_empty() {
+                continue
+            }
+            ret.insert(
+                target,
+                json_map([("configured", to_json(configured)), ("custom", to_json(custom))]),
+            );
+        }
+
+        JsonValue::Object(ret)
+    }
+}
+
+/// Lowercase and deduplicate keywords, dropping empty ones
+pub fn normalize_keywords(keywords: &[String]) -> Vec<String> {
+    let mut ret: Vec<String> =
+        keywords.iter().map(|k| k.trim().to_lowercase()).filter(|k| !k.is_empty()).collect();
+    ret.sort();
+    ret.dedup();
+    ret
+}
+
+/// Return the first keyword appearing as a whole word in the given
+/// text, ignoring case.
+pub fn match_keyword<'a>(keywords: &'a [String], text: &str) -> Option<&'a str> {
+    let text = text.to_lowercase();
+    let is_boundary = |c: Option<char>| c.map_or(true, |c| !c.is_alphanumeric());
+
+    keywords
+        .iter()
+        .find(|keyword| {
+            text.match_indices(keyword.as_str()).any(|(i, m)| {
+                is_boundary(text[..i].chars().next_back()) &&
+                    is_boundary(text[i + m.len()..].chars().next())
+            })
+        })
+        .map(|k| k.as_str())
+}
+
+/// Escape a value for use in an IRCv3 message tag
+pub fn escape_tag_value(value: &str) -> String {
+    let mut ret = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            ';' => ret.push_str("\\:"),
+            ' ' => ret.push_str("\\s"),
+            '\\' => ret.push_str("\\\\"),
+            '\r' => ret.push_str("\\r"),
+            '\n' => ret.push_str("\\n"),
+            c => ret.push(c),
+        }
+    }
+    ret
+}
+
+/// Decrypt a single event and notify subscribers if it matches any
+/// highlight keyword of its channel, skipping anything that isn't a `Privmsg`
+async fn notify_event(notify_sub: &JsonSubscriber, server: &IrcServer, event: &Event) {
+    let mut privmsg = match Msg::deserialize(event.content()).await {
+        Ok(Msg::V1(old_msg)) => old_msg.into_new(),
+        Ok(Msg::V2(new_msg)) => new_msg,
+        Err(_) => return,
+    };
+
+    server.try_decrypt(&mut privmsg, "").await;
+
+    let Some(keyword) = server.highlight(&privmsg.channel, &privmsg.msg) else { return };
+
+    let notification = json_map([
+        ("id", JsonValue::String(event.id().to_string())),
+        ("timestamp", JsonValue::Number(event.timestamp as f64)),
+        ("channel", JsonValue::String(privmsg.channel)),
+        ("nick", JsonValue::String(privmsg.nick)),
+        ("msg", JsonValue::String(privmsg.msg)),
+        ("keyword", JsonValue::String(keyword)),
+    ]);
+    notify_sub.notify(vec![notification].into()).await;
+}
+
+/// Background task notifying JSON-RPC subscribers of every new event
+/// inserted into the DAG matching a highlight keyword.
+pub async fn notify_task(
+    notify_sub: JsonSubscriber,
+    server: Arc<IrcServer>,
+    event_graph: EventGraphPtr,
+) -> Result<()> {
+    let incoming = event_graph.event_pub.clone().subscribe().await;
+    info!(target: "darkirc::notify", "Watching new messages for highlights");
+
+    loop {
+        let event = incoming.receive().await;
+        notify_event(&notify_sub, &server, &event).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn keywords(keywords: &[&str]) -> Vec<String> {
+        normalize_keywords(&keywords.iter().map(|k| k.to_string()).collect::<Vec<_>>())
+    }
+
+    #[test]
+    fn notify_normalize_keywords() {
+        assert_eq!(keywords(&["Foo", " bar ", "FOO", "", "  "]), vec!["bar", "foo"]);
+    }
+
+    #[test]
+    fn notify_match_keyword() {
+        let kws = keywords(&["DarkFi", "zk proof", "über"]);
+
+        // Matching ignores case
+        assert_eq!(match_keyword(&kws, "darkfi rocks"), Some("darkfi"));
+        assert_eq!(match_keyword(&kws, "I love DARKFI"), Some("darkfi"));
+        assert_eq!(match_keyword(&kws, "a ZK Proof!"), Some("zk proof"));
+
+        // Keywords only match as whole words
+        assert_eq!(match_keyword(&kws, "darkfi."), Some("darkfi"));
+        assert_eq!(match_keyword(&kws, "(darkfi)"), Some("darkfi"));
+        assert_eq!(match_keyword(&kws, "#darkfi-dev"), Some("darkfi"));
+        assert_eq!(match_keyword(&kws, "darkfid is syncing"), None);
+        assert_eq!(match_keyword(&kws, "mydarkfi"), None);
+        assert_eq!(match_keyword(&kws, "darkfi2"), None);
+        assert_eq!(match_keyword(&kws, "zk proofs"), None);
+
+        // A later whole word occurrence still matches
+        assert_eq!(match_keyword(&kws, "darkfid and darkfi"), Some("darkfi"));
+
+        // Non-ASCII text and keywords are handled
+        assert_eq!(match_keyword(&kws, "ÜBER alles"), Some("über"));
+        assert_eq!(match_keyword(&kws, "ßüber"), None);
+        assert_eq!(match_keyword(&kws, "¡über!"), Some("über"));
+        assert_eq!(match_keyword(&kws, "日本darkfi"), None);
+        assert_eq!(match_keyword(&kws, "日本 darkfi"), Some("darkfi"));
+
+        assert_eq!(match_keyword(&kws, ""), None);
+        assert_eq!(match_keyword(&[], "darkfi"), None);
+    }
+
+    #[test]
+    fn notify_escape_tag_value() {
+        assert_eq!(escape_tag_value("plain"), "plain");
+        assert_eq!(escape_tag_value(";"), "\\:");
+        assert_eq!(escape_tag_value(" "), "\\s");
+        assert_eq!(escape_tag_value("\\"), "\\\\");
+        assert_eq!(escape_tag_value("\r"), "\\r");
+        assert_eq!(escape_tag_value("\n"), "\\n");
+        assert_eq!(escape_tag_value("a b;c\\d\r\ne"), "a\\sb\\:c\\\\d\\r\\ne");
+        assert_eq!(escape_tag_value("über ünïcode"), "über\\sünïcode");
+        assert_eq!(escape_tag_value(""), "");
+    }
+}
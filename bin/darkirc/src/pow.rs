@@ -0,0 +1,204 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2025 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Proof-of-work anti-spam stamps.
+//!
+//! Plaintext channels can be configured to require a hashcash-style
+//! stamp on every message, as an alternative or supplement to RLN.
+//! The stamp is a `u64` nonce appended to the serialized PRIVMSG in
+//! the event content, such that the BLAKE3 hash of the event header
+//! (timestamp, layer and parents), the PRIVMSG and the nonce has at
+//! least the channel's difficulty of leading zero bits. Binding the
+//! whole header means a stamp can't be reused for another event with
+//! the same message. Since PRIVMSGs are deserialized partially, nodes
+//! unaware of stamps simply ignore the trailing nonce.
+//!
+//! Encrypted channels are not covered, as their names are not visible
+//! to relays verifying the stamps.
+
+use std::{collections::HashMap, sync::Arc};
+
+use darkfi::event_graph::{policy::EventFilterFn, Event};
+use darkfi_serial::deserialize_partial;
+
+use crate::irc::{IrcChannel, OldPrivmsg, Privmsg};
+
+/// Maximum configurable stamp difficulty, in leading zero bits
+pub const MAX_POW_DIFFICULTY: u8 = 32;
+
+/// Size of a serialized stamp nonce
+const STAMP_LEN: usize = 8;
+
+/// Serialize the stamped parts of provided event: its header, placing
+/// it in the DAG, followed by the serialized PRIVMSG.
+fn stamp_preimage(event: &Event, privmsg: &[u8]) -> Vec<u8> {
+    let mut preimage = Vec::with_capacity(16 + event.parents.len() * 32 + privmsg.len());
+    preimage.extend_from_slice(&event.timestamp.to_le_bytes());
+    preimage.extend_from_slice(&event.layer.to_le_bytes());
+    for parent in event.parents.iter() {
+        preimage.extend_from_slice(parent.as_bytes());
+    }
+    preimage.extend_from_slice(privmsg);
+    preimage
+}
+
+/// Compute the stamp hash of provided stamp preimage and nonce.
+fn stamp_hash(preimage: &[u8], nonce: u64) -> blake3::Hash {
+    let mut hasher = blake3::Hasher::new();
+    hasher.update(preimage);
+    hasher.update(&nonce.to_le_bytes());
+    hasher.finalize()
+}
+
+/// Count the leading zero bits of provided hash.
+fn leading_zeros(hash: &blake3::Hash) -> u32 {
+    let mut zeros = 0;
+    for byte in hash.as_bytes() {
+        zeros += byte.leading_zeros();
+        if *byte != 0 {
+            break
+        }
+    }
+    zeros
+}
+
+/// Find a nonce satisfying provided difficulty for given stamp
+/// preimage. This is CPU intensive, so it should not run on the
+/// async executor.
+fn mint(preimage: &[u8], difficulty: u8) -> u64 {
+    let mut nonce = 0;
+    while leading_zeros(&stamp_hash(preimage, nonce)) < difficulty as u32 {
+        nonce += 1;
+    }
+    nonce
+}
+
+/// Append a stamp of provided difficulty to the given event,
+/// whose content must be a serialized PRIVMSG. The event header
+/// must not change afterwards.
+pub async fn stamp(event: &mut Event, difficulty: u8) {
+    let preimage = stamp_preimage(event, &event.content);
+    let nonce = smol::unblock(move || mint(&preimage, difficulty)).await;
+    event.content.extend_from_slice(&nonce.to_le_bytes());
+}
+
+/// Parse the channel and serialized length of the PRIVMSG carried
+/// in provided event content, the same way `Msg::deserialize` does.
+fn parse_privmsg(content: &[u8]) -> Option<(String, usize)> {
+    if let Ok((privmsg, len)) = deserialize_partial::<OldPrivmsg>(content) {
+        return Some((privmsg.channel, len))
+    }
+
+    if let Ok((privmsg, len)) = deserialize_partial::<Privmsg>(content) {
+        return Some((privmsg.channel, len))
+    }
+
+    None
+}
+
+/// Verify that provided event carries a stamp satisfying the difficulty
+/// of its channel. Events not carrying a PRIVMSG, or sent to channels
+/// not requiring stamps, are accepted.
+pub fn verify(event: &Event, difficulties: &HashMap<String, u8>) -> bool {
+    let content = event.content();
+    let Some((channel, len)) = parse_privmsg(content) else { return true };
+    let Some(difficulty) = difficulties.get(&channel) else { return true };
+
+    if content.len() != len + STAMP_LEN {
+        return false
+    }
+
+    let nonce = u64::from_le_bytes(content[len..].try_into().unwrap());
+    let preimage = stamp_preimage(event, &content[..len]);
+    leading_zeros(&stamp_hash(&preimage, nonce)) >= *difficulty as u32
+}
+
+/// Build the event graph filter enforcing the stamp difficulties of
+/// provided channels. Returns `None` if no channel requires stamps.
+pub fn event_filter(channels: &HashMap<String, IrcChannel>) -> Option<EventFilterFn> {
+    let difficulties: HashMap<String, u8> = channels
+        .iter()
+        .filter(|(_, chan)| chan.saltbox.is_none() && chan.pow > 0)
+        .map(|(name, chan)| (name.clone(), chan.pow))
+        .collect();
+
+    if difficulties.is_empty() {
+        return None
+    }
+
+    Some(Arc::new(move |event: &Event| verify(event, &difficulties)))
+}
+
+#[cfg(test)]
+mod tests {
+    use darkfi::event_graph::N_EVENT_PARENTS;
+    use darkfi_serial::serialize;
+
+    use super::*;
+
+    fn privmsg_event(channel: &str) -> Event {
+        let privmsg = Privmsg {
+            version: 0,
+            msg_type: 0,
+            channel: channel.to_string(),
+            nick: "anon".to_string(),
+            msg: "gm".to_string(),
+        };
+
+        Event {
+            timestamp: 1700000000,
+            content: serialize(&privmsg),
+            parents: [blake3::hash(b"parent"); N_EVENT_PARENTS],
+            layer: 1,
+            author: None,
+        }
+    }
+
+    #[test]
+    fn stamps_bind_the_event_header() {
+        smol::block_on(async {
+            let difficulties = HashMap::from([("#dev".to_string(), 16)]);
+
+            // Unstamped messages are rejected on stamped channels only
+            assert!(!verify(&privmsg_event("#dev"), &difficulties));
+            assert!(verify(&privmsg_event("#memes"), &difficulties));
+
+            let mut event = privmsg_event("#dev");
+            stamp(&mut event, 16).await;
+            assert!(verify(&event, &difficulties));
+
+            // Reusing the stamp at another place of the DAG fails
+            let mut moved = event.clone();
+            moved.parents[0] = blake3::hash(b"other parent");
+            assert!(!verify(&moved, &difficulties));
+
+            let mut moved = event.clone();
+            moved.layer += 1;
+            assert!(!verify(&moved, &difficulties));
+
+            let mut moved = event.clone();
+            moved.timestamp += 1;
+            assert!(!verify(&moved, &difficulties));
+
+            // Trailing garbage after the stamp is rejected
+            let mut event = event;
+            event.content.push(0);
+            assert!(!verify(&event, &difficulties));
+        })
+    }
+}
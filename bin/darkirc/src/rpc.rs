@@ -15,7 +15,7 @@
  * You should have received a copy of the GNU Affero General Public License
  * along with this program.  If not, see <https://www.gnu.org/licenses/>.
  */
-use std::collections::HashSet;
+use std::{collections::HashSet, ffi::OsStr, path::Path};
 
 use async_trait::async_trait;
 use darkfi::{
@@ -28,11 +28,12 @@ use darkfi::{
         util::JsonValue,
     },
     system::StoppableTaskPtr,
+    util::path::expand_path,
 };
-use log::debug;
-use smol::lock::MutexGuard;
+use log::{debug, error};
+use smol::{fs, lock::MutexGuard};
 
-use super::DarkIrc;
+use super::{search::DEFAULT_SEARCH_LIMIT, DarkIrc};
 
 #[async_trait]
 impl RequestHandler<()> for DarkIrc {
@@ -43,13 +44,28 @@ impl RequestHandler<()> for DarkIrc {
             "ping" => self.pong(req.id, req.params).await,
             "dnet.switch" => self.dnet_switch(req.id, req.params).await,
             "dnet.subscribe_events" => self.dnet_subscribe_events(req.id, req.params).await,
+            "dnet.get_recent" => self.dnet_get_recent(req.id, req.params).await,
             // TODO: Make this optional
             "p2p.get_info" => self.p2p_get_info(req.id, req.params).await,
+            "p2p.get_transport_stats" => self.p2p_get_transport_stats(req.id, req.params).await,
+            "p2p.export_hosts" => self.p2p_export_hosts(req.id, req.params).await,
+            "p2p.import_hosts" => self.p2p_import_hosts(req.id, req.params).await,
+            "p2p.add_peer" => self.p2p_add_peer(req.id, req.params).await,
+            "p2p.remove_peer" => self.p2p_remove_peer(req.id, req.params).await,
 
             "deg.switch" => self.deg_switch(req.id, req.params).await,
             "deg.subscribe_events" => self.deg_subscribe_events(req.id, req.params).await,
             "eventgraph.get_info" => self.eg_get_info(req.id, req.params).await,
             "eventgraph.replay" => self.eg_rep_info(req.id, req.params).await,
+            "eventgraph.export_snapshot" => self.eg_export_snapshot(req.id, req.params).await,
+            "eventgraph.import_snapshot" => self.eg_import_snapshot(req.id, req.params).await,
+
+            "search.query" => self.search_query(req.id, req.params).await,
+            "stats.get" => self.stats_get(req.id, req.params).await,
+
+            "notify.subscribe_events" => self.notify_subscribe_events(req.id, req.params).await,
+            "highlights.list" => self.highlights_list(req.id, req.params).await,
+            "highlights.set" => self.highlights_set(req.id, req.params).await,
 
             _ => JsonError::new(ErrorCode::MethodNotFound, None, req.id).into(),
         }
@@ -168,6 +184,200 @@ impl DarkIrc {
 
         recreate_from_replayer_log(&self.replay_datastore).await
     }
+
+    // RPCAPI:
+    // Exports a compressed snapshot of the current DAG and its tips to the
+    // given file, inside the `snapshots` directory of the datastore. Only
+    // plain file names are accepted and existing files are not overwritten.
+    // Other nodes can bootstrap from it using `eventgraph.import_snapshot`
+    // or the `--import-snapshot` flag.
+    // Returns the number of exported events.
+    //
+    // --> {"jsonrpc": "2.0", "method": "eventgraph.export_snapshot", "params": ["dag.snapshot"], "id": 42}
+    // <-- {"jsonrpc": "2.0", "result": 1337, "id": 42}
+    async fn eg_export_snapshot(&self, id: u16, params: JsonValue) -> JsonResult {
+        let params = params.get::<Vec<JsonValue>>().unwrap();
+        if params.len() != 1 || !params[0].is_string() {
+            return JsonError::new(ErrorCode::InvalidParams, None, id).into()
+        }
+
+        // Don't let the file escape the snapshots directory
+        let name = params[0].get::<String>().unwrap();
+        if Path::new(name).file_name() != Some(OsStr::new(name)) {
+            return JsonError::new(ErrorCode::InvalidParams, None, id).into()
+        }
+
+        if let Err(e) = fs::create_dir_all(&self.snapshot_dir).await {
+            return JsonError::new(ErrorCode::InternalError, Some(e.to_string()), id).into()
+        }
+
+        match self.event_graph.export_snapshot(&self.snapshot_dir.join(name)).await {
+            Ok(exported) => JsonResponse::new(JsonValue::Number(exported as f64), id).into(),
+            Err(e) => JsonError::new(ErrorCode::InternalError, Some(e.to_string()), id).into(),
+        }
+    }
+
+    // RPCAPI:
+    // Imports a DAG snapshot from the given file, inserting the events we
+    // are missing after validating them. The rest of the DAG gets synced
+    // from our peers as usual.
+    // Returns the number of imported events.
+    //
+    // --> {"jsonrpc": "2.0", "method": "eventgraph.import_snapshot", "params": ["~/dag.snapshot"], "id": 42}
+    // <-- {"jsonrpc": "2.0", "result": 1337, "id": 42}
+    async fn eg_import_snapshot(&self, id: u16, params: JsonValue) -> JsonResult {
+        let params = params.get::<Vec<JsonValue>>().unwrap();
+        if params.len() != 1 || !params[0].is_string() {
+            return JsonError::new(ErrorCode::InvalidParams, None, id).into()
+        }
+
+        let Ok(path) = expand_path(params[0].get::<String>().unwrap()) else {
+            return JsonError::new(ErrorCode::InvalidParams, None, id).into()
+        };
+
+        match self.event_graph.import_snapshot(&path).await {
+            Ok(imported) => JsonResponse::new(JsonValue::Number(imported as f64), id).into(),
+            Err(e) => JsonError::new(ErrorCode::InternalError, Some(e.to_string()), id).into(),
+        }
+    }
+
+    // RPCAPI:
+    // Search the local message history index for messages containing all
+    // the words in the given query. An optional second parameter limits the
+    // amount of returned matches. Each match is returned along with the
+    // previous and next message in the same channel, if any.
+    // Returns an error if the search index is not enabled.
+    //
+    // --> {"jsonrpc": "2.0", "method": "search.query", "params": ["query", 10], "id": 42}
+    // <-- {"jsonrpc": "2.0", "result": [{"match": {...}, "before": {...}, "after": null}, ...], "id": 42}
+    async fn search_query(&self, id: u16, params: JsonValue) -> JsonResult {
+        let params = params.get::<Vec<JsonValue>>().unwrap();
+        if params.is_empty() || params.len() > 2 || !params[0].is_string() {
+            return JsonError::new(ErrorCode::InvalidParams, None, id).into()
+        }
+
+        let limit = match params.get(1) {
+            Some(JsonValue::Number(n)) if *n >= 1.0 => *n as usize,
+            Some(_) => return JsonError::new(ErrorCode::InvalidParams, None, id).into(),
+            None => DEFAULT_SEARCH_LIMIT,
+        };
+
+        let Some(index) = &self.search_index else {
+            return JsonError::new(
+                ErrorCode::InternalError,
+                Some("Search index is disabled".to_string()),
+                id,
+            )
+            .into()
+        };
+
+        let query = params[0].get::<String>().unwrap();
+        let results = match index.search(query, limit) {
+            Ok(v) => v,
+            Err(e) => {
+                error!(target: "darkirc::rpc::search_query", "Search failed: {}", e);
+                return JsonError::new(ErrorCode::InternalError, None, id).into()
+            }
+        };
+
+        let results: Vec<JsonValue> = results.iter().map(|r| r.into()).collect();
+        JsonResponse::new(JsonValue::Array(results), id).into()
+    }
+
+    // RPCAPI:
+    // Returns the local usage statistics of the node: message volumes and
+    // active nicks per channel, messages we couldn't decrypt, the smoothed
+    // sync lag of incoming events in milliseconds, the current DAG size,
+    // and hourly samples of the DAG size, message count and sync lag.
+    // Returns an error if usage statistics are not enabled.
+    //
+    // --> {"jsonrpc": "2.0", "method": "stats.get", "params": [], "id": 42}
+    // <-- {"jsonrpc": "2.0", "result": {"channels": {"#dev": {...}}, "undecrypted": {...}, "messages": 1337, "sync_lag": 420, "dag_size": 1400, "samples": [...]}, "id": 42}
+    async fn stats_get(&self, id: u16, params: JsonValue) -> JsonResult {
+        let params = params.get::<Vec<JsonValue>>().unwrap();
+        if !params.is_empty() {
+            return JsonError::new(ErrorCode::InvalidParams, None, id).into()
+        }
+
+        let Some(stats) = &self.usage_stats else {
+            return JsonError::new(
+                ErrorCode::InternalError,
+                Some("Usage statistics are disabled".to_string()),
+                id,
+            )
+            .into()
+        };
+
+        match stats.json(self.event_graph.dag_len() as u64) {
+            Ok(v) => JsonResponse::new(v, id).into(),
+            Err(e) => {
+                error!(target: "darkirc::rpc::stats_get", "Failed reading statistics: {}", e);
+                JsonError::new(ErrorCode::InternalError, None, id).into()
+            }
+        }
+    }
+
+    // RPCAPI:
+    // Initializes a subscription to highlight notifications.
+    // Once a subscription is established, `darkirc` will send JSON-RPC notifications
+    // of new messages matching any highlight keyword of their channel or contact.
+    //
+    // --> {"jsonrpc": "2.0", "method": "notify.subscribe_events", "params": [], "id": 1}
+    // <-- {"jsonrpc": "2.0", "method": "notify.subscribe_events", "params": [{"id": "...", "timestamp": 1700000000000, "channel": "#dev", "nick": "anon", "msg": "new release soon", "keyword": "release"}]}
+    pub async fn notify_subscribe_events(&self, id: u16, params: JsonValue) -> JsonResult {
+        let params = params.get::<Vec<JsonValue>>().unwrap();
+        if !params.is_empty() {
+            return JsonError::new(ErrorCode::InvalidParams, None, id).into()
+        }
+
+        self.notify_sub.clone().into()
+    }
+
+    // RPCAPI:
+    // Returns the highlight keywords of every channel or contact having any,
+    // split into the ones configured in TOML and the ones set through JSON-RPC.
+    //
+    // --> {"jsonrpc": "2.0", "method": "highlights.list", "params": [], "id": 42}
+    // <-- {"jsonrpc": "2.0", "result": {"#dev": {"configured": ["darkirc"], "custom": ["release"]}}, "id": 42}
+    async fn highlights_list(&self, id: u16, params: JsonValue) -> JsonResult {
+        let params = params.get::<Vec<JsonValue>>().unwrap();
+        if !params.is_empty() {
+            return JsonError::new(ErrorCode::InvalidParams, None, id).into()
+        }
+
+        JsonResponse::new(self.highlights.json(), id).into()
+    }
+
+    // RPCAPI:
+    // Replaces the highlight keywords of the given channel or contact set
+    // through JSON-RPC, which are kept across restarts. Keywords match whole
+    // words, ignoring case. An empty list removes them, while keywords
+    // configured in TOML are left untouched. Returns `true` on success.
+    //
+    // --> {"jsonrpc": "2.0", "method": "highlights.set", "params": ["#dev", ["release", "mynick"]], "id": 42}
+    // <-- {"jsonrpc": "2.0", "result": true, "id": 42}
+    async fn highlights_set(&self, id: u16, params: JsonValue) -> JsonResult {
+        let params = params.get::<Vec<JsonValue>>().unwrap();
+        if params.len() != 2 || !params[0].is_string() || !params[1].is_array() {
+            return JsonError::new(ErrorCode::InvalidParams, None, id).into()
+        }
+
+        let target = params[0].get::<String>().unwrap();
+        let mut keywords = vec![];
+        for keyword in params[1].get::<Vec<JsonValue>>().unwrap() {
+            let Some(keyword) = keyword.get::<String>() else {
+                return JsonError::new(ErrorCode::InvalidParams, None, id).into()
+            };
+            keywords.push(keyword.clone());
+        }
+
+        if let Err(e) = self.highlights.set(target, &keywords) {
+            error!(target: "darkirc::rpc::highlights_set", "Failed storing highlights: {}", e);
+            return JsonError::new(ErrorCode::InternalError, None, id).into()
+        }
+
+        JsonResponse::new(JsonValue::Boolean(true), id).into()
+    }
 }
 
 impl HandlerP2p for DarkIrc {
@@ -0,0 +1,397 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2025 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use std::{collections::HashSet, sync::Arc};
+
+use darkfi::{
+    event_graph::{Event, EventGraphPtr},
+    rpc::util::{json_map, JsonValue},
+    Result,
+};
+use darkfi_serial::{deserialize, serialize, SerialDecodable, SerialEncodable};
+use log::{debug, error, info};
+use sled_overlay::sled;
+
+use crate::irc::{server::IrcServer, Msg, Privmsg};
+
+/// Sled tree holding the indexed messages, keyed by event ID
+const SEARCH_MSGS_TREE: &str = "darkirc_search_msgs";
+/// Sled tree holding the inverted index: `term || 0x00 || event_id`
+const SEARCH_TERMS_TREE: &str = "darkirc_search_terms";
+/// Sled tree holding per-channel ordering: `channel || 0x00 || timestamp || event_id`
+const SEARCH_CHANS_TREE: &str = "darkirc_search_chans";
+
+/// Minimum length of an indexed term
+const MIN_TERM_LEN: usize = 2;
+/// Maximum length of an indexed term
+const MAX_TERM_LEN: usize = 64;
+/// Default amount of search results returned
+pub const DEFAULT_SEARCH_LIMIT: usize = 10;
+/// Hard cap on the amount of search results returned
+pub const MAX_SEARCH_LIMIT: usize = 100;
+/// Nickname used to decrypt direct messages we sent ourselves outside
+/// of an IRC client session.
+pub(crate) const SELF_NICK: &str = "self";
+
+/// A decrypted message stored in the search index
+#[derive(Clone, Debug, SerialEncodable, SerialDecodable)]
+pub struct IndexedMsg {
+    /// Event timestamp in milliseconds
+    pub timestamp: u64,
+    /// Channel or contact name
+    pub channel: String,
+    /// Sender nickname
+    pub nick: String,
+    /// Message text
+    pub msg: String,
+}
+
+impl From<&IndexedMsg> for JsonValue {
+    fn from(m: &IndexedMsg) -> JsonValue {
+        json_map([
+            ("timestamp", JsonValue::Number(m.timestamp as f64)),
+            ("channel", JsonValue::String(m.channel.clone())),
+            ("nick", JsonValue::String(m.nick.clone())),
+            ("msg", JsonValue::String(m.msg.clone())),
+        ])
+    }
+}
+
+/// A search match along with the surrounding channel messages
+pub struct SearchResult {
+    /// The matching message
+    pub msg: IndexedMsg,
+    /// Previous message in the same channel, if any
+    pub before: Option<IndexedMsg>,
+    /// Next message in the same channel, if any
+    pub after: Option<IndexedMsg>,
+}
+
+impl From<&SearchResult> for JsonValue {
+    fn from(r: &SearchResult) -> JsonValue {
+        let before = r.before.as_ref().map(|m| m.into()).unwrap_or(JsonValue::Null);
+        let after = r.after.as_ref().map(|m| m.into()).unwrap_or(JsonValue::Null);
+        json_map([("match", (&r.msg).into()), ("before", before), ("after", after)])
+    }
+}
+
+/// Local full-text search index over decrypted channel messages.
+/// This is a simple inverted index stored in sled, so history can be
+/// searched even after the DAG has been pruned or the IRC client has
+/// dropped it. Direct messages are never indexed, so their plaintext
+/// doesn't outlive the DAG on disk.
+pub struct SearchIndex {
+    /// Indexed messages
+    msgs: sled::Tree,
+    /// Inverted index of terms to event IDs
+    terms: sled::Tree,
+    /// Per-channel message ordering
+    chans: sled::Tree,
+}
+
+impl SearchIndex {
+    /// Open the search index trees in the given sled database
+    pub fn new(sled_db: &sled::Db) -> Result<Self> {
+        Ok(Self {
+            msgs: sled_db.open_tree(SEARCH_MSGS_TREE)?,
+            terms: sled_db.open_tree(SEARCH_TERMS_TREE)?,
+            chans: sled_db.open_tree(SEARCH_CHANS_TREE)?,
+        })
+    }
+
+    /// Split a text into lowercase alphanumeric terms
+    fn tokenize(text: &str) -> HashSet<String> {
+        text.split(|c: char| !c.is_alphanumeric())
+            .filter(|t| t.chars().count() >= MIN_TERM_LEN)
+            .map(|t| t.chars().take(MAX_TERM_LEN).collect::<String>().to_lowercase())
+            .collect()
+    }
+
+    fn term_prefix(term: &str) -> Vec<u8> {
+        let mut key = term.as_bytes().to_vec();
+        key.push(0x00);
+        key
+    }
+
+    fn chan_key(channel: &str, timestamp: u64, event_id: &[u8]) -> Vec<u8> {
+        let mut key = Self::term_prefix(channel);
+        key.extend_from_slice(&timestamp.to_be_bytes());
+        key.extend_from_slice(event_id);
+        key
+    }
+
+    /// Check if an event was already indexed
+    pub fn contains(&self, event_id: &blake3::Hash) -> Result<bool> {
+        Ok(self.msgs.contains_key(event_id.as_bytes())?)
+    }
+
+    /// Index an already decrypted `Privmsg` carried by the given event.
+    /// Anything that isn't a channel message is skipped: direct messages,
+    /// ciphertext we couldn't decrypt, or noise.
+    pub fn insert(&self, event: &Event, privmsg: &Privmsg) -> Result<()> {
+        if !privmsg.channel.starts_with('#') {
+            return Ok(())
+        }
+
+        let event_id = event.id();
+        if self.contains(&event_id)? {
+            return Ok(())
+        }
+
+        let msg = IndexedMsg {
+            timestamp: event.timestamp,
+            channel: privmsg.channel.clone(),
+            nick: privmsg.nick.clone(),
+            msg: privmsg.msg.clone(),
+        };
+
+        let mut terms_batch = sled::Batch::default();
+        for term in Self::tokenize(&msg.msg) {
+            let mut key = Self::term_prefix(&term);
+            key.extend_from_slice(event_id.as_bytes());
+            terms_batch.insert(key, &[]);
+        }
+
+        self.terms.apply_batch(terms_batch)?;
+        self.chans.insert(Self::chan_key(&msg.channel, msg.timestamp, event_id.as_bytes()), &[])?;
+        self.msgs.insert(event_id.as_bytes(), serialize(&msg))?;
+
+        debug!(target: "darkirc::search", "Indexed event {}", event_id);
+        Ok(())
+    }
+
+    /// Fetch an indexed message by its event ID
+    fn get(&self, event_id: &[u8]) -> Result<Option<IndexedMsg>> {
+        let Some(bytes) = self.msgs.get(event_id)? else { return Ok(None) };
+        Ok(Some(deserialize(&bytes)?))
+    }
+
+    /// Find the messages surrounding the given one in its channel
+    fn context(
+        &self,
+        msg: &IndexedMsg,
+        event_id: &[u8],
+    ) -> Result<(Option<IndexedMsg>, Option<IndexedMsg>)> {
+        let prefix = Self::term_prefix(&msg.channel);
+        let key = Self::chan_key(&msg.channel, msg.timestamp, event_id);
+
+        let mut neighbours = [None, None];
+        let before = self.chans.range(prefix.clone()..key.clone()).next_back();
+        let after = self.chans.range(key.clone()..).find(|r| match r {
+            Ok((k, _)) => k.as_ref() != key.as_slice(),
+            Err(_) => true,
+        });
+
+        for (i, entry) in [before, after].into_iter().enumerate() {
+            let Some(entry) = entry else { continue };
+            let (k, _) = entry?;
+            if !k.starts_with(&prefix) {
+                continue
+            }
+            neighbours[i] = self.get(&k[k.len() - blake3::OUT_LEN..])?;
+        }
+
+        let [before, after] = neighbours;
+        Ok((before, after))
+    }
+
+    /// Search the index for messages containing all the terms in `query`.
+    /// Results are returned newest first, up to `limit` entries.
+    pub fn search(&self, query: &str, limit: usize) -> Result<Vec<SearchResult>> {
+        let terms = Self::tokenize(query);
+        if terms.is_empty() {
+            return Ok(vec![])
+        }
+
+        // Intersect the event ID sets of all the terms
+        let mut matches: Option<HashSet<Vec<u8>>> = None;
+        for term in terms.iter() {
+            let prefix = Self::term_prefix(term);
+            let mut ids = HashSet::new();
+            for entry in self.terms.scan_prefix(&prefix) {
+                let (k, _) = entry?;
+                ids.insert(k[prefix.len()..].to_vec());
+            }
+
+            matches = Some(match matches {
+                Some(m) => m.intersection(&ids).cloned().collect(),
+                None => ids,
+            });
+
+            if matches.as_ref().unwrap().is_empty() {
+                return Ok(vec![])
+            }
+        }
+
+        let mut msgs = vec![];
+        for event_id in matches.unwrap() {
+            if let Some(msg) = self.get(&event_id)? {
+                msgs.push((event_id, msg));
+            }
+        }
+        msgs.sort_by(|a, b| b.1.timestamp.cmp(&a.1.timestamp));
+        msgs.truncate(limit.min(MAX_SEARCH_LIMIT));
+
+        let mut results = Vec::with_capacity(msgs.len());
+        for (event_id, msg) in msgs {
+            let (before, after) = self.context(&msg, &event_id)?;
+            results.push(SearchResult { msg, before, after });
+        }
+
+        Ok(results)
+    }
+}
+
+/// Decrypt and index a single event, skipping anything that isn't a `Privmsg`
+async fn index_event(index: &SearchIndex, server: &IrcServer, event: &Event) -> Result<()> {
+    if index.contains(&event.id())? {
+        return Ok(())
+    }
+
+    let mut privmsg = match Msg::deserialize(event.content()).await {
+        Ok(Msg::V1(old_msg)) => old_msg.into_new(),
+        Ok(Msg::V2(new_msg)) => new_msg,
+        Err(_) => return Ok(()),
+    };
+
+    server.try_decrypt(&mut privmsg, SELF_NICK).await;
+    index.insert(event, &privmsg)
+}
+
+/// Background task feeding the search index. It first indexes the current
+/// DAG contents, and then every new event inserted into the DAG.
+pub async fn index_task(
+    index: Arc<SearchIndex>,
+    server: Arc<IrcServer>,
+    event_graph: EventGraphPtr,
+) -> Result<()> {
+    // Subscribe first so we don't miss anything while backfilling
+    let incoming = event_graph.event_pub.clone().subscribe().await;
+
+    info!(target: "darkirc::search", "Indexing existing DAG events");
+    for event in event_graph.order_events().await {
+        if let Err(e) = index_event(&index, &server, &event).await {
+            error!(target: "darkirc::search", "Failed indexing event {}: {}", event.id(), e);
+        }
+    }
+
+    loop {
+        let event = incoming.receive().await;
+        if let Err(e) = index_event(&index, &server, &event).await {
+            error!(target: "darkirc::search", "Failed indexing event {}: {}", event.id(), e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use darkfi::event_graph::{NULL_ID, N_EVENT_PARENTS};
+
+    use super::*;
+
+    fn event(timestamp: u64, content: &[u8]) -> Event {
+        Event {
+            timestamp,
+            content: content.to_vec(),
+            parents: [NULL_ID; N_EVENT_PARENTS],
+            layer: 1,
+            author: None,
+        }
+    }
+
+    fn privmsg(channel: &str, nick: &str, msg: &str) -> Privmsg {
+        Privmsg {
+            version: 0,
+            msg_type: 0,
+            channel: channel.to_string(),
+            nick: nick.to_string(),
+            msg: msg.to_string(),
+        }
+    }
+
+    fn msgs(results: &[SearchResult]) -> Vec<&str> {
+        results.iter().map(|r| r.msg.msg.as_str()).collect()
+    }
+
+    #[test]
+    fn search_index() {
+        let path = std::env::temp_dir().join(format!("darkirc_search_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&path);
+        let sled_db = sled::open(&path).unwrap();
+        let index = SearchIndex::new(&sled_db).unwrap();
+
+        let lines = [
+            (1, "#dev", "alice", "The DAG sync is broken again"),
+            (2, "#dev", "bob", "which dag?"),
+            (3, "#random", "carol", "sync your clocks"),
+            (4, "#dev", "alice", "Never mind, dag SYNC works"),
+        ];
+        for (ts, chan, nick, msg) in lines {
+            index.insert(&event(ts, msg.as_bytes()), &privmsg(chan, nick, msg)).unwrap();
+        }
+
+        // Terms are matched case-insensitively, newest first
+        let results = index.search("dag", 10).unwrap();
+        assert_eq!(
+            msgs(&results),
+            vec!["Never mind, dag SYNC works", "which dag?", "The DAG sync is broken again"]
+        );
+
+        // All the query terms must match
+        let results = index.search("Sync DAG", 10).unwrap();
+        assert_eq!(
+            msgs(&results),
+            vec!["Never mind, dag SYNC works", "The DAG sync is broken again"]
+        );
+        assert!(index.search("dag clocks", 10).unwrap().is_empty());
+        assert!(index.search("nothing", 10).unwrap().is_empty());
+        // Terms shorter than the minimum are ignored
+        assert!(index.search("a", 10).unwrap().is_empty());
+
+        // The limit is applied after ordering
+        assert_eq!(msgs(&index.search("sync", 1).unwrap()), vec!["Never mind, dag SYNC works"]);
+
+        // Results carry their neighbours in the same channel only
+        let results = index.search("which", 10).unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].msg.nick, "bob");
+        assert_eq!(results[0].before.as_ref().unwrap().timestamp, 1);
+        assert_eq!(results[0].after.as_ref().unwrap().timestamp, 4);
+        let results = index.search("clocks", 10).unwrap();
+        assert!(results[0].before.is_none() && results[0].after.is_none());
+
+        // Inserting the same event again is a no-op
+        let (ts, chan, nick, msg) = lines[1];
+        index.insert(&event(ts, msg.as_bytes()), &privmsg(chan, nick, msg)).unwrap();
+        assert_eq!(index.search("which", 10).unwrap().len(), 1);
+
+        // Direct messages and undecrypted ciphertext are never indexed
+        let dm = event(5, b"dm");
+        index.insert(&dm, &privmsg("alice", "bob", "secret dag plans")).unwrap();
+        let noise = event(6, b"noise");
+        index.insert(&noise, &privmsg("3Kx9fA", "7Qw2Ld", "9Jd8Lm")).unwrap();
+        assert!(!index.contains(&dm.id()).unwrap());
+        assert!(!index.contains(&noise.id()).unwrap());
+        assert!(index.search("secret", 10).unwrap().is_empty());
+        assert_eq!(index.search("dag", 10).unwrap().len(), 3);
+
+        drop(index);
+        drop(sled_db);
+        std::fs::remove_dir_all(&path).unwrap();
+    }
+}
@@ -23,9 +23,13 @@ use std::{
 
 use crypto_box::{ChaChaBox, PublicKey};
 use darkfi::{Error::ParseFailed, Result};
-use log::info;
+use log::{info, warn};
 
-use crate::irc::{IrcChannel, IrcContact};
+use crate::{
+    irc::{HistoryRetention, IrcChannel, IrcContact},
+    notify::normalize_keywords,
+    pow::MAX_POW_DIFFICULTY,
+};
 
 /// Parse configured autojoin channels from a TOML map.
 ///
@@ -159,7 +163,7 @@ pub fn parse_configured_contacts(
         }
 
         info!("Instantiated ChaChaBox for contact \"{}\"", name);
-        ret.insert(name.to_string(), IrcContact { saltbox });
+        ret.insert(name.to_string(), IrcContact { public, saltbox });
     }
 
     Ok((ret, Some(Arc::new(crypto_box::ChaChaBox::new(&secret.public_key(), &secret)))))
@@ -172,6 +176,12 @@ pub fn parse_configured_contacts(
 /// [channel."#memes"]
 /// secret = "7CkVuFgwTUpJn5Sv67Q3fyEDpa28yrSeL5Hg2GqQ4jfM"
 /// topic = "Dank Memes"
+///
+/// [channel."#dev"]
+/// pow = 16
+/// max_events = 1000
+/// max_age = 604800
+/// highlights = ["darkirc", "release"]
 /// ```
 pub fn parse_configured_channels(data: &toml::Value) -> Result<HashMap<String, IrcChannel>> {
     let mut ret = HashMap::new();
@@ -181,7 +191,14 @@ pub fn parse_configured_channels(data: &toml::Value) -> Result<HashMap<String, I
     let Some(chans) = chans.as_table() else { return Err(ParseFailed("`channel` not a map")) };
 
     for (name, items) in chans {
-        let mut chan = IrcChannel { topic: String::new(), nicks: HashSet::new(), saltbox: None };
+        let mut chan = IrcChannel {
+            topic: String::new(),
+            nicks: HashSet::new(),
+            saltbox: None,
+            pow: 0,
+            retention: HistoryRetention::default(),
+            highlights: vec![],
+        };
 
         if let Some(topic) = items.get("topic") {
             if let Some(topic) = topic.as_str() {
@@ -212,6 +229,71 @@ pub fn parse_configured_channels(data: &toml::Value) -> Result<HashMap<String, I
             }
         }
 
+        if let Some(pow) = items.get("pow") {
+            let Some(pow) = pow.as_integer() else {
+                return Err(ParseFailed("Channel pow not an integer"))
+            };
+
+            if !(0..=MAX_POW_DIFFICULTY as i64).contains(&pow) {
+                return Err(ParseFailed("Channel pow out of range"))
+            }
+
+            if chan.saltbox.is_some() {
+                warn!("Ignoring pow for encrypted channel {}", name);
+            } else if pow > 0 {
+                chan.pow = pow as u8;
+                info!("Requiring {} bits message stamps for channel {}", pow, name);
+            }
+        }
+
+        if let Some(max_events) = items.get("max_events") {
+            let Some(max_events) = max_events.as_integer() else {
+                return Err(ParseFailed("Channel max_events not an integer"))
+            };
+
+            if max_events < 0 {
+                return Err(ParseFailed("Channel max_events out of range"))
+            }
+
+            chan.retention.max_events = max_events as usize;
+        }
+
+        if let Some(max_age) = items.get("max_age") {
+            let Some(max_age) = max_age.as_integer() else {
+                return Err(ParseFailed("Channel max_age not an integer"))
+            };
+
+            if max_age < 0 {
+                return Err(ParseFailed("Channel max_age out of range"))
+            }
+
+            chan.retention.max_age = max_age as u64;
+        }
+
+        if let Some(highlights) = items.get("highlights") {
+            let Some(highlights) = highlights.as_array() else {
+                return Err(ParseFailed("Channel highlights not an array"))
+            };
+
+            let mut keywords = Vec::with_capacity(highlights.len());
+            for keyword in highlights {
+                let Some(keyword) = keyword.as_str() else {
+                    return Err(ParseFailed("Channel highlight not a string"))
+                };
+                keywords.push(keyword.to_string());
+            }
+
+            chan.highlights = normalize_keywords(&keywords);
+            info!("Highlighting {} keywords in channel {}", chan.highlights.len(), name);
+        }
+
+        if !chan.retention.is_unlimited() {
+            info!(
+                "Retaining at most {} events up to {} seconds old for channel {}",
+                chan.retention.max_events, chan.retention.max_age, name,
+            );
+        }
+
         info!("Configured channel {}", name);
         ret.insert(name.to_string(), chan);
     }
@@ -0,0 +1,430 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2025 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Local usage statistics.
+//!
+//! Keeps per-channel message volumes, the nicks recently observed in
+//! every channel, the sync lag of incoming events and periodic samples
+//! of the DAG size, so operators can understand their node's load
+//! through the `stats.get` JSON-RPC method instead of parsing logs.
+//! Counters and samples are persisted in sled so they survive restarts.
+//! Nothing here is ever shared with the network.
+
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+    time::{Duration, UNIX_EPOCH},
+};
+
+use darkfi::{
+    event_graph::{Event, EventGraphPtr},
+    rpc::util::{json_map, JsonValue},
+    Result,
+};
+use darkfi_serial::{deserialize, serialize, SerialDecodable, SerialEncodable};
+use log::{debug, error, info};
+use sled_overlay::sled;
+use smol::{future, Timer};
+
+use crate::irc::{server::IrcServer, Msg};
+
+/// Sled tree holding the per-channel counters, keyed by channel name
+const STATS_CHANNELS_TREE: &str = "darkirc_stats_channels";
+/// Sled tree holding the periodic samples, keyed by big-endian timestamp
+const STATS_SAMPLES_TREE: &str = "darkirc_stats_samples";
+
+/// Interval between samples, in seconds
+const SAMPLE_INTERVAL: u64 = 3600;
+/// Amount of samples kept (one week)
+const MAX_SAMPLES: usize = 168;
+/// Nicks seen within this window, in milliseconds, count as active
+const ACTIVE_NICK_WINDOW: u64 = 3_600_000;
+/// Maximum amount of nicks tracked per channel
+const MAX_CHANNEL_NICKS: usize = 512;
+/// Events older or newer than this, in milliseconds, don't count towards
+/// the sync lag. Matches the drift the event graph tolerates for new events.
+const MAX_SYNC_LAG: u64 = 60_000;
+/// Key under which messages of unconfigured or undecryptable channels get accounted
+const UNDECRYPTED: &str = "*";
+
+/// Persisted message counters of a single channel
+#[derive(Clone, Debug, Default, SerialEncodable, SerialDecodable)]
+pub struct ChannelCounters {
+    /// Amount of messages seen
+    pub messages: u64,
+    /// Total size of the seen events content, in bytes
+    pub bytes: u64,
+    /// Timestamp of the last seen message, in milliseconds
+    pub last_timestamp: u64,
+}
+
+impl From<&ChannelCounters> for JsonValue {
+    fn from(c: &ChannelCounters) -> JsonValue {
+        json_map([
+            ("messages", JsonValue::Number(c.messages as f64)),
+            ("bytes", JsonValue::Number(c.bytes as f64)),
+            ("last_timestamp", JsonValue::Number(c.last_timestamp as f64)),
+        ])
+    }
+}
+
+/// Periodic snapshot of the node load
+#[derive(Clone, Debug, SerialEncodable, SerialDecodable)]
+pub struct StatsSample {
+    /// Sample timestamp, in seconds
+    pub timestamp: u64,
+    /// Amount of events in the DAG
+    pub dag_size: u64,
+    /// Total amount of messages seen so far
+    pub messages: u64,
+    /// Smoothed sync lag, in milliseconds
+    pub sync_lag: u64,
+}
+
+impl From<&StatsSample> for JsonValue {
+    fn from(s: &StatsSample) -> JsonValue {
+        json_map([
+            ("timestamp", JsonValue::Number(s.timestamp as f64)),
+            ("dag_size", JsonValue::Number(s.dag_size as f64)),
+            ("messages", JsonValue::Number(s.messages as f64)),
+            ("sync_lag", JsonValue::Number(s.sync_lag as f64)),
+        ])
+    }
+}
+
+/// Local usage statistics of the node
+pub struct UsageStats {
+    /// Per-channel message counters
+    channels: sled::Tree,
+    /// Periodic samples
+    samples: sled::Tree,
+    /// Nicks observed in each channel, with the last time they were seen
+    nicks: Mutex<HashMap<String, HashMap<String, u64>>>,
+    /// Exponential moving average of the incoming events lag, in milliseconds
+    sync_lag: AtomicU64,
+}
+
+impl UsageStats {
+    /// Open the statistics trees in the given sled database
+    pub fn new(sled_db: &sled::Db) -> Result<Self> {
+        Ok(Self {
+            channels: sled_db.open_tree(STATS_CHANNELS_TREE)?,
+            samples: sled_db.open_tree(STATS_SAMPLES_TREE)?,
+            nicks: Mutex::new(HashMap::new()),
+            sync_lag: AtomicU64::new(0),
+        })
+    }
+
+    /// Record a message seen on the given channel, with an optional nick
+    /// if we managed to decrypt it.
+    ///
+    /// Event timestamps are chosen by their sender, so they get clamped
+    /// to the current time, and only events within [`MAX_SYNC_LAG`] of it
+    /// are used to measure the sync lag.
+    pub fn record(&self, event: &Event, channel: &str, nick: Option<&str>) -> Result<()> {
+        let now = now_millis();
+        let timestamp = event.timestamp.min(now);
+
+        let mut counters = match self.channels.get(channel.as_bytes())? {
+            Some(bytes) => deserialize(&bytes)?,
+            None => ChannelCounters::default(),
+        };
+        counters.messages += 1;
+        counters.bytes += event.content().len() as u64;
+        counters.last_timestamp = counters.last_timestamp.max(timestamp);
+        self.channels.insert(channel.as_bytes(), serialize(&counters))?;
+
+        if let Some(nick) = nick {
+            let mut nicks = self.nicks.lock().unwrap();
+            let last_seen = nicks.entry(channel.to_string()).or_default();
+            if !last_seen.contains_key(nick) && last_seen.len() >= MAX_CHANNEL_NICKS {
+                // Make room by forgetting inactive nicks, or the least
+                // recently seen one if they are all still active.
+                let cutoff = now.saturating_sub(ACTIVE_NICK_WINDOW);
+                last_seen.retain(|_, seen| *seen >= cutoff);
+                if last_seen.len() >= MAX_CHANNEL_NICKS {
+                    let oldest = last_seen.iter().min_by_key(|(_, seen)| **seen);
+                    let oldest = oldest.map(|(n, _)| n.clone()).unwrap();
+                    last_seen.remove(&oldest);
+                }
+            }
+            let seen = last_seen.entry(nick.to_string()).or_default();
+            *seen = (*seen).max(timestamp);
+        }
+
+        // Events from the future or from far in the past, like the ones
+        // we sync on startup, tell us nothing about the current lag.
+        if event.timestamp > now || now - event.timestamp > MAX_SYNC_LAG {
+            return Ok(())
+        }
+
+        // Keep a moving average of how long events take to reach us,
+        // weighting the new observation by 1/8.
+        let lag = now - event.timestamp;
+        let avg = self.sync_lag.load(Ordering::Relaxed);
+        let avg = if avg == 0 { lag } else { avg - avg / 8 + lag / 8 };
+        self.sync_lag.store(avg, Ordering::Relaxed);
+
+        Ok(())
+    }
+
+    /// Return the persisted counters of every channel
+    pub fn channels(&self) -> Result<Vec<(String, ChannelCounters)>> {
+        let mut channels = vec![];
+        for entry in self.channels.iter() {
+            let (k, v) = entry?;
+            channels.push((String::from_utf8_lossy(&k).to_string(), deserialize(&v)?));
+        }
+        Ok(channels)
+    }
+
+    /// Return the amount of nicks seen in the given channel within
+    /// the active window.
+    pub fn active_nicks(&self, channel: &str) -> usize {
+        let cutoff = now_millis().saturating_sub(ACTIVE_NICK_WINDOW);
+        let mut nicks = self.nicks.lock().unwrap();
+        let Some(last_seen) = nicks.get_mut(channel) else { return 0 };
+        last_seen.retain(|_, seen| *seen >= cutoff);
+        last_seen.len()
+    }
+
+    /// Return the smoothed sync lag, in milliseconds
+    pub fn sync_lag(&self) -> u64 {
+        self.sync_lag.load(Ordering::Relaxed)
+    }
+
+    /// Record a new sample, dropping the oldest ones past [`MAX_SAMPLES`]
+    pub fn sample(&self, dag_size: u64) -> Result<()> {
+        let messages = self.channels()?.iter().map(|(_, c)| c.messages).sum();
+        let sample = StatsSample {
+            timestamp: now_millis() / 1000,
+            dag_size,
+            messages,
+            sync_lag: self.sync_lag(),
+        };
+        self.samples.insert(sample.timestamp.to_be_bytes(), serialize(&sample))?;
+
+        while self.samples.len() > MAX_SAMPLES {
+            let Some((k, _)) = self.samples.pop_min()? else { break };
+            debug!(target: "darkirc::stats", "Dropped sample {:?}", k);
+        }
+
+        Ok(())
+    }
+
+    /// Return the recorded samples, oldest first
+    pub fn samples(&self) -> Result<Vec<StatsSample>> {
+        let mut samples = vec![];
+        for entry in self.samples.iter() {
+            let (_, v) = entry?;
+            samples.push(deserialize(&v)?);
+        }
+        Ok(samples)
+    }
+
+    /// Build the JSON representation of the statistics, given the current DAG size
+    pub fn json(&self, dag_size: u64) -> Result<JsonValue> {
+        let mut channels = HashMap::new();
+        let mut undecrypted = ChannelCounters::default();
+        let mut messages = 0;
+        for (channel, counters) in self.channels()? {
+            messages += counters.messages;
+            if channel == UNDECRYPTED {
+                undecrypted = counters;
+                continue
+            }
+
+            let JsonValue::Object(mut map) = JsonValue::from(&counters) else { unreachable!() };
+            map.insert(
+                "active_nicks".to_string(),
+                JsonValue::Number(self.active_nicks(&channel) as f64),
+            );
+            channels.insert(channel, JsonValue::Object(map));
+        }
+
+        let samples: Vec<JsonValue> = self.samples()?.iter().map(|s| s.into()).collect();
+        Ok(json_map([
+            ("channels", JsonValue::Object(channels)),
+            ("undecrypted", (&undecrypted).into()),
+            ("messages", JsonValue::Number(messages as f64)),
+            ("sync_lag", JsonValue::Number(self.sync_lag() as f64)),
+            ("dag_size", JsonValue::Number(dag_size as f64)),
+            ("samples", JsonValue::Array(samples)),
+        ]))
+    }
+}
+
+/// Current time in milliseconds since UNIX epoch
+fn now_millis() -> u64 {
+    UNIX_EPOCH.elapsed().unwrap().as_millis() as u64
+}
+
+/// Decrypt and account a single event, skipping anything that isn't a `Privmsg`
+async fn record_event(stats: &UsageStats, server: &IrcServer, event: &Event) -> Result<()> {
+    let mut privmsg = match Msg::deserialize(event.content()).await {
+        Ok(Msg::V1(old_msg)) => old_msg.into_new(),
+        Ok(Msg::V2(new_msg)) => new_msg,
+        Err(_) => return Ok(()),
+    };
+
+    server.try_decrypt(&mut privmsg, "").await;
+
+    // Only configured channels and contacts get their own counters.
+    // Anyone can send messages to arbitrary channel names, and the names
+    // of messages we couldn't decrypt are ciphertext, so they all get
+    // accounted together.
+    let configured = server.channels.read().await.contains_key(&privmsg.channel) ||
+        server.autojoin.read().await.contains(&privmsg.channel) ||
+        server.contacts.read().await.contains_key(&privmsg.channel);
+    if !configured {
+        return stats.record(event, UNDECRYPTED, None)
+    }
+
+    stats.record(event, &privmsg.channel, Some(&privmsg.nick))
+}
+
+/// Background task feeding the usage statistics with every new event
+/// inserted into the DAG, and periodically sampling the DAG size.
+pub async fn stats_task(
+    stats: Arc<UsageStats>,
+    server: Arc<IrcServer>,
+    event_graph: EventGraphPtr,
+) -> Result<()> {
+    let incoming = event_graph.event_pub.clone().subscribe().await;
+
+    // Resume the sampling schedule from the last recorded sample
+    let last_sample = stats.samples()?.last().map(|s| s.timestamp).unwrap_or(0);
+    let mut next_sample = last_sample + SAMPLE_INTERVAL;
+    info!(target: "darkirc::stats", "Recording usage statistics");
+
+    loop {
+        let until_sample = next_sample.saturating_sub(now_millis() / 1000);
+        let event = future::or(async { Some(incoming.receive().await) }, async {
+            Timer::after(Duration::from_secs(until_sample)).await;
+            None
+        })
+        .await;
+
+        let Some(event) = event else {
+            if let Err(e) = stats.sample(event_graph.dag_len() as u64) {
+                error!(target: "darkirc::stats", "Failed recording sample: {}", e);
+            }
+            next_sample = now_millis() / 1000 + SAMPLE_INTERVAL;
+            continue
+        };
+
+        if let Err(e) = record_event(&stats, &server, &event).await {
+            error!(target: "darkirc::stats", "Failed recording event {}: {}", event.id(), e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use darkfi::event_graph::{NULL_ID, N_EVENT_PARENTS};
+
+    use super::*;
+
+    fn event(timestamp: u64, content: &[u8]) -> Event {
+        Event {
+            timestamp,
+            content: content.to_vec(),
+            parents: [NULL_ID; N_EVENT_PARENTS],
+            layer: 1,
+            author: None,
+        }
+    }
+
+    #[test]
+    fn stats_counters() -> Result<()> {
+        let sled_db = sled::Config::new().temporary(true).open()?;
+        let stats = UsageStats::new(&sled_db)?;
+        let now = now_millis();
+
+        stats.record(&event(now, b"hello"), "#dev", Some("alice"))?;
+        stats.record(&event(now, b"world!"), "#dev", Some("bob"))?;
+        stats.record(&event(now, b"???"), UNDECRYPTED, None)?;
+
+        let channels: HashMap<String, ChannelCounters> = stats.channels()?.into_iter().collect();
+        assert_eq!(channels.len(), 2);
+        assert_eq!(channels["#dev"].messages, 2);
+        assert_eq!(channels["#dev"].bytes, 11);
+        assert_eq!(channels[UNDECRYPTED].messages, 1);
+        assert_eq!(stats.active_nicks("#dev"), 2);
+        assert_eq!(stats.active_nicks("#random"), 0);
+
+        // Timestamps from the future are clamped
+        stats.record(&event(u64::MAX, b""), "#dev", Some("mallory"))?;
+        let channels: HashMap<String, ChannelCounters> = stats.channels()?.into_iter().collect();
+        assert!(channels["#dev"].last_timestamp <= now_millis());
+        assert!(stats.nicks.lock().unwrap()["#dev"]["mallory"] <= now_millis());
+
+        // Counters survive reopening the trees
+        let stats = UsageStats::new(&sled_db)?;
+        assert_eq!(stats.channels()?.len(), 2);
+        Ok(())
+    }
+
+    #[test]
+    fn stats_nicks_bounded() -> Result<()> {
+        let sled_db = sled::Config::new().temporary(true).open()?;
+        let stats = UsageStats::new(&sled_db)?;
+        let now = now_millis();
+
+        for i in 0..MAX_CHANNEL_NICKS as u64 + 10 {
+            stats.record(&event(now - 1000 + i, b""), "#dev", Some(&format!("nick{i}")))?;
+        }
+
+        // The least recently seen nicks got evicted
+        let nicks = stats.nicks.lock().unwrap();
+        assert_eq!(nicks["#dev"].len(), MAX_CHANNEL_NICKS);
+        assert!(!nicks["#dev"].contains_key("nick0"));
+        assert!(nicks["#dev"].contains_key(&format!("nick{}", MAX_CHANNEL_NICKS + 9)));
+        Ok(())
+    }
+
+    #[test]
+    fn stats_sync_lag() -> Result<()> {
+        let sled_db = sled::Config::new().temporary(true).open()?;
+        let stats = UsageStats::new(&sled_db)?;
+        let now = now_millis();
+
+        // Old and future events don't affect the lag
+        stats.record(&event(now - 10 * MAX_SYNC_LAG, b""), "#dev", None)?;
+        stats.record(&event(now + MAX_SYNC_LAG, b""), "#dev", None)?;
+        assert_eq!(stats.sync_lag(), 0);
+
+        // Recent ones do, and no single event can push it past the window
+        stats.record(&event(now - 8000, b""), "#dev", None)?;
+        let lag = stats.sync_lag();
+        assert!((8000..MAX_SYNC_LAG).contains(&lag));
+        stats.record(&event(now - 8000, b""), "#dev", None)?;
+        assert!(stats.sync_lag() <= MAX_SYNC_LAG);
+
+        // Samples record the totals
+        stats.sample(42)?;
+        let samples = stats.samples()?;
+        assert_eq!(samples.len(), 1);
+        assert_eq!(samples[0].dag_size, 42);
+        assert_eq!(samples[0].messages, 4);
+        Ok(())
+    }
+}
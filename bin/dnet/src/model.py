@@ -155,6 +155,22 @@ class Model:
                 key = (f'{name}', 'outbound')
                 event[key] = f'peer discovery: {state} (attempt {attempt})'
                 logging.debug(f'{current_time}  peer_discovery: {state} (attempt {attempt})')
+            case 'channel_handshake':
+                addr = info['addr']
+                session = info['session']
+                duration = info['duration_ms']
+                err = info['err']
+                if err is None:
+                    logging.debug(f'{current_time}  handshake ({session}): {addr} in {duration}ms')
+                else:
+                    logging.debug(f'{current_time}  handshake ({session}): {addr} failed after {duration}ms err={err}')
+            case 'tor_bootstrap':
+                progress = int(info['progress'] * 100)
+                status = info['status']
+                event = self.nodes[name]['event']
+                key = (f'{name}', 'tor')
+                event[key] = f'tor bootstrap: {progress}% {status}'
+                logging.debug(f'{current_time}  tor_bootstrap: {progress}% {status}')
 
 
     def add_lilith(self, lilith):
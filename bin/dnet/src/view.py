@@ -201,13 +201,13 @@ class View():
 
         match kind:
             case "outbound":
-                key = (focus_w[0].name, "outbound")
                 info = self.model.nodes.get(focus_w[0].name)
-                if key in info['event']:
-                    ev = info['event'].get(key)
-                    self.pile.contents.append((
-                        urwid.Text(f" {ev}"),
-                        self.pile.options()))
+                for key in [(focus_w[0].name, "tor"), (focus_w[0].name, "outbound")]:
+                    if key in info['event']:
+                        ev = info['event'].get(key)
+                        self.pile.contents.append((
+                            urwid.Text(f" {ev}"),
+                            self.pile.options()))
             case "outbound-slot" | "inbound-slot" | \
                     "manual-slot" | "seed-slot":
                 addr = focus_w[0].addr
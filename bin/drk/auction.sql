@@ -0,0 +1,9 @@
+-- Wallet definition for Auction contract
+-- Table names are prefixed with the native Contract ID, which is
+-- filled in when the schema is executed.
+
+-- All the auctions seen on-chain
+CREATE TABLE IF NOT EXISTS {AUCTION_CONTRACT_ID}_auction_auctions (
+	auction_id BLOB PRIMARY KEY NOT NULL,
+	record BLOB NOT NULL
+);
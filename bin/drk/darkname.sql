@@ -0,0 +1,19 @@
+-- Wallet definition for Darkname contract
+-- Table names are prefixed with the native Contract ID, which is
+-- filled in when the schema is executed.
+
+-- All the names seen on-chain
+CREATE TABLE IF NOT EXISTS {DARKNAME_CONTRACT_ID}_darkname_names (
+	name_id BLOB PRIMARY KEY NOT NULL,
+	name TEXT,
+	record BLOB NOT NULL
+);
+
+-- Owner secrets of names we have registered or are receiving
+CREATE TABLE IF NOT EXISTS {DARKNAME_CONTRACT_ID}_darkname_owned (
+	id INTEGER PRIMARY KEY AUTOINCREMENT,
+	name TEXT NOT NULL,
+	owner BLOB UNIQUE NOT NULL,
+	secret BLOB NOT NULL,
+	blind BLOB NOT NULL
+);
@@ -12,6 +12,13 @@ CREATE TABLE IF NOT EXISTS BZHKGQ26bzmBithTQYTJtjo2QdCqpkR9tjSBopT4yf4o_money_sm
 	smt_value BLOB NOT NULL
 );
 
+-- The mnemonic seed all our keypairs are derived from
+CREATE TABLE IF NOT EXISTS BZHKGQ26bzmBithTQYTJtjo2QdCqpkR9tjSBopT4yf4o_money_seed (
+	entropy BLOB NOT NULL,
+	next_index INTEGER NOT NULL,
+	next_dao_index INTEGER NOT NULL
+);
+
 -- The keypairs in our wallet
 CREATE TABLE IF NOT EXISTS BZHKGQ26bzmBithTQYTJtjo2QdCqpkR9tjSBopT4yf4o_money_keys (
 	key_id INTEGER PRIMARY KEY NOT NULL,
@@ -20,6 +27,15 @@ CREATE TABLE IF NOT EXISTS BZHKGQ26bzmBithTQYTJtjo2QdCqpkR9tjSBopT4yf4o_money_ke
 	secret BLOB NOT NULL
 );
 
+-- Keys retired by a key rotation in favor of their successor. They keep
+-- getting scanned for until `grace_blocks` after the rotation height.
+CREATE TABLE IF NOT EXISTS BZHKGQ26bzmBithTQYTJtjo2QdCqpkR9tjSBopT4yf4o_money_key_rotations (
+	public BLOB PRIMARY KEY NOT NULL,
+	successor BLOB NOT NULL,
+	height INTEGER NOT NULL,
+	grace_blocks INTEGER NOT NULL
+);
+
 -- The coins we have the information to and can spend
 CREATE TABLE IF NOT EXISTS BZHKGQ26bzmBithTQYTJtjo2QdCqpkR9tjSBopT4yf4o_money_coins (
 	coin BLOB PRIMARY KEY NOT NULL,
@@ -50,3 +66,40 @@ CREATE TABLE IF NOT EXISTS BZHKGQ26bzmBithTQYTJtjo2QdCqpkR9tjSBopT4yf4o_money_al
 	alias BLOB PRIMARY KEY NOT NULL,
 	token_id BLOB NOT NULL
 );
+
+-- Incoming view keys imported into our wallet
+CREATE TABLE IF NOT EXISTS BZHKGQ26bzmBithTQYTJtjo2QdCqpkR9tjSBopT4yf4o_money_view_keys (
+	view_key BLOB PRIMARY KEY NOT NULL
+);
+
+-- The incoming notes we found using imported view keys
+CREATE TABLE IF NOT EXISTS BZHKGQ26bzmBithTQYTJtjo2QdCqpkR9tjSBopT4yf4o_money_view_notes (
+	coin BLOB PRIMARY KEY NOT NULL,
+	view_key BLOB NOT NULL,
+	note BLOB NOT NULL,
+	tx_hash TEXT NOT NULL
+);
+
+-- The outputs we paid to others, used to create payment proofs
+CREATE TABLE IF NOT EXISTS BZHKGQ26bzmBithTQYTJtjo2QdCqpkR9tjSBopT4yf4o_money_payments (
+	tx_hash TEXT NOT NULL,
+	call_idx INTEGER NOT NULL,
+	output_idx INTEGER NOT NULL,
+	recipient BLOB NOT NULL,
+	note BLOB NOT NULL,
+	PRIMARY KEY (tx_hash, call_idx, output_idx)
+);
+
+-- Fee offers of liquidity providers, paying fees in exchange for other tokens
+CREATE TABLE IF NOT EXISTS BZHKGQ26bzmBithTQYTJtjo2QdCqpkR9tjSBopT4yf4o_money_fee_offers (
+	token_id BLOB NOT NULL,
+	recipient BLOB NOT NULL,
+	offer BLOB NOT NULL,
+	PRIMARY KEY (token_id, recipient)
+);
+
+-- The signing secrets of our fee swap requests, waiting for their fee call
+CREATE TABLE IF NOT EXISTS BZHKGQ26bzmBithTQYTJtjo2QdCqpkR9tjSBopT4yf4o_money_fee_swaps (
+	request TEXT PRIMARY KEY NOT NULL,
+	secrets BLOB NOT NULL
+);
@@ -0,0 +1,884 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2025 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use std::fmt;
+
+use lazy_static::lazy_static;
+use rand::rngs::OsRng;
+use rusqlite::types::Value;
+
+use darkfi::{
+    tx::{ContractCallLeaf, Transaction, TransactionBuilder},
+    zk::{empty_witnesses, halo2::Field, Proof, ProvingKey, ZkCircuit},
+    zkas::ZkBinary,
+    Error, Result,
+};
+use darkfi_auction_contract::{
+    client::{
+        bid_v1::AuctionBidCallBuilder, claim_v1::AuctionClaimCallBuilder,
+        create_v1::AuctionCreateCallBuilder, reveal_v1::AuctionRevealCallBuilder,
+    },
+    model::{
+        auction_spend_hook, AuctionBidParamsV1, AuctionClaimParamsV1, AuctionCreateParamsV1,
+        AuctionId, AuctionParams, AuctionRecord, AuctionRevealParamsV1,
+    },
+    AuctionFunction, AUCTION_CONTRACT_ZKAS_BID_NS, AUCTION_CONTRACT_ZKAS_CLAIM_NS,
+    AUCTION_CONTRACT_ZKAS_COIN_NS,
+};
+use darkfi_money_contract::{
+    client::{
+        swap_v1::SwapCallBuilder,
+        transfer_v1::{make_transfer_call, TransferCallSecrets},
+        OwnCoin,
+    },
+    model::{Coin, MoneyTransferParamsV1, TokenId},
+    MoneyFunction, MONEY_CONTRACT_ZKAS_BURN_NS_V1, MONEY_CONTRACT_ZKAS_MINT_NS_V1,
+};
+use darkfi_sdk::{
+    crypto::{
+        BaseBlind, Blind, FuncId, Keypair, ScalarBlind, AUCTION_CONTRACT_ID, MONEY_CONTRACT_ID,
+    },
+    dark_tree::DarkTree,
+    pasta::pallas,
+    ContractCall,
+};
+use darkfi_serial::{
+    async_trait, deserialize_async, serialize_async, AsyncEncodable, SerialDecodable,
+    SerialEncodable,
+};
+
+use crate::{
+    convert_named_params,
+    error::{WalletDbError, WalletDbResult},
+    money::{MONEY_COINS_COL_COIN, MONEY_COINS_TABLE},
+    Drk,
+};
+
+// Wallet SQL table constant names. These have to represent the `auction.sql`
+// SQL schema. Table names are prefixed with the contract ID to avoid collisions.
+lazy_static! {
+    pub static ref AUCTION_AUCTIONS_TABLE: String =
+        format!("{}_auction_auctions", AUCTION_CONTRACT_ID.to_string());
+}
+
+// AUCTION_AUCTIONS_TABLE
+pub const AUCTION_AUCTIONS_COL_AUCTION_ID: &str = "auction_id";
+pub const AUCTION_AUCTIONS_COL_RECORD: &str = "record";
+
+#[derive(Debug, Clone, SerialEncodable, SerialDecodable)]
+/// The seller's half of an auction settlement, includes the asset coin swap
+/// half and the proof releasing it from the auction.
+pub struct PartialSettleData {
+    auction_id: AuctionId,
+    params: MoneyTransferParamsV1,
+    proofs: Vec<Proof>,
+    claim_proof: Proof,
+    value_pair: (u64, u64),
+    token_pair: (TokenId, TokenId),
+    value_blinds: Vec<ScalarBlind>,
+    token_blinds: Vec<BaseBlind>,
+}
+
+impl fmt::Display for PartialSettleData {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let s = format!(
+            "Auction ID: {}\n{:#?}\nValue pair: {}:{}\nToken pair: {}:{}\n",
+            self.auction_id,
+            self.params,
+            self.value_pair.0,
+            self.value_pair.1,
+            self.token_pair.0,
+            self.token_pair.1,
+        );
+
+        write!(f, "{}", s)
+    }
+}
+
+impl Drk {
+    /// Initialize wallet with tables for the Auction contract.
+    pub fn initialize_auction(&self) -> WalletDbResult<()> {
+        // Initialize Auction wallet schema. Since table names are prefixed
+        // with the contract ID, we fill it in here.
+        let wallet_schema = include_str!("../auction.sql")
+            .replace("{AUCTION_CONTRACT_ID}", &AUCTION_CONTRACT_ID.to_string());
+        self.wallet.exec_batch_sql(&wallet_schema)?;
+
+        Ok(())
+    }
+
+    /// Reset the auctions records in the wallet.
+    pub fn reset_auction_auctions(&self) -> WalletDbResult<()> {
+        println!("Resetting Auction auctions");
+        let query = format!("DELETE FROM {};", *AUCTION_AUCTIONS_TABLE);
+        self.wallet.exec_sql(&query, &[])?;
+        println!("Successfully reset Auction auctions");
+
+        Ok(())
+    }
+
+    /// Fetch the on-chain record of the given auction from the wallet, if we have seen it.
+    pub async fn get_auction_record(
+        &self,
+        auction_id: &AuctionId,
+    ) -> Result<Option<AuctionRecord>> {
+        let row = match self.wallet.query_single(
+            &AUCTION_AUCTIONS_TABLE,
+            &[AUCTION_AUCTIONS_COL_RECORD],
+            convert_named_params! {(AUCTION_AUCTIONS_COL_AUCTION_ID, serialize_async(auction_id).await)},
+        ) {
+            Ok(r) => r,
+            Err(WalletDbError::RowNotFound) => return Ok(None),
+            Err(e) => {
+                return Err(Error::DatabaseError(format!(
+                    "[get_auction_record] Auction record retrieval failed: {e:?}"
+                )))
+            }
+        };
+
+        let Value::Blob(ref record_bytes) = row[0] else {
+            return Err(Error::ParseFailed(
+                "[get_auction_record] Auction record bytes parsing failed",
+            ))
+        };
+
+        Ok(Some(deserialize_async(record_bytes).await?))
+    }
+
+    /// List all the auctions seen on-chain, along with a flag indicating
+    /// if we have coins locked in them.
+    pub async fn list_auctions(&self) -> Result<Vec<(AuctionId, AuctionRecord, bool)>> {
+        let rows = match self.wallet.query_multiple(
+            &AUCTION_AUCTIONS_TABLE,
+            &[AUCTION_AUCTIONS_COL_AUCTION_ID, AUCTION_AUCTIONS_COL_RECORD],
+            &[],
+        ) {
+            Ok(r) => r,
+            Err(e) => {
+                return Err(Error::DatabaseError(format!(
+                    "[list_auctions] Auctions retrieval failed: {e:?}"
+                )))
+            }
+        };
+
+        let mut ret = Vec::with_capacity(rows.len());
+        for row in rows {
+            let Value::Blob(ref auction_id_bytes) = row[0] else {
+                return Err(Error::ParseFailed("[list_auctions] Auction ID bytes parsing failed"))
+            };
+            let Value::Blob(ref record_bytes) = row[1] else {
+                return Err(Error::ParseFailed(
+                    "[list_auctions] Auction record bytes parsing failed",
+                ))
+            };
+            let auction_id: AuctionId = deserialize_async(auction_id_bytes).await?;
+            let record: AuctionRecord = deserialize_async(record_bytes).await?;
+            let participating = !self.get_locked_coins(&auction_id, &record).await?.is_empty();
+            ret.push((auction_id, record, participating));
+        }
+
+        Ok(ret)
+    }
+
+    /// Store the given auction record in the wallet, and its inverse query into the cache.
+    async fn put_auction_record(
+        &self,
+        auction_id: &AuctionId,
+        record: &AuctionRecord,
+    ) -> Result<()> {
+        let key = serialize_async(auction_id).await;
+
+        // Create its inverse query, restoring the previous record if one existed
+        let inverse = match self.get_auction_record(auction_id).await? {
+            Some(previous) => self.wallet.create_prepared_statement(
+                &format!(
+                    "UPDATE {} SET {} = ?1 WHERE {} = ?2;",
+                    *AUCTION_AUCTIONS_TABLE,
+                    AUCTION_AUCTIONS_COL_RECORD,
+                    AUCTION_AUCTIONS_COL_AUCTION_ID,
+                ),
+                rusqlite::params![serialize_async(&previous).await, key],
+            ),
+            None => self.wallet.create_prepared_statement(
+                &format!(
+                    "DELETE FROM {} WHERE {} = ?1;",
+                    *AUCTION_AUCTIONS_TABLE, AUCTION_AUCTIONS_COL_AUCTION_ID,
+                ),
+                rusqlite::params![key],
+            ),
+        };
+        let inverse = match inverse {
+            Ok(q) => q,
+            Err(e) => {
+                return Err(Error::DatabaseError(format!(
+                    "[put_auction_record] Creating auction record inverse query failed: {e:?}"
+                )))
+            }
+        };
+
+        let query = format!(
+            "INSERT OR REPLACE INTO {} ({}, {}) VALUES (?1, ?2);",
+            *AUCTION_AUCTIONS_TABLE, AUCTION_AUCTIONS_COL_AUCTION_ID, AUCTION_AUCTIONS_COL_RECORD,
+        );
+        if let Err(e) =
+            self.wallet.exec_sql(&query, rusqlite::params![key, serialize_async(record).await])
+        {
+            return Err(Error::DatabaseError(format!(
+                "[put_auction_record] Inserting auction record failed: {e:?}"
+            )))
+        }
+
+        // Store its inverse
+        if let Err(e) = self.wallet.cache_inverse(inverse) {
+            return Err(Error::DatabaseError(format!(
+                "[put_auction_record] Inserting inverse query into cache failed: {e:?}"
+            )))
+        }
+
+        Ok(())
+    }
+
+    /// Auxiliary function to check if the given coin exists in our wallet.
+    async fn is_own_coin(&self, coin: &Coin) -> Result<bool> {
+        match self.wallet.query_single(
+            &MONEY_COINS_TABLE,
+            &[MONEY_COINS_COL_COIN],
+            convert_named_params! {(MONEY_COINS_COL_COIN, serialize_async(coin).await)},
+        ) {
+            Ok(_) => Ok(true),
+            Err(WalletDbError::RowNotFound) => Ok(false),
+            Err(e) => {
+                Err(Error::DatabaseError(format!("[is_own_coin] Coin retrieval failed: {e:?}")))
+            }
+        }
+    }
+
+    /// Append data related to Auction contract transactions into the wallet
+    /// database, and store their inverse queries into the cache. The contract
+    /// state is public, so we keep track of all the auctions we see, in order
+    /// to be able to bid in them. Returns a flag indicating if the provided
+    /// call refers to our own wallet.
+    pub async fn apply_tx_auction_data(&self, data: &[u8]) -> Result<bool> {
+        // Here we mirror the state transitions of the contract. The `Money`
+        // calls locking or spending the coins have already been applied, so
+        // we can check if the coins are ours.
+        match AuctionFunction::try_from(data[0])? {
+            AuctionFunction::CreateV1 => {
+                let params: AuctionCreateParamsV1 = deserialize_async(&data[1..]).await?;
+                let auction_id = AuctionId::derive(&params.params);
+                let record = AuctionRecord {
+                    params: params.params,
+                    asset_coin: params.asset_coin,
+                    winner: None,
+                    highest_bid: 0,
+                    settled: false,
+                };
+                self.put_auction_record(&auction_id, &record).await?;
+
+                self.is_own_coin(&params.asset_coin).await
+            }
+
+            AuctionFunction::BidV1 => {
+                let params: AuctionBidParamsV1 = deserialize_async(&data[1..]).await?;
+                self.is_own_coin(&params.coin).await
+            }
+
+            AuctionFunction::RevealV1 => {
+                let params: AuctionRevealParamsV1 = deserialize_async(&data[1..]).await?;
+                let Some(mut record) = self.get_auction_record(&params.auction_id).await? else {
+                    return Ok(false)
+                };
+
+                if record.winner.is_none() || params.value > record.highest_bid {
+                    record.winner = Some(params.coin);
+                    record.highest_bid = params.value;
+                    self.put_auction_record(&params.auction_id, &record).await?;
+                }
+
+                self.is_own_coin(&params.coin).await
+            }
+
+            AuctionFunction::ClaimV1 => {
+                let params: AuctionClaimParamsV1 = deserialize_async(&data[1..]).await?;
+                let Some(mut record) = self.get_auction_record(&params.auction_id).await? else {
+                    return Ok(false)
+                };
+
+                // Only a claim spending both the asset and the winning bid settles the auction
+                if let Some(winner) = record.winner {
+                    if params.coins.contains(&record.asset_coin) && params.coins.contains(&winner) {
+                        record.settled = true;
+                        self.put_auction_record(&params.auction_id, &record).await?;
+                    }
+                }
+
+                for coin in &params.coins {
+                    if self.is_own_coin(coin).await? {
+                        return Ok(true)
+                    }
+                }
+
+                Ok(false)
+            }
+        }
+    }
+
+    /// Fetch our unspent coins locked in the given auction.
+    async fn get_locked_coins(
+        &self,
+        auction_id: &AuctionId,
+        record: &AuctionRecord,
+    ) -> Result<Vec<OwnCoin>> {
+        let spend_hook = auction_spend_hook(*AUCTION_CONTRACT_ID);
+        let mut coins = self
+            .get_contract_token_coins(
+                &record.params.asset_token_id,
+                &spend_hook,
+                &auction_id.inner(),
+            )
+            .await?;
+
+        if record.params.payment_token_id != record.params.asset_token_id {
+            coins.extend(
+                self.get_contract_token_coins(
+                    &record.params.payment_token_id,
+                    &spend_hook,
+                    &auction_id.inner(),
+                )
+                .await?,
+            );
+        }
+
+        Ok(coins)
+    }
+
+    /// Auxiliary function to fetch an auction record, erroring if we haven't seen it.
+    async fn get_known_auction(&self, auction_id: &AuctionId) -> Result<AuctionRecord> {
+        match self.get_auction_record(auction_id).await? {
+            Some(record) => Ok(record),
+            None => Err(Error::Custom(format!("Auction {auction_id} was not found"))),
+        }
+    }
+
+    /// Auxiliary function to grab the last scanned block height.
+    fn auction_height(&self) -> Result<u32> {
+        match self.get_last_scanned_block() {
+            Ok((height, _)) => Ok(height),
+            Err(e) => Err(Error::DatabaseError(format!(
+                "[auction_height] Retrieving last scanned block failed: {e:?}"
+            ))),
+        }
+    }
+
+    /// Auxiliary function to build the proving key of the given Auction circuit.
+    async fn auction_circuit_pk(&self, namespace: &str) -> Result<(ZkBinary, ProvingKey)> {
+        let zkas_bins = self.lookup_zkas(&AUCTION_CONTRACT_ID).await?;
+
+        let Some(zkbin) = zkas_bins.iter().find(|x| x.0 == namespace) else {
+            return Err(Error::Custom(format!("{namespace} circuit not found")))
+        };
+
+        let zkbin = ZkBinary::decode(&zkbin.1)?;
+        let circuit = ZkCircuit::new(empty_witnesses(&zkbin)?, &zkbin);
+        let pk = ProvingKey::build(zkbin.k, &circuit);
+
+        Ok((zkbin, pk))
+    }
+
+    /// Auxiliary function to build the `Money` Mint and Burn circuits proving keys.
+    async fn auction_money_pks(&self) -> Result<(ZkBinary, ProvingKey, ZkBinary, ProvingKey)> {
+        let zkas_bins = self.lookup_zkas(&MONEY_CONTRACT_ID).await?;
+
+        let Some(mint_zkbin) = zkas_bins.iter().find(|x| x.0 == MONEY_CONTRACT_ZKAS_MINT_NS_V1)
+        else {
+            return Err(Error::Custom("Mint circuit not found".to_string()))
+        };
+
+        let Some(burn_zkbin) = zkas_bins.iter().find(|x| x.0 == MONEY_CONTRACT_ZKAS_BURN_NS_V1)
+        else {
+            return Err(Error::Custom("Burn circuit not found".to_string()))
+        };
+
+        let mint_zkbin = ZkBinary::decode(&mint_zkbin.1)?;
+        let burn_zkbin = ZkBinary::decode(&burn_zkbin.1)?;
+
+        let mint_circuit = ZkCircuit::new(empty_witnesses(&mint_zkbin)?, &mint_zkbin);
+        let burn_circuit = ZkCircuit::new(empty_witnesses(&burn_zkbin)?, &burn_zkbin);
+
+        let mint_pk = ProvingKey::build(mint_zkbin.k, &mint_circuit);
+        let burn_pk = ProvingKey::build(burn_zkbin.k, &burn_circuit);
+
+        Ok((mint_zkbin, mint_pk, burn_zkbin, burn_pk))
+    }
+
+    /// Build a `Money::Transfer` call locking the given value into a coin owned
+    /// by our default address, carrying the auction spend hook and `AuctionId`.
+    /// Returns the call, along with its signature secrets and the locked coin.
+    async fn auction_lock(
+        &self,
+        auction_id: &AuctionId,
+        value: u64,
+        token_id: TokenId,
+    ) -> Result<(ContractCallLeaf, TransferCallSecrets, OwnCoin)> {
+        let owncoins = self.get_token_coins(&token_id).await?;
+        if owncoins.is_empty() {
+            return Err(Error::Custom(format!(
+                "Did not find any unspent coins with token ID: {token_id}"
+            )))
+        }
+
+        let keypair = Keypair::new(self.default_secret().await?);
+        let tree = self.get_money_tree().await?;
+        let (mint_zkbin, mint_pk, burn_zkbin, burn_pk) = self.auction_money_pks().await?;
+
+        let (params, secrets, _) = make_transfer_call(
+            keypair,
+            keypair.public,
+            None,
+            value,
+            token_id,
+            owncoins,
+            tree,
+            Some(auction_spend_hook(*AUCTION_CONTRACT_ID)),
+            Some(auction_id.inner()),
+            mint_zkbin,
+            mint_pk,
+            burn_zkbin,
+            burn_pk,
+            false,
+        )?;
+
+        // The locked coin is always the first output, followed by the change
+        let mut locked_coin = secrets.minted_coins(&params)[0].clone();
+        locked_coin.secret = keypair.secret;
+
+        let mut data = vec![MoneyFunction::TransferV1 as u8];
+        params.encode_async(&mut data).await?;
+        let call = ContractCall { contract_id: *MONEY_CONTRACT_ID, data };
+        let leaf = ContractCallLeaf { call, proofs: secrets.proofs.clone() };
+
+        Ok((leaf, secrets, locked_coin))
+    }
+
+    /// Create an auction creation transaction, without a fee call, locking
+    /// the auctioned asset. The auction phases are given as durations in
+    /// blocks, starting from the last scanned block height.
+    #[allow(clippy::too_many_arguments)]
+    pub async fn auction_create(
+        &self,
+        asset_amount: u64,
+        asset_token_id: TokenId,
+        payment_token_id: TokenId,
+        reserve_price: u64,
+        commit_duration: u32,
+        reveal_duration: u32,
+    ) -> Result<(AuctionId, Transaction)> {
+        let height = self.auction_height()?;
+        let commit_end = height + commit_duration;
+        let reveal_end = commit_end + reveal_duration;
+        let params = AuctionParams {
+            asset_token_id,
+            asset_amount,
+            payment_token_id,
+            reserve_price,
+            commit_end,
+            reveal_end,
+            nonce: pallas::Base::random(&mut OsRng),
+        };
+        if !params.is_valid() {
+            return Err(Error::Custom("Invalid auction parameters".to_string()))
+        }
+        let auction_id = AuctionId::derive(&params);
+
+        // Lock the asset
+        let (xfer_leaf, xfer_secrets, locked_coin) =
+            self.auction_lock(&auction_id, asset_amount, asset_token_id).await?;
+
+        // Create the contract call
+        let (coin_zkbin, coin_pk) = self.auction_circuit_pk(AUCTION_CONTRACT_ZKAS_COIN_NS).await?;
+        let create_call = AuctionCreateCallBuilder {
+            params,
+            asset_coin: locked_coin,
+            coin_zkbin: &coin_zkbin,
+            coin_pk: &coin_pk,
+        };
+        let create_debris = create_call.build()?;
+
+        // Encode the call
+        let mut data = vec![AuctionFunction::CreateV1 as u8];
+        create_debris.params.encode_async(&mut data).await?;
+        let call = ContractCall { contract_id: *AUCTION_CONTRACT_ID, data };
+        let mut tx_builder = TransactionBuilder::new(xfer_leaf, vec![])?;
+        tx_builder.append(ContractCallLeaf { call, proofs: create_debris.proofs }, vec![])?;
+
+        let mut tx = tx_builder.build()?;
+        let sigs = tx.create_sigs(&xfer_secrets.signature_secrets)?;
+        tx.signatures = vec![sigs, vec![]];
+
+        Ok((auction_id, tx))
+    }
+
+    /// Create a sealed bid transaction, without a fee call, locking the bid value.
+    pub async fn auction_bid(&self, auction_id: &AuctionId, value: u64) -> Result<Transaction> {
+        let record = self.get_known_auction(auction_id).await?;
+        if self.auction_height()? >= record.params.commit_end {
+            return Err(Error::Custom(format!("Auction {auction_id} commit phase is over")))
+        }
+
+        if value < record.params.reserve_price {
+            return Err(Error::Custom(format!(
+                "Bid is below the auction reserve price: {}",
+                record.params.reserve_price
+            )))
+        }
+
+        // Lock the bid
+        let (xfer_leaf, xfer_secrets, locked_coin) =
+            self.auction_lock(auction_id, value, record.params.payment_token_id).await?;
+
+        // Create the contract call
+        let (bid_zkbin, bid_pk) = self.auction_circuit_pk(AUCTION_CONTRACT_ZKAS_BID_NS).await?;
+        let bid_call = AuctionBidCallBuilder {
+            auction_id: *auction_id,
+            coin: locked_coin,
+            reserve_price: record.params.reserve_price,
+            bid_zkbin: &bid_zkbin,
+            bid_pk: &bid_pk,
+        };
+        let bid_debris = bid_call.build()?;
+
+        // Encode the call
+        let mut data = vec![AuctionFunction::BidV1 as u8];
+        bid_debris.params.encode_async(&mut data).await?;
+        let call = ContractCall { contract_id: *AUCTION_CONTRACT_ID, data };
+        let mut tx_builder = TransactionBuilder::new(xfer_leaf, vec![])?;
+        tx_builder.append(ContractCallLeaf { call, proofs: bid_debris.proofs }, vec![])?;
+
+        let mut tx = tx_builder.build()?;
+        let sigs = tx.create_sigs(&xfer_secrets.signature_secrets)?;
+        tx.signatures = vec![sigs, vec![]];
+
+        Ok(tx)
+    }
+
+    /// Create a bid reveal transaction, without a fee call, for our
+    /// locked bid in the given auction.
+    pub async fn auction_reveal(&self, auction_id: &AuctionId) -> Result<Transaction> {
+        let record = self.get_known_auction(auction_id).await?;
+        let height = self.auction_height()?;
+        if height < record.params.commit_end || height >= record.params.reveal_end {
+            return Err(Error::Custom(format!("Auction {auction_id} is not in its reveal phase")))
+        }
+
+        let mut bids = self
+            .get_contract_token_coins(
+                &record.params.payment_token_id,
+                &auction_spend_hook(*AUCTION_CONTRACT_ID),
+                &auction_id.inner(),
+            )
+            .await?;
+        bids.retain(|c| c.coin != record.asset_coin);
+        let Some(bid_coin) = bids.pop() else {
+            return Err(Error::Custom(format!("No bid found in auction {auction_id}")))
+        };
+
+        // Create the contract call
+        let (coin_zkbin, coin_pk) = self.auction_circuit_pk(AUCTION_CONTRACT_ZKAS_COIN_NS).await?;
+        let reveal_call = AuctionRevealCallBuilder {
+            auction_id: *auction_id,
+            coin: bid_coin,
+            coin_zkbin: &coin_zkbin,
+            coin_pk: &coin_pk,
+        };
+        let reveal_debris = reveal_call.build()?;
+
+        // Encode the call
+        let mut data = vec![AuctionFunction::RevealV1 as u8];
+        reveal_debris.params.encode_async(&mut data).await?;
+        let call = ContractCall { contract_id: *AUCTION_CONTRACT_ID, data };
+        let mut tx_builder = TransactionBuilder::new(
+            ContractCallLeaf { call, proofs: reveal_debris.proofs },
+            vec![],
+        )?;
+
+        // Revealing requires no signatures
+        let mut tx = tx_builder.build()?;
+        tx.signatures = vec![vec![]];
+
+        Ok(tx)
+    }
+
+    /// Create a refund transaction, without a fee call, returning our
+    /// locked coins of the given auction back to our default address.
+    pub async fn auction_refund(&self, auction_id: &AuctionId) -> Result<Transaction> {
+        let record = self.get_known_auction(auction_id).await?;
+        if self.auction_height()? < record.params.reveal_end {
+            return Err(Error::Custom(format!("Auction {auction_id} has not ended yet")))
+        }
+
+        // The asset and the winning bid can only be unlocked by settling
+        let mut locked_coins = self.get_locked_coins(auction_id, &record).await?;
+        if let Some(winner) = record.winner {
+            locked_coins.retain(|c| c.coin != record.asset_coin && c.coin != winner);
+        }
+        if locked_coins.is_empty() {
+            return Err(Error::Custom(format!("No refundable coins found in auction {auction_id}")))
+        }
+
+        // A transfer can only spend coins of a single token
+        let token_id = locked_coins[0].note.token_id;
+        locked_coins.retain(|c| c.note.token_id == token_id);
+        let value = locked_coins.iter().map(|c| c.note.value).sum();
+
+        let keypair = Keypair::new(self.default_secret().await?);
+        let tree = self.get_money_tree().await?;
+        let (mint_zkbin, mint_pk, burn_zkbin, burn_pk) = self.auction_money_pks().await?;
+
+        let (xfer_params, xfer_secrets, spent_coins) = make_transfer_call(
+            keypair,
+            keypair.public,
+            None,
+            value,
+            token_id,
+            locked_coins,
+            tree,
+            None,
+            None,
+            mint_zkbin,
+            mint_pk,
+            burn_zkbin,
+            burn_pk,
+            false,
+        )?;
+
+        // Claim proofs follow the transfer inputs order
+        let (claim_zkbin, claim_pk) =
+            self.auction_circuit_pk(AUCTION_CONTRACT_ZKAS_CLAIM_NS).await?;
+        let claim_call = AuctionClaimCallBuilder {
+            auction_id: *auction_id,
+            coins: spent_coins,
+            claim_zkbin: &claim_zkbin,
+            claim_pk: &claim_pk,
+        };
+        let claim_debris = claim_call.build()?;
+
+        // Encode the calls
+        let mut data = vec![AuctionFunction::ClaimV1 as u8];
+        claim_debris.params.encode_async(&mut data).await?;
+        let claim_call = ContractCall { contract_id: *AUCTION_CONTRACT_ID, data };
+
+        let mut data = vec![MoneyFunction::TransferV1 as u8];
+        xfer_params.encode_async(&mut data).await?;
+        let xfer_call = ContractCall { contract_id: *MONEY_CONTRACT_ID, data };
+
+        // The claim is the parent of the refunding transfer
+        let mut tx_builder = TransactionBuilder::new(
+            ContractCallLeaf { call: claim_call, proofs: claim_debris.proofs },
+            vec![DarkTree::new(
+                ContractCallLeaf { call: xfer_call, proofs: xfer_secrets.proofs },
+                vec![],
+                None,
+                None,
+            )],
+        )?;
+
+        let mut tx = tx_builder.build()?;
+        let sigs = tx.create_sigs(&xfer_secrets.signature_secrets)?;
+        tx.signatures = vec![sigs, vec![]];
+
+        Ok(tx)
+    }
+
+    /// Initialize the seller's half of an auction settlement, swapping
+    /// our locked asset coin with the winning bid.
+    pub async fn auction_settle_init(&self, auction_id: &AuctionId) -> Result<PartialSettleData> {
+        let record = self.get_known_auction(auction_id).await?;
+        if self.auction_height()? < record.params.reveal_end {
+            return Err(Error::Custom(format!("Auction {auction_id} has not ended yet")))
+        }
+
+        if record.settled {
+            return Err(Error::Custom(format!("Auction {auction_id} is already settled")))
+        }
+
+        if record.winner.is_none() {
+            return Err(Error::Custom(format!("Auction {auction_id} has no winner")))
+        }
+
+        let locked_coins = self.get_locked_coins(auction_id, &record).await?;
+        let Some(asset_coin) = locked_coins.into_iter().find(|c| c.coin == record.asset_coin)
+        else {
+            return Err(Error::Custom(format!(
+                "Auction {auction_id} asset is not owned by this wallet"
+            )))
+        };
+
+        let value_pair = (asset_coin.note.value, record.highest_bid);
+        let token_pair = (asset_coin.note.token_id, record.params.payment_token_id);
+
+        let address = self.default_address().await?;
+        let tree = self.get_money_tree().await?;
+        let (mint_zkbin, mint_pk, burn_zkbin, burn_pk) = self.auction_money_pks().await?;
+
+        // Since we're creating the first half, we generate the blinds.
+        let value_blinds = [Blind::random(&mut OsRng), Blind::random(&mut OsRng)];
+        let token_blinds = [Blind::random(&mut OsRng), Blind::random(&mut OsRng)];
+
+        // Received coins are not locked anymore
+        let builder = SwapCallBuilder {
+            pubkey: address,
+            value_send: value_pair.0,
+            token_id_send: token_pair.0,
+            value_recv: value_pair.1,
+            token_id_recv: token_pair.1,
+            user_data_blind_send: Blind::random(&mut OsRng),
+            spend_hook_recv: FuncId::none(),
+            user_data_recv: pallas::Base::ZERO,
+            value_blinds,
+            token_blinds,
+            coin: asset_coin.clone(),
+            tree,
+            mint_zkbin,
+            mint_pk,
+            burn_zkbin,
+            burn_pk,
+        };
+        let debris = builder.build()?;
+
+        // Prove we can release the asset coin from the auction
+        let (claim_zkbin, claim_pk) =
+            self.auction_circuit_pk(AUCTION_CONTRACT_ZKAS_CLAIM_NS).await?;
+        let claim_call = AuctionClaimCallBuilder {
+            auction_id: *auction_id,
+            coins: vec![asset_coin],
+            claim_zkbin: &claim_zkbin,
+            claim_pk: &claim_pk,
+        };
+        let claim_debris = claim_call.build()?;
+
+        Ok(PartialSettleData {
+            auction_id: *auction_id,
+            params: debris.params,
+            proofs: debris.proofs,
+            claim_proof: claim_debris.proofs[0].clone(),
+            value_pair,
+            token_pair,
+            value_blinds: value_blinds.to_vec(),
+            token_blinds: token_blinds.to_vec(),
+        })
+    }
+
+    /// Create the full settlement transaction, without a fee call, by making
+    /// the winner's half of the swap and joining it with given seller's half.
+    /// The seller has to sign the transaction afterwards, using `drk otc sign`.
+    pub async fn auction_settle_join(&self, partial: PartialSettleData) -> Result<Transaction> {
+        let auction_id = partial.auction_id;
+        let record = self.get_known_auction(&auction_id).await?;
+        let Some(winner) = record.winner else {
+            return Err(Error::Custom(format!("Auction {auction_id} has no winner")))
+        };
+
+        if partial.value_pair != (record.params.asset_amount, record.highest_bid) ||
+            partial.token_pair != (record.params.asset_token_id, record.params.payment_token_id)
+        {
+            return Err(Error::Custom("Settlement data doesn't match the auction".to_string()))
+        }
+
+        let locked_coins = self.get_locked_coins(&auction_id, &record).await?;
+        let Some(bid_coin) = locked_coins.into_iter().find(|c| c.coin == winner) else {
+            return Err(Error::Custom(format!(
+                "Auction {auction_id} winning bid is not owned by this wallet"
+            )))
+        };
+
+        let address = self.default_address().await?;
+        let tree = self.get_money_tree().await?;
+        let (mint_zkbin, mint_pk, burn_zkbin, burn_pk) = self.auction_money_pks().await?;
+
+        let builder = SwapCallBuilder {
+            pubkey: address,
+            value_send: partial.value_pair.1,
+            token_id_send: partial.token_pair.1,
+            value_recv: partial.value_pair.0,
+            token_id_recv: partial.token_pair.0,
+            user_data_blind_send: Blind::random(&mut OsRng),
+            spend_hook_recv: FuncId::none(),
+            user_data_recv: pallas::Base::ZERO,
+            value_blinds: [partial.value_blinds[1], partial.value_blinds[0]],
+            token_blinds: [partial.token_blinds[1], partial.token_blinds[0]],
+            coin: bid_coin.clone(),
+            tree,
+            mint_zkbin,
+            mint_pk,
+            burn_zkbin,
+            burn_pk,
+        };
+        let debris = builder.build()?;
+
+        // Prove we can release the winning bid coin from the auction
+        let (claim_zkbin, claim_pk) =
+            self.auction_circuit_pk(AUCTION_CONTRACT_ZKAS_CLAIM_NS).await?;
+        let claim_call = AuctionClaimCallBuilder {
+            auction_id,
+            coins: vec![bid_coin],
+            claim_zkbin: &claim_zkbin,
+            claim_pk: &claim_pk,
+        };
+        let claim_debris = claim_call.build()?;
+
+        // Build the full calls, with the seller's half first
+        let swap_params = MoneyTransferParamsV1 {
+            inputs: vec![partial.params.inputs[0].clone(), debris.params.inputs[0].clone()],
+            outputs: vec![partial.params.outputs[0].clone(), debris.params.outputs[0].clone()],
+        };
+        let swap_proofs = vec![
+            partial.proofs[0].clone(),
+            debris.proofs[0].clone(),
+            partial.proofs[1].clone(),
+            debris.proofs[1].clone(),
+        ];
+        let claim_params =
+            AuctionClaimParamsV1 { auction_id, coins: vec![record.asset_coin, winner] };
+        let claim_proofs = vec![partial.claim_proof, claim_debris.proofs[0].clone()];
+
+        let mut data = vec![AuctionFunction::ClaimV1 as u8];
+        claim_params.encode_async(&mut data).await?;
+        let claim_call = ContractCall { contract_id: *AUCTION_CONTRACT_ID, data };
+
+        let mut data = vec![MoneyFunction::OtcSwapV1 as u8];
+        swap_params.encode_async(&mut data).await?;
+        let swap_call = ContractCall { contract_id: *MONEY_CONTRACT_ID, data };
+
+        let mut tx_builder = TransactionBuilder::new(
+            ContractCallLeaf { call: claim_call, proofs: claim_proofs },
+            vec![DarkTree::new(
+                ContractCallLeaf { call: swap_call, proofs: swap_proofs },
+                vec![],
+                None,
+                None,
+            )],
+        )?;
+
+        // Sign our half of the swap, the seller signs theirs afterwards
+        let mut tx = tx_builder.build()?;
+        let sigs = tx.create_sigs(&[debris.signature_secret])?;
+        tx.signatures = vec![sigs, vec![]];
+
+        Ok(tx)
+    }
+}
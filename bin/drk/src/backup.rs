@@ -0,0 +1,257 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2025 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use darkfi::{rpc::util::JsonValue, util::encoding::base64, Error, Result};
+use darkfi_money_contract::MONEY_CONTRACT_COIN_ROOTS_TREE;
+use darkfi_sdk::crypto::MONEY_CONTRACT_ID;
+use darkfi_serial::serialize_async;
+use rusqlite::types::Value;
+
+use crate::{doctor::verify_coin_witness, Drk};
+
+/// Filename prefix used for wallet backups
+const BACKUP_PREFIX: &str = "wallet-";
+
+/// Filename suffix used for wallet backups
+const BACKUP_SUFFIX: &str = ".db";
+
+/// Auxiliary function to grab the current UNIX timestamp in seconds.
+fn unix_now() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0)
+}
+
+/// Auxiliary function to escape a string for use inside an SQL literal.
+fn sql_escape(s: &str) -> String {
+    s.replace('\'', "''")
+}
+
+/// List the wallet backups found in given directory, sorted by their
+/// creation timestamp, oldest first.
+pub fn list_backups(backup_dir: &Path) -> Result<Vec<(u64, PathBuf)>> {
+    let mut backups = vec![];
+    if !backup_dir.exists() {
+        return Ok(backups)
+    }
+
+    for entry in fs::read_dir(backup_dir)? {
+        let path = entry?.path();
+        let Some(name) = path.file_name().and_then(|n| n.to_str()) else { continue };
+        let Some(ts) = name.strip_prefix(BACKUP_PREFIX).and_then(|n| n.strip_suffix(BACKUP_SUFFIX))
+        else {
+            continue
+        };
+        let Ok(ts) = ts.parse::<u64>() else { continue };
+        backups.push((ts, path));
+    }
+
+    backups.sort_by_key(|(ts, _)| *ts);
+    Ok(backups)
+}
+
+/// Check if a new backup is due, given the interval in hours between
+/// scheduled backups.
+pub fn backup_due(backup_dir: &Path, interval: u64) -> Result<bool> {
+    let backups = list_backups(backup_dir)?;
+    let Some((last, _)) = backups.last() else { return Ok(true) };
+    Ok(unix_now().saturating_sub(*last) >= interval * 3600)
+}
+
+/// Remove the oldest wallet backups in given directory, keeping only
+/// the `copies` most recent ones. The most recent backup is always
+/// kept, even if `copies` is zero.
+pub fn rotate_backups(backup_dir: &Path, copies: usize) -> Result<()> {
+    let copies = copies.max(1);
+    let backups = list_backups(backup_dir)?;
+    if backups.len() <= copies {
+        return Ok(())
+    }
+
+    for (_, path) in &backups[..backups.len() - copies] {
+        fs::remove_file(path)?;
+    }
+
+    Ok(())
+}
+
+impl Drk {
+    /// Export an encrypted copy of the wallet database into given directory,
+    /// using the provided password, and rotate old backups so at most `copies`
+    /// of them are kept. Returns the path of the new backup.
+    pub fn backup_wallet(
+        &self,
+        backup_dir: &Path,
+        password: &str,
+        copies: usize,
+    ) -> Result<PathBuf> {
+        fs::create_dir_all(backup_dir)?;
+
+        // Never overwrite an existing backup, in case we get called
+        // more than once within the same second.
+        let mut ts = unix_now();
+        let mut backup_path = backup_dir.join(format!("{BACKUP_PREFIX}{ts}{BACKUP_SUFFIX}"));
+        while backup_path.exists() {
+            ts += 1;
+            backup_path = backup_dir.join(format!("{BACKUP_PREFIX}{ts}{BACKUP_SUFFIX}"));
+        }
+
+        let Some(path) = backup_path.to_str() else {
+            return Err(Error::Custom(format!("Invalid backup path: {backup_path:?}")))
+        };
+
+        // SQLCipher exports the full database, encrypting the attached copy
+        // with the provided key.
+        let query = format!(
+            "ATTACH DATABASE '{}' AS backup KEY '{}'; SELECT sqlcipher_export('backup'); DETACH DATABASE backup;",
+            sql_escape(path),
+            sql_escape(password),
+        );
+        if let Err(e) = self.wallet.exec_batch_sql(&query) {
+            return Err(Error::DatabaseError(format!("[backup_wallet] Wallet export failed: {e:?}")))
+        }
+
+        rotate_backups(backup_dir, copies)?;
+
+        Ok(backup_path)
+    }
+
+    /// Verify the integrity of the wallet database. This runs SQLite's
+    /// integrity check, verifies that every unspent coin's Merkle witness
+    /// resolves to the wallet's Money Merkle tree root, and checks the
+    /// tree root and last scanned block against darkfid. Returns a list
+    /// of the problems found, which is empty if the wallet is consistent.
+    pub async fn verify_wallet(&self) -> Result<Vec<String>> {
+        let mut problems = vec![];
+
+        // Database integrity
+        match self.wallet.query_custom("PRAGMA integrity_check;", &[]) {
+            Ok(rows) => {
+                for row in rows {
+                    let Some(Value::Text(msg)) = row.first() else { continue };
+                    if msg != "ok" {
+                        problems.push(format!("Database integrity: {msg}"));
+                    }
+                }
+            }
+            Err(e) => problems.push(format!("Database integrity check failed: {e:?}")),
+        }
+
+        // Merkle witness consistency
+        let tree = self.get_money_tree().await?;
+        let Some(root) = tree.root(0) else {
+            problems.push(String::from("Money Merkle tree has no root"));
+            return Ok(problems)
+        };
+
+        for (coin, _, _) in self.get_coins(false).await? {
+            match verify_coin_witness(&tree, &root, &coin) {
+                Some(true) => {}
+                Some(false) => problems.push(format!(
+                    "Coin {:?} Merkle witness does not match the tree root",
+                    coin.coin
+                )),
+                None => problems.push(format!("Coin {:?} has no Merkle witness", coin.coin)),
+            }
+        }
+
+        // Consistency against darkfid
+        let params = JsonValue::Array(vec![
+            JsonValue::String(MONEY_CONTRACT_ID.to_string()),
+            JsonValue::String(MONEY_CONTRACT_COIN_ROOTS_TREE.to_string()),
+            JsonValue::String(base64::encode(&serialize_async(&root).await)),
+        ]);
+        let rep = self.darkfid_daemon_request("blockchain.get_contract_state_key", &params).await?;
+        if rep.is_null() {
+            problems.push(format!("Money Merkle tree root {root:?} is unknown to darkfid"));
+        }
+
+        if let Ok((height, hash)) = self.get_last_scanned_block() {
+            let block = self.get_block_by_height(height).await?;
+            let block_hash = block.hash().to_string();
+            if block_hash != hash {
+                problems.push(format!(
+                    "Last scanned block {height} hash {hash} does not match darkfid's {block_hash}"
+                ));
+            }
+        }
+
+        Ok(problems)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn backups_rotation() {
+        let backup_dir = std::env::temp_dir().join(format!("drk_backups_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&backup_dir);
+        fs::create_dir_all(&backup_dir).unwrap();
+
+        // No backups yet, so one is due
+        assert!(list_backups(&backup_dir).unwrap().is_empty());
+        assert!(backup_due(&backup_dir, 24).unwrap());
+
+        // Create some backups along with unrelated files
+        for ts in [5, 1, 3, 2, 4] {
+            fs::write(backup_dir.join(format!("{BACKUP_PREFIX}{ts}{BACKUP_SUFFIX}")), b"").unwrap();
+        }
+        fs::write(backup_dir.join("wallet-foo.db"), b"").unwrap();
+        fs::write(backup_dir.join("notes.txt"), b"").unwrap();
+
+        // Backups are listed oldest first, skipping unrelated files
+        let backups: Vec<u64> =
+            list_backups(&backup_dir).unwrap().into_iter().map(|(ts, _)| ts).collect();
+        assert_eq!(backups, vec![1, 2, 3, 4, 5]);
+
+        // The last backup is from 1970, so a new one is due
+        assert!(backup_due(&backup_dir, 24).unwrap());
+
+        // Rotation keeps the most recent ones
+        rotate_backups(&backup_dir, 3).unwrap();
+        let backups: Vec<u64> =
+            list_backups(&backup_dir).unwrap().into_iter().map(|(ts, _)| ts).collect();
+        assert_eq!(backups, vec![3, 4, 5]);
+
+        // Keeping more copies than we have is a no-op
+        rotate_backups(&backup_dir, 10).unwrap();
+        assert_eq!(list_backups(&backup_dir).unwrap().len(), 3);
+
+        // The most recent backup survives even with zero copies
+        rotate_backups(&backup_dir, 0).unwrap();
+        let backups = list_backups(&backup_dir).unwrap();
+        assert_eq!(backups.len(), 1);
+        assert_eq!(backups[0].0, 5);
+
+        // Unrelated files are left alone
+        assert!(backup_dir.join("wallet-foo.db").exists());
+        assert!(backup_dir.join("notes.txt").exists());
+
+        // A fresh backup means none is due
+        let now = unix_now();
+        fs::write(backup_dir.join(format!("{BACKUP_PREFIX}{now}{BACKUP_SUFFIX}")), b"").unwrap();
+        assert!(!backup_due(&backup_dir, 24).unwrap());
+
+        fs::remove_dir_all(&backup_dir).unwrap();
+    }
+}
@@ -31,7 +31,13 @@ use darkfi::{
     util::{encoding::base64, parse::decode_base10},
     Error, Result,
 };
+use darkfi_darkname_contract::model::NameTarget;
 use darkfi_money_contract::model::TokenId;
+use darkfi_oracle_contract::model::FeedKind;
+use darkfi_sdk::{
+    crypto::{ContractId, PublicKey},
+    pasta::{group::ff::PrimeField, pallas},
+};
 use darkfi_serial::deserialize_async;
 
 use crate::{money::BALANCE_BASE10_DECIMALS, Drk};
@@ -92,6 +98,58 @@ pub async fn parse_token_pair(drk: &Drk, s: &str) -> Result<(TokenId, TokenId)>
     Ok((tok0.unwrap(), tok1.unwrap()))
 }
 
+/// Auxiliary function to parse a name target from provided address and contract ID.
+pub fn parse_name_target(address: Option<String>, contract: Option<String>) -> Result<NameTarget> {
+    let public_key = match address {
+        Some(a) => Some(PublicKey::from_str(&a)?),
+        None => None,
+    };
+
+    let contract_id = match contract {
+        Some(c) => Some(ContractId::from_str(&c)?),
+        None => None,
+    };
+
+    let target = NameTarget { public_key, contract_id };
+    if target.is_empty() {
+        return Err(Error::Custom("Name target needs an address or a contract ID".to_string()))
+    }
+
+    Ok(target)
+}
+
+/// Auxiliary function to parse a base58 encoded name owner commitment.
+pub fn parse_name_owner(s: &str) -> Result<pallas::Base> {
+    let bytes: [u8; 32] = match bs58::decode(s).into_vec()?.try_into() {
+        Ok(b) => b,
+        Err(_) => return Err(Error::ParseFailed("Invalid name owner commitment length")),
+    };
+
+    match pallas::Base::from_repr(bytes).into() {
+        Some(v) => Ok(v),
+        None => Err(Error::ParseFailed("Invalid name owner commitment")),
+    }
+}
+
+/// Auxiliary function to parse an oracle feed kind.
+pub fn parse_feed_kind(s: &str) -> Result<FeedKind> {
+    match s.to_lowercase().as_str() {
+        "price" => Ok(FeedKind::Price),
+        "randomness" => Ok(FeedKind::Randomness),
+        _ => Err(Error::Custom(format!("Invalid feed kind: {s}, use price or randomness"))),
+    }
+}
+
+/// Auxiliary function to parse a list of oracle public keys.
+pub fn parse_oracle_keys(oracles: &[String]) -> Result<Vec<PublicKey>> {
+    let mut keys = Vec::with_capacity(oracles.len());
+    for oracle in oracles {
+        keys.push(PublicKey::from_str(oracle)?);
+    }
+
+    Ok(keys)
+}
+
 /// Fun police go away
 pub async fn kaching() {
     const WALLET_MP3: &[u8] = include_bytes!("../wallet.mp3");
@@ -131,6 +189,13 @@ pub fn generate_completions(shell: &str) -> Result<()> {
     let initialize =
         Arg::with_name("initialize").long("initialize").help("Initialize wallet database");
 
+    let restore = Arg::with_name("restore")
+        .long("restore")
+        .help("Initialize wallet database, recovering it from a mnemonic phrase given from stdin");
+
+    let mnemonic =
+        Arg::with_name("mnemonic").long("mnemonic").help("Print the mnemonic phrase of the wallet");
+
     let keygen =
         Arg::with_name("keygen").long("keygen").help("Generate a new keypair in the wallet");
 
@@ -159,8 +224,22 @@ pub fn generate_completions(shell: &str) -> Result<()> {
 
     let coins = Arg::with_name("coins").long("coins").help("Print all the coins in the wallet");
 
+    let backup = Arg::with_name("backup")
+        .long("backup")
+        .help("Create an encrypted backup of the wallet database");
+
+    let verify = Arg::with_name("verify")
+        .long("verify")
+        .help("Verify wallet database integrity and consistency against darkfid");
+
+    let doctor = Arg::with_name("doctor")
+        .long("doctor")
+        .help("Scan the wallet state for anomalies and suggest fixes for them");
+
     let wallet = SubCommand::with_name("wallet").about("Wallet operations").args(&vec![
         initialize,
+        restore,
+        mnemonic,
         keygen,
         balance,
         address,
@@ -170,6 +249,9 @@ pub fn generate_completions(shell: &str) -> Result<()> {
         import_secrets,
         tree,
         coins,
+        backup,
+        verify,
+        doctor,
     ]);
 
     // Spend
@@ -206,6 +288,37 @@ pub fn generate_completions(shell: &str) -> Result<()> {
             half_split,
         ]);
 
+    // Sweep
+    let max_inputs = Arg::with_name("max-inputs")
+        .long("max-inputs")
+        .takes_value(true)
+        .help("Maximum number of coins spent per transaction");
+
+    let max_value = Arg::with_name("max-value")
+        .long("max-value")
+        .takes_value(true)
+        .help("Only sweep coins worth at most this amount");
+
+    let max_fee = Arg::with_name("max-fee")
+        .long("max-fee")
+        .takes_value(true)
+        .help("Stop sweeping if a transaction fee would exceed this amount");
+
+    let max_delay = Arg::with_name("max-delay")
+        .long("max-delay")
+        .takes_value(true)
+        .help("Maximum random delay between transactions, in seconds");
+
+    let sweep = SubCommand::with_name("sweep")
+        .about("Consolidate small coins of a token into fewer outputs")
+        .args(&vec![
+            token.clone(),
+            max_inputs.clone(),
+            max_value,
+            max_fee.clone(),
+            max_delay.clone(),
+        ]);
+
     // Otc
     let value_pair = Arg::with_name("value-pair")
         .short("v")
@@ -235,6 +348,34 @@ pub fn generate_completions(shell: &str) -> Result<()> {
         .about("OTC atomic swap")
         .subcommands(vec![init, join, inspect, sign]);
 
+    // FeeSwap
+    let rate =
+        Arg::with_name("rate").help("Price of a native token, in units of the payment token");
+
+    let offer_max_fee = Arg::with_name("max-fee").help("Maximum fee to pay per transaction");
+
+    let offer = SubCommand::with_name("offer")
+        .about("Create a fee offer, paying fees in exchange for a token")
+        .args(&vec![token.clone(), rate, offer_max_fee]);
+
+    let import = SubCommand::with_name("import").about("Import a fee offer given from stdin");
+
+    let list = SubCommand::with_name("list").about("List the fee offers in the wallet");
+
+    let transfer_request = SubCommand::with_name("transfer")
+        .about("Create a fee swap request paying to an address, using the best fee offer for our balances")
+        .args(&vec![amount.clone(), token.clone(), recipient.clone()]);
+
+    let fund = SubCommand::with_name("fund")
+        .about("Attach the fee call to a fee swap request given from stdin");
+
+    let sign_fee_swap =
+        SubCommand::with_name("sign").about("Sign a funded fee swap transaction given from stdin");
+
+    let fee_swap = SubCommand::with_name("fee-swap")
+        .about("Pay transaction fees in other tokens through fee offers")
+        .subcommands(vec![offer, import, list, transfer_request, fund, sign_fee_swap]);
+
     // AttachFee
     let attach_fee = SubCommand::with_name("attach-fee")
         .about("Attach the fee call to a transaction given from stdin");
@@ -269,12 +410,19 @@ pub fn generate_completions(shell: &str) -> Result<()> {
 
     let gov_token_id = Arg::with_name("gov-token-id").help("DAO's governance token ID");
 
+    let members = Arg::with_name("members")
+        .long("members")
+        .takes_value(true)
+        .multiple(true)
+        .help("Public keys of the DAO members");
+
     let create = SubCommand::with_name("create").about("Create DAO parameters").args(&vec![
         proposer_limit,
         quorum,
         early_exec_quorum,
         approval_ratio,
         gov_token_id,
+        members,
     ]);
 
     let view = SubCommand::with_name("view").about("View DAO data from stdin");
@@ -356,6 +504,19 @@ pub fn generate_completions(shell: &str) -> Result<()> {
     let spend_hook_cmd = SubCommand::with_name("spend-hook")
         .about("Print the DAO contract base58-encoded spend hook");
 
+    let desktop = Arg::with_name("desktop")
+        .long("desktop")
+        .help("Send desktop notifications using `notify-send`");
+
+    let hook = Arg::with_name("hook")
+        .long("hook")
+        .takes_value(true)
+        .help("Shell command executed for each event");
+
+    let watch = SubCommand::with_name("watch")
+        .about("Subscribe to new blocks and notify about DAO proposals activity")
+        .args(&vec![desktop, hook]);
+
     let dao = SubCommand::with_name("dao").about("DAO functionalities").subcommands(vec![
         create,
         view,
@@ -372,6 +533,7 @@ pub fn generate_completions(shell: &str) -> Result<()> {
         vote,
         exec,
         spend_hook_cmd,
+        watch,
     ]);
 
     // Scan
@@ -491,6 +653,200 @@ pub fn generate_completions(shell: &str) -> Result<()> {
         freeze,
     ]);
 
+    // Viewkey
+    let incoming_only = Arg::with_name("incoming-only")
+        .long("incoming-only")
+        .help("Only export keys able to detect incoming notes");
+
+    let export = SubCommand::with_name("export")
+        .about("Export the view keys of the wallet")
+        .arg(incoming_only);
+
+    let address = SubCommand::with_name("address").about(
+        "Print the default view address of the wallet. \
+                    Payments to it can be seen using its incoming view key.",
+    );
+
+    let import = SubCommand::with_name("import")
+        .about("Import incoming view keys from stdin, separated by newlines");
+
+    let notes = SubCommand::with_name("notes")
+        .about("Print all the incoming notes found using imported view keys");
+
+    let viewkey = SubCommand::with_name("viewkey")
+        .about("View key functionalities")
+        .subcommands(vec![export, address, import, notes]);
+
+    // Keys
+    let grace_blocks = Arg::with_name("grace-blocks")
+        .long("grace-blocks")
+        .takes_value(true)
+        .help("Number of blocks the retired keys keep getting scanned for");
+
+    let rotate = SubCommand::with_name("rotate")
+        .about("Generate a new default key, retire the rest and move their coins to it")
+        .args(&vec![grace_blocks, max_inputs.clone(), max_fee.clone(), max_delay.clone()]);
+
+    let migrate = SubCommand::with_name("migrate")
+        .about("Resume moving the coins of retired keys to the default key")
+        .args(&vec![max_inputs, max_fee, max_delay]);
+
+    let status = SubCommand::with_name("status")
+        .about("Print the retired keys and the coins they still own");
+
+    let keys = SubCommand::with_name("keys")
+        .about("Key rotation functionalities")
+        .subcommands(vec![rotate, migrate, status]);
+
+    // Name
+    let name = Arg::with_name("name").help("Name to manage");
+
+    let duration = Arg::with_name("duration").help("Registration duration in blocks");
+
+    let address = Arg::with_name("address")
+        .long("address")
+        .takes_value(true)
+        .help("Address the name resolves to");
+
+    let contract = Arg::with_name("contract")
+        .long("contract")
+        .takes_value(true)
+        .help("Contract ID the name resolves to");
+
+    let commit = SubCommand::with_name("commit")
+        .about("Commit to registering a name, required before registering it")
+        .arg(name.clone());
+
+    let register = SubCommand::with_name("register")
+        .about("Register a previously committed name pointing to an address and/or contract ID")
+        .args(&vec![name.clone(), duration.clone(), address.clone(), contract.clone()]);
+
+    let resolve = SubCommand::with_name("resolve")
+        .about("Resolve a name using the scanned names records")
+        .arg(name.clone());
+
+    let renew = SubCommand::with_name("renew")
+        .about("Extend the registration of an owned name")
+        .args(&vec![name.clone(), duration]);
+
+    let receive = SubCommand::with_name("receive")
+        .about("Generate an owner commitment to receive a name transfer")
+        .arg(name.clone());
+
+    let owner = Arg::with_name("owner").help("Owner commitment of the recipient");
+
+    let transfer = SubCommand::with_name("transfer")
+        .about("Transfer an owned name to a new owner, optionally changing its target")
+        .args(&vec![name, owner, address, contract]);
+
+    let list = SubCommand::with_name("list").about("List names owned by the wallet");
+
+    let name = SubCommand::with_name("name")
+        .about("Name service functionalities")
+        .subcommands(vec![commit, register, resolve, renew, receive, transfer, list]);
+
+    // Auction
+    let amount = Arg::with_name("amount").help("Amount of the asset to auction");
+
+    let token = Arg::with_name("token").help("Token ID of the asset to auction");
+
+    let payment_token = Arg::with_name("payment-token").help("Token ID bids have to be made in");
+
+    let reserve_price =
+        Arg::with_name("reserve-price").help("Minimum bid value able to win the auction");
+
+    let commit_duration =
+        Arg::with_name("commit-duration").help("Duration of the commit phase in blocks");
+
+    let reveal_duration =
+        Arg::with_name("reveal-duration").help("Duration of the reveal phase in blocks");
+
+    let create = SubCommand::with_name("create")
+        .about("Create an auction, locking the auctioned asset")
+        .args(&vec![amount, token, payment_token, reserve_price, commit_duration, reveal_duration]);
+
+    let auction_id = Arg::with_name("auction-id").help("Auction ID");
+
+    let amount = Arg::with_name("amount").help("Bid value");
+
+    let bid = SubCommand::with_name("bid")
+        .about("Place a sealed bid in an auction, locking its value")
+        .args(&vec![auction_id.clone(), amount]);
+
+    let reveal = SubCommand::with_name("reveal")
+        .about("Reveal our bid in an auction")
+        .arg(auction_id.clone());
+
+    let refund = SubCommand::with_name("refund")
+        .about("Refund our coins locked in an ended auction")
+        .arg(auction_id.clone());
+
+    let settle_init = SubCommand::with_name("settle-init")
+        .about("Create the seller's half of an auction settlement")
+        .arg(auction_id);
+
+    let settle_join = SubCommand::with_name("settle-join")
+        .about("Build the auction settlement tx given the seller's half from stdin");
+
+    let list = SubCommand::with_name("list").about("List the auctions seen on-chain");
+
+    let auction = SubCommand::with_name("auction")
+        .about("Sealed-bid auction functionalities")
+        .subcommands(vec![create, bid, reveal, refund, settle_init, settle_join, list]);
+
+    // Oracle
+    let name = Arg::with_name("name").help("Feed name");
+
+    let kind = Arg::with_name("kind").help("Feed kind (price or randomness)");
+
+    let threshold =
+        Arg::with_name("threshold").help("Amount of oracle attestations required per round");
+
+    let oracles = Arg::with_name("oracles").multiple(true).help("Oracle public keys");
+
+    let register = SubCommand::with_name("register")
+        .about("Register a feed, using the default address as its authority")
+        .args(&vec![name, kind, threshold.clone(), oracles.clone()]);
+
+    let feed_id = Arg::with_name("feed-id").help("Feed ID");
+
+    let rotate = SubCommand::with_name("rotate")
+        .about("Replace the oracle set of a feed we are the authority of")
+        .args(&vec![feed_id.clone(), threshold, oracles]);
+
+    let show = SubCommand::with_name("show")
+        .about("Show a feed and its latest attested value")
+        .arg(feed_id.clone());
+
+    let reporters = Arg::with_name("reporters").multiple(true).help("oracled JSON-RPC endpoints");
+
+    let collect = SubCommand::with_name("collect")
+        .about("Gather attestations from oracle reporters and submit the newest round")
+        .args(&vec![feed_id, reporters]);
+
+    let oracle = SubCommand::with_name("oracle")
+        .about("Oracle feed functionalities")
+        .subcommands(vec![register, rotate, show, collect]);
+
+    // Proof
+    let tx_hash = Arg::with_name("tx-hash").help("Hash of the payment transaction");
+
+    let output = Arg::with_name("output")
+        .long("output")
+        .takes_value(true)
+        .help("Only prove the payment of this output index");
+
+    let create = SubCommand::with_name("create")
+        .about("Create payment proofs for a transaction we sent")
+        .args(&vec![tx_hash, output]);
+
+    let verify = SubCommand::with_name("verify")
+        .about("Verify payment proofs from stdin against the blockchain, separated by newlines");
+
+    let proof = SubCommand::with_name("proof")
+        .about("Payment proof functionalities")
+        .subcommands(vec![create, verify]);
+
     // Main arguments
     let config = Arg::with_name("config")
         .short("c")
@@ -511,7 +867,9 @@ pub fn generate_completions(shell: &str) -> Result<()> {
         spend,
         unspend,
         transfer,
+        sweep,
         otc,
+        fee_swap,
         attach_fee,
         inspect,
         broadcast,
@@ -521,6 +879,12 @@ pub fn generate_completions(shell: &str) -> Result<()> {
         explorer,
         alias,
         token,
+        viewkey,
+        keys,
+        name,
+        auction,
+        oracle,
+        proof,
     ];
 
     let fun = Arg::with_name("fun")
@@ -528,6 +892,11 @@ pub fn generate_completions(shell: &str) -> Result<()> {
         .long("fun")
         .help("Flag indicating whether you want some fun in your life");
 
+    let locale = Arg::with_name("locale")
+        .long("locale")
+        .takes_value(true)
+        .help("Locale used to format numbers (e.g. de_DE), defaults to the environment's");
+
     let log = Arg::with_name("log")
         .short("l")
         .long("log")
@@ -541,7 +910,7 @@ pub fn generate_completions(shell: &str) -> Result<()> {
 
     let mut app = App::new("drk")
         .about(cli_desc!())
-        .args(&vec![config, network, fun, log, verbose])
+        .args(&vec![config, network, fun, locale, log, verbose])
         .subcommands(command);
 
     let shell = match Shell::from_str(shell) {
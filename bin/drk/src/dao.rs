@@ -33,8 +33,8 @@ use darkfi::{
 use darkfi_dao_contract::{
     blockwindow,
     client::{
-        make_mint_call, DaoAuthMoneyTransferCall, DaoExecCall, DaoProposeCall,
-        DaoProposeStakeInput, DaoVoteCall, DaoVoteInput,
+        make_members_tree, make_mint_call, DaoAuthMoneyTransferCall, DaoExecCall, DaoMemberInput,
+        DaoProposeCall, DaoProposeStakeInput, DaoVoteCall, DaoVoteInput,
     },
     model::{
         Dao, DaoAuthCall, DaoBulla, DaoExecParams, DaoMintParams, DaoProposal, DaoProposalBulla,
@@ -58,7 +58,7 @@ use darkfi_sdk::{
         poseidon_hash,
         smt::{MemoryStorageFp, PoseidonFp, SmtMemoryFp, EMPTY_NODES_FP},
         util::{fp_mod_fv, fp_to_u64},
-        BaseBlind, Blind, FuncId, FuncRef, MerkleNode, MerkleTree, PublicKey, ScalarBlind,
+        BaseBlind, Blind, FuncId, FuncRef, Keypair, MerkleNode, MerkleTree, PublicKey, ScalarBlind,
         SecretKey, DAO_CONTRACT_ID, MONEY_CONTRACT_ID,
     },
     dark_tree::DarkTree,
@@ -74,7 +74,12 @@ use darkfi_serial::{
 use crate::{
     convert_named_params,
     error::{WalletDbError, WalletDbResult},
-    money::{BALANCE_BASE10_DECIMALS, MONEY_SMT_COL_KEY, MONEY_SMT_COL_VALUE, MONEY_SMT_TABLE},
+    hooks::WalletEvent,
+    locale::format_amount,
+    money::{
+        BALANCE_BASE10_DECIMALS, MONEY_SEED_COL_NEXT_DAO_INDEX, MONEY_SMT_COL_KEY,
+        MONEY_SMT_COL_VALUE, MONEY_SMT_TABLE,
+    },
     walletdb::{WalletSmt, WalletStorage},
     Drk,
 };
@@ -124,11 +129,17 @@ pub const DAO_VOTES_COL_TX_HASH: &str = "tx_hash";
 pub const DAO_VOTES_COL_CALL_INDEX: &str = "call_index";
 pub const DAO_VOTES_COL_NULLIFIERS: &str = "nullifiers";
 
+/// HD derivation path of the DAO keys, under which each DAO created
+/// by the wallet is derived using its index.
+pub const DAO_KEYS_HD_PATH: [u32; 1] = [1];
+
 #[derive(Debug, Clone, SerialEncodable, SerialDecodable)]
 /// Parameters representing a DAO to be initialized
 pub struct DaoParams {
     /// The on chain representation of the DAO
     pub dao: Dao,
+    /// DAO members public keys. Empty when the DAO has no members set.
+    pub members: Vec<PublicKey>,
     /// DAO notes decryption secret key
     pub notes_secret_key: Option<SecretKey>,
     /// DAO proposals creator secret key
@@ -143,6 +154,63 @@ pub struct DaoParams {
     pub early_exec_secret_key: Option<SecretKey>,
 }
 
+/// Layout of [`DaoParams`] stored in wallets before DAOs got an optional
+/// members set, used to migrate them.
+#[derive(SerialDecodable)]
+struct LegacyDaoParams {
+    proposer_limit: u64,
+    quorum: u64,
+    early_exec_quorum: u64,
+    approval_ratio_quot: u64,
+    approval_ratio_base: u64,
+    gov_token_id: TokenId,
+    notes_public_key: PublicKey,
+    proposer_public_key: PublicKey,
+    proposals_public_key: PublicKey,
+    votes_public_key: PublicKey,
+    exec_public_key: PublicKey,
+    early_exec_public_key: PublicKey,
+    bulla_blind: BaseBlind,
+    notes_secret_key: Option<SecretKey>,
+    proposer_secret_key: Option<SecretKey>,
+    proposals_secret_key: Option<SecretKey>,
+    votes_secret_key: Option<SecretKey>,
+    exec_secret_key: Option<SecretKey>,
+    early_exec_secret_key: Option<SecretKey>,
+}
+
+impl From<LegacyDaoParams> for DaoParams {
+    fn from(legacy: LegacyDaoParams) -> Self {
+        let dao = Dao {
+            proposer_limit: legacy.proposer_limit,
+            quorum: legacy.quorum,
+            early_exec_quorum: legacy.early_exec_quorum,
+            approval_ratio_quot: legacy.approval_ratio_quot,
+            approval_ratio_base: legacy.approval_ratio_base,
+            gov_token_id: legacy.gov_token_id,
+            notes_public_key: legacy.notes_public_key,
+            proposer_public_key: legacy.proposer_public_key,
+            proposals_public_key: legacy.proposals_public_key,
+            votes_public_key: legacy.votes_public_key,
+            exec_public_key: legacy.exec_public_key,
+            early_exec_public_key: legacy.early_exec_public_key,
+            members_root: pallas::Base::ZERO,
+            bulla_blind: legacy.bulla_blind,
+        };
+
+        Self {
+            dao,
+            members: vec![],
+            notes_secret_key: legacy.notes_secret_key,
+            proposer_secret_key: legacy.proposer_secret_key,
+            proposals_secret_key: legacy.proposals_secret_key,
+            votes_secret_key: legacy.votes_secret_key,
+            exec_secret_key: legacy.exec_secret_key,
+            early_exec_secret_key: legacy.early_exec_secret_key,
+        }
+    }
+}
+
 impl DaoParams {
     /// Generate new `DaoParams`. If a specific secret key is provided,
     /// the corresponding public key will be derived from it and ignore the provided one.
@@ -166,6 +234,7 @@ impl DaoParams {
         exec_public_key: PublicKey,
         early_exec_secret_key: Option<SecretKey>,
         early_exec_public_key: PublicKey,
+        members: Vec<PublicKey>,
         bulla_blind: BaseBlind,
     ) -> Self {
         // Derive corresponding keys from their secret or use the provided ones.
@@ -194,6 +263,13 @@ impl DaoParams {
             None => early_exec_public_key,
         };
 
+        // Compute the members set root, if members were provided
+        let members_root = if members.is_empty() {
+            pallas::Base::ZERO
+        } else {
+            make_members_tree(&members).root(0).unwrap().inner()
+        };
+
         let dao = Dao {
             proposer_limit,
             quorum,
@@ -207,10 +283,12 @@ impl DaoParams {
             votes_public_key,
             exec_public_key,
             early_exec_public_key,
+            members_root,
             bulla_blind,
         };
         Self {
             dao,
+            members,
             notes_secret_key,
             proposer_secret_key,
             proposals_secret_key,
@@ -294,6 +372,26 @@ impl DaoParams {
         };
         let bulla_blind = BaseBlind::from_str(bulla_blind)?;
 
+        let members = match table.get("members") {
+            Some(members) => {
+                let Some(members) = members.as_array() else {
+                    return Err(Error::ParseFailed("Invalid members: Not an array"))
+                };
+                let mut ret = Vec::with_capacity(members.len());
+                for member in members {
+                    let Some(member) = member.as_str() else {
+                        return Err(Error::ParseFailed("Invalid member: Not a string"))
+                    };
+                    let Ok(member) = PublicKey::from_str(member) else {
+                        return Err(Error::ParseFailed("Invalid member: Decoding failed"))
+                    };
+                    ret.push(member);
+                }
+                ret
+            }
+            None => vec![],
+        };
+
         // Grab DAO actions keypairs
         let notes_secret_key = match table.get("notes_secret_key") {
             Some(notes_secret_key) => {
@@ -482,6 +580,7 @@ impl DaoParams {
             exec_public_key,
             early_exec_secret_key,
             early_exec_public_key,
+            members,
             bulla_blind,
         ))
     }
@@ -528,6 +627,16 @@ impl DaoParams {
             self.dao.bulla_blind,
         );
 
+        // DAO members set
+        let members: Vec<String> = self.members.iter().map(|m| format!("\"{m}\"")).collect();
+        toml += &format!(
+            "## DAO members public keys. When set, only members can create\n\
+            ## proposals and vote, without revealing which member did so.\n\
+            ## Leave empty to allow any governance token holder.\n\
+            members = [{}]\n\n",
+            members.join(", "),
+        );
+
         // DAO actions keypairs
         toml += &format!(
             "## ====== DAO actions keypairs =====\n\n\
@@ -616,23 +725,32 @@ impl fmt::Display for DaoParams {
             None => "None".to_string(),
         };
 
+        let members = if self.members.is_empty() {
+            "None".to_string()
+        } else {
+            let members: Vec<String> = self.members.iter().map(|m| format!("\n\t{m}")).collect();
+            members.concat()
+        };
+
         let s = format!(
-            "{}\n{}\n{}: {} ({})\n{}: {} ({})\n{}: {} ({})\n{}: {}\n{}: {}\n{}: {}\n{}: {}\n{}: {}\n{}: {}\n{}: {}\n{}: {}\n{}: {}\n{}: {}\n{}: {}\n{}: {}\n{}: {}\n{}: {}\n{}: {}",
+            "{}\n{}\n{}: {} ({})\n{}: {} ({})\n{}: {} ({})\n{}: {}\n{}: {}\n{}: {}\n{}: {}\n{}: {}\n{}: {}\n{}: {}\n{}: {}\n{}: {}\n{}: {}\n{}: {}\n{}: {}\n{}: {}\n{}: {}\n{}: {}\n{}: {}",
             "DAO Parameters",
             "==============",
             "Proposer limit",
-            encode_base10(self.dao.proposer_limit, BALANCE_BASE10_DECIMALS),
+            format_amount(self.dao.proposer_limit),
             self.dao.proposer_limit,
             "Quorum",
-            encode_base10(self.dao.quorum, BALANCE_BASE10_DECIMALS),
+            format_amount(self.dao.quorum),
             self.dao.quorum,
             "Early Exec Quorum",
-            encode_base10(self.dao.early_exec_quorum, BALANCE_BASE10_DECIMALS),
+            format_amount(self.dao.early_exec_quorum),
             self.dao.early_exec_quorum,
             "Approval ratio",
             self.dao.approval_ratio_quot as f64 / self.dao.approval_ratio_base as f64,
             "Governance Token ID",
             self.dao.gov_token_id,
+            "Members",
+            members,
             "Notes Public key",
             self.dao.notes_public_key,
             "Notes Secret key",
@@ -747,13 +865,13 @@ impl fmt::Display for DaoRecord {
             "Bulla",
             self.bulla(),
             "Proposer limit",
-            encode_base10(self.params.dao.proposer_limit, BALANCE_BASE10_DECIMALS),
+            format_amount(self.params.dao.proposer_limit),
             self.params.dao.proposer_limit,
             "Quorum",
-            encode_base10(self.params.dao.quorum, BALANCE_BASE10_DECIMALS),
+            format_amount(self.params.dao.quorum),
             self.params.dao.quorum,
             "Early Exec Quorum",
-            encode_base10(self.params.dao.early_exec_quorum, BALANCE_BASE10_DECIMALS),
+            format_amount(self.params.dao.early_exec_quorum),
             self.params.dao.early_exec_quorum,
             "Approval ratio",
             self.params.dao.approval_ratio_quot as f64 / self.params.dao.approval_ratio_base as f64,
@@ -894,6 +1012,9 @@ impl Drk {
         let wallet_schema = include_str!("../dao.sql");
         self.wallet.exec_batch_sql(wallet_schema)?;
 
+        // Migrate DAOs stored in older layouts
+        self.migrate_dao_params().await?;
+
         // Check if we have to initialize the Merkle trees.
         // We check if one exists, but we actually create two. This should be written
         // a bit better and safer.
@@ -913,6 +1034,62 @@ impl Drk {
         Ok(())
     }
 
+    /// Derive the keys of a new DAO from the wallet mnemonic seed.
+    /// Returns the notes, proposer, proposals, votes, exec and early exec
+    /// keypairs, along with the DAO bulla blind.
+    pub async fn dao_keygen(&self) -> Result<([Keypair; 6], BaseBlind)> {
+        let key = self.money_seed_derive(&DAO_KEYS_HD_PATH, MONEY_SEED_COL_NEXT_DAO_INDEX).await?;
+
+        let keypairs = core::array::from_fn(|i| key.derive_child(i as u32).keypair());
+        let bulla_blind = Blind(key.derive_child(6).secret().inner());
+
+        Ok((keypairs, bulla_blind))
+    }
+
+    /// Migrate DAO records stored before DAOs got an optional members set
+    /// to the current [`DaoParams`] layout. DAOs without a members set keep
+    /// their bulla, so the records and their on chain state stay valid.
+    async fn migrate_dao_params(&self) -> WalletDbResult<()> {
+        let rows = self.wallet.query_multiple(
+            &DAO_DAOS_TABLE,
+            &[DAO_DAOS_COL_BULLA, DAO_DAOS_COL_NAME, DAO_DAOS_COL_PARAMS],
+            &[],
+        )?;
+
+        for row in rows {
+            let (Value::Blob(ref bulla), Value::Text(ref name), Value::Blob(ref params_bytes)) =
+                (&row[0], &row[1], &row[2])
+            else {
+                continue
+            };
+
+            // Records in the current layout match their bulla
+            if let Ok(params) = deserialize_async::<DaoParams>(params_bytes).await {
+                if serialize_async(&params.dao.to_bulla()).await == *bulla {
+                    continue
+                }
+            }
+
+            let Ok(legacy) = deserialize_async::<LegacyDaoParams>(params_bytes).await else {
+                continue
+            };
+            let params = DaoParams::from(legacy);
+            if serialize_async(&params.dao.to_bulla()).await != *bulla {
+                continue
+            }
+
+            println!("Migrating \"{name}\" DAO parameters");
+            let query = format!(
+                "UPDATE {} SET {} = ?1 WHERE {} = ?2;",
+                *DAO_DAOS_TABLE, DAO_DAOS_COL_PARAMS, DAO_DAOS_COL_BULLA,
+            );
+            self.wallet
+                .exec_sql(&query, rusqlite::params![serialize_async(&params).await, bulla])?;
+        }
+
+        Ok(())
+    }
+
     /// Replace the DAO Merkle trees in the wallet.
     pub async fn put_dao_trees(
         &self,
@@ -1297,6 +1474,15 @@ impl Drk {
                 )))
             }
 
+            self.hooks
+                .queue(WalletEvent::ProposalCreated {
+                    dao: dao.name.clone(),
+                    dao_bulla: dao.bulla(),
+                    proposal: params.proposal_bulla,
+                    tx_hash,
+                })
+                .await;
+
             return Ok(true);
         }
 
@@ -2316,6 +2502,36 @@ impl Drk {
         Ok(proposal_record)
     }
 
+    /// Create the [`DaoMemberInput`] proving one of our keys is part of
+    /// the provided DAO members set. DAOs without a members set use a
+    /// dummy input.
+    async fn dao_member_input(&self, dao: &DaoRecord) -> Result<DaoMemberInput> {
+        if !dao.params.dao.has_members() {
+            return Ok(DaoMemberInput::none())
+        }
+
+        // Find a wallet secret key belonging to the members set
+        let secrets = self.get_money_secrets().await?;
+        let Some((index, secret)) = dao.params.members.iter().enumerate().find_map(|(i, m)| {
+            secrets.iter().find(|s| PublicKey::from_secret(**s) == *m).map(|s| (i, *s))
+        }) else {
+            return Err(Error::Custom(format!(
+                "[dao_member_input] None of our keys is a member of DAO: {}",
+                dao.name
+            )))
+        };
+
+        let tree = make_members_tree(&dao.params.members);
+        let leaf_position = (index as u64).into();
+        let Ok(merkle_path) = tree.witness(leaf_position, 0) else {
+            return Err(Error::Custom(
+                "[dao_member_input] Failed to create DAO member Merkle path".to_string(),
+            ))
+        };
+
+        Ok(DaoMemberInput { secret, leaf_position, merkle_path })
+    }
+
     /// Create a DAO transfer proposal transaction.
     pub async fn dao_transfer_proposal_tx(&self, proposal: &ProposalRecord) -> Result<Transaction> {
         // Check we know the plaintext data
@@ -2490,6 +2706,9 @@ impl Drk {
         );
         let money_null_smt = WalletSmt::new(store, PoseidonFp::new(), &EMPTY_NODES_FP);
 
+        // Create our DAO membership input
+        let member = self.dao_member_input(&dao).await?;
+
         // Create the proposal call
         let call = DaoProposeCall {
             money_null_smt: &money_null_smt,
@@ -2499,6 +2718,7 @@ impl Drk {
             dao_leaf_position: dao.leaf_position.unwrap(),
             dao_merkle_path,
             dao_merkle_root,
+            member,
             signature_secret,
         };
 
@@ -2678,6 +2898,9 @@ impl Drk {
         );
         let money_null_smt = WalletSmt::new(store, PoseidonFp::new(), &EMPTY_NODES_FP);
 
+        // Create our DAO membership input
+        let member = self.dao_member_input(&dao).await?;
+
         // Create the proposal call
         let call = DaoProposeCall {
             money_null_smt: &money_null_smt,
@@ -2687,6 +2910,7 @@ impl Drk {
             dao_leaf_position: dao.leaf_position.unwrap(),
             dao_merkle_path,
             dao_merkle_root,
+            member,
             signature_secret,
         };
 
@@ -2884,6 +3108,9 @@ impl Drk {
         let store = MemoryStorageFp { tree: proposal.nullifiers_smt_snapshot.unwrap() };
         let money_null_smt = SmtMemoryFp::new(store, PoseidonFp::new(), &EMPTY_NODES_FP);
 
+        // Create our DAO membership input
+        let member = self.dao_member_input(&dao).await?;
+
         // Create the vote call
         let call = DaoVoteCall {
             money_null_smt: &money_null_smt,
@@ -2891,6 +3118,7 @@ impl Drk {
             vote_option,
             proposal: proposal.proposal.clone(),
             dao: dao.params.dao.clone(),
+            member,
             current_blockwindow,
         };
 
@@ -3174,6 +3402,7 @@ impl Drk {
             clear_inputs: vec![],
             inputs,
             outputs,
+            output_note_keys: vec![],
             mint_zkbin: mint_zkbin.clone(),
             mint_pk: mint_pk.clone(),
             burn_zkbin: burn_zkbin.clone(),
@@ -0,0 +1,453 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2025 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use std::{
+    collections::{HashMap, HashSet},
+    fmt,
+};
+
+use smol::{lock::Mutex, process::Command};
+
+use darkfi::{blockchain::BlockInfo, Result};
+use darkfi_dao_contract::{
+    blockwindow,
+    model::{DaoExecParams, DaoProposalBulla, DaoProposeParams, DaoVoteParams},
+    DaoFunction,
+};
+use darkfi_sdk::{crypto::DAO_CONTRACT_ID, tx::TransactionHash};
+use darkfi_serial::deserialize_async;
+
+use crate::{dao::ProposalRecord, Drk};
+
+/// Number of blocks we remember sent notifications for, so transactions
+/// included again after a reorg don't get notified twice
+const NOTIFIED_DEPTH: u32 = 100;
+
+/// Kinds of DAO activity we notify about
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum DaoEventKind {
+    /// A new proposal was created for one of our DAOs
+    Proposal,
+    /// A vote was cast on one of our proposals
+    Vote,
+    /// One of our proposals was executed
+    Exec,
+    /// The voting window of one of our proposals is about to close
+    Closing,
+}
+
+impl fmt::Display for DaoEventKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let s = match self {
+            Self::Proposal => "proposal",
+            Self::Vote => "vote",
+            Self::Exec => "exec",
+            Self::Closing => "closing",
+        };
+        write!(f, "{s}")
+    }
+}
+
+/// A DAO activity event affecting the wallet
+pub struct DaoEvent {
+    /// Kind of the event
+    pub kind: DaoEventKind,
+    /// Name of the affected DAO
+    pub dao: String,
+    /// Bulla of the affected proposal
+    pub proposal: DaoProposalBulla,
+    /// Transaction that triggered the event, if any
+    pub tx_hash: Option<TransactionHash>,
+    /// Block height the event was observed at
+    pub height: u32,
+    /// Block window the proposal voting ends at
+    pub end_blockwindow: u64,
+}
+
+impl fmt::Display for DaoEvent {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.kind {
+            DaoEventKind::Proposal => write!(
+                f,
+                "New proposal {} for DAO {}, voting open until block window {}",
+                self.proposal, self.dao, self.end_blockwindow
+            ),
+            DaoEventKind::Vote => {
+                write!(f, "New vote on DAO {} proposal {}", self.dao, self.proposal)
+            }
+            DaoEventKind::Exec => {
+                write!(f, "DAO {} proposal {} was executed", self.dao, self.proposal)
+            }
+            DaoEventKind::Closing => write!(
+                f,
+                "Voting on DAO {} proposal {} closes at block window {}",
+                self.dao, self.proposal, self.end_blockwindow
+            ),
+        }
+    }
+}
+
+/// Extract the DAO proposal, vote and exec calls of a block, as
+/// `(kind, proposal, tx_hash)` tuples. Calls that can't be decoded
+/// are reported and skipped.
+pub async fn dao_calls(
+    block: &BlockInfo,
+) -> Vec<(DaoEventKind, DaoProposalBulla, TransactionHash)> {
+    let mut calls = vec![];
+    for tx in &block.txs {
+        let tx_hash = tx.hash();
+        for call in &tx.calls {
+            if call.data.contract_id != *DAO_CONTRACT_ID || call.data.data.is_empty() {
+                continue
+            }
+
+            match decode_dao_call(&call.data.data).await {
+                Ok(Some((kind, proposal_bulla))) => calls.push((kind, proposal_bulla, tx_hash)),
+                Ok(None) => {}
+                Err(e) => {
+                    eprintln!("[dao_watch] Decoding DAO call in transaction {tx_hash} failed: {e}")
+                }
+            }
+        }
+    }
+
+    calls
+}
+
+/// Decode the event kind and proposal bulla of a DAO call, if it's
+/// a call we notify about.
+async fn decode_dao_call(data: &[u8]) -> Result<Option<(DaoEventKind, DaoProposalBulla)>> {
+    let event = match DaoFunction::try_from(data[0])? {
+        DaoFunction::Propose => {
+            let params: DaoProposeParams = deserialize_async(&data[1..]).await?;
+            (DaoEventKind::Proposal, params.proposal_bulla)
+        }
+        DaoFunction::Vote => {
+            let params: DaoVoteParams = deserialize_async(&data[1..]).await?;
+            (DaoEventKind::Vote, params.proposal_bulla)
+        }
+        DaoFunction::Exec => {
+            let params: DaoExecParams = deserialize_async(&data[1..]).await?;
+            (DaoEventKind::Exec, params.proposal_bulla)
+        }
+        _ => return Ok(None),
+    };
+
+    Ok(Some(event))
+}
+
+/// Check if the voting window of an unexecuted proposal closes in the
+/// block window following `current_blockwindow`.
+pub fn closes_next(proposal: &ProposalRecord, current_blockwindow: u64) -> bool {
+    if proposal.exec_tx_hash.is_some() || proposal.leaf_position.is_none() {
+        return false
+    }
+
+    let end_blockwindow =
+        proposal.proposal.creation_blockwindow + proposal.proposal.duration_blockwindows;
+    current_blockwindow + 1 == end_blockwindow
+}
+
+/// Watches scanned blocks for DAO activity affecting the wallet,
+/// and dispatches notifications for it.
+pub struct DaoWatcher {
+    /// Flag indicating if desktop notifications should be sent
+    desktop: bool,
+    /// Optional shell command executed for each event
+    hook: Option<String>,
+    /// Proposals we already sent a closing reminder for
+    reminded: Mutex<HashSet<DaoProposalBulla>>,
+    /// Transaction events we already notified about, along with
+    /// the block height they were observed at
+    notified: Mutex<HashMap<(TransactionHash, DaoEventKind, DaoProposalBulla), u32>>,
+}
+
+impl DaoWatcher {
+    pub fn new(desktop: bool, hook: Option<String>) -> Self {
+        Self {
+            desktop,
+            hook,
+            reminded: Mutex::new(HashSet::new()),
+            notified: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Check an already scanned block for DAO activity affecting the
+    /// wallet, and notify about it. Must be called after the block has
+    /// been scanned, so new proposals are already in the wallet.
+    /// Failures on single events are reported and skipped, and events
+    /// of transactions re-included after a reorg are not notified again.
+    pub async fn on_block(&self, drk: &Drk, block: &BlockInfo) -> Result<()> {
+        let height = block.header.height;
+        let block_target = drk.get_block_target().await?;
+
+        for (kind, proposal_bulla, tx_hash) in dao_calls(block).await {
+            // Skip proposals of DAOs we don't participate in
+            let Ok(proposal) = drk.get_dao_proposal_by_bulla(&proposal_bulla).await else {
+                continue
+            };
+
+            if !self.mark_notified(tx_hash, kind, proposal_bulla, height).await {
+                continue
+            }
+
+            let event = match self.event(drk, kind, &proposal, Some(tx_hash), height).await {
+                Ok(v) => v,
+                Err(e) => {
+                    eprintln!("[dao_watch] Building {kind} event for {proposal_bulla} failed: {e}");
+                    continue
+                }
+            };
+            self.notify(&event).await;
+        }
+
+        // Remind about voting windows closing in the next block window
+        let current_blockwindow = blockwindow(height, block_target);
+        for proposal in drk.get_proposals().await? {
+            if !closes_next(&proposal, current_blockwindow) {
+                continue
+            }
+
+            if !self.reminded.lock().await.insert(proposal.bulla()) {
+                continue
+            }
+
+            let event = match self.event(drk, DaoEventKind::Closing, &proposal, None, height).await
+            {
+                Ok(v) => v,
+                Err(e) => {
+                    eprintln!(
+                        "[dao_watch] Building closing event for {} failed: {e}",
+                        proposal.bulla()
+                    );
+                    continue
+                }
+            };
+            self.notify(&event).await;
+        }
+
+        Ok(())
+    }
+
+    /// Record a transaction event observed at `height`, returning `false`
+    /// if it was already notified, like when its transaction got included
+    /// again after a reorg.
+    async fn mark_notified(
+        &self,
+        tx_hash: TransactionHash,
+        kind: DaoEventKind,
+        proposal_bulla: DaoProposalBulla,
+        height: u32,
+    ) -> bool {
+        let mut notified = self.notified.lock().await;
+
+        // Forget notifications old enough to not get reorged anymore
+        notified.retain(|_, h| *h + NOTIFIED_DEPTH > height);
+
+        notified.insert((tx_hash, kind, proposal_bulla), height).is_none()
+    }
+
+    /// Build a [`DaoEvent`] for the given proposal
+    async fn event(
+        &self,
+        drk: &Drk,
+        kind: DaoEventKind,
+        proposal: &ProposalRecord,
+        tx_hash: Option<TransactionHash>,
+        height: u32,
+    ) -> Result<DaoEvent> {
+        let dao = drk.get_dao_by_bulla(&proposal.proposal.dao_bulla).await?;
+        Ok(DaoEvent {
+            kind,
+            dao: dao.name,
+            proposal: proposal.bulla(),
+            tx_hash,
+            height,
+            end_blockwindow: proposal.proposal.creation_blockwindow +
+                proposal.proposal.duration_blockwindows,
+        })
+    }
+
+    /// Dispatch a notification for the given event. Failures are
+    /// reported but never abort the watcher.
+    async fn notify(&self, event: &DaoEvent) {
+        println!("[dao_watch] {event}");
+
+        if self.desktop {
+            let res = Command::new("notify-send").arg("drk").arg(event.to_string()).status().await;
+            if let Err(e) = res {
+                eprintln!("[dao_watch] Sending desktop notification failed: {e}");
+            }
+        }
+
+        if let Some(hook) = &self.hook {
+            let tx_hash = match event.tx_hash {
+                Some(tx_hash) => tx_hash.to_string(),
+                None => String::new(),
+            };
+
+            let res = Command::new("sh")
+                .arg("-c")
+                .arg(hook)
+                .env("DRK_DAO_EVENT", event.kind.to_string())
+                .env("DRK_DAO_NAME", &event.dao)
+                .env("DRK_DAO_PROPOSAL", event.proposal.to_string())
+                .env("DRK_DAO_TX_HASH", tx_hash)
+                .env("DRK_DAO_HEIGHT", event.height.to_string())
+                .env("DRK_DAO_END_BLOCKWINDOW", event.end_blockwindow.to_string())
+                .env("DRK_DAO_MESSAGE", event.to_string())
+                .status()
+                .await;
+
+            match res {
+                Ok(status) if !status.success() => {
+                    eprintln!("[dao_watch] Notification hook exited with {status}");
+                }
+                Ok(_) => {}
+                Err(e) => eprintln!("[dao_watch] Executing notification hook failed: {e}"),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use darkfi::{tx::Transaction, zk::halo2::Field};
+    use darkfi_dao_contract::model::{DaoBlindAggregateVote, DaoBulla, DaoProposal};
+    use darkfi_sdk::{
+        crypto::{BaseBlind, ContractId, PublicKey, SecretKey, MONEY_CONTRACT_ID},
+        dark_tree::DarkLeaf,
+        pasta::{group::Group, pallas},
+        tx::ContractCall,
+    };
+    use darkfi_serial::serialize_async;
+    use rand::rngs::OsRng;
+
+    use super::*;
+
+    fn tx(calls: Vec<(ContractId, Vec<u8>)>) -> Transaction {
+        let calls = calls
+            .into_iter()
+            .map(|(contract_id, data)| DarkLeaf {
+                data: ContractCall { contract_id, data },
+                parent_index: None,
+                children_indexes: vec![],
+            })
+            .collect();
+        Transaction { calls, proofs: vec![], signatures: vec![], ..Default::default() }
+    }
+
+    async fn exec_call(proposal_bulla: DaoProposalBulla) -> Vec<u8> {
+        let params = DaoExecParams {
+            proposal_bulla,
+            proposal_auth_calls: vec![],
+            blind_total_vote: DaoBlindAggregateVote {
+                yes_vote_commit: pallas::Point::identity(),
+                all_vote_commit: pallas::Point::identity(),
+            },
+            early_exec: false,
+            signature_public: PublicKey::from_secret(SecretKey::random(&mut OsRng)),
+        };
+        [vec![DaoFunction::Exec as u8], serialize_async(&params).await].concat()
+    }
+
+    #[test]
+    fn dao_watch_calls() {
+        smol::block_on(async {
+            let bulla_a = DaoProposalBulla::from(pallas::Base::from(1u64));
+            let bulla_b = DaoProposalBulla::from(pallas::Base::from(2u64));
+
+            // Undecodable DAO calls don't hide the rest of the block
+            let tx_a = tx(vec![
+                (*MONEY_CONTRACT_ID, exec_call(bulla_b).await),
+                (*DAO_CONTRACT_ID, vec![DaoFunction::Propose as u8, 42]),
+                (*DAO_CONTRACT_ID, vec![0xff]),
+                (*DAO_CONTRACT_ID, vec![]),
+                (*DAO_CONTRACT_ID, exec_call(bulla_a).await),
+            ]);
+            let tx_b = tx(vec![
+                (*DAO_CONTRACT_ID, vec![DaoFunction::Mint as u8]),
+                (*DAO_CONTRACT_ID, exec_call(bulla_b).await),
+            ]);
+
+            let block = BlockInfo { txs: vec![tx_a.clone(), tx_b.clone()], ..Default::default() };
+            assert_eq!(
+                dao_calls(&block).await,
+                vec![
+                    (DaoEventKind::Exec, bulla_a, tx_a.hash()),
+                    (DaoEventKind::Exec, bulla_b, tx_b.hash()),
+                ]
+            );
+        })
+    }
+
+    #[test]
+    fn dao_watch_closes_next() {
+        let mut proposal = ProposalRecord {
+            proposal: DaoProposal {
+                auth_calls: vec![],
+                creation_blockwindow: 10,
+                duration_blockwindows: 5,
+                user_data: pallas::Base::ZERO,
+                dao_bulla: DaoBulla::from(pallas::Base::ZERO),
+                blind: BaseBlind::random(&mut OsRng),
+            },
+            data: None,
+            leaf_position: None,
+            money_snapshot_tree: None,
+            nullifiers_smt_snapshot: None,
+            tx_hash: None,
+            call_index: None,
+            exec_tx_hash: None,
+        };
+
+        // Proposals not yet on chain are skipped
+        assert!(!closes_next(&proposal, 14));
+
+        proposal.leaf_position = Some(0u64.into());
+        assert!(!closes_next(&proposal, 13));
+        assert!(closes_next(&proposal, 14));
+        assert!(!closes_next(&proposal, 15));
+
+        // So are executed ones
+        proposal.exec_tx_hash = Some(TransactionHash([0u8; 32]));
+        assert!(!closes_next(&proposal, 14));
+    }
+
+    #[test]
+    fn dao_watch_reorg_dedup() {
+        smol::block_on(async {
+            let watcher = DaoWatcher::new(false, None);
+            let tx_hash = TransactionHash([1u8; 32]);
+            let bulla = DaoProposalBulla::from(pallas::Base::from(1u64));
+
+            assert!(watcher.mark_notified(tx_hash, DaoEventKind::Vote, bulla, 10).await);
+
+            // The same transaction re-included after a reorg
+            assert!(!watcher.mark_notified(tx_hash, DaoEventKind::Vote, bulla, 10).await);
+            assert!(!watcher.mark_notified(tx_hash, DaoEventKind::Vote, bulla, 11).await);
+
+            // Other events of the transaction are still notified
+            assert!(watcher.mark_notified(tx_hash, DaoEventKind::Exec, bulla, 11).await);
+
+            // Old enough notifications get forgotten
+            let height = 11 + NOTIFIED_DEPTH;
+            assert!(watcher.mark_notified(tx_hash, DaoEventKind::Vote, bulla, height).await);
+        })
+    }
+}
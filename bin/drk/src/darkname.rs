@@ -0,0 +1,549 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2025 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use lazy_static::lazy_static;
+use rusqlite::types::Value;
+
+use darkfi::{
+    tx::{ContractCallLeaf, Transaction, TransactionBuilder},
+    zk::{empty_witnesses, ProvingKey, ZkCircuit},
+    zkas::ZkBinary,
+    Error, Result,
+};
+use darkfi_darkname_contract::{
+    client::{
+        commit_v1::DarknameCommitCallBuilder, register_v1::DarknameRegisterCallBuilder,
+        renew_v1::DarknameRenewCallBuilder, transfer_v1::DarknameTransferCallBuilder, NameOwner,
+    },
+    model::{
+        DarknameRegisterParamsV1, DarknameRenewParamsV1, DarknameTransferParamsV1, NameId,
+        NameRecord, NameTarget,
+    },
+    DarknameFunction, DARKNAME_CONTRACT_ZKAS_NAME_OWNERSHIP_NS,
+};
+use darkfi_sdk::{crypto::DARKNAME_CONTRACT_ID, pasta::pallas, ContractCall};
+use darkfi_serial::{deserialize_async, serialize_async, AsyncEncodable};
+
+use crate::{
+    convert_named_params,
+    error::{WalletDbError, WalletDbResult},
+    Drk,
+};
+
+// Wallet SQL table constant names. These have to represent the `darkname.sql`
+// SQL schema. Table names are prefixed with the contract ID to avoid collisions.
+lazy_static! {
+    pub static ref DARKNAME_NAMES_TABLE: String =
+        format!("{}_darkname_names", DARKNAME_CONTRACT_ID.to_string());
+    pub static ref DARKNAME_OWNED_TABLE: String =
+        format!("{}_darkname_owned", DARKNAME_CONTRACT_ID.to_string());
+}
+
+// DARKNAME_NAMES_TABLE
+pub const DARKNAME_NAMES_COL_NAME_ID: &str = "name_id";
+pub const DARKNAME_NAMES_COL_NAME: &str = "name";
+pub const DARKNAME_NAMES_COL_RECORD: &str = "record";
+
+// DARKNAME_OWNED_TABLE
+pub const DARKNAME_OWNED_COL_ID: &str = "id";
+pub const DARKNAME_OWNED_COL_NAME: &str = "name";
+pub const DARKNAME_OWNED_COL_OWNER: &str = "owner";
+pub const DARKNAME_OWNED_COL_SECRET: &str = "secret";
+pub const DARKNAME_OWNED_COL_BLIND: &str = "blind";
+
+impl Drk {
+    /// Initialize wallet with tables for the Darkname contract.
+    pub fn initialize_darkname(&self) -> WalletDbResult<()> {
+        // Initialize Darkname wallet schema. Since table names are prefixed
+        // with the contract ID, we fill it in here.
+        let wallet_schema = include_str!("../darkname.sql")
+            .replace("{DARKNAME_CONTRACT_ID}", &DARKNAME_CONTRACT_ID.to_string());
+        self.wallet.exec_batch_sql(&wallet_schema)?;
+
+        Ok(())
+    }
+
+    /// Reset the names records in the wallet. Owned names secrets are kept,
+    /// so ownership gets restored when rescanning.
+    pub fn reset_darkname_names(&self) -> WalletDbResult<()> {
+        println!("Resetting Darkname names");
+        let query = format!("DELETE FROM {};", *DARKNAME_NAMES_TABLE);
+        self.wallet.exec_sql(&query, &[])?;
+        println!("Successfully reset Darkname names");
+
+        Ok(())
+    }
+
+    /// Fetch the on-chain record of the given name from the wallet, if we have seen it.
+    pub async fn get_name_record(&self, name_id: &NameId) -> Result<Option<NameRecord>> {
+        let row = match self.wallet.query_single(
+            &DARKNAME_NAMES_TABLE,
+            &[DARKNAME_NAMES_COL_RECORD],
+            convert_named_params! {(DARKNAME_NAMES_COL_NAME_ID, serialize_async(name_id).await)},
+        ) {
+            Ok(r) => r,
+            Err(WalletDbError::RowNotFound) => return Ok(None),
+            Err(e) => {
+                return Err(Error::DatabaseError(format!(
+                    "[get_name_record] Name record retrieval failed: {e:?}"
+                )))
+            }
+        };
+
+        let Value::Blob(ref record_bytes) = row[0] else {
+            return Err(Error::ParseFailed("[get_name_record] Name record bytes parsing failed"))
+        };
+
+        Ok(Some(deserialize_async(record_bytes).await?))
+    }
+
+    /// Resolve the given name to its target, using the last scanned block
+    /// height to check the registration hasn't expired.
+    pub async fn resolve_name(&self, name: &str) -> Result<NameTarget> {
+        let Some(record) = self.get_name_record(&NameId::derive(name)).await? else {
+            return Err(Error::Custom(format!("Name {name} is not registered")))
+        };
+
+        let (height, _) = match self.get_last_scanned_block() {
+            Ok(v) => v,
+            Err(e) => {
+                return Err(Error::DatabaseError(format!(
+                    "[resolve_name] Retrieving last scanned block failed: {e:?}"
+                )))
+            }
+        };
+
+        if record.expiry < height {
+            return Err(Error::Custom(format!("Name {name} has expired")))
+        }
+
+        Ok(record.target)
+    }
+
+    /// Generate a new `NameOwner` for the given name and store it in the wallet.
+    /// Returns the generated owner along with its commitment.
+    pub async fn put_name_owner(&self, name: &str) -> Result<(NameOwner, pallas::Base)> {
+        let owner = NameOwner::random();
+        let commitment = owner.commitment(&NameId::derive(name));
+
+        let query = format!(
+            "INSERT INTO {} ({}, {}, {}, {}) VALUES (?1, ?2, ?3, ?4);",
+            *DARKNAME_OWNED_TABLE,
+            DARKNAME_OWNED_COL_NAME,
+            DARKNAME_OWNED_COL_OWNER,
+            DARKNAME_OWNED_COL_SECRET,
+            DARKNAME_OWNED_COL_BLIND,
+        );
+        if let Err(e) = self.wallet.exec_sql(
+            &query,
+            rusqlite::params![
+                name,
+                serialize_async(&commitment).await,
+                serialize_async(&owner.secret).await,
+                serialize_async(&owner.blind).await,
+            ],
+        ) {
+            return Err(Error::DatabaseError(format!(
+                "[put_name_owner] Inserting name owner failed: {e:?}"
+            )))
+        }
+
+        Ok((owner, commitment))
+    }
+
+    /// Fetch the `NameOwner` of the given owner commitment from the wallet, if we have it.
+    async fn get_name_owner(&self, commitment: &pallas::Base) -> Result<Option<NameOwner>> {
+        let row = match self.wallet.query_single(
+            &DARKNAME_OWNED_TABLE,
+            &[DARKNAME_OWNED_COL_SECRET, DARKNAME_OWNED_COL_BLIND],
+            convert_named_params! {(DARKNAME_OWNED_COL_OWNER, serialize_async(commitment).await)},
+        ) {
+            Ok(r) => r,
+            Err(WalletDbError::RowNotFound) => return Ok(None),
+            Err(e) => {
+                return Err(Error::DatabaseError(format!(
+                    "[get_name_owner] Name owner retrieval failed: {e:?}"
+                )))
+            }
+        };
+
+        let Value::Blob(ref secret_bytes) = row[0] else {
+            return Err(Error::ParseFailed("[get_name_owner] Secret bytes parsing failed"))
+        };
+        let Value::Blob(ref blind_bytes) = row[1] else {
+            return Err(Error::ParseFailed("[get_name_owner] Blind bytes parsing failed"))
+        };
+
+        Ok(Some(NameOwner {
+            secret: deserialize_async(secret_bytes).await?,
+            blind: deserialize_async(blind_bytes).await?,
+        }))
+    }
+
+    /// List the names currently owned by the wallet, along with their records.
+    pub async fn list_owned_names(&self) -> Result<Vec<(String, NameRecord)>> {
+        let rows = match self.wallet.query_multiple(
+            &DARKNAME_OWNED_TABLE,
+            &[DARKNAME_OWNED_COL_NAME, DARKNAME_OWNED_COL_OWNER],
+            &[],
+        ) {
+            Ok(r) => r,
+            Err(e) => {
+                return Err(Error::DatabaseError(format!(
+                    "[list_owned_names] Owned names retrieval failed: {e:?}"
+                )))
+            }
+        };
+
+        let mut ret = vec![];
+        for row in rows {
+            let Value::Text(ref name) = row[0] else {
+                return Err(Error::ParseFailed("[list_owned_names] Name parsing failed"))
+            };
+            let Value::Blob(ref owner_bytes) = row[1] else {
+                return Err(Error::ParseFailed("[list_owned_names] Owner bytes parsing failed"))
+            };
+            let owner: pallas::Base = deserialize_async(owner_bytes).await?;
+
+            // Only names whose current owner is this commitment are ours
+            let Some(record) = self.get_name_record(&NameId::derive(name)).await? else { continue };
+            if record.owner == owner {
+                ret.push((name.clone(), record));
+            }
+        }
+
+        Ok(ret)
+    }
+
+    /// Store the given name record in the wallet, and its inverse query into the cache.
+    async fn put_name_record(
+        &self,
+        name_id: &NameId,
+        name: Option<&str>,
+        record: &NameRecord,
+    ) -> Result<()> {
+        let key = serialize_async(name_id).await;
+
+        // Create its inverse query, restoring the previous record if one existed
+        let inverse = match self.get_name_record(name_id).await? {
+            Some(previous) => self.wallet.create_prepared_statement(
+                &format!(
+                    "UPDATE {} SET {} = ?1 WHERE {} = ?2;",
+                    *DARKNAME_NAMES_TABLE, DARKNAME_NAMES_COL_RECORD, DARKNAME_NAMES_COL_NAME_ID,
+                ),
+                rusqlite::params![serialize_async(&previous).await, key],
+            ),
+            None => self.wallet.create_prepared_statement(
+                &format!(
+                    "DELETE FROM {} WHERE {} = ?1;",
+                    *DARKNAME_NAMES_TABLE, DARKNAME_NAMES_COL_NAME_ID,
+                ),
+                rusqlite::params![key],
+            ),
+        };
+        let inverse = match inverse {
+            Ok(q) => q,
+            Err(e) => {
+                return Err(Error::DatabaseError(format!(
+                    "[put_name_record] Creating name record inverse query failed: {e:?}"
+                )))
+            }
+        };
+
+        // Upsert the record, keeping the known name if this call doesn't carry it
+        let query = format!(
+            "INSERT INTO {} ({}, {}, {}) VALUES (?1, ?2, ?3) ON CONFLICT({}) DO UPDATE SET {} = COALESCE(excluded.{}, {}), {} = excluded.{};",
+            *DARKNAME_NAMES_TABLE,
+            DARKNAME_NAMES_COL_NAME_ID,
+            DARKNAME_NAMES_COL_NAME,
+            DARKNAME_NAMES_COL_RECORD,
+            DARKNAME_NAMES_COL_NAME_ID,
+            DARKNAME_NAMES_COL_NAME,
+            DARKNAME_NAMES_COL_NAME,
+            DARKNAME_NAMES_COL_NAME,
+            DARKNAME_NAMES_COL_RECORD,
+            DARKNAME_NAMES_COL_RECORD,
+        );
+        if let Err(e) = self
+            .wallet
+            .exec_sql(&query, rusqlite::params![key, name, serialize_async(record).await])
+        {
+            return Err(Error::DatabaseError(format!(
+                "[put_name_record] Inserting name record failed: {e:?}"
+            )))
+        }
+
+        // Store its inverse
+        if let Err(e) = self.wallet.cache_inverse(inverse) {
+            return Err(Error::DatabaseError(format!(
+                "[put_name_record] Inserting inverse query into cache failed: {e:?}"
+            )))
+        }
+
+        Ok(())
+    }
+
+    /// Append data related to Darkname contract transactions into the wallet
+    /// database, and store their inverse queries into the cache. The contract
+    /// state is public, so we keep track of all the names we see, in order to
+    /// be able to resolve them. Returns a flag indicating if the provided call
+    /// refers to our own wallet.
+    pub async fn apply_tx_darkname_data(&self, data: &[u8], block_height: u32) -> Result<bool> {
+        // Here we mirror the state transitions of the contract
+        let (name_id, name, record) = match DarknameFunction::try_from(data[0])? {
+            DarknameFunction::RegisterV1 => {
+                let params: DarknameRegisterParamsV1 = deserialize_async(&data[1..]).await?;
+                let record = NameRecord {
+                    owner: params.owner,
+                    target: params.target,
+                    expiry: block_height + params.duration,
+                };
+                (NameId::derive(&params.name), Some(params.name), record)
+            }
+
+            DarknameFunction::RenewV1 => {
+                let params: DarknameRenewParamsV1 = deserialize_async(&data[1..]).await?;
+                let Some(mut record) = self.get_name_record(&params.name_id).await? else {
+                    return Ok(false)
+                };
+                record.expiry = record.expiry.max(block_height) + params.duration;
+                (params.name_id, None, record)
+            }
+
+            DarknameFunction::TransferV1 => {
+                let params: DarknameTransferParamsV1 = deserialize_async(&data[1..]).await?;
+                let Some(mut record) = self.get_name_record(&params.name_id).await? else {
+                    return Ok(false)
+                };
+                record.owner = params.new_owner;
+                record.target = params.new_target;
+                (params.name_id, None, record)
+            }
+
+            // Commitments don't reveal the name, so there is nothing to track
+            DarknameFunction::CommitV1 => return Ok(false),
+        };
+
+        self.put_name_record(&name_id, name.as_deref(), &record).await?;
+
+        Ok(self.get_name_owner(&record.owner).await?.is_some())
+    }
+
+    /// Auxiliary function to build the `NameOwnership` circuit proving key.
+    async fn darkname_ownership_pk(&self) -> Result<(ZkBinary, ProvingKey)> {
+        let zkas_bins = self.lookup_zkas(&DARKNAME_CONTRACT_ID).await?;
+
+        let Some(ownership_zkbin) =
+            zkas_bins.iter().find(|x| x.0 == DARKNAME_CONTRACT_ZKAS_NAME_OWNERSHIP_NS)
+        else {
+            return Err(Error::Custom("Name ownership circuit not found".to_string()))
+        };
+
+        let ownership_zkbin = ZkBinary::decode(&ownership_zkbin.1)?;
+        let ownership_circuit =
+            ZkCircuit::new(empty_witnesses(&ownership_zkbin)?, &ownership_zkbin);
+        let ownership_pk = ProvingKey::build(ownership_zkbin.k, &ownership_circuit);
+
+        Ok((ownership_zkbin, ownership_pk))
+    }
+
+    /// Auxiliary function to fetch the record of a name we own, along with its owner.
+    async fn get_owned_name(&self, name: &str) -> Result<(NameId, NameRecord, NameOwner)> {
+        let name_id = NameId::derive(name);
+        let Some(record) = self.get_name_record(&name_id).await? else {
+            return Err(Error::Custom(format!("Name {name} is not registered")))
+        };
+
+        let Some(owner) = self.get_name_owner(&record.owner).await? else {
+            return Err(Error::Custom(format!("Name {name} is not owned by this wallet")))
+        };
+
+        Ok((name_id, record, owner))
+    }
+
+    /// Fetch the `NameOwner` generated by our latest commitment to the
+    /// given name from the wallet.
+    async fn get_committed_name_owner(&self, name: &str) -> Result<NameOwner> {
+        let rows = match self.wallet.query_multiple(
+            &DARKNAME_OWNED_TABLE,
+            &[DARKNAME_OWNED_COL_ID, DARKNAME_OWNED_COL_SECRET, DARKNAME_OWNED_COL_BLIND],
+            convert_named_params! {(DARKNAME_OWNED_COL_NAME, name)},
+        ) {
+            Ok(r) => r,
+            Err(e) => {
+                return Err(Error::DatabaseError(format!(
+                    "[get_committed_name_owner] Name owners retrieval failed: {e:?}"
+                )))
+            }
+        };
+
+        let mut latest = None;
+        for row in rows {
+            let Value::Integer(id) = row[0] else {
+                return Err(Error::ParseFailed("[get_committed_name_owner] ID parsing failed"))
+            };
+            let Value::Blob(ref secret_bytes) = row[1] else {
+                return Err(Error::ParseFailed("[get_committed_name_owner] Secret parsing failed"))
+            };
+            let Value::Blob(ref blind_bytes) = row[2] else {
+                return Err(Error::ParseFailed("[get_committed_name_owner] Blind parsing failed"))
+            };
+
+            if latest.as_ref().is_some_and(|(latest_id, _)| *latest_id > id) {
+                continue
+            }
+
+            let owner = NameOwner {
+                secret: deserialize_async(secret_bytes).await?,
+                blind: deserialize_async(blind_bytes).await?,
+            };
+            latest = Some((id, owner));
+        }
+
+        let Some((_, owner)) = latest else {
+            return Err(Error::Custom(format!("No commitment found for name {name}")))
+        };
+
+        Ok(owner)
+    }
+
+    /// Create a name registration commitment transaction, without a fee call.
+    /// The name can be registered with `darkname_register()` once the
+    /// commitment has been on-chain for `NAME_COMMIT_MIN_AGE` blocks.
+    pub async fn darkname_commit(&self, name: &str) -> Result<Transaction> {
+        // Generate the name owner and keep it in the wallet
+        let (owner, _) = self.put_name_owner(name).await?;
+
+        // Create the contract call
+        let commit_call = DarknameCommitCallBuilder { name: name.to_string(), owner };
+        let commit_debris = commit_call.build()?;
+
+        // Encode the call
+        let mut data = vec![DarknameFunction::CommitV1 as u8];
+        commit_debris.params.encode_async(&mut data).await?;
+        let call = ContractCall { contract_id: *DARKNAME_CONTRACT_ID, data };
+        let mut tx_builder =
+            TransactionBuilder::new(ContractCallLeaf { call, proofs: vec![] }, vec![])?;
+
+        // Committing requires no signatures
+        let mut tx = tx_builder.build()?;
+        tx.signatures = vec![vec![]];
+
+        Ok(tx)
+    }
+
+    /// Create a name registration transaction, without a fee call, revealing
+    /// our latest commitment to the name.
+    pub async fn darkname_register(
+        &self,
+        name: &str,
+        target: NameTarget,
+        duration: u32,
+    ) -> Result<Transaction> {
+        // Grab the name owner we committed to
+        let owner = self.get_committed_name_owner(name).await?;
+
+        // Create the contract call
+        let register_call =
+            DarknameRegisterCallBuilder { name: name.to_string(), owner, target, duration };
+        let register_debris = register_call.build()?;
+
+        // Encode the call
+        let mut data = vec![DarknameFunction::RegisterV1 as u8];
+        register_debris.params.encode_async(&mut data).await?;
+        let call = ContractCall { contract_id: *DARKNAME_CONTRACT_ID, data };
+        let mut tx_builder =
+            TransactionBuilder::new(ContractCallLeaf { call, proofs: vec![] }, vec![])?;
+
+        // Registration requires no signatures
+        let mut tx = tx_builder.build()?;
+        tx.signatures = vec![vec![]];
+
+        Ok(tx)
+    }
+
+    /// Create a name renewal transaction, without a fee call.
+    pub async fn darkname_renew(&self, name: &str, duration: u32) -> Result<Transaction> {
+        let (name_id, _, owner) = self.get_owned_name(name).await?;
+        let (ownership_zkbin, ownership_pk) = self.darkname_ownership_pk().await?;
+
+        // Create the contract call
+        let renew_call = DarknameRenewCallBuilder {
+            name_id,
+            owner,
+            duration,
+            ownership_zkbin: &ownership_zkbin,
+            ownership_pk: &ownership_pk,
+        };
+        let renew_debris = renew_call.build()?;
+
+        // Encode the call
+        let mut data = vec![DarknameFunction::RenewV1 as u8];
+        renew_debris.params.encode_async(&mut data).await?;
+        let call = ContractCall { contract_id: *DARKNAME_CONTRACT_ID, data };
+        let mut tx_builder = TransactionBuilder::new(
+            ContractCallLeaf { call, proofs: renew_debris.proofs },
+            vec![],
+        )?;
+
+        let mut tx = tx_builder.build()?;
+        let sigs = tx.create_sigs(&[renew_debris.signature_secret])?;
+        tx.signatures = vec![sigs];
+
+        Ok(tx)
+    }
+
+    /// Create a name transfer transaction, without a fee call. The recipient
+    /// has to provide the owner commitment generated by `drk name receive`.
+    /// If no new target is provided, the current one is kept.
+    pub async fn darkname_transfer(
+        &self,
+        name: &str,
+        new_owner: pallas::Base,
+        new_target: Option<NameTarget>,
+    ) -> Result<Transaction> {
+        let (name_id, record, owner) = self.get_owned_name(name).await?;
+        let (ownership_zkbin, ownership_pk) = self.darkname_ownership_pk().await?;
+
+        // Create the contract call
+        let transfer_call = DarknameTransferCallBuilder {
+            name_id,
+            owner,
+            new_owner,
+            new_target: new_target.unwrap_or(record.target),
+            ownership_zkbin: &ownership_zkbin,
+            ownership_pk: &ownership_pk,
+        };
+        let transfer_debris = transfer_call.build()?;
+
+        // Encode the call
+        let mut data = vec![DarknameFunction::TransferV1 as u8];
+        transfer_debris.params.encode_async(&mut data).await?;
+        let call = ContractCall { contract_id: *DARKNAME_CONTRACT_ID, data };
+        let mut tx_builder = TransactionBuilder::new(
+            ContractCallLeaf { call, proofs: transfer_debris.proofs },
+            vec![],
+        )?;
+
+        let mut tx = tx_builder.build()?;
+        let sigs = tx.create_sigs(&[transfer_debris.signature_secret])?;
+        tx.signatures = vec![sigs];
+
+        Ok(tx)
+    }
+}
@@ -0,0 +1,371 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2025 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use std::{collections::HashMap, fmt};
+
+use darkfi::{rpc::util::JsonValue, Error, Result};
+use darkfi_money_contract::{client::OwnCoin, model::Coin};
+use darkfi_sdk::{
+    bridgetree::{Hashable, Level},
+    crypto::{MerkleNode, MerkleTree},
+};
+use darkfi_serial::serialize_async;
+
+use crate::Drk;
+
+/// A wallet state anomaly found by the wallet doctor
+#[derive(Debug)]
+pub enum Anomaly {
+    /// Unspent coin without a Merkle witness in the wallet tree,
+    /// along with the block height it was found at, if known
+    UnwitnessedCoin(String, Option<u32>),
+    /// Unspent coin whose Merkle path no longer resolves to the
+    /// wallet tree root, along with the block height it was found
+    /// at, if known
+    InvalidWitness(String, Option<u32>),
+    /// Coins sharing the same note blind or leaf position, along with
+    /// the lowest block height they were found at, if known
+    DuplicateNotes(Vec<String>, Option<u32>),
+    /// Broadcasted transaction neither confirmed nor pending in darkfid
+    StaleTx(String),
+    /// Broadcasted transaction confirmed in darkfid, but not by our scan
+    UnscannedTx(String),
+}
+
+impl Anomaly {
+    /// Actionable fix for the anomaly
+    pub fn fix(&self) -> String {
+        match self {
+            Self::UnwitnessedCoin(_, height) |
+            Self::InvalidWitness(_, height) |
+            Self::DuplicateNotes(_, height) => match height {
+                Some(h) => format!("drk scan --reset {}", h.saturating_sub(1)),
+                None => String::from("drk scan --reset 0"),
+            },
+            Self::StaleTx(tx_hash) => {
+                format!("drk explorer txs-history {tx_hash} --encode | drk broadcast")
+            }
+            Self::UnscannedTx(_) => String::from("drk scan"),
+        }
+    }
+}
+
+impl fmt::Display for Anomaly {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::UnwitnessedCoin(coin, _) => write!(f, "Coin {coin} has no Merkle witness"),
+            Self::InvalidWitness(coin, _) => {
+                write!(f, "Coin {coin} Merkle path does not verify against the tree root")
+            }
+            Self::DuplicateNotes(coins, _) => {
+                write!(f, "Coins {} share the same note", coins.join(", "))
+            }
+            Self::StaleTx(tx_hash) => {
+                write!(f, "Transaction {tx_hash} is neither confirmed nor pending")
+            }
+            Self::UnscannedTx(tx_hash) => {
+                write!(f, "Transaction {tx_hash} is confirmed but was not scanned")
+            }
+        }
+    }
+}
+
+/// Auxiliary function to verify an `OwnCoin` Merkle path against the
+/// given tree root. Returns `None` if the tree has no witness for it.
+pub fn verify_coin_witness(tree: &MerkleTree, root: &MerkleNode, coin: &OwnCoin) -> Option<bool> {
+    let path = tree.witness(coin.leaf_position, 0).ok()?;
+
+    let pos = u64::from(coin.leaf_position);
+    let mut current = MerkleNode::from(coin.coin.inner());
+    for (i, sibling) in path.iter().enumerate() {
+        let level = Level::from(i as u8);
+        current = if (pos >> i) & 1 == 0 {
+            MerkleNode::combine(level, &current, sibling)
+        } else {
+            MerkleNode::combine(level, sibling, &current)
+        };
+    }
+
+    Some(&current == root)
+}
+
+impl Drk {
+    /// Auxiliary function to encode a coin the way the CLI accepts it.
+    async fn encode_coin(coin: &Coin) -> String {
+        bs58::encode(&serialize_async(&coin.inner()).await).into_string()
+    }
+
+    /// Find the block heights our coins were found at, by looking up
+    /// the inverse queries of their insertion in the scanned blocks
+    /// rollback queries.
+    async fn coins_heights(&self, coins: &[OwnCoin]) -> Result<HashMap<[u8; 32], u32>> {
+        let mut inverses = HashMap::with_capacity(coins.len());
+        for coin in coins {
+            let inverse = match self.coin_insert_inverse_query(&coin.coin).await {
+                Ok(q) => q,
+                Err(e) => {
+                    return Err(Error::DatabaseError(format!(
+                        "[coins_heights] Creating Money coin insert inverse query failed: {e:?}"
+                    )))
+                }
+            };
+            inverses.insert(inverse, coin.coin.to_bytes());
+        }
+
+        let records = match self.get_scanned_block_records() {
+            Ok(r) => r,
+            Err(e) => {
+                return Err(Error::DatabaseError(format!(
+                    "[coins_heights] Scanned blocks retrieval failed: {e:?}"
+                )))
+            }
+        };
+
+        // Rollback queries concatenate the cached inverse queries, each
+        // terminated by a semicolon, so we compare them one by one.
+        let mut heights = HashMap::new();
+        for (height, _, query) in records {
+            for statement in query.split_inclusive(';') {
+                if let Some(coin) = inverses.get(statement) {
+                    heights.entry(*coin).or_insert(height);
+                }
+            }
+        }
+
+        Ok(heights)
+    }
+
+    /// Scan the wallet state for anomalies: unspent coins without a
+    /// Merkle witness or with a Merkle path that no longer verifies,
+    /// coins sharing the same note, and broadcasted transactions that
+    /// darkfid doesn't know about or that our scan missed.
+    pub async fn wallet_doctor(&self) -> Result<Vec<Anomaly>> {
+        let mut anomalies = vec![];
+
+        let tree = self.get_money_tree().await?;
+        let root = tree.root(0);
+        let coins: Vec<OwnCoin> =
+            self.get_coins(false).await?.into_iter().map(|(coin, _, _)| coin).collect();
+        let heights = self.coins_heights(&coins).await?;
+
+        // Merkle witnesses
+        for coin in &coins {
+            let height = heights.get(&coin.coin.to_bytes()).copied();
+            let verified = match root {
+                Some(ref root) => verify_coin_witness(&tree, root, coin),
+                None => None,
+            };
+            match verified {
+                Some(true) => {}
+                Some(false) => anomalies
+                    .push(Anomaly::InvalidWitness(Self::encode_coin(&coin.coin).await, height)),
+                None => anomalies
+                    .push(Anomaly::UnwitnessedCoin(Self::encode_coin(&coin.coin).await, height)),
+            }
+        }
+
+        // Duplicate notes, sharing their blind or their leaf position
+        let mut by_blind: HashMap<String, Vec<&OwnCoin>> = HashMap::new();
+        let mut by_position: HashMap<u64, Vec<&OwnCoin>> = HashMap::new();
+        for coin in &coins {
+            by_blind.entry(coin.note.coin_blind.to_string()).or_default().push(coin);
+            by_position.entry(u64::from(coin.leaf_position)).or_default().push(coin);
+        }
+        for duplicates in by_blind.into_values().chain(by_position.into_values()) {
+            if duplicates.len() < 2 {
+                continue
+            }
+
+            let mut encoded = Vec::with_capacity(duplicates.len());
+            for coin in &duplicates {
+                encoded.push(Self::encode_coin(&coin.coin).await);
+            }
+            let height =
+                duplicates.iter().filter_map(|c| heights.get(&c.coin.to_bytes())).min().copied();
+            anomalies.push(Anomaly::DuplicateNotes(encoded, height));
+        }
+
+        // Transactions still pending in our history
+        let history = match self.get_txs_history() {
+            Ok(h) => h,
+            Err(e) => {
+                return Err(Error::DatabaseError(format!(
+                    "[wallet_doctor] Transactions history retrieval failed: {e:?}"
+                )))
+            }
+        };
+        let broadcasted: Vec<String> = history
+            .into_iter()
+            .filter(|(_, status)| status == "Broadcasted")
+            .map(|(tx_hash, _)| tx_hash)
+            .collect();
+
+        if broadcasted.is_empty() {
+            return Ok(anomalies)
+        }
+
+        let rep = self.darkfid_daemon_request("tx.pending", &JsonValue::Array(vec![])).await?;
+        let pending: Vec<String> = match rep.get::<Vec<JsonValue>>() {
+            Some(hashes) => hashes.iter().filter_map(|h| h.get::<String>().cloned()).collect(),
+            None => vec![],
+        };
+
+        for tx_hash in broadcasted {
+            if pending.contains(&tx_hash) {
+                continue
+            }
+
+            let params = JsonValue::Array(vec![JsonValue::String(tx_hash.clone())]);
+            match self.darkfid_daemon_request("blockchain.get_tx", &params).await {
+                Ok(_) => anomalies.push(Anomaly::UnscannedTx(tx_hash)),
+                // Check if transaction was not found
+                Err(Error::JsonRpcError((-32113, _))) => anomalies.push(Anomaly::StaleTx(tx_hash)),
+                Err(e) => return Err(e),
+            }
+        }
+
+        Ok(anomalies)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use darkfi::zk::halo2::Field;
+    use darkfi_money_contract::{client::MoneyNote, model::DARK_TOKEN_ID};
+    use darkfi_sdk::{
+        crypto::{BaseBlind, FuncId, ScalarBlind, SecretKey},
+        pasta::pallas,
+    };
+    use rand::rngs::OsRng;
+
+    use crate::walletdb::WalletDb;
+
+    use super::*;
+
+    /// Create a random coin at the given leaf position
+    fn random_coin(leaf_position: u64) -> OwnCoin {
+        OwnCoin {
+            coin: Coin::from(pallas::Base::random(&mut OsRng)),
+            note: MoneyNote {
+                value: 1_000,
+                token_id: *DARK_TOKEN_ID,
+                spend_hook: FuncId::none(),
+                user_data: pallas::Base::ZERO,
+                coin_blind: BaseBlind::random(&mut OsRng),
+                value_blind: ScalarBlind::random(&mut OsRng),
+                token_blind: BaseBlind::random(&mut OsRng),
+                memo: vec![],
+            },
+            secret: SecretKey::random(&mut OsRng),
+            leaf_position: leaf_position.into(),
+        }
+    }
+
+    #[test]
+    fn coin_witness() {
+        let mut tree = MerkleTree::new(1);
+        let mut coins = vec![];
+        for i in 0..3 {
+            let coin = random_coin(i);
+            tree.append(MerkleNode::from(coin.coin.inner()));
+            assert_eq!(tree.mark(), Some(coin.leaf_position));
+            coins.push(coin);
+        }
+        // An unmarked leaf has no witness
+        let unmarked = random_coin(3);
+        tree.append(MerkleNode::from(unmarked.coin.inner()));
+        let root = tree.root(0).unwrap();
+
+        for coin in &coins {
+            assert_eq!(verify_coin_witness(&tree, &root, coin), Some(true));
+        }
+        assert_eq!(verify_coin_witness(&tree, &root, &unmarked), None);
+
+        // A coin pointing to another coin's leaf doesn't verify
+        let mut moved = coins[0].clone();
+        moved.leaf_position = coins[1].leaf_position;
+        assert_eq!(verify_coin_witness(&tree, &root, &moved), Some(false));
+
+        // Neither does a coin checked against a stale root
+        let mut stale_tree = MerkleTree::new(1);
+        stale_tree.append(MerkleNode::from(coins[0].coin.inner()));
+        let stale_root = stale_tree.root(0).unwrap();
+        assert_eq!(verify_coin_witness(&tree, &stale_root, &coins[0]), Some(false));
+    }
+
+    #[test]
+    fn anomaly_fix() {
+        let coin = String::from("coin");
+        let tx_hash = String::from("tx_hash");
+
+        // Coin anomalies rescan from right before the block they were found at
+        assert_eq!(Anomaly::UnwitnessedCoin(coin.clone(), Some(42)).fix(), "drk scan --reset 41");
+        assert_eq!(Anomaly::InvalidWitness(coin.clone(), Some(0)).fix(), "drk scan --reset 0");
+        assert_eq!(Anomaly::InvalidWitness(coin.clone(), None).fix(), "drk scan --reset 0");
+        assert_eq!(
+            Anomaly::DuplicateNotes(vec![coin.clone(), coin], Some(7)).fix(),
+            "drk scan --reset 6"
+        );
+
+        // Transaction anomalies rebroadcast or rescan
+        assert_eq!(
+            Anomaly::StaleTx(tx_hash.clone()).fix(),
+            "drk explorer txs-history tx_hash --encode | drk broadcast"
+        );
+        assert_eq!(Anomaly::UnscannedTx(tx_hash).fix(), "drk scan");
+    }
+
+    #[test]
+    fn coins_heights() {
+        smol::block_on(async {
+            let wallet = WalletDb::new(None, Some("foobar")).unwrap();
+            let drk = Drk::from_wallet(wallet, None, false, vec![]);
+            drk.initialize_wallet().await.unwrap();
+            drk.initialize_money().await.unwrap();
+
+            let coins: Vec<OwnCoin> = (0..3).map(random_coin).collect();
+
+            // Store a scanned block record with the cached inverse queries
+            let scan_block = |height: u32| {
+                let rollback_query = drk.wallet.grab_inverse_cache_block().unwrap();
+                drk.wallet.clear_inverse_cache().unwrap();
+                drk.put_scanned_block_record(height, &format!("block_{height}"), &rollback_query)
+                    .unwrap();
+            };
+
+            // First two coins get found at different blocks
+            let inverse = drk.coin_insert_inverse_query(&coins[0].coin).await.unwrap();
+            drk.wallet.cache_inverse(inverse).unwrap();
+            scan_block(3);
+            let inverse = drk.coin_insert_inverse_query(&coins[1].coin).await.unwrap();
+            drk.wallet.cache_inverse(inverse).unwrap();
+            scan_block(5);
+
+            // A later block referencing the first coin, without inserting
+            // it, doesn't move its height.
+            drk.mark_spent_coin(&coins[0].coin, &"tx_hash".to_string()).await.unwrap();
+            scan_block(7);
+
+            let heights = drk.coins_heights(&coins).await.unwrap();
+            assert_eq!(heights.len(), 2);
+            assert_eq!(heights.get(&coins[0].coin.to_bytes()), Some(&3));
+            assert_eq!(heights.get(&coins[1].coin.to_bytes()), Some(&5));
+            assert!(!heights.contains_key(&coins[2].coin.to_bytes()));
+        })
+    }
+}
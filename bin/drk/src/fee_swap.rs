@@ -0,0 +1,724 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2025 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use rusqlite::types::Value;
+
+use darkfi::{
+    tx::Transaction,
+    zk::{proof::ProvingKey, vm::ZkCircuit, vm_heap::empty_witnesses, Proof},
+    zkas::ZkBinary,
+    Error, Result,
+};
+use darkfi_money_contract::{
+    client::{fee_v1::FEE_CALL_GAS, transfer_v1::make_transfer_call, MoneyNote, OwnCoin},
+    model::{CoinAttributes, MoneyTransferParamsV1, TokenId},
+    MoneyFunction, MONEY_CONTRACT_ZKAS_BURN_NS_V1, MONEY_CONTRACT_ZKAS_MINT_NS_V1,
+};
+use darkfi_sdk::{
+    crypto::{contract_id::MONEY_CONTRACT_ID, FuncId, Keypair, MerkleTree, PublicKey, SecretKey},
+    dark_tree::DarkLeaf,
+    tx::ContractCall,
+};
+use darkfi_serial::{
+    async_trait, deserialize_async, serialize_async, AsyncEncodable, SerialDecodable,
+    SerialEncodable,
+};
+
+use crate::{
+    convert_named_params,
+    locale::format_amount,
+    money::{
+        BALANCE_BASE10_DECIMALS, MONEY_FEE_OFFERS_COL_OFFER, MONEY_FEE_OFFERS_COL_RECIPIENT,
+        MONEY_FEE_OFFERS_COL_TOKEN_ID, MONEY_FEE_OFFERS_TABLE, MONEY_FEE_SWAPS_COL_REQUEST,
+        MONEY_FEE_SWAPS_COL_SECRETS, MONEY_FEE_SWAPS_TABLE,
+    },
+    Drk,
+};
+
+/// Maximum number of times we rebuild the provider payment of a fee
+/// swap request, when the measured gas exceeds what we paid for.
+const FEE_SWAP_MAX_ATTEMPTS: usize = 3;
+
+/// An offer of a liquidity provider to pay transaction fees in native
+/// tokens, in exchange for a payment in another token.
+#[derive(Debug, Clone, PartialEq, Eq, SerialEncodable, SerialDecodable)]
+pub struct FeeOffer {
+    /// Token the provider accepts as payment
+    pub token_id: TokenId,
+    /// Price of a native token, in units of `token_id`
+    pub rate: u64,
+    /// Maximum fee the provider is willing to pay, in native units
+    pub max_fee: u64,
+    /// Provider address receiving the payments
+    pub recipient: PublicKey,
+}
+
+impl FeeOffer {
+    /// Amount of `token_id` the provider charges for paying `gas` native
+    /// units. Returns `None` if the fee exceeds the offer maximum.
+    pub fn quote(&self, gas: u64) -> Option<u64> {
+        if gas > self.max_fee {
+            return None
+        }
+
+        let scale = 10_u128.pow(BALANCE_BASE10_DECIMALS as u32);
+        u64::try_from((gas as u128 * self.rate as u128).div_ceil(scale)).ok()
+    }
+}
+
+/// A fee-less transaction paying a fee offer provider, waiting for the
+/// provider to attach the `Money::Fee` call.
+#[derive(Debug, Clone, SerialEncodable, SerialDecodable)]
+pub struct FeeSwapRequest {
+    /// The signed fee-less transaction
+    pub tx: Transaction,
+    /// The offer the transaction is paying for
+    pub offer: FeeOffer,
+    /// Index of the `Money::Transfer` call paying the provider
+    pub payment_idx: u32,
+}
+
+/// Compute the wallet key of a fee swap request, committing to the calls
+/// and proofs of its fee-less transaction.
+fn fee_swap_key(calls: &[DarkLeaf<ContractCall>], proofs: &[Vec<Proof>]) -> String {
+    Transaction {
+        calls: calls.to_vec(),
+        proofs: proofs.to_vec(),
+        signatures: vec![],
+        ..Default::default()
+    }
+    .hash()
+    .to_string()
+}
+
+/// Zkas circuits and proving keys required to build `Money::Transfer` calls.
+struct TransferCircuits {
+    mint_zkbin: ZkBinary,
+    mint_pk: ProvingKey,
+    burn_zkbin: ZkBinary,
+    burn_pk: ProvingKey,
+}
+
+impl Drk {
+    /// Create a fee offer paying fees up to `max_fee` native units, in
+    /// exchange for `token_id` at the given `rate`. The offer is stored
+    /// in the wallet so we can later verify the requests against it.
+    pub async fn fee_offer_create(
+        &self,
+        token_id: TokenId,
+        rate: u64,
+        max_fee: u64,
+    ) -> Result<FeeOffer> {
+        if rate == 0 || max_fee == 0 {
+            return Err(Error::Custom("Fee offer rate and maximum fee must be positive".to_string()))
+        }
+
+        let recipient = self.default_address().await?;
+        let offer = FeeOffer { token_id, rate, max_fee, recipient };
+        self.put_fee_offer(&offer).await?;
+
+        Ok(offer)
+    }
+
+    /// Store a fee offer in the wallet, replacing any previous offer of
+    /// the same provider for the same token.
+    pub async fn put_fee_offer(&self, offer: &FeeOffer) -> Result<()> {
+        let query = format!(
+            "INSERT OR REPLACE INTO {} ({}, {}, {}) VALUES (?1, ?2, ?3);",
+            *MONEY_FEE_OFFERS_TABLE,
+            MONEY_FEE_OFFERS_COL_TOKEN_ID,
+            MONEY_FEE_OFFERS_COL_RECIPIENT,
+            MONEY_FEE_OFFERS_COL_OFFER,
+        );
+
+        if let Err(e) = self.wallet.exec_sql(
+            &query,
+            rusqlite::params![
+                serialize_async(&offer.token_id).await,
+                serialize_async(&offer.recipient).await,
+                serialize_async(offer).await,
+            ],
+        ) {
+            return Err(Error::DatabaseError(format!(
+                "[put_fee_offer] Inserting fee offer failed: {e:?}"
+            )))
+        }
+
+        Ok(())
+    }
+
+    /// Fetch all the fee offers stored in the wallet, including our own.
+    pub async fn get_fee_offers(&self) -> Result<Vec<FeeOffer>> {
+        let rows = match self.wallet.query_multiple(
+            &MONEY_FEE_OFFERS_TABLE,
+            &[MONEY_FEE_OFFERS_COL_OFFER],
+            convert_named_params! {},
+        ) {
+            Ok(r) => r,
+            Err(e) => {
+                return Err(Error::DatabaseError(format!(
+                    "[get_fee_offers] Fee offers retrieval failed: {e:?}"
+                )))
+            }
+        };
+
+        let mut ret = Vec::with_capacity(rows.len());
+        for row in rows {
+            let Value::Blob(ref offer_bytes) = row[0] else {
+                return Err(Error::ParseFailed("[get_fee_offers] Offer bytes parsing failed"))
+            };
+            ret.push(deserialize_async(offer_bytes).await?);
+        }
+
+        Ok(ret)
+    }
+
+    /// Find the best route to pay a fee of `gas` native units through the
+    /// imported fee offers, skipping the `spent_coins`. Offers are ranked
+    /// by the share of our token balance their payment costs. Returns the
+    /// offer along with the payment amount and the coins to spend.
+    pub async fn fee_route(
+        &self,
+        gas: u64,
+        spent_coins: &[OwnCoin],
+    ) -> Result<Option<(FeeOffer, u64, Vec<OwnCoin>)>> {
+        let own_addresses: Vec<PublicKey> =
+            self.addresses().await?.into_iter().map(|(_, public, _, _)| public).collect();
+
+        let mut best: Option<(FeeOffer, u64, Vec<OwnCoin>)> = None;
+        let mut best_share = f64::MAX;
+        for offer in self.get_fee_offers().await? {
+            // Skip the offers we made ourselves
+            if own_addresses.contains(&offer.recipient) {
+                continue
+            }
+
+            let Some(amount) = offer.quote(gas) else { continue };
+
+            let mut coins = self.get_token_coins(&offer.token_id).await?;
+            coins.retain(|c| !spent_coins.contains(c));
+            let balance: u64 = coins.iter().map(|c| c.note.value).sum();
+            if balance < amount {
+                continue
+            }
+
+            let share = amount as f64 / balance as f64;
+            if share < best_share {
+                best_share = share;
+                best = Some((offer, amount, coins));
+            }
+        }
+
+        Ok(best)
+    }
+
+    /// Fetch the `Money::Transfer` circuits and build their proving keys.
+    async fn transfer_circuits(&self) -> Result<TransferCircuits> {
+        let zkas_bins = self.lookup_zkas(&MONEY_CONTRACT_ID).await?;
+
+        let Some(mint_zkbin) = zkas_bins.iter().find(|x| x.0 == MONEY_CONTRACT_ZKAS_MINT_NS_V1)
+        else {
+            return Err(Error::Custom("Mint circuit not found".to_string()))
+        };
+
+        let Some(burn_zkbin) = zkas_bins.iter().find(|x| x.0 == MONEY_CONTRACT_ZKAS_BURN_NS_V1)
+        else {
+            return Err(Error::Custom("Burn circuit not found".to_string()))
+        };
+
+        let mint_zkbin = ZkBinary::decode(&mint_zkbin.1)?;
+        let burn_zkbin = ZkBinary::decode(&burn_zkbin.1)?;
+
+        let mint_circuit = ZkCircuit::new(empty_witnesses(&mint_zkbin)?, &mint_zkbin);
+        let burn_circuit = ZkCircuit::new(empty_witnesses(&burn_zkbin)?, &burn_zkbin);
+
+        let mint_pk = ProvingKey::build(mint_zkbin.k, &mint_circuit);
+        let burn_pk = ProvingKey::build(burn_zkbin.k, &burn_circuit);
+
+        Ok(TransferCircuits { mint_zkbin, mint_pk, burn_zkbin, burn_pk })
+    }
+
+    /// Append a `Money::Transfer` call to the given transaction, without
+    /// signing it. Returns the call signature secrets and the spent coins.
+    #[allow(clippy::too_many_arguments)]
+    async fn append_transfer_call(
+        &self,
+        tx: &mut Transaction,
+        circuits: &TransferCircuits,
+        tree: &MerkleTree,
+        amount: u64,
+        token_id: TokenId,
+        recipient: PublicKey,
+        recipient_view_key: Option<PublicKey>,
+        owncoins: Vec<OwnCoin>,
+    ) -> Result<(Vec<SecretKey>, Vec<OwnCoin>)> {
+        let keypair = Keypair::new(self.default_secret().await?);
+
+        let (params, secrets, spent_coins) = make_transfer_call(
+            keypair,
+            recipient,
+            recipient_view_key,
+            amount,
+            token_id,
+            owncoins,
+            tree.clone(),
+            None,
+            None,
+            circuits.mint_zkbin.clone(),
+            circuits.mint_pk.clone(),
+            circuits.burn_zkbin.clone(),
+            circuits.burn_pk.clone(),
+            false,
+        )?;
+
+        let mut data = vec![MoneyFunction::TransferV1 as u8];
+        params.encode_async(&mut data).await?;
+        let call = ContractCall { contract_id: *MONEY_CONTRACT_ID, data };
+
+        tx.calls.push(DarkLeaf { data: call, parent_index: None, children_indexes: vec![] });
+        tx.proofs.push(secrets.proofs);
+        tx.signatures.push(vec![]);
+
+        Ok((secrets.signature_secrets, spent_coins))
+    }
+
+    /// Create a fee swap request for a payment of `amount` of `token_id`
+    /// to `recipient`. The fee token is selected automatically among our
+    /// balances, using the best route through the imported fee offers.
+    pub async fn fee_swap_transfer(
+        &self,
+        amount: u64,
+        token_id: TokenId,
+        recipient: PublicKey,
+        recipient_view_key: Option<PublicKey>,
+    ) -> Result<FeeSwapRequest> {
+        let owncoins = self.get_token_coins(&token_id).await?;
+        let balance: u64 = owncoins.iter().map(|c| c.note.value).sum();
+        if balance < amount {
+            return Err(Error::Custom(format!(
+                "Not enough balance for token ID: {token_id}, found: {}",
+                format_amount(balance)
+            )))
+        }
+
+        let circuits = self.transfer_circuits().await?;
+        let tree = self.get_money_tree().await?;
+
+        let mut tx = Transaction::default();
+        let (secrets, spent_coins) = self
+            .append_transfer_call(
+                &mut tx,
+                &circuits,
+                &tree,
+                amount,
+                token_id,
+                recipient,
+                recipient_view_key,
+                owncoins,
+            )
+            .await?;
+
+        self.fee_swap_request(tx, vec![secrets], &spent_coins, &circuits, &tree).await
+    }
+
+    /// Append a payment to a fee offer provider to the given fee-less
+    /// transaction, created by us along with the signature `secrets` of
+    /// each of its calls. The secrets are kept in the wallet, so we can
+    /// sign the transaction again once the provider attached the fee.
+    async fn fee_swap_request(
+        &self,
+        tx: Transaction,
+        secrets: Vec<Vec<SecretKey>>,
+        spent_coins: &[OwnCoin],
+        circuits: &TransferCircuits,
+        tree: &MerkleTree,
+    ) -> Result<FeeSwapRequest> {
+        // Measure the gas of the fee-less transaction. The payment call
+        // costs about the same as a transfer, so we initially assume it
+        // doubles the used gas.
+        let mut signed = tx.clone();
+        for (i, call_secrets) in secrets.iter().enumerate() {
+            signed.signatures[i] = signed.create_sigs(call_secrets)?;
+        }
+        let mut gas = FEE_CALL_GAS + 2 * self.get_tx_gas(&signed, false).await?;
+
+        let Some((offer, _, coins)) = self.fee_route(gas, spent_coins).await? else {
+            return Err(Error::Custom(
+                "No fee offer found to pay the transaction fee with our balances".to_string(),
+            ))
+        };
+
+        let payment_idx = tx.calls.len();
+        for _ in 0..FEE_SWAP_MAX_ATTEMPTS {
+            let Some(amount) = offer.quote(gas) else {
+                return Err(Error::Custom(format!(
+                    "Transaction fee exceeds the fee offer maximum of {}",
+                    format_amount(offer.max_fee)
+                )))
+            };
+
+            let mut request_tx = tx.clone();
+            let (payment_secrets, _) = self
+                .append_transfer_call(
+                    &mut request_tx,
+                    circuits,
+                    tree,
+                    amount,
+                    offer.token_id,
+                    offer.recipient,
+                    None,
+                    coins.clone(),
+                )
+                .await?;
+
+            let mut request_secrets = secrets.clone();
+            request_secrets.push(payment_secrets);
+            for (i, call_secrets) in request_secrets.iter().enumerate() {
+                request_tx.signatures[i] = request_tx.create_sigs(call_secrets)?;
+            }
+
+            // Check the payment covers the actual gas, otherwise retry
+            // paying for it.
+            gas = FEE_CALL_GAS + self.get_tx_gas(&request_tx, false).await?;
+            if offer.quote(gas).is_some_and(|required| required <= amount) {
+                let key = fee_swap_key(&request_tx.calls, &request_tx.proofs);
+                self.put_fee_swap_secrets(&key, &request_secrets).await?;
+
+                return Ok(FeeSwapRequest { tx: request_tx, offer, payment_idx: payment_idx as u32 })
+            }
+        }
+
+        Err(Error::Custom("Failed to estimate the fee swap payment".to_string()))
+    }
+
+    /// Store the signature secrets of a fee swap request in the wallet.
+    async fn put_fee_swap_secrets(&self, key: &str, secrets: &[Vec<SecretKey>]) -> Result<()> {
+        let query = format!(
+            "INSERT OR REPLACE INTO {} ({}, {}) VALUES (?1, ?2);",
+            *MONEY_FEE_SWAPS_TABLE, MONEY_FEE_SWAPS_COL_REQUEST, MONEY_FEE_SWAPS_COL_SECRETS,
+        );
+
+        if let Err(e) = self
+            .wallet
+            .exec_sql(&query, rusqlite::params![key, serialize_async(&secrets.to_vec()).await])
+        {
+            return Err(Error::DatabaseError(format!(
+                "[put_fee_swap_secrets] Inserting fee swap secrets failed: {e:?}"
+            )))
+        }
+
+        Ok(())
+    }
+
+    /// Fetch the signature secrets of a fee swap request from the wallet.
+    async fn get_fee_swap_secrets(&self, key: &str) -> Result<Option<Vec<Vec<SecretKey>>>> {
+        let rows = match self.wallet.query_multiple(
+            &MONEY_FEE_SWAPS_TABLE,
+            &[MONEY_FEE_SWAPS_COL_SECRETS],
+            convert_named_params! {(MONEY_FEE_SWAPS_COL_REQUEST, key)},
+        ) {
+            Ok(r) => r,
+            Err(e) => {
+                return Err(Error::DatabaseError(format!(
+                    "[get_fee_swap_secrets] Fee swap secrets retrieval failed: {e:?}"
+                )))
+            }
+        };
+
+        let Some(row) = rows.first() else { return Ok(None) };
+        let Value::Blob(ref secrets_bytes) = row[0] else {
+            return Err(Error::ParseFailed("[get_fee_swap_secrets] Secrets bytes parsing failed"))
+        };
+
+        Ok(Some(deserialize_async(secrets_bytes).await?))
+    }
+
+    /// Fund a fee swap request paying one of our fee offers, by attaching
+    /// the fee call to its transaction, after verifying the payment covers
+    /// the required fee.
+    pub async fn fee_swap_fund(&self, request: FeeSwapRequest) -> Result<Transaction> {
+        let offer = &request.offer;
+        if !self.get_fee_offers().await?.contains(offer) {
+            return Err(Error::Custom("Fee offer was not found in the wallet".to_string()))
+        }
+
+        let Some(secret) = self
+            .get_money_secrets()
+            .await?
+            .into_iter()
+            .find(|s| PublicKey::from_secret(*s) == offer.recipient)
+        else {
+            return Err(Error::Custom("Fee offer recipient is not one of our addresses".to_string()))
+        };
+
+        // Grab our payment out of the provider call outputs
+        let Some(call) = request.tx.calls.get(request.payment_idx as usize) else {
+            return Err(Error::Custom("Fee swap payment index is out of bounds".to_string()))
+        };
+        if call.data.contract_id != *MONEY_CONTRACT_ID ||
+            call.data.data.first() != Some(&(MoneyFunction::TransferV1 as u8))
+        {
+            return Err(Error::Custom("Fee swap payment is not a Money::Transfer".to_string()))
+        }
+        let params: MoneyTransferParamsV1 = deserialize_async(&call.data.data[1..]).await?;
+
+        let mut paid = 0_u64;
+        for output in &params.outputs {
+            let Ok(note) = output.note.decrypt::<MoneyNote>(&secret) else { continue };
+            if note.token_id != offer.token_id || note.spend_hook != FuncId::none() {
+                continue
+            }
+
+            let coin = CoinAttributes {
+                public_key: offer.recipient,
+                value: note.value,
+                token_id: note.token_id,
+                spend_hook: note.spend_hook,
+                user_data: note.user_data,
+                blind: note.coin_blind,
+            }
+            .to_coin();
+            if coin == output.coin {
+                paid = paid.saturating_add(note.value);
+            }
+        }
+
+        let gas = FEE_CALL_GAS + self.get_tx_gas(&request.tx, false).await?;
+        let Some(required) = offer.quote(gas) else {
+            return Err(Error::Custom(format!(
+                "Transaction fee {} exceeds the fee offer maximum",
+                format_amount(gas)
+            )))
+        };
+        if paid < required {
+            return Err(Error::Custom(format!(
+                "Fee swap payment is too low, required: {}, found: {}",
+                format_amount(required),
+                format_amount(paid)
+            )))
+        }
+
+        let mut tx = request.tx;
+        self.attach_fee(&mut tx).await?;
+
+        Ok(tx)
+    }
+
+    /// Sign a funded fee swap transaction we requested, after verifying
+    /// the provider only attached the fee call to it.
+    pub async fn fee_swap_sign(&self, tx: &mut Transaction) -> Result<()> {
+        let Some(fee_call) = tx.calls.last() else {
+            return Err(Error::Custom("Transaction has no calls".to_string()))
+        };
+        if fee_call.data.contract_id != *MONEY_CONTRACT_ID ||
+            fee_call.data.data.first() != Some(&(MoneyFunction::FeeV1 as u8))
+        {
+            return Err(Error::Custom("Transaction fee call is missing".to_string()))
+        }
+
+        // The rest of the transaction must be exactly our request
+        let n = tx.calls.len() - 1;
+        let key = fee_swap_key(&tx.calls[..n], &tx.proofs[..n]);
+        let Some(secrets) = self.get_fee_swap_secrets(&key).await? else {
+            return Err(Error::Custom("Fee swap request was not found in the wallet".to_string()))
+        };
+        if secrets.len() != n || tx.signatures.len() != tx.calls.len() {
+            return Err(Error::Custom("Fee swap transaction signatures mismatch".to_string()))
+        }
+
+        for (i, call_secrets) in secrets.iter().enumerate() {
+            tx.signatures[i] = tx.create_sigs(call_secrets)?;
+        }
+
+        let query = format!(
+            "DELETE FROM {} WHERE {} = ?1;",
+            *MONEY_FEE_SWAPS_TABLE, MONEY_FEE_SWAPS_COL_REQUEST,
+        );
+        if let Err(e) = self.wallet.exec_sql(&query, rusqlite::params![key]) {
+            return Err(Error::DatabaseError(format!(
+                "[fee_swap_sign] Removing fee swap secrets failed: {e:?}"
+            )))
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use darkfi::zk::halo2::Field;
+    use darkfi_money_contract::model::Coin;
+    use darkfi_sdk::{
+        crypto::{BaseBlind, ScalarBlind},
+        pasta::pallas,
+    };
+    use rand::rngs::OsRng;
+
+    use crate::{
+        money::{
+            MONEY_COINS_COL_COIN, MONEY_COINS_COL_COIN_BLIND, MONEY_COINS_COL_IS_SPENT,
+            MONEY_COINS_COL_LEAF_POSITION, MONEY_COINS_COL_MEMO, MONEY_COINS_COL_SECRET,
+            MONEY_COINS_COL_SPEND_HOOK, MONEY_COINS_COL_TOKEN_BLIND, MONEY_COINS_COL_TOKEN_ID,
+            MONEY_COINS_COL_USER_DATA, MONEY_COINS_COL_VALUE, MONEY_COINS_COL_VALUE_BLIND,
+            MONEY_COINS_TABLE,
+        },
+        walletdb::WalletDb,
+    };
+
+    use super::*;
+
+    /// One whole token, in base units
+    const UNIT: u64 = 100_000_000;
+
+    fn offer(token_id: TokenId, rate: u64, max_fee: u64, recipient: PublicKey) -> FeeOffer {
+        FeeOffer { token_id, rate, max_fee, recipient }
+    }
+
+    fn random_token() -> TokenId {
+        TokenId::from(pallas::Base::random(&mut OsRng))
+    }
+
+    fn random_address() -> PublicKey {
+        PublicKey::from_secret(SecretKey::random(&mut OsRng))
+    }
+
+    /// Insert an unspent coin of `value` of `token_id` into the wallet
+    async fn put_coin(drk: &Drk, token_id: TokenId, value: u64) -> OwnCoin {
+        let owncoin = OwnCoin {
+            coin: Coin::from(pallas::Base::random(&mut OsRng)),
+            note: MoneyNote {
+                value,
+                token_id,
+                spend_hook: FuncId::none(),
+                user_data: pallas::Base::ZERO,
+                coin_blind: BaseBlind::random(&mut OsRng),
+                value_blind: ScalarBlind::random(&mut OsRng),
+                token_blind: BaseBlind::random(&mut OsRng),
+                memo: vec![],
+            },
+            secret: SecretKey::random(&mut OsRng),
+            leaf_position: 0u64.into(),
+        };
+
+        let query = format!(
+            "INSERT INTO {} ({}, {}, {}, {}, {}, {}, {}, {}, {}, {}, {}, {}) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12);",
+            *MONEY_COINS_TABLE,
+            MONEY_COINS_COL_COIN,
+            MONEY_COINS_COL_IS_SPENT,
+            MONEY_COINS_COL_VALUE,
+            MONEY_COINS_COL_TOKEN_ID,
+            MONEY_COINS_COL_SPEND_HOOK,
+            MONEY_COINS_COL_USER_DATA,
+            MONEY_COINS_COL_COIN_BLIND,
+            MONEY_COINS_COL_VALUE_BLIND,
+            MONEY_COINS_COL_TOKEN_BLIND,
+            MONEY_COINS_COL_SECRET,
+            MONEY_COINS_COL_LEAF_POSITION,
+            MONEY_COINS_COL_MEMO,
+        );
+        let params = rusqlite::params![
+            serialize_async(&owncoin.coin).await,
+            0,
+            serialize_async(&owncoin.note.value).await,
+            serialize_async(&owncoin.note.token_id).await,
+            serialize_async(&owncoin.note.spend_hook).await,
+            serialize_async(&owncoin.note.user_data).await,
+            serialize_async(&owncoin.note.coin_blind).await,
+            serialize_async(&owncoin.note.value_blind).await,
+            serialize_async(&owncoin.note.token_blind).await,
+            serialize_async(&owncoin.secret).await,
+            serialize_async(&owncoin.leaf_position).await,
+            serialize_async(&owncoin.note.memo).await,
+        ];
+        drk.wallet.exec_sql(&query, params).unwrap();
+
+        owncoin
+    }
+
+    #[test]
+    fn fee_offer_quote() {
+        let token_id = random_token();
+        let recipient = random_address();
+
+        // Two tokens per native token
+        let double = offer(token_id, 2 * UNIT, 1_000, recipient);
+        assert_eq!(double.quote(0), Some(0));
+        assert_eq!(double.quote(100), Some(200));
+        assert_eq!(double.quote(1_000), Some(2_000));
+        assert_eq!(double.quote(1_001), None);
+
+        // Fractional payments are rounded up in the provider's favour
+        let cheap = offer(token_id, 1, u64::MAX, recipient);
+        assert_eq!(cheap.quote(1), Some(1));
+        assert_eq!(cheap.quote(UNIT), Some(1));
+        assert_eq!(cheap.quote(UNIT + 1), Some(2));
+        let one_and_half = offer(token_id, UNIT + UNIT / 2, 1_000, recipient);
+        assert_eq!(one_and_half.quote(3), Some(5));
+        assert_eq!(one_and_half.quote(4), Some(6));
+
+        // Payments not fitting in a u64 can't be quoted
+        let pricey = offer(token_id, u64::MAX, u64::MAX, recipient);
+        assert_eq!(pricey.quote(UNIT), Some(u64::MAX));
+        assert_eq!(pricey.quote(UNIT + 1), None);
+        assert_eq!(pricey.quote(u64::MAX), None);
+    }
+
+    #[test]
+    fn fee_route_selection() {
+        smol::block_on(async {
+            let wallet = WalletDb::new(None, Some("foobar")).unwrap();
+            let drk = Drk::from_wallet(wallet, None, false, vec![]);
+            drk.initialize_wallet().await.unwrap();
+            drk.initialize_money().await.unwrap();
+            let own = drk.import_money_secrets(vec![SecretKey::random(&mut OsRng)]).await.unwrap();
+
+            let (token_a, token_b, token_c) = (random_token(), random_token(), random_token());
+            let coin_a = put_coin(&drk, token_a, 1_000).await;
+            let coins_b =
+                vec![put_coin(&drk, token_b, 4_000).await, put_coin(&drk, token_b, 6_000).await];
+            put_coin(&drk, token_c, 1_000_000).await;
+
+            let offer_a = offer(token_a, UNIT, 10_000, random_address());
+            let offer_b = offer(token_b, 2 * UNIT, 10_000, random_address());
+            let offer_c = offer(token_c, UNIT, 100, random_address());
+            // Our own offer would be the cheapest, but we never route through it
+            let offer_own = offer(token_a, 1, 10_000, own[0]);
+            for offer in [&offer_a, &offer_b, &offer_c, &offer_own] {
+                drk.put_fee_offer(offer).await.unwrap();
+            }
+
+            // No offers are routed without any gas quote fitting
+            assert!(drk.fee_route(20_000, &[]).await.unwrap().is_none());
+
+            // The offer costing the smallest share of our balance wins,
+            // skipping the ones whose maximum fee is too low.
+            let (offer, amount, coins) = drk.fee_route(500, &[]).await.unwrap().unwrap();
+            assert_eq!((offer, amount, coins.len()), (offer_b.clone(), 1_000, 2));
+
+            // Spent coins don't count towards the balance
+            let (offer, amount, coins) = drk.fee_route(500, &coins_b[1..]).await.unwrap().unwrap();
+            assert_eq!((offer, amount, coins), (offer_b.clone(), 1_000, coins_b[..1].to_vec()));
+            let (offer, amount, coins) = drk.fee_route(500, &coins_b).await.unwrap().unwrap();
+            assert_eq!((offer, amount, coins), (offer_a, 500, vec![coin_a]));
+
+            // Offers we can't afford are skipped
+            let (offer, amount, _) = drk.fee_route(2_000, &[]).await.unwrap().unwrap();
+            assert_eq!((offer, amount), (offer_b, 4_000));
+        })
+    }
+}
@@ -0,0 +1,272 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2025 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use std::{
+    collections::HashMap,
+    fs,
+    path::PathBuf,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use darkfi::{net::transport::Dialer, rpc::util::JsonValue, Error, Result};
+use darkfi_serial::{async_trait, deserialize, serialize};
+use smol::io::{AsyncReadExt, AsyncWriteExt};
+use url::Url;
+
+/// Warning printed whenever fiat prices are fetched from the network
+pub const PRICE_PRIVACY_WARNING: &str = "\
+Warning: Fetching fiat prices contacts a third party. The request goes over
+Tor, but its timing and the token symbols asked for can still be linked to
+your wallet activity. Fiat values are approximate and only informational.";
+
+/// Maximum size of a price source response we accept
+const MAX_RESPONSE_SIZE: u64 = 64 * 1024;
+
+/// Timeout for price source requests, in seconds
+const PRICE_FETCH_TIMEOUT: u64 = 60;
+
+/// A pluggable source of approximate fiat token prices.
+#[async_trait]
+pub trait PriceSource: Send + Sync {
+    /// Human-readable name of the source, shown next to fiat values.
+    fn name(&self) -> String;
+
+    /// Fetch the prices of given token symbols in `currency`.
+    /// Symbols the source does not know about are left out of the result.
+    async fn fetch(&self, currency: &str, symbols: &[String]) -> Result<HashMap<String, f64>>;
+}
+
+/// [`PriceSource`] fetching prices over HTTP through Tor.
+///
+/// The endpoint is queried with `GET <path>?currency=<currency>&symbols=<a,b>`
+/// and is expected to respond with a JSON object mapping each symbol to its
+/// price, e.g. `{"DRK": 0.42}`.
+pub struct HttpPriceSource {
+    /// Configured endpoint, using either `tor://` or `tor+tls://`
+    endpoint: Url,
+}
+
+impl HttpPriceSource {
+    /// Create a new price source for given endpoint. Only Tor transports
+    /// are accepted, so the request never leaves from our own address.
+    pub fn new(endpoint: Url) -> Result<Self> {
+        if !matches!(endpoint.scheme(), "tor" | "tor+tls") {
+            return Err(Error::Custom(format!(
+                "Price endpoint must use tor:// or tor+tls://, got {}://",
+                endpoint.scheme()
+            )))
+        }
+
+        if endpoint.host_str().is_none() {
+            return Err(Error::Custom("Price endpoint is missing a host".to_string()))
+        }
+
+        Ok(Self { endpoint })
+    }
+}
+
+#[async_trait]
+impl PriceSource for HttpPriceSource {
+    fn name(&self) -> String {
+        self.endpoint.host_str().unwrap_or_default().to_string()
+    }
+
+    async fn fetch(&self, currency: &str, symbols: &[String]) -> Result<HashMap<String, f64>> {
+        let mut endpoint = self.endpoint.clone();
+        if endpoint.port().is_none() {
+            let port = if endpoint.scheme() == "tor+tls" { 443 } else { 80 };
+            let _ = endpoint.set_port(Some(port));
+        }
+
+        let mut path = endpoint.clone();
+        path.query_pairs_mut()
+            .append_pair("currency", currency)
+            .append_pair("symbols", &symbols.join(","));
+        let path = match path.query() {
+            Some(q) => format!("{}?{q}", path.path()),
+            None => path.path().to_string(),
+        };
+
+        // We speak HTTP/1.0 so the response comes back in one piece,
+        // and keep the headers to the bare minimum.
+        let request = format!(
+            "GET {path} HTTP/1.0\r\nHost: {}\r\nAccept: application/json\r\n\r\n",
+            endpoint.host_str().unwrap()
+        );
+
+        let timeout = Duration::from_secs(PRICE_FETCH_TIMEOUT);
+        let dialer = Dialer::new(endpoint, None).await?;
+        let mut stream = dialer.dial(Some(timeout)).await?;
+        stream.write_all(request.as_bytes()).await?;
+
+        let mut response = vec![];
+        stream.take(MAX_RESPONSE_SIZE).read_to_end(&mut response).await?;
+
+        parse_price_response(&String::from_utf8_lossy(&response), symbols)
+    }
+}
+
+/// Parse an HTTP price source response, keeping the prices of given
+/// token symbols.
+fn parse_price_response(response: &str, symbols: &[String]) -> Result<HashMap<String, f64>> {
+    let Some((head, body)) = response.split_once("\r\n\r\n") else {
+        return Err(Error::Custom("Malformed price source response".to_string()))
+    };
+
+    let status = head.lines().next().unwrap_or_default();
+    if status.split_whitespace().nth(1) != Some("200") {
+        return Err(Error::Custom(format!("Price source responded with: {status}")))
+    }
+
+    let Ok(json) = body.trim().parse::<JsonValue>() else {
+        return Err(Error::Custom("Price source response is not valid JSON".to_string()))
+    };
+
+    let Some(object) = json.get::<HashMap<String, JsonValue>>() else {
+        return Err(Error::Custom("Price source response is not a JSON object".to_string()))
+    };
+
+    let mut prices = HashMap::new();
+    for symbol in symbols {
+        if let Some(price) = object.get(symbol).and_then(|v| v.get::<f64>()) {
+            prices.insert(symbol.clone(), *price);
+        }
+    }
+
+    Ok(prices)
+}
+
+/// Fiat price lookup with an on-disk cache in front of a [`PriceSource`].
+pub struct FiatPrices {
+    /// Source prices are fetched from
+    source: Box<dyn PriceSource>,
+    /// Fiat currency prices are expressed in
+    pub currency: String,
+    /// Path of the price cache file
+    cache_path: PathBuf,
+    /// Seconds cached prices are considered fresh for
+    cache_ttl: u64,
+}
+
+impl FiatPrices {
+    pub fn new(
+        source: Box<dyn PriceSource>,
+        currency: &str,
+        cache_path: PathBuf,
+        cache_ttl: u64,
+    ) -> Self {
+        Self { source, currency: currency.to_uppercase(), cache_path, cache_ttl }
+    }
+
+    /// Name of the configured price source
+    pub fn source_name(&self) -> String {
+        self.source.name()
+    }
+
+    /// Grab the prices of given token symbols. Fresh cached prices are used
+    /// when available, otherwise they are fetched from the source and the
+    /// privacy warning is printed. Returns an empty map if fetching fails,
+    /// since fiat values are only ever informational.
+    pub async fn prices(&self, symbols: &[String]) -> HashMap<String, f64> {
+        if symbols.is_empty() {
+            return HashMap::new()
+        }
+
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0);
+
+        // Serve everything from the cache if we can
+        let cached = self.read_cache();
+        if let Some((timestamp, ref currency, ref prices)) = cached {
+            let fresh = now.saturating_sub(timestamp) < self.cache_ttl;
+            if fresh && *currency == self.currency && symbols.iter().all(|s| prices.contains_key(s))
+            {
+                return prices.clone()
+            }
+        }
+
+        eprintln!("{PRICE_PRIVACY_WARNING}");
+        match self.source.fetch(&self.currency, symbols).await {
+            Ok(prices) => {
+                self.write_cache(now, &prices);
+                prices
+            }
+            Err(e) => {
+                eprintln!("Failed to fetch fiat prices from {}: {e}", self.source.name());
+                // Stale prices are better than nothing, as long as we label them
+                match cached {
+                    Some((_, currency, prices)) if currency == self.currency => {
+                        eprintln!("Showing stale cached fiat prices");
+                        prices
+                    }
+                    _ => HashMap::new(),
+                }
+            }
+        }
+    }
+
+    /// Read the cache file, returning its timestamp, currency and prices.
+    fn read_cache(&self) -> Option<(u64, String, HashMap<String, f64>)> {
+        let bytes = fs::read(&self.cache_path).ok()?;
+        let (timestamp, currency, prices): (u64, String, Vec<(String, f64)>) =
+            deserialize(&bytes).ok()?;
+        Some((timestamp, currency, prices.into_iter().collect()))
+    }
+
+    /// Write given prices to the cache file. Failures are not fatal.
+    fn write_cache(&self, timestamp: u64, prices: &HashMap<String, f64>) {
+        let prices: Vec<(String, f64)> = prices.iter().map(|(k, v)| (k.clone(), *v)).collect();
+        let bytes = serialize(&(timestamp, self.currency.clone(), prices));
+        if let Err(e) = fs::write(&self.cache_path, bytes) {
+            eprintln!("Failed to write fiat price cache: {e}");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn price_response_parsing() {
+        let symbols = vec!["DRK".to_string(), "XMR".to_string()];
+
+        let response = "HTTP/1.0 200 OK\r\nContent-Type: application/json\r\n\r\n\
+                        {\"DRK\": 0.42, \"BTC\": 60000.0}\n";
+        let prices = parse_price_response(response, &symbols).unwrap();
+        assert_eq!(prices.len(), 1);
+        assert_eq!(prices["DRK"], 0.42);
+
+        // Non-numeric prices are left out
+        let response = "HTTP/1.0 200 OK\r\n\r\n{\"DRK\": \"0.42\", \"XMR\": 150.5}";
+        let prices = parse_price_response(response, &symbols).unwrap();
+        assert_eq!(prices, HashMap::from([("XMR".to_string(), 150.5)]));
+
+        // Errors, non-JSON bodies and malformed responses are rejected
+        assert!(parse_price_response("HTTP/1.0 404 Not Found\r\n\r\n{}", &symbols).is_err());
+        assert!(parse_price_response("HTTP/1.0 200 OK\r\n\r\nnot json", &symbols).is_err());
+        assert!(parse_price_response("HTTP/1.0 200 OK\r\n\r\n[0.42]", &symbols).is_err());
+        assert!(parse_price_response("HTTP/1.0 200 OK", &symbols).is_err());
+    }
+
+    #[test]
+    fn http_price_source_endpoints() {
+        assert!(HttpPriceSource::new(Url::parse("tor://prices.onion/v1").unwrap()).is_ok());
+        assert!(HttpPriceSource::new(Url::parse("tor+tls://prices.onion").unwrap()).is_ok());
+        assert!(HttpPriceSource::new(Url::parse("https://prices.example").unwrap()).is_err());
+    }
+}
@@ -0,0 +1,302 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2025 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use std::{collections::HashMap, fmt, process::Stdio};
+
+use smol::{
+    io::AsyncWriteExt,
+    lock::Mutex,
+    process::{Child, Command},
+};
+
+use darkfi::{rpc::util::JsonValue, system::sleep, util::path::expand_path};
+use darkfi_dao_contract::model::{DaoBulla, DaoProposalBulla};
+use darkfi_money_contract::model::{Coin, TokenId};
+use darkfi_sdk::tx::TransactionHash;
+
+/// Wallet events external hooks get invoked for
+#[derive(Clone, Debug)]
+pub enum WalletEvent {
+    /// A coin owned by the wallet was received
+    CoinReceived { coin: Coin, value: u64, token_id: TokenId, tx_hash: String },
+    /// A wallet transaction was confirmed in a block
+    TxConfirmed { tx_hash: String },
+    /// A proposal was created for one of our DAOs
+    ProposalCreated {
+        dao: String,
+        dao_bulla: DaoBulla,
+        proposal: DaoProposalBulla,
+        tx_hash: TransactionHash,
+    },
+}
+
+impl fmt::Display for WalletEvent {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let s = match self {
+            Self::CoinReceived { .. } => "coin_received",
+            Self::TxConfirmed { .. } => "tx_confirmed",
+            Self::ProposalCreated { .. } => "proposal_created",
+        };
+        write!(f, "{s}")
+    }
+}
+
+impl WalletEvent {
+    /// Build the JSON payload handed to hooks for this event,
+    /// observed at the given block height.
+    pub fn json(&self, height: u32) -> JsonValue {
+        let mut payload = HashMap::from([
+            ("event".to_string(), JsonValue::String(self.to_string())),
+            ("height".to_string(), JsonValue::Number(height as f64)),
+        ]);
+
+        match self {
+            Self::CoinReceived { coin, value, token_id, tx_hash } => {
+                payload.insert("coin".to_string(), JsonValue::String(coin.to_string()));
+                // Values are passed as strings, since JSON numbers can't hold all u64
+                payload.insert("value".to_string(), JsonValue::String(value.to_string()));
+                payload.insert("token_id".to_string(), JsonValue::String(token_id.to_string()));
+                payload.insert("tx_hash".to_string(), JsonValue::String(tx_hash.clone()));
+            }
+            Self::TxConfirmed { tx_hash } => {
+                payload.insert("tx_hash".to_string(), JsonValue::String(tx_hash.clone()));
+            }
+            Self::ProposalCreated { dao, dao_bulla, proposal, tx_hash } => {
+                payload.insert("dao".to_string(), JsonValue::String(dao.clone()));
+                payload.insert("dao_bulla".to_string(), JsonValue::String(dao_bulla.to_string()));
+                payload.insert("proposal".to_string(), JsonValue::String(proposal.to_string()));
+                payload.insert("tx_hash".to_string(), JsonValue::String(tx_hash.to_string()));
+            }
+        }
+
+        JsonValue::Object(payload)
+    }
+}
+
+/// Time a hook gets to consume its payload and exit, before it gets killed
+/// (in seconds)
+pub const HOOK_TIMEOUT: u64 = 30;
+
+/// External executables invoked on wallet events. Events are queued
+/// while a block is being scanned, and dispatched once it has been
+/// fully applied to the wallet.
+#[derive(Default)]
+pub struct Hooks {
+    /// Paths of the configured hook executables
+    executables: Vec<String>,
+    /// Events of the block currently being scanned
+    pending: Mutex<Vec<WalletEvent>>,
+    /// Highest block height whose events were already dispatched
+    /// before a wallet reset. Blocks up to it are being rescanned,
+    /// so their events don't fire again.
+    rescan_height: Mutex<Option<u32>>,
+}
+
+impl Hooks {
+    pub fn new(executables: Vec<String>) -> Self {
+        Self { executables, pending: Mutex::new(vec![]), rescan_height: Mutex::new(None) }
+    }
+
+    /// Drop the queued events, e.g. of a block that failed to be scanned.
+    pub async fn clear(&self) {
+        self.pending.lock().await.clear();
+    }
+
+    /// Mark the blocks up to the given height as being rescanned,
+    /// so their events are never dispatched again.
+    pub async fn rescan_until(&self, height: u32) {
+        let mut rescan_height = self.rescan_height.lock().await;
+        if rescan_height.is_none_or(|h| h < height) {
+            *rescan_height = Some(height);
+        }
+    }
+
+    /// Queue an event to be dispatched on the next [`Hooks::dispatch`]
+    /// call. Does nothing if no hooks are configured.
+    pub async fn queue(&self, event: WalletEvent) {
+        if self.executables.is_empty() {
+            return
+        }
+        self.pending.lock().await.push(event);
+    }
+
+    /// Invoke every configured hook for each queued event, observed at
+    /// the given block height. Each hook receives the event JSON payload
+    /// on its stdin, and the event name in the `DRK_HOOK_EVENT` env var.
+    /// Hooks run detached, so they never stall the scan, and get killed
+    /// if they don't exit within [`HOOK_TIMEOUT`]. Events of rescanned
+    /// blocks are dropped.
+    pub async fn dispatch(&self, height: u32) {
+        let events = std::mem::take(&mut *self.pending.lock().await);
+        if self.rescan_height.lock().await.is_some_and(|h| height <= h) {
+            return
+        }
+
+        for event in events {
+            let payload = event.json(height).stringify().unwrap();
+            for executable in &self.executables {
+                if let Err(e) = Self::invoke(executable, &event, payload.clone()) {
+                    eprintln!("[hooks] Executing hook {executable} for {event} failed: {e}");
+                }
+            }
+        }
+    }
+
+    /// Spawn a single hook executable, feeding it the given event payload
+    /// in the background.
+    fn invoke(executable: &str, event: &WalletEvent, payload: String) -> std::io::Result<()> {
+        let path = match expand_path(executable) {
+            Ok(p) => p,
+            Err(e) => return Err(std::io::Error::other(e.to_string())),
+        };
+
+        let mut child = Command::new(path)
+            .env("DRK_HOOK_EVENT", event.to_string())
+            .stdin(Stdio::piped())
+            .spawn()?;
+
+        let hook = format!("{executable} for {event}");
+        smol::spawn(async move {
+            let timeout = async {
+                sleep(HOOK_TIMEOUT).await;
+                None
+            };
+            let result = smol::future::or(
+                async { Some(Self::supervise(&mut child, &payload).await) },
+                timeout,
+            )
+            .await;
+
+            match result {
+                Some(Ok(status)) if !status.success() => {
+                    eprintln!("[hooks] Hook {hook} exited with {status}")
+                }
+                Some(Ok(_)) => { /* Do nothing */ }
+                Some(Err(e)) => eprintln!("[hooks] Executing hook {hook} failed: {e}"),
+                None => {
+                    eprintln!("[hooks] Hook {hook} timed out, killing it");
+                    let _ = child.kill();
+                }
+            }
+        })
+        .detach();
+
+        Ok(())
+    }
+
+    /// Write the payload to the hook stdin and wait for it to exit
+    async fn supervise(
+        child: &mut Child,
+        payload: &str,
+    ) -> std::io::Result<std::process::ExitStatus> {
+        if let Some(mut stdin) = child.stdin.take() {
+            // A hook is free to ignore its payload, so a broken pipe is fine
+            if let Err(e) = stdin.write_all(payload.as_bytes()).await {
+                if e.kind() != std::io::ErrorKind::BrokenPipe {
+                    return Err(e)
+                }
+            }
+        }
+
+        child.status().await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use darkfi_sdk::{pasta::pallas, tx::TransactionHash};
+
+    use super::*;
+
+    /// Grab a string field of an event payload
+    fn field<'a>(payload: &'a HashMap<String, JsonValue>, key: &str) -> &'a String {
+        payload[key].get::<String>().unwrap()
+    }
+
+    #[test]
+    fn wallet_event_json() {
+        let coin = Coin::from(pallas::Base::from(42));
+        let token_id = TokenId::from(pallas::Base::from(69));
+        let event = WalletEvent::CoinReceived {
+            coin,
+            value: u64::MAX,
+            token_id,
+            tx_hash: "tx".to_string(),
+        };
+
+        // Payloads must survive their way through the hook stdin
+        let json = event.json(7).stringify().unwrap();
+        let json: JsonValue = json.parse().unwrap();
+        let payload = json.get::<HashMap<String, JsonValue>>().unwrap();
+        assert_eq!(payload.len(), 6);
+        assert_eq!(field(payload, "event"), "coin_received");
+        assert_eq!(*payload["height"].get::<f64>().unwrap(), 7.0);
+        assert_eq!(field(payload, "coin"), &coin.to_string());
+        // Values don't lose precision as JSON numbers would
+        assert_eq!(field(payload, "value"), &u64::MAX.to_string());
+        assert_eq!(field(payload, "token_id"), &token_id.to_string());
+        assert_eq!(field(payload, "tx_hash"), "tx");
+
+        let event = WalletEvent::TxConfirmed { tx_hash: "tx".to_string() };
+        let json = event.json(8);
+        let payload = json.get::<HashMap<String, JsonValue>>().unwrap();
+        assert_eq!(payload.len(), 3);
+        assert_eq!(field(payload, "event"), "tx_confirmed");
+        assert_eq!(field(payload, "tx_hash"), "tx");
+
+        let dao_bulla = DaoBulla::from(pallas::Base::from(1));
+        let proposal = DaoProposalBulla::from(pallas::Base::from(2));
+        let tx_hash = TransactionHash([3; 32]);
+        let event =
+            WalletEvent::ProposalCreated { dao: "dao".to_string(), dao_bulla, proposal, tx_hash };
+        let json = event.json(9);
+        let payload = json.get::<HashMap<String, JsonValue>>().unwrap();
+        assert_eq!(payload.len(), 6);
+        assert_eq!(field(payload, "event"), "proposal_created");
+        assert_eq!(field(payload, "dao"), "dao");
+        assert_eq!(field(payload, "dao_bulla"), &dao_bulla.to_string());
+        assert_eq!(field(payload, "proposal"), &proposal.to_string());
+        assert_eq!(field(payload, "tx_hash"), &tx_hash.to_string());
+    }
+
+    #[test]
+    fn pending_events() {
+        smol::block_on(async {
+            let event = WalletEvent::TxConfirmed { tx_hash: "tx".to_string() };
+
+            // Nothing gets queued without hooks
+            let hooks = Hooks::new(vec![]);
+            hooks.queue(event.clone()).await;
+            assert!(hooks.pending.lock().await.is_empty());
+
+            // Events of a failed block are dropped
+            let hooks = Hooks::new(vec!["/nonexistent/hook".to_string()]);
+            hooks.queue(event.clone()).await;
+            hooks.clear().await;
+            assert!(hooks.pending.lock().await.is_empty());
+
+            // Rescanned blocks drop their events, later ones keep firing
+            hooks.rescan_until(10).await;
+            hooks.rescan_until(5).await;
+            assert_eq!(*hooks.rescan_height.lock().await, Some(10));
+            hooks.queue(event.clone()).await;
+            hooks.dispatch(10).await;
+            assert!(hooks.pending.lock().await.is_empty());
+        })
+    }
+}
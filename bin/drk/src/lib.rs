@@ -20,7 +20,7 @@ use std::{fs, sync::Arc};
 
 use url::Url;
 
-use darkfi::{rpc::client::RpcClient, util::path::expand_path, Error, Result};
+use darkfi::{rpc::client::RpcClient, util::path::expand_path, zk::ProverPool, Error, Result};
 
 /// Error codes
 pub mod error;
@@ -32,24 +32,57 @@ pub mod rpc;
 /// Payment methods
 pub mod transfer;
 
+/// Coin consolidation methods
+pub mod sweep;
+
+/// Key rotation methods
+pub mod rotation;
+
+/// Payment proof methods
+pub mod payment_proof;
+
 /// Swap methods
 pub mod swap;
 
+/// Fee swap methods
+pub mod fee_swap;
+
 /// Token methods
 pub mod token;
 
 /// CLI utility functions
 pub mod cli_util;
 
+/// Locale-aware number formatting
+pub mod locale;
+
+/// Optional fiat price display
+pub mod fiat;
+
 /// Wallet functionality related to Money
 pub mod money;
 
 /// Wallet functionality related to Dao
 pub mod dao;
 
+/// DAO activity notifications
+pub mod dao_watch;
+
+/// Wallet event hooks
+pub mod hooks;
+
 /// Wallet functionality related to Deployooor
 pub mod deploy;
 
+/// Wallet functionality related to Darkname
+pub mod darkname;
+
+/// Wallet functionality related to Auction
+pub mod auction;
+
+/// Wallet functionality related to Oracle
+pub mod oracle;
+
 /// Wallet functionality related to transactions history
 pub mod txs_history;
 
@@ -58,6 +91,13 @@ pub mod scanned_blocks;
 
 /// Wallet database operations handler
 pub mod walletdb;
+
+/// Wallet backup and integrity verification
+pub mod backup;
+
+/// Wallet state anomaly detection
+pub mod doctor;
+use hooks::Hooks;
 use walletdb::{WalletDb, WalletPtr};
 
 /// CLI-util structure
@@ -68,6 +108,10 @@ pub struct Drk {
     pub rpc_client: Option<RpcClient>,
     /// Flag indicating if fun stuff are enabled
     pub fun: bool,
+    /// External executables invoked on wallet events
+    pub hooks: Hooks,
+    /// Shared pool creating the transactions ZK proofs
+    pub prover: ProverPool,
 }
 
 impl Drk {
@@ -77,6 +121,7 @@ impl Drk {
         endpoint: Option<Url>,
         ex: Arc<smol::Executor<'static>>,
         fun: bool,
+        hooks: Vec<String>,
     ) -> Result<Self> {
         // Initialize wallet
         let wallet_path = expand_path(&wallet_path)?;
@@ -96,7 +141,18 @@ impl Drk {
             None
         };
 
-        Ok(Self { wallet, rpc_client, fun })
+        Ok(Self::from_wallet(wallet, rpc_client, fun, hooks))
+    }
+
+    /// Generate a `Drk` instance over an already opened wallet database,
+    /// e.g. an in-memory one.
+    pub fn from_wallet(
+        wallet: WalletPtr,
+        rpc_client: Option<RpcClient>,
+        fun: bool,
+        hooks: Vec<String>,
+    ) -> Self {
+        Self { wallet, rpc_client, fun, hooks: Hooks::new(hooks), prover: ProverPool::default() }
     }
 
     /// Initialize wallet with tables for `Drk`.
@@ -110,15 +166,24 @@ impl Drk {
     /// Auxiliary function to completely reset wallet state.
     pub async fn reset(&self) -> WalletDbResult<()> {
         println!("Resetting full wallet state");
+        // Hooks already fired for the blocks we are about to rescan
+        if let Ok((last, hash)) = self.get_last_scanned_block() {
+            if hash != "-" {
+                self.hooks.rescan_until(last).await;
+            }
+        }
         self.reset_scanned_blocks()?;
         self.reset_money_tree().await?;
         self.reset_money_smt()?;
         self.reset_money_coins()?;
+        self.reset_money_view_notes()?;
         self.reset_mint_authorities()?;
         self.reset_dao_trees().await?;
         self.reset_daos().await?;
         self.reset_dao_proposals().await?;
         self.reset_dao_votes()?;
+        self.reset_darkname_names()?;
+        self.reset_auction_auctions()?;
         self.reset_tx_history()?;
         println!("Successfully reset full wallet state");
         Ok(())
@@ -0,0 +1,171 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2025 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use std::sync::OnceLock;
+
+use darkfi::util::parse::encode_base10;
+
+use crate::money::BALANCE_BASE10_DECIMALS;
+
+/// Number formatting locale used for the whole `drk` output
+static LOCALE: OnceLock<NumberLocale> = OnceLock::new();
+
+/// Number formatting conventions of a locale
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct NumberLocale {
+    /// Decimal separator
+    pub decimal: char,
+    /// Thousands grouping separator, if the locale groups digits
+    pub grouping: Option<char>,
+}
+
+impl NumberLocale {
+    /// The `C`/`POSIX` locale: `.` decimal separator and no grouping
+    pub const C: Self = Self { decimal: '.', grouping: None };
+
+    /// Resolve a locale from its name, e.g. `de_DE.UTF-8`.
+    /// Unknown locales fall back to English conventions.
+    pub fn from_name(name: &str) -> Self {
+        // Strip the codeset and modifier, e.g. `de_DE.UTF-8@euro`
+        let name = name.split(['.', '@']).next().unwrap_or_default();
+        if name.is_empty() || name == "C" || name == "POSIX" {
+            return Self::C
+        }
+
+        let mut parts = name.split(['_', '-']);
+        let language = parts.next().unwrap_or_default().to_lowercase();
+        let territory = parts.next().unwrap_or_default().to_uppercase();
+
+        match (language.as_str(), territory.as_str()) {
+            ("de" | "it" | "fr", "CH") | ("rm", _) => Self { decimal: '.', grouping: Some('\'') },
+            (
+                "de" | "es" | "it" | "nl" | "pt" | "id" | "tr" | "da" | "el" | "ro" | "sl" | "hr",
+                _,
+            ) => Self { decimal: ',', grouping: Some('.') },
+            (
+                "fr" | "ru" | "pl" | "cs" | "sk" | "sv" | "fi" | "nb" | "nn" | "no" | "uk" | "hu" |
+                "bg" | "lt" | "lv" | "et",
+                _,
+            ) => Self { decimal: ',', grouping: Some(' ') },
+            _ => Self { decimal: '.', grouping: Some(',') },
+        }
+    }
+
+    /// Localize a plain decimal number string, e.g. `-1234567.89`.
+    pub fn localize(&self, number: &str) -> String {
+        let (sign, number) = match number.strip_prefix('-') {
+            Some(n) => ("-", n),
+            None => ("", number),
+        };
+
+        let (integer, fraction) = match number.split_once('.') {
+            Some((i, f)) => (i, Some(f)),
+            None => (number, None),
+        };
+
+        let mut ret = String::from(sign);
+        for (i, digit) in integer.chars().enumerate() {
+            if i > 0 && (integer.len() - i) % 3 == 0 {
+                if let Some(sep) = self.grouping {
+                    ret.push(sep);
+                }
+            }
+            ret.push(digit);
+        }
+
+        if let Some(fraction) = fraction {
+            ret.push(self.decimal);
+            ret.push_str(fraction);
+        }
+
+        ret
+    }
+}
+
+/// Set the locale used for formatting numbers in `drk` output.
+/// Only the first call has any effect. When never called, the `C`
+/// locale is used, so output stays parseable by scripts and amount
+/// inputs regardless of the environment.
+pub fn set_locale(locale: NumberLocale) {
+    let _ = LOCALE.set(locale);
+}
+
+/// Grab the locale used for formatting numbers in `drk` output.
+pub fn locale() -> NumberLocale {
+    *LOCALE.get().unwrap_or(&NumberLocale::C)
+}
+
+/// Format a token amount for display, using the configured locale.
+///
+/// This is meant for human-readable output only. Anything that gets
+/// parsed back, like exported configuration files, must keep using
+/// [`encode_base10`].
+pub fn format_amount(value: u64) -> String {
+    locale().localize(&encode_base10(value, BALANCE_BASE10_DECIMALS))
+}
+
+/// Format a decimal number with given precision for display, using the
+/// configured locale.
+pub fn format_decimal(value: f64, precision: usize) -> String {
+    locale().localize(&format!("{value:.precision$}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn locale_from_name() {
+        assert_eq!(NumberLocale::from_name(""), NumberLocale::C);
+        assert_eq!(NumberLocale::from_name("C"), NumberLocale::C);
+        assert_eq!(NumberLocale::from_name("POSIX"), NumberLocale::C);
+        assert_eq!(NumberLocale::from_name("C.UTF-8"), NumberLocale::C);
+
+        let english = NumberLocale { decimal: '.', grouping: Some(',') };
+        assert_eq!(NumberLocale::from_name("en_US.UTF-8"), english);
+        assert_eq!(NumberLocale::from_name("xx_YY"), english);
+
+        let german = NumberLocale { decimal: ',', grouping: Some('.') };
+        assert_eq!(NumberLocale::from_name("de_DE.UTF-8@euro"), german);
+        assert_eq!(NumberLocale::from_name("es-ES"), german);
+
+        let swiss = NumberLocale { decimal: '.', grouping: Some('\'') };
+        assert_eq!(NumberLocale::from_name("de_CH"), swiss);
+        assert_eq!(NumberLocale::from_name("fr_ch"), swiss);
+
+        let french = NumberLocale { decimal: ',', grouping: Some(' ') };
+        assert_eq!(NumberLocale::from_name("fr_FR"), french);
+    }
+
+    #[test]
+    fn locale_localize() {
+        // The C locale leaves numbers untouched
+        assert_eq!(NumberLocale::C.localize("-1234567.89"), "-1234567.89");
+
+        let english = NumberLocale::from_name("en_US");
+        assert_eq!(english.localize("0"), "0");
+        assert_eq!(english.localize("123"), "123");
+        assert_eq!(english.localize("1234"), "1,234");
+        assert_eq!(english.localize("123456.5"), "123,456.5");
+        assert_eq!(english.localize("-1234567.89"), "-1,234,567.89");
+
+        let german = NumberLocale::from_name("de_DE");
+        assert_eq!(german.localize("1234567.00000001"), "1.234.567,00000001");
+        assert_eq!(german.localize("-0.5"), "-0,5");
+    }
+}
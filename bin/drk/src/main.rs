@@ -18,6 +18,7 @@
 
 use std::{
     io::{stdin, Read},
+    path::PathBuf,
     process::exit,
     str::FromStr,
     sync::Arc,
@@ -33,17 +34,26 @@ use darkfi::{
     async_daemonize, cli_desc,
     util::{
         encoding::base64,
-        parse::{decode_base10, encode_base10},
+        parse::decode_base10,
         path::{expand_path, get_config_path},
     },
     zk::halo2::Field,
     Error, Result,
 };
+use darkfi_auction_contract::model::AuctionId;
 use darkfi_dao_contract::{blockwindow, model::DaoProposalBulla, DaoFunction};
-use darkfi_money_contract::model::{Coin, CoinAttributes, TokenId};
+use darkfi_darkname_contract::{is_valid_name, model::NameTarget};
+use darkfi_money_contract::{
+    client::{
+        payment_proof::PaymentProof,
+        view_key::{IncomingViewKey, ViewAddress},
+    },
+    model::{Coin, CoinAttributes, TokenId},
+};
+use darkfi_oracle_contract::model::{FeedId, FeedKind};
 use darkfi_sdk::{
     crypto::{
-        note::AeadEncryptedNote, BaseBlind, FuncId, FuncRef, Keypair, PublicKey, SecretKey,
+        note::AeadEncryptedNote, BaseBlind, FuncId, FuncRef, Mnemonic, PublicKey, SecretKey,
         DAO_CONTRACT_ID,
     },
     pasta::{group::ff::PrimeField, pallas},
@@ -52,11 +62,18 @@ use darkfi_sdk::{
 use darkfi_serial::{deserialize_async, serialize_async};
 
 use drk::{
+    auction::PartialSettleData,
+    backup::backup_due,
     cli_util::{
-        generate_completions, kaching, parse_token_pair, parse_tx_from_stdin, parse_value_pair,
+        generate_completions, kaching, parse_feed_kind, parse_name_owner, parse_name_target,
+        parse_oracle_keys, parse_token_pair, parse_tx_from_stdin, parse_value_pair,
     },
     dao::{DaoParams, ProposalRecord},
-    money::BALANCE_BASE10_DECIMALS,
+    dao_watch::DaoWatcher,
+    fee_swap::{FeeOffer, FeeSwapRequest},
+    fiat::{FiatPrices, HttpPriceSource},
+    locale::{format_amount, format_decimal, set_locale, NumberLocale},
+    money::{BALANCE_BASE10_DECIMALS, MONEY_KEYS_RESTORE_COUNT},
     swap::PartialSwapData,
     Drk,
 };
@@ -86,6 +103,10 @@ struct Args {
     /// Flag indicating whether you want some fun in your life
     fun: bool,
 
+    #[structopt(long)]
+    /// Locale used to format numbers (e.g. de_DE), defaults to C
+    locale: Option<String>,
+
     #[structopt(short, long)]
     /// Set log file to ouput into
     log: Option<String>,
@@ -117,6 +138,14 @@ enum Subcmd {
         /// Initialize wallet database
         initialize: bool,
 
+        #[structopt(long)]
+        /// Initialize wallet database, recovering it from a mnemonic phrase given from stdin
+        restore: bool,
+
+        #[structopt(long)]
+        /// Print the mnemonic phrase of the wallet
+        mnemonic: bool,
+
         #[structopt(long)]
         /// Generate a new keypair in the wallet
         keygen: bool,
@@ -152,6 +181,18 @@ enum Subcmd {
         #[structopt(long)]
         /// Print all the coins in the wallet
         coins: bool,
+
+        #[structopt(long)]
+        /// Create an encrypted backup of the wallet database
+        backup: bool,
+
+        #[structopt(long)]
+        /// Verify wallet database integrity and consistency against darkfid
+        verify: bool,
+
+        #[structopt(long)]
+        /// Scan the wallet state for anomalies and suggest fixes for them
+        doctor: bool,
     },
 
     /// Read a transaction from stdin and mark its input coins as spent
@@ -185,6 +226,28 @@ enum Subcmd {
         half_split: bool,
     },
 
+    /// Consolidate small coins of a token into fewer outputs
+    Sweep {
+        /// Token ID to sweep
+        token: String,
+
+        #[structopt(long, default_value = "10")]
+        /// Maximum number of coins spent per transaction
+        max_inputs: usize,
+
+        #[structopt(long)]
+        /// Only sweep coins worth at most this amount
+        max_value: Option<String>,
+
+        #[structopt(long)]
+        /// Stop sweeping if a transaction fee would exceed this amount
+        max_fee: Option<String>,
+
+        #[structopt(long, default_value = "300")]
+        /// Maximum random delay between transactions, in seconds
+        max_delay: u64,
+    },
+
     /// OTC atomic swap
     Otc {
         #[structopt(subcommand)]
@@ -192,6 +255,13 @@ enum Subcmd {
         command: OtcSubcmd,
     },
 
+    /// Pay transaction fees in other tokens through fee offers
+    FeeSwap {
+        #[structopt(subcommand)]
+        /// Sub command to execute
+        command: FeeSwapSubcmd,
+    },
+
     /// Attach the fee call to a transaction given from stdin
     AttachFee,
 
@@ -242,12 +312,54 @@ enum Subcmd {
         command: TokenSubcmd,
     },
 
+    /// View key functionalities
+    Viewkey {
+        #[structopt(subcommand)]
+        /// Sub command to execute
+        command: ViewkeySubcmd,
+    },
+
+    /// Key rotation functionalities
+    Keys {
+        #[structopt(subcommand)]
+        /// Sub command to execute
+        command: KeysSubcmd,
+    },
+
     /// Contract functionalities
     Contract {
         #[structopt(subcommand)]
         /// Sub command to execute
         command: ContractSubcmd,
     },
+
+    /// Name service functionalities
+    Name {
+        #[structopt(subcommand)]
+        /// Sub command to execute
+        command: NameSubcmd,
+    },
+
+    /// Sealed-bid auction functionalities
+    Auction {
+        #[structopt(subcommand)]
+        /// Sub command to execute
+        command: AuctionSubcmd,
+    },
+
+    /// Oracle feed functionalities
+    Oracle {
+        #[structopt(subcommand)]
+        /// Sub command to execute
+        command: OracleSubcmd,
+    },
+
+    /// Payment proof functionalities
+    Proof {
+        #[structopt(subcommand)]
+        /// Sub command to execute
+        command: ProofSubcmd,
+    },
 }
 
 #[derive(Clone, Debug, Deserialize, StructOpt)]
@@ -273,6 +385,46 @@ enum OtcSubcmd {
     Sign,
 }
 
+#[derive(Clone, Debug, Deserialize, StructOpt)]
+enum FeeSwapSubcmd {
+    /// Create a fee offer, paying fees in exchange for a token
+    Offer {
+        /// Token ID to accept as payment
+        token: String,
+
+        /// Price of a native token, in units of the payment token
+        rate: String,
+
+        /// Maximum fee to pay per transaction
+        max_fee: String,
+    },
+
+    /// Import a fee offer given from stdin
+    Import,
+
+    /// List the fee offers in the wallet
+    List,
+
+    /// Create a fee swap request paying to an address, using the
+    /// best fee offer for our balances
+    Transfer {
+        /// Amount to send
+        amount: String,
+
+        /// Token ID to send
+        token: String,
+
+        /// Recipient address
+        recipient: String,
+    },
+
+    /// Attach the fee call to a fee swap request given from stdin
+    Fund,
+
+    /// Sign a funded fee swap transaction given from stdin
+    Sign,
+}
+
 #[derive(Clone, Debug, Deserialize, StructOpt)]
 enum DaoSubcmd {
     /// Create DAO parameters
@@ -289,6 +441,11 @@ enum DaoSubcmd {
         approval_ratio: f64,
         /// DAO's governance token ID
         gov_token_id: String,
+
+        #[structopt(long)]
+        /// Public keys of the DAO members. When set, only members
+        /// can create proposals and vote.
+        members: Vec<String>,
     },
 
     /// View DAO data from stdin
@@ -404,6 +561,19 @@ enum DaoSubcmd {
 
     /// Print the DAO contract base58-encoded spend hook
     SpendHook,
+
+    /// Subscribe to new blocks and notify about activity
+    /// on the proposals of the DAOs we participate in
+    Watch {
+        #[structopt(long)]
+        /// Send desktop notifications using `notify-send`
+        desktop: bool,
+
+        #[structopt(long)]
+        /// Shell command executed for each event, with its details
+        /// exported as `DRK_DAO_*` environment variables
+        hook: Option<String>,
+    },
 }
 
 #[derive(Clone, Debug, Deserialize, StructOpt)]
@@ -475,6 +645,66 @@ enum AliasSubcmd {
     },
 }
 
+#[derive(Clone, Debug, Deserialize, StructOpt)]
+enum ViewkeySubcmd {
+    /// Export the view keys of the wallet
+    Export {
+        #[structopt(long)]
+        /// Only export keys able to detect incoming notes
+        incoming_only: bool,
+    },
+
+    /// Print the default view address of the wallet.
+    /// Payments to it can be seen using its incoming view key.
+    Address,
+
+    /// Import incoming view keys from stdin, separated by newlines
+    Import,
+
+    /// Print all the incoming notes found using imported view keys
+    Notes,
+}
+
+#[derive(Clone, Debug, Deserialize, StructOpt)]
+enum KeysSubcmd {
+    /// Generate a new default key, retire the rest and move their coins to it
+    Rotate {
+        #[structopt(long, default_value = "2880")]
+        /// Number of blocks the retired keys keep getting scanned for
+        grace_blocks: u32,
+
+        #[structopt(long, default_value = "10")]
+        /// Maximum number of coins spent per transaction
+        max_inputs: usize,
+
+        #[structopt(long)]
+        /// Stop moving coins if a transaction fee would exceed this amount
+        max_fee: Option<String>,
+
+        #[structopt(long, default_value = "300")]
+        /// Maximum random delay between transactions, in seconds
+        max_delay: u64,
+    },
+
+    /// Resume moving the coins of retired keys to the default key
+    Migrate {
+        #[structopt(long, default_value = "10")]
+        /// Maximum number of coins spent per transaction
+        max_inputs: usize,
+
+        #[structopt(long)]
+        /// Stop moving coins if a transaction fee would exceed this amount
+        max_fee: Option<String>,
+
+        #[structopt(long, default_value = "300")]
+        /// Maximum random delay between transactions, in seconds
+        max_delay: u64,
+    },
+
+    /// Print the retired keys and the coins they still own
+    Status,
+}
+
 #[derive(Clone, Debug, Deserialize, StructOpt)]
 enum TokenSubcmd {
     /// Import a mint authority
@@ -544,6 +774,191 @@ enum ContractSubcmd {
     },
 }
 
+#[derive(Clone, Debug, Deserialize, StructOpt)]
+enum ProofSubcmd {
+    /// Create payment proofs for a transaction we sent
+    Create {
+        /// Hash of the payment transaction
+        tx_hash: String,
+
+        #[structopt(long)]
+        /// Only prove the payment of this output index
+        output: Option<u32>,
+    },
+
+    /// Verify payment proofs from stdin against the blockchain, separated by newlines
+    Verify,
+}
+
+#[derive(Clone, Debug, Deserialize, StructOpt)]
+enum NameSubcmd {
+    /// Commit to registering a name, required before registering it
+    Commit {
+        /// Name to commit to
+        name: String,
+    },
+
+    /// Register a previously committed name pointing to an address and/or contract ID
+    Register {
+        /// Name to register
+        name: String,
+
+        /// Registration duration in blocks
+        duration: u32,
+
+        #[structopt(long)]
+        /// Address the name resolves to
+        address: Option<String>,
+
+        #[structopt(long)]
+        /// Contract ID the name resolves to
+        contract: Option<String>,
+    },
+
+    /// Resolve a name using the scanned names records
+    Resolve {
+        /// Name to resolve
+        name: String,
+    },
+
+    /// Extend the registration of an owned name
+    Renew {
+        /// Name to renew
+        name: String,
+
+        /// Amount of blocks to extend the registration by
+        duration: u32,
+    },
+
+    /// Generate an owner commitment to receive a name transfer
+    Receive {
+        /// Name to receive
+        name: String,
+    },
+
+    /// Transfer an owned name to a new owner, optionally changing its target
+    Transfer {
+        /// Name to transfer
+        name: String,
+
+        /// Owner commitment of the recipient
+        owner: String,
+
+        #[structopt(long)]
+        /// New address the name resolves to
+        address: Option<String>,
+
+        #[structopt(long)]
+        /// New contract ID the name resolves to
+        contract: Option<String>,
+    },
+
+    /// List names owned by the wallet
+    List,
+}
+
+#[derive(Clone, Debug, Deserialize, StructOpt)]
+enum AuctionSubcmd {
+    /// Create an auction, locking the auctioned asset
+    Create {
+        /// Amount of the asset to auction
+        amount: String,
+
+        /// Token ID of the asset to auction
+        token: String,
+
+        /// Token ID bids have to be made in
+        payment_token: String,
+
+        /// Minimum bid value able to win the auction
+        reserve_price: String,
+
+        /// Duration of the commit phase in blocks
+        commit_duration: u32,
+
+        /// Duration of the reveal phase in blocks
+        reveal_duration: u32,
+    },
+
+    /// Place a sealed bid in an auction, locking its value
+    Bid {
+        /// Auction ID to bid in
+        auction_id: String,
+
+        /// Bid value
+        amount: String,
+    },
+
+    /// Reveal our bid in an auction
+    Reveal {
+        /// Auction ID to reveal our bid in
+        auction_id: String,
+    },
+
+    /// Refund our coins locked in an ended auction
+    Refund {
+        /// Auction ID to get the refund from
+        auction_id: String,
+    },
+
+    /// Create the seller's half of an auction settlement
+    SettleInit {
+        /// Auction ID to settle
+        auction_id: String,
+    },
+
+    /// Build the auction settlement tx given the seller's half from stdin
+    SettleJoin,
+
+    /// List the auctions seen on-chain
+    List,
+}
+
+#[derive(Clone, Debug, Deserialize, StructOpt)]
+enum OracleSubcmd {
+    /// Register a feed, using the default address as its authority
+    Register {
+        /// Feed name
+        name: String,
+
+        /// Feed kind (price or randomness)
+        kind: String,
+
+        /// Amount of oracle attestations required per round
+        threshold: u8,
+
+        /// Oracle public keys
+        oracles: Vec<String>,
+    },
+
+    /// Replace the oracle set of a feed we are the authority of
+    Rotate {
+        /// Feed ID to rotate the oracle set of
+        feed_id: String,
+
+        /// Amount of oracle attestations required per round
+        threshold: u8,
+
+        /// Oracle public keys
+        oracles: Vec<String>,
+    },
+
+    /// Show a feed and its latest attested value
+    Show {
+        /// Feed ID to show
+        feed_id: String,
+    },
+
+    /// Gather attestations from oracle reporters and submit the newest round
+    Collect {
+        /// Feed ID to submit a round of
+        feed_id: String,
+
+        /// oracled JSON-RPC endpoints
+        reporters: Vec<Url>,
+    },
+}
+
 /// Defines a blockchain network configuration.
 /// Default values correspond to a local network.
 #[derive(Clone, Debug, serde::Deserialize, structopt::StructOpt, structopt_toml::StructOptToml)]
@@ -560,6 +975,37 @@ struct BlockchainNetwork {
     #[structopt(short, long, default_value = "tcp://127.0.0.1:8240")]
     /// darkfid JSON-RPC endpoint
     endpoint: Url,
+
+    #[structopt(long)]
+    /// Directory to store encrypted wallet backups in.
+    /// Scheduled backups are disabled if not set.
+    backup_path: Option<String>,
+
+    #[structopt(long, default_value = "5")]
+    /// Number of wallet backups to keep (at least one)
+    backup_copies: usize,
+
+    #[structopt(long, default_value = "24")]
+    /// Interval in hours between scheduled wallet backups
+    backup_interval: u64,
+
+    #[structopt(long)]
+    /// Endpoint to fetch approximate fiat prices from, over Tor.
+    /// Fiat price display is disabled if not set.
+    price_endpoint: Option<Url>,
+
+    #[structopt(long, default_value = "USD")]
+    /// Fiat currency to display prices in
+    price_currency: String,
+
+    #[structopt(long, default_value = "600")]
+    /// Seconds fetched fiat prices are cached for
+    price_cache_ttl: u64,
+
+    #[structopt(long)]
+    /// Executables invoked with a JSON payload on wallet events
+    /// (coin received, transaction confirmed, DAO proposal created)
+    hooks: Vec<String>,
 }
 
 /// Auxiliary function to parse darkfid configuration file and extract requested
@@ -612,6 +1058,7 @@ async fn new_wallet(
     endpoint: Option<Url>,
     ex: Arc<smol::Executor<'static>>,
     fun: bool,
+    hooks: Vec<String>,
 ) -> Drk {
     // Script kiddies protection
     if wallet_pass == "changeme" {
@@ -619,7 +1066,7 @@ async fn new_wallet(
         exit(2);
     }
 
-    match Drk::new(wallet_path, wallet_pass, endpoint, ex, fun).await {
+    match Drk::new(wallet_path, wallet_pass, endpoint, ex, fun, hooks).await {
         Ok(wallet) => wallet,
         Err(e) => {
             eprintln!("Error initializing wallet: {e:?}");
@@ -628,8 +1075,117 @@ async fn new_wallet(
     }
 }
 
+/// Auxiliary function to create the fiat price lookup for provided
+/// configuration. Returns `None` if no price endpoint is configured.
+fn fiat_prices(config: &BlockchainNetwork) -> Option<FiatPrices> {
+    let endpoint = config.price_endpoint.clone()?;
+
+    let source = match HttpPriceSource::new(endpoint) {
+        Ok(s) => s,
+        Err(e) => {
+            eprintln!("Fiat price display disabled: {e}");
+            return None
+        }
+    };
+
+    // Keep the cache next to the wallet, like the backups
+    let wallet_path = expand_path(&config.wallet_path).ok()?;
+    let parent = wallet_path.parent().map(|p| p.to_path_buf()).unwrap_or_default();
+
+    Some(FiatPrices::new(
+        Box::new(source),
+        &config.price_currency,
+        parent.join("prices.cache"),
+        config.price_cache_ttl,
+    ))
+}
+
+/// Auxiliary function to grab the wallet backups directory for provided
+/// configuration. Defaults to a `backups` directory next to the wallet.
+fn backup_dir(config: &BlockchainNetwork) -> Result<PathBuf> {
+    if let Some(ref path) = config.backup_path {
+        return expand_path(path)
+    }
+
+    let wallet_path = expand_path(&config.wallet_path)?;
+    let parent = wallet_path.parent().map(|p| p.to_path_buf()).unwrap_or_default();
+    Ok(parent.join("backups"))
+}
+
+/// Auxiliary function to create a scheduled wallet backup, if backups are
+/// configured and the newest existing one is older than the configured interval.
+async fn scheduled_backup(config: &BlockchainNetwork, ex: Arc<smol::Executor<'static>>) {
+    if config.backup_path.is_none() || config.wallet_pass == "changeme" {
+        return
+    }
+
+    let Ok(wallet_path) = expand_path(&config.wallet_path) else { return };
+    if !wallet_path.exists() {
+        return
+    }
+
+    let Ok(backup_dir) = backup_dir(config) else { return };
+    match backup_due(&backup_dir, config.backup_interval) {
+        Ok(true) => {}
+        Ok(false) => return,
+        Err(e) => {
+            eprintln!("Failed checking wallet backups: {e:?}");
+            return
+        }
+    }
+
+    let drk = new_wallet(
+        config.wallet_path.clone(),
+        config.wallet_pass.clone(),
+        None,
+        ex,
+        false,
+        config.hooks.clone(),
+    )
+    .await;
+    if let Err(e) = drk.backup_wallet(&backup_dir, &config.wallet_pass, config.backup_copies) {
+        eprintln!("Scheduled wallet backup failed: {e:?}");
+    }
+}
+
+/// Auxiliary function to move the coins of retired keys to the default
+/// key, exiting on failure.
+async fn migrate_rotated_coins(
+    drk: &Drk,
+    max_inputs: usize,
+    max_fee: Option<String>,
+    max_delay: u64,
+) {
+    let max_fee = match max_fee {
+        Some(v) => match decode_base10(&v, BALANCE_BASE10_DECIMALS, false) {
+            Ok(v) => Some(v),
+            Err(e) => {
+                eprintln!("Invalid max fee: {e:?}");
+                exit(2);
+            }
+        },
+        None => None,
+    };
+
+    let txids = match drk.migrate_rotated_coins(max_inputs, max_fee, max_delay).await {
+        Ok(t) => t,
+        Err(e) => {
+            eprintln!("Failed to move coins of retired keys: {e:?}");
+            exit(2);
+        }
+    };
+    println!("Moved coins of retired keys in {} transactions", txids.len());
+}
+
 async_daemonize!(realmain);
 async fn realmain(args: Args, ex: Arc<smol::Executor<'static>>) -> Result<()> {
+    // Configure number formatting for all output. We don't follow the
+    // environment's locale, since localized output can't be fed back
+    // into amount inputs, so it has to be explicitly requested.
+    if let Some(ref locale) = args.locale {
+        set_locale(NumberLocale::from_name(locale));
+    }
+
     // Grab blockchain network configuration
     let blockchain_config = match args.network.as_str() {
         "localnet" => parse_blockchain_config(args.config, "localnet").await?,
@@ -641,6 +1197,9 @@ async fn realmain(args: Args, ex: Arc<smol::Executor<'static>>) -> Result<()> {
         }
     };
 
+    // Create a scheduled wallet backup, if one is due
+    scheduled_backup(&blockchain_config, ex.clone()).await;
+
     match args.command {
         Subcmd::Kaching => {
             if !args.fun {
@@ -658,6 +1217,7 @@ async fn realmain(args: Args, ex: Arc<smol::Executor<'static>>) -> Result<()> {
                 Some(blockchain_config.endpoint),
                 ex,
                 args.fun,
+                blockchain_config.hooks.clone(),
             )
             .await;
             drk.ping().await?;
@@ -668,6 +1228,8 @@ async fn realmain(args: Args, ex: Arc<smol::Executor<'static>>) -> Result<()> {
 
         Subcmd::Wallet {
             initialize,
+            restore,
+            mnemonic,
             keygen,
             balance,
             address,
@@ -677,8 +1239,13 @@ async fn realmain(args: Args, ex: Arc<smol::Executor<'static>>) -> Result<()> {
             import_secrets,
             tree,
             coins,
+            backup,
+            verify,
+            doctor,
         } => {
             if !initialize &&
+                !restore &&
+                !mnemonic &&
                 !keygen &&
                 !balance &&
                 !address &&
@@ -687,23 +1254,44 @@ async fn realmain(args: Args, ex: Arc<smol::Executor<'static>>) -> Result<()> {
                 !secrets &&
                 !tree &&
                 !coins &&
-                !import_secrets
+                !import_secrets &&
+                !backup &&
+                !verify &&
+                !doctor
             {
                 eprintln!("Error: You must use at least one flag for this subcommand");
                 eprintln!("Run with \"wallet -h\" to see the subcommand usage.");
                 exit(2);
             }
 
+            let backup_dir = backup_dir(&blockchain_config)?;
+            let endpoint = if verify || doctor { Some(blockchain_config.endpoint) } else { None };
             let drk = new_wallet(
                 blockchain_config.wallet_path,
-                blockchain_config.wallet_pass,
-                None,
+                blockchain_config.wallet_pass.clone(),
+                endpoint,
                 ex,
                 args.fun,
+                blockchain_config.hooks.clone(),
             )
             .await;
 
-            if initialize {
+            if initialize || restore {
+                // Grab the mnemonic phrase to recover from, if any
+                let recovered = if restore {
+                    let mut phrase = String::new();
+                    stdin().read_to_string(&mut phrase)?;
+                    match Mnemonic::from_str(&phrase) {
+                        Ok(m) => Some(m),
+                        Err(e) => {
+                            eprintln!("Invalid mnemonic phrase: {e}");
+                            exit(2);
+                        }
+                    }
+                } else {
+                    None
+                };
+
                 if let Err(e) = drk.initialize_wallet().await {
                     eprintln!("Error initializing wallet: {e:?}");
                     exit(2);
@@ -720,43 +1308,127 @@ async fn realmain(args: Args, ex: Arc<smol::Executor<'static>>) -> Result<()> {
                     eprintln!("Failed to initialize Deployooor: {e:?}");
                     exit(2);
                 }
-                return Ok(())
-            }
-
-            if keygen {
-                if let Err(e) = drk.money_keygen().await {
-                    eprintln!("Failed to generate keypair: {e:?}");
+                if let Err(e) = drk.initialize_darkname() {
+                    eprintln!("Failed to initialize Darkname: {e:?}");
+                    exit(2);
+                }
+                if let Err(e) = drk.initialize_auction() {
+                    eprintln!("Failed to initialize Auction: {e:?}");
                     exit(2);
                 }
-                return Ok(())
-            }
-
-            if balance {
-                let balmap = drk.money_balance().await?;
+
+                let is_restore = recovered.is_some();
+                let mnemonic = match drk.initialize_money_seed(recovered).await {
+                    Ok(m) => m,
+                    Err(e) => {
+                        eprintln!("Failed to initialize mnemonic seed: {e:?}");
+                        exit(2);
+                    }
+                };
+
+                if is_restore {
+                    if let Err(e) = drk.money_restore_keys(MONEY_KEYS_RESTORE_COUNT).await {
+                        eprintln!("Failed to restore wallet keypairs: {e:?}");
+                        exit(2);
+                    }
+                    println!("Wallet restored from mnemonic phrase.");
+                    println!("Use \"scan\" to find your coins.");
+                } else {
+                    println!("Write down the following mnemonic phrase, and store it securely.");
+                    println!("It is the only way to recover your wallet keys:");
+                    println!("{mnemonic}");
+                }
+
+                return Ok(())
+            }
+
+            if mnemonic {
+                let mnemonic = match drk.get_money_mnemonic().await {
+                    Ok(m) => m,
+                    Err(e) => {
+                        eprintln!("Failed to fetch wallet mnemonic phrase: {e:?}");
+                        exit(2);
+                    }
+                };
+
+                println!("{mnemonic}");
+
+                return Ok(())
+            }
+
+            if keygen {
+                if let Err(e) = drk.money_keygen().await {
+                    eprintln!("Failed to generate keypair: {e:?}");
+                    exit(2);
+                }
+                return Ok(())
+            }
+
+            if balance {
+                let balmap = drk.money_balance().await?;
 
                 let aliases_map = drk.get_aliases_mapped_by_token().await?;
 
+                // Grab fiat prices for aliased tokens, if enabled
+                let fiat = fiat_prices(&blockchain_config);
+                let prices = match fiat {
+                    Some(ref fiat) => {
+                        let symbols: Vec<String> = balmap
+                            .keys()
+                            .filter_map(|t| aliases_map.get(t))
+                            .flat_map(|a| a.split(", ").map(|s| s.to_string()))
+                            .collect();
+                        fiat.prices(&symbols).await
+                    }
+                    None => Default::default(),
+                };
+
                 // Create a prettytable with the new data:
                 let mut table = Table::new();
                 table.set_format(*format::consts::FORMAT_NO_BORDER_LINE_SEPARATOR);
-                table.set_titles(row!["Token ID", "Aliases", "Balance"]);
+                match fiat {
+                    Some(ref fiat) => table.set_titles(row![
+                        "Token ID",
+                        "Aliases",
+                        "Balance",
+                        format!("≈ {}", fiat.currency)
+                    ]),
+                    None => table.set_titles(row!["Token ID", "Aliases", "Balance"]),
+                }
                 for (token_id, balance) in balmap.iter() {
                     let aliases = match aliases_map.get(token_id) {
                         Some(a) => a,
                         None => "-",
                     };
 
-                    table.add_row(row![
-                        token_id,
-                        aliases,
-                        encode_base10(*balance, BALANCE_BASE10_DECIMALS)
-                    ]);
+                    if fiat.is_none() {
+                        table.add_row(row![token_id, aliases, format_amount(*balance)]);
+                        continue
+                    }
+
+                    let value = aliases
+                        .split(", ")
+                        .find_map(|a| prices.get(a))
+                        .map(|price| {
+                            let amount =
+                                *balance as f64 / 10_f64.powi(BALANCE_BASE10_DECIMALS as i32);
+                            format_decimal(amount * price, 2)
+                        })
+                        .unwrap_or_else(|| "-".to_string());
+
+                    table.add_row(row![token_id, aliases, format_amount(*balance), value]);
                 }
 
                 if table.is_empty() {
                     println!("No unspent balances found");
                 } else {
                     println!("{table}");
+                    if let Some(fiat) = fiat {
+                        println!(
+                            "Fiat values are approximate, using current prices from {}",
+                            fiat.source_name()
+                        );
+                    }
                 }
 
                 return Ok(())
@@ -903,11 +1575,7 @@ async fn realmain(args: Args, ex: Arc<smol::Executor<'static>>) -> Result<()> {
                         coin.1,
                         coin.0.note.token_id,
                         aliases,
-                        format!(
-                            "{} ({})",
-                            coin.0.note.value,
-                            encode_base10(coin.0.note.value, BALANCE_BASE10_DECIMALS)
-                        ),
+                        format!("{} ({})", coin.0.note.value, format_amount(coin.0.note.value)),
                         spend_hook,
                         user_data,
                         coin.2
@@ -919,6 +1587,65 @@ async fn realmain(args: Args, ex: Arc<smol::Executor<'static>>) -> Result<()> {
                 return Ok(())
             }
 
+            if backup {
+                match drk.backup_wallet(
+                    &backup_dir,
+                    &blockchain_config.wallet_pass,
+                    blockchain_config.backup_copies,
+                ) {
+                    Ok(path) => println!("Wallet backed up to {}", path.display()),
+                    Err(e) => {
+                        eprintln!("Failed to backup wallet: {e:?}");
+                        exit(2);
+                    }
+                }
+
+                return Ok(())
+            }
+
+            if verify {
+                let problems = match drk.verify_wallet().await {
+                    Ok(p) => p,
+                    Err(e) => {
+                        eprintln!("Failed to verify wallet: {e:?}");
+                        exit(2);
+                    }
+                };
+                drk.stop_rpc_client().await?;
+
+                if problems.is_empty() {
+                    println!("Wallet is consistent");
+                    return Ok(())
+                }
+
+                for problem in problems {
+                    eprintln!("{problem}");
+                }
+                exit(2);
+            }
+
+            if doctor {
+                let anomalies = match drk.wallet_doctor().await {
+                    Ok(a) => a,
+                    Err(e) => {
+                        eprintln!("Failed to scan wallet for anomalies: {e:?}");
+                        exit(2);
+                    }
+                };
+                drk.stop_rpc_client().await?;
+
+                if anomalies.is_empty() {
+                    println!("No wallet anomalies found");
+                    return Ok(())
+                }
+
+                for anomaly in anomalies {
+                    eprintln!("{anomaly}");
+                    eprintln!("  Fix: {}", anomaly.fix());
+                }
+                exit(2);
+            }
+
             unreachable!()
         }
 
@@ -931,6 +1658,7 @@ async fn realmain(args: Args, ex: Arc<smol::Executor<'static>>) -> Result<()> {
                 None,
                 ex,
                 args.fun,
+                blockchain_config.hooks.clone(),
             )
             .await;
 
@@ -966,6 +1694,7 @@ async fn realmain(args: Args, ex: Arc<smol::Executor<'static>>) -> Result<()> {
                 None,
                 ex,
                 args.fun,
+                blockchain_config.hooks.clone(),
             )
             .await;
             if let Err(e) = drk.unspend_coin(&coin).await {
@@ -983,6 +1712,7 @@ async fn realmain(args: Args, ex: Arc<smol::Executor<'static>>) -> Result<()> {
                 Some(blockchain_config.endpoint),
                 ex,
                 args.fun,
+                blockchain_config.hooks.clone(),
             )
             .await;
 
@@ -991,12 +1721,33 @@ async fn realmain(args: Args, ex: Arc<smol::Executor<'static>>) -> Result<()> {
                 exit(2);
             }
 
-            let rcpt = match PublicKey::from_str(&recipient) {
-                Ok(r) => r,
-                Err(e) => {
-                    eprintln!("Invalid recipient: {e:?}");
-                    exit(2);
-                }
+            // Recipient can either be a plain public key, a `ViewAddress`,
+            // or a registered name resolving to an address.
+            let (rcpt, rcpt_view_key) = match PublicKey::from_str(&recipient) {
+                Ok(r) => (r, None),
+                Err(e) => match ViewAddress::from_str(&recipient) {
+                    Ok(a) => (a.public_key, Some(a.view_key)),
+                    Err(_) if is_valid_name(&recipient) => {
+                        match drk.resolve_name(&recipient).await {
+                            Ok(NameTarget { public_key: Some(r), .. }) => {
+                                eprintln!("Resolved {recipient} to {r}");
+                                (r, None)
+                            }
+                            Ok(_) => {
+                                eprintln!("Name {recipient} doesn't resolve to an address");
+                                exit(2);
+                            }
+                            Err(e) => {
+                                eprintln!("Failed to resolve recipient name: {e:?}");
+                                exit(2);
+                            }
+                        }
+                    }
+                    Err(_) => {
+                        eprintln!("Invalid recipient: {e:?}");
+                        exit(2);
+                    }
+                },
             };
 
             let token_id = match drk.get_token(token).await {
@@ -1040,7 +1791,7 @@ async fn realmain(args: Args, ex: Arc<smol::Executor<'static>>) -> Result<()> {
             };
 
             let tx = match drk
-                .transfer(&amount, token_id, rcpt, spend_hook, user_data, half_split)
+                .transfer(&amount, token_id, rcpt, rcpt_view_key, spend_hook, user_data, half_split)
                 .await
             {
                 Ok(t) => t,
@@ -1055,6 +1806,65 @@ async fn realmain(args: Args, ex: Arc<smol::Executor<'static>>) -> Result<()> {
             drk.stop_rpc_client().await
         }
 
+        Subcmd::Sweep { token, max_inputs, max_value, max_fee, max_delay } => {
+            let drk = new_wallet(
+                blockchain_config.wallet_path,
+                blockchain_config.wallet_pass,
+                Some(blockchain_config.endpoint),
+                ex,
+                args.fun,
+                blockchain_config.hooks.clone(),
+            )
+            .await;
+
+            let token_id = match drk.get_token(token).await {
+                Ok(t) => t,
+                Err(e) => {
+                    eprintln!("Invalid token alias: {e:?}");
+                    exit(2);
+                }
+            };
+
+            let max_value = match max_value {
+                Some(v) => match decode_base10(&v, BALANCE_BASE10_DECIMALS, false) {
+                    Ok(v) => Some(v),
+                    Err(e) => {
+                        eprintln!("Invalid max value: {e:?}");
+                        exit(2);
+                    }
+                },
+                None => None,
+            };
+
+            let max_fee = match max_fee {
+                Some(v) => match decode_base10(&v, BALANCE_BASE10_DECIMALS, false) {
+                    Ok(v) => Some(v),
+                    Err(e) => {
+                        eprintln!("Invalid max fee: {e:?}");
+                        exit(2);
+                    }
+                },
+                None => None,
+            };
+
+            // Make sure the wallet is up to date before selecting coins
+            if let Err(e) = drk.scan_blocks().await {
+                eprintln!("Failed during scanning: {e:?}");
+                exit(2);
+            }
+
+            let txids = match drk.sweep(token_id, max_inputs, max_value, max_fee, max_delay).await {
+                Ok(t) => t,
+                Err(e) => {
+                    eprintln!("Failed to sweep coins: {e:?}");
+                    exit(2);
+                }
+            };
+            println!("Swept coins in {} transactions", txids.len());
+
+            drk.stop_rpc_client().await
+        }
+
         Subcmd::Otc { command } => match command {
             OtcSubcmd::Init { value_pair, token_pair } => {
                 let drk = new_wallet(
@@ -1063,6 +1873,7 @@ async fn realmain(args: Args, ex: Arc<smol::Executor<'static>>) -> Result<()> {
                     Some(blockchain_config.endpoint),
                     ex,
                     args.fun,
+                    blockchain_config.hooks.clone(),
                 )
                 .await;
                 let value_pair = parse_value_pair(&value_pair)?;
@@ -1096,6 +1907,7 @@ async fn realmain(args: Args, ex: Arc<smol::Executor<'static>>) -> Result<()> {
                     Some(blockchain_config.endpoint),
                     ex,
                     args.fun,
+                    blockchain_config.hooks.clone(),
                 )
                 .await;
                 let tx = match drk.join_swap(partial, None, None, None).await {
@@ -1124,6 +1936,7 @@ async fn realmain(args: Args, ex: Arc<smol::Executor<'static>>) -> Result<()> {
                     None,
                     ex,
                     args.fun,
+                    blockchain_config.hooks.clone(),
                 )
                 .await;
                 if let Err(e) = drk.inspect_swap(bytes).await {
@@ -1143,6 +1956,7 @@ async fn realmain(args: Args, ex: Arc<smol::Executor<'static>>) -> Result<()> {
                     None,
                     ex,
                     args.fun,
+                    blockchain_config.hooks.clone(),
                 )
                 .await;
                 if let Err(e) = drk.sign_swap(&mut tx).await {
@@ -1155,39 +1969,23 @@ async fn realmain(args: Args, ex: Arc<smol::Executor<'static>>) -> Result<()> {
             }
         },
 
-        Subcmd::Dao { command } => match command {
-            DaoSubcmd::Create {
-                proposer_limit,
-                quorum,
-                early_exec_quorum,
-                approval_ratio,
-                gov_token_id,
-            } => {
-                if let Err(e) = f64::from_str(&proposer_limit) {
-                    eprintln!("Invalid proposer limit: {e:?}");
-                    exit(2);
-                }
-                if let Err(e) = f64::from_str(&quorum) {
-                    eprintln!("Invalid quorum: {e:?}");
-                    exit(2);
-                }
-                if let Err(e) = f64::from_str(&early_exec_quorum) {
-                    eprintln!("Invalid early exec quorum: {e:?}");
-                    exit(2);
-                }
-
-                let proposer_limit = decode_base10(&proposer_limit, BALANCE_BASE10_DECIMALS, true)?;
-                let quorum = decode_base10(&quorum, BALANCE_BASE10_DECIMALS, true)?;
-                let early_exec_quorum =
-                    decode_base10(&early_exec_quorum, BALANCE_BASE10_DECIMALS, true)?;
-
-                if approval_ratio > 1.0 {
-                    eprintln!("Error: Approval ratio cannot be >1.0");
-                    exit(2);
-                }
+        Subcmd::FeeSwap { command } => match command {
+            FeeSwapSubcmd::Offer { token, rate, max_fee } => {
+                let rate = match decode_base10(&rate, BALANCE_BASE10_DECIMALS, false) {
+                    Ok(r) => r,
+                    Err(e) => {
+                        eprintln!("Invalid rate: {e:?}");
+                        exit(2);
+                    }
+                };
 
-                let approval_ratio_base = 100_u64;
-                let approval_ratio_quot = (approval_ratio * approval_ratio_base as f64) as u64;
+                let max_fee = match decode_base10(&max_fee, BALANCE_BASE10_DECIMALS, false) {
+                    Ok(m) => m,
+                    Err(e) => {
+                        eprintln!("Invalid maximum fee: {e:?}");
+                        exit(2);
+                    }
+                };
 
                 let drk = new_wallet(
                     blockchain_config.wallet_path,
@@ -1195,64 +1993,39 @@ async fn realmain(args: Args, ex: Arc<smol::Executor<'static>>) -> Result<()> {
                     None,
                     ex,
                     args.fun,
+                    blockchain_config.hooks.clone(),
                 )
                 .await;
-                let gov_token_id = match drk.get_token(gov_token_id).await {
-                    Ok(g) => g,
+
+                let token_id = match drk.get_token(token).await {
+                    Ok(t) => t,
                     Err(e) => {
-                        eprintln!("Invalid Token ID: {e:?}");
+                        eprintln!("Invalid token alias: {e:?}");
                         exit(2);
                     }
                 };
 
-                let notes_keypair = Keypair::random(&mut OsRng);
-                let proposer_keypair = Keypair::random(&mut OsRng);
-                let proposals_keypair = Keypair::random(&mut OsRng);
-                let votes_keypair = Keypair::random(&mut OsRng);
-                let exec_keypair = Keypair::random(&mut OsRng);
-                let early_exec_keypair = Keypair::random(&mut OsRng);
-                let bulla_blind = BaseBlind::random(&mut OsRng);
-
-                let params = DaoParams::new(
-                    proposer_limit,
-                    quorum,
-                    early_exec_quorum,
-                    approval_ratio_base,
-                    approval_ratio_quot,
-                    gov_token_id,
-                    Some(notes_keypair.secret),
-                    notes_keypair.public,
-                    Some(proposer_keypair.secret),
-                    proposer_keypair.public,
-                    Some(proposals_keypair.secret),
-                    proposals_keypair.public,
-                    Some(votes_keypair.secret),
-                    votes_keypair.public,
-                    Some(exec_keypair.secret),
-                    exec_keypair.public,
-                    Some(early_exec_keypair.secret),
-                    early_exec_keypair.public,
-                    bulla_blind,
-                );
-
-                println!("{}", params.toml_str());
+                let offer = match drk.fee_offer_create(token_id, rate, max_fee).await {
+                    Ok(o) => o,
+                    Err(e) => {
+                        eprintln!("Failed to create fee offer: {e:?}");
+                        exit(2);
+                    }
+                };
 
+                println!("{}", base64::encode(&serialize_async(&offer).await));
                 Ok(())
             }
 
-            DaoSubcmd::View => {
+            FeeSwapSubcmd::Import => {
                 let mut buf = String::new();
                 stdin().read_to_string(&mut buf)?;
-                let params = DaoParams::from_toml_str(&buf)?;
-                println!("{params}");
-
-                Ok(())
-            }
+                let Some(bytes) = base64::decode(buf.trim()) else {
+                    eprintln!("Failed to decode fee offer");
+                    exit(2);
+                };
 
-            DaoSubcmd::Import { name } => {
-                let mut buf = String::new();
-                stdin().read_to_string(&mut buf)?;
-                let params = DaoParams::from_toml_str(&buf)?;
+                let offer: FeeOffer = deserialize_async(&bytes).await?;
 
                 let drk = new_wallet(
                     blockchain_config.wallet_path,
@@ -1260,44 +2033,333 @@ async fn realmain(args: Args, ex: Arc<smol::Executor<'static>>) -> Result<()> {
                     None,
                     ex,
                     args.fun,
+                    blockchain_config.hooks.clone(),
                 )
                 .await;
-                if let Err(e) = drk.import_dao(&name, &params).await {
-                    eprintln!("Failed to import DAO: {e:?}");
+                if let Err(e) = drk.put_fee_offer(&offer).await {
+                    eprintln!("Failed to import fee offer: {e:?}");
                     exit(2);
                 }
 
+                println!("Imported fee offer for token {}", offer.token_id);
                 Ok(())
             }
 
-            DaoSubcmd::UpdateKeys => {
-                let mut buf = String::new();
-                stdin().read_to_string(&mut buf)?;
-                let params = DaoParams::from_toml_str(&buf)?;
-
+            FeeSwapSubcmd::List => {
                 let drk = new_wallet(
                     blockchain_config.wallet_path,
                     blockchain_config.wallet_pass,
                     None,
                     ex,
                     args.fun,
+                    blockchain_config.hooks.clone(),
                 )
                 .await;
-                if let Err(e) = drk.update_dao_keys(&params).await {
-                    eprintln!("Failed to update DAO keys: {e:?}");
-                    exit(2);
+
+                let offers = match drk.get_fee_offers().await {
+                    Ok(o) => o,
+                    Err(e) => {
+                        eprintln!("Failed to fetch fee offers: {e:?}");
+                        exit(2);
+                    }
+                };
+
+                let aliases_map = drk.get_aliases_mapped_by_token().await?;
+
+                // Create a prettytable with the new data:
+                let mut table = Table::new();
+                table.set_format(*format::consts::FORMAT_NO_BORDER_LINE_SEPARATOR);
+                table.set_titles(row!["Token ID", "Aliases", "Rate", "Max Fee", "Recipient"]);
+                for offer in offers {
+                    let token_id = offer.token_id.to_string();
+                    let aliases = match aliases_map.get(&token_id) {
+                        Some(a) => a,
+                        None => "-",
+                    };
+
+                    table.add_row(row![
+                        token_id,
+                        aliases,
+                        format_amount(offer.rate),
+                        format_amount(offer.max_fee),
+                        offer.recipient
+                    ]);
+                }
+
+                if table.is_empty() {
+                    println!("No fee offers found");
+                } else {
+                    println!("{table}");
                 }
 
                 Ok(())
             }
 
-            DaoSubcmd::List { name } => {
-                let drk = new_wallet(
+            FeeSwapSubcmd::Transfer { amount, token, recipient } => {
+                let amount = match decode_base10(&amount, BALANCE_BASE10_DECIMALS, false) {
+                    Ok(a) => a,
+                    Err(e) => {
+                        eprintln!("Invalid amount: {e:?}");
+                        exit(2);
+                    }
+                };
+
+                let (rcpt, rcpt_view_key) = match PublicKey::from_str(&recipient) {
+                    Ok(r) => (r, None),
+                    Err(e) => match ViewAddress::from_str(&recipient) {
+                        Ok(a) => (a.public_key, Some(a.view_key)),
+                        Err(_) => {
+                            eprintln!("Invalid recipient: {e:?}");
+                            exit(2);
+                        }
+                    },
+                };
+
+                let drk = new_wallet(
+                    blockchain_config.wallet_path,
+                    blockchain_config.wallet_pass,
+                    Some(blockchain_config.endpoint),
+                    ex,
+                    args.fun,
+                    blockchain_config.hooks.clone(),
+                )
+                .await;
+
+                let token_id = match drk.get_token(token).await {
+                    Ok(t) => t,
+                    Err(e) => {
+                        eprintln!("Invalid token alias: {e:?}");
+                        exit(2);
+                    }
+                };
+
+                let request =
+                    match drk.fee_swap_transfer(amount, token_id, rcpt, rcpt_view_key).await {
+                        Ok(r) => r,
+                        Err(e) => {
+                            eprintln!("Failed to create fee swap request: {e:?}");
+                            exit(2);
+                        }
+                    };
+
+                println!("{}", base64::encode(&serialize_async(&request).await));
+                drk.stop_rpc_client().await
+            }
+
+            FeeSwapSubcmd::Fund => {
+                let mut buf = String::new();
+                stdin().read_to_string(&mut buf)?;
+                let Some(bytes) = base64::decode(buf.trim()) else {
+                    eprintln!("Failed to decode fee swap request");
+                    exit(2);
+                };
+
+                let request: FeeSwapRequest = deserialize_async(&bytes).await?;
+
+                let drk = new_wallet(
+                    blockchain_config.wallet_path,
+                    blockchain_config.wallet_pass,
+                    Some(blockchain_config.endpoint),
+                    ex,
+                    args.fun,
+                    blockchain_config.hooks.clone(),
+                )
+                .await;
+                let tx = match drk.fee_swap_fund(request).await {
+                    Ok(tx) => tx,
+                    Err(e) => {
+                        eprintln!("Failed to fund fee swap request: {e:?}");
+                        exit(2);
+                    }
+                };
+
+                println!("{}", base64::encode(&serialize_async(&tx).await));
+                drk.stop_rpc_client().await
+            }
+
+            FeeSwapSubcmd::Sign => {
+                let mut tx = parse_tx_from_stdin().await?;
+
+                let drk = new_wallet(
+                    blockchain_config.wallet_path,
+                    blockchain_config.wallet_pass,
+                    None,
+                    ex,
+                    args.fun,
+                    blockchain_config.hooks.clone(),
+                )
+                .await;
+                if let Err(e) = drk.fee_swap_sign(&mut tx).await {
+                    eprintln!("Failed to sign fee swap transaction: {e:?}");
+                    exit(2);
+                };
+
+                println!("{}", base64::encode(&serialize_async(&tx).await));
+                Ok(())
+            }
+        },
+
+        Subcmd::Dao { command } => match command {
+            DaoSubcmd::Create {
+                proposer_limit,
+                quorum,
+                early_exec_quorum,
+                approval_ratio,
+                gov_token_id,
+                members,
+            } => {
+                if let Err(e) = f64::from_str(&proposer_limit) {
+                    eprintln!("Invalid proposer limit: {e:?}");
+                    exit(2);
+                }
+                if let Err(e) = f64::from_str(&quorum) {
+                    eprintln!("Invalid quorum: {e:?}");
+                    exit(2);
+                }
+                if let Err(e) = f64::from_str(&early_exec_quorum) {
+                    eprintln!("Invalid early exec quorum: {e:?}");
+                    exit(2);
+                }
+
+                let proposer_limit = decode_base10(&proposer_limit, BALANCE_BASE10_DECIMALS, true)?;
+                let quorum = decode_base10(&quorum, BALANCE_BASE10_DECIMALS, true)?;
+                let early_exec_quorum =
+                    decode_base10(&early_exec_quorum, BALANCE_BASE10_DECIMALS, true)?;
+
+                if approval_ratio > 1.0 {
+                    eprintln!("Error: Approval ratio cannot be >1.0");
+                    exit(2);
+                }
+
+                let approval_ratio_base = 100_u64;
+                let approval_ratio_quot = (approval_ratio * approval_ratio_base as f64) as u64;
+
+                let drk = new_wallet(
+                    blockchain_config.wallet_path,
+                    blockchain_config.wallet_pass,
+                    None,
+                    ex,
+                    args.fun,
+                    blockchain_config.hooks.clone(),
+                )
+                .await;
+                let gov_token_id = match drk.get_token(gov_token_id).await {
+                    Ok(g) => g,
+                    Err(e) => {
+                        eprintln!("Invalid Token ID: {e:?}");
+                        exit(2);
+                    }
+                };
+
+                let mut members_keys = Vec::with_capacity(members.len());
+                for member in members {
+                    match PublicKey::from_str(&member) {
+                        Ok(m) => members_keys.push(m),
+                        Err(e) => {
+                            eprintln!("Invalid member public key: {e:?}");
+                            exit(2);
+                        }
+                    }
+                }
+
+                let (keypairs, bulla_blind) = match drk.dao_keygen().await {
+                    Ok(k) => k,
+                    Err(e) => {
+                        eprintln!("Failed to derive DAO keys: {e:?}");
+                        exit(2);
+                    }
+                };
+                let [notes, proposer, proposals, votes, exec, early_exec] = keypairs;
+
+                let params = DaoParams::new(
+                    proposer_limit,
+                    quorum,
+                    early_exec_quorum,
+                    approval_ratio_base,
+                    approval_ratio_quot,
+                    gov_token_id,
+                    Some(notes.secret),
+                    notes.public,
+                    Some(proposer.secret),
+                    proposer.public,
+                    Some(proposals.secret),
+                    proposals.public,
+                    Some(votes.secret),
+                    votes.public,
+                    Some(exec.secret),
+                    exec.public,
+                    Some(early_exec.secret),
+                    early_exec.public,
+                    members_keys,
+                    bulla_blind,
+                );
+
+                println!("{}", params.toml_str());
+
+                Ok(())
+            }
+
+            DaoSubcmd::View => {
+                let mut buf = String::new();
+                stdin().read_to_string(&mut buf)?;
+                let params = DaoParams::from_toml_str(&buf)?;
+                println!("{params}");
+
+                Ok(())
+            }
+
+            DaoSubcmd::Import { name } => {
+                let mut buf = String::new();
+                stdin().read_to_string(&mut buf)?;
+                let params = DaoParams::from_toml_str(&buf)?;
+
+                let drk = new_wallet(
+                    blockchain_config.wallet_path,
+                    blockchain_config.wallet_pass,
+                    None,
+                    ex,
+                    args.fun,
+                    blockchain_config.hooks.clone(),
+                )
+                .await;
+                if let Err(e) = drk.import_dao(&name, &params).await {
+                    eprintln!("Failed to import DAO: {e:?}");
+                    exit(2);
+                }
+
+                Ok(())
+            }
+
+            DaoSubcmd::UpdateKeys => {
+                let mut buf = String::new();
+                stdin().read_to_string(&mut buf)?;
+                let params = DaoParams::from_toml_str(&buf)?;
+
+                let drk = new_wallet(
+                    blockchain_config.wallet_path,
+                    blockchain_config.wallet_pass,
+                    None,
+                    ex,
+                    args.fun,
+                    blockchain_config.hooks.clone(),
+                )
+                .await;
+                if let Err(e) = drk.update_dao_keys(&params).await {
+                    eprintln!("Failed to update DAO keys: {e:?}");
+                    exit(2);
+                }
+
+                Ok(())
+            }
+
+            DaoSubcmd::List { name } => {
+                let drk = new_wallet(
                     blockchain_config.wallet_path,
                     blockchain_config.wallet_pass,
                     None,
                     ex,
                     args.fun,
+                    blockchain_config.hooks.clone(),
                 )
                 .await;
                 if let Err(e) = drk.dao_list(&name).await {
@@ -1315,6 +2377,7 @@ async fn realmain(args: Args, ex: Arc<smol::Executor<'static>>) -> Result<()> {
                     None,
                     ex,
                     args.fun,
+                    blockchain_config.hooks.clone(),
                 )
                 .await;
                 let balmap = match drk.dao_balance(&name).await {
@@ -1343,11 +2406,7 @@ async fn realmain(args: Args, ex: Arc<smol::Executor<'static>>) -> Result<()> {
                         None => "-",
                     };
 
-                    table.add_row(row![
-                        token_id,
-                        aliases,
-                        encode_base10(*balance, BALANCE_BASE10_DECIMALS)
-                    ]);
+                    table.add_row(row![token_id, aliases, format_amount(*balance)]);
                 }
 
                 if table.is_empty() {
@@ -1366,6 +2425,7 @@ async fn realmain(args: Args, ex: Arc<smol::Executor<'static>>) -> Result<()> {
                     Some(blockchain_config.endpoint),
                     ex,
                     args.fun,
+                    blockchain_config.hooks.clone(),
                 )
                 .await;
                 let tx = match drk.dao_mint(&name).await {
@@ -1395,6 +2455,7 @@ async fn realmain(args: Args, ex: Arc<smol::Executor<'static>>) -> Result<()> {
                     Some(blockchain_config.endpoint),
                     ex,
                     args.fun,
+                    blockchain_config.hooks.clone(),
                 )
                 .await;
 
@@ -1476,6 +2537,7 @@ async fn realmain(args: Args, ex: Arc<smol::Executor<'static>>) -> Result<()> {
                     Some(blockchain_config.endpoint),
                     ex,
                     args.fun,
+                    blockchain_config.hooks.clone(),
                 )
                 .await;
 
@@ -1520,6 +2582,7 @@ async fn realmain(args: Args, ex: Arc<smol::Executor<'static>>) -> Result<()> {
                     None,
                     ex,
                     args.fun,
+                    blockchain_config.hooks.clone(),
                 )
                 .await;
                 let proposals = drk.get_dao_proposals(&name).await?;
@@ -1546,6 +2609,7 @@ async fn realmain(args: Args, ex: Arc<smol::Executor<'static>>) -> Result<()> {
                     Some(blockchain_config.endpoint),
                     ex,
                     args.fun,
+                    blockchain_config.hooks.clone(),
                 )
                 .await;
                 let proposal = drk.get_dao_proposal_by_bulla(&bulla).await?;
@@ -1642,7 +2706,7 @@ async fn realmain(args: Args, ex: Arc<smol::Executor<'static>>) -> Result<()> {
                         coin.public_key,
                         "Amount",
                         coin.value,
-                        encode_base10(coin.value, BALANCE_BASE10_DECIMALS),
+                        format_amount(coin.value),
                         "Token",
                         coin.token_id,
                         "Spend hook",
@@ -1675,7 +2739,7 @@ async fn realmain(args: Args, ex: Arc<smol::Executor<'static>>) -> Result<()> {
 
                     table.add_row(row![
                         vote.tx_hash,
-                        encode_base10(vote.all_vote_value, BALANCE_BASE10_DECIMALS),
+                        format_amount(vote.all_vote_value),
                         vote_option
                     ]);
                 }
@@ -1686,19 +2750,16 @@ async fn realmain(args: Args, ex: Arc<smol::Executor<'static>>) -> Result<()> {
                 } else {
                     println!("Votes:");
                     println!("{table}");
-                    println!(
-                        "Total tokens votes: {}",
-                        encode_base10(total_all_vote_value, BALANCE_BASE10_DECIMALS)
-                    );
+                    println!("Total tokens votes: {}", format_amount(total_all_vote_value));
                     let approval_ratio =
                         (total_yes_vote_value as f64 * 100.0) / total_all_vote_value as f64;
                     println!(
                         "Total tokens Yes votes: {} ({approval_ratio:.2}%)",
-                        encode_base10(total_yes_vote_value, BALANCE_BASE10_DECIMALS)
+                        format_amount(total_yes_vote_value)
                     );
                     println!(
                         "Total tokens No votes: {} ({:.2}%)",
-                        encode_base10(total_no_vote_value, BALANCE_BASE10_DECIMALS),
+                        format_amount(total_no_vote_value),
                         (total_no_vote_value as f64 * 100.0) / total_all_vote_value as f64
                     );
 
@@ -1753,6 +2814,7 @@ async fn realmain(args: Args, ex: Arc<smol::Executor<'static>>) -> Result<()> {
                     None,
                     ex,
                     args.fun,
+                    blockchain_config.hooks.clone(),
                 )
                 .await;
 
@@ -1819,6 +2881,7 @@ async fn realmain(args: Args, ex: Arc<smol::Executor<'static>>) -> Result<()> {
                     Some(blockchain_config.endpoint),
                     ex,
                     args.fun,
+                    blockchain_config.hooks.clone(),
                 )
                 .await;
                 let tx = match drk.dao_vote(&bulla, vote, weight).await {
@@ -1848,6 +2911,7 @@ async fn realmain(args: Args, ex: Arc<smol::Executor<'static>>) -> Result<()> {
                     Some(blockchain_config.endpoint),
                     ex,
                     args.fun,
+                    blockchain_config.hooks.clone(),
                 )
                 .await;
                 let proposal = drk.get_dao_proposal_by_bulla(&bulla).await?;
@@ -1896,6 +2960,27 @@ async fn realmain(args: Args, ex: Arc<smol::Executor<'static>>) -> Result<()> {
 
                 Ok(())
             }
+
+            DaoSubcmd::Watch { desktop, hook } => {
+                let drk = new_wallet(
+                    blockchain_config.wallet_path,
+                    blockchain_config.wallet_pass,
+                    Some(blockchain_config.endpoint.clone()),
+                    ex.clone(),
+                    args.fun,
+                )
+                .await;
+
+                let dao_watcher = DaoWatcher::new(desktop, hook);
+                if let Err(e) =
+                    drk.subscribe_blocks(blockchain_config.endpoint, ex, Some(&dao_watcher)).await
+                {
+                    eprintln!("DAO watch subscription failed: {e:?}");
+                    exit(2);
+                }
+
+                drk.stop_rpc_client().await
+            }
         },
 
         Subcmd::AttachFee => {
@@ -1907,6 +2992,7 @@ async fn realmain(args: Args, ex: Arc<smol::Executor<'static>>) -> Result<()> {
                 Some(blockchain_config.endpoint),
                 ex,
                 args.fun,
+                blockchain_config.hooks.clone(),
             )
             .await;
             if let Err(e) = drk.attach_fee(&mut tx).await {
@@ -1936,6 +3022,7 @@ async fn realmain(args: Args, ex: Arc<smol::Executor<'static>>) -> Result<()> {
                 Some(blockchain_config.endpoint),
                 ex,
                 args.fun,
+                blockchain_config.hooks.clone(),
             )
             .await;
 
@@ -1972,7 +3059,7 @@ async fn realmain(args: Args, ex: Arc<smol::Executor<'static>>) -> Result<()> {
             )
             .await;
 
-            if let Err(e) = drk.subscribe_blocks(blockchain_config.endpoint, ex).await {
+            if let Err(e) = drk.subscribe_blocks(blockchain_config.endpoint, ex, None).await {
                 eprintln!("Block subscription failed: {e:?}");
                 exit(2);
             }
@@ -1987,6 +3074,7 @@ async fn realmain(args: Args, ex: Arc<smol::Executor<'static>>) -> Result<()> {
                 Some(blockchain_config.endpoint),
                 ex,
                 args.fun,
+                blockchain_config.hooks.clone(),
             )
             .await;
 
@@ -2016,6 +3104,7 @@ async fn realmain(args: Args, ex: Arc<smol::Executor<'static>>) -> Result<()> {
                     Some(blockchain_config.endpoint),
                     ex,
                     args.fun,
+                    blockchain_config.hooks.clone(),
                 )
                 .await;
 
@@ -2057,6 +3146,7 @@ async fn realmain(args: Args, ex: Arc<smol::Executor<'static>>) -> Result<()> {
                     Some(blockchain_config.endpoint),
                     ex,
                     args.fun,
+                    blockchain_config.hooks.clone(),
                 )
                 .await;
 
@@ -2081,6 +3171,7 @@ async fn realmain(args: Args, ex: Arc<smol::Executor<'static>>) -> Result<()> {
                     None,
                     ex,
                     args.fun,
+                    blockchain_config.hooks.clone(),
                 )
                 .await;
 
@@ -2131,6 +3222,7 @@ async fn realmain(args: Args, ex: Arc<smol::Executor<'static>>) -> Result<()> {
                     None,
                     ex,
                     args.fun,
+                    blockchain_config.hooks.clone(),
                 )
                 .await;
 
@@ -2149,6 +3241,7 @@ async fn realmain(args: Args, ex: Arc<smol::Executor<'static>>) -> Result<()> {
                     None,
                     ex,
                     args.fun,
+                    blockchain_config.hooks.clone(),
                 )
                 .await;
 
@@ -2214,6 +3307,7 @@ async fn realmain(args: Args, ex: Arc<smol::Executor<'static>>) -> Result<()> {
                     None,
                     ex,
                     args.fun,
+                    blockchain_config.hooks.clone(),
                 )
                 .await;
                 if let Err(e) = drk.add_alias(alias, token_id).await {
@@ -2242,6 +3336,7 @@ async fn realmain(args: Args, ex: Arc<smol::Executor<'static>>) -> Result<()> {
                     None,
                     ex,
                     args.fun,
+                    blockchain_config.hooks.clone(),
                 )
                 .await;
                 let map = drk.get_aliases(alias, token_id).await?;
@@ -2270,6 +3365,7 @@ async fn realmain(args: Args, ex: Arc<smol::Executor<'static>>) -> Result<()> {
                     None,
                     ex,
                     args.fun,
+                    blockchain_config.hooks.clone(),
                 )
                 .await;
                 if let Err(e) = drk.remove_alias(alias).await {
@@ -2305,6 +3401,7 @@ async fn realmain(args: Args, ex: Arc<smol::Executor<'static>>) -> Result<()> {
                     None,
                     ex,
                     args.fun,
+                    blockchain_config.hooks.clone(),
                 )
                 .await;
                 let token_id = drk.import_mint_authority(mint_authority, token_blind).await?;
@@ -2320,6 +3417,7 @@ async fn realmain(args: Args, ex: Arc<smol::Executor<'static>>) -> Result<()> {
                     None,
                     ex,
                     args.fun,
+                    blockchain_config.hooks.clone(),
                 )
                 .await;
                 let mint_authority = SecretKey::random(&mut OsRng);
@@ -2337,6 +3435,7 @@ async fn realmain(args: Args, ex: Arc<smol::Executor<'static>>) -> Result<()> {
                     None,
                     ex,
                     args.fun,
+                    blockchain_config.hooks.clone(),
                 )
                 .await;
                 let tokens = drk.get_mint_authorities().await?;
@@ -2383,6 +3482,7 @@ async fn realmain(args: Args, ex: Arc<smol::Executor<'static>>) -> Result<()> {
                     Some(blockchain_config.endpoint),
                     ex,
                     args.fun,
+                    blockchain_config.hooks.clone(),
                 )
                 .await;
 
@@ -2460,6 +3560,7 @@ async fn realmain(args: Args, ex: Arc<smol::Executor<'static>>) -> Result<()> {
                     Some(blockchain_config.endpoint),
                     ex,
                     args.fun,
+                    blockchain_config.hooks.clone(),
                 )
                 .await;
                 let token_id = match drk.get_token(token).await {
@@ -2484,43 +3585,319 @@ async fn realmain(args: Args, ex: Arc<smol::Executor<'static>>) -> Result<()> {
             }
         },
 
-        Subcmd::Contract { command } => match command {
-            ContractSubcmd::GenerateDeploy => {
+        Subcmd::Viewkey { command } => match command {
+            ViewkeySubcmd::Export { incoming_only } => {
+                // Notes are encrypted for the spend key, so a full view
+                // key is the spend key itself.
+                if !incoming_only {
+                    eprintln!("Error: Full view keys are able to spend coins.");
+                    eprintln!("Use \"wallet --secrets\" to export them, or \"--incoming-only\"");
+                    eprintln!("to export keys only able to detect incoming notes.");
+                    exit(2);
+                }
+
                 let drk = new_wallet(
                     blockchain_config.wallet_path,
                     blockchain_config.wallet_pass,
                     None,
                     ex,
                     args.fun,
+                    blockchain_config.hooks.clone(),
                 )
                 .await;
 
-                if let Err(e) = drk.deploy_auth_keygen().await {
-                    eprintln!("Error creating deploy auth keypair: {:?}", e);
-                    exit(2);
+                let view_keys = match drk.money_incoming_view_keys().await {
+                    Ok(v) => v,
+                    Err(e) => {
+                        eprintln!("Failed to derive incoming view keys: {e:?}");
+                        exit(2);
+                    }
+                };
+
+                for view_key in view_keys {
+                    println!("{view_key}");
                 }
 
                 Ok(())
             }
 
-            ContractSubcmd::List => {
+            ViewkeySubcmd::Address => {
                 let drk = new_wallet(
                     blockchain_config.wallet_path,
                     blockchain_config.wallet_pass,
                     None,
                     ex,
                     args.fun,
+                    blockchain_config.hooks.clone(),
                 )
                 .await;
-                let auths = drk.list_deploy_auth().await?;
 
-                let mut table = Table::new();
-                table.set_format(*format::consts::FORMAT_NO_BORDER_LINE_SEPARATOR);
-                table.set_titles(row!["Index", "Contract ID", "Frozen"]);
+                let address = match drk.default_view_address().await {
+                    Ok(a) => a,
+                    Err(e) => {
+                        eprintln!("Failed to fetch default view address: {e:?}");
+                        exit(2);
+                    }
+                };
 
-                for (idx, contract_id, frozen) in auths {
-                    table.add_row(row![idx, contract_id, frozen]);
-                }
+                println!("{address}");
+
+                Ok(())
+            }
+
+            ViewkeySubcmd::Import => {
+                let mut view_keys = vec![];
+                let lines = stdin().lines();
+                for (i, line) in lines.enumerate() {
+                    if let Ok(line) = line {
+                        let Ok(view_key) = IncomingViewKey::from_str(line.trim()) else {
+                            println!("Warning: Failed to parse view key on line {i}");
+                            continue
+                        };
+                        view_keys.push(view_key);
+                    }
+                }
+
+                let drk = new_wallet(
+                    blockchain_config.wallet_path,
+                    blockchain_config.wallet_pass,
+                    None,
+                    ex,
+                    args.fun,
+                    blockchain_config.hooks.clone(),
+                )
+                .await;
+
+                let pubkeys = match drk.import_view_keys(view_keys).await {
+                    Ok(p) => p,
+                    Err(e) => {
+                        eprintln!("Failed to import view keys into wallet: {e:?}");
+                        exit(2);
+                    }
+                };
+
+                for key in pubkeys {
+                    println!("{key}");
+                }
+
+                Ok(())
+            }
+
+            ViewkeySubcmd::Notes => {
+                let drk = new_wallet(
+                    blockchain_config.wallet_path,
+                    blockchain_config.wallet_pass,
+                    None,
+                    ex,
+                    args.fun,
+                    blockchain_config.hooks.clone(),
+                )
+                .await;
+
+                let notes = drk.get_view_notes().await?;
+                let aliases_map = drk.get_aliases_mapped_by_token().await?;
+
+                let mut table = Table::new();
+                table.set_format(*format::consts::FORMAT_NO_BORDER_LINE_SEPARATOR);
+                table.set_titles(row!["Coin", "View Key", "Token ID", "Aliases", "Value", "TX"]);
+                for (coin, view_key, note, tx_hash) in notes {
+                    let aliases = match aliases_map.get(&note.token_id.to_string()) {
+                        Some(a) => a,
+                        None => "-",
+                    };
+
+                    table.add_row(row![
+                        bs58::encode(&serialize_async(&coin.inner()).await).into_string(),
+                        view_key,
+                        note.token_id,
+                        aliases,
+                        format_amount(note.value),
+                        tx_hash
+                    ]);
+                }
+
+                if table.is_empty() {
+                    println!("No view notes found");
+                } else {
+                    println!("{table}");
+                }
+
+                Ok(())
+            }
+        },
+
+        Subcmd::Keys { command } => match command {
+            KeysSubcmd::Rotate { grace_blocks, max_inputs, max_fee, max_delay } => {
+                let drk = new_wallet(
+                    blockchain_config.wallet_path,
+                    blockchain_config.wallet_pass,
+                    Some(blockchain_config.endpoint),
+                    ex,
+                    args.fun,
+                    blockchain_config.hooks.clone(),
+                )
+                .await;
+
+                // Make sure the wallet is up to date, so the grace window
+                // starts from the current height.
+                if let Err(e) = drk.scan_blocks().await {
+                    eprintln!("Failed during scanning: {e:?}");
+                    exit(2);
+                }
+
+                let successor = match drk.rotate_keys(grace_blocks).await {
+                    Ok(s) => s,
+                    Err(e) => {
+                        eprintln!("Failed to rotate keys: {e:?}");
+                        exit(2);
+                    }
+                };
+                println!("Retired keys in favor of new default address {successor}");
+                println!("They will keep getting scanned for during {grace_blocks} blocks");
+
+                migrate_rotated_coins(&drk, max_inputs, max_fee, max_delay).await;
+
+                drk.stop_rpc_client().await
+            }
+
+            KeysSubcmd::Migrate { max_inputs, max_fee, max_delay } => {
+                let drk = new_wallet(
+                    blockchain_config.wallet_path,
+                    blockchain_config.wallet_pass,
+                    Some(blockchain_config.endpoint),
+                    ex,
+                    args.fun,
+                    blockchain_config.hooks.clone(),
+                )
+                .await;
+
+                // Make sure the wallet is up to date before selecting coins
+                if let Err(e) = drk.scan_blocks().await {
+                    eprintln!("Failed during scanning: {e:?}");
+                    exit(2);
+                }
+
+                migrate_rotated_coins(&drk, max_inputs, max_fee, max_delay).await;
+
+                drk.stop_rpc_client().await
+            }
+
+            KeysSubcmd::Status => {
+                let drk = new_wallet(
+                    blockchain_config.wallet_path,
+                    blockchain_config.wallet_pass,
+                    None,
+                    ex,
+                    args.fun,
+                    blockchain_config.hooks.clone(),
+                )
+                .await;
+
+                let rotations = match drk.get_key_rotations().await {
+                    Ok(r) => r,
+                    Err(e) => {
+                        eprintln!("Failed to fetch key rotations: {e:?}");
+                        exit(2);
+                    }
+                };
+                if rotations.is_empty() {
+                    println!("No keys have been rotated");
+                    return Ok(())
+                }
+
+                let height = match drk.get_last_scanned_block() {
+                    Ok((height, _)) => height,
+                    Err(e) => {
+                        eprintln!("Failed to retrieve last scanned block: {e:?}");
+                        exit(2);
+                    }
+                };
+                let mut table = Table::new();
+                table.set_format(*format::consts::FORMAT_NO_BORDER_LINE_SEPARATOR);
+                table.set_titles(row![
+                    "Retired Key",
+                    "Successor",
+                    "Rotation Height",
+                    "Scanned Until",
+                    "Status"
+                ]);
+                for rotation in rotations {
+                    let status = if rotation.grace_end() < height { "Expired" } else { "Grace" };
+                    table.add_row(row![
+                        rotation.public,
+                        rotation.successor,
+                        rotation.height,
+                        rotation.grace_end(),
+                        status
+                    ]);
+                }
+                println!("{table}");
+
+                let coins = match drk.rotated_coins().await {
+                    Ok(c) => c,
+                    Err(e) => {
+                        eprintln!("Failed to fetch coins of retired keys: {e:?}");
+                        exit(2);
+                    }
+                };
+                if coins.is_empty() {
+                    println!("All coins of retired keys have been moved");
+                    return Ok(())
+                }
+
+                let mut table = Table::new();
+                table.set_format(*format::consts::FORMAT_NO_BORDER_LINE_SEPARATOR);
+                table.set_titles(row!["Token ID", "Coins", "Balance"]);
+                for (token_id, token_coins) in coins {
+                    let balance: u64 = token_coins.iter().map(|c| c.note.value).sum();
+                    table.add_row(row![token_id, token_coins.len(), format_amount(balance)]);
+                }
+                println!("Coins still owned by retired keys:");
+                println!("{table}");
+
+                Ok(())
+            }
+        },
+
+        Subcmd::Contract { command } => match command {
+            ContractSubcmd::GenerateDeploy => {
+                let drk = new_wallet(
+                    blockchain_config.wallet_path,
+                    blockchain_config.wallet_pass,
+                    None,
+                    ex,
+                    args.fun,
+                    blockchain_config.hooks.clone(),
+                )
+                .await;
+
+                if let Err(e) = drk.deploy_auth_keygen().await {
+                    eprintln!("Error creating deploy auth keypair: {:?}", e);
+                    exit(2);
+                }
+
+                Ok(())
+            }
+
+            ContractSubcmd::List => {
+                let drk = new_wallet(
+                    blockchain_config.wallet_path,
+                    blockchain_config.wallet_pass,
+                    None,
+                    ex,
+                    args.fun,
+                    blockchain_config.hooks.clone(),
+                )
+                .await;
+                let auths = drk.list_deploy_auth().await?;
+
+                let mut table = Table::new();
+                table.set_format(*format::consts::FORMAT_NO_BORDER_LINE_SEPARATOR);
+                table.set_titles(row!["Index", "Contract ID", "Frozen"]);
+
+                for (idx, contract_id, frozen) in auths {
+                    table.add_row(row![idx, contract_id, frozen]);
+                }
 
                 if table.is_empty() {
                     println!("No deploy authorities found");
@@ -2542,6 +3919,7 @@ async fn realmain(args: Args, ex: Arc<smol::Executor<'static>>) -> Result<()> {
                     Some(blockchain_config.endpoint),
                     ex,
                     args.fun,
+                    blockchain_config.hooks.clone(),
                 )
                 .await;
 
@@ -2570,6 +3948,7 @@ async fn realmain(args: Args, ex: Arc<smol::Executor<'static>>) -> Result<()> {
                     Some(blockchain_config.endpoint),
                     ex,
                     args.fun,
+                    blockchain_config.hooks.clone(),
                 )
                 .await;
 
@@ -2591,5 +3970,875 @@ async fn realmain(args: Args, ex: Arc<smol::Executor<'static>>) -> Result<()> {
                 drk.stop_rpc_client().await
             }
         },
+
+        Subcmd::Name { command } => match command {
+            NameSubcmd::Commit { name } => {
+                let drk = new_wallet(
+                    blockchain_config.wallet_path,
+                    blockchain_config.wallet_pass,
+                    Some(blockchain_config.endpoint),
+                    ex,
+                    args.fun,
+                    blockchain_config.hooks.clone(),
+                )
+                .await;
+
+                let mut tx = match drk.darkname_commit(&name).await {
+                    Ok(v) => v,
+                    Err(e) => {
+                        eprintln!("Error creating name commitment tx: {e:?}");
+                        exit(2);
+                    }
+                };
+
+                if let Err(e) = drk.attach_fee(&mut tx).await {
+                    eprintln!("Failed to attach the fee call to the transaction: {e:?}");
+                    exit(2);
+                };
+
+                println!("{}", base64::encode(&serialize_async(&tx).await));
+
+                drk.stop_rpc_client().await
+            }
+
+            NameSubcmd::Register { name, duration, address, contract } => {
+                let target = match parse_name_target(address, contract) {
+                    Ok(t) => t,
+                    Err(e) => {
+                        eprintln!("Invalid name target: {e:?}");
+                        exit(2);
+                    }
+                };
+
+                let drk = new_wallet(
+                    blockchain_config.wallet_path,
+                    blockchain_config.wallet_pass,
+                    Some(blockchain_config.endpoint),
+                    ex,
+                    args.fun,
+                    blockchain_config.hooks.clone(),
+                )
+                .await;
+
+                let mut tx = match drk.darkname_register(&name, target, duration).await {
+                    Ok(v) => v,
+                    Err(e) => {
+                        eprintln!("Error creating name registration tx: {e:?}");
+                        exit(2);
+                    }
+                };
+
+                if let Err(e) = drk.attach_fee(&mut tx).await {
+                    eprintln!("Failed to attach the fee call to the transaction: {e:?}");
+                    exit(2);
+                };
+
+                println!("{}", base64::encode(&serialize_async(&tx).await));
+
+                drk.stop_rpc_client().await
+            }
+
+            NameSubcmd::Resolve { name } => {
+                let drk = new_wallet(
+                    blockchain_config.wallet_path,
+                    blockchain_config.wallet_pass,
+                    None,
+                    ex,
+                    args.fun,
+                    blockchain_config.hooks.clone(),
+                )
+                .await;
+
+                let target = match drk.resolve_name(&name).await {
+                    Ok(t) => t,
+                    Err(e) => {
+                        eprintln!("Failed to resolve name: {e:?}");
+                        exit(2);
+                    }
+                };
+
+                if let Some(public_key) = target.public_key {
+                    println!("Address: {public_key}");
+                }
+                if let Some(contract_id) = target.contract_id {
+                    println!("Contract ID: {contract_id}");
+                }
+
+                Ok(())
+            }
+
+            NameSubcmd::Renew { name, duration } => {
+                let drk = new_wallet(
+                    blockchain_config.wallet_path,
+                    blockchain_config.wallet_pass,
+                    Some(blockchain_config.endpoint),
+                    ex,
+                    args.fun,
+                    blockchain_config.hooks.clone(),
+                )
+                .await;
+
+                let mut tx = match drk.darkname_renew(&name, duration).await {
+                    Ok(v) => v,
+                    Err(e) => {
+                        eprintln!("Error creating name renewal tx: {e:?}");
+                        exit(2);
+                    }
+                };
+
+                if let Err(e) = drk.attach_fee(&mut tx).await {
+                    eprintln!("Failed to attach the fee call to the transaction: {e:?}");
+                    exit(2);
+                };
+
+                println!("{}", base64::encode(&serialize_async(&tx).await));
+
+                drk.stop_rpc_client().await
+            }
+
+            NameSubcmd::Receive { name } => {
+                if !is_valid_name(&name) {
+                    eprintln!("Invalid name: {name}");
+                    exit(2);
+                }
+
+                let drk = new_wallet(
+                    blockchain_config.wallet_path,
+                    blockchain_config.wallet_pass,
+                    None,
+                    ex,
+                    args.fun,
+                    blockchain_config.hooks.clone(),
+                )
+                .await;
+
+                let (_, owner) = match drk.put_name_owner(&name).await {
+                    Ok(v) => v,
+                    Err(e) => {
+                        eprintln!("Failed to generate name owner: {e:?}");
+                        exit(2);
+                    }
+                };
+
+                eprintln!("Give this owner commitment to the current owner of {name}:");
+                println!("{}", bs58::encode(owner.to_repr()).into_string());
+
+                Ok(())
+            }
+
+            NameSubcmd::Transfer { name, owner, address, contract } => {
+                let new_owner = match parse_name_owner(&owner) {
+                    Ok(o) => o,
+                    Err(e) => {
+                        eprintln!("Invalid owner commitment: {e:?}");
+                        exit(2);
+                    }
+                };
+
+                let new_target = if address.is_some() || contract.is_some() {
+                    match parse_name_target(address, contract) {
+                        Ok(t) => Some(t),
+                        Err(e) => {
+                            eprintln!("Invalid name target: {e:?}");
+                            exit(2);
+                        }
+                    }
+                } else {
+                    None
+                };
+
+                let drk = new_wallet(
+                    blockchain_config.wallet_path,
+                    blockchain_config.wallet_pass,
+                    Some(blockchain_config.endpoint),
+                    ex,
+                    args.fun,
+                    blockchain_config.hooks.clone(),
+                )
+                .await;
+
+                let mut tx = match drk.darkname_transfer(&name, new_owner, new_target).await {
+                    Ok(v) => v,
+                    Err(e) => {
+                        eprintln!("Error creating name transfer tx: {e:?}");
+                        exit(2);
+                    }
+                };
+
+                if let Err(e) = drk.attach_fee(&mut tx).await {
+                    eprintln!("Failed to attach the fee call to the transaction: {e:?}");
+                    exit(2);
+                };
+
+                println!("{}", base64::encode(&serialize_async(&tx).await));
+
+                drk.stop_rpc_client().await
+            }
+
+            NameSubcmd::List => {
+                let drk = new_wallet(
+                    blockchain_config.wallet_path,
+                    blockchain_config.wallet_pass,
+                    None,
+                    ex,
+                    args.fun,
+                    blockchain_config.hooks.clone(),
+                )
+                .await;
+
+                let names = drk.list_owned_names().await?;
+
+                let mut table = Table::new();
+                table.set_format(*format::consts::FORMAT_NO_BORDER_LINE_SEPARATOR);
+                table.set_titles(row!["Name", "Address", "Contract ID", "Expiry"]);
+
+                for (name, record) in names {
+                    let address = match record.target.public_key {
+                        Some(p) => p.to_string(),
+                        None => "-".to_string(),
+                    };
+                    let contract_id = match record.target.contract_id {
+                        Some(c) => c.to_string(),
+                        None => "-".to_string(),
+                    };
+                    table.add_row(row![name, address, contract_id, record.expiry]);
+                }
+
+                if table.is_empty() {
+                    println!("No owned names found");
+                } else {
+                    println!("{table}");
+                }
+
+                Ok(())
+            }
+        },
+
+        Subcmd::Auction { command } => match command {
+            AuctionSubcmd::Create {
+                amount,
+                token,
+                payment_token,
+                reserve_price,
+                commit_duration,
+                reveal_duration,
+            } => {
+                let amount = match decode_base10(&amount, BALANCE_BASE10_DECIMALS, false) {
+                    Ok(v) => v,
+                    Err(e) => {
+                        eprintln!("Invalid amount: {e:?}");
+                        exit(2);
+                    }
+                };
+
+                let reserve_price =
+                    match decode_base10(&reserve_price, BALANCE_BASE10_DECIMALS, false) {
+                        Ok(v) => v,
+                        Err(e) => {
+                            eprintln!("Invalid reserve price: {e:?}");
+                            exit(2);
+                        }
+                    };
+
+                let drk = new_wallet(
+                    blockchain_config.wallet_path,
+                    blockchain_config.wallet_pass,
+                    Some(blockchain_config.endpoint),
+                    ex,
+                    args.fun,
+                    blockchain_config.hooks.clone(),
+                )
+                .await;
+
+                let token_id = match drk.get_token(token).await {
+                    Ok(t) => t,
+                    Err(e) => {
+                        eprintln!("Invalid token alias: {e:?}");
+                        exit(2);
+                    }
+                };
+
+                let payment_token_id = match drk.get_token(payment_token).await {
+                    Ok(t) => t,
+                    Err(e) => {
+                        eprintln!("Invalid payment token alias: {e:?}");
+                        exit(2);
+                    }
+                };
+
+                let (auction_id, mut tx) = match drk
+                    .auction_create(
+                        amount,
+                        token_id,
+                        payment_token_id,
+                        reserve_price,
+                        commit_duration,
+                        reveal_duration,
+                    )
+                    .await
+                {
+                    Ok(v) => v,
+                    Err(e) => {
+                        eprintln!("Error creating auction creation tx: {e:?}");
+                        exit(2);
+                    }
+                };
+
+                if let Err(e) = drk.attach_fee(&mut tx).await {
+                    eprintln!("Failed to attach the fee call to the transaction: {e:?}");
+                    exit(2);
+                };
+
+                eprintln!("Auction ID: {auction_id}");
+                println!("{}", base64::encode(&serialize_async(&tx).await));
+
+                drk.stop_rpc_client().await
+            }
+
+            AuctionSubcmd::Bid { auction_id, amount } => {
+                let auction_id = match AuctionId::from_str(&auction_id) {
+                    Ok(a) => a,
+                    Err(e) => {
+                        eprintln!("Invalid auction ID: {e:?}");
+                        exit(2);
+                    }
+                };
+
+                let amount = match decode_base10(&amount, BALANCE_BASE10_DECIMALS, false) {
+                    Ok(v) => v,
+                    Err(e) => {
+                        eprintln!("Invalid amount: {e:?}");
+                        exit(2);
+                    }
+                };
+
+                let drk = new_wallet(
+                    blockchain_config.wallet_path,
+                    blockchain_config.wallet_pass,
+                    Some(blockchain_config.endpoint),
+                    ex,
+                    args.fun,
+                    blockchain_config.hooks.clone(),
+                )
+                .await;
+
+                let mut tx = match drk.auction_bid(&auction_id, amount).await {
+                    Ok(v) => v,
+                    Err(e) => {
+                        eprintln!("Error creating auction bid tx: {e:?}");
+                        exit(2);
+                    }
+                };
+
+                if let Err(e) = drk.attach_fee(&mut tx).await {
+                    eprintln!("Failed to attach the fee call to the transaction: {e:?}");
+                    exit(2);
+                };
+
+                println!("{}", base64::encode(&serialize_async(&tx).await));
+
+                drk.stop_rpc_client().await
+            }
+
+            AuctionSubcmd::Reveal { auction_id } => {
+                let auction_id = match AuctionId::from_str(&auction_id) {
+                    Ok(a) => a,
+                    Err(e) => {
+                        eprintln!("Invalid auction ID: {e:?}");
+                        exit(2);
+                    }
+                };
+
+                let drk = new_wallet(
+                    blockchain_config.wallet_path,
+                    blockchain_config.wallet_pass,
+                    Some(blockchain_config.endpoint),
+                    ex,
+                    args.fun,
+                    blockchain_config.hooks.clone(),
+                )
+                .await;
+
+                let mut tx = match drk.auction_reveal(&auction_id).await {
+                    Ok(v) => v,
+                    Err(e) => {
+                        eprintln!("Error creating auction reveal tx: {e:?}");
+                        exit(2);
+                    }
+                };
+
+                if let Err(e) = drk.attach_fee(&mut tx).await {
+                    eprintln!("Failed to attach the fee call to the transaction: {e:?}");
+                    exit(2);
+                };
+
+                println!("{}", base64::encode(&serialize_async(&tx).await));
+
+                drk.stop_rpc_client().await
+            }
+
+            AuctionSubcmd::Refund { auction_id } => {
+                let auction_id = match AuctionId::from_str(&auction_id) {
+                    Ok(a) => a,
+                    Err(e) => {
+                        eprintln!("Invalid auction ID: {e:?}");
+                        exit(2);
+                    }
+                };
+
+                let drk = new_wallet(
+                    blockchain_config.wallet_path,
+                    blockchain_config.wallet_pass,
+                    Some(blockchain_config.endpoint),
+                    ex,
+                    args.fun,
+                    blockchain_config.hooks.clone(),
+                )
+                .await;
+
+                let mut tx = match drk.auction_refund(&auction_id).await {
+                    Ok(v) => v,
+                    Err(e) => {
+                        eprintln!("Error creating auction refund tx: {e:?}");
+                        exit(2);
+                    }
+                };
+
+                if let Err(e) = drk.attach_fee(&mut tx).await {
+                    eprintln!("Failed to attach the fee call to the transaction: {e:?}");
+                    exit(2);
+                };
+
+                println!("{}", base64::encode(&serialize_async(&tx).await));
+
+                drk.stop_rpc_client().await
+            }
+
+            AuctionSubcmd::SettleInit { auction_id } => {
+                let auction_id = match AuctionId::from_str(&auction_id) {
+                    Ok(a) => a,
+                    Err(e) => {
+                        eprintln!("Invalid auction ID: {e:?}");
+                        exit(2);
+                    }
+                };
+
+                let drk = new_wallet(
+                    blockchain_config.wallet_path,
+                    blockchain_config.wallet_pass,
+                    Some(blockchain_config.endpoint),
+                    ex,
+                    args.fun,
+                    blockchain_config.hooks.clone(),
+                )
+                .await;
+
+                let half = match drk.auction_settle_init(&auction_id).await {
+                    Ok(h) => h,
+                    Err(e) => {
+                        eprintln!("Failed to create auction settlement half: {e:?}");
+                        exit(2);
+                    }
+                };
+
+                println!("{}", base64::encode(&serialize_async(&half).await));
+                drk.stop_rpc_client().await
+            }
+
+            AuctionSubcmd::SettleJoin => {
+                let mut buf = String::new();
+                stdin().read_to_string(&mut buf)?;
+                let Some(bytes) = base64::decode(buf.trim()) else {
+                    eprintln!("Failed to decode partial settlement data");
+                    exit(2);
+                };
+
+                let partial: PartialSettleData = deserialize_async(&bytes).await?;
+
+                let drk = new_wallet(
+                    blockchain_config.wallet_path,
+                    blockchain_config.wallet_pass,
+                    Some(blockchain_config.endpoint),
+                    ex,
+                    args.fun,
+                    blockchain_config.hooks.clone(),
+                )
+                .await;
+                let tx = match drk.auction_settle_join(partial).await {
+                    Ok(tx) => tx,
+                    Err(e) => {
+                        eprintln!("Failed to create auction settlement transaction: {e:?}");
+                        exit(2);
+                    }
+                };
+
+                println!("{}", base64::encode(&serialize_async(&tx).await));
+                drk.stop_rpc_client().await
+            }
+
+            AuctionSubcmd::List => {
+                let drk = new_wallet(
+                    blockchain_config.wallet_path,
+                    blockchain_config.wallet_pass,
+                    None,
+                    ex,
+                    args.fun,
+                    blockchain_config.hooks.clone(),
+                )
+                .await;
+
+                let auctions = drk.list_auctions().await?;
+                let aliases_map = drk.get_aliases_mapped_by_token().await?;
+
+                let mut table = Table::new();
+                table.set_format(*format::consts::FORMAT_NO_BORDER_LINE_SEPARATOR);
+                table.set_titles(row![
+                    "Auction ID",
+                    "Asset",
+                    "Amount",
+                    "Payment Token",
+                    "Reserve Price",
+                    "Commit End",
+                    "Reveal End",
+                    "Highest Bid",
+                    "Settled",
+                    "Participating"
+                ]);
+
+                for (auction_id, record, participating) in auctions {
+                    let asset = match aliases_map.get(&record.params.asset_token_id.to_string()) {
+                        Some(a) => a.clone(),
+                        None => record.params.asset_token_id.to_string(),
+                    };
+                    let payment_token =
+                        match aliases_map.get(&record.params.payment_token_id.to_string()) {
+                            Some(a) => a.clone(),
+                            None => record.params.payment_token_id.to_string(),
+                        };
+                    let highest_bid = match record.winner {
+                        Some(_) => format_amount(record.highest_bid),
+                        None => "-".to_string(),
+                    };
+                    table.add_row(row![
+                        auction_id,
+                        asset,
+                        format_amount(record.params.asset_amount),
+                        payment_token,
+                        format_amount(record.params.reserve_price),
+                        record.params.commit_end,
+                        record.params.reveal_end,
+                        highest_bid,
+                        record.settled,
+                        participating
+                    ]);
+                }
+
+                if table.is_empty() {
+                    println!("No auctions found");
+                } else {
+                    println!("{table}");
+                }
+
+                Ok(())
+            }
+        },
+
+        Subcmd::Oracle { command } => match command {
+            OracleSubcmd::Register { name, kind, threshold, oracles } => {
+                let kind = match parse_feed_kind(&kind) {
+                    Ok(k) => k,
+                    Err(e) => {
+                        eprintln!("{e}");
+                        exit(2);
+                    }
+                };
+
+                let oracles = match parse_oracle_keys(&oracles) {
+                    Ok(o) => o,
+                    Err(e) => {
+                        eprintln!("Invalid oracle public key: {e:?}");
+                        exit(2);
+                    }
+                };
+
+                let drk = new_wallet(
+                    blockchain_config.wallet_path,
+                    blockchain_config.wallet_pass,
+                    Some(blockchain_config.endpoint),
+                    ex,
+                    args.fun,
+                    blockchain_config.hooks.clone(),
+                )
+                .await;
+
+                let (mut tx, feed_id) =
+                    match drk.oracle_register(&name, kind, oracles, threshold).await {
+                        Ok(v) => v,
+                        Err(e) => {
+                            eprintln!("Error creating feed registration tx: {e:?}");
+                            exit(2);
+                        }
+                    };
+
+                if let Err(e) = drk.attach_fee(&mut tx).await {
+                    eprintln!("Failed to attach the fee call to the transaction: {e:?}");
+                    exit(2);
+                };
+
+                eprintln!("Feed ID: {feed_id}");
+                println!("{}", base64::encode(&serialize_async(&tx).await));
+
+                drk.stop_rpc_client().await
+            }
+
+            OracleSubcmd::Rotate { feed_id, threshold, oracles } => {
+                let feed_id = match FeedId::from_str(&feed_id) {
+                    Ok(f) => f,
+                    Err(e) => {
+                        eprintln!("Invalid feed ID: {e:?}");
+                        exit(2);
+                    }
+                };
+
+                let oracles = match parse_oracle_keys(&oracles) {
+                    Ok(o) => o,
+                    Err(e) => {
+                        eprintln!("Invalid oracle public key: {e:?}");
+                        exit(2);
+                    }
+                };
+
+                let drk = new_wallet(
+                    blockchain_config.wallet_path,
+                    blockchain_config.wallet_pass,
+                    Some(blockchain_config.endpoint),
+                    ex,
+                    args.fun,
+                    blockchain_config.hooks.clone(),
+                )
+                .await;
+
+                let mut tx = match drk.oracle_rotate(feed_id, oracles, threshold).await {
+                    Ok(v) => v,
+                    Err(e) => {
+                        eprintln!("Error creating oracle set rotation tx: {e:?}");
+                        exit(2);
+                    }
+                };
+
+                if let Err(e) = drk.attach_fee(&mut tx).await {
+                    eprintln!("Failed to attach the fee call to the transaction: {e:?}");
+                    exit(2);
+                };
+
+                println!("{}", base64::encode(&serialize_async(&tx).await));
+
+                drk.stop_rpc_client().await
+            }
+
+            OracleSubcmd::Show { feed_id } => {
+                let feed_id = match FeedId::from_str(&feed_id) {
+                    Ok(f) => f,
+                    Err(e) => {
+                        eprintln!("Invalid feed ID: {e:?}");
+                        exit(2);
+                    }
+                };
+
+                let drk = new_wallet(
+                    blockchain_config.wallet_path,
+                    blockchain_config.wallet_pass,
+                    Some(blockchain_config.endpoint),
+                    ex,
+                    args.fun,
+                    blockchain_config.hooks.clone(),
+                )
+                .await;
+
+                let feed = match drk.get_oracle_feed(&feed_id).await {
+                    Ok(Some(f)) => f,
+                    Ok(None) => {
+                        eprintln!("Feed {feed_id} is not registered");
+                        exit(2);
+                    }
+                    Err(e) => {
+                        eprintln!("Failed to fetch feed: {e:?}");
+                        exit(2);
+                    }
+                };
+
+                let kind = match feed.kind {
+                    FeedKind::Price => "Price",
+                    FeedKind::Randomness => "Randomness",
+                };
+                println!("Name: {}", feed.name);
+                println!("Kind: {kind}");
+                println!("Authority: {}", feed.authority);
+                println!("Threshold: {}/{}", feed.threshold, feed.oracles.len());
+                for oracle in &feed.oracles {
+                    println!("Oracle: {oracle}");
+                }
+
+                match drk.get_oracle_value(&feed_id).await {
+                    Ok(Some(value)) => {
+                        println!("Round: {}", value.round);
+                        println!("Value: {}", value.value);
+                        println!("Digest: {}", blake3::Hash::from_bytes(value.digest));
+                        println!("Height: {}", value.height);
+                        println!("Signers: {}", value.signers);
+                    }
+                    Ok(None) => println!("No attested value yet"),
+                    Err(e) => {
+                        eprintln!("Failed to fetch feed value: {e:?}");
+                        exit(2);
+                    }
+                }
+
+                drk.stop_rpc_client().await
+            }
+
+            OracleSubcmd::Collect { feed_id, reporters } => {
+                let feed_id = match FeedId::from_str(&feed_id) {
+                    Ok(f) => f,
+                    Err(e) => {
+                        eprintln!("Invalid feed ID: {e:?}");
+                        exit(2);
+                    }
+                };
+
+                let drk = new_wallet(
+                    blockchain_config.wallet_path,
+                    blockchain_config.wallet_pass,
+                    Some(blockchain_config.endpoint),
+                    ex.clone(),
+                    args.fun,
+                    blockchain_config.hooks.clone(),
+                )
+                .await;
+
+                let (mut tx, round, value) = match drk.oracle_collect(feed_id, &reporters, ex).await
+                {
+                    Ok(v) => v,
+                    Err(e) => {
+                        eprintln!("Error creating feed submission tx: {e:?}");
+                        exit(2);
+                    }
+                };
+
+                if let Err(e) = drk.attach_fee(&mut tx).await {
+                    eprintln!("Failed to attach the fee call to the transaction: {e:?}");
+                    exit(2);
+                };
+
+                eprintln!("Submitting round {round} with value {value}");
+                println!("{}", base64::encode(&serialize_async(&tx).await));
+
+                drk.stop_rpc_client().await
+            }
+        },
+
+        Subcmd::Proof { command } => match command {
+            ProofSubcmd::Create { tx_hash, output } => {
+                let tx_hash = match TransactionHash::from_str(&tx_hash) {
+                    Ok(h) => h,
+                    Err(e) => {
+                        eprintln!("Invalid transaction hash: {e:?}");
+                        exit(2);
+                    }
+                };
+
+                let drk = new_wallet(
+                    blockchain_config.wallet_path,
+                    blockchain_config.wallet_pass,
+                    None,
+                    ex,
+                    args.fun,
+                    blockchain_config.hooks.clone(),
+                )
+                .await;
+
+                let proofs = match drk.create_payment_proofs(&tx_hash, output).await {
+                    Ok(p) => p,
+                    Err(e) => {
+                        eprintln!("Failed to create payment proofs: {e:?}");
+                        exit(2);
+                    }
+                };
+
+                for proof in proofs {
+                    println!("{}", base64::encode(&serialize_async(&proof).await));
+                }
+
+                Ok(())
+            }
+
+            ProofSubcmd::Verify => {
+                let mut proofs = vec![];
+                let lines = stdin().lines();
+                for (i, line) in lines.enumerate() {
+                    if let Ok(line) = line {
+                        let Some(bytes) = base64::decode(line.trim()) else {
+                            eprintln!("Failed to decode payment proof on line {i}");
+                            exit(2);
+                        };
+                        let proof: PaymentProof = match deserialize_async(&bytes).await {
+                            Ok(p) => p,
+                            Err(e) => {
+                                eprintln!("Failed to deserialize payment proof on line {i}: {e:?}");
+                                exit(2);
+                            }
+                        };
+                        proofs.push(proof);
+                    }
+                }
+
+                let drk = new_wallet(
+                    blockchain_config.wallet_path,
+                    blockchain_config.wallet_pass,
+                    Some(blockchain_config.endpoint),
+                    ex,
+                    args.fun,
+                    blockchain_config.hooks.clone(),
+                )
+                .await;
+
+                let aliases_map = drk.get_aliases_mapped_by_token().await?;
+
+                let mut table = Table::new();
+                table.set_format(*format::consts::FORMAT_NO_BORDER_LINE_SEPARATOR);
+                table.set_titles(row![
+                    "Transaction",
+                    "Output",
+                    "Token ID",
+                    "Aliases",
+                    "Value",
+                    "Recipient"
+                ]);
+
+                for proof in proofs {
+                    if let Err(e) = drk.verify_payment_proof(&proof).await {
+                        eprintln!("Invalid payment proof for transaction {}: {e}", proof.tx_hash);
+                        exit(2);
+                    }
+
+                    let aliases = match aliases_map.get(&proof.token_id.to_string()) {
+                        Some(a) => a,
+                        None => "-",
+                    };
+                    table.add_row(row![
+                        proof.tx_hash,
+                        proof.output_idx,
+                        proof.token_id,
+                        aliases,
+                        format_amount(proof.value),
+                        proof.recipient.public_key
+                    ]);
+                }
+
+                println!("{table}");
+
+                drk.stop_rpc_client().await
+            }
+        },
     }
 }
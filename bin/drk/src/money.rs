@@ -24,8 +24,12 @@ use rand::rngs::OsRng;
 use rusqlite::types::Value;
 
 use darkfi::{
+    blockchain::BlockInfo,
     tx::Transaction,
-    zk::{halo2::Field, proof::ProvingKey, vm::ZkCircuit, vm_heap::empty_witnesses, Proof},
+    zk::{
+        halo2::Field, proof::ProvingKey, vm::ZkCircuit, vm_heap::empty_witnesses, Proof,
+        ProofPriority,
+    },
     zkas::ZkBinary,
     Error, Result,
 };
@@ -33,6 +37,7 @@ use darkfi_money_contract::{
     client::{
         compute_remainder_blind,
         fee_v1::{create_fee_proof, FeeCallInput, FeeCallOutput, FEE_CALL_GAS},
+        view_key::{IncomingViewKey, ViewAddress},
         MoneyNote, OwnCoin,
     },
     model::{
@@ -45,11 +50,12 @@ use darkfi_money_contract::{
 use darkfi_sdk::{
     bridgetree,
     crypto::{
+        mnemonic::WordCount,
         note::AeadEncryptedNote,
         pasta_prelude::PrimeField,
         smt::{PoseidonFp, EMPTY_NODES_FP},
-        BaseBlind, FuncId, Keypair, MerkleNode, MerkleTree, PublicKey, ScalarBlind, SecretKey,
-        MONEY_CONTRACT_ID,
+        BaseBlind, ExtendedSecretKey, FuncId, Keypair, MerkleNode, MerkleTree, Mnemonic, PublicKey,
+        ScalarBlind, SecretKey, MONEY_CONTRACT_ID,
     },
     dark_tree::DarkLeaf,
     pasta::pallas,
@@ -61,6 +67,7 @@ use crate::{
     cli_util::kaching,
     convert_named_params,
     error::WalletDbResult,
+    hooks::WalletEvent,
     walletdb::{WalletSmt, WalletStorage},
     Drk,
 };
@@ -71,14 +78,28 @@ lazy_static! {
     pub static ref MONEY_TREE_TABLE: String =
         format!("{}_money_tree", MONEY_CONTRACT_ID.to_string());
     pub static ref MONEY_SMT_TABLE: String = format!("{}_money_smt", MONEY_CONTRACT_ID.to_string());
+    pub static ref MONEY_SEED_TABLE: String =
+        format!("{}_money_seed", MONEY_CONTRACT_ID.to_string());
     pub static ref MONEY_KEYS_TABLE: String =
         format!("{}_money_keys", MONEY_CONTRACT_ID.to_string());
+    pub static ref MONEY_KEY_ROTATIONS_TABLE: String =
+        format!("{}_money_key_rotations", MONEY_CONTRACT_ID.to_string());
     pub static ref MONEY_COINS_TABLE: String =
         format!("{}_money_coins", MONEY_CONTRACT_ID.to_string());
     pub static ref MONEY_TOKENS_TABLE: String =
         format!("{}_money_tokens", MONEY_CONTRACT_ID.to_string());
     pub static ref MONEY_ALIASES_TABLE: String =
         format!("{}_money_aliases", MONEY_CONTRACT_ID.to_string());
+    pub static ref MONEY_VIEW_KEYS_TABLE: String =
+        format!("{}_money_view_keys", MONEY_CONTRACT_ID.to_string());
+    pub static ref MONEY_VIEW_NOTES_TABLE: String =
+        format!("{}_money_view_notes", MONEY_CONTRACT_ID.to_string());
+    pub static ref MONEY_PAYMENTS_TABLE: String =
+        format!("{}_money_payments", MONEY_CONTRACT_ID.to_string());
+    pub static ref MONEY_FEE_OFFERS_TABLE: String =
+        format!("{}_money_fee_offers", MONEY_CONTRACT_ID.to_string());
+    pub static ref MONEY_FEE_SWAPS_TABLE: String =
+        format!("{}_money_fee_swaps", MONEY_CONTRACT_ID.to_string());
 }
 
 // MONEY_TREE_TABLE
@@ -88,12 +109,23 @@ pub const MONEY_TREE_COL_TREE: &str = "tree";
 pub const MONEY_SMT_COL_KEY: &str = "smt_key";
 pub const MONEY_SMT_COL_VALUE: &str = "smt_value";
 
+// MONEY_SEED_TABLE
+pub const MONEY_SEED_COL_ENTROPY: &str = "entropy";
+pub const MONEY_SEED_COL_NEXT_INDEX: &str = "next_index";
+pub const MONEY_SEED_COL_NEXT_DAO_INDEX: &str = "next_dao_index";
+
 // MONEY_KEYS_TABLE
 pub const MONEY_KEYS_COL_KEY_ID: &str = "key_id";
 pub const MONEY_KEYS_COL_IS_DEFAULT: &str = "is_default";
 pub const MONEY_KEYS_COL_PUBLIC: &str = "public";
 pub const MONEY_KEYS_COL_SECRET: &str = "secret";
 
+// MONEY_KEY_ROTATIONS_TABLE
+pub const MONEY_KEY_ROTATIONS_COL_PUBLIC: &str = "public";
+pub const MONEY_KEY_ROTATIONS_COL_SUCCESSOR: &str = "successor";
+pub const MONEY_KEY_ROTATIONS_COL_HEIGHT: &str = "height";
+pub const MONEY_KEY_ROTATIONS_COL_GRACE_BLOCKS: &str = "grace_blocks";
+
 // MONEY_COINS_TABLE
 pub const MONEY_COINS_COL_COIN: &str = "coin";
 pub const MONEY_COINS_COL_IS_SPENT: &str = "is_spent";
@@ -119,8 +151,102 @@ pub const MONEY_TOKENS_COL_IS_FROZEN: &str = "is_frozen";
 pub const MONEY_ALIASES_COL_ALIAS: &str = "alias";
 pub const MONEY_ALIASES_COL_TOKEN_ID: &str = "token_id";
 
+// MONEY_VIEW_KEYS_TABLE
+pub const MONEY_VIEW_KEYS_COL_VIEW_KEY: &str = "view_key";
+
+// MONEY_VIEW_NOTES_TABLE
+pub const MONEY_VIEW_NOTES_COL_COIN: &str = "coin";
+pub const MONEY_VIEW_NOTES_COL_VIEW_KEY: &str = "view_key";
+pub const MONEY_VIEW_NOTES_COL_NOTE: &str = "note";
+pub const MONEY_VIEW_NOTES_COL_TX_HASH: &str = "tx_hash";
+
+// MONEY_PAYMENTS_TABLE
+pub const MONEY_PAYMENTS_COL_TX_HASH: &str = "tx_hash";
+pub const MONEY_PAYMENTS_COL_CALL_IDX: &str = "call_idx";
+pub const MONEY_PAYMENTS_COL_OUTPUT_IDX: &str = "output_idx";
+pub const MONEY_PAYMENTS_COL_RECIPIENT: &str = "recipient";
+pub const MONEY_PAYMENTS_COL_NOTE: &str = "note";
+
+// MONEY_FEE_OFFERS_TABLE
+pub const MONEY_FEE_OFFERS_COL_TOKEN_ID: &str = "token_id";
+pub const MONEY_FEE_OFFERS_COL_RECIPIENT: &str = "recipient";
+pub const MONEY_FEE_OFFERS_COL_OFFER: &str = "offer";
+
+// MONEY_FEE_SWAPS_TABLE
+pub const MONEY_FEE_SWAPS_COL_REQUEST: &str = "request";
+pub const MONEY_FEE_SWAPS_COL_SECRETS: &str = "secrets";
+
 pub const BALANCE_BASE10_DECIMALS: usize = 8;
 
+/// HD derivation path of the Money keypairs, under which each
+/// keypair is derived using its index.
+pub const MONEY_KEYS_HD_PATH: [u32; 1] = [0];
+
+/// Amount of keypairs derived when restoring a wallet from its
+/// mnemonic phrase, so coins sent to them are found on scan.
+pub const MONEY_KEYS_RESTORE_COUNT: u32 = 20;
+
+/// Money notes we managed to trial decrypt, keyed by their coin
+#[derive(Default)]
+pub struct DecryptedNotes {
+    /// Notes decrypted with our keys, along with the secret key owning the coin
+    pub owned: HashMap<[u8; 32], (MoneyNote, SecretKey)>,
+    /// Notes decrypted with imported view keys, along with the view key public key
+    pub viewed: HashMap<[u8; 32], Vec<(PublicKey, MoneyNote)>>,
+}
+
+/// Trial decrypt the given coin notes in parallel, splitting them evenly
+/// over all available threads.
+fn trial_decrypt_notes(
+    outputs: &[(Coin, AeadEncryptedNote)],
+    decryption_keys: &[(SecretKey, SecretKey)],
+    view_keys: &[IncomingViewKey],
+) -> DecryptedNotes {
+    let n_threads = std::thread::available_parallelism().map(|n| n.get()).unwrap_or(1);
+    let chunk_size = outputs.len().div_ceil(n_threads).max(1);
+
+    let mut ret = DecryptedNotes::default();
+    std::thread::scope(|s| {
+        let handles: Vec<_> = outputs
+            .chunks(chunk_size)
+            .map(|chunk| {
+                s.spawn(move || {
+                    let mut decrypted = DecryptedNotes::default();
+                    for (coin, note) in chunk {
+                        let coin_key = coin.inner().to_repr();
+
+                        for (key, secret) in decryption_keys {
+                            if let Ok(note) = note.decrypt::<MoneyNote>(key) {
+                                decrypted.owned.insert(coin_key, (note, *secret));
+                                break
+                            }
+                        }
+
+                        for view_key in view_keys {
+                            if let Some(note) = view_key.decrypt_note(note) {
+                                decrypted
+                                    .viewed
+                                    .entry(coin_key)
+                                    .or_default()
+                                    .push((view_key.public(), note));
+                            }
+                        }
+                    }
+                    decrypted
+                })
+            })
+            .collect();
+
+        for handle in handles {
+            let decrypted = handle.join().unwrap();
+            ret.owned.extend(decrypted.owned);
+            ret.viewed.extend(decrypted.viewed);
+        }
+    });
+
+    ret
+}
+
 impl Drk {
     /// Initialize wallet with tables for the Money contract.
     pub async fn initialize_money(&self) -> WalletDbResult<()> {
@@ -150,12 +276,112 @@ impl Drk {
         Ok(())
     }
 
+    /// Initialize the wallet mnemonic seed, either using the provided
+    /// `Mnemonic` for recovery, or by generating a new one.
+    /// Returns the stored `Mnemonic`, so the caller can display it.
+    pub async fn initialize_money_seed(&self, mnemonic: Option<Mnemonic>) -> Result<Mnemonic> {
+        if self.get_money_mnemonic().await.is_ok() {
+            return Err(Error::Custom("Wallet mnemonic seed is already initialized".to_string()))
+        }
+
+        let mnemonic = match mnemonic {
+            Some(m) => m,
+            None => Mnemonic::generate(&mut OsRng, WordCount::Words24),
+        };
+
+        let query = format!(
+            "INSERT INTO {} ({}, {}, {}) VALUES (?1, ?2, ?3);",
+            *MONEY_SEED_TABLE,
+            MONEY_SEED_COL_ENTROPY,
+            MONEY_SEED_COL_NEXT_INDEX,
+            MONEY_SEED_COL_NEXT_DAO_INDEX
+        );
+        if let Err(e) = self.wallet.exec_sql(&query, rusqlite::params![mnemonic.entropy(), 0, 0]) {
+            return Err(Error::DatabaseError(format!(
+                "[initialize_money_seed] Inserting mnemonic seed failed: {e:?}"
+            )))
+        }
+
+        Ok(mnemonic)
+    }
+
+    /// Fetch the wallet `Mnemonic` along with the next index stored in
+    /// the given seed table column.
+    async fn get_money_seed(&self, index_col: &str) -> Result<(Mnemonic, u32)> {
+        let row = match self.wallet.query_single(
+            &MONEY_SEED_TABLE,
+            &[MONEY_SEED_COL_ENTROPY, index_col],
+            &[],
+        ) {
+            Ok(r) => r,
+            Err(e) => {
+                return Err(Error::DatabaseError(format!(
+                    "[get_money_seed] Mnemonic seed retrieval failed: {e:?}"
+                )))
+            }
+        };
+
+        let Value::Blob(ref entropy) = row[0] else {
+            return Err(Error::ParseFailed("[get_money_seed] Entropy bytes parsing failed"))
+        };
+        let mnemonic = match Mnemonic::from_entropy(entropy) {
+            Ok(m) => m,
+            Err(e) => return Err(Error::Custom(format!("[get_money_seed] {e}"))),
+        };
+
+        let Value::Integer(next_index) = row[1] else {
+            return Err(Error::ParseFailed("[get_money_seed] Next index parsing failed"))
+        };
+        let Ok(next_index) = u32::try_from(next_index) else {
+            return Err(Error::ParseFailed("[get_money_seed] Next index parsing failed"))
+        };
+
+        Ok((mnemonic, next_index))
+    }
+
+    /// Fetch the wallet `Mnemonic`.
+    pub async fn get_money_mnemonic(&self) -> Result<Mnemonic> {
+        Ok(self.get_money_seed(MONEY_SEED_COL_NEXT_INDEX).await?.0)
+    }
+
+    /// Derive the next key under the given HD `path` from the wallet
+    /// mnemonic seed, advancing the index stored in the given seed
+    /// table column. Errors if the wallet has no mnemonic seed.
+    pub async fn money_seed_derive(
+        &self,
+        path: &[u32],
+        index_col: &str,
+    ) -> Result<ExtendedSecretKey> {
+        let (mnemonic, index) = match self.get_money_seed(index_col).await {
+            Ok(s) => s,
+            Err(e) => {
+                return Err(Error::Custom(format!(
+                    "[money_seed_derive] Wallet has no mnemonic seed, initialize or restore it first: {e}"
+                )))
+            }
+        };
+
+        let master = ExtendedSecretKey::from_seed(&mnemonic.to_seed(""));
+        let key = master.derive_path(path).derive_child(index);
+
+        let query = format!("UPDATE {} SET {} = ?1;", *MONEY_SEED_TABLE, index_col);
+        if let Err(e) = self.wallet.exec_sql(&query, rusqlite::params![index + 1]) {
+            return Err(Error::DatabaseError(format!(
+                "[money_seed_derive] Updating next index failed: {e:?}"
+            )))
+        }
+
+        Ok(key)
+    }
+
     /// Generate a new keypair and place it into the wallet.
-    pub async fn money_keygen(&self) -> WalletDbResult<()> {
+    /// Keypairs are derived from the wallet mnemonic seed.
+    /// Returns the generated keypair.
+    pub async fn money_keygen(&self) -> Result<Keypair> {
         println!("Generating a new keypair");
 
-        // TODO: We might want to have hierarchical deterministic key derivation.
-        let keypair = Keypair::random(&mut OsRng);
+        let keypair =
+            self.money_seed_derive(&MONEY_KEYS_HD_PATH, MONEY_SEED_COL_NEXT_INDEX).await?.keypair();
         let is_default = 0;
 
         let query = format!(
@@ -165,18 +391,49 @@ impl Drk {
             MONEY_KEYS_COL_PUBLIC,
             MONEY_KEYS_COL_SECRET
         );
-        self.wallet.exec_sql(
+        if let Err(e) = self.wallet.exec_sql(
             &query,
             rusqlite::params![
                 is_default,
                 serialize_async(&keypair.public).await,
                 serialize_async(&keypair.secret).await
             ],
-        )?;
+        ) {
+            return Err(Error::DatabaseError(format!(
+                "[money_keygen] Inserting new keypair failed: {e:?}"
+            )))
+        }
 
         println!("New address:");
         println!("{}", keypair.public);
 
+        Ok(keypair)
+    }
+
+    /// Re-derive the first `count` keypairs of a wallet restored from its
+    /// mnemonic phrase, setting the first one as the default address.
+    pub async fn money_restore_keys(&self, count: u32) -> Result<()> {
+        let mut first = None;
+        for _ in 0..count {
+            let keypair = self.money_keygen().await?;
+            first.get_or_insert(keypair.public);
+        }
+
+        let Some(first) = first else { return Ok(()) };
+        let Some((key_id, ..)) =
+            self.addresses().await?.into_iter().find(|(_, public, ..)| *public == first)
+        else {
+            return Err(Error::DatabaseError(
+                "[money_restore_keys] Restored keypair not found".to_string(),
+            ))
+        };
+
+        if let Err(e) = self.set_default_address(key_id as usize) {
+            return Err(Error::DatabaseError(format!(
+                "[money_restore_keys] Setting default address failed: {e:?}"
+            )))
+        }
+
         Ok(())
     }
 
@@ -352,6 +609,120 @@ impl Drk {
         Ok(ret)
     }
 
+    /// Derive the incoming view keys of all the secret keys in the wallet.
+    pub async fn money_incoming_view_keys(&self) -> Result<Vec<IncomingViewKey>> {
+        let secrets = self.get_money_secrets().await?;
+        Ok(secrets.iter().map(IncomingViewKey::derive).collect())
+    }
+
+    /// Fetch the default `ViewAddress` from the wallet.
+    pub async fn default_view_address(&self) -> Result<ViewAddress> {
+        let secret = self.default_secret().await?;
+        Ok(ViewAddress::from_secret(&secret))
+    }
+
+    /// Fetch all imported incoming view keys from the wallet.
+    pub async fn get_view_keys(&self) -> Result<Vec<IncomingViewKey>> {
+        let rows = match self.wallet.query_multiple(
+            &MONEY_VIEW_KEYS_TABLE,
+            &[MONEY_VIEW_KEYS_COL_VIEW_KEY],
+            &[],
+        ) {
+            Ok(r) => r,
+            Err(e) => {
+                return Err(Error::DatabaseError(format!(
+                    "[get_view_keys] View keys retrieval failed: {e:?}"
+                )))
+            }
+        };
+
+        let mut view_keys = Vec::with_capacity(rows.len());
+        for row in rows {
+            let Value::Blob(ref key_bytes) = row[0] else {
+                return Err(Error::ParseFailed("[get_view_keys] View key bytes parsing failed"))
+            };
+            let secret: SecretKey = deserialize_async(key_bytes).await?;
+            view_keys.push(IncomingViewKey::from(secret));
+        }
+
+        Ok(view_keys)
+    }
+
+    /// Import given incoming view keys into the wallet.
+    /// If the key already exists, it will be skipped.
+    /// Returns the respective view key public keys for the imported keys.
+    pub async fn import_view_keys(
+        &self,
+        view_keys: Vec<IncomingViewKey>,
+    ) -> Result<Vec<PublicKey>> {
+        let existing_view_keys = self.get_view_keys().await?;
+
+        let mut ret = Vec::with_capacity(view_keys.len());
+
+        let query = format!(
+            "INSERT INTO {} ({}) VALUES (?1);",
+            *MONEY_VIEW_KEYS_TABLE, MONEY_VIEW_KEYS_COL_VIEW_KEY
+        );
+
+        for view_key in view_keys {
+            // Check if view key already exists
+            if existing_view_keys.contains(&view_key) {
+                println!("Existing view key found: {}", view_key.public());
+                continue
+            }
+
+            ret.push(view_key.public());
+            let key = serialize_async(&view_key.secret()).await;
+            if let Err(e) = self.wallet.exec_sql(&query, rusqlite::params![key]) {
+                return Err(Error::DatabaseError(format!(
+                    "[import_view_keys] Inserting view key failed: {e:?}"
+                )))
+            }
+        }
+
+        Ok(ret)
+    }
+
+    /// Fetch all the incoming notes found using imported view keys.
+    /// Returns a vector of tuples containing the coin, the view key public key
+    /// it was found with, the decrypted note and the transaction hash.
+    pub async fn get_view_notes(&self) -> Result<Vec<(Coin, PublicKey, MoneyNote, String)>> {
+        let rows = match self.wallet.query_multiple(&MONEY_VIEW_NOTES_TABLE, &[], &[]) {
+            Ok(r) => r,
+            Err(e) => {
+                return Err(Error::DatabaseError(format!(
+                    "[get_view_notes] View notes retrieval failed: {e:?}"
+                )))
+            }
+        };
+
+        let mut ret = Vec::with_capacity(rows.len());
+        for row in rows {
+            let Value::Blob(ref coin_bytes) = row[0] else {
+                return Err(Error::ParseFailed("[get_view_notes] Coin bytes parsing failed"))
+            };
+            let coin: Coin = deserialize_async(coin_bytes).await?;
+
+            let Value::Blob(ref key_bytes) = row[1] else {
+                return Err(Error::ParseFailed("[get_view_notes] View key bytes parsing failed"))
+            };
+            let view_key: PublicKey = deserialize_async(key_bytes).await?;
+
+            let Value::Blob(ref note_bytes) = row[2] else {
+                return Err(Error::ParseFailed("[get_view_notes] Note bytes parsing failed"))
+            };
+            let note: MoneyNote = deserialize_async(note_bytes).await?;
+
+            let Value::Text(ref tx_hash) = row[3] else {
+                return Err(Error::ParseFailed("[get_view_notes] Transaction hash parsing failed"))
+            };
+
+            ret.push((coin, view_key, note, tx_hash.clone()));
+        }
+
+        Ok(ret)
+    }
+
     /// Fetch known unspent balances from the wallet and return them as a hashmap.
     pub async fn money_balance(&self) -> Result<HashMap<String, u64>> {
         let mut coins = self.get_coins(false).await?;
@@ -484,6 +855,15 @@ impl Drk {
         Ok(owncoins)
     }
 
+    /// Auxiliary function to create the inverse query of a coin
+    /// insertion, as cached when scanning the block containing it.
+    pub async fn coin_insert_inverse_query(&self, coin: &Coin) -> WalletDbResult<String> {
+        let query =
+            format!("DELETE FROM {} WHERE {} = ?1;", *MONEY_COINS_TABLE, MONEY_COINS_COL_COIN);
+        self.wallet
+            .create_prepared_statement(&query, rusqlite::params![serialize_async(coin).await])
+    }
+
     /// Auxiliary function to parse a `MONEY_COINS_TABLE` record.
     /// The boolean in the returned tuple notes if the coin was marked as spent.
     async fn parse_coin_record(&self, row: &[Value]) -> Result<(OwnCoin, bool, String)> {
@@ -757,26 +1137,22 @@ impl Drk {
         let data = &call.data.data;
         match MoneyFunction::try_from(data[0])? {
             MoneyFunction::FeeV1 => {
-                println!("[parse_money_call] Found Money::FeeV1 call");
                 let params: MoneyFeeParamsV1 = deserialize_async(&data[9..]).await?;
                 nullifiers.push(params.input.nullifier);
                 coins.push(params.output.coin);
                 notes.push(params.output.note);
             }
             MoneyFunction::GenesisMintV1 => {
-                println!("[parse_money_call] Found Money::GenesisMintV1 call");
                 let params: MoneyGenesisMintParamsV1 = deserialize_async(&data[1..]).await?;
                 coins.push(params.output.coin);
                 notes.push(params.output.note);
             }
             MoneyFunction::PoWRewardV1 => {
-                println!("[parse_money_call] Found Money::PoWRewardV1 call");
                 let params: MoneyPoWRewardParamsV1 = deserialize_async(&data[1..]).await?;
                 coins.push(params.output.coin);
                 notes.push(params.output.note);
             }
             MoneyFunction::TransferV1 => {
-                println!("[parse_money_call] Found Money::TransferV1 call");
                 let params: MoneyTransferParamsV1 = deserialize_async(&data[1..]).await?;
 
                 for input in params.inputs {
@@ -789,7 +1165,6 @@ impl Drk {
                 }
             }
             MoneyFunction::OtcSwapV1 => {
-                println!("[parse_money_call] Found Money::OtcSwapV1 call");
                 let params: MoneyTransferParamsV1 = deserialize_async(&data[1..]).await?;
 
                 for input in params.inputs {
@@ -802,16 +1177,13 @@ impl Drk {
                 }
             }
             MoneyFunction::AuthTokenMintV1 => {
-                println!("[parse_money_call] Found Money::AuthTokenMintV1 call");
                 // Handled in TokenMint
             }
             MoneyFunction::AuthTokenFreezeV1 => {
-                println!("[parse_money_call] Found Money::AuthTokenFreezeV1 call");
                 let params: MoneyAuthTokenFreezeParamsV1 = deserialize_async(&data[1..]).await?;
                 freezes.push(params.token_id);
             }
             MoneyFunction::TokenMintV1 => {
-                println!("[parse_money_call] Found Money::TokenMintV1 call");
                 let params: MoneyTokenMintParamsV1 = deserialize_async(&data[1..]).await?;
                 coins.push(params.coin);
                 // Grab the note from the child auth call
@@ -826,37 +1198,105 @@ impl Drk {
         Ok((nullifiers, coins, notes, freezes))
     }
 
+    /// Auxiliary function to grab all the keys we can decrypt Money notes with
+    /// in blocks from provided height onwards, along with the secret key owning
+    /// the coins of those notes. Rotated keys whose grace window ended before
+    /// that height are skipped.
+    async fn money_decryption_keys(&self, height: u32) -> Result<Vec<(SecretKey, SecretKey)>> {
+        let mut secrets = self.get_money_secrets().await?;
+        let expired: Vec<PublicKey> = self
+            .get_key_rotations()
+            .await?
+            .into_iter()
+            .filter(|r| r.grace_end() < height)
+            .map(|r| r.public)
+            .collect();
+        secrets.retain(|s| !expired.contains(&PublicKey::from_secret(*s)));
+
+        let dao_notes_secrets = self.get_dao_notes_secrets().await?;
+
+        // Notes sent to our `ViewAddress` are encrypted for the incoming view
+        // key, so we try both the secret and its view key, and keep the secret
+        // as the coin owner.
+        let mut decryption_keys = vec![];
+        for secret in secrets.iter().chain(dao_notes_secrets.iter()) {
+            decryption_keys.push((*secret, *secret));
+            decryption_keys.push((IncomingViewKey::derive(secret).secret(), *secret));
+        }
+
+        Ok(decryption_keys)
+    }
+
+    /// Trial decrypt all the Money notes found in the given blocks, using our
+    /// wallet keys and the imported view keys. Keys are retrieved once for the
+    /// whole batch, since scanning never creates new ones, and the notes get
+    /// decrypted in parallel over all available threads.
+    pub async fn trial_decrypt_blocks(&self, blocks: &[BlockInfo]) -> Result<DecryptedNotes> {
+        // Gather all the coins and notes of the Money calls
+        let mut outputs = vec![];
+        for block in blocks {
+            for tx in block.txs.iter() {
+                for (i, call) in tx.calls.iter().enumerate() {
+                    if call.data.contract_id != *MONEY_CONTRACT_ID {
+                        continue
+                    }
+
+                    let (_, coins, notes, _) = self.parse_money_call(i, &tx.calls).await?;
+                    outputs.extend(coins.into_iter().zip(notes));
+                }
+            }
+        }
+
+        if outputs.is_empty() {
+            return Ok(DecryptedNotes::default())
+        }
+
+        let height = blocks.iter().map(|b| b.header.height).min().unwrap_or_default();
+        let decryption_keys = self.money_decryption_keys(height).await?;
+        let view_keys = self.get_view_keys().await?;
+
+        Ok(smol::unblock(move || trial_decrypt_notes(&outputs, &decryption_keys, &view_keys)).await)
+    }
+
     /// Append data related to Money contract transactions into the wallet database,
-    /// and store their inverse queries into the cache.
+    /// and store their inverse queries into the cache. Notes must have already
+    /// been trial decrypted using [`Drk::trial_decrypt_blocks`].
     /// Returns a flag indicating if the provided data refer to our own wallet.
     pub async fn apply_tx_money_data(
         &self,
         call_idx: usize,
         calls: &[DarkLeaf<ContractCall>],
         tx_hash: &String,
+        decrypted: &DecryptedNotes,
     ) -> Result<bool> {
-        let (nullifiers, coins, notes, freezes) = self.parse_money_call(call_idx, calls).await?;
-        let secrets = self.get_money_secrets().await?;
-        let dao_notes_secrets = self.get_dao_notes_secrets().await?;
+        let (nullifiers, coins, _, freezes) = self.parse_money_call(call_idx, calls).await?;
         let mut tree = self.get_money_tree().await?;
 
         let mut owncoins = vec![];
+        let mut view_notes = vec![];
 
-        for (coin, note) in coins.iter().zip(notes.iter()) {
+        for coin in coins.iter() {
             // Append the new coin to the Merkle tree. Every coin has to be added.
             tree.append(MerkleNode::from(coin.inner()));
 
-            // Attempt to decrypt the note
-            for secret in secrets.iter().chain(dao_notes_secrets.iter()) {
-                if let Ok(note) = note.decrypt::<MoneyNote>(secret) {
-                    println!("[apply_tx_money_data] Successfully decrypted a Money Note");
-                    println!("[apply_tx_money_data] Witnessing coin in Merkle tree");
-                    let leaf_position = tree.mark().unwrap();
+            // Check if we managed to decrypt its note
+            let coin_key = coin.inner().to_repr();
+            if let Some((note, secret)) = decrypted.owned.get(&coin_key) {
+                println!("[apply_tx_money_data] Successfully decrypted a Money Note");
+                println!("[apply_tx_money_data] Witnessing coin in Merkle tree");
+                let leaf_position = tree.mark().unwrap();
 
-                    let owncoin =
-                        OwnCoin { coin: *coin, note: note.clone(), secret: *secret, leaf_position };
+                let owncoin =
+                    OwnCoin { coin: *coin, note: note.clone(), secret: *secret, leaf_position };
 
-                    owncoins.push(owncoin);
+                owncoins.push(owncoin);
+            }
+
+            // Check if any of the imported view keys decrypted its note
+            if let Some(notes) = decrypted.viewed.get(&coin_key) {
+                for (view_key, note) in notes {
+                    println!("[apply_tx_money_data] Found incoming note for view key");
+                    view_notes.push((*coin, *view_key, note.clone()));
                 }
             }
         }
@@ -887,10 +1327,6 @@ impl Drk {
             MONEY_COINS_COL_MEMO,
         );
 
-        // This is its inverse query
-        let inverse_query =
-            format!("DELETE FROM {} WHERE {} = ?1;", *MONEY_COINS_TABLE, MONEY_COINS_COL_COIN);
-
         println!("Found {} OwnCoin(s) in transaction", owncoins.len());
         for owncoin in &owncoins {
             println!("OwnCoin: {:?}", owncoin.coin);
@@ -898,16 +1334,14 @@ impl Drk {
             let key = serialize_async(&owncoin.coin).await;
 
             // Create its inverse query
-            let inverse =
-                match self.wallet.create_prepared_statement(&inverse_query, rusqlite::params![key])
-                {
-                    Ok(q) => q,
-                    Err(e) => {
-                        return Err(Error::DatabaseError(format!(
+            let inverse = match self.coin_insert_inverse_query(&owncoin.coin).await {
+                Ok(q) => q,
+                Err(e) => {
+                    return Err(Error::DatabaseError(format!(
                     "[apply_tx_money_data] Creating Money coin insert inverse query failed: {e:?}"
                 )))
-                    }
-                };
+                }
+            };
 
             // Execute the query
             let params = rusqlite::params![
@@ -937,6 +1371,69 @@ impl Drk {
                     "[apply_tx_money_data] Inserting inverse query into cache failed: {e:?}"
                 )))
             }
+
+            self.hooks
+                .queue(WalletEvent::CoinReceived {
+                    coin: owncoin.coin,
+                    value: owncoin.note.value,
+                    token_id: owncoin.note.token_id,
+                    tx_hash: tx_hash.clone(),
+                })
+                .await;
+        }
+
+        // This is the SQL query we'll be executing to insert view notes into the wallet
+        let query = format!(
+            "INSERT OR IGNORE INTO {} ({}, {}, {}, {}) VALUES (?1, ?2, ?3, ?4);",
+            *MONEY_VIEW_NOTES_TABLE,
+            MONEY_VIEW_NOTES_COL_COIN,
+            MONEY_VIEW_NOTES_COL_VIEW_KEY,
+            MONEY_VIEW_NOTES_COL_NOTE,
+            MONEY_VIEW_NOTES_COL_TX_HASH,
+        );
+
+        // This is its inverse query
+        let inverse_query = format!(
+            "DELETE FROM {} WHERE {} = ?1;",
+            *MONEY_VIEW_NOTES_TABLE, MONEY_VIEW_NOTES_COL_COIN
+        );
+
+        for (coin, view_key, note) in &view_notes {
+            // Grab view note record key
+            let key = serialize_async(coin).await;
+
+            // Create its inverse query
+            let inverse =
+                match self.wallet.create_prepared_statement(&inverse_query, rusqlite::params![key])
+                {
+                    Ok(q) => q,
+                    Err(e) => {
+                        return Err(Error::DatabaseError(format!(
+                    "[apply_tx_money_data] Creating Money view note insert inverse query failed: {e:?}"
+                )))
+                    }
+                };
+
+            // Execute the query
+            let params = rusqlite::params![
+                key,
+                serialize_async(view_key).await,
+                serialize_async(note).await,
+                tx_hash,
+            ];
+
+            if let Err(e) = self.wallet.exec_sql(&query, params) {
+                return Err(Error::DatabaseError(format!(
+                    "[apply_tx_money_data] Inserting Money view note failed: {e:?}"
+                )))
+            }
+
+            // Store its inverse
+            if let Err(e) = self.wallet.cache_inverse(inverse) {
+                return Err(Error::DatabaseError(format!(
+                    "[apply_tx_money_data] Inserting inverse query into cache failed: {e:?}"
+                )))
+            }
         }
 
         // This is the SQL query we'll be executing to update frozen tokens into the wallet
@@ -986,7 +1483,10 @@ impl Drk {
             kaching().await;
         }
 
-        Ok(wallet_spent_coins || !owncoins.is_empty() || !freezes.is_empty())
+        Ok(wallet_spent_coins ||
+            !owncoins.is_empty() ||
+            !view_notes.is_empty() ||
+            !freezes.is_empty())
     }
 
     /// Auxiliary function to  grab all the nullifiers from a transaction money call.
@@ -1155,6 +1655,16 @@ impl Drk {
         Ok(())
     }
 
+    /// Reset the Money view notes in the wallet.
+    pub fn reset_money_view_notes(&self) -> WalletDbResult<()> {
+        println!("Resetting view notes");
+        let query = format!("DELETE FROM {};", *MONEY_VIEW_NOTES_TABLE);
+        self.wallet.exec_sql(&query, &[])?;
+        println!("Successfully reset view notes");
+
+        Ok(())
+    }
+
     /// Retrieve token by provided string.
     /// Input string represents either an alias or a token id.
     pub async fn get_token(&self, input: String) -> Result<TokenId> {
@@ -1226,19 +1736,25 @@ impl Drk {
         let signature_secret = SecretKey::random(&mut OsRng);
 
         // Create the actual fee proof
-        let (proof, public_inputs) = create_fee_proof(
-            fee_zkbin,
-            fee_pk,
-            &input,
-            input_value_blind,
-            &output,
-            output_value_blind,
-            output.spend_hook,
-            output.user_data,
-            output.blind,
-            token_blind,
-            signature_secret,
-        )?;
+        let (fee_zkbin, fee_pk, proof_output) = (fee_zkbin.clone(), fee_pk.clone(), output.clone());
+        let (proof, public_inputs) = self
+            .prover
+            .run(ProofPriority::Interactive, move || {
+                create_fee_proof(
+                    &fee_zkbin,
+                    &fee_pk,
+                    &input,
+                    input_value_blind,
+                    &proof_output,
+                    output_value_blind,
+                    proof_output.spend_hook,
+                    proof_output.user_data,
+                    proof_output.blind,
+                    token_blind,
+                    signature_secret,
+                )
+            })
+            .await??;
 
         // Encrypted note for the output
         let note = MoneyNote {
@@ -0,0 +1,247 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2025 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use std::{collections::BTreeMap, sync::Arc};
+
+use url::Url;
+
+use darkfi::{
+    rpc::{client::RpcClient, jsonrpc::JsonRequest, util::JsonValue},
+    tx::{ContractCallLeaf, Transaction, TransactionBuilder},
+    util::encoding::base64,
+    Error, Result,
+};
+use darkfi_oracle_contract::{
+    client::{
+        register_v1::OracleRegisterCallBuilder, rotate_v1::OracleRotateCallBuilder,
+        submit_v1::OracleSubmitCallBuilder,
+    },
+    model::{AttestedValue, FeedId, FeedKind, OracleAttestation, OracleFeed},
+    OracleFunction, ORACLE_CONTRACT_FEEDS_TREE, ORACLE_CONTRACT_VALUES_TREE,
+};
+use darkfi_sdk::{
+    crypto::{PublicKey, ORACLE_CONTRACT_ID},
+    ContractCall,
+};
+use darkfi_serial::{deserialize_async, serialize_async, AsyncDecodable, AsyncEncodable};
+
+use crate::Drk;
+
+impl Drk {
+    /// Fetch the on-chain record of the given feed from darkfid.
+    pub async fn get_oracle_feed(&self, feed_id: &FeedId) -> Result<Option<OracleFeed>> {
+        self.get_oracle_state(ORACLE_CONTRACT_FEEDS_TREE, feed_id).await
+    }
+
+    /// Fetch the latest attested value of the given feed from darkfid.
+    pub async fn get_oracle_value(&self, feed_id: &FeedId) -> Result<Option<AttestedValue>> {
+        self.get_oracle_state(ORACLE_CONTRACT_VALUES_TREE, feed_id).await
+    }
+
+    /// Auxiliary function to fetch a `FeedId` keyed entry of an Oracle
+    /// contract state tree from darkfid.
+    async fn get_oracle_state<T: AsyncDecodable>(
+        &self,
+        tree: &str,
+        feed_id: &FeedId,
+    ) -> Result<Option<T>> {
+        let params = JsonValue::Array(vec![
+            JsonValue::String(ORACLE_CONTRACT_ID.to_string()),
+            JsonValue::String(tree.to_string()),
+            JsonValue::String(base64::encode(&serialize_async(feed_id).await)),
+        ]);
+        let rep = self.darkfid_daemon_request("blockchain.get_contract_state_key", &params).await?;
+
+        let Some(value) = rep.get::<String>() else { return Ok(None) };
+        let Some(bytes) = base64::decode(value) else {
+            return Err(Error::ParseFailed("[get_oracle_state] Failed decoding state value"))
+        };
+
+        Ok(Some(deserialize_async(&bytes).await?))
+    }
+
+    /// Create a feed registration transaction, without a fee call. The
+    /// wallet's default address is used as the feed authority.
+    pub async fn oracle_register(
+        &self,
+        name: &str,
+        kind: FeedKind,
+        oracles: Vec<PublicKey>,
+        threshold: u8,
+    ) -> Result<(Transaction, FeedId)> {
+        let authority = self.default_secret().await?;
+
+        // Create the contract call
+        let register_call = OracleRegisterCallBuilder {
+            authority: PublicKey::from_secret(authority),
+            name: name.to_string(),
+            kind,
+            oracles,
+            threshold,
+        };
+        let register_debris = register_call.build()?;
+
+        // Encode the call
+        let mut data = vec![OracleFunction::RegisterV1 as u8];
+        register_debris.params.encode_async(&mut data).await?;
+        let call = ContractCall { contract_id: *ORACLE_CONTRACT_ID, data };
+        let mut tx_builder =
+            TransactionBuilder::new(ContractCallLeaf { call, proofs: vec![] }, vec![])?;
+
+        let mut tx = tx_builder.build()?;
+        let sigs = tx.create_sigs(&[authority])?;
+        tx.signatures = vec![sigs];
+
+        Ok((tx, register_debris.feed_id))
+    }
+
+    /// Create an oracle set rotation transaction, without a fee call.
+    /// The feed authority has to be one of the wallet's addresses.
+    pub async fn oracle_rotate(
+        &self,
+        feed_id: FeedId,
+        oracles: Vec<PublicKey>,
+        threshold: u8,
+    ) -> Result<Transaction> {
+        let Some(feed) = self.get_oracle_feed(&feed_id).await? else {
+            return Err(Error::Custom(format!("Feed {feed_id} is not registered")))
+        };
+
+        let Some(authority) = self
+            .get_money_secrets()
+            .await?
+            .into_iter()
+            .find(|s| PublicKey::from_secret(*s) == feed.authority)
+        else {
+            return Err(Error::Custom(format!("Feed {feed_id} authority is not in this wallet")))
+        };
+
+        // Create the contract call
+        let rotate_call = OracleRotateCallBuilder { feed_id, oracles, threshold };
+        let rotate_debris = rotate_call.build()?;
+
+        // Encode the call
+        let mut data = vec![OracleFunction::RotateV1 as u8];
+        rotate_debris.params.encode_async(&mut data).await?;
+        let call = ContractCall { contract_id: *ORACLE_CONTRACT_ID, data };
+        let mut tx_builder =
+            TransactionBuilder::new(ContractCallLeaf { call, proofs: vec![] }, vec![])?;
+
+        let mut tx = tx_builder.build()?;
+        let sigs = tx.create_sigs(&[authority])?;
+        tx.signatures = vec![sigs];
+
+        Ok(tx)
+    }
+
+    /// Gather the latest attestations for the given feed from `oracled`
+    /// reporters, and create a transaction submitting the newest round
+    /// reaching the feed threshold, without a fee call.
+    ///
+    /// Returns the transaction along with the submitted round and the
+    /// value it is expected to be accepted with.
+    pub async fn oracle_collect(
+        &self,
+        feed_id: FeedId,
+        reporters: &[Url],
+        ex: Arc<smol::Executor<'static>>,
+    ) -> Result<(Transaction, u64, u64)> {
+        let Some(feed) = self.get_oracle_feed(&feed_id).await? else {
+            return Err(Error::Custom(format!("Feed {feed_id} is not registered")))
+        };
+        let latest_round = self.get_oracle_value(&feed_id).await?.map(|v| v.round);
+
+        // Gather the attestations, grouped by round
+        let mut rounds: BTreeMap<u64, Vec<OracleAttestation>> = BTreeMap::new();
+        for reporter in reporters {
+            match fetch_attestation(reporter, &feed_id, ex.clone()).await {
+                Ok(Some((round, attestation))) => {
+                    rounds.entry(round).or_default().push(attestation)
+                }
+                Ok(None) => eprintln!("Reporter {reporter} has no attestation yet"),
+                Err(e) => eprintln!("Failed fetching attestation from {reporter}: {e}"),
+            }
+        }
+
+        // Try the newest rounds first, skipping the ones already on-chain
+        for (round, attestations) in rounds.into_iter().rev() {
+            if latest_round.is_some_and(|latest| round <= latest) {
+                break
+            }
+
+            let submit_call = OracleSubmitCallBuilder { feed_id, feed: &feed, round, attestations };
+            let submit_debris = match submit_call.build() {
+                Ok(v) => v,
+                Err(e) => {
+                    eprintln!("Skipping round {round}: {e}");
+                    continue
+                }
+            };
+
+            // Encode the call
+            let mut data = vec![OracleFunction::SubmitV1 as u8];
+            submit_debris.params.encode_async(&mut data).await?;
+            let call = ContractCall { contract_id: *ORACLE_CONTRACT_ID, data };
+            let mut tx_builder =
+                TransactionBuilder::new(ContractCallLeaf { call, proofs: vec![] }, vec![])?;
+
+            // Submissions require no signatures
+            let mut tx = tx_builder.build()?;
+            tx.signatures = vec![vec![]];
+
+            return Ok((tx, round, submit_debris.value))
+        }
+
+        Err(Error::Custom(format!("No new round of feed {feed_id} reached the threshold")))
+    }
+}
+
+/// Auxiliary function to fetch the latest attestation of a feed from
+/// an `oracled` JSON-RPC endpoint.
+async fn fetch_attestation(
+    endpoint: &Url,
+    feed_id: &FeedId,
+    ex: Arc<smol::Executor<'static>>,
+) -> Result<Option<(u64, OracleAttestation)>> {
+    let rpc_client = RpcClient::new(endpoint.clone(), ex).await?;
+    let req = JsonRequest::new(
+        "oracle.attestation",
+        JsonValue::Array(vec![JsonValue::String(feed_id.to_string())]),
+    );
+    let rep = rpc_client.request(req).await;
+    rpc_client.stop().await;
+    let rep = rep?;
+
+    if rep.is_null() {
+        return Ok(None)
+    }
+
+    let Some(rep) = rep.get::<Vec<JsonValue>>() else {
+        return Err(Error::ParseFailed("[fetch_attestation] Invalid response"))
+    };
+    if rep.len() != 2 || !rep[0].is_number() || !rep[1].is_string() {
+        return Err(Error::ParseFailed("[fetch_attestation] Invalid response"))
+    }
+
+    let round = *rep[0].get::<f64>().unwrap() as u64;
+    let Some(bytes) = base64::decode(rep[1].get::<String>().unwrap()) else {
+        return Err(Error::ParseFailed("[fetch_attestation] Failed decoding attestation"))
+    };
+
+    Ok(Some((round, deserialize_async(&bytes).await?)))
+}
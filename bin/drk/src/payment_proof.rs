@@ -0,0 +1,208 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2025 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use rusqlite::types::Value;
+
+use darkfi::{tx::Transaction, Error, Result};
+use darkfi_money_contract::{
+    client::{payment_proof::PaymentProof, MoneyNote},
+    model::{CoinAttributes, MoneyTransferParamsV1},
+    MoneyFunction,
+};
+use darkfi_sdk::{
+    crypto::{PublicKey, MONEY_CONTRACT_ID},
+    tx::TransactionHash,
+};
+use darkfi_serial::{deserialize_async, serialize_async};
+
+use crate::{
+    convert_named_params,
+    money::{
+        MONEY_PAYMENTS_COL_CALL_IDX, MONEY_PAYMENTS_COL_NOTE, MONEY_PAYMENTS_COL_OUTPUT_IDX,
+        MONEY_PAYMENTS_COL_RECIPIENT, MONEY_PAYMENTS_COL_TX_HASH, MONEY_PAYMENTS_TABLE,
+    },
+    Drk,
+};
+
+impl Drk {
+    /// Store the notes of the outputs of a `Money::Transfer` call paying
+    /// to `recipient`, so we can later create payment proofs for them.
+    pub async fn put_payment_notes(
+        &self,
+        tx_hash: &TransactionHash,
+        call_idx: u32,
+        params: &MoneyTransferParamsV1,
+        notes: &[MoneyNote],
+        recipient: &PublicKey,
+    ) -> Result<()> {
+        let query = format!(
+            "INSERT OR REPLACE INTO {} ({}, {}, {}, {}, {}) VALUES (?1, ?2, ?3, ?4, ?5);",
+            *MONEY_PAYMENTS_TABLE,
+            MONEY_PAYMENTS_COL_TX_HASH,
+            MONEY_PAYMENTS_COL_CALL_IDX,
+            MONEY_PAYMENTS_COL_OUTPUT_IDX,
+            MONEY_PAYMENTS_COL_RECIPIENT,
+            MONEY_PAYMENTS_COL_NOTE,
+        );
+
+        for (output_idx, (output, note)) in params.outputs.iter().zip(notes.iter()).enumerate() {
+            // Skip outputs not minted to the recipient, like our change
+            let coin = CoinAttributes {
+                public_key: *recipient,
+                value: note.value,
+                token_id: note.token_id,
+                spend_hook: note.spend_hook,
+                user_data: note.user_data,
+                blind: note.coin_blind,
+            }
+            .to_coin();
+            if coin != output.coin {
+                continue
+            }
+
+            if let Err(e) = self.wallet.exec_sql(
+                &query,
+                rusqlite::params![
+                    tx_hash.to_string(),
+                    call_idx,
+                    output_idx as u32,
+                    serialize_async(recipient).await,
+                    serialize_async(note).await,
+                ],
+            ) {
+                return Err(Error::DatabaseError(format!(
+                    "[put_payment_notes] Inserting payment note failed: {e:?}"
+                )))
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Fetch all the stored payment notes of the given transaction.
+    /// Returns a vector of tuples containing the call index, the output
+    /// index, the recipient and the output note.
+    pub async fn get_payment_notes(
+        &self,
+        tx_hash: &TransactionHash,
+    ) -> Result<Vec<(u32, u32, PublicKey, MoneyNote)>> {
+        let rows = match self.wallet.query_multiple(
+            &MONEY_PAYMENTS_TABLE,
+            &[
+                MONEY_PAYMENTS_COL_CALL_IDX,
+                MONEY_PAYMENTS_COL_OUTPUT_IDX,
+                MONEY_PAYMENTS_COL_RECIPIENT,
+                MONEY_PAYMENTS_COL_NOTE,
+            ],
+            convert_named_params! {(MONEY_PAYMENTS_COL_TX_HASH, tx_hash.to_string())},
+        ) {
+            Ok(r) => r,
+            Err(e) => {
+                return Err(Error::DatabaseError(format!(
+                    "[get_payment_notes] Payment notes retrieval failed: {e:?}"
+                )))
+            }
+        };
+
+        let mut ret = Vec::with_capacity(rows.len());
+        for row in rows {
+            let Value::Integer(call_idx) = row[0] else {
+                return Err(Error::ParseFailed("[get_payment_notes] Call index parsing failed"))
+            };
+            let Ok(call_idx) = u32::try_from(call_idx) else {
+                return Err(Error::ParseFailed("[get_payment_notes] Call index parsing failed"))
+            };
+
+            let Value::Integer(output_idx) = row[1] else {
+                return Err(Error::ParseFailed("[get_payment_notes] Output index parsing failed"))
+            };
+            let Ok(output_idx) = u32::try_from(output_idx) else {
+                return Err(Error::ParseFailed("[get_payment_notes] Output index parsing failed"))
+            };
+
+            let Value::Blob(ref recipient_bytes) = row[2] else {
+                return Err(Error::ParseFailed("[get_payment_notes] Recipient bytes parsing failed"))
+            };
+            let recipient: PublicKey = deserialize_async(recipient_bytes).await?;
+
+            let Value::Blob(ref note_bytes) = row[3] else {
+                return Err(Error::ParseFailed("[get_payment_notes] Note bytes parsing failed"))
+            };
+            let note: MoneyNote = deserialize_async(note_bytes).await?;
+
+            ret.push((call_idx, output_idx, recipient, note));
+        }
+
+        Ok(ret)
+    }
+
+    /// Create payment proofs for the outputs we paid in the given transaction.
+    /// If `output_idx` is provided, only that output is proven.
+    pub async fn create_payment_proofs(
+        &self,
+        tx_hash: &TransactionHash,
+        output_idx: Option<u32>,
+    ) -> Result<Vec<PaymentProof>> {
+        let notes = self.get_payment_notes(tx_hash).await?;
+
+        let mut proofs = vec![];
+        for (call_idx, idx, recipient, note) in notes {
+            if output_idx.is_some_and(|i| i != idx) {
+                continue
+            }
+            proofs.push(PaymentProof::new(*tx_hash, call_idx, idx, &note, recipient));
+        }
+
+        if proofs.is_empty() {
+            return Err(Error::Custom(format!("No payment found for transaction: {tx_hash}")))
+        }
+
+        Ok(proofs)
+    }
+
+    /// Verify a payment proof against its confirmed transaction, fetched
+    /// from darkfid. Returns the transaction on success.
+    pub async fn verify_payment_proof(&self, proof: &PaymentProof) -> Result<Transaction> {
+        let Some(tx) = self.get_tx(&proof.tx_hash).await? else {
+            return Err(Error::Custom(format!(
+                "Transaction {} was not found in the blockchain",
+                proof.tx_hash
+            )))
+        };
+
+        let Some(call) = tx.calls.get(proof.call_idx as usize) else {
+            return Err(Error::Custom("Payment proof call index is out of bounds".to_string()))
+        };
+        if call.data.contract_id != *MONEY_CONTRACT_ID ||
+            call.data.data.first() != Some(&(MoneyFunction::TransferV1 as u8))
+        {
+            return Err(Error::Custom("Payment proof call is not a Money::Transfer".to_string()))
+        }
+        let params: MoneyTransferParamsV1 = deserialize_async(&call.data.data[1..]).await?;
+
+        let Some(output) = params.outputs.get(proof.output_idx as usize) else {
+            return Err(Error::Custom("Payment proof output index is out of bounds".to_string()))
+        };
+
+        if !proof.verify(output) {
+            return Err(Error::Custom("Payment proof doesn't match the transaction".to_string()))
+        }
+
+        Ok(tx)
+    }
+}
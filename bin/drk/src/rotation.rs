@@ -0,0 +1,271 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2025 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use darkfi::{system::sleep, Error, Result};
+use darkfi_money_contract::{client::OwnCoin, model::TokenId};
+use darkfi_sdk::crypto::{FuncId, PublicKey, SecretKey};
+use darkfi_serial::{deserialize_async, serialize_async};
+use rand::{rngs::OsRng, seq::SliceRandom, Rng};
+use rusqlite::types::Value;
+
+use crate::{
+    locale::format_amount,
+    money::{
+        MONEY_KEY_ROTATIONS_COL_GRACE_BLOCKS, MONEY_KEY_ROTATIONS_COL_HEIGHT,
+        MONEY_KEY_ROTATIONS_COL_PUBLIC, MONEY_KEY_ROTATIONS_COL_SUCCESSOR,
+        MONEY_KEY_ROTATIONS_TABLE,
+    },
+    Drk,
+};
+
+/// A wallet key retired by a key rotation
+#[derive(Clone, Debug)]
+pub struct KeyRotation {
+    /// Retired public key
+    pub public: PublicKey,
+    /// Public key that replaced it as the default
+    pub successor: PublicKey,
+    /// Last scanned block height when the rotation started
+    pub height: u32,
+    /// Number of blocks after `height` the retired key keeps getting scanned for
+    pub grace_blocks: u32,
+}
+
+impl KeyRotation {
+    /// Last block height the retired key gets scanned for
+    pub fn grace_end(&self) -> u32 {
+        self.height.saturating_add(self.grace_blocks)
+    }
+}
+
+impl Drk {
+    /// Fetch all the key rotations recorded in the wallet.
+    pub async fn get_key_rotations(&self) -> Result<Vec<KeyRotation>> {
+        let rows = match self.wallet.query_multiple(&MONEY_KEY_ROTATIONS_TABLE, &[], &[]) {
+            Ok(r) => r,
+            Err(e) => {
+                return Err(Error::DatabaseError(format!(
+                    "[get_key_rotations] Key rotations retrieval failed: {e:?}"
+                )))
+            }
+        };
+
+        let mut rotations = Vec::with_capacity(rows.len());
+        for row in rows {
+            let Value::Blob(ref public_bytes) = row[0] else {
+                return Err(Error::ParseFailed("[get_key_rotations] Public key parsing failed"))
+            };
+            let public = deserialize_async(public_bytes).await?;
+
+            let Value::Blob(ref successor_bytes) = row[1] else {
+                return Err(Error::ParseFailed("[get_key_rotations] Successor parsing failed"))
+            };
+            let successor = deserialize_async(successor_bytes).await?;
+
+            let Value::Integer(height) = row[2] else {
+                return Err(Error::ParseFailed("[get_key_rotations] Height parsing failed"))
+            };
+            let Ok(height) = u32::try_from(height) else {
+                return Err(Error::ParseFailed("[get_key_rotations] Height parsing failed"))
+            };
+
+            let Value::Integer(grace_blocks) = row[3] else {
+                return Err(Error::ParseFailed("[get_key_rotations] Grace blocks parsing failed"))
+            };
+            let Ok(grace_blocks) = u32::try_from(grace_blocks) else {
+                return Err(Error::ParseFailed("[get_key_rotations] Grace blocks parsing failed"))
+            };
+
+            rotations.push(KeyRotation { public, successor, height, grace_blocks });
+        }
+
+        Ok(rotations)
+    }
+
+    /// Rotate the wallet keys: generate a new default key and retire all
+    /// the keys that weren't retired already in its favor. Retired keys keep
+    /// getting scanned for during `grace_blocks` blocks, so payments still
+    /// in flight to them aren't lost. Their coins must then be moved to the
+    /// new key using [`Drk::migrate_rotated_coins`].
+    /// Returns the new default address.
+    pub async fn rotate_keys(&self, grace_blocks: u32) -> Result<PublicKey> {
+        let rotated: Vec<PublicKey> =
+            self.get_key_rotations().await?.into_iter().map(|r| r.public).collect();
+        let retiring: Vec<PublicKey> = self
+            .addresses()
+            .await?
+            .into_iter()
+            .map(|(_, public, _, _)| public)
+            .filter(|public| !rotated.contains(public))
+            .collect();
+        if retiring.is_empty() {
+            return Err(Error::Custom("Wallet has no keys to rotate".to_string()))
+        }
+
+        let (height, _) = match self.get_last_scanned_block() {
+            Ok(v) => v,
+            Err(e) => {
+                return Err(Error::DatabaseError(format!(
+                    "[rotate_keys] Retrieving last scanned block failed: {e:?}"
+                )))
+            }
+        };
+
+        // Generate the successor and make it our default key
+        let successor = self.money_keygen().await?.public;
+        let Some((key_id, _, _, _)) =
+            self.addresses().await?.into_iter().find(|(_, public, _, _)| *public == successor)
+        else {
+            return Err(Error::DatabaseError(
+                "[rotate_keys] Generated keypair not found".to_string(),
+            ))
+        };
+        if let Err(e) = self.set_default_address(key_id as usize) {
+            return Err(Error::DatabaseError(format!(
+                "[rotate_keys] Setting default address failed: {e:?}"
+            )))
+        }
+
+        let query = format!(
+            "INSERT INTO {} ({}, {}, {}, {}) VALUES (?1, ?2, ?3, ?4);",
+            *MONEY_KEY_ROTATIONS_TABLE,
+            MONEY_KEY_ROTATIONS_COL_PUBLIC,
+            MONEY_KEY_ROTATIONS_COL_SUCCESSOR,
+            MONEY_KEY_ROTATIONS_COL_HEIGHT,
+            MONEY_KEY_ROTATIONS_COL_GRACE_BLOCKS,
+        );
+        for public in retiring {
+            if let Err(e) = self.wallet.exec_sql(
+                &query,
+                rusqlite::params![
+                    serialize_async(&public).await,
+                    serialize_async(&successor).await,
+                    height,
+                    grace_blocks
+                ],
+            ) {
+                return Err(Error::DatabaseError(format!(
+                    "[rotate_keys] Inserting key rotation failed: {e:?}"
+                )))
+            }
+        }
+
+        Ok(successor)
+    }
+
+    /// Fetch the unspent coins still owned by retired keys, grouped by token.
+    pub async fn rotated_coins(&self) -> Result<Vec<(TokenId, Vec<OwnCoin>)>> {
+        let rotated: Vec<PublicKey> =
+            self.get_key_rotations().await?.into_iter().map(|r| r.public).collect();
+        let secrets: Vec<SecretKey> = self
+            .addresses()
+            .await?
+            .into_iter()
+            .filter(|(_, public, _, _)| rotated.contains(public))
+            .map(|(_, _, secret, _)| secret)
+            .collect();
+
+        let mut coins: Vec<(TokenId, Vec<OwnCoin>)> = vec![];
+        for (coin, _, _) in self.get_coins(false).await? {
+            if coin.note.spend_hook != FuncId::none() || !secrets.contains(&coin.secret) {
+                continue
+            }
+
+            match coins.iter_mut().find(|(token_id, _)| *token_id == coin.note.token_id) {
+                Some((_, token_coins)) => token_coins.push(coin),
+                None => coins.push((coin.note.token_id, vec![coin])),
+            }
+        }
+
+        Ok(coins)
+    }
+
+    /// Move the coins owned by retired keys to our default key.
+    ///
+    /// Coins get moved in batches of a random size of up to `max_inputs`
+    /// coins of the same token each, waiting a random delay of up to
+    /// `max_delay` seconds between them, so the batches can't be trivially
+    /// linked together. Batches whose fee would exceed `max_fee` are not
+    /// broadcasted, and the migration stops. It can be resumed later on,
+    /// since only coins still owned by retired keys get picked up.
+    ///
+    /// Returns the IDs of the broadcasted transactions.
+    pub async fn migrate_rotated_coins(
+        &self,
+        max_inputs: usize,
+        max_fee: Option<u64>,
+        max_delay: u64,
+    ) -> Result<Vec<String>> {
+        if max_inputs == 0 {
+            return Err(Error::Custom("Max inputs per transaction must be at least 1".to_string()))
+        }
+
+        let mut txids = vec![];
+        for (token_id, mut coins) in self.rotated_coins().await? {
+            coins.shuffle(&mut OsRng);
+            while !coins.is_empty() {
+                // Wait a random delay and pick up the previous batch changes
+                if !txids.is_empty() {
+                    let delay = OsRng.gen_range(0..=max_delay);
+                    println!("Waiting {delay}s before the next batch...");
+                    sleep(delay).await;
+                    if let Err(e) = self.scan_blocks().await {
+                        return Err(Error::DatabaseError(format!(
+                            "[migrate_rotated_coins] Scanning blocks failed: {e:?}"
+                        )))
+                    }
+
+                    // Previous batch fees may have spent some of them
+                    self.retain_unspent_coins(&mut coins).await?;
+                    if coins.is_empty() {
+                        break
+                    }
+                }
+
+                let batch_size = OsRng.gen_range(1..=max_inputs.min(coins.len()));
+                let batch: Vec<OwnCoin> = coins.drain(..batch_size).collect();
+                let value: u64 = batch.iter().map(|c| c.note.value).sum();
+
+                let tx = self.sweep_tx(token_id, batch).await?;
+
+                if let Some(max_fee) = max_fee {
+                    let fee = self.get_tx_gas(&tx, true).await?;
+                    if fee > max_fee {
+                        println!(
+                            "Batch fee {} exceeds the maximum of {}, stopping the migration",
+                            format_amount(fee),
+                            format_amount(max_fee)
+                        );
+                        return Ok(txids)
+                    }
+                }
+
+                self.simulate_tx(&tx).await?;
+                self.mark_tx_spend(&tx).await?;
+                let txid = self.broadcast_tx(&tx).await?;
+                println!(
+                    "Moved {batch_size} coins worth {} of token {token_id} in transaction {txid}",
+                    format_amount(value)
+                );
+                txids.push(txid);
+            }
+        }
+
+        Ok(txids)
+    }
+}
@@ -16,7 +16,7 @@
  * along with this program.  If not, see <https://www.gnu.org/licenses/>.
  */
 
-use std::{sync::Arc, time::Instant};
+use std::{slice, sync::Arc, time::Instant};
 
 use url::Url;
 
@@ -29,20 +29,32 @@ use darkfi::{
     },
     system::{Publisher, StoppableTask},
     tx::Transaction,
-    util::encoding::base64,
+    util::{cli::ProgressBar, encoding::base64},
     Error, Result,
 };
 use darkfi_sdk::{
-    crypto::{ContractId, DAO_CONTRACT_ID, DEPLOYOOOR_CONTRACT_ID, MONEY_CONTRACT_ID},
+    crypto::{
+        ContractId, AUCTION_CONTRACT_ID, DAO_CONTRACT_ID, DARKNAME_CONTRACT_ID,
+        DEPLOYOOOR_CONTRACT_ID, MONEY_CONTRACT_ID, ORACLE_CONTRACT_ID,
+    },
     tx::TransactionHash,
 };
 use darkfi_serial::{deserialize_async, serialize_async};
 
 use crate::{
+    dao_watch::DaoWatcher,
     error::{WalletDbError, WalletDbResult},
+    hooks::WalletEvent,
+    money::DecryptedNotes,
     Drk,
 };
 
+/// Maximum amount of blocks fetched ahead of the scanner
+const SCAN_PREFETCH_BLOCKS: usize = 64;
+
+/// Maximum amount of blocks trial decrypted in a single batch
+const SCAN_BATCH_SIZE: usize = 32;
+
 impl Drk {
     /// Subscribes to darkfid's JSON-RPC notification endpoint that serves
     /// new confirmed blocks. Upon receiving them, all the transactions are
@@ -51,11 +63,13 @@ impl Drk {
     /// the metadata to our wallet. If a reorg block is received, we revert
     /// to its previous height and then scan it. We assume that the blocks
     /// up to that point are unchanged, since darkfid will just broadcast
-    /// the sequence after the reorg.
+    /// the sequence after the reorg. If a [`DaoWatcher`] is provided, each
+    /// scanned block is also checked for DAO activity affecting the wallet.
     pub async fn subscribe_blocks(
         &self,
         endpoint: Url,
         ex: Arc<smol::Executor<'static>>,
+        dao_watcher: Option<&DaoWatcher>,
     ) -> Result<()> {
         // Grab last confirmed block height
         let (last_confirmed_height, _) = self.get_last_confirmed_block().await?;
@@ -191,6 +205,13 @@ impl Drk {
 
                         // Set new last scanned block height
                         last_scanned_height = block.header.height;
+
+                        // Notify about any DAO activity in the block
+                        if let Some(dao_watcher) = dao_watcher {
+                            if let Err(e) = dao_watcher.on_block(self, &block).await {
+                                eprintln!("[subscribe_blocks] DAO watcher failed: {e:?}");
+                            }
+                        }
                     }
                 }
 
@@ -211,10 +232,24 @@ impl Drk {
         Err(e)
     }
 
-    /// `scan_block` will go over over transactions in a block and handle their calls
-    /// based on the called contract. Additionally, will update `last_scanned_block` to
-    /// the provided block height and will store its height, hash and inverse query.
+    /// Trial decrypt the notes of a single block and then scan it.
     async fn scan_block(&self, block: &BlockInfo) -> Result<()> {
+        let decrypted = self.trial_decrypt_blocks(slice::from_ref(block)).await?;
+        self.scan_decrypted_block(block, &decrypted).await
+    }
+
+    /// `scan_decrypted_block` will go over over transactions in a block and handle their
+    /// calls based on the called contract, using the already trial decrypted Money notes.
+    /// Additionally, will update `last_scanned_block` to the provided block height and
+    /// will store its height, hash and inverse query.
+    async fn scan_decrypted_block(
+        &self,
+        block: &BlockInfo,
+        decrypted: &DecryptedNotes,
+    ) -> Result<()> {
+        // Drop any events left over by a previous block that failed
+        self.hooks.clear().await;
+
         // Reset wallet inverse cache state
         self.reset_inverse_cache().await?;
 
@@ -231,7 +266,7 @@ impl Drk {
             for (i, call) in tx.calls.iter().enumerate() {
                 if call.data.contract_id == *MONEY_CONTRACT_ID {
                     println!("[scan_block] Found Money contract in call {i}");
-                    if self.apply_tx_money_data(i, &tx.calls, &tx_hash).await? {
+                    if self.apply_tx_money_data(i, &tx.calls, &tx_hash, decrypted).await? {
                         wallet_tx = true;
                     };
                     continue
@@ -254,6 +289,28 @@ impl Drk {
                     continue
                 }
 
+                if call.data.contract_id == *DARKNAME_CONTRACT_ID {
+                    println!("[scan_block] Found Darkname contract in call {i}");
+                    if self.apply_tx_darkname_data(&call.data.data, block.header.height).await? {
+                        wallet_tx = true;
+                    };
+                    continue
+                }
+
+                if call.data.contract_id == *AUCTION_CONTRACT_ID {
+                    println!("[scan_block] Found Auction contract in call {i}");
+                    if self.apply_tx_auction_data(&call.data.data).await? {
+                        wallet_tx = true;
+                    };
+                    continue
+                }
+
+                if call.data.contract_id == *ORACLE_CONTRACT_ID {
+                    println!("[scan_block] Found Oracle contract in call {i}");
+                    // Feeds state is queried from darkfid, so there is nothing to keep
+                    continue
+                }
+
                 if call.data.contract_id == *DEPLOYOOOR_CONTRACT_ID {
                     println!("[scan_block] Found DeployoOor contract in call {i}");
                     // TODO: implement
@@ -271,15 +328,24 @@ impl Drk {
         }
 
         // Update wallet transactions records
-        if let Err(e) = self.put_tx_history_records(&wallet_txs, "Confirmed").await {
-            return Err(Error::DatabaseError(format!(
-                "[scan_block] Inserting transaction history records failed: {e:?}"
-            )))
+        let tx_hashes = match self.put_tx_history_records(&wallet_txs, "Confirmed").await {
+            Ok(hashes) => hashes,
+            Err(e) => {
+                return Err(Error::DatabaseError(format!(
+                    "[scan_block] Inserting transaction history records failed: {e:?}"
+                )))
+            }
+        };
+        for tx_hash in tx_hashes {
+            self.hooks.queue(WalletEvent::TxConfirmed { tx_hash }).await;
         }
 
         // Store this block rollback query
         self.store_inverse_cache(block.header.height, &block.hash().to_string())?;
 
+        // Notify hooks about the wallet events of this block
+        self.hooks.dispatch(block.header.height).await;
+
         Ok(())
     }
 
@@ -359,25 +425,57 @@ impl Drk {
                 return Ok(())
             }
 
-            while height <= last_height {
-                println!("Requesting block {height}...");
-                let block = match self.get_block_by_height(height).await {
-                    Ok(b) => b,
-                    Err(e) => {
-                        eprintln!("[scan_blocks] RPC client request failed: {e:?}");
-                        return Err(WalletDbError::GenericError)
-                    }
-                };
-                println!("Block {height} received! Scanning block...");
-                if let Err(e) = self.scan_block(&block).await {
-                    eprintln!("[scan_blocks] Scan block failed: {e:?}");
-                    return Err(WalletDbError::GenericError)
-                };
-                height += 1;
-            }
+            if let Err(e) = self.scan_block_range(height, last_height).await {
+                eprintln!("[scan_blocks] Scan blocks failed: {e:?}");
+                return Err(WalletDbError::GenericError)
+            };
+            height = last_height + 1;
         }
     }
 
+    /// Scan all blocks in the given height range, both ends included.
+    /// Blocks get fetched ahead from darkfid while previous ones are being
+    /// processed, and the Money notes of each available batch of blocks are
+    /// trial decrypted in parallel. The blocks themselves are still scanned
+    /// one by one, so coins get witnessed in the Merkle tree in order.
+    async fn scan_block_range(&self, start: u32, end: u32) -> Result<()> {
+        let progress = ProgressBar::new((end - start + 1) as u64, "blocks scanned");
+        let (block_tx, block_rx) = smol::channel::bounded(SCAN_PREFETCH_BLOCKS);
+
+        // Fetch blocks ahead, until the scanner stops receiving them
+        let fetcher = async move {
+            for height in start..=end {
+                let block = self.get_block_by_height(height).await?;
+                if block_tx.send(block).await.is_err() {
+                    break
+                }
+            }
+            Ok::<(), Error>(())
+        };
+
+        // Scan blocks in batches of whatever has been fetched so far
+        let scanner = async move {
+            while let Ok(block) = block_rx.recv().await {
+                let mut batch = vec![block];
+                while batch.len() < SCAN_BATCH_SIZE {
+                    let Ok(block) = block_rx.try_recv() else { break };
+                    batch.push(block);
+                }
+
+                let decrypted = self.trial_decrypt_blocks(&batch).await?;
+                for block in batch.iter() {
+                    self.scan_decrypted_block(block, &decrypted).await?;
+                    progress.inc(1);
+                }
+            }
+            Ok::<(), Error>(())
+        };
+
+        let (fetched, scanned) = smol::future::zip(fetcher, scanner).await;
+        scanned?;
+        fetched
+    }
+
     // Queries darkfid for last confirmed block.
     async fn get_last_confirmed_block(&self) -> Result<(u32, String)> {
         let rep = self
@@ -390,8 +488,8 @@ impl Drk {
         Ok((height, hash))
     }
 
-    // Queries darkfid for a block with given height.
-    async fn get_block_by_height(&self, height: u32) -> Result<BlockInfo> {
+    /// Queries darkfid for a block with given height.
+    pub async fn get_block_by_height(&self, height: u32) -> Result<BlockInfo> {
         let params = self
             .darkfid_daemon_request(
                 "blockchain.get_block",
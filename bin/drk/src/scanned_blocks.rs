@@ -140,6 +140,9 @@ impl Drk {
             return Ok(())
         }
 
+        // Hooks already fired for the blocks we are about to rescan
+        self.hooks.rescan_until(last).await;
+
         // Iterate the range (height, last] in reverse to grab the corresponding blocks
         for height in (height + 1..=last).rev() {
             let (height, hash, query) = self.get_scanned_block_record(height)?;
@@ -21,7 +21,6 @@ use rand::rngs::OsRng;
 
 use darkfi::{
     tx::{ContractCallLeaf, Transaction, TransactionBuilder},
-    util::parse::encode_base10,
     zk::{halo2::Field, proof::ProvingKey, vm::ZkCircuit, vm_heap::empty_witnesses, Proof},
     zkas::ZkBinary,
     Error, Result,
@@ -43,7 +42,7 @@ use darkfi_serial::{
     async_trait, deserialize_async, AsyncEncodable, SerialDecodable, SerialEncodable,
 };
 
-use super::{money::BALANCE_BASE10_DECIMALS, Drk};
+use super::{locale::format_amount, Drk};
 
 #[derive(Debug, Clone, SerialEncodable, SerialDecodable)]
 /// Half of the swap data, includes the coin that is supposed to be sent,
@@ -358,11 +357,7 @@ impl Drk {
             return insection_error
         };
 
-        println!(
-            "Output[{output_idx}] value: {} ({})",
-            note.value,
-            encode_base10(note.value, BALANCE_BASE10_DECIMALS)
-        );
+        println!("Output[{output_idx}] value: {} ({})", note.value, format_amount(note.value));
         println!("Output[{output_idx}] token ID: {}", note.token_id);
 
         let skey = skey.unwrap();
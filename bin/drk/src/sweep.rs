@@ -0,0 +1,278 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2025 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use darkfi::{system::sleep, tx::Transaction, zk::ProofPriority, Error, Result};
+use darkfi_money_contract::{
+    client::OwnCoin,
+    model::{Coin, TokenId},
+};
+use rand::{rngs::OsRng, seq::SliceRandom, Rng};
+
+use crate::{locale::format_amount, Drk};
+
+/// Minimum number of coins worth consolidating in a transaction
+const MIN_SWEEP_INPUTS: usize = 2;
+
+impl Drk {
+    /// Fetch the unspent coins of provided token eligible for a sweep,
+    /// optionally only those worth at most `max_value`.
+    /// Coins are returned in random order, so batches don't follow
+    /// the order in which we received them.
+    pub async fn sweep_candidates(
+        &self,
+        token_id: &TokenId,
+        max_value: Option<u64>,
+    ) -> Result<Vec<OwnCoin>> {
+        let mut owncoins = self.get_token_coins(token_id).await?;
+        if let Some(max_value) = max_value {
+            owncoins.retain(|c| c.note.value <= max_value);
+        }
+        owncoins.shuffle(&mut OsRng);
+        Ok(owncoins)
+    }
+
+    /// Drop the provided coins that got spent since they were fetched,
+    /// e.g. by the fee call of a previous batch, so they never get
+    /// picked up by another transaction.
+    pub async fn retain_unspent_coins(&self, coins: &mut Vec<OwnCoin>) -> Result<()> {
+        let unspent: Vec<Coin> =
+            self.get_coins(false).await?.into_iter().map(|(c, _, _)| c.coin).collect();
+        coins.retain(|c| unspent.contains(&c.coin));
+        Ok(())
+    }
+
+    /// Create a transaction consolidating all provided coins into
+    /// a single output to our default address. Its proofs are created
+    /// with background priority, yielding to interactive ones.
+    pub async fn sweep_tx(&self, token_id: TokenId, coins: Vec<OwnCoin>) -> Result<Transaction> {
+        let value = coins.iter().map(|c| c.note.value).sum();
+        let recipient = self.default_address().await?;
+        self.transfer_coins_with_priority(
+            ProofPriority::Background,
+            value,
+            token_id,
+            recipient,
+            None,
+            None,
+            None,
+            false,
+            coins,
+        )
+        .await
+    }
+
+    /// Consolidate the small coins of provided token into fewer outputs.
+    ///
+    /// Coins get swept in batches of a random size of up to `max_inputs`
+    /// coins each, waiting a random delay of up to `max_delay` seconds
+    /// between them, so the batches can't be trivially linked together.
+    /// Batches whose fee would exceed `max_fee` are not broadcasted, and
+    /// the sweep stops. After each batch the wallet rescans the chain, so
+    /// the fee change coin can be reused by the next batch, and drops the
+    /// candidates spent meanwhile, e.g. by a previous batch fee.
+    ///
+    /// Returns the IDs of the broadcasted transactions.
+    pub async fn sweep(
+        &self,
+        token_id: TokenId,
+        max_inputs: usize,
+        max_value: Option<u64>,
+        max_fee: Option<u64>,
+        max_delay: u64,
+    ) -> Result<Vec<String>> {
+        if max_inputs < MIN_SWEEP_INPUTS {
+            return Err(Error::Custom(format!(
+                "Max inputs per transaction must be at least {MIN_SWEEP_INPUTS}"
+            )))
+        }
+
+        let mut coins = self.sweep_candidates(&token_id, max_value).await?;
+        if coins.len() < MIN_SWEEP_INPUTS {
+            return Err(Error::Custom(format!(
+                "Not enough coins to sweep for token ID: {token_id}, found: {}",
+                coins.len()
+            )))
+        }
+
+        let mut txids = vec![];
+        while coins.len() >= MIN_SWEEP_INPUTS {
+            // Wait a random delay and pick up the previous batch changes
+            if !txids.is_empty() {
+                let delay = OsRng.gen_range(0..=max_delay);
+                println!("Waiting {delay}s before the next batch...");
+                sleep(delay).await;
+                if let Err(e) = self.scan_blocks().await {
+                    return Err(Error::DatabaseError(format!(
+                        "[sweep] Scanning blocks failed: {e:?}"
+                    )))
+                }
+
+                self.retain_unspent_coins(&mut coins).await?;
+                if coins.len() < MIN_SWEEP_INPUTS {
+                    break
+                }
+            }
+
+            // Avoid leaving a single coin behind for the last batch
+            let max_batch = max_inputs.min(coins.len());
+            let mut batch_size = OsRng.gen_range(MIN_SWEEP_INPUTS..=max_batch);
+            if coins.len() - batch_size == 1 && batch_size < max_batch {
+                batch_size += 1;
+            }
+            let batch: Vec<OwnCoin> = coins.drain(..batch_size).collect();
+            let value: u64 = batch.iter().map(|c| c.note.value).sum();
+
+            let tx = self.sweep_tx(token_id, batch).await?;
+
+            if let Some(max_fee) = max_fee {
+                let fee = self.get_tx_gas(&tx, true).await?;
+                if fee > max_fee {
+                    println!(
+                        "Batch fee {} exceeds the maximum of {}, stopping the sweep",
+                        format_amount(fee),
+                        format_amount(max_fee)
+                    );
+                    break
+                }
+            }
+
+            self.simulate_tx(&tx).await?;
+            self.mark_tx_spend(&tx).await?;
+            let txid = self.broadcast_tx(&tx).await?;
+            println!(
+                "Swept {batch_size} coins worth {} in transaction {txid}",
+                format_amount(value)
+            );
+            txids.push(txid);
+        }
+
+        Ok(txids)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use darkfi::zk::halo2::Field;
+    use darkfi_money_contract::{client::MoneyNote, model::DARK_TOKEN_ID};
+    use darkfi_sdk::{
+        crypto::{BaseBlind, FuncId, ScalarBlind, SecretKey},
+        pasta::pallas,
+    };
+    use darkfi_serial::serialize_async;
+    use rand::rngs::OsRng;
+
+    use crate::{
+        money::{
+            MONEY_COINS_COL_COIN, MONEY_COINS_COL_COIN_BLIND, MONEY_COINS_COL_IS_SPENT,
+            MONEY_COINS_COL_LEAF_POSITION, MONEY_COINS_COL_MEMO, MONEY_COINS_COL_SECRET,
+            MONEY_COINS_COL_SPEND_HOOK, MONEY_COINS_COL_TOKEN_BLIND, MONEY_COINS_COL_TOKEN_ID,
+            MONEY_COINS_COL_USER_DATA, MONEY_COINS_COL_VALUE, MONEY_COINS_COL_VALUE_BLIND,
+            MONEY_COINS_TABLE,
+        },
+        walletdb::WalletDb,
+        Drk,
+    };
+
+    use super::*;
+
+    /// Insert an unspent coin of `value` into the wallet
+    async fn put_coin(drk: &Drk, value: u64, leaf_position: u64) -> OwnCoin {
+        let owncoin = OwnCoin {
+            coin: Coin::from(pallas::Base::random(&mut OsRng)),
+            note: MoneyNote {
+                value,
+                token_id: *DARK_TOKEN_ID,
+                spend_hook: FuncId::none(),
+                user_data: pallas::Base::ZERO,
+                coin_blind: BaseBlind::random(&mut OsRng),
+                value_blind: ScalarBlind::random(&mut OsRng),
+                token_blind: BaseBlind::random(&mut OsRng),
+                memo: vec![],
+            },
+            secret: SecretKey::random(&mut OsRng),
+            leaf_position: leaf_position.into(),
+        };
+
+        let query = format!(
+            "INSERT INTO {} ({}, {}, {}, {}, {}, {}, {}, {}, {}, {}, {}, {}) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12);",
+            *MONEY_COINS_TABLE,
+            MONEY_COINS_COL_COIN,
+            MONEY_COINS_COL_IS_SPENT,
+            MONEY_COINS_COL_VALUE,
+            MONEY_COINS_COL_TOKEN_ID,
+            MONEY_COINS_COL_SPEND_HOOK,
+            MONEY_COINS_COL_USER_DATA,
+            MONEY_COINS_COL_COIN_BLIND,
+            MONEY_COINS_COL_VALUE_BLIND,
+            MONEY_COINS_COL_TOKEN_BLIND,
+            MONEY_COINS_COL_SECRET,
+            MONEY_COINS_COL_LEAF_POSITION,
+            MONEY_COINS_COL_MEMO,
+        );
+        let params = rusqlite::params![
+            serialize_async(&owncoin.coin).await,
+            0,
+            serialize_async(&owncoin.note.value).await,
+            serialize_async(&owncoin.note.token_id).await,
+            serialize_async(&owncoin.note.spend_hook).await,
+            serialize_async(&owncoin.note.user_data).await,
+            serialize_async(&owncoin.note.coin_blind).await,
+            serialize_async(&owncoin.note.value_blind).await,
+            serialize_async(&owncoin.note.token_blind).await,
+            serialize_async(&owncoin.secret).await,
+            serialize_async(&owncoin.leaf_position).await,
+            serialize_async(&owncoin.note.memo).await,
+        ];
+        drk.wallet.exec_sql(&query, params).unwrap();
+
+        owncoin
+    }
+
+    #[test]
+    fn retain_unspent_coins() {
+        smol::block_on(async {
+            let wallet = WalletDb::new(None, Some("foobar")).unwrap();
+            let drk = Drk::from_wallet(wallet, None, false, vec![]);
+            drk.initialize_wallet().await.unwrap();
+            drk.initialize_money().await.unwrap();
+
+            let mut coins = vec![];
+            for i in 0..4 {
+                coins.push(put_coin(&drk, 1_000 * (i + 1), i).await);
+            }
+
+            // Nothing got spent, so every candidate stays
+            let mut candidates = drk.sweep_candidates(&DARK_TOKEN_ID, None).await.unwrap();
+            assert_eq!(candidates.len(), 4);
+            drk.retain_unspent_coins(&mut candidates).await.unwrap();
+            assert_eq!(candidates.len(), 4);
+
+            // A previous batch fee spends one of the remaining candidates,
+            // which must not be picked up by the next batch.
+            drk.mark_spent_coin(&coins[2].coin, &"fee_tx".to_string()).await.unwrap();
+            drk.retain_unspent_coins(&mut candidates).await.unwrap();
+            assert_eq!(candidates.len(), 3);
+            assert!(!candidates.contains(&coins[2]));
+
+            // Candidates keep their order, so batches stay randomized
+            let order: Vec<Coin> = candidates.iter().map(|c| c.coin).collect();
+            drk.retain_unspent_coins(&mut candidates).await.unwrap();
+            assert_eq!(candidates.iter().map(|c| c.coin).collect::<Vec<_>>(), order);
+        })
+    }
+}
@@ -18,14 +18,16 @@
 
 use darkfi::{
     tx::{ContractCallLeaf, Transaction, TransactionBuilder},
-    util::parse::{decode_base10, encode_base10},
-    zk::{proof::ProvingKey, vm::ZkCircuit, vm_heap::empty_witnesses},
+    util::parse::decode_base10,
+    zk::{proof::ProvingKey, vm::ZkCircuit, vm_heap::empty_witnesses, ProofPriority},
     zkas::ZkBinary,
     Error, Result,
 };
 use darkfi_money_contract::{
-    client::transfer_v1::make_transfer_call, model::TokenId, MoneyFunction,
-    MONEY_CONTRACT_ZKAS_BURN_NS_V1, MONEY_CONTRACT_ZKAS_FEE_NS_V1, MONEY_CONTRACT_ZKAS_MINT_NS_V1,
+    client::{transfer_v1::make_transfer_call, OwnCoin},
+    model::TokenId,
+    MoneyFunction, MONEY_CONTRACT_ZKAS_BURN_NS_V1, MONEY_CONTRACT_ZKAS_FEE_NS_V1,
+    MONEY_CONTRACT_ZKAS_MINT_NS_V1,
 };
 use darkfi_sdk::{
     crypto::{contract_id::MONEY_CONTRACT_ID, FuncId, Keypair, PublicKey},
@@ -34,15 +36,19 @@ use darkfi_sdk::{
 };
 use darkfi_serial::AsyncEncodable;
 
-use crate::{money::BALANCE_BASE10_DECIMALS, Drk};
+use crate::{locale::format_amount, money::BALANCE_BASE10_DECIMALS, Drk};
 
 impl Drk {
     /// Create a payment transaction. Returns the transaction object on success.
+    /// If `recipient_view_key` is provided, the recipient notes are encrypted
+    /// for it instead of the recipient public key.
+    #[allow(clippy::too_many_arguments)]
     pub async fn transfer(
         &self,
         amount: &str,
         token_id: TokenId,
         recipient: PublicKey,
+        recipient_view_key: Option<PublicKey>,
         spend_hook: Option<FuncId>,
         user_data: Option<pallas::Base>,
         half_split: bool,
@@ -64,10 +70,66 @@ impl Drk {
         if balance < amount {
             return Err(Error::Custom(format!(
                 "Not enough balance for token ID: {token_id}, found: {}",
-                encode_base10(balance, BALANCE_BASE10_DECIMALS)
+                format_amount(balance)
             )))
         }
 
+        self.transfer_coins(
+            amount,
+            token_id,
+            recipient,
+            recipient_view_key,
+            spend_hook,
+            user_data,
+            half_split,
+            owncoins,
+        )
+        .await
+    }
+
+    /// Create a payment transaction of `amount`, spending coins out of
+    /// provided `owncoins`. Returns the transaction object on success.
+    #[allow(clippy::too_many_arguments)]
+    pub async fn transfer_coins(
+        &self,
+        amount: u64,
+        token_id: TokenId,
+        recipient: PublicKey,
+        recipient_view_key: Option<PublicKey>,
+        spend_hook: Option<FuncId>,
+        user_data: Option<pallas::Base>,
+        half_split: bool,
+        owncoins: Vec<OwnCoin>,
+    ) -> Result<Transaction> {
+        self.transfer_coins_with_priority(
+            ProofPriority::Interactive,
+            amount,
+            token_id,
+            recipient,
+            recipient_view_key,
+            spend_hook,
+            user_data,
+            half_split,
+            owncoins,
+        )
+        .await
+    }
+
+    /// Create a payment transaction like [`Drk::transfer_coins`], creating
+    /// its proofs with the given priority in our prover pool.
+    #[allow(clippy::too_many_arguments)]
+    pub async fn transfer_coins_with_priority(
+        &self,
+        priority: ProofPriority,
+        amount: u64,
+        token_id: TokenId,
+        recipient: PublicKey,
+        recipient_view_key: Option<PublicKey>,
+        spend_hook: Option<FuncId>,
+        user_data: Option<pallas::Base>,
+        half_split: bool,
+        owncoins: Vec<OwnCoin>,
+    ) -> Result<Transaction> {
         // Fetch our default secret
         let secret = self.default_secret().await?;
         let keypair = Keypair::new(secret);
@@ -108,22 +170,30 @@ impl Drk {
         let burn_pk = ProvingKey::build(burn_zkbin.k, &burn_circuit);
         let fee_pk = ProvingKey::build(fee_zkbin.k, &fee_circuit);
 
-        // Building transaction parameters
-        let (params, secrets, spent_coins) = make_transfer_call(
-            keypair,
-            recipient,
-            amount,
-            token_id,
-            owncoins,
-            tree.clone(),
-            spend_hook,
-            user_data,
-            mint_zkbin,
-            mint_pk,
-            burn_zkbin,
-            burn_pk,
-            half_split,
-        )?;
+        // Building transaction parameters, proving in the background
+        // so we don't hog every core while doing it.
+        let call_tree = tree.clone();
+        let (params, secrets, spent_coins) = self
+            .prover
+            .run(priority, move || {
+                make_transfer_call(
+                    keypair,
+                    recipient,
+                    recipient_view_key,
+                    amount,
+                    token_id,
+                    owncoins,
+                    call_tree,
+                    spend_hook,
+                    user_data,
+                    mint_zkbin,
+                    mint_pk,
+                    burn_zkbin,
+                    burn_pk,
+                    half_split,
+                )
+            })
+            .await??;
 
         // Encode the call
         let mut data = vec![MoneyFunction::TransferV1 as u8];
@@ -158,6 +228,9 @@ impl Drk {
         let sigs = tx.create_sigs(&fee_secrets)?;
         tx.signatures.push(sigs);
 
+        // Keep the recipient notes so we can prove the payment later
+        self.put_payment_notes(&tx.hash(), 0, &params, &secrets.output_notes, &recipient).await?;
+
         Ok(tx)
     }
 }
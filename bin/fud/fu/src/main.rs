@@ -58,6 +58,66 @@ enum Subcmd {
         #[clap(short, long)]
         /// File name
         file: String,
+
+        #[clap(short, long)]
+        /// Local copy of the file, or directory of local copies, to reuse chunks from
+        local: Option<String>,
+
+        #[clap(short, long)]
+        /// Destination path template, e.g. "~/Downloads/{name}-{hash8}"
+        dest: Option<String>,
+
+        #[clap(short, long)]
+        /// File name substituted for {name} in the destination
+        name: Option<String>,
+    },
+
+    /// Manage local resource names
+    Name {
+        #[clap(subcommand)]
+        command: NameSubcmd,
+    },
+
+    /// Manage download integrity reports
+    Report {
+        #[clap(subcommand)]
+        command: ReportSubcmd,
+    },
+}
+
+#[derive(Subcommand)]
+enum NameSubcmd {
+    /// Attach a local name to a resource, usable wherever a hash is
+    Set {
+        /// File hash, or a name already pointing to one
+        hash: String,
+
+        /// Name to attach
+        name: String,
+    },
+
+    /// Remove a local resource name
+    Remove {
+        /// Name to remove
+        name: String,
+    },
+
+    /// List local resource names
+    List,
+}
+
+#[derive(Subcommand)]
+enum ReportSubcmd {
+    /// Generate the integrity report of a complete file
+    Generate {
+        /// File hash or local name
+        hash: String,
+    },
+
+    /// Verify the stored integrity report of a file
+    Verify {
+        /// File hash or local name
+        hash: String,
     },
 }
 
@@ -120,11 +180,87 @@ impl Fu {
         Ok(())
     }
 
-    async fn get(&self, file: String) -> Result<()> {
-        let req = JsonRequest::new("get", json!([file]));
+    async fn get(
+        &self,
+        file: String,
+        local: Option<String>,
+        dest: Option<String>,
+        name: Option<String>,
+    ) -> Result<()> {
+        let mut params = vec![file, local.unwrap_or_default()];
+        if dest.is_some() || name.is_some() {
+            params.push(dest.unwrap_or_default());
+            params.extend(name);
+        }
+        let req = JsonRequest::new("get", json!(params));
         let rep = self.rpc_client.request(req).await?;
-        let path = rep.as_str().unwrap();
-        info!("File waits you at: {}", path);
+        match rep.as_str() {
+            Some(path) => info!("File waits you at: {}", path),
+            None => info!("File chunks wait you at: {}", rep),
+        }
+        Ok(())
+    }
+
+    async fn name(&self, command: NameSubcmd) -> Result<()> {
+        match command {
+            NameSubcmd::Set { hash, name } => {
+                let req = JsonRequest::new("name.set", json!([hash, name]));
+                self.rpc_client.request(req).await?;
+                info!("Name {} set", name);
+            }
+
+            NameSubcmd::Remove { name } => {
+                let req = JsonRequest::new("name.remove", json!([name]));
+                let rep = self.rpc_client.request(req).await?;
+                if rep.as_bool() == Some(true) {
+                    info!("Name {} removed", name);
+                } else {
+                    info!("Name {} not found", name);
+                }
+            }
+
+            NameSubcmd::List => {
+                let req = JsonRequest::new("names", json!([]));
+                let rep = self.rpc_client.request(req).await?;
+                let names = rep.as_object().unwrap();
+                if names.is_empty() {
+                    info!("No names set.");
+                }
+                for (name, hash) in names {
+                    info!("\t{}\t{}", name, hash.as_str().unwrap());
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn report(&self, command: ReportSubcmd) -> Result<()> {
+        match command {
+            ReportSubcmd::Generate { hash } => {
+                let req = JsonRequest::new("report.generate", json!([hash]));
+                let rep = self.rpc_client.request(req).await?;
+                info!("{}", rep);
+            }
+
+            ReportSubcmd::Verify { hash } => {
+                let req = JsonRequest::new("report.verify", json!([hash]));
+                let rep = self.rpc_client.request(req).await?;
+                match rep["publisher"].as_str() {
+                    Some(publisher) => info!("Report signed by {}", publisher),
+                    None => info!("Report is unsigned"),
+                }
+                if rep["valid"].as_bool() == Some(true) {
+                    info!("Report is valid");
+                } else {
+                    info!("Report is invalid:");
+                    for error in rep["errors"].as_array().unwrap() {
+                        info!("\t{}", error.as_str().unwrap());
+                    }
+                }
+            }
+        }
+
         Ok(())
     }
 }
@@ -143,7 +279,9 @@ async fn main() -> Result<()> {
     match args.command {
         Subcmd::List => fu.list().await,
         Subcmd::Sync => fu.sync().await,
-        Subcmd::Get { file } => fu.get(file).await,
+        Subcmd::Get { file, local, dest, name } => fu.get(file, local, dest, name).await,
+        Subcmd::Name { command } => fu.name(command).await,
+        Subcmd::Report { command } => fu.report(command).await,
     }?;
 
     fu.close_connection().await
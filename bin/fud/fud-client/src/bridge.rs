@@ -0,0 +1,108 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2025 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! The fud websocket bridge lets clients that can't join the P2P
+//! network, such as browsers, fetch resources through a fud daemon.
+//! Every binary websocket message carries exactly one serialized
+//! [`BridgeRequest`] or [`BridgeReply`].
+
+use async_trait::async_trait;
+use darkfi_serial::{deserialize, serialize, SerialDecodable, SerialEncodable};
+
+use crate::{
+    transport::{FudTransport, MaybeSendSync},
+    Error, Result,
+};
+
+/// Request sent from a bridge client to the daemon
+#[derive(Clone, Debug, PartialEq, Eq, SerialEncodable, SerialDecodable)]
+pub enum BridgeRequest {
+    /// Request the metadata of a file
+    File(blake3::Hash),
+    /// Request a chunk
+    Chunk(blake3::Hash),
+}
+
+/// Reply sent from the daemon to a bridge client. Every reply echoes
+/// the requested hash so clients can match it to their request.
+#[derive(Clone, Debug, PartialEq, Eq, SerialEncodable, SerialDecodable)]
+pub enum BridgeReply {
+    /// Metadata of the requested file
+    File(blake3::Hash, Vec<blake3::Hash>),
+    /// The requested chunk
+    Chunk(blake3::Hash, Vec<u8>),
+    /// The daemon could not find the requested file
+    FileNotFound(blake3::Hash),
+    /// The daemon could not find the requested chunk
+    ChunkNotFound(blake3::Hash),
+}
+
+/// A message-oriented websocket connection. Browser clients implement
+/// this on top of the platform `WebSocket`, using binary messages.
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+pub trait BridgeSocket: MaybeSendSync {
+    /// Send a binary message
+    async fn send(&self, msg: Vec<u8>) -> Result<()>;
+
+    /// Receive the next binary message
+    async fn recv(&self) -> Result<Vec<u8>>;
+}
+
+/// [`FudTransport`] talking to a fud daemon's websocket bridge.
+/// Requests are sent one at a time, so callers must not issue
+/// concurrent requests over the same socket.
+pub struct WsTransport<S: BridgeSocket> {
+    socket: S,
+}
+
+impl<S: BridgeSocket> WsTransport<S> {
+    pub fn new(socket: S) -> Self {
+        Self { socket }
+    }
+
+    async fn request(&self, request: &BridgeRequest) -> Result<BridgeReply> {
+        self.socket.send(serialize(request)).await?;
+        let reply = self.socket.recv().await?;
+        deserialize(&reply).map_err(|e| Error::InvalidMessage(e.to_string()))
+    }
+}
+
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+impl<S: BridgeSocket> FudTransport for WsTransport<S> {
+    async fn fetch_metadata(&self, file_hash: &blake3::Hash) -> Result<Vec<blake3::Hash>> {
+        match self.request(&BridgeRequest::File(*file_hash)).await? {
+            BridgeReply::File(hash, chunk_hashes) if &hash == file_hash => Ok(chunk_hashes),
+            BridgeReply::FileNotFound(hash) if &hash == file_hash => {
+                Err(Error::FileNotFound(*file_hash))
+            }
+            _ => Err(Error::InvalidMessage("Unexpected bridge reply".to_string())),
+        }
+    }
+
+    async fn fetch_chunk(&self, chunk_hash: &blake3::Hash) -> Result<Vec<u8>> {
+        match self.request(&BridgeRequest::Chunk(*chunk_hash)).await? {
+            BridgeReply::Chunk(hash, chunk) if &hash == chunk_hash => Ok(chunk),
+            BridgeReply::ChunkNotFound(hash) if &hash == chunk_hash => {
+                Err(Error::ChunkNotFound(*chunk_hash))
+            }
+            _ => Err(Error::InvalidMessage("Unexpected bridge reply".to_string())),
+        }
+    }
+}
@@ -0,0 +1,60 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2025 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use std::fmt;
+
+/// Errors returned by the fud client
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Error {
+    /// No peer could provide the metadata of the requested file
+    FileNotFound(blake3::Hash),
+    /// No peer could provide the requested chunk
+    ChunkNotFound(blake3::Hash),
+    /// A chunk does not hash to the expected value or is oversized
+    ChunkMismatch(blake3::Hash),
+    /// A chunk that is not part of the resource was received
+    UnknownChunk(blake3::Hash),
+    /// The assembled resource does not hash to its file hash
+    FileMismatch(blake3::Hash),
+    /// Attempted to assemble a resource with missing chunks
+    ResourceIncomplete(usize),
+    /// Malformed message received over the transport
+    InvalidMessage(String),
+    /// Transport-specific failure
+    Transport(String),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::FileNotFound(h) => write!(f, "File {} not found", h),
+            Self::ChunkNotFound(h) => write!(f, "Chunk {} not found", h),
+            Self::ChunkMismatch(h) => write!(f, "Chunk {} failed verification", h),
+            Self::UnknownChunk(h) => write!(f, "Chunk {} is not part of the resource", h),
+            Self::FileMismatch(h) => write!(f, "Resource does not hash to {}", h),
+            Self::ResourceIncomplete(n) => write!(f, "Resource is missing {} chunks", n),
+            Self::InvalidMessage(e) => write!(f, "Invalid message: {}", e),
+            Self::Transport(e) => write!(f, "Transport error: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+/// Result type used by the fud client
+pub type Result<T> = std::result::Result<T, Error>;
@@ -0,0 +1,149 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2025 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Hash functions deriving chunk and file IDs.
+//!
+//! Clients have to hash resources the same way the fud daemons serving
+//! them do, so every hash backend of `darkfi::geode::hasher` has its
+//! counterpart here. All of them produce 32-byte digests, carried around
+//! as [`blake3::Hash`] values regardless of the function producing them.
+
+use darkfi_serial::StreamHasher;
+use sha2::Digest;
+
+use crate::transport::MaybeSendSync;
+
+/// Hash function used to derive chunk and file IDs.
+/// It must match the hash backend of the fud daemons serving resources.
+pub trait ResourceHasher: Clone + MaybeSendSync {
+    /// Incremental hasher state, used to derive file IDs
+    type State: StreamHasher<Output = blake3::Hash>;
+
+    /// Create a new incremental hasher.
+    fn hasher(&self) -> Self::State;
+
+    /// Hash the provided data in one go.
+    fn hash(&self, data: &[u8]) -> blake3::Hash {
+        let mut hasher = self.hasher();
+        hasher.update(data);
+        hasher.finalize()
+    }
+}
+
+/// Plain BLAKE3, the default of fud
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Blake3;
+
+impl ResourceHasher for Blake3 {
+    type State = blake3::Hasher;
+
+    fn hasher(&self) -> Self::State {
+        blake3::Hasher::new()
+    }
+
+    fn hash(&self, data: &[u8]) -> blake3::Hash {
+        blake3::hash(data)
+    }
+}
+
+/// BLAKE3 in keyed mode, used by private swarms sharing a secret key
+#[derive(Clone)]
+pub struct KeyedBlake3 {
+    key: [u8; 32],
+}
+
+impl KeyedBlake3 {
+    pub fn new(key: [u8; 32]) -> Self {
+        Self { key }
+    }
+}
+
+impl ResourceHasher for KeyedBlake3 {
+    type State = blake3::Hasher;
+
+    fn hasher(&self) -> Self::State {
+        blake3::Hasher::new_keyed(&self.key)
+    }
+
+    fn hash(&self, data: &[u8]) -> blake3::Hash {
+        blake3::keyed_hash(&self.key, data)
+    }
+}
+
+/// SHA256, used for interop with other content-addressed systems
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Sha256;
+
+/// Incremental [`Sha256`] hasher state
+#[derive(Clone, Default)]
+pub struct Sha256State(sha2::Sha256);
+
+impl StreamHasher for Sha256State {
+    type Output = blake3::Hash;
+
+    fn update(&mut self, data: &[u8]) {
+        Digest::update(&mut self.0, data);
+    }
+
+    fn finalize(&self) -> Self::Output {
+        let digest: [u8; 32] = Digest::finalize(self.0.clone()).into();
+        blake3::Hash::from(digest)
+    }
+}
+
+impl ResourceHasher for Sha256 {
+    type State = Sha256State;
+
+    fn hasher(&self) -> Self::State {
+        Sha256State::default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hash_incrementally<H: ResourceHasher>(hasher: &H, data: &[u8]) -> blake3::Hash {
+        let mut state = hasher.hasher();
+        state.update(&data[..6]);
+        state.update(&data[6..]);
+        state.finalize()
+    }
+
+    #[test]
+    fn resource_hashers() {
+        let data = b"darkfi geode";
+        let key = [7u8; 32];
+
+        assert_eq!(Blake3.hash(data), blake3::hash(data));
+        assert_eq!(KeyedBlake3::new(key).hash(data), blake3::keyed_hash(&key, data));
+        assert_ne!(KeyedBlake3::new(key).hash(data), KeyedBlake3::new([8u8; 32]).hash(data));
+        assert_eq!(
+            Sha256.hash(b"abc").to_hex().as_str(),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+
+        // Incremental hashing matches one-shot hashing
+        assert_eq!(hash_incrementally(&Blake3, data), Blake3.hash(data));
+        assert_eq!(
+            hash_incrementally(&KeyedBlake3::new(key), data),
+            KeyedBlake3::new(key).hash(data)
+        );
+        assert_eq!(hash_incrementally(&Sha256, data), Sha256.hash(data));
+    }
+}
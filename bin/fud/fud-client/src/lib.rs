@@ -0,0 +1,50 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2025 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Client-side logic of fud that does not depend on the P2P stack or
+//! the filesystem: chunking and hashing of resources, verification of
+//! file metadata and chunks, and assembly of downloaded resources.
+//!
+//! The network is abstracted behind the [`FudTransport`] trait. The fud
+//! daemon implements it natively over its P2P network, while browser
+//! clients can use [`bridge::WsTransport`] to talk to a fud daemon's
+//! websocket bridge. The crate has no native-only dependencies so it
+//! can be compiled for `wasm32-unknown-unknown`.
+
+/// Client error types
+pub mod error;
+pub use error::{Error, Result};
+
+/// Hash functions deriving chunk and file IDs
+pub mod hasher;
+pub use hasher::ResourceHasher;
+
+/// Chunking, verification and assembly of resources
+pub mod resource;
+pub use resource::{hash_resource, verify_chunk, Resource};
+
+/// Networking abstraction and the generic download logic
+pub mod transport;
+pub use transport::{FudClient, FudTransport};
+
+/// Wire format and transport for the fud websocket bridge
+pub mod bridge;
+
+/// Maximum size of a single chunk of a resource.
+/// Must stay in sync with `darkfi::geode::MAX_CHUNK_SIZE`.
+pub const MAX_CHUNK_SIZE: usize = 262_144;
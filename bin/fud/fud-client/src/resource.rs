@@ -0,0 +1,187 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2025 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use darkfi_serial::StreamHasher;
+
+use crate::{hasher::ResourceHasher, Error, Result, MAX_CHUNK_SIZE};
+
+/// Split `data` into chunks and hash them the same way Geode does on
+/// insertion. Returns a tuple of `(blake3::Hash, Vec<blake3::Hash>)`
+/// which represents the file hash and the file's chunk hashes.
+pub fn hash_resource<H: ResourceHasher>(
+    hasher: &H,
+    data: &[u8],
+) -> (blake3::Hash, Vec<blake3::Hash>) {
+    let mut file_hasher = hasher.hasher();
+    let mut chunk_hashes = vec![];
+
+    for chunk in data.chunks(MAX_CHUNK_SIZE) {
+        file_hasher.update(chunk);
+        chunk_hashes.push(hasher.hash(chunk));
+    }
+
+    (file_hasher.finalize(), chunk_hashes)
+}
+
+/// Verify that `chunk` is a valid chunk with the given hash.
+pub fn verify_chunk<H: ResourceHasher>(
+    hasher: &H,
+    chunk_hash: &blake3::Hash,
+    chunk: &[u8],
+) -> bool {
+    chunk.len() <= MAX_CHUNK_SIZE && &hasher.hash(chunk) == chunk_hash
+}
+
+/// A resource being downloaded, created from its file hash and the
+/// metadata (ordered chunk hashes) returned by the network.
+///
+/// Chunks are verified as they are inserted. The metadata itself can
+/// only be verified once all chunks are present, since the file hash
+/// commits to the whole content, so [`Resource::assemble`] performs
+/// the final check.
+#[derive(Clone, Debug)]
+pub struct Resource<H: ResourceHasher> {
+    hasher: H,
+    file_hash: blake3::Hash,
+    chunk_hashes: Vec<blake3::Hash>,
+    chunks: Vec<Option<Vec<u8>>>,
+}
+
+impl<H: ResourceHasher> Resource<H> {
+    pub fn new(hasher: H, file_hash: blake3::Hash, chunk_hashes: Vec<blake3::Hash>) -> Self {
+        let chunks = vec![None; chunk_hashes.len()];
+        Self { hasher, file_hash, chunk_hashes, chunks }
+    }
+
+    /// The hash of the resource
+    pub fn file_hash(&self) -> &blake3::Hash {
+        &self.file_hash
+    }
+
+    /// The ordered chunk hashes of the resource
+    pub fn chunk_hashes(&self) -> &[blake3::Hash] {
+        &self.chunk_hashes
+    }
+
+    /// Returns the hashes of the chunks we don't have yet, without duplicates.
+    pub fn missing_chunks(&self) -> Vec<blake3::Hash> {
+        let mut missing: Vec<blake3::Hash> = vec![];
+        for (hash, chunk) in self.chunk_hashes.iter().zip(self.chunks.iter()) {
+            if chunk.is_none() && !missing.contains(hash) {
+                missing.push(*hash);
+            }
+        }
+        missing
+    }
+
+    /// Returns `true` if all chunks of the resource are present.
+    pub fn is_complete(&self) -> bool {
+        self.chunks.iter().all(|c| c.is_some())
+    }
+
+    /// Verify and insert a chunk into the resource. A chunk may appear
+    /// several times in a resource, in which case all of its positions
+    /// are filled. Returns the hash of the inserted chunk.
+    pub fn insert_chunk(&mut self, chunk: &[u8]) -> Result<blake3::Hash> {
+        let chunk_hash = self.hasher.hash(chunk);
+        if chunk.len() > MAX_CHUNK_SIZE {
+            return Err(Error::ChunkMismatch(chunk_hash))
+        }
+
+        let mut found = false;
+        for (hash, slot) in self.chunk_hashes.iter().zip(self.chunks.iter_mut()) {
+            if hash == &chunk_hash {
+                *slot = Some(chunk.to_vec());
+                found = true;
+            }
+        }
+
+        if !found {
+            return Err(Error::UnknownChunk(chunk_hash))
+        }
+
+        Ok(chunk_hash)
+    }
+
+    /// Assemble the resource content, verifying it against the file hash.
+    pub fn assemble(self) -> Result<Vec<u8>> {
+        let missing = self.chunks.iter().filter(|c| c.is_none()).count();
+        if missing > 0 {
+            return Err(Error::ResourceIncomplete(missing))
+        }
+
+        let mut file_hasher = self.hasher.hasher();
+        let mut content = vec![];
+        for chunk in self.chunks.into_iter().flatten() {
+            file_hasher.update(&chunk);
+            content.extend_from_slice(&chunk);
+        }
+
+        if file_hasher.finalize() != self.file_hash {
+            return Err(Error::FileMismatch(self.file_hash))
+        }
+
+        Ok(content)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hasher::{Blake3, KeyedBlake3, Sha256};
+
+    #[test]
+    fn resource_roundtrip() {
+        resource_roundtrip_with(Blake3);
+        resource_roundtrip_with(KeyedBlake3::new([7u8; 32]));
+        resource_roundtrip_with(Sha256);
+
+        // Resources hashed with another function are rejected
+        let data = vec![42u8; 1337];
+        let (file_hash, chunk_hashes) = hash_resource(&Sha256, &data);
+        let mut resource = Resource::new(Blake3, file_hash, chunk_hashes);
+        assert_eq!(resource.insert_chunk(&data), Err(Error::UnknownChunk(blake3::hash(&data))));
+        assert!(!verify_chunk(&Blake3, &Sha256.hash(&data), &data));
+    }
+
+    fn resource_roundtrip_with<H: ResourceHasher>(hasher: H) {
+        let data: Vec<u8> = (0..MAX_CHUNK_SIZE * 2 + 1337).map(|i| (i % 251) as u8).collect();
+        let (file_hash, chunk_hashes) = hash_resource(&hasher, &data);
+        assert_eq!(chunk_hashes.len(), 3);
+
+        let mut resource = Resource::new(hasher.clone(), file_hash, chunk_hashes.clone());
+        assert_eq!(resource.missing_chunks(), chunk_hashes);
+        assert_eq!(resource.clone().assemble(), Err(Error::ResourceIncomplete(3)));
+        assert!(resource.insert_chunk(b"not a chunk").is_err());
+
+        for chunk in data.chunks(MAX_CHUNK_SIZE).rev() {
+            assert!(verify_chunk(&hasher, &hasher.hash(chunk), chunk));
+            resource.insert_chunk(chunk).unwrap();
+        }
+
+        assert!(resource.is_complete());
+        assert_eq!(resource.assemble().unwrap(), data);
+
+        // Metadata that does not match the file hash is rejected on assembly
+        let mut resource = Resource::new(hasher, blake3::hash(b"foo"), chunk_hashes);
+        for chunk in data.chunks(MAX_CHUNK_SIZE) {
+            resource.insert_chunk(chunk).unwrap();
+        }
+        assert!(resource.assemble().is_err());
+    }
+}
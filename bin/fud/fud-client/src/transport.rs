@@ -0,0 +1,100 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2025 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use async_trait::async_trait;
+
+use crate::{
+    hasher::ResourceHasher,
+    resource::{verify_chunk, Resource},
+    Error, Result,
+};
+
+/// `Send + Sync` on native targets, where transports are driven from
+/// multi-threaded executors, and no bound at all on `wasm32`, where
+/// browser futures are not `Send`.
+#[cfg(not(target_arch = "wasm32"))]
+pub trait MaybeSendSync: Send + Sync {}
+#[cfg(not(target_arch = "wasm32"))]
+impl<T: Send + Sync> MaybeSendSync for T {}
+
+#[cfg(target_arch = "wasm32")]
+pub trait MaybeSendSync {}
+#[cfg(target_arch = "wasm32")]
+impl<T> MaybeSendSync for T {}
+
+/// Networking abstraction used by [`FudClient`] to retrieve file
+/// metadata and chunks. The fud daemon implements this over its P2P
+/// network, and browser clients over the fud websocket bridge.
+///
+/// Implementations don't have to verify what they return, the client
+/// verifies chunks and metadata itself.
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+pub trait FudTransport: MaybeSendSync {
+    /// Fetch the metadata (ordered chunk hashes) of the given file.
+    async fn fetch_metadata(&self, file_hash: &blake3::Hash) -> Result<Vec<blake3::Hash>>;
+
+    /// Fetch the chunk with the given hash.
+    async fn fetch_chunk(&self, chunk_hash: &blake3::Hash) -> Result<Vec<u8>>;
+}
+
+/// Transport-agnostic fud client, downloading resources over the given
+/// [`FudTransport`] and verifying them with the given [`ResourceHasher`].
+pub struct FudClient<T: FudTransport, H: ResourceHasher> {
+    transport: T,
+    hasher: H,
+}
+
+impl<T: FudTransport, H: ResourceHasher> FudClient<T, H> {
+    pub fn new(transport: T, hasher: H) -> Self {
+        Self { transport, hasher }
+    }
+
+    /// Reference to the underlying transport
+    pub fn transport(&self) -> &T {
+        &self.transport
+    }
+
+    /// Fetch the metadata of a file and create a [`Resource`] to
+    /// download its chunks into.
+    pub async fn fetch_resource(&self, file_hash: &blake3::Hash) -> Result<Resource<H>> {
+        let chunk_hashes = self.transport.fetch_metadata(file_hash).await?;
+        Ok(Resource::new(self.hasher.clone(), *file_hash, chunk_hashes))
+    }
+
+    /// Fetch and verify the missing chunks of `resource`. Chunks fetched
+    /// before an error occurs are kept, so the call can be retried.
+    pub async fn fetch_missing_chunks(&self, resource: &mut Resource<H>) -> Result<()> {
+        for chunk_hash in resource.missing_chunks() {
+            let chunk = self.transport.fetch_chunk(&chunk_hash).await?;
+            if !verify_chunk(&self.hasher, &chunk_hash, &chunk) {
+                return Err(Error::ChunkMismatch(chunk_hash))
+            }
+            resource.insert_chunk(&chunk)?;
+        }
+
+        Ok(())
+    }
+
+    /// Download a whole file and return its verified content.
+    pub async fn download(&self, file_hash: &blake3::Hash) -> Result<Vec<u8>> {
+        let mut resource = self.fetch_resource(file_hash).await?;
+        self.fetch_missing_chunks(&mut resource).await?;
+        resource.assemble()
+    }
+}
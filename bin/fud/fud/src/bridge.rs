@@ -0,0 +1,144 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2025 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Websocket bridge serving [`fud_client::bridge`] requests, so clients
+//! that can't join the P2P network (e.g. browsers running the client
+//! library compiled to wasm32) can fetch resources through this daemon.
+//! Requests are served from Geode when possible, and otherwise proxied
+//! to the P2P network.
+
+use std::sync::Arc;
+
+use async_tungstenite::tungstenite::{self, Message};
+use futures::{SinkExt, StreamExt};
+use log::{debug, error, info};
+use smol::{
+    net::{TcpListener, TcpStream},
+    Executor,
+};
+use url::Url;
+
+use darkfi::{Error, Result};
+use darkfi_serial::{deserialize, serialize};
+use fud_client::{
+    bridge::{BridgeReply, BridgeRequest},
+    FudTransport,
+};
+
+use super::{transport::P2pTransport, Fud};
+
+/// Accept websocket bridge connections on `listen` and spawn a handler
+/// for each of them.
+pub async fn bridge_task(fud: Arc<Fud>, listen: Url, executor: Arc<Executor<'_>>) -> Result<()> {
+    let (Some(host), Some(port)) = (listen.host_str(), listen.port()) else {
+        return Err(Error::ParseFailed("Invalid websocket bridge listen URL"))
+    };
+
+    let listener = TcpListener::bind((host, port)).await?;
+    info!(target: "fud::bridge", "Websocket bridge listening on {}", listen);
+
+    loop {
+        let (stream, peer_addr) = match listener.accept().await {
+            Ok(v) => v,
+            Err(e) => {
+                error!(target: "fud::bridge", "Failed accepting bridge connection: {}", e);
+                continue
+            }
+        };
+
+        debug!(target: "fud::bridge", "Accepted bridge connection from {}", peer_addr);
+        let fud_ = fud.clone();
+        let executor_ = executor.clone();
+        executor
+            .spawn(async move {
+                if let Err(e) = handle_connection(&fud_, &executor_, stream).await {
+                    debug!(target: "fud::bridge", "Bridge connection {} closed: {}", peer_addr, e);
+                }
+            })
+            .detach();
+    }
+}
+
+/// Perform the websocket handshake and serve requests until the client
+/// disconnects or sends a malformed request.
+async fn handle_connection(
+    fud: &Fud,
+    executor: &Arc<Executor<'_>>,
+    stream: TcpStream,
+) -> tungstenite::Result<()> {
+    let mut ws = async_tungstenite::accept_async(stream).await?;
+
+    while let Some(msg) = ws.next().await {
+        let data = match msg? {
+            Message::Binary(data) => data,
+            Message::Close(_) => break,
+            _ => continue,
+        };
+
+        let request: BridgeRequest = match deserialize(&data) {
+            Ok(v) => v,
+            Err(e) => {
+                debug!(target: "fud::bridge", "Malformed bridge request: {}", e);
+                break
+            }
+        };
+
+        let reply = handle_request(fud, executor, request).await;
+        ws.send(Message::Binary(serialize(&reply))).await?;
+    }
+
+    Ok(())
+}
+
+/// Serve a single bridge request. Requests that have to go to the
+/// P2P network count against the daemon's concurrent fetches limit.
+async fn handle_request(
+    fud: &Fud,
+    executor: &Arc<Executor<'_>>,
+    request: BridgeRequest,
+) -> BridgeReply {
+    let transport = P2pTransport::new(fud, executor);
+
+    match request {
+        BridgeRequest::File(file_hash) => {
+            if let Ok(chunked_file) = fud.geode.get(&file_hash).await {
+                let chunk_hashes = chunked_file.iter().map(|(chunk, _)| *chunk).collect();
+                return BridgeReply::File(file_hash, chunk_hashes)
+            }
+
+            match fud.fetch_semaphore.run(transport.fetch_metadata(&file_hash)).await {
+                Ok(chunk_hashes) => BridgeReply::File(file_hash, chunk_hashes),
+                Err(_) => BridgeReply::FileNotFound(file_hash),
+            }
+        }
+
+        BridgeRequest::Chunk(chunk_hash) => {
+            // Geode checks the chunk consistency for us
+            if let Ok(chunk_path) = fud.geode.get_chunk(&chunk_hash).await {
+                if let Ok(chunk) = smol::fs::read(&chunk_path).await {
+                    return BridgeReply::Chunk(chunk_hash, chunk)
+                }
+            }
+
+            match fud.fetch_semaphore.run(transport.fetch_chunk(&chunk_hash)).await {
+                Ok(chunk) => BridgeReply::Chunk(chunk_hash, chunk),
+                Err(_) => BridgeReply::ChunkNotFound(chunk_hash),
+            }
+        }
+    }
+}
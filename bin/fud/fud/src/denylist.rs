@@ -0,0 +1,312 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2025 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Moderator-signed denylists.
+//!
+//! A moderator publishes a list of resource hashes (files or chunks)
+//! signed with its key, and nodes may opt into the lists of moderators
+//! they choose. Lists are gossiped between subscribed nodes, a newer
+//! version of a moderator's list replacing the older one. fud refuses
+//! to fetch, seed, or route any hash found in a subscribed list, and
+//! reports which list matched.
+
+use std::{
+    collections::{HashMap, HashSet},
+    fmt,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+use async_trait::async_trait;
+use darkfi::{Error, Result};
+use darkfi_sdk::crypto::{
+    schnorr::{SchnorrPublic, SchnorrSecret, Signature},
+    PublicKey, SecretKey,
+};
+use darkfi_serial::{deserialize, serialize, SerialDecodable, SerialEncodable};
+use log::{info, warn};
+use smol::{fs, lock::RwLock};
+
+/// Directory inside the base directory holding the subscribed denylists
+const DENYLISTS_PATH: &str = "denylists";
+
+/// A list of denied resource hashes, signed by a moderator
+#[derive(Debug, Clone, SerialEncodable, SerialDecodable)]
+pub struct Denylist {
+    pub moderator: PublicKey,
+    /// Human-readable name of the list
+    pub name: String,
+    /// Version of the list, increasing with each publication
+    pub version: u64,
+    pub hashes: Vec<blake3::Hash>,
+    pub signature: Signature,
+}
+
+impl Denylist {
+    /// Sign a denylist with the moderator secret key
+    pub fn sign(secret: &SecretKey, name: &str, version: u64, hashes: Vec<blake3::Hash>) -> Self {
+        let message = denylist_message(name, version, &hashes);
+        let signature = secret.sign(message.as_bytes());
+        Self {
+            moderator: PublicKey::from_secret(*secret),
+            name: name.to_string(),
+            version,
+            hashes,
+            signature,
+        }
+    }
+
+    /// Verify the moderator signature over the list
+    pub fn verify(&self) -> bool {
+        let message = denylist_message(&self.name, self.version, &self.hashes);
+        self.moderator.verify(message.as_bytes(), &self.signature)
+    }
+}
+
+/// Hash of the denylist contents that gets signed by moderators
+fn denylist_message(name: &str, version: u64, hashes: &[blake3::Hash]) -> blake3::Hash {
+    let mut hasher = blake3::Hasher::new();
+    hasher.update(b"fud:denylist");
+    hasher.update(&serialize(&name.to_string()));
+    hasher.update(&version.to_le_bytes());
+    for hash in hashes {
+        hasher.update(hash.as_bytes());
+    }
+    hasher.finalize()
+}
+
+/// The subscribed denylist a hash was found in
+#[derive(Debug, Clone)]
+pub struct DenylistMatch {
+    pub moderator: PublicKey,
+    pub name: String,
+}
+
+impl fmt::Display for DenylistMatch {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "\"{}\" by {}", self.name, self.moderator)
+    }
+}
+
+/// Denylist subscriptions and storage
+pub struct Denylists {
+    /// Our own moderator key, used to publish our list
+    secret: Option<SecretKey>,
+    /// Moderators whose lists we enforce
+    subscribed: HashSet<PublicKey>,
+    /// Latest list of each subscribed moderator, with its hashes indexed
+    lists: RwLock<HashMap<PublicKey, (Denylist, HashSet<blake3::Hash>)>>,
+    /// Path to the denylist storage directory
+    path: PathBuf,
+}
+
+impl Denylists {
+    /// Instantiate the denylist subscriptions, creating the storage
+    /// directory inside `basedir` if it doesn't exist and loading the
+    /// stored lists of the subscribed moderators. A configured moderator
+    /// key implicitly subscribes to our own list.
+    pub async fn new(
+        basedir: &Path,
+        secret: Option<&str>,
+        subscriptions: &[String],
+    ) -> Result<Self> {
+        let secret = match secret {
+            Some(s) => match SecretKey::from_str(s) {
+                Ok(v) => Some(v),
+                Err(_) => return Err(Error::ParseFailed("Invalid moderator secret key")),
+            },
+            None => None,
+        };
+
+        let mut subscribed = HashSet::new();
+        for key in subscriptions {
+            let Ok(public_key) = PublicKey::from_str(key) else {
+                return Err(Error::ParseFailed("Invalid denylist moderator key"))
+            };
+            subscribed.insert(public_key);
+        }
+        if let Some(secret) = secret {
+            subscribed.insert(PublicKey::from_secret(secret));
+        }
+
+        let path = basedir.join(DENYLISTS_PATH);
+        fs::create_dir_all(&path).await?;
+
+        let mut lists = HashMap::new();
+        for moderator in &subscribed {
+            let Ok(data) = fs::read(path.join(moderator.to_string())).await else { continue };
+            match deserialize::<Denylist>(&data) {
+                Ok(list) if list.moderator == *moderator && list.verify() => {
+                    let hashes = list.hashes.iter().copied().collect();
+                    lists.insert(*moderator, (list, hashes));
+                }
+                _ => {
+                    warn!(target: "fud::denylist", "Ignoring invalid stored denylist of {}", moderator)
+                }
+            }
+        }
+
+        Ok(Self { secret, subscribed, lists: RwLock::new(lists), path })
+    }
+
+    /// Moderators whose lists we subscribed to
+    pub fn subscriptions(&self) -> Vec<PublicKey> {
+        self.subscribed.iter().copied().collect()
+    }
+
+    /// Check whether a hash is denied by any subscribed list,
+    /// returning the list it was found in
+    pub async fn check(&self, hash: &blake3::Hash) -> Option<DenylistMatch> {
+        let lists = self.lists.read().await;
+        lists
+            .values()
+            .find(|(_, hashes)| hashes.contains(hash))
+            .map(|(list, _)| DenylistMatch { moderator: list.moderator, name: list.name.clone() })
+    }
+
+    /// Fetch the stored lists of the given moderators
+    pub async fn get(&self, moderators: &[PublicKey]) -> Vec<Denylist> {
+        let lists = self.lists.read().await;
+        moderators.iter().filter_map(|m| lists.get(m).map(|(list, _)| list.clone())).collect()
+    }
+
+    /// Fetch all stored lists
+    pub async fn lists(&self) -> Vec<Denylist> {
+        self.lists.read().await.values().map(|(list, _)| list.clone()).collect()
+    }
+
+    /// Store a list received from the network. Only validly signed lists
+    /// of subscribed moderators, newer than the one we hold, are kept.
+    /// Returns true if the list was stored, so it should be relayed on.
+    pub async fn insert(&self, list: &Denylist) -> Result<bool> {
+        if !self.subscribed.contains(&list.moderator) {
+            return Ok(false)
+        }
+
+        if !list.verify() {
+            warn!(target: "fud::denylist", "Invalid denylist signature from {}", list.moderator);
+            return Ok(false)
+        }
+
+        let mut lists = self.lists.write().await;
+        if let Some((current, _)) = lists.get(&list.moderator) {
+            if current.version >= list.version {
+                return Ok(false)
+            }
+        }
+
+        fs::write(self.path.join(list.moderator.to_string()), serialize(list)).await?;
+
+        info!(
+            target: "fud::denylist",
+            "Updated denylist \"{}\" of {} to version {} ({} hashes)",
+            list.name, list.moderator, list.version, list.hashes.len(),
+        );
+        let hashes = list.hashes.iter().copied().collect();
+        lists.insert(list.moderator, (list.clone(), hashes));
+
+        Ok(true)
+    }
+
+    /// Publish a new version of our own list with the given hashes.
+    /// Returns `None` if no moderator key is configured.
+    pub async fn publish(&self, name: &str, hashes: Vec<blake3::Hash>) -> Result<Option<Denylist>> {
+        let Some(secret) = &self.secret else { return Ok(None) };
+
+        let moderator = PublicKey::from_secret(*secret);
+        let version = match self.lists.read().await.get(&moderator) {
+            Some((current, _)) => current.version + 1,
+            None => 1,
+        };
+
+        let list = Denylist::sign(secret, name, version, hashes);
+        self.insert(&list).await?;
+        Ok(Some(list))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::{rngs::OsRng, Rng};
+
+    use super::*;
+
+    #[test]
+    fn denylist_sign_verify() {
+        let secret = SecretKey::random(&mut OsRng);
+        let hashes = vec![blake3::hash(b"foo"), blake3::hash(b"bar")];
+        let list = Denylist::sign(&secret, "spam", 1, hashes);
+        assert_eq!(list.moderator, PublicKey::from_secret(secret));
+        assert!(list.verify());
+
+        // Any tampering with the signed contents is detected
+        let mut tampered = list.clone();
+        tampered.hashes.pop();
+        assert!(!tampered.verify());
+        let mut tampered = list.clone();
+        tampered.version = 2;
+        assert!(!tampered.verify());
+        let mut tampered = list.clone();
+        tampered.name = "ham".to_string();
+        assert!(!tampered.verify());
+        let mut tampered = list;
+        tampered.moderator = PublicKey::from_secret(SecretKey::random(&mut OsRng));
+        assert!(!tampered.verify());
+    }
+
+    #[test]
+    fn denylists_insert() {
+        smol::block_on(async {
+            let basedir = std::env::temp_dir().join(format!("fud_denylist_{}", OsRng.gen::<u64>()));
+            let moderator = SecretKey::random(&mut OsRng);
+            let stranger = SecretKey::random(&mut OsRng);
+            let subscriptions = vec![PublicKey::from_secret(moderator).to_string()];
+            let denylists = Denylists::new(&basedir, None, &subscriptions).await.unwrap();
+
+            let denied = blake3::hash(b"denied");
+            let v1 = Denylist::sign(&moderator, "spam", 1, vec![denied]);
+            assert!(denylists.insert(&v1).await.unwrap());
+            assert!(denylists.check(&denied).await.is_some());
+
+            // Same or older versions are not stored again
+            assert!(!denylists.insert(&v1).await.unwrap());
+
+            // Lists of moderators we're not subscribed to are ignored
+            let other = blake3::hash(b"other");
+            let foreign = Denylist::sign(&stranger, "spam", 5, vec![other]);
+            assert!(!denylists.insert(&foreign).await.unwrap());
+            assert!(denylists.check(&other).await.is_none());
+
+            // Invalidly signed lists are ignored
+            let mut forged = Denylist::sign(&moderator, "spam", 2, vec![other]);
+            forged.hashes.push(blake3::hash(b"forged"));
+            assert!(!denylists.insert(&forged).await.unwrap());
+
+            // Newer versions replace the previous one, and are persisted
+            let v2 = Denylist::sign(&moderator, "spam", 2, vec![other]);
+            assert!(denylists.insert(&v2).await.unwrap());
+            assert!(denylists.check(&denied).await.is_none());
+            assert!(denylists.check(&other).await.is_some());
+
+            let reloaded = Denylists::new(&basedir, None, &subscriptions).await.unwrap();
+            assert_eq!(reloaded.lists().await[0].version, 2);
+
+            let _ = std::fs::remove_dir_all(basedir);
+        });
+    }
+}
@@ -0,0 +1,50 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2025 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use darkfi::{rpc::error_code::FUD, rpc_error_codes};
+
+rpc_error_codes! {
+    /// Custom RPC errors available for fud.
+    /// Please sort them sensefully.
+    pub enum RpcError in FUD {
+        // Resource-related errors
+        FileNotFound = 10 => "File not found on the network",
+        MissingChunks = 11 => "Failed fetching some of the file's chunks",
+        Denylisted = 12 => "Resource is denylisted",
+        FetchFailed = 13 => "Failed fetching resource",
+
+        // Geode errors
+        GeodeNeedsGc = 20 => "Geode needs garbage collection",
+        GeodeInsertFailed = -32603 => "Failed inserting file to Geode",
+        AssembleFailed = 22 => "Failed assembling file at its destination",
+
+        // Seedbox errors
+        SeedboxMode = 30 => "Downloads are disabled in seedbox mode",
+        Unauthorized = 31 => "Invalid seedbox access token",
+
+        // Denylist errors
+        NotModerator = 40 => "No moderator key configured",
+
+        // Name errors
+        NameStoreFailed = 50 => "Failed storing resource names",
+
+        // Report errors
+        ReportNotFound = 60 => "No download report found for the file",
+        ReportFailed = 61 => "Failed generating or reading download report",
+    }
+}
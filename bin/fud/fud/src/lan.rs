@@ -0,0 +1,328 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2025 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Local network peer discovery.
+//!
+//! When enabled, fud periodically multicasts SSDP `NOTIFY` announcements
+//! on the LAN, carrying a random per-session node ID and the transports
+//! and ports of its inbound P2P addresses, and listens for the ones of
+//! other fud nodes. Discovered peers are addressed by the source address
+//! of their announcements, and are tried first when fetching, so nodes on
+//! the same network transfer directly at line speed instead of through
+//! WAN seeders.
+//!
+//! Discovery is opt-in, since announcing reveals to everyone on the local
+//! network that a fud node is running. Announcements carry nothing about
+//! the stored files, and discovered peers are only used for fetching, never
+//! shared with the P2P network.
+
+use std::{
+    collections::HashMap,
+    net::{IpAddr, Ipv4Addr, SocketAddr, SocketAddrV4},
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use log::{debug, error, info};
+use rand::{rngs::OsRng, RngCore};
+use smol::{lock::RwLock, net::UdpSocket};
+use socket2::{Domain, Protocol, Socket, Type};
+use url::Url;
+
+use darkfi::{
+    system::{sleep, timeout},
+    Result,
+};
+
+use super::Fud;
+
+/// SSDP multicast group
+pub const SSDP_ADDR: Ipv4Addr = Ipv4Addr::new(239, 255, 255, 250);
+/// SSDP port
+pub const SSDP_PORT: u16 = 1900;
+/// SSDP notification type identifying fud announcements
+const SSDP_NT: &str = "urn:dark-fi:service:fud:1";
+
+/// Interval in seconds between our periodic announcements
+const ANNOUNCE_INTERVAL: u64 = 60;
+/// Time after which peers that stopped announcing are forgotten
+const PEER_TTL: Duration = Duration::from_secs(3 * ANNOUNCE_INTERVAL);
+/// Transports LAN peers may be reached with
+const LAN_TRANSPORTS: [&str; 2] = ["tcp", "tcp+tls"];
+
+/// Peers discovered on the local network
+pub struct LanDiscovery {
+    /// Random node ID, regenerated every session
+    node_id: String,
+    /// Discovered peer addresses, along with the last time they announced
+    peers: RwLock<HashMap<Url, Instant>>,
+}
+
+impl Default for LanDiscovery {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl LanDiscovery {
+    pub fn new() -> Self {
+        let mut node_id = [0u8; 16];
+        OsRng.fill_bytes(&mut node_id);
+        let node_id = node_id.iter().map(|b| format!("{b:02x}")).collect();
+        Self { node_id, peers: RwLock::new(HashMap::new()) }
+    }
+
+    /// Retrieve the currently known LAN peers.
+    pub async fn peers(&self) -> Vec<Url> {
+        let mut peers = self.peers.write().await;
+        peers.retain(|_, seen| seen.elapsed() < PEER_TTL);
+        peers.keys().cloned().collect()
+    }
+
+    /// Forget a LAN peer we failed to connect to.
+    pub async fn forget(&self, peer: &Url) {
+        self.peers.write().await.remove(peer);
+    }
+
+    /// Build our SSDP announcement for the given inbound addresses.
+    /// Returns `None` if none of them can be reached over the LAN.
+    fn announcement(&self, inbound: &[Url]) -> Option<String> {
+        let addrs: Vec<_> = inbound
+            .iter()
+            .filter(|u| LAN_TRANSPORTS.contains(&u.scheme()))
+            .filter_map(|u| u.port().map(|p| format!("{}:{}", u.scheme(), p)))
+            .collect();
+        if addrs.is_empty() {
+            return None
+        }
+
+        Some(format!(
+            "NOTIFY * HTTP/1.1\r\n\
+             HOST: {SSDP_ADDR}:{SSDP_PORT}\r\n\
+             CACHE-CONTROL: max-age={}\r\n\
+             NT: {SSDP_NT}\r\n\
+             NTS: ssdp:alive\r\n\
+             USN: uuid:{}::{SSDP_NT}\r\n\
+             X-FUD-ADDRS: {}\r\n\r\n",
+            PEER_TTL.as_secs(),
+            self.node_id,
+            addrs.join(" "),
+        ))
+    }
+
+    /// Parse a received SSDP datagram, returning the peer addresses it
+    /// announces. Our own announcements, ones not coming from the local
+    /// network, and any other SSDP traffic are ignored.
+    fn parse(&self, datagram: &[u8], source: &SocketAddr) -> Option<Vec<Url>> {
+        let ip = match source.ip() {
+            IpAddr::V4(ip) if ip.is_private() || ip.is_link_local() || ip.is_loopback() => ip,
+            _ => return None,
+        };
+
+        let datagram = std::str::from_utf8(datagram).ok()?;
+        let mut lines = datagram.split("\r\n");
+        if lines.next()? != "NOTIFY * HTTP/1.1" {
+            return None
+        }
+
+        let mut headers = HashMap::new();
+        for line in lines {
+            if let Some((name, value)) = line.split_once(':') {
+                headers.insert(name.trim().to_ascii_uppercase(), value.trim());
+            }
+        }
+
+        if headers.get("NT") != Some(&SSDP_NT) || headers.get("NTS") != Some(&"ssdp:alive") {
+            return None
+        }
+
+        // Skip our own announcements
+        let usn = headers.get("USN")?;
+        if usn.strip_prefix("uuid:")?.split("::").next()? == self.node_id {
+            return None
+        }
+
+        let addrs = headers
+            .get("X-FUD-ADDRS")?
+            .split_whitespace()
+            .filter_map(|a| a.split_once(':'))
+            .filter(|(scheme, _)| LAN_TRANSPORTS.contains(scheme))
+            .filter_map(|(scheme, port)| port.parse::<u16>().ok().map(|p| (scheme, p)))
+            .filter_map(|(scheme, port)| Url::parse(&format!("{scheme}://{ip}:{port}")).ok())
+            .collect();
+
+        Some(addrs)
+    }
+
+    /// Note down the given peer addresses, returning `true` if any
+    /// of them was not known before.
+    async fn insert(&self, addrs: Vec<Url>) -> bool {
+        let mut peers = self.peers.write().await;
+        let mut new = false;
+        for addr in addrs {
+            if peers.insert(addr.clone(), Instant::now()).is_none() {
+                info!(target: "fud::lan", "Discovered LAN peer {}", addr);
+                new = true;
+            }
+        }
+        new
+    }
+}
+
+/// Bind a UDP socket to the SSDP port, sharing it with any other SSDP
+/// listeners on this host, and join the SSDP multicast group.
+fn ssdp_socket() -> std::io::Result<UdpSocket> {
+    let socket = Socket::new(Domain::IPV4, Type::DGRAM, Some(Protocol::UDP))?;
+    socket.set_reuse_address(true)?;
+    #[cfg(unix)]
+    socket.set_reuse_port(true)?;
+    socket.bind(&SocketAddrV4::new(Ipv4Addr::UNSPECIFIED, SSDP_PORT).into())?;
+    socket.join_multicast_v4(&SSDP_ADDR, &Ipv4Addr::UNSPECIFIED)?;
+    socket.set_multicast_loop_v4(true)?;
+    socket.set_nonblocking(true)?;
+    UdpSocket::try_from(std::net::UdpSocket::from(socket))
+}
+
+/// Send our announcement to the SSDP multicast group.
+async fn announce(fud: &Fud, socket: &UdpSocket) {
+    let inbound = fud.p2p.settings().read().await.inbound_addrs.clone();
+    let Some(announcement) = fud.lan.announcement(&inbound) else {
+        debug!(target: "fud::lan", "No LAN reachable inbound addresses to announce");
+        return
+    };
+
+    let target = SocketAddrV4::new(SSDP_ADDR, SSDP_PORT);
+    if let Err(e) = socket.send_to(announcement.as_bytes(), target).await {
+        error!(target: "fud::lan", "Failed sending LAN announcement: {}", e);
+    }
+}
+
+/// Background task announcing ourselves on the local network and
+/// discovering the other fud nodes announcing themselves on it.
+pub async fn lan_discovery_task(fud: Arc<Fud>) -> Result<()> {
+    let socket = ssdp_socket()?;
+    info!(target: "fud::lan", "Announcing on {}:{} for LAN peer discovery", SSDP_ADDR, SSDP_PORT);
+
+    let interval = Duration::from_secs(ANNOUNCE_INTERVAL);
+    let mut buf = [0u8; 1500];
+    announce(&fud, &socket).await;
+    let mut last_announce = Instant::now();
+
+    loop {
+        let remaining = interval.saturating_sub(last_announce.elapsed());
+        let Ok(received) = timeout(remaining, socket.recv_from(&mut buf)).await else {
+            announce(&fud, &socket).await;
+            last_announce = Instant::now();
+            continue
+        };
+
+        // Receive errors are transient, e.g. ICMP unreachable replies
+        // to our own sends, so we keep listening.
+        let (len, source) = match received {
+            Ok(r) => r,
+            Err(e) => {
+                error!(target: "fud::lan", "Failed receiving LAN announcement: {}", e);
+                sleep(1).await;
+                continue
+            }
+        };
+        let Some(addrs) = fud.lan.parse(&buf[..len], &source) else { continue };
+
+        // Answer newcomers right away, so they don't have to wait
+        // for our next periodic announcement to find us.
+        if fud.lan.insert(addrs).await {
+            announce(&fud, &socket).await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn inbound() -> Vec<Url> {
+        vec![
+            Url::parse("tcp://0.0.0.0:13337").unwrap(),
+            Url::parse("tor://fud.onion:13338").unwrap(),
+            Url::parse("tcp+tls://[::]:13339").unwrap(),
+            Url::parse("tcp://localhost").unwrap(),
+        ]
+    }
+
+    #[test]
+    fn lan_announcement() {
+        let lan = LanDiscovery::new();
+        let announcement = lan.announcement(&inbound()).unwrap();
+        assert!(announcement.starts_with("NOTIFY * HTTP/1.1\r\n"));
+        assert!(announcement.ends_with("\r\n\r\n"));
+        assert!(announcement.contains(&format!("NT: {SSDP_NT}\r\n")));
+        assert!(announcement.contains(&format!("USN: uuid:{}::{SSDP_NT}\r\n", lan.node_id)));
+        // Only LAN transports with a port get announced
+        assert!(announcement.contains("X-FUD-ADDRS: tcp:13337 tcp+tls:13339\r\n"));
+
+        assert!(lan.announcement(&[Url::parse("tor://fud.onion:13338").unwrap()]).is_none());
+        assert!(lan.announcement(&[]).is_none());
+    }
+
+    #[test]
+    fn lan_parse() {
+        let lan = LanDiscovery::new();
+        let other = LanDiscovery::new();
+        let announcement = other.announcement(&inbound()).unwrap();
+        let source: SocketAddr = "192.168.1.7:1900".parse().unwrap();
+
+        // Peers are addressed by the announcement source address
+        assert_eq!(
+            lan.parse(announcement.as_bytes(), &source),
+            Some(vec![
+                Url::parse("tcp://192.168.1.7:13337").unwrap(),
+                Url::parse("tcp+tls://192.168.1.7:13339").unwrap(),
+            ])
+        );
+
+        // Our own announcements are skipped
+        let own = lan.announcement(&inbound()).unwrap();
+        assert!(lan.parse(own.as_bytes(), &source).is_none());
+
+        // Only announcements from the local network are accepted
+        for source in ["10.0.0.2:1900", "169.254.0.2:1900", "127.0.0.1:1900"] {
+            assert!(lan.parse(announcement.as_bytes(), &source.parse().unwrap()).is_some());
+        }
+        for source in ["8.8.8.8:1900", "[fe80::1]:1900", "[::1]:1900"] {
+            assert!(lan.parse(announcement.as_bytes(), &source.parse().unwrap()).is_none());
+        }
+
+        // Other SSDP traffic is ignored
+        let byebye = announcement.replace("ssdp:alive", "ssdp:byebye");
+        assert!(lan.parse(byebye.as_bytes(), &source).is_none());
+        let other_nt = announcement.replace(SSDP_NT, "upnp:rootdevice");
+        assert!(lan.parse(other_nt.as_bytes(), &source).is_none());
+        let search = announcement.replace("NOTIFY * HTTP/1.1", "M-SEARCH * HTTP/1.1");
+        assert!(lan.parse(search.as_bytes(), &source).is_none());
+        assert!(lan.parse(&[0xff, 0xfe, 0xfd], &source).is_none());
+
+        // Headers are case insensitive, and unusable addresses are skipped
+        let mangled = announcement
+            .replace("X-FUD-ADDRS", "x-fud-addrs")
+            .replace("tcp:13337 tcp+tls:13339", "tor:13338 tcp:port tcp:99999 tcp+tls:13339");
+        assert_eq!(
+            lan.parse(mangled.as_bytes(), &source),
+            Some(vec![Url::parse("tcp+tls://192.168.1.7:13339").unwrap()])
+        );
+    }
+}
@@ -0,0 +1,192 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2025 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Reuse of chunks from local copies of files.
+//!
+//! When fetching a file the user already partially has, e.g. a previous
+//! release, the local copies are chunked the same way [`Geode::insert`]
+//! does it, and the chunks the file is missing are inserted into Geode
+//! before contacting seeders, so only the changed content is downloaded.
+//! Only content at matching chunk offsets can be found.
+
+use std::{collections::HashSet, path::PathBuf};
+
+use log::{debug, info, warn};
+use smol::{
+    fs::{self, File},
+    io::AsyncReadExt,
+    stream::StreamExt,
+};
+
+use darkfi::{
+    geode::{ChunkedFile, Geode, MAX_CHUNK_SIZE},
+    Result,
+};
+
+/// Scan the given local files, or all the files found under the given
+/// directories, for chunks the provided [`ChunkedFile`] is missing, and
+/// insert any found into Geode. Symlinks are not followed, and paths
+/// that can't be read are skipped.
+/// Returns the number of chunks that were imported.
+pub async fn import_local(
+    geode: &Geode,
+    chunked_file: &ChunkedFile,
+    local_paths: &[PathBuf],
+) -> Result<usize> {
+    info!(target: "fud::import_local()", "Scanning local copies for chunks...");
+    let mut missing: HashSet<blake3::Hash> =
+        chunked_file.iter().filter(|(_, path)| path.is_none()).map(|(h, _)| *h).collect();
+
+    let mut imported = 0;
+    let mut pending = local_paths.to_vec();
+    while let Some(path) = pending.pop() {
+        if missing.is_empty() {
+            break
+        }
+
+        // Don't follow symlinks, so link cycles can't make us loop
+        let metadata = match fs::symlink_metadata(&path).await {
+            Ok(v) => v,
+            Err(e) => {
+                warn!(target: "fud::import_local()", "Skipping {:?}: {}", path, e);
+                continue
+            }
+        };
+
+        if metadata.is_dir() {
+            let mut entries = match fs::read_dir(&path).await {
+                Ok(v) => v,
+                Err(e) => {
+                    warn!(target: "fud::import_local()", "Skipping {:?}: {}", path, e);
+                    continue
+                }
+            };
+            while let Some(entry) = entries.next().await {
+                match entry {
+                    Ok(entry) => pending.push(entry.path()),
+                    Err(e) => {
+                        warn!(target: "fud::import_local()", "Skipping entry of {:?}: {}", path, e)
+                    }
+                }
+            }
+            continue
+        }
+
+        if !metadata.is_file() {
+            continue
+        }
+
+        match import_file(geode, &path, &mut missing).await {
+            Ok(n) => imported += n,
+            Err(e) => warn!(target: "fud::import_local()", "Failed scanning {:?}: {}", path, e),
+        }
+    }
+
+    info!(target: "fud::import_local()", "Imported {} chunks from local copies", imported);
+    Ok(imported)
+}
+
+/// Insert the chunks of the given local file found in `missing` into
+/// Geode, removing them from the set. Returns the number of chunks that
+/// were imported.
+async fn import_file(
+    geode: &Geode,
+    path: &PathBuf,
+    missing: &mut HashSet<blake3::Hash>,
+) -> Result<usize> {
+    let mut fd = File::open(path).await?;
+    let mut buf = vec![0u8; MAX_CHUNK_SIZE];
+    let mut imported = 0;
+
+    loop {
+        // Fill a whole chunk, since reads may return less than asked for
+        let mut bytes_read = 0;
+        while bytes_read < MAX_CHUNK_SIZE {
+            let n = fd.read(&mut buf[bytes_read..]).await?;
+            if n == 0 {
+                break
+            }
+            bytes_read += n;
+        }
+        if bytes_read == 0 {
+            break
+        }
+
+        let chunk_slice = &buf[..bytes_read];
+        if missing.remove(&geode.hash_chunk(chunk_slice)) {
+            let chunk_hash = geode.insert_chunk(chunk_slice).await?;
+            debug!(target: "fud::import_local()", "Imported chunk {} from {:?}", chunk_hash, path);
+            imported += 1;
+        }
+
+        if bytes_read < MAX_CHUNK_SIZE {
+            break
+        }
+    }
+
+    Ok(imported)
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::{rngs::OsRng, Rng};
+
+    use super::*;
+
+    #[test]
+    fn import_local_copies() {
+        smol::block_on(async {
+            let basedir = std::env::temp_dir().join(format!("fud_local_{}", OsRng.gen::<u64>()));
+            let geode = Geode::new(&basedir.join("geode")).await.unwrap();
+
+            // A file made of three chunks, of which we have outdated copies
+            let data: Vec<u8> = (0..MAX_CHUNK_SIZE * 2 + 1337).map(|i| (i % 251) as u8).collect();
+            let chunk_hashes: Vec<blake3::Hash> =
+                data.chunks(MAX_CHUNK_SIZE).map(|c| geode.hash_chunk(c)).collect();
+            let file_hash = blake3::hash(&data);
+            geode.insert_file(&file_hash, &chunk_hashes).await.unwrap();
+            let chunked_file = geode.get(&file_hash).await.unwrap();
+            assert!(!chunked_file.is_complete());
+
+            let local = basedir.join("local");
+            fs::create_dir_all(local.join("nested")).await.unwrap();
+            let copy = [&data[..MAX_CHUNK_SIZE], b"changed"].concat();
+            fs::write(local.join("nested").join("copy"), copy).await.unwrap();
+            fs::write(local.join("shifted"), &data[1..]).await.unwrap();
+
+            // Symlink cycles are not followed, and missing paths are skipped
+            std::os::unix::fs::symlink(&local, local.join("nested").join("loop")).unwrap();
+            std::os::unix::fs::symlink(local.join("nested").join("copy"), local.join("link"))
+                .unwrap();
+            let paths = vec![basedir.join("missing"), local.clone()];
+
+            // Only the chunk at a matching offset is found
+            assert_eq!(import_local(&geode, &chunked_file, &paths).await.unwrap(), 1);
+            let chunked_file = geode.get(&file_hash).await.unwrap();
+            assert!(chunked_file.iter().next().unwrap().1.is_some());
+            assert!(chunked_file.iter().skip(1).all(|(_, path)| path.is_none()));
+
+            // A full copy completes the file
+            fs::write(local.join("full"), &data).await.unwrap();
+            assert_eq!(import_local(&geode, &chunked_file, &paths).await.unwrap(), 2);
+            assert!(geode.get(&file_hash).await.unwrap().is_complete());
+
+            fs::remove_dir_all(&basedir).await.unwrap();
+        })
+    }
+}
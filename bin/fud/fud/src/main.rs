@@ -18,11 +18,13 @@
 
 use std::{
     collections::{HashMap, HashSet},
-    sync::Arc,
+    path::Path,
+    str::FromStr,
+    sync::{atomic::AtomicU64, Arc},
 };
 
 use async_trait::async_trait;
-use log::{debug, error, info, warn};
+use log::{error, info, warn};
 use smol::{
     channel,
     fs::File,
@@ -36,30 +38,88 @@ use url::Url;
 
 use darkfi::{
     async_daemonize, cli_desc,
-    geode::Geode,
-    net::{
-        self, connector::Connector, protocol::ProtocolVersion, session::Session,
-        settings::SettingsOpt, P2p, P2pPtr,
-    },
+    geode::{hasher::HashAlgorithm, ChunkedFile, Geode},
+    net::{self, settings::SettingsOpt, ChannelPtr, P2p, P2pPtr},
     rpc::{
         jsonrpc::{ErrorCode, JsonError, JsonRequest, JsonResponse, JsonResult},
+        log_method::HandlerLog,
         server::{listen_and_serve, RequestHandler},
     },
-    system::{StoppableTask, StoppableTaskPtr},
+    rpc_error,
+    system::{Semaphore, SemaphorePtr, StoppableTask, StoppableTaskPtr},
     util::path::expand_path,
     Error, Result,
 };
+use fud_client::FudTransport;
+
+/// Custom RPC errors
+mod error;
+use error::RpcError;
 
 /// P2P protocols
 mod proto;
-use proto::{
-    FudChunkPut, FudChunkReply, FudChunkRequest, FudFilePut, FudFileReply, FudFileRequest,
-    ProtocolFud,
-};
+use proto::{FudChunkPut, FudDenylistPut, FudFilePut, ProtocolFud};
+
+/// Native P2P implementation of the fud client transport
+mod transport;
+use transport::{FetchChannels, P2pTransport};
+
+/// Websocket bridge for browser clients
+mod bridge;
+
+/// Filesystem watcher re-verifying changed chunks
+mod watch;
+
+/// Background integrity scrubbing of stored files
+mod scrub;
+
+/// Download scheduling policies
+mod scheduler;
+use scheduler::{Priority, ScheduleWindow, Scheduler, CHECKPOINT_CHUNKS};
+
+/// Authenticated remote control for seedbox mode
+mod seedbox;
+use seedbox::{SeedboxRpcHandler, Uploads};
+
+/// Per-peer transfer accounting
+mod swarm;
+use swarm::SwarmStats;
+
+/// Publisher signatures over file metadata
+mod publisher;
+use publisher::Publishers;
+
+mod denylist;
+use denylist::{DenylistMatch, Denylists};
+
+/// Local names for resources
+mod names;
+use names::Names;
+
+/// Download integrity reports
+mod report;
+use report::Reports;
+
+/// Download destination templates and file name sanitization
+mod util;
+
+/// Replication of announces to the peers closest to a key
+mod replication;
+use replication::Replicas;
+
+/// Opt-in peer discovery on the local network
+mod lan;
+use lan::LanDiscovery;
+
+/// Reuse of chunks from local copies of files
+mod local;
 
 const CONFIG_FILE: &str = "fud_config.toml";
 const CONFIG_FILE_CONTENTS: &str = include_str!("../fud_config.toml");
 
+// Browser clients chunk and verify resources the same way Geode does
+const _: () = assert!(fud_client::MAX_CHUNK_SIZE == darkfi::geode::MAX_CHUNK_SIZE);
+
 #[derive(Clone, Debug, serde::Deserialize, StructOpt, StructOptToml)]
 #[serde(default)]
 #[structopt(name = "fud", about = cli_desc!())]
@@ -72,6 +132,10 @@ struct Args {
     /// JSON-RPC listen URL
     rpc_listen: Url,
 
+    #[structopt(long)]
+    /// Websocket bridge listen URL for browser clients (disabled if unset)
+    bridge_listen: Option<Url>,
+
     #[structopt(short, long)]
     /// Configuration file to use
     config: Option<String>,
@@ -84,199 +148,1094 @@ struct Args {
     /// Base directory for filesystem storage
     base_dir: String,
 
+    #[structopt(long, default_value = "blake3")]
+    /// Hash algorithm deriving chunk and file IDs (blake3, blake3-keyed, sha256)
+    hash_algorithm: String,
+
+    #[structopt(long)]
+    /// Hex-encoded 32-byte key for the blake3-keyed hash algorithm
+    hash_key: Option<String>,
+
+    #[structopt(long, default_value = "4")]
+    /// Maximum number of concurrent file and chunk fetches
+    max_fetches: usize,
+
+    #[structopt(long, default_value = "8")]
+    /// Maximum number of outstanding chunk requests per seeder connection
+    chunk_window: usize,
+
+    #[structopt(long, default_value = "2")]
+    /// Maximum number of concurrently active scheduled downloads
+    max_downloads: usize,
+
+    #[structopt(long)]
+    /// Daily UTC time window during which scheduled downloads may start (e.g. 01:00-07:00)
+    download_window: Option<String>,
+
+    #[structopt(long)]
+    /// Destination path template for fetched files (e.g. ~/Downloads/{name}-{hash8})
+    download_template: Option<String>,
+
+    #[structopt(long, default_value = "1048576")]
+    /// Background integrity scrubbing rate in bytes/sec (disabled if zero)
+    scrub_rate: u64,
+
+    #[structopt(long, default_value = "24")]
+    /// Hours to wait before scrubbing a stored file again
+    scrub_interval: u64,
+
+    #[structopt(long)]
+    /// Run as an upload-only seedbox, disabling local downloads
+    seedbox: bool,
+
+    #[structopt(long)]
+    /// Authenticated seedbox JSON-RPC listen URL (disabled if unset)
+    seedbox_rpc_listen: Option<Url>,
+
+    #[structopt(long)]
+    /// Access token required by the seedbox JSON-RPC
+    seedbox_token: Option<String>,
+
+    #[structopt(long, default_value = "0")]
+    /// Maximum total storage used for chunks in MiB (unlimited if zero)
+    max_storage: u64,
+
+    #[structopt(long)]
+    /// Publisher secret key used to sign the metadata of inserted files
+    publisher_key: Option<String>,
+
+    #[structopt(long)]
+    /// Trusted publisher public keys, rejecting metadata not signed by them (repeatable)
+    trusted_publishers: Vec<String>,
+
+    #[structopt(long)]
+    /// Moderator secret key used to sign our published denylist
+    moderator_key: Option<String>,
+
+    #[structopt(long)]
+    /// Moderator public keys whose denylists we subscribe to and enforce (repeatable)
+    denylist_subscriptions: Vec<String>,
+
+    #[structopt(long, default_value = "0")]
+    /// Number of closest peers announces get re-replicated to (disabled if zero)
+    replication_factor: usize,
+
+    #[structopt(long, default_value = "120")]
+    /// Seconds between checks for churned responsible peers to re-replicate to
+    replication_interval: u64,
+
+    #[structopt(long)]
+    /// Announce ourselves on and discover fud peers from the local network
+    lan_discovery: bool,
+
+    #[structopt(long)]
+    /// Write an integrity report, signed with the publisher key if set,
+    /// when a scheduled download completes
+    download_reports: bool,
+
     #[structopt(flatten)]
     /// Network settings
     net: SettingsOpt,
 }
 
+/// A fetch request for the background fetch tasks: the hash of the
+/// object to fetch, and the channel the fetch status gets sent back on,
+/// so concurrent requests never see each other's replies.
+type FetchRequest = (blake3::Hash, channel::Sender<Result<()>>);
+
 pub struct Fud {
     /// Routing table for file metadata
     metadata_router: Arc<RwLock<HashMap<blake3::Hash, HashSet<Url>>>>,
     /// Routing table for file chunks
     chunks_router: Arc<RwLock<HashMap<blake3::Hash, HashSet<Url>>>>,
+    /// Channel each chunk route was first learned from, keyed by the chunk
+    /// hash and the seeder, which is the only one allowed to withdraw it
+    chunk_route_sources: RwLock<HashMap<(blake3::Hash, Url), Url>>,
     /// Pointer to the P2P network instance
     p2p: P2pPtr,
     /// The Geode instance
     geode: Geode,
 
-    file_fetch_tx: channel::Sender<(blake3::Hash, Result<()>)>,
-    file_fetch_rx: channel::Receiver<(blake3::Hash, Result<()>)>,
-    chunk_fetch_tx: channel::Sender<(blake3::Hash, Result<()>)>,
-    chunk_fetch_rx: channel::Receiver<(blake3::Hash, Result<()>)>,
+    /// File fetch requests, handled by the background file fetch task
+    file_fetch_tx: channel::Sender<FetchRequest>,
+    file_fetch_rx: channel::Receiver<FetchRequest>,
+    /// Chunk fetch requests, handled by the background chunk fetch task
+    chunk_fetch_tx: channel::Sender<FetchRequest>,
+    chunk_fetch_rx: channel::Receiver<FetchRequest>,
+    /// Semaphore bounding the amount of concurrent fetches
+    fetch_semaphore: SemaphorePtr,
+    /// Maximum number of outstanding chunk requests per seeder connection
+    chunk_window: usize,
+    /// Download scheduler
+    scheduler: Scheduler,
+    /// Default destination path template for fetched files
+    download_template: Option<String>,
+
+    /// Whether we're running as an upload-only seedbox
+    seedbox: bool,
+    /// Access token for the seedbox JSON-RPC
+    seedbox_token: Option<String>,
+    /// Maximum total storage used for chunks in bytes (unlimited if zero)
+    max_storage: AtomicU64,
+    /// Bytes uploaded to peers, per stored chunk
+    uploads: Uploads,
+    /// Transfers performed with peers
+    swarm: SwarmStats,
+    /// Publisher keys and stored metadata signatures
+    publishers: Publishers,
+    /// Subscribed moderator denylists
+    denylists: Denylists,
+    /// Local resource names
+    names: Names,
+    /// Download integrity reports
+    reports: Reports,
+    /// Announce replication to the peers closest to a key
+    replicas: Replicas,
+    /// Channels opened to peers for fetching, reused across fetches
+    /// until they stay idle
+    channels: FetchChannels,
+    /// Peers discovered on the local network
+    lan: LanDiscovery,
 
     rpc_connections: Mutex<HashSet<StoppableTaskPtr>>,
+    seedbox_rpc_connections: Mutex<HashSet<StoppableTaskPtr>>,
 }
 
 #[async_trait]
 impl RequestHandler<()> for Fud {
     async fn handle_request(&self, req: JsonRequest) -> JsonResult {
         return match req.method.as_str() {
-            "ping" => self.pong(req.id, req.params).await,
+            "ping" => <Fud as RequestHandler<()>>::pong(self, req.id, req.params).await,
 
             "put" => self.put(req.id, req.params).await,
             "get" => self.get(req.id, req.params).await,
 
-            "dnet_switch" => self.dnet_switch(req.id, req.params).await,
-            _ => JsonError::new(ErrorCode::MethodNotFound, None, req.id).into(),
+            "download" => self.download(req.id, req.params).await,
+            "downloads" => self.downloads(req.id, req.params).await,
+            "download_priority" => self.download_priority(req.id, req.params).await,
+            "download_cancel" => self.download_cancel(req.id, req.params).await,
+            "set_max_downloads" => self.set_max_downloads(req.id, req.params).await,
+            "set_download_window" => self.set_download_window(req.id, req.params).await,
+
+            "resource.peers" => self.resource_peers(req.id, req.params).await,
+            "resource.publisher" => self.resource_publisher(req.id, req.params).await,
+
+            "name.set" => self.name_set(req.id, req.params).await,
+            "name.remove" => self.name_remove(req.id, req.params).await,
+            "names" => self.names(req.id, req.params).await,
+
+            "report.generate" => self.report_generate(req.id, req.params).await,
+            "report.verify" => self.report_verify(req.id, req.params).await,
+
+            "denylists" => self.denylists(req.id, req.params).await,
+            "denylist.check" => self.denylist_check(req.id, req.params).await,
+            "denylist.publish" => self.denylist_publish(req.id, req.params).await,
+
+            "dnet_switch" => self.dnet_switch(req.id, req.params).await,
+
+            "log.get_levels" => self.log_get_levels(req.id, req.params).await,
+            "log.set_level" => self.log_set_level(req.id, req.params).await,
+            "log.reset_level" => self.log_reset_level(req.id, req.params).await,
+            _ => JsonError::new(ErrorCode::MethodNotFound, None, req.id).into(),
+        }
+    }
+
+    async fn connections_mut(&self) -> MutexGuard<'_, HashSet<StoppableTaskPtr>> {
+        self.rpc_connections.lock().await
+    }
+}
+
+impl HandlerLog for Fud {}
+
+impl Fud {
+    /// Check whether a file or any of its chunks is denylisted,
+    /// returning the list that matched
+    async fn denylisted(
+        &self,
+        file_hash: &blake3::Hash,
+        chunk_hashes: &[blake3::Hash],
+    ) -> Option<DenylistMatch> {
+        if let Some(m) = self.denylists.check(file_hash).await {
+            return Some(m)
+        }
+
+        for chunk_hash in chunk_hashes {
+            if let Some(m) = self.denylists.check(chunk_hash).await {
+                return Some(m)
+            }
+        }
+
+        None
+    }
+
+    /// Record the channel the routes of the given chunks to `peer` were
+    /// learned from, unless they were already known from another one.
+    async fn add_chunk_route_sources(
+        &self,
+        chunk_hashes: &[blake3::Hash],
+        peer: &Url,
+        source: &Url,
+    ) {
+        let mut sources = self.chunk_route_sources.write().await;
+        for chunk_hash in chunk_hashes {
+            sources.entry((*chunk_hash, peer.clone())).or_insert_with(|| source.clone());
+        }
+    }
+
+    /// Check if the route of the given chunk to `peer` was learned from
+    /// the channel at `source`
+    async fn is_chunk_route_source(
+        &self,
+        chunk_hash: &blake3::Hash,
+        peer: &Url,
+        source: &Url,
+    ) -> bool {
+        self.chunk_route_sources.read().await.get(&(*chunk_hash, peer.clone())) == Some(source)
+    }
+
+    /// Remove `peer` from the routes of the given chunk.
+    /// Returns `true` if the route existed.
+    async fn remove_chunk_route(&self, chunk_hash: &blake3::Hash, peer: &Url) -> bool {
+        self.chunk_route_sources.write().await.remove(&(*chunk_hash, peer.clone()));
+
+        let mut chunks_lock = self.chunks_router.write().await;
+        let Some(peers) = chunks_lock.get_mut(chunk_hash) else { return false };
+
+        let removed = peers.remove(peer);
+        if peers.is_empty() {
+            chunks_lock.remove(chunk_hash);
+        }
+
+        removed
+    }
+
+    // RPCAPI:
+    // Put a file onto the network. Takes a local filesystem path as a parameter.
+    // Returns the file hash that serves as a pointer to the uploaded file.
+    //
+    // --> {"jsonrpc": "2.0", "method": "put", "params": ["/foo.txt"], "id": 42}
+    // <-- {"jsonrpc": "2.0", "result: "df4...3db7", "id": 42}
+    async fn put(&self, id: u16, params: JsonValue) -> JsonResult {
+        let params = params.get::<Vec<JsonValue>>().unwrap();
+        if params.len() != 1 || !params[0].is_string() {
+            return JsonError::new(ErrorCode::InvalidParams, None, id).into()
+        }
+
+        let path = params[0].get::<String>().unwrap();
+        let path = match expand_path(path.as_str()) {
+            Ok(v) => v,
+            Err(_) => return JsonError::new(ErrorCode::InvalidParams, None, id).into(),
+        };
+
+        // A valid path was passed. Let's see if we can read it, and if so,
+        // add it to Geode.
+        let fd = match File::open(&path).await {
+            Ok(v) => v,
+            Err(e) => {
+                error!("Failed to open {:?}: {}", path, e);
+                return JsonError::new(ErrorCode::InvalidParams, None, id).into()
+            }
+        };
+
+        let (file_hash, chunk_hashes) = match self.geode.insert(fd).await {
+            Ok(v) => v,
+            Err(e) => {
+                error!("Failed inserting file {:?} to geode: {}", path, e);
+                return rpc_error!(RpcError::GeodeInsertFailed, id)
+            }
+        };
+
+        // Refuse to seed denylisted files. They are not served either.
+        if let Some(m) = self.denylisted(&file_hash, &chunk_hashes).await {
+            warn!("Refusing to put {:?}, denylisted by {}", path, m);
+            return rpc_error!(RpcError::Denylisted, id, format!("Denylisted by {}", m))
+        }
+
+        if let Some(publisher) = self.publishers.sign(&file_hash, &chunk_hashes) {
+            if let Err(e) = self.publishers.put(&file_hash, &publisher).await {
+                error!("Failed storing publisher signature of {}: {}", file_hash, e);
+            }
+        }
+
+        let fud_file = FudFilePut { file_hash, chunk_hashes };
+        self.replicas.announce(&self.p2p, &file_hash, &fud_file, &[]).await;
+
+        JsonResponse::new(JsonValue::String(file_hash.to_hex().to_string()), id).into()
+    }
+
+    // RPCAPI:
+    // Fetch a file from the network. Takes a file hash or local name as
+    // parameter, and optionally the path to a local copy of the file, or a
+    // directory holding local copies, whose matching chunks are reused
+    // instead of downloaded (an empty string skips it). A destination path template and a file
+    // name can follow, e.g. `~/Downloads/{name}-{hash8}`, with an empty or
+    // missing template defaulting to the configured `download_template`.
+    // See `util` for the supported placeholders.
+    // Returns the paths to the local chunks of the file, if found/fetched,
+    // or the path the file was assembled at if a destination is used.
+    //
+    // --> {"jsonrpc": "2.0", "method": "get", "params": ["1211...abfd", "~/old-release"], "id": 42}
+    // <-- {"jsonrpc": "2.0", "result: ["~/.local/share/darkfi/fud/chunks/fab1...2314", ...], "id": 42}
+    //
+    // --> {"jsonrpc": "2.0", "method": "get", "params": ["1211...abfd", "", "~/Downloads/{name}-{hash8}", "release.tar"], "id": 42}
+    // <-- {"jsonrpc": "2.0", "result: "/home/user/Downloads/release.tar-1211abfd", "id": 42}
+    async fn get(&self, id: u16, params: JsonValue) -> JsonResult {
+        if self.seedbox {
+            return rpc_error!(RpcError::SeedboxMode, id)
+        }
+
+        let params = params.get::<Vec<JsonValue>>().unwrap();
+        if params.is_empty() || params.len() > 4 || !params.iter().all(|p| p.is_string()) {
+            return JsonError::new(ErrorCode::InvalidParams, None, id).into()
+        }
+
+        let Some(file_hash) = self.names.resolve(params[0].get::<String>().unwrap()).await else {
+            return JsonError::new(ErrorCode::InvalidParams, None, id).into()
+        };
+
+        let local_path = match params.get(1).map(|p| p.get::<String>().unwrap()) {
+            Some(p) if !p.is_empty() => match expand_path(p) {
+                Ok(v) => Some(v),
+                Err(_) => return JsonError::new(ErrorCode::InvalidParams, None, id).into(),
+            },
+            _ => None,
+        };
+
+        let template = match params.get(2).map(|t| t.get::<String>().unwrap()) {
+            Some(t) if !t.is_empty() => Some(t),
+            _ => self.download_template.as_ref(),
+        };
+        let name = params.get(3).map(|n| n.get::<String>().unwrap().as_str());
+
+        let dest = match template {
+            Some(t) => match util::expand_template(t, &file_hash, name) {
+                Ok(v) => Some(v),
+                Err(_) => return JsonError::new(ErrorCode::InvalidParams, None, id).into(),
+            },
+            None => None,
+        };
+
+        if let Some(m) = self.denylists.check(&file_hash).await {
+            warn!("Refusing to fetch {}, denylisted by {}", file_hash, m);
+            return rpc_error!(RpcError::Denylisted, id, format!("Denylisted by {}", m))
+        }
+
+        let chunked_file = match self.geode.get_checkpointed(&file_hash).await {
+            Ok(v) => v,
+            Err(Error::GeodeNeedsGc) => return rpc_error!(RpcError::GeodeNeedsGc, id),
+            Err(Error::GeodeFileNotFound) => {
+                info!("Requested file {} not found in Geode, triggering fetch", file_hash);
+                let status = match self.request_fetch(&self.file_fetch_tx, file_hash).await {
+                    Ok(reply) => {
+                        info!("Waiting for background file fetch task...");
+                        reply.recv().await.unwrap_or(Err(Error::DetachedTaskStopped))
+                    }
+                    Err(e) => Err(e),
+                };
+
+                match status {
+                    Ok(()) => {}
+                    Err(Error::GeodeFileRouteNotFound) => {
+                        return rpc_error!(RpcError::FileNotFound, id)
+                    }
+                    Err(e) => return rpc_error!(RpcError::FetchFailed, id, e.to_string()),
+                }
+
+                let ch_file = match self.geode.get_checkpointed(&file_hash).await {
+                    Ok(v) => v,
+                    Err(e) => return rpc_error!(RpcError::FetchFailed, id, e.to_string()),
+                };
+
+                let m = FudFilePut {
+                    file_hash,
+                    chunk_hashes: ch_file.iter().map(|(h, _)| *h).collect(),
+                };
+                self.replicas.announce(&self.p2p, &file_hash, &m, &[]).await;
+
+                ch_file
+            }
+
+            Err(e) => return rpc_error!(RpcError::FetchFailed, id, e.to_string()),
+        };
+
+        // Reuse any chunks found in local copies before contacting seeders
+        let chunked_file = match local_path {
+            Some(path) if !chunked_file.is_complete() => {
+                if let Err(e) = local::import_local(&self.geode, &chunked_file, &[path]).await {
+                    warn!("Failed scanning local copies of file {}: {}", file_hash, e);
+                }
+
+                match self.geode.get_checkpointed(&file_hash).await {
+                    Ok(v) => v,
+                    Err(e) => return rpc_error!(RpcError::FetchFailed, id, e.to_string()),
+                }
+            }
+            _ => chunked_file,
+        };
+
+        if chunked_file.is_complete() {
+            return self.get_reply(id, &file_hash, &chunked_file, dest.as_deref()).await
+        }
+
+        // Request all the missing chunks at once, the background task
+        // bounds how many of them get fetched concurrently
+        let mut pending = vec![];
+        for (chunk, path) in chunked_file.iter() {
+            if path.is_some() {
+                continue
+            }
+            match self.request_fetch(&self.chunk_fetch_tx, *chunk).await {
+                Ok(reply) => pending.push((*chunk, reply)),
+                Err(e) => return rpc_error!(RpcError::FetchFailed, id, e.to_string()),
+            }
+        }
+
+        // Progress is checkpointed every `CHECKPOINT_CHUNKS` chunks, so a
+        // fetch interrupted by a crash resumes without re-hashing them.
+        let mut chunked_file = chunked_file;
+        let mut fetched = vec![];
+        let mut failed = None;
+        for (chunk_hash, reply) in pending {
+            match reply.recv().await.unwrap_or(Err(Error::DetachedTaskStopped)) {
+                Ok(()) => {
+                    let m = FudChunkPut { chunk_hash };
+                    self.replicas.announce(&self.p2p, &chunk_hash, &m, &[]).await;
+                    fetched.push(chunk_hash);
+                }
+                Err(Error::GeodeChunkRouteNotFound) => continue,
+                Err(e) => {
+                    failed = Some(e);
+                    break
+                }
+            }
+
+            if fetched.len() >= CHECKPOINT_CHUNKS {
+                self.checkpoint_fetch(&file_hash, &mut chunked_file, &fetched).await;
+                fetched.clear();
+            }
+        }
+        self.checkpoint_fetch(&file_hash, &mut chunked_file, &fetched).await;
+
+        if let Some(e) = failed {
+            return rpc_error!(RpcError::FetchFailed, id, e.to_string())
+        }
+
+        if !chunked_file.is_complete() {
+            let missing: Vec<JsonValue> = chunked_file
+                .iter()
+                .filter(|(_, path)| path.is_none())
+                .map(|(chunk, _)| JsonValue::String(chunk.to_hex().to_string()))
+                .collect();
+            let msg = format!("Missing {} chunks", missing.len());
+            return rpc_error!(RpcError::MissingChunks, id, msg, JsonValue::Array(missing))
+        }
+
+        // The checkpoint is only needed to resume the fetch
+        if let Err(e) = self.geode.remove_checkpoint(&file_hash).await {
+            warn!("Failed removing checkpoint of {}: {}", file_hash, e);
+        }
+
+        self.get_reply(id, &file_hash, &chunked_file, dest.as_deref()).await
+    }
+
+    /// Record the chunks fetched for a file in its Geode fetch checkpoint.
+    async fn checkpoint_fetch(
+        &self,
+        file_hash: &blake3::Hash,
+        chunked_file: &mut ChunkedFile,
+        fetched: &[blake3::Hash],
+    ) {
+        if fetched.is_empty() {
+            return
+        }
+
+        if let Err(e) = self.geode.checkpoint(file_hash, chunked_file, fetched).await {
+            warn!("Failed checkpointing fetch of {}: {}", file_hash, e);
+        }
+    }
+
+    /// Queue a fetch of the given object on a background fetch task,
+    /// returning the channel its status will be sent back on.
+    async fn request_fetch(
+        &self,
+        requests: &channel::Sender<FetchRequest>,
+        hash: blake3::Hash,
+    ) -> Result<channel::Receiver<Result<()>>> {
+        let (reply_tx, reply_rx) = channel::bounded(1);
+        if requests.send((hash, reply_tx)).await.is_err() {
+            return Err(Error::DetachedTaskStopped)
+        }
+        Ok(reply_rx)
+    }
+
+    /// Build the `get` reply for a complete file: the paths to its chunks,
+    /// or the path it was assembled at if a destination was requested, in
+    /// which case the file's download report is written alongside it.
+    async fn get_reply(
+        &self,
+        id: u16,
+        file_hash: &blake3::Hash,
+        chunked_file: &ChunkedFile,
+        dest: Option<&Path>,
+    ) -> JsonResult {
+        let Some(dest) = dest else {
+            let chunks: Vec<JsonValue> = chunked_file
+                .iter()
+                .map(|(_, path)| {
+                    JsonValue::String(
+                        path.as_ref().unwrap().clone().into_os_string().into_string().unwrap(),
+                    )
+                })
+                .collect();
+
+            return JsonResponse::new(JsonValue::Array(chunks), id).into()
+        };
+
+        match util::assemble_file(chunked_file, dest).await {
+            Ok(path) => {
+                info!("Assembled file at {}", path.display());
+                if let Err(e) = self.reports.put_alongside(file_hash, &path).await {
+                    warn!(
+                        "Failed writing report of {} alongside {}: {}",
+                        file_hash,
+                        path.display(),
+                        e
+                    );
+                }
+                JsonResponse::new(JsonValue::String(path.to_string_lossy().into_owned()), id).into()
+            }
+            Err(e) => {
+                error!("Failed assembling file at {}: {}", dest.display(), e);
+                rpc_error!(RpcError::AssembleFailed, id)
+            }
+        }
+    }
+
+    // RPCAPI:
+    // Queue a file for download in the background. Takes a file hash or
+    // local name, an optional priority (`low`, `normal` or `high`,
+    // defaults to `normal`) and an optional maximum file size in bytes.
+    // Files exceeding the maximum size are refused once their metadata
+    // is retrieved, before any of their chunks get fetched.
+    // Queued files start downloading as the scheduling policies allow.
+    // Returns `false` if the file is already being downloaded.
+    //
+    // --> {"jsonrpc": "2.0", "method": "download", "params": ["1211...abfd", "high", 1048576], "id": 42}
+    // <-- {"jsonrpc": "2.0", "result": true, "id": 42}
+    async fn download(&self, id: u16, params: JsonValue) -> JsonResult {
+        if self.seedbox {
+            return rpc_error!(RpcError::SeedboxMode, id)
+        }
+
+        let params = params.get::<Vec<JsonValue>>().unwrap();
+        if params.is_empty() ||
+            params.len() > 3 ||
+            !params.iter().take(2).all(|p| p.is_string()) ||
+            params.get(2).is_some_and(|p| !p.is_number())
+        {
+            return JsonError::new(ErrorCode::InvalidParams, None, id).into()
+        }
+
+        let Some(file_hash) = self.names.resolve(params[0].get::<String>().unwrap()).await else {
+            return JsonError::new(ErrorCode::InvalidParams, None, id).into()
+        };
+
+        let priority = match params.get(1) {
+            Some(p) => match Priority::from_str(p.get::<String>().unwrap()) {
+                Ok(v) => v,
+                Err(_) => return JsonError::new(ErrorCode::InvalidParams, None, id).into(),
+            },
+            None => Priority::Normal,
+        };
+
+        let max_size = params.get(2).map(|p| *p.get::<f64>().unwrap() as u64);
+
+        if let Some(m) = self.denylists.check(&file_hash).await {
+            warn!("Refusing to download {}, denylisted by {}", file_hash, m);
+            return rpc_error!(RpcError::Denylisted, id, format!("Denylisted by {}", m))
+        }
+
+        let queued = self.scheduler.enqueue(file_hash, priority, max_size).await;
+        JsonResponse::new(JsonValue::Boolean(queued), id).into()
+    }
+
+    // RPCAPI:
+    // Returns the download scheduler state: its policies and the tracked
+    // downloads, ordered by priority, along with their local names.
+    //
+    // --> {"jsonrpc": "2.0", "method": "downloads", "params": [], "id": 42}
+    // <-- {"jsonrpc": "2.0", "result": {"max_active": 2, "window": "01:00-07:00", "downloads": [{"file_hash": "1211...abfd", "names": ["release"], "priority": "high", "status": "active"}]}, "id": 42}
+    async fn downloads(&self, id: u16, params: JsonValue) -> JsonResult {
+        let params = params.get::<Vec<JsonValue>>().unwrap();
+        if !params.is_empty() {
+            return JsonError::new(ErrorCode::InvalidParams, None, id).into()
+        }
+
+        JsonResponse::new(self.scheduler.json(&self.names).await, id).into()
+    }
+
+    // RPCAPI:
+    // Change the priority of a tracked download. Takes a file hash or
+    // local name and a priority (`low`, `normal` or `high`).
+    // Returns `false` if the file is not tracked.
+    //
+    // --> {"jsonrpc": "2.0", "method": "download_priority", "params": ["1211...abfd", "low"], "id": 42}
+    // <-- {"jsonrpc": "2.0", "result": true, "id": 42}
+    async fn download_priority(&self, id: u16, params: JsonValue) -> JsonResult {
+        let params = params.get::<Vec<JsonValue>>().unwrap();
+        if params.len() != 2 || !params[0].is_string() || !params[1].is_string() {
+            return JsonError::new(ErrorCode::InvalidParams, None, id).into()
+        }
+
+        let Some(file_hash) = self.names.resolve(params[0].get::<String>().unwrap()).await else {
+            return JsonError::new(ErrorCode::InvalidParams, None, id).into()
+        };
+
+        let Ok(priority) = Priority::from_str(params[1].get::<String>().unwrap()) else {
+            return JsonError::new(ErrorCode::InvalidParams, None, id).into()
+        };
+
+        let updated = self.scheduler.set_priority(&file_hash, priority).await;
+        JsonResponse::new(JsonValue::Boolean(updated), id).into()
+    }
+
+    // RPCAPI:
+    // Stop tracking a download. Queued downloads will not be started,
+    // while active ones run to completion.
+    // Returns `false` if the file is not tracked.
+    //
+    // --> {"jsonrpc": "2.0", "method": "download_cancel", "params": ["1211...abfd"], "id": 42}
+    // <-- {"jsonrpc": "2.0", "result": true, "id": 42}
+    async fn download_cancel(&self, id: u16, params: JsonValue) -> JsonResult {
+        let params = params.get::<Vec<JsonValue>>().unwrap();
+        if params.len() != 1 || !params[0].is_string() {
+            return JsonError::new(ErrorCode::InvalidParams, None, id).into()
+        }
+
+        let Some(file_hash) = self.names.resolve(params[0].get::<String>().unwrap()).await else {
+            return JsonError::new(ErrorCode::InvalidParams, None, id).into()
+        };
+
+        let removed = self.scheduler.remove(&file_hash).await;
+        JsonResponse::new(JsonValue::Boolean(removed), id).into()
+    }
+
+    // RPCAPI:
+    // Set the maximum number of concurrently active downloads.
+    // Returns `true` on success.
+    //
+    // --> {"jsonrpc": "2.0", "method": "set_max_downloads", "params": [4], "id": 42}
+    // <-- {"jsonrpc": "2.0", "result": true, "id": 42}
+    async fn set_max_downloads(&self, id: u16, params: JsonValue) -> JsonResult {
+        let params = params.get::<Vec<JsonValue>>().unwrap();
+        if params.len() != 1 || !params[0].is_number() {
+            return JsonError::new(ErrorCode::InvalidParams, None, id).into()
+        }
+
+        let max_downloads = *params[0].get::<f64>().unwrap();
+        if max_downloads < 1.0 || max_downloads.fract() != 0.0 {
+            return JsonError::new(ErrorCode::InvalidParams, None, id).into()
+        }
+
+        self.scheduler.set_max_active(max_downloads as usize);
+        JsonResponse::new(JsonValue::Boolean(true), id).into()
+    }
+
+    // RPCAPI:
+    // Set the daily UTC time window during which downloads may start,
+    // formatted as `HH:MM-HH:MM`. An empty string clears the window,
+    // allowing downloads at any time. Returns `true` on success.
+    //
+    // --> {"jsonrpc": "2.0", "method": "set_download_window", "params": ["01:00-07:00"], "id": 42}
+    // <-- {"jsonrpc": "2.0", "result": true, "id": 42}
+    async fn set_download_window(&self, id: u16, params: JsonValue) -> JsonResult {
+        let params = params.get::<Vec<JsonValue>>().unwrap();
+        if params.len() != 1 || !params[0].is_string() {
+            return JsonError::new(ErrorCode::InvalidParams, None, id).into()
+        }
+
+        let window = params[0].get::<String>().unwrap();
+        let window = if window.is_empty() {
+            None
+        } else {
+            match ScheduleWindow::from_str(window) {
+                Ok(v) => Some(v),
+                Err(_) => return JsonError::new(ErrorCode::InvalidParams, None, id).into(),
+            }
+        };
+
+        self.scheduler.set_window(window).await;
+        JsonResponse::new(JsonValue::Boolean(true), id).into()
+    }
+
+    // RPCAPI:
+    // Returns the swarm of a resource. Takes a file hash or local name as parameter.
+    // Lists the peers known to serve the resource or that we exchanged its
+    // chunks with, along with whether we're currently connected to them,
+    // their client info if connected, how many of the resource's chunks
+    // they have, and the bytes transferred with them and the current
+    // transfer rates in bytes/sec. Chunk counts are only known if we have
+    // the resource metadata. Also returns aggregate swarm size estimates
+    // from the routing tables: the amount of seeders announcing the file,
+    // the amount of known peers, how many of them have all the chunks, and
+    // the availability, being the least amount of peers serving any chunk.
+    //
+    // --> {"jsonrpc": "2.0", "method": "resource.peers", "params": ["1211...abfd"], "id": 42}
+    // <-- {"jsonrpc": "2.0", "result": {"chunks": 4, "swarm": {"seeders": 3, "peers": 5, "complete": 2, "availability": 2}, "peers": [{"address": "tcp://...", "connected": true, "client": {"node_id": "", "version": "0.5.0"}, "chunks": 4, "downloaded": 1048576, "uploaded": 0, "download_rate": 34952.5, "upload_rate": 0.0}]}, "id": 42}
+    async fn resource_peers(&self, id: u16, params: JsonValue) -> JsonResult {
+        let params = params.get::<Vec<JsonValue>>().unwrap();
+        if params.len() != 1 || !params[0].is_string() {
+            return JsonError::new(ErrorCode::InvalidParams, None, id).into()
+        }
+
+        let Some(file_hash) = self.names.resolve(params[0].get::<String>().unwrap()).await else {
+            return JsonError::new(ErrorCode::InvalidParams, None, id).into()
+        };
+
+        // Grab the resource chunks, if we know its metadata
+        let chunk_hashes: Vec<blake3::Hash> = match self.geode.get(&file_hash).await {
+            Ok(chunked_file) => chunked_file.iter().map(|(h, _)| *h).collect(),
+            Err(_) => vec![],
+        };
+
+        let seeders =
+            self.metadata_router.read().await.get(&file_hash).cloned().unwrap_or_default();
+
+        // Count the resource chunks each known peer serves
+        let mut peer_chunks: HashMap<Url, usize> = HashMap::new();
+        let mut availability = None;
+        let chunks_router = self.chunks_router.read().await;
+        for chunk_hash in &chunk_hashes {
+            let peers = chunks_router.get(chunk_hash);
+            let count = peers.map_or(0, |p| p.len());
+            availability = Some(availability.map_or(count, |a: usize| a.min(count)));
+            for peer in peers.into_iter().flatten() {
+                *peer_chunks.entry(peer.clone()).or_default() += 1;
+            }
+        }
+        drop(chunks_router);
+
+        let chunk_set: HashSet<blake3::Hash> = chunk_hashes.iter().copied().collect();
+        let mut transfers = self.swarm.resource_transfers(&chunk_set).await;
+        for peer in seeders.iter().chain(transfers.keys()) {
+            peer_chunks.entry(peer.clone()).or_default();
+        }
+
+        let mut channels: HashMap<Url, ChannelPtr> = HashMap::new();
+        for channel in self.p2p.hosts().peers() {
+            channels.insert(replication::peer_id(&channel).await, channel);
+        }
+
+        let mut complete = 0;
+        let mut peers = vec![];
+        for (peer, chunks) in peer_chunks {
+            if !chunk_hashes.is_empty() && chunks == chunk_hashes.len() {
+                complete += 1;
+            }
+
+            let channel = channels.get(&peer);
+            let client = match channel {
+                Some(c) => match c.version.lock().await.as_ref() {
+                    Some(v) => JsonValue::Object(HashMap::from([
+                        ("node_id".to_string(), JsonValue::String(v.node_id.clone())),
+                        ("version".to_string(), JsonValue::String(v.version.to_string())),
+                    ])),
+                    None => JsonValue::Null,
+                },
+                None => JsonValue::Null,
+            };
+
+            let stats = transfers.remove(&peer).unwrap_or_default();
+            peers.push(JsonValue::Object(HashMap::from([
+                ("address".to_string(), JsonValue::String(peer.to_string())),
+                ("connected".to_string(), JsonValue::Boolean(channel.is_some())),
+                ("client".to_string(), client),
+                ("chunks".to_string(), JsonValue::Number(chunks as f64)),
+                ("downloaded".to_string(), JsonValue::Number(stats.downloaded as f64)),
+                ("uploaded".to_string(), JsonValue::Number(stats.uploaded as f64)),
+                ("download_rate".to_string(), JsonValue::Number(stats.download_rate)),
+                ("upload_rate".to_string(), JsonValue::Number(stats.upload_rate)),
+            ])));
+        }
+
+        let swarm = JsonValue::Object(HashMap::from([
+            ("seeders".to_string(), JsonValue::Number(seeders.len() as f64)),
+            ("peers".to_string(), JsonValue::Number(peers.len() as f64)),
+            ("complete".to_string(), JsonValue::Number(complete as f64)),
+            (
+                "availability".to_string(),
+                match availability {
+                    Some(a) => JsonValue::Number(a as f64),
+                    None => JsonValue::Null,
+                },
+            ),
+        ]));
+
+        let result = JsonValue::Object(HashMap::from([
+            ("chunks".to_string(), JsonValue::Number(chunk_hashes.len() as f64)),
+            ("swarm".to_string(), swarm),
+            ("peers".to_string(), JsonValue::Array(peers)),
+        ]));
+
+        JsonResponse::new(result, id).into()
+    }
+
+    // RPCAPI:
+    // Returns the publisher public key that signed the metadata of a
+    // file, or `null` if the file metadata we hold is unsigned.
+    //
+    // --> {"jsonrpc": "2.0", "method": "resource.publisher", "params": ["1211...abfd"], "id": 42}
+    // <-- {"jsonrpc": "2.0", "result": "8sRw...9Lq1", "id": 42}
+    async fn resource_publisher(&self, id: u16, params: JsonValue) -> JsonResult {
+        let params = params.get::<Vec<JsonValue>>().unwrap();
+        if params.len() != 1 || !params[0].is_string() {
+            return JsonError::new(ErrorCode::InvalidParams, None, id).into()
+        }
+
+        let Some(file_hash) = self.names.resolve(params[0].get::<String>().unwrap()).await else {
+            return JsonError::new(ErrorCode::InvalidParams, None, id).into()
+        };
+
+        let result = match self.publishers.get(&file_hash).await {
+            Some(p) => JsonValue::String(p.public_key.to_string()),
+            None => JsonValue::Null,
+        };
+
+        JsonResponse::new(result, id).into()
+    }
+
+    // RPCAPI:
+    // Attach a local name to a resource. Takes a file hash, or a name
+    // already pointing to one, and the new name. Names can't contain
+    // whitespace, and can be used anywhere a file hash is accepted.
+    // Setting an existing name points it to the new resource.
+    // Returns `true` on success.
+    //
+    // --> {"jsonrpc": "2.0", "method": "name.set", "params": ["1211...abfd", "release"], "id": 42}
+    // <-- {"jsonrpc": "2.0", "result": true, "id": 42}
+    async fn name_set(&self, id: u16, params: JsonValue) -> JsonResult {
+        let params = params.get::<Vec<JsonValue>>().unwrap();
+        if params.len() != 2 || !params[0].is_string() || !params[1].is_string() {
+            return JsonError::new(ErrorCode::InvalidParams, None, id).into()
+        }
+
+        let Some(file_hash) = self.names.resolve(params[0].get::<String>().unwrap()).await else {
+            return JsonError::new(ErrorCode::InvalidParams, None, id).into()
+        };
+
+        let name = params[1].get::<String>().unwrap();
+        if !names::valid_name(name) {
+            return JsonError::new(ErrorCode::InvalidParams, None, id).into()
+        }
+
+        if let Err(e) = self.names.set(name, file_hash).await {
+            error!("Failed storing name {} of {}: {}", name, file_hash, e);
+            return rpc_error!(RpcError::NameStoreFailed, id)
+        }
+
+        JsonResponse::new(JsonValue::Boolean(true), id).into()
+    }
+
+    // RPCAPI:
+    // Remove a local resource name.
+    // Returns `false` if the name doesn't exist.
+    //
+    // --> {"jsonrpc": "2.0", "method": "name.remove", "params": ["release"], "id": 42}
+    // <-- {"jsonrpc": "2.0", "result": true, "id": 42}
+    async fn name_remove(&self, id: u16, params: JsonValue) -> JsonResult {
+        let params = params.get::<Vec<JsonValue>>().unwrap();
+        if params.len() != 1 || !params[0].is_string() {
+            return JsonError::new(ErrorCode::InvalidParams, None, id).into()
+        }
+
+        let name = params[0].get::<String>().unwrap();
+        match self.names.remove(name).await {
+            Ok(removed) => JsonResponse::new(JsonValue::Boolean(removed), id).into(),
+            Err(e) => {
+                error!("Failed removing name {}: {}", name, e);
+                rpc_error!(RpcError::NameStoreFailed, id)
+            }
         }
     }
 
-    async fn connections_mut(&self) -> MutexGuard<'_, HashSet<StoppableTaskPtr>> {
-        self.rpc_connections.lock().await
+    // RPCAPI:
+    // Returns the local resource names and the file hashes they point to.
+    //
+    // --> {"jsonrpc": "2.0", "method": "names", "params": [], "id": 42}
+    // <-- {"jsonrpc": "2.0", "result": {"release": "1211...abfd"}, "id": 42}
+    async fn names(&self, id: u16, params: JsonValue) -> JsonResult {
+        let params = params.get::<Vec<JsonValue>>().unwrap();
+        if !params.is_empty() {
+            return JsonError::new(ErrorCode::InvalidParams, None, id).into()
+        }
+
+        JsonResponse::new(self.names.json().await, id).into()
     }
-}
 
-impl Fud {
     // RPCAPI:
-    // Put a file onto the network. Takes a local filesystem path as a parameter.
-    // Returns the file hash that serves as a pointer to the uploaded file.
+    // Generate the integrity report of a complete file, signed with our
+    // publisher key if configured, and write it to the reports directory.
+    // Takes a file hash or local name. An existing report is replaced,
+    // keeping its completion time and seeders. Returns the report.
     //
-    // --> {"jsonrpc": "2.0", "method": "put", "params": ["/foo.txt"], "id": 42}
-    // <-- {"jsonrpc": "2.0", "result: "df4...3db7", "id": 42}
-    async fn put(&self, id: u16, params: JsonValue) -> JsonResult {
+    // --> {"jsonrpc": "2.0", "method": "report.generate", "params": ["1211...abfd"], "id": 42}
+    // <-- {"jsonrpc": "2.0", "result": {"file_hash": "1211...abfd", "size": 524288, "chunks": [{"hash": "fab1...2314", "size": 262144}, ...], "completed": 1700000000, "seeders": [{"peer": "tcp://...", "downloaded": 524288}], "publisher": "8sRw...9Lq1", "signature": "0a3f...91c2"}, "id": 42}
+    async fn report_generate(&self, id: u16, params: JsonValue) -> JsonResult {
         let params = params.get::<Vec<JsonValue>>().unwrap();
         if params.len() != 1 || !params[0].is_string() {
             return JsonError::new(ErrorCode::InvalidParams, None, id).into()
         }
 
-        let path = params[0].get::<String>().unwrap();
-        let path = match expand_path(path.as_str()) {
-            Ok(v) => v,
-            Err(_) => return JsonError::new(ErrorCode::InvalidParams, None, id).into(),
-        };
-
-        // A valid path was passed. Let's see if we can read it, and if so,
-        // add it to Geode.
-        let fd = match File::open(&path).await {
-            Ok(v) => v,
-            Err(e) => {
-                error!("Failed to open {:?}: {}", path, e);
-                return JsonError::new(ErrorCode::InvalidParams, None, id).into()
-            }
+        let Some(file_hash) = self.names.resolve(params[0].get::<String>().unwrap()).await else {
+            return JsonError::new(ErrorCode::InvalidParams, None, id).into()
         };
 
-        let (file_hash, chunk_hashes) = match self.geode.insert(fd).await {
-            Ok(v) => v,
+        match report::generate_report(self, &file_hash, None, None).await {
+            Ok(report) => JsonResponse::new(report.json(), id).into(),
             Err(e) => {
-                error!("Failed inserting file {:?} to geode: {}", path, e);
-                return JsonError::new(ErrorCode::InternalError, None, id).into()
+                error!("Failed generating report of {}: {}", file_hash, e);
+                rpc_error!(RpcError::ReportFailed, id, e.to_string())
             }
-        };
-
-        let fud_file = FudFilePut { file_hash, chunk_hashes };
-        self.p2p.broadcast(&fud_file).await;
-
-        JsonResponse::new(JsonValue::String(file_hash.to_hex().to_string()), id).into()
+        }
     }
 
     // RPCAPI:
-    // Fetch a file from the network. Takes a file hash as parameter.
-    // Returns the paths to the local chunks of the file, if found/fetched.
+    // Verify the stored integrity report of a file against its signature
+    // and the chunks we currently hold. Takes a file hash or local name.
+    // Returns whether the report is valid, its signer, if any, and the
+    // problems found. Unsigned reports are never valid.
     //
-    // --> {"jsonrpc": "2.0", "method": "get", "params": ["1211...abfd"], "id": 42}
-    // <-- {"jsonrpc": "2.0", "result: ["~/.local/share/darkfi/fud/chunks/fab1...2314", ...], "id": 42}
-    async fn get(&self, id: u16, params: JsonValue) -> JsonResult {
+    // --> {"jsonrpc": "2.0", "method": "report.verify", "params": ["1211...abfd"], "id": 42}
+    // <-- {"jsonrpc": "2.0", "result": {"valid": true, "publisher": "8sRw...9Lq1", "errors": []}, "id": 42}
+    async fn report_verify(&self, id: u16, params: JsonValue) -> JsonResult {
         let params = params.get::<Vec<JsonValue>>().unwrap();
         if params.len() != 1 || !params[0].is_string() {
             return JsonError::new(ErrorCode::InvalidParams, None, id).into()
         }
 
-        let file_hash = match blake3::Hash::from_hex(params[0].get::<String>().unwrap()) {
-            Ok(v) => v,
-            Err(_) => return JsonError::new(ErrorCode::InvalidParams, None, id).into(),
+        let Some(file_hash) = self.names.resolve(params[0].get::<String>().unwrap()).await else {
+            return JsonError::new(ErrorCode::InvalidParams, None, id).into()
         };
 
-        let chunked_file = match self.geode.get(&file_hash).await {
-            Ok(v) => v,
-            Err(Error::GeodeNeedsGc) => todo!(),
-            Err(Error::GeodeFileNotFound) => {
-                info!("Requested file {} not found in Geode, triggering fetch", file_hash);
-                self.file_fetch_tx.send((file_hash, Ok(()))).await.unwrap();
-                info!("Waiting for background file fetch task...");
-                let (i_file_hash, status) = self.file_fetch_rx.recv().await.unwrap();
-                match status {
-                    Ok(()) => {
-                        let ch_file = self.geode.get(&file_hash).await.unwrap();
-
-                        let m = FudFilePut {
-                            file_hash: i_file_hash,
-                            chunk_hashes: ch_file.iter().map(|(h, _)| *h).collect(),
-                        };
+        let report = match self.reports.get(&file_hash).await {
+            Ok(Some(v)) => v,
+            Ok(None) => return rpc_error!(RpcError::ReportNotFound, id),
+            Err(e) => return rpc_error!(RpcError::ReportFailed, id, e.to_string()),
+        };
 
-                        self.p2p.broadcast(&m).await;
+        let errors = report::verify_report(self, &report).await;
+        let publisher = match &report.signature {
+            Some(s) => JsonValue::String(s.public_key.to_string()),
+            None => JsonValue::Null,
+        };
 
-                        ch_file
-                    }
+        let result = JsonValue::Object(HashMap::from([
+            ("valid".to_string(), JsonValue::Boolean(errors.is_empty())),
+            ("publisher".to_string(), publisher),
+            (
+                "errors".to_string(),
+                JsonValue::Array(errors.into_iter().map(JsonValue::String).collect()),
+            ),
+        ]));
 
-                    Err(Error::GeodeFileRouteNotFound) => {
-                        // TODO: Return FileNotFound error
-                        return JsonError::new(ErrorCode::InternalError, None, id).into()
-                    }
+        JsonResponse::new(result, id).into()
+    }
 
-                    Err(e) => panic!("{}", e),
-                }
-            }
+    // RPCAPI:
+    // Returns the subscribed denylists we hold, with their moderator,
+    // name, version and amount of denied hashes.
+    //
+    // --> {"jsonrpc": "2.0", "method": "denylists", "params": [], "id": 42}
+    // <-- {"jsonrpc": "2.0", "result": [{"moderator": "8sRw...9Lq1", "name": "abuse", "version": 3, "hashes": 120}], "id": 42}
+    async fn denylists(&self, id: u16, params: JsonValue) -> JsonResult {
+        let params = params.get::<Vec<JsonValue>>().unwrap();
+        if !params.is_empty() {
+            return JsonError::new(ErrorCode::InvalidParams, None, id).into()
+        }
 
-            Err(e) => panic!("{}", e),
-        };
+        let lists = self
+            .denylists
+            .lists()
+            .await
+            .into_iter()
+            .map(|list| {
+                JsonValue::Object(HashMap::from([
+                    ("moderator".to_string(), JsonValue::String(list.moderator.to_string())),
+                    ("name".to_string(), JsonValue::String(list.name)),
+                    ("version".to_string(), JsonValue::Number(list.version as f64)),
+                    ("hashes".to_string(), JsonValue::Number(list.hashes.len() as f64)),
+                ]))
+            })
+            .collect();
 
-        if chunked_file.is_complete() {
-            let chunks: Vec<JsonValue> = chunked_file
-                .iter()
-                .map(|(_, path)| {
-                    JsonValue::String(
-                        path.as_ref().unwrap().clone().into_os_string().into_string().unwrap(),
-                    )
-                })
-                .collect();
+        JsonResponse::new(JsonValue::Array(lists), id).into()
+    }
 
-            return JsonResponse::new(JsonValue::Array(chunks), id).into()
+    // RPCAPI:
+    // Check whether a file or chunk hash is denylisted. Returns the
+    // moderator and name of the matching list, or `null` if none matches.
+    //
+    // --> {"jsonrpc": "2.0", "method": "denylist.check", "params": ["1211...abfd"], "id": 42}
+    // <-- {"jsonrpc": "2.0", "result": {"moderator": "8sRw...9Lq1", "name": "abuse"}, "id": 42}
+    async fn denylist_check(&self, id: u16, params: JsonValue) -> JsonResult {
+        let params = params.get::<Vec<JsonValue>>().unwrap();
+        if params.len() != 1 || !params[0].is_string() {
+            return JsonError::new(ErrorCode::InvalidParams, None, id).into()
         }
 
-        // Fetch any missing chunks
-        let mut missing_chunks = vec![];
-        for (chunk, path) in chunked_file.iter() {
-            if path.is_none() {
-                missing_chunks.push(*chunk);
-            }
-        }
+        let Some(hash) = self.names.resolve(params[0].get::<String>().unwrap()).await else {
+            return JsonError::new(ErrorCode::InvalidParams, None, id).into()
+        };
+
+        let result = match self.denylists.check(&hash).await {
+            Some(m) => JsonValue::Object(HashMap::from([
+                ("moderator".to_string(), JsonValue::String(m.moderator.to_string())),
+                ("name".to_string(), JsonValue::String(m.name)),
+            ])),
+            None => JsonValue::Null,
+        };
 
-        for chunk in missing_chunks {
-            self.chunk_fetch_tx.send((chunk, Ok(()))).await.unwrap();
-            let (i_chunk_hash, status) = self.chunk_fetch_rx.recv().await.unwrap();
+        JsonResponse::new(result, id).into()
+    }
 
-            match status {
-                Ok(()) => {
-                    let m = FudChunkPut { chunk_hash: i_chunk_hash };
-                    self.p2p.broadcast(&m).await;
-                    break
-                }
-                Err(Error::GeodeChunkRouteNotFound) => continue,
+    // RPCAPI:
+    // Publish a new version of our denylist, signed with the configured
+    // moderator key. Takes the list name and the denied hashes, replacing
+    // the previous version. Returns the published version.
+    //
+    // --> {"jsonrpc": "2.0", "method": "denylist.publish", "params": ["abuse", ["1211...abfd", ...]], "id": 42}
+    // <-- {"jsonrpc": "2.0", "result": 4, "id": 42}
+    async fn denylist_publish(&self, id: u16, params: JsonValue) -> JsonResult {
+        let params = params.get::<Vec<JsonValue>>().unwrap();
+        if params.len() != 2 || !params[0].is_string() || !params[1].is_array() {
+            return JsonError::new(ErrorCode::InvalidParams, None, id).into()
+        }
 
-                Err(e) => panic!("{}", e),
-            }
+        let name = params[0].get::<String>().unwrap();
+        let mut hashes = vec![];
+        for hash in params[1].get::<Vec<JsonValue>>().unwrap() {
+            let Some(hash) = hash.get::<String>() else {
+                return JsonError::new(ErrorCode::InvalidParams, None, id).into()
+            };
+            let Ok(hash) = blake3::Hash::from_hex(hash) else {
+                return JsonError::new(ErrorCode::InvalidParams, None, id).into()
+            };
+            hashes.push(hash);
         }
 
-        let chunked_file = match self.geode.get(&file_hash).await {
-            Ok(v) => v,
-            Err(e) => panic!("{}", e),
+        let denylist = match self.denylists.publish(name, hashes).await {
+            Ok(Some(v)) => v,
+            Ok(None) => return rpc_error!(RpcError::NotModerator, id),
+            Err(e) => {
+                error!("Failed storing published denylist: {}", e);
+                return JsonError::new(ErrorCode::InternalError, None, id).into()
+            }
         };
 
-        if !chunked_file.is_complete() {
-            todo!();
-            // Return JsonError missing chunks
-        }
-
-        let chunks: Vec<JsonValue> = chunked_file
-            .iter()
-            .map(|(_, path)| {
-                JsonValue::String(
-                    path.as_ref().unwrap().clone().into_os_string().into_string().unwrap(),
-                )
-            })
-            .collect();
+        let version = denylist.version;
+        self.p2p.broadcast(&FudDenylistPut { denylist }).await;
 
-        JsonResponse::new(JsonValue::Array(chunks), id).into()
+        JsonResponse::new(JsonValue::Number(version as f64), id).into()
     }
 
     // RPCAPI:
@@ -304,227 +1263,144 @@ impl Fud {
     }
 }
 
-/// Background task that receives file fetch requests and tries to
-/// fetch objects from the network using the routing table.
-/// TODO: This can be optimised a lot for connection reuse, etc.
+/// Background task that receives file fetch requests and spawns a fetch
+/// for each of them. The amount of concurrent fetches is bounded by the
+/// fetch semaphore.
 async fn fetch_file_task(fud: Arc<Fud>, executor: Arc<Executor<'_>>) -> Result<()> {
     info!("Started background file fetch task");
     loop {
-        let (file_hash, _) = fud.file_fetch_rx.recv().await.unwrap();
+        let Ok((file_hash, reply)) = fud.file_fetch_rx.recv().await else {
+            return Err(Error::DetachedTaskStopped)
+        };
         info!("fetch_file_task: Received {}", file_hash);
 
-        let mut metadata_router = fud.metadata_router.write().await;
-        let peers = metadata_router.get_mut(&file_hash);
-
-        if peers.is_none() {
-            warn!("File {} not in routing table, cannot fetch", file_hash);
-            fud.file_fetch_tx.send((file_hash, Err(Error::GeodeFileRouteNotFound))).await.unwrap();
-            continue
-        }
-
-        let mut found = false;
-        let peers = peers.unwrap();
-        let mut invalid_file_routes = vec![];
-
-        for peer in peers.iter() {
-            let session_out = fud.p2p.session_outbound();
-            let session_weak = Arc::downgrade(&fud.p2p.session_outbound());
-
-            info!("Connecting to {} to fetch {}", peer, file_hash);
-            let connector = Connector::new(fud.p2p.settings(), session_weak);
-            match connector.connect(peer).await {
-                Ok((url, channel)) => {
-                    let proto_ver = ProtocolVersion::new(
-                        channel.clone(),
-                        fud.p2p.settings().clone(),
-                        fud.p2p.hosts().clone(),
-                    )
-                    .await;
-
-                    let handshake_task = session_out.perform_handshake_protocols(
-                        proto_ver,
-                        channel.clone(),
-                        executor.clone(),
-                    );
-
-                    channel.clone().start(executor.clone());
-
-                    if let Err(e) = handshake_task.await {
-                        error!("Handshake with {} failed: {}", url, e);
-                        // Delete peer from router
-                        invalid_file_routes.push(peer.clone());
-                        continue
-                    }
-
-                    let msg_subscriber = channel.subscribe_msg::<FudFileReply>().await.unwrap();
-                    let request = FudFileRequest { file_hash };
-
-                    if let Err(e) = channel.send(&request).await {
-                        error!("Failed sending FudFileRequest({}) to {}: {}", file_hash, url, e);
-                        continue
-                    }
-
-                    // TODO: With timeout!
-                    let reply = match msg_subscriber.receive().await {
-                        Ok(v) => v,
-                        Err(e) => {
-                            error!("Error receiving FudFileReply from subscriber: {}", e);
-                            continue
-                        }
-                    };
-
-                    msg_subscriber.unsubscribe().await;
-                    channel.stop().await;
-
-                    if let Err(e) = fud.geode.insert_file(&file_hash, &reply.chunk_hashes).await {
-                        error!("Failed inserting file {} to Geode: {}", file_hash, e);
-                        continue
-                    }
-
-                    found = true;
-                    break
-                }
-
-                Err(e) => {
-                    error!("Failed to connect to {}: {}", peer, e);
-                    continue
-                }
-            }
-        }
-
-        for peer in invalid_file_routes {
-            debug!("Removing peer {} from {} file router", peer, file_hash);
-            peers.remove(&peer);
-        }
+        // Wait for a free fetch slot before spawning the fetch
+        let permit = fud.fetch_semaphore.acquire().await;
+        let fud_ = fud.clone();
+        let executor_ = executor.clone();
+        executor
+            .spawn(async move {
+                let status = fetch_file(&fud_, &executor_, file_hash).await;
+                drop(permit);
+                // The requester may have gone away in the meantime
+                let _ = reply.send(status).await;
+            })
+            .detach();
+    }
+}
 
-        if !found {
-            warn!("Did not manage to fetch {} file metadata", file_hash);
-            fud.file_fetch_tx.send((file_hash, Err(Error::GeodeFileRouteNotFound))).await.unwrap();
-            continue
+/// Try to fetch a file's metadata from the network and insert it into Geode.
+async fn fetch_file(
+    fud: &Fud,
+    executor: &Arc<Executor<'_>>,
+    file_hash: blake3::Hash,
+) -> Result<()> {
+    let transport = P2pTransport::new(fud, executor);
+    let chunk_hashes = match transport.fetch_metadata(&file_hash).await {
+        Ok(v) => v,
+        Err(e) => {
+            warn!("Did not manage to fetch {} file metadata: {}", file_hash, e);
+            return Err(Error::GeodeFileRouteNotFound)
         }
+    };
 
-        info!("Successfully fetched {} file metadata", file_hash);
-        fud.file_fetch_tx.send((file_hash, Ok(()))).await.unwrap();
+    if let Err(e) = fud.geode.insert_file(&file_hash, &chunk_hashes).await {
+        error!("Failed inserting file {} to Geode: {}", file_hash, e);
+        return Err(Error::GeodeFileRouteNotFound)
     }
+
+    info!("Successfully fetched {} file metadata", file_hash);
+    Ok(())
 }
 
-/// Background task that receives chunk fetch requests and tries to
-/// fetch objects from the network using the routing table.
-/// TODO: This can be optimised a lot for connection reuse, etc.
+/// Background task that receives chunk fetch requests and spawns a fetch
+/// for each of them. The amount of concurrent fetches is bounded by the
+/// fetch semaphore.
 async fn fetch_chunk_task(fud: Arc<Fud>, executor: Arc<Executor<'_>>) -> Result<()> {
     info!("Started background chunk fetch task");
     loop {
-        let (chunk_hash, _) = fud.chunk_fetch_rx.recv().await.unwrap();
+        let Ok((chunk_hash, reply)) = fud.chunk_fetch_rx.recv().await else {
+            return Err(Error::DetachedTaskStopped)
+        };
         info!("fetch_chunk_task: Received {}", chunk_hash);
 
-        let mut chunk_router = fud.chunks_router.write().await;
-        let peers = chunk_router.get_mut(&chunk_hash);
-
-        if peers.is_none() {
-            warn!("Chunk {} not in routing table, cannot fetch", chunk_hash);
-            fud.chunk_fetch_tx
-                .send((chunk_hash, Err(Error::GeodeChunkRouteNotFound)))
-                .await
-                .unwrap();
-            continue
-        }
-
-        let mut found = false;
-        let peers = peers.unwrap();
-        let mut invalid_chunk_routes = vec![];
-
-        for peer in peers.iter() {
-            let session_out = fud.p2p.session_outbound();
-            let session_weak = Arc::downgrade(&fud.p2p.session_outbound());
-
-            info!("Connecting to {} to fetch {}", peer, chunk_hash);
-            let connector = Connector::new(fud.p2p.settings(), session_weak);
-            match connector.connect(peer).await {
-                Ok((url, channel)) => {
-                    let proto_ver = ProtocolVersion::new(
-                        channel.clone(),
-                        fud.p2p.settings().clone(),
-                        fud.p2p.hosts().clone(),
-                    )
-                    .await;
-
-                    let handshake_task = session_out.perform_handshake_protocols(
-                        proto_ver,
-                        channel.clone(),
-                        executor.clone(),
-                    );
-
-                    channel.clone().start(executor.clone());
-
-                    if let Err(e) = handshake_task.await {
-                        error!("Handshake with {} failed: {}", url, e);
-                        // Delete peer from router
-                        invalid_chunk_routes.push(peer.clone());
-                        continue
-                    }
-
-                    let msg_subscriber = channel.subscribe_msg::<FudChunkReply>().await.unwrap();
-                    let request = FudChunkRequest { chunk_hash };
+        // Wait for a free fetch slot before spawning the fetch
+        let permit = fud.fetch_semaphore.acquire().await;
+        let fud_ = fud.clone();
+        let executor_ = executor.clone();
+        executor
+            .spawn(async move {
+                let status = fetch_chunk(&fud_, &executor_, chunk_hash).await;
+                drop(permit);
+                // The requester may have gone away in the meantime
+                let _ = reply.send(status).await;
+            })
+            .detach();
+    }
+}
 
-                    if let Err(e) = channel.send(&request).await {
-                        error!("Failed sending FudChunkRequest({}) to {}: {}", chunk_hash, url, e);
-                        continue
-                    }
+/// Try to fetch a chunk from the network and insert it into Geode.
+async fn fetch_chunk(
+    fud: &Fud,
+    executor: &Arc<Executor<'_>>,
+    chunk_hash: blake3::Hash,
+) -> Result<()> {
+    let transport = P2pTransport::new(fud, executor);
+    let chunk = match transport.fetch_chunk(&chunk_hash).await {
+        Ok(v) => v,
+        Err(e) => {
+            warn!("Did not manage to fetch {} chunk: {}", chunk_hash, e);
+            return Err(Error::GeodeChunkRouteNotFound)
+        }
+    };
 
-                    // TODO: With timeout!
-                    let reply = match msg_subscriber.receive().await {
-                        Ok(v) => v,
-                        Err(e) => {
-                            error!("Error receiving FudChunkReply from subscriber: {}", e);
-                            continue
-                        }
-                    };
-
-                    msg_subscriber.unsubscribe().await;
-                    channel.stop().await;
-
-                    match fud.geode.insert_chunk(&reply.chunk).await {
-                        Ok(inserted_hash) => {
-                            if inserted_hash != chunk_hash {
-                                warn!("Received chunk does not match requested chunk");
-                                invalid_chunk_routes.push(peer.clone());
-                                continue
-                            }
-                        }
-                        Err(e) => {
-                            error!("Failed inserting chunk {} to Geode: {}", chunk_hash, e);
-                            continue
-                        }
-                    }
+    if let Err(e) = fud.geode.insert_chunk(&chunk).await {
+        error!("Failed inserting chunk {} to Geode: {}", chunk_hash, e);
+        return Err(Error::GeodeChunkRouteNotFound)
+    }
 
-                    found = true;
-                    break
-                }
+    info!("Successfully fetched {} chunk", chunk_hash);
+    Ok(())
+}
 
-                Err(e) => {
-                    error!("Failed to connect to {}: {}", peer, e);
-                    continue
-                }
-            }
-        }
+/// Try to fetch a set of chunks from the network, pipelining the requests
+/// to each seeder, and insert them into Geode. Returns the hashes of the
+/// inserted chunks along with the bytes each seeder served, or an error
+/// if any of them failed to be inserted.
+async fn fetch_chunks(
+    fud: &Fud,
+    executor: &Arc<Executor<'_>>,
+    chunk_hashes: &[blake3::Hash],
+) -> Result<(Vec<blake3::Hash>, HashMap<Url, u64>)> {
+    let transport = P2pTransport::new(fud, executor);
+    let mut seeders: HashMap<Url, u64> = HashMap::new();
+    let mut chunks = HashMap::new();
+    for (chunk_hash, (peer, chunk)) in transport.fetch_chunks(chunk_hashes).await {
+        *seeders.entry(peer).or_default() += chunk.len() as u64;
+        chunks.insert(chunk_hash, chunk);
+    }
+    let inserted = insert_chunks(&fud.geode, chunks).await?;
 
-        for peer in invalid_chunk_routes {
-            debug!("Removing peer {} from {} chunk router", peer, chunk_hash);
-            peers.remove(&peer);
-        }
+    info!("Successfully fetched {} of {} chunks", inserted.len(), chunk_hashes.len());
+    Ok((inserted, seeders))
+}
 
-        if !found {
-            warn!("Did not manage to fetch {} chunk", chunk_hash);
-            fud.chunk_fetch_tx
-                .send((chunk_hash, Err(Error::GeodeChunkRouteNotFound)))
-                .await
-                .unwrap();
-            continue
+/// Insert fetched chunks into Geode, returning their hashes.
+/// Fails on the first chunk that can't be inserted.
+async fn insert_chunks(
+    geode: &Geode,
+    chunks: HashMap<blake3::Hash, Vec<u8>>,
+) -> Result<Vec<blake3::Hash>> {
+    let mut inserted = Vec::with_capacity(chunks.len());
+    for (chunk_hash, chunk) in chunks {
+        if let Err(e) = geode.insert_chunk(&chunk).await {
+            error!("Failed inserting chunk {} to Geode: {}", chunk_hash, e);
+            return Err(e)
         }
-
-        info!("Successfully fetched {} chunk", chunk_hash);
-        fud.chunk_fetch_tx.send((chunk_hash, Ok(()))).await.unwrap();
+        inserted.push(chunk_hash);
     }
+
+    Ok(inserted)
 }
 
 async_daemonize!(realmain);
@@ -536,25 +1412,86 @@ async fn realmain(args: Args, ex: Arc<Executor<'static>>) -> Result<()> {
     let metadata_router = Arc::new(RwLock::new(HashMap::new()));
     let chunks_router = Arc::new(RwLock::new(HashMap::new()));
 
-    info!("Instantiating Geode instance");
-    let geode = Geode::new(&basedir).await?;
+    // Every node of the swarm has to use the same hash backend
+    let hash_algorithm: HashAlgorithm = args.hash_algorithm.parse()?;
+    let hash_key = match args.hash_key.as_deref().map(blake3::Hash::from_hex) {
+        Some(Ok(key)) => Some(*key.as_bytes()),
+        Some(Err(_)) => return Err(Error::ParseFailed("Invalid hash key")),
+        None => None,
+    };
+    let hash_backend = hash_algorithm.backend(hash_key)?;
+
+    // Browser clients only verify plain BLAKE3 content
+    if args.bridge_listen.is_some() && hash_algorithm != HashAlgorithm::Blake3 {
+        return Err(Error::ParseFailed("Websocket bridge requires the blake3 hash algorithm"))
+    }
+
+    info!("Instantiating Geode instance using {} hashes", hash_algorithm);
+    let geode = Geode::with_hash_backend(&basedir, hash_backend).await?;
 
     info!("Instantiating P2P network");
     let p2p = P2p::new(args.net.into(), ex.clone()).await;
 
+    // Parse the download scheduling policies
+    if args.max_downloads == 0 {
+        return Err(Error::ParseFailed("Max downloads must be greater than zero"))
+    }
+    if args.max_fetches == 0 {
+        return Err(Error::ParseFailed("Max fetches must be greater than zero"))
+    }
+    let download_window = scheduler::parse_window(&args.download_window)?;
+
+    // Remote control of the seedbox must be authenticated
+    if args.seedbox_rpc_listen.is_some() && args.seedbox_token.as_deref().unwrap_or("").is_empty() {
+        return Err(Error::ParseFailed("Seedbox JSON-RPC requires a seedbox token"))
+    }
+
+    // Parse the publisher keys
+    let publishers =
+        Publishers::new(&basedir, args.publisher_key.as_deref(), &args.trusted_publishers).await?;
+    if let Some(public_key) = publishers.public_key() {
+        info!(target: "fud", "Signing inserted files as publisher {}", public_key);
+    }
+
+    // Load the subscribed denylists
+    let denylists =
+        Denylists::new(&basedir, args.moderator_key.as_deref(), &args.denylist_subscriptions)
+            .await?;
+    for moderator in denylists.subscriptions() {
+        info!(target: "fud", "Enforcing denylist of moderator {}", moderator);
+    }
+
     // Daemon instantiation
     let (file_fetch_tx, file_fetch_rx) = smol::channel::unbounded();
     let (chunk_fetch_tx, chunk_fetch_rx) = smol::channel::unbounded();
     let fud = Arc::new(Fud {
         metadata_router,
         chunks_router,
+        chunk_route_sources: RwLock::new(HashMap::new()),
         p2p: p2p.clone(),
         geode,
         file_fetch_tx,
         file_fetch_rx,
         chunk_fetch_tx,
         chunk_fetch_rx,
+        fetch_semaphore: Semaphore::new(args.max_fetches),
+        chunk_window: args.chunk_window,
+        scheduler: Scheduler::new(args.max_downloads, download_window),
+        download_template: args.download_template,
+        seedbox: args.seedbox,
+        seedbox_token: args.seedbox_token,
+        max_storage: AtomicU64::new(args.max_storage * 1024 * 1024),
+        uploads: Uploads::default(),
+        swarm: SwarmStats::new(),
+        publishers,
+        denylists,
+        names: Names::new(&basedir).await?,
+        reports: Reports::new(&basedir, args.download_reports).await?,
+        replicas: Replicas::new(args.replication_factor),
+        channels: FetchChannels::default(),
+        lan: LanDiscovery::new(),
         rpc_connections: Mutex::new(HashSet::new()),
+        seedbox_rpc_connections: Mutex::new(HashSet::new()),
     });
 
     info!(target: "fud", "Starting fetch file task");
@@ -585,14 +1522,115 @@ async fn realmain(args: Args, ex: Arc<Executor<'static>>) -> Result<()> {
         ex.clone(),
     );
 
+    info!(target: "fud", "Starting download scheduler task");
+    let scheduler_task = StoppableTask::new();
+    scheduler_task.clone().start(
+        scheduler::scheduler_task(fud.clone(), ex.clone()),
+        |res| async {
+            match res {
+                Ok(()) | Err(Error::DetachedTaskStopped) => { /* Do nothing */ }
+                Err(e) => error!(target: "fud", "Failed starting download scheduler task: {}", e),
+            }
+        },
+        Error::DetachedTaskStopped,
+        ex.clone(),
+    );
+
+    info!(target: "fud", "Starting chunks watch task");
+    let watch_task = StoppableTask::new();
+    watch_task.clone().start(
+        watch::watch_task(fud.clone()),
+        |res| async {
+            match res {
+                Ok(()) | Err(Error::DetachedTaskStopped) => { /* Do nothing */ }
+                Err(e) => error!(target: "fud", "Failed starting chunks watch task: {}", e),
+            }
+        },
+        Error::DetachedTaskStopped,
+        ex.clone(),
+    );
+
+    info!(target: "fud", "Starting fetch channels task");
+    let fetch_channels_task = StoppableTask::new();
+    fetch_channels_task.clone().start(
+        transport::fetch_channels_task(fud.clone()),
+        |res| async {
+            match res {
+                Ok(()) | Err(Error::DetachedTaskStopped) => { /* Do nothing */ }
+                Err(e) => error!(target: "fud", "Failed starting fetch channels task: {}", e),
+            }
+        },
+        Error::DetachedTaskStopped,
+        ex.clone(),
+    );
+
+    let scrub_task = if args.scrub_rate > 0 {
+        info!(target: "fud", "Starting integrity scrub task");
+        let scrub_task = StoppableTask::new();
+        scrub_task.clone().start(
+            scrub::scrub_task(fud.clone(), args.scrub_rate, args.scrub_interval * 3600),
+            |res| async {
+                match res {
+                    Ok(()) | Err(Error::DetachedTaskStopped) => { /* Do nothing */ }
+                    Err(e) => error!(target: "fud", "Failed starting integrity scrub task: {}", e),
+                }
+            },
+            Error::DetachedTaskStopped,
+            ex.clone(),
+        );
+        Some(scrub_task)
+    } else {
+        None
+    };
+
+    let replication_task = if args.replication_factor > 0 {
+        info!(target: "fud", "Starting replication task");
+        let replication_task = StoppableTask::new();
+        replication_task.clone().start(
+            replication::replication_task(fud.clone(), args.replication_interval),
+            |res| async {
+                match res {
+                    Ok(()) | Err(Error::DetachedTaskStopped) => { /* Do nothing */ }
+                    Err(e) => error!(target: "fud", "Failed starting replication task: {}", e),
+                }
+            },
+            Error::DetachedTaskStopped,
+            ex.clone(),
+        );
+        Some(replication_task)
+    } else {
+        None
+    };
+
+    let lan_task = if args.lan_discovery {
+        info!(target: "fud", "Starting LAN discovery task");
+        let lan_task = StoppableTask::new();
+        lan_task.clone().start(
+            lan::lan_discovery_task(fud.clone()),
+            |res| async {
+                match res {
+                    Ok(()) | Err(Error::DetachedTaskStopped) => { /* Do nothing */ }
+                    Err(e) => error!(target: "fud", "Failed starting LAN discovery task: {}", e),
+                }
+            },
+            Error::DetachedTaskStopped,
+            ex.clone(),
+        );
+        Some(lan_task)
+    } else {
+        None
+    };
+
     info!(target: "fud", "Starting JSON-RPC server on {}", args.rpc_listen);
     let rpc_task = StoppableTask::new();
     let fud_ = fud.clone();
     rpc_task.clone().start(
-        listen_and_serve(args.rpc_listen, fud.clone(), None, ex.clone()),
+        listen_and_serve::<()>(args.rpc_listen, fud.clone(), None, ex.clone()),
         |res| async move {
             match res {
-                Ok(()) | Err(Error::RpcServerStopped) => fud_.stop_connections().await,
+                Ok(()) | Err(Error::RpcServerStopped) => {
+                    <Fud as RequestHandler<()>>::stop_connections(&fud_).await
+                }
                 Err(e) => error!(target: "fud", "Failed starting sync JSON-RPC server: {}", e),
             }
         },
@@ -600,6 +1638,57 @@ async fn realmain(args: Args, ex: Arc<Executor<'static>>) -> Result<()> {
         ex.clone(),
     );
 
+    let seedbox_rpc_task = match args.seedbox_rpc_listen {
+        Some(seedbox_rpc_listen) => {
+            info!(target: "fud", "Starting seedbox JSON-RPC server on {}", seedbox_rpc_listen);
+            let seedbox_rpc_task = StoppableTask::new();
+            let fud_ = fud.clone();
+            seedbox_rpc_task.clone().start(
+                listen_and_serve::<SeedboxRpcHandler>(
+                    seedbox_rpc_listen,
+                    fud.clone(),
+                    None,
+                    ex.clone(),
+                ),
+                |res| async move {
+                    match res {
+                        Ok(()) | Err(Error::RpcServerStopped) => {
+                            <Fud as RequestHandler<SeedboxRpcHandler>>::stop_connections(&fud_)
+                                .await
+                        }
+                        Err(e) => {
+                            error!(target: "fud", "Failed starting seedbox JSON-RPC server: {}", e)
+                        }
+                    }
+                },
+                Error::RpcServerStopped,
+                ex.clone(),
+            );
+            Some(seedbox_rpc_task)
+        }
+        None => None,
+    };
+
+    let bridge_task = match args.bridge_listen {
+        Some(bridge_listen) => {
+            info!(target: "fud", "Starting websocket bridge on {}", bridge_listen);
+            let bridge_task = StoppableTask::new();
+            bridge_task.clone().start(
+                bridge::bridge_task(fud.clone(), bridge_listen, ex.clone()),
+                |res| async {
+                    match res {
+                        Ok(()) | Err(Error::DetachedTaskStopped) => { /* Do nothing */ }
+                        Err(e) => error!(target: "fud", "Failed starting websocket bridge: {}", e),
+                    }
+                },
+                Error::DetachedTaskStopped,
+                ex.clone(),
+            );
+            Some(bridge_task)
+        }
+        None => None,
+    };
+
     info!("Starting P2P protocols");
     let registry = p2p.protocol_registry();
     let fud_ = fud.clone();
@@ -622,12 +1711,78 @@ async fn realmain(args: Args, ex: Arc<Executor<'static>>) -> Result<()> {
     info!(target: "fud", "Stopping fetch chunk task...");
     chunk_task.stop().await;
 
+    info!(target: "fud", "Stopping download scheduler task...");
+    scheduler_task.stop().await;
+
+    info!(target: "fud", "Stopping chunks watch task...");
+    watch_task.stop().await;
+
+    if let Some(scrub_task) = scrub_task {
+        info!(target: "fud", "Stopping integrity scrub task...");
+        scrub_task.stop().await;
+    }
+
+    if let Some(replication_task) = replication_task {
+        info!(target: "fud", "Stopping replication task...");
+        replication_task.stop().await;
+    }
+
+    if let Some(lan_task) = lan_task {
+        info!(target: "fud", "Stopping LAN discovery task...");
+        lan_task.stop().await;
+    }
+
     info!(target: "fud", "Stopping JSON-RPC server...");
     rpc_task.stop().await;
 
+    if let Some(seedbox_rpc_task) = seedbox_rpc_task {
+        info!(target: "fud", "Stopping seedbox JSON-RPC server...");
+        seedbox_rpc_task.stop().await;
+    }
+
+    if let Some(bridge_task) = bridge_task {
+        info!(target: "fud", "Stopping websocket bridge...");
+        bridge_task.stop().await;
+    }
+
+    info!(target: "fud", "Stopping fetch channels...");
+    fetch_channels_task.stop().await;
+    fud.channels.stop().await;
+
     info!("Stopping P2P network");
     p2p.stop().await;
 
     info!("Bye!");
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use rand::{rngs::OsRng, Rng};
+
+    use super::*;
+
+    #[test]
+    fn insert_chunks_errors() {
+        smol::block_on(async {
+            let basedir = std::env::temp_dir().join(format!("fud_insert_{}", OsRng.gen::<u64>()));
+            let geode = Geode::new(&basedir).await.unwrap();
+
+            let chunk = b"chunk".to_vec();
+            let chunk_hash = geode.hash_chunk(&chunk);
+            let inserted =
+                insert_chunks(&geode, HashMap::from([(chunk_hash, chunk)])).await.unwrap();
+            assert_eq!(inserted, vec![chunk_hash]);
+            assert!(geode.get_chunk(&chunk_hash).await.is_ok());
+
+            // Failing inserts are reported instead of being skipped
+            smol::fs::remove_dir_all(geode.chunks_path()).await.unwrap();
+            smol::fs::write(geode.chunks_path(), b"").await.unwrap();
+            let chunk = b"other".to_vec();
+            let chunks = HashMap::from([(geode.hash_chunk(&chunk), chunk)]);
+            assert!(insert_chunks(&geode, chunks).await.is_err());
+
+            smol::fs::remove_dir_all(&basedir).await.unwrap();
+        })
+    }
+}
@@ -0,0 +1,232 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2025 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+//! Local names for resources.
+//!
+//! Users can label resources with names of their choosing, which are
+//! then accepted anywhere a file hash is in the JSON-RPC API. Names are
+//! purely local and never shared with the network. They are persisted
+//! as `<hash> <name>` lines in a file inside the base directory.
+
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+};
+
+use darkfi::Result;
+use log::warn;
+use smol::{fs, lock::RwLock};
+use tinyjson::JsonValue;
+
+/// File inside the base directory holding the resource names
+const NAMES_PATH: &str = "names";
+
+/// Maximum length of a resource name
+const MAX_NAME_LEN: usize = 64;
+
+/// Check whether `name` can be used as a resource name. Names can't
+/// contain whitespace, and can't be mistaken for a file hash.
+pub fn valid_name(name: &str) -> bool {
+    !name.is_empty() &&
+        name.len() <= MAX_NAME_LEN &&
+        !name.chars().any(char::is_whitespace) &&
+        blake3::Hash::from_hex(name).is_err()
+}
+
+/// Local resource names storage
+pub struct Names {
+    /// Resource names, mapped to the file hash they point to
+    names: RwLock<HashMap<String, blake3::Hash>>,
+    /// Path to the names file
+    path: PathBuf,
+}
+
+impl Names {
+    /// Instantiate the names storage, loading the names file inside
+    /// `basedir` if it exists.
+    pub async fn new(basedir: &Path) -> Result<Self> {
+        let path = basedir.join(NAMES_PATH);
+
+        let mut names = HashMap::new();
+        if path.exists() {
+            for line in fs::read_to_string(&path).await?.lines() {
+                let parsed = line.split_once(' ').and_then(|(hash, name)| {
+                    let hash = blake3::Hash::from_hex(hash).ok()?;
+                    valid_name(name).then(|| (name.to_string(), hash))
+                });
+
+                match parsed {
+                    Some((name, hash)) => {
+                        names.insert(name, hash);
+                    }
+                    None => warn!(target: "fud::names", "Skipping invalid name entry: {}", line),
+                }
+            }
+        }
+
+        Ok(Self { names: RwLock::new(names), path })
+    }
+
+    /// Resolve a file hash or a resource name into a file hash
+    pub async fn resolve(&self, hash_or_name: &str) -> Option<blake3::Hash> {
+        if let Ok(hash) = blake3::Hash::from_hex(hash_or_name) {
+            return Some(hash)
+        }
+
+        self.names.read().await.get(hash_or_name).copied()
+    }
+
+    /// Point `name` to the given file hash, replacing any previous target.
+    pub async fn set(&self, name: &str, file_hash: blake3::Hash) -> Result<()> {
+        let mut names = self.names.write().await;
+        names.insert(name.to_string(), file_hash);
+        self.save(&names).await
+    }
+
+    /// Remove `name`. Returns `false` if it didn't exist.
+    pub async fn remove(&self, name: &str) -> Result<bool> {
+        let mut names = self.names.write().await;
+        if names.remove(name).is_none() {
+            return Ok(false)
+        }
+        self.save(&names).await?;
+        Ok(true)
+    }
+
+    /// Names pointing to the given file hash, sorted
+    pub async fn names_of(&self, file_hash: &blake3::Hash) -> Vec<String> {
+        let names = self.names.read().await;
+        let mut ret: Vec<String> =
+            names.iter().filter(|(_, h)| *h == file_hash).map(|(n, _)| n.clone()).collect();
+        ret.sort();
+        ret
+    }
+
+    /// Human-readable label of a file hash, listing its names if any
+    pub async fn label(&self, file_hash: &blake3::Hash) -> String {
+        let names = self.names_of(file_hash).await;
+        if names.is_empty() {
+            return file_hash.to_string()
+        }
+        format!("{} ({})", file_hash, names.join(", "))
+    }
+
+    /// JSON array of the names pointing to the given file hash
+    pub async fn json_of(&self, file_hash: &blake3::Hash) -> JsonValue {
+        JsonValue::Array(
+            self.names_of(file_hash).await.into_iter().map(JsonValue::String).collect(),
+        )
+    }
+
+    /// JSON object of all names and the file hashes they point to
+    pub async fn json(&self) -> JsonValue {
+        let names = self.names.read().await;
+        JsonValue::Object(
+            names
+                .iter()
+                .map(|(n, h)| (n.clone(), JsonValue::String(h.to_hex().to_string())))
+                .collect(),
+        )
+    }
+
+    /// Write the names file, replacing it atomically
+    async fn save(&self, names: &HashMap<String, blake3::Hash>) -> Result<()> {
+        let mut data = String::new();
+        for (name, hash) in names {
+            data.push_str(&format!("{} {}\n", hash.to_hex(), name));
+        }
+
+        let tmp_path = self.path.with_extension("tmp");
+        fs::write(&tmp_path, data).await?;
+        fs::rename(&tmp_path, &self.path).await?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn names_valid_name() {
+        assert!(valid_name("foo"));
+        assert!(valid_name("my-file_v2.tar.gz"));
+        assert!(valid_name("ünïcode"));
+        assert!(valid_name(&"a".repeat(MAX_NAME_LEN)));
+
+        assert!(!valid_name(""));
+        assert!(!valid_name(&"a".repeat(MAX_NAME_LEN + 1)));
+        assert!(!valid_name("foo bar"));
+        assert!(!valid_name("foo\tbar"));
+        assert!(!valid_name("foo\n"));
+
+        // Names can't be mistaken for a file hash
+        let hash = blake3::hash(b"foo").to_hex();
+        assert!(!valid_name(&hash));
+        assert!(!valid_name(&hash.to_uppercase()));
+        assert!(valid_name(&hash[1..]));
+    }
+
+    #[test]
+    fn names_storage() {
+        let basedir = std::env::temp_dir().join(format!("fud_names_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&basedir);
+        std::fs::create_dir_all(&basedir).unwrap();
+        let (foo, bar) = (blake3::hash(b"foo"), blake3::hash(b"bar"));
+
+        smol::block_on(async {
+            let names = Names::new(&basedir).await.unwrap();
+
+            // Hashes resolve to themselves, names to their target
+            assert_eq!(names.resolve(&foo.to_hex()).await, Some(foo));
+            assert_eq!(names.resolve("foo").await, None);
+            names.set("foo", foo).await.unwrap();
+            names.set("also-foo", foo).await.unwrap();
+            names.set("bar", foo).await.unwrap();
+            names.set("bar", bar).await.unwrap();
+            assert_eq!(names.resolve("foo").await, Some(foo));
+            assert_eq!(names.resolve("bar").await, Some(bar));
+            assert_eq!(names.names_of(&foo).await, vec!["also-foo", "foo"]);
+            assert_eq!(names.label(&foo).await, format!("{foo} (also-foo, foo)"));
+            assert_eq!(names.label(&blake3::hash(b"baz")).await, blake3::hash(b"baz").to_string());
+
+            assert!(names.remove("also-foo").await.unwrap());
+            assert!(!names.remove("also-foo").await.unwrap());
+            assert_eq!(names.resolve("also-foo").await, None);
+        });
+
+        // Names survive a restart, skipping corrupted entries
+        let path = basedir.join(NAMES_PATH);
+        let mut data = std::fs::read_to_string(&path).unwrap();
+        data.push_str("garbage\n");
+        data.push_str(&format!("{} {}\n", foo.to_hex(), foo.to_hex()));
+        data.push_str(&format!("{} with space\n", bar.to_hex()));
+        std::fs::write(&path, data).unwrap();
+
+        smol::block_on(async {
+            let names = Names::new(&basedir).await.unwrap();
+            assert_eq!(names.resolve("foo").await, Some(foo));
+            assert_eq!(names.resolve("bar").await, Some(bar));
+            assert_eq!(names.names_of(&foo).await, vec!["foo"]);
+            assert_eq!(names.names_of(&bar).await, vec!["bar"]);
+            let JsonValue::Object(json) = names.json().await else { panic!("Expected an object") };
+            assert_eq!(json.len(), 2);
+        });
+
+        std::fs::remove_dir_all(&basedir).unwrap();
+    }
+}
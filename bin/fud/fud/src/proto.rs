@@ -24,16 +24,20 @@ use darkfi::{
     impl_p2p_message,
     net::{
         ChannelPtr, Message, MessageSubscription, P2pPtr, ProtocolBase, ProtocolBasePtr,
-        ProtocolJobsManager, ProtocolJobsManagerPtr,
+        ProtocolJobsManager, ProtocolJobsManagerPtr, StreamPtr,
     },
     Error, Result,
 };
-use darkfi_serial::{SerialDecodable, SerialEncodable};
-use log::{debug, error};
-use smol::{fs::File, io::AsyncReadExt, Executor};
+use darkfi_sdk::crypto::PublicKey;
+use darkfi_serial::{deserialize_async, SerialDecodable, SerialEncodable};
+use log::{debug, error, info};
+use smol::{channel::Receiver, fs::File, io::AsyncReadExt, Executor};
 use url::Url;
 
-use super::Fud;
+use super::{denylist::Denylist, publisher::PublisherSignature, replication::peer_id, Fud};
+
+/// Protocol name of the streams fud peers fetch files and chunks over
+pub const FUD_STREAM: &str = "fud";
 
 /// Message representing a new file on the network
 #[derive(Debug, Clone, SerialEncodable, SerialDecodable)]
@@ -67,6 +71,21 @@ pub struct FudChunkRoute {
 }
 impl_p2p_message!(FudChunkRoute, "FudChunkRoute");
 
+/// Message representing a chunk no longer being available from a peer
+#[derive(Debug, Clone, SerialEncodable, SerialDecodable)]
+pub struct FudChunkWithdraw {
+    pub chunk_hash: blake3::Hash,
+}
+impl_p2p_message!(FudChunkWithdraw, "FudChunkWithdraw");
+
+/// Message representing a withdrawn route for a chunk on the network
+#[derive(Debug, Clone, SerialEncodable, SerialDecodable)]
+pub struct FudChunkRouteWithdraw {
+    pub chunk_hash: blake3::Hash,
+    pub peer: Url,
+}
+impl_p2p_message!(FudChunkRouteWithdraw, "FudChunkRouteWithdraw");
+
 /// Message representing a file request from the network
 #[derive(Debug, Clone, SerialEncodable, SerialDecodable)]
 pub struct FudFileRequest {
@@ -81,6 +100,24 @@ pub struct FudFileReply {
 }
 impl_p2p_message!(FudFileReply, "FudFileReply");
 
+/// Versioned [`FudFileReply`], carrying the publisher signature over the
+/// file metadata. It is only sent on streams, which older peers never
+/// open, while requests sent as plain channel messages keep getting the
+/// unversioned reply.
+#[derive(Debug, Clone, SerialEncodable, SerialDecodable)]
+pub struct FudFileReplyV2 {
+    pub chunk_hashes: Vec<blake3::Hash>,
+    /// Publisher signature over the file metadata, if signed
+    pub publisher: Option<PublisherSignature>,
+}
+impl_p2p_message!(FudFileReplyV2, "FudFileReplyV2");
+
+impl From<FudFileReply> for FudFileReplyV2 {
+    fn from(reply: FudFileReply) -> Self {
+        Self { chunk_hashes: reply.chunk_hashes, publisher: None }
+    }
+}
+
 /// Message representing a chunk request from the network
 #[derive(Debug, Clone, SerialEncodable, SerialDecodable)]
 pub struct FudChunkRequest {
@@ -106,6 +143,30 @@ impl_p2p_message!(FudFileNotFound, "FudFileNotFound");
 pub struct FudChunkNotFound;
 impl_p2p_message!(FudChunkNotFound, "FudChunkNotFound");
 
+/// Versioned [`FudChunkNotFound`], naming the chunk that was not found
+/// so replies to pipelined requests can be matched. It is only sent on
+/// streams, which older peers never open, while requests sent as plain
+/// channel messages keep getting the unversioned reply.
+#[derive(Debug, Clone, SerialEncodable, SerialDecodable)]
+pub struct FudChunkNotFoundV2 {
+    pub chunk_hash: blake3::Hash,
+}
+impl_p2p_message!(FudChunkNotFoundV2, "FudChunkNotFoundV2");
+
+/// Message carrying a moderator-signed denylist
+#[derive(Debug, Clone, SerialEncodable, SerialDecodable)]
+pub struct FudDenylistPut {
+    pub denylist: Denylist,
+}
+impl_p2p_message!(FudDenylistPut, "FudDenylistPut");
+
+/// Message requesting the denylists of the given moderators
+#[derive(Debug, Clone, SerialEncodable, SerialDecodable)]
+pub struct FudDenylistRequest {
+    pub moderators: Vec<PublicKey>,
+}
+impl_p2p_message!(FudDenylistRequest, "FudDenylistRequest");
+
 /// P2P protocol implementation for fud.
 pub struct ProtocolFud {
     channel: ChannelPtr,
@@ -113,8 +174,13 @@ pub struct ProtocolFud {
     chunk_put_sub: MessageSubscription<FudChunkPut>,
     file_route_sub: MessageSubscription<FudFileRoute>,
     chunk_route_sub: MessageSubscription<FudChunkRoute>,
+    chunk_withdraw_sub: MessageSubscription<FudChunkWithdraw>,
+    chunk_route_withdraw_sub: MessageSubscription<FudChunkRouteWithdraw>,
     file_request_sub: MessageSubscription<FudFileRequest>,
     chunk_request_sub: MessageSubscription<FudChunkRequest>,
+    denylist_put_sub: MessageSubscription<FudDenylistPut>,
+    denylist_request_sub: MessageSubscription<FudDenylistRequest>,
+    stream_listener: Receiver<StreamPtr>,
     fud: Arc<Fud>,
     p2p: P2pPtr,
     jobsman: ProtocolJobsManagerPtr,
@@ -132,15 +198,24 @@ impl ProtocolFud {
         msg_subsystem.add_dispatch::<FudChunkPut>().await;
         msg_subsystem.add_dispatch::<FudFileRoute>().await;
         msg_subsystem.add_dispatch::<FudChunkRoute>().await;
+        msg_subsystem.add_dispatch::<FudChunkWithdraw>().await;
+        msg_subsystem.add_dispatch::<FudChunkRouteWithdraw>().await;
         msg_subsystem.add_dispatch::<FudFileRequest>().await;
         msg_subsystem.add_dispatch::<FudChunkRequest>().await;
+        msg_subsystem.add_dispatch::<FudDenylistPut>().await;
+        msg_subsystem.add_dispatch::<FudDenylistRequest>().await;
 
         let file_put_sub = channel.subscribe_msg::<FudFilePut>().await?;
         let chunk_put_sub = channel.subscribe_msg::<FudChunkPut>().await?;
         let file_route_sub = channel.subscribe_msg::<FudFileRoute>().await?;
         let chunk_route_sub = channel.subscribe_msg::<FudChunkRoute>().await?;
+        let chunk_withdraw_sub = channel.subscribe_msg::<FudChunkWithdraw>().await?;
+        let chunk_route_withdraw_sub = channel.subscribe_msg::<FudChunkRouteWithdraw>().await?;
         let file_request_sub = channel.subscribe_msg::<FudFileRequest>().await?;
         let chunk_request_sub = channel.subscribe_msg::<FudChunkRequest>().await?;
+        let denylist_put_sub = channel.subscribe_msg::<FudDenylistPut>().await?;
+        let denylist_request_sub = channel.subscribe_msg::<FudDenylistRequest>().await?;
+        let stream_listener = channel.streams().listen(FUD_STREAM).await;
 
         Ok(Arc::new(Self {
             channel: channel.clone(),
@@ -148,8 +223,13 @@ impl ProtocolFud {
             chunk_put_sub,
             file_route_sub,
             chunk_route_sub,
+            chunk_withdraw_sub,
+            chunk_route_withdraw_sub,
             file_request_sub,
             chunk_request_sub,
+            denylist_put_sub,
+            denylist_request_sub,
+            stream_listener,
             fud,
             p2p,
             jobsman: ProtocolJobsManager::new("ProtocolFud", channel.clone()),
@@ -171,6 +251,15 @@ impl ProtocolFud {
                 }
             };
 
+            // Don't route denylisted resources
+            if let Some(m) = self.fud.denylists.check(&fud_file.file_hash).await {
+                debug!(
+                    target: "fud::ProtocolFud::handle_fud_file_put()",
+                    "Ignoring {} denylisted by {}", fud_file.file_hash, m,
+                );
+                continue
+            }
+
             // TODO: This approach is naive and optimistic. Needs to be fixed.
             let mut metadata_lock = self.fud.metadata_router.write().await;
             let file_route = metadata_lock.get_mut(&fud_file.file_hash);
@@ -202,6 +291,9 @@ impl ProtocolFud {
             }
             drop(chunks_lock);
 
+            let peer = self.channel.address();
+            self.fud.add_chunk_route_sources(&fud_file.chunk_hashes, peer, peer).await;
+
             // Relay this knowledge of the new route
             let route = FudFileRoute {
                 file_hash: fud_file.file_hash,
@@ -209,7 +301,10 @@ impl ProtocolFud {
                 peer: self.channel.address().clone(),
             };
 
-            self.p2p.broadcast_with_exclude(&route, &[self.channel.address().clone()]).await;
+            self.fud
+                .replicas
+                .announce(&self.p2p, &route.file_hash, &route, &[self.channel.address().clone()])
+                .await;
         }
     }
 
@@ -228,6 +323,15 @@ impl ProtocolFud {
                 }
             };
 
+            // Don't route denylisted resources
+            if let Some(m) = self.fud.denylists.check(&fud_chunk.chunk_hash).await {
+                debug!(
+                    target: "fud::ProtocolFud::handle_fud_chunk_put()",
+                    "Ignoring {} denylisted by {}", fud_chunk.chunk_hash, m,
+                );
+                continue
+            }
+
             // TODO: This approach is naive and optimistic. Needs to be fixed.
             let mut chunks_lock = self.fud.chunks_router.write().await;
             let chunk_route = chunks_lock.get_mut(&fud_chunk.chunk_hash);
@@ -243,13 +347,19 @@ impl ProtocolFud {
             }
             drop(chunks_lock);
 
+            let peer = self.channel.address();
+            self.fud.add_chunk_route_sources(&[fud_chunk.chunk_hash], peer, peer).await;
+
             // Relay this knowledge of the new route
             let route = FudChunkRoute {
                 chunk_hash: fud_chunk.chunk_hash,
                 peer: self.channel.address().clone(),
             };
 
-            self.p2p.broadcast_with_exclude(&route, &[self.channel.address().clone()]).await;
+            self.fud
+                .replicas
+                .announce(&self.p2p, &route.chunk_hash, &route, &[self.channel.address().clone()])
+                .await;
         }
     }
 
@@ -268,6 +378,15 @@ impl ProtocolFud {
                 }
             };
 
+            // Don't route denylisted resources
+            if let Some(m) = self.fud.denylists.check(&fud_file.file_hash).await {
+                debug!(
+                    target: "fud::ProtocolFud::handle_fud_file_route()",
+                    "Ignoring {} denylisted by {}", fud_file.file_hash, m,
+                );
+                continue
+            }
+
             // TODO: This approach is naive and optimistic. Needs to be fixed.
             let mut metadata_lock = self.fud.metadata_router.write().await;
             let file_route = metadata_lock.get_mut(&fud_file.file_hash);
@@ -299,6 +418,14 @@ impl ProtocolFud {
             }
             drop(chunks_lock);
 
+            self.fud
+                .add_chunk_route_sources(
+                    &fud_file.chunk_hashes,
+                    &fud_file.peer,
+                    self.channel.address(),
+                )
+                .await;
+
             // Relay this knowledge of the new route
             let route = FudFileRoute {
                 file_hash: fud_file.file_hash,
@@ -306,8 +433,11 @@ impl ProtocolFud {
                 peer: fud_file.peer.clone(),
             };
 
-            self.p2p
-                .broadcast_with_exclude(
+            self.fud
+                .replicas
+                .announce(
+                    &self.p2p,
+                    &route.file_hash,
                     &route,
                     &[self.channel.address().clone(), fud_file.peer.clone()],
                 )
@@ -330,6 +460,15 @@ impl ProtocolFud {
                 }
             };
 
+            // Don't route denylisted resources
+            if let Some(m) = self.fud.denylists.check(&fud_chunk.chunk_hash).await {
+                debug!(
+                    target: "fud::ProtocolFud::handle_fud_chunk_route()",
+                    "Ignoring {} denylisted by {}", fud_chunk.chunk_hash, m,
+                );
+                continue
+            }
+
             // TODO: This approach is naive and optimistic. Needs to be fixed.
             let mut chunks_lock = self.fud.chunks_router.write().await;
             let chunk_route = chunks_lock.get_mut(&fud_chunk.chunk_hash);
@@ -345,13 +484,99 @@ impl ProtocolFud {
             }
             drop(chunks_lock);
 
+            self.fud
+                .add_chunk_route_sources(
+                    &[fud_chunk.chunk_hash],
+                    &fud_chunk.peer,
+                    self.channel.address(),
+                )
+                .await;
+
             // Relay this knowledge of the new route
             let route =
                 FudChunkRoute { chunk_hash: fud_chunk.chunk_hash, peer: fud_chunk.peer.clone() };
 
+            self.fud
+                .replicas
+                .announce(
+                    &self.p2p,
+                    &route.chunk_hash,
+                    &route,
+                    &[self.channel.address().clone(), fud_chunk.peer.clone()],
+                )
+                .await;
+        }
+    }
+
+    async fn handle_fud_chunk_withdraw(self: Arc<Self>) -> Result<()> {
+        debug!(target: "fud::ProtocolFud::handle_fud_chunk_withdraw()", "START");
+
+        loop {
+            let fud_chunk = match self.chunk_withdraw_sub.receive().await {
+                Ok(v) => v,
+                Err(e) => {
+                    error!(
+                        target: "fud::ProtocolFud::handle_fud_chunk_withdraw()",
+                        "recv fail: {}", e,
+                    );
+                    continue
+                }
+            };
+
+            let peer = self.channel.address().clone();
+            if !self.fud.remove_chunk_route(&fud_chunk.chunk_hash, &peer).await {
+                continue
+            }
+
+            // Relay this knowledge of the withdrawn route
+            let route = FudChunkRouteWithdraw { chunk_hash: fud_chunk.chunk_hash, peer };
+
+            self.p2p.broadcast_with_exclude(&route, &[self.channel.address().clone()]).await;
+        }
+    }
+
+    async fn handle_fud_chunk_route_withdraw(self: Arc<Self>) -> Result<()> {
+        debug!(target: "fud::ProtocolFud::handle_fud_chunk_route_withdraw()", "START");
+
+        loop {
+            let fud_chunk = match self.chunk_route_withdraw_sub.receive().await {
+                Ok(v) => v,
+                Err(e) => {
+                    error!(
+                        target: "fud::ProtocolFud::handle_fud_chunk_route_withdraw()",
+                        "recv fail: {}", e,
+                    );
+                    continue
+                }
+            };
+
+            // Only the channel we learned the route from can withdraw it,
+            // so peers can't erase the routes of others
+            if !self
+                .fud
+                .is_chunk_route_source(
+                    &fud_chunk.chunk_hash,
+                    &fud_chunk.peer,
+                    self.channel.address(),
+                )
+                .await
+            {
+                debug!(
+                    target: "fud::ProtocolFud::handle_fud_chunk_route_withdraw()",
+                    "Ignoring withdrawal of {} route to {} from {}",
+                    fud_chunk.chunk_hash, fud_chunk.peer, self.channel.address(),
+                );
+                continue
+            }
+
+            // Only relay routes we knew about, so withdrawals don't loop forever
+            if !self.fud.remove_chunk_route(&fud_chunk.chunk_hash, &fud_chunk.peer).await {
+                continue
+            }
+
             self.p2p
                 .broadcast_with_exclude(
-                    &route,
+                    &fud_chunk,
                     &[self.channel.address().clone(), fud_chunk.peer.clone()],
                 )
                 .await;
@@ -373,30 +598,42 @@ impl ProtocolFud {
                 }
             };
 
-            let chunked_file = match self.fud.geode.get(&file_request.file_hash).await {
-                Ok(v) => v,
-                Err(Error::GeodeNeedsGc) => {
-                    // TODO: Run GC
-                    continue
-                }
+            self.reply_file(&file_request.file_hash, &Replier::Channel(&self.channel)).await;
+        }
+    }
+
+    /// Reply to a file request with the file metadata, if we have it
+    /// and it isn't denylisted
+    async fn reply_file(&self, file_hash: &blake3::Hash, replier: &Replier<'_>) {
+        if let Some(m) = self.fud.denylists.check(file_hash).await {
+            info!(target: "fud::ProtocolFud", "Refusing to serve {} denylisted by {}", file_hash, m);
+            let _ = replier.send(&FudFileNotFound).await;
+            return
+        }
 
-                Err(Error::GeodeFileNotFound) => match self.channel.send(&FudFileNotFound).await {
-                    Ok(()) => continue,
-                    Err(_e) => continue,
-                },
+        let chunked_file = match self.fud.geode.get(file_hash).await {
+            Ok(v) => v,
+            Err(Error::GeodeNeedsGc) => {
+                // TODO: Run GC
+                return
+            }
 
-                Err(_e) => continue,
-            };
+            Err(Error::GeodeFileNotFound) => {
+                let _ = replier.send(&FudFileNotFound).await;
+                return
+            }
 
-            let file_reply = FudFileReply {
-                chunk_hashes: chunked_file.iter().map(|(chunk, _)| *chunk).collect(),
-            };
+            Err(_e) => return,
+        };
 
-            match self.channel.send(&file_reply).await {
-                Ok(()) => continue,
-                Err(_e) => continue,
+        let chunk_hashes = chunked_file.iter().map(|(chunk, _)| *chunk).collect();
+        let _ = match replier {
+            Replier::Channel(channel) => channel.send(&FudFileReply { chunk_hashes }).await,
+            Replier::Stream(stream) => {
+                let publisher = self.fud.publishers.get(file_hash).await;
+                stream.send(&FudFileReplyV2 { chunk_hashes, publisher }).await
             }
-        }
+        };
     }
 
     async fn handle_fud_chunk_request(self: Arc<Self>) -> Result<()> {
@@ -414,34 +651,183 @@ impl ProtocolFud {
                 }
             };
 
-            let chunk_path = match self.fud.geode.get_chunk(&chunk_request.chunk_hash).await {
+            self.reply_chunk(&chunk_request.chunk_hash, &Replier::Channel(&self.channel)).await;
+        }
+    }
+
+    /// Reply to a chunk request with the chunk, if we have it and it
+    /// isn't denylisted
+    async fn reply_chunk(&self, chunk_hash: &blake3::Hash, replier: &Replier<'_>) {
+        if let Some(m) = self.fud.denylists.check(chunk_hash).await {
+            info!(target: "fud::ProtocolFud", "Refusing to serve {} denylisted by {}", chunk_hash, m);
+            let _ = replier.chunk_not_found(chunk_hash).await;
+            return
+        }
+
+        let chunk_path = match self.fud.geode.get_chunk(chunk_hash).await {
+            Ok(v) => v,
+            Err(Error::GeodeNeedsGc) => {
+                // TODO: Run GC
+                return
+            }
+
+            Err(Error::GeodeChunkNotFound) => {
+                let _ = replier.chunk_not_found(chunk_hash).await;
+                return
+            }
+
+            Err(_e) => return,
+        };
+
+        // The consistency should already be checked in Geode, so we're
+        // fine not checking and unwrapping here.
+        let mut buf = [0u8; MAX_CHUNK_SIZE];
+        let mut chunk_fd = File::open(&chunk_path).await.unwrap();
+        let bytes_read = chunk_fd.read(&mut buf).await.unwrap();
+        let chunk_slice = &buf[..bytes_read];
+
+        let reply = FudChunkReply { chunk: chunk_slice.to_vec() };
+        if replier.send(&reply).await.is_ok() {
+            // Account the upload for the seeding ratios
+            self.fud.uploads.record(*chunk_hash, bytes_read as u64).await;
+
+            let peer = peer_id(&self.channel).await;
+            self.fud.swarm.record_upload(&peer, *chunk_hash, bytes_read as u64).await;
+        }
+    }
+
+    async fn handle_fud_denylist_put(self: Arc<Self>) -> Result<()> {
+        debug!(target: "fud::ProtocolFud::handle_fud_denylist_put()", "START");
+
+        loop {
+            let put = match self.denylist_put_sub.receive().await {
                 Ok(v) => v,
-                Err(Error::GeodeNeedsGc) => {
-                    // TODO: Run GC
+                Err(e) => {
+                    error!(
+                        target: "fud::ProtocolFud::handle_fud_denylist_put()",
+                        "recv fail: {}", e,
+                    );
                     continue
                 }
+            };
 
-                Err(Error::GeodeChunkNotFound) => {
-                    match self.channel.send(&FudChunkNotFound).await {
-                        Ok(()) => continue,
-                        Err(_e) => continue,
-                    }
+            // Relay lists we hadn't seen to the other subscribers
+            match self.fud.denylists.insert(&put.denylist).await {
+                Ok(true) => {
+                    self.p2p
+                        .broadcast_with_exclude(put.as_ref(), &[self.channel.address().clone()])
+                        .await;
+                }
+                Ok(false) => {}
+                Err(e) => {
+                    error!(
+                        target: "fud::ProtocolFud::handle_fud_denylist_put()",
+                        "Failed storing denylist of {}: {}", put.denylist.moderator, e,
+                    );
                 }
+            }
+        }
+    }
+
+    async fn handle_fud_denylist_request(self: Arc<Self>) -> Result<()> {
+        debug!(target: "fud::ProtocolFud::handle_fud_denylist_request()", "START");
+
+        // Ask the peer for the lists we're subscribed to
+        let subscriptions = self.fud.denylists.subscriptions();
+        if !subscriptions.is_empty() {
+            let request = FudDenylistRequest { moderators: subscriptions };
+            self.channel.send(&request).await?;
+        }
 
-                Err(_e) => continue,
+        loop {
+            let request = match self.denylist_request_sub.receive().await {
+                Ok(v) => v,
+                Err(e) => {
+                    error!(
+                        target: "fud::ProtocolFud::handle_fud_denylist_request()",
+                        "recv fail: {}", e,
+                    );
+                    continue
+                }
             };
 
-            // The consistency should already be checked in Geode, so we're
-            // fine not checking and unwrapping here.
-            let mut buf = [0u8; MAX_CHUNK_SIZE];
-            let mut chunk_fd = File::open(&chunk_path).await.unwrap();
-            let bytes_read = chunk_fd.read(&mut buf).await.unwrap();
-            let chunk_slice = &buf[..bytes_read];
-
-            let reply = FudChunkReply { chunk: chunk_slice.to_vec() };
-            match self.channel.send(&reply).await {
-                Ok(()) => continue,
-                Err(_e) => continue,
+            for denylist in self.fud.denylists.get(&request.moderators).await {
+                let _ = self.channel.send(&FudDenylistPut { denylist }).await;
+            }
+        }
+    }
+
+    /// Accept the fud streams opened by the peer, serving each of them
+    /// in its own task.
+    async fn handle_fud_streams(self: Arc<Self>, executor: Arc<Executor<'_>>) -> Result<()> {
+        debug!(target: "fud::ProtocolFud::handle_fud_streams()", "START");
+
+        while let Ok(stream) = self.stream_listener.recv().await {
+            executor.spawn(self.clone().serve_stream(stream)).detach();
+        }
+
+        Err(Error::ChannelStopped)
+    }
+
+    /// Serve the file and chunk requests sent on a stream until the
+    /// peer closes it.
+    async fn serve_stream(self: Arc<Self>, stream: StreamPtr) {
+        let replier = Replier::Stream(&stream);
+
+        loop {
+            let Ok((command, payload)) = stream.receive_raw().await else { break };
+
+            match command.as_str() {
+                FudFileRequest::NAME => {
+                    let Ok(request) = deserialize_async::<FudFileRequest>(&payload).await else {
+                        break
+                    };
+                    self.reply_file(&request.file_hash, &replier).await;
+                }
+
+                FudChunkRequest::NAME => {
+                    let Ok(request) = deserialize_async::<FudChunkRequest>(&payload).await else {
+                        break
+                    };
+                    self.reply_chunk(&request.chunk_hash, &replier).await;
+                }
+
+                _ => {
+                    debug!(
+                        target: "fud::ProtocolFud::serve_stream()",
+                        "Unexpected {} on stream from {}", command, self.channel.address(),
+                    );
+                    break
+                }
+            }
+        }
+
+        stream.close().await;
+    }
+}
+
+/// Destination of the replies to a request: the channel itself for
+/// plain message requests, or the stream the request came in on.
+enum Replier<'a> {
+    Channel(&'a ChannelPtr),
+    Stream(&'a StreamPtr),
+}
+
+impl Replier<'_> {
+    async fn send<M: Message>(&self, message: &M) -> Result<()> {
+        match self {
+            Self::Channel(channel) => channel.send(message).await,
+            Self::Stream(stream) => stream.send(message).await,
+        }
+    }
+
+    /// Reply that the requested chunk was not found, with the message
+    /// version the requesting peer understands
+    async fn chunk_not_found(&self, chunk_hash: &blake3::Hash) -> Result<()> {
+        match self {
+            Self::Channel(channel) => channel.send(&FudChunkNotFound).await,
+            Self::Stream(stream) => {
+                stream.send(&FudChunkNotFoundV2 { chunk_hash: *chunk_hash }).await
             }
         }
     }
@@ -456,8 +842,25 @@ impl ProtocolBase for ProtocolFud {
         self.jobsman.clone().spawn(self.clone().handle_fud_chunk_put(), executor.clone()).await;
         self.jobsman.clone().spawn(self.clone().handle_fud_file_route(), executor.clone()).await;
         self.jobsman.clone().spawn(self.clone().handle_fud_chunk_route(), executor.clone()).await;
+        self.jobsman
+            .clone()
+            .spawn(self.clone().handle_fud_chunk_withdraw(), executor.clone())
+            .await;
+        self.jobsman
+            .clone()
+            .spawn(self.clone().handle_fud_chunk_route_withdraw(), executor.clone())
+            .await;
         self.jobsman.clone().spawn(self.clone().handle_fud_file_request(), executor.clone()).await;
         self.jobsman.clone().spawn(self.clone().handle_fud_chunk_request(), executor.clone()).await;
+        self.jobsman.clone().spawn(self.clone().handle_fud_denylist_put(), executor.clone()).await;
+        self.jobsman
+            .clone()
+            .spawn(self.clone().handle_fud_denylist_request(), executor.clone())
+            .await;
+        self.jobsman
+            .clone()
+            .spawn(self.clone().handle_fud_streams(executor.clone()), executor.clone())
+            .await;
         debug!(target: "fud::ProtocolFud::start()", "END");
         Ok(())
     }
@@ -466,3 +869,48 @@ impl ProtocolBase for ProtocolFud {
         "ProtocolFud"
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use darkfi_sdk::crypto::SecretKey;
+    use darkfi_serial::{deserialize, serialize};
+    use rand::rngs::OsRng;
+
+    use super::*;
+
+    #[test]
+    fn chunk_not_found_versions() {
+        // The unversioned reply keeps its empty payload for older peers
+        assert!(serialize(&FudChunkNotFound).is_empty());
+        assert_ne!(FudChunkNotFound::NAME, FudChunkNotFoundV2::NAME);
+
+        let chunk_hash = blake3::hash(b"chunk");
+        let reply = serialize(&FudChunkNotFoundV2 { chunk_hash });
+        let decoded: FudChunkNotFoundV2 = deserialize(&reply).unwrap();
+        assert_eq!(decoded.chunk_hash, chunk_hash);
+
+        // An unversioned payload can't pass for a versioned one
+        assert!(deserialize::<FudChunkNotFoundV2>(&serialize(&FudChunkNotFound)).is_err());
+    }
+
+    #[test]
+    fn file_reply_versions() {
+        let chunk_hashes = vec![blake3::hash(b"a"), blake3::hash(b"b")];
+
+        // Older peers keep getting and sending replies without a publisher
+        let legacy = FudFileReply { chunk_hashes: chunk_hashes.clone() };
+        assert_eq!(serialize(&legacy), serialize(&chunk_hashes));
+        let upgraded = FudFileReplyV2::from(legacy);
+        assert_eq!(upgraded.chunk_hashes, chunk_hashes);
+        assert!(upgraded.publisher.is_none());
+        assert_ne!(FudFileReply::NAME, FudFileReplyV2::NAME);
+
+        let file_hash = blake3::hash(b"file");
+        let secret = SecretKey::random(&mut OsRng);
+        let publisher = Some(PublisherSignature::sign(&secret, &file_hash, &chunk_hashes));
+        let reply = FudFileReplyV2 { chunk_hashes: chunk_hashes.clone(), publisher };
+        let decoded: FudFileReplyV2 = deserialize(&serialize(&reply)).unwrap();
+        assert_eq!(decoded.chunk_hashes, chunk_hashes);
+        assert!(decoded.publisher.unwrap().verify(&file_hash, &chunk_hashes));
+    }
+}
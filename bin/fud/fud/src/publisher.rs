@@ -0,0 +1,278 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2025 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Publisher signatures over file metadata.
+//!
+//! A publisher signs the file hash together with its chunk hashes, and
+//! the signature travels along with the metadata in [`FudFileReplyV2`].
+//! Nodes keep the signatures of the files they hold so they can serve
+//! them on, and may pin a set of trusted publishers, rejecting metadata
+//! that wasn't signed by one of them.
+//!
+//! [`FudFileReplyV2`]: crate::proto::FudFileReplyV2
+
+use std::{
+    collections::HashSet,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+use async_trait::async_trait;
+use darkfi::{Error, Result};
+use darkfi_sdk::crypto::{
+    schnorr::{SchnorrPublic, SchnorrSecret, Signature},
+    PublicKey, SecretKey,
+};
+use darkfi_serial::{deserialize, serialize, SerialDecodable, SerialEncodable};
+use log::warn;
+use smol::fs;
+
+/// Directory inside the base directory holding publisher signatures
+const PUBLISHERS_PATH: &str = "publishers";
+
+/// Signature of a publisher over a file's metadata
+#[derive(Debug, Clone, SerialEncodable, SerialDecodable)]
+pub struct PublisherSignature {
+    pub public_key: PublicKey,
+    pub signature: Signature,
+}
+
+impl PublisherSignature {
+    /// Sign the given file metadata with the publisher secret key
+    pub fn sign(
+        secret: &SecretKey,
+        file_hash: &blake3::Hash,
+        chunk_hashes: &[blake3::Hash],
+    ) -> Self {
+        Self::sign_hash(secret, &metadata_message(file_hash, chunk_hashes))
+    }
+
+    /// Verify the signature over the given file metadata
+    pub fn verify(&self, file_hash: &blake3::Hash, chunk_hashes: &[blake3::Hash]) -> bool {
+        self.verify_hash(&metadata_message(file_hash, chunk_hashes))
+    }
+
+    /// Sign an arbitrary message hash with the publisher secret key
+    pub fn sign_hash(secret: &SecretKey, message: &blake3::Hash) -> Self {
+        let signature = secret.sign(message.as_bytes());
+        Self { public_key: PublicKey::from_secret(*secret), signature }
+    }
+
+    /// Verify the signature over an arbitrary message hash
+    pub fn verify_hash(&self, message: &blake3::Hash) -> bool {
+        self.public_key.verify(message.as_bytes(), &self.signature)
+    }
+}
+
+/// Hash of the file metadata that gets signed by publishers
+fn metadata_message(file_hash: &blake3::Hash, chunk_hashes: &[blake3::Hash]) -> blake3::Hash {
+    let mut hasher = blake3::Hasher::new();
+    hasher.update(b"fud:metadata");
+    hasher.update(file_hash.as_bytes());
+    for chunk_hash in chunk_hashes {
+        hasher.update(chunk_hash.as_bytes());
+    }
+    hasher.finalize()
+}
+
+/// Publisher key configuration and signature storage
+pub struct Publishers {
+    /// Our own publisher key, used to sign inserted files
+    secret: Option<SecretKey>,
+    /// Pinned publisher keys, empty if any publisher is accepted
+    trusted: HashSet<PublicKey>,
+    /// Path to the signature storage directory
+    path: PathBuf,
+}
+
+impl Publishers {
+    /// Instantiate the publisher configuration, creating the signature
+    /// storage directory inside `basedir` if it doesn't exist.
+    pub async fn new(basedir: &Path, secret: Option<&str>, trusted: &[String]) -> Result<Self> {
+        let secret = match secret {
+            Some(s) => match SecretKey::from_str(s) {
+                Ok(v) => Some(v),
+                Err(_) => return Err(Error::ParseFailed("Invalid publisher secret key")),
+            },
+            None => None,
+        };
+
+        let mut pinned = HashSet::new();
+        for key in trusted {
+            let Ok(public_key) = PublicKey::from_str(key) else {
+                return Err(Error::ParseFailed("Invalid trusted publisher key"))
+            };
+            pinned.insert(public_key);
+        }
+
+        let path = basedir.join(PUBLISHERS_PATH);
+        fs::create_dir_all(&path).await?;
+
+        Ok(Self { secret, trusted: pinned, path })
+    }
+
+    /// Our publisher public key, if we sign inserted files
+    pub fn public_key(&self) -> Option<PublicKey> {
+        self.secret.map(PublicKey::from_secret)
+    }
+
+    /// Sign the given file metadata with our publisher key, if configured
+    pub fn sign(
+        &self,
+        file_hash: &blake3::Hash,
+        chunk_hashes: &[blake3::Hash],
+    ) -> Option<PublisherSignature> {
+        self.secret.as_ref().map(|s| PublisherSignature::sign(s, file_hash, chunk_hashes))
+    }
+
+    /// Sign an arbitrary message hash with our publisher key, if configured
+    pub fn sign_hash(&self, message: &blake3::Hash) -> Option<PublisherSignature> {
+        self.secret.as_ref().map(|s| PublisherSignature::sign_hash(s, message))
+    }
+
+    /// Check whether metadata received from the network is acceptable.
+    /// A signature, if present, must be valid. If trusted publishers are
+    /// pinned, the metadata must be signed by one of them.
+    pub fn accept(
+        &self,
+        file_hash: &blake3::Hash,
+        chunk_hashes: &[blake3::Hash],
+        publisher: &Option<PublisherSignature>,
+    ) -> bool {
+        match publisher {
+            Some(p) => {
+                if !p.verify(file_hash, chunk_hashes) {
+                    warn!(target: "fud::publisher", "Invalid publisher signature for {}", file_hash);
+                    return false
+                }
+
+                if !self.trusted.is_empty() && !self.trusted.contains(&p.public_key) {
+                    warn!(target: "fud::publisher", "{} signed by untrusted publisher {}", file_hash, p.public_key);
+                    return false
+                }
+
+                true
+            }
+            None => {
+                if !self.trusted.is_empty() {
+                    warn!(target: "fud::publisher", "Unsigned metadata for {} rejected", file_hash);
+                    return false
+                }
+
+                true
+            }
+        }
+    }
+
+    /// Store the publisher signature of a file
+    pub async fn put(
+        &self,
+        file_hash: &blake3::Hash,
+        publisher: &PublisherSignature,
+    ) -> Result<()> {
+        let path = self.path.join(file_hash.to_hex().as_str());
+        fs::write(path, serialize(publisher)).await?;
+        Ok(())
+    }
+
+    /// Fetch the stored publisher signature of a file, if any
+    pub async fn get(&self, file_hash: &blake3::Hash) -> Option<PublisherSignature> {
+        let path = self.path.join(file_hash.to_hex().as_str());
+        let data = fs::read(path).await.ok()?;
+        deserialize(&data).ok()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::{rngs::OsRng, Rng};
+
+    use super::*;
+
+    #[test]
+    fn publisher_signature() {
+        let secret = SecretKey::random(&mut OsRng);
+        let file_hash = blake3::hash(b"file");
+        let chunk_hashes = vec![blake3::hash(b"a"), blake3::hash(b"b")];
+
+        let publisher = PublisherSignature::sign(&secret, &file_hash, &chunk_hashes);
+        assert_eq!(publisher.public_key, PublicKey::from_secret(secret));
+        assert!(publisher.verify(&file_hash, &chunk_hashes));
+
+        // Any tampering with the signed metadata is detected
+        assert!(!publisher.verify(&blake3::hash(b"other"), &chunk_hashes));
+        assert!(!publisher.verify(&file_hash, &chunk_hashes[..1]));
+        let reordered = vec![chunk_hashes[1], chunk_hashes[0]];
+        assert!(!publisher.verify(&file_hash, &reordered));
+        let mut forged = publisher.clone();
+        forged.public_key = PublicKey::from_secret(SecretKey::random(&mut OsRng));
+        assert!(!forged.verify(&file_hash, &chunk_hashes));
+
+        // Metadata signatures can't be replayed as arbitrary message ones
+        let message = metadata_message(&file_hash, &chunk_hashes);
+        assert!(publisher.verify_hash(&message));
+        assert!(!publisher.verify_hash(&file_hash));
+    }
+
+    #[test]
+    fn publishers_accept() {
+        smol::block_on(async {
+            let basedir =
+                std::env::temp_dir().join(format!("fud_publisher_{}", OsRng.gen::<u64>()));
+            let trusted = SecretKey::random(&mut OsRng);
+            let stranger = SecretKey::random(&mut OsRng);
+            let file_hash = blake3::hash(b"file");
+            let chunk_hashes = vec![blake3::hash(b"a")];
+            let signed = Some(PublisherSignature::sign(&trusted, &file_hash, &chunk_hashes));
+            let foreign = Some(PublisherSignature::sign(&stranger, &file_hash, &chunk_hashes));
+            let forged = Some(PublisherSignature::sign(&trusted, &file_hash, &[]));
+
+            // Without pinned publishers, anything validly signed or unsigned goes
+            let open = Publishers::new(&basedir, None, &[]).await.unwrap();
+            assert!(open.public_key().is_none());
+            assert!(open.sign(&file_hash, &chunk_hashes).is_none());
+            assert!(open.accept(&file_hash, &chunk_hashes, &None));
+            assert!(open.accept(&file_hash, &chunk_hashes, &signed));
+            assert!(open.accept(&file_hash, &chunk_hashes, &foreign));
+            assert!(!open.accept(&file_hash, &chunk_hashes, &forged));
+
+            // Pinned publishers reject unsigned and foreign metadata
+            let pinned = vec![PublicKey::from_secret(trusted).to_string()];
+            let secret = trusted.to_string();
+            let strict = Publishers::new(&basedir, Some(&secret), &pinned).await.unwrap();
+            assert_eq!(strict.public_key(), Some(PublicKey::from_secret(trusted)));
+            assert!(!strict.accept(&file_hash, &chunk_hashes, &None));
+            assert!(strict.accept(&file_hash, &chunk_hashes, &signed));
+            assert!(!strict.accept(&file_hash, &chunk_hashes, &foreign));
+            assert!(!strict.accept(&file_hash, &chunk_hashes, &forged));
+
+            // Our own signatures are accepted by ourselves, and stored
+            let own = strict.sign(&file_hash, &chunk_hashes);
+            assert!(strict.accept(&file_hash, &chunk_hashes, &own));
+            strict.put(&file_hash, own.as_ref().unwrap()).await.unwrap();
+            let stored = strict.get(&file_hash).await.unwrap();
+            assert!(stored.verify(&file_hash, &chunk_hashes));
+            assert!(strict.get(&blake3::hash(b"missing")).await.is_none());
+
+            assert!(Publishers::new(&basedir, Some("invalid"), &[]).await.is_err());
+            assert!(Publishers::new(&basedir, None, &["invalid".to_string()]).await.is_err());
+
+            fs::remove_dir_all(&basedir).await.unwrap();
+        })
+    }
+}
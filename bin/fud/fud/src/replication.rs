@@ -0,0 +1,332 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2025 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Replication of announces to the peers closest to a key.
+//!
+//! fud floods announces and route relays to every connected peer, since
+//! there is no DHT lookup yet for nodes to find the peers responsible for
+//! a key, so they can only learn routes that reach them. With a
+//! replication factor set, the peers whose IDs are closest to the
+//! announced key by XOR distance are tracked as its replicas.
+//!
+//! Peers come and go though, and the responsible set of a key drifts with
+//! them. The replication task periodically recomputes the closest peers
+//! for the keys we store or route, and re-replicates to the ones that
+//! joined the set since the last time we announced to it, so peers
+//! connecting after the flood still learn the routes they are
+//! responsible for.
+//!
+//! Peers are identified by the first external address they advertise,
+//! which stays the same across reconnections, falling back to their
+//! channel address when they advertise none.
+
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Arc,
+};
+
+use log::{debug, error, info};
+use smol::lock::RwLock;
+use url::Url;
+
+use darkfi::{
+    net::{ChannelPtr, Message, P2pPtr},
+    system::sleep,
+    Error, Result,
+};
+
+use super::{
+    proto::{FudChunkRoute, FudFilePut, FudFileRoute},
+    Fud,
+};
+
+/// XOR distance between a key and the hash of a peer ID
+fn distance(key: &blake3::Hash, peer: &Url) -> [u8; 32] {
+    let peer_hash = blake3::hash(peer.as_str().as_bytes());
+    let mut distance = [0u8; 32];
+    for (i, byte) in distance.iter_mut().enumerate() {
+        *byte = key.as_bytes()[i] ^ peer_hash.as_bytes()[i];
+    }
+    distance
+}
+
+/// Stable ID of the peer of a channel: the first external address it
+/// advertised, or its channel address if it advertised none.
+pub async fn peer_id(channel: &ChannelPtr) -> Url {
+    let version = channel.version.lock().await.clone();
+    let advertised = version.and_then(|v| v.ext_send_addr.first().cloned());
+    advertised.unwrap_or_else(|| channel.address().clone())
+}
+
+/// Keep the `factor` peers closest to `key` by the XOR distance of their IDs
+fn closest_peers<T>(key: &blake3::Hash, mut peers: Vec<(Url, T)>, factor: usize) -> Vec<(Url, T)> {
+    peers.sort_by_cached_key(|(id, _)| distance(key, id));
+    peers.truncate(factor);
+    peers
+}
+
+/// Announce replication state
+pub struct Replicas {
+    /// Number of closest peers tracked as replicas of a key
+    /// (replication is disabled if zero)
+    factor: usize,
+    /// IDs of the peers each key was last replicated to
+    replicated: RwLock<HashMap<blake3::Hash, HashSet<Url>>>,
+}
+
+impl Replicas {
+    pub fn new(factor: usize) -> Self {
+        Self { factor, replicated: RwLock::new(HashMap::new()) }
+    }
+
+    /// Number of closest peers tracked as replicas of a key
+    /// (replication is disabled if zero)
+    pub fn factor(&self) -> usize {
+        self.factor
+    }
+
+    /// Retrieve the `factor` channels closest to `key`, along with
+    /// their peer IDs.
+    async fn closest(&self, p2p: &P2pPtr, key: &blake3::Hash) -> Vec<(Url, ChannelPtr)> {
+        let mut peers = vec![];
+        for channel in p2p.hosts().peers() {
+            peers.push((peer_id(&channel).await, channel));
+        }
+
+        closest_peers(key, peers, self.factor)
+    }
+
+    /// Flood an announce for `key` to all peers, excluding the ones in
+    /// `exclude`, and remember its closest peers as replicas of the key.
+    pub async fn announce<M: Message>(
+        &self,
+        p2p: &P2pPtr,
+        key: &blake3::Hash,
+        message: &M,
+        exclude: &[Url],
+    ) {
+        p2p.broadcast_with_exclude(message, exclude).await;
+
+        if self.factor == 0 {
+            return
+        }
+
+        let closest = self.closest(p2p, key).await;
+        let mut replicated = self.replicated.write().await;
+        let replicas = replicated.entry(*key).or_default();
+        replicas.extend(closest.into_iter().map(|(id, _)| id));
+    }
+
+    /// Recompute the closest peers of `key`, returning the ones it was
+    /// not yet replicated to. Peers that left the closest set are
+    /// forgotten, so they get re-replicated to if they join it again.
+    async fn churned(&self, p2p: &P2pPtr, key: &blake3::Hash) -> Vec<ChannelPtr> {
+        let closest = self.closest(p2p, key).await;
+
+        let mut replicated = self.replicated.write().await;
+        let previous = replicated.remove(key).unwrap_or_default();
+        replicated.insert(*key, closest.iter().map(|(id, _)| id.clone()).collect());
+
+        closest.into_iter().filter(|(id, _)| !previous.contains(id)).map(|(_, c)| c).collect()
+    }
+
+    /// Forget the replicas of keys we no longer store or route
+    async fn retain(&self, keys: &HashSet<blake3::Hash>) {
+        self.replicated.write().await.retain(|key, _| keys.contains(key));
+    }
+}
+
+/// Background task re-replicating the keys we store or route to peers
+/// that joined their closest set, every `interval` seconds.
+pub async fn replication_task(fud: Arc<Fud>, interval: u64) -> Result<()> {
+    info!(
+        target: "fud::replication",
+        "Replicating announces to {} closest peers", fud.replicas.factor(),
+    );
+
+    loop {
+        sleep(interval).await;
+
+        if !fud.p2p.is_connected() {
+            continue
+        }
+
+        let mut keys = HashSet::new();
+        let mut rereplicated = 0;
+        let mut sent = 0;
+
+        // Files we store, announcing ourselves as their seeder
+        let files = match fud.geode.list_files().await {
+            Ok(v) => v,
+            Err(e) => {
+                error!(target: "fud::replication", "Failed listing stored files: {}", e);
+                vec![]
+            }
+        };
+
+        for file_hash in files {
+            let chunked_file = match fud.geode.get(&file_hash).await {
+                Ok(v) => v,
+                Err(Error::GeodeFileNotFound) | Err(Error::GeodeNeedsGc) => continue,
+                Err(e) => {
+                    error!(target: "fud::replication", "Failed retrieving file {}: {}", file_hash, e);
+                    continue
+                }
+            };
+
+            // Denylisted files are neither seeded nor routed
+            let chunk_hashes: Vec<_> = chunked_file.iter().map(|(h, _)| *h).collect();
+            if fud.denylisted(&file_hash, &chunk_hashes).await.is_some() {
+                continue
+            }
+
+            keys.insert(file_hash);
+            let channels = fud.replicas.churned(&fud.p2p, &file_hash).await;
+            if channels.is_empty() {
+                continue
+            }
+
+            fud.p2p.broadcast_to(&FudFilePut { file_hash, chunk_hashes }, &channels).await;
+            rereplicated += 1;
+            sent += channels.len();
+        }
+
+        // File routes we know about. Their chunk hashes are only known
+        // to us if we hold the file metadata.
+        let metadata_routes: Vec<_> = fud
+            .metadata_router
+            .read()
+            .await
+            .iter()
+            .filter(|(file_hash, _)| !keys.contains(*file_hash))
+            .map(|(file_hash, peers)| (*file_hash, peers.clone()))
+            .collect();
+
+        for (file_hash, peers) in metadata_routes {
+            let Ok(chunked_file) = fud.geode.get(&file_hash).await else {
+                debug!(target: "fud::replication", "No metadata for routed file {}", file_hash);
+                continue
+            };
+
+            // Denylisted files are neither seeded nor routed
+            let chunk_hashes: Vec<_> = chunked_file.iter().map(|(h, _)| *h).collect();
+            if fud.denylisted(&file_hash, &chunk_hashes).await.is_some() {
+                continue
+            }
+
+            keys.insert(file_hash);
+            let channels = fud.replicas.churned(&fud.p2p, &file_hash).await;
+            if channels.is_empty() {
+                continue
+            }
+
+            for peer in peers {
+                let route = FudFileRoute { file_hash, chunk_hashes: chunk_hashes.clone(), peer };
+                relay(&fud.p2p, &route, &route.peer, &channels).await;
+            }
+            rereplicated += 1;
+            sent += channels.len();
+        }
+
+        // Chunk routes we know about
+        let chunk_routes: Vec<_> = fud
+            .chunks_router
+            .read()
+            .await
+            .iter()
+            .map(|(chunk_hash, peers)| (*chunk_hash, peers.clone()))
+            .collect();
+
+        for (chunk_hash, peers) in chunk_routes {
+            if fud.denylists.check(&chunk_hash).await.is_some() {
+                continue
+            }
+
+            keys.insert(chunk_hash);
+            let channels = fud.replicas.churned(&fud.p2p, &chunk_hash).await;
+            if channels.is_empty() {
+                continue
+            }
+
+            for peer in peers {
+                let route = FudChunkRoute { chunk_hash, peer };
+                relay(&fud.p2p, &route, &route.peer, &channels).await;
+            }
+            rereplicated += 1;
+            sent += channels.len();
+        }
+
+        fud.replicas.retain(&keys).await;
+
+        if rereplicated > 0 {
+            info!(
+                target: "fud::replication",
+                "Re-replicated {} keys to {} new responsible peers", rereplicated, sent,
+            );
+        }
+    }
+}
+
+/// Relay a route to the given channels, skipping the routed peer itself
+async fn relay<M: Message>(p2p: &P2pPtr, route: &M, peer: &Url, channels: &[ChannelPtr]) {
+    let channels: Vec<_> = channels.iter().filter(|c| c.address() != peer).cloned().collect();
+    if channels.is_empty() {
+        return
+    }
+    p2p.broadcast_to(route, &channels).await;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn closest_replicas() {
+        let key = blake3::hash(b"foo");
+        let ids: Vec<Url> =
+            (0..10).map(|i| Url::parse(&format!("tcp://node{i}.dark.fi:13337")).unwrap()).collect();
+
+        // A peer whose ID hashes to the key is at distance zero
+        assert_eq!(distance(&blake3::hash(ids[0].as_str().as_bytes()), &ids[0]), [0u8; 32]);
+        assert_ne!(distance(&key, &ids[0]), [0u8; 32]);
+
+        // The closest peers are selected by the XOR distance of their IDs,
+        // regardless of the order they are connected in
+        let peers: Vec<_> = ids.iter().cloned().enumerate().map(|(i, id)| (id, i)).collect();
+        let closest = closest_peers(&key, peers.clone(), 3);
+        assert_eq!(closest.len(), 3);
+        let mut reversed = peers.clone();
+        reversed.reverse();
+        assert_eq!(closest_peers(&key, reversed, 3), closest);
+
+        let farthest = distance(&key, &closest[2].0);
+        assert!(closest.windows(2).all(|w| distance(&key, &w[0].0) <= distance(&key, &w[1].0)));
+        for (id, _) in peers.iter().filter(|p| !closest.contains(p)) {
+            assert!(distance(&key, id) >= farthest);
+        }
+
+        // Another key gets its own closest set
+        let other = closest_peers(&blake3::hash(b"bar"), peers.clone(), 3);
+        assert_eq!(other.len(), 3);
+
+        // All peers are kept when the factor exceeds their number,
+        // and none when replication is disabled
+        assert_eq!(closest_peers(&key, peers.clone(), 20).len(), peers.len());
+        assert!(closest_peers(&key, peers, 0).is_empty());
+    }
+}
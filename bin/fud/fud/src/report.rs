@@ -0,0 +1,458 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2025 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Download integrity reports.
+//!
+//! Once a scheduled download completes, fud can write a report of what
+//! was fetched and from where: the file hash, its chunk hashes and sizes,
+//! the completion time, and the seeders that served its chunks during
+//! that download. If a publisher key is configured, the report is signed
+//! with it, so downstream automation can prove what was fetched. Reports
+//! are kept as `<hash>.json` files in a directory inside the base
+//! directory, and whenever the file is assembled at a destination, its
+//! report is written alongside it as `<destination>.report.json`.
+//! Unsigned reports can't prove anything, so they never verify as valid.
+
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+use darkfi::{util::time::Timestamp, Error, Result};
+use darkfi_sdk::{
+    crypto::PublicKey,
+    error::GenericResult,
+    hex::{decode_hex, AsHex},
+};
+use darkfi_serial::{deserialize, serialize};
+use smol::fs;
+use tinyjson::JsonValue;
+
+use super::{publisher::PublisherSignature, Fud};
+
+/// Directory inside the base directory holding download reports
+const REPORTS_PATH: &str = "reports";
+
+/// Report of a completed download
+#[derive(Clone, Debug)]
+pub struct DownloadReport {
+    /// Hash of the downloaded file
+    pub file_hash: blake3::Hash,
+    /// Hashes and sizes of the file's chunks
+    pub chunks: Vec<(blake3::Hash, u64)>,
+    /// Download completion UNIX timestamp
+    pub completed: u64,
+    /// Seeders the chunks were downloaded from, with the bytes they served
+    pub seeders: Vec<(String, u64)>,
+    /// Signature over the report, if a publisher key is configured
+    pub signature: Option<PublisherSignature>,
+}
+
+impl DownloadReport {
+    /// Total size of the downloaded file
+    pub fn size(&self) -> u64 {
+        self.chunks.iter().map(|(_, size)| size).sum()
+    }
+
+    /// Hash of the report contents that gets signed
+    fn message(&self) -> blake3::Hash {
+        let mut hasher = blake3::Hasher::new();
+        hasher.update(b"fud:report");
+        hasher.update(self.file_hash.as_bytes());
+        for (chunk_hash, size) in &self.chunks {
+            hasher.update(chunk_hash.as_bytes());
+            hasher.update(&size.to_le_bytes());
+        }
+        hasher.update(&self.completed.to_le_bytes());
+        for (seeder, bytes) in &self.seeders {
+            hasher.update(&(seeder.len() as u64).to_le_bytes());
+            hasher.update(seeder.as_bytes());
+            hasher.update(&bytes.to_le_bytes());
+        }
+        hasher.finalize()
+    }
+
+    /// Check the report signature. Returns `None` if the report is unsigned.
+    pub fn verify_signature(&self) -> Option<bool> {
+        self.signature.as_ref().map(|s| s.verify_hash(&self.message()))
+    }
+
+    /// Returns a JSON representation of the report
+    pub fn json(&self) -> JsonValue {
+        let chunks = self
+            .chunks
+            .iter()
+            .map(|(chunk_hash, size)| {
+                JsonValue::Object(HashMap::from([
+                    ("hash".to_string(), JsonValue::String(chunk_hash.to_hex().to_string())),
+                    ("size".to_string(), JsonValue::Number(*size as f64)),
+                ]))
+            })
+            .collect();
+
+        let seeders = self
+            .seeders
+            .iter()
+            .map(|(seeder, bytes)| {
+                JsonValue::Object(HashMap::from([
+                    ("peer".to_string(), JsonValue::String(seeder.clone())),
+                    ("downloaded".to_string(), JsonValue::Number(*bytes as f64)),
+                ]))
+            })
+            .collect();
+
+        let (publisher, signature) = match &self.signature {
+            Some(s) => (
+                JsonValue::String(s.public_key.to_string()),
+                JsonValue::String(serialize(&s.signature).hex()),
+            ),
+            None => (JsonValue::Null, JsonValue::Null),
+        };
+
+        JsonValue::Object(HashMap::from([
+            ("file_hash".to_string(), JsonValue::String(self.file_hash.to_hex().to_string())),
+            ("size".to_string(), JsonValue::Number(self.size() as f64)),
+            ("chunks".to_string(), JsonValue::Array(chunks)),
+            ("completed".to_string(), JsonValue::Number(self.completed as f64)),
+            ("seeders".to_string(), JsonValue::Array(seeders)),
+            ("publisher".to_string(), publisher),
+            ("signature".to_string(), signature),
+        ]))
+    }
+
+    /// Parse a report from its JSON representation
+    pub fn from_json(json: &JsonValue) -> Option<Self> {
+        let object = json.get::<HashMap<String, JsonValue>>()?;
+
+        let file_hash = blake3::Hash::from_hex(object.get("file_hash")?.get::<String>()?).ok()?;
+
+        let mut chunks = vec![];
+        for chunk in object.get("chunks")?.get::<Vec<JsonValue>>()? {
+            let chunk = chunk.get::<HashMap<String, JsonValue>>()?;
+            let chunk_hash = blake3::Hash::from_hex(chunk.get("hash")?.get::<String>()?).ok()?;
+            let size = *chunk.get("size")?.get::<f64>()? as u64;
+            chunks.push((chunk_hash, size));
+        }
+
+        let completed = *object.get("completed")?.get::<f64>()? as u64;
+
+        let mut seeders = vec![];
+        for seeder in object.get("seeders")?.get::<Vec<JsonValue>>()? {
+            let seeder = seeder.get::<HashMap<String, JsonValue>>()?;
+            let peer = seeder.get("peer")?.get::<String>()?.clone();
+            let bytes = *seeder.get("downloaded")?.get::<f64>()? as u64;
+            seeders.push((peer, bytes));
+        }
+
+        let signature = match (object.get("publisher")?, object.get("signature")?) {
+            (JsonValue::Null, JsonValue::Null) => None,
+            (JsonValue::String(public_key), JsonValue::String(signature)) => {
+                let public_key = PublicKey::from_str(public_key).ok()?;
+                let signature: Vec<u8> =
+                    decode_hex(signature).collect::<GenericResult<_>>().ok()?;
+                let signature = deserialize(&signature).ok()?;
+                Some(PublisherSignature { public_key, signature })
+            }
+            _ => return None,
+        };
+
+        Some(Self { file_hash, chunks, completed, seeders, signature })
+    }
+}
+
+/// Download report configuration and storage
+pub struct Reports {
+    /// Whether reports are written when scheduled downloads complete
+    enabled: bool,
+    /// Path to the reports directory
+    path: PathBuf,
+}
+
+impl Reports {
+    /// Instantiate the report storage, creating the reports directory
+    /// inside `basedir` if it doesn't exist.
+    pub async fn new(basedir: &Path, enabled: bool) -> Result<Self> {
+        let path = basedir.join(REPORTS_PATH);
+        fs::create_dir_all(&path).await?;
+        Ok(Self { enabled, path })
+    }
+
+    /// Whether reports are written when scheduled downloads complete
+    pub fn enabled(&self) -> bool {
+        self.enabled
+    }
+
+    /// Path to the report file of a file
+    pub fn path(&self, file_hash: &blake3::Hash) -> PathBuf {
+        self.path.join(format!("{}.json", file_hash.to_hex()))
+    }
+
+    /// Fetch the stored report of a file, if any. Returns an error if
+    /// the report exists but can't be parsed.
+    pub async fn get(&self, file_hash: &blake3::Hash) -> Result<Option<DownloadReport>> {
+        let path = self.path(file_hash);
+        if !path.exists() {
+            return Ok(None)
+        }
+
+        let data = fs::read_to_string(&path).await?;
+        let Ok(json) = data.parse::<JsonValue>() else {
+            return Err(Error::ParseFailed("Invalid download report JSON"))
+        };
+        match DownloadReport::from_json(&json) {
+            Some(report) => Ok(Some(report)),
+            None => Err(Error::ParseFailed("Invalid download report")),
+        }
+    }
+
+    /// Store a report, returning the path it was written to
+    pub async fn put(&self, report: &DownloadReport) -> Result<PathBuf> {
+        let path = self.path(&report.file_hash);
+        write_report(report, &path).await?;
+        Ok(path)
+    }
+
+    /// Write the stored report of a file alongside the file assembled at
+    /// `content_path`, if reports are enabled and one exists. Returns the
+    /// path the report was written to.
+    pub async fn put_alongside(
+        &self,
+        file_hash: &blake3::Hash,
+        content_path: &Path,
+    ) -> Result<Option<PathBuf>> {
+        if !self.enabled {
+            return Ok(None)
+        }
+
+        let Some(report) = self.get(file_hash).await? else { return Ok(None) };
+        let mut path = content_path.as_os_str().to_owned();
+        path.push(".report.json");
+        let path = PathBuf::from(path);
+        write_report(&report, &path).await?;
+        Ok(Some(path))
+    }
+}
+
+/// Write a report to `path`, replacing any existing one atomically
+async fn write_report(report: &DownloadReport, path: &Path) -> Result<()> {
+    let Ok(data) = report.json().format() else {
+        return Err(Error::ParseFailed("Failed formatting download report"))
+    };
+
+    let tmp_path = path.with_extension("tmp");
+    fs::write(&tmp_path, data).await?;
+    fs::rename(&tmp_path, path).await?;
+    Ok(())
+}
+
+/// Generate and store the report of a complete file, signing it with our
+/// publisher key if configured. Unless they are given, the completion
+/// time and the seeders used by the download are kept from the previously
+/// stored report, so regenerating a report doesn't lose them.
+pub async fn generate_report(
+    fud: &Fud,
+    file_hash: &blake3::Hash,
+    completed: Option<u64>,
+    seeders: Option<Vec<(String, u64)>>,
+) -> Result<DownloadReport> {
+    let chunked_file = fud.geode.get(file_hash).await?;
+    if !chunked_file.is_complete() {
+        return Err(Error::Custom(format!("File {} is incomplete", file_hash)))
+    }
+
+    let mut chunks = Vec::with_capacity(chunked_file.iter().len());
+    for (chunk_hash, path) in chunked_file.iter() {
+        let size = fs::metadata(path.as_ref().unwrap()).await?.len();
+        chunks.push((*chunk_hash, size));
+    }
+
+    let previous = fud.reports.get(file_hash).await.ok().flatten();
+    let completed = match (completed, &previous) {
+        (Some(completed), _) => completed,
+        (None, Some(p)) => p.completed,
+        (None, None) => Timestamp::current_time().inner(),
+    };
+    let mut seeders = match (seeders, previous) {
+        (Some(seeders), _) => seeders,
+        (None, Some(p)) => p.seeders,
+        (None, None) => vec![],
+    };
+    seeders.sort();
+
+    let mut report =
+        DownloadReport { file_hash: *file_hash, chunks, completed, seeders, signature: None };
+    report.signature = fud.publishers.sign_hash(&report.message());
+
+    fud.reports.put(&report).await?;
+    Ok(report)
+}
+
+/// Verify the stored report of a file against its signature and the
+/// chunks we currently hold. Returns a list of the problems found, which
+/// is empty if the report is valid.
+pub async fn verify_report(fud: &Fud, report: &DownloadReport) -> Vec<String> {
+    let mut errors = vec![];
+
+    match report.verify_signature() {
+        Some(true) => {}
+        Some(false) => errors.push("Invalid report signature".to_string()),
+        None => errors.push("Report is unsigned".to_string()),
+    }
+
+    let chunked_file = match fud.geode.get(&report.file_hash).await {
+        Ok(v) => v,
+        Err(e) => {
+            errors.push(format!("Failed reading file metadata: {}", e));
+            return errors
+        }
+    };
+
+    if chunked_file.iter().len() != report.chunks.len() ||
+        chunked_file.iter().zip(&report.chunks).any(|((a, _), (b, _))| a != b)
+    {
+        errors.push("Chunk hashes don't match the file metadata".to_string());
+        return errors
+    }
+
+    // Geode only returns paths to chunks that pass their consistency check
+    for ((chunk_hash, path), (_, size)) in chunked_file.iter().zip(&report.chunks) {
+        let Some(path) = path else {
+            errors.push(format!("Chunk {} is missing or corrupted", chunk_hash));
+            continue
+        };
+
+        match fs::metadata(path).await {
+            Ok(m) if m.len() == *size => {}
+            Ok(m) => errors.push(format!(
+                "Chunk {} is {} bytes, report says {}",
+                chunk_hash,
+                m.len(),
+                size
+            )),
+            Err(e) => errors.push(format!("Failed reading chunk {}: {}", chunk_hash, e)),
+        }
+    }
+
+    errors
+}
+
+#[cfg(test)]
+mod tests {
+    use darkfi_sdk::crypto::SecretKey;
+    use rand::{rngs::OsRng, Rng};
+
+    use super::*;
+
+    fn report(signer: Option<&SecretKey>) -> DownloadReport {
+        let mut report = DownloadReport {
+            file_hash: blake3::hash(b"file"),
+            chunks: vec![(blake3::hash(b"a"), 262_144), (blake3::hash(b"b"), 1234)],
+            completed: 1_700_000_000,
+            seeders: vec![("tcp+tls://seeder:13337".to_string(), 263_378)],
+            signature: None,
+        };
+        report.signature = signer.map(|s| PublisherSignature::sign_hash(s, &report.message()));
+        report
+    }
+
+    #[test]
+    fn report_json_roundtrip() {
+        let secret = SecretKey::random(&mut OsRng);
+        for report in [report(Some(&secret)), report(None)] {
+            let data = report.json().format().unwrap();
+            let parsed = DownloadReport::from_json(&data.parse().unwrap()).unwrap();
+            assert_eq!(parsed.json(), report.json());
+            assert_eq!(parsed.size(), 263_378);
+            assert_eq!(parsed.verify_signature(), report.verify_signature());
+        }
+
+        // A publisher without a signature, or the other way around, is rejected
+        let mut json = report(Some(&secret)).json();
+        let JsonValue::Object(ref mut object) = json else { unreachable!() };
+        object.insert("signature".to_string(), JsonValue::Null);
+        assert!(DownloadReport::from_json(&json).is_none());
+        object.remove("publisher");
+        assert!(DownloadReport::from_json(&json).is_none());
+    }
+
+    #[test]
+    fn report_signature() {
+        let secret = SecretKey::random(&mut OsRng);
+        let signed = report(Some(&secret));
+        assert_eq!(signed.verify_signature(), Some(true));
+        assert_eq!(signed.signature.as_ref().unwrap().public_key, PublicKey::from_secret(secret));
+
+        // Unsigned reports can't be verified
+        assert_eq!(report(None).verify_signature(), None);
+
+        // Any tampering with the report is detected
+        let mut tampered = signed.clone();
+        tampered.seeders[0].1 += 1;
+        assert_eq!(tampered.verify_signature(), Some(false));
+
+        let mut tampered = signed.clone();
+        tampered.chunks.swap(0, 1);
+        assert_eq!(tampered.verify_signature(), Some(false));
+
+        let mut tampered = signed.clone();
+        tampered.completed += 1;
+        assert_eq!(tampered.verify_signature(), Some(false));
+
+        let mut forged = signed.clone();
+        forged.signature.as_mut().unwrap().public_key =
+            PublicKey::from_secret(SecretKey::random(&mut OsRng));
+        assert_eq!(forged.verify_signature(), Some(false));
+    }
+
+    #[test]
+    fn reports_storage() {
+        smol::block_on(async {
+            let basedir = std::env::temp_dir().join(format!("fud_report_{}", OsRng.gen::<u64>()));
+            let report = report(Some(&SecretKey::random(&mut OsRng)));
+            let content_path = basedir.join("release.tar");
+
+            // Nothing is written alongside the content when disabled
+            let reports = Reports::new(&basedir, false).await.unwrap();
+            assert!(reports.get(&report.file_hash).await.unwrap().is_none());
+            reports.put(&report).await.unwrap();
+            assert!(reports
+                .put_alongside(&report.file_hash, &content_path)
+                .await
+                .unwrap()
+                .is_none());
+
+            let reports = Reports::new(&basedir, true).await.unwrap();
+            let stored = reports.get(&report.file_hash).await.unwrap().unwrap();
+            assert_eq!(stored.json(), report.json());
+
+            let path =
+                reports.put_alongside(&report.file_hash, &content_path).await.unwrap().unwrap();
+            assert_eq!(path, basedir.join("release.tar.report.json"));
+            let data = fs::read_to_string(&path).await.unwrap();
+            let alongside = DownloadReport::from_json(&data.parse().unwrap()).unwrap();
+            assert_eq!(alongside.json(), report.json());
+
+            // Files without a report get nothing written alongside them
+            let other = blake3::hash(b"other");
+            assert!(reports.put_alongside(&other, &content_path).await.unwrap().is_none());
+
+            fs::remove_dir_all(&basedir).await.unwrap();
+        })
+    }
+}
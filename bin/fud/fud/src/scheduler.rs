@@ -0,0 +1,621 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2025 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Download scheduler.
+//!
+//! Files requested through the `download` RPC are queued here instead
+//! of being fetched right away. The scheduler keeps at most
+//! `max_active` downloads running, starting queued ones by priority
+//! (and by request order within the same priority) as others complete.
+//! An optional daily time window restricts when new downloads may start.
+//! Downloads may also be capped in size, in which case files with more
+//! chunks than the cap allows are refused once their metadata is known,
+//! before any of their chunks get fetched.
+//!
+//! Missing chunks are fetched in batches, and the file's verified chunks
+//! are checkpointed in Geode after each batch, so a download interrupted
+//! by a crash resumes without re-hashing everything already written.
+
+use std::{
+    collections::HashMap,
+    fmt,
+    str::FromStr,
+    sync::{
+        atomic::{AtomicUsize, Ordering::SeqCst},
+        Arc,
+    },
+    time::Duration,
+};
+
+use log::{error, info, warn};
+use smol::{lock::RwLock, Executor};
+use tinyjson::JsonValue;
+use url::Url;
+
+use darkfi::{geode::MAX_CHUNK_SIZE, system::CondVar, util::time::Timestamp, Error, Result};
+
+use super::{
+    fetch_chunks, fetch_file,
+    names::Names,
+    proto::{FudChunkPut, FudFilePut},
+    report::generate_report,
+    Fud,
+};
+
+/// How often the scheduler re-checks its state when nothing happens,
+/// so schedule windows opening are noticed.
+const SCHEDULER_TICK: Duration = Duration::from_secs(60);
+
+/// Number of chunks fetched between download checkpoints (1 GiB)
+pub(crate) const CHECKPOINT_CHUNKS: usize = 4096;
+
+/// Download priority levels
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Ord, PartialOrd)]
+pub enum Priority {
+    Low = 0,
+    Normal = 1,
+    High = 2,
+}
+
+impl FromStr for Priority {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "low" => Ok(Self::Low),
+            "normal" => Ok(Self::Normal),
+            "high" => Ok(Self::High),
+            _ => Err(Error::ParseFailed("Invalid download priority")),
+        }
+    }
+}
+
+impl fmt::Display for Priority {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Low => write!(f, "low"),
+            Self::Normal => write!(f, "normal"),
+            Self::High => write!(f, "high"),
+        }
+    }
+}
+
+/// Download status of a scheduled resource
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum DownloadStatus {
+    Queued,
+    Active,
+    Done,
+    Failed(String),
+}
+
+impl fmt::Display for DownloadStatus {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Queued => write!(f, "queued"),
+            Self::Active => write!(f, "active"),
+            Self::Done => write!(f, "done"),
+            Self::Failed(e) => write!(f, "failed: {e}"),
+        }
+    }
+}
+
+/// Daily time window, in UTC, during which downloads may start.
+/// Windows wrapping around midnight, like `22:00-06:00`, are supported.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct ScheduleWindow {
+    /// Window start, in minutes since midnight
+    start: u32,
+    /// Window end, in minutes since midnight
+    end: u32,
+}
+
+impl ScheduleWindow {
+    /// Check if the given minute of the day falls in this window
+    pub fn contains(&self, minute: u32) -> bool {
+        if self.start <= self.end {
+            minute >= self.start && minute < self.end
+        } else {
+            minute >= self.start || minute < self.end
+        }
+    }
+
+    /// Check if the current time falls in this window
+    pub fn is_open(&self) -> bool {
+        let minute = (Timestamp::current_time().inner() % 86400) / 60;
+        self.contains(minute as u32)
+    }
+}
+
+/// Parse a `HH:MM` string into minutes since midnight
+fn parse_time_of_day(s: &str) -> Result<u32> {
+    let Some((hours, minutes)) = s.trim().split_once(':') else {
+        return Err(Error::ParseFailed("Invalid time of day: Expected HH:MM"))
+    };
+    let (Ok(hours), Ok(minutes)) = (hours.parse::<u32>(), minutes.parse::<u32>()) else {
+        return Err(Error::ParseFailed("Invalid time of day: Not a number"))
+    };
+    if hours > 23 || minutes > 59 {
+        return Err(Error::ParseFailed("Invalid time of day: Out of range"))
+    }
+
+    Ok(hours * 60 + minutes)
+}
+
+impl FromStr for ScheduleWindow {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let Some((start, end)) = s.split_once('-') else {
+            return Err(Error::ParseFailed("Invalid schedule window: Expected HH:MM-HH:MM"))
+        };
+        let start = parse_time_of_day(start)?;
+        let end = parse_time_of_day(end)?;
+        if start == end {
+            return Err(Error::ParseFailed("Invalid schedule window: Empty window"))
+        }
+
+        Ok(Self { start, end })
+    }
+}
+
+impl fmt::Display for ScheduleWindow {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{:02}:{:02}-{:02}:{:02}",
+            self.start / 60,
+            self.start % 60,
+            self.end / 60,
+            self.end % 60
+        )
+    }
+}
+
+/// A resource tracked by the scheduler
+#[derive(Clone, Debug)]
+struct Download {
+    /// Download priority
+    priority: Priority,
+    /// Monotonic request order, used to keep FIFO order within a priority
+    order: u64,
+    /// Current download status
+    status: DownloadStatus,
+    /// Optional maximum file size in bytes
+    max_size: Option<u64>,
+}
+
+/// Scheduler state, managing queued and active downloads
+pub struct Scheduler {
+    /// Tracked downloads
+    downloads: RwLock<HashMap<blake3::Hash, Download>>,
+    /// Request counter, used for ordering
+    counter: AtomicUsize,
+    /// Maximum number of concurrently active downloads
+    max_active: AtomicUsize,
+    /// Optional daily window during which downloads may start
+    window: RwLock<Option<ScheduleWindow>>,
+    /// Signal to wake up the scheduler task on state changes
+    notify: CondVar,
+}
+
+impl Scheduler {
+    pub fn new(max_active: usize, window: Option<ScheduleWindow>) -> Self {
+        Self {
+            downloads: RwLock::new(HashMap::new()),
+            counter: AtomicUsize::new(0),
+            max_active: AtomicUsize::new(max_active),
+            window: RwLock::new(window),
+            notify: CondVar::new(),
+        }
+    }
+
+    /// Queue a file for download with the given priority and optional
+    /// maximum size. If the file is already tracked, its priority and
+    /// maximum size are updated, and failed or completed downloads are
+    /// queued again. Returns `false` if it is already active.
+    pub async fn enqueue(
+        &self,
+        file_hash: blake3::Hash,
+        priority: Priority,
+        max_size: Option<u64>,
+    ) -> bool {
+        let mut downloads = self.downloads.write().await;
+        if let Some(download) = downloads.get_mut(&file_hash) {
+            download.priority = priority;
+            if download.status == DownloadStatus::Active {
+                return false
+            }
+            download.status = DownloadStatus::Queued;
+            download.max_size = max_size;
+        } else {
+            let order = self.counter.fetch_add(1, SeqCst) as u64;
+            let status = DownloadStatus::Queued;
+            downloads.insert(file_hash, Download { priority, order, status, max_size });
+        }
+        drop(downloads);
+
+        self.notify.notify();
+        true
+    }
+
+    /// Change the priority of a tracked download.
+    /// Returns `false` if the file is not tracked.
+    pub async fn set_priority(&self, file_hash: &blake3::Hash, priority: Priority) -> bool {
+        let mut downloads = self.downloads.write().await;
+        let Some(download) = downloads.get_mut(file_hash) else { return false };
+        download.priority = priority;
+        drop(downloads);
+
+        self.notify.notify();
+        true
+    }
+
+    /// Stop tracking a download. Active downloads run to completion,
+    /// but queued ones will not be started.
+    /// Returns `false` if the file is not tracked.
+    pub async fn remove(&self, file_hash: &blake3::Hash) -> bool {
+        self.downloads.write().await.remove(file_hash).is_some()
+    }
+
+    /// Set the maximum number of concurrently active downloads
+    pub fn set_max_active(&self, max_active: usize) {
+        self.max_active.store(max_active, SeqCst);
+        self.notify.notify();
+    }
+
+    /// Set or clear the daily schedule window
+    pub async fn set_window(&self, window: Option<ScheduleWindow>) {
+        *self.window.write().await = window;
+        self.notify.notify();
+    }
+
+    /// Pick the next queued download to start, if allowed, marking it active.
+    /// Returns its file hash along with its maximum size.
+    async fn next(&self) -> Option<(blake3::Hash, Option<u64>)> {
+        if let Some(window) = *self.window.read().await {
+            if !window.is_open() {
+                return None
+            }
+        }
+
+        let mut downloads = self.downloads.write().await;
+        let active = downloads.values().filter(|d| d.status == DownloadStatus::Active).count();
+        if active >= self.max_active.load(SeqCst) {
+            return None
+        }
+
+        // Highest priority first, then oldest request first
+        let (file_hash, download) = downloads
+            .iter_mut()
+            .filter(|(_, d)| d.status == DownloadStatus::Queued)
+            .max_by(|(_, a), (_, b)| a.priority.cmp(&b.priority).then(b.order.cmp(&a.order)))?;
+
+        download.status = DownloadStatus::Active;
+        Some((*file_hash, download.max_size))
+    }
+
+    /// Record the result of a finished download
+    async fn finish(&self, file_hash: &blake3::Hash, result: Result<()>) {
+        if let Some(download) = self.downloads.write().await.get_mut(file_hash) {
+            download.status = match result {
+                Ok(()) => DownloadStatus::Done,
+                Err(e) => DownloadStatus::Failed(e.to_string()),
+            };
+        }
+
+        self.notify.notify();
+    }
+
+    /// Returns a JSON representation of the scheduler state,
+    /// including the local names of the downloaded files.
+    pub async fn json(&self, names: &Names) -> JsonValue {
+        let mut downloads: Vec<(blake3::Hash, Download)> =
+            self.downloads.read().await.iter().map(|(h, d)| (*h, d.clone())).collect();
+        downloads.sort_by(|(_, a), (_, b)| b.priority.cmp(&a.priority).then(a.order.cmp(&b.order)));
+
+        let mut downloads_json = Vec::with_capacity(downloads.len());
+        for (file_hash, download) in downloads {
+            downloads_json.push(JsonValue::Object(HashMap::from([
+                ("file_hash".to_string(), JsonValue::String(file_hash.to_hex().to_string())),
+                ("names".to_string(), names.json_of(&file_hash).await),
+                ("priority".to_string(), JsonValue::String(download.priority.to_string())),
+                ("status".to_string(), JsonValue::String(download.status.to_string())),
+            ])));
+        }
+
+        let window = match *self.window.read().await {
+            Some(window) => JsonValue::String(window.to_string()),
+            None => JsonValue::Null,
+        };
+
+        JsonValue::Object(HashMap::from([
+            ("max_active".to_string(), JsonValue::Number(self.max_active.load(SeqCst) as f64)),
+            ("window".to_string(), window),
+            ("downloads".to_string(), JsonValue::Array(downloads_json)),
+        ]))
+    }
+}
+
+/// Check whether a file of `chunks` chunks fits in the given maximum size.
+/// Since only the last chunk may be partial, a file fits when it doesn't
+/// have more chunks than needed to hold `max_size` bytes.
+fn fits_max_size(chunks: usize, max_size: u64) -> bool {
+    chunks as u64 <= max_size.div_ceil(MAX_CHUNK_SIZE as u64)
+}
+
+/// Download a file and all of its missing chunks from the network,
+/// using the fetch semaphore to bound concurrent fetches. Progress is
+/// checkpointed every [`CHECKPOINT_CHUNKS`] chunks. Files exceeding the
+/// optional maximum size are refused before fetching any chunk.
+/// Returns the seeders the chunks were fetched from, with the bytes
+/// each of them served.
+async fn download(
+    fud: &Fud,
+    executor: &Arc<Executor<'_>>,
+    file_hash: blake3::Hash,
+    max_size: Option<u64>,
+) -> Result<HashMap<Url, u64>> {
+    let mut chunked_file = match fud.geode.get_checkpointed(&file_hash).await {
+        Ok(v) => v,
+        Err(Error::GeodeFileNotFound) => {
+            fud.fetch_semaphore.run(fetch_file(fud, executor, file_hash)).await?;
+            fud.geode.get_checkpointed(&file_hash).await?
+        }
+        Err(e) => return Err(e),
+    };
+
+    // Refuse files exceeding the requested maximum size
+    let chunks = chunked_file.iter().count();
+    if let Some(max_size) = max_size {
+        if !fits_max_size(chunks, max_size) {
+            return Err(Error::Custom(format!(
+                "File size exceeded: {} chunks of up to {} bytes, {} bytes allowed",
+                chunks, MAX_CHUNK_SIZE, max_size
+            )))
+        }
+    }
+
+    // Refuse downloads that could exceed the storage cap
+    let max_storage = fud.max_storage.load(SeqCst);
+    if max_storage > 0 {
+        let missing = chunked_file.iter().filter(|(_, path)| path.is_none()).count() as u64;
+        let used = fud.geode.storage_size().await?;
+        if used + missing * MAX_CHUNK_SIZE as u64 > max_storage {
+            return Err(Error::Custom(format!(
+                "Storage cap exceeded: {} bytes used of {} allowed",
+                used, max_storage
+            )))
+        }
+    }
+
+    let missing: Vec<_> =
+        chunked_file.iter().filter(|(_, path)| path.is_none()).map(|(hash, _)| *hash).collect();
+    let mut seeders: HashMap<Url, u64> = HashMap::new();
+    for batch in missing.chunks(CHECKPOINT_CHUNKS) {
+        let (fetched, served) = fud.fetch_semaphore.run(fetch_chunks(fud, executor, batch)).await?;
+        for (peer, bytes) in served {
+            *seeders.entry(peer).or_default() += bytes;
+        }
+        if let Err(e) = fud.geode.checkpoint(&file_hash, &mut chunked_file, &fetched).await {
+            warn!(target: "fud::scheduler", "Failed checkpointing download of {}: {}", file_hash, e);
+        }
+
+        for chunk_hash in fetched {
+            fud.replicas.announce(&fud.p2p, &chunk_hash, &FudChunkPut { chunk_hash }, &[]).await;
+        }
+    }
+
+    if !chunked_file.is_complete() {
+        return Err(Error::GeodeChunkRouteNotFound)
+    }
+
+    // The checkpoint is only needed to resume the download
+    if let Err(e) = fud.geode.remove_checkpoint(&file_hash).await {
+        warn!(target: "fud::scheduler", "Failed removing checkpoint of {}: {}", file_hash, e);
+    }
+
+    let chunk_hashes = chunked_file.iter().map(|(h, _)| *h).collect();
+    fud.replicas.announce(&fud.p2p, &file_hash, &FudFilePut { file_hash, chunk_hashes }, &[]).await;
+
+    Ok(seeders)
+}
+
+/// Background task starting queued downloads as the scheduling
+/// policies allow.
+pub async fn scheduler_task(fud: Arc<Fud>, executor: Arc<Executor<'_>>) -> Result<()> {
+    info!(target: "fud::scheduler", "Started download scheduler task");
+    loop {
+        // Start as many downloads as we're allowed to
+        while let Some((file_hash, max_size)) = fud.scheduler.next().await {
+            let label = fud.names.label(&file_hash).await;
+            info!(target: "fud::scheduler", "Starting download of {}", label);
+            let fud_ = fud.clone();
+            let executor_ = executor.clone();
+            executor
+                .spawn(async move {
+                    let result = download(&fud_, &executor_, file_hash, max_size).await;
+                    match &result {
+                        Ok(seeders) => {
+                            info!(target: "fud::scheduler", "Download of {} completed", label);
+                            if fud_.reports.enabled() {
+                                let completed = Some(Timestamp::current_time().inner());
+                                let seeders =
+                                    seeders.iter().map(|(p, b)| (p.to_string(), *b)).collect();
+                                if let Err(e) =
+                                    generate_report(&fud_, &file_hash, completed, Some(seeders)).await
+                                {
+                                    error!(target: "fud::scheduler", "Failed writing report of {}: {}", label, e);
+                                }
+                            }
+                        }
+                        Err(e) => {
+                            error!(target: "fud::scheduler", "Download of {} failed: {}", label, e)
+                        }
+                    }
+                    fud_.scheduler.finish(&file_hash, result.map(|_| ())).await;
+                })
+                .detach();
+        }
+
+        // Wait for a state change, or recheck periodically in case
+        // a schedule window opened.
+        if !fud.scheduler.notify.wait_timeout(SCHEDULER_TICK).await {
+            continue
+        }
+        fud.scheduler.notify.reset();
+    }
+}
+
+/// Parse the optional schedule window configuration
+pub fn parse_window(window: &Option<String>) -> Result<Option<ScheduleWindow>> {
+    match window {
+        Some(w) if !w.is_empty() => match ScheduleWindow::from_str(w) {
+            Ok(w) => Ok(Some(w)),
+            Err(e) => {
+                warn!(target: "fud::scheduler", "Invalid download window {}: {}", w, e);
+                Err(e)
+            }
+        },
+        _ => Ok(None),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn max_size_chunks() {
+        let chunk = MAX_CHUNK_SIZE as u64;
+        assert!(fits_max_size(0, 0));
+        assert!(!fits_max_size(1, 0));
+        assert!(fits_max_size(1, 1));
+        assert!(fits_max_size(1, chunk));
+        assert!(!fits_max_size(2, chunk));
+        assert!(fits_max_size(2, chunk + 1));
+        assert!(!fits_max_size(3, 2 * chunk));
+    }
+
+    #[test]
+    fn priority_parsing() {
+        for priority in [Priority::Low, Priority::Normal, Priority::High] {
+            assert_eq!(Priority::from_str(&priority.to_string()).unwrap(), priority);
+        }
+        assert_eq!(Priority::from_str("HIGH").unwrap(), Priority::High);
+        assert!(Priority::from_str("urgent").is_err());
+        assert!(Priority::High > Priority::Normal && Priority::Normal > Priority::Low);
+    }
+
+    #[test]
+    fn schedule_windows() {
+        let window = ScheduleWindow::from_str("08:30-17:00").unwrap();
+        assert_eq!(window.to_string(), "08:30-17:00");
+        assert!(!window.contains(8 * 60 + 29));
+        assert!(window.contains(8 * 60 + 30));
+        assert!(window.contains(16 * 60 + 59));
+        assert!(!window.contains(17 * 60));
+
+        // Windows wrapping around midnight
+        let window = ScheduleWindow::from_str(" 22:00 - 06:00 ").unwrap();
+        assert_eq!(window.to_string(), "22:00-06:00");
+        assert!(window.contains(23 * 60));
+        assert!(window.contains(0));
+        assert!(window.contains(5 * 60 + 59));
+        assert!(!window.contains(6 * 60));
+        assert!(!window.contains(12 * 60));
+
+        for invalid in ["", "08:00", "08:00-08:00", "24:00-01:00", "08:60-09:00", "a:00-09:00"] {
+            assert!(ScheduleWindow::from_str(invalid).is_err(), "{invalid}");
+        }
+
+        assert_eq!(parse_window(&None).unwrap(), None);
+        assert_eq!(parse_window(&Some(String::new())).unwrap(), None);
+        assert_eq!(
+            parse_window(&Some("22:00-06:00".to_string())).unwrap(),
+            Some(ScheduleWindow { start: 22 * 60, end: 6 * 60 })
+        );
+        assert!(parse_window(&Some("22:00".to_string())).is_err());
+    }
+
+    #[test]
+    fn download_ordering() {
+        smol::block_on(async {
+            let hash = |i: u8| blake3::hash(&[i]);
+            let scheduler = Scheduler::new(2, None);
+
+            assert!(scheduler.enqueue(hash(0), Priority::Normal, None).await);
+            assert!(scheduler.enqueue(hash(1), Priority::Low, Some(1024)).await);
+            assert!(scheduler.enqueue(hash(2), Priority::High, None).await);
+            assert!(scheduler.enqueue(hash(3), Priority::Normal, None).await);
+
+            // Highest priority first, then oldest request first,
+            // up to the active downloads limit
+            assert_eq!(scheduler.next().await, Some((hash(2), None)));
+            assert_eq!(scheduler.next().await, Some((hash(0), None)));
+            assert_eq!(scheduler.next().await, None);
+
+            // Active downloads can't be queued again
+            assert!(!scheduler.enqueue(hash(0), Priority::High, None).await);
+
+            // Finishing a download frees a slot, and raising a priority
+            // moves it ahead of older requests
+            assert!(scheduler.set_priority(&hash(1), Priority::High).await);
+            scheduler.finish(&hash(2), Ok(())).await;
+            assert_eq!(scheduler.next().await, Some((hash(1), Some(1024))));
+
+            // Failed and completed downloads get queued again
+            scheduler.finish(&hash(0), Err(Error::GeodeChunkRouteNotFound)).await;
+            assert!(matches!(
+                scheduler.downloads.read().await[&hash(0)].status,
+                DownloadStatus::Failed(_)
+            ));
+            assert!(scheduler.enqueue(hash(2), Priority::Low, None).await);
+            scheduler.finish(&hash(1), Ok(())).await;
+            assert_eq!(scheduler.next().await, Some((hash(3), None)));
+            assert_eq!(scheduler.next().await, Some((hash(2), None)));
+
+            // Removed downloads are not started, untracked ones can't be updated
+            assert!(scheduler.remove(&hash(0)).await);
+            assert!(!scheduler.remove(&hash(0)).await);
+            assert!(!scheduler.set_priority(&hash(0), Priority::High).await);
+            scheduler.set_max_active(3);
+            assert_eq!(scheduler.next().await, None);
+        });
+    }
+
+    #[test]
+    fn download_window() {
+        smol::block_on(async {
+            let file_hash = blake3::hash(b"file");
+            let scheduler = Scheduler::new(1, None);
+            assert!(scheduler.enqueue(file_hash, Priority::Normal, None).await);
+
+            // A window starting in an hour is closed now
+            let minute = ((Timestamp::current_time().inner() % 86400) / 60) as u32;
+            let start = (minute + 60) % 1440;
+            let window = ScheduleWindow { start, end: (start + 60) % 1440 };
+            assert!(!window.is_open());
+            scheduler.set_window(Some(window)).await;
+            assert_eq!(scheduler.next().await, None);
+
+            scheduler.set_window(None).await;
+            assert_eq!(scheduler.next().await, Some((file_hash, None)));
+        });
+    }
+}
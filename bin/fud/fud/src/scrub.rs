@@ -0,0 +1,146 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2025 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Background integrity scrubbing of the Geode store.
+//!
+//! The filesystem watcher only catches chunks modified behind our back,
+//! while disk bit-rot happens silently. Here we continuously scrub the
+//! stored files at a limited rate, least recently scrubbed first, and
+//! handle corrupted chunks the same way the watcher does, so they get
+//! garbage collected and withdrawn before a peer requests them.
+
+use std::{
+    sync::Arc,
+    time::{Duration, SystemTime},
+};
+
+use log::{debug, error, info, warn};
+
+use darkfi::{system::sleep, Error, Result};
+
+use super::{watch::verify_chunks, Fud};
+
+/// Time to sleep when no stored file is due for scrubbing, in seconds
+const SCRUB_IDLE_SECS: u64 = 60;
+
+/// Background task scrubbing stored files at `rate` bytes per second,
+/// rescrubbing each file once `interval` seconds have passed since
+/// its last scrub.
+pub async fn scrub_task(fud: Arc<Fud>, rate: u64, interval: u64) -> Result<()> {
+    info!(target: "fud::scrub", "Scrubbing stored files at {} bytes/sec", rate);
+    let interval = Duration::from_secs(interval);
+
+    loop {
+        let queue = match fud.geode.scrub_queue().await {
+            Ok(v) => v,
+            Err(e) => {
+                error!(target: "fud::scrub", "Failed retrieving scrub queue: {}", e);
+                sleep(SCRUB_IDLE_SECS).await;
+                continue
+            }
+        };
+
+        let now = SystemTime::now();
+        for (file_hash, last_scrub) in queue {
+            // The queue is ordered, so once we find a recently
+            // scrubbed file, the rest are recent too.
+            if let Some(last_scrub) = last_scrub {
+                if now.duration_since(last_scrub).unwrap_or_default() < interval {
+                    break
+                }
+            }
+
+            let report = match fud.geode.scrub(&file_hash, rate).await {
+                Ok(v) => v,
+                // File got removed in the meantime
+                Err(Error::GeodeFileNotFound) => continue,
+                Err(Error::GeodeNeedsGc) => {
+                    warn!(target: "fud::scrub", "File {} metadata is corrupted", file_hash);
+                    match fud.geode.garbage_collect().await {
+                        Ok((_, deleted_chunks)) => fud.uploads.remove(&deleted_chunks).await,
+                        Err(e) => error!(target: "fud::scrub", "Failed garbage collecting: {}", e),
+                    }
+                    continue
+                }
+                Err(e) => {
+                    error!(target: "fud::scrub", "Failed scrubbing file {}: {}", file_hash, e);
+                    continue
+                }
+            };
+
+            debug!(
+                target: "fud::scrub",
+                "Scrubbed file {}: {} chunks, {} bytes", file_hash, report.chunks, report.bytes,
+            );
+
+            if report.corrupted.is_empty() {
+                continue
+            }
+
+            warn!(
+                target: "fud::scrub",
+                "Found {} corrupted chunks in file {}", report.corrupted.len(), file_hash,
+            );
+            if let Err(e) = verify_chunks(&fud, report.corrupted.into_iter().collect()).await {
+                error!(target: "fud::scrub", "Failed handling corrupted chunks: {}", e);
+            }
+        }
+
+        sleep(SCRUB_IDLE_SECS).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::{rngs::OsRng, Rng};
+    use smol::{fs, io::Cursor};
+
+    use darkfi::geode::{Geode, MAX_CHUNK_SIZE};
+
+    use super::*;
+
+    #[test]
+    fn scrub_queue_corruption() {
+        smol::block_on(async {
+            let basedir = std::env::temp_dir().join(format!("fud_scrub_{}", OsRng.gen::<u64>()));
+            let geode = Geode::new(&basedir).await.unwrap();
+
+            let data: Vec<u8> = (0..MAX_CHUNK_SIZE + 1337).map(|i| (i % 251) as u8).collect();
+            let (file_a, chunks_a) = geode.insert(Cursor::new(&data)).await.unwrap();
+            let (file_b, _) = geode.insert(Cursor::new(b"another file")).await.unwrap();
+
+            // Once scrubbed, a file goes to the back of the queue
+            geode.scrub(&file_a, 0).await.unwrap();
+            let queue = geode.scrub_queue().await.unwrap();
+            assert_eq!(queue.len(), 2);
+            assert_eq!(queue[0], (file_b, None));
+            assert_eq!(queue[1].0, file_a);
+            assert!(queue[1].1.is_some());
+
+            // A corrupted chunk gets detected by the scrubber, and is
+            // flagged for garbage collection when handed to the watcher.
+            let chunk_path = geode.chunks_path().join(chunks_a[0].to_hex().as_str());
+            fs::write(&chunk_path, &data[1..MAX_CHUNK_SIZE + 1]).await.unwrap();
+            let report = geode.scrub(&file_a, 0).await.unwrap();
+            assert_eq!(report.corrupted, vec![chunks_a[0]]);
+            assert!(matches!(geode.get_chunk(&chunks_a[0]).await, Err(Error::GeodeNeedsGc)));
+
+            fs::remove_dir_all(&basedir).await.unwrap();
+        })
+    }
+}
@@ -0,0 +1,258 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2025 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use std::{
+    collections::{HashMap, HashSet},
+    str::FromStr,
+    sync::atomic::Ordering::SeqCst,
+};
+
+use async_trait::async_trait;
+use log::warn;
+use smol::lock::{MutexGuard, RwLock};
+use tinyjson::JsonValue;
+
+use darkfi::{
+    rpc::{
+        jsonrpc::{ErrorCode, JsonError, JsonRequest, JsonResponse, JsonResult},
+        server::RequestHandler,
+    },
+    rpc_error,
+    system::StoppableTaskPtr,
+};
+
+use crate::{error::RpcError, scheduler::Priority, Fud};
+
+/// Marker type for the authenticated seedbox JSON-RPC interface
+pub struct SeedboxRpcHandler;
+
+/// Bytes uploaded to peers per stored chunk, used for the seeding ratios.
+/// Entries only get created for chunks we serve from Geode, and get
+/// dropped once their chunks are removed from it, so the map is bounded
+/// by the chunks we store.
+#[derive(Default)]
+pub struct Uploads(RwLock<HashMap<blake3::Hash, u64>>);
+
+impl Uploads {
+    /// Account `bytes` uploaded to a peer for provided stored chunk
+    pub async fn record(&self, chunk_hash: blake3::Hash, bytes: u64) {
+        *self.0.write().await.entry(chunk_hash).or_insert(0) += bytes;
+    }
+
+    /// Total bytes uploaded for provided chunks
+    pub async fn total<'a>(&self, chunk_hashes: impl Iterator<Item = &'a blake3::Hash>) -> u64 {
+        let uploads = self.0.read().await;
+        chunk_hashes.map(|chunk_hash| uploads.get(chunk_hash).copied().unwrap_or(0)).sum()
+    }
+
+    /// Drop the entries of provided removed chunks
+    pub async fn remove(&self, chunk_hashes: &HashSet<blake3::Hash>) {
+        self.0.write().await.retain(|chunk_hash, _| !chunk_hashes.contains(chunk_hash));
+    }
+}
+
+#[async_trait]
+impl RequestHandler<SeedboxRpcHandler> for Fud {
+    async fn handle_request(&self, req: JsonRequest) -> JsonResult {
+        // Every seedbox method takes the access token as its first parameter
+        let Some(params) = req.params.get::<Vec<JsonValue>>() else {
+            return JsonError::new(ErrorCode::InvalidParams, None, req.id).into()
+        };
+        let Some(token) = params.first().and_then(|t| t.get::<String>()) else {
+            return JsonError::new(ErrorCode::InvalidParams, None, req.id).into()
+        };
+        if !self.seedbox_authorized(token) {
+            warn!(target: "fud::seedbox", "Rejected unauthorized {} request", req.method);
+            return rpc_error!(RpcError::Unauthorized, req.id)
+        }
+        let params = &params[1..];
+
+        return match req.method.as_str() {
+            "ping" => {
+                <Fud as RequestHandler<SeedboxRpcHandler>>::pong(self, req.id, JsonValue::Null)
+                    .await
+            }
+
+            "seed" => self.seedbox_seed(req.id, params).await,
+            "stats" => self.seedbox_stats(req.id, params).await,
+            "set_max_storage" => self.seedbox_set_max_storage(req.id, params).await,
+            _ => JsonError::new(ErrorCode::MethodNotFound, None, req.id).into(),
+        }
+    }
+
+    async fn connections_mut(&self) -> MutexGuard<'_, HashSet<StoppableTaskPtr>> {
+        self.seedbox_rpc_connections.lock().await
+    }
+}
+
+impl Fud {
+    /// Check the given token against the configured seedbox token,
+    /// in constant time.
+    fn seedbox_authorized(&self, token: &str) -> bool {
+        let Some(expected) = &self.seedbox_token else { return false };
+        if token.len() != expected.len() {
+            return false
+        }
+
+        token.bytes().zip(expected.bytes()).fold(0u8, |acc, (a, b)| acc | (a ^ b)) == 0
+    }
+
+    // RPCAPI:
+    // Fetch a file from the network and keep seeding it. Takes the access
+    // token, a file hash and an optional priority (`low`, `normal` or `high`,
+    // defaults to `normal`). Returns `false` if the file is already being
+    // downloaded.
+    //
+    // --> {"jsonrpc": "2.0", "method": "seed", "params": ["s3cr3t", "1211...abfd"], "id": 42}
+    // <-- {"jsonrpc": "2.0", "result": true, "id": 42}
+    async fn seedbox_seed(&self, id: u16, params: &[JsonValue]) -> JsonResult {
+        if params.is_empty() || params.len() > 2 || !params.iter().all(|p| p.is_string()) {
+            return JsonError::new(ErrorCode::InvalidParams, None, id).into()
+        }
+
+        let Some(file_hash) = self.names.resolve(params[0].get::<String>().unwrap()).await else {
+            return JsonError::new(ErrorCode::InvalidParams, None, id).into()
+        };
+
+        let priority = match params.get(1) {
+            Some(p) => match Priority::from_str(p.get::<String>().unwrap()) {
+                Ok(v) => v,
+                Err(_) => return JsonError::new(ErrorCode::InvalidParams, None, id).into(),
+            },
+            None => Priority::Normal,
+        };
+
+        let queued = self.scheduler.enqueue(file_hash, priority, None).await;
+        JsonResponse::new(JsonValue::Boolean(queued), id).into()
+    }
+
+    // RPCAPI:
+    // Returns the seedbox state: the used and maximum storage in bytes
+    // (zero meaning unlimited), the seeded resources along with how many
+    // bytes of them got uploaded and their upload ratio, and the download
+    // scheduler state.
+    //
+    // --> {"jsonrpc": "2.0", "method": "stats", "params": ["s3cr3t"], "id": 42}
+    // <-- {"jsonrpc": "2.0", "result": {"storage_used": 1048576, "max_storage": 0, "resources": [{"file_hash": "1211...abfd", "size": 1048576, "uploaded": 2097152, "ratio": 2.0, "complete": true}], "downloads": {...}}, "id": 42}
+    async fn seedbox_stats(&self, id: u16, params: &[JsonValue]) -> JsonResult {
+        if !params.is_empty() {
+            return JsonError::new(ErrorCode::InvalidParams, None, id).into()
+        }
+
+        let storage_used = match self.geode.storage_size().await {
+            Ok(v) => v,
+            Err(_) => return rpc_error!(RpcError::GeodeNeedsGc, id),
+        };
+        let file_hashes = match self.geode.list_files().await {
+            Ok(v) => v,
+            Err(_) => return rpc_error!(RpcError::GeodeNeedsGc, id),
+        };
+
+        let mut resources = vec![];
+        for file_hash in file_hashes {
+            let Ok(chunked_file) = self.geode.get(&file_hash).await else { continue };
+
+            let mut size = 0;
+            let uploaded = self.uploads.total(chunked_file.iter().map(|(h, _)| h)).await;
+            for (_, path) in chunked_file.iter() {
+                let Some(path) = path else { continue };
+                if let Ok(metadata) = smol::fs::metadata(path).await {
+                    size += metadata.len();
+                }
+            }
+
+            let ratio = if size == 0 { 0.0 } else { uploaded as f64 / size as f64 };
+            resources.push(JsonValue::Object(
+                [
+                    ("file_hash".to_string(), JsonValue::String(file_hash.to_hex().to_string())),
+                    ("size".to_string(), JsonValue::Number(size as f64)),
+                    ("uploaded".to_string(), JsonValue::Number(uploaded as f64)),
+                    ("ratio".to_string(), JsonValue::Number(ratio)),
+                    ("complete".to_string(), JsonValue::Boolean(chunked_file.is_complete())),
+                ]
+                .into(),
+            ));
+        }
+
+        let stats = JsonValue::Object(
+            [
+                ("storage_used".to_string(), JsonValue::Number(storage_used as f64)),
+                (
+                    "max_storage".to_string(),
+                    JsonValue::Number(self.max_storage.load(SeqCst) as f64),
+                ),
+                ("resources".to_string(), JsonValue::Array(resources)),
+                ("downloads".to_string(), self.scheduler.json(&self.names).await),
+            ]
+            .into(),
+        );
+
+        JsonResponse::new(stats, id).into()
+    }
+
+    // RPCAPI:
+    // Set the maximum total storage in bytes the seedbox may use for
+    // chunks. Zero means unlimited. Downloads that would exceed the cap
+    // are refused. Returns `true` on success.
+    //
+    // --> {"jsonrpc": "2.0", "method": "set_max_storage", "params": ["s3cr3t", 1073741824], "id": 42}
+    // <-- {"jsonrpc": "2.0", "result": true, "id": 42}
+    async fn seedbox_set_max_storage(&self, id: u16, params: &[JsonValue]) -> JsonResult {
+        if params.len() != 1 || !params[0].is_number() {
+            return JsonError::new(ErrorCode::InvalidParams, None, id).into()
+        }
+
+        let max_storage = *params[0].get::<f64>().unwrap();
+        if max_storage < 0.0 || max_storage.fract() != 0.0 {
+            return JsonError::new(ErrorCode::InvalidParams, None, id).into()
+        }
+
+        self.max_storage.store(max_storage as u64, SeqCst);
+        JsonResponse::new(JsonValue::Boolean(true), id).into()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn seedbox_uploads_accounting() {
+        smol::block_on(async {
+            let uploads = Uploads::default();
+            let (a, b, c) = (blake3::hash(b"a"), blake3::hash(b"b"), blake3::hash(b"c"));
+
+            uploads.record(a, 100).await;
+            uploads.record(a, 50).await;
+            uploads.record(b, 10).await;
+            assert_eq!(uploads.total([a].iter()).await, 150);
+            assert_eq!(uploads.total([a, b].iter()).await, 160);
+            // Chunks we never served count as zero
+            assert_eq!(uploads.total([a, b, c].iter()).await, 160);
+            assert_eq!(uploads.total([].iter()).await, 0);
+
+            // Removed chunks get dropped, the rest is kept
+            uploads.remove(&HashSet::from([a, c])).await;
+            assert_eq!(uploads.total([a, b].iter()).await, 10);
+            assert_eq!(uploads.0.read().await.len(), 1);
+
+            uploads.remove(&HashSet::from([b])).await;
+            assert!(uploads.0.read().await.is_empty());
+        });
+    }
+}
@@ -0,0 +1,248 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2025 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Per-peer transfer accounting.
+//!
+//! Every chunk downloaded from, or uploaded to, a peer is recorded here,
+//! so swarm statistics of a resource can be reported by restricting the
+//! transfers to the resource's chunks. Transfer rates are averaged over
+//! the last [`RATE_WINDOW`].
+//!
+//! Peers are keyed by their stable ID, see [`crate::replication::peer_id`],
+//! so reconnections from another ephemeral address are accounted to the
+//! same peer. At most [`MAX_PEERS`] peers are tracked, evicting the least
+//! recently active ones, and at most [`MAX_PEER_CHUNKS`] chunks per peer,
+//! evicting the oldest ones.
+
+use std::{
+    collections::{HashMap, HashSet, VecDeque},
+    time::{Duration, Instant},
+};
+
+use smol::lock::RwLock;
+use url::Url;
+
+/// Time window transfer rates are averaged over
+pub const RATE_WINDOW: Duration = Duration::from_secs(30);
+
+/// Maximum number of peers transfers are kept for
+pub const MAX_PEERS: usize = 1024;
+
+/// Maximum number of chunks transfers are kept for, per peer
+pub const MAX_PEER_CHUNKS: usize = 4096;
+
+/// Transfers performed with a single peer
+struct PeerTransfers {
+    /// Time of the last transfer with the peer
+    last_active: Instant,
+    /// Total downloaded and uploaded bytes, per chunk
+    chunks: HashMap<blake3::Hash, (u64, u64)>,
+    /// Chunks in the order they were first transferred
+    order: VecDeque<blake3::Hash>,
+    /// Transfers performed in the last [`RATE_WINDOW`], as
+    /// `(time, chunk, downloaded bytes, uploaded bytes)`
+    recent: VecDeque<(Instant, blake3::Hash, u64, u64)>,
+}
+
+impl PeerTransfers {
+    fn new(now: Instant) -> Self {
+        Self {
+            last_active: now,
+            chunks: HashMap::new(),
+            order: VecDeque::new(),
+            recent: VecDeque::new(),
+        }
+    }
+
+    /// Drop recent transfers that fell out of the rate window
+    fn prune(&mut self, now: Instant) {
+        while let Some((time, ..)) = self.recent.front() {
+            if now.duration_since(*time) <= RATE_WINDOW {
+                break
+            }
+            self.recent.pop_front();
+        }
+    }
+}
+
+/// Transfer statistics of a peer, restricted to a resource's chunks
+#[derive(Clone, Debug, Default)]
+pub struct ResourceTransfers {
+    /// Total bytes downloaded from the peer
+    pub downloaded: u64,
+    /// Total bytes uploaded to the peer
+    pub uploaded: u64,
+    /// Download rate in bytes/sec
+    pub download_rate: f64,
+    /// Upload rate in bytes/sec
+    pub upload_rate: f64,
+}
+
+/// Transfer accounting of the peers we exchanged chunks with
+pub struct SwarmStats {
+    peers: RwLock<HashMap<Url, PeerTransfers>>,
+    max_peers: usize,
+    max_peer_chunks: usize,
+}
+
+impl SwarmStats {
+    pub fn new() -> Self {
+        Self::with_limits(MAX_PEERS, MAX_PEER_CHUNKS)
+    }
+
+    fn with_limits(max_peers: usize, max_peer_chunks: usize) -> Self {
+        Self { peers: RwLock::new(HashMap::new()), max_peers, max_peer_chunks }
+    }
+
+    /// Record a chunk downloaded from the peer with the given stable ID
+    pub async fn record_download(&self, peer: &Url, chunk_hash: blake3::Hash, bytes: u64) {
+        self.record(peer, chunk_hash, bytes, 0).await
+    }
+
+    /// Record a chunk uploaded to the peer with the given stable ID
+    pub async fn record_upload(&self, peer: &Url, chunk_hash: blake3::Hash, bytes: u64) {
+        self.record(peer, chunk_hash, 0, bytes).await
+    }
+
+    async fn record(&self, peer: &Url, chunk_hash: blake3::Hash, downloaded: u64, uploaded: u64) {
+        let now = Instant::now();
+        let mut peers = self.peers.write().await;
+
+        // Evict the least recently active peer to make room for a new one
+        if !peers.contains_key(peer) && peers.len() >= self.max_peers {
+            let oldest = peers.iter().min_by_key(|(_, t)| t.last_active).map(|(p, _)| p.clone());
+            if let Some(oldest) = oldest {
+                peers.remove(&oldest);
+            }
+        }
+
+        let transfers = peers.entry(peer.clone()).or_insert_with(|| PeerTransfers::new(now));
+        transfers.last_active = now;
+
+        // Evict the oldest chunk to make room for a new one
+        if !transfers.chunks.contains_key(&chunk_hash) {
+            if transfers.chunks.len() >= self.max_peer_chunks {
+                if let Some(oldest) = transfers.order.pop_front() {
+                    transfers.chunks.remove(&oldest);
+                }
+            }
+            transfers.order.push_back(chunk_hash);
+        }
+
+        let totals = transfers.chunks.entry(chunk_hash).or_insert((0, 0));
+        totals.0 += downloaded;
+        totals.1 += uploaded;
+
+        transfers.prune(now);
+        transfers.recent.push_back((now, chunk_hash, downloaded, uploaded));
+    }
+
+    /// Return the transfer statistics of every peer we exchanged any of
+    /// the given chunks with, keyed by their stable ID.
+    pub async fn resource_transfers(
+        &self,
+        chunk_hashes: &HashSet<blake3::Hash>,
+    ) -> HashMap<Url, ResourceTransfers> {
+        let now = Instant::now();
+        let window = RATE_WINDOW.as_secs_f64();
+        let mut ret = HashMap::new();
+
+        let mut peers = self.peers.write().await;
+        for (peer, transfers) in peers.iter_mut() {
+            transfers.prune(now);
+
+            let mut stats = ResourceTransfers::default();
+            let mut found = false;
+            for (chunk_hash, (downloaded, uploaded)) in &transfers.chunks {
+                if !chunk_hashes.contains(chunk_hash) {
+                    continue
+                }
+                found = true;
+                stats.downloaded += downloaded;
+                stats.uploaded += uploaded;
+            }
+
+            if !found {
+                continue
+            }
+
+            for (_, chunk_hash, downloaded, uploaded) in &transfers.recent {
+                if !chunk_hashes.contains(chunk_hash) {
+                    continue
+                }
+                stats.download_rate += *downloaded as f64 / window;
+                stats.upload_rate += *uploaded as f64 / window;
+            }
+
+            ret.insert(peer.clone(), stats);
+        }
+
+        ret
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn swarm_stats() {
+        smol::block_on(async {
+            let stats = SwarmStats::with_limits(2, 2);
+            let alice = Url::parse("tcp://alice:1").unwrap();
+            let bob = Url::parse("tcp://bob:1").unwrap();
+            let charlie = Url::parse("tcp://charlie:1").unwrap();
+            let chunks: Vec<blake3::Hash> = (0..3u8).map(|i| blake3::hash(&[i])).collect();
+
+            stats.record_download(&alice, chunks[0], 100).await;
+            stats.record_download(&alice, chunks[0], 50).await;
+            stats.record_upload(&alice, chunks[1], 10).await;
+            stats.record_upload(&bob, chunks[1], 20).await;
+
+            // Transfers are restricted to the requested chunks
+            let transfers = stats.resource_transfers(&HashSet::from([chunks[0]])).await;
+            assert_eq!(transfers.len(), 1);
+            assert_eq!(transfers[&alice].downloaded, 150);
+            assert_eq!(transfers[&alice].uploaded, 0);
+            let rate = 150.0 / RATE_WINDOW.as_secs_f64();
+            assert!((transfers[&alice].download_rate - rate).abs() < f64::EPSILON);
+
+            let transfers = stats.resource_transfers(&chunks.iter().copied().collect()).await;
+            assert_eq!(transfers.len(), 2);
+            assert_eq!(transfers[&alice].uploaded, 10);
+            assert_eq!(transfers[&bob].uploaded, 20);
+
+            // The oldest chunk of a peer gets evicted past the limit
+            stats.record_download(&alice, chunks[2], 1).await;
+            let transfers = stats.resource_transfers(&HashSet::from([chunks[0]])).await;
+            assert!(transfers.is_empty());
+            let transfers = stats.resource_transfers(&chunks.iter().copied().collect()).await;
+            assert_eq!(transfers[&alice].uploaded, 10);
+            assert_eq!(transfers[&alice].downloaded, 1);
+
+            // The least recently active peer gets evicted past the limit
+            stats.record_upload(&bob, chunks[1], 20).await;
+            stats.record_upload(&charlie, chunks[1], 30).await;
+            let transfers = stats.resource_transfers(&chunks.iter().copied().collect()).await;
+            assert_eq!(transfers.len(), 2);
+            assert!(!transfers.contains_key(&alice));
+            assert_eq!(transfers[&bob].uploaded, 40);
+            assert_eq!(transfers[&charlie].uploaded, 30);
+        })
+    }
+}
@@ -0,0 +1,624 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2025 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use async_trait::async_trait;
+use darkfi_serial::deserialize_async;
+use log::{debug, error, info, warn};
+use smol::{future, lock::Mutex, Executor};
+use url::Url;
+
+use darkfi::{
+    geode::MAX_CHUNK_SIZE,
+    net::{
+        connector::Connector, protocol::ProtocolVersion, session::Session, ChannelPtr, Message,
+        MessageSubscription, StreamPtr,
+    },
+    system::{sleep, timeout},
+    Error, Result,
+};
+use fud_client::FudTransport;
+
+use super::{
+    proto::{
+        FudChunkNotFound, FudChunkNotFoundV2, FudChunkReply, FudChunkRequest, FudFileNotFound,
+        FudFileReply, FudFileReplyV2, FudFileRequest, FUD_STREAM,
+    },
+    Fud,
+};
+
+/// Time to wait for any reply to the outstanding requests on a
+/// stream before giving up on a peer
+const REPLY_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Seconds a fetch channel may stay unused before it gets stopped
+pub const FETCH_CHANNEL_IDLE: u64 = 300;
+
+/// Channels we opened to peers for fetching, along with when they were
+/// last used, so they are reused across fetches and stopped once idle.
+#[derive(Default)]
+pub struct FetchChannels(Mutex<HashMap<Url, (ChannelPtr, Instant)>>);
+
+impl FetchChannels {
+    /// Returns the open channel to `peer`, marking it used
+    async fn get(&self, peer: &Url) -> Option<ChannelPtr> {
+        let mut channels = self.0.lock().await;
+        channels.retain(|_, (channel, _)| !channel.is_stopped());
+        let (channel, last_used) = channels.get_mut(peer)?;
+        *last_used = Instant::now();
+        Some(channel.clone())
+    }
+
+    async fn insert(&self, peer: Url, channel: ChannelPtr) {
+        self.0.lock().await.insert(peer, (channel, Instant::now()));
+    }
+
+    async fn remove(&self, peer: &Url) {
+        self.0.lock().await.remove(peer);
+    }
+
+    /// Stop the channels without open streams which were not used in
+    /// the last `idle` duration.
+    pub async fn prune(&self, idle: Duration) {
+        let mut channels = self.0.lock().await;
+        let mut expired = vec![];
+        for (peer, (channel, last_used)) in channels.iter() {
+            if last_used.elapsed() >= idle && channel.streams().open_streams().await == 0 {
+                expired.push(peer.clone());
+            }
+        }
+
+        for peer in expired {
+            let (channel, _) = channels.remove(&peer).unwrap();
+            debug!("Stopping idle fetch channel to {}", peer);
+            channel.stop().await;
+        }
+    }
+
+    /// Stop all the channels
+    pub async fn stop(&self) {
+        for (_, (channel, _)) in self.0.lock().await.drain() {
+            channel.stop().await;
+        }
+    }
+}
+
+/// Background task stopping the idle fetch channels
+pub async fn fetch_channels_task(fud: Arc<Fud>) -> Result<()> {
+    loop {
+        sleep(FETCH_CHANNEL_IDLE).await;
+        fud.channels.prune(Duration::from_secs(FETCH_CHANNEL_IDLE)).await;
+    }
+}
+
+/// A reply to a fud request
+enum Reply {
+    /// File metadata, without a publisher signature if the peer replied
+    /// with the unversioned [`FudFileReply`]
+    File(FudFileReplyV2),
+    FileNotFound,
+    Chunk(FudChunkReply),
+    /// Chunk not found, naming the chunk unless the peer replied with
+    /// the unversioned [`FudChunkNotFound`]
+    ChunkNotFound(Option<blake3::Hash>),
+}
+
+/// Connection fud requests are sent over. Requests go on a stream when
+/// the peer supports them, otherwise on a dedicated channel, replying
+/// with plain messages.
+enum Fetcher {
+    Stream(StreamPtr),
+    Channel {
+        channel: ChannelPtr,
+        file_sub: MessageSubscription<FudFileReply>,
+        file_not_found_sub: MessageSubscription<FudFileNotFound>,
+        chunk_sub: MessageSubscription<FudChunkReply>,
+        chunk_not_found_sub: MessageSubscription<FudChunkNotFound>,
+    },
+}
+
+impl Fetcher {
+    /// Subscribe to the fud replies on a dedicated channel
+    async fn channel(channel: ChannelPtr) -> Result<Self> {
+        Ok(Self::Channel {
+            file_sub: channel.subscribe_msg::<FudFileReply>().await?,
+            file_not_found_sub: channel.subscribe_msg::<FudFileNotFound>().await?,
+            chunk_sub: channel.subscribe_msg::<FudChunkReply>().await?,
+            chunk_not_found_sub: channel.subscribe_msg::<FudChunkNotFound>().await?,
+            channel,
+        })
+    }
+
+    async fn send<M: Message>(&self, message: &M) -> Result<()> {
+        match self {
+            Self::Stream(stream) => stream.send(message).await,
+            Self::Channel { channel, .. } => channel.send(message).await,
+        }
+    }
+
+    /// Receive the next reply. Returns [`Error::MalformedPacket`] if the
+    /// peer sent a malformed or unexpected message.
+    async fn receive(&self) -> Result<Reply> {
+        match self {
+            Self::Stream(stream) => {
+                let (command, payload) = stream.receive_raw().await?;
+                let reply = match command.as_str() {
+                    FudFileReplyV2::NAME => deserialize_async(&payload).await.map(Reply::File),
+                    FudFileNotFound::NAME => Ok(Reply::FileNotFound),
+                    FudChunkReply::NAME => deserialize_async(&payload).await.map(Reply::Chunk),
+                    FudChunkNotFoundV2::NAME => deserialize_async(&payload)
+                        .await
+                        .map(|r: FudChunkNotFoundV2| Reply::ChunkNotFound(Some(r.chunk_hash))),
+                    _ => return Err(Error::MalformedPacket),
+                };
+                reply.map_err(|_| Error::MalformedPacket)
+            }
+            Self::Channel {
+                file_sub, file_not_found_sub, chunk_sub, chunk_not_found_sub, ..
+            } => {
+                future::or(
+                    future::or(
+                        async {
+                            file_sub.receive().await.map(|r| Reply::File((*r).clone().into()))
+                        },
+                        async { file_not_found_sub.receive().await.map(|_| Reply::FileNotFound) },
+                    ),
+                    future::or(
+                        async { chunk_sub.receive().await.map(|r| Reply::Chunk((*r).clone())) },
+                        async {
+                            chunk_not_found_sub.receive().await.map(|_| Reply::ChunkNotFound(None))
+                        },
+                    ),
+                )
+                .await
+            }
+        }
+    }
+
+    /// Maximum amount of outstanding chunk requests. Peers without
+    /// stream support reply with the unversioned [`FudChunkNotFound`],
+    /// which can't be matched to a request, so they only get one at a time.
+    fn chunk_window(&self, window: usize) -> usize {
+        match self {
+            Self::Stream(_) => window.max(1),
+            Self::Channel { .. } => 1,
+        }
+    }
+
+    /// Close the stream, or stop the dedicated channel
+    async fn close(self) {
+        match self {
+            Self::Stream(stream) => stream.close().await,
+            Self::Channel {
+                channel,
+                file_sub,
+                file_not_found_sub,
+                chunk_sub,
+                chunk_not_found_sub,
+            } => {
+                file_sub.unsubscribe().await;
+                file_not_found_sub.unsubscribe().await;
+                chunk_sub.unsubscribe().await;
+                chunk_not_found_sub.unsubscribe().await;
+                channel.stop().await;
+            }
+        }
+    }
+}
+
+/// Native [`FudTransport`] implementation over the fud P2P network.
+/// Peers are looked up in the routing tables, and routes of peers we
+/// fail to handshake with, or which serve invalid chunks, are removed.
+///
+/// Each fetch runs on its own stream, multiplexed over a channel to the
+/// peer which is kept open in [`Fud`] and reused by later fetches, until
+/// it stays idle for [`FETCH_CHANNEL_IDLE`] seconds. Peers without stream
+/// support are fetched from over a dedicated channel with plain messages,
+/// stopped once the fetch is done.
+pub struct P2pTransport<'a, 'e> {
+    fud: &'a Fud,
+    executor: &'a Arc<Executor<'e>>,
+}
+
+impl<'a, 'e> P2pTransport<'a, 'e> {
+    pub fn new(fud: &'a Fud, executor: &'a Arc<Executor<'e>>) -> Self {
+        Self { fud, executor }
+    }
+
+    /// Open a new fud stream to `peer`, reusing an existing channel to it
+    /// if there is one. Peers without stream support get a dedicated
+    /// channel instead. Peers failing the handshake are appended to
+    /// `invalid_routes`.
+    async fn open(&self, peer: &Url, invalid_routes: &mut Vec<Url>) -> Option<Fetcher> {
+        let channel = self.channel(peer, invalid_routes).await?;
+
+        match channel.open_stream(FUD_STREAM).await {
+            Ok(stream) => Some(Fetcher::Stream(stream)),
+            Err(Error::StreamsUnsupported) => {
+                debug!("Peer {} does not support streams, using a dedicated channel", peer);
+                self.fud.channels.remove(peer).await;
+                let channel = self.connect(peer, invalid_routes).await?;
+                match Fetcher::channel(channel.clone()).await {
+                    Ok(fetcher) => Some(fetcher),
+                    Err(e) => {
+                        error!("Failed subscribing to replies of {}: {}", peer, e);
+                        channel.stop().await;
+                        None
+                    }
+                }
+            }
+            Err(e) => {
+                error!("Failed opening stream to {}: {}", peer, e);
+                self.fud.channels.remove(peer).await;
+                None
+            }
+        }
+    }
+
+    /// Returns a channel to `peer`. Channels of our P2P sessions and the
+    /// ones we previously opened for fetching are reused, otherwise a new
+    /// one is connected and kept for later fetches.
+    async fn channel(&self, peer: &Url, invalid_routes: &mut Vec<Url>) -> Option<ChannelPtr> {
+        if let Some(channel) =
+            self.fud.p2p.hosts().peers().into_iter().find(|c| c.address() == peer)
+        {
+            return Some(channel)
+        }
+
+        if let Some(channel) = self.fud.channels.get(peer).await {
+            return Some(channel)
+        }
+
+        let channel = self.connect(peer, invalid_routes).await?;
+        self.fud.channels.insert(peer.clone(), channel.clone()).await;
+        Some(channel)
+    }
+
+    /// Connect to `peer` and perform the handshake protocols.
+    /// Peers failing the handshake are appended to `invalid_routes`.
+    async fn connect(&self, peer: &Url, invalid_routes: &mut Vec<Url>) -> Option<ChannelPtr> {
+        info!("Connecting to {}", peer);
+        let session_out = self.fud.p2p.session_outbound();
+        let session_weak = Arc::downgrade(&self.fud.p2p.session_outbound());
+
+        let connector = Connector::new(self.fud.p2p.settings(), session_weak);
+        let (url, channel) = match connector.connect(peer).await {
+            Ok(v) => v,
+            Err(e) => {
+                error!("Failed to connect to {}: {}", peer, e);
+                return None
+            }
+        };
+
+        let proto_ver = ProtocolVersion::new(
+            channel.clone(),
+            self.fud.p2p.settings().clone(),
+            self.fud.p2p.hosts().clone(),
+        )
+        .await;
+
+        let handshake_task = session_out.perform_handshake_protocols(
+            proto_ver,
+            channel.clone(),
+            self.executor.clone(),
+        );
+
+        channel.clone().start(self.executor.clone());
+
+        if let Err(e) = handshake_task.await {
+            error!("Handshake with {} failed: {}", url, e);
+            // Delete peer from router
+            invalid_routes.push(peer.clone());
+            return None
+        }
+
+        Some(channel)
+    }
+
+    /// Fetch the given chunks, opening a single stream per seeder, or a
+    /// dedicated channel to seeders without stream support, and
+    /// pipelining the requests over it. Peers discovered on the local
+    /// network are asked for every chunk and tried first, followed by the
+    /// seeders routing the most missing chunks. Returns the fetched chunks
+    /// along with the seeder that served each of them, which may be fewer
+    /// than the requested ones.
+    pub async fn fetch_chunks(
+        &self,
+        chunk_hashes: &[blake3::Hash],
+    ) -> HashMap<blake3::Hash, (Url, Vec<u8>)> {
+        // Grab the known seeders of each chunk, so we don't hold the
+        // router lock while fetching
+        let lan_peers = self.fud.lan.peers().await;
+        let mut seeders: HashMap<Url, Vec<blake3::Hash>> = HashMap::new();
+        let mut remaining = HashSet::new();
+        let chunks_router = self.fud.chunks_router.read().await;
+        for chunk_hash in chunk_hashes {
+            if !remaining.insert(*chunk_hash) {
+                continue
+            }
+
+            if let Some(m) = self.fud.denylists.check(chunk_hash).await {
+                warn!("Not fetching chunk {}, denylisted by {}", chunk_hash, m);
+                continue
+            }
+
+            for peer in &lan_peers {
+                seeders.entry(peer.clone()).or_default().push(*chunk_hash);
+            }
+
+            let Some(peers) = chunks_router.get(chunk_hash) else { continue };
+            for peer in peers.iter().filter(|p| !lan_peers.contains(p)) {
+                seeders.entry(peer.clone()).or_default().push(*chunk_hash);
+            }
+        }
+        drop(chunks_router);
+
+        let mut seeders: Vec<_> = seeders.into_iter().collect();
+        seeders.sort_by(|a, b| {
+            let (a_lan, b_lan) = (lan_peers.contains(&a.0), lan_peers.contains(&b.0));
+            b_lan.cmp(&a_lan).then(b.1.len().cmp(&a.1.len()))
+        });
+
+        let mut chunks = HashMap::new();
+        let mut invalid_chunk_routes = vec![];
+
+        for (peer, routed) in seeders {
+            let wanted: Vec<_> = routed.into_iter().filter(|h| remaining.contains(h)).collect();
+            if wanted.is_empty() {
+                continue
+            }
+
+            info!("Fetching {} chunks from {}", wanted.len(), peer);
+            let mut invalid_routes = vec![];
+            let Some(fetcher) = self.open(&peer, &mut invalid_routes).await else {
+                if lan_peers.contains(&peer) {
+                    self.fud.lan.forget(&peer).await;
+                }
+                if !invalid_routes.is_empty() {
+                    invalid_chunk_routes.extend(wanted.iter().map(|h| (*h, peer.clone())));
+                }
+                continue
+            };
+
+            let (fetched, invalid) = self.pipeline(&fetcher, &peer, &wanted).await;
+            fetcher.close().await;
+
+            for (chunk_hash, chunk) in fetched {
+                remaining.remove(&chunk_hash);
+                chunks.insert(chunk_hash, (peer.clone(), chunk));
+            }
+
+            // Seeder served an invalid chunk, so we don't trust it anymore
+            if invalid {
+                invalid_chunk_routes.extend(
+                    wanted.iter().filter(|h| remaining.contains(h)).map(|h| (*h, peer.clone())),
+                );
+            }
+
+            if remaining.is_empty() {
+                break
+            }
+        }
+
+        for (chunk_hash, peer) in invalid_chunk_routes {
+            debug!("Removing peer {} from {} chunk router", peer, chunk_hash);
+            self.fud.remove_chunk_route(&chunk_hash, &peer).await;
+        }
+
+        chunks
+    }
+
+    /// Request the given chunks from a seeder, keeping up to the
+    /// configured window of requests outstanding. Replies are matched
+    /// to requests by their chunk hash, so they may arrive in any order.
+    /// Returns the fetched chunks, and whether the seeder served an invalid one.
+    async fn pipeline(
+        &self,
+        fetcher: &Fetcher,
+        peer: &Url,
+        chunk_hashes: &[blake3::Hash],
+    ) -> (Vec<(blake3::Hash, Vec<u8>)>, bool) {
+        let window = fetcher.chunk_window(self.fud.chunk_window);
+        let mut pending = chunk_hashes.iter();
+        let mut outstanding = HashSet::new();
+        let mut fetched = vec![];
+        let mut invalid = false;
+
+        'pipeline: loop {
+            // Fill the window with new requests
+            while outstanding.len() < window {
+                let Some(chunk_hash) = pending.next() else { break };
+                let request = FudChunkRequest { chunk_hash: *chunk_hash };
+                if let Err(e) = fetcher.send(&request).await {
+                    error!("Failed sending FudChunkRequest({}) to {}: {}", chunk_hash, peer, e);
+                    break 'pipeline
+                }
+                outstanding.insert(*chunk_hash);
+            }
+
+            if outstanding.is_empty() {
+                break
+            }
+
+            let reply = match timeout(REPLY_TIMEOUT, fetcher.receive()).await {
+                Ok(Ok(v)) => v,
+                Ok(Err(Error::MalformedPacket)) => {
+                    error!("Received malformed chunk reply from {}", peer);
+                    invalid = true;
+                    break
+                }
+                Ok(Err(e)) => {
+                    error!("Error receiving chunk reply from {}: {}", peer, e);
+                    break
+                }
+                Err(_) => {
+                    warn!(
+                        "Timed out waiting for {} chunk replies from {}",
+                        outstanding.len(),
+                        peer
+                    );
+                    break
+                }
+            };
+
+            match reply {
+                Reply::Chunk(FudChunkReply { chunk }) => {
+                    let chunk_hash = self.fud.geode.hash_chunk(&chunk);
+                    if chunk.len() > MAX_CHUNK_SIZE || !outstanding.remove(&chunk_hash) {
+                        error!("Received chunk from {} does not match any requested chunk", peer);
+                        invalid = true;
+                        break
+                    }
+
+                    self.fud.swarm.record_download(peer, chunk_hash, chunk.len() as u64).await;
+                    fetched.push((chunk_hash, chunk));
+                }
+                Reply::ChunkNotFound(Some(chunk_hash)) if outstanding.remove(&chunk_hash) => {
+                    debug!("Peer {} does not have chunk {}", peer, chunk_hash);
+                }
+                // With a window of one, the unversioned reply can only be
+                // about the single outstanding request
+                Reply::ChunkNotFound(None) if window == 1 => {
+                    debug!("Peer {} does not have chunk {:?}", peer, outstanding);
+                    outstanding.clear();
+                }
+                _ => {
+                    error!("Received unexpected reply to chunk requests from {}", peer);
+                    invalid = true;
+                    break
+                }
+            }
+        }
+
+        (fetched, invalid)
+    }
+}
+
+#[async_trait]
+impl FudTransport for P2pTransport<'_, '_> {
+    async fn fetch_metadata(
+        &self,
+        file_hash: &blake3::Hash,
+    ) -> fud_client::Result<Vec<blake3::Hash>> {
+        if let Some(m) = self.fud.denylists.check(file_hash).await {
+            return Err(fud_client::Error::Transport(format!("{} denylisted by {}", file_hash, m)))
+        }
+
+        // Grab the known peers, so we don't hold the router lock while fetching.
+        // Peers discovered on the local network are asked first.
+        let lan_peers = self.fud.lan.peers().await;
+        let routed = self.fud.metadata_router.read().await.get(file_hash).cloned();
+        let mut peers = lan_peers.clone();
+        peers.extend(routed.into_iter().flatten().filter(|p| !lan_peers.contains(p)));
+        if peers.is_empty() {
+            return Err(fud_client::Error::FileNotFound(*file_hash))
+        }
+
+        let mut chunk_hashes = None;
+        let mut invalid_file_routes = vec![];
+
+        for peer in peers.iter() {
+            info!("Fetching {} from {}", file_hash, peer);
+            let Some(fetcher) = self.open(peer, &mut invalid_file_routes).await else {
+                if lan_peers.contains(peer) {
+                    self.fud.lan.forget(peer).await;
+                }
+                continue
+            };
+
+            let request = FudFileRequest { file_hash: *file_hash };
+            if let Err(e) = fetcher.send(&request).await {
+                error!("Failed sending FudFileRequest({}) to {}: {}", file_hash, peer, e);
+                fetcher.close().await;
+                continue
+            }
+
+            let reply = timeout(REPLY_TIMEOUT, fetcher.receive()).await;
+            fetcher.close().await;
+
+            let reply = match reply {
+                Ok(Ok(Reply::File(v))) => v,
+                Ok(Ok(Reply::FileNotFound)) => {
+                    debug!("Peer {} does not have file {}", peer, file_hash);
+                    continue
+                }
+                Ok(Ok(_)) | Ok(Err(Error::MalformedPacket)) => {
+                    error!("Received malformed FudFileReply from {}", peer);
+                    continue
+                }
+                Ok(Err(e)) => {
+                    error!("Error receiving FudFileReply from {}: {}", peer, e);
+                    continue
+                }
+                Err(_) => {
+                    warn!("Timed out waiting for FudFileReply from {}", peer);
+                    continue
+                }
+            };
+
+            // Reject tampered metadata, or metadata not signed by a pinned publisher
+            if !self.fud.publishers.accept(file_hash, &reply.chunk_hashes, &reply.publisher) {
+                warn!("Rejected {} metadata served by {}", file_hash, peer);
+                continue
+            }
+
+            // Files containing denylisted chunks are not fetched at all
+            if let Some(m) = self.fud.denylisted(file_hash, &reply.chunk_hashes).await {
+                return Err(fud_client::Error::Transport(format!(
+                    "{} contains chunks denylisted by {}",
+                    file_hash, m
+                )))
+            }
+
+            // Keep the publisher signature so we can serve it on
+            if let Some(publisher) = &reply.publisher {
+                if let Err(e) = self.fud.publishers.put(file_hash, publisher).await {
+                    error!("Failed storing publisher signature of {}: {}", file_hash, e);
+                }
+            }
+
+            chunk_hashes = Some(reply.chunk_hashes);
+            break
+        }
+
+        if !invalid_file_routes.is_empty() {
+            let mut metadata_router = self.fud.metadata_router.write().await;
+            if let Some(peers) = metadata_router.get_mut(file_hash) {
+                for peer in invalid_file_routes {
+                    debug!("Removing peer {} from {} file router", peer, file_hash);
+                    peers.remove(&peer);
+                }
+            }
+        }
+
+        chunk_hashes.ok_or(fud_client::Error::FileNotFound(*file_hash))
+    }
+
+    async fn fetch_chunk(&self, chunk_hash: &blake3::Hash) -> fud_client::Result<Vec<u8>> {
+        let mut chunks = self.fetch_chunks(&[*chunk_hash]).await;
+        let Some((_, chunk)) = chunks.remove(chunk_hash) else {
+            return Err(fud_client::Error::ChunkNotFound(*chunk_hash))
+        };
+        Ok(chunk)
+    }
+}
@@ -0,0 +1,235 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2025 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Destination path handling for fetched files.
+//!
+//! Download destinations can be given as templates, e.g.
+//! `~/Downloads/{name}-{hash8}`, supporting the following placeholders:
+//!
+//! * `{name}`: The file name, falling back to the file hash if unknown
+//! * `{hash}`: The full hex-encoded file hash
+//! * `{hash8}`: The first 8 hex characters of the file hash
+//!
+//! The template itself is trusted local configuration, while file names
+//! may come from untrusted sources, so they are always sanitized into a
+//! single path component before substitution.
+
+use std::{
+    io::ErrorKind,
+    path::{Path, PathBuf},
+};
+
+use smol::{fs, io::AsyncWriteExt};
+
+use darkfi::{geode::ChunkedFile, util::path::expand_path, Error, Result};
+
+/// Maximum length in bytes of a sanitized file name
+const MAX_NAME_LEN: usize = 255;
+
+/// Sanitize a possibly attacker-supplied file name into a single path
+/// component. Directory separators and control characters are replaced,
+/// leading dots are stripped so the result can't be `.`, `..` or a
+/// hidden file, and the name is truncated to [`MAX_NAME_LEN`] bytes.
+/// Returns `None` if nothing usable remains.
+pub fn sanitize_file_name(name: &str) -> Option<String> {
+    let mut sanitized: String = name
+        .chars()
+        .map(|c| match c {
+            '/' | '\\' | ':' => '_',
+            c if c.is_control() => '_',
+            c => c,
+        })
+        .collect();
+
+    sanitized = sanitized.trim().trim_start_matches('.').to_string();
+
+    if sanitized.len() > MAX_NAME_LEN {
+        let mut end = MAX_NAME_LEN;
+        while !sanitized.is_char_boundary(end) {
+            end -= 1;
+        }
+        sanitized.truncate(end);
+    }
+
+    if sanitized.is_empty() {
+        return None
+    }
+
+    Some(sanitized)
+}
+
+/// Expand a destination path template for the given file.
+/// `name` is sanitized with [`sanitize_file_name`] before substitution.
+pub fn expand_template(
+    template: &str,
+    file_hash: &blake3::Hash,
+    name: Option<&str>,
+) -> Result<PathBuf> {
+    let hash = file_hash.to_hex().to_string();
+    let name = name.and_then(sanitize_file_name).unwrap_or_else(|| hash.clone());
+
+    let path =
+        template.replace("{name}", &name).replace("{hash8}", &hash[..8]).replace("{hash}", &hash);
+
+    expand_path(&path)
+}
+
+/// Reserve a free destination by creating an empty file at `path`, or at
+/// the first free path obtained by appending `-1`, `-2`, ... to the file
+/// stem. Files are created with create-new semantics, so an existing file
+/// is never reused, even if it gets created concurrently.
+/// Returns the reserved path.
+pub async fn unique_path(path: &Path) -> Result<PathBuf> {
+    let stem = path.file_stem().map(|s| s.to_string_lossy().into_owned()).unwrap_or_default();
+    let ext = path.extension().map(|e| e.to_string_lossy().into_owned());
+
+    let mut candidate = path.to_path_buf();
+    let mut i = 1;
+    loop {
+        match fs::OpenOptions::new().write(true).create_new(true).open(&candidate).await {
+            Ok(_) => return Ok(candidate),
+            Err(e) if e.kind() == ErrorKind::AlreadyExists => {}
+            Err(e) => return Err(e.into()),
+        }
+
+        let file_name = match &ext {
+            Some(ext) => format!("{}-{}.{}", stem, i, ext),
+            None => format!("{}-{}", stem, i),
+        };
+        candidate = path.with_file_name(file_name);
+        i += 1;
+    }
+}
+
+/// Concatenate the chunks of a complete file into a free destination
+/// reserved from `dest` with [`unique_path`], creating any missing parent
+/// directories. The file is written to a `.part` sibling first and only
+/// renamed over the reserved destination once fully written, so existing
+/// files are never overwritten. Returns the final path.
+pub async fn assemble_file(chunked_file: &ChunkedFile, dest: &Path) -> Result<PathBuf> {
+    if !chunked_file.is_complete() {
+        return Err(Error::GeodeFileNotFound)
+    }
+
+    if let Some(parent) = dest.parent() {
+        fs::create_dir_all(parent).await?;
+    }
+
+    let dest = unique_path(dest).await?;
+    let mut part = dest.clone().into_os_string();
+    part.push(".part");
+    let part = PathBuf::from(part);
+
+    if let Err(e) = write_chunks(chunked_file, &part).await {
+        let _ = fs::remove_file(&part).await;
+        let _ = fs::remove_file(&dest).await;
+        return Err(e)
+    }
+
+    fs::rename(&part, &dest).await?;
+    Ok(dest)
+}
+
+/// Concatenate the chunks of a complete file into `path`.
+async fn write_chunks(chunked_file: &ChunkedFile, path: &Path) -> Result<()> {
+    let mut file = fs::File::create(path).await?;
+    for (_, chunk_path) in chunked_file.iter() {
+        let chunk = fs::read(chunk_path.as_ref().unwrap()).await?;
+        file.write_all(&chunk).await?;
+    }
+    file.sync_all().await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use darkfi::geode::Geode;
+    use rand::{rngs::OsRng, Rng};
+
+    use super::*;
+
+    #[test]
+    fn sanitize_file_names() {
+        assert_eq!(sanitize_file_name("file.txt").unwrap(), "file.txt");
+        assert_eq!(sanitize_file_name("../../etc/passwd").unwrap(), "_.._etc_passwd");
+        assert_eq!(sanitize_file_name("a\\b:c\nd").unwrap(), "a_b_c_d");
+        assert_eq!(sanitize_file_name("  .hidden ").unwrap(), "hidden");
+        assert!(sanitize_file_name("..").is_none());
+        assert!(sanitize_file_name(" . ").is_none());
+        assert!(sanitize_file_name("").is_none());
+
+        // Long names are truncated on a char boundary
+        let long = "é".repeat(MAX_NAME_LEN);
+        let sanitized = sanitize_file_name(&long).unwrap();
+        assert!(sanitized.len() <= MAX_NAME_LEN);
+        assert!(sanitized.chars().all(|c| c == 'é'));
+    }
+
+    #[test]
+    fn expand_templates() {
+        let file_hash = blake3::hash(b"fud");
+        let hash = file_hash.to_hex().to_string();
+
+        let path = expand_template("/dl/{name}-{hash8}", &file_hash, Some("file.txt")).unwrap();
+        assert_eq!(path, PathBuf::from(format!("/dl/file.txt-{}", &hash[..8])));
+
+        // Unusable or missing names fall back to the file hash
+        let path = expand_template("/dl/{name}", &file_hash, Some("..")).unwrap();
+        assert_eq!(path, PathBuf::from(format!("/dl/{}", hash)));
+        let path = expand_template("/dl/{hash}/{name}", &file_hash, None).unwrap();
+        assert_eq!(path, PathBuf::from(format!("/dl/{}/{}", hash, hash)));
+
+        // Names can't escape the template directory
+        let path = expand_template("/dl/{name}", &file_hash, Some("../../etc/passwd")).unwrap();
+        assert_eq!(path, PathBuf::from("/dl/_.._etc_passwd"));
+    }
+
+    #[test]
+    fn unique_paths_and_assembly() {
+        smol::block_on(async {
+            let basedir = std::env::temp_dir().join(format!("fud_util_{}", OsRng.gen::<u64>()));
+            fs::create_dir_all(&basedir).await.unwrap();
+
+            // Paths get reserved, never reusing an existing file
+            let path = basedir.join("file.txt");
+            assert_eq!(unique_path(&path).await.unwrap(), path);
+            assert_eq!(unique_path(&path).await.unwrap(), basedir.join("file-1.txt"));
+            assert_eq!(unique_path(&path).await.unwrap(), basedir.join("file-2.txt"));
+            let path = basedir.join("noext");
+            assert_eq!(unique_path(&path).await.unwrap(), path);
+            assert_eq!(unique_path(&path).await.unwrap(), basedir.join("noext-1"));
+
+            // Assembling doesn't overwrite existing files
+            let geode = Geode::new(&basedir.join("geode")).await.unwrap();
+            let (file_hash, _) = geode.insert(smol::io::Cursor::new(b"content")).await.unwrap();
+            let chunked_file = geode.get(&file_hash).await.unwrap();
+
+            let dest = basedir.join("out").join("file.txt");
+            fs::create_dir_all(basedir.join("out")).await.unwrap();
+            fs::write(&dest, b"existing").await.unwrap();
+
+            let assembled = assemble_file(&chunked_file, &dest).await.unwrap();
+            assert_eq!(assembled, basedir.join("out").join("file-1.txt"));
+            assert_eq!(fs::read(&assembled).await.unwrap(), b"content");
+            assert_eq!(fs::read(&dest).await.unwrap(), b"existing");
+            assert!(!PathBuf::from(format!("{}.part", assembled.display())).exists());
+
+            fs::remove_dir_all(&basedir).await.unwrap();
+        })
+    }
+}
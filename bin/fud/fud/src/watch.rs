@@ -0,0 +1,144 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2025 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Filesystem watcher for the Geode chunks directory.
+//!
+//! Chunks we are seeding may get modified or removed behind our back.
+//! Geode refuses to serve inconsistent chunks, but the rest of the
+//! network would keep routing requests to us until the next garbage
+//! collection. Here we re-verify chunks as soon as their files change,
+//! garbage collect the corrupted ones, and withdraw their announces so
+//! peers stop routing to us. Files using those chunks become incomplete
+//! and will be fetched again on the next `get`.
+
+use std::{collections::HashSet, path::Path, sync::Arc, time::Duration};
+
+use log::{debug, error, info, warn};
+use notify::{EventKind, RecursiveMode, Watcher};
+use smol::{channel, future, Timer};
+
+use darkfi::{Error, Result};
+
+use super::{proto::FudChunkWithdraw, Fud};
+
+/// Time to wait for changes to settle before re-verifying chunks,
+/// so we don't verify chunks while they're still being written.
+const SETTLE_DELAY: Duration = Duration::from_secs(2);
+
+/// Parse a chunk hash from its path in the chunks directory
+fn chunk_hash_from_path(path: &Path) -> Option<blake3::Hash> {
+    let file_name = path.file_name()?.to_str()?;
+    blake3::Hash::from_hex(file_name).ok()
+}
+
+/// Re-verify the given chunks, garbage collecting the corrupted ones and
+/// withdrawing announces for anything we are no longer able to serve.
+pub async fn verify_chunks(fud: &Fud, chunks: HashSet<blake3::Hash>) -> Result<()> {
+    let mut withdrawn = HashSet::new();
+    let mut needs_gc = false;
+
+    for chunk_hash in chunks {
+        match fud.geode.get_chunk(&chunk_hash).await {
+            Ok(_) => debug!(target: "fud::watch", "Chunk {} is consistent", chunk_hash),
+            Err(Error::GeodeChunkNotFound) => {
+                info!(target: "fud::watch", "Chunk {} was removed", chunk_hash);
+                withdrawn.insert(chunk_hash);
+            }
+            Err(Error::GeodeNeedsGc) => {
+                warn!(target: "fud::watch", "Chunk {} is corrupted", chunk_hash);
+                needs_gc = true;
+            }
+            Err(e) => return Err(e),
+        }
+    }
+
+    if needs_gc {
+        let (_, deleted_chunks) = fud.geode.garbage_collect().await?;
+        withdrawn.extend(deleted_chunks);
+    }
+
+    // Removed chunks are not seeded anymore
+    fud.uploads.remove(&withdrawn).await;
+
+    for chunk_hash in withdrawn {
+        info!(target: "fud::watch", "Withdrawing announce for chunk {}", chunk_hash);
+        fud.p2p.broadcast(&FudChunkWithdraw { chunk_hash }).await;
+    }
+
+    Ok(())
+}
+
+/// Background task watching the Geode chunks directory for changes,
+/// and re-verifying the chunks whose files were modified or removed.
+pub async fn watch_task(fud: Arc<Fud>) -> Result<()> {
+    let chunks_path = fud.geode.chunks_path();
+    let (event_tx, event_rx) = channel::unbounded();
+
+    // The watcher runs its own thread, so we forward its events
+    // into our channel.
+    let mut watcher = match notify::recommended_watcher(move |res| {
+        let _ = event_tx.send_blocking(res);
+    }) {
+        Ok(v) => v,
+        Err(e) => {
+            error!(target: "fud::watch", "Failed creating filesystem watcher: {}", e);
+            return Err(Error::Custom(e.to_string()))
+        }
+    };
+
+    if let Err(e) = watcher.watch(&chunks_path, RecursiveMode::NonRecursive) {
+        error!(target: "fud::watch", "Failed watching {:?}: {}", chunks_path, e);
+        return Err(Error::Custom(e.to_string()))
+    }
+
+    info!(target: "fud::watch", "Watching {:?} for changes", chunks_path);
+
+    let mut changed = HashSet::new();
+    loop {
+        // Gather changed chunks until things settle down
+        let event = if changed.is_empty() {
+            Some(event_rx.recv().await)
+        } else {
+            future::or(async { Some(event_rx.recv().await) }, async {
+                Timer::after(SETTLE_DELAY).await;
+                None
+            })
+            .await
+        };
+
+        match event {
+            Some(Ok(Ok(event))) => {
+                if !matches!(event.kind, EventKind::Modify(_) | EventKind::Remove(_)) {
+                    continue
+                }
+
+                changed.extend(event.paths.iter().filter_map(|p| chunk_hash_from_path(p)));
+            }
+            Some(Ok(Err(e))) => {
+                warn!(target: "fud::watch", "Filesystem watcher error: {}", e);
+            }
+            Some(Err(_)) => return Err(Error::DetachedTaskStopped),
+            None => {
+                let chunks = std::mem::take(&mut changed);
+                if let Err(e) = verify_chunks(&fud, chunks).await {
+                    error!(target: "fud::watch", "Failed re-verifying chunks: {}", e);
+                }
+            }
+        }
+    }
+}
@@ -57,7 +57,13 @@ impl RequestHandler<()> for JsonRpcInterface {
             "ping" => self.pong(req.id, req.params).await,
             "dnet.subscribe_events" => self.dnet_subscribe_events(req.id, req.params).await,
             "dnet.switch" => self.dnet_switch(req.id, req.params).await,
+            "dnet.get_recent" => self.dnet_get_recent(req.id, req.params).await,
             "p2p.get_info" => self.p2p_get_info(req.id, req.params).await,
+            "p2p.get_transport_stats" => self.p2p_get_transport_stats(req.id, req.params).await,
+            "p2p.export_hosts" => self.p2p_export_hosts(req.id, req.params).await,
+            "p2p.import_hosts" => self.p2p_import_hosts(req.id, req.params).await,
+            "p2p.add_peer" => self.p2p_add_peer(req.id, req.params).await,
+            "p2p.remove_peer" => self.p2p_remove_peer(req.id, req.params).await,
 
             "deg.switch" => self.deg_switch(req.id, req.params).await,
             "deg.subscribe_events" => self.deg_subscribe_events(req.id, req.params).await,
@@ -26,6 +26,7 @@ use std::{
 use async_trait::async_trait;
 use log::{debug, error, info, warn};
 use semver::Version;
+use sled_overlay::sled;
 use smol::{
     lock::{Mutex, MutexGuard},
     stream::StreamExt,
@@ -45,10 +46,13 @@ use darkfi::{
         server::{listen_and_serve, RequestHandler},
     },
     system::{sleep, StoppableTask, StoppableTaskPtr},
-    util::path::get_config_path,
+    util::path::{expand_path, get_config_path},
     Error, Result,
 };
 
+mod stats;
+use stats::StatsStore;
+
 const CONFIG_FILE: &str = "lilith_config.toml";
 const CONFIG_FILE_CONTENTS: &str = include_str!("../lilith_config.toml");
 
@@ -75,6 +79,18 @@ struct Args {
     #[structopt(long, default_value = "120")]
     /// Interval after which to check whitelist peers
     whitelist_refinery_interval: u64,
+
+    #[structopt(long, default_value = "~/.local/share/darkfi/lilith/stats")]
+    /// Path to the network statistics history database
+    stats_datastore: String,
+
+    #[structopt(long, default_value = "300")]
+    /// Interval in seconds between network statistics samples (at least 1)
+    stats_interval: u64,
+
+    #[structopt(long, default_value = "30")]
+    /// Days to keep network statistics history for
+    stats_retention: u64,
 }
 
 /// Struct representing a spawned P2P network
@@ -155,6 +171,8 @@ struct NetInfo {
 struct Lilith {
     /// Spawned networks
     pub networks: Vec<Spawn>,
+    /// Networks statistics history
+    pub stats: Arc<StatsStore>,
     /// JSON-RPC connection tracker
     pub rpc_connections: Mutex<HashSet<StoppableTaskPtr>>,
 }
@@ -223,6 +241,29 @@ impl Lilith {
             }
         }
     }
+
+    /// Periodically sample the given network health and store it in the
+    /// statistics history, pruning samples older than `retention` seconds.
+    async fn stats_task(
+        network_name: String,
+        p2p: P2pPtr,
+        stats: Arc<StatsStore>,
+        interval: u64,
+        retention: u64,
+    ) -> Result<()> {
+        debug!(target: "lilith::stats_task", "Starting stats task for \"{}\"", network_name);
+
+        let mut known_hosts = None;
+        loop {
+            if let Err(e) = stats.record(&network_name, &p2p, &mut known_hosts, retention).await {
+                error!(target: "lilith::stats_task", "Failed recording stats for \"{}\": {}",
+                       network_name, e);
+            }
+
+            sleep(interval).await;
+        }
+    }
+
     // RPCAPI:
     // Returns all spawned networks names with their node addresses.
     // --> {"jsonrpc": "2.0", "method": "spawns", "params": [], "id": 42}
@@ -238,6 +279,44 @@ impl Lilith {
 
         JsonResponse::new(json, id).into()
     }
+
+    // RPCAPI:
+    // Returns the health statistics history of a spawned network, sampled
+    // over the given time window in seconds. Each sample contains the
+    // hostlists sizes, the connected channels count, the host churn since
+    // the previous sample, and the connected peers version distribution.
+    // --> {"jsonrpc": "2.0", "method": "stats.get_history", "params": ["darkirc_v4", 86400], "id": 42}
+    // <-- {"jsonrpc": "2.0", "result": [{"timestamp": 1700000000, "whitelist": 20, "greylist": 50, "goldlist": 3, "connected": 12, "joined": 2, "left": 1, "versions": {"0.4.1": 12}}, ...], "id": 42}
+    async fn stats_get_history(&self, id: u16, params: JsonValue) -> JsonResult {
+        let params = params.get::<Vec<JsonValue>>().unwrap();
+        if params.len() != 2 || !params[0].is_string() || !params[1].is_number() {
+            return JsonError::new(ErrorCode::InvalidParams, None, id).into()
+        }
+
+        let network = params[0].get::<String>().unwrap();
+        if !self.networks.iter().any(|spawn| &spawn.name == network) {
+            return JsonError::new(
+                ErrorCode::InvalidParams,
+                Some(format!("Unknown network: {network}")),
+                id,
+            )
+            .into()
+        }
+
+        let window = *params[1].get::<f64>().unwrap() as u64;
+        let since = UNIX_EPOCH.elapsed().unwrap().as_secs().saturating_sub(window);
+
+        let history = match self.stats.get_history(network, since) {
+            Ok(v) => v,
+            Err(e) => {
+                error!(target: "lilith::stats_get_history", "Failed retrieving stats history: {}", e);
+                return JsonError::new(ErrorCode::InternalError, None, id).into()
+            }
+        };
+
+        let history = history.iter().map(|stats| stats.to_json()).collect();
+        JsonResponse::new(JsonValue::Array(history), id).into()
+    }
 }
 
 #[async_trait]
@@ -246,6 +325,7 @@ impl RequestHandler<()> for Lilith {
         return match req.method.as_str() {
             "ping" => self.pong(req.id, req.params).await,
             "spawns" => self.spawns(req.id, req.params).await,
+            "stats.get_history" => self.stats_get_history(req.id, req.params).await,
             _ => JsonError::new(ErrorCode::MethodNotFound, None, req.id).into(),
         }
     }
@@ -390,6 +470,11 @@ async fn realmain(args: Args, ex: Arc<Executor<'static>>) -> Result<()> {
         exit(1);
     }
 
+    if args.stats_interval == 0 {
+        error!(target: "lilith", "stats_interval must be at least 1 second");
+        exit(1);
+    }
+
     // Spawn configured networks
     let mut networks = vec![];
     for (name, info) in &configured_nets {
@@ -402,8 +487,16 @@ async fn realmain(args: Args, ex: Arc<Executor<'static>>) -> Result<()> {
         }
     }
 
+    // Open the statistics history database
+    let stats_db = sled::open(expand_path(&args.stats_datastore)?)?;
+    let stats = Arc::new(StatsStore::new(stats_db));
+
     // Set up main daemon and background refinery_tasks
-    let lilith = Arc::new(Lilith { networks, rpc_connections: Mutex::new(HashSet::new()) });
+    let lilith = Arc::new(Lilith {
+        networks,
+        stats: stats.clone(),
+        rpc_connections: Mutex::new(HashSet::new()),
+    });
     let mut refinery_tasks = HashMap::new();
     for network in &lilith.networks {
         let name = network.name.clone();
@@ -422,6 +515,33 @@ async fn realmain(args: Args, ex: Arc<Executor<'static>>) -> Result<()> {
         refinery_tasks.insert(network.name.clone(), task);
     }
 
+    // Background stats_tasks
+    let stats_retention = args.stats_retention * 24 * 60 * 60;
+    let mut stats_tasks = HashMap::new();
+    for network in &lilith.networks {
+        let name = network.name.clone();
+        let stats_task = Lilith::stats_task(
+            name.clone(),
+            network.p2p.clone(),
+            stats.clone(),
+            args.stats_interval,
+            stats_retention,
+        );
+        let task = StoppableTask::new();
+        task.clone().start(
+            stats_task,
+            |res| async move {
+                match res {
+                    Ok(()) | Err(Error::DetachedTaskStopped) => { /* Do nothing */ }
+                    Err(e) => error!(target: "lilith", "Failed starting stats task for \"{}\": {}", name, e),
+                }
+            },
+            Error::DetachedTaskStopped,
+            ex.clone(),
+        );
+        stats_tasks.insert(network.name.clone(), task);
+    }
+
     // JSON-RPC server
     info!(target: "lilith", "Starting JSON-RPC server on {}", args.rpc_listen);
     let lilith_ = lilith.clone();
@@ -450,10 +570,94 @@ async fn realmain(args: Args, ex: Arc<Executor<'static>>) -> Result<()> {
     for spawn in &lilith.networks {
         info!(target: "lilith", "Stopping \"{}\" task", spawn.name);
         refinery_tasks.get(&spawn.name).unwrap().stop().await;
+        stats_tasks.get(&spawn.name).unwrap().stop().await;
         info!(target: "lilith", "Stopping \"{}\" P2P", spawn.name);
         spawn.p2p.stop().await;
     }
 
+    info!(target: "lilith", "Flushing stats database...");
+    stats.flush().await?;
+
     info!(target: "lilith", "Bye!");
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use stats::NetStats;
+
+    fn sample(timestamp: u64) -> NetStats {
+        NetStats {
+            timestamp,
+            whitelist: 1,
+            greylist: 2,
+            goldlist: 3,
+            connected: 4,
+            joined: 0,
+            left: 0,
+            versions: vec![],
+        }
+    }
+
+    fn error_code(res: JsonResult) -> i32 {
+        let JsonResult::Error(e) = res else { panic!("Expected an error") };
+        e.error.code
+    }
+
+    #[test]
+    fn lilith_stats_get_history() {
+        let path = std::env::temp_dir().join(format!("lilith_rpc_stats_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&path);
+        let ex = Arc::new(Executor::new());
+
+        smol::block_on(async {
+            let p2p = P2p::new(net::Settings::default(), ex.clone()).await.unwrap();
+            let stats = Arc::new(StatsStore::new(sled::open(&path).unwrap()));
+            let now = UNIX_EPOCH.elapsed().unwrap().as_secs();
+            stats.insert("foo", &sample(now - 1000)).unwrap();
+            stats.insert("foo", &sample(now - 10)).unwrap();
+
+            let lilith = Lilith {
+                networks: vec![Spawn { name: "foo".to_string(), p2p }],
+                stats,
+                rpc_connections: Mutex::new(HashSet::new()),
+            };
+
+            // Only the samples within the window are returned
+            let params = |name: &str, window: f64| {
+                JsonValue::Array(vec![
+                    JsonValue::String(name.to_string()),
+                    JsonValue::Number(window),
+                ])
+            };
+            let JsonResult::Response(rep) = lilith.stats_get_history(1, params("foo", 100.0)).await
+            else {
+                panic!("Expected a response")
+            };
+            let history = rep.result.get::<Vec<JsonValue>>().unwrap();
+            assert_eq!(history.len(), 1);
+            assert_eq!(history[0]["timestamp"].get::<f64>(), Some(&((now - 10) as f64)));
+            assert_eq!(history[0]["goldlist"].get::<f64>(), Some(&3.0));
+
+            let JsonResult::Response(rep) =
+                lilith.stats_get_history(1, params("foo", 2000.0)).await
+            else {
+                panic!("Expected a response")
+            };
+            assert_eq!(rep.result.get::<Vec<JsonValue>>().unwrap().len(), 2);
+
+            // Unknown networks and malformed params are rejected
+            let code = ErrorCode::InvalidParams.code();
+            assert_eq!(error_code(lilith.stats_get_history(1, params("bar", 100.0)).await), code);
+            let res = lilith.stats_get_history(1, JsonValue::Array(vec![])).await;
+            assert_eq!(error_code(res), code);
+            let res = lilith
+                .stats_get_history(1, JsonValue::Array(vec![JsonValue::String("foo".to_string())]))
+                .await;
+            assert_eq!(error_code(res), code);
+        });
+
+        std::fs::remove_dir_all(&path).unwrap();
+    }
+}
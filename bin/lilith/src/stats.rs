@@ -0,0 +1,275 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2025 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Historical network health statistics.
+//!
+//! Every spawned network gets periodically sampled for its hostlist sizes,
+//! host churn, and the version distribution of its connected peers. Samples
+//! are persisted in a sled tree per network, keyed by their timestamp, so
+//! they can be queried over a time window through JSON-RPC.
+
+use std::{
+    collections::{HashMap, HashSet},
+    time::UNIX_EPOCH,
+};
+
+use darkfi::{
+    net::{hosts::HostColor, P2pPtr},
+    Result,
+};
+use darkfi_serial::{deserialize, serialize, SerialDecodable, SerialEncodable};
+use log::{debug, warn};
+use sled_overlay::sled;
+use tinyjson::JsonValue;
+use url::Url;
+
+/// Ratio of previously known hosts that must disappear between two
+/// samples for us to consider it a mass disconnect event.
+const MASS_DISCONNECT_RATIO: f64 = 0.5;
+
+/// Snapshot of a network's health at a given point in time
+#[derive(Clone, Debug, SerialEncodable, SerialDecodable)]
+pub struct NetStats {
+    /// UNIX timestamp of the sample
+    pub timestamp: u64,
+    /// Amount of hosts in the whitelist
+    pub whitelist: u64,
+    /// Amount of hosts in the greylist
+    pub greylist: u64,
+    /// Amount of hosts in the goldlist
+    pub goldlist: u64,
+    /// Amount of currently connected channels
+    pub connected: u64,
+    /// Hosts that appeared in the white or gold lists since the previous sample
+    pub joined: u64,
+    /// Hosts that disappeared from the white or gold lists since the previous sample
+    pub left: u64,
+    /// Connected peers version distribution
+    pub versions: Vec<(String, u64)>,
+}
+
+impl NetStats {
+    /// Sample the given P2P network. `known_hosts` holds the white and gold
+    /// hosts of the previous sample, and gets updated with the current ones.
+    pub async fn sample(p2p: &P2pPtr, known_hosts: &mut Option<HashSet<Url>>) -> Self {
+        let hosts = p2p.hosts();
+        let whitelist = hosts.container.fetch_all(HostColor::White);
+        let greylist = hosts.container.fetch_all(HostColor::Grey);
+        let goldlist = hosts.container.fetch_all(HostColor::Gold);
+
+        // Compute the churn against the previous sample
+        let current: HashSet<Url> =
+            whitelist.iter().chain(goldlist.iter()).map(|(url, _)| url.clone()).collect();
+        let (joined, left) = match known_hosts {
+            Some(previous) => (
+                current.difference(previous).count() as u64,
+                previous.difference(&current).count() as u64,
+            ),
+            None => (0, 0),
+        };
+        *known_hosts = Some(current);
+
+        // Grab the connected peers versions
+        let channels = hosts.channels();
+        let mut versions: HashMap<String, u64> = HashMap::new();
+        for channel in channels.iter() {
+            let version = match channel.version.lock().await.as_ref() {
+                Some(v) => v.version.to_string(),
+                None => "unknown".to_string(),
+            };
+            *versions.entry(version).or_insert(0) += 1;
+        }
+        let mut versions: Vec<(String, u64)> = versions.into_iter().collect();
+        versions.sort();
+
+        Self {
+            timestamp: UNIX_EPOCH.elapsed().unwrap().as_secs(),
+            whitelist: whitelist.len() as u64,
+            greylist: greylist.len() as u64,
+            goldlist: goldlist.len() as u64,
+            connected: channels.len() as u64,
+            joined,
+            left,
+            versions,
+        }
+    }
+
+    /// Check if this sample indicates a mass disconnect event, given the
+    /// amount of white and gold hosts of the previous sample.
+    pub fn is_mass_disconnect(&self, previous_hosts: u64) -> bool {
+        previous_hosts > 0 && self.left as f64 / previous_hosts as f64 >= MASS_DISCONNECT_RATIO
+    }
+
+    pub fn to_json(&self) -> JsonValue {
+        let versions = self
+            .versions
+            .iter()
+            .map(|(version, count)| (version.clone(), JsonValue::Number(*count as f64)))
+            .collect();
+
+        JsonValue::Object(HashMap::from([
+            ("timestamp".to_string(), JsonValue::Number(self.timestamp as f64)),
+            ("whitelist".to_string(), JsonValue::Number(self.whitelist as f64)),
+            ("greylist".to_string(), JsonValue::Number(self.greylist as f64)),
+            ("goldlist".to_string(), JsonValue::Number(self.goldlist as f64)),
+            ("connected".to_string(), JsonValue::Number(self.connected as f64)),
+            ("joined".to_string(), JsonValue::Number(self.joined as f64)),
+            ("left".to_string(), JsonValue::Number(self.left as f64)),
+            ("versions".to_string(), JsonValue::Object(versions)),
+        ]))
+    }
+}
+
+/// Persistent store of the networks statistics history
+pub struct StatsStore {
+    /// Sled database holding a tree per network
+    db: sled::Db,
+}
+
+impl StatsStore {
+    pub fn new(db: sled::Db) -> Self {
+        Self { db }
+    }
+
+    /// Store a sample for the given network
+    pub fn insert(&self, network: &str, stats: &NetStats) -> Result<()> {
+        let tree = self.db.open_tree(network)?;
+        tree.insert(stats.timestamp.to_be_bytes(), serialize(stats))?;
+        Ok(())
+    }
+
+    /// Retrieve all samples of the given network taken since `since`
+    pub fn get_history(&self, network: &str, since: u64) -> Result<Vec<NetStats>> {
+        let tree = self.db.open_tree(network)?;
+        let mut ret = vec![];
+        for record in tree.range(since.to_be_bytes()..) {
+            let (_, value) = record?;
+            ret.push(deserialize(&value)?);
+        }
+        Ok(ret)
+    }
+
+    /// Remove all samples of the given network taken before `before`
+    pub fn prune(&self, network: &str, before: u64) -> Result<()> {
+        let tree = self.db.open_tree(network)?;
+        let mut removed = 0;
+        for record in tree.range(..before.to_be_bytes()) {
+            let (key, _) = record?;
+            tree.remove(key)?;
+            removed += 1;
+        }
+        debug!(target: "lilith::stats", "Pruned {} samples of \"{}\"", removed, network);
+        Ok(())
+    }
+
+    /// Flush the database to disk
+    pub async fn flush(&self) -> Result<()> {
+        self.db.flush_async().await?;
+        Ok(())
+    }
+
+    /// Sample the network, store the sample and prune the ones older than
+    /// the retention period. Logs a warning on mass disconnect events.
+    pub async fn record(
+        &self,
+        network: &str,
+        p2p: &P2pPtr,
+        known_hosts: &mut Option<HashSet<Url>>,
+        retention: u64,
+    ) -> Result<()> {
+        let previous_hosts = known_hosts.as_ref().map(|h| h.len() as u64).unwrap_or(0);
+        let stats = NetStats::sample(p2p, known_hosts).await;
+
+        if stats.is_mass_disconnect(previous_hosts) {
+            warn!(
+                target: "lilith::stats",
+                "Mass disconnect detected on \"{}\": {} of {} hosts left",
+                network, stats.left, previous_hosts,
+            );
+        }
+
+        self.insert(network, &stats)?;
+        self.prune(network, stats.timestamp.saturating_sub(retention))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample(timestamp: u64) -> NetStats {
+        NetStats {
+            timestamp,
+            whitelist: 20,
+            greylist: 50,
+            goldlist: 3,
+            connected: 12,
+            joined: 2,
+            left: 1,
+            versions: vec![("0.4.1".to_string(), 12)],
+        }
+    }
+
+    #[test]
+    fn stats_store_persistence() {
+        let path = std::env::temp_dir().join(format!("lilith_stats_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&path);
+
+        smol::block_on(async {
+            let store = StatsStore::new(sled::open(&path).unwrap());
+            for timestamp in [100, 200, 300] {
+                store.insert("foo", &sample(timestamp)).unwrap();
+            }
+            store.insert("bar", &sample(400)).unwrap();
+
+            // Queries are bounded by time and scoped to their network
+            let history = store.get_history("foo", 150).unwrap();
+            assert_eq!(history.iter().map(|s| s.timestamp).collect::<Vec<_>>(), vec![200, 300]);
+            assert_eq!(store.get_history("foo", 0).unwrap().len(), 3);
+            assert_eq!(store.get_history("bar", 0).unwrap().len(), 1);
+            assert!(store.get_history("baz", 0).unwrap().is_empty());
+
+            // Pruning drops the samples older than the given timestamp
+            store.prune("foo", 250).unwrap();
+            assert_eq!(store.get_history("foo", 0).unwrap().len(), 1);
+            assert_eq!(store.get_history("bar", 0).unwrap().len(), 1);
+            store.flush().await.unwrap();
+            drop(store);
+
+            // Samples survive reopening the database
+            let store = StatsStore::new(sled::open(&path).unwrap());
+            let history = store.get_history("foo", 0).unwrap();
+            assert_eq!(history.len(), 1);
+            assert_eq!(history[0].timestamp, 300);
+            assert_eq!(history[0].versions, vec![("0.4.1".to_string(), 12)]);
+            assert_eq!(store.get_history("bar", 0).unwrap()[0].timestamp, 400);
+        });
+
+        std::fs::remove_dir_all(&path).unwrap();
+    }
+
+    #[test]
+    fn stats_mass_disconnect() {
+        let mut stats = sample(0);
+        stats.left = 4;
+        assert!(!stats.is_mass_disconnect(0));
+        assert!(!stats.is_mass_disconnect(10));
+        assert!(stats.is_mass_disconnect(8));
+        assert!(stats.is_mass_disconnect(4));
+    }
+}
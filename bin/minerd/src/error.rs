@@ -16,39 +16,21 @@
  * along with this program.  If not, see <https://www.gnu.org/licenses/>.
  */
 
-use darkfi::rpc::jsonrpc::{ErrorCode::ServerError, JsonError, JsonResult};
+use darkfi::{rpc::error_code::MINERD, rpc_error_codes};
 
-/// Custom RPC errors available for minerd.
-/// Please sort them sensefully.
-pub enum RpcError {
-    // Parsing errors
-    TargetParseError = -32101,
-    BlockParseError = -32102,
-
-    // Miner errors
-    MiningFailed = -32201,
-    StopFailed = -32202,
-}
-
-fn to_tuple(e: RpcError) -> (i32, String) {
-    let msg = match e {
+rpc_error_codes! {
+    /// Custom RPC errors available for minerd.
+    /// Please sort them sensefully.
+    pub enum RpcError in MINERD {
         // Parsing errors
-        RpcError::TargetParseError => "Target parse error",
-        RpcError::BlockParseError => "Block parse error",
-        // Miner errors
-        RpcError::MiningFailed => "Mining block failed",
-        RpcError::StopFailed => "Failed to stop previous request",
-    };
+        TargetParseError = -32101 => "Target parse error",
+        BlockParseError = -32102 => "Block parse error",
 
-    (e as i32, msg.to_string())
-}
-
-pub fn server_error(e: RpcError, id: u16, msg: Option<&str>) -> JsonResult {
-    let (code, default_msg) = to_tuple(e);
+        // Miner errors
+        MiningFailed = -32201 => "Mining block failed",
+        StopFailed = -32202 => "Failed to stop previous request",
 
-    if let Some(message) = msg {
-        return JsonError::new(ServerError(code), Some(message.to_string()), id).into()
+        // Solutions log errors
+        SolutionsLogFailed = 21 => "Failed reading solutions log",
     }
-
-    JsonError::new(ServerError(code), Some(default_msg), id).into()
 }
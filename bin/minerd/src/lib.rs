@@ -16,7 +16,10 @@
  * along with this program.  If not, see <https://www.gnu.org/licenses/>.
  */
 
-use std::{collections::HashSet, sync::Arc};
+use std::{
+    collections::HashSet,
+    sync::{atomic::AtomicUsize, Arc},
+};
 
 use log::{error, info};
 use smol::{
@@ -26,6 +29,7 @@ use smol::{
 use url::Url;
 
 use darkfi::{
+    blockchain::HeaderHash,
     rpc::server::{listen_and_serve, RequestHandler},
     system::{ExecutorPtr, StoppableTask, StoppableTaskPtr},
     Error, Result,
@@ -37,6 +41,18 @@ mod error;
 /// JSON-RPC server methods
 mod rpc;
 
+/// Temperature/power aware throttle controller
+pub mod throttle;
+use throttle::{throttle_task, Throttle, ThrottleConfig};
+
+/// Persistent log of submitted solutions
+pub mod solutions;
+use solutions::{reconcile_task, SolutionLog};
+
+/// Mining jobs preemption on darkfid best fork tip changes
+pub mod preempt;
+use preempt::preempt_task;
+
 /// Atomic pointer to the DarkFi mining node
 pub type MinerNodePtr = Arc<MinerNode>;
 
@@ -44,17 +60,46 @@ pub type MinerNodePtr = Arc<MinerNode>;
 pub struct MinerNode {
     /// PoW miner number of threads to use
     threads: usize,
+    /// Amount of PoW miner threads currently allowed to run
+    active_threads: Arc<AtomicUsize>,
+    /// Optional throttle controller
+    throttle: Option<Arc<Throttle>>,
     /// Sender to stop miner threads
     sender: Sender<()>,
     /// Receiver to stop miner threads
     stop_signal: Receiver<()>,
+    /// Lock held by the mining job in progress, so aborts can wait for it to terminate
+    job: Mutex<()>,
+    /// Previous block hash of the mining job in progress
+    job_previous: Mutex<Option<HeaderHash>>,
+    /// Log of the submitted solutions
+    solutions: Arc<SolutionLog>,
     /// JSON-RPC connection tracker
     rpc_connections: Mutex<HashSet<StoppableTaskPtr>>,
 }
 
 impl MinerNode {
-    pub fn new(threads: usize, sender: Sender<()>, stop_signal: Receiver<()>) -> MinerNodePtr {
-        Arc::new(Self { threads, sender, stop_signal, rpc_connections: Mutex::new(HashSet::new()) })
+    pub fn new(
+        threads: usize,
+        throttle: Option<ThrottleConfig>,
+        sender: Sender<()>,
+        stop_signal: Receiver<()>,
+        solutions: SolutionLog,
+    ) -> MinerNodePtr {
+        let active_threads = Arc::new(AtomicUsize::new(threads));
+        let throttle =
+            throttle.map(|config| Arc::new(Throttle::new(config, threads, active_threads.clone())));
+        Arc::new(Self {
+            threads,
+            active_threads,
+            throttle,
+            sender,
+            stop_signal,
+            job: Mutex::new(()),
+            job_previous: Mutex::new(None),
+            solutions: Arc::new(solutions),
+            rpc_connections: Mutex::new(HashSet::new()),
+        })
     }
 }
 
@@ -67,28 +112,61 @@ pub struct Minerd {
     node: MinerNodePtr,
     /// JSON-RPC background task
     rpc_task: StoppableTaskPtr,
+    /// Throttle controller background task
+    throttle_task: StoppableTaskPtr,
+    /// Optional darkfid JSON-RPC endpoint to reconcile solutions against
+    darkfid_endpoint: Option<Url>,
+    /// Solutions reconciliation background task
+    reconcile_task: StoppableTaskPtr,
+    /// Mining jobs preemption background task
+    preempt_task: StoppableTaskPtr,
 }
 
 impl Minerd {
     /// Initialize a DarkFi mining daemon.
     ///
     /// Corresponding communication channels are setup to generate a new `MinerNode`,
-    /// and a new task is generated to handle the JSON-RPC API.
-    pub fn init(threads: usize) -> MinerdPtr {
+    /// and a new task is generated to handle the JSON-RPC API. If a throttle
+    /// configuration is provided, another task is generated to poll the sensors.
+    /// If a darkfid endpoint is provided, another task is generated to reconcile
+    /// the submitted solutions against its confirmed chain, along with a task
+    /// preempting mining jobs when its best fork tip changes.
+    pub fn init(
+        threads: usize,
+        throttle: Option<ThrottleConfig>,
+        solutions: SolutionLog,
+        darkfid_endpoint: Option<Url>,
+    ) -> MinerdPtr {
         info!(target: "minerd::Minerd::init", "Initializing a new mining daemon...");
 
         // Initialize the smol channels to send signal between the threads
         let (sender, stop_signal) = smol::channel::bounded(1);
 
         // Generate the node
-        let node = MinerNode::new(threads, sender, stop_signal);
+        let node = MinerNode::new(threads, throttle, sender, stop_signal, solutions);
 
         // Generate the JSON-RPC task
         let rpc_task = StoppableTask::new();
 
+        // Generate the throttle controller task
+        let throttle_task = StoppableTask::new();
+
+        // Generate the solutions reconciliation task
+        let reconcile_task = StoppableTask::new();
+
+        // Generate the mining jobs preemption task
+        let preempt_task = StoppableTask::new();
+
         info!(target: "minerd::Minerd::init", "Mining daemon initialized successfully!");
 
-        Arc::new(Self { node, rpc_task })
+        Arc::new(Self {
+            node,
+            rpc_task,
+            throttle_task,
+            darkfid_endpoint,
+            reconcile_task,
+            preempt_task,
+        })
     }
 
     /// Start the DarkFi mining daemon in the given executor, using the provided JSON-RPC listen url.
@@ -109,6 +187,49 @@ impl Minerd {
             executor.clone(),
         );
 
+        // Start the throttle controller task
+        if let Some(throttle) = &self.node.throttle {
+            self.throttle_task.clone().start(
+                throttle_task(throttle.clone()),
+                |res| async {
+                    match res {
+                        Ok(()) | Err(Error::DetachedTaskStopped) => { /* Do nothing */ }
+                        Err(e) => error!(target: "minerd::Minerd::start", "Failed starting throttle controller task: {}", e),
+                    }
+                },
+                Error::DetachedTaskStopped,
+                executor.clone(),
+            );
+        }
+
+        // Start the solutions reconciliation task
+        if let Some(endpoint) = &self.darkfid_endpoint {
+            self.reconcile_task.clone().start(
+                reconcile_task(self.node.solutions.clone(), endpoint.clone(), executor.clone()),
+                |res| async {
+                    match res {
+                        Ok(()) | Err(Error::DetachedTaskStopped) => { /* Do nothing */ }
+                        Err(e) => error!(target: "minerd::Minerd::start", "Failed starting solutions reconciliation task: {}", e),
+                    }
+                },
+                Error::DetachedTaskStopped,
+                executor.clone(),
+            );
+
+            // Start the mining jobs preemption task
+            self.preempt_task.clone().start(
+                preempt_task(self.node.clone(), endpoint.clone(), executor.clone()),
+                |res| async {
+                    match res {
+                        Ok(()) | Err(Error::DetachedTaskStopped) => { /* Do nothing */ }
+                        Err(e) => error!(target: "minerd::Minerd::start", "Failed starting mining jobs preemption task: {}", e),
+                    }
+                },
+                Error::DetachedTaskStopped,
+                executor.clone(),
+            );
+        }
+
         info!(target: "minerd::Minerd::start", "Mining daemon started successfully!");
     }
 
@@ -124,6 +245,21 @@ impl Minerd {
         info!(target: "minerd::Minerd::stop", "Stopping JSON-RPC server...");
         self.rpc_task.stop().await;
 
+        // Stop the throttle controller task
+        if self.node.throttle.is_some() {
+            info!(target: "minerd::Minerd::stop", "Stopping throttle controller...");
+            self.throttle_task.stop().await;
+        }
+
+        // Stop the solutions reconciliation task
+        if self.darkfid_endpoint.is_some() {
+            info!(target: "minerd::Minerd::stop", "Stopping solutions reconciliation task...");
+            self.reconcile_task.stop().await;
+
+            info!(target: "minerd::Minerd::stop", "Stopping mining jobs preemption task...");
+            self.preempt_task.stop().await;
+        }
+
         // Consume channel item so its empty again
         if self.node.stop_signal.is_full() {
             self.node.stop_signal.recv().await?;
@@ -167,9 +303,9 @@ fn minerd_programmatic_control() -> Result<()> {
     let (signal, shutdown) = smol::channel::unbounded::<()>();
 
     // Generate a dummy mining job
-    let target = darkfi::rpc::util::JsonValue::String(
-        num_bigint::BigUint::from_bytes_be(&[0xFF; 32]).to_string(),
-    );
+    let target = darkfi::rpc::util::JsonValue::String(darkfi::util::encoding::base64::encode(
+        &darkfi_serial::serialize(&num_bigint::BigUint::from_bytes_be(&[0xFF; 32])),
+    ));
     let block = darkfi::rpc::util::JsonValue::String(darkfi::util::encoding::base64::encode(
         &darkfi_serial::serialize(&darkfi::blockchain::BlockInfo::default()),
     ));
@@ -183,7 +319,9 @@ fn minerd_programmatic_control() -> Result<()> {
         .finish(|| {
             smol::block_on(async {
                 // Initialize a daemon
-                let daemon = Minerd::init(threads);
+                let sled_db = sled_overlay::sled::Config::new().temporary(true).open().unwrap();
+                let solutions = SolutionLog::new(&sled_db).unwrap();
+                let daemon = Minerd::init(threads, None, solutions, None);
 
                 // Start it
                 daemon.start(&ex, &rpc_listen);
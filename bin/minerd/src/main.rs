@@ -23,9 +23,9 @@ use smol::{stream::StreamExt, Executor};
 use structopt_toml::{serde::Deserialize, structopt::StructOpt, StructOptToml};
 use url::Url;
 
-use darkfi::{async_daemonize, cli_desc, Result};
+use darkfi::{async_daemonize, cli_desc, util::path::expand_path, Result};
 
-use minerd::Minerd;
+use minerd::{solutions::SolutionLog, throttle::ThrottleConfig, Minerd};
 
 const CONFIG_FILE: &str = "minerd.toml";
 const CONFIG_FILE_CONTENTS: &str = include_str!("../minerd.toml");
@@ -46,6 +46,42 @@ struct Args {
     /// PoW miner number of threads to use
     threads: usize,
 
+    #[structopt(long, default_value = "~/.local/share/darkfi/minerd")]
+    /// Path to the database keeping the submitted solutions log
+    database: String,
+
+    #[structopt(long)]
+    /// darkfid JSON-RPC endpoint to reconcile submitted solutions against and follow tips of
+    darkfid_endpoint: Option<Url>,
+
+    #[structopt(long)]
+    /// hwmon temperature input paths to monitor for throttling (repeatable flag)
+    hwmon_temp: Vec<String>,
+
+    #[structopt(long)]
+    /// hwmon power input paths to monitor for throttling (repeatable flag)
+    hwmon_power: Vec<String>,
+
+    #[structopt(long, default_value = "85")]
+    /// Temperature (°C) over which mining threads get reduced
+    throttle_temp: f64,
+
+    #[structopt(long, default_value = "95")]
+    /// Temperature (°C) over which mining gets paused
+    pause_temp: f64,
+
+    #[structopt(long, default_value = "75")]
+    /// Temperature (°C) under which mining threads get restored
+    resume_temp: f64,
+
+    #[structopt(long)]
+    /// Total power (W) over which mining threads get reduced
+    max_power: Option<f64>,
+
+    #[structopt(long, default_value = "5")]
+    /// Throttle controller sensors polling interval, in seconds
+    throttle_interval: u64,
+
     #[structopt(short, long)]
     /// Set log file to ouput into
     log: Option<String>,
@@ -58,7 +94,28 @@ struct Args {
 async_daemonize!(realmain);
 async fn realmain(args: Args, ex: Arc<Executor<'static>>) -> Result<()> {
     info!(target: "minerd", "Starting DarkFi Mining Daemon...");
-    let daemon = Minerd::init(args.threads);
+
+    // Parse the throttle configuration, if hwmon paths were provided
+    let throttle = if args.hwmon_temp.is_empty() && args.hwmon_power.is_empty() {
+        None
+    } else {
+        Some(ThrottleConfig::new(
+            &args.hwmon_temp,
+            &args.hwmon_power,
+            args.throttle_temp,
+            args.pause_temp,
+            args.resume_temp,
+            args.max_power,
+            args.throttle_interval,
+        )?)
+    };
+
+    // Open the submitted solutions log
+    let db_path = expand_path(&args.database)?;
+    let sled_db = sled_overlay::sled::open(&db_path)?;
+    let solutions = SolutionLog::new(&sled_db)?;
+
+    let daemon = Minerd::init(args.threads, throttle, solutions, args.darkfid_endpoint);
     daemon.start(&ex, &args.rpc_listen);
 
     // Signal handling for graceful termination.
@@ -68,6 +125,10 @@ async fn realmain(args: Args, ex: Arc<Executor<'static>>) -> Result<()> {
 
     daemon.stop().await?;
 
+    // Flush sled database data
+    let flushed_bytes = sled_db.flush_async().await?;
+    info!(target: "minerd", "Flushed {} bytes", flushed_bytes);
+
     info!(target: "minerd", "Shut down successfully");
     Ok(())
 }
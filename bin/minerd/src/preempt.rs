@@ -0,0 +1,260 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2025 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use std::str::FromStr;
+
+use log::{debug, error, info};
+use url::Url;
+
+use darkfi::{
+    blockchain::HeaderHash,
+    rpc::{
+        client::RpcClient,
+        jsonrpc::{JsonNotification, JsonRequest, JsonResult},
+        util::JsonValue,
+    },
+    system::{sleep, ExecutorPtr, Publisher},
+    Error, Result,
+};
+
+use crate::MinerNodePtr;
+
+/// Interval in seconds to wait before resubscribing to darkfid
+pub const RESUBSCRIBE_INTERVAL: u64 = 10;
+
+/// Auxiliary function to parse the best fork tip hash out of a
+/// `blockchain.subscribe_tips` notification.
+fn parse_tip(notification: &JsonNotification) -> Result<HeaderHash> {
+    let Some(params) = notification.params.get::<Vec<JsonValue>>() else {
+        return Err(Error::UnexpectedJsonRpc("Tip notification params are not an array".into()))
+    };
+    let Some(hash) = params.first().and_then(|h| h.get::<String>()) else {
+        return Err(Error::UnexpectedJsonRpc("Tip notification is missing the hash".into()))
+    };
+    HeaderHash::from_str(hash)
+}
+
+/// Async task subscribing to the best fork tip changes of darkfid at
+/// provided endpoint, preempting the mining job in progress as soon
+/// as it doesn't extend the new tip, instead of waiting for darkfid to
+/// request the next one.
+pub async fn preempt_task(node: MinerNodePtr, endpoint: Url, executor: ExecutorPtr) -> Result<()> {
+    loop {
+        let client = match RpcClient::new(endpoint.clone(), executor.clone()).await {
+            Ok(client) => client,
+            Err(e) => {
+                error!(
+                    target: "minerd::preempt::preempt_task",
+                    "Failed connecting to darkfid at {}: {}", endpoint, e,
+                );
+                sleep(RESUBSCRIBE_INTERVAL).await;
+                continue
+            }
+        };
+
+        let publisher = Publisher::new();
+        let subscription = publisher.clone().subscribe().await;
+        let req = JsonRequest::new("blockchain.subscribe_tips", JsonValue::Array(vec![]));
+        info!(target: "minerd::preempt::preempt_task", "Subscribed to darkfid best fork tips");
+
+        let listener = async {
+            loop {
+                let JsonResult::Notification(notification) = subscription.receive().await else {
+                    continue
+                };
+                match parse_tip(&notification) {
+                    Ok(tip) => {
+                        if node.preempt(&tip).await {
+                            debug!(target: "minerd::preempt::preempt_task", "Preempted stale mining job");
+                        }
+                    }
+                    Err(e) => error!(
+                        target: "minerd::preempt::preempt_task",
+                        "Received invalid tip notification: {}", e,
+                    ),
+                }
+            }
+        };
+        if let Err(e) = smol::future::or(client.subscribe(req, publisher), listener).await {
+            error!(target: "minerd::preempt::preempt_task", "Tips subscription failed: {}", e);
+        }
+
+        subscription.unsubscribe().await;
+        client.stop().await;
+        sleep(RESUBSCRIBE_INTERVAL).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::HashSet, sync::Arc};
+
+    use darkfi::{
+        rpc::{
+            jsonrpc::{ErrorCode, JsonError, JsonSubscriber},
+            server::{listen_and_serve, RequestHandler},
+        },
+        system::{msleep, StoppableTask, StoppableTaskPtr},
+    };
+    use darkfi_serial::async_trait;
+    use sled_overlay::sled;
+    use smol::{
+        lock::{Mutex, MutexGuard},
+        net::TcpListener,
+        Executor,
+    };
+
+    use super::*;
+    use crate::{solutions::SolutionLog, MinerNode};
+
+    fn hash(n: u8) -> HeaderHash {
+        HeaderHash::new([n; 32])
+    }
+
+    fn test_node() -> MinerNodePtr {
+        let sled_db = sled::Config::new().temporary(true).open().unwrap();
+        let (sender, stop_signal) = smol::channel::bounded(1);
+        MinerNode::new(1, None, sender, stop_signal, SolutionLog::new(&sled_db).unwrap())
+    }
+
+    /// Auxiliary function simulating a mining job extending `previous`,
+    /// running until the stop signal is received.
+    async fn fake_job(node: &MinerNode, previous: HeaderHash) {
+        let job = node.job.lock().await;
+        *node.job_previous.lock().await = Some(previous);
+        while !node.stop_signal.is_full() {
+            msleep(10).await;
+        }
+        *node.job_previous.lock().await = None;
+        drop(job);
+    }
+
+    /// Auxiliary function to wait until a mining job is in progress
+    async fn wait_job(node: &MinerNode) {
+        while node.job_previous.lock().await.is_none() {
+            msleep(10).await;
+        }
+    }
+
+    #[test]
+    fn minerd_preempt() {
+        smol::block_on(async {
+            let node = test_node();
+
+            // Nothing to preempt without a job in progress
+            assert!(!node.preempt(&hash(1)).await);
+
+            let checks = async {
+                wait_job(&node).await;
+
+                // A job extending the tip keeps running
+                assert!(!node.preempt(&hash(1)).await);
+
+                // A stale one gets preempted
+                assert!(node.preempt(&hash(2)).await);
+            };
+            smol::future::zip(fake_job(&node, hash(1)), checks).await;
+
+            // The stop signal got consumed
+            assert!(!node.stop_signal.is_full());
+        });
+    }
+
+    #[test]
+    fn minerd_parse_tip() {
+        let tip = |params| JsonNotification::new("blockchain.subscribe_tips", params);
+        let params =
+            JsonValue::Array(vec![JsonValue::String(hash(1).to_string()), JsonValue::Number(1.0)]);
+        assert_eq!(parse_tip(&tip(params)).unwrap(), hash(1));
+        assert!(parse_tip(&tip(JsonValue::Array(vec![]))).is_err());
+        let params = JsonValue::Array(vec![JsonValue::String("foo".to_string())]);
+        assert!(parse_tip(&tip(params)).is_err());
+    }
+
+    /// Minimal darkfid serving best fork tip notifications
+    struct Darkfid {
+        tips: JsonSubscriber,
+        rpc_connections: Mutex<HashSet<StoppableTaskPtr>>,
+    }
+
+    #[async_trait]
+    impl RequestHandler<()> for Darkfid {
+        async fn handle_request(&self, req: JsonRequest) -> JsonResult {
+            match req.method.as_str() {
+                "blockchain.subscribe_tips" => self.tips.clone().into(),
+                _ => JsonError::new(ErrorCode::MethodNotFound, None, req.id).into(),
+            }
+        }
+
+        async fn connections_mut(&self) -> MutexGuard<'life0, HashSet<StoppableTaskPtr>> {
+            self.rpc_connections.lock().await
+        }
+    }
+
+    #[test]
+    fn minerd_preempt_task() -> Result<()> {
+        let ex = Arc::new(Executor::new());
+
+        smol::block_on(ex.run(async {
+            // Find an available port
+            let listener = TcpListener::bind("127.0.0.1:0").await?;
+            let port = listener.local_addr()?.port();
+            let endpoint = Url::parse(&format!("tcp://127.0.0.1:{port}"))?;
+            drop(listener);
+
+            // Start darkfid
+            let darkfid = Arc::new(Darkfid {
+                tips: JsonSubscriber::new("blockchain.subscribe_tips"),
+                rpc_connections: Mutex::new(HashSet::new()),
+            });
+            let server_task = StoppableTask::new();
+            server_task.clone().start(
+                listen_and_serve(endpoint.clone(), darkfid.clone(), None, ex.clone()),
+                |_| async { /* Do nothing */ },
+                Error::RpcServerStopped,
+                ex.clone(),
+            );
+
+            // Start the preemption task and wait for it to subscribe
+            let node = test_node();
+            let task = StoppableTask::new();
+            task.clone().start(
+                preempt_task(node.clone(), endpoint, ex.clone()),
+                |_| async { /* Do nothing */ },
+                Error::DetachedTaskStopped,
+                ex.clone(),
+            );
+            while darkfid.tips.publisher.subscriptions().await == 0 {
+                msleep(10).await;
+            }
+
+            // Pushing a new tip ends the stale job right away
+            let notify = async {
+                wait_job(&node).await;
+                let params = vec![JsonValue::String(hash(2).to_string()), JsonValue::Number(1.0)];
+                darkfid.tips.notify(JsonValue::Array(params)).await;
+            };
+            smol::future::zip(fake_job(&node, hash(1)), notify).await;
+            assert!(node.job_previous.lock().await.is_none());
+
+            task.stop().await;
+            server_task.stop().await;
+            Ok(())
+        }))
+    }
+}
@@ -16,30 +16,27 @@
  * along with this program.  If not, see <https://www.gnu.org/licenses/>.
  */
 
-use std::collections::HashSet;
+use std::{collections::HashSet, sync::atomic::Ordering};
 
 use log::{debug, error, info};
 use num_bigint::BigUint;
 use smol::lock::MutexGuard;
 
 use darkfi::{
-    blockchain::BlockInfo,
+    blockchain::{BlockInfo, HeaderHash},
     rpc::{
         jsonrpc::{ErrorCode, JsonError, JsonRequest, JsonResponse, JsonResult},
         server::RequestHandler,
         util::JsonValue,
     },
-    system::{sleep, StoppableTaskPtr},
+    rpc_error,
+    system::{trace, StoppableTaskPtr},
     util::encoding::base64,
-    validator::pow::mine_block,
+    validator::pow::mine_block_throttled,
 };
-use darkfi_sdk::num_traits::Num;
 use darkfi_serial::{async_trait, deserialize_async};
 
-use crate::{
-    error::{server_error, RpcError},
-    MinerNode,
-};
+use crate::{error::RpcError, solutions::Solution, MinerNode};
 
 #[async_trait]
 impl RequestHandler<()> for MinerNode {
@@ -50,10 +47,18 @@ impl RequestHandler<()> for MinerNode {
             "ping" => self.pong(req.id, req.params).await,
             "abort" => self.abort(req.id, req.params).await,
             "mine" => self.mine(req.id, req.params).await,
+            "stats" => self.stats(req.id, req.params).await,
+            "stats.rewards" => self.stats_rewards(req.id, req.params).await,
             _ => JsonError::new(ErrorCode::MethodNotFound, None, req.id).into(),
         }
     }
 
+    // Always trace, so mining jobs requested by a traced darkfid
+    // request get logged under its correlation ID.
+    fn tracing(&self) -> bool {
+        true
+    }
+
     async fn connections_mut(&self) -> MutexGuard<'life0, HashSet<StoppableTaskPtr>> {
         self.rpc_connections.lock().await
     }
@@ -75,8 +80,11 @@ impl MinerNode {
 
     // RPCAPI:
     // Mine provided block for requested mine target, and return the corresponding nonce value.
+    // Optionally takes the block's mining reward recipient address, which gets recorded
+    // in the submitted solutions log. The target and the block are both provided
+    // as base64-encoded serializations.
     //
-    // --> {"jsonrpc": "2.0", "method": "mine", "params": ["target", "block"], "id": 42}
+    // --> {"jsonrpc": "2.0", "method": "mine", "params": ["target", "block", "recipient"], "id": 42}
     // --> {"jsonrpc": "2.0", "result": "nonce", "id": 42}
     async fn mine(&self, id: u16, params: JsonValue) -> JsonResult {
         // Verify parameters
@@ -84,42 +92,123 @@ impl MinerNode {
             return JsonError::new(ErrorCode::InvalidParams, None, id).into()
         }
         let params = params.get::<Vec<JsonValue>>().unwrap();
-        if params.len() != 2 || !params[0].is_string() || !params[1].is_string() {
+        if !(2..=3).contains(&params.len()) || !params.iter().all(|p| p.is_string()) {
             return JsonError::new(ErrorCode::InvalidParams, None, id).into()
         }
 
         // Parse parameters
-        let Ok(target) = BigUint::from_str_radix(params[0].get::<String>().unwrap(), 10) else {
+        let Some(target_bytes) = base64::decode(params[0].get::<String>().unwrap()) else {
+            error!(target: "minerd::rpc", "Failed to parse target bytes");
+            return rpc_error!(RpcError::TargetParseError, id)
+        };
+        let Ok(target) = deserialize_async::<BigUint>(&target_bytes).await else {
             error!(target: "minerd::rpc", "Failed to parse target");
-            return server_error(RpcError::TargetParseError, id, None)
+            return rpc_error!(RpcError::TargetParseError, id)
         };
         let Some(block_bytes) = base64::decode(params[1].get::<String>().unwrap()) else {
             error!(target: "minerd::rpc", "Failed to parse block bytes");
-            return server_error(RpcError::BlockParseError, id, None)
+            return rpc_error!(RpcError::BlockParseError, id)
         };
         let Ok(mut block) = deserialize_async::<BlockInfo>(&block_bytes).await else {
             error!(target: "minerd::rpc", "Failed to parse block");
-            return server_error(RpcError::BlockParseError, id, None)
+            return rpc_error!(RpcError::BlockParseError, id)
         };
         let block_hash = block.hash();
-        info!(target: "minerd::rpc", "Received request to mine block {} for target: {}", block_hash, target);
+        let recipient = params.get(2).map(|r| r.get::<String>().unwrap().clone());
+        let prefix = trace::log_prefix();
+        info!(target: "minerd::rpc", "{}Received request to mine block {} for target: {}", prefix, block_hash, target);
 
         // Check if another request is being processed
         if let Some(e) = self.abort_pending(id).await {
             return e
         };
 
-        // Mine provided block
-        info!(target: "minerd::rpc", "Mining block {} for target: {}", block_hash, target);
-        if let Err(e) = mine_block(&target, &mut block, self.threads, &self.stop_signal.clone()) {
-            error!(target: "minerd::rpc", "Failed mining block {} with error: {}", block_hash, e);
-            return server_error(RpcError::MiningFailed, id, None)
+        // Mine provided block, holding the job lock until we are done
+        info!(target: "minerd::rpc", "{}Mining block {} for target: {}", prefix, block_hash, target);
+        let job = self.job.lock().await;
+        *self.job_previous.lock().await = Some(block.header.previous);
+        let result = mine_block_throttled(
+            &target,
+            &mut block,
+            self.threads,
+            &self.stop_signal.clone(),
+            &self.active_threads,
+        );
+        *self.job_previous.lock().await = None;
+        drop(job);
+        if let Err(e) = result {
+            error!(target: "minerd::rpc", "{}Failed mining block {} with error: {}", prefix, block_hash, e);
+            return rpc_error!(RpcError::MiningFailed, id)
+        }
+
+        // Record the solution in the log
+        let solution = Solution::new(block.header.height, block_hash, block.hash(), recipient);
+        if let Err(e) = self.solutions.insert(&solution) {
+            error!(target: "minerd::rpc", "{}Failed recording solution {}: {}", prefix, solution.block_hash, e);
         }
 
         // Return block nonce
         JsonResponse::new(JsonValue::Number(block.header.nonce as f64), id).into()
     }
 
+    // RPCAPI:
+    // Returns the miner daemon threads status, along with the throttle
+    // controller sensors readings, if its enabled.
+    //
+    // --> {"jsonrpc": "2.0", "method": "stats", "params": [], "id": 42}
+    // <-- {"jsonrpc": "2.0", "result": {"threads": 4, "active_threads": 2, "throttled": true, "throttle": {...}}, "id": 42}
+    async fn stats(&self, id: u16, params: JsonValue) -> JsonResult {
+        let Some(params) = params.get::<Vec<JsonValue>>() else {
+            return JsonError::new(ErrorCode::InvalidParams, None, id).into()
+        };
+        if !params.is_empty() {
+            return JsonError::new(ErrorCode::InvalidParams, None, id).into()
+        }
+
+        let active_threads = self.active_threads.load(Ordering::Relaxed);
+        let throttle = match &self.throttle {
+            Some(throttle) => throttle.status().await,
+            None => JsonValue::Null,
+        };
+
+        let stats = JsonValue::Object(
+            [
+                ("threads".to_string(), JsonValue::Number(self.threads as f64)),
+                ("active_threads".to_string(), JsonValue::Number(active_threads as f64)),
+                ("throttled".to_string(), JsonValue::Boolean(active_threads < self.threads)),
+                ("throttle".to_string(), throttle),
+            ]
+            .into(),
+        );
+
+        JsonResponse::new(stats, id).into()
+    }
+
+    // RPCAPI:
+    // Returns the submitted solutions log, along with the totals of found,
+    // accepted, rejected and pending solutions. Solutions get reconciled
+    // against the confirmed chain when a darkfid endpoint is configured,
+    // so solo miners can audit whether their found blocks paid out.
+    //
+    // --> {"jsonrpc": "2.0", "method": "stats.rewards", "params": [], "id": 42}
+    // <-- {"jsonrpc": "2.0", "result": {"found": 2, "accepted": 1, "rejected": 0, "pending": 1, "solutions": [...]}, "id": 42}
+    async fn stats_rewards(&self, id: u16, params: JsonValue) -> JsonResult {
+        let Some(params) = params.get::<Vec<JsonValue>>() else {
+            return JsonError::new(ErrorCode::InvalidParams, None, id).into()
+        };
+        if !params.is_empty() {
+            return JsonError::new(ErrorCode::InvalidParams, None, id).into()
+        }
+
+        match self.solutions.summary() {
+            Ok(summary) => JsonResponse::new(summary, id).into(),
+            Err(e) => {
+                error!(target: "minerd::rpc", "Failed reading solutions log: {}", e);
+                rpc_error!(RpcError::SolutionsLogFailed, id)
+            }
+        }
+    }
+
     /// Auxiliary function to abort pending request.
     async fn abort_pending(&self, id: u16) -> Option<JsonResult> {
         // Check if a pending request is being processed
@@ -133,22 +222,33 @@ impl MinerNode {
         // Send stop signal to worker
         if self.sender.send(()).await.is_err() {
             error!(target: "minerd::rpc", "Failed to stop pending request");
-            return Some(server_error(RpcError::StopFailed, id, None))
+            return Some(rpc_error!(RpcError::StopFailed, id))
         }
 
-        // Wait for worker to terminate
+        // Wait for worker to terminate and release the job lock
         info!(target: "minerd::rpc", "Waiting for request to terminate...");
-        while self.stop_signal.receiver_count() > 1 {
-            sleep(1).await;
-        }
+        drop(self.job.lock().await);
         info!(target: "minerd::rpc", "Pending request terminated!");
 
         // Consume channel item so its empty again
         if self.stop_signal.recv().await.is_err() {
             error!(target: "minerd::rpc", "Failed to cleanup stop signal channel");
-            return Some(server_error(RpcError::StopFailed, id, None))
+            return Some(rpc_error!(RpcError::StopFailed, id))
         }
 
         None
     }
+
+    /// Auxiliary function to abort the mining job in progress, if it
+    /// doesn't extend provided best fork tip anymore.
+    /// Returns `true` if a job got preempted.
+    pub(crate) async fn preempt(&self, tip: &HeaderHash) -> bool {
+        match *self.job_previous.lock().await {
+            Some(previous) if previous != *tip => {}
+            _ => return false,
+        }
+
+        info!(target: "minerd::rpc", "Best fork tip changed to {}, preempting mining job", tip);
+        self.abort_pending(0).await.is_none()
+    }
 }
@@ -0,0 +1,380 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2025 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use std::{future::Future, sync::Arc};
+
+use log::{debug, error, info};
+use sled_overlay::sled;
+use url::Url;
+
+use darkfi::{
+    blockchain::{BlockInfo, HeaderHash},
+    rpc::{client::RpcClient, jsonrpc::JsonRequest, util::JsonValue},
+    system::{sleep, ExecutorPtr},
+    util::{encoding::base64, time::Timestamp},
+    Error, Result,
+};
+use darkfi_serial::{async_trait, deserialize, serialize, SerialDecodable, SerialEncodable};
+
+/// Sled tree containing the submitted solutions log
+const SLED_SOLUTIONS_TREE: &[u8] = b"_solutions";
+
+/// Interval in seconds between solutions reconciliation runs
+pub const RECONCILE_INTERVAL: u64 = 60;
+
+/// Depth below the last confirmed block within which decided solutions
+/// get re-checked, so they follow any late chain reorganization.
+pub const RECONCILE_DEPTH: u32 = 100;
+
+/// Outcome of a submitted solution
+#[derive(Copy, Clone, Debug, PartialEq, Eq, SerialEncodable, SerialDecodable)]
+pub enum SolutionStatus {
+    /// Its height has not been confirmed yet
+    Pending,
+    /// The confirmed chain contains its block
+    Accepted,
+    /// The confirmed chain contains another block in its height
+    Rejected,
+}
+
+impl SolutionStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Pending => "pending",
+            Self::Accepted => "accepted",
+            Self::Rejected => "rejected",
+        }
+    }
+}
+
+/// A block solution found by the miner and returned to darkfid
+#[derive(Clone, Debug, PartialEq, SerialEncodable, SerialDecodable)]
+pub struct Solution {
+    /// Height of the mined block
+    pub height: u32,
+    /// Hash of the block template, as received
+    pub template_hash: HeaderHash,
+    /// Hash of the mined block
+    pub block_hash: HeaderHash,
+    /// Mining reward recipient address, if darkfid provided it
+    pub recipient: Option<String>,
+    /// Time the solution was found
+    pub timestamp: Timestamp,
+    /// Outcome of the solution
+    pub status: SolutionStatus,
+}
+
+impl Solution {
+    /// Create a new pending solution record.
+    pub fn new(
+        height: u32,
+        template_hash: HeaderHash,
+        block_hash: HeaderHash,
+        recipient: Option<String>,
+    ) -> Self {
+        Self {
+            height,
+            template_hash,
+            block_hash,
+            recipient,
+            timestamp: Timestamp::current_time(),
+            status: SolutionStatus::Pending,
+        }
+    }
+
+    /// Sled key of the solution, ordering records by height.
+    fn key(&self) -> Vec<u8> {
+        let mut key = self.height.to_be_bytes().to_vec();
+        key.extend_from_slice(self.block_hash.inner());
+        key
+    }
+
+    /// Export the solution as a JSON object.
+    pub fn to_json(&self) -> JsonValue {
+        JsonValue::Object(
+            [
+                ("height".to_string(), JsonValue::Number(self.height as f64)),
+                ("template_hash".to_string(), JsonValue::String(self.template_hash.to_string())),
+                ("block_hash".to_string(), JsonValue::String(self.block_hash.to_string())),
+                (
+                    "recipient".to_string(),
+                    match &self.recipient {
+                        Some(r) => JsonValue::String(r.clone()),
+                        None => JsonValue::Null,
+                    },
+                ),
+                ("timestamp".to_string(), JsonValue::Number(self.timestamp.inner() as f64)),
+                ("status".to_string(), JsonValue::String(self.status.as_str().to_string())),
+            ]
+            .into(),
+        )
+    }
+}
+
+/// Persistent log of the submitted solutions
+pub struct SolutionLog {
+    /// Sled tree containing the solutions, keyed by height and block hash
+    tree: sled::Tree,
+}
+
+impl SolutionLog {
+    /// Open the solutions log in provided sled database.
+    pub fn new(sled_db: &sled::Db) -> Result<Self> {
+        let tree = sled_db.open_tree(SLED_SOLUTIONS_TREE)?;
+        Ok(Self { tree })
+    }
+
+    /// Insert or update a solution record.
+    pub fn insert(&self, solution: &Solution) -> Result<()> {
+        self.tree.insert(solution.key(), serialize(solution))?;
+        Ok(())
+    }
+
+    /// Retrieve all solution records, ordered by height.
+    pub fn get_all(&self) -> Result<Vec<Solution>> {
+        let mut solutions = vec![];
+        for record in self.tree.iter() {
+            let (_, solution) = record?;
+            solutions.push(deserialize(&solution)?);
+        }
+        Ok(solutions)
+    }
+
+    /// Export the solution records along with their outcome totals
+    /// as a JSON object.
+    pub fn summary(&self) -> Result<JsonValue> {
+        let solutions = self.get_all()?;
+        let count = |status| solutions.iter().filter(|s| s.status == status).count() as f64;
+        Ok(JsonValue::Object(
+            [
+                ("found".to_string(), JsonValue::Number(solutions.len() as f64)),
+                ("accepted".to_string(), JsonValue::Number(count(SolutionStatus::Accepted))),
+                ("rejected".to_string(), JsonValue::Number(count(SolutionStatus::Rejected))),
+                ("pending".to_string(), JsonValue::Number(count(SolutionStatus::Pending))),
+                (
+                    "solutions".to_string(),
+                    JsonValue::Array(solutions.iter().map(|s| s.to_json()).collect()),
+                ),
+            ]
+            .into(),
+        ))
+    }
+
+    /// Reconcile the solution records against the confirmed chain of
+    /// provided darkfid client. Solutions above the last confirmed block
+    /// are pending, while the rest get accepted if the confirmed block in
+    /// their height is theirs, or rejected otherwise. Returns the number
+    /// of records whose status changed.
+    pub async fn reconcile(&self, client: &RpcClient) -> Result<usize> {
+        let req = JsonRequest::new("blockchain.last_confirmed_block", JsonValue::Array(vec![]));
+        let rep = client.request(req).await?;
+        let Some(Some(last_confirmed)) = rep.get::<Vec<JsonValue>>().map(|r| r.first()) else {
+            return Err(Error::UnexpectedJsonRpc("Invalid last confirmed block".to_string()))
+        };
+        let Some(last_confirmed) = last_confirmed.get::<f64>().map(|h| *h as u32) else {
+            return Err(Error::UnexpectedJsonRpc("Invalid last confirmed block".to_string()))
+        };
+
+        self.reconcile_against(last_confirmed, |height| async move {
+            Ok(confirmed_block(client, height).await?.hash())
+        })
+        .await
+    }
+
+    /// Reconcile the solution records against a confirmed chain ending
+    /// at `last_confirmed`, using `confirmed_hash` to retrieve the hash
+    /// of the confirmed block in a given height. Returns the number of
+    /// records whose status changed.
+    pub async fn reconcile_against<F, Fut>(
+        &self,
+        last_confirmed: u32,
+        confirmed_hash: F,
+    ) -> Result<usize>
+    where
+        F: Fn(u32) -> Fut,
+        Fut: Future<Output = Result<HeaderHash>>,
+    {
+        let mut changed = 0;
+        for mut solution in self.get_all()? {
+            let status = if solution.height > last_confirmed {
+                SolutionStatus::Pending
+            } else if solution.status == SolutionStatus::Pending ||
+                solution.height + RECONCILE_DEPTH > last_confirmed
+            {
+                if confirmed_hash(solution.height).await? == solution.block_hash {
+                    SolutionStatus::Accepted
+                } else {
+                    SolutionStatus::Rejected
+                }
+            } else {
+                continue
+            };
+
+            if status != solution.status {
+                info!(
+                    target: "minerd::solutions::reconcile",
+                    "Solution {} for height {} is now {}",
+                    solution.block_hash, solution.height, status.as_str(),
+                );
+                solution.status = status;
+                self.insert(&solution)?;
+                changed += 1;
+            }
+        }
+
+        Ok(changed)
+    }
+}
+
+/// Auxiliary function to retrieve the confirmed block of provided height from darkfid.
+async fn confirmed_block(client: &RpcClient, height: u32) -> Result<BlockInfo> {
+    let req = JsonRequest::new(
+        "blockchain.get_block",
+        JsonValue::Array(vec![JsonValue::String(height.to_string())]),
+    );
+    let rep = client.request(req).await?;
+    let Some(bytes) = rep.get::<String>().and_then(|b| base64::decode(b)) else {
+        return Err(Error::UnexpectedJsonRpc(format!("Invalid block for height {height}")))
+    };
+    Ok(deserialize(&bytes)?)
+}
+
+/// Async task periodically reconciling the solutions log against the
+/// confirmed chain of darkfid at provided endpoint.
+pub async fn reconcile_task(
+    solutions: Arc<SolutionLog>,
+    endpoint: Url,
+    executor: ExecutorPtr,
+) -> Result<()> {
+    loop {
+        match RpcClient::new(endpoint.clone(), executor.clone()).await {
+            Ok(client) => {
+                match solutions.reconcile(&client).await {
+                    Ok(changed) => debug!(
+                        target: "minerd::solutions::reconcile_task",
+                        "Reconciled solutions log, {} records changed", changed,
+                    ),
+                    Err(e) => error!(
+                        target: "minerd::solutions::reconcile_task",
+                        "Failed reconciling solutions log: {}", e,
+                    ),
+                }
+                client.stop().await;
+            }
+            Err(e) => error!(
+                target: "minerd::solutions::reconcile_task",
+                "Failed connecting to darkfid at {}: {}", endpoint, e,
+            ),
+        }
+
+        sleep(RECONCILE_INTERVAL).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+
+    fn hash(n: u8) -> HeaderHash {
+        HeaderHash::new([n; 32])
+    }
+
+    #[test]
+    fn solutions_reconcile() {
+        smol::block_on(async {
+            let sled_db = sled::Config::new().temporary(true).open().unwrap();
+            let log = SolutionLog::new(&sled_db).unwrap();
+
+            // Solutions for heights 1 to 3, where we lose height 2
+            for height in 1..=3 {
+                log.insert(&Solution::new(height, hash(0), hash(height as u8), None)).unwrap();
+            }
+            let chain = HashMap::from([(1, hash(1)), (2, hash(42)), (3, hash(3))]);
+            let confirmed_hash = |height: u32| {
+                let hash = chain.get(&height).copied();
+                async move { hash.ok_or(Error::Custom("Unknown height".to_string())) }
+            };
+
+            // Only height 1 is confirmed so far
+            assert_eq!(log.reconcile_against(1, confirmed_hash).await.unwrap(), 1);
+            let statuses: Vec<_> = log.get_all().unwrap().iter().map(|s| s.status).collect();
+            assert_eq!(
+                statuses,
+                vec![SolutionStatus::Accepted, SolutionStatus::Pending, SolutionStatus::Pending]
+            );
+
+            assert_eq!(log.reconcile_against(3, confirmed_hash).await.unwrap(), 2);
+            let statuses: Vec<_> = log.get_all().unwrap().iter().map(|s| s.status).collect();
+            assert_eq!(
+                statuses,
+                vec![SolutionStatus::Accepted, SolutionStatus::Rejected, SolutionStatus::Accepted]
+            );
+
+            // Nothing changes when reconciling against the same chain again
+            assert_eq!(log.reconcile_against(3, confirmed_hash).await.unwrap(), 0);
+
+            // A reorg within the reconciliation depth flips decided solutions
+            let reorged = HashMap::from([(1, hash(1)), (2, hash(2)), (3, hash(43))]);
+            let reorged_hash = |height: u32| {
+                let hash = reorged.get(&height).copied();
+                async move { hash.ok_or(Error::Custom("Unknown height".to_string())) }
+            };
+            assert_eq!(log.reconcile_against(3, reorged_hash).await.unwrap(), 2);
+
+            // Lookup failures are propagated
+            let failing = |_| async { Err(Error::Custom("Lookup failed".to_string())) };
+            log.insert(&Solution::new(4, hash(0), hash(4), None)).unwrap();
+            assert!(log.reconcile_against(4, failing).await.is_err());
+        });
+    }
+
+    #[test]
+    fn solutions_summary() {
+        let sled_db = sled::Config::new().temporary(true).open().unwrap();
+        let log = SolutionLog::new(&sled_db).unwrap();
+
+        let summary = log.summary().unwrap();
+        assert_eq!(summary["found"], JsonValue::Number(0.0));
+        assert_eq!(summary["solutions"], JsonValue::Array(vec![]));
+
+        let mut accepted = Solution::new(1, hash(0), hash(1), Some("addr".to_string()));
+        accepted.status = SolutionStatus::Accepted;
+        let mut rejected = Solution::new(2, hash(0), hash(2), None);
+        rejected.status = SolutionStatus::Rejected;
+        let pending = Solution::new(3, hash(0), hash(3), None);
+        for solution in [&pending, &rejected, &accepted] {
+            log.insert(solution).unwrap();
+        }
+        // Updating a record doesn't duplicate it
+        log.insert(&accepted).unwrap();
+
+        let summary = log.summary().unwrap();
+        assert_eq!(summary["found"], JsonValue::Number(3.0));
+        assert_eq!(summary["accepted"], JsonValue::Number(1.0));
+        assert_eq!(summary["rejected"], JsonValue::Number(1.0));
+        assert_eq!(summary["pending"], JsonValue::Number(1.0));
+
+        // Records are ordered by height
+        let JsonValue::Array(solutions) = &summary["solutions"] else { panic!() };
+        assert_eq!(solutions[0]["status"], JsonValue::String("accepted".to_string()));
+        assert_eq!(solutions[0]["recipient"], JsonValue::String("addr".to_string()));
+        assert_eq!(solutions[2]["recipient"], JsonValue::Null);
+    }
+}
@@ -0,0 +1,311 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2025 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use std::{
+    path::PathBuf,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+};
+
+use log::{debug, info, warn};
+use smol::lock::RwLock;
+
+use darkfi::{rpc::util::JsonValue, system::sleep, util::path::expand_path, Error, Result};
+
+/// Throttle controller configuration
+#[derive(Clone, Debug)]
+pub struct ThrottleConfig {
+    /// hwmon temperature input paths, reporting millidegrees Celsius
+    pub temp_paths: Vec<PathBuf>,
+    /// hwmon power input paths, reporting microwatts
+    pub power_paths: Vec<PathBuf>,
+    /// Temperature (°C) over which mining threads get reduced
+    pub throttle_temp: f64,
+    /// Temperature (°C) over which mining gets paused
+    pub pause_temp: f64,
+    /// Temperature (°C) under which mining threads get restored
+    pub resume_temp: f64,
+    /// Optional total power (W) over which mining threads get reduced
+    pub max_power: Option<f64>,
+    /// Sensors polling interval, in seconds
+    pub interval: u64,
+}
+
+impl ThrottleConfig {
+    /// Generate a new throttle configuration, verifying provided thresholds
+    /// and expanding the hwmon paths.
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        temp_paths: &[String],
+        power_paths: &[String],
+        throttle_temp: f64,
+        pause_temp: f64,
+        resume_temp: f64,
+        max_power: Option<f64>,
+        interval: u64,
+    ) -> Result<Self> {
+        if temp_paths.is_empty() && power_paths.is_empty() {
+            return Err(Error::ParseFailed("No hwmon paths configured for throttling"))
+        }
+
+        if !(resume_temp < throttle_temp && throttle_temp < pause_temp) {
+            return Err(Error::ParseFailed(
+                "Throttle temperatures must satisfy resume < throttle < pause",
+            ))
+        }
+
+        if interval == 0 {
+            return Err(Error::ParseFailed("Throttle interval must be greater than zero"))
+        }
+
+        let temp_paths = temp_paths.iter().map(|p| expand_path(p)).collect::<Result<_>>()?;
+        let power_paths = power_paths.iter().map(|p| expand_path(p)).collect::<Result<_>>()?;
+
+        Ok(Self {
+            temp_paths,
+            power_paths,
+            throttle_temp,
+            pause_temp,
+            resume_temp,
+            max_power,
+            interval,
+        })
+    }
+}
+
+/// Latest sensor readings and the resulting throttle status
+#[derive(Clone, Debug, Default)]
+pub struct ThrottleStatus {
+    /// Highest temperature reading (°C)
+    pub temperature: Option<f64>,
+    /// Total power reading (W)
+    pub power: Option<f64>,
+    /// Mining is paused due to temperature
+    pub paused: bool,
+}
+
+/// Throttle controller, adjusting the amount of active mining threads
+/// based on hwmon temperature and power readings.
+pub struct Throttle {
+    /// Controller configuration
+    config: ThrottleConfig,
+    /// Configured number of mining threads
+    threads: usize,
+    /// Amount of mining threads currently allowed to run
+    active_threads: Arc<AtomicUsize>,
+    /// Latest throttle status
+    status: RwLock<ThrottleStatus>,
+}
+
+impl Throttle {
+    pub fn new(config: ThrottleConfig, threads: usize, active_threads: Arc<AtomicUsize>) -> Self {
+        Self { config, threads, active_threads, status: RwLock::new(ThrottleStatus::default()) }
+    }
+
+    /// Auxiliary function to read and scale hwmon sensor values.
+    /// Returns `None` if any of the sensors is unreadable.
+    async fn read_sensors(paths: &[PathBuf], scale: f64) -> Option<Vec<f64>> {
+        let mut values = Vec::with_capacity(paths.len());
+        for path in paths {
+            let contents = match smol::fs::read_to_string(path).await {
+                Ok(c) => c,
+                Err(e) => {
+                    warn!(target: "minerd::throttle", "Failed reading hwmon sensor {:?}: {}", path, e);
+                    return None
+                }
+            };
+
+            match contents.trim().parse::<f64>() {
+                Ok(v) => values.push(v / scale),
+                Err(_) => {
+                    warn!(target: "minerd::throttle", "Invalid hwmon sensor value in {:?}", path);
+                    return None
+                }
+            }
+        }
+
+        Some(values)
+    }
+
+    /// Poll the sensors and adjust the active mining threads.
+    /// Threads are reduced one at a time while over the throttle thresholds,
+    /// mining gets paused over the pause temperature, and threads are
+    /// restored one at a time once we are cooled under the resume temperature.
+    /// Mining also gets paused while any of the sensors can't be read, since
+    /// we can't tell whether it's safe to keep going.
+    pub async fn poll(&self) {
+        let temps = Self::read_sensors(&self.config.temp_paths, 1_000.0).await;
+        let powers = Self::read_sensors(&self.config.power_paths, 1_000_000.0).await;
+
+        let mut status = self.status.write().await;
+        let active = self.active_threads.load(Ordering::Relaxed);
+
+        let (Some(temps), Some(powers)) = (temps, powers) else {
+            if active != 0 {
+                warn!(target: "minerd::throttle", "Pausing mining until all hwmon sensors are readable");
+                self.active_threads.store(0, Ordering::Relaxed);
+            }
+            status.paused = true;
+            status.temperature = None;
+            status.power = None;
+            return
+        };
+
+        let temperature = temps.into_iter().reduce(f64::max);
+        let power = if powers.is_empty() { None } else { Some(powers.iter().sum()) };
+        let over_power = matches!((power, self.config.max_power), (Some(p), Some(m)) if p > m);
+
+        let mut next = active;
+        match temperature {
+            Some(t) if t >= self.config.pause_temp => {
+                status.paused = true;
+                next = 0;
+            }
+            Some(t) if t >= self.config.throttle_temp && !status.paused => {
+                next = active.saturating_sub(1).max(1);
+            }
+            _ if over_power && !status.paused => next = active.saturating_sub(1).max(1),
+            Some(t) if t > self.config.resume_temp => { /* Hold current threads */ }
+            _ if over_power => { /* Hold current threads */ }
+            _ => {
+                status.paused = false;
+                next = (active + 1).min(self.threads);
+            }
+        }
+
+        if next != active {
+            info!(
+                target: "minerd::throttle",
+                "Adjusting active mining threads {} -> {} (temperature: {:?}, power: {:?})",
+                active, next, temperature, power,
+            );
+            self.active_threads.store(next, Ordering::Relaxed);
+        }
+
+        status.temperature = temperature;
+        status.power = power;
+    }
+
+    /// Export the throttle status as a JSON object.
+    pub async fn status(&self) -> JsonValue {
+        let status = self.status.read().await;
+        let optional = |v: Option<f64>| match v {
+            Some(v) => JsonValue::Number(v),
+            None => JsonValue::Null,
+        };
+
+        JsonValue::Object(
+            [
+                ("temperature".to_string(), optional(status.temperature)),
+                ("power".to_string(), optional(status.power)),
+                ("paused".to_string(), JsonValue::Boolean(status.paused)),
+                ("throttle_temp".to_string(), JsonValue::Number(self.config.throttle_temp)),
+                ("pause_temp".to_string(), JsonValue::Number(self.config.pause_temp)),
+                ("resume_temp".to_string(), JsonValue::Number(self.config.resume_temp)),
+                ("max_power".to_string(), optional(self.config.max_power)),
+            ]
+            .into(),
+        )
+    }
+}
+
+/// Async task polling the throttle controller sensors in the configured interval.
+pub async fn throttle_task(throttle: Arc<Throttle>) -> Result<()> {
+    info!(target: "minerd::throttle", "Starting throttle controller...");
+    loop {
+        throttle.poll().await;
+        debug!(target: "minerd::throttle", "Throttle status: {:?}", throttle.status.read().await);
+        sleep(throttle.config.interval).await;
+    }
+}
+
+#[test]
+/// Test the throttle controller threads adjustments, using a
+/// temporary file as the hwmon temperature sensor.
+fn throttle_controller_adjustments() -> Result<()> {
+    let sensor_dir = std::env::temp_dir().join(format!(
+        "minerd_throttle_{}_{}",
+        std::process::id(),
+        std::time::UNIX_EPOCH.elapsed().unwrap().as_nanos()
+    ));
+    std::fs::create_dir_all(&sensor_dir)?;
+    let sensor = sensor_dir.join("temp1_input");
+    let set_temp = |t: u64| std::fs::write(&sensor, format!("{}\n", t * 1_000));
+
+    let config = ThrottleConfig::new(
+        &[sensor.to_string_lossy().to_string()],
+        &[],
+        85.0,
+        95.0,
+        75.0,
+        None,
+        1,
+    )?;
+    let active_threads = Arc::new(AtomicUsize::new(4));
+    let throttle = Throttle::new(config, 4, active_threads.clone());
+
+    smol::block_on(async {
+        // Over throttle temperature, threads get reduced down to one
+        set_temp(90)?;
+        for expected in [3, 2, 1, 1] {
+            throttle.poll().await;
+            assert_eq!(active_threads.load(Ordering::Relaxed), expected);
+        }
+
+        // Over pause temperature, mining gets paused
+        set_temp(96)?;
+        throttle.poll().await;
+        assert_eq!(active_threads.load(Ordering::Relaxed), 0);
+
+        // Cooling down under the throttle temperature keeps it paused
+        set_temp(80)?;
+        throttle.poll().await;
+        assert_eq!(active_threads.load(Ordering::Relaxed), 0);
+        assert!(throttle.status.read().await.paused);
+
+        // Under resume temperature, threads get restored
+        set_temp(70)?;
+        for expected in [1, 2, 3, 4, 4] {
+            throttle.poll().await;
+            assert_eq!(active_threads.load(Ordering::Relaxed), expected);
+        }
+        assert!(!throttle.status.read().await.paused);
+
+        // Unreadable or invalid sensors pause mining
+        std::fs::remove_file(&sensor)?;
+        throttle.poll().await;
+        assert_eq!(active_threads.load(Ordering::Relaxed), 0);
+        assert!(throttle.status.read().await.paused);
+        std::fs::write(&sensor, "garbage\n")?;
+        throttle.poll().await;
+        assert_eq!(active_threads.load(Ordering::Relaxed), 0);
+
+        // Once readable again, threads get restored under resume temperature
+        set_temp(70)?;
+        throttle.poll().await;
+        assert_eq!(active_threads.load(Ordering::Relaxed), 1);
+        assert!(!throttle.status.read().await.paused);
+
+        Ok::<(), Error>(())
+    })?;
+
+    std::fs::remove_dir_all(&sensor_dir)?;
+    Ok(())
+}
@@ -0,0 +1,31 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2025 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use darkfi::{rpc::error_code::ORACLED, rpc_error_codes};
+
+rpc_error_codes! {
+    /// Custom RPC errors available for oracled.
+    /// Please sort them sensefully.
+    pub enum RpcError in ORACLED {
+        // Parsing errors
+        FeedIdParseError = 1 => "Feed ID parse error",
+
+        // Reporter errors
+        UnknownFeed = 11 => "Feed is not reported by this oracle",
+    }
+}
@@ -0,0 +1,121 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2025 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use std::{str::FromStr, sync::Arc};
+
+use log::info;
+use rand::rngs::OsRng;
+use smol::{stream::StreamExt, Executor};
+use structopt_toml::{serde::Deserialize, structopt::StructOpt, StructOptToml};
+use url::Url;
+
+use darkfi::{async_daemonize, cli_desc, Error, Result};
+use darkfi_sdk::crypto::{Keypair, SecretKey};
+
+/// Daemon error codes
+mod error;
+
+/// Feed reporting
+mod reporter;
+use reporter::{FeedReporter, Reporter};
+
+/// JSON-RPC server methods
+mod rpc;
+
+const CONFIG_FILE: &str = "oracled.toml";
+const CONFIG_FILE_CONTENTS: &str = include_str!("../oracled.toml");
+
+#[derive(Clone, Debug, Deserialize, StructOpt, StructOptToml)]
+#[serde(default)]
+#[structopt(name = "oracled", about = cli_desc!())]
+struct Args {
+    #[structopt(short, long)]
+    /// Configuration file to use
+    config: Option<String>,
+
+    #[structopt(short, long, default_value = "tcp://127.0.0.1:28490")]
+    /// JSON-RPC listen URL
+    rpc_listen: Url,
+
+    #[structopt(long)]
+    /// Oracle secret key, base58 encoded
+    secret: Option<String>,
+
+    #[structopt(long, default_value = "60")]
+    /// Round duration, in seconds
+    interval: u64,
+
+    #[structopt(long)]
+    /// Feed to report, as `FEED_ID=COMMAND` (repeatable flag)
+    feed: Vec<String>,
+
+    #[structopt(long)]
+    /// Generate a new oracle keypair and exit
+    keygen: bool,
+
+    #[structopt(short, long)]
+    /// Set log file to ouput into
+    log: Option<String>,
+
+    #[structopt(short, parse(from_occurrences))]
+    /// Increase verbosity (-vvv supported)
+    verbose: u8,
+}
+
+async_daemonize!(realmain);
+async fn realmain(args: Args, ex: Arc<Executor<'static>>) -> Result<()> {
+    if args.keygen {
+        let keypair = Keypair::random(&mut OsRng);
+        println!("Secret key: {}", keypair.secret);
+        println!("Public key: {}", keypair.public);
+        return Ok(())
+    }
+
+    info!(target: "oracled", "Starting DarkFi Oracle Reporter Daemon...");
+
+    let Some(secret) = args.secret else {
+        return Err(Error::Custom("Oracle secret key is not configured".to_string()))
+    };
+    let secret = SecretKey::from_str(&secret)?;
+
+    if args.interval == 0 {
+        return Err(Error::Custom("Round interval can't be zero".to_string()))
+    }
+
+    let mut feeds = Vec::with_capacity(args.feed.len());
+    for feed in &args.feed {
+        feeds.push(FeedReporter::from_str(feed)?);
+    }
+    if feeds.is_empty() {
+        return Err(Error::Custom("No feeds are configured".to_string()))
+    }
+
+    let reporter = Reporter::new(secret, feeds, args.interval);
+    info!(target: "oracled", "Reporting as oracle {}", reporter.public_key());
+    reporter.start(&ex, &args.rpc_listen);
+
+    // Signal handling for graceful termination.
+    let (signals_handler, signals_task) = SignalHandler::new(ex)?;
+    signals_handler.wait_termination(signals_task).await?;
+    info!(target: "oracled", "Caught termination signal, cleaning up and exiting");
+
+    reporter.stop().await;
+
+    info!(target: "oracled", "Shut down successfully");
+    Ok(())
+}
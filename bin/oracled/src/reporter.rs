@@ -0,0 +1,205 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2025 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use std::{
+    collections::{HashMap, HashSet},
+    process::Command,
+    str::FromStr,
+    sync::Arc,
+};
+
+use log::{error, info, warn};
+use rand::{rngs::OsRng, RngCore};
+use smol::lock::Mutex;
+use url::Url;
+
+use darkfi::{
+    rpc::server::{listen_and_serve, RequestHandler},
+    system::{sleep, ExecutorPtr, StoppableTask, StoppableTaskPtr},
+    util::time::Timestamp,
+    Error, Result,
+};
+use darkfi_oracle_contract::{
+    client::sign_attestation,
+    model::{FeedId, OracleAttestation},
+};
+use darkfi_sdk::crypto::{PublicKey, SecretKey};
+
+/// A feed reported by this oracle
+#[derive(Clone, Debug)]
+pub struct FeedReporter {
+    /// The reported feed
+    pub feed_id: FeedId,
+    /// Shell command printing the value to attest, or `None`
+    /// to attest random contributions
+    pub command: Option<String>,
+}
+
+impl FromStr for FeedReporter {
+    type Err = Error;
+
+    /// Parse a `FEED_ID=COMMAND` or `FEED_ID` configuration entry
+    fn from_str(s: &str) -> Result<Self> {
+        let (feed_id, command) = match s.split_once('=') {
+            Some((feed_id, command)) => (feed_id, Some(command.trim().to_string())),
+            None => (s, None),
+        };
+
+        let Ok(feed_id) = FeedId::from_str(feed_id.trim()) else {
+            return Err(Error::Custom(format!("Invalid feed ID in feed entry: {s}")))
+        };
+
+        Ok(Self { feed_id, command })
+    }
+}
+
+impl FeedReporter {
+    /// Produce the value to attest for the current round
+    async fn value(&self) -> Result<u64> {
+        let Some(command) = self.command.clone() else { return Ok(OsRng.next_u64()) };
+
+        let output =
+            smol::unblock(move || Command::new("sh").arg("-c").arg(command).output()).await?;
+        if !output.status.success() {
+            return Err(Error::Custom(format!("Feed command exited with {}", output.status)))
+        }
+
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        match stdout.trim().parse::<u64>() {
+            Ok(v) => Ok(v),
+            Err(e) => Err(Error::Custom(format!("Failed parsing feed command output: {e}"))),
+        }
+    }
+}
+
+/// Atomic pointer to the oracle reporter
+pub type ReporterPtr = Arc<Reporter>;
+
+/// Structure representing an oracle reporter, signing a value for each
+/// of its feeds once per round, and serving the attestations over JSON-RPC
+/// so they can be gathered and submitted on-chain.
+pub struct Reporter {
+    /// Oracle secret key used to sign attestations
+    secret: SecretKey,
+    /// Feeds reported by this oracle
+    feeds: Vec<FeedReporter>,
+    /// Round duration, in seconds
+    interval: u64,
+    /// Latest signed attestation of each feed, along with its round
+    pub(crate) attestations: Mutex<HashMap<[u8; 32], (u64, OracleAttestation)>>,
+    /// Reporting background task
+    report_task: StoppableTaskPtr,
+    /// JSON-RPC background task
+    rpc_task: StoppableTaskPtr,
+    /// JSON-RPC connection tracker
+    pub(crate) rpc_connections: Mutex<HashSet<StoppableTaskPtr>>,
+}
+
+impl Reporter {
+    pub fn new(secret: SecretKey, feeds: Vec<FeedReporter>, interval: u64) -> ReporterPtr {
+        Arc::new(Self {
+            secret,
+            feeds,
+            interval,
+            attestations: Mutex::new(HashMap::new()),
+            report_task: StoppableTask::new(),
+            rpc_task: StoppableTask::new(),
+            rpc_connections: Mutex::new(HashSet::new()),
+        })
+    }
+
+    /// The oracle public key attestations are signed with
+    pub fn public_key(&self) -> PublicKey {
+        PublicKey::from_secret(self.secret)
+    }
+
+    /// Check if the given feed is reported by this oracle
+    pub(crate) fn reports(&self, feed_id: &FeedId) -> bool {
+        self.feeds.iter().any(|f| f.feed_id == *feed_id)
+    }
+
+    /// Start the reporting and JSON-RPC tasks in the given executor.
+    pub fn start(self: &Arc<Self>, executor: &ExecutorPtr, rpc_listen: &Url) {
+        info!(target: "oracled::Reporter::start", "Starting oracle reporter...");
+
+        self.report_task.clone().start(
+            report_task(self.clone()),
+            |res| async {
+                match res {
+                    Ok(()) | Err(Error::DetachedTaskStopped) => { /* Do nothing */ }
+                    Err(e) => error!(target: "oracled::Reporter::start", "Failed starting reporting task: {}", e),
+                }
+            },
+            Error::DetachedTaskStopped,
+            executor.clone(),
+        );
+
+        let self_ = self.clone();
+        self.rpc_task.clone().start(
+            listen_and_serve(rpc_listen.clone(), self.clone(), None, executor.clone()),
+            |res| async move {
+                match res {
+                    Ok(()) | Err(Error::RpcServerStopped) => self_.stop_connections().await,
+                    Err(e) => error!(target: "oracled::Reporter::start", "Failed starting JSON-RPC server: {}", e),
+                }
+            },
+            Error::RpcServerStopped,
+            executor.clone(),
+        );
+
+        info!(target: "oracled::Reporter::start", "Oracle reporter started successfully!");
+    }
+
+    /// Stop the reporting and JSON-RPC tasks.
+    pub async fn stop(&self) {
+        info!(target: "oracled::Reporter::stop", "Terminating oracle reporter...");
+        self.report_task.stop().await;
+        self.rpc_task.stop().await;
+        info!(target: "oracled::Reporter::stop", "Oracle reporter terminated successfully!");
+    }
+
+    /// Sign the current round value of every feed
+    async fn report(&self, round: u64) {
+        for feed in &self.feeds {
+            let value = match feed.value().await {
+                Ok(v) => v,
+                Err(e) => {
+                    warn!(target: "oracled::Reporter::report", "Failed producing value for feed {} round {}: {}", feed.feed_id, round, e);
+                    continue
+                }
+            };
+
+            let attestation = sign_attestation(&self.secret, &feed.feed_id, round, value);
+            self.attestations.lock().await.insert(feed.feed_id.to_bytes(), (round, attestation));
+            info!(target: "oracled::Reporter::report", "Feed {} round {}: {}", feed.feed_id, round, value);
+        }
+    }
+}
+
+/// Background task signing a value for each feed once per round. Rounds
+/// are derived from the UNIX time, so independent oracles using the same
+/// interval sign the same rounds.
+async fn report_task(reporter: ReporterPtr) -> Result<()> {
+    loop {
+        let now = Timestamp::current_time().inner();
+        reporter.report(now / reporter.interval).await;
+
+        // Sleep until the next round starts
+        sleep(reporter.interval - now % reporter.interval).await;
+    }
+}
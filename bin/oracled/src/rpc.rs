@@ -0,0 +1,109 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2025 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use std::{collections::HashSet, str::FromStr};
+
+use log::{debug, error};
+use smol::lock::MutexGuard;
+
+use darkfi::{
+    rpc::{
+        jsonrpc::{ErrorCode, JsonError, JsonRequest, JsonResponse, JsonResult},
+        server::RequestHandler,
+        util::JsonValue,
+    },
+    rpc_error,
+    system::StoppableTaskPtr,
+    util::encoding::base64,
+};
+use darkfi_oracle_contract::model::FeedId;
+use darkfi_serial::{async_trait, serialize_async};
+
+use crate::{error::RpcError, reporter::Reporter};
+
+#[async_trait]
+impl RequestHandler<()> for Reporter {
+    async fn handle_request(&self, req: JsonRequest) -> JsonResult {
+        debug!(target: "oracled::rpc", "--> {}", req.stringify().unwrap());
+
+        match req.method.as_str() {
+            "ping" => self.pong(req.id, req.params).await,
+            "oracle.public_key" => self.oracle_public_key(req.id, req.params).await,
+            "oracle.attestation" => self.oracle_attestation(req.id, req.params).await,
+            _ => JsonError::new(ErrorCode::MethodNotFound, None, req.id).into(),
+        }
+    }
+
+    async fn connections_mut(&self) -> MutexGuard<'life0, HashSet<StoppableTaskPtr>> {
+        self.rpc_connections.lock().await
+    }
+}
+
+impl Reporter {
+    // RPCAPI:
+    // Returns the oracle public key attestations are signed with.
+    //
+    // --> {"jsonrpc": "2.0", "method": "oracle.public_key", "params": [], "id": 42}
+    // <-- {"jsonrpc": "2.0", "result": "PublicKey", "id": 42}
+    async fn oracle_public_key(&self, id: u16, params: JsonValue) -> JsonResult {
+        let Some(params) = params.get::<Vec<JsonValue>>() else {
+            return JsonError::new(ErrorCode::InvalidParams, None, id).into()
+        };
+        if !params.is_empty() {
+            return JsonError::new(ErrorCode::InvalidParams, None, id).into()
+        }
+
+        JsonResponse::new(JsonValue::String(self.public_key().to_string()), id).into()
+    }
+
+    // RPCAPI:
+    // Returns the latest signed attestation for the given feed, along with
+    // its round. The attestation is a base64-encoded serialized `OracleAttestation`.
+    // Returns `null` if no round has been signed yet.
+    //
+    // --> {"jsonrpc": "2.0", "method": "oracle.attestation", "params": ["FeedId"], "id": 42}
+    // <-- {"jsonrpc": "2.0", "result": [round, "attestation"], "id": 42}
+    async fn oracle_attestation(&self, id: u16, params: JsonValue) -> JsonResult {
+        let Some(params) = params.get::<Vec<JsonValue>>() else {
+            return JsonError::new(ErrorCode::InvalidParams, None, id).into()
+        };
+        if params.len() != 1 || !params[0].is_string() {
+            return JsonError::new(ErrorCode::InvalidParams, None, id).into()
+        }
+
+        let Ok(feed_id) = FeedId::from_str(params[0].get::<String>().unwrap()) else {
+            error!(target: "oracled::rpc", "Failed to parse feed ID");
+            return rpc_error!(RpcError::FeedIdParseError, id)
+        };
+
+        if !self.reports(&feed_id) {
+            return rpc_error!(RpcError::UnknownFeed, id)
+        }
+
+        let Some((round, attestation)) =
+            self.attestations.lock().await.get(&feed_id.to_bytes()).cloned()
+        else {
+            return JsonResponse::new(JsonValue::Null, id).into()
+        };
+
+        let attestation = base64::encode(&serialize_async(&attestation).await);
+        let result =
+            JsonValue::Array(vec![JsonValue::Number(round as f64), JsonValue::String(attestation)]);
+        JsonResponse::new(result, id).into()
+    }
+}
@@ -86,3 +86,18 @@ async def export_to(path, server_name, port):
 
 async def import_from(path, server_name, port):
     return await query("import", [path], server_name, int(port))
+
+async def invite_member(member, server_name, port):
+    return await query("ws_invite", [member], server_name, int(port))
+
+async def revoke_member(member, server_name, port):
+    return await query("ws_revoke", [member], server_name, int(port))
+
+async def get_members(server_name, port):
+    return await query("ws_members", [], server_name, int(port))
+
+async def get_board(server_name, port):
+    return await query("board", [], server_name, int(port))
+
+async def move_task(refid, column, before_refid, server_name, port):
+    return await query("board_move", [refid, column, before_refid], server_name, int(port))
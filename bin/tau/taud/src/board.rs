@@ -0,0 +1,217 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2025 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Kanban board views over tasks.
+//!
+//! Every task sits in a board column, which is its explicitly set column,
+//! or its state if it was never moved. Inside a column, tasks are ordered
+//! by their position. Positions are fractional, so moving a task usually
+//! modifies the moved task itself only, and a move syncs as a single event.
+//! Once repeated moves exhaust the room between two neighbouring positions,
+//! the column is renumbered and the renumbered tasks sync as well.
+
+use std::collections::HashMap;
+
+use tinyjson::JsonValue;
+
+use crate::task_info::TaskInfo;
+
+/// Columns that are always shown on a board, in this order.
+/// Custom columns follow them, sorted by name.
+pub const DEFAULT_COLUMNS: [&str; 3] = ["open", "start", "pause"];
+
+/// A board column with its tasks in order
+pub struct BoardColumn {
+    pub name: String,
+    pub tasks: Vec<TaskInfo>,
+}
+
+impl From<&BoardColumn> for JsonValue {
+    fn from(column: &BoardColumn) -> JsonValue {
+        let tasks: Vec<JsonValue> = column.tasks.iter().map(|t| t.into()).collect();
+        JsonValue::Object(HashMap::from([
+            ("name".to_string(), JsonValue::String(column.name.clone())),
+            ("tasks".to_string(), JsonValue::Array(tasks)),
+        ]))
+    }
+}
+
+/// Arrange the given tasks into board columns
+pub fn build_board(tasks: Vec<TaskInfo>) -> Vec<BoardColumn> {
+    let mut columns: HashMap<String, Vec<TaskInfo>> = HashMap::new();
+    for column in DEFAULT_COLUMNS {
+        columns.insert(column.to_string(), vec![]);
+    }
+
+    for task in tasks {
+        columns.entry(task.get_column()).or_default().push(task);
+    }
+
+    let mut names: Vec<String> =
+        columns.keys().filter(|c| !DEFAULT_COLUMNS.contains(&c.as_str())).cloned().collect();
+    names.sort();
+
+    DEFAULT_COLUMNS
+        .iter()
+        .map(|c| c.to_string())
+        .chain(names)
+        .map(|name| {
+            let mut tasks = columns.remove(&name).unwrap();
+            sort_column(&mut tasks);
+            BoardColumn { name, tasks }
+        })
+        .collect()
+}
+
+/// Order the tasks of a column by position, falling back to their
+/// reference ID so every node agrees on ties.
+pub fn sort_column(tasks: &mut [TaskInfo]) {
+    tasks.sort_by(|a, b| a.position.total_cmp(&b.position).then_with(|| a.ref_id.cmp(&b.ref_id)));
+}
+
+/// Compute the position of a task moved into the given ordered column,
+/// placed right before the task with reference ID `before`, or at the
+/// end of the column if `before` is `None`. The moved task itself must
+/// not be part of `column`. Returns `None` if `before` isn't in the column.
+///
+/// If there's no room left between the neighbours of the new spot, the
+/// column gets renumbered first, and the tasks whose position changed are
+/// returned along with the position.
+pub fn move_position(
+    column: &mut [TaskInfo],
+    before: Option<&str>,
+) -> Option<(f64, Vec<TaskInfo>)> {
+    let Some(before) = before else {
+        return Some((column.last().map_or(0.0, |t| t.position + 1.0), vec![]))
+    };
+
+    let idx = column.iter().position(|t| t.ref_id == before)?;
+    if idx == 0 {
+        return Some((column[0].position - 1.0, vec![]))
+    }
+
+    if let Some(position) = bisect(column[idx - 1].position, column[idx].position) {
+        return Some((position, vec![]))
+    }
+
+    let renumbered = renumber_column(column);
+    let position = bisect(column[idx - 1].position, column[idx].position)?;
+    Some((position, renumbered))
+}
+
+/// Position halfway between `prev` and `next`, if it's representable
+/// strictly between them.
+fn bisect(prev: f64, next: f64) -> Option<f64> {
+    let position = prev + (next - prev) / 2.0;
+    (prev < position && position < next).then_some(position)
+}
+
+/// Space the positions of an ordered column one unit apart, starting from
+/// its first position and keeping its order. Returns the tasks whose
+/// position changed.
+pub fn renumber_column(column: &mut [TaskInfo]) -> Vec<TaskInfo> {
+    let Some(start) = column.first().map(|t| t.position) else { return vec![] };
+
+    let mut renumbered = vec![];
+    for (i, task) in column.iter_mut().enumerate() {
+        let position = start + i as f64;
+        if task.position != position {
+            task.set_position(position);
+            renumbered.push(task.clone());
+        }
+    }
+
+    renumbered
+}
+
+#[cfg(test)]
+mod tests {
+    use darkfi::util::time::Timestamp;
+
+    use super::*;
+
+    fn column(positions: &[f64]) -> Vec<TaskInfo> {
+        positions
+            .iter()
+            .map(|p| {
+                let mut task = TaskInfo::new(
+                    "darkfi".to_string(),
+                    "test_title",
+                    "test_desc",
+                    "NICKNAME",
+                    None,
+                    None,
+                    Timestamp::from_u64(1_700_000_000),
+                )
+                .unwrap();
+                task.set_position(*p);
+                task
+            })
+            .collect()
+    }
+
+    #[test]
+    fn move_positions() {
+        let mut tasks = column(&[1.0, 2.0, 4.0]);
+        let ids: Vec<String> = tasks.iter().map(|t| t.ref_id.clone()).collect();
+
+        assert_eq!(move_position(&mut tasks, None), Some((5.0, vec![])));
+        assert_eq!(move_position(&mut tasks, Some(&ids[0])), Some((0.0, vec![])));
+        assert_eq!(move_position(&mut tasks, Some(&ids[2])), Some((3.0, vec![])));
+        assert_eq!(move_position(&mut tasks, Some("unknown")), None);
+        assert_eq!(move_position(&mut [], None), Some((0.0, vec![])));
+    }
+
+    #[test]
+    fn renumber_exhausted_gap() {
+        // Positions default to the creation timestamp, so they start
+        // out large and bisecting them runs out of precision quickly.
+        let mut tasks = column(&[1_700_000_000.0, 1_700_000_001.0]);
+        let last = tasks[1].ref_id.clone();
+
+        let mut renumbered_once = false;
+        for _ in 0..100 {
+            let (position, renumbered) = move_position(&mut tasks, Some(&last)).unwrap();
+            renumbered_once |= !renumbered.is_empty();
+
+            let moved = column(&[position]).remove(0);
+            tasks.insert(tasks.len() - 1, moved);
+
+            // Every position stays distinct and in order
+            assert!(tasks.windows(2).all(|w| w[0].position < w[1].position));
+        }
+
+        assert!(renumbered_once);
+        assert_eq!(tasks.last().unwrap().ref_id, last);
+    }
+
+    #[test]
+    fn renumber_keeps_order() {
+        let mut tasks = column(&[1.0, 1.0, 1.5, 7.0]);
+        let ids: Vec<String> = tasks.iter().map(|t| t.ref_id.clone()).collect();
+
+        let renumbered = renumber_column(&mut tasks);
+        let positions: Vec<f64> = tasks.iter().map(|t| t.position).collect();
+        assert_eq!(positions, vec![1.0, 2.0, 3.0, 4.0]);
+        assert_eq!(tasks.iter().map(|t| t.ref_id.clone()).collect::<Vec<_>>(), ids);
+
+        // The first task kept its position, so it needs no sync
+        let renumbered: Vec<String> = renumbered.into_iter().map(|t| t.ref_id).collect();
+        assert_eq!(renumbered, ids[1..].to_vec());
+    }
+}
@@ -24,12 +24,14 @@ use std::{
 };
 
 use async_trait::async_trait;
-use log::{debug, info, warn};
+use darkfi_serial::serialize_async;
+use log::{debug, error, info, warn};
+use sled_overlay::sled;
 use smol::lock::{Mutex, MutexGuard};
 use tinyjson::JsonValue;
 
 use darkfi::{
-    event_graph::EventGraphPtr,
+    event_graph::{proto::EventPut, Event, EventGraphPtr},
     net,
     rpc::{
         jsonrpc::{ErrorCode, JsonError, JsonRequest, JsonResult, JsonSubscriber},
@@ -42,13 +44,14 @@ use darkfi::{
 };
 
 use taud::{
+    board::{build_board, move_position, sort_column},
     error::{to_json_result, TaudError, TaudResult},
     month_tasks::MonthTasks,
     task_info::{Comment, TaskInfo},
     util::set_event,
 };
 
-use crate::Workspace;
+use crate::{membership, Workspace};
 
 const DEFAULT_WORKSPACE: &str = "darkfi-dev";
 
@@ -58,6 +61,7 @@ pub struct JsonRpcInterface {
     nickname: String,
     workspace: Mutex<String>,
     workspaces: Arc<HashMap<String, Workspace>>,
+    sled_db: sled::Db,
     p2p: net::P2pPtr,
     event_graph: EventGraphPtr,
     dnet_sub: JsonSubscriber,
@@ -76,16 +80,22 @@ impl RequestHandler<()> for JsonRpcInterface {
             "set_state" => self.set_state(req.params).await,
             "set_comment" => self.set_comment(req.params).await,
             "get_task_by_ref_id" => self.get_task_by_ref_id(req.params).await,
+            "board" => self.board(req.params).await,
+            "board_move" => self.board_move(req.params).await,
             "switch_ws" => self.switch_ws(req.params).await,
             "get_ws" => self.get_ws(req.params).await,
             "export" => self.export_to(req.params).await,
             "import" => self.import_from(req.params).await,
             "fetch_deactive_tasks" => self.fetch_deactive_tasks(req.params).await,
             "fetch_archive_task" => self.fetch_archive_task(req.params).await,
+            "ws_invite" => self.ws_invite(req.params).await,
+            "ws_revoke" => self.ws_revoke(req.params).await,
+            "ws_members" => self.ws_members(req.params).await,
 
             "ping" => return self.pong(req.id, req.params).await,
             "dnet.subscribe_events" => return self.dnet_subscribe_events(req.id, req.params).await,
             "dnet.switch" => self.dnet_switch(req.params).await,
+            "dnet.get_recent" => return self.dnet_get_recent(req.id, req.params).await,
 
             "deg.switch" => self.deg_switch(req.id, req.params).await,
             "deg.subscribe_events" => return self.deg_subscribe_events(req.id, req.params).await,
@@ -93,6 +103,13 @@ impl RequestHandler<()> for JsonRpcInterface {
 
             // TODO: make this optional
             "p2p.get_info" => return self.p2p_get_info(req.id, req.params).await,
+            "p2p.get_transport_stats" => {
+                return self.p2p_get_transport_stats(req.id, req.params).await
+            }
+            "p2p.export_hosts" => return self.p2p_export_hosts(req.id, req.params).await,
+            "p2p.import_hosts" => return self.p2p_import_hosts(req.id, req.params).await,
+            "p2p.add_peer" => return self.p2p_add_peer(req.id, req.params).await,
+            "p2p.remove_peer" => return self.p2p_remove_peer(req.id, req.params).await,
             _ => return JsonError::new(ErrorCode::MethodNotFound, None, req.id).into(),
         };
 
@@ -117,6 +134,7 @@ impl JsonRpcInterface {
         notify_queue_sender: smol::channel::Sender<TaskInfo>,
         nickname: String,
         workspaces: Arc<HashMap<String, Workspace>>,
+        sled_db: sled::Db,
         p2p: net::P2pPtr,
         event_graph: EventGraphPtr,
         dnet_sub: JsonSubscriber,
@@ -128,6 +146,7 @@ impl JsonRpcInterface {
             nickname,
             workspace,
             workspaces,
+            sled_db,
             notify_queue_sender,
             p2p,
             event_graph,
@@ -500,6 +519,82 @@ impl JsonRpcInterface {
         Ok(task)
     }
 
+    // RPCAPI:
+    // Get the Kanban board view of the active tasks, as a list of columns
+    // with their tasks in order.
+    // --> {"jsonrpc": "2.0", "method": "board", "params": [], "id": 1}
+    // <-- {"jsonrpc": "2.0", "result": [{"name": "open", "tasks": [task, ...]}, ...], "id": 1}
+    async fn board(&self, params: JsonValue) -> TaudResult<JsonValue> {
+        let params = params.get::<Vec<JsonValue>>().unwrap();
+        debug!(target: "tau", "JsonRpc::board() params {:?}", params);
+
+        let ws = self.workspace.lock().await.clone();
+        let tasks = MonthTasks::load_current_tasks(&self.dataset_path, ws, false)?;
+        let columns: Vec<JsonValue> = build_board(tasks).iter().map(|c| c.into()).collect();
+
+        Ok(JsonValue::Array(columns))
+    }
+
+    // RPCAPI:
+    // Move a task to a board column, placing it right before another task
+    // of that column, or at its end if `null` is given. Returns `true` upon
+    // success.
+    // --> {"jsonrpc": "2.0", "method": "board_move", "params": [task_id, column, before_task_id], "id": 1}
+    // <-- {"jsonrpc": "2.0", "result": true, "id": 1}
+    async fn board_move(&self, params: JsonValue) -> TaudResult<JsonValue> {
+        let params = params.get::<Vec<JsonValue>>().unwrap();
+        debug!(target: "tau", "JsonRpc::board_move() params {:?}", params);
+
+        if params.len() != 3 ||
+            !params[0].is_string() ||
+            !params[1].is_string() ||
+            !(params[2].is_string() || params[2].is_null())
+        {
+            return Err(TaudError::InvalidData("len of params should be 3".into()))
+        }
+
+        let ref_id = params[0].get::<String>().unwrap();
+        let column = params[1].get::<String>().unwrap();
+        let before = params[2].get::<String>().map(|s| s.as_str());
+        if column.is_empty() {
+            return Err(TaudError::InvalidData("Invalid parameter \"column\"".into()))
+        }
+
+        let ws = self.workspace.lock().await.clone();
+        if self.workspaces.get(&ws).unwrap().write_key.is_none() {
+            info!("You don't have write access!");
+            return Ok(JsonValue::Boolean(false))
+        }
+
+        let tasks = MonthTasks::load_current_tasks(&self.dataset_path, ws, false)?;
+        let Some(mut task) = tasks.iter().find(|t| t.get_ref_id() == *ref_id).cloned() else {
+            return Err(TaudError::InvalidId)
+        };
+
+        // Grab the target column, without the moved task
+        let mut target: Vec<TaskInfo> = tasks
+            .into_iter()
+            .filter(|t| t.get_column() == *column && t.get_ref_id() != *ref_id)
+            .collect();
+        sort_column(&mut target);
+
+        let Some((position, renumbered)) = move_position(&mut target, before) else {
+            return Err(TaudError::InvalidData("Invalid parameter \"before\"".into()))
+        };
+
+        for renumbered_task in renumbered {
+            self.notify_queue_sender.send(renumbered_task).await.map_err(Error::from)?;
+        }
+
+        task.set_column(column);
+        task.set_position(position);
+        set_event(&mut task, "column", &self.nickname, column);
+
+        self.notify_queue_sender.send(task).await.map_err(Error::from)?;
+
+        Ok(JsonValue::Boolean(true))
+    }
+
     // RPCAPI:
     // Get all tasks.
     // --> {"jsonrpc": "2.0", "method": "fetch_deactive_tasks", "params": [task_id], "id": 1}
@@ -604,6 +699,78 @@ impl JsonRpcInterface {
         Ok(JsonValue::String(ws))
     }
 
+    // RPCAPI:
+    // Invite a member to the current workspace, rotating the workspace key
+    // so the member can decrypt tasks published from now on.
+    // Takes the member's public key. Requires write access.
+    // --> {"jsonrpc": "2.0", "method": "ws_invite", "params": [member_public_key], "id": 1}
+    // <-- {"jsonrpc": "2.0", "result": "true", "id": 1}
+    async fn ws_invite(&self, params: JsonValue) -> TaudResult<JsonValue> {
+        let params = params.get::<Vec<JsonValue>>().unwrap();
+        debug!(target: "tau", "JsonRpc::ws_invite() params {:?}", params);
+        let member = Self::member_from_params(&params)?;
+
+        let ws = self.workspace.lock().await.clone();
+        let workspace = self.workspaces.get(&ws).unwrap();
+        if workspace.write_key.is_none() {
+            info!("You don't have write access!");
+            return Ok(JsonValue::Boolean(false))
+        }
+
+        let Some(members) = membership::members_with(workspace, member).await else {
+            warn!("Member is already invited to \"{}\" workspace", ws);
+            return Ok(JsonValue::Boolean(false))
+        };
+
+        self.rotate_workspace_key(workspace, members).await?;
+        Ok(JsonValue::Boolean(true))
+    }
+
+    // RPCAPI:
+    // Revoke a member from the current workspace, rotating the workspace key
+    // so the member can't decrypt tasks published from now on.
+    // Takes the member's public key. Requires write access.
+    // --> {"jsonrpc": "2.0", "method": "ws_revoke", "params": [member_public_key], "id": 1}
+    // <-- {"jsonrpc": "2.0", "result": "true", "id": 1}
+    async fn ws_revoke(&self, params: JsonValue) -> TaudResult<JsonValue> {
+        let params = params.get::<Vec<JsonValue>>().unwrap();
+        debug!(target: "tau", "JsonRpc::ws_revoke() params {:?}", params);
+        let member = Self::member_from_params(&params)?;
+
+        let ws = self.workspace.lock().await.clone();
+        let workspace = self.workspaces.get(&ws).unwrap();
+        if workspace.write_key.is_none() {
+            info!("You don't have write access!");
+            return Ok(JsonValue::Boolean(false))
+        }
+
+        let Some(members) = membership::members_without(workspace, &member).await else {
+            warn!("Member is not invited to \"{}\" workspace", ws);
+            return Ok(JsonValue::Boolean(false))
+        };
+
+        self.rotate_workspace_key(workspace, members).await?;
+        Ok(JsonValue::Boolean(true))
+    }
+
+    // RPCAPI:
+    // List the members invited to the current workspace.
+    // --> {"jsonrpc": "2.0", "method": "ws_members", "params": [], "id": 1}
+    // <-- {"jsonrpc": "2.0", "result": [member_public_key, ...], "id": 1}
+    async fn ws_members(&self, params: JsonValue) -> TaudResult<JsonValue> {
+        let params = params.get::<Vec<JsonValue>>().unwrap();
+        debug!(target: "tau", "JsonRpc::ws_members() params {:?}", params);
+
+        let ws = self.workspace.lock().await.clone();
+        let members = self.workspaces.get(&ws).unwrap().members.read().await;
+        let members = members
+            .iter()
+            .map(|m| JsonValue::String(bs58::encode(m.as_bytes()).into_string()))
+            .collect();
+
+        Ok(JsonValue::Array(members))
+    }
+
     // RPCAPI:
     // Export tasks.
     // --> {"jsonrpc": "2.0", "method": "export_to", "params": [path], "id": 1}
@@ -676,6 +843,39 @@ impl JsonRpcInterface {
         Ok(JsonValue::Boolean(true))
     }
 
+    /// Parse a member public key from the given RPC params
+    fn member_from_params(params: &[JsonValue]) -> TaudResult<crypto_box::PublicKey> {
+        if params.len() != 1 || !params[0].is_string() {
+            return Err(TaudError::InvalidData("Invalid member public key".into()))
+        }
+
+        match membership::parse_member_key(params[0].get::<String>().unwrap()) {
+            Ok(v) => Ok(crypto_box::PublicKey::from(v)),
+            Err(_) => Err(TaudError::InvalidData("Invalid member public key".into())),
+        }
+    }
+
+    /// Rotate the key of provided workspace, granting it to provided
+    /// members, and publish the rotation. The workspace only gets the
+    /// new key and members once the rotation is in the DAG.
+    async fn rotate_workspace_key(
+        &self,
+        workspace: &Workspace,
+        members: Vec<crypto_box::PublicKey>,
+    ) -> TaudResult<()> {
+        let pending = membership::prepare_rotation(workspace, members).await?;
+        let event = Event::new(serialize_async(&pending.rotation).await, &self.event_graph).await;
+
+        if let Err(e) = self.event_graph.dag_insert(&[event.clone()]).await {
+            error!(target: "taud", "Failed inserting key rotation event to DAG: {}", e);
+            return Err(e.into())
+        }
+        membership::commit_rotation(&self.sled_db, workspace, pending).await?;
+        self.p2p.broadcast(&EventPut(event)).await;
+
+        Ok(())
+    }
+
     fn load_task_by_ref_id(&self, task_ref_id: &str, ws: String) -> TaudResult<TaskInfo> {
         let tasks = MonthTasks::load_current_tasks(&self.dataset_path, ws, false)?;
         let task = tasks.into_iter().find(|t| (t.get_ref_id()) == task_ref_id);
@@ -27,7 +27,7 @@ use std::{
 
 use crypto_box::{
     aead::{Aead, AeadCore},
-    ChaChaBox, SecretKey,
+    ChaChaBox, PublicKey, SecretKey,
 };
 use darkfi_serial::{
     async_trait, deserialize, deserialize_async_partial, serialize, serialize_async,
@@ -42,7 +42,7 @@ use ring::{
     signature::{Ed25519KeyPair, KeyPair, Signature, UnparsedPublicKey, ED25519},
 };
 use sled_overlay::sled;
-use smol::{fs, stream::StreamExt};
+use smol::{fs, lock::RwLock, stream::StreamExt};
 use structopt_toml::StructOptToml;
 use tinyjson::JsonValue;
 
@@ -63,6 +63,7 @@ use darkfi::{
 };
 
 mod jsonrpc;
+mod membership;
 mod settings;
 
 use taud::{
@@ -73,22 +74,27 @@ use taud::{
 
 use crate::{
     jsonrpc::JsonRpcInterface,
+    membership::{workspace_box, KeyRotation},
     settings::{Args, CONFIG_FILE, CONFIG_FILE_CONTENTS},
 };
 
 struct Workspace {
-    read_key: ChaChaBox,
+    /// Workspace keys of each known epoch, ordered by epoch.
+    /// The configured read_key, if any, is the epoch 0 key.
+    read_keys: RwLock<Vec<(u64, SecretKey)>>,
     write_key: Option<Ed25519KeyPair>,
     write_pubkey: UnparsedPublicKey<Vec<u8>>,
+    /// Members the workspace keys get granted to on rotation
+    members: RwLock<Vec<PublicKey>>,
 }
 
 impl Workspace {
     fn new() -> Self {
-        let secret_key = SecretKey::generate(&mut OsRng);
         Self {
-            read_key: ChaChaBox::new(&secret_key.public_key(), &secret_key),
+            read_keys: RwLock::new(vec![]),
             write_key: None,
             write_pubkey: UnparsedPublicKey::new(&ED25519, vec![0]),
+            members: RwLock::new(vec![]),
         }
     }
 }
@@ -110,8 +116,8 @@ impl SignedTask {
     }
 }
 
-/// Sign then encrypt a task
-fn encrypt_sign_task(task: &TaskInfo, workspace: &Workspace) -> TaudResult<EncryptedTask> {
+/// Sign then encrypt a task using the latest workspace key
+async fn encrypt_sign_task(task: &TaskInfo, workspace: &Workspace) -> TaudResult<EncryptedTask> {
     debug!(target: "taud", "start encrypting task");
     if workspace.write_key.is_none() {
         error!(target: "taud", "You don't have write access")
@@ -121,7 +127,10 @@ fn encrypt_sign_task(task: &TaskInfo, workspace: &Workspace) -> TaudResult<Encry
 
     let nonce = ChaChaBox::generate_nonce(&mut OsRng);
     let payload = &serialize(&signed_task)[..];
-    let mut payload = workspace.read_key.encrypt(&nonce, payload)?;
+    let Some((_, read_key)) = workspace.read_keys.read().await.last().cloned() else {
+        return Err(TaudError::EncryptionError("No workspace key available".to_string()))
+    };
+    let mut payload = workspace_box(&read_key).encrypt(&nonce, payload)?;
 
     let mut concat = vec![];
     concat.append(&mut nonce.as_slice().to_vec());
@@ -184,14 +193,12 @@ fn parse_configured_workspaces(data: &toml::Value) -> Result<HashMap<String, Wor
                 }
 
                 let read_key_bytes: [u8; 32] = read_key_bytes.try_into().unwrap();
-                let read_key = crypto_box::SecretKey::from(read_key_bytes);
-                let public = read_key.public_key();
-                ws.read_key = ChaChaBox::new(&public, &read_key);
+                ws.read_keys.get_mut().push((0, SecretKey::from(read_key_bytes)));
             } else {
                 return Err(Error::ParseFailed("Workspace read_key not a string"))
             }
         } else {
-            return Err(Error::ParseFailed("Workspace read_key is not set"))
+            info!(target: "taud", "No read_key for {} workspace, waiting for a grant", name);
         }
 
         if let Some(write_pubkey) = items.get("write_public_key") {
@@ -293,6 +300,7 @@ async fn start_sync_loop(
     settings: Args,
     p2p: P2pPtr,
     seen: OnceLock<sled::Tree>,
+    member_key: Option<SecretKey>,
 ) -> TaudResult<()> {
     let incoming = event_graph.event_pub.clone().subscribe().await;
 
@@ -303,7 +311,7 @@ async fn start_sync_loop(
                 let tk = task_event.map_err(Error::from)?;
                 if workspaces.contains_key(&tk.workspace) {
                     let ws = workspaces.get(&tk.workspace).unwrap();
-                    let encrypted_task = encrypt_sign_task(&tk, ws)?;
+                    let encrypted_task = encrypt_sign_task(&tk, ws).await?;
                     info!(target: "taud", "Send the task: ref: {}", tk.ref_id);
                     // Build a DAG event and return it.
                     let event = Event::new(
@@ -330,40 +338,90 @@ async fn start_sync_loop(
                 }
                 mark_seen(sled_db.clone(), seen.clone(), &event_id).await?;
 
-                // Try to deserialize the `Event`'s content into a `EncryptedTask`
-                let enc_task: EncryptedTask = match deserialize_async_partial(task_event.content()).await {
-                    Ok((v, _)) => v,
-                    Err(e) => {
-                        error!(target: "taud", "[TAUD] Failed deserializing incoming EncryptedTask event: {}", e);
-                        continue
-                    }
-                };
-                on_receive_task(&enc_task, &workspaces, &settings)
-                    .await?;
+                on_receive_event(
+                    task_event.content(),
+                    &workspaces,
+                    member_key.as_ref(),
+                    &sled_db,
+                    &settings,
+                )
+                .await?;
             }
         }
     }
 }
 
+/// Handle received event content, either a workspace key rotation or
+/// an encrypted task. Tasks none of our workspace keys can decrypt get
+/// queued and retried once a new workspace key is granted to us.
+async fn on_receive_event(
+    content: &[u8],
+    workspaces: &HashMap<String, Workspace>,
+    member_key: Option<&SecretKey>,
+    sled_db: &sled::Db,
+    settings: &Args,
+) -> TaudResult<()> {
+    // Handle workspace key rotations
+    if let Some(rotation) = KeyRotation::decode(content) {
+        if !membership::on_receive_rotation(&rotation, workspaces.iter(), member_key, sled_db)
+            .await?
+        {
+            return Ok(())
+        }
+
+        // Retry the tasks that were waiting for a key
+        for (key, pending) in membership::pending_tasks(sled_db)? {
+            let Ok((enc_task, _)) = deserialize_async_partial(&pending).await else {
+                membership::remove_pending_task(sled_db, &key)?;
+                continue
+            };
+            if on_receive_task(&enc_task, workspaces, settings).await? {
+                membership::remove_pending_task(sled_db, &key)?;
+            }
+        }
+        return Ok(())
+    }
+
+    // Try to deserialize the `Event`'s content into a `EncryptedTask`
+    let enc_task: EncryptedTask = match deserialize_async_partial(content).await {
+        Ok((v, _)) => v,
+        Err(e) => {
+            error!(target: "taud", "[TAUD] Failed deserializing incoming EncryptedTask event: {}", e);
+            return Ok(())
+        }
+    };
+
+    if !on_receive_task(&enc_task, workspaces, settings).await? {
+        debug!(target: "taud", "Queueing task until a workspace key to decrypt it is granted");
+        membership::queue_pending_task(sled_db, content)?;
+    }
+    Ok(())
+}
+
 /// Handle a received task, decrypt it, verify it, optionally write it
 /// to a named pipe and save it on disk.
+/// Returns `false` if none of the workspace keys could decrypt it.
 async fn on_receive_task(
     enc_task: &EncryptedTask,
     workspaces: &HashMap<String, Workspace>,
     settings: &Args,
-) -> TaudResult<()> {
+) -> TaudResult<bool> {
+    let mut decrypted = false;
     for (ws_name, workspace) in workspaces.iter() {
-        let signed_task = try_decrypt_task(enc_task, &workspace.read_key);
-        if let Err(e) = signed_task {
-            debug!(target: "taud", "Unable to decrypt the task: {}", e);
+        // Try the workspace keys, latest epoch first
+        let read_keys = workspace.read_keys.read().await;
+        let signed_task = read_keys
+            .iter()
+            .rev()
+            .find_map(|(_, read_key)| try_decrypt_task(enc_task, &workspace_box(read_key)).ok());
+        drop(read_keys);
+        let Some(signed_task) = signed_task else {
+            debug!(target: "taud", "Unable to decrypt the task with {} workspace keys", ws_name);
             continue
-        }
+        };
+        decrypted = true;
 
-        if workspace
-            .write_pubkey
-            .verify(&signed_task.as_ref().unwrap().task, &signed_task.as_ref().unwrap().signature)
-            .is_err()
-        {
+        if workspace.write_pubkey.verify(&signed_task.task, &signed_task.signature).is_err() {
             // *verified.lock().await = false;
             error!(target: "taud", "Task is not verified: wrong write_public_key");
             error!(target: "taud", "Task is not saved");
@@ -373,7 +431,7 @@ async fn on_receive_task(
         //     *verified.lock().await = true;
         // }
 
-        let mut task: TaskInfo = deserialize(&signed_task.unwrap().task)?;
+        let mut task: TaskInfo = deserialize(&signed_task.task)?;
         info!(target: "taud", "Save the task: ref: {}", task.ref_id);
         task.workspace.clone_from(ws_name);
         let datastore_path = expand_path(&settings.datastore)?;
@@ -415,7 +473,7 @@ async fn on_receive_task(
 
         task.save(&datastore_path)?;
     }
-    Ok(())
+    Ok(decrypted)
 }
 
 async_daemonize!(realmain);
@@ -499,6 +557,21 @@ async fn realmain(settings: Args, executor: Arc<smol::Executor<'static>>) -> Res
         return Ok(())
     }
 
+    if settings.generate_member_key {
+        // Chachabox secret key (member_key) used for receiving workspace keys.
+        let secret_key = SecretKey::generate(&mut OsRng);
+        println!("Please add the following to the config file:");
+        println!("member_key = \"{}\"", bs58::encode(secret_key.to_bytes()).into_string());
+        println!("Share this public key with workspace writers to get invited:");
+        println!("{}", bs58::encode(secret_key.public_key().as_bytes()).into_string());
+        return Ok(())
+    }
+
+    let member_key = match &settings.member_key {
+        Some(key) => Some(SecretKey::from(membership::parse_member_key(key)?)),
+        None => None,
+    };
+
     let workspaces = Arc::new(get_workspaces(&settings).await?);
     // let verified = Arc::new(Mutex::new(false));
 
@@ -519,6 +592,11 @@ async fn realmain(settings: Args, executor: Arc<smol::Executor<'static>>) -> Res
 
     info!(target: "taud", "Instantiating event DAG");
     let sled_db = sled::open(datastore)?;
+
+    // Load the workspace keys received or rotated so far
+    for (name, workspace) in workspaces.iter() {
+        membership::load_state(&sled_db, name, workspace).await?;
+    }
     let p2p = P2p::new(settings.net.clone().into(), executor.clone()).await?;
     let event_graph = EventGraph::new(
         p2p.clone(),
@@ -589,11 +667,12 @@ async fn realmain(settings: Args, executor: Arc<smol::Executor<'static>>) -> Res
         }
         mark_seen(sled_db.clone(), seen.clone(), &event_id).await?;
 
-        // Try to deserialize it. (Here we skip errors)
-        let Ok((enc_task, _)) = deserialize_async_partial(event.content()).await else { continue };
-
-        // Potentially decrypt the privmsg
-        on_receive_task(&enc_task, &workspaces, &settings).await.unwrap();
+        if let Err(e) =
+            on_receive_event(event.content(), &workspaces, member_key.as_ref(), &sled_db, &settings)
+                .await
+        {
+            error!(target: "taud", "Failed handling history event {}: {}", event_id, e);
+        }
     }
 
     ////////////////////
@@ -611,6 +690,7 @@ async fn realmain(settings: Args, executor: Arc<smol::Executor<'static>>) -> Res
             settings.clone(),
             p2p.clone(),
             seen.clone(),
+            member_key,
         ),
         |res| async {
             match res {
@@ -683,6 +763,7 @@ async fn realmain(settings: Args, executor: Arc<smol::Executor<'static>>) -> Res
         broadcast_snd,
         nickname.unwrap(),
         workspaces.clone(),
+        sled_db.clone(),
         p2p.clone(),
         event_graph.clone(),
         json_sub,
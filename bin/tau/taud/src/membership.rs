@@ -0,0 +1,506 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2025 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Workspace membership and key rotation.
+//!
+//! Besides the configured `read_key`, which acts as the epoch 0 key of a
+//! workspace, the workspace writer can rotate the workspace key whenever
+//! a member gets invited or revoked. A rotation is published as a signed
+//! [`KeyRotation`] event carrying the new key sealed to each remaining
+//! member's public key, so only they can decrypt tasks published after it.
+//! Revoked members keep access to the tasks they could already read.
+
+use crypto_box::{
+    aead::{Aead, AeadCore},
+    ChaChaBox, PublicKey, SecretKey,
+};
+use darkfi_serial::{deserialize, serialize, SerialDecodable, SerialEncodable};
+use log::{debug, info, warn};
+use rand::rngs::OsRng;
+use ring::signature::Signature;
+use sled_overlay::sled;
+
+use darkfi::{Error, Result};
+use taud::error::{TaudError, TaudResult};
+
+use crate::Workspace;
+
+/// Sled tree holding the workspace keys and members
+pub const WORKSPACE_KEYS_TREE: &str = "tau_workspace_keys";
+
+/// Sled tree holding received tasks none of our workspace keys can
+/// decrypt yet, retried whenever a new workspace key gets granted to us
+pub const PENDING_TASKS_TREE: &str = "tau_pending_tasks";
+
+/// Maximum amount of pending tasks kept, oldest ones get evicted first
+pub const MAX_PENDING_TASKS: usize = 1024;
+
+/// Magic prefix distinguishing [`KeyRotation`] events from tasks
+const ROTATION_MAGIC: [u8; 8] = *b"taurekey";
+
+/// Size of a sealed key grant: ephemeral public key, nonce and
+/// the encrypted 32 bytes key along with its 16 bytes tag.
+const GRANT_LEN: usize = 32 + 24 + 32 + 16;
+
+/// Workspace key of a given epoch
+#[derive(Clone, SerialEncodable, SerialDecodable)]
+struct EpochKey {
+    epoch: u64,
+    secret: [u8; 32],
+}
+
+/// Persisted membership state of a workspace
+#[derive(SerialEncodable, SerialDecodable)]
+struct WorkspaceState {
+    keys: Vec<EpochKey>,
+    members: Vec<[u8; 32]>,
+}
+
+/// Event content announcing a new workspace key epoch
+#[derive(SerialEncodable, SerialDecodable)]
+pub struct KeyRotation {
+    magic: [u8; 8],
+    /// Workspace identifier, the hash of its write public key
+    workspace: [u8; 32],
+    /// New key epoch
+    epoch: u64,
+    /// New workspace key, sealed to each member
+    grants: Vec<Vec<u8>>,
+    /// Signature by the workspace write key
+    signature: Vec<u8>,
+}
+
+impl KeyRotation {
+    /// Try to decode a [`KeyRotation`] from provided event content.
+    pub fn decode(content: &[u8]) -> Option<Self> {
+        let rotation: Self = deserialize(content).ok()?;
+        if rotation.magic != ROTATION_MAGIC {
+            return None
+        }
+        Some(rotation)
+    }
+
+    /// Message covered by the writer signature
+    fn signing_message(&self) -> Vec<u8> {
+        [serialize(&self.workspace), serialize(&self.epoch), serialize(&self.grants)].concat()
+    }
+}
+
+/// Build a [`ChaChaBox`] usable for encrypting with the given workspace key.
+pub fn workspace_box(secret: &SecretKey) -> ChaChaBox {
+    ChaChaBox::new(&secret.public_key(), secret)
+}
+
+/// Return the identifier of provided workspace.
+fn workspace_id(workspace: &Workspace) -> [u8; 32] {
+    *blake3::hash(workspace.write_pubkey.as_ref()).as_bytes()
+}
+
+/// Parse a base58 encoded member key.
+pub fn parse_member_key(key: &str) -> Result<[u8; 32]> {
+    let Ok(bytes) = bs58::decode(key).into_vec() else {
+        return Err(Error::ParseFailed("Member key not valid base58"))
+    };
+
+    match bytes.try_into() {
+        Ok(v) => Ok(v),
+        Err(_) => Err(Error::ParseFailed("Member key not 32 bytes long")),
+    }
+}
+
+/// Seal the workspace key to provided member public key.
+fn seal_grant(member: &PublicKey, secret: &SecretKey) -> TaudResult<Vec<u8>> {
+    let ephemeral = SecretKey::generate(&mut OsRng);
+    let nonce = ChaChaBox::generate_nonce(&mut OsRng);
+    let ciphertext = ChaChaBox::new(member, &ephemeral).encrypt(&nonce, &secret.to_bytes()[..])?;
+    Ok([&ephemeral.public_key().as_bytes()[..], nonce.as_slice(), &ciphertext[..]].concat())
+}
+
+/// Try opening a sealed grant using our member secret key.
+fn open_grant(grant: &[u8], member_key: &SecretKey) -> Option<SecretKey> {
+    if grant.len() != GRANT_LEN {
+        return None
+    }
+
+    let ephemeral: [u8; 32] = grant[..32].try_into().unwrap();
+    let chacha_box = ChaChaBox::new(&PublicKey::from(ephemeral), member_key);
+    let plaintext = chacha_box.decrypt(grant[32..56].into(), &grant[56..]).ok()?;
+    let secret: [u8; 32] = plaintext.try_into().ok()?;
+    Some(SecretKey::from(secret))
+}
+
+/// Load the persisted keys and members of provided workspace.
+pub async fn load_state(sled_db: &sled::Db, name: &str, workspace: &Workspace) -> Result<()> {
+    let tree = sled_db.open_tree(WORKSPACE_KEYS_TREE)?;
+    let Some(bytes) = tree.get(workspace_id(workspace))? else { return Ok(()) };
+    let state: WorkspaceState = deserialize(&bytes)?;
+
+    let mut read_keys = workspace.read_keys.write().await;
+    for key in state.keys {
+        let secret = SecretKey::from(key.secret);
+        if !read_keys.iter().any(|(_, k)| k.to_bytes() == secret.to_bytes()) {
+            read_keys.push((key.epoch, secret));
+        }
+    }
+    read_keys.sort_by_key(|(epoch, _)| *epoch);
+
+    *workspace.members.write().await = state.members.into_iter().map(PublicKey::from).collect();
+    info!(target: "taud", "Loaded {} key epochs for {} workspace", read_keys.len(), name);
+
+    Ok(())
+}
+
+/// Persist provided keys and members as the state of provided workspace.
+fn store_state(
+    sled_db: &sled::Db,
+    workspace: &Workspace,
+    keys: &[(u64, SecretKey)],
+    members: &[PublicKey],
+) -> Result<()> {
+    let keys = keys
+        .iter()
+        .map(|(epoch, secret)| EpochKey { epoch: *epoch, secret: secret.to_bytes() })
+        .collect();
+    let members = members.iter().map(|m| *m.as_bytes()).collect();
+
+    let tree = sled_db.open_tree(WORKSPACE_KEYS_TREE)?;
+    tree.insert(workspace_id(workspace), serialize(&WorkspaceState { keys, members }))?;
+    Ok(())
+}
+
+/// A signed [`KeyRotation`] along with the new workspace key and
+/// members, applied to the workspace by [`commit_rotation`].
+pub struct PendingRotation {
+    pub rotation: KeyRotation,
+    secret: SecretKey,
+    members: Vec<PublicKey>,
+}
+
+/// Generate a new workspace key, grant it to provided members and
+/// return the signed [`KeyRotation`] to publish. The workspace is left
+/// untouched until the rotation gets committed.
+/// Requires write access to the workspace.
+pub async fn prepare_rotation(
+    workspace: &Workspace,
+    members: Vec<PublicKey>,
+) -> TaudResult<PendingRotation> {
+    let Some(write_key) = workspace.write_key.as_ref() else {
+        return Err(TaudError::InvalidData("You don't have write access".into()))
+    };
+
+    let secret = SecretKey::generate(&mut OsRng);
+    let mut grants = vec![];
+    for member in members.iter() {
+        grants.push(seal_grant(member, &secret)?);
+    }
+
+    let epoch = workspace.read_keys.read().await.last().map(|(epoch, _)| epoch + 1).unwrap_or(1);
+
+    let mut rotation = KeyRotation {
+        magic: ROTATION_MAGIC,
+        workspace: workspace_id(workspace),
+        epoch,
+        grants,
+        signature: vec![],
+    };
+    let signature: Signature = write_key.sign(&rotation.signing_message());
+    rotation.signature = signature.as_ref().to_vec();
+
+    Ok(PendingRotation { rotation, secret, members })
+}
+
+/// Persist provided rotation and apply it to the workspace.
+/// Fails without touching the workspace if another rotation was
+/// committed since this one got prepared, or if persisting fails.
+pub async fn commit_rotation(
+    sled_db: &sled::Db,
+    workspace: &Workspace,
+    pending: PendingRotation,
+) -> TaudResult<()> {
+    let mut read_keys = workspace.read_keys.write().await;
+    let mut members = workspace.members.write().await;
+
+    let epoch = read_keys.last().map(|(epoch, _)| epoch + 1).unwrap_or(1);
+    if epoch != pending.rotation.epoch {
+        return Err(TaudError::InvalidData("Workspace key was rotated concurrently".into()))
+    }
+
+    let mut keys = read_keys.clone();
+    keys.push((epoch, pending.secret));
+    store_state(sled_db, workspace, &keys, &pending.members)?;
+
+    *read_keys = keys;
+    *members = pending.members;
+    Ok(())
+}
+
+/// Return the members of provided workspace with `member` added,
+/// or `None` if it is already a member.
+pub async fn members_with(workspace: &Workspace, member: PublicKey) -> Option<Vec<PublicKey>> {
+    let mut members = workspace.members.read().await.clone();
+    if members.contains(&member) {
+        return None
+    }
+    members.push(member);
+    Some(members)
+}
+
+/// Return the members of provided workspace with `member` removed,
+/// or `None` if it is not a member.
+pub async fn members_without(workspace: &Workspace, member: &PublicKey) -> Option<Vec<PublicKey>> {
+    let mut members = workspace.members.read().await.clone();
+    let len = members.len();
+    members.retain(|m| m != member);
+    if members.len() == len {
+        return None
+    }
+    Some(members)
+}
+
+/// Handle a received [`KeyRotation`], storing the new workspace key
+/// if it was granted to us. Returns `true` if a new key got stored.
+pub async fn on_receive_rotation<'a>(
+    rotation: &KeyRotation,
+    workspaces: impl Iterator<Item = (&'a String, &'a Workspace)>,
+    member_key: Option<&SecretKey>,
+    sled_db: &sled::Db,
+) -> Result<bool> {
+    let mut stored = false;
+    for (name, workspace) in workspaces {
+        if workspace_id(workspace) != rotation.workspace {
+            continue
+        }
+
+        if workspace.write_pubkey.verify(&rotation.signing_message(), &rotation.signature).is_err()
+        {
+            warn!(target: "taud", "Key rotation for {} workspace has an invalid signature", name);
+            continue
+        }
+
+        let Some(member_key) = member_key else {
+            debug!(target: "taud", "No member_key configured, skipping key rotation for {}", name);
+            continue
+        };
+
+        let Some(secret) = rotation.grants.iter().find_map(|g| open_grant(g, member_key)) else {
+            info!(target: "taud", "Not granted key epoch {} of {} workspace", rotation.epoch, name);
+            continue
+        };
+
+        let mut read_keys = workspace.read_keys.write().await;
+        if read_keys.iter().any(|(_, k)| k.to_bytes() == secret.to_bytes()) {
+            continue
+        }
+
+        let mut keys = read_keys.clone();
+        keys.push((rotation.epoch, secret));
+        keys.sort_by_key(|(epoch, _)| *epoch);
+        store_state(sled_db, workspace, &keys, &workspace.members.read().await)?;
+        *read_keys = keys;
+
+        info!(target: "taud", "Received key epoch {} of {} workspace", rotation.epoch, name);
+        stored = true;
+    }
+
+    Ok(stored)
+}
+
+/// Queue a received task none of our workspace keys could decrypt,
+/// evicting the oldest queued tasks past [`MAX_PENDING_TASKS`].
+pub fn queue_pending_task(sled_db: &sled::Db, content: &[u8]) -> Result<()> {
+    let tree = sled_db.open_tree(PENDING_TASKS_TREE)?;
+    tree.insert(sled_db.generate_id()?.to_be_bytes(), content)?;
+
+    while tree.len() > MAX_PENDING_TASKS {
+        if tree.pop_min()?.is_none() {
+            break
+        }
+    }
+
+    Ok(())
+}
+
+/// Return the queued pending tasks, oldest first, along with their keys.
+pub fn pending_tasks(sled_db: &sled::Db) -> Result<Vec<(sled::IVec, sled::IVec)>> {
+    let tree = sled_db.open_tree(PENDING_TASKS_TREE)?;
+    let mut ret = vec![];
+    for record in tree.iter() {
+        ret.push(record?);
+    }
+    Ok(ret)
+}
+
+/// Remove a queued pending task by its key.
+pub fn remove_pending_task(sled_db: &sled::Db, key: &[u8]) -> Result<()> {
+    sled_db.open_tree(PENDING_TASKS_TREE)?.remove(key)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use ring::{
+        rand::SystemRandom,
+        signature::{Ed25519KeyPair, KeyPair, UnparsedPublicKey, ED25519},
+    };
+
+    use super::*;
+
+    fn temp_db() -> sled::Db {
+        sled::Config::new().temporary(true).open().unwrap()
+    }
+
+    fn writer_workspace() -> Workspace {
+        let pkcs8 = Ed25519KeyPair::generate_pkcs8(&SystemRandom::new()).unwrap();
+        let write_key = Ed25519KeyPair::from_pkcs8(pkcs8.as_ref()).unwrap();
+
+        let mut workspace = Workspace::new();
+        workspace.write_pubkey =
+            UnparsedPublicKey::new(&ED25519, write_key.public_key().as_ref().to_vec());
+        workspace.write_key = Some(write_key);
+        workspace
+    }
+
+    #[test]
+    fn rotation_requires_write_key() {
+        smol::block_on(async {
+            let workspace = Workspace::new();
+            let member = SecretKey::generate(&mut OsRng).public_key();
+
+            let members = members_with(&workspace, member).await.unwrap();
+            assert!(prepare_rotation(&workspace, members).await.is_err());
+            assert!(workspace.members.read().await.is_empty());
+            assert!(workspace.read_keys.read().await.is_empty());
+        });
+    }
+
+    #[test]
+    fn rotation_applies_on_commit() {
+        smol::block_on(async {
+            let sled_db = temp_db();
+            let workspace = writer_workspace();
+            let member_key = SecretKey::generate(&mut OsRng);
+
+            let members = members_with(&workspace, member_key.public_key()).await.unwrap();
+            let pending = prepare_rotation(&workspace, members).await.unwrap();
+            assert_eq!(pending.rotation.epoch, 1);
+            assert!(workspace.members.read().await.is_empty());
+            assert!(workspace.read_keys.read().await.is_empty());
+
+            // A rotation prepared against a stale epoch must not commit
+            let stale = prepare_rotation(&workspace, vec![member_key.public_key()]).await.unwrap();
+
+            commit_rotation(&sled_db, &workspace, pending).await.unwrap();
+            assert_eq!(*workspace.members.read().await, vec![member_key.public_key()]);
+            assert_eq!(workspace.read_keys.read().await.len(), 1);
+
+            assert!(commit_rotation(&sled_db, &workspace, stale).await.is_err());
+            assert_eq!(workspace.read_keys.read().await.len(), 1);
+
+            // The committed state is persisted
+            let mut reloaded = Workspace::new();
+            reloaded.write_pubkey = UnparsedPublicKey::new(
+                &ED25519,
+                workspace.write_key.as_ref().unwrap().public_key().as_ref().to_vec(),
+            );
+            load_state(&sled_db, "test", &reloaded).await.unwrap();
+            assert_eq!(*reloaded.members.read().await, vec![member_key.public_key()]);
+            assert_eq!(
+                reloaded.read_keys.read().await[0].1.to_bytes(),
+                workspace.read_keys.read().await[0].1.to_bytes()
+            );
+
+            // Revoking and inviting existing members is rejected
+            assert!(members_with(&workspace, member_key.public_key()).await.is_none());
+            let other = SecretKey::generate(&mut OsRng).public_key();
+            assert!(members_without(&workspace, &other).await.is_none());
+            assert!(members_without(&workspace, &member_key.public_key())
+                .await
+                .unwrap()
+                .is_empty());
+        });
+    }
+
+    #[test]
+    fn members_receive_rotation() {
+        smol::block_on(async {
+            let writer_db = temp_db();
+            let writer = writer_workspace();
+            let member_key = SecretKey::generate(&mut OsRng);
+            let outsider_key = SecretKey::generate(&mut OsRng);
+
+            let pending = prepare_rotation(&writer, vec![member_key.public_key()]).await.unwrap();
+            let content = serialize(&pending.rotation);
+            commit_rotation(&writer_db, &writer, pending).await.unwrap();
+            let rotation = KeyRotation::decode(&content).unwrap();
+
+            let write_pubkey = writer.write_key.as_ref().unwrap().public_key().as_ref().to_vec();
+            let mut workspaces = std::collections::HashMap::new();
+            let mut workspace = Workspace::new();
+            workspace.write_pubkey = UnparsedPublicKey::new(&ED25519, write_pubkey);
+            workspaces.insert("test".to_string(), workspace);
+
+            // Not granted to outsiders
+            let sled_db = temp_db();
+            assert!(!on_receive_rotation(
+                &rotation,
+                workspaces.iter(),
+                Some(&outsider_key),
+                &sled_db
+            )
+            .await
+            .unwrap());
+            assert!(workspaces["test"].read_keys.read().await.is_empty());
+
+            // Granted to members, once
+            assert!(on_receive_rotation(&rotation, workspaces.iter(), Some(&member_key), &sled_db)
+                .await
+                .unwrap());
+            assert!(!on_receive_rotation(
+                &rotation,
+                workspaces.iter(),
+                Some(&member_key),
+                &sled_db
+            )
+            .await
+            .unwrap());
+            let read_keys = workspaces["test"].read_keys.read().await;
+            assert_eq!(read_keys.len(), 1);
+            assert_eq!(read_keys[0].0, 1);
+            assert_eq!(read_keys[0].1.to_bytes(), writer.read_keys.read().await[0].1.to_bytes());
+        });
+    }
+
+    #[test]
+    fn pending_tasks_queue() {
+        let sled_db = temp_db();
+
+        for i in 0..MAX_PENDING_TASKS + 2 {
+            queue_pending_task(&sled_db, &(i as u64).to_be_bytes()).unwrap();
+        }
+
+        // Oldest tasks got evicted
+        let pending = pending_tasks(&sled_db).unwrap();
+        assert_eq!(pending.len(), MAX_PENDING_TASKS);
+        assert_eq!(pending[0].1.as_ref(), 2u64.to_be_bytes());
+
+        remove_pending_task(&sled_db, &pending[0].0).unwrap();
+        let pending = pending_tasks(&sled_db).unwrap();
+        assert_eq!(pending.len(), MAX_PENDING_TASKS - 1);
+        assert_eq!(pending[0].1.as_ref(), 3u64.to_be_bytes());
+    }
+}
@@ -69,6 +69,14 @@ pub struct Args {
     #[structopt(long)]
     pub write: Option<String>,
 
+    /// Secret key used to receive workspace keys from workspace writers
+    #[structopt(long)]
+    pub member_key: Option<String>,
+
+    /// Generate a new member key
+    #[structopt(long)]
+    pub generate_member_key: bool,
+
     /// Password
     #[structopt(long)]
     pub password: Option<String>,
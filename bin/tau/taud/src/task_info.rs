@@ -19,11 +19,14 @@
 use std::{
     collections::HashMap,
     fmt,
+    io::{self, Read, Write},
     path::{Path, PathBuf},
     str::FromStr,
 };
 
-use darkfi_serial::{async_trait, SerialDecodable, SerialEncodable};
+use darkfi_serial::{
+    async_trait, Decodable, Encodable, SerialDecodable, SerialEncodable, WriteExt,
+};
 use log::debug;
 use tinyjson::JsonValue;
 
@@ -188,7 +191,12 @@ impl Comment {
     }
 }
 
-#[derive(Clone, Debug, SerialEncodable, SerialDecodable, PartialEq)]
+/// Version of the board metadata trailing an encoded [`TaskInfo`].
+/// Tasks without board metadata are encoded without the trailer, so
+/// nodes predating the board keep accepting them.
+const TASK_INFO_BOARD_VERSION: u8 = 1;
+
+#[derive(Clone, Debug, PartialEq)]
 pub struct TaskInfo {
     pub ref_id: String,
     pub workspace: String,
@@ -204,6 +212,84 @@ pub struct TaskInfo {
     pub state: String,
     pub events: Vec<TaskEvent>,
     pub comments: Vec<Comment>,
+    /// Board column, if the task was moved out of its state column
+    pub column: Option<String>,
+    /// Ordering position inside the board column
+    pub position: f64,
+}
+
+impl Encodable for TaskInfo {
+    fn encode<S: Write>(&self, s: &mut S) -> io::Result<usize> {
+        let mut len = 0;
+        len += self.ref_id.encode(s)?;
+        len += self.workspace.encode(s)?;
+        len += self.title.encode(s)?;
+        len += self.tags.encode(s)?;
+        len += self.desc.encode(s)?;
+        len += self.owner.encode(s)?;
+        len += self.assign.encode(s)?;
+        len += self.project.encode(s)?;
+        len += self.due.encode(s)?;
+        len += self.rank.encode(s)?;
+        len += self.created_at.encode(s)?;
+        len += self.state.encode(s)?;
+        len += self.events.encode(s)?;
+        len += self.comments.encode(s)?;
+
+        if self.has_board_metadata() {
+            s.write_u8(TASK_INFO_BOARD_VERSION)?;
+            len += 1;
+            len += self.column.encode(s)?;
+            len += self.position.encode(s)?;
+        }
+
+        Ok(len)
+    }
+}
+
+impl Decodable for TaskInfo {
+    fn decode<D: Read>(d: &mut D) -> io::Result<Self> {
+        let mut task = Self {
+            ref_id: Decodable::decode(d)?,
+            workspace: Decodable::decode(d)?,
+            title: Decodable::decode(d)?,
+            tags: Decodable::decode(d)?,
+            desc: Decodable::decode(d)?,
+            owner: Decodable::decode(d)?,
+            assign: Decodable::decode(d)?,
+            project: Decodable::decode(d)?,
+            due: Decodable::decode(d)?,
+            rank: Decodable::decode(d)?,
+            created_at: Decodable::decode(d)?,
+            state: Decodable::decode(d)?,
+            events: Decodable::decode(d)?,
+            comments: Decodable::decode(d)?,
+            column: None,
+            position: 0.0,
+        };
+        task.position = task.created_at.inner() as f64;
+
+        // Tasks encoded before board support end here
+        let mut version = [0u8; 1];
+        if d.read(&mut version)? == 0 {
+            return Ok(task)
+        }
+
+        match version[0] {
+            TASK_INFO_BOARD_VERSION => {
+                task.column = Decodable::decode(d)?;
+                task.position = Decodable::decode(d)?;
+            }
+            v => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("Unknown TaskInfo board version: {v}"),
+                ))
+            }
+        }
+
+        Ok(task)
+    }
 }
 
 impl From<&TaskInfo> for JsonValue {
@@ -235,6 +321,11 @@ impl From<&TaskInfo> for JsonValue {
 
         let created_at = JsonValue::String(task.created_at.inner().to_string());
         let state = JsonValue::String(task.state.clone());
+        let column = match &task.column {
+            Some(c) => JsonValue::String(c.clone()),
+            None => JsonValue::Null,
+        };
+        let position = JsonValue::Number(task.position);
         let events: Vec<JsonValue> = task.events.iter().map(|x| x.clone().into()).collect();
         let comments: Vec<JsonValue> = task.comments.iter().map(|x| x.clone().into()).collect();
 
@@ -251,6 +342,8 @@ impl From<&TaskInfo> for JsonValue {
             ("rank".to_string(), rank),
             ("created_at".to_string(), created_at),
             ("state".to_string(), state),
+            ("column".to_string(), column),
+            ("position".to_string(), position),
             ("events".to_string(), JsonValue::Array(events)),
             ("comments".to_string(), JsonValue::Array(comments)),
         ]))
@@ -287,6 +380,14 @@ impl From<JsonValue> for TaskInfo {
             Timestamp::from_u64(u64_str.parse::<u64>().unwrap())
         };
 
+        // Tasks saved before board support carry no ordering metadata
+        let map = value.get::<HashMap<String, JsonValue>>().unwrap();
+        let column = map.get("column").and_then(|c| c.get::<String>()).cloned();
+        let position = match map.get("position").and_then(|p| p.get::<f64>()) {
+            Some(p) => *p,
+            None => created_at.inner() as f64,
+        };
+
         let events: Vec<TaskEvent> = events.iter().map(|x| x.into()).collect();
         let comments: Vec<Comment> = comments.iter().map(|x| (*x).clone().into()).collect();
 
@@ -305,6 +406,8 @@ impl From<JsonValue> for TaskInfo {
             state: value["state"].get::<String>().unwrap().clone(),
             events,
             comments,
+            column,
+            position,
         }
     }
 }
@@ -343,6 +446,8 @@ impl TaskInfo {
             state: "open".into(),
             comments: vec![],
             events: vec![],
+            column: None,
+            position: created_at.inner() as f64,
         })
     }
 
@@ -385,6 +490,17 @@ impl TaskInfo {
         self.state.clone()
     }
 
+    /// Whether the task carries board metadata other than the defaults
+    /// every node derives on its own
+    pub fn has_board_metadata(&self) -> bool {
+        self.column.is_some() || self.position != self.created_at.inner() as f64
+    }
+
+    /// Board column the task sits in, defaulting to its state
+    pub fn get_column(&self) -> String {
+        self.column.clone().unwrap_or_else(|| self.get_state())
+    }
+
     pub fn get_path(ref_id: &str, dataset_path: &Path) -> PathBuf {
         debug!(target: "tau", "TaskInfo::get_path()");
         dataset_path.join("task").join(ref_id)
@@ -452,6 +568,16 @@ impl TaskInfo {
         self.due = d;
     }
 
+    pub fn set_column(&mut self, column: &str) {
+        debug!(target: "tau", "TaskInfo::set_column()");
+        self.column = Some(column.to_string());
+    }
+
+    pub fn set_position(&mut self, position: f64) {
+        debug!(target: "tau", "TaskInfo::set_position()");
+        self.position = position;
+    }
+
     pub fn set_state(&mut self, state: &str) {
         debug!(target: "tau", "TaskInfo::set_state()");
         if self.get_state() == state {
@@ -460,3 +586,119 @@ impl TaskInfo {
         self.state = state.to_string();
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use darkfi_serial::{deserialize, serialize};
+
+    use super::*;
+
+    /// Layout of [`TaskInfo`] before board support
+    #[derive(SerialEncodable)]
+    struct LegacyTaskInfo {
+        ref_id: String,
+        workspace: String,
+        title: String,
+        tags: Vec<String>,
+        desc: String,
+        owner: String,
+        assign: Vec<String>,
+        project: Vec<String>,
+        due: Option<Timestamp>,
+        rank: Option<f32>,
+        created_at: Timestamp,
+        state: String,
+        events: Vec<TaskEvent>,
+        comments: Vec<Comment>,
+    }
+
+    impl From<&TaskInfo> for LegacyTaskInfo {
+        fn from(task: &TaskInfo) -> Self {
+            Self {
+                ref_id: task.ref_id.clone(),
+                workspace: task.workspace.clone(),
+                title: task.title.clone(),
+                tags: task.tags.clone(),
+                desc: task.desc.clone(),
+                owner: task.owner.clone(),
+                assign: task.assign.clone(),
+                project: task.project.clone(),
+                due: task.due,
+                rank: task.rank,
+                created_at: task.created_at,
+                state: task.state.clone(),
+                events: task.events.clone(),
+                comments: task.comments.clone(),
+            }
+        }
+    }
+
+    fn task() -> TaskInfo {
+        let mut task = TaskInfo::new(
+            "darkfi".to_string(),
+            "test_title",
+            "test_desc",
+            "NICKNAME",
+            None,
+            Some(0.5),
+            Timestamp::current_time(),
+        )
+        .unwrap();
+        task.set_tags(&["+board".to_string()]);
+        task.set_comment(Comment::new("test_comment", "NICKNAME"));
+        task
+    }
+
+    #[test]
+    fn encoding_without_board_metadata() {
+        let task = task();
+        assert!(!task.has_board_metadata());
+
+        // Legacy nodes keep decoding tasks that were never moved
+        let legacy = serialize(&LegacyTaskInfo::from(&task));
+        assert_eq!(serialize(&task), legacy);
+
+        // and tasks from legacy nodes get the default board metadata
+        let decoded: TaskInfo = deserialize(&legacy).unwrap();
+        assert_eq!(decoded, task);
+        assert_eq!(decoded.get_column(), "open");
+    }
+
+    #[test]
+    fn encoding_with_board_metadata() {
+        let mut task = task();
+        task.set_column("review");
+        task.set_position(-2.25);
+        assert!(task.has_board_metadata());
+
+        let encoded = serialize(&task);
+        let decoded: TaskInfo = deserialize(&encoded).unwrap();
+        assert_eq!(decoded, task);
+        assert_eq!(decoded.get_column(), "review");
+
+        // Moving within the state column alone is kept too
+        let mut moved = self::task();
+        moved.set_position(3.0);
+        let decoded: TaskInfo = deserialize(&serialize(&moved)).unwrap();
+        assert_eq!(decoded.position, 3.0);
+        assert_eq!(decoded.column, None);
+
+        // Unknown versions of the board metadata are rejected
+        let mut encoded = encoded;
+        let version_idx = serialize(&LegacyTaskInfo::from(&decoded)).len();
+        encoded[version_idx] = TASK_INFO_BOARD_VERSION + 1;
+        assert!(deserialize::<TaskInfo>(&encoded).is_err());
+    }
+
+    #[test]
+    fn json_board_metadata() {
+        let mut task = task();
+        let json: JsonValue = (&task).into();
+        assert_eq!(TaskInfo::from(json), task);
+
+        task.set_column("review");
+        task.set_position(7.5);
+        let json: JsonValue = (&task).into();
+        assert_eq!(TaskInfo::from(json), task);
+    }
+}
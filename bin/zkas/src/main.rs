@@ -17,17 +17,20 @@
  */
 
 use std::{
-    fs::{read_to_string, File},
+    fs::{read, read_to_string, File},
     io::Write,
+    path::Path,
     process::ExitCode,
 };
 
 use arg::Args;
 
 use darkfi::{
+    zk::stats::{CircuitStats, DEFAULT_REGRESSION_TOLERANCE},
     zkas::{Analyzer, Compiler, Lexer, Parser, ZkBinary},
     ANSI_LOGO,
 };
+use darkfi_serial::{deserialize, serialize};
 
 const ABOUT: &str =
     concat!("zkas ", env!("CARGO_PKG_VERSION"), '\n', env!("CARGO_PKG_DESCRIPTION"));
@@ -44,6 +47,10 @@ Options:
   -p         Preprocess only; do not compile
   -i         Interactive semantic analysis
   -e         Examine decoded bytecode
+  -t         Print circuit cost statistics
+  -k <K>     Estimate proving cost for <K> instead of the circuit's k
+  -r <FILE>  Check cost statistics against the baseline in <FILE>,
+             creating it if it does not exist
   -h         Print this help
 "#;
 
@@ -58,7 +65,10 @@ fn main() -> ExitCode {
     let mut eflag = false;
     let mut sflag = false;
     let mut hflag = false;
+    let mut tflag = false;
     let mut output = String::new();
+    let mut estimate_k = String::new();
+    let mut baseline = String::new();
 
     {
         let mut args = Args::new().with_cb(|args, flag| match flag {
@@ -66,7 +76,10 @@ fn main() -> ExitCode {
             'i' => iflag = true,
             'e' => eflag = true,
             's' => sflag = true,
+            't' => tflag = true,
             'o' => output = args.eargf().to_string(),
+            'k' => estimate_k = args.eargf().to_string(),
+            'r' => baseline = args.eargf().to_string(),
             _ => hflag = true,
         });
 
@@ -168,5 +181,69 @@ fn main() -> ExitCode {
         println!("{:#?}", zkbin);
     }
 
+    if tflag || !baseline.is_empty() {
+        return report_stats(&bincode, &estimate_k, &baseline)
+    }
+
     ExitCode::SUCCESS
 }
+
+/// Print the cost statistics of the compiled circuit and, if a baseline
+/// file is given, fail when any tracked metric grew beyond the tolerance.
+fn report_stats(bincode: &[u8], estimate_k: &str, baseline: &str) -> ExitCode {
+    let zkbin = ZkBinary::decode(bincode).unwrap();
+
+    let mut stats = match CircuitStats::measure(&zkbin) {
+        Ok(v) => v,
+        Err(e) => {
+            eprintln!("Error: Failed measuring circuit. {}", e);
+            return ExitCode::FAILURE
+        }
+    };
+
+    if !estimate_k.is_empty() {
+        match estimate_k.parse::<u32>() {
+            Ok(k) if (1..32).contains(&k) => stats.cost = stats.estimate(k),
+            _ => {
+                eprintln!("Error: Invalid k \"{}\"", estimate_k);
+                return ExitCode::FAILURE
+            }
+        }
+    }
+
+    print!("{}", stats);
+
+    if baseline.is_empty() {
+        return ExitCode::SUCCESS
+    }
+
+    if !Path::new(baseline).exists() {
+        if let Err(e) = File::create(baseline).and_then(|mut f| f.write_all(&serialize(&stats))) {
+            eprintln!("Error: Failed to write baseline to \"{}\". {}", baseline, e);
+            return ExitCode::FAILURE
+        }
+        println!("Wrote baseline to {}", baseline);
+        return ExitCode::SUCCESS
+    }
+
+    let base: CircuitStats = match read(baseline).map(|b| deserialize(&b)) {
+        Ok(Ok(v)) => v,
+        Ok(Err(e)) | Err(e) => {
+            eprintln!("Error: Failed reading baseline from \"{}\". {}", baseline, e);
+            return ExitCode::FAILURE
+        }
+    };
+
+    let regressions = stats.regressions(&base, DEFAULT_REGRESSION_TOLERANCE);
+    if regressions.is_empty() {
+        println!("No regressions against {}", baseline);
+        return ExitCode::SUCCESS
+    }
+
+    eprintln!("Cost regressions against {}:", baseline);
+    for regression in regressions {
+        eprintln!("  {}", regression);
+    }
+
+    ExitCode::FAILURE
+}
@@ -33,6 +33,10 @@ use super::SledDbOverlayPtr;
 
 pub const SLED_CONTRACTS_TREE: &[u8] = b"_contracts";
 pub const SLED_BINCODE_TREE: &[u8] = b"_wasm_bincode";
+pub const SLED_CONTRACT_STATE_SIZE_TREE: &[u8] = b"_contract_state_size";
+
+/// Default maximum amount of state bytes a non-native contract can hold
+pub const CONTRACT_STATE_QUOTA: u64 = 64 * 1024 * 1024;
 
 /// The hardcoded db name for the zkas circuits database tree
 pub const SMART_CONTRACT_ZKAS_DB_NAME: &str = "_zkas";
@@ -58,6 +62,17 @@ pub struct ContractStore {
     /// ```
     /// These values get mutated with `init()` and `remove()`.
     pub state: sled::Tree,
+    /// The `sled` tree accounting the bytes each contract keeps in its
+    /// state trees. The layout looks like this:
+    /// ```plaintext
+    ///  tree: "_contract_state_size"
+    ///   key: ContractId
+    /// value: u64
+    /// ```
+    /// These values get mutated by the wasm runtime on every state write.
+    pub state_size: sled::Tree,
+    /// Maximum amount of state bytes a non-native contract can hold
+    pub state_quota: u64,
 }
 
 impl ContractStore {
@@ -65,7 +80,44 @@ impl ContractStore {
     pub fn new(db: &sled::Db) -> Result<Self> {
         let wasm = db.open_tree(SLED_BINCODE_TREE)?;
         let state = db.open_tree(SLED_CONTRACTS_TREE)?;
-        Ok(Self { wasm, state })
+        let state_size = db.open_tree(SLED_CONTRACT_STATE_SIZE_TREE)?;
+        Ok(Self { wasm, state, state_size, state_quota: CONTRACT_STATE_QUOTA })
+    }
+
+    /// Account the state size of contracts holding state from before it
+    /// was tracked, by summing up the records of all their state trees.
+    /// Contracts already accounted for are skipped.
+    pub fn backfill_state_sizes(&self, db: &sled::Db) -> Result<()> {
+        for (contract_id, state_pointers) in self.get_all_states()? {
+            let key = serialize(&contract_id);
+            if self.state_size.contains_key(&key)? {
+                continue
+            }
+
+            let mut size = 0;
+            for ptr in state_pointers {
+                for record in db.open_tree(ptr.as_bytes())?.iter() {
+                    let (k, v) = record?;
+                    size += (k.len() + v.len()) as u64;
+                }
+            }
+
+            if size > 0 {
+                debug!(target: "blockchain::contractstore", "Backfilling state size of {}: {}", contract_id, size);
+                self.state_size.insert(key, serialize(&size))?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Fetches the accounted state size, in bytes, of a given ContractId.
+    /// Contracts that never wrote any state have a size of zero.
+    pub fn get_state_size(&self, contract_id: &ContractId) -> Result<u64> {
+        match self.state_size.get(serialize(contract_id))? {
+            Some(bytes) => Ok(deserialize(&bytes)?),
+            None => Ok(0),
+        }
     }
 
     /// Fetches the bincode for a given ContractId from the store's wasm tree.
@@ -218,6 +270,7 @@ impl ContractStoreOverlay {
     pub fn new(overlay: &SledDbOverlayPtr) -> Result<Self> {
         overlay.lock().unwrap().open_tree(SLED_BINCODE_TREE, true)?;
         overlay.lock().unwrap().open_tree(SLED_CONTRACTS_TREE, true)?;
+        overlay.lock().unwrap().open_tree(SLED_CONTRACT_STATE_SIZE_TREE, true)?;
         Ok(Self(overlay.clone()))
     }
 
@@ -246,6 +299,27 @@ impl ContractStoreOverlay {
         Ok(())
     }
 
+    /// Fetches the accounted state size, in bytes, of a given ContractId
+    /// from the overlay. Contracts that never wrote any state have a size
+    /// of zero.
+    pub fn get_state_size(&self, contract_id: &ContractId) -> Result<u64> {
+        match self.0.lock().unwrap().get(SLED_CONTRACT_STATE_SIZE_TREE, &serialize(contract_id))? {
+            Some(bytes) => Ok(deserialize(&bytes)?),
+            None => Ok(0),
+        }
+    }
+
+    /// Sets the accounted state size, in bytes, of a given ContractId in
+    /// the overlay.
+    pub fn set_state_size(&self, contract_id: &ContractId, size: u64) -> Result<()> {
+        self.0.lock().unwrap().insert(
+            SLED_CONTRACT_STATE_SIZE_TREE,
+            &serialize(contract_id),
+            &serialize(&size),
+        )?;
+        Ok(())
+    }
+
     /// Try to initialize a new contract state. Contracts can create a number
     /// of trees, separated by `tree_name`, which they can then use from the
     /// smart contract API. `init()` will look into the main `ContractStateStoreOverlay`
@@ -26,7 +26,9 @@ use darkfi_sdk::{
 };
 #[cfg(feature = "async-serial")]
 use darkfi_serial::async_trait;
-use darkfi_serial::{deserialize, serialize, Encodable, SerialDecodable, SerialEncodable};
+use darkfi_serial::{
+    deserialize, serialize, serialize_hashed, Encodable, SerialDecodable, SerialEncodable,
+};
 use sled_overlay::{
     serial::{parse_record, parse_u32_key_record},
     sled,
@@ -36,7 +38,7 @@ use crate::{util::time::Timestamp, Error, Result};
 
 use super::SledDbOverlayPtr;
 
-#[derive(Copy, Clone, Debug, Eq, PartialEq, SerialEncodable, SerialDecodable)]
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash, SerialEncodable, SerialDecodable)]
 // We have to introduce a type rather than using an alias so we can restrict API access
 pub struct HeaderHash(pub [u8; 32]);
 
@@ -104,6 +106,12 @@ impl Header {
 
         HeaderHash(hasher.finalize().into())
     }
+
+    /// Serialize the header, computing its hash in the same pass
+    pub fn serialize_with_hash(&self) -> (Vec<u8>, HeaderHash) {
+        let (encoded, hash) = serialize_hashed::<_, blake3::Hasher>(self);
+        (encoded, HeaderHash(hash.into()))
+    }
 }
 
 impl Default for Header {
@@ -192,8 +200,8 @@ impl HeaderStore {
         let mut batch = sled::Batch::default();
 
         for header in headers {
-            let headerhash = header.hash();
-            batch.insert(headerhash.inner(), serialize(header));
+            let (header_se, headerhash) = header.serialize_with_hash();
+            batch.insert(headerhash.inner(), header_se);
             ret.push(headerhash);
         }
 
@@ -365,8 +373,8 @@ impl HeaderStoreOverlay {
         let mut lock = self.0.lock().unwrap();
 
         for header in headers {
-            let headerhash = header.hash();
-            lock.insert(SLED_HEADER_TREE, headerhash.inner(), &serialize(header))?;
+            let (header_se, headerhash) = header.serialize_with_hash();
+            lock.insert(SLED_HEADER_TREE, headerhash.inner(), &header_se)?;
             ret.push(headerhash);
         }
 
@@ -47,7 +47,8 @@ pub use tx_store::{
 /// Contracts and Wasm storage implementations
 pub mod contract_store;
 pub use contract_store::{
-    ContractStore, ContractStoreOverlay, SLED_BINCODE_TREE, SLED_CONTRACTS_TREE,
+    ContractStore, ContractStoreOverlay, CONTRACT_STATE_QUOTA, SLED_BINCODE_TREE,
+    SLED_CONTRACTS_TREE, SLED_CONTRACT_STATE_SIZE_TREE,
 };
 
 /// Structure holding all sled trees that define the concept of Blockchain.
@@ -408,6 +409,8 @@ pub struct BlockchainOverlay {
     pub transactions: TxStoreOverlay,
     /// Contract overlay
     pub contracts: ContractStoreOverlay,
+    /// Maximum amount of state bytes a non-native contract can hold
+    pub state_quota: u64,
 }
 
 impl BlockchainOverlay {
@@ -427,6 +430,7 @@ impl BlockchainOverlay {
             SLED_PENDING_TX_ORDER_TREE,
             SLED_CONTRACTS_TREE,
             SLED_BINCODE_TREE,
+            SLED_CONTRACT_STATE_SIZE_TREE,
         ];
         let overlay = Arc::new(Mutex::new(sled_overlay::SledDbOverlay::new(
             &blockchain.sled_db,
@@ -436,8 +440,16 @@ impl BlockchainOverlay {
         let blocks = BlockStoreOverlay::new(&overlay)?;
         let transactions = TxStoreOverlay::new(&overlay)?;
         let contracts = ContractStoreOverlay::new(&overlay)?;
+        let state_quota = blockchain.contracts.state_quota;
 
-        Ok(Arc::new(Mutex::new(Self { overlay, headers, blocks, transactions, contracts })))
+        Ok(Arc::new(Mutex::new(Self {
+            overlay,
+            headers,
+            blocks,
+            transactions,
+            contracts,
+            state_quota,
+        })))
     }
 
     /// Check if blockchain contains any blocks
@@ -580,7 +592,15 @@ impl BlockchainOverlay {
         let blocks = BlockStoreOverlay::new(&overlay)?;
         let transactions = TxStoreOverlay::new(&overlay)?;
         let contracts = ContractStoreOverlay::new(&overlay)?;
-
-        Ok(Arc::new(Mutex::new(Self { overlay, headers, blocks, transactions, contracts })))
+        let state_quota = self.state_quota;
+
+        Ok(Arc::new(Mutex::new(Self {
+            overlay,
+            headers,
+            blocks,
+            transactions,
+            contracts,
+            state_quota,
+        })))
     }
 }
@@ -110,8 +110,8 @@ impl TxStore {
         let mut batch = sled::Batch::default();
 
         for tx in transactions {
-            let tx_hash = tx.hash();
-            batch.insert(tx_hash.inner(), serialize(tx));
+            let (tx_se, tx_hash) = tx.serialize_with_hash();
+            batch.insert(tx_hash.inner(), tx_se);
             ret.push(tx_hash);
         }
 
@@ -152,8 +152,8 @@ impl TxStore {
         let mut batch = sled::Batch::default();
 
         for tx in transactions {
-            let tx_hash = tx.hash();
-            batch.insert(tx_hash.inner(), serialize(tx));
+            let (tx_se, tx_hash) = tx.serialize_with_hash();
+            batch.insert(tx_hash.inner(), tx_se);
             ret.push(tx_hash);
         }
 
@@ -431,8 +431,8 @@ impl TxStoreOverlay {
         let mut lock = self.0.lock().unwrap();
 
         for tx in transactions {
-            let tx_hash = tx.hash();
-            lock.insert(SLED_TX_TREE, tx_hash.inner(), &serialize(tx))?;
+            let (tx_se, tx_hash) = tx.serialize_with_hash();
+            lock.insert(SLED_TX_TREE, tx_hash.inner(), &tx_se)?;
             ret.push(tx_hash);
         }
 
@@ -0,0 +1,89 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2025 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use darkfi::{
+    zk::{halo2::Value, Proof, ProvingKey, Witness, ZkCircuit},
+    zkas::ZkBinary,
+    Error, Result,
+};
+use darkfi_money_contract::client::OwnCoin;
+use darkfi_sdk::pasta::pallas;
+use log::debug;
+use rand::rngs::OsRng;
+
+use crate::model::{AuctionBidParamsV1, AuctionId};
+
+pub struct AuctionBidCallDebris {
+    pub params: AuctionBidParamsV1,
+    pub proofs: Vec<Proof>,
+}
+
+/// Struct holding necessary information to build an `Auction::BidV1` contract call.
+pub struct AuctionBidCallBuilder<'a> {
+    /// The `AuctionId` to bid in
+    pub auction_id: AuctionId,
+    /// Coin holding the locked bid, minted by a `Money::Transfer`
+    /// in the same transaction
+    pub coin: OwnCoin,
+    /// Reserve price of the auction
+    pub reserve_price: u64,
+    /// `AuctionBid_V1` zkas circuit ZkBinary
+    pub bid_zkbin: &'a ZkBinary,
+    /// Proving key for the `AuctionBid_V1` zk circuit
+    pub bid_pk: &'a ProvingKey,
+}
+
+impl AuctionBidCallBuilder<'_> {
+    pub fn build(&self) -> Result<AuctionBidCallDebris> {
+        debug!(target: "contract::auction::client::bid", "Building Auction::BidV1 contract call");
+
+        if self.coin.note.user_data != self.auction_id.inner() {
+            return Err(Error::Custom("Bid coin is not locked to the auction".to_string()))
+        }
+
+        if self.coin.note.value < self.reserve_price {
+            return Err(Error::Custom("Bid is below the auction reserve price".to_string()))
+        }
+
+        // The bid value stays hidden, we only prove it meets the reserve price
+        let prover_witnesses = vec![
+            Witness::Base(Value::known(self.coin.secret.inner())),
+            Witness::Base(Value::known(pallas::Base::from(self.coin.note.value))),
+            Witness::Base(Value::known(self.coin.note.token_id.inner())),
+            Witness::Base(Value::known(self.coin.note.spend_hook.inner())),
+            Witness::Base(Value::known(self.coin.note.user_data)),
+            Witness::Base(Value::known(self.coin.note.coin_blind.inner())),
+            Witness::Base(Value::known(pallas::Base::from(self.reserve_price))),
+        ];
+
+        let public_inputs = vec![
+            self.coin.coin.inner(),
+            self.coin.note.token_id.inner(),
+            self.coin.note.spend_hook.inner(),
+            self.coin.note.user_data,
+            pallas::Base::from(self.reserve_price),
+        ];
+
+        let circuit = ZkCircuit::new(prover_witnesses, self.bid_zkbin);
+        let proof = Proof::create(self.bid_pk, &[circuit], &public_inputs, &mut OsRng)?;
+
+        let params = AuctionBidParamsV1 { auction_id: self.auction_id, coin: self.coin.coin };
+
+        Ok(AuctionBidCallDebris { params, proofs: vec![proof] })
+    }
+}
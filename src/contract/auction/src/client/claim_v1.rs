@@ -0,0 +1,69 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2025 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use darkfi::{
+    zk::{Proof, ProvingKey},
+    zkas::ZkBinary,
+    Result,
+};
+use darkfi_money_contract::client::OwnCoin;
+use log::debug;
+
+use super::create_claim_proof;
+use crate::model::{AuctionClaimParamsV1, AuctionId};
+
+pub struct AuctionClaimCallDebris {
+    pub params: AuctionClaimParamsV1,
+    pub proofs: Vec<Proof>,
+}
+
+/// Struct holding necessary information to build an `Auction::ClaimV1` contract call.
+///
+/// The call has to be the parent of the `Money::Transfer` refunding the
+/// given coins, or of the `Money::OtcSwap` settling the auction. In the
+/// latter case, the seller and the winner each build the call for their
+/// own coin, and the halves get combined in the same order as the swap
+/// inputs.
+pub struct AuctionClaimCallBuilder<'a> {
+    /// The `AuctionId` the coins are locked in
+    pub auction_id: AuctionId,
+    /// Locked coins spent by the child call, in input order
+    pub coins: Vec<OwnCoin>,
+    /// `AuctionClaim_V1` zkas circuit ZkBinary
+    pub claim_zkbin: &'a ZkBinary,
+    /// Proving key for the `AuctionClaim_V1` zk circuit
+    pub claim_pk: &'a ProvingKey,
+}
+
+impl AuctionClaimCallBuilder<'_> {
+    pub fn build(&self) -> Result<AuctionClaimCallDebris> {
+        debug!(target: "contract::auction::client::claim", "Building Auction::ClaimV1 contract call");
+
+        let mut proofs = Vec::with_capacity(self.coins.len());
+        for coin in &self.coins {
+            proofs.push(create_claim_proof(coin, self.claim_zkbin, self.claim_pk)?);
+        }
+
+        let params = AuctionClaimParamsV1 {
+            auction_id: self.auction_id,
+            coins: self.coins.iter().map(|c| c.coin).collect(),
+        };
+
+        Ok(AuctionClaimCallDebris { params, proofs })
+    }
+}
@@ -0,0 +1,73 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2025 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use darkfi::{
+    zk::{Proof, ProvingKey},
+    zkas::ZkBinary,
+    Error, Result,
+};
+use darkfi_money_contract::client::OwnCoin;
+use darkfi_sdk::crypto::AUCTION_CONTRACT_ID;
+use log::debug;
+
+use super::create_coin_proof;
+use crate::model::{auction_spend_hook, AuctionCreateParamsV1, AuctionId, AuctionParams};
+
+pub struct AuctionCreateCallDebris {
+    pub params: AuctionCreateParamsV1,
+    pub proofs: Vec<Proof>,
+}
+
+/// Struct holding necessary information to build an `Auction::CreateV1` contract call.
+pub struct AuctionCreateCallBuilder<'a> {
+    /// Auction parameters
+    pub params: AuctionParams,
+    /// Coin holding the locked asset, minted by a `Money::Transfer`
+    /// in the same transaction
+    pub asset_coin: OwnCoin,
+    /// `AuctionCoin_V1` zkas circuit ZkBinary
+    pub coin_zkbin: &'a ZkBinary,
+    /// Proving key for the `AuctionCoin_V1` zk circuit
+    pub coin_pk: &'a ProvingKey,
+}
+
+impl AuctionCreateCallBuilder<'_> {
+    pub fn build(&self) -> Result<AuctionCreateCallDebris> {
+        debug!(target: "contract::auction::client::create", "Building Auction::CreateV1 contract call");
+
+        if !self.params.is_valid() {
+            return Err(Error::Custom("Invalid auction parameters".to_string()))
+        }
+
+        let note = &self.asset_coin.note;
+        if note.value != self.params.asset_amount ||
+            note.token_id != self.params.asset_token_id ||
+            note.spend_hook != auction_spend_hook(*AUCTION_CONTRACT_ID) ||
+            note.user_data != AuctionId::derive(&self.params).inner()
+        {
+            return Err(Error::Custom("Asset coin is not locked to the auction".to_string()))
+        }
+
+        let proof = create_coin_proof(&self.asset_coin, self.coin_zkbin, self.coin_pk)?;
+
+        let params =
+            AuctionCreateParamsV1 { params: self.params, asset_coin: self.asset_coin.coin };
+
+        Ok(AuctionCreateCallDebris { params, proofs: vec![proof] })
+    }
+}
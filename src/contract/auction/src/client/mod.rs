@@ -0,0 +1,92 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2025 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! This module implements the client-side API for creating, bidding in,
+//! and settling sealed-bid auctions on the DarkFi network.
+//!
+//! Coins locked in an auction are regular `Money` coins, created with
+//! [`auction_spend_hook()`](crate::model::auction_spend_hook) as their
+//! spend hook and the `AuctionId` as their user data, so the `Money`
+//! transfer locking them is built with the `Money` client API.
+
+use darkfi::{
+    zk::{halo2::Value, Proof, ProvingKey, Witness, ZkCircuit},
+    zkas::ZkBinary,
+    Result,
+};
+use darkfi_money_contract::client::OwnCoin;
+use darkfi_sdk::{crypto::poseidon_hash, pasta::pallas};
+use rand::rngs::OsRng;
+
+/// `Auction::CreateV1` API
+pub mod create_v1;
+
+/// `Auction::BidV1` API
+pub mod bid_v1;
+
+/// `Auction::RevealV1` API
+pub mod reveal_v1;
+
+/// `Auction::ClaimV1` API
+pub mod claim_v1;
+
+/// Create a proof revealing the attributes of a locked coin, apart from
+/// its owner, and showing knowledge of its secret key.
+pub(crate) fn create_coin_proof(
+    coin: &OwnCoin,
+    coin_zkbin: &ZkBinary,
+    coin_pk: &ProvingKey,
+) -> Result<Proof> {
+    let prover_witnesses = vec![
+        Witness::Base(Value::known(coin.secret.inner())),
+        Witness::Base(Value::known(pallas::Base::from(coin.note.value))),
+        Witness::Base(Value::known(coin.note.token_id.inner())),
+        Witness::Base(Value::known(coin.note.spend_hook.inner())),
+        Witness::Base(Value::known(coin.note.user_data)),
+        Witness::Base(Value::known(coin.note.coin_blind.inner())),
+    ];
+
+    let public_inputs = vec![
+        coin.coin.inner(),
+        pallas::Base::from(coin.note.value),
+        coin.note.token_id.inner(),
+        coin.note.spend_hook.inner(),
+        coin.note.user_data,
+    ];
+
+    let circuit = ZkCircuit::new(prover_witnesses, coin_zkbin);
+    Ok(Proof::create(coin_pk, &[circuit], &public_inputs, &mut OsRng)?)
+}
+
+/// Create a proof linking the nullifier of a locked coin to the coin itself.
+pub(crate) fn create_claim_proof(
+    coin: &OwnCoin,
+    claim_zkbin: &ZkBinary,
+    claim_pk: &ProvingKey,
+) -> Result<Proof> {
+    let prover_witnesses = vec![
+        Witness::Base(Value::known(coin.secret.inner())),
+        Witness::Base(Value::known(coin.coin.inner())),
+    ];
+
+    let nullifier = poseidon_hash([coin.secret.inner(), coin.coin.inner()]);
+    let public_inputs = vec![coin.coin.inner(), nullifier];
+
+    let circuit = ZkCircuit::new(prover_witnesses, claim_zkbin);
+    Ok(Proof::create(claim_pk, &[circuit], &public_inputs, &mut OsRng)?)
+}
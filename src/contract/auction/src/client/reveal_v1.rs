@@ -0,0 +1,65 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2025 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use darkfi::{
+    zk::{Proof, ProvingKey},
+    zkas::ZkBinary,
+    Error, Result,
+};
+use darkfi_money_contract::client::OwnCoin;
+use log::debug;
+
+use super::create_coin_proof;
+use crate::model::{AuctionId, AuctionRevealParamsV1};
+
+pub struct AuctionRevealCallDebris {
+    pub params: AuctionRevealParamsV1,
+    pub proofs: Vec<Proof>,
+}
+
+/// Struct holding necessary information to build an `Auction::RevealV1` contract call.
+pub struct AuctionRevealCallBuilder<'a> {
+    /// The `AuctionId` the bid was made in
+    pub auction_id: AuctionId,
+    /// Coin holding the locked bid
+    pub coin: OwnCoin,
+    /// `AuctionCoin_V1` zkas circuit ZkBinary
+    pub coin_zkbin: &'a ZkBinary,
+    /// Proving key for the `AuctionCoin_V1` zk circuit
+    pub coin_pk: &'a ProvingKey,
+}
+
+impl AuctionRevealCallBuilder<'_> {
+    pub fn build(&self) -> Result<AuctionRevealCallDebris> {
+        debug!(target: "contract::auction::client::reveal", "Building Auction::RevealV1 contract call");
+
+        if self.coin.note.user_data != self.auction_id.inner() {
+            return Err(Error::Custom("Bid coin is not locked to the auction".to_string()))
+        }
+
+        let proof = create_coin_proof(&self.coin, self.coin_zkbin, self.coin_pk)?;
+
+        let params = AuctionRevealParamsV1 {
+            auction_id: self.auction_id,
+            coin: self.coin.coin,
+            value: self.coin.note.value,
+        };
+
+        Ok(AuctionRevealCallDebris { params, proofs: vec![proof] })
+    }
+}
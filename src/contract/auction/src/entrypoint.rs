@@ -0,0 +1,188 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2025 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use darkfi_money_contract::{
+    model::{Coin, MoneyTransferParamsV1},
+    MoneyFunction,
+};
+use darkfi_sdk::{
+    crypto::{ContractId, MONEY_CONTRACT_ID},
+    dark_tree::DarkLeaf,
+    error::{ContractError, ContractResult},
+    wasm, ContractCall,
+};
+use darkfi_serial::{deserialize, serialize};
+
+use crate::{
+    model::{
+        AuctionBidUpdateV1, AuctionClaimUpdateV1, AuctionCreateUpdateV1, AuctionRevealUpdateV1,
+    },
+    AuctionFunction, AUCTION_CONTRACT_AUCTIONS_TREE, AUCTION_CONTRACT_COINS_TREE,
+    AUCTION_CONTRACT_DB_VERSION, AUCTION_CONTRACT_INFO_TREE,
+};
+
+/// `Auction::Create` functions
+mod create_v1;
+use create_v1::{create_get_metadata_v1, create_process_instruction_v1, create_process_update_v1};
+
+/// `Auction::Bid` functions
+mod bid_v1;
+use bid_v1::{bid_get_metadata_v1, bid_process_instruction_v1, bid_process_update_v1};
+
+/// `Auction::Reveal` functions
+mod reveal_v1;
+use reveal_v1::{reveal_get_metadata_v1, reveal_process_instruction_v1, reveal_process_update_v1};
+
+/// `Auction::Claim` functions
+mod claim_v1;
+use claim_v1::{claim_get_metadata_v1, claim_process_instruction_v1, claim_process_update_v1};
+
+darkfi_sdk::define_contract!(
+    init: init_contract,
+    exec: process_instruction,
+    apply: process_update,
+    metadata: get_metadata
+);
+
+/// This entrypoint function runs when the contract is (re)deployed and initialized.
+/// We use this function to initialize all the necessary databases and prepare them
+/// with initial data if necessary. This is also the place where we bundle the zkas
+/// circuits that are to be used with functions provided by the contract.
+fn init_contract(cid: ContractId, _ix: &[u8]) -> ContractResult {
+    // zkas circuits can simply be embedded in the wasm and set up by using
+    // respective db functions.
+    let auction_coin_v1_bincode = include_bytes!("../proof/auction-coin.zk.bin");
+    let auction_bid_v1_bincode = include_bytes!("../proof/auction-bid.zk.bin");
+    let auction_claim_v1_bincode = include_bytes!("../proof/auction-claim.zk.bin");
+    wasm::db::zkas_db_set(&auction_coin_v1_bincode[..])?;
+    wasm::db::zkas_db_set(&auction_bid_v1_bincode[..])?;
+    wasm::db::zkas_db_set(&auction_claim_v1_bincode[..])?;
+
+    // Set up a database tree for arbitrary data
+    let info_db = match wasm::db::db_lookup(cid, AUCTION_CONTRACT_INFO_TREE) {
+        Ok(v) => v,
+        Err(_) => wasm::db::db_init(cid, AUCTION_CONTRACT_INFO_TREE)?,
+    };
+
+    // Set up a database to hold the auctions
+    // k=AuctionId, v=AuctionRecord
+    if wasm::db::db_lookup(cid, AUCTION_CONTRACT_AUCTIONS_TREE).is_err() {
+        wasm::db::db_init(cid, AUCTION_CONTRACT_AUCTIONS_TREE)?;
+    }
+
+    // Set up a database to hold the coins locked in auctions
+    // k=Coin, v=AuctionCoinRecord
+    if wasm::db::db_lookup(cid, AUCTION_CONTRACT_COINS_TREE).is_err() {
+        wasm::db::db_init(cid, AUCTION_CONTRACT_COINS_TREE)?;
+    }
+
+    // Update db version
+    wasm::db::db_set(info_db, AUCTION_CONTRACT_DB_VERSION, &serialize(&env!("CARGO_PKG_VERSION")))?;
+
+    Ok(())
+}
+
+/// This function is used by the wasm VM's host to fetch the necessary metadata
+/// for verifying signatures and zk proofs. The payload given here are all the
+/// contract calls in the transaction.
+fn get_metadata(cid: ContractId, ix: &[u8]) -> ContractResult {
+    let call_idx = wasm::util::get_call_index()? as usize;
+    let calls: Vec<DarkLeaf<ContractCall>> = deserialize(ix)?;
+    let self_ = &calls[call_idx].data;
+    let func = AuctionFunction::try_from(self_.data[0])?;
+
+    let metadata = match func {
+        AuctionFunction::CreateV1 => create_get_metadata_v1(cid, call_idx, calls)?,
+        AuctionFunction::BidV1 => bid_get_metadata_v1(cid, call_idx, calls)?,
+        AuctionFunction::RevealV1 => reveal_get_metadata_v1(cid, call_idx, calls)?,
+        AuctionFunction::ClaimV1 => claim_get_metadata_v1(cid, call_idx, calls)?,
+    };
+
+    wasm::util::set_return_data(&metadata)
+}
+
+/// This function verifies a state transition and produces a state update
+/// if everything is successful.
+fn process_instruction(cid: ContractId, ix: &[u8]) -> ContractResult {
+    let call_idx = wasm::util::get_call_index()? as usize;
+    let calls: Vec<DarkLeaf<ContractCall>> = deserialize(ix)?;
+    let self_ = &calls[call_idx].data;
+    let func = AuctionFunction::try_from(self_.data[0])?;
+
+    let update_data = match func {
+        AuctionFunction::CreateV1 => create_process_instruction_v1(cid, call_idx, calls)?,
+        AuctionFunction::BidV1 => bid_process_instruction_v1(cid, call_idx, calls)?,
+        AuctionFunction::RevealV1 => reveal_process_instruction_v1(cid, call_idx, calls)?,
+        AuctionFunction::ClaimV1 => claim_process_instruction_v1(cid, call_idx, calls)?,
+    };
+
+    wasm::util::set_return_data(&update_data)
+}
+
+/// This function attempts to write a given state update provided the previous
+/// steps of the contract call execution were all successful. It's the last in
+/// line, and assumes that the transaction/call was successful. The payload
+/// given to the function is the update data retrieved from `process_instruction()`.
+fn process_update(cid: ContractId, update_data: &[u8]) -> ContractResult {
+    match AuctionFunction::try_from(update_data[0])? {
+        AuctionFunction::CreateV1 => {
+            let update: AuctionCreateUpdateV1 = deserialize(&update_data[1..])?;
+            Ok(create_process_update_v1(cid, update)?)
+        }
+
+        AuctionFunction::BidV1 => {
+            let update: AuctionBidUpdateV1 = deserialize(&update_data[1..])?;
+            Ok(bid_process_update_v1(cid, update)?)
+        }
+
+        AuctionFunction::RevealV1 => {
+            let update: AuctionRevealUpdateV1 = deserialize(&update_data[1..])?;
+            Ok(reveal_process_update_v1(cid, update)?)
+        }
+
+        AuctionFunction::ClaimV1 => {
+            let update: AuctionClaimUpdateV1 = deserialize(&update_data[1..])?;
+            Ok(claim_process_update_v1(cid, update)?)
+        }
+    }
+}
+
+/// Check if the given coin is minted by a top-level `Money::Transfer` call
+/// in the same transaction. Locked coins are only accepted along with the
+/// transfer creating them, so they can't be registered by anyone else.
+pub(crate) fn coin_minted_in_tx(
+    calls: &[DarkLeaf<ContractCall>],
+    coin: &Coin,
+) -> Result<bool, ContractError> {
+    for call in calls {
+        if call.data.contract_id != *MONEY_CONTRACT_ID ||
+            call.parent_index.is_some() ||
+            call.data.data.is_empty() ||
+            call.data.data[0] != MoneyFunction::TransferV1 as u8
+        {
+            continue
+        }
+
+        let params: MoneyTransferParamsV1 = deserialize(&call.data.data[1..])?;
+        if params.outputs.iter().any(|output| &output.coin == coin) {
+            return Ok(true)
+        }
+    }
+
+    Ok(false)
+}
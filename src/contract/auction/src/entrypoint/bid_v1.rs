@@ -0,0 +1,128 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2025 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use darkfi_sdk::{
+    crypto::{ContractId, PublicKey},
+    dark_tree::DarkLeaf,
+    error::{ContractError, ContractResult},
+    msg,
+    pasta::pallas,
+    wasm, ContractCall,
+};
+use darkfi_serial::{deserialize, serialize, Encodable, WriteExt};
+
+use super::coin_minted_in_tx;
+use crate::{
+    error::AuctionError,
+    model::{
+        auction_spend_hook, AuctionBidParamsV1, AuctionBidUpdateV1, AuctionCoinRecord,
+        AuctionRecord,
+    },
+    AuctionFunction, AUCTION_CONTRACT_AUCTIONS_TREE, AUCTION_CONTRACT_COINS_TREE,
+    AUCTION_CONTRACT_ZKAS_BID_NS,
+};
+
+/// `get_metadata` function for `Auction::BidV1`
+pub(crate) fn bid_get_metadata_v1(
+    cid: ContractId,
+    call_idx: usize,
+    calls: Vec<DarkLeaf<ContractCall>>,
+) -> Result<Vec<u8>, ContractError> {
+    let self_ = &calls[call_idx];
+    let params: AuctionBidParamsV1 = deserialize(&self_.data.data[1..])?;
+
+    let auctions_db = wasm::db::db_lookup(cid, AUCTION_CONTRACT_AUCTIONS_TREE)?;
+    let Some(v) = wasm::db::db_get(auctions_db, &serialize(&params.auction_id))? else {
+        msg!("[BidV1] Error: Auction {} not found", params.auction_id);
+        return Err(AuctionError::AuctionNotFound.into())
+    };
+    let record: AuctionRecord = deserialize(&v)?;
+
+    // Bids are sealed, so their value stays hidden, but the bidder has
+    // to prove the bid coin is in the payment token, that it's locked
+    // to this auction, and that its value meets the reserve price.
+    let zk_public_inputs: Vec<(String, Vec<pallas::Base>)> = vec![(
+        AUCTION_CONTRACT_ZKAS_BID_NS.to_string(),
+        vec![
+            params.coin.inner(),
+            record.params.payment_token_id.inner(),
+            auction_spend_hook(cid).inner(),
+            params.auction_id.inner(),
+            pallas::Base::from(record.params.reserve_price),
+        ],
+    )];
+    let signature_pubkeys: Vec<PublicKey> = vec![];
+
+    // Serialize everything gathered and return it
+    let mut metadata = vec![];
+    zk_public_inputs.encode(&mut metadata)?;
+    signature_pubkeys.encode(&mut metadata)?;
+
+    Ok(metadata)
+}
+
+/// `process_instruction` function for `Auction::BidV1`
+pub(crate) fn bid_process_instruction_v1(
+    cid: ContractId,
+    call_idx: usize,
+    calls: Vec<DarkLeaf<ContractCall>>,
+) -> Result<Vec<u8>, ContractError> {
+    let self_ = &calls[call_idx];
+    let params: AuctionBidParamsV1 = deserialize(&self_.data.data[1..])?;
+
+    let auctions_db = wasm::db::db_lookup(cid, AUCTION_CONTRACT_AUCTIONS_TREE)?;
+    let Some(v) = wasm::db::db_get(auctions_db, &serialize(&params.auction_id))? else {
+        msg!("[BidV1] Error: Auction {} not found", params.auction_id);
+        return Err(AuctionError::AuctionNotFound.into())
+    };
+    let record: AuctionRecord = deserialize(&v)?;
+
+    if wasm::util::get_verifying_block_height()? >= record.params.commit_end {
+        msg!("[BidV1] Error: Commit phase of auction {} is over", params.auction_id);
+        return Err(AuctionError::CommitPhaseOver.into())
+    }
+
+    let coins_db = wasm::db::db_lookup(cid, AUCTION_CONTRACT_COINS_TREE)?;
+    if wasm::db::db_contains_key(coins_db, &serialize(&params.coin))? {
+        msg!("[BidV1] Error: Bid coin {} is already registered", params.coin);
+        return Err(AuctionError::CoinAlreadyRegistered.into())
+    }
+
+    if !coin_minted_in_tx(&calls, &params.coin)? {
+        msg!("[BidV1] Error: Bid coin {} is not minted in this tx", params.coin);
+        return Err(AuctionError::CoinNotMinted.into())
+    }
+
+    let update = AuctionBidUpdateV1 { auction_id: params.auction_id, coin: params.coin };
+    let mut update_data = vec![];
+    update_data.write_u8(AuctionFunction::BidV1 as u8)?;
+    update.encode(&mut update_data)?;
+
+    Ok(update_data)
+}
+
+/// `process_update` function for `Auction::BidV1`
+pub(crate) fn bid_process_update_v1(cid: ContractId, update: AuctionBidUpdateV1) -> ContractResult {
+    msg!("[BidV1] Placing bid {} in auction {}", update.coin, update.auction_id);
+    let coin_record =
+        AuctionCoinRecord { auction_id: update.auction_id, is_asset: false, revealed: None };
+    let coins_db = wasm::db::db_lookup(cid, AUCTION_CONTRACT_COINS_TREE)?;
+    wasm::db::db_set(coins_db, &serialize(&update.coin), &serialize(&coin_record))?;
+
+    Ok(())
+}
@@ -0,0 +1,214 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2025 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use darkfi_money_contract::{model::MoneyTransferParamsV1, MoneyFunction};
+use darkfi_sdk::{
+    crypto::{ContractId, PublicKey, MONEY_CONTRACT_ID},
+    dark_tree::DarkLeaf,
+    error::{ContractError, ContractResult},
+    msg,
+    pasta::pallas,
+    wasm, ContractCall,
+};
+use darkfi_serial::{deserialize, serialize, Encodable, WriteExt};
+
+use crate::{
+    error::AuctionError,
+    model::{AuctionClaimParamsV1, AuctionClaimUpdateV1, AuctionCoinRecord, AuctionRecord},
+    AuctionFunction, AUCTION_CONTRACT_AUCTIONS_TREE, AUCTION_CONTRACT_COINS_TREE,
+    AUCTION_CONTRACT_ZKAS_CLAIM_NS,
+};
+
+/// Grab the child `Money` call of an `Auction::Claim` call, returning a flag
+/// indicating if it's a `Money::OtcSwap`, along with its parameters.
+fn child_money_call(
+    call_idx: usize,
+    calls: &[DarkLeaf<ContractCall>],
+) -> Result<(bool, MoneyTransferParamsV1), ContractError> {
+    let self_ = &calls[call_idx];
+    if self_.children_indexes.len() != 1 {
+        msg!("[ClaimV1] Error: Expected exactly one child call");
+        return Err(AuctionError::InvalidChildCall.into())
+    }
+
+    let child = &calls[self_.children_indexes[0]].data;
+    if child.contract_id != *MONEY_CONTRACT_ID || child.data.is_empty() {
+        msg!("[ClaimV1] Error: Child call is not a Money call");
+        return Err(AuctionError::InvalidChildCall.into())
+    }
+
+    let is_swap = match MoneyFunction::try_from(child.data[0])? {
+        MoneyFunction::TransferV1 => false,
+        MoneyFunction::OtcSwapV1 => true,
+        _ => {
+            msg!("[ClaimV1] Error: Child call is not a Money::Transfer or Money::OtcSwap");
+            return Err(AuctionError::InvalidChildCall.into())
+        }
+    };
+
+    Ok((is_swap, deserialize(&child.data[1..])?))
+}
+
+/// `get_metadata` function for `Auction::ClaimV1`
+pub(crate) fn claim_get_metadata_v1(
+    _cid: ContractId,
+    call_idx: usize,
+    calls: Vec<DarkLeaf<ContractCall>>,
+) -> Result<Vec<u8>, ContractError> {
+    let self_ = &calls[call_idx];
+    let params: AuctionClaimParamsV1 = deserialize(&self_.data.data[1..])?;
+    let (_, child_params) = child_money_call(call_idx, &calls)?;
+
+    if params.coins.len() != child_params.inputs.len() {
+        msg!("[ClaimV1] Error: Claimed coins don't match the child call inputs");
+        return Err(AuctionError::ClaimInputsMismatch.into())
+    }
+
+    // For each spent input we have to prove its nullifier is derived
+    // from the claimed coin. The child call signatures authorize the
+    // actual spend, so no signatures are needed here.
+    let mut zk_public_inputs: Vec<(String, Vec<pallas::Base>)> = vec![];
+    for (coin, input) in params.coins.iter().zip(child_params.inputs.iter()) {
+        zk_public_inputs.push((
+            AUCTION_CONTRACT_ZKAS_CLAIM_NS.to_string(),
+            vec![coin.inner(), input.nullifier.inner()],
+        ));
+    }
+    let signature_pubkeys: Vec<PublicKey> = vec![];
+
+    // Serialize everything gathered and return it
+    let mut metadata = vec![];
+    zk_public_inputs.encode(&mut metadata)?;
+    signature_pubkeys.encode(&mut metadata)?;
+
+    Ok(metadata)
+}
+
+/// `process_instruction` function for `Auction::ClaimV1`
+pub(crate) fn claim_process_instruction_v1(
+    cid: ContractId,
+    call_idx: usize,
+    calls: Vec<DarkLeaf<ContractCall>>,
+) -> Result<Vec<u8>, ContractError> {
+    let self_ = &calls[call_idx];
+    let params: AuctionClaimParamsV1 = deserialize(&self_.data.data[1..])?;
+    let (is_swap, child_params) = child_money_call(call_idx, &calls)?;
+
+    if params.coins.is_empty() || params.coins.len() != child_params.inputs.len() {
+        msg!("[ClaimV1] Error: Claimed coins don't match the child call inputs");
+        return Err(AuctionError::ClaimInputsMismatch.into())
+    }
+
+    let auctions_db = wasm::db::db_lookup(cid, AUCTION_CONTRACT_AUCTIONS_TREE)?;
+    let Some(v) = wasm::db::db_get(auctions_db, &serialize(&params.auction_id))? else {
+        msg!("[ClaimV1] Error: Auction {} not found", params.auction_id);
+        return Err(AuctionError::AuctionNotFound.into())
+    };
+    let record: AuctionRecord = deserialize(&v)?;
+
+    // Nothing can be claimed before all bids had the chance to be revealed
+    let verifying_block_height = wasm::util::get_verifying_block_height()?;
+    if verifying_block_height < record.params.reveal_end {
+        msg!("[ClaimV1] Error: Auction {} has not ended yet", params.auction_id);
+        return Err(AuctionError::AuctionNotEnded.into())
+    }
+
+    // All the claimed coins must be locked in this auction
+    let coins_db = wasm::db::db_lookup(cid, AUCTION_CONTRACT_COINS_TREE)?;
+    for coin in &params.coins {
+        let Some(v) = wasm::db::db_get(coins_db, &serialize(coin))? else {
+            msg!("[ClaimV1] Error: Coin {} is not registered", coin);
+            return Err(AuctionError::CoinNotRegistered.into())
+        };
+        let coin_record: AuctionCoinRecord = deserialize(&v)?;
+        if coin_record.auction_id != params.auction_id {
+            msg!("[ClaimV1] Error: Coin {} is not locked in this auction", coin);
+            return Err(AuctionError::CoinNotRegistered.into())
+        }
+    }
+
+    let settled = if is_swap {
+        // Settlement: the asset and the winning bid are exchanged atomically
+        if record.settled {
+            msg!("[ClaimV1] Error: Auction {} is already settled", params.auction_id);
+            return Err(AuctionError::AuctionAlreadySettled.into())
+        }
+
+        let Some(winner) = record.winner else {
+            msg!("[ClaimV1] Error: Auction {} has no winning bid", params.auction_id);
+            return Err(AuctionError::NoWinner.into())
+        };
+
+        if params.coins.len() != 2 ||
+            !params.coins.contains(&record.asset_coin) ||
+            !params.coins.contains(&winner)
+        {
+            msg!("[ClaimV1] Error: Settlement must swap the asset and the winning bid");
+            return Err(AuctionError::SettlementCoinsMismatch.into())
+        }
+
+        true
+    } else {
+        // Refund: losing bids can be reclaimed right away, while the asset
+        // and the winning bid can only be unlocked by settling, so neither
+        // side can back out. If there is no winner, the asset can be
+        // reclaimed right away as well.
+        for coin in &params.coins {
+            let locked = match record.winner {
+                Some(winner) => *coin == record.asset_coin || *coin == winner,
+                None => false,
+            };
+
+            if locked {
+                msg!("[ClaimV1] Error: Coin {} is still locked", coin);
+                return Err(AuctionError::CoinStillLocked.into())
+            }
+        }
+
+        false
+    };
+
+    let update = AuctionClaimUpdateV1 { auction_id: params.auction_id, settled };
+    let mut update_data = vec![];
+    update_data.write_u8(AuctionFunction::ClaimV1 as u8)?;
+    update.encode(&mut update_data)?;
+
+    Ok(update_data)
+}
+
+/// `process_update` function for `Auction::ClaimV1`
+pub(crate) fn claim_process_update_v1(
+    cid: ContractId,
+    update: AuctionClaimUpdateV1,
+) -> ContractResult {
+    // Spent coins are tracked by the `Money` nullifiers, so we only
+    // have to keep track of the auction settlement.
+    if !update.settled {
+        msg!("[ClaimV1] Refunding coins of auction {}", update.auction_id);
+        return Ok(())
+    }
+
+    msg!("[ClaimV1] Settling auction {}", update.auction_id);
+    let auctions_db = wasm::db::db_lookup(cid, AUCTION_CONTRACT_AUCTIONS_TREE)?;
+    let key = serialize(&update.auction_id);
+    let mut record: AuctionRecord = deserialize(&wasm::db::db_get(auctions_db, &key)?.unwrap())?;
+    record.settled = true;
+    wasm::db::db_set(auctions_db, &key, &serialize(&record))?;
+
+    Ok(())
+}
@@ -0,0 +1,141 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2025 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use darkfi_sdk::{
+    crypto::{ContractId, PublicKey},
+    dark_tree::DarkLeaf,
+    error::{ContractError, ContractResult},
+    msg,
+    pasta::pallas,
+    wasm, ContractCall,
+};
+use darkfi_serial::{deserialize, serialize, Encodable, WriteExt};
+
+use super::coin_minted_in_tx;
+use crate::{
+    error::AuctionError,
+    model::{
+        auction_spend_hook, AuctionCoinRecord, AuctionCreateParamsV1, AuctionCreateUpdateV1,
+        AuctionId, AuctionRecord,
+    },
+    AuctionFunction, AUCTION_CONTRACT_AUCTIONS_TREE, AUCTION_CONTRACT_COINS_TREE,
+    AUCTION_CONTRACT_ZKAS_COIN_NS,
+};
+
+/// `get_metadata` function for `Auction::CreateV1`
+pub(crate) fn create_get_metadata_v1(
+    cid: ContractId,
+    call_idx: usize,
+    calls: Vec<DarkLeaf<ContractCall>>,
+) -> Result<Vec<u8>, ContractError> {
+    let self_ = &calls[call_idx];
+    let params: AuctionCreateParamsV1 = deserialize(&self_.data.data[1..])?;
+
+    // The seller has to prove the asset coin holds the auctioned asset,
+    // and that it's locked to this auction.
+    let auction_id = AuctionId::derive(&params.params);
+    let zk_public_inputs: Vec<(String, Vec<pallas::Base>)> = vec![(
+        AUCTION_CONTRACT_ZKAS_COIN_NS.to_string(),
+        vec![
+            params.asset_coin.inner(),
+            pallas::Base::from(params.params.asset_amount),
+            params.params.asset_token_id.inner(),
+            auction_spend_hook(cid).inner(),
+            auction_id.inner(),
+        ],
+    )];
+    let signature_pubkeys: Vec<PublicKey> = vec![];
+
+    // Serialize everything gathered and return it
+    let mut metadata = vec![];
+    zk_public_inputs.encode(&mut metadata)?;
+    signature_pubkeys.encode(&mut metadata)?;
+
+    Ok(metadata)
+}
+
+/// `process_instruction` function for `Auction::CreateV1`
+pub(crate) fn create_process_instruction_v1(
+    cid: ContractId,
+    call_idx: usize,
+    calls: Vec<DarkLeaf<ContractCall>>,
+) -> Result<Vec<u8>, ContractError> {
+    let self_ = &calls[call_idx];
+    let params: AuctionCreateParamsV1 = deserialize(&self_.data.data[1..])?;
+
+    if !params.params.is_valid() {
+        msg!("[CreateV1] Error: Invalid auction parameters");
+        return Err(AuctionError::InvalidParams.into())
+    }
+
+    // Bids must be able to be placed
+    if params.params.commit_end <= wasm::util::get_verifying_block_height()? {
+        msg!("[CreateV1] Error: Commit phase ends in the past");
+        return Err(AuctionError::InvalidParams.into())
+    }
+
+    let auction_id = AuctionId::derive(&params.params);
+    let auctions_db = wasm::db::db_lookup(cid, AUCTION_CONTRACT_AUCTIONS_TREE)?;
+    if wasm::db::db_contains_key(auctions_db, &serialize(&auction_id))? {
+        msg!("[CreateV1] Error: Auction {} already exists", auction_id);
+        return Err(AuctionError::AuctionAlreadyExists.into())
+    }
+
+    let coins_db = wasm::db::db_lookup(cid, AUCTION_CONTRACT_COINS_TREE)?;
+    if wasm::db::db_contains_key(coins_db, &serialize(&params.asset_coin))? {
+        msg!("[CreateV1] Error: Asset coin {} is already registered", params.asset_coin);
+        return Err(AuctionError::CoinAlreadyRegistered.into())
+    }
+
+    if !coin_minted_in_tx(&calls, &params.asset_coin)? {
+        msg!("[CreateV1] Error: Asset coin {} is not minted in this tx", params.asset_coin);
+        return Err(AuctionError::CoinNotMinted.into())
+    }
+
+    let record = AuctionRecord {
+        params: params.params,
+        asset_coin: params.asset_coin,
+        winner: None,
+        highest_bid: 0,
+        settled: false,
+    };
+
+    let update = AuctionCreateUpdateV1 { auction_id, record };
+    let mut update_data = vec![];
+    update_data.write_u8(AuctionFunction::CreateV1 as u8)?;
+    update.encode(&mut update_data)?;
+
+    Ok(update_data)
+}
+
+/// `process_update` function for `Auction::CreateV1`
+pub(crate) fn create_process_update_v1(
+    cid: ContractId,
+    update: AuctionCreateUpdateV1,
+) -> ContractResult {
+    msg!("[CreateV1] Creating auction {}", update.auction_id);
+    let auctions_db = wasm::db::db_lookup(cid, AUCTION_CONTRACT_AUCTIONS_TREE)?;
+    wasm::db::db_set(auctions_db, &serialize(&update.auction_id), &serialize(&update.record))?;
+
+    let coin_record =
+        AuctionCoinRecord { auction_id: update.auction_id, is_asset: true, revealed: None };
+    let coins_db = wasm::db::db_lookup(cid, AUCTION_CONTRACT_COINS_TREE)?;
+    wasm::db::db_set(coins_db, &serialize(&update.record.asset_coin), &serialize(&coin_record))?;
+
+    Ok(())
+}
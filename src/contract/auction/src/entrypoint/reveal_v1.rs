@@ -0,0 +1,163 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2025 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use darkfi_sdk::{
+    crypto::{ContractId, PublicKey},
+    dark_tree::DarkLeaf,
+    error::{ContractError, ContractResult},
+    msg,
+    pasta::pallas,
+    wasm, ContractCall,
+};
+use darkfi_serial::{deserialize, serialize, Encodable, WriteExt};
+
+use crate::{
+    error::AuctionError,
+    model::{
+        auction_spend_hook, AuctionCoinRecord, AuctionRecord, AuctionRevealParamsV1,
+        AuctionRevealUpdateV1,
+    },
+    AuctionFunction, AUCTION_CONTRACT_AUCTIONS_TREE, AUCTION_CONTRACT_COINS_TREE,
+    AUCTION_CONTRACT_ZKAS_COIN_NS,
+};
+
+/// `get_metadata` function for `Auction::RevealV1`
+pub(crate) fn reveal_get_metadata_v1(
+    cid: ContractId,
+    call_idx: usize,
+    calls: Vec<DarkLeaf<ContractCall>>,
+) -> Result<Vec<u8>, ContractError> {
+    let self_ = &calls[call_idx];
+    let params: AuctionRevealParamsV1 = deserialize(&self_.data.data[1..])?;
+
+    let auctions_db = wasm::db::db_lookup(cid, AUCTION_CONTRACT_AUCTIONS_TREE)?;
+    let Some(v) = wasm::db::db_get(auctions_db, &serialize(&params.auction_id))? else {
+        msg!("[RevealV1] Error: Auction {} not found", params.auction_id);
+        return Err(AuctionError::AuctionNotFound.into())
+    };
+    let record: AuctionRecord = deserialize(&v)?;
+
+    // The bidder has to prove the bid coin holds the revealed value in
+    // the payment token, and that it's locked to this auction.
+    let zk_public_inputs: Vec<(String, Vec<pallas::Base>)> = vec![(
+        AUCTION_CONTRACT_ZKAS_COIN_NS.to_string(),
+        vec![
+            params.coin.inner(),
+            pallas::Base::from(params.value),
+            record.params.payment_token_id.inner(),
+            auction_spend_hook(cid).inner(),
+            params.auction_id.inner(),
+        ],
+    )];
+    let signature_pubkeys: Vec<PublicKey> = vec![];
+
+    // Serialize everything gathered and return it
+    let mut metadata = vec![];
+    zk_public_inputs.encode(&mut metadata)?;
+    signature_pubkeys.encode(&mut metadata)?;
+
+    Ok(metadata)
+}
+
+/// `process_instruction` function for `Auction::RevealV1`
+pub(crate) fn reveal_process_instruction_v1(
+    cid: ContractId,
+    call_idx: usize,
+    calls: Vec<DarkLeaf<ContractCall>>,
+) -> Result<Vec<u8>, ContractError> {
+    let self_ = &calls[call_idx];
+    let params: AuctionRevealParamsV1 = deserialize(&self_.data.data[1..])?;
+
+    let auctions_db = wasm::db::db_lookup(cid, AUCTION_CONTRACT_AUCTIONS_TREE)?;
+    let Some(v) = wasm::db::db_get(auctions_db, &serialize(&params.auction_id))? else {
+        msg!("[RevealV1] Error: Auction {} not found", params.auction_id);
+        return Err(AuctionError::AuctionNotFound.into())
+    };
+    let record: AuctionRecord = deserialize(&v)?;
+
+    let verifying_block_height = wasm::util::get_verifying_block_height()?;
+    if verifying_block_height < record.params.commit_end ||
+        verifying_block_height >= record.params.reveal_end
+    {
+        msg!("[RevealV1] Error: Auction {} is not in its reveal phase", params.auction_id);
+        return Err(AuctionError::NotRevealPhase.into())
+    }
+
+    let coins_db = wasm::db::db_lookup(cid, AUCTION_CONTRACT_COINS_TREE)?;
+    let Some(v) = wasm::db::db_get(coins_db, &serialize(&params.coin))? else {
+        msg!("[RevealV1] Error: Bid coin {} is not registered", params.coin);
+        return Err(AuctionError::CoinNotRegistered.into())
+    };
+    let coin_record: AuctionCoinRecord = deserialize(&v)?;
+
+    if coin_record.auction_id != params.auction_id || coin_record.is_asset {
+        msg!("[RevealV1] Error: Coin {} is not a bid of this auction", params.coin);
+        return Err(AuctionError::CoinNotRegistered.into())
+    }
+
+    if coin_record.revealed.is_some() {
+        msg!("[RevealV1] Error: Bid {} is already revealed", params.coin);
+        return Err(AuctionError::BidAlreadyRevealed.into())
+    }
+
+    if params.value < record.params.reserve_price {
+        msg!("[RevealV1] Error: Bid {} is below the reserve price", params.coin);
+        return Err(AuctionError::BidBelowReserve.into())
+    }
+
+    // Ties are won by the bid revealed first
+    let is_highest = record.winner.is_none() || params.value > record.highest_bid;
+
+    let update = AuctionRevealUpdateV1 {
+        auction_id: params.auction_id,
+        coin: params.coin,
+        value: params.value,
+        is_highest,
+    };
+    let mut update_data = vec![];
+    update_data.write_u8(AuctionFunction::RevealV1 as u8)?;
+    update.encode(&mut update_data)?;
+
+    Ok(update_data)
+}
+
+/// `process_update` function for `Auction::RevealV1`
+pub(crate) fn reveal_process_update_v1(
+    cid: ContractId,
+    update: AuctionRevealUpdateV1,
+) -> ContractResult {
+    msg!("[RevealV1] Revealing bid {} in auction {}", update.coin, update.auction_id);
+    let coins_db = wasm::db::db_lookup(cid, AUCTION_CONTRACT_COINS_TREE)?;
+    let key = serialize(&update.coin);
+    let mut coin_record: AuctionCoinRecord =
+        deserialize(&wasm::db::db_get(coins_db, &key)?.unwrap())?;
+    coin_record.revealed = Some(update.value);
+    wasm::db::db_set(coins_db, &key, &serialize(&coin_record))?;
+
+    if update.is_highest {
+        let auctions_db = wasm::db::db_lookup(cid, AUCTION_CONTRACT_AUCTIONS_TREE)?;
+        let key = serialize(&update.auction_id);
+        let mut record: AuctionRecord =
+            deserialize(&wasm::db::db_get(auctions_db, &key)?.unwrap())?;
+        record.winner = Some(update.coin);
+        record.highest_bid = update.value;
+        wasm::db::db_set(auctions_db, &key, &serialize(&record))?;
+    }
+
+    Ok(())
+}
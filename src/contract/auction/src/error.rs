@@ -0,0 +1,97 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2025 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use darkfi_sdk::error::ContractError;
+
+#[derive(Debug, Clone, thiserror::Error)]
+pub enum AuctionError {
+    #[error("Invalid auction parameters")]
+    InvalidParams,
+
+    #[error("Auction already exists")]
+    AuctionAlreadyExists,
+
+    #[error("Auction not found")]
+    AuctionNotFound,
+
+    #[error("Coin is not minted by a Money::Transfer in this transaction")]
+    CoinNotMinted,
+
+    #[error("Coin is already registered")]
+    CoinAlreadyRegistered,
+
+    #[error("Coin is not registered for this auction")]
+    CoinNotRegistered,
+
+    #[error("Auction commit phase is over")]
+    CommitPhaseOver,
+
+    #[error("Auction is not in its reveal phase")]
+    NotRevealPhase,
+
+    #[error("Bid is already revealed")]
+    BidAlreadyRevealed,
+
+    #[error("Bid is below the reserve price")]
+    BidBelowReserve,
+
+    #[error("Auction has not ended yet")]
+    AuctionNotEnded,
+
+    #[error("Auction is already settled")]
+    AuctionAlreadySettled,
+
+    #[error("Auction has no winning bid")]
+    NoWinner,
+
+    #[error("Settlement coins mismatch")]
+    SettlementCoinsMismatch,
+
+    #[error("Coin is still locked")]
+    CoinStillLocked,
+
+    #[error("Invalid child call")]
+    InvalidChildCall,
+
+    #[error("Claimed coins don't match the spent inputs")]
+    ClaimInputsMismatch,
+}
+
+impl From<AuctionError> for ContractError {
+    fn from(e: AuctionError) -> Self {
+        match e {
+            AuctionError::InvalidParams => Self::Custom(1),
+            AuctionError::AuctionAlreadyExists => Self::Custom(2),
+            AuctionError::AuctionNotFound => Self::Custom(3),
+            AuctionError::CoinNotMinted => Self::Custom(4),
+            AuctionError::CoinAlreadyRegistered => Self::Custom(5),
+            AuctionError::CoinNotRegistered => Self::Custom(6),
+            AuctionError::CommitPhaseOver => Self::Custom(7),
+            AuctionError::NotRevealPhase => Self::Custom(8),
+            AuctionError::BidAlreadyRevealed => Self::Custom(9),
+            AuctionError::BidBelowReserve => Self::Custom(10),
+            AuctionError::AuctionNotEnded => Self::Custom(11),
+            AuctionError::AuctionAlreadySettled => Self::Custom(12),
+            AuctionError::NoWinner => Self::Custom(13),
+            AuctionError::SettlementCoinsMismatch => Self::Custom(14),
+            AuctionError::CoinStillLocked => Self::Custom(15),
+            AuctionError::InvalidChildCall => Self::Custom(16),
+            AuctionError::ClaimInputsMismatch => Self::Custom(17),
+        }
+    }
+}
@@ -0,0 +1,84 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2025 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Smart contract implementing sealed-bid auctions on top of the money contract.
+//!
+//! A seller locks the auctioned asset in a `Money` coin, and bidders lock
+//! their bids in shielded `Money` coins, so bid amounts stay hidden during
+//! the commit phase. During the reveal phase, bidders prove in ZK what their
+//! locked coin holds, and the highest valid bid wins. All locked coins carry
+//! the `Auction::Claim` spend hook, which enforces that the asset and the
+//! winning bid can only be exchanged atomically, and that everything else
+//! can be refunded once the auction is over. Settlement is binding: once
+//! a winner is revealed, neither the asset nor the winning bid can be
+//! refunded, so the only way to unlock them is to settle.
+
+use darkfi_sdk::error::ContractError;
+
+/// Functions available in the contract
+#[repr(u8)]
+#[derive(PartialEq, Debug)]
+pub enum AuctionFunction {
+    CreateV1 = 0x00,
+    BidV1 = 0x01,
+    RevealV1 = 0x02,
+    ClaimV1 = 0x03,
+}
+
+impl TryFrom<u8> for AuctionFunction {
+    type Error = ContractError;
+
+    fn try_from(b: u8) -> core::result::Result<Self, Self::Error> {
+        match b {
+            0x00 => Ok(Self::CreateV1),
+            0x01 => Ok(Self::BidV1),
+            0x02 => Ok(Self::RevealV1),
+            0x03 => Ok(Self::ClaimV1),
+            _ => Err(ContractError::InvalidFunction),
+        }
+    }
+}
+
+/// Internal contract errors
+pub mod error;
+
+/// Call parameters definitions
+pub mod model;
+
+#[cfg(not(feature = "no-entrypoint"))]
+/// WASM entrypoint functions
+pub mod entrypoint;
+
+#[cfg(feature = "client")]
+/// Client API for interaction with this smart contract
+pub mod client;
+
+// These are the different sled trees that will be created
+pub const AUCTION_CONTRACT_INFO_TREE: &str = "info";
+pub const AUCTION_CONTRACT_AUCTIONS_TREE: &str = "auctions";
+pub const AUCTION_CONTRACT_COINS_TREE: &str = "coins";
+
+// These are keys inside the info tree
+pub const AUCTION_CONTRACT_DB_VERSION: &[u8] = b"db_version";
+
+/// zkas locked coin opening circuit namespace
+pub const AUCTION_CONTRACT_ZKAS_COIN_NS: &str = "AuctionCoin_V1";
+/// zkas sealed bid placement circuit namespace
+pub const AUCTION_CONTRACT_ZKAS_BID_NS: &str = "AuctionBid_V1";
+/// zkas locked coin spending circuit namespace
+pub const AUCTION_CONTRACT_ZKAS_CLAIM_NS: &str = "AuctionClaim_V1";
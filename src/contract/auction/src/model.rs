@@ -0,0 +1,208 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2025 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use darkfi_money_contract::model::{Coin, TokenId};
+use darkfi_sdk::{
+    crypto::{pasta_prelude::PrimeField, util::hash_to_base, ContractId, FuncId, FuncRef},
+    error::ContractError,
+    pasta::pallas,
+};
+use darkfi_serial::{serialize, SerialDecodable, SerialEncodable};
+
+#[cfg(feature = "client")]
+use darkfi_serial::async_trait;
+
+use crate::AuctionFunction;
+
+/// BLAKE2b personalization used to derive an [`AuctionId`]
+pub const AUCTION_ID_PERSONALIZATION: &[u8] = b"DarkFi_AuctionID";
+
+/// Parameters of an auction, fixed at creation
+#[derive(Copy, Clone, Debug, Eq, PartialEq, SerialEncodable, SerialDecodable)]
+pub struct AuctionParams {
+    /// Token ID of the auctioned asset
+    pub asset_token_id: TokenId,
+    /// Amount of the auctioned asset
+    pub asset_amount: u64,
+    /// Token ID bids have to be made in
+    pub payment_token_id: TokenId,
+    /// Minimum value a bid has to have to be able to win
+    pub reserve_price: u64,
+    /// Block height at which the commit phase ends and the reveal phase starts
+    pub commit_end: u32,
+    /// Block height at which the reveal phase ends and settlement can happen.
+    /// Once there is a winner, the asset and the winning bid can only be
+    /// unlocked by settling the auction.
+    pub reveal_end: u32,
+    /// Random nonce making the `AuctionId` unique
+    pub nonce: pallas::Base,
+}
+
+impl AuctionParams {
+    /// Check that the auction phases are ordered and the asset is not empty
+    pub fn is_valid(&self) -> bool {
+        self.asset_amount > 0 && self.commit_end < self.reveal_end
+    }
+}
+
+/// AuctionId represents the on-chain identifier of an auction.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, SerialEncodable, SerialDecodable)]
+pub struct AuctionId(pallas::Base);
+
+impl AuctionId {
+    /// Derives an `AuctionId` from its parameters
+    pub fn derive(params: &AuctionParams) -> Self {
+        Self(hash_to_base(AUCTION_ID_PERSONALIZATION, &[&serialize(params)]))
+    }
+
+    /// Get the inner `pallas::Base` element.
+    pub fn inner(&self) -> pallas::Base {
+        self.0
+    }
+
+    /// Create an `AuctionId` object from given bytes, erroring if the input
+    /// bytes are noncanonical.
+    pub fn from_bytes(x: [u8; 32]) -> Result<Self, ContractError> {
+        match pallas::Base::from_repr(x).into() {
+            Some(v) => Ok(Self(v)),
+            None => Err(ContractError::IoError(
+                "Failed to instantiate AuctionId from bytes".to_string(),
+            )),
+        }
+    }
+
+    /// Convert the `AuctionId` type into 32 raw bytes
+    pub fn to_bytes(&self) -> [u8; 32] {
+        self.0.to_repr()
+    }
+}
+
+use core::str::FromStr;
+darkfi_sdk::fp_from_bs58!(AuctionId);
+darkfi_sdk::fp_to_bs58!(AuctionId);
+darkfi_sdk::ty_from_fp!(AuctionId);
+
+/// The spend hook all coins locked in an auction have to carry,
+/// for the given auction contract ID.
+pub fn auction_spend_hook(contract_id: ContractId) -> FuncId {
+    FuncRef { contract_id, func_code: AuctionFunction::ClaimV1 as u8 }.to_func_id()
+}
+
+/// On-chain record of an auction
+#[derive(Copy, Clone, Debug, Eq, PartialEq, SerialEncodable, SerialDecodable)]
+pub struct AuctionRecord {
+    /// Auction parameters
+    pub params: AuctionParams,
+    /// Coin holding the locked asset
+    pub asset_coin: Coin,
+    /// Current highest revealed bid coin, if any
+    pub winner: Option<Coin>,
+    /// Value of the current highest revealed bid
+    pub highest_bid: u64,
+    /// Flag indicating the asset and the winning bid have been exchanged
+    pub settled: bool,
+}
+
+/// On-chain record of a coin locked in an auction
+#[derive(Copy, Clone, Debug, Eq, PartialEq, SerialEncodable, SerialDecodable)]
+pub struct AuctionCoinRecord {
+    /// The auction this coin is locked in
+    pub auction_id: AuctionId,
+    /// Flag indicating this is the asset coin, instead of a bid
+    pub is_asset: bool,
+    /// Revealed value of the bid, if it has been revealed
+    pub revealed: Option<u64>,
+}
+
+/// Parameters for `Auction::Create`
+#[derive(Clone, Debug, SerialEncodable, SerialDecodable)]
+pub struct AuctionCreateParamsV1 {
+    /// Auction parameters
+    pub params: AuctionParams,
+    /// Coin holding the locked asset, minted in the same transaction
+    pub asset_coin: Coin,
+}
+
+/// State update for `Auction::Create`
+#[derive(Clone, Debug, SerialEncodable, SerialDecodable)]
+pub struct AuctionCreateUpdateV1 {
+    /// The created `AuctionId`
+    pub auction_id: AuctionId,
+    /// The new auction record
+    pub record: AuctionRecord,
+}
+
+/// Parameters for `Auction::Bid`
+#[derive(Clone, Debug, SerialEncodable, SerialDecodable)]
+pub struct AuctionBidParamsV1 {
+    /// The `AuctionId` to bid in
+    pub auction_id: AuctionId,
+    /// Coin holding the locked bid, minted in the same transaction
+    pub coin: Coin,
+}
+
+/// State update for `Auction::Bid`
+#[derive(Clone, Debug, SerialEncodable, SerialDecodable)]
+pub struct AuctionBidUpdateV1 {
+    /// The `AuctionId` the bid was made in
+    pub auction_id: AuctionId,
+    /// Coin holding the locked bid
+    pub coin: Coin,
+}
+
+/// Parameters for `Auction::Reveal`
+#[derive(Clone, Debug, SerialEncodable, SerialDecodable)]
+pub struct AuctionRevealParamsV1 {
+    /// The `AuctionId` the bid was made in
+    pub auction_id: AuctionId,
+    /// Coin holding the locked bid
+    pub coin: Coin,
+    /// Value of the bid
+    pub value: u64,
+}
+
+/// State update for `Auction::Reveal`
+#[derive(Clone, Debug, SerialEncodable, SerialDecodable)]
+pub struct AuctionRevealUpdateV1 {
+    /// The `AuctionId` the bid was made in
+    pub auction_id: AuctionId,
+    /// Coin holding the locked bid
+    pub coin: Coin,
+    /// Value of the bid
+    pub value: u64,
+    /// Flag indicating this bid is now the highest one
+    pub is_highest: bool,
+}
+
+/// Parameters for `Auction::Claim`
+#[derive(Clone, Debug, SerialEncodable, SerialDecodable)]
+pub struct AuctionClaimParamsV1 {
+    /// The `AuctionId` the spent coins are locked in
+    pub auction_id: AuctionId,
+    /// Locked coins spent by the child `Money` call, in input order
+    pub coins: Vec<Coin>,
+}
+
+/// State update for `Auction::Claim`
+#[derive(Clone, Debug, SerialEncodable, SerialDecodable)]
+pub struct AuctionClaimUpdateV1 {
+    /// The `AuctionId` the spent coins were locked in
+    pub auction_id: AuctionId,
+    /// Flag indicating this claim settled the auction
+    pub settled: bool,
+}
@@ -0,0 +1,294 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2025 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Integration test for sealed-bid auctions.
+//!
+//! Alice auctions some of her tokens, while Bob and Charlie place sealed
+//! bids in Bob's tokens. Bids in the wrong token or below the reserve price
+//! are rejected when placed. After the reveal phase, Charlie gets his losing
+//! bid refunded, while the asset and Bob's winning bid can only be unlocked
+//! by Alice and Bob settling the auction atomically.
+
+use darkfi::Result;
+use darkfi_auction_contract::model::{AuctionId, AuctionParams};
+use darkfi_contract_test_harness::{init_logger, Holder, TestHarness};
+use darkfi_sdk::{
+    crypto::{pasta_prelude::*, BaseBlind},
+    pasta::pallas,
+};
+use log::info;
+use rand::rngs::OsRng;
+
+#[test]
+fn auction_integration() -> Result<()> {
+    smol::block_on(async {
+        init_logger();
+
+        // Holders this test will use
+        const HOLDERS: [Holder; 3] = [Holder::Alice, Holder::Bob, Holder::Charlie];
+
+        // Some numbers we want to assert
+        const ASSET_AMOUNT: u64 = 10;
+        const ASSET_SUPPLY: u64 = 1000;
+        const RESERVE_PRICE: u64 = 100;
+        const BOB_BID: u64 = 300;
+        const CHARLIE_BID: u64 = 200;
+        const PAYMENT_SUPPLY: u64 = 500;
+
+        // Auction phases
+        const COMMIT_END: u32 = 5;
+        const REVEAL_END: u32 = 10;
+
+        // Initialize harness
+        let mut th = TestHarness::new(&HOLDERS, false).await?;
+
+        // Alice mints the auctioned asset, and Bob mints the payment
+        // token for himself and Charlie.
+        info!(target: "auction", "[Alice] Minting the auctioned asset");
+        let alice_token_blind = BaseBlind::random(&mut OsRng);
+        let bob_token_blind = BaseBlind::random(&mut OsRng);
+        for (minter, recipient, amount, blind) in [
+            (Holder::Alice, Holder::Alice, ASSET_SUPPLY, alice_token_blind),
+            (Holder::Bob, Holder::Bob, PAYMENT_SUPPLY, bob_token_blind),
+            (Holder::Bob, Holder::Charlie, PAYMENT_SUPPLY, bob_token_blind),
+        ] {
+            let (tx, mint_params, auth_params, fee_params) =
+                th.token_mint(amount, &minter, &recipient, blind, None, None, 0).await?;
+
+            for holder in &HOLDERS {
+                th.execute_token_mint_tx(
+                    holder,
+                    tx.clone(),
+                    &mint_params,
+                    &auth_params,
+                    &fee_params,
+                    0,
+                    true,
+                )
+                .await?;
+            }
+        }
+
+        th.assert_trees(&HOLDERS);
+
+        let alice_coins = th.holders.get(&Holder::Alice).unwrap().unspent_money_coins.clone();
+        let bob_coins = th.holders.get(&Holder::Bob).unwrap().unspent_money_coins.clone();
+        let charlie_coins = th.holders.get(&Holder::Charlie).unwrap().unspent_money_coins.clone();
+        let asset_token_id = alice_coins[0].note.token_id;
+        let payment_token_id = bob_coins[0].note.token_id;
+        assert!(charlie_coins[0].note.token_id == payment_token_id);
+
+        info!(target: "auction", "[Alice] Creating the auction");
+        let params = AuctionParams {
+            asset_token_id,
+            asset_amount: ASSET_AMOUNT,
+            payment_token_id,
+            reserve_price: RESERVE_PRICE,
+            commit_end: COMMIT_END,
+            reveal_end: REVEAL_END,
+            nonce: pallas::Base::random(&mut OsRng),
+        };
+        let auction_id = AuctionId::derive(&params);
+
+        let (tx, xfer_params, create_params, fee_params) =
+            th.auction_create(&Holder::Alice, params, &alice_coins, 1).await?;
+
+        let mut asset_coin = None;
+        for holder in &HOLDERS {
+            let found = th
+                .execute_auction_tx(holder, tx.clone(), Some(&xfer_params), &fee_params, 1, true)
+                .await?;
+            if holder == &Holder::Alice {
+                asset_coin = found.into_iter().find(|c| c.coin == create_params.asset_coin);
+            }
+        }
+        let asset_coin = asset_coin.unwrap();
+
+        th.assert_trees(&HOLDERS);
+
+        info!(target: "auction", "[Alice] Bidding in the wrong token must fail");
+        let alice_change: Vec<_> = th
+            .holders
+            .get(&Holder::Alice)
+            .unwrap()
+            .unspent_money_coins
+            .iter()
+            .filter(|c| c.coin != asset_coin.coin)
+            .cloned()
+            .collect();
+        let (tx, _, _, fee_params) = th
+            .auction_bid(
+                &Holder::Alice,
+                auction_id,
+                BOB_BID,
+                asset_token_id,
+                RESERVE_PRICE,
+                &alice_change,
+                2,
+            )
+            .await?;
+        assert!(th
+            .execute_auction_tx(&Holder::Alice, tx, None, &fee_params, 2, false)
+            .await
+            .is_err());
+
+        info!(target: "auction", "[Charlie] Bidding below the reserve price must fail");
+        assert!(th
+            .auction_bid(
+                &Holder::Charlie,
+                auction_id,
+                RESERVE_PRICE - 1,
+                payment_token_id,
+                RESERVE_PRICE,
+                &charlie_coins,
+                2,
+            )
+            .await
+            .is_err());
+
+        info!(target: "auction", "[Bob, Charlie] Placing sealed bids");
+        let mut bid_coins = vec![];
+        for (holder, value, coins) in
+            [(Holder::Bob, BOB_BID, &bob_coins), (Holder::Charlie, CHARLIE_BID, &charlie_coins)]
+        {
+            let (tx, xfer_params, bid_params, fee_params) = th
+                .auction_bid(&holder, auction_id, value, payment_token_id, RESERVE_PRICE, coins, 2)
+                .await?;
+
+            let mut bid_coin = None;
+            for h in &HOLDERS {
+                let found = th
+                    .execute_auction_tx(h, tx.clone(), Some(&xfer_params), &fee_params, 2, true)
+                    .await?;
+                if h == &holder {
+                    bid_coin = found.into_iter().find(|c| c.coin == bid_params.coin);
+                }
+            }
+            bid_coins.push(bid_coin.unwrap());
+        }
+        let bob_bid = bid_coins[0].clone();
+        let charlie_bid = bid_coins[1].clone();
+
+        th.assert_trees(&HOLDERS);
+
+        info!(target: "auction", "[Bob] Revealing during the commit phase must fail");
+        let (tx, _, fee_params) =
+            th.auction_reveal(&Holder::Bob, auction_id, &bob_bid, COMMIT_END - 1).await?;
+        assert!(th
+            .execute_auction_tx(&Holder::Bob, tx, None, &fee_params, COMMIT_END - 1, false)
+            .await
+            .is_err());
+
+        info!(target: "auction", "[Charlie, Bob] Revealing bids");
+        for (holder, bid) in [(Holder::Charlie, &charlie_bid), (Holder::Bob, &bob_bid)] {
+            let (tx, _, fee_params) =
+                th.auction_reveal(&holder, auction_id, bid, COMMIT_END).await?;
+            for h in &HOLDERS {
+                th.execute_auction_tx(h, tx.clone(), None, &fee_params, COMMIT_END, true).await?;
+            }
+        }
+
+        info!(target: "auction", "[Charlie] Refunding before the reveal phase is over must fail");
+        let (tx, _, _, fee_params) =
+            th.auction_refund(&Holder::Charlie, auction_id, &charlie_bid, REVEAL_END - 1).await?;
+        assert!(th
+            .execute_auction_tx(&Holder::Charlie, tx, None, &fee_params, REVEAL_END - 1, false)
+            .await
+            .is_err());
+
+        info!(target: "auction", "[Alice] Refunding the asset while a winner exists must fail");
+        let (tx, _, _, fee_params) =
+            th.auction_refund(&Holder::Alice, auction_id, &asset_coin, REVEAL_END).await?;
+        assert!(th
+            .execute_auction_tx(&Holder::Alice, tx, None, &fee_params, REVEAL_END, false)
+            .await
+            .is_err());
+
+        info!(target: "auction", "[Charlie] Refunding the losing bid");
+        let (tx, xfer_params, _, fee_params) =
+            th.auction_refund(&Holder::Charlie, auction_id, &charlie_bid, REVEAL_END).await?;
+        for holder in &HOLDERS {
+            th.execute_auction_tx(
+                holder,
+                tx.clone(),
+                Some(&xfer_params),
+                &fee_params,
+                REVEAL_END,
+                true,
+            )
+            .await?;
+        }
+
+        th.assert_trees(&HOLDERS);
+
+        info!(target: "auction", "[Bob] Refunding the winning bid must fail, even long after");
+        let (tx, _, _, fee_params) =
+            th.auction_refund(&Holder::Bob, auction_id, &bob_bid, REVEAL_END * 10).await?;
+        assert!(th
+            .execute_auction_tx(&Holder::Bob, tx, None, &fee_params, REVEAL_END * 10, false)
+            .await
+            .is_err());
+
+        info!(target: "auction", "[Alice, Bob] Settling the auction");
+        let (tx, swap_params, _, fee_params) = th
+            .auction_settle(
+                &Holder::Alice,
+                &asset_coin,
+                &Holder::Bob,
+                &bob_bid,
+                auction_id,
+                REVEAL_END,
+            )
+            .await?;
+        for holder in &HOLDERS {
+            th.execute_auction_tx(
+                holder,
+                tx.clone(),
+                Some(&swap_params),
+                &fee_params,
+                REVEAL_END,
+                true,
+            )
+            .await?;
+        }
+
+        th.assert_trees(&HOLDERS);
+
+        // Alice got paid the winning bid, and Bob got the asset
+        let alice_coins = &th.holders.get(&Holder::Alice).unwrap().unspent_money_coins;
+        assert!(alice_coins
+            .iter()
+            .any(|c| c.note.token_id == payment_token_id && c.note.value == BOB_BID));
+        let bob_coins = &th.holders.get(&Holder::Bob).unwrap().unspent_money_coins;
+        assert!(bob_coins
+            .iter()
+            .any(|c| c.note.token_id == asset_token_id && c.note.value == ASSET_AMOUNT));
+
+        // Charlie got his bid back
+        let charlie_coins = &th.holders.get(&Holder::Charlie).unwrap().unspent_money_coins;
+        let charlie_balance: u64 = charlie_coins
+            .iter()
+            .filter(|c| c.note.token_id == payment_token_id)
+            .map(|c| c.note.value)
+            .sum();
+        assert!(charlie_balance == PAYMENT_SUPPLY);
+
+        // Thanks for reading
+        Ok(())
+    })
+}
@@ -163,6 +163,7 @@ impl DaoAuthMoneyTransferCall {
             Witness::Base(Value::known(dao_exec_pub_y)),
             Witness::Base(Value::known(dao_early_exec_pub_x)),
             Witness::Base(Value::known(dao_early_exec_pub_y)),
+            Witness::Base(Value::known(self.dao.members_root)),
             Witness::Base(Value::known(self.dao.bulla_blind.inner())),
             // Dao input user data blind
             Witness::Base(Value::known(self.input_user_data_blind.inner())),
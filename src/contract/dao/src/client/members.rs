@@ -0,0 +1,110 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2025 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use darkfi_sdk::{
+    bridgetree,
+    bridgetree::Hashable,
+    crypto::{
+        constants::MERKLE_DEPTH, pasta_prelude::*, poseidon_hash, MerkleNode, MerkleTree,
+        PublicKey, SecretKey,
+    },
+    pasta::pallas,
+};
+
+use darkfi::{
+    zk::{halo2::Value, Witness},
+    ClientFailed, Result,
+};
+
+use crate::{error::DaoError, model::Dao};
+
+/// Compute the members set leaf of the given member public key
+pub fn dao_member_leaf(public_key: &PublicKey) -> MerkleNode {
+    let (pub_x, pub_y) = public_key.xy();
+    MerkleNode::from(poseidon_hash([pub_x, pub_y]))
+}
+
+/// Create the members set Merkle tree of the given member public keys.
+/// Its root is used as `Dao::members_root`, and members can be marked
+/// to produce their Merkle paths.
+pub fn make_members_tree(members: &[PublicKey]) -> MerkleTree {
+    let mut tree = MerkleTree::new(1);
+    for member in members {
+        tree.append(dao_member_leaf(member));
+        tree.mark();
+    }
+    tree
+}
+
+/// Input proving membership in a DAO members set, used in proposals
+/// and votes. The circuits skip the membership check for DAOs without
+/// a members set, so [`DaoMemberInput::none`] can be used for them.
+pub struct DaoMemberInput {
+    /// Secret key of the member
+    pub secret: SecretKey,
+    /// Position of the member in the members set
+    pub leaf_position: bridgetree::Position,
+    /// Merkle path of the member in the members set
+    pub merkle_path: Vec<MerkleNode>,
+}
+
+impl DaoMemberInput {
+    /// Dummy input for DAOs without a members set
+    pub fn none() -> Self {
+        Self {
+            secret: SecretKey::from(pallas::Base::ONE),
+            leaf_position: 0.into(),
+            merkle_path: vec![MerkleNode::from(pallas::Base::ZERO); MERKLE_DEPTH as usize],
+        }
+    }
+
+    /// Compute the members set root this input proves membership of
+    pub fn root(&self) -> MerkleNode {
+        let position: u64 = self.leaf_position.into();
+        let mut current = dao_member_leaf(&PublicKey::from_secret(self.secret));
+        for (level, sibling) in self.merkle_path.iter().enumerate() {
+            let level = level as u8;
+            current = if position & (1 << level) == 0 {
+                MerkleNode::combine(level.into(), &current, sibling)
+            } else {
+                MerkleNode::combine(level.into(), sibling, &current)
+            };
+        }
+        current
+    }
+
+    /// Verify this input proves membership of the given DAO members set
+    pub(crate) fn verify(&self, dao: &Dao) -> Result<()> {
+        if dao.has_members() && self.root().inner() != dao.members_root {
+            return Err(ClientFailed::VerifyError(DaoError::InvalidMembership.to_string()).into())
+        }
+
+        Ok(())
+    }
+
+    /// Circuit witnesses of this input. It's important to keep these
+    /// in the same order as the zkas code.
+    pub(crate) fn witnesses(&self) -> Vec<Witness> {
+        let leaf_position: u64 = self.leaf_position.into();
+        vec![
+            Witness::Base(Value::known(self.secret.inner())),
+            Witness::Uint32(Value::known(leaf_position.try_into().unwrap())),
+            Witness::MerklePath(Value::known(self.merkle_path.clone().try_into().unwrap())),
+        ]
+    }
+}
@@ -39,3 +39,10 @@ pub use exec::DaoExecCall;
 
 pub mod auth_xfer;
 pub use auth_xfer::DaoAuthMoneyTransferCall;
+
+/// Provides core structs for DAO membership proofs
+///
+/// * `DaoMemberInput` proves membership in a DAO members set.
+/// * `make_members_tree` creates the members set of a DAO.
+pub mod members;
+pub use members::{dao_member_leaf, make_members_tree, DaoMemberInput};
@@ -38,6 +38,7 @@ use darkfi::{
     ClientFailed, Result,
 };
 
+use super::DaoMemberInput;
 use crate::{
     error::DaoError,
     model::{Dao, DaoProposal, DaoProposeParams, DaoProposeParamsInput, VecAuthCallCommit},
@@ -59,6 +60,7 @@ pub struct DaoProposeCall<'a, T: StorageAdapter<Value = pallas::Base>> {
     pub dao_leaf_position: bridgetree::Position,
     pub dao_merkle_path: Vec<MerkleNode>,
     pub dao_merkle_root: MerkleNode,
+    pub member: DaoMemberInput,
     pub signature_secret: SecretKey,
 }
 
@@ -197,9 +199,10 @@ impl<T: StorageAdapter<Value = pallas::Base>> DaoProposeCall<'_, T> {
         if self.dao.to_bulla() != self.proposal.dao_bulla {
             return Err(ClientFailed::VerifyError(DaoError::InvalidCalls.to_string()).into())
         }
+        self.member.verify(&self.dao)?;
         let proposal_bulla = self.proposal.to_bulla();
 
-        let prover_witnesses = vec![
+        let mut prover_witnesses = vec![
             // Proposers total number of gov tokens
             Witness::Base(Value::known(total_funds)),
             Witness::Scalar(Value::known(total_funds_blinds.inner())),
@@ -229,10 +232,14 @@ impl<T: StorageAdapter<Value = pallas::Base>> DaoProposeCall<'_, T> {
             Witness::Base(Value::known(dao_exec_pub_y)),
             Witness::Base(Value::known(dao_early_exec_pub_x)),
             Witness::Base(Value::known(dao_early_exec_pub_y)),
+            Witness::Base(Value::known(self.dao.members_root)),
             Witness::Base(Value::known(self.dao.bulla_blind.inner())),
             Witness::Uint32(Value::known(dao_leaf_position.try_into().unwrap())),
             Witness::MerklePath(Value::known(self.dao_merkle_path.try_into().unwrap())),
         ];
+        // DAO membership proof
+        prover_witnesses.extend(self.member.witnesses());
+
         let public_inputs = vec![
             token_commit,
             self.dao_merkle_root.inner(),
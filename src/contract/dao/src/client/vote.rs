@@ -39,6 +39,7 @@ use darkfi::{
     ClientFailed, Result,
 };
 
+use super::DaoMemberInput;
 use crate::{
     error::DaoError,
     model::{Dao, DaoProposal, DaoVoteParams, DaoVoteParamsInput, VecAuthCallCommit},
@@ -60,6 +61,7 @@ pub struct DaoVoteCall<'a, T: StorageAdapter<Value = pallas::Base>> {
     pub vote_option: bool,
     pub proposal: DaoProposal,
     pub dao: Dao,
+    pub member: DaoMemberInput,
     pub current_blockwindow: u64,
 }
 
@@ -76,6 +78,7 @@ impl<T: StorageAdapter<Value = pallas::Base>> DaoVoteCall<'_, T> {
         if self.dao.to_bulla() != self.proposal.dao_bulla {
             return Err(ClientFailed::VerifyError(DaoError::InvalidCalls.to_string()).into())
         }
+        self.member.verify(&self.dao)?;
         let proposal_bulla = self.proposal.to_bulla();
 
         let mut proofs = vec![];
@@ -257,7 +260,7 @@ impl<T: StorageAdapter<Value = pallas::Base>> DaoVoteCall<'_, T> {
 
         let current_blockwindow = pallas::Base::from(self.current_blockwindow);
 
-        let prover_witnesses = vec![
+        let mut prover_witnesses = vec![
             // Proposal params
             Witness::Base(Value::known(self.proposal.auth_calls.commit())),
             Witness::Base(Value::known(pallas::Base::from(self.proposal.creation_blockwindow))),
@@ -282,6 +285,7 @@ impl<T: StorageAdapter<Value = pallas::Base>> DaoVoteCall<'_, T> {
             Witness::Base(Value::known(dao_exec_pub_y)),
             Witness::Base(Value::known(dao_early_exec_pub_x)),
             Witness::Base(Value::known(dao_early_exec_pub_y)),
+            Witness::Base(Value::known(self.dao.members_root)),
             Witness::Base(Value::known(self.dao.bulla_blind.inner())),
             // Vote
             Witness::Base(Value::known(vote_option)),
@@ -296,6 +300,8 @@ impl<T: StorageAdapter<Value = pallas::Base>> DaoVoteCall<'_, T> {
             // verifiable encryption
             Witness::Base(Value::known(ephem_secret.inner())),
         ];
+        // DAO membership proof
+        prover_witnesses.extend(self.member.witnesses());
 
         let note = [vote_option, yes_vote_blind.inner(), all_vote_value_fp, all_vote_blind.inner()];
         let enc_note =
@@ -94,6 +94,9 @@ pub enum DaoError {
 
     #[error("Wrong output coin")]
     AuthXferWrongOutputCoin,
+
+    #[error("Not a member of the DAO members set")]
+    InvalidMembership,
 }
 
 impl From<DaoError> for ContractError {
@@ -124,6 +127,7 @@ impl From<DaoError> for ContractError {
             DaoError::AuthXferCallNotFoundInParent => Self::Custom(23),
             DaoError::AuthXferWrongNumberOutputs => Self::Custom(24),
             DaoError::AuthXferWrongOutputCoin => Self::Custom(25),
+            DaoError::InvalidMembership => Self::Custom(26),
         }
     }
 }
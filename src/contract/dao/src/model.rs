@@ -62,12 +62,20 @@ pub struct Dao {
     pub exec_public_key: PublicKey,
     /// DAO strongly supported proposals executor public key
     pub early_exec_public_key: PublicKey,
+    /// Merkle root of the DAO members set. When set, proposals and votes
+    /// are restricted to members. Zero for DAOs without a members set.
+    pub members_root: pallas::Base,
     /// DAO bulla blind
     pub bulla_blind: BaseBlind,
 }
 // ANCHOR_END: dao
 
 impl Dao {
+    /// Check if the DAO restricts proposals and votes to a members set
+    pub fn has_members(&self) -> bool {
+        self.members_root != pallas::Base::ZERO
+    }
+
     pub fn to_bulla(&self) -> DaoBulla {
         let proposer_limit = pallas::Base::from(self.proposer_limit);
         let quorum = pallas::Base::from(self.quorum);
@@ -101,7 +109,14 @@ impl Dao {
             early_exec_pub_y,
             self.bulla_blind.inner(),
         ]);
-        DaoBulla(bulla)
+
+        // DAOs with a members set commit to its root on top of the
+        // base bulla, so DAOs without one keep their original bulla.
+        if !self.has_members() {
+            return DaoBulla(bulla)
+        }
+
+        DaoBulla(poseidon_hash([bulla, self.members_root]))
     }
 }
 
@@ -42,11 +42,13 @@ use rand::rngs::OsRng;
 
 // Integration test configuration
 // Holders this test will use:
-// * Alice, Bob, and Charlie are members of the DAO.
+// * Alice, Bob, and Charlie hold the DAO governance tokens.
 // * Dao is the DAO wallet
 // * Rachel is the transfer proposal recipient.
 const HOLDERS: [Holder; 5] =
     [Holder::Alice, Holder::Bob, Holder::Charlie, Holder::Dao, Holder::Rachel];
+// DAO members set, used by the private membership test
+const DAO_MEMBERS: [Holder; 3] = [Holder::Alice, Holder::Bob, Holder::Charlie];
 // DAO gov tokens distribution
 const ALICE_GOV_SUPPLY: u64 = 100_000_000;
 const BOB_GOV_SUPPLY: u64 = 100_000_000;
@@ -65,340 +67,354 @@ const TRANSFER_PROPOSAL_AMOUNT: u64 = 250_000_000;
 
 #[test]
 fn integration_test() -> Result<()> {
-    smol::block_on(async {
-        init_logger();
+    smol::block_on(integration(&[]))
+}
 
-        // Initialize harness
-        let mut th = TestHarness::new(&HOLDERS, false).await?;
+#[test]
+fn integration_test_members() -> Result<()> {
+    smol::block_on(integration(&DAO_MEMBERS))
+}
 
-        // We'll use the ALICE token as the DAO governance token
-        let wallet = th.holders.get_mut(&Holder::Alice).unwrap();
-        //wallet.bench_wasm = true;
-        let mint_authority = wallet.token_mint_authority;
-        let gov_token_blind = BaseBlind::random(&mut OsRng);
+/// Run the full DAO flow. An empty `dao_members` set creates a DAO
+/// without a private members set, like the ones minted before it
+/// existed.
+async fn integration(dao_members: &[Holder]) -> Result<()> {
+    init_logger();
 
-        let auth_func_id = FuncRef {
-            contract_id: *MONEY_CONTRACT_ID,
-            func_code: MoneyFunction::AuthTokenMintV1 as u8,
-        }
-        .to_func_id();
-        let token_attrs = TokenAttributes {
-            auth_parent: auth_func_id,
-            user_data: poseidon_hash([mint_authority.public.x(), mint_authority.public.y()]),
-            blind: gov_token_blind,
-        };
-        let gov_token_id = token_attrs.to_token_id();
-
-        // Block height to verify against
-        let mut current_block_height = 0;
-
-        // DAO parameters
-        let dao_notes_keypair = th.holders.get(&Holder::Dao).unwrap().keypair;
-        let mut rng = Pcg32::new(42);
-        let dao_proposer_keypair = Keypair::random(&mut rng);
-        let dao_proposals_keypair = Keypair::random(&mut rng);
-        let dao_votes_keypair = Keypair::random(&mut rng);
-        let dao_exec_keypair = Keypair::random(&mut rng);
-        let dao_early_exec_keypair = Keypair::random(&mut rng);
-        let dao = Dao {
-            proposer_limit: PROPOSER_LIMIT,
-            quorum: QUORUM,
-            early_exec_quorum: EARLY_EXEC_QUORUM,
-            approval_ratio_base: APPROVAL_RATIO_BASE,
-            approval_ratio_quot: APPROVAL_RATIO_QUOT,
-            gov_token_id,
-            notes_public_key: dao_notes_keypair.public,
-            proposer_public_key: dao_proposer_keypair.public,
-            proposals_public_key: dao_proposals_keypair.public,
-            votes_public_key: dao_votes_keypair.public,
-            exec_public_key: dao_exec_keypair.public,
-            early_exec_public_key: dao_early_exec_keypair.public,
-            bulla_blind: Blind::random(&mut OsRng),
-        };
+    // Initialize harness
+    let mut th = TestHarness::new(&HOLDERS, false).await?;
 
-        // =======================================
-        // Airdrop some treasury tokens to the DAO
-        // =======================================
-        info!("[Dao] Building DAO airdrop tx");
-
-        let spend_hook =
-            FuncRef { contract_id: *DAO_CONTRACT_ID, func_code: DaoFunction::Exec as u8 }
-                .to_func_id();
-
-        let (genesis_mint_tx, genesis_mint_params) = th
-            .genesis_mint(
-                &Holder::Dao,
-                DRK_TOKEN_SUPPLY,
-                Some(spend_hook),
-                Some(dao.to_bulla().inner()),
-            )
-            .await?;
-
-        for holder in &HOLDERS {
-            th.execute_genesis_mint_tx(
-                holder,
-                genesis_mint_tx.clone(),
-                &genesis_mint_params,
-                current_block_height,
-                true,
-            )
-            .await?;
-        }
+    // We'll use the ALICE token as the DAO governance token
+    let wallet = th.holders.get_mut(&Holder::Alice).unwrap();
+    //wallet.bench_wasm = true;
+    let mint_authority = wallet.token_mint_authority;
+    let gov_token_blind = BaseBlind::random(&mut OsRng);
 
-        th.assert_trees(&HOLDERS);
+    let auth_func_id = FuncRef {
+        contract_id: *MONEY_CONTRACT_ID,
+        func_code: MoneyFunction::AuthTokenMintV1 as u8,
+    }
+    .to_func_id();
+    let token_attrs = TokenAttributes {
+        auth_parent: auth_func_id,
+        user_data: poseidon_hash([mint_authority.public.x(), mint_authority.public.y()]),
+        blind: gov_token_blind,
+    };
+    let gov_token_id = token_attrs.to_token_id();
+
+    // Block height to verify against
+    let mut current_block_height = 0;
+
+    // DAO parameters
+    let dao_notes_keypair = th.holders.get(&Holder::Dao).unwrap().keypair;
+    let mut rng = Pcg32::new(42);
+    let dao_proposer_keypair = Keypair::random(&mut rng);
+    let dao_proposals_keypair = Keypair::random(&mut rng);
+    let dao_votes_keypair = Keypair::random(&mut rng);
+    let dao_exec_keypair = Keypair::random(&mut rng);
+    let dao_early_exec_keypair = Keypair::random(&mut rng);
+    let dao = Dao {
+        proposer_limit: PROPOSER_LIMIT,
+        quorum: QUORUM,
+        early_exec_quorum: EARLY_EXEC_QUORUM,
+        approval_ratio_base: APPROVAL_RATIO_BASE,
+        approval_ratio_quot: APPROVAL_RATIO_QUOT,
+        gov_token_id,
+        notes_public_key: dao_notes_keypair.public,
+        proposer_public_key: dao_proposer_keypair.public,
+        proposals_public_key: dao_proposals_keypair.public,
+        votes_public_key: dao_votes_keypair.public,
+        exec_public_key: dao_exec_keypair.public,
+        early_exec_public_key: dao_early_exec_keypair.public,
+        members_root: th.dao_members_root(dao_members),
+        bulla_blind: Blind::random(&mut OsRng),
+    };
+
+    // =======================================
+    // Airdrop some treasury tokens to the DAO
+    // =======================================
+    info!("[Dao] Building DAO airdrop tx");
+
+    let spend_hook =
+        FuncRef { contract_id: *DAO_CONTRACT_ID, func_code: DaoFunction::Exec as u8 }.to_func_id();
+
+    let (genesis_mint_tx, genesis_mint_params) = th
+        .genesis_mint(
+            &Holder::Dao,
+            DRK_TOKEN_SUPPLY,
+            Some(spend_hook),
+            Some(dao.to_bulla().inner()),
+        )
+        .await?;
 
-        let _dao_tokens = &th.holders.get(&Holder::Dao).unwrap().unspent_money_coins;
-        assert!(_dao_tokens.len() == 1);
-        assert!(_dao_tokens[0].note.token_id == *DARK_TOKEN_ID);
-        assert!(_dao_tokens[0].note.value == DRK_TOKEN_SUPPLY);
+    for holder in &HOLDERS {
+        th.execute_genesis_mint_tx(
+            holder,
+            genesis_mint_tx.clone(),
+            &genesis_mint_params,
+            current_block_height,
+            true,
+        )
+        .await?;
+    }
 
-        current_block_height += 1;
+    th.assert_trees(&HOLDERS);
 
-        // ====================
-        // Dao::Mint
-        // Create the DAO bulla
-        // ====================
-        info!("[Dao] Building DAO mint tx");
-        let (dao_mint_tx, dao_mint_params, fee_params) = th
-            .dao_mint(
-                &Holder::Alice,
-                &dao,
-                &dao_notes_keypair.secret,
-                &dao_proposer_keypair.secret,
-                &dao_proposals_keypair.secret,
-                &dao_votes_keypair.secret,
-                &dao_exec_keypair.secret,
-                &dao_early_exec_keypair.secret,
-                current_block_height,
-            )
-            .await?;
-
-        for holder in &HOLDERS {
-            info!("[{holder:?}] Executing DAO Mint tx");
-            th.execute_dao_mint_tx(
-                holder,
-                dao_mint_tx.clone(),
-                &dao_mint_params,
-                &fee_params,
-                current_block_height,
-                true,
-            )
-            .await?;
-        }
+    let _dao_tokens = &th.holders.get(&Holder::Dao).unwrap().unspent_money_coins;
+    assert!(_dao_tokens.len() == 1);
+    assert!(_dao_tokens[0].note.token_id == *DARK_TOKEN_ID);
+    assert!(_dao_tokens[0].note.value == DRK_TOKEN_SUPPLY);
 
-        th.assert_trees(&HOLDERS);
+    current_block_height += 1;
 
-        current_block_height += 1;
+    // ====================
+    // Dao::Mint
+    // Create the DAO bulla
+    // ====================
+    info!("[Dao] Building DAO mint tx");
+    let (dao_mint_tx, dao_mint_params, fee_params) = th
+        .dao_mint(
+            &Holder::Alice,
+            &dao,
+            &dao_notes_keypair.secret,
+            &dao_proposer_keypair.secret,
+            &dao_proposals_keypair.secret,
+            &dao_votes_keypair.secret,
+            &dao_exec_keypair.secret,
+            &dao_early_exec_keypair.secret,
+            current_block_height,
+        )
+        .await?;
 
-        // ======================================
-        // Mint the governance token to 3 holders
-        // ======================================
-        info!("[Dao] Building governance token mint tx for Alice");
-        let (a_token_mint_tx, a_token_mint_params, a_auth_token_mint_params, a_fee_params) = th
-            .token_mint(
-                ALICE_GOV_SUPPLY,
-                &Holder::Alice,
-                &Holder::Alice,
-                gov_token_blind,
-                None,
-                None,
-                current_block_height,
-            )
-            .await?;
-
-        for holder in &HOLDERS {
-            info!("[{holder:?}] Executing governance token mint tx for Alice");
-            th.execute_token_mint_tx(
-                holder,
-                a_token_mint_tx.clone(),
-                &a_token_mint_params,
-                &a_auth_token_mint_params,
-                &a_fee_params,
-                current_block_height,
-                true,
-            )
-            .await?;
-        }
+    for holder in &HOLDERS {
+        info!("[{holder:?}] Executing DAO Mint tx");
+        th.execute_dao_mint_tx(
+            holder,
+            dao_mint_tx.clone(),
+            &dao_mint_params,
+            &fee_params,
+            current_block_height,
+            true,
+        )
+        .await?;
+    }
 
-        th.assert_trees(&HOLDERS);
+    th.assert_trees(&HOLDERS);
 
-        let _alice_tokens = &th.holders.get(&Holder::Alice).unwrap().unspent_money_coins;
-        assert!(_alice_tokens.len() == 1);
-        assert!(_alice_tokens[0].note.token_id == gov_token_id);
-        assert!(_alice_tokens[0].note.value == ALICE_GOV_SUPPLY);
+    current_block_height += 1;
 
-        info!("[Dao] Building governance token mint tx for Bob");
-        let (b_token_mint_tx, b_token_mint_params, b_auth_token_mint_params, b_fee_params) = th
-            .token_mint(
-                BOB_GOV_SUPPLY,
-                &Holder::Alice,
-                &Holder::Bob,
-                gov_token_blind,
-                None,
-                None,
-                current_block_height,
-            )
-            .await?;
-
-        for holder in &HOLDERS {
-            info!("[{holder:?}] Executing governance token mint tx for Bob");
-            th.execute_token_mint_tx(
-                holder,
-                b_token_mint_tx.clone(),
-                &b_token_mint_params,
-                &b_auth_token_mint_params,
-                &b_fee_params,
-                current_block_height,
-                true,
-            )
-            .await?;
-        }
+    // ======================================
+    // Mint the governance token to 3 holders
+    // ======================================
+    info!("[Dao] Building governance token mint tx for Alice");
+    let (a_token_mint_tx, a_token_mint_params, a_auth_token_mint_params, a_fee_params) = th
+        .token_mint(
+            ALICE_GOV_SUPPLY,
+            &Holder::Alice,
+            &Holder::Alice,
+            gov_token_blind,
+            None,
+            None,
+            current_block_height,
+        )
+        .await?;
 
-        th.assert_trees(&HOLDERS);
+    for holder in &HOLDERS {
+        info!("[{holder:?}] Executing governance token mint tx for Alice");
+        th.execute_token_mint_tx(
+            holder,
+            a_token_mint_tx.clone(),
+            &a_token_mint_params,
+            &a_auth_token_mint_params,
+            &a_fee_params,
+            current_block_height,
+            true,
+        )
+        .await?;
+    }
 
-        let _bob_tokens = &th.holders.get(&Holder::Bob).unwrap().unspent_money_coins;
-        assert!(_bob_tokens.len() == 1);
-        assert!(_bob_tokens[0].note.token_id == gov_token_id);
-        assert!(_bob_tokens[0].note.value == BOB_GOV_SUPPLY);
+    th.assert_trees(&HOLDERS);
 
-        info!("[Dao] Building governance token mint tx for Charlie");
-        let (c_token_mint_tx, c_token_mint_params, c_auth_token_mint_params, c_fee_params) = th
-            .token_mint(
-                CHARLIE_GOV_SUPPLY,
-                &Holder::Alice,
-                &Holder::Charlie,
-                gov_token_blind,
-                None,
-                None,
-                current_block_height,
-            )
-            .await?;
-
-        for holder in &HOLDERS {
-            info!("[{holder:?}] Executing governance token mint tx for Charlie");
-            th.execute_token_mint_tx(
-                holder,
-                c_token_mint_tx.clone(),
-                &c_token_mint_params,
-                &c_auth_token_mint_params,
-                &c_fee_params,
-                current_block_height,
-                true,
-            )
-            .await?;
-        }
+    let _alice_tokens = &th.holders.get(&Holder::Alice).unwrap().unspent_money_coins;
+    assert!(_alice_tokens.len() == 1);
+    assert!(_alice_tokens[0].note.token_id == gov_token_id);
+    assert!(_alice_tokens[0].note.value == ALICE_GOV_SUPPLY);
 
-        th.assert_trees(&HOLDERS);
+    info!("[Dao] Building governance token mint tx for Bob");
+    let (b_token_mint_tx, b_token_mint_params, b_auth_token_mint_params, b_fee_params) = th
+        .token_mint(
+            BOB_GOV_SUPPLY,
+            &Holder::Alice,
+            &Holder::Bob,
+            gov_token_blind,
+            None,
+            None,
+            current_block_height,
+        )
+        .await?;
 
-        let _charlie_tokens = &th.holders.get(&Holder::Charlie).unwrap().unspent_money_coins;
-        assert!(_charlie_tokens.len() == 1);
-        assert!(_charlie_tokens[0].note.token_id == gov_token_id);
-        assert!(_charlie_tokens[0].note.value == CHARLIE_GOV_SUPPLY);
+    for holder in &HOLDERS {
+        info!("[{holder:?}] Executing governance token mint tx for Bob");
+        th.execute_token_mint_tx(
+            holder,
+            b_token_mint_tx.clone(),
+            &b_token_mint_params,
+            &b_auth_token_mint_params,
+            &b_fee_params,
+            current_block_height,
+            true,
+        )
+        .await?;
+    }
 
-        current_block_height += 1;
+    th.assert_trees(&HOLDERS);
 
-        // We can add whatever we want in here, even arbitrary text
-        // It's up to the auth module to decide what to do with it.
-        let user_data = pallas::Base::ZERO;
+    let _bob_tokens = &th.holders.get(&Holder::Bob).unwrap().unspent_money_coins;
+    assert!(_bob_tokens.len() == 1);
+    assert!(_bob_tokens[0].note.token_id == gov_token_id);
+    assert!(_bob_tokens[0].note.value == BOB_GOV_SUPPLY);
 
-        // ============================
-        // Execute proposals test cases
-        // ============================
-        info!("[Dao] DAO transfer proposal tx test case");
-        execute_transfer_proposal(
-            &mut th,
-            &dao,
-            &dao_proposer_keypair.secret,
-            &dao_votes_keypair.secret,
-            &dao_exec_keypair.secret,
-            &None,
-            user_data,
-            &mut current_block_height,
-            0,
-            TRANSFER_PROPOSAL_AMOUNT,
-            TRANSFER_PROPOSAL_AMOUNT,
+    info!("[Dao] Building governance token mint tx for Charlie");
+    let (c_token_mint_tx, c_token_mint_params, c_auth_token_mint_params, c_fee_params) = th
+        .token_mint(
+            CHARLIE_GOV_SUPPLY,
+            &Holder::Alice,
+            &Holder::Charlie,
+            gov_token_blind,
+            None,
+            None,
+            current_block_height,
         )
         .await?;
 
-        info!("[Dao] DAO early execution transfer proposal tx test case");
-        execute_transfer_proposal(
-            &mut th,
-            &dao,
-            &dao_proposer_keypair.secret,
-            &dao_votes_keypair.secret,
-            &dao_exec_keypair.secret,
-            &Some(dao_early_exec_keypair.secret),
-            user_data,
-            &mut current_block_height,
-            1,
-            TRANSFER_PROPOSAL_AMOUNT,
-            TRANSFER_PROPOSAL_AMOUNT * 2,
+    for holder in &HOLDERS {
+        info!("[{holder:?}] Executing governance token mint tx for Charlie");
+        th.execute_token_mint_tx(
+            holder,
+            c_token_mint_tx.clone(),
+            &c_token_mint_params,
+            &c_auth_token_mint_params,
+            &c_fee_params,
+            current_block_height,
+            true,
         )
         .await?;
+    }
 
-        info!("[Dao] DAO generic proposal tx test case");
-        execute_generic_proposal(
-            &mut th,
-            &dao,
-            &dao_proposer_keypair.secret,
-            &dao_votes_keypair.secret,
-            &dao_exec_keypair.secret,
-            &None,
-            user_data,
-            &mut current_block_height,
+    th.assert_trees(&HOLDERS);
+
+    let _charlie_tokens = &th.holders.get(&Holder::Charlie).unwrap().unspent_money_coins;
+    assert!(_charlie_tokens.len() == 1);
+    assert!(_charlie_tokens[0].note.token_id == gov_token_id);
+    assert!(_charlie_tokens[0].note.value == CHARLIE_GOV_SUPPLY);
+
+    current_block_height += 1;
+
+    // We can add whatever we want in here, even arbitrary text
+    // It's up to the auth module to decide what to do with it.
+    let user_data = pallas::Base::ZERO;
+
+    // ============================
+    // Execute proposals test cases
+    // ============================
+    info!("[Dao] DAO transfer proposal tx test case");
+    execute_transfer_proposal(
+        &mut th,
+        &dao,
+        dao_members,
+        &dao_proposer_keypair.secret,
+        &dao_votes_keypair.secret,
+        &dao_exec_keypair.secret,
+        &None,
+        user_data,
+        &mut current_block_height,
+        0,
+        TRANSFER_PROPOSAL_AMOUNT,
+        TRANSFER_PROPOSAL_AMOUNT,
+    )
+    .await?;
+
+    info!("[Dao] DAO early execution transfer proposal tx test case");
+    execute_transfer_proposal(
+        &mut th,
+        &dao,
+        dao_members,
+        &dao_proposer_keypair.secret,
+        &dao_votes_keypair.secret,
+        &dao_exec_keypair.secret,
+        &Some(dao_early_exec_keypair.secret),
+        user_data,
+        &mut current_block_height,
+        1,
+        TRANSFER_PROPOSAL_AMOUNT,
+        TRANSFER_PROPOSAL_AMOUNT * 2,
+    )
+    .await?;
+
+    info!("[Dao] DAO generic proposal tx test case");
+    execute_generic_proposal(
+        &mut th,
+        &dao,
+        dao_members,
+        &dao_proposer_keypair.secret,
+        &dao_votes_keypair.secret,
+        &dao_exec_keypair.secret,
+        &None,
+        user_data,
+        &mut current_block_height,
+    )
+    .await?;
+
+    // Now we will execute a random money transaction,
+    // to update our merkle tree so our snapshot is fresh.
+    info!("[Dao] Building governance token mint tx for Alice");
+    let (a_token_mint_tx, a_token_mint_params, a_auth_token_mint_params, a_fee_params) = th
+        .token_mint(
+            ALICE_GOV_SUPPLY,
+            &Holder::Alice,
+            &Holder::Alice,
+            gov_token_blind,
+            None,
+            None,
+            current_block_height,
         )
         .await?;
 
-        // Now we will execute a random money transaction,
-        // to update our merkle tree so our snapshot is fresh.
-        info!("[Dao] Building governance token mint tx for Alice");
-        let (a_token_mint_tx, a_token_mint_params, a_auth_token_mint_params, a_fee_params) = th
-            .token_mint(
-                ALICE_GOV_SUPPLY,
-                &Holder::Alice,
-                &Holder::Alice,
-                gov_token_blind,
-                None,
-                None,
-                current_block_height,
-            )
-            .await?;
-
-        for holder in &HOLDERS {
-            info!("[{holder:?}] Executing governance token mint tx for Alice");
-            th.execute_token_mint_tx(
-                holder,
-                a_token_mint_tx.clone(),
-                &a_token_mint_params,
-                &a_auth_token_mint_params,
-                &a_fee_params,
-                current_block_height,
-                true,
-            )
-            .await?;
-        }
-
-        th.assert_trees(&HOLDERS);
-
-        current_block_height += 1;
-
-        // Now we can continue our test cases
-        info!("[Dao] DAO early execution generic proposal tx test case");
-        execute_generic_proposal(
-            &mut th,
-            &dao,
-            &dao_proposer_keypair.secret,
-            &dao_votes_keypair.secret,
-            &dao_exec_keypair.secret,
-            &Some(dao_early_exec_keypair.secret),
-            user_data,
-            &mut current_block_height,
+    for holder in &HOLDERS {
+        info!("[{holder:?}] Executing governance token mint tx for Alice");
+        th.execute_token_mint_tx(
+            holder,
+            a_token_mint_tx.clone(),
+            &a_token_mint_params,
+            &a_auth_token_mint_params,
+            &a_fee_params,
+            current_block_height,
+            true,
         )
         .await?;
+    }
+
+    th.assert_trees(&HOLDERS);
 
-        // Thanks for reading
-        Ok(())
-    })
+    current_block_height += 1;
+
+    // Now we can continue our test cases
+    info!("[Dao] DAO early execution generic proposal tx test case");
+    execute_generic_proposal(
+        &mut th,
+        &dao,
+        dao_members,
+        &dao_proposer_keypair.secret,
+        &dao_votes_keypair.secret,
+        &dao_exec_keypair.secret,
+        &Some(dao_early_exec_keypair.secret),
+        user_data,
+        &mut current_block_height,
+    )
+    .await?;
+
+    // Thanks for reading
+    Ok(())
 }
 
 /// Test case:
@@ -407,6 +423,7 @@ fn integration_test() -> Result<()> {
 async fn execute_transfer_proposal(
     th: &mut TestHarness,
     dao: &Dao,
+    dao_members: &[Holder],
     dao_proposer_secret_key: &SecretKey,
     dao_votes_secret_key: &SecretKey,
     dao_exec_secret_key: &SecretKey,
@@ -445,6 +462,7 @@ async fn execute_transfer_proposal(
             &proposal_coinattrs,
             user_data,
             dao,
+            dao_members,
             dao_proposer_secret_key,
             *current_block_height,
             PROPOSAL_DURATION_BLOCKWINDOW,
@@ -466,21 +484,43 @@ async fn execute_transfer_proposal(
     th.assert_trees(&HOLDERS);
     *current_block_height += 1;
 
+    // A holder outside the DAO members set must not be able to vote.
+    // Alice holds governance tokens, but she is not part of this one.
+    if !dao_members.is_empty() {
+        info!("[Alice] Building transfer vote tx as a non-member");
+        let outsiders = [Holder::Bob, Holder::Charlie];
+        let other_dao = Dao { members_root: th.dao_members_root(&outsiders), ..dao.clone() };
+        assert!(th
+            .dao_vote(
+                &Holder::Alice,
+                true,
+                &other_dao,
+                &outsiders,
+                &proposal_info,
+                *current_block_height
+            )
+            .await
+            .is_err());
+    }
+
     // =====================================
     // Dao::Vote
     // Proposal is accepted. Start the vote.
     // =====================================
     info!("[Alice] Building transfer vote tx (yes)");
-    let (alice_vote_tx, alice_vote_params, alice_vote_fee_params) =
-        th.dao_vote(&Holder::Alice, true, dao, &proposal_info, *current_block_height).await?;
+    let (alice_vote_tx, alice_vote_params, alice_vote_fee_params) = th
+        .dao_vote(&Holder::Alice, true, dao, dao_members, &proposal_info, *current_block_height)
+        .await?;
 
     info!("[Bob] Building transfer vote tx (no)");
-    let (bob_vote_tx, bob_vote_params, bob_vote_fee_params) =
-        th.dao_vote(&Holder::Bob, false, dao, &proposal_info, *current_block_height).await?;
+    let (bob_vote_tx, bob_vote_params, bob_vote_fee_params) = th
+        .dao_vote(&Holder::Bob, false, dao, dao_members, &proposal_info, *current_block_height)
+        .await?;
 
     info!("[Charlie] Building transfer vote tx (yes)");
-    let (charlie_vote_tx, charlie_vote_params, charlie_vote_fee_params) =
-        th.dao_vote(&Holder::Charlie, true, dao, &proposal_info, *current_block_height).await?;
+    let (charlie_vote_tx, charlie_vote_params, charlie_vote_fee_params) = th
+        .dao_vote(&Holder::Charlie, true, dao, dao_members, &proposal_info, *current_block_height)
+        .await?;
 
     for holder in &HOLDERS {
         info!("[{holder:?}] Executing Alice transfer vote tx");
@@ -594,6 +634,7 @@ async fn execute_transfer_proposal(
 async fn execute_generic_proposal(
     th: &mut TestHarness,
     dao: &Dao,
+    dao_members: &[Holder],
     dao_proposer_secret_key: &SecretKey,
     dao_votes_secret_key: &SecretKey,
     dao_exec_secret_key: &SecretKey,
@@ -617,6 +658,7 @@ async fn execute_generic_proposal(
             &Holder::Alice,
             user_data,
             dao,
+            dao_members,
             dao_proposer_secret_key,
             *current_block_height,
             PROPOSAL_DURATION_BLOCKWINDOW,
@@ -643,16 +685,19 @@ async fn execute_generic_proposal(
     // Proposal is accepted. Start the vote.
     // =====================================
     info!("[Alice] Building generic vote tx (yes)");
-    let (alice_vote_tx, alice_vote_params, alice_vote_fee_params) =
-        th.dao_vote(&Holder::Alice, true, dao, &proposal_info, *current_block_height).await?;
+    let (alice_vote_tx, alice_vote_params, alice_vote_fee_params) = th
+        .dao_vote(&Holder::Alice, true, dao, dao_members, &proposal_info, *current_block_height)
+        .await?;
 
     info!("[Bob] Building generic vote tx (no)");
-    let (bob_vote_tx, bob_vote_params, bob_vote_fee_params) =
-        th.dao_vote(&Holder::Bob, false, dao, &proposal_info, *current_block_height).await?;
+    let (bob_vote_tx, bob_vote_params, bob_vote_fee_params) = th
+        .dao_vote(&Holder::Bob, false, dao, dao_members, &proposal_info, *current_block_height)
+        .await?;
 
     info!("[Charlie] Building generic vote tx (no)");
-    let (charlie_vote_tx, charlie_vote_params, charlie_vote_fee_params) =
-        th.dao_vote(&Holder::Charlie, true, dao, &proposal_info, *current_block_height).await?;
+    let (charlie_vote_tx, charlie_vote_params, charlie_vote_fee_params) = th
+        .dao_vote(&Holder::Charlie, true, dao, dao_members, &proposal_info, *current_block_height)
+        .await?;
 
     for holder in &HOLDERS {
         info!("[{holder:?}] Executing Alice generic vote tx");
@@ -0,0 +1,93 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2025 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! This module implements the client-side API for registering and
+//! managing names on the DarkFi network.
+
+use darkfi::{
+    zk::{halo2::Value, Proof, ProvingKey, Witness, ZkCircuit},
+    zkas::ZkBinary,
+    Result,
+};
+use darkfi_sdk::{
+    crypto::{pasta_prelude::Field, poseidon_hash, PublicKey, SecretKey},
+    pasta::pallas,
+};
+use rand::rngs::OsRng;
+
+use crate::model::NameId;
+
+/// `Darkname::CommitV1` API
+pub mod commit_v1;
+
+/// `Darkname::RegisterV1` API
+pub mod register_v1;
+
+/// `Darkname::RenewV1` API
+pub mod renew_v1;
+
+/// `Darkname::TransferV1` API
+pub mod transfer_v1;
+
+/// Secret opening of a name owner commitment
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct NameOwner {
+    /// Secret value known only to the owner
+    pub secret: pallas::Base,
+    /// Random blinding factor
+    pub blind: pallas::Base,
+}
+
+impl NameOwner {
+    /// Generate a new random `NameOwner`
+    pub fn random() -> Self {
+        Self { secret: pallas::Base::random(&mut OsRng), blind: pallas::Base::random(&mut OsRng) }
+    }
+
+    /// Compute the owner commitment for the given `NameId`
+    pub fn commitment(&self, name_id: &NameId) -> pallas::Base {
+        poseidon_hash([self.secret, name_id.inner(), self.blind])
+    }
+}
+
+/// Create a proof of ownership for the given `NameId`, bound to a fresh
+/// signature key. Returns the proof along with the secret key the
+/// transaction has to be signed with.
+pub(crate) fn create_ownership_proof(
+    owner: &NameOwner,
+    name_id: &NameId,
+    ownership_zkbin: &ZkBinary,
+    ownership_pk: &ProvingKey,
+) -> Result<(Proof, SecretKey)> {
+    let signature_secret = SecretKey::random(&mut OsRng);
+    let (sig_x, sig_y) = PublicKey::from_secret(signature_secret).xy();
+
+    let prover_witnesses = vec![
+        Witness::Base(Value::known(name_id.inner())),
+        Witness::Base(Value::known(owner.secret)),
+        Witness::Base(Value::known(owner.blind)),
+        Witness::Base(Value::known(signature_secret.inner())),
+    ];
+
+    let public_inputs = vec![name_id.inner(), owner.commitment(name_id), sig_x, sig_y];
+
+    let circuit = ZkCircuit::new(prover_witnesses, ownership_zkbin);
+    let proof = Proof::create(ownership_pk, &[circuit], &public_inputs, &mut OsRng)?;
+
+    Ok((proof, signature_secret))
+}
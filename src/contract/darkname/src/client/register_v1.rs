@@ -0,0 +1,62 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2025 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use darkfi::{Error, Result};
+use log::debug;
+
+use super::NameOwner;
+use crate::{
+    is_valid_name,
+    model::{DarknameRegisterParamsV1, NameId, NameTarget},
+};
+
+pub struct DarknameRegisterCallDebris {
+    pub params: DarknameRegisterParamsV1,
+}
+
+/// Struct holding necessary information to build a `Darkname::RegisterV1` contract call.
+pub struct DarknameRegisterCallBuilder {
+    /// The name to register
+    pub name: String,
+    /// Secret opening of the owner commitment
+    pub owner: NameOwner,
+    /// What the name resolves to
+    pub target: NameTarget,
+    /// Amount of blocks to register the name for
+    pub duration: u32,
+}
+
+impl DarknameRegisterCallBuilder {
+    pub fn build(&self) -> Result<DarknameRegisterCallDebris> {
+        debug!(target: "contract::darkname::client::register", "Building Darkname::RegisterV1 contract call");
+
+        if !is_valid_name(&self.name) {
+            return Err(Error::Custom(format!("Invalid name: {}", self.name)))
+        }
+
+        let name_id = NameId::derive(&self.name);
+        let params = DarknameRegisterParamsV1 {
+            name: self.name.clone(),
+            owner: self.owner.commitment(&name_id),
+            target: self.target,
+            duration: self.duration,
+        };
+
+        Ok(DarknameRegisterCallDebris { params })
+    }
+}
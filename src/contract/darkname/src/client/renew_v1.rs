@@ -0,0 +1,70 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2025 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use darkfi::{
+    zk::{Proof, ProvingKey},
+    zkas::ZkBinary,
+    Result,
+};
+use darkfi_sdk::crypto::{PublicKey, SecretKey};
+use log::debug;
+
+use super::{create_ownership_proof, NameOwner};
+use crate::model::{DarknameRenewParamsV1, NameId};
+
+pub struct DarknameRenewCallDebris {
+    pub params: DarknameRenewParamsV1,
+    pub proofs: Vec<Proof>,
+    pub signature_secret: SecretKey,
+}
+
+/// Struct holding necessary information to build a `Darkname::RenewV1` contract call.
+pub struct DarknameRenewCallBuilder<'a> {
+    /// The `NameId` to renew
+    pub name_id: NameId,
+    /// Secret opening of the owner commitment
+    pub owner: NameOwner,
+    /// Amount of blocks to extend the registration by
+    pub duration: u32,
+    /// `NameOwnership` zkas circuit ZkBinary
+    pub ownership_zkbin: &'a ZkBinary,
+    /// Proving key for the `NameOwnership` zk circuit
+    pub ownership_pk: &'a ProvingKey,
+}
+
+impl DarknameRenewCallBuilder<'_> {
+    pub fn build(&self) -> Result<DarknameRenewCallDebris> {
+        debug!(target: "contract::darkname::client::renew", "Building Darkname::RenewV1 contract call");
+
+        let (proof, signature_secret) = create_ownership_proof(
+            &self.owner,
+            &self.name_id,
+            self.ownership_zkbin,
+            self.ownership_pk,
+        )?;
+
+        let params = DarknameRenewParamsV1 {
+            name_id: self.name_id,
+            owner: self.owner.commitment(&self.name_id),
+            signature_public: PublicKey::from_secret(signature_secret),
+            duration: self.duration,
+        };
+
+        Ok(DarknameRenewCallDebris { params, proofs: vec![proof], signature_secret })
+    }
+}
@@ -0,0 +1,76 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2025 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use darkfi::{
+    zk::{Proof, ProvingKey},
+    zkas::ZkBinary,
+    Result,
+};
+use darkfi_sdk::{
+    crypto::{PublicKey, SecretKey},
+    pasta::pallas,
+};
+use log::debug;
+
+use super::{create_ownership_proof, NameOwner};
+use crate::model::{DarknameTransferParamsV1, NameId, NameTarget};
+
+pub struct DarknameTransferCallDebris {
+    pub params: DarknameTransferParamsV1,
+    pub proofs: Vec<Proof>,
+    pub signature_secret: SecretKey,
+}
+
+/// Struct holding necessary information to build a `Darkname::TransferV1` contract call.
+pub struct DarknameTransferCallBuilder<'a> {
+    /// The `NameId` to transfer
+    pub name_id: NameId,
+    /// Secret opening of the current owner commitment
+    pub owner: NameOwner,
+    /// Owner commitment of the recipient
+    pub new_owner: pallas::Base,
+    /// New target of the name
+    pub new_target: NameTarget,
+    /// `NameOwnership` zkas circuit ZkBinary
+    pub ownership_zkbin: &'a ZkBinary,
+    /// Proving key for the `NameOwnership` zk circuit
+    pub ownership_pk: &'a ProvingKey,
+}
+
+impl DarknameTransferCallBuilder<'_> {
+    pub fn build(&self) -> Result<DarknameTransferCallDebris> {
+        debug!(target: "contract::darkname::client::transfer", "Building Darkname::TransferV1 contract call");
+
+        let (proof, signature_secret) = create_ownership_proof(
+            &self.owner,
+            &self.name_id,
+            self.ownership_zkbin,
+            self.ownership_pk,
+        )?;
+
+        let params = DarknameTransferParamsV1 {
+            name_id: self.name_id,
+            owner: self.owner.commitment(&self.name_id),
+            signature_public: PublicKey::from_secret(signature_secret),
+            new_owner: self.new_owner,
+            new_target: self.new_target,
+        };
+
+        Ok(DarknameTransferCallDebris { params, proofs: vec![proof], signature_secret })
+    }
+}
@@ -0,0 +1,161 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2025 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use darkfi_sdk::{
+    crypto::ContractId, dark_tree::DarkLeaf, error::ContractResult, wasm, ContractCall,
+};
+use darkfi_serial::{deserialize, serialize};
+
+use crate::{
+    model::{
+        DarknameCommitUpdateV1, DarknameRegisterUpdateV1, DarknameRenewUpdateV1,
+        DarknameTransferUpdateV1,
+    },
+    DarknameFunction, DARKNAME_CONTRACT_COMMITMENTS_TREE, DARKNAME_CONTRACT_DB_VERSION,
+    DARKNAME_CONTRACT_INFO_TREE, DARKNAME_CONTRACT_NAMES_TREE,
+};
+
+/// `Darkname::Register` functions
+mod register_v1;
+use register_v1::{
+    register_get_metadata_v1, register_process_instruction_v1, register_process_update_v1,
+};
+
+/// `Darkname::Renew` functions
+mod renew_v1;
+use renew_v1::{renew_get_metadata_v1, renew_process_instruction_v1, renew_process_update_v1};
+
+/// `Darkname::Transfer` functions
+mod transfer_v1;
+use transfer_v1::{
+    transfer_get_metadata_v1, transfer_process_instruction_v1, transfer_process_update_v1,
+};
+
+/// `Darkname::Commit` functions
+mod commit_v1;
+use commit_v1::{commit_get_metadata_v1, commit_process_instruction_v1, commit_process_update_v1};
+
+darkfi_sdk::define_contract!(
+    init: init_contract,
+    exec: process_instruction,
+    apply: process_update,
+    metadata: get_metadata
+);
+
+/// This entrypoint function runs when the contract is (re)deployed and initialized.
+/// We use this function to initialize all the necessary databases and prepare them
+/// with initial data if necessary. This is also the place where we bundle the zkas
+/// circuits that are to be used with functions provided by the contract.
+fn init_contract(cid: ContractId, _ix: &[u8]) -> ContractResult {
+    // zkas circuits can simply be embedded in the wasm and set up by using
+    // respective db functions.
+    let name_ownership_v1_bincode = include_bytes!("../proof/name-ownership.zk.bin");
+    wasm::db::zkas_db_set(&name_ownership_v1_bincode[..])?;
+
+    // Set up a database tree for arbitrary data
+    let info_db = match wasm::db::db_lookup(cid, DARKNAME_CONTRACT_INFO_TREE) {
+        Ok(v) => v,
+        Err(_) => wasm::db::db_init(cid, DARKNAME_CONTRACT_INFO_TREE)?,
+    };
+
+    // Set up a database to hold the registered names
+    // k=NameId, v=NameRecord
+    if wasm::db::db_lookup(cid, DARKNAME_CONTRACT_NAMES_TREE).is_err() {
+        wasm::db::db_init(cid, DARKNAME_CONTRACT_NAMES_TREE)?;
+    }
+
+    // Set up a database to hold the registration commitments
+    // k=Commitment, v=Block height
+    if wasm::db::db_lookup(cid, DARKNAME_CONTRACT_COMMITMENTS_TREE).is_err() {
+        wasm::db::db_init(cid, DARKNAME_CONTRACT_COMMITMENTS_TREE)?;
+    }
+
+    // Update db version
+    wasm::db::db_set(
+        info_db,
+        DARKNAME_CONTRACT_DB_VERSION,
+        &serialize(&env!("CARGO_PKG_VERSION")),
+    )?;
+
+    Ok(())
+}
+
+/// This function is used by the wasm VM's host to fetch the necessary metadata
+/// for verifying signatures and zk proofs. The payload given here are all the
+/// contract calls in the transaction.
+fn get_metadata(cid: ContractId, ix: &[u8]) -> ContractResult {
+    let call_idx = wasm::util::get_call_index()? as usize;
+    let calls: Vec<DarkLeaf<ContractCall>> = deserialize(ix)?;
+    let self_ = &calls[call_idx].data;
+    let func = DarknameFunction::try_from(self_.data[0])?;
+
+    let metadata = match func {
+        DarknameFunction::RegisterV1 => register_get_metadata_v1(cid, call_idx, calls)?,
+        DarknameFunction::RenewV1 => renew_get_metadata_v1(cid, call_idx, calls)?,
+        DarknameFunction::TransferV1 => transfer_get_metadata_v1(cid, call_idx, calls)?,
+        DarknameFunction::CommitV1 => commit_get_metadata_v1(cid, call_idx, calls)?,
+    };
+
+    wasm::util::set_return_data(&metadata)
+}
+
+/// This function verifies a state transition and produces a state update
+/// if everything is successful.
+fn process_instruction(cid: ContractId, ix: &[u8]) -> ContractResult {
+    let call_idx = wasm::util::get_call_index()? as usize;
+    let calls: Vec<DarkLeaf<ContractCall>> = deserialize(ix)?;
+    let self_ = &calls[call_idx].data;
+    let func = DarknameFunction::try_from(self_.data[0])?;
+
+    let update_data = match func {
+        DarknameFunction::RegisterV1 => register_process_instruction_v1(cid, call_idx, calls)?,
+        DarknameFunction::RenewV1 => renew_process_instruction_v1(cid, call_idx, calls)?,
+        DarknameFunction::TransferV1 => transfer_process_instruction_v1(cid, call_idx, calls)?,
+        DarknameFunction::CommitV1 => commit_process_instruction_v1(cid, call_idx, calls)?,
+    };
+
+    wasm::util::set_return_data(&update_data)
+}
+
+/// This function attempts to write a given state update provided the previous
+/// steps of the contract call execution were all successful. It's the last in
+/// line, and assumes that the transaction/call was successful. The payload
+/// given to the function is the update data retrieved from `process_instruction()`.
+fn process_update(cid: ContractId, update_data: &[u8]) -> ContractResult {
+    match DarknameFunction::try_from(update_data[0])? {
+        DarknameFunction::RegisterV1 => {
+            let update: DarknameRegisterUpdateV1 = deserialize(&update_data[1..])?;
+            Ok(register_process_update_v1(cid, update)?)
+        }
+
+        DarknameFunction::RenewV1 => {
+            let update: DarknameRenewUpdateV1 = deserialize(&update_data[1..])?;
+            Ok(renew_process_update_v1(cid, update)?)
+        }
+
+        DarknameFunction::TransferV1 => {
+            let update: DarknameTransferUpdateV1 = deserialize(&update_data[1..])?;
+            Ok(transfer_process_update_v1(cid, update)?)
+        }
+
+        DarknameFunction::CommitV1 => {
+            let update: DarknameCommitUpdateV1 = deserialize(&update_data[1..])?;
+            Ok(commit_process_update_v1(cid, update)?)
+        }
+    }
+}
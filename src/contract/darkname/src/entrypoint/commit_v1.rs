@@ -0,0 +1,91 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2025 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use darkfi_sdk::{
+    crypto::{ContractId, PublicKey},
+    dark_tree::DarkLeaf,
+    error::{ContractError, ContractResult},
+    msg,
+    pasta::pallas,
+    wasm, ContractCall,
+};
+use darkfi_serial::{deserialize, serialize, Encodable, WriteExt};
+
+use crate::{
+    error::DarknameError,
+    model::{DarknameCommitParamsV1, DarknameCommitUpdateV1},
+    DarknameFunction, DARKNAME_CONTRACT_COMMITMENTS_TREE,
+};
+
+/// `get_metadata` function for `Darkname::CommitV1`
+pub(crate) fn commit_get_metadata_v1(
+    _cid: ContractId,
+    _call_idx: usize,
+    _calls: Vec<DarkLeaf<ContractCall>>,
+) -> Result<Vec<u8>, ContractError> {
+    // Committing requires no proofs or signatures, since the commitment
+    // is only checked against the registration revealing it.
+    let zk_public_inputs: Vec<(String, Vec<pallas::Base>)> = vec![];
+    let signature_pubkeys: Vec<PublicKey> = vec![];
+
+    // Serialize everything gathered and return it
+    let mut metadata = vec![];
+    zk_public_inputs.encode(&mut metadata)?;
+    signature_pubkeys.encode(&mut metadata)?;
+
+    Ok(metadata)
+}
+
+/// `process_instruction` function for `Darkname::CommitV1`
+pub(crate) fn commit_process_instruction_v1(
+    cid: ContractId,
+    call_idx: usize,
+    calls: Vec<DarkLeaf<ContractCall>>,
+) -> Result<Vec<u8>, ContractError> {
+    let self_ = &calls[call_idx];
+    let params: DarknameCommitParamsV1 = deserialize(&self_.data.data[1..])?;
+
+    // A commitment can't be refreshed, so its age can't be reset
+    let commitments_db = wasm::db::db_lookup(cid, DARKNAME_CONTRACT_COMMITMENTS_TREE)?;
+    if wasm::db::db_contains_key(commitments_db, &serialize(&params.commitment))? {
+        msg!("[CommitV1] Error: Commitment already exists");
+        return Err(DarknameError::CommitmentAlreadyExists.into())
+    }
+
+    let update = DarknameCommitUpdateV1 {
+        commitment: params.commitment,
+        height: wasm::util::get_verifying_block_height()?,
+    };
+    let mut update_data = vec![];
+    update_data.write_u8(DarknameFunction::CommitV1 as u8)?;
+    update.encode(&mut update_data)?;
+
+    Ok(update_data)
+}
+
+/// `process_update` function for `Darkname::CommitV1`
+pub(crate) fn commit_process_update_v1(
+    cid: ContractId,
+    update: DarknameCommitUpdateV1,
+) -> ContractResult {
+    msg!("[CommitV1] Storing registration commitment at height {}", update.height);
+    let commitments_db = wasm::db::db_lookup(cid, DARKNAME_CONTRACT_COMMITMENTS_TREE)?;
+    wasm::db::db_set(commitments_db, &serialize(&update.commitment), &serialize(&update.height))?;
+
+    Ok(())
+}
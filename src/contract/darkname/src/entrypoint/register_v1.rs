@@ -0,0 +1,143 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2025 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use darkfi_sdk::{
+    crypto::{ContractId, PublicKey},
+    dark_tree::DarkLeaf,
+    error::{ContractError, ContractResult},
+    msg,
+    pasta::pallas,
+    wasm, ContractCall,
+};
+use darkfi_serial::{deserialize, serialize, Encodable, WriteExt};
+
+use crate::{
+    error::DarknameError,
+    is_valid_name,
+    model::{
+        name_commitment, DarknameRegisterParamsV1, DarknameRegisterUpdateV1, NameId, NameRecord,
+    },
+    DarknameFunction, DARKNAME_CONTRACT_COMMITMENTS_TREE, DARKNAME_CONTRACT_NAMES_TREE,
+    NAME_COMMIT_MAX_AGE, NAME_COMMIT_MIN_AGE, NAME_MAX_DURATION,
+};
+
+/// `get_metadata` function for `Darkname::RegisterV1`
+pub(crate) fn register_get_metadata_v1(
+    _cid: ContractId,
+    _call_idx: usize,
+    _calls: Vec<DarkLeaf<ContractCall>>,
+) -> Result<Vec<u8>, ContractError> {
+    // Registering a name requires no proofs or signatures, since
+    // ownership is only established by the owner commitment, and
+    // the registration is bound to it by the prior commitment.
+    let zk_public_inputs: Vec<(String, Vec<pallas::Base>)> = vec![];
+    let signature_pubkeys: Vec<PublicKey> = vec![];
+
+    // Serialize everything gathered and return it
+    let mut metadata = vec![];
+    zk_public_inputs.encode(&mut metadata)?;
+    signature_pubkeys.encode(&mut metadata)?;
+
+    Ok(metadata)
+}
+
+/// `process_instruction` function for `Darkname::RegisterV1`
+pub(crate) fn register_process_instruction_v1(
+    cid: ContractId,
+    call_idx: usize,
+    calls: Vec<DarkLeaf<ContractCall>>,
+) -> Result<Vec<u8>, ContractError> {
+    let self_ = &calls[call_idx];
+    let params: DarknameRegisterParamsV1 = deserialize(&self_.data.data[1..])?;
+
+    if !is_valid_name(&params.name) {
+        msg!("[RegisterV1] Error: Invalid name");
+        return Err(DarknameError::InvalidName.into())
+    }
+
+    if params.target.is_empty() {
+        msg!("[RegisterV1] Error: Name target is empty");
+        return Err(DarknameError::EmptyTarget.into())
+    }
+
+    if params.duration == 0 || params.duration > NAME_MAX_DURATION {
+        msg!("[RegisterV1] Error: Invalid duration {}", params.duration);
+        return Err(DarknameError::InvalidDuration.into())
+    }
+
+    // Names can only be registered if they don't exist, or
+    // their previous registration has expired.
+    let verifying_block_height = wasm::util::get_verifying_block_height()?;
+    let names_db = wasm::db::db_lookup(cid, DARKNAME_CONTRACT_NAMES_TREE)?;
+    let name_id = NameId::derive(&params.name);
+    if let Some(v) = wasm::db::db_get(names_db, &serialize(&name_id))? {
+        let record: NameRecord = deserialize(&v)?;
+        if record.expiry >= verifying_block_height {
+            msg!("[RegisterV1] Error: Name {} is already registered", params.name);
+            return Err(DarknameError::NameAlreadyRegistered.into())
+        }
+    }
+
+    // The registration must reveal a commitment made early enough, so
+    // anyone seeing it can't get their own registration in first.
+    let commitment = name_commitment(&name_id, params.owner);
+    let commitments_db = wasm::db::db_lookup(cid, DARKNAME_CONTRACT_COMMITMENTS_TREE)?;
+    let Some(v) = wasm::db::db_get(commitments_db, &serialize(&commitment))? else {
+        msg!("[RegisterV1] Error: No commitment found for name {}", params.name);
+        return Err(DarknameError::CommitmentNotFound.into())
+    };
+    let committed_height: u32 = deserialize(&v)?;
+
+    if verifying_block_height < committed_height + NAME_COMMIT_MIN_AGE {
+        msg!("[RegisterV1] Error: Commitment for name {} is too recent", params.name);
+        return Err(DarknameError::CommitmentNotMature.into())
+    }
+
+    if verifying_block_height > committed_height + NAME_COMMIT_MAX_AGE {
+        msg!("[RegisterV1] Error: Commitment for name {} has expired", params.name);
+        return Err(DarknameError::CommitmentExpired.into())
+    }
+
+    let record = NameRecord {
+        owner: params.owner,
+        target: params.target,
+        expiry: verifying_block_height + params.duration,
+    };
+
+    let update = DarknameRegisterUpdateV1 { name_id, record, commitment };
+    let mut update_data = vec![];
+    update_data.write_u8(DarknameFunction::RegisterV1 as u8)?;
+    update.encode(&mut update_data)?;
+
+    Ok(update_data)
+}
+
+/// `process_update` function for `Darkname::RegisterV1`
+pub(crate) fn register_process_update_v1(
+    cid: ContractId,
+    update: DarknameRegisterUpdateV1,
+) -> ContractResult {
+    msg!("[RegisterV1] Registering name {}", update.name_id);
+    let names_db = wasm::db::db_lookup(cid, DARKNAME_CONTRACT_NAMES_TREE)?;
+    wasm::db::db_set(names_db, &serialize(&update.name_id), &serialize(&update.record))?;
+
+    let commitments_db = wasm::db::db_lookup(cid, DARKNAME_CONTRACT_COMMITMENTS_TREE)?;
+    wasm::db::db_del(commitments_db, &serialize(&update.commitment))?;
+
+    Ok(())
+}
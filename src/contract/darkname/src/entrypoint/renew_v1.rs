@@ -0,0 +1,114 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2025 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use darkfi_sdk::{
+    crypto::{ContractId, PublicKey},
+    dark_tree::DarkLeaf,
+    error::{ContractError, ContractResult},
+    msg,
+    pasta::pallas,
+    wasm, ContractCall,
+};
+use darkfi_serial::{deserialize, serialize, Encodable, WriteExt};
+
+use crate::{
+    error::DarknameError,
+    model::{DarknameRenewParamsV1, DarknameRenewUpdateV1, NameRecord},
+    DarknameFunction, DARKNAME_CONTRACT_NAMES_TREE, DARKNAME_CONTRACT_ZKAS_NAME_OWNERSHIP_NS,
+    NAME_MAX_DURATION,
+};
+
+/// `get_metadata` function for `Darkname::RenewV1`
+pub(crate) fn renew_get_metadata_v1(
+    _cid: ContractId,
+    call_idx: usize,
+    calls: Vec<DarkLeaf<ContractCall>>,
+) -> Result<Vec<u8>, ContractError> {
+    let self_ = &calls[call_idx];
+    let params: DarknameRenewParamsV1 = deserialize(&self_.data.data[1..])?;
+
+    // The caller has to prove ownership of the name, binding the
+    // proof to the key signing the transaction.
+    let (sig_x, sig_y) = params.signature_public.xy();
+    let zk_public_inputs: Vec<(String, Vec<pallas::Base>)> = vec![(
+        DARKNAME_CONTRACT_ZKAS_NAME_OWNERSHIP_NS.to_string(),
+        vec![params.name_id.inner(), params.owner, sig_x, sig_y],
+    )];
+    let signature_pubkeys: Vec<PublicKey> = vec![params.signature_public];
+
+    // Serialize everything gathered and return it
+    let mut metadata = vec![];
+    zk_public_inputs.encode(&mut metadata)?;
+    signature_pubkeys.encode(&mut metadata)?;
+
+    Ok(metadata)
+}
+
+/// `process_instruction` function for `Darkname::RenewV1`
+pub(crate) fn renew_process_instruction_v1(
+    cid: ContractId,
+    call_idx: usize,
+    calls: Vec<DarkLeaf<ContractCall>>,
+) -> Result<Vec<u8>, ContractError> {
+    let self_ = &calls[call_idx];
+    let params: DarknameRenewParamsV1 = deserialize(&self_.data.data[1..])?;
+
+    let names_db = wasm::db::db_lookup(cid, DARKNAME_CONTRACT_NAMES_TREE)?;
+    let Some(v) = wasm::db::db_get(names_db, &serialize(&params.name_id))? else {
+        msg!("[RenewV1] Error: Name {} is not registered", params.name_id);
+        return Err(DarknameError::NameNotRegistered.into())
+    };
+    let record: NameRecord = deserialize(&v)?;
+
+    if record.owner != params.owner {
+        msg!("[RenewV1] Error: Owner mismatch for name {}", params.name_id);
+        return Err(DarknameError::OwnerMismatch.into())
+    }
+
+    // Renewals extend the current registration, or start from the
+    // current height if it has already expired. A registration can
+    // never run for longer than the maximum duration.
+    let verifying_block_height = wasm::util::get_verifying_block_height()?;
+    let expiry = record.expiry.max(verifying_block_height).saturating_add(params.duration);
+    if params.duration == 0 || expiry - verifying_block_height > NAME_MAX_DURATION {
+        msg!("[RenewV1] Error: Invalid duration {}", params.duration);
+        return Err(DarknameError::InvalidDuration.into())
+    }
+
+    let update = DarknameRenewUpdateV1 { name_id: params.name_id, expiry };
+    let mut update_data = vec![];
+    update_data.write_u8(DarknameFunction::RenewV1 as u8)?;
+    update.encode(&mut update_data)?;
+
+    Ok(update_data)
+}
+
+/// `process_update` function for `Darkname::RenewV1`
+pub(crate) fn renew_process_update_v1(
+    cid: ContractId,
+    update: DarknameRenewUpdateV1,
+) -> ContractResult {
+    msg!("[RenewV1] Renewing name {} until {}", update.name_id, update.expiry);
+    let names_db = wasm::db::db_lookup(cid, DARKNAME_CONTRACT_NAMES_TREE)?;
+    let key = serialize(&update.name_id);
+    let mut record: NameRecord = deserialize(&wasm::db::db_get(names_db, &key)?.unwrap())?;
+    record.expiry = update.expiry;
+    wasm::db::db_set(names_db, &key, &serialize(&record))?;
+
+    Ok(())
+}
@@ -0,0 +1,73 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2025 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use darkfi_sdk::error::ContractError;
+
+#[derive(Debug, Clone, thiserror::Error)]
+pub enum DarknameError {
+    #[error("Invalid name")]
+    InvalidName,
+
+    #[error("Name is already registered")]
+    NameAlreadyRegistered,
+
+    #[error("Name is not registered")]
+    NameNotRegistered,
+
+    #[error("Name registration has expired")]
+    NameExpired,
+
+    #[error("Invalid registration duration")]
+    InvalidDuration,
+
+    #[error("Name owner mismatch")]
+    OwnerMismatch,
+
+    #[error("Name target is empty")]
+    EmptyTarget,
+
+    #[error("Registration commitment already exists")]
+    CommitmentAlreadyExists,
+
+    #[error("Registration commitment not found")]
+    CommitmentNotFound,
+
+    #[error("Registration commitment is too recent")]
+    CommitmentNotMature,
+
+    #[error("Registration commitment has expired")]
+    CommitmentExpired,
+}
+
+impl From<DarknameError> for ContractError {
+    fn from(e: DarknameError) -> Self {
+        match e {
+            DarknameError::InvalidName => Self::Custom(1),
+            DarknameError::NameAlreadyRegistered => Self::Custom(2),
+            DarknameError::NameNotRegistered => Self::Custom(3),
+            DarknameError::NameExpired => Self::Custom(4),
+            DarknameError::InvalidDuration => Self::Custom(5),
+            DarknameError::OwnerMismatch => Self::Custom(6),
+            DarknameError::EmptyTarget => Self::Custom(7),
+            DarknameError::CommitmentAlreadyExists => Self::Custom(8),
+            DarknameError::CommitmentNotFound => Self::Custom(9),
+            DarknameError::CommitmentNotMature => Self::Custom(10),
+            DarknameError::CommitmentExpired => Self::Custom(11),
+        }
+    }
+}
@@ -0,0 +1,105 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2025 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Smart contract implementing the on-chain name service.
+//!
+//! Human-readable names can be registered for a limited amount of blocks,
+//! pointing to an address and/or a contract ID. Name ownership is shielded:
+//! names are owned by a commitment, and the owner proves knowledge of its
+//! opening in ZK in order to renew or transfer them.
+//!
+//! Registration is done with a commit-reveal scheme, so a pending
+//! registration can't be front-run: the registrant first commits to the
+//! name and its owner commitment, and can only reveal and register the
+//! name once the commitment has been on-chain for a few blocks.
+
+use darkfi_sdk::error::ContractError;
+
+/// Functions available in the contract
+#[repr(u8)]
+#[derive(PartialEq, Debug)]
+pub enum DarknameFunction {
+    RegisterV1 = 0x00,
+    RenewV1 = 0x01,
+    TransferV1 = 0x02,
+    CommitV1 = 0x03,
+}
+
+impl TryFrom<u8> for DarknameFunction {
+    type Error = ContractError;
+
+    fn try_from(b: u8) -> core::result::Result<Self, Self::Error> {
+        match b {
+            0x00 => Ok(Self::RegisterV1),
+            0x01 => Ok(Self::RenewV1),
+            0x02 => Ok(Self::TransferV1),
+            0x03 => Ok(Self::CommitV1),
+            _ => Err(ContractError::InvalidFunction),
+        }
+    }
+}
+
+/// Internal contract errors
+pub mod error;
+
+/// Call parameters definitions
+pub mod model;
+
+#[cfg(not(feature = "no-entrypoint"))]
+/// WASM entrypoint functions
+pub mod entrypoint;
+
+#[cfg(feature = "client")]
+/// Client API for interaction with this smart contract
+pub mod client;
+
+// These are the different sled trees that will be created
+pub const DARKNAME_CONTRACT_INFO_TREE: &str = "info";
+pub const DARKNAME_CONTRACT_NAMES_TREE: &str = "names";
+pub const DARKNAME_CONTRACT_COMMITMENTS_TREE: &str = "commitments";
+
+// These are keys inside the info tree
+pub const DARKNAME_CONTRACT_DB_VERSION: &[u8] = b"db_version";
+
+/// zkas name ownership circuit namespace
+pub const DARKNAME_CONTRACT_ZKAS_NAME_OWNERSHIP_NS: &str = "NameOwnership";
+
+/// Maximum length of a name, in bytes
+pub const NAME_MAX_LEN: usize = 32;
+
+/// Maximum amount of blocks a name can be registered for in advance,
+/// roughly two years using the 90 seconds PoW target.
+pub const NAME_MAX_DURATION: u32 = 700_800;
+
+/// Minimum amount of blocks a registration commitment has to be on-chain
+/// for, before the name can be registered with it.
+pub const NAME_COMMIT_MIN_AGE: u32 = 1;
+
+/// Maximum amount of blocks a registration commitment can be used for,
+/// roughly a day using the 90 seconds PoW target.
+pub const NAME_COMMIT_MAX_AGE: u32 = 960;
+
+/// Check that the given name is valid. Names consist of lowercase ASCII
+/// letters, digits and hyphens, and can't start or end with a hyphen.
+pub fn is_valid_name(name: &str) -> bool {
+    !name.is_empty() &&
+        name.len() <= NAME_MAX_LEN &&
+        !name.starts_with('-') &&
+        !name.ends_with('-') &&
+        name.bytes().all(|b| b.is_ascii_lowercase() || b.is_ascii_digit() || b == b'-')
+}
@@ -0,0 +1,189 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2025 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use darkfi_sdk::{
+    crypto::{pasta_prelude::PrimeField, poseidon_hash, util::hash_to_base, ContractId, PublicKey},
+    error::ContractError,
+    pasta::pallas,
+};
+use darkfi_serial::{SerialDecodable, SerialEncodable};
+
+#[cfg(feature = "client")]
+use darkfi_serial::async_trait;
+
+/// BLAKE2b personalization used to derive a [`NameId`]
+pub const NAME_ID_PERSONALIZATION: &[u8] = b"DarkFi_Name_ID__";
+
+/// NameId represents the on-chain identifier of a registered name.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, SerialEncodable, SerialDecodable)]
+pub struct NameId(pallas::Base);
+
+impl NameId {
+    /// Derives a `NameId` from a human-readable name
+    pub fn derive(name: &str) -> Self {
+        Self(hash_to_base(NAME_ID_PERSONALIZATION, &[name.as_bytes()]))
+    }
+
+    /// Get the inner `pallas::Base` element.
+    pub fn inner(&self) -> pallas::Base {
+        self.0
+    }
+
+    /// Create a `NameId` object from given bytes, erroring if the input
+    /// bytes are noncanonical.
+    pub fn from_bytes(x: [u8; 32]) -> Result<Self, ContractError> {
+        match pallas::Base::from_repr(x).into() {
+            Some(v) => Ok(Self(v)),
+            None => {
+                Err(ContractError::IoError("Failed to instantiate NameId from bytes".to_string()))
+            }
+        }
+    }
+
+    /// Convert the `NameId` type into 32 raw bytes
+    pub fn to_bytes(&self) -> [u8; 32] {
+        self.0.to_repr()
+    }
+}
+
+use core::str::FromStr;
+darkfi_sdk::fp_from_bs58!(NameId);
+darkfi_sdk::fp_to_bs58!(NameId);
+darkfi_sdk::ty_from_fp!(NameId);
+
+/// Compute the registration commitment of a name, binding the `NameId`
+/// to its owner commitment. Since the owner commitment is blinded, the
+/// registration commitment doesn't reveal the name.
+pub fn name_commitment(name_id: &NameId, owner: pallas::Base) -> pallas::Base {
+    poseidon_hash([name_id.inner(), owner])
+}
+
+/// What a registered name resolves to
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq, SerialEncodable, SerialDecodable)]
+pub struct NameTarget {
+    /// Address payments to this name should be sent to
+    pub public_key: Option<PublicKey>,
+    /// Contract this name points to
+    pub contract_id: Option<ContractId>,
+}
+
+impl NameTarget {
+    /// Check if the target points to nothing
+    pub fn is_empty(&self) -> bool {
+        self.public_key.is_none() && self.contract_id.is_none()
+    }
+}
+
+/// On-chain record of a registered name
+#[derive(Copy, Clone, Debug, Eq, PartialEq, SerialEncodable, SerialDecodable)]
+pub struct NameRecord {
+    /// Owner commitment, `poseidon_hash(owner_secret, name_id, owner_blind)`
+    pub owner: pallas::Base,
+    /// What the name resolves to
+    pub target: NameTarget,
+    /// Block height after which the registration expires
+    pub expiry: u32,
+}
+
+/// Parameters for `Darkname::Register`
+#[derive(Clone, Debug, SerialEncodable, SerialDecodable)]
+pub struct DarknameRegisterParamsV1 {
+    /// The name to register
+    pub name: String,
+    /// Owner commitment of the name
+    pub owner: pallas::Base,
+    /// What the name resolves to
+    pub target: NameTarget,
+    /// Amount of blocks the name is registered for
+    pub duration: u32,
+}
+
+/// State update for `Darkname::Register`
+#[derive(Clone, Debug, SerialEncodable, SerialDecodable)]
+pub struct DarknameRegisterUpdateV1 {
+    /// The registered `NameId`
+    pub name_id: NameId,
+    /// The new name record
+    pub record: NameRecord,
+    /// The registration commitment used up by this registration
+    pub commitment: pallas::Base,
+}
+
+/// Parameters for `Darkname::Commit`
+#[derive(Clone, Debug, SerialEncodable, SerialDecodable)]
+pub struct DarknameCommitParamsV1 {
+    /// Registration commitment, see [`name_commitment`]
+    pub commitment: pallas::Base,
+}
+
+/// State update for `Darkname::Commit`
+#[derive(Clone, Debug, SerialEncodable, SerialDecodable)]
+pub struct DarknameCommitUpdateV1 {
+    /// The registration commitment
+    pub commitment: pallas::Base,
+    /// Block height the commitment was made at
+    pub height: u32,
+}
+
+/// Parameters for `Darkname::Renew`
+#[derive(Clone, Debug, SerialEncodable, SerialDecodable)]
+pub struct DarknameRenewParamsV1 {
+    /// The `NameId` to renew
+    pub name_id: NameId,
+    /// Current owner commitment of the name
+    pub owner: pallas::Base,
+    /// Public key used to sign the transaction
+    pub signature_public: PublicKey,
+    /// Amount of blocks to extend the registration by
+    pub duration: u32,
+}
+
+/// State update for `Darkname::Renew`
+#[derive(Clone, Debug, SerialEncodable, SerialDecodable)]
+pub struct DarknameRenewUpdateV1 {
+    /// The renewed `NameId`
+    pub name_id: NameId,
+    /// The new expiry block height
+    pub expiry: u32,
+}
+
+/// Parameters for `Darkname::Transfer`
+#[derive(Clone, Debug, SerialEncodable, SerialDecodable)]
+pub struct DarknameTransferParamsV1 {
+    /// The `NameId` to transfer
+    pub name_id: NameId,
+    /// Current owner commitment of the name
+    pub owner: pallas::Base,
+    /// Public key used to sign the transaction
+    pub signature_public: PublicKey,
+    /// New owner commitment of the name
+    pub new_owner: pallas::Base,
+    /// New target of the name
+    pub new_target: NameTarget,
+}
+
+/// State update for `Darkname::Transfer`
+#[derive(Clone, Debug, SerialEncodable, SerialDecodable)]
+pub struct DarknameTransferUpdateV1 {
+    /// The transferred `NameId`
+    pub name_id: NameId,
+    /// The new owner commitment
+    pub owner: pallas::Base,
+    /// The new target
+    pub target: NameTarget,
+}
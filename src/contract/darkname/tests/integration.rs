@@ -0,0 +1,121 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2025 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Integration test for darkname registration.
+//!
+//! Alice commits to a name and registers it once the commitment has
+//! matured. Bob, seeing her registration, tries to front-run it with a
+//! commitment of his own, which isn't accepted in the same block, and
+//! the name is taken by the time it would be.
+
+use darkfi::Result;
+use darkfi_contract_test_harness::{init_logger, Holder, TestHarness};
+use darkfi_darkname_contract::{client::NameOwner, model::NameTarget, NAME_COMMIT_MAX_AGE};
+use log::info;
+
+#[test]
+fn darkname_integration() -> Result<()> {
+    smol::block_on(async {
+        init_logger();
+
+        // Holders this test will use
+        const HOLDERS: [Holder; 2] = [Holder::Alice, Holder::Bob];
+
+        // Initialize harness
+        let mut th = TestHarness::new(&HOLDERS, false).await?;
+
+        let alice_owner = NameOwner::random();
+        let alice_target = NameTarget {
+            public_key: Some(th.holders.get(&Holder::Alice).unwrap().keypair.public),
+            contract_id: None,
+        };
+        let bob_owner = NameOwner::random();
+        let bob_target = NameTarget {
+            public_key: Some(th.holders.get(&Holder::Bob).unwrap().keypair.public),
+            contract_id: None,
+        };
+
+        info!(target: "darkname", "[Bob] Trying to register a name without a commitment");
+        let (tx, fee_params) =
+            th.darkname_register(&Holder::Bob, "bob", bob_owner, bob_target, 100, 1).await?;
+        assert!(th.execute_darkname_tx(&Holder::Bob, tx, &fee_params, 1, true).await.is_err());
+
+        info!(target: "darkname", "[Alice] Committing to register \"darkfi\"");
+        let (tx, fee_params) = th.darkname_commit(&Holder::Alice, "darkfi", alice_owner, 1).await?;
+        for holder in &HOLDERS {
+            th.execute_darkname_tx(holder, tx.clone(), &fee_params, 1, true).await?;
+        }
+
+        info!(target: "darkname", "[Alice] Trying to commit to \"darkfi\" again");
+        let (tx, fee_params) = th.darkname_commit(&Holder::Alice, "darkfi", alice_owner, 1).await?;
+        assert!(th.execute_darkname_tx(&Holder::Alice, tx, &fee_params, 1, true).await.is_err());
+
+        info!(target: "darkname", "[Alice] Trying to register \"darkfi\" in the commitment block");
+        let (tx, fee_params) = th
+            .darkname_register(&Holder::Alice, "darkfi", alice_owner, alice_target, 100, 1)
+            .await?;
+        assert!(th.execute_darkname_tx(&Holder::Alice, tx, &fee_params, 1, true).await.is_err());
+
+        info!(target: "darkname", "[Alice] Registering \"darkfi\"");
+        let (tx, fee_params) = th
+            .darkname_register(&Holder::Alice, "darkfi", alice_owner, alice_target, 100, 2)
+            .await?;
+
+        info!(target: "darkname", "[Bob] Trying to front-run Alice's registration");
+        let (bob_tx, bob_fee_params) =
+            th.darkname_commit(&Holder::Bob, "darkfi", bob_owner, 2).await?;
+        for holder in &HOLDERS {
+            th.execute_darkname_tx(holder, bob_tx.clone(), &bob_fee_params, 2, true).await?;
+        }
+        let (bob_tx, bob_fee_params) =
+            th.darkname_register(&Holder::Bob, "darkfi", bob_owner, bob_target, 100, 2).await?;
+        assert!(th
+            .execute_darkname_tx(&Holder::Bob, bob_tx, &bob_fee_params, 2, true)
+            .await
+            .is_err());
+
+        for holder in &HOLDERS {
+            th.execute_darkname_tx(holder, tx.clone(), &fee_params, 2, true).await?;
+        }
+        let record = th.darkname_record(&Holder::Bob, "darkfi").unwrap();
+        assert_eq!(record.target, alice_target);
+        assert_eq!(record.expiry, 102);
+
+        info!(target: "darkname", "[Bob] Trying to register \"darkfi\" once his commitment matured");
+        let (tx, fee_params) =
+            th.darkname_register(&Holder::Bob, "darkfi", bob_owner, bob_target, 100, 3).await?;
+        assert!(th.execute_darkname_tx(&Holder::Bob, tx, &fee_params, 3, true).await.is_err());
+        assert_eq!(th.darkname_record(&Holder::Alice, "darkfi").unwrap().target, alice_target);
+
+        info!(target: "darkname", "[Bob] Committing to register \"bob\"");
+        let (tx, fee_params) = th.darkname_commit(&Holder::Bob, "bob", bob_owner, 3).await?;
+        for holder in &HOLDERS {
+            th.execute_darkname_tx(holder, tx.clone(), &fee_params, 3, true).await?;
+        }
+
+        info!(target: "darkname", "[Bob] Trying to register \"bob\" with an expired commitment");
+        let height = 3 + NAME_COMMIT_MAX_AGE + 1;
+        let (tx, fee_params) =
+            th.darkname_register(&Holder::Bob, "bob", bob_owner, bob_target, 100, height).await?;
+        assert!(th.execute_darkname_tx(&Holder::Bob, tx, &fee_params, height, true).await.is_err());
+        assert!(th.darkname_record(&Holder::Bob, "bob").is_none());
+
+        // Thanks for reading
+        Ok(())
+    })
+}
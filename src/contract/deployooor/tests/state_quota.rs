@@ -0,0 +1,128 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2025 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use darkfi::{
+    blockchain::{BlockchainOverlay, BlockchainOverlayPtr},
+    runtime::vm_runtime::{Runtime, STATE_RENT_PER_BYTE},
+    Result,
+};
+use darkfi_contract_test_harness::{init_logger, Holder, TestHarness};
+use darkfi_sdk::{
+    crypto::{ContractId, MONEY_CONTRACT_ID},
+    tx::TransactionHash,
+};
+use darkfi_serial::serialize;
+
+/// Deploy the DAO contract bincode as a non-native contract to the given
+/// overlay. When `quota` is given, it overrides the configured state quota.
+/// Returns the gas used, or `None` if the deployment failed.
+async fn deploy(
+    th: &TestHarness,
+    overlay: &BlockchainOverlayPtr,
+    quota: Option<Option<u64>>,
+) -> Result<Option<u64>> {
+    let wasm_bincode = include_bytes!("../../dao/darkfi_dao_contract.wasm");
+    let mut runtime = Runtime::new(
+        wasm_bincode,
+        overlay.clone(),
+        ContractId::from_bytes([42; 32])?,
+        0,
+        th.validator.consensus.module.read().await.target,
+        TransactionHash::none(),
+        0,
+    )?;
+    if let Some(quota) = quota {
+        runtime.set_state_quota(quota);
+    }
+
+    if runtime.deploy(&[]).is_err() {
+        return Ok(None)
+    }
+
+    Ok(Some(runtime.gas_used()))
+}
+
+#[test]
+fn state_quota_and_rent() -> Result<()> {
+    smol::block_on(async {
+        init_logger();
+
+        let th = TestHarness::new(&[Holder::Alice], false).await?;
+        let contract_id = ContractId::from_bytes([42; 32])?;
+
+        // Deploy without any quota to grab the state the contract needs
+        let overlay = BlockchainOverlay::new(&th.validator.blockchain)?;
+        let gas_used = deploy(&th, &overlay, Some(None)).await?.unwrap();
+        let size = overlay.lock().unwrap().contracts.get_state_size(&contract_id)?;
+        assert!(size > 0);
+
+        // The state growth must have been charged its rent
+        assert!(gas_used >= size * STATE_RENT_PER_BYTE);
+
+        // Redeploying doesn't grow the state, so no rent gets charged
+        let redeploy_gas_used = deploy(&th, &overlay, Some(None)).await?.unwrap();
+        assert_eq!(overlay.lock().unwrap().contracts.get_state_size(&contract_id)?, size);
+        assert!(redeploy_gas_used < gas_used);
+
+        // A quota fitting the state exactly is fine
+        let overlay = BlockchainOverlay::new(&th.validator.blockchain)?;
+        assert!(deploy(&th, &overlay, Some(Some(size))).await?.is_some());
+
+        // While the deployment fails if it doesn't fit
+        let overlay = BlockchainOverlay::new(&th.validator.blockchain)?;
+        assert!(deploy(&th, &overlay, Some(Some(size - 1))).await?.is_none());
+
+        // The configured quota applies when it isn't overridden
+        let overlay = BlockchainOverlay::new(&th.validator.blockchain)?;
+        overlay.lock().unwrap().state_quota = size - 1;
+        assert!(deploy(&th, &overlay, None).await?.is_none());
+
+        // Thanks for reading
+        Ok(())
+    })
+}
+
+#[test]
+fn state_size_backfill() -> Result<()> {
+    smol::block_on(async {
+        init_logger();
+
+        let th = TestHarness::new(&[Holder::Alice], false).await?;
+        let contracts = &th.validator.blockchain.contracts;
+
+        // The native contracts deployment and genesis block wrote state
+        // through all the host functions, so backfilling their sizes from
+        // the records they hold must match what the runtime accounted.
+        let states = contracts.get_all_states()?;
+        assert!(!states.is_empty());
+        let money_size = contracts.get_state_size(&MONEY_CONTRACT_ID)?;
+        assert!(money_size > 0);
+        for (contract_id, _) in states {
+            let size = contracts.get_state_size(&contract_id)?;
+
+            contracts.state_size.remove(serialize(&contract_id))?;
+            assert_eq!(contracts.get_state_size(&contract_id)?, 0);
+
+            contracts.backfill_state_sizes(&th.validator.blockchain.sled_db)?;
+            assert_eq!(contracts.get_state_size(&contract_id)?, size);
+        }
+
+        // Thanks for reading
+        Ok(())
+    })
+}
@@ -66,6 +66,12 @@ pub mod auth_token_freeze_v1;
 /// `Money::TokenMintV1` API
 pub mod token_mint_v1;
 
+/// Incoming view keys API
+pub mod view_key;
+
+/// Payment proofs API
+pub mod payment_proof;
+
 /// `MoneyNote` holds the inner attributes of a `Coin`.
 ///
 /// It does not store the public key since it's encrypted for that key,
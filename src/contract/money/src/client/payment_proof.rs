@@ -0,0 +1,207 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2025 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Payment proofs.
+//!
+//! The sender of a transfer knows the opening of every output it created,
+//! since it built their notes. A [`PaymentProof`] reveals the value and
+//! token ID of a specific transaction output along with the blinds of its
+//! commitments, so any third party holding the transaction can check that
+//! the output carries the claimed amount. The proof also reveals the coin
+//! attributes, binding the output to the recipient public key, so it can't
+//! be presented as a payment to someone else. The memo and anything about
+//! the other outputs stay private.
+
+use darkfi_sdk::{
+    crypto::{pedersen_commitment_u64, poseidon_hash, BaseBlind, FuncId, PublicKey, ScalarBlind},
+    pasta::pallas,
+    tx::TransactionHash,
+};
+use darkfi_serial::{async_trait, SerialDecodable, SerialEncodable};
+
+use super::MoneyNote;
+use crate::model::{CoinAttributes, Output, TokenId};
+
+/// Opening of the coin of a payment, binding it to its recipient
+#[derive(Debug, Clone, Eq, PartialEq, SerialEncodable, SerialDecodable)]
+pub struct PaymentRecipient {
+    /// Public key the coin was minted to
+    pub public_key: PublicKey,
+    /// Spend hook of the coin
+    pub spend_hook: FuncId,
+    /// User data of the coin
+    pub user_data: pallas::Base,
+    /// Blinding factor of the coin
+    pub coin_blind: BaseBlind,
+}
+
+/// Proof that a transaction output transferred a given amount
+#[derive(Debug, Clone, Eq, PartialEq, SerialEncodable, SerialDecodable)]
+pub struct PaymentProof {
+    /// Hash of the transaction containing the payment
+    pub tx_hash: TransactionHash,
+    /// Index of the `Money::Transfer` call in the transaction
+    pub call_idx: u32,
+    /// Index of the output in the call
+    pub output_idx: u32,
+    /// Paid value
+    pub value: u64,
+    /// Blinding factor of the output value commitment
+    pub value_blind: ScalarBlind,
+    /// Paid token ID
+    pub token_id: TokenId,
+    /// Blinding factor of the output token commitment
+    pub token_blind: BaseBlind,
+    /// Opening of the output coin, revealing the recipient
+    pub recipient: PaymentRecipient,
+}
+
+impl PaymentProof {
+    /// Create a payment proof for the output with the given note,
+    /// paid to the given recipient public key.
+    pub fn new(
+        tx_hash: TransactionHash,
+        call_idx: u32,
+        output_idx: u32,
+        note: &MoneyNote,
+        recipient: PublicKey,
+    ) -> Self {
+        let recipient = PaymentRecipient {
+            public_key: recipient,
+            spend_hook: note.spend_hook,
+            user_data: note.user_data,
+            coin_blind: note.coin_blind,
+        };
+
+        Self {
+            tx_hash,
+            call_idx,
+            output_idx,
+            value: note.value,
+            value_blind: note.value_blind,
+            token_id: note.token_id,
+            token_blind: note.token_blind,
+            recipient,
+        }
+    }
+
+    /// Verify the proof against the referenced transaction output.
+    pub fn verify(&self, output: &Output) -> bool {
+        if pedersen_commitment_u64(self.value, self.value_blind) != output.value_commit {
+            return false
+        }
+
+        if poseidon_hash([self.token_id.inner(), self.token_blind.inner()]) != output.token_commit {
+            return false
+        }
+
+        let coin = CoinAttributes {
+            public_key: self.recipient.public_key,
+            value: self.value,
+            token_id: self.token_id,
+            spend_hook: self.recipient.spend_hook,
+            user_data: self.recipient.user_data,
+            blind: self.recipient.coin_blind,
+        }
+        .to_coin();
+
+        coin == output.coin
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use darkfi_sdk::crypto::{note::AeadEncryptedNote, pasta_prelude::Field, Blind, Keypair};
+    use rand::rngs::OsRng;
+
+    use super::*;
+
+    /// Build a transaction output paying `value` to `recipient`
+    fn payment(recipient: &PublicKey, value: u64) -> (MoneyNote, Output) {
+        let note = MoneyNote {
+            value,
+            token_id: TokenId::from(pallas::Base::random(&mut OsRng)),
+            spend_hook: FuncId::none(),
+            user_data: pallas::Base::ZERO,
+            coin_blind: Blind::random(&mut OsRng),
+            value_blind: Blind::random(&mut OsRng),
+            token_blind: Blind::random(&mut OsRng),
+            memo: vec![],
+        };
+
+        let coin = CoinAttributes {
+            public_key: *recipient,
+            value: note.value,
+            token_id: note.token_id,
+            spend_hook: note.spend_hook,
+            user_data: note.user_data,
+            blind: note.coin_blind,
+        }
+        .to_coin();
+
+        let output = Output {
+            value_commit: pedersen_commitment_u64(note.value, note.value_blind),
+            token_commit: poseidon_hash([note.token_id.inner(), note.token_blind.inner()]),
+            coin,
+            note: AeadEncryptedNote::encrypt(&note, recipient, &mut OsRng).unwrap(),
+        };
+
+        (note, output)
+    }
+
+    #[test]
+    fn payment_proof_verify() {
+        let recipient = Keypair::random(&mut OsRng).public;
+        let (note, output) = payment(&recipient, 42);
+
+        let proof = PaymentProof::new(TransactionHash::none(), 0, 0, &note, recipient);
+        assert!(proof.verify(&output));
+
+        // The proof doesn't match another output
+        let (_, other) = payment(&recipient, 42);
+        assert!(!proof.verify(&other));
+
+        // Claiming another value fails
+        let mut forged = proof.clone();
+        forged.value = 43;
+        assert!(!forged.verify(&output));
+
+        // Claiming another token fails
+        let mut forged = proof.clone();
+        forged.token_id = TokenId::from(pallas::Base::random(&mut OsRng));
+        assert!(!forged.verify(&output));
+
+        // Claiming another recipient fails
+        let mut forged = proof.clone();
+        forged.recipient.public_key = Keypair::random(&mut OsRng).public;
+        assert!(!forged.verify(&output));
+        let forged = PaymentProof::new(
+            TransactionHash::none(),
+            0,
+            0,
+            &note,
+            Keypair::random(&mut OsRng).public,
+        );
+        assert!(!forged.verify(&output));
+
+        // Tampering with the coin opening fails
+        let mut forged = proof;
+        forged.recipient.coin_blind = Blind::random(&mut OsRng);
+        assert!(!forged.verify(&output));
+    }
+}
@@ -23,8 +23,8 @@ use darkfi::{
 };
 use darkfi_sdk::{
     crypto::{
-        note::AeadEncryptedNote, pasta_prelude::*, BaseBlind, Blind, MerkleNode, ScalarBlind,
-        SecretKey,
+        note::AeadEncryptedNote, pasta_prelude::*, BaseBlind, Blind, MerkleNode, PublicKey,
+        ScalarBlind, SecretKey,
     },
     pasta::pallas,
 };
@@ -46,6 +46,11 @@ pub struct TransferCallBuilder {
     pub inputs: Vec<TransferCallInput>,
     /// Anonymous outputs
     pub outputs: Vec<TransferCallOutput>,
+    /// Optional note encryption keys, indexed like `outputs`.
+    /// When set, the output note is encrypted for this key instead of the
+    /// output public key, e.g. a `ViewAddress` view key. Missing entries
+    /// default to the output public key.
+    pub output_note_keys: Vec<Option<PublicKey>>,
     /// `Mint_V1` zkas circuit ZkBinary
     pub mint_zkbin: ZkBinary,
     /// Proving key for the `Mint_V1` zk circuit
@@ -165,7 +170,9 @@ impl TransferCallBuilder {
                 memo: vec![],
             };
 
-            let encrypted_note = AeadEncryptedNote::encrypt(&note, &output.public_key, &mut OsRng)?;
+            let note_key =
+                self.output_note_keys.get(i).copied().flatten().unwrap_or(output.public_key);
+            let encrypted_note = AeadEncryptedNote::encrypt(&note, &note_key, &mut OsRng)?;
             output_notes.push(note);
 
             params.outputs.push(Output {
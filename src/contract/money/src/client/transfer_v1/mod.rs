@@ -68,6 +68,8 @@ pub fn select_coins(coins: Vec<OwnCoin>, min_value: u64) -> Result<(Vec<OwnCoin>
 ///
 /// * `keypair`: Caller's keypair
 /// * `recipient`: Recipient's public key
+/// * `recipient_view_key`: Optional public key to encrypt the recipient
+///    notes for, instead of `recipient`. Used to pay to a `ViewAddress`.
 /// * `value`: Amount that we want to send to the recipient
 /// * `token_id`: Token ID that we want to send to the recipient
 /// * `coins`: Set of `OwnCoin` we're given to use in this builder
@@ -92,6 +94,7 @@ pub fn select_coins(coins: Vec<OwnCoin>, min_value: u64) -> Result<(Vec<OwnCoin>
 pub fn make_transfer_call(
     keypair: Keypair,
     recipient: PublicKey,
+    recipient_view_key: Option<PublicKey>,
     value: u64,
     token_id: TokenId,
     coins: Vec<OwnCoin>,
@@ -188,6 +191,10 @@ pub fn make_transfer_call(
         });
     }
 
+    // Recipient notes are encrypted for their view key, if any, while
+    // the change note stays encrypted for our own key.
+    let output_note_keys = vec![recipient_view_key; outputs.len()];
+
     if change_value > 0 {
         outputs.push(TransferCallOutput {
             public_key: keypair.public,
@@ -208,6 +215,7 @@ pub fn make_transfer_call(
         clear_inputs: vec![],
         inputs,
         outputs,
+        output_note_keys,
         mint_zkbin,
         mint_pk,
         burn_zkbin,
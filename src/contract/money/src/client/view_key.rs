@@ -0,0 +1,205 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2025 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Incoming view keys.
+//!
+//! A coin's note is encrypted outside of the ZK circuits, so the key used
+//! for note encryption does not have to be the coin's public key. An
+//! [`IncomingViewKey`] is deterministically derived from a spend secret
+//! and can only decrypt notes that were sent to its [`ViewAddress`].
+//! It cannot compute nullifiers, so it cannot see when coins get spent,
+//! and it cannot spend them either. This makes it suitable to give to
+//! accountants or auditors who only need to see incoming payments.
+//!
+//! View keys are client-side only. The `Mint_V1` circuit doesn't constrain
+//! which key a note gets encrypted for, so no circuit changes or new
+//! proving keys are involved, and the view key only sees the notes that
+//! senders paying to a [`ViewAddress`] encrypt for it.
+
+use core::str::FromStr;
+
+use darkfi_sdk::{
+    crypto::{note::AeadEncryptedNote, poseidon_hash, PublicKey, SecretKey},
+    error::ContractError,
+    pasta::pallas,
+};
+
+use super::MoneyNote;
+
+/// Domain separator for incoming view key derivation (`ivk` in ASCII)
+const INCOMING_VIEW_KEY_DOMAIN: u64 = 0x6976_6b;
+
+/// Secret key able to decrypt incoming notes for a [`ViewAddress`]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct IncomingViewKey(SecretKey);
+
+impl IncomingViewKey {
+    /// Derive the incoming view key of the given spend secret
+    pub fn derive(secret: &SecretKey) -> Self {
+        let domain = pallas::Base::from(INCOMING_VIEW_KEY_DOMAIN);
+        Self(SecretKey::from(poseidon_hash([domain, secret.inner()])))
+    }
+
+    /// Get the inner `SecretKey` used for note decryption
+    pub fn secret(&self) -> SecretKey {
+        self.0
+    }
+
+    /// Get the public key notes should be encrypted for
+    pub fn public(&self) -> PublicKey {
+        PublicKey::from_secret(self.0)
+    }
+
+    /// Attempt to decrypt the given note
+    pub fn decrypt_note(&self, note: &AeadEncryptedNote) -> Option<MoneyNote> {
+        note.decrypt::<MoneyNote>(&self.0).ok()
+    }
+}
+
+impl From<SecretKey> for IncomingViewKey {
+    fn from(x: SecretKey) -> Self {
+        Self(x)
+    }
+}
+
+impl FromStr for IncomingViewKey {
+    type Err = ContractError;
+
+    /// Tries to create an `IncomingViewKey` object from a base58 encoded string.
+    fn from_str(enc: &str) -> Result<Self, Self::Err> {
+        Ok(Self(SecretKey::from_str(enc)?))
+    }
+}
+
+impl core::fmt::Display for IncomingViewKey {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+/// Address carrying both the public key coins are minted to, and the
+/// public key of its [`IncomingViewKey`] which notes get encrypted for.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct ViewAddress {
+    /// Public key the coins will be owned by
+    pub public_key: PublicKey,
+    /// Public key the coin notes will be encrypted for
+    pub view_key: PublicKey,
+}
+
+impl ViewAddress {
+    /// Create the `ViewAddress` of the given spend secret
+    pub fn from_secret(secret: &SecretKey) -> Self {
+        Self {
+            public_key: PublicKey::from_secret(*secret),
+            view_key: IncomingViewKey::derive(secret).public(),
+        }
+    }
+}
+
+impl FromStr for ViewAddress {
+    type Err = ContractError;
+
+    /// Tries to create a `ViewAddress` object from a base58 encoded string.
+    fn from_str(enc: &str) -> Result<Self, Self::Err> {
+        let decoded = bs58::decode(enc).into_vec()?;
+        if decoded.len() != 64 {
+            return Err(Self::Err::IoError(
+                "Failed decoding ViewAddress from bytes, len is not 64".to_string(),
+            ))
+        }
+
+        let public_key = PublicKey::from_bytes(decoded[..32].try_into().unwrap())?;
+        let view_key = PublicKey::from_bytes(decoded[32..].try_into().unwrap())?;
+        Ok(Self { public_key, view_key })
+    }
+}
+
+impl core::fmt::Display for ViewAddress {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        let mut bytes = self.public_key.to_bytes().to_vec();
+        bytes.extend_from_slice(&self.view_key.to_bytes());
+        write!(f, "{}", bs58::encode(bytes).into_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use darkfi_sdk::crypto::{pasta_prelude::Field, Blind, FuncId, Keypair};
+    use rand::rngs::OsRng;
+
+    use super::*;
+    use crate::model::TokenId;
+
+    fn note(value: u64) -> MoneyNote {
+        MoneyNote {
+            value,
+            token_id: TokenId::from(pallas::Base::random(&mut OsRng)),
+            spend_hook: FuncId::none(),
+            user_data: pallas::Base::ZERO,
+            coin_blind: Blind::random(&mut OsRng),
+            value_blind: Blind::random(&mut OsRng),
+            token_blind: Blind::random(&mut OsRng),
+            memo: vec![],
+        }
+    }
+
+    #[test]
+    fn view_key_derive() {
+        let secret = SecretKey::random(&mut OsRng);
+        let view_key = IncomingViewKey::derive(&secret);
+
+        // Derivation is deterministic and doesn't reveal the spend secret
+        assert_eq!(view_key, IncomingViewKey::derive(&secret));
+        assert_ne!(view_key.secret(), secret);
+        assert_ne!(view_key, IncomingViewKey::derive(&SecretKey::random(&mut OsRng)));
+
+        let address = ViewAddress::from_secret(&secret);
+        assert_eq!(address.public_key, PublicKey::from_secret(secret));
+        assert_eq!(address.view_key, view_key.public());
+
+        // Both round-trip through their string encodings
+        assert_eq!(IncomingViewKey::from_str(&view_key.to_string()).unwrap(), view_key);
+        assert_eq!(ViewAddress::from_str(&address.to_string()).unwrap(), address);
+        assert!(ViewAddress::from_str(&address.public_key.to_string()).is_err());
+    }
+
+    #[test]
+    fn view_key_scan() {
+        let keypair = Keypair::random(&mut OsRng);
+        let view_key = IncomingViewKey::derive(&keypair.secret);
+        let address = ViewAddress::from_secret(&keypair.secret);
+
+        // Notes paid to the view address are found by the view key
+        let incoming = note(42);
+        let encrypted =
+            AeadEncryptedNote::encrypt(&incoming, &address.view_key, &mut OsRng).unwrap();
+        assert_eq!(view_key.decrypt_note(&encrypted), Some(incoming));
+
+        // Other view keys can't see them
+        let other = IncomingViewKey::derive(&SecretKey::random(&mut OsRng));
+        assert!(other.decrypt_note(&encrypted).is_none());
+
+        // Notes encrypted for the plain public key, like our own change
+        // outputs, stay out of the view key's scope
+        let change = note(7);
+        let encrypted = AeadEncryptedNote::encrypt(&change, &keypair.public, &mut OsRng).unwrap();
+        assert!(view_key.decrypt_note(&encrypted).is_none());
+        assert_eq!(encrypted.decrypt::<MoneyNote>(&keypair.secret).unwrap(), change);
+    }
+}
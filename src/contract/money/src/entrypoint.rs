@@ -22,7 +22,8 @@ use darkfi_sdk::{
     error::ContractResult,
     msg,
     pasta::pallas,
-    wasm, ContractCall,
+    wasm::{self, storage::StorageMap},
+    ContractCall,
 };
 use darkfi_serial::{deserialize, serialize, Encodable, WriteExt};
 
@@ -179,8 +180,9 @@ fn init_contract(cid: ContractId, _ix: &[u8]) -> ContractResult {
     if wasm::db::db_lookup(cid, MONEY_CONTRACT_FEES_TREE).is_err() {
         let fees_db = wasm::db::db_init(cid, MONEY_CONTRACT_FEES_TREE)?;
         // Initialize the first two accumulators
-        wasm::db::db_set(fees_db, &serialize(&0_u32), &serialize(&0_u64))?;
-        wasm::db::db_set(fees_db, &serialize(&1_u32), &serialize(&0_u64))?;
+        let fees = StorageMap::<u32, u64>::new(fees_db);
+        fees.insert(&0, &0)?;
+        fees.insert(&1, &0)?;
     }
 
     // Set up a database tree for arbitrary data
@@ -22,13 +22,14 @@ use darkfi_sdk::{
     error::{ContractError, ContractResult},
     msg,
     pasta::pallas,
-    wasm, ContractCall,
+    wasm::{self, storage::StorageSet},
+    ContractCall,
 };
 use darkfi_serial::{deserialize, serialize, Encodable, WriteExt};
 
 use crate::{
     error::MoneyError,
-    model::{MoneyAuthTokenFreezeParamsV1, MoneyAuthTokenFreezeUpdateV1},
+    model::{MoneyAuthTokenFreezeParamsV1, MoneyAuthTokenFreezeUpdateV1, TokenId},
     MoneyFunction, MONEY_CONTRACT_TOKEN_FREEZE_TREE, MONEY_CONTRACT_ZKAS_AUTH_TOKEN_MINT_NS_V1,
 };
 
@@ -76,7 +77,7 @@ pub(crate) fn money_auth_token_freeze_process_instruction_v1(
     let token_freeze_db = wasm::db::db_lookup(cid, MONEY_CONTRACT_TOKEN_FREEZE_TREE)?;
 
     // Check that the mint is not frozen
-    if wasm::db::db_contains_key(token_freeze_db, &serialize(&params.token_id))? {
+    if StorageSet::<TokenId>::new(token_freeze_db).contains(&params.token_id)? {
         msg!("[AuthTokenFreezeV1] Error: Token mint for {} is frozen", params.token_id);
         return Err(MoneyError::TokenMintFrozen.into())
     }
@@ -97,7 +98,7 @@ pub(crate) fn money_auth_token_freeze_process_update_v1(
 ) -> ContractResult {
     let token_freeze_db = wasm::db::db_lookup(cid, MONEY_CONTRACT_TOKEN_FREEZE_TREE)?;
     msg!("[AuthTokenFreezeV1] Freezing mint for token {}", update.token_id);
-    wasm::db::db_set(token_freeze_db, &serialize(&update.token_id), &[])?;
+    StorageSet::<TokenId>::new(token_freeze_db).insert(&update.token_id)?;
 
     Ok(())
 }
@@ -30,13 +30,17 @@ use darkfi_sdk::{
     error::{ContractError, ContractResult},
     msg,
     pasta::pallas,
-    wasm, ContractCall,
+    wasm::{
+        self,
+        storage::{StorageMap, StorageSet},
+    },
+    ContractCall,
 };
 use darkfi_serial::{deserialize, serialize, Encodable, WriteExt};
 
 use crate::{
     error::MoneyError,
-    model::{MoneyFeeParamsV1, MoneyFeeUpdateV1, DARK_TOKEN_ID},
+    model::{Coin, MoneyFeeParamsV1, MoneyFeeUpdateV1, DARK_TOKEN_ID},
     MoneyFunction, MONEY_CONTRACT_COINS_TREE, MONEY_CONTRACT_COIN_MERKLE_TREE,
     MONEY_CONTRACT_COIN_ROOTS_TREE, MONEY_CONTRACT_FEES_TREE, MONEY_CONTRACT_INFO_TREE,
     MONEY_CONTRACT_LATEST_COIN_ROOT, MONEY_CONTRACT_LATEST_NULLIFIER_ROOT,
@@ -150,7 +154,7 @@ pub(crate) fn money_fee_process_instruction_v1(
     }
 
     // The new coin should not exist
-    if wasm::db::db_contains_key(coins_db, &serialize(&params.output.coin))? {
+    if StorageSet::<Coin>::new(coins_db).contains(&params.output.coin)? {
         msg!("[FeeV1] Error: Duplicate coin found");
         return Err(MoneyError::DuplicateCoin.into())
     }
@@ -179,8 +183,7 @@ pub(crate) fn money_fee_process_instruction_v1(
 
     // Accumulate the height paid fee
     let verifying_block_height = wasm::util::get_verifying_block_height()?;
-    let mut paid_fee: u64 =
-        deserialize(&wasm::db::db_get(fees_db, &serialize(&verifying_block_height))?.unwrap())?;
+    let mut paid_fee = StorageMap::<u32, u64>::new(fees_db).get(&verifying_block_height)?.unwrap();
     paid_fee += fee;
 
     // At this point the state transition has passed, so we create a state update.
@@ -210,7 +213,7 @@ pub(crate) fn money_fee_process_update_v1(
     let nullifier_roots_db = wasm::db::db_lookup(cid, MONEY_CONTRACT_NULLIFIER_ROOTS_TREE)?;
     let fees_db = wasm::db::db_lookup(cid, MONEY_CONTRACT_FEES_TREE)?;
 
-    wasm::db::db_set(fees_db, &serialize(&update.height), &serialize(&update.fee))?;
+    StorageMap::<u32, u64>::new(fees_db).insert(&update.height, &update.fee)?;
 
     wasm::merkle::sparse_merkle_insert_batch(
         info_db,
@@ -220,7 +223,7 @@ pub(crate) fn money_fee_process_update_v1(
         &[update.nullifier.inner()],
     )?;
 
-    wasm::db::db_set(coins_db, &serialize(&update.coin), &[])?;
+    StorageSet::<Coin>::new(coins_db).insert(&update.coin)?;
 
     wasm::merkle::merkle_add(
         info_db,
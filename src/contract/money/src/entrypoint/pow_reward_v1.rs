@@ -23,13 +23,17 @@ use darkfi_sdk::{
     error::{ContractError, ContractResult},
     msg,
     pasta::pallas,
-    wasm, ContractCall,
+    wasm::{
+        self,
+        storage::{StorageMap, StorageSet},
+    },
+    ContractCall,
 };
 use darkfi_serial::{deserialize, serialize, Encodable, WriteExt};
 
 use crate::{
     error::MoneyError,
-    model::{MoneyPoWRewardParamsV1, MoneyPoWRewardUpdateV1, DARK_TOKEN_ID},
+    model::{Coin, MoneyPoWRewardParamsV1, MoneyPoWRewardUpdateV1, DARK_TOKEN_ID},
     MoneyFunction, MONEY_CONTRACT_COINS_TREE, MONEY_CONTRACT_COIN_MERKLE_TREE,
     MONEY_CONTRACT_COIN_ROOTS_TREE, MONEY_CONTRACT_FEES_TREE, MONEY_CONTRACT_INFO_TREE,
     MONEY_CONTRACT_LATEST_COIN_ROOT, MONEY_CONTRACT_LATEST_NULLIFIER_ROOT,
@@ -111,8 +115,7 @@ pub(crate) fn money_pow_reward_process_instruction_v1(
 
     // Grab the currect height accumulated fees
     let fees_db = wasm::db::db_lookup(cid, MONEY_CONTRACT_FEES_TREE)?;
-    let paid_fee: u64 =
-        deserialize(&wasm::db::db_get(fees_db, &serialize(&verifying_block_height))?.unwrap())?;
+    let paid_fee = StorageMap::<u32, u64>::new(fees_db).get(&verifying_block_height)?.unwrap();
 
     // Verify reward value matches the expected one for this block height,
     // including the paid fees.
@@ -131,7 +134,7 @@ pub(crate) fn money_pow_reward_process_instruction_v1(
     let coins_db = wasm::db::db_lookup(cid, MONEY_CONTRACT_COINS_TREE)?;
 
     // Check that the coin from the output hasn't existed before.
-    if wasm::db::db_contains_key(coins_db, &serialize(&params.output.coin))? {
+    if StorageSet::<Coin>::new(coins_db).contains(&params.output.coin)? {
         msg!("[PoWRewardV1] Error: Duplicate coin in output");
         return Err(MoneyError::DuplicateCoin.into())
     }
@@ -178,7 +181,7 @@ pub(crate) fn money_pow_reward_process_update_v1(
 
     // Generate the accumulator for the next height
     msg!("[PowRewardV1] Creating next height fees acummulator");
-    wasm::db::db_set(fees_db, &serialize(&(update.height + 1)), &serialize(&0_u64))?;
+    StorageMap::<u32, u64>::new(fees_db).insert(&(update.height + 1), &0)?;
 
     // This will just make a snapshot to match the coins one
     msg!("[PowRewardV1] Updating nullifiers snapshot");
@@ -191,7 +194,7 @@ pub(crate) fn money_pow_reward_process_update_v1(
     )?;
 
     msg!("[PoWRewardV1] Adding new coin to the set");
-    wasm::db::db_set(coins_db, &serialize(&update.coin), &[])?;
+    StorageSet::<Coin>::new(coins_db).insert(&update.coin)?;
 
     msg!("[PoWRewardV1] Adding new coin to the Merkle tree");
     let coins = vec![MerkleNode::from(update.coin.inner())];
@@ -29,14 +29,15 @@ use darkfi_sdk::{
     error::{ContractError, ContractResult},
     msg,
     pasta::pallas,
-    wasm, ContractCall,
+    wasm::{self, storage::StorageSet},
+    ContractCall,
 };
 use darkfi_serial::{deserialize, serialize, Encodable, WriteExt};
 
 use super::transfer_v1::{money_transfer_get_metadata_v1, money_transfer_process_update_v1};
 use crate::{
     error::MoneyError,
-    model::{MoneyTransferParamsV1, MoneyTransferUpdateV1},
+    model::{Coin, MoneyTransferParamsV1, MoneyTransferUpdateV1},
     MoneyFunction, MONEY_CONTRACT_COINS_TREE, MONEY_CONTRACT_COIN_ROOTS_TREE,
     MONEY_CONTRACT_NULLIFIERS_TREE,
 };
@@ -136,7 +137,7 @@ pub(crate) fn money_otcswap_process_instruction_v1(
     // Newly created coins for this call are in the outputs
     for (i, output) in params.outputs.iter().enumerate() {
         if new_coins.contains(&output.coin) ||
-            wasm::db::db_contains_key(coins_db, &serialize(&output.coin))?
+            StorageSet::<Coin>::new(coins_db).contains(&output.coin)?
         {
             msg!("[OtcSwapV1] Error: Duplicate coin found in output {}", i);
             return Err(MoneyError::DuplicateCoin.into())
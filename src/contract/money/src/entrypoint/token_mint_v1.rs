@@ -22,13 +22,14 @@ use darkfi_sdk::{
     error::{ContractError, ContractResult},
     msg,
     pasta::pallas,
-    wasm, ContractCall,
+    wasm::{self, storage::StorageSet},
+    ContractCall,
 };
 use darkfi_serial::{deserialize, serialize, Encodable, WriteExt};
 
 use crate::{
     error::MoneyError,
-    model::{MoneyTokenMintParamsV1, MoneyTokenMintUpdateV1},
+    model::{Coin, MoneyTokenMintParamsV1, MoneyTokenMintUpdateV1},
     MoneyFunction, MONEY_CONTRACT_COINS_TREE, MONEY_CONTRACT_COIN_MERKLE_TREE,
     MONEY_CONTRACT_COIN_ROOTS_TREE, MONEY_CONTRACT_INFO_TREE, MONEY_CONTRACT_LATEST_COIN_ROOT,
     MONEY_CONTRACT_LATEST_NULLIFIER_ROOT, MONEY_CONTRACT_NULLIFIERS_TREE,
@@ -93,7 +94,7 @@ pub(crate) fn money_token_mint_process_instruction_v1(
     let coins_db = wasm::db::db_lookup(cid, MONEY_CONTRACT_COINS_TREE)?;
 
     // Check that the coin from the output hasn't existed before
-    if wasm::db::db_contains_key(coins_db, &serialize(&params.coin))? {
+    if StorageSet::<Coin>::new(coins_db).contains(&params.coin)? {
         msg!("[TokenMintV1] Error: Duplicate coin in output");
         return Err(MoneyError::DuplicateCoin.into())
     }
@@ -130,7 +131,7 @@ pub(crate) fn money_token_mint_process_update_v1(
     )?;
 
     msg!("[TokenMintV1] Adding new coin to the set");
-    wasm::db::db_set(coins_db, &serialize(&update.coin), &[])?;
+    StorageSet::<Coin>::new(coins_db).insert(&update.coin)?;
 
     msg!("[TokenMintV1] Adding new coin to the Merkle tree");
     let coins = vec![MerkleNode::from(update.coin.inner())];
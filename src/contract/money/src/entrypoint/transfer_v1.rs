@@ -29,13 +29,14 @@ use darkfi_sdk::{
     error::{ContractError, ContractResult},
     msg,
     pasta::pallas,
-    wasm, ContractCall,
+    wasm::{self, storage::StorageSet},
+    ContractCall,
 };
 use darkfi_serial::{deserialize, serialize, Encodable, WriteExt};
 
 use crate::{
     error::MoneyError,
-    model::{MoneyTransferParamsV1, MoneyTransferUpdateV1},
+    model::{Coin, MoneyTransferParamsV1, MoneyTransferUpdateV1},
     MoneyFunction, MONEY_CONTRACT_COINS_TREE, MONEY_CONTRACT_COIN_MERKLE_TREE,
     MONEY_CONTRACT_COIN_ROOTS_TREE, MONEY_CONTRACT_INFO_TREE, MONEY_CONTRACT_LATEST_COIN_ROOT,
     MONEY_CONTRACT_LATEST_NULLIFIER_ROOT, MONEY_CONTRACT_NULLIFIERS_TREE,
@@ -184,7 +185,7 @@ pub(crate) fn money_transfer_process_instruction_v1(
     msg!("[TransferV1] Iterating over anonymous outputs");
     for (i, output) in params.outputs.iter().enumerate() {
         if new_coins.contains(&output.coin) ||
-            wasm::db::db_contains_key(coins_db, &serialize(&output.coin))?
+            StorageSet::<Coin>::new(coins_db).contains(&output.coin)?
         {
             msg!("[TransferV1] Error: Duplicate coin found in output {}", i);
             return Err(MoneyError::DuplicateCoin.into())
@@ -248,7 +249,7 @@ pub(crate) fn money_transfer_process_update_v1(
 
     msg!("[TransferV1] Adding new coins to the set");
     for coin in &update.coins {
-        wasm::db::db_set(coins_db, &serialize(coin), &[])?;
+        StorageSet::<Coin>::new(coins_db).insert(coin)?;
     }
 
     msg!("[TransferV1] Adding new coins to the Merkle tree");
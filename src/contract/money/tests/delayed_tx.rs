@@ -88,6 +88,7 @@ fn delayed_tx() -> Result<()> {
         let (alice_xfer_params, secrets, _) = make_transfer_call(
             wallet.keypair,
             rcpt,
+            None,
             alice_coins[0].note.value / 2,
             alice_coins[0].note.token_id,
             alice_coins.to_owned(),
@@ -0,0 +1,158 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2025 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Integration test verifying that parallel block transactions verification
+//! yields the same outcome as the sequential one.
+
+use darkfi::{
+    blockchain::BlockchainOverlay,
+    error::TxVerifyFailed,
+    tx::Transaction,
+    validator::{parallel::TxScheduler, verification::verify_transactions, Validator},
+    Error, Result,
+};
+use darkfi_contract_test_harness::{init_logger, Holder, TestHarness};
+use darkfi_sdk::{
+    crypto::{MerkleNode, MerkleTree},
+    tx::TransactionHash,
+};
+use darkfi_serial::serialize;
+
+/// Height the transactions get verified at
+const VERIFYING_HEIGHT: u32 = 7;
+
+/// Outcome of a transactions set verification: the gas used and paid or the
+/// erroneous transactions, the resulting transactions Merkle root, and the
+/// serialized state diff of the overlay.
+type Outcome = (std::result::Result<(u64, u64), Vec<TransactionHash>>, Option<MerkleNode>, Vec<u8>);
+
+/// Verify given transactions over a fresh overlay, either in sequence or
+/// using the provided scheduler.
+async fn verify(
+    validator: &Validator,
+    scheduler: Option<&TxScheduler>,
+    txs: &[Transaction],
+) -> Result<Outcome> {
+    let target = validator.consensus.module.read().await.target;
+    let overlay = BlockchainOverlay::new(&validator.blockchain)?;
+    let mut tree = MerkleTree::new(1);
+
+    let result = match scheduler {
+        Some(scheduler) => {
+            scheduler
+                .verify_transactions(&overlay, VERIFYING_HEIGHT, target, txs, &mut tree, true)
+                .await
+        }
+        None => verify_transactions(&overlay, VERIFYING_HEIGHT, target, txs, &mut tree, true).await,
+    };
+    let result = match result {
+        Ok(gas) => Ok(gas),
+        Err(Error::TxVerifyFailed(TxVerifyFailed::ErroneousTxs(erroneous))) => {
+            Err(erroneous.iter().map(|tx| tx.hash()).collect())
+        }
+        Err(e) => return Err(e),
+    };
+
+    let diff = overlay.lock().unwrap().overlay.lock().unwrap().diff(&[])?;
+    Ok((result, tree.root(0), serialize(&diff)))
+}
+
+#[test]
+fn parallel_verification() -> Result<()> {
+    smol::block_on(async {
+        init_logger();
+
+        // Holders this test will use
+        const HOLDERS: [Holder; 3] = [Holder::Alice, Holder::Bob, Holder::Charlie];
+
+        // Initialize harness
+        let mut th = TestHarness::new(&HOLDERS, true).await?;
+
+        // Generate two new blocks mined by each holder
+        for holder in &HOLDERS {
+            th.generate_block(holder, &HOLDERS).await?;
+            th.generate_block(holder, &HOLDERS).await?;
+        }
+
+        // Each holder pays the next one
+        let mut txs = vec![];
+        for (i, holder) in HOLDERS.iter().enumerate() {
+            let recipient = &HOLDERS[(i + 1) % HOLDERS.len()];
+            let coin = th.holders.get(holder).unwrap().unspent_money_coins[0].clone();
+            let (tx, _, _) = th
+                .transfer(
+                    coin.note.value,
+                    holder,
+                    recipient,
+                    &[coin.clone()],
+                    coin.note.token_id,
+                    VERIFYING_HEIGHT,
+                    false,
+                )
+                .await?;
+            txs.push(tx);
+        }
+
+        let validator = th.holders.get(&Holder::Alice).unwrap().validator.clone();
+        let target = validator.consensus.module.read().await.target;
+        let scheduler = TxScheduler::with_workers(4);
+
+        // Money metadata doesn't look up any state, so the transfers
+        // share a single wave
+        let overlay = BlockchainOverlay::new(&validator.blockchain)?;
+        assert_eq!(
+            scheduler.schedule(&overlay, VERIFYING_HEIGHT, target, &txs).await,
+            vec![vec![0, 1, 2]]
+        );
+
+        // Both verifications must yield the same gas, root and state
+        let sequential = verify(&validator, None, &txs).await?;
+        let parallel = verify(&validator, Some(&scheduler), &txs).await?;
+        assert!(sequential.0.is_ok());
+        assert_eq!(sequential, parallel);
+
+        // Alice spending her coin again only gets detected when executing
+        // it, so parallel verification falls back to the sequential one and
+        // both reject it
+        let coin = th.holders.get(&Holder::Alice).unwrap().unspent_money_coins[0].clone();
+        let (double_spend, _, _) = th
+            .transfer(
+                coin.note.value,
+                &Holder::Alice,
+                &Holder::Charlie,
+                &[coin.clone()],
+                coin.note.token_id,
+                VERIFYING_HEIGHT,
+                false,
+            )
+            .await?;
+        txs.push(double_spend.clone());
+        assert_eq!(
+            scheduler.schedule(&overlay, VERIFYING_HEIGHT, target, &txs).await,
+            vec![vec![0, 1, 2, 3]]
+        );
+
+        let sequential = verify(&validator, None, &txs).await?;
+        let parallel = verify(&validator, Some(&scheduler), &txs).await?;
+        assert_eq!(sequential.0, Err(vec![double_spend.hash()]));
+        assert_eq!(sequential, parallel);
+
+        // Thanks for reading
+        Ok(())
+    })
+}
@@ -0,0 +1,66 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2025 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Read-only API for other contracts to consume oracle feeds.
+//!
+//! Contracts depending on this crate with the `no-entrypoint` feature
+//! can call these functions from any of their entrypoints to read the
+//! latest value attested for a feed:
+//!
+//! ```ignore
+//! let Some(price) = darkfi_oracle_contract::api::fresh_value(&feed_id, 10)? else {
+//!     return Err(MyError::StalePrice.into())
+//! };
+//! ```
+
+use darkfi_sdk::{
+    crypto::ORACLE_CONTRACT_ID,
+    error::GenericResult,
+    wasm::{self, storage::StorageMap},
+};
+
+use crate::{
+    model::{AttestedValue, FeedId, OracleFeed},
+    ORACLE_CONTRACT_FEEDS_TREE, ORACLE_CONTRACT_VALUES_TREE,
+};
+
+/// Fetch the record of the given feed, if it is registered.
+pub fn feed(feed_id: &FeedId) -> GenericResult<Option<OracleFeed>> {
+    let feeds_db = wasm::db::db_lookup(*ORACLE_CONTRACT_ID, ORACLE_CONTRACT_FEEDS_TREE)?;
+    StorageMap::<FeedId, OracleFeed>::new(feeds_db).get(feed_id)
+}
+
+/// Fetch the latest value attested for the given feed, if any round
+/// has been accepted yet.
+pub fn latest_value(feed_id: &FeedId) -> GenericResult<Option<AttestedValue>> {
+    let values_db = wasm::db::db_lookup(*ORACLE_CONTRACT_ID, ORACLE_CONTRACT_VALUES_TREE)?;
+    StorageMap::<FeedId, AttestedValue>::new(values_db).get(feed_id)
+}
+
+/// Fetch the latest value attested for the given feed, only if it was
+/// accepted at most `max_age` blocks before the block being verified.
+pub fn fresh_value(feed_id: &FeedId, max_age: u32) -> GenericResult<Option<AttestedValue>> {
+    let Some(value) = latest_value(feed_id)? else { return Ok(None) };
+
+    let verifying_block_height = wasm::util::get_verifying_block_height()?;
+    if verifying_block_height.saturating_sub(value.height) > max_age {
+        return Ok(None)
+    }
+
+    Ok(Some(value))
+}
@@ -0,0 +1,45 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2025 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! This module implements the client-side API for registering oracle
+//! feeds, signing attestations, and submitting attested rounds on the
+//! DarkFi network.
+
+use darkfi_sdk::crypto::{schnorr::SchnorrSecret, PublicKey, SecretKey};
+
+use crate::model::{attestation_message, FeedId, OracleAttestation};
+
+/// `Oracle::RegisterV1` API
+pub mod register_v1;
+
+/// `Oracle::RotateV1` API
+pub mod rotate_v1;
+
+/// `Oracle::SubmitV1` API
+pub mod submit_v1;
+
+/// Sign `value` for the given feed round with an oracle secret key.
+pub fn sign_attestation(
+    secret: &SecretKey,
+    feed_id: &FeedId,
+    round: u64,
+    value: u64,
+) -> OracleAttestation {
+    let signature = secret.sign(&attestation_message(feed_id, round, value));
+    OracleAttestation { oracle: PublicKey::from_secret(*secret), value, signature }
+}
@@ -0,0 +1,71 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2025 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use darkfi::{Error, Result};
+use darkfi_sdk::crypto::PublicKey;
+use log::debug;
+
+use crate::{
+    is_valid_feed_name,
+    model::{is_valid_oracle_set, FeedId, FeedKind, OracleFeed, OracleRegisterParamsV1},
+};
+
+pub struct OracleRegisterCallDebris {
+    pub params: OracleRegisterParamsV1,
+    pub feed_id: FeedId,
+}
+
+/// Struct holding necessary information to build an `Oracle::RegisterV1` contract call.
+/// The transaction has to be signed with the authority secret key.
+pub struct OracleRegisterCallBuilder {
+    /// Public key allowed to rotate the oracle set
+    pub authority: PublicKey,
+    /// Human-readable feed name
+    pub name: String,
+    /// Kind of data the feed carries
+    pub kind: FeedKind,
+    /// Public keys of the oracles attesting to the feed
+    pub oracles: Vec<PublicKey>,
+    /// Amount of oracle attestations required to accept a round
+    pub threshold: u8,
+}
+
+impl OracleRegisterCallBuilder {
+    pub fn build(&self) -> Result<OracleRegisterCallDebris> {
+        debug!(target: "contract::oracle::client::register", "Building Oracle::RegisterV1 contract call");
+
+        if !is_valid_feed_name(&self.name) {
+            return Err(Error::Custom(format!("Invalid feed name: {}", self.name)))
+        }
+
+        if !is_valid_oracle_set(&self.oracles, self.threshold) {
+            return Err(Error::Custom("Invalid oracle set".to_string()))
+        }
+
+        let feed = OracleFeed {
+            authority: self.authority,
+            name: self.name.clone(),
+            kind: self.kind,
+            oracles: self.oracles.clone(),
+            threshold: self.threshold,
+        };
+        let feed_id = FeedId::derive(&feed.authority, &feed.name);
+
+        Ok(OracleRegisterCallDebris { params: OracleRegisterParamsV1 { feed }, feed_id })
+    }
+}
@@ -0,0 +1,56 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2025 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use darkfi::{Error, Result};
+use darkfi_sdk::crypto::PublicKey;
+use log::debug;
+
+use crate::model::{is_valid_oracle_set, FeedId, OracleRotateParamsV1};
+
+pub struct OracleRotateCallDebris {
+    pub params: OracleRotateParamsV1,
+}
+
+/// Struct holding necessary information to build an `Oracle::RotateV1` contract call.
+/// The transaction has to be signed with the feed authority secret key.
+pub struct OracleRotateCallBuilder {
+    /// The `FeedId` to rotate the oracle set of
+    pub feed_id: FeedId,
+    /// The new oracle set
+    pub oracles: Vec<PublicKey>,
+    /// The new threshold
+    pub threshold: u8,
+}
+
+impl OracleRotateCallBuilder {
+    pub fn build(&self) -> Result<OracleRotateCallDebris> {
+        debug!(target: "contract::oracle::client::rotate", "Building Oracle::RotateV1 contract call");
+
+        if !is_valid_oracle_set(&self.oracles, self.threshold) {
+            return Err(Error::Custom("Invalid oracle set".to_string()))
+        }
+
+        let params = OracleRotateParamsV1 {
+            feed_id: self.feed_id,
+            oracles: self.oracles.clone(),
+            threshold: self.threshold,
+        };
+
+        Ok(OracleRotateCallDebris { params })
+    }
+}
@@ -0,0 +1,93 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2025 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use darkfi::{Error, Result};
+use darkfi_sdk::crypto::{schnorr::SchnorrPublic, PublicKey};
+use log::{debug, warn};
+
+use crate::model::{
+    aggregate_attestations, attestation_message, FeedId, OracleAttestation, OracleFeed,
+    OracleSubmitParamsV1,
+};
+
+pub struct OracleSubmitCallDebris {
+    pub params: OracleSubmitParamsV1,
+    /// The value the round is expected to be accepted with
+    pub value: u64,
+}
+
+/// Struct holding necessary information to build an `Oracle::SubmitV1` contract call.
+/// Submissions require no signatures, so anyone holding enough attestations
+/// for a round can relay it.
+pub struct OracleSubmitCallBuilder<'a> {
+    /// The `FeedId` the round belongs to
+    pub feed_id: FeedId,
+    /// The current on-chain record of the feed
+    pub feed: &'a OracleFeed,
+    /// The submitted round
+    pub round: u64,
+    /// Gathered oracle attestations for the round
+    pub attestations: Vec<OracleAttestation>,
+}
+
+impl OracleSubmitCallBuilder<'_> {
+    pub fn build(&self) -> Result<OracleSubmitCallDebris> {
+        debug!(target: "contract::oracle::client::submit", "Building Oracle::SubmitV1 contract call");
+
+        // Keep only the attestations the contract would accept, so a single
+        // misbehaving or outdated oracle can't make the whole round fail.
+        let mut attestations: Vec<OracleAttestation> = vec![];
+        let mut signers: Vec<PublicKey> = vec![];
+        for attestation in &self.attestations {
+            if !self.feed.oracles.contains(&attestation.oracle) {
+                warn!(target: "contract::oracle::client::submit", "Skipping attestation from unknown oracle {}", attestation.oracle);
+                continue
+            }
+
+            if signers.contains(&attestation.oracle) {
+                warn!(target: "contract::oracle::client::submit", "Skipping duplicate attestation from {}", attestation.oracle);
+                continue
+            }
+
+            let message = attestation_message(&self.feed_id, self.round, attestation.value);
+            if !attestation.oracle.verify(&message, &attestation.signature) {
+                warn!(target: "contract::oracle::client::submit", "Skipping invalid attestation from {}", attestation.oracle);
+                continue
+            }
+
+            signers.push(attestation.oracle);
+            attestations.push(*attestation);
+        }
+
+        if attestations.len() < self.feed.threshold as usize {
+            return Err(Error::Custom(format!(
+                "Got {} valid attestations for round {}, threshold is {}",
+                attestations.len(),
+                self.round,
+                self.feed.threshold
+            )))
+        }
+
+        let (value, _) =
+            aggregate_attestations(self.feed.kind, &self.feed_id, self.round, &attestations);
+        let params =
+            OracleSubmitParamsV1 { feed_id: self.feed_id, round: self.round, attestations };
+
+        Ok(OracleSubmitCallDebris { params, value })
+    }
+}
@@ -0,0 +1,135 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2025 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use darkfi_sdk::{
+    crypto::ContractId, dark_tree::DarkLeaf, error::ContractResult, wasm, ContractCall,
+};
+use darkfi_serial::{deserialize, serialize};
+
+use crate::{
+    model::{OracleRegisterUpdateV1, OracleRotateUpdateV1, OracleSubmitUpdateV1},
+    OracleFunction, ORACLE_CONTRACT_DB_VERSION, ORACLE_CONTRACT_FEEDS_TREE,
+    ORACLE_CONTRACT_INFO_TREE, ORACLE_CONTRACT_VALUES_TREE,
+};
+
+/// `Oracle::Register` functions
+mod register_v1;
+use register_v1::{
+    register_get_metadata_v1, register_process_instruction_v1, register_process_update_v1,
+};
+
+/// `Oracle::Rotate` functions
+mod rotate_v1;
+use rotate_v1::{rotate_get_metadata_v1, rotate_process_instruction_v1, rotate_process_update_v1};
+
+/// `Oracle::Submit` functions
+mod submit_v1;
+use submit_v1::{submit_get_metadata_v1, submit_process_instruction_v1, submit_process_update_v1};
+
+darkfi_sdk::define_contract!(
+    init: init_contract,
+    exec: process_instruction,
+    apply: process_update,
+    metadata: get_metadata
+);
+
+/// This entrypoint function runs when the contract is (re)deployed and initialized.
+/// We use this function to initialize all the necessary databases and prepare them
+/// with initial data if necessary.
+fn init_contract(cid: ContractId, _ix: &[u8]) -> ContractResult {
+    // Set up a database tree for arbitrary data
+    let info_db = match wasm::db::db_lookup(cid, ORACLE_CONTRACT_INFO_TREE) {
+        Ok(v) => v,
+        Err(_) => wasm::db::db_init(cid, ORACLE_CONTRACT_INFO_TREE)?,
+    };
+
+    // Set up a database to hold the registered feeds
+    // k=FeedId, v=OracleFeed
+    if wasm::db::db_lookup(cid, ORACLE_CONTRACT_FEEDS_TREE).is_err() {
+        wasm::db::db_init(cid, ORACLE_CONTRACT_FEEDS_TREE)?;
+    }
+
+    // Set up a database to hold the latest attested value of each feed
+    // k=FeedId, v=AttestedValue
+    if wasm::db::db_lookup(cid, ORACLE_CONTRACT_VALUES_TREE).is_err() {
+        wasm::db::db_init(cid, ORACLE_CONTRACT_VALUES_TREE)?;
+    }
+
+    // Update db version
+    wasm::db::db_set(info_db, ORACLE_CONTRACT_DB_VERSION, &serialize(&env!("CARGO_PKG_VERSION")))?;
+
+    Ok(())
+}
+
+/// This function is used by the wasm VM's host to fetch the necessary metadata
+/// for verifying signatures and zk proofs. The payload given here are all the
+/// contract calls in the transaction.
+fn get_metadata(cid: ContractId, ix: &[u8]) -> ContractResult {
+    let call_idx = wasm::util::get_call_index()? as usize;
+    let calls: Vec<DarkLeaf<ContractCall>> = deserialize(ix)?;
+    let self_ = &calls[call_idx].data;
+    let func = OracleFunction::try_from(self_.data[0])?;
+
+    let metadata = match func {
+        OracleFunction::RegisterV1 => register_get_metadata_v1(cid, call_idx, calls)?,
+        OracleFunction::RotateV1 => rotate_get_metadata_v1(cid, call_idx, calls)?,
+        OracleFunction::SubmitV1 => submit_get_metadata_v1(cid, call_idx, calls)?,
+    };
+
+    wasm::util::set_return_data(&metadata)
+}
+
+/// This function verifies a state transition and produces a state update
+/// if everything is successful.
+fn process_instruction(cid: ContractId, ix: &[u8]) -> ContractResult {
+    let call_idx = wasm::util::get_call_index()? as usize;
+    let calls: Vec<DarkLeaf<ContractCall>> = deserialize(ix)?;
+    let self_ = &calls[call_idx].data;
+    let func = OracleFunction::try_from(self_.data[0])?;
+
+    let update_data = match func {
+        OracleFunction::RegisterV1 => register_process_instruction_v1(cid, call_idx, calls)?,
+        OracleFunction::RotateV1 => rotate_process_instruction_v1(cid, call_idx, calls)?,
+        OracleFunction::SubmitV1 => submit_process_instruction_v1(cid, call_idx, calls)?,
+    };
+
+    wasm::util::set_return_data(&update_data)
+}
+
+/// This function attempts to write a given state update provided the previous
+/// steps of the contract call execution were all successful. It's the last in
+/// line, and assumes that the transaction/call was successful. The payload
+/// given to the function is the update data retrieved from `process_instruction()`.
+fn process_update(cid: ContractId, update_data: &[u8]) -> ContractResult {
+    match OracleFunction::try_from(update_data[0])? {
+        OracleFunction::RegisterV1 => {
+            let update: OracleRegisterUpdateV1 = deserialize(&update_data[1..])?;
+            Ok(register_process_update_v1(cid, update)?)
+        }
+
+        OracleFunction::RotateV1 => {
+            let update: OracleRotateUpdateV1 = deserialize(&update_data[1..])?;
+            Ok(rotate_process_update_v1(cid, update)?)
+        }
+
+        OracleFunction::SubmitV1 => {
+            let update: OracleSubmitUpdateV1 = deserialize(&update_data[1..])?;
+            Ok(submit_process_update_v1(cid, update)?)
+        }
+    }
+}
@@ -0,0 +1,106 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2025 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use darkfi_sdk::{
+    crypto::{ContractId, PublicKey},
+    dark_tree::DarkLeaf,
+    error::{ContractError, ContractResult},
+    msg,
+    pasta::pallas,
+    wasm::{self, storage::StorageMap},
+    ContractCall,
+};
+use darkfi_serial::{deserialize, Encodable, WriteExt};
+
+use crate::{
+    error::OracleError,
+    is_valid_feed_name,
+    model::{
+        is_valid_oracle_set, FeedId, OracleFeed, OracleRegisterParamsV1, OracleRegisterUpdateV1,
+    },
+    OracleFunction, ORACLE_CONTRACT_FEEDS_TREE,
+};
+
+/// `get_metadata` function for `Oracle::RegisterV1`
+pub(crate) fn register_get_metadata_v1(
+    _cid: ContractId,
+    call_idx: usize,
+    calls: Vec<DarkLeaf<ContractCall>>,
+) -> Result<Vec<u8>, ContractError> {
+    let self_ = &calls[call_idx];
+    let params: OracleRegisterParamsV1 = deserialize(&self_.data.data[1..])?;
+
+    // The feed authority has to sign the registration, so nobody
+    // can register feeds on someone else's behalf.
+    let zk_public_inputs: Vec<(String, Vec<pallas::Base>)> = vec![];
+    let signature_pubkeys: Vec<PublicKey> = vec![params.feed.authority];
+
+    // Serialize everything gathered and return it
+    let mut metadata = vec![];
+    zk_public_inputs.encode(&mut metadata)?;
+    signature_pubkeys.encode(&mut metadata)?;
+
+    Ok(metadata)
+}
+
+/// `process_instruction` function for `Oracle::RegisterV1`
+pub(crate) fn register_process_instruction_v1(
+    cid: ContractId,
+    call_idx: usize,
+    calls: Vec<DarkLeaf<ContractCall>>,
+) -> Result<Vec<u8>, ContractError> {
+    let self_ = &calls[call_idx];
+    let params: OracleRegisterParamsV1 = deserialize(&self_.data.data[1..])?;
+    let feed = params.feed;
+
+    if !is_valid_feed_name(&feed.name) {
+        msg!("[RegisterV1] Error: Invalid feed name");
+        return Err(OracleError::InvalidFeedName.into())
+    }
+
+    if !is_valid_oracle_set(&feed.oracles, feed.threshold) {
+        msg!("[RegisterV1] Error: Invalid oracle set");
+        return Err(OracleError::InvalidOracleSet.into())
+    }
+
+    let feed_id = FeedId::derive(&feed.authority, &feed.name);
+    let feeds_db = wasm::db::db_lookup(cid, ORACLE_CONTRACT_FEEDS_TREE)?;
+    if StorageMap::<FeedId, OracleFeed>::new(feeds_db).contains(&feed_id)? {
+        msg!("[RegisterV1] Error: Feed {} is already registered", feed_id);
+        return Err(OracleError::FeedAlreadyRegistered.into())
+    }
+
+    let update = OracleRegisterUpdateV1 { feed_id, feed };
+    let mut update_data = vec![];
+    update_data.write_u8(OracleFunction::RegisterV1 as u8)?;
+    update.encode(&mut update_data)?;
+
+    Ok(update_data)
+}
+
+/// `process_update` function for `Oracle::RegisterV1`
+pub(crate) fn register_process_update_v1(
+    cid: ContractId,
+    update: OracleRegisterUpdateV1,
+) -> ContractResult {
+    msg!("[RegisterV1] Registering feed {}", update.feed_id);
+    let feeds_db = wasm::db::db_lookup(cid, ORACLE_CONTRACT_FEEDS_TREE)?;
+    StorageMap::<FeedId, OracleFeed>::new(feeds_db).insert(&update.feed_id, &update.feed)?;
+
+    Ok(())
+}
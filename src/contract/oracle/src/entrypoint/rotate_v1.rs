@@ -0,0 +1,109 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2025 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use darkfi_sdk::{
+    crypto::{ContractId, PublicKey},
+    dark_tree::DarkLeaf,
+    error::{ContractError, ContractResult},
+    msg,
+    pasta::pallas,
+    wasm::{self, storage::StorageMap},
+    ContractCall,
+};
+use darkfi_serial::{deserialize, Encodable, WriteExt};
+
+use crate::{
+    error::OracleError,
+    model::{is_valid_oracle_set, FeedId, OracleFeed, OracleRotateParamsV1, OracleRotateUpdateV1},
+    OracleFunction, ORACLE_CONTRACT_FEEDS_TREE,
+};
+
+/// `get_metadata` function for `Oracle::RotateV1`
+pub(crate) fn rotate_get_metadata_v1(
+    cid: ContractId,
+    call_idx: usize,
+    calls: Vec<DarkLeaf<ContractCall>>,
+) -> Result<Vec<u8>, ContractError> {
+    let self_ = &calls[call_idx];
+    let params: OracleRotateParamsV1 = deserialize(&self_.data.data[1..])?;
+
+    // Only the feed authority can rotate its oracle set
+    let feeds_db = wasm::db::db_lookup(cid, ORACLE_CONTRACT_FEEDS_TREE)?;
+    let Some(feed) = StorageMap::<FeedId, OracleFeed>::new(feeds_db).get(&params.feed_id)? else {
+        msg!("[RotateV1] Error: Feed {} is not registered", params.feed_id);
+        return Err(OracleError::FeedNotRegistered.into())
+    };
+
+    let zk_public_inputs: Vec<(String, Vec<pallas::Base>)> = vec![];
+    let signature_pubkeys: Vec<PublicKey> = vec![feed.authority];
+
+    // Serialize everything gathered and return it
+    let mut metadata = vec![];
+    zk_public_inputs.encode(&mut metadata)?;
+    signature_pubkeys.encode(&mut metadata)?;
+
+    Ok(metadata)
+}
+
+/// `process_instruction` function for `Oracle::RotateV1`
+pub(crate) fn rotate_process_instruction_v1(
+    cid: ContractId,
+    call_idx: usize,
+    calls: Vec<DarkLeaf<ContractCall>>,
+) -> Result<Vec<u8>, ContractError> {
+    let self_ = &calls[call_idx];
+    let params: OracleRotateParamsV1 = deserialize(&self_.data.data[1..])?;
+
+    let feeds_db = wasm::db::db_lookup(cid, ORACLE_CONTRACT_FEEDS_TREE)?;
+    if !StorageMap::<FeedId, OracleFeed>::new(feeds_db).contains(&params.feed_id)? {
+        msg!("[RotateV1] Error: Feed {} is not registered", params.feed_id);
+        return Err(OracleError::FeedNotRegistered.into())
+    }
+
+    if !is_valid_oracle_set(&params.oracles, params.threshold) {
+        msg!("[RotateV1] Error: Invalid oracle set");
+        return Err(OracleError::InvalidOracleSet.into())
+    }
+
+    let update = OracleRotateUpdateV1 {
+        feed_id: params.feed_id,
+        oracles: params.oracles,
+        threshold: params.threshold,
+    };
+    let mut update_data = vec![];
+    update_data.write_u8(OracleFunction::RotateV1 as u8)?;
+    update.encode(&mut update_data)?;
+
+    Ok(update_data)
+}
+
+/// `process_update` function for `Oracle::RotateV1`
+pub(crate) fn rotate_process_update_v1(
+    cid: ContractId,
+    update: OracleRotateUpdateV1,
+) -> ContractResult {
+    msg!("[RotateV1] Rotating oracle set of feed {}", update.feed_id);
+    let feeds_db = wasm::db::db_lookup(cid, ORACLE_CONTRACT_FEEDS_TREE)?;
+    let feeds = StorageMap::<FeedId, OracleFeed>::new(feeds_db);
+    let mut feed = feeds.get(&update.feed_id)?.unwrap();
+    feed.oracles = update.oracles;
+    feed.threshold = update.threshold;
+    feeds.insert(&update.feed_id, &feed)?;
+
+    Ok(())
+}
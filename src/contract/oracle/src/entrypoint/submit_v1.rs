@@ -0,0 +1,146 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2025 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use darkfi_sdk::{
+    crypto::{schnorr::SchnorrPublic, ContractId, PublicKey},
+    dark_tree::DarkLeaf,
+    error::{ContractError, ContractResult},
+    msg,
+    pasta::pallas,
+    wasm::{self, storage::StorageMap},
+    ContractCall,
+};
+use darkfi_serial::{deserialize, Encodable, WriteExt};
+
+use crate::{
+    error::OracleError,
+    model::{
+        aggregate_attestations, attestation_message, AttestedValue, FeedId, OracleFeed,
+        OracleSubmitParamsV1, OracleSubmitUpdateV1,
+    },
+    OracleFunction, ORACLE_CONTRACT_FEEDS_TREE, ORACLE_CONTRACT_VALUES_TREE,
+};
+
+/// `get_metadata` function for `Oracle::SubmitV1`
+pub(crate) fn submit_get_metadata_v1(
+    _cid: ContractId,
+    _call_idx: usize,
+    _calls: Vec<DarkLeaf<ContractCall>>,
+) -> Result<Vec<u8>, ContractError> {
+    // Anyone can relay a round to the chain. The oracle signatures are
+    // over the attested data rather than the transaction, so they get
+    // verified in `process_instruction()` instead.
+    let zk_public_inputs: Vec<(String, Vec<pallas::Base>)> = vec![];
+    let signature_pubkeys: Vec<PublicKey> = vec![];
+
+    // Serialize everything gathered and return it
+    let mut metadata = vec![];
+    zk_public_inputs.encode(&mut metadata)?;
+    signature_pubkeys.encode(&mut metadata)?;
+
+    Ok(metadata)
+}
+
+/// `process_instruction` function for `Oracle::SubmitV1`
+pub(crate) fn submit_process_instruction_v1(
+    cid: ContractId,
+    call_idx: usize,
+    calls: Vec<DarkLeaf<ContractCall>>,
+) -> Result<Vec<u8>, ContractError> {
+    let self_ = &calls[call_idx];
+    let params: OracleSubmitParamsV1 = deserialize(&self_.data.data[1..])?;
+
+    let feeds_db = wasm::db::db_lookup(cid, ORACLE_CONTRACT_FEEDS_TREE)?;
+    let Some(feed) = StorageMap::<FeedId, OracleFeed>::new(feeds_db).get(&params.feed_id)? else {
+        msg!("[SubmitV1] Error: Feed {} is not registered", params.feed_id);
+        return Err(OracleError::FeedNotRegistered.into())
+    };
+
+    // Rounds have to strictly increase, so old attestations can't be replayed
+    let values_db = wasm::db::db_lookup(cid, ORACLE_CONTRACT_VALUES_TREE)?;
+    if let Some(latest) =
+        StorageMap::<FeedId, AttestedValue>::new(values_db).get(&params.feed_id)?
+    {
+        if params.round <= latest.round {
+            msg!("[SubmitV1] Error: Round {} is not newer than {}", params.round, latest.round);
+            return Err(OracleError::StaleRound.into())
+        }
+    }
+
+    // Every attestation has to come from a distinct oracle of the
+    // current set, and carry a valid signature over the round data.
+    let mut signers: Vec<PublicKey> = Vec::with_capacity(params.attestations.len());
+    for attestation in &params.attestations {
+        if !feed.oracles.contains(&attestation.oracle) {
+            msg!("[SubmitV1] Error: Oracle {} is not part of the feed", attestation.oracle);
+            return Err(OracleError::UnknownOracle.into())
+        }
+
+        if signers.contains(&attestation.oracle) {
+            msg!("[SubmitV1] Error: Duplicate attestation from {}", attestation.oracle);
+            return Err(OracleError::DuplicateOracle.into())
+        }
+
+        let message = attestation_message(&params.feed_id, params.round, attestation.value);
+        if !attestation.oracle.verify(&message, &attestation.signature) {
+            msg!("[SubmitV1] Error: Invalid signature from {}", attestation.oracle);
+            return Err(OracleError::InvalidAttestation.into())
+        }
+
+        signers.push(attestation.oracle);
+    }
+
+    if signers.len() < feed.threshold as usize {
+        msg!(
+            "[SubmitV1] Error: Got {} attestations, threshold is {}",
+            signers.len(),
+            feed.threshold
+        );
+        return Err(OracleError::ThresholdNotMet.into())
+    }
+
+    let (value, digest) =
+        aggregate_attestations(feed.kind, &params.feed_id, params.round, &params.attestations);
+
+    let value = AttestedValue {
+        round: params.round,
+        value,
+        digest,
+        height: wasm::util::get_verifying_block_height()?,
+        signers: signers.len() as u8,
+    };
+
+    let update = OracleSubmitUpdateV1 { feed_id: params.feed_id, value };
+    let mut update_data = vec![];
+    update_data.write_u8(OracleFunction::SubmitV1 as u8)?;
+    update.encode(&mut update_data)?;
+
+    Ok(update_data)
+}
+
+/// `process_update` function for `Oracle::SubmitV1`
+pub(crate) fn submit_process_update_v1(
+    cid: ContractId,
+    update: OracleSubmitUpdateV1,
+) -> ContractResult {
+    msg!("[SubmitV1] Feed {} round {}: {}", update.feed_id, update.value.round, update.value.value);
+    let values_db = wasm::db::db_lookup(cid, ORACLE_CONTRACT_VALUES_TREE)?;
+    StorageMap::<FeedId, AttestedValue>::new(values_db).insert(&update.feed_id, &update.value)?;
+
+    Ok(())
+}
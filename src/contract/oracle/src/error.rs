@@ -0,0 +1,65 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2025 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use darkfi_sdk::error::ContractError;
+
+#[derive(Debug, Clone, thiserror::Error)]
+pub enum OracleError {
+    #[error("Invalid feed name")]
+    InvalidFeedName,
+
+    #[error("Feed is already registered")]
+    FeedAlreadyRegistered,
+
+    #[error("Feed is not registered")]
+    FeedNotRegistered,
+
+    #[error("Invalid oracle set")]
+    InvalidOracleSet,
+
+    #[error("Round is not newer than the latest attested one")]
+    StaleRound,
+
+    #[error("Attestation from an unknown oracle")]
+    UnknownOracle,
+
+    #[error("Duplicate attestation from the same oracle")]
+    DuplicateOracle,
+
+    #[error("Invalid attestation signature")]
+    InvalidAttestation,
+
+    #[error("Not enough attestations to reach the threshold")]
+    ThresholdNotMet,
+}
+
+impl From<OracleError> for ContractError {
+    fn from(e: OracleError) -> Self {
+        match e {
+            OracleError::InvalidFeedName => Self::Custom(1),
+            OracleError::FeedAlreadyRegistered => Self::Custom(2),
+            OracleError::FeedNotRegistered => Self::Custom(3),
+            OracleError::InvalidOracleSet => Self::Custom(4),
+            OracleError::StaleRound => Self::Custom(5),
+            OracleError::UnknownOracle => Self::Custom(6),
+            OracleError::DuplicateOracle => Self::Custom(7),
+            OracleError::InvalidAttestation => Self::Custom(8),
+            OracleError::ThresholdNotMet => Self::Custom(9),
+        }
+    }
+}
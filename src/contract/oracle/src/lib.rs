@@ -0,0 +1,91 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2025 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Smart contract implementing threshold-attested oracle feeds.
+//!
+//! A feed is registered by an authority along with a set of oracle
+//! public keys and a signing threshold. Oracles sign data points for
+//! numbered rounds off-chain, and anyone can submit a round once enough
+//! attestations have been gathered. The contract verifies every
+//! signature, aggregates the attested values, and stores the result so
+//! other contracts can read it through the [`api`] module.
+
+use darkfi_sdk::error::ContractError;
+
+/// Functions available in the contract
+#[repr(u8)]
+#[derive(PartialEq, Debug)]
+pub enum OracleFunction {
+    RegisterV1 = 0x00,
+    RotateV1 = 0x01,
+    SubmitV1 = 0x02,
+}
+
+impl TryFrom<u8> for OracleFunction {
+    type Error = ContractError;
+
+    fn try_from(b: u8) -> core::result::Result<Self, Self::Error> {
+        match b {
+            0x00 => Ok(Self::RegisterV1),
+            0x01 => Ok(Self::RotateV1),
+            0x02 => Ok(Self::SubmitV1),
+            _ => Err(ContractError::InvalidFunction),
+        }
+    }
+}
+
+/// Internal contract errors
+pub mod error;
+
+/// Call parameters definitions
+pub mod model;
+
+/// API for other contracts to read attested values
+pub mod api;
+
+#[cfg(not(feature = "no-entrypoint"))]
+/// WASM entrypoint functions
+pub mod entrypoint;
+
+#[cfg(feature = "client")]
+/// Client API for interaction with this smart contract
+pub mod client;
+
+// These are the different sled trees that will be created
+pub const ORACLE_CONTRACT_INFO_TREE: &str = "info";
+pub const ORACLE_CONTRACT_FEEDS_TREE: &str = "feeds";
+pub const ORACLE_CONTRACT_VALUES_TREE: &str = "values";
+
+// These are keys inside the info tree
+pub const ORACLE_CONTRACT_DB_VERSION: &[u8] = b"db_version";
+
+/// Maximum length of a feed name, in bytes
+pub const FEED_NAME_MAX_LEN: usize = 32;
+
+/// Maximum amount of oracles a feed can have. Every attestation in a
+/// submission gets its signature verified on-chain, so this also bounds
+/// the cost of a single submission.
+pub const ORACLE_SET_MAX: usize = 16;
+
+/// Check that the given feed name is valid. Feed names consist of
+/// printable ASCII characters, without whitespace.
+pub fn is_valid_feed_name(name: &str) -> bool {
+    !name.is_empty() &&
+        name.len() <= FEED_NAME_MAX_LEN &&
+        name.bytes().all(|b| b.is_ascii_graphic())
+}
@@ -0,0 +1,317 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2025 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use darkfi_sdk::{
+    crypto::{pasta_prelude::PrimeField, schnorr::Signature, util::hash_to_base, PublicKey},
+    error::ContractError,
+    pasta::pallas,
+};
+use darkfi_serial::{serialize, SerialDecodable, SerialEncodable};
+
+#[cfg(feature = "client")]
+use darkfi_serial::async_trait;
+
+use crate::ORACLE_SET_MAX;
+
+/// BLAKE2b personalization used to derive a [`FeedId`]
+pub const FEED_ID_PERSONALIZATION: &[u8] = b"DarkFi_Feed_ID__";
+
+/// Domain separator prepended to every message signed by an oracle
+pub const ATTESTATION_DOMAIN: &[u8] = b"DarkFi:OracleAttestation";
+
+/// FeedId represents the on-chain identifier of a registered feed.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, SerialEncodable, SerialDecodable)]
+pub struct FeedId(pallas::Base);
+
+impl FeedId {
+    /// Derives a `FeedId` from the feed authority and its name, so
+    /// different authorities can use the same feed name.
+    pub fn derive(authority: &PublicKey, name: &str) -> Self {
+        Self(hash_to_base(FEED_ID_PERSONALIZATION, &[&authority.to_bytes(), name.as_bytes()]))
+    }
+
+    /// Get the inner `pallas::Base` element.
+    pub fn inner(&self) -> pallas::Base {
+        self.0
+    }
+
+    /// Create a `FeedId` object from given bytes, erroring if the input
+    /// bytes are noncanonical.
+    pub fn from_bytes(x: [u8; 32]) -> Result<Self, ContractError> {
+        match pallas::Base::from_repr(x).into() {
+            Some(v) => Ok(Self(v)),
+            None => {
+                Err(ContractError::IoError("Failed to instantiate FeedId from bytes".to_string()))
+            }
+        }
+    }
+
+    /// Convert the `FeedId` type into 32 raw bytes
+    pub fn to_bytes(&self) -> [u8; 32] {
+        self.0.to_repr()
+    }
+}
+
+use core::str::FromStr;
+darkfi_sdk::fp_from_bs58!(FeedId);
+darkfi_sdk::fp_to_bs58!(FeedId);
+darkfi_sdk::ty_from_fp!(FeedId);
+
+/// Kind of data a feed carries, defining how attestations get aggregated
+#[derive(Copy, Clone, Debug, Eq, PartialEq, SerialEncodable, SerialDecodable)]
+pub enum FeedKind {
+    /// The attested value is the median of the values signed by the oracles
+    Price,
+    /// The attested value is derived from the digest of all the signed
+    /// contributions. It can't be predicted as long as one contributing
+    /// oracle is honest, but the submitter chooses which attestations
+    /// get included, so it should not be relied upon for high stakes.
+    Randomness,
+}
+
+/// On-chain record of a registered feed
+#[derive(Clone, Debug, Eq, PartialEq, SerialEncodable, SerialDecodable)]
+pub struct OracleFeed {
+    /// Public key allowed to rotate the oracle set
+    pub authority: PublicKey,
+    /// Human-readable feed name
+    pub name: String,
+    /// Kind of data the feed carries
+    pub kind: FeedKind,
+    /// Public keys of the oracles attesting to the feed
+    pub oracles: Vec<PublicKey>,
+    /// Amount of oracle attestations required to accept a round
+    pub threshold: u8,
+}
+
+/// Check that the given oracle set is valid. The set can't be empty,
+/// exceed [`ORACLE_SET_MAX`] or contain duplicate keys, and the threshold
+/// has to be reachable.
+pub fn is_valid_oracle_set(oracles: &[PublicKey], threshold: u8) -> bool {
+    if oracles.is_empty() || oracles.len() > ORACLE_SET_MAX {
+        return false
+    }
+
+    if threshold == 0 || threshold as usize > oracles.len() {
+        return false
+    }
+
+    oracles.iter().enumerate().all(|(i, oracle)| !oracles[i + 1..].contains(oracle))
+}
+
+/// A data point for a feed round, signed by an oracle
+#[derive(Copy, Clone, Debug, Eq, PartialEq, SerialEncodable, SerialDecodable)]
+pub struct OracleAttestation {
+    /// Public key of the signing oracle
+    pub oracle: PublicKey,
+    /// The attested value
+    pub value: u64,
+    /// Signature over [`attestation_message()`]
+    pub signature: Signature,
+}
+
+/// Build the message an oracle signs to attest to `value` for the given
+/// feed round.
+pub fn attestation_message(feed_id: &FeedId, round: u64, value: u64) -> Vec<u8> {
+    let mut message = ATTESTATION_DOMAIN.to_vec();
+    message.extend_from_slice(&feed_id.to_bytes());
+    message.extend_from_slice(&round.to_le_bytes());
+    message.extend_from_slice(&value.to_le_bytes());
+    message
+}
+
+/// Aggregate a non-empty set of attestations for a feed round into its
+/// value and digest. Attestations are sorted by oracle key first, so the
+/// result does not depend on their order in the submission.
+pub fn aggregate_attestations(
+    kind: FeedKind,
+    feed_id: &FeedId,
+    round: u64,
+    attestations: &[OracleAttestation],
+) -> (u64, [u8; 32]) {
+    let mut sorted: Vec<&OracleAttestation> = attestations.iter().collect();
+    sorted.sort_by_key(|a| a.oracle.to_bytes());
+
+    let mut hasher = blake3::Hasher::new();
+    hasher.update(ATTESTATION_DOMAIN);
+    hasher.update(&feed_id.to_bytes());
+    hasher.update(&round.to_le_bytes());
+    for attestation in &sorted {
+        hasher.update(&attestation.oracle.to_bytes());
+        hasher.update(&attestation.value.to_le_bytes());
+        hasher.update(&serialize(&attestation.signature));
+    }
+    let digest = *hasher.finalize().as_bytes();
+
+    let value = match kind {
+        FeedKind::Price => {
+            let mut values: Vec<u64> = sorted.iter().map(|a| a.value).collect();
+            values.sort_unstable();
+            values[(values.len() - 1) / 2]
+        }
+        FeedKind::Randomness => u64::from_le_bytes(digest[..8].try_into().unwrap()),
+    };
+
+    (value, digest)
+}
+
+/// On-chain record of the latest accepted round of a feed
+#[derive(Copy, Clone, Debug, Eq, PartialEq, SerialEncodable, SerialDecodable)]
+pub struct AttestedValue {
+    /// The accepted round
+    pub round: u64,
+    /// The aggregated value
+    pub value: u64,
+    /// Digest committing to all the attestations of the round
+    pub digest: [u8; 32],
+    /// Block height the round was accepted at
+    pub height: u32,
+    /// Amount of oracles that attested to the round
+    pub signers: u8,
+}
+
+/// Parameters for `Oracle::Register`
+#[derive(Clone, Debug, SerialEncodable, SerialDecodable)]
+pub struct OracleRegisterParamsV1 {
+    /// The new feed
+    pub feed: OracleFeed,
+}
+
+/// State update for `Oracle::Register`
+#[derive(Clone, Debug, SerialEncodable, SerialDecodable)]
+pub struct OracleRegisterUpdateV1 {
+    /// The registered `FeedId`
+    pub feed_id: FeedId,
+    /// The new feed record
+    pub feed: OracleFeed,
+}
+
+/// Parameters for `Oracle::Rotate`
+#[derive(Clone, Debug, SerialEncodable, SerialDecodable)]
+pub struct OracleRotateParamsV1 {
+    /// The `FeedId` to rotate the oracle set of
+    pub feed_id: FeedId,
+    /// The new oracle set
+    pub oracles: Vec<PublicKey>,
+    /// The new threshold
+    pub threshold: u8,
+}
+
+/// State update for `Oracle::Rotate`
+#[derive(Clone, Debug, SerialEncodable, SerialDecodable)]
+pub struct OracleRotateUpdateV1 {
+    /// The rotated `FeedId`
+    pub feed_id: FeedId,
+    /// The new oracle set
+    pub oracles: Vec<PublicKey>,
+    /// The new threshold
+    pub threshold: u8,
+}
+
+/// Parameters for `Oracle::Submit`
+#[derive(Clone, Debug, SerialEncodable, SerialDecodable)]
+pub struct OracleSubmitParamsV1 {
+    /// The `FeedId` the round belongs to
+    pub feed_id: FeedId,
+    /// The submitted round
+    pub round: u64,
+    /// Oracle attestations for the round
+    pub attestations: Vec<OracleAttestation>,
+}
+
+/// State update for `Oracle::Submit`
+#[derive(Clone, Debug, SerialEncodable, SerialDecodable)]
+pub struct OracleSubmitUpdateV1 {
+    /// The `FeedId` the round belongs to
+    pub feed_id: FeedId,
+    /// The new latest value of the feed
+    pub value: AttestedValue,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use darkfi_sdk::crypto::Keypair;
+    use rand::rngs::OsRng;
+
+    fn attestations(values: &[u64]) -> Vec<OracleAttestation> {
+        values
+            .iter()
+            .map(|value| OracleAttestation {
+                oracle: Keypair::random(&mut OsRng).public,
+                value: *value,
+                signature: Signature::dummy(),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn aggregate_price() {
+        let feed_id = FeedId::derive(&Keypair::random(&mut OsRng).public, "DRK/USD");
+
+        // The median is picked, the lower one for an even amount of values
+        let mut odd = attestations(&[5, 1, 9]);
+        assert_eq!(aggregate_attestations(FeedKind::Price, &feed_id, 1, &odd).0, 5);
+        let even = attestations(&[4, 1, 3, 2]);
+        assert_eq!(aggregate_attestations(FeedKind::Price, &feed_id, 1, &even).0, 2);
+        let single = attestations(&[7]);
+        assert_eq!(aggregate_attestations(FeedKind::Price, &feed_id, 1, &single).0, 7);
+
+        // The submission order doesn't matter
+        let aggregated = aggregate_attestations(FeedKind::Price, &feed_id, 1, &odd);
+        odd.reverse();
+        assert_eq!(aggregate_attestations(FeedKind::Price, &feed_id, 1, &odd), aggregated);
+
+        // While the digest commits to the round
+        let other_round = aggregate_attestations(FeedKind::Price, &feed_id, 2, &odd);
+        assert_eq!(other_round.0, aggregated.0);
+        assert_ne!(other_round.1, aggregated.1);
+    }
+
+    #[test]
+    fn aggregate_randomness() {
+        let feed_id = FeedId::derive(&Keypair::random(&mut OsRng).public, "beacon");
+        let mut contributions = attestations(&[1, 2, 3]);
+
+        let (value, digest) =
+            aggregate_attestations(FeedKind::Randomness, &feed_id, 1, &contributions);
+        assert_eq!(value, u64::from_le_bytes(digest[..8].try_into().unwrap()));
+
+        // The submission order doesn't matter
+        contributions.rotate_left(1);
+        assert_eq!(
+            aggregate_attestations(FeedKind::Randomness, &feed_id, 1, &contributions),
+            (value, digest)
+        );
+
+        // But every contribution, the round and the feed affect the value
+        let partial =
+            aggregate_attestations(FeedKind::Randomness, &feed_id, 1, &contributions[1..]);
+        assert_ne!(partial.0, value);
+        let other_round = aggregate_attestations(FeedKind::Randomness, &feed_id, 2, &contributions);
+        assert_ne!(other_round.0, value);
+        let other_feed = FeedId::derive(&Keypair::random(&mut OsRng).public, "beacon");
+        let other_feed =
+            aggregate_attestations(FeedKind::Randomness, &other_feed, 1, &contributions);
+        assert_ne!(other_feed.0, value);
+
+        // The same digest is used by both feed kinds
+        let price = aggregate_attestations(FeedKind::Price, &feed_id, 1, &contributions);
+        assert_eq!(price.1, digest);
+    }
+}
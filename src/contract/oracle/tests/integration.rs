@@ -0,0 +1,120 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2025 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Integration test for oracle feeds.
+//!
+//! Alice registers a price feed attested by three oracles with a
+//! threshold of two, and Bob relays the signed rounds to the chain.
+//! Alice then rotates the oracle set, after which attestations from
+//! the removed oracle are no longer accepted.
+
+use darkfi::Result;
+use darkfi_contract_test_harness::{init_logger, Holder, TestHarness};
+use darkfi_oracle_contract::{
+    client::sign_attestation,
+    model::{FeedKind, OracleAttestation},
+};
+use darkfi_sdk::crypto::{Keypair, PublicKey};
+use log::info;
+use rand::rngs::OsRng;
+
+#[test]
+fn oracle_integration() -> Result<()> {
+    smol::block_on(async {
+        init_logger();
+
+        // Holders this test will use
+        const HOLDERS: [Holder; 2] = [Holder::Alice, Holder::Bob];
+
+        // Initialize harness
+        let mut th = TestHarness::new(&HOLDERS, false).await?;
+
+        // Generate the oracle keys
+        let oracles: Vec<Keypair> = (0..3).map(|_| Keypair::random(&mut OsRng)).collect();
+        let oracle_keys: Vec<PublicKey> = oracles.iter().map(|o| o.public).collect();
+
+        info!(target: "oracle", "[Alice] Registering the DRK/USD feed");
+        let (tx, feed_id, fee_params) = th
+            .oracle_register(&Holder::Alice, "DRK/USD", FeedKind::Price, oracle_keys.clone(), 2, 1)
+            .await?;
+        for holder in &HOLDERS {
+            th.execute_oracle_tx(holder, tx.clone(), &fee_params, 1, true).await?;
+        }
+        assert_eq!(th.oracle_feed(&Holder::Bob, &feed_id).unwrap().oracles, oracle_keys);
+
+        info!(target: "oracle", "[Alice] Trying to register the same feed again");
+        let (tx, _, fee_params) = th
+            .oracle_register(&Holder::Alice, "DRK/USD", FeedKind::Price, oracle_keys.clone(), 1, 2)
+            .await?;
+        assert!(th.execute_oracle_tx(&Holder::Alice, tx, &fee_params, 2, true).await.is_err());
+
+        info!(target: "oracle", "[Bob] Submitting round 1");
+        let attestations: Vec<OracleAttestation> = oracles
+            .iter()
+            .zip([100, 250, 102])
+            .map(|(o, value)| sign_attestation(&o.secret, &feed_id, 1, value))
+            .collect();
+        let (tx, value, fee_params) =
+            th.oracle_submit(&Holder::Bob, feed_id, 1, attestations.clone(), 2).await?;
+        assert_eq!(value, 102);
+        for holder in &HOLDERS {
+            th.execute_oracle_tx(holder, tx.clone(), &fee_params, 2, true).await?;
+        }
+        let attested = th.oracle_value(&Holder::Alice, &feed_id).unwrap();
+        assert_eq!((attested.round, attested.value, attested.signers), (1, 102, 3));
+
+        info!(target: "oracle", "[Bob] Trying to replay round 1");
+        let (tx, _, fee_params) =
+            th.oracle_submit(&Holder::Bob, feed_id, 1, attestations, 3).await?;
+        assert!(th.execute_oracle_tx(&Holder::Bob, tx, &fee_params, 3, true).await.is_err());
+
+        info!(target: "oracle", "[Bob] Trying to submit round 2 below the threshold");
+        let attestations = vec![sign_attestation(&oracles[0].secret, &feed_id, 2, 110)];
+        assert!(th.oracle_submit(&Holder::Bob, feed_id, 2, attestations, 3).await.is_err());
+
+        info!(target: "oracle", "[Alice] Removing the third oracle from the feed");
+        let (tx, fee_params) =
+            th.oracle_rotate(&Holder::Alice, feed_id, oracle_keys[..2].to_vec(), 2, 3).await?;
+        for holder in &HOLDERS {
+            th.execute_oracle_tx(holder, tx.clone(), &fee_params, 3, true).await?;
+        }
+
+        info!(target: "oracle", "[Bob] Trying to submit round 2 with the removed oracle");
+        let attestations = vec![
+            sign_attestation(&oracles[0].secret, &feed_id, 2, 110),
+            sign_attestation(&oracles[2].secret, &feed_id, 2, 120),
+        ];
+        assert!(th.oracle_submit(&Holder::Bob, feed_id, 2, attestations, 4).await.is_err());
+
+        info!(target: "oracle", "[Bob] Submitting round 2");
+        let attestations = vec![
+            sign_attestation(&oracles[0].secret, &feed_id, 2, 110),
+            sign_attestation(&oracles[1].secret, &feed_id, 2, 120),
+        ];
+        let (tx, value, fee_params) =
+            th.oracle_submit(&Holder::Bob, feed_id, 2, attestations, 4).await?;
+        for holder in &HOLDERS {
+            th.execute_oracle_tx(holder, tx.clone(), &fee_params, 4, true).await?;
+        }
+        let attested = th.oracle_value(&Holder::Alice, &feed_id).unwrap();
+        assert_eq!((attested.round, attested.value, attested.height), (2, value, 4));
+
+        // Thanks for reading
+        Ok(())
+    })
+}
@@ -0,0 +1,548 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2025 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use darkfi::{
+    tx::{ContractCallLeaf, Transaction, TransactionBuilder},
+    zk::halo2::Field,
+    Result,
+};
+use darkfi_auction_contract::{
+    client::{
+        bid_v1::AuctionBidCallBuilder, claim_v1::AuctionClaimCallBuilder,
+        create_v1::AuctionCreateCallBuilder, reveal_v1::AuctionRevealCallBuilder,
+    },
+    model::{
+        auction_spend_hook, AuctionBidParamsV1, AuctionClaimParamsV1, AuctionCreateParamsV1,
+        AuctionId, AuctionParams, AuctionRevealParamsV1,
+    },
+    AuctionFunction, AUCTION_CONTRACT_ZKAS_BID_NS, AUCTION_CONTRACT_ZKAS_CLAIM_NS,
+    AUCTION_CONTRACT_ZKAS_COIN_NS,
+};
+use darkfi_money_contract::{
+    client::{
+        swap_v1::SwapCallBuilder,
+        transfer_v1::{make_transfer_call, TransferCallSecrets},
+        MoneyNote, OwnCoin,
+    },
+    model::{MoneyFeeParamsV1, MoneyTransferParamsV1, TokenId},
+    MoneyFunction, MONEY_CONTRACT_ZKAS_BURN_NS_V1, MONEY_CONTRACT_ZKAS_MINT_NS_V1,
+};
+use darkfi_sdk::{
+    crypto::{
+        contract_id::{AUCTION_CONTRACT_ID, MONEY_CONTRACT_ID},
+        BaseBlind, Blind, FuncId, MerkleNode, SecretKey,
+    },
+    dark_tree::DarkTree,
+    pasta::pallas,
+    ContractCall,
+};
+use darkfi_serial::AsyncEncodable;
+use log::debug;
+use rand::rngs::OsRng;
+
+use super::{Holder, TestHarness};
+
+impl TestHarness {
+    /// Create an `Auction::Create` transaction, locking the auctioned asset
+    /// from the given coins with a sibling `Money::Transfer`.
+    ///
+    /// Returns the [`Transaction`], the locking transfer parameters, and
+    /// the auction creation parameters.
+    pub async fn auction_create(
+        &mut self,
+        holder: &Holder,
+        params: AuctionParams,
+        owncoins: &[OwnCoin],
+        block_height: u32,
+    ) -> Result<(Transaction, MoneyTransferParamsV1, AuctionCreateParamsV1, Option<MoneyFeeParamsV1>)>
+    {
+        let auction_id = AuctionId::derive(&params);
+        let (xfer_params, xfer_secrets, locked_coin) = self.auction_lock(
+            holder,
+            auction_id,
+            params.asset_amount,
+            params.asset_token_id,
+            owncoins,
+        )?;
+
+        let (coin_pk, coin_zkbin) = self.proving_keys.get(AUCTION_CONTRACT_ZKAS_COIN_NS).unwrap();
+        let builder =
+            AuctionCreateCallBuilder { params, asset_coin: locked_coin, coin_zkbin, coin_pk };
+        let debris = builder.build()?;
+
+        let mut data = vec![AuctionFunction::CreateV1 as u8];
+        debris.params.encode_async(&mut data).await?;
+        let create_call = ContractCall { contract_id: *AUCTION_CONTRACT_ID, data };
+
+        let mut data = vec![MoneyFunction::TransferV1 as u8];
+        xfer_params.encode_async(&mut data).await?;
+        let xfer_call = ContractCall { contract_id: *MONEY_CONTRACT_ID, data };
+
+        let mut tx_builder = TransactionBuilder::new(
+            ContractCallLeaf { call: xfer_call, proofs: xfer_secrets.proofs },
+            vec![],
+        )?;
+        tx_builder.append(ContractCallLeaf { call: create_call, proofs: debris.proofs }, vec![])?;
+
+        let (tx, fee_params) = self
+            .auction_sign_tx(
+                holder,
+                tx_builder,
+                &[xfer_secrets.signature_secrets, vec![]],
+                owncoins,
+                block_height,
+            )
+            .await?;
+
+        Ok((tx, xfer_params, debris.params, fee_params))
+    }
+
+    /// Create an `Auction::Bid` transaction, locking the bid value from
+    /// the given coins with a sibling `Money::Transfer`.
+    ///
+    /// Returns the [`Transaction`], the locking transfer parameters, and
+    /// the bid parameters.
+    #[allow(clippy::too_many_arguments)]
+    pub async fn auction_bid(
+        &mut self,
+        holder: &Holder,
+        auction_id: AuctionId,
+        value: u64,
+        payment_token_id: TokenId,
+        reserve_price: u64,
+        owncoins: &[OwnCoin],
+        block_height: u32,
+    ) -> Result<(Transaction, MoneyTransferParamsV1, AuctionBidParamsV1, Option<MoneyFeeParamsV1>)>
+    {
+        let (xfer_params, xfer_secrets, locked_coin) =
+            self.auction_lock(holder, auction_id, value, payment_token_id, owncoins)?;
+
+        let (bid_pk, bid_zkbin) = self.proving_keys.get(AUCTION_CONTRACT_ZKAS_BID_NS).unwrap();
+        let builder = AuctionBidCallBuilder {
+            auction_id,
+            coin: locked_coin,
+            reserve_price,
+            bid_zkbin,
+            bid_pk,
+        };
+        let debris = builder.build()?;
+
+        let mut data = vec![AuctionFunction::BidV1 as u8];
+        debris.params.encode_async(&mut data).await?;
+        let bid_call = ContractCall { contract_id: *AUCTION_CONTRACT_ID, data };
+
+        let mut data = vec![MoneyFunction::TransferV1 as u8];
+        xfer_params.encode_async(&mut data).await?;
+        let xfer_call = ContractCall { contract_id: *MONEY_CONTRACT_ID, data };
+
+        let mut tx_builder = TransactionBuilder::new(
+            ContractCallLeaf { call: xfer_call, proofs: xfer_secrets.proofs },
+            vec![],
+        )?;
+        tx_builder.append(ContractCallLeaf { call: bid_call, proofs: debris.proofs }, vec![])?;
+
+        let (tx, fee_params) = self
+            .auction_sign_tx(
+                holder,
+                tx_builder,
+                &[xfer_secrets.signature_secrets, vec![]],
+                owncoins,
+                block_height,
+            )
+            .await?;
+
+        Ok((tx, xfer_params, debris.params, fee_params))
+    }
+
+    /// Create an `Auction::Reveal` transaction for a locked bid coin.
+    pub async fn auction_reveal(
+        &mut self,
+        holder: &Holder,
+        auction_id: AuctionId,
+        bid_coin: &OwnCoin,
+        block_height: u32,
+    ) -> Result<(Transaction, AuctionRevealParamsV1, Option<MoneyFeeParamsV1>)> {
+        let (coin_pk, coin_zkbin) = self.proving_keys.get(AUCTION_CONTRACT_ZKAS_COIN_NS).unwrap();
+        let builder =
+            AuctionRevealCallBuilder { auction_id, coin: bid_coin.clone(), coin_zkbin, coin_pk };
+        let debris = builder.build()?;
+
+        let mut data = vec![AuctionFunction::RevealV1 as u8];
+        debris.params.encode_async(&mut data).await?;
+        let call = ContractCall { contract_id: *AUCTION_CONTRACT_ID, data };
+        let tx_builder =
+            TransactionBuilder::new(ContractCallLeaf { call, proofs: debris.proofs }, vec![])?;
+
+        let (tx, fee_params) =
+            self.auction_sign_tx(holder, tx_builder, &[vec![]], &[], block_height).await?;
+
+        Ok((tx, debris.params, fee_params))
+    }
+
+    /// Create an `Auction::Claim` transaction refunding a locked coin back
+    /// to its owner with a child `Money::Transfer`.
+    pub async fn auction_refund(
+        &mut self,
+        holder: &Holder,
+        auction_id: AuctionId,
+        locked_coin: &OwnCoin,
+        block_height: u32,
+    ) -> Result<(Transaction, MoneyTransferParamsV1, AuctionClaimParamsV1, Option<MoneyFeeParamsV1>)>
+    {
+        let wallet = self.holders.get(holder).unwrap();
+
+        let (mint_pk, mint_zkbin) = self.proving_keys.get(MONEY_CONTRACT_ZKAS_MINT_NS_V1).unwrap();
+        let (burn_pk, burn_zkbin) = self.proving_keys.get(MONEY_CONTRACT_ZKAS_BURN_NS_V1).unwrap();
+
+        let (xfer_params, xfer_secrets, _) = make_transfer_call(
+            wallet.keypair,
+            wallet.keypair.public,
+            None,
+            locked_coin.note.value,
+            locked_coin.note.token_id,
+            vec![locked_coin.clone()],
+            wallet.money_merkle_tree.clone(),
+            None,
+            None,
+            mint_zkbin.clone(),
+            mint_pk.clone(),
+            burn_zkbin.clone(),
+            burn_pk.clone(),
+            false,
+        )?;
+
+        let (claim_pk, claim_zkbin) =
+            self.proving_keys.get(AUCTION_CONTRACT_ZKAS_CLAIM_NS).unwrap();
+        let builder = AuctionClaimCallBuilder {
+            auction_id,
+            coins: vec![locked_coin.clone()],
+            claim_zkbin,
+            claim_pk,
+        };
+        let debris = builder.build()?;
+
+        let mut data = vec![AuctionFunction::ClaimV1 as u8];
+        debris.params.encode_async(&mut data).await?;
+        let claim_call = ContractCall { contract_id: *AUCTION_CONTRACT_ID, data };
+
+        let mut data = vec![MoneyFunction::TransferV1 as u8];
+        xfer_params.encode_async(&mut data).await?;
+        let xfer_call = ContractCall { contract_id: *MONEY_CONTRACT_ID, data };
+
+        // The claim is the parent of the refunding transfer
+        let tx_builder = TransactionBuilder::new(
+            ContractCallLeaf { call: claim_call, proofs: debris.proofs },
+            vec![DarkTree::new(
+                ContractCallLeaf { call: xfer_call, proofs: xfer_secrets.proofs },
+                vec![],
+                None,
+                None,
+            )],
+        )?;
+
+        let (tx, fee_params) = self
+            .auction_sign_tx(
+                holder,
+                tx_builder,
+                &[xfer_secrets.signature_secrets, vec![]],
+                &[locked_coin.clone()],
+                block_height,
+            )
+            .await?;
+
+        Ok((tx, xfer_params, debris.params, fee_params))
+    }
+
+    /// Create an `Auction::Claim` transaction settling an auction, by
+    /// atomically swapping the seller's asset coin with the winner's bid
+    /// coin with a child `Money::OtcSwap`.
+    pub async fn auction_settle(
+        &mut self,
+        seller: &Holder,
+        asset_coin: &OwnCoin,
+        winner: &Holder,
+        bid_coin: &OwnCoin,
+        auction_id: AuctionId,
+        block_height: u32,
+    ) -> Result<(Transaction, MoneyTransferParamsV1, AuctionClaimParamsV1, Option<MoneyFeeParamsV1>)>
+    {
+        let seller_wallet = self.holders.get(seller).unwrap();
+        let winner_wallet = self.holders.get(winner).unwrap();
+
+        let (mint_pk, mint_zkbin) = self.proving_keys.get(MONEY_CONTRACT_ZKAS_MINT_NS_V1).unwrap();
+        let (burn_pk, burn_zkbin) = self.proving_keys.get(MONEY_CONTRACT_ZKAS_BURN_NS_V1).unwrap();
+
+        // Received coins are not locked anymore
+        let rcpt_user_data_blind = Blind::random(&mut OsRng);
+        let value_send_blind = Blind::random(&mut OsRng);
+        let value_recv_blind = Blind::random(&mut OsRng);
+        let token_send_blind = BaseBlind::random(&mut OsRng);
+        let token_recv_blind = BaseBlind::random(&mut OsRng);
+
+        // The seller sends the asset and receives the winning bid
+        let builder = SwapCallBuilder {
+            pubkey: seller_wallet.keypair.public,
+            value_send: asset_coin.note.value,
+            token_id_send: asset_coin.note.token_id,
+            value_recv: bid_coin.note.value,
+            token_id_recv: bid_coin.note.token_id,
+            user_data_blind_send: rcpt_user_data_blind,
+            spend_hook_recv: FuncId::none(),
+            user_data_recv: pallas::Base::ZERO,
+            value_blinds: [value_send_blind, value_recv_blind],
+            token_blinds: [token_send_blind, token_recv_blind],
+            coin: asset_coin.clone(),
+            tree: seller_wallet.money_merkle_tree.clone(),
+            mint_zkbin: mint_zkbin.clone(),
+            mint_pk: mint_pk.clone(),
+            burn_zkbin: burn_zkbin.clone(),
+            burn_pk: burn_pk.clone(),
+        };
+        let debris0 = builder.build()?;
+
+        // The winner sends the bid and receives the asset
+        let builder = SwapCallBuilder {
+            pubkey: winner_wallet.keypair.public,
+            value_send: bid_coin.note.value,
+            token_id_send: bid_coin.note.token_id,
+            value_recv: asset_coin.note.value,
+            token_id_recv: asset_coin.note.token_id,
+            user_data_blind_send: rcpt_user_data_blind,
+            spend_hook_recv: FuncId::none(),
+            user_data_recv: pallas::Base::ZERO,
+            value_blinds: [value_recv_blind, value_send_blind],
+            token_blinds: [token_recv_blind, token_send_blind],
+            coin: bid_coin.clone(),
+            tree: winner_wallet.money_merkle_tree.clone(),
+            mint_zkbin: mint_zkbin.clone(),
+            mint_pk: mint_pk.clone(),
+            burn_zkbin: burn_zkbin.clone(),
+            burn_pk: burn_pk.clone(),
+        };
+        let debris1 = builder.build()?;
+
+        let swap_params = MoneyTransferParamsV1 {
+            inputs: vec![debris0.params.inputs[0].clone(), debris1.params.inputs[0].clone()],
+            outputs: vec![debris0.params.outputs[0].clone(), debris1.params.outputs[0].clone()],
+        };
+        let swap_proofs = vec![
+            debris0.proofs[0].clone(),
+            debris1.proofs[0].clone(),
+            debris0.proofs[1].clone(),
+            debris1.proofs[1].clone(),
+        ];
+
+        // Claim proofs follow the swap inputs order
+        let (claim_pk, claim_zkbin) =
+            self.proving_keys.get(AUCTION_CONTRACT_ZKAS_CLAIM_NS).unwrap();
+        let builder = AuctionClaimCallBuilder {
+            auction_id,
+            coins: vec![asset_coin.clone(), bid_coin.clone()],
+            claim_zkbin,
+            claim_pk,
+        };
+        let debris = builder.build()?;
+
+        let mut data = vec![AuctionFunction::ClaimV1 as u8];
+        debris.params.encode_async(&mut data).await?;
+        let claim_call = ContractCall { contract_id: *AUCTION_CONTRACT_ID, data };
+
+        let mut data = vec![MoneyFunction::OtcSwapV1 as u8];
+        swap_params.encode_async(&mut data).await?;
+        let swap_call = ContractCall { contract_id: *MONEY_CONTRACT_ID, data };
+
+        let tx_builder = TransactionBuilder::new(
+            ContractCallLeaf { call: claim_call, proofs: debris.proofs },
+            vec![DarkTree::new(
+                ContractCallLeaf { call: swap_call, proofs: swap_proofs },
+                vec![],
+                None,
+                None,
+            )],
+        )?;
+
+        let (tx, fee_params) = self
+            .auction_sign_tx(
+                seller,
+                tx_builder,
+                &[vec![debris0.signature_secret, debris1.signature_secret], vec![]],
+                &[asset_coin.clone()],
+                block_height,
+            )
+            .await?;
+
+        Ok((tx, swap_params, debris.params, fee_params))
+    }
+
+    /// Execute a transaction created by the `auction_*()` functions for a
+    /// given [`Holder`], along with its `Money` call parameters, if any.
+    ///
+    /// Returns any found [`OwnCoin`]s.
+    pub async fn execute_auction_tx(
+        &mut self,
+        holder: &Holder,
+        tx: Transaction,
+        money_params: Option<&MoneyTransferParamsV1>,
+        fee_params: &Option<MoneyFeeParamsV1>,
+        block_height: u32,
+        append: bool,
+    ) -> Result<Vec<OwnCoin>> {
+        let wallet = self.holders.get_mut(holder).unwrap();
+
+        // Execute the transaction
+        wallet.add_transaction("auction", tx, block_height).await?;
+
+        if !append {
+            return Ok(vec![])
+        }
+
+        let (mut inputs, mut outputs) = match money_params {
+            Some(params) => (params.inputs.to_vec(), params.outputs.to_vec()),
+            None => (vec![], vec![]),
+        };
+
+        if let Some(ref fee_params) = fee_params {
+            inputs.push(fee_params.input.clone());
+            outputs.push(fee_params.output.clone());
+        }
+
+        let nullifiers = inputs.iter().map(|i| i.nullifier.inner()).map(|l| (l, l)).collect();
+        wallet.money_null_smt.insert_batch(nullifiers).expect("smt.insert_batch()");
+
+        for input in inputs {
+            if let Some(spent_coin) = wallet
+                .unspent_money_coins
+                .iter()
+                .find(|x| x.nullifier() == input.nullifier)
+                .cloned()
+            {
+                debug!("Found spent OwnCoin({}) for {:?}", spent_coin.coin, holder);
+                wallet.unspent_money_coins.retain(|x| x.nullifier() != input.nullifier);
+                wallet.spent_money_coins.push(spent_coin.clone());
+            }
+        }
+
+        let mut found_owncoins = vec![];
+        for output in outputs {
+            wallet.money_merkle_tree.append(MerkleNode::from(output.coin.inner()));
+
+            let Ok(note) = output.note.decrypt::<MoneyNote>(&wallet.keypair.secret) else {
+                continue
+            };
+
+            let owncoin = OwnCoin {
+                coin: output.coin,
+                note: note.clone(),
+                secret: wallet.keypair.secret,
+                leaf_position: wallet.money_merkle_tree.mark().unwrap(),
+            };
+
+            debug!("Found new OwnCoin({}) for {:?}", owncoin.coin, holder);
+            wallet.unspent_money_coins.push(owncoin.clone());
+            found_owncoins.push(owncoin);
+        }
+
+        Ok(found_owncoins)
+    }
+
+    /// Build a `Money::Transfer` locking the given value into a coin owned
+    /// by the [`Holder`], carrying the auction spend hook and `AuctionId`.
+    fn auction_lock(
+        &self,
+        holder: &Holder,
+        auction_id: AuctionId,
+        value: u64,
+        token_id: TokenId,
+        owncoins: &[OwnCoin],
+    ) -> Result<(MoneyTransferParamsV1, TransferCallSecrets, OwnCoin)> {
+        let wallet = self.holders.get(holder).unwrap();
+
+        let (mint_pk, mint_zkbin) = self.proving_keys.get(MONEY_CONTRACT_ZKAS_MINT_NS_V1).unwrap();
+        let (burn_pk, burn_zkbin) = self.proving_keys.get(MONEY_CONTRACT_ZKAS_BURN_NS_V1).unwrap();
+
+        let (params, secrets, _) = make_transfer_call(
+            wallet.keypair,
+            wallet.keypair.public,
+            None,
+            value,
+            token_id,
+            owncoins.to_owned(),
+            wallet.money_merkle_tree.clone(),
+            Some(auction_spend_hook(*AUCTION_CONTRACT_ID)),
+            Some(auction_id.inner()),
+            mint_zkbin.clone(),
+            mint_pk.clone(),
+            burn_zkbin.clone(),
+            burn_pk.clone(),
+            false,
+        )?;
+
+        // The locked coin is always the first output, followed by the change
+        let mut locked_coin = secrets.minted_coins(&params)[0].clone();
+        locked_coin.secret = wallet.keypair.secret;
+
+        Ok((params, secrets, locked_coin))
+    }
+
+    /// Sign the transaction built by the given [`TransactionBuilder`] with
+    /// the secrets of each call, making a fee offering if fees are enabled.
+    async fn auction_sign_tx(
+        &mut self,
+        holder: &Holder,
+        mut tx_builder: TransactionBuilder,
+        call_secrets: &[Vec<SecretKey>],
+        spent_coins: &[OwnCoin],
+        block_height: u32,
+    ) -> Result<(Transaction, Option<MoneyFeeParamsV1>)> {
+        let sign = |tx: &mut Transaction| -> Result<()> {
+            let mut signatures = Vec::with_capacity(call_secrets.len());
+            for secrets in call_secrets {
+                signatures.push(tx.create_sigs(secrets)?);
+            }
+            tx.signatures = signatures;
+            Ok(())
+        };
+
+        // If fees are enabled, make an offering
+        let mut fee_params = None;
+        let mut fee_signature_secrets = None;
+        if self.verify_fees {
+            let mut tx = tx_builder.build()?;
+            sign(&mut tx)?;
+
+            let (fee_call, fee_proofs, fee_secrets, _spent_fee_coins, fee_call_params) =
+                self.append_fee_call(holder, tx, block_height, spent_coins).await?;
+
+            // Append the fee call to the transaction
+            tx_builder.append(ContractCallLeaf { call: fee_call, proofs: fee_proofs }, vec![])?;
+            fee_signature_secrets = Some(fee_secrets);
+            fee_params = Some(fee_call_params);
+        }
+
+        // Now build the actual transaction and sign it with necessary keys.
+        let mut tx = tx_builder.build()?;
+        sign(&mut tx)?;
+
+        if let Some(fee_signature_secrets) = fee_signature_secrets {
+            let sigs = tx.create_sigs(&fee_signature_secrets)?;
+            tx.signatures.push(sigs);
+        }
+
+        Ok((tx, fee_params))
+    }
+}
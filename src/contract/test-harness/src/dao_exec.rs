@@ -128,6 +128,7 @@ impl TestHarness {
             clear_inputs: vec![],
             inputs,
             outputs,
+            output_note_keys: vec![],
             mint_zkbin: mint_zkbin.clone(),
             mint_pk: mint_pk.clone(),
             burn_zkbin: burn_zkbin.clone(),
@@ -18,10 +18,11 @@
 
 use darkfi::{
     tx::{ContractCallLeaf, Transaction, TransactionBuilder},
+    zk::halo2::Field,
     Result,
 };
 use darkfi_dao_contract::{
-    client::make_mint_call,
+    client::{make_members_tree, make_mint_call, DaoMemberInput},
     model::{Dao, DaoMintParams},
     DaoFunction, DAO_CONTRACT_ZKAS_DAO_MINT_NS,
 };
@@ -30,7 +31,8 @@ use darkfi_money_contract::{
     model::MoneyFeeParamsV1,
 };
 use darkfi_sdk::{
-    crypto::{contract_id::DAO_CONTRACT_ID, MerkleNode, SecretKey},
+    crypto::{contract_id::DAO_CONTRACT_ID, MerkleNode, PublicKey, SecretKey},
+    pasta::pallas,
     ContractCall,
 };
 use darkfi_serial::AsyncEncodable;
@@ -171,4 +173,37 @@ impl TestHarness {
 
         Ok(vec![])
     }
+
+    /// Compute the members set root of the given DAO member [`Holder`]s.
+    pub fn dao_members_root(&self, dao_members: &[Holder]) -> pallas::Base {
+        if dao_members.is_empty() {
+            return pallas::Base::ZERO
+        }
+
+        let members: Vec<PublicKey> =
+            dao_members.iter().map(|x| self.holders.get(x).unwrap().keypair.public).collect();
+        make_members_tree(&members).root(0).unwrap().inner()
+    }
+
+    /// Create the [`DaoMemberInput`] of the given [`Holder`] in the given DAO
+    /// members set. An empty set produces the input for DAOs without members.
+    /// A [`Holder`] outside the set gets the first member's path, which the
+    /// client must reject.
+    pub fn dao_member_input(&self, member: &Holder, dao_members: &[Holder]) -> DaoMemberInput {
+        if dao_members.is_empty() {
+            return DaoMemberInput::none()
+        }
+
+        let members: Vec<PublicKey> =
+            dao_members.iter().map(|x| self.holders.get(x).unwrap().keypair.public).collect();
+        let tree = make_members_tree(&members);
+        let index = dao_members.iter().position(|x| x == member).unwrap_or(0);
+        let leaf_position = (index as u64).into();
+
+        DaoMemberInput {
+            secret: self.holders.get(member).unwrap().keypair.secret,
+            leaf_position,
+            merkle_path: tree.witness(leaf_position, 0).unwrap(),
+        }
+    }
 }
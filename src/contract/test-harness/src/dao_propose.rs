@@ -54,6 +54,7 @@ impl TestHarness {
         proposal_coinattrs: &[CoinAttributes],
         user_data: pallas::Base,
         dao: &Dao,
+        dao_members: &[Holder],
         dao_proposer_secret_key: &SecretKey,
         block_height: u32,
         duration_blockwindows: u64,
@@ -144,6 +145,7 @@ impl TestHarness {
                 .witness(*wallet.dao_leafs.get(&dao_bulla).unwrap(), 0)
                 .unwrap(),
             dao_merkle_root: wallet.dao_merkle_tree.root(0).unwrap(),
+            member: self.dao_member_input(proposer, dao_members),
             signature_secret,
         };
 
@@ -191,11 +193,13 @@ impl TestHarness {
     }
 
     /// Create a generic `Dao::Propose` transaction.
+    #[allow(clippy::too_many_arguments)]
     pub async fn dao_propose_generic(
         &mut self,
         proposer: &Holder,
         user_data: pallas::Base,
         dao: &Dao,
+        dao_members: &[Holder],
         dao_proposer_secret_key: &SecretKey,
         block_height: u32,
         duration_blockwindows: u64,
@@ -264,6 +268,7 @@ impl TestHarness {
                 .witness(*wallet.dao_leafs.get(&dao_bulla).unwrap(), 0)
                 .unwrap(),
             dao_merkle_root: wallet.dao_merkle_tree.root(0).unwrap(),
+            member: self.dao_member_input(proposer, dao_members),
             signature_secret,
         };
 
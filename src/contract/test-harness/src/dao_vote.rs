@@ -47,6 +47,7 @@ impl TestHarness {
         voter: &Holder,
         vote_option: bool,
         dao: &Dao,
+        dao_members: &[Holder],
         proposal: &DaoProposal,
         block_height: u32,
     ) -> Result<(Transaction, DaoVoteParams, Option<MoneyFeeParamsV1>)> {
@@ -86,6 +87,7 @@ impl TestHarness {
             vote_option,
             proposal: proposal.clone(),
             dao: dao.clone(),
+            member: self.dao_member_input(voter, dao_members),
             current_blockwindow,
         };
 
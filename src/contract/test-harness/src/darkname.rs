@@ -0,0 +1,191 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2025 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use darkfi::{
+    tx::{ContractCallLeaf, Transaction, TransactionBuilder},
+    Result,
+};
+use darkfi_darkname_contract::{
+    client::{
+        commit_v1::DarknameCommitCallBuilder, register_v1::DarknameRegisterCallBuilder, NameOwner,
+    },
+    model::{NameId, NameRecord, NameTarget},
+    DarknameFunction, DARKNAME_CONTRACT_NAMES_TREE,
+};
+use darkfi_money_contract::{
+    client::{MoneyNote, OwnCoin},
+    model::MoneyFeeParamsV1,
+};
+use darkfi_sdk::{
+    crypto::{contract_id::DARKNAME_CONTRACT_ID, MerkleNode},
+    ContractCall,
+};
+use darkfi_serial::{deserialize, serialize, AsyncEncodable};
+use log::debug;
+
+use super::{Holder, TestHarness};
+
+impl TestHarness {
+    /// Create a `Darkname::Commit` transaction committing to register
+    /// the given name with the given [`NameOwner`].
+    pub async fn darkname_commit(
+        &mut self,
+        holder: &Holder,
+        name: &str,
+        owner: NameOwner,
+        block_height: u32,
+    ) -> Result<(Transaction, Option<MoneyFeeParamsV1>)> {
+        let builder = DarknameCommitCallBuilder { name: name.to_string(), owner };
+        let debris = builder.build()?;
+
+        let mut data = vec![DarknameFunction::CommitV1 as u8];
+        debris.params.encode_async(&mut data).await?;
+        let call = ContractCall { contract_id: *DARKNAME_CONTRACT_ID, data };
+        let tx_builder =
+            TransactionBuilder::new(ContractCallLeaf { call, proofs: vec![] }, vec![])?;
+
+        self.darkname_sign_tx(holder, tx_builder, block_height).await
+    }
+
+    /// Create a `Darkname::Register` transaction registering the given
+    /// name with the given [`NameOwner`].
+    pub async fn darkname_register(
+        &mut self,
+        holder: &Holder,
+        name: &str,
+        owner: NameOwner,
+        target: NameTarget,
+        duration: u32,
+        block_height: u32,
+    ) -> Result<(Transaction, Option<MoneyFeeParamsV1>)> {
+        let builder =
+            DarknameRegisterCallBuilder { name: name.to_string(), owner, target, duration };
+        let debris = builder.build()?;
+
+        let mut data = vec![DarknameFunction::RegisterV1 as u8];
+        debris.params.encode_async(&mut data).await?;
+        let call = ContractCall { contract_id: *DARKNAME_CONTRACT_ID, data };
+        let tx_builder =
+            TransactionBuilder::new(ContractCallLeaf { call, proofs: vec![] }, vec![])?;
+
+        self.darkname_sign_tx(holder, tx_builder, block_height).await
+    }
+
+    /// Fetch the record of the given name from the [`Holder`]'s state.
+    pub fn darkname_record(&self, holder: &Holder, name: &str) -> Option<NameRecord> {
+        let blockchain = &self.holders.get(holder).unwrap().validator.blockchain;
+        let tree = blockchain
+            .contracts
+            .lookup(&blockchain.sled_db, &DARKNAME_CONTRACT_ID, DARKNAME_CONTRACT_NAMES_TREE)
+            .unwrap();
+        let value = tree.get(serialize(&NameId::derive(name))).unwrap()?;
+        Some(deserialize(&value).unwrap())
+    }
+
+    /// Execute a transaction created by the `darkname_*()` functions for a
+    /// given [`Holder`].
+    ///
+    /// Returns any found [`OwnCoin`]s.
+    pub async fn execute_darkname_tx(
+        &mut self,
+        holder: &Holder,
+        tx: Transaction,
+        fee_params: &Option<MoneyFeeParamsV1>,
+        block_height: u32,
+        append: bool,
+    ) -> Result<Vec<OwnCoin>> {
+        let wallet = self.holders.get_mut(holder).unwrap();
+
+        // Execute the transaction
+        wallet.add_transaction("darkname", tx, block_height).await?;
+
+        if !append {
+            return Ok(vec![])
+        }
+
+        let Some(ref fee_params) = fee_params else { return Ok(vec![]) };
+
+        let nullifier = fee_params.input.nullifier.inner();
+        wallet
+            .money_null_smt
+            .insert_batch(vec![(nullifier, nullifier)])
+            .expect("smt.insert_batch()");
+
+        if let Some(spent_coin) = wallet
+            .unspent_money_coins
+            .iter()
+            .find(|x| x.nullifier() == fee_params.input.nullifier)
+            .cloned()
+        {
+            debug!("Found spent OwnCoin({}) for {:?}", spent_coin.coin, holder);
+            wallet.unspent_money_coins.retain(|x| x.nullifier() != fee_params.input.nullifier);
+            wallet.spent_money_coins.push(spent_coin.clone());
+        }
+
+        wallet.money_merkle_tree.append(MerkleNode::from(fee_params.output.coin.inner()));
+
+        let Ok(note) = fee_params.output.note.decrypt::<MoneyNote>(&wallet.keypair.secret) else {
+            return Ok(vec![])
+        };
+
+        let owncoin = OwnCoin {
+            coin: fee_params.output.coin,
+            note: note.clone(),
+            secret: wallet.keypair.secret,
+            leaf_position: wallet.money_merkle_tree.mark().unwrap(),
+        };
+
+        debug!("Found new OwnCoin({}) for {:?}", owncoin.coin, holder);
+        wallet.unspent_money_coins.push(owncoin.clone());
+        Ok(vec![owncoin])
+    }
+
+    /// Build the transaction, appending a fee call if fees are enabled.
+    async fn darkname_sign_tx(
+        &mut self,
+        holder: &Holder,
+        mut tx_builder: TransactionBuilder,
+        block_height: u32,
+    ) -> Result<(Transaction, Option<MoneyFeeParamsV1>)> {
+        // If fees are enabled, make an offering
+        let mut fee_params = None;
+        let mut fee_signature_secrets = None;
+        if self.verify_fees {
+            let mut tx = tx_builder.build()?;
+            tx.signatures = vec![vec![]];
+
+            let (fee_call, fee_proofs, fee_secrets, _spent_fee_coins, fee_call_params) =
+                self.append_fee_call(holder, tx, block_height, &[]).await?;
+
+            // Append the fee call to the transaction
+            tx_builder.append(ContractCallLeaf { call: fee_call, proofs: fee_proofs }, vec![])?;
+            fee_signature_secrets = Some(fee_secrets);
+            fee_params = Some(fee_call_params);
+        }
+
+        // Now build the actual transaction and sign it with necessary keys.
+        let mut tx = tx_builder.build()?;
+        tx.signatures = vec![vec![]];
+        if let Some(fee_signature_secrets) = fee_signature_secrets {
+            let sigs = tx.create_sigs(&fee_signature_secrets)?;
+            tx.signatures.push(sigs);
+        }
+
+        Ok((tx, fee_params))
+    }
+}
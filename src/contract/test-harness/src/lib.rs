@@ -22,7 +22,7 @@ use std::{
 };
 
 use darkfi::{
-    blockchain::{BlockInfo, BlockchainOverlay},
+    blockchain::{BlockInfo, BlockchainOverlay, CONTRACT_STATE_QUOTA},
     runtime::vm_runtime::Runtime,
     tx::Transaction,
     util::{pcg::Pcg32, time::Timestamp},
@@ -82,6 +82,15 @@ mod dao_vote;
 /// `Dao::Exec` functionality
 mod dao_exec;
 
+/// `Auction` functionality
+mod auction;
+
+/// `Oracle` functionality
+mod oracle;
+
+/// `Darkname` functionality
+mod darkname;
+
 /// Initialize the logging mechanism
 pub fn init_logger() {
     let mut cfg = simplelog::ConfigBuilder::new();
@@ -169,6 +178,10 @@ impl Wallet {
             pow_fixed_difficulty: Some(BigUint::from(1_u8)),
             genesis_block,
             verify_fees,
+            admission_policies: vec![],
+            spend_extractor: None,
+            snapshot_interval: 0,
+            contract_state_quota: CONTRACT_STATE_QUOTA,
         };
         let validator = Validator::new(&sled_db, &validator_config).await?;
 
@@ -58,6 +58,7 @@ impl TestHarness {
         let (params, secrets, mut spent_coins) = make_transfer_call(
             wallet.keypair,
             rcpt,
+            None,
             amount,
             token_id,
             owncoins.to_owned(),
@@ -0,0 +1,250 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2025 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use darkfi::{
+    tx::{ContractCallLeaf, Transaction, TransactionBuilder},
+    Result,
+};
+use darkfi_money_contract::{
+    client::{MoneyNote, OwnCoin},
+    model::MoneyFeeParamsV1,
+};
+use darkfi_oracle_contract::{
+    client::{
+        register_v1::OracleRegisterCallBuilder, rotate_v1::OracleRotateCallBuilder,
+        submit_v1::OracleSubmitCallBuilder,
+    },
+    model::{AttestedValue, FeedId, FeedKind, OracleAttestation, OracleFeed},
+    OracleFunction, ORACLE_CONTRACT_FEEDS_TREE, ORACLE_CONTRACT_VALUES_TREE,
+};
+use darkfi_sdk::{
+    crypto::{contract_id::ORACLE_CONTRACT_ID, MerkleNode, PublicKey, SecretKey},
+    ContractCall,
+};
+use darkfi_serial::{deserialize, serialize, AsyncEncodable};
+use log::debug;
+
+use super::{Holder, TestHarness};
+
+impl TestHarness {
+    /// Create an `Oracle::Register` transaction, using the [`Holder`]'s
+    /// keypair as the feed authority.
+    ///
+    /// Returns the [`Transaction`], the registered [`FeedId`], and the
+    /// fee call parameters.
+    pub async fn oracle_register(
+        &mut self,
+        holder: &Holder,
+        name: &str,
+        kind: FeedKind,
+        oracles: Vec<PublicKey>,
+        threshold: u8,
+        block_height: u32,
+    ) -> Result<(Transaction, FeedId, Option<MoneyFeeParamsV1>)> {
+        let authority = self.holders.get(holder).unwrap().keypair;
+
+        let builder = OracleRegisterCallBuilder {
+            authority: authority.public,
+            name: name.to_string(),
+            kind,
+            oracles,
+            threshold,
+        };
+        let debris = builder.build()?;
+
+        let mut data = vec![OracleFunction::RegisterV1 as u8];
+        debris.params.encode_async(&mut data).await?;
+        let call = ContractCall { contract_id: *ORACLE_CONTRACT_ID, data };
+        let tx_builder =
+            TransactionBuilder::new(ContractCallLeaf { call, proofs: vec![] }, vec![])?;
+
+        let (tx, fee_params) =
+            self.oracle_sign_tx(holder, tx_builder, &[authority.secret], block_height).await?;
+
+        Ok((tx, debris.feed_id, fee_params))
+    }
+
+    /// Create an `Oracle::Rotate` transaction, signed with the [`Holder`]'s
+    /// keypair as the feed authority.
+    pub async fn oracle_rotate(
+        &mut self,
+        holder: &Holder,
+        feed_id: FeedId,
+        oracles: Vec<PublicKey>,
+        threshold: u8,
+        block_height: u32,
+    ) -> Result<(Transaction, Option<MoneyFeeParamsV1>)> {
+        let authority = self.holders.get(holder).unwrap().keypair;
+
+        let builder = OracleRotateCallBuilder { feed_id, oracles, threshold };
+        let debris = builder.build()?;
+
+        let mut data = vec![OracleFunction::RotateV1 as u8];
+        debris.params.encode_async(&mut data).await?;
+        let call = ContractCall { contract_id: *ORACLE_CONTRACT_ID, data };
+        let tx_builder =
+            TransactionBuilder::new(ContractCallLeaf { call, proofs: vec![] }, vec![])?;
+
+        self.oracle_sign_tx(holder, tx_builder, &[authority.secret], block_height).await
+    }
+
+    /// Create an `Oracle::Submit` transaction relaying the given attestations,
+    /// checking them against the feed record as seen by the [`Holder`].
+    ///
+    /// Returns the [`Transaction`], the value the round is expected to be
+    /// accepted with, and the fee call parameters.
+    pub async fn oracle_submit(
+        &mut self,
+        holder: &Holder,
+        feed_id: FeedId,
+        round: u64,
+        attestations: Vec<OracleAttestation>,
+        block_height: u32,
+    ) -> Result<(Transaction, u64, Option<MoneyFeeParamsV1>)> {
+        let feed = self.oracle_feed(holder, &feed_id).expect("Feed not found");
+
+        let builder = OracleSubmitCallBuilder { feed_id, feed: &feed, round, attestations };
+        let debris = builder.build()?;
+
+        let mut data = vec![OracleFunction::SubmitV1 as u8];
+        debris.params.encode_async(&mut data).await?;
+        let call = ContractCall { contract_id: *ORACLE_CONTRACT_ID, data };
+        let tx_builder =
+            TransactionBuilder::new(ContractCallLeaf { call, proofs: vec![] }, vec![])?;
+
+        let (tx, fee_params) = self.oracle_sign_tx(holder, tx_builder, &[], block_height).await?;
+
+        Ok((tx, debris.value, fee_params))
+    }
+
+    /// Fetch the record of the given feed from the [`Holder`]'s state.
+    pub fn oracle_feed(&self, holder: &Holder, feed_id: &FeedId) -> Option<OracleFeed> {
+        let value = self.oracle_state_get(holder, ORACLE_CONTRACT_FEEDS_TREE, feed_id)?;
+        Some(deserialize(&value).unwrap())
+    }
+
+    /// Fetch the latest attested value of the given feed from the [`Holder`]'s state.
+    pub fn oracle_value(&self, holder: &Holder, feed_id: &FeedId) -> Option<AttestedValue> {
+        let value = self.oracle_state_get(holder, ORACLE_CONTRACT_VALUES_TREE, feed_id)?;
+        Some(deserialize(&value).unwrap())
+    }
+
+    /// Execute a transaction created by the `oracle_*()` functions for a
+    /// given [`Holder`].
+    ///
+    /// Returns any found [`OwnCoin`]s.
+    pub async fn execute_oracle_tx(
+        &mut self,
+        holder: &Holder,
+        tx: Transaction,
+        fee_params: &Option<MoneyFeeParamsV1>,
+        block_height: u32,
+        append: bool,
+    ) -> Result<Vec<OwnCoin>> {
+        let wallet = self.holders.get_mut(holder).unwrap();
+
+        // Execute the transaction
+        wallet.add_transaction("oracle", tx, block_height).await?;
+
+        if !append {
+            return Ok(vec![])
+        }
+
+        let Some(ref fee_params) = fee_params else { return Ok(vec![]) };
+
+        let nullifier = fee_params.input.nullifier.inner();
+        wallet
+            .money_null_smt
+            .insert_batch(vec![(nullifier, nullifier)])
+            .expect("smt.insert_batch()");
+
+        if let Some(spent_coin) = wallet
+            .unspent_money_coins
+            .iter()
+            .find(|x| x.nullifier() == fee_params.input.nullifier)
+            .cloned()
+        {
+            debug!("Found spent OwnCoin({}) for {:?}", spent_coin.coin, holder);
+            wallet.unspent_money_coins.retain(|x| x.nullifier() != fee_params.input.nullifier);
+            wallet.spent_money_coins.push(spent_coin.clone());
+        }
+
+        wallet.money_merkle_tree.append(MerkleNode::from(fee_params.output.coin.inner()));
+
+        let Ok(note) = fee_params.output.note.decrypt::<MoneyNote>(&wallet.keypair.secret) else {
+            return Ok(vec![])
+        };
+
+        let owncoin = OwnCoin {
+            coin: fee_params.output.coin,
+            note: note.clone(),
+            secret: wallet.keypair.secret,
+            leaf_position: wallet.money_merkle_tree.mark().unwrap(),
+        };
+
+        debug!("Found new OwnCoin({}) for {:?}", owncoin.coin, holder);
+        wallet.unspent_money_coins.push(owncoin.clone());
+        Ok(vec![owncoin])
+    }
+
+    /// Read a `FeedId` keyed entry of the given Oracle contract state tree.
+    fn oracle_state_get(&self, holder: &Holder, tree: &str, feed_id: &FeedId) -> Option<Vec<u8>> {
+        let blockchain = &self.holders.get(holder).unwrap().validator.blockchain;
+        let tree =
+            blockchain.contracts.lookup(&blockchain.sled_db, &ORACLE_CONTRACT_ID, tree).unwrap();
+        tree.get(serialize(feed_id)).unwrap().map(|v| v.to_vec())
+    }
+
+    /// Build the transaction, signing its Oracle call with the given secrets
+    /// and appending a fee call if fees are enabled.
+    async fn oracle_sign_tx(
+        &mut self,
+        holder: &Holder,
+        mut tx_builder: TransactionBuilder,
+        secrets: &[SecretKey],
+        block_height: u32,
+    ) -> Result<(Transaction, Option<MoneyFeeParamsV1>)> {
+        // If fees are enabled, make an offering
+        let mut fee_params = None;
+        let mut fee_signature_secrets = None;
+        if self.verify_fees {
+            let mut tx = tx_builder.build()?;
+            let sigs = tx.create_sigs(secrets)?;
+            tx.signatures = vec![sigs];
+
+            let (fee_call, fee_proofs, fee_secrets, _spent_fee_coins, fee_call_params) =
+                self.append_fee_call(holder, tx, block_height, &[]).await?;
+
+            // Append the fee call to the transaction
+            tx_builder.append(ContractCallLeaf { call: fee_call, proofs: fee_proofs }, vec![])?;
+            fee_signature_secrets = Some(fee_secrets);
+            fee_params = Some(fee_call_params);
+        }
+
+        // Now build the actual transaction and sign it with necessary keys.
+        let mut tx = tx_builder.build()?;
+        let sigs = tx.create_sigs(secrets)?;
+        tx.signatures = vec![sigs];
+        if let Some(fee_signature_secrets) = fee_signature_secrets {
+            let sigs = tx.create_sigs(&fee_signature_secrets)?;
+            tx.signatures.push(sigs);
+        }
+
+        Ok((tx, fee_params))
+    }
+}
@@ -29,6 +29,9 @@ use darkfi::{
     zkas::ZkBinary,
     Result,
 };
+use darkfi_auction_contract::{
+    AUCTION_CONTRACT_ZKAS_BID_NS, AUCTION_CONTRACT_ZKAS_CLAIM_NS, AUCTION_CONTRACT_ZKAS_COIN_NS,
+};
 use darkfi_dao_contract::{
     DAO_CONTRACT_ZKAS_DAO_AUTH_MONEY_TRANSFER_ENC_COIN_NS,
     DAO_CONTRACT_ZKAS_DAO_AUTH_MONEY_TRANSFER_NS, DAO_CONTRACT_ZKAS_DAO_EARLY_EXEC_NS,
@@ -41,7 +44,7 @@ use darkfi_money_contract::{
     MONEY_CONTRACT_ZKAS_FEE_NS_V1, MONEY_CONTRACT_ZKAS_MINT_NS_V1,
     MONEY_CONTRACT_ZKAS_TOKEN_MINT_NS_V1,
 };
-use darkfi_sdk::crypto::{DAO_CONTRACT_ID, MONEY_CONTRACT_ID};
+use darkfi_sdk::crypto::{AUCTION_CONTRACT_ID, DAO_CONTRACT_ID, MONEY_CONTRACT_ID};
 use darkfi_serial::{deserialize, serialize};
 
 use log::debug;
@@ -137,6 +140,10 @@ pub fn get_cached_pks_and_vks() -> Result<(Pks, Vks)> {
         &include_bytes!("../../dao/proof/early-exec.zk.bin")[..],
         &include_bytes!("../../dao/proof/auth-money-transfer.zk.bin")[..],
         &include_bytes!("../../dao/proof/auth-money-transfer-enc-coin.zk.bin")[..],
+        // Auction
+        &include_bytes!("../../auction/proof/auction-coin.zk.bin")[..],
+        &include_bytes!("../../auction/proof/auction-bid.zk.bin")[..],
+        &include_bytes!("../../auction/proof/auction-claim.zk.bin")[..],
     ];
 
     let mut pks = vec![];
@@ -182,10 +189,12 @@ pub fn inject(sled_db: &sled::Db, vks: &Vks) -> Result<()> {
     // Derive the database names for the specific contracts
     let money_db_name = MONEY_CONTRACT_ID.hash_state_id(SMART_CONTRACT_ZKAS_DB_NAME);
     let dao_db_name = DAO_CONTRACT_ID.hash_state_id(SMART_CONTRACT_ZKAS_DB_NAME);
+    let auction_db_name = AUCTION_CONTRACT_ID.hash_state_id(SMART_CONTRACT_ZKAS_DB_NAME);
 
     // Create the db trees
     let money_tree = sled_db.open_tree(money_db_name)?;
     let dao_tree = sled_db.open_tree(dao_db_name)?;
+    let auction_tree = sled_db.open_tree(auction_db_name)?;
 
     for (bincode, namespace, vk) in vks.iter() {
         match namespace.as_str() {
@@ -215,6 +224,15 @@ pub fn inject(sled_db: &sled::Db, vks: &Vks) -> Result<()> {
                 dao_tree.insert(key, value)?;
             }
 
+            // Auction contract circuits
+            AUCTION_CONTRACT_ZKAS_COIN_NS |
+            AUCTION_CONTRACT_ZKAS_BID_NS |
+            AUCTION_CONTRACT_ZKAS_CLAIM_NS => {
+                let key = serialize(&namespace.as_str());
+                let value = serialize(&(bincode.clone(), vk.clone()));
+                auction_tree.insert(key, value)?;
+            }
+
             x => panic!("Found unhandled zkas namespace {}", x),
         }
     }
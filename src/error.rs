@@ -131,6 +131,15 @@ pub enum Error {
     #[error("Channel timed out")]
     ChannelTimeout,
 
+    #[error("Stream closed")]
+    StreamClosed,
+
+    #[error("Peer does not support streams")]
+    StreamsUnsupported,
+
+    #[error("Unexpected message on stream: {0}")]
+    StreamUnexpectedMessage(String),
+
     #[error("Failed to reach any seeds")]
     SeedFailed,
 
@@ -264,6 +273,10 @@ pub enum Error {
     #[error("JSON-RPC client stopped")]
     RpcClientStopped,
 
+    #[cfg(feature = "rpc")]
+    #[error("JSON-RPC authentication failed")]
+    RpcUnauthorized,
+
     #[error("Unexpected JSON-RPC data received: {0}")]
     UnexpectedJsonRpc(String),
 
@@ -306,6 +319,9 @@ pub enum Error {
     #[error("Consensus task stopped")]
     ConsensusTaskStopped,
 
+    #[error("Chain tip is stale")]
+    StaleChainTip,
+
     #[error("Miner task stopped")]
     MinerTaskStopped,
 
@@ -386,6 +402,9 @@ pub enum Error {
     #[error("zkas bincode not found in sled database")]
     ZkasBincodeNotFound,
 
+    #[error("State snapshot is invalid: {0}")]
+    SnapshotInvalid(String),
+
     // ===================
     // wasm runtime errors
     // ===================
@@ -446,6 +465,12 @@ pub enum Error {
     #[error("Event is invalid")]
     EventIsInvalid,
 
+    #[error("Event violates signature policy of topic: {0}")]
+    EventSignatureRequired(String),
+
+    #[error("Invalid DAG snapshot: {0}")]
+    DagSnapshotInvalid(String),
+
     // ====================
     // Miscellaneous errors
     // ====================
@@ -531,6 +556,9 @@ pub enum Error {
     #[error("Geode chunk route not found")]
     GeodeChunkRouteNotFound,
 
+    #[error("Geode hash algorithm mismatch: {0}")]
+    GeodeHashAlgorithmMismatch(String),
+
     // ==================
     // Event Graph errors
     // ==================
@@ -589,6 +617,18 @@ pub enum TxVerifyFailed {
     #[error("Insufficient fee paid")]
     InsufficientFee,
 
+    #[error("Transaction is not valid before block height {0}")]
+    NotYetValid(u32),
+
+    #[error("Transaction expired after block height {0}")]
+    Expired(u32),
+
+    #[error("Transaction rejected by mempool admission policy: {0}")]
+    AdmissionRejected(String),
+
+    #[error("Transaction double-spends a nullifier of transaction {0}")]
+    DoubleSpend(String),
+
     #[error("Erroneous transactions found")]
     ErroneousTxs(Vec<crate::tx::Transaction>),
 }
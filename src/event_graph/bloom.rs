@@ -0,0 +1,121 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2025 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use darkfi_serial::{async_trait, SerialDecodable, SerialEncodable};
+
+/// Maximum size of a bloom filter bit array in bytes (1 MiB).
+/// Filters received from the network above this size are rejected.
+pub const BLOOM_MAX_BYTES: usize = 1024 * 1024;
+/// Maximum number of hash functions a bloom filter may use
+pub const BLOOM_MAX_HASHES: u8 = 16;
+/// Target false positive rate used when building sync filters
+pub const BLOOM_FP_RATE: f64 = 0.001;
+
+/// A simple bloom filter over event IDs, used for set reconciliation
+/// during DAG sync. Since event IDs are BLAKE3 hashes, their bytes are
+/// already uniformly distributed, so the bit indexes are derived from
+/// the ID itself using double hashing instead of rehashing it.
+#[derive(Clone, Debug, SerialEncodable, SerialDecodable)]
+pub struct BloomFilter {
+    /// Bit array
+    bits: Vec<u8>,
+    /// Number of hash functions
+    n_hashes: u8,
+}
+
+impl BloomFilter {
+    /// Create a new empty [`BloomFilter`] sized to hold `n_items`
+    /// with the given false positive rate `fp_rate`.
+    pub fn new(n_items: usize, fp_rate: f64) -> Self {
+        let n_items = n_items.max(1) as f64;
+        let ln2 = std::f64::consts::LN_2;
+
+        // m = -n * ln(p) / ln(2)^2
+        let n_bits = (-n_items * fp_rate.ln() / (ln2 * ln2)).ceil() as usize;
+        let n_bytes = n_bits.div_ceil(8).clamp(1, BLOOM_MAX_BYTES);
+
+        // k = m / n * ln(2)
+        let n_hashes = ((n_bytes * 8) as f64 / n_items * ln2).round() as u8;
+        let n_hashes = n_hashes.clamp(1, BLOOM_MAX_HASHES);
+
+        Self { bits: vec![0; n_bytes], n_hashes }
+    }
+
+    /// Check if the filter parameters are within sane bounds.
+    /// Used to verify filters received from peers.
+    pub fn is_valid(&self) -> bool {
+        !self.bits.is_empty() &&
+            self.bits.len() <= BLOOM_MAX_BYTES &&
+            self.n_hashes > 0 &&
+            self.n_hashes <= BLOOM_MAX_HASHES
+    }
+
+    /// Compute the bit indexes for the given event ID
+    fn indexes(&self, id: &blake3::Hash) -> impl Iterator<Item = usize> {
+        let bytes = id.as_bytes();
+        let h1 = u64::from_le_bytes(bytes[0..8].try_into().unwrap());
+        let h2 = u64::from_le_bytes(bytes[8..16].try_into().unwrap());
+        let n_bits = (self.bits.len() * 8) as u64;
+
+        (0..self.n_hashes as u64)
+            .map(move |i| (h1.wrapping_add(i.wrapping_mul(h2)) % n_bits) as usize)
+    }
+
+    /// Insert an event ID into the filter
+    pub fn insert(&mut self, id: &blake3::Hash) {
+        let indexes: Vec<usize> = self.indexes(id).collect();
+        for idx in indexes {
+            self.bits[idx / 8] |= 1 << (idx % 8);
+        }
+    }
+
+    /// Check if an event ID might be in the filter.
+    /// False positives are possible, false negatives are not.
+    pub fn contains(&self, id: &blake3::Hash) -> bool {
+        self.indexes(id).all(|idx| self.bits[idx / 8] & (1 << (idx % 8)) != 0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bloom_filter() {
+        let n_items = 5000;
+        let mut filter = BloomFilter::new(n_items, BLOOM_FP_RATE);
+        assert!(filter.is_valid());
+
+        let inserted: Vec<_> =
+            (0..n_items as u64).map(|i| blake3::hash(&i.to_le_bytes())).collect();
+        for id in inserted.iter() {
+            filter.insert(id);
+        }
+
+        // No false negatives
+        for id in inserted.iter() {
+            assert!(filter.contains(id));
+        }
+
+        // False positives should stay around the target rate
+        let false_positives = (n_items as u64..2 * n_items as u64)
+            .filter(|i| filter.contains(&blake3::hash(&i.to_le_bytes())))
+            .count();
+        assert!(false_positives < n_items / 100);
+    }
+}
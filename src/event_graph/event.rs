@@ -16,20 +16,44 @@
  * along with this program.  If not, see <https://www.gnu.org/licenses/>.
  */
 
-use std::{collections::HashSet, time::UNIX_EPOCH};
+use std::{
+    collections::HashSet,
+    io::{Error as IoError, ErrorKind, Read, Write},
+    time::UNIX_EPOCH,
+};
 
-use darkfi_serial::{async_trait, deserialize_async, Encodable, SerialDecodable, SerialEncodable};
+use darkfi_sdk::crypto::{
+    schnorr::{SchnorrPublic, SchnorrSecret, Signature},
+    PublicKey, SecretKey,
+};
+use darkfi_serial::{
+    async_trait, deserialize_async, AsyncDecodable, AsyncEncodable, AsyncRead, AsyncWrite,
+    Decodable, Encodable, HashedWriter, SerialDecodable, SerialEncodable,
+};
 use sled_overlay::{sled, SledTreeOverlay};
 
 use crate::Result;
 
 use super::{
-    util::next_rotation_timestamp, EventGraph, EVENT_TIME_DRIFT, INITIAL_GENESIS, NULL_ID,
-    N_EVENT_PARENTS,
+    tombstone::Tombstone, util::next_rotation_timestamp, EventGraph, EVENT_TIME_DRIFT,
+    INITIAL_GENESIS, NULL_ID, N_EVENT_PARENTS,
 };
 
-/// Representation of an event in the Event Graph
+/// Author attribution of a signed [`Event`]
 #[derive(Debug, Clone, PartialEq, SerialEncodable, SerialDecodable)]
+pub struct EventAuthor {
+    /// Public key of the event author
+    pub public: PublicKey,
+    /// Schnorr signature over the event ID
+    pub signature: Signature,
+}
+
+/// Representation of an event in the Event Graph
+///
+/// Unsigned events keep using the legacy encoding, so they remain
+/// readable by older nodes and from existing databases, while signed
+/// events use a versioned encoding carrying their author.
+#[derive(Debug, Clone, PartialEq)]
 pub struct Event {
     /// Timestamp of the event in whole seconds
     pub timestamp: u64,
@@ -39,8 +63,18 @@ pub struct Event {
     pub parents: [blake3::Hash; N_EVENT_PARENTS],
     /// DAG layer index of the event
     pub layer: u64,
+    /// Optional author attribution of the event
+    pub author: Option<EventAuthor>,
 }
 
+/// Marker prefixing versioned event encodings. It takes the place of
+/// the timestamp in the legacy encoding, which can never be this big,
+/// so both encodings can be told apart while decoding.
+const EVENT_VERSION_MARKER: u64 = u64::MAX;
+
+/// Current versioned event encoding, used by signed events.
+pub const EVENT_VERSION: u8 = 1;
+
 impl Event {
     /// Create a new event with the given data and an [`EventGraph`] reference.
     /// The timestamp of the event will be the current time, and the parents
@@ -54,23 +88,81 @@ impl Event {
             content: data,
             parents,
             layer,
+            author: None,
         }
     }
 
     /// Same as `Event::new()` but allows specifying the timestamp explicitly.
     pub async fn with_timestamp(timestamp: u64, data: Vec<u8>, event_graph: &EventGraph) -> Self {
         let (layer, parents) = event_graph.get_next_layer_with_parents().await;
-        Self { timestamp, content: data, parents, layer }
+        Self { timestamp, content: data, parents, layer, author: None }
     }
 
-    /// Hash the [`Event`] to retrieve its ID
+    /// Hash the [`Event`] to retrieve its ID.
+    /// For signed events, the author public key is part of the ID,
+    /// so the attribution can't be stripped or replaced.
     pub fn id(&self) -> blake3::Hash {
-        let mut hasher = blake3::Hasher::new();
-        self.timestamp.encode(&mut hasher).unwrap();
-        self.content.encode(&mut hasher).unwrap();
-        self.parents.encode(&mut hasher).unwrap();
-        self.layer.encode(&mut hasher).unwrap();
-        hasher.finalize()
+        self.encode_hashed(&mut std::io::sink()).unwrap().1
+    }
+
+    /// Serialize the [`Event`], computing its ID in the same pass.
+    pub fn serialize_with_id(&self) -> (Vec<u8>, blake3::Hash) {
+        let mut encoded = vec![];
+        let (_, id) = self.encode_hashed(&mut encoded).unwrap();
+        (encoded, id)
+    }
+
+    /// Encode the [`Event`] into the given writer, hashing the ID over
+    /// the encoded bytes as they are written. The version prefix and the
+    /// author signature are encoded outside of the hashed span.
+    fn encode_hashed<S: Write>(
+        &self,
+        s: &mut S,
+    ) -> std::result::Result<(usize, blake3::Hash), IoError> {
+        let mut len = 0;
+        if self.author.is_some() {
+            len += EVENT_VERSION_MARKER.encode(s)?;
+            len += EVENT_VERSION.encode(s)?;
+        }
+
+        let mut hashed = HashedWriter::new(&mut *s, blake3::Hasher::new());
+        len += self.timestamp.encode(&mut hashed)?;
+        len += self.content.encode(&mut hashed)?;
+        len += self.parents.encode(&mut hashed)?;
+        len += self.layer.encode(&mut hashed)?;
+        if let Some(author) = &self.author {
+            len += author.public.encode(&mut hashed)?;
+        }
+        let (s, id) = hashed.finalize();
+
+        if let Some(author) = &self.author {
+            len += author.signature.encode(s)?;
+        }
+        Ok((len, id))
+    }
+
+    /// Sign the [`Event`] using provided secret key, attributing
+    /// it to the corresponding public key.
+    pub fn sign(&mut self, secret: &SecretKey) {
+        let public = PublicKey::from_secret(*secret);
+        self.author = Some(EventAuthor { public, signature: Signature::dummy() });
+        let signature = secret.sign(self.id().as_bytes());
+        self.author.as_mut().unwrap().signature = signature;
+    }
+
+    /// Return the event's author public key, if it is signed.
+    /// Note: This does *NOT* verify the signature.
+    pub fn author(&self) -> Option<&PublicKey> {
+        self.author.as_ref().map(|a| &a.public)
+    }
+
+    /// Verify the event signature, if it is signed.
+    /// Unsigned events are considered valid.
+    pub fn verify_signature(&self) -> bool {
+        match &self.author {
+            Some(author) => author.public.verify(self.id().as_bytes(), &author.signature),
+            None => true,
+        }
     }
 
     /// Return a reference to the event's content
@@ -78,6 +170,11 @@ impl Event {
         &self.content
     }
 
+    /// Decode the event content as a [`Tombstone`], if it is one.
+    pub fn as_tombstone(&self) -> Option<Tombstone> {
+        Tombstone::from_content(&self.content)
+    }
+
     /*
     /// Check if an [`Event`] is considered too old.
     fn is_too_old(&self) -> bool {
@@ -101,6 +198,11 @@ impl Event {
             return Ok(false)
         }
 
+        // Check the author signature, if the event is signed
+        if !self.verify_signature() {
+            return Ok(false)
+        }
+
         // Check if the event timestamp is after genesis timestamp
         if self.timestamp < genesis_timestamp - EVENT_TIME_DRIFT {
             return Ok(false)
@@ -176,6 +278,11 @@ impl Event {
             return false
         }
 
+        // Check the author signature, if the event is signed
+        if !self.verify_signature() {
+            return false
+        }
+
         // Check if the event is too old or too new
         let now = UNIX_EPOCH.elapsed().unwrap().as_millis() as u64;
         let too_old = self.timestamp < now - EVENT_TIME_DRIFT;
@@ -209,10 +316,101 @@ impl Event {
     }
 }
 
+impl Encodable for Event {
+    fn encode<S: Write>(&self, s: &mut S) -> std::result::Result<usize, IoError> {
+        Ok(self.encode_hashed(s)?.0)
+    }
+}
+
+impl Decodable for Event {
+    fn decode<D: Read>(d: &mut D) -> std::result::Result<Self, IoError> {
+        let prefix: u64 = Decodable::decode(d)?;
+        if prefix != EVENT_VERSION_MARKER {
+            // Legacy encoding, where the prefix is the timestamp
+            return Ok(Self {
+                timestamp: prefix,
+                content: Decodable::decode(d)?,
+                parents: Decodable::decode(d)?,
+                layer: Decodable::decode(d)?,
+                author: None,
+            })
+        }
+
+        let version: u8 = Decodable::decode(d)?;
+        if version != EVENT_VERSION {
+            return Err(IoError::new(ErrorKind::InvalidData, "Unknown event version"))
+        }
+
+        Ok(Self {
+            timestamp: Decodable::decode(d)?,
+            content: Decodable::decode(d)?,
+            parents: Decodable::decode(d)?,
+            layer: Decodable::decode(d)?,
+            author: Some(Decodable::decode(d)?),
+        })
+    }
+}
+
+#[async_trait]
+impl AsyncEncodable for Event {
+    async fn encode_async<S: AsyncWrite + Unpin + Send>(
+        &self,
+        s: &mut S,
+    ) -> std::result::Result<usize, IoError> {
+        let mut len = 0;
+        if self.author.is_some() {
+            len += EVENT_VERSION_MARKER.encode_async(s).await?;
+            len += EVENT_VERSION.encode_async(s).await?;
+        }
+        len += self.timestamp.encode_async(s).await?;
+        len += self.content.encode_async(s).await?;
+        len += self.parents.encode_async(s).await?;
+        len += self.layer.encode_async(s).await?;
+        if let Some(author) = &self.author {
+            len += author.encode_async(s).await?;
+        }
+        Ok(len)
+    }
+}
+
+#[async_trait]
+impl AsyncDecodable for Event {
+    async fn decode_async<D: AsyncRead + Unpin + Send>(
+        d: &mut D,
+    ) -> std::result::Result<Self, IoError> {
+        let prefix: u64 = AsyncDecodable::decode_async(d).await?;
+        if prefix != EVENT_VERSION_MARKER {
+            // Legacy encoding, where the prefix is the timestamp
+            return Ok(Self {
+                timestamp: prefix,
+                content: AsyncDecodable::decode_async(d).await?,
+                parents: AsyncDecodable::decode_async(d).await?,
+                layer: AsyncDecodable::decode_async(d).await?,
+                author: None,
+            })
+        }
+
+        let version: u8 = AsyncDecodable::decode_async(d).await?;
+        if version != EVENT_VERSION {
+            return Err(IoError::new(ErrorKind::InvalidData, "Unknown event version"))
+        }
+
+        Ok(Self {
+            timestamp: AsyncDecodable::decode_async(d).await?,
+            content: AsyncDecodable::decode_async(d).await?,
+            parents: AsyncDecodable::decode_async(d).await?,
+            layer: AsyncDecodable::decode_async(d).await?,
+            author: Some(AsyncDecodable::decode_async(d).await?),
+        })
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use std::sync::Arc;
 
+    use darkfi_serial::{deserialize, serialize, serialize_async};
+    use rand::rngs::OsRng;
     use smol::Executor;
 
     use crate::{
@@ -241,6 +439,14 @@ mod tests {
             // Validate our test Event struct
             assert!(valid_event.dag_validate(&event_graph).await?);
 
+            // Sign it and validate it again
+            let secret = SecretKey::random(&mut OsRng);
+            let mut signed_event = valid_event.clone();
+            signed_event.sign(&secret);
+            assert_ne!(signed_event.id(), valid_event.id());
+            assert_eq!(signed_event.author(), Some(&PublicKey::from_secret(secret)));
+            assert!(signed_event.dag_validate(&event_graph).await?);
+
             // Thanks for reading
             Ok(())
         })
@@ -280,6 +486,152 @@ mod tests {
             event_same_layer_as_parents.layer = 0;
             assert!(!event_same_layer_as_parents.dag_validate(&event_graph).await?);
 
+            let mut event_tampered_signature = valid_event.clone();
+            event_tampered_signature.sign(&SecretKey::random(&mut OsRng));
+            event_tampered_signature.content = vec![2u8];
+            assert!(!event_tampered_signature.dag_validate(&event_graph).await?);
+
+            let mut event_replaced_author = valid_event.clone();
+            event_replaced_author.sign(&SecretKey::random(&mut OsRng));
+            event_replaced_author.author.as_mut().unwrap().public =
+                PublicKey::from_secret(SecretKey::random(&mut OsRng));
+            assert!(!event_replaced_author.dag_validate(&event_graph).await?);
+
+            // Thanks for reading
+            Ok(())
+        })
+    }
+
+    #[test]
+    fn signature_policy() -> Result<()> {
+        smol::block_on(async {
+            // Generate a dummy event graph
+            let event_graph = make_event_graph().await?;
+
+            // Classify events by their first content byte, and require
+            // topic "1" events to be signed by a moderator
+            event_graph
+                .set_topic_classifier(Arc::new(|event: &Event| {
+                    event.content().first().map(|b| b.to_string())
+                }))
+                .await;
+            let moderator = SecretKey::random(&mut OsRng);
+            event_graph
+                .require_signatures("1", Some(vec![PublicKey::from_secret(moderator)]))
+                .await;
+
+            // Events of other topics don't need to be signed
+            let event = Event::new(vec![2u8], &event_graph).await;
+            assert!(event_graph.dag_insert(&[event]).await.is_ok());
+
+            // Unsigned events get rejected
+            let mut event = Event::new(vec![1u8], &event_graph).await;
+            assert!(event_graph.dag_insert(&[event.clone()]).await.is_err());
+
+            // Events signed by someone else get rejected
+            event.sign(&SecretKey::random(&mut OsRng));
+            assert!(event_graph.dag_insert(&[event.clone()]).await.is_err());
+
+            // Events signed by the moderator get accepted
+            event.sign(&moderator);
+            assert!(event_graph.dag_insert(&[event.clone()]).await.is_ok());
+
+            // Once released, unsigned events get accepted again
+            event_graph.release_signatures("1").await;
+            let event = Event::new(vec![1u8, 1u8], &event_graph).await;
+            assert!(event_graph.dag_insert(&[event]).await.is_ok());
+
+            // Thanks for reading
+            Ok(())
+        })
+    }
+
+    #[test]
+    fn event_encoding_versions() -> Result<()> {
+        smol::block_on(async {
+            // Generate a dummy event graph
+            let event_graph = make_event_graph().await?;
+
+            // Unsigned events keep the legacy encoding
+            let event = Event::new(vec![1u8, 2u8], &event_graph).await;
+            let mut legacy = vec![];
+            event.timestamp.encode(&mut legacy)?;
+            event.content.encode(&mut legacy)?;
+            event.parents.encode(&mut legacy)?;
+            event.layer.encode(&mut legacy)?;
+            assert_eq!(serialize(&event), legacy);
+            assert_eq!(serialize_async(&event).await, legacy);
+            assert_eq!(deserialize::<Event>(&legacy)?, event);
+            assert_eq!(deserialize_async::<Event>(&legacy).await?, event);
+            assert_eq!(event.serialize_with_id(), (legacy.clone(), blake3::hash(&legacy)));
+
+            // Signed events round-trip through the versioned encoding
+            let mut signed_event = event.clone();
+            signed_event.sign(&SecretKey::random(&mut OsRng));
+            let encoded = serialize_async(&signed_event).await;
+            assert_eq!(encoded, serialize(&signed_event));
+            assert_eq!(&encoded[..8], &EVENT_VERSION_MARKER.to_le_bytes());
+            let decoded: Event = deserialize_async(&encoded).await?;
+            assert_eq!(decoded, signed_event);
+            assert_eq!(deserialize::<Event>(&encoded)?, signed_event);
+            assert!(decoded.verify_signature());
+
+            // The single-pass encoding matches the separately hashed ID,
+            // which leaves the version prefix and the signature out
+            let (encoded_with_id, id) = signed_event.serialize_with_id();
+            assert_eq!(encoded_with_id, encoded);
+            assert_eq!(id, signed_event.id());
+            assert_eq!(id, blake3::hash(&encoded[9..encoded.len() - 64]));
+
+            // Unknown versions are rejected
+            let mut unknown = encoded.clone();
+            unknown[8] = EVENT_VERSION + 1;
+            assert!(deserialize::<Event>(&unknown).is_err());
+
+            // Thanks for reading
+            Ok(())
+        })
+    }
+
+    #[test]
+    fn event_filter() -> Result<()> {
+        smol::block_on(async {
+            // Generate a dummy event graph
+            let event_graph = make_event_graph().await?;
+
+            // Only accept events with even content length
+            event_graph
+                .set_event_filter(Some(Arc::new(|event: &Event| event.content().len() % 2 == 0)))
+                .await;
+
+            let event = Event::new(vec![1u8, 2u8], &event_graph).await;
+            assert!(event_graph.dag_insert(&[event]).await.is_ok());
+
+            // Filtered events are skipped, without failing the insertion
+            let event = Event::new(vec![1u8], &event_graph).await;
+            assert!(event_graph.dag_insert(&[event.clone()]).await?.is_empty());
+            assert!(event_graph.dag_get(&event.id()).await?.is_none());
+
+            // Within a batch, the filtered events and the events building
+            // on them are skipped, while the rest get inserted
+            let good = Event::new(vec![3u8, 4u8], &event_graph).await;
+            let mut child_parents = [NULL_ID; N_EVENT_PARENTS];
+            child_parents[0] = event.id();
+            let child = Event {
+                timestamp: event.timestamp,
+                content: vec![5u8, 6u8],
+                parents: child_parents,
+                layer: event.layer + 1,
+                author: None,
+            };
+            let ids = event_graph.dag_insert(&[event.clone(), child.clone(), good.clone()]).await?;
+            assert_eq!(ids, vec![good.id()]);
+            assert!(event_graph.dag_get(&child.id()).await?.is_none());
+
+            // Once removed, everything gets accepted again
+            event_graph.set_event_filter(None).await;
+            assert!(event_graph.dag_insert(&[event]).await.is_ok());
+
             // Thanks for reading
             Ok(())
         })
@@ -22,39 +22,57 @@ use std::{
     sync::Arc,
 };
 
-use darkfi_serial::{deserialize_async, serialize_async};
+use darkfi_sdk::crypto::PublicKey;
+use darkfi_serial::deserialize_async;
 use log::{debug, error, info, warn};
-use num_bigint::BigUint;
 use sled_overlay::{sled, SledTreeOverlay};
 use smol::{
     lock::{OnceCell, RwLock},
     Executor,
 };
 use tinyjson::JsonValue::{self};
+use url::Url;
 
 use crate::{
     event_graph::util::replayer_log,
-    net::P2pPtr,
+    net::{ChannelPtr, P2pPtr},
     rpc::{
         jsonrpc::{JsonResponse, JsonResult},
         util::json_map,
     },
     system::{msleep, Publisher, PublisherPtr, StoppableTask, StoppableTaskPtr, Subscription},
+    util::time::NanoTimestamp,
     Error, Result,
 };
 
 /// An event graph event
 pub mod event;
-pub use event::Event;
+pub use event::{Event, EventAuthor};
+
+/// Per-topic event signature policies
+pub mod policy;
+use policy::{EventFilterFn, EventRateLimit, SignaturePolicy, TopicFn};
 
 /// P2P protocol implementation for the Event Graph
 pub mod proto;
-use proto::{EventRep, EventReq, TipRep, TipReq};
+use proto::{BloomRep, BloomReq, EventRep, EventReq, TipRep, TipReq};
+
+/// Bloom filter used for sync set reconciliation
+pub mod bloom;
+use bloom::{BloomFilter, BLOOM_FP_RATE};
 
 /// Utility functions
 pub mod util;
 use util::{generate_genesis, millis_until_next_rotation, next_rotation_timestamp};
 
+/// DAG snapshots for fast bootstrap
+pub mod snapshot;
+pub use snapshot::DagSnapshot;
+
+/// Tombstones for event edits, deletions and expiry
+pub mod tombstone;
+pub use tombstone::{EventVersion, Tombstone};
+
 // Debugging event graph
 pub mod deg;
 use deg::DegEvent;
@@ -72,6 +90,8 @@ pub const GENESIS_CONTENTS: &[u8] = &[0x47, 0x45, 0x4e, 0x45, 0x53, 0x49, 0x53];
 pub const N_EVENT_PARENTS: usize = 5;
 /// Allowed timestamp drift in milliseconds
 const EVENT_TIME_DRIFT: u64 = 60_000;
+/// Maximum number of event IDs requested in a single `EventReq` during sync
+const SYNC_REQ_BATCH_SIZE: usize = 200;
 /// Null event ID
 pub const NULL_ID: blake3::Hash = blake3::Hash::from_bytes([0x00; blake3::OUT_LEN]);
 
@@ -112,6 +132,16 @@ pub struct EventGraph {
     pub deg_enabled: RwLock<bool>,
     /// The publisher for which we can give deg info over
     deg_publisher: PublisherPtr<DegEvent>,
+    /// Per-topic signature requirements for inserted events
+    signature_policy: RwLock<SignaturePolicy>,
+    /// Optional application defined filter for inserted events
+    event_filter: RwLock<Option<EventFilterFn>>,
+    /// Tombstone event IDs, mapped by the event ID they target
+    tombstones: RwLock<HashMap<blake3::Hash, HashSet<blake3::Hash>>>,
+    /// Optional per-peer rate limit of unsolicited new events
+    rate_limit: RwLock<Option<EventRateLimit>>,
+    /// Greylisted peers, mapped to the time they got greylisted
+    greylist: RwLock<HashMap<Url, NanoTimestamp>>,
 }
 
 impl EventGraph {
@@ -158,6 +188,11 @@ impl EventGraph {
             synced: RwLock::new(false),
             deg_enabled: RwLock::new(false),
             deg_publisher: Publisher::new(),
+            signature_policy: RwLock::new(SignaturePolicy::default()),
+            event_filter: RwLock::new(None),
+            tombstones: RwLock::new(HashMap::new()),
+            rate_limit: RwLock::new(None),
+            greylist: RwLock::new(HashMap::new()),
         });
 
         // Check if we have it in our DAG.
@@ -173,6 +208,9 @@ impl EventGraph {
         // Find the unreferenced tips in the current DAG state.
         *self_.unreferenced_tips.write().await = self_.find_unreferenced_tips().await;
 
+        // Index the tombstones in the current DAG state.
+        *self_.tombstones.write().await = self_.find_tombstones().await?;
+
         // Spawn the DAG pruning task
         if days_rotation > 0 {
             let prune_task = StoppableTask::new();
@@ -198,6 +236,75 @@ impl EventGraph {
         self.days_rotation
     }
 
+    /// Return the amount of events currently in the DAG
+    pub fn dag_len(&self) -> usize {
+        self.dag.len()
+    }
+
+    /// Set the classifier used to map events into application topics,
+    /// used to enforce per-topic signature requirements.
+    pub async fn set_topic_classifier(&self, classifier: TopicFn) {
+        self.signature_policy.write().await.set_classifier(classifier);
+    }
+
+    /// Require events of provided topic to be signed, optionally
+    /// only by one of provided authors, for them to be inserted
+    /// into the DAG.
+    pub async fn require_signatures(&self, topic: &str, authors: Option<Vec<PublicKey>>) {
+        self.signature_policy.write().await.require(topic, authors);
+    }
+
+    /// Stop requiring events of provided topic to be signed.
+    pub async fn release_signatures(&self, topic: &str) {
+        self.signature_policy.write().await.release(topic);
+    }
+
+    /// Set the application defined filter events must pass for them
+    /// to be inserted into the DAG. Passing `None` removes the filter.
+    pub async fn set_event_filter(&self, filter: Option<EventFilterFn>) {
+        *self.event_filter.write().await = filter;
+    }
+
+    /// Set the per-peer rate limit of unsolicited new events.
+    /// Passing `None` disables it and clears the greylist.
+    pub async fn set_rate_limit(&self, rate_limit: Option<EventRateLimit>) {
+        if rate_limit.is_none() {
+            self.greylist.write().await.clear();
+        }
+        *self.rate_limit.write().await = rate_limit;
+    }
+
+    /// Greylist provided peer for exceeding the rate limit.
+    async fn greylist_peer(&self, addr: &Url) {
+        let previous =
+            self.greylist.write().await.insert(addr.clone(), NanoTimestamp::current_time());
+        if previous.is_none() {
+            warn!(
+                target: "event_graph::greylist_peer()",
+                "[EVENTGRAPH] Peer {} exceeded the event rate limit, greylisting", addr,
+            );
+        }
+    }
+
+    /// Return the currently greylisted peers, dropping expired entries.
+    pub async fn greylisted_peers(&self) -> HashSet<Url> {
+        let Some(rate_limit) = *self.rate_limit.read().await else { return HashSet::new() };
+        let greylist_time = NanoTimestamp::from_secs(rate_limit.greylist_time as u128);
+
+        let mut greylist = self.greylist.write().await;
+        greylist.retain(|addr, since| {
+            let active = since.elapsed().map(|e| e < greylist_time).unwrap_or(false);
+            if !active {
+                info!(
+                    target: "event_graph::greylisted_peers()",
+                    "[EVENTGRAPH] Peer {} greylisting expired", addr,
+                );
+            }
+            active
+        });
+        greylist.keys().cloned().collect()
+    }
+
     /// Sync the DAG from connected peers
     pub async fn dag_sync(&self) -> Result<()> {
         // We do an optimistic sync where we ask all our connected peers for
@@ -215,8 +322,10 @@ impl EventGraph {
         //   amount of iterations, these could be faulty peers and we can try again
         //   from the beginning
 
-        // Get references to all our peers.
-        let channels = self.p2p.hosts().peers();
+        // Get references to all our peers, asking greylisted ones last.
+        let greylisted = self.greylisted_peers().await;
+        let mut channels = self.p2p.hosts().peers();
+        channels.sort_by_key(|c| greylisted.contains(c.address()));
         let mut communicated_peers = channels.len();
         info!(
             target: "event_graph::dag_sync()",
@@ -301,25 +410,65 @@ impl EventGraph {
         }
         drop(tips);
 
-        // Now begin fetching the events backwards.
-        let mut missing_parents = HashSet::new();
+        let mut received_events: BTreeMap<u64, Vec<Event>> = BTreeMap::new();
+        let mut received_events_hashes = HashSet::new();
+
+        // Check if we are missing any of the considered tips.
+        let mut missing_tips = false;
         for tip in considered_tips.iter() {
             assert!(tip != &NULL_ID);
 
             if !self.dag.contains_key(tip.as_bytes()).unwrap() {
+                missing_tips = true;
+                break
+            }
+        }
+
+        // If we're missing anything, first try to reconcile our event set with
+        // our peers using a bloom filter, so they can tell us exactly which events
+        // we lack instead of us walking the DAG backwards one layer at a time.
+        if missing_tips {
+            info!(target: "event_graph::dag_sync()", "[EVENTGRAPH] Reconciling event sets");
+            self.dag_sync_bloom(
+                &channels,
+                &considered_tips,
+                &mut received_events,
+                &mut received_events_hashes,
+            )
+            .await;
+        }
+
+        // Now begin fetching whatever is still missing backwards.
+        // This covers bloom filter false positives and peers not
+        // replying to the reconciliation request.
+        let mut missing_parents = HashSet::new();
+        for tip in considered_tips.iter() {
+            if !received_events_hashes.contains(tip) &&
+                !self.dag.contains_key(tip.as_bytes()).unwrap()
+            {
                 missing_parents.insert(*tip);
             }
         }
+        for events in received_events.values() {
+            for event in events {
+                for parent in event.parents.iter() {
+                    if parent != &NULL_ID &&
+                        !received_events_hashes.contains(parent) &&
+                        !self.dag.contains_key(parent.as_bytes()).unwrap()
+                    {
+                        missing_parents.insert(*parent);
+                    }
+                }
+            }
+        }
 
-        if missing_parents.is_empty() {
+        if missing_parents.is_empty() && received_events.is_empty() {
             *self.synced.write().await = true;
             info!(target: "event_graph::dag_sync()", "[EVENTGRAPH] DAG synced successfully!");
             return Ok(())
         }
 
         info!(target: "event_graph::dag_sync()", "[EVENTGRAPH] Fetching events");
-        let mut received_events: BTreeMap<u64, Vec<Event>> = BTreeMap::new();
-        let mut received_events_hashes = HashSet::new();
 
         while !missing_parents.is_empty() {
             let mut found_event = false;
@@ -428,7 +577,9 @@ impl EventGraph {
         } // <-- while !missing_parents.is_empty
 
         // At this point we should've got all the events.
-        // We should add them to the DAG.
+        // We should add them to the DAG. Events deleted by a tombstone
+        // received in the same sync are inserted, since they are still
+        // referenced as parents, but they are not notified further.
         let mut events = vec![];
         for (_, tips) in received_events {
             for tip in tips {
@@ -443,7 +594,176 @@ impl EventGraph {
         Ok(())
     }
 
+    /// Reconcile our DAG with the given peers using a bloom filter.
+    /// We send each peer a filter of all the event IDs we have, and they
+    /// reply with the IDs they have that are not in it. Those events are
+    /// then requested in batches from the same peer, moving on to the next
+    /// peer until every ancestor of the `considered_tips` is found.
+    /// Only events reachable from the considered tips are kept, so a single
+    /// peer can't feed us events the majority doesn't agree on. They are
+    /// collected into `received_events`, mapped by their layer. Failures
+    /// here are not fatal, since `dag_sync()` falls back to fetching the
+    /// missing parents backwards.
+    async fn dag_sync_bloom(
+        &self,
+        channels: &[ChannelPtr],
+        considered_tips: &HashSet<blake3::Hash>,
+        received_events: &mut BTreeMap<u64, Vec<Event>>,
+        received_events_hashes: &mut HashSet<blake3::Hash>,
+    ) {
+        // Build the filter over our current DAG
+        let mut filter = BloomFilter::new(self.dag.len(), BLOOM_FP_RATE);
+        for event_id in self.dag.iter().keys() {
+            let event_id = event_id.unwrap();
+            filter.insert(&blake3::Hash::from_bytes((&event_id as &[u8]).try_into().unwrap()));
+        }
+
+        let timeout = self.p2p.settings().read().await.outbound_connect_timeout;
+
+        // Events received from peers, not yet known to reach the tips
+        let mut candidates: HashMap<blake3::Hash, Event> = HashMap::new();
+        let mut reachable = HashSet::new();
+
+        for channel in channels.iter() {
+            let url = channel.address();
+
+            let (bloom_rep_sub, ev_rep_sub) = match (
+                channel.subscribe_msg::<BloomRep>().await,
+                channel.subscribe_msg::<EventRep>().await,
+            ) {
+                (Ok(b), Ok(e)) => (b, e),
+                _ => {
+                    error!(
+                        target: "event_graph::dag_sync_bloom()",
+                        "[EVENTGRAPH] Sync: Couldn't subscribe BloomRep/EventRep for peer {}, skipping",
+                        url,
+                    );
+                    continue
+                }
+            };
+
+            if let Err(e) = channel.send(&BloomReq(filter.clone())).await {
+                error!(
+                    target: "event_graph::dag_sync_bloom()",
+                    "[EVENTGRAPH] Sync: Couldn't send BloomReq to peer {}, skipping ({})", url, e,
+                );
+                continue
+            }
+
+            let Ok(peer_missing) = bloom_rep_sub.receive_with_timeout(timeout).await else {
+                error!(
+                    target: "event_graph::dag_sync_bloom()",
+                    "[EVENTGRAPH] Sync: Peer {} didn't reply with BloomRep in time, skipping", url,
+                );
+                continue
+            };
+
+            // Only keep the IDs we don't already have
+            let missing: Vec<blake3::Hash> = peer_missing
+                .0
+                .iter()
+                .filter(|id| {
+                    !candidates.contains_key(*id) && !self.dag.contains_key(id.as_bytes()).unwrap()
+                })
+                .copied()
+                .collect();
+
+            debug!(
+                target: "event_graph::dag_sync_bloom()",
+                "Peer {} reported {} events we are missing", url, missing.len(),
+            );
+
+            for batch in missing.chunks(SYNC_REQ_BATCH_SIZE) {
+                if let Err(e) = channel.send(&EventReq(batch.to_vec())).await {
+                    error!(
+                        target: "event_graph::dag_sync_bloom()",
+                        "[EVENTGRAPH] Sync: Failed communicating EventReq to {}: {}", url, e,
+                    );
+                    break
+                }
+
+                let Ok(events) = ev_rep_sub.receive_with_timeout(timeout).await else {
+                    error!(
+                        target: "event_graph::dag_sync_bloom()",
+                        "[EVENTGRAPH] Sync: Timeout waiting for events from {}", url,
+                    );
+                    break
+                };
+
+                for event in events.0.iter() {
+                    let event_id = event.id();
+                    if !batch.contains(&event_id) {
+                        error!(
+                            target: "event_graph::dag_sync_bloom()",
+                            "[EVENTGRAPH] Sync: Peer {} replied with a wrong event: {}",
+                            url, event_id,
+                        );
+                        continue
+                    }
+
+                    candidates.entry(event_id).or_insert_with(|| event.clone());
+                }
+            }
+
+            // Stop asking once we have every ancestor of the tips
+            let (reached, unresolved) = self.dag_sync_ancestors(considered_tips, &candidates);
+            reachable = reached;
+            if unresolved.is_empty() {
+                break
+            }
+        }
+
+        let dropped = candidates.len() - reachable.len();
+        if dropped > 0 {
+            warn!(
+                target: "event_graph::dag_sync_bloom()",
+                "[EVENTGRAPH] Sync: Dropping {} events not reaching the DAG tips", dropped,
+            );
+        }
+
+        for event_id in reachable {
+            let event = candidates.remove(&event_id).unwrap();
+            received_events_hashes.insert(event_id);
+            received_events.entry(event.layer).or_default().push(event);
+        }
+    }
+
+    /// Walk the given `candidates` backwards from the `tips`, returning the
+    /// candidate events reached, along with the IDs reached that are neither
+    /// candidates nor in our DAG. Since event IDs commit to their parents,
+    /// every event reached is an ancestor of the tips.
+    fn dag_sync_ancestors(
+        &self,
+        tips: &HashSet<blake3::Hash>,
+        candidates: &HashMap<blake3::Hash, Event>,
+    ) -> (HashSet<blake3::Hash>, HashSet<blake3::Hash>) {
+        let mut reachable = HashSet::new();
+        let mut unresolved = HashSet::new();
+        let mut stack: Vec<blake3::Hash> = tips.iter().copied().collect();
+
+        while let Some(event_id) = stack.pop() {
+            if event_id == NULL_ID ||
+                reachable.contains(&event_id) ||
+                self.dag.contains_key(event_id.as_bytes()).unwrap()
+            {
+                continue
+            }
+
+            let Some(event) = candidates.get(&event_id) else {
+                unresolved.insert(event_id);
+                continue
+            };
+
+            reachable.insert(event_id);
+            stack.extend(event.parents.iter().copied());
+        }
+
+        (reachable, unresolved)
+    }
+
     /// Atomically prune the DAG and insert the given event as genesis.
+    /// Tombstones are dropped along with their targets, so the index
+    /// is cleared as well.
     async fn dag_prune(&self, genesis_event: Event) -> Result<()> {
         debug!(target: "event_graph::dag_prune()", "Pruning DAG...");
 
@@ -455,13 +775,15 @@ impl EventGraph {
         let mut unreferenced_tips = self.unreferenced_tips.write().await;
         let mut broadcasted_ids = self.broadcasted_ids.write().await;
         let mut current_genesis = self.current_genesis.write().await;
+        let mut tombstones = self.tombstones.write().await;
 
         // Atomically clear the DAG and write the new genesis event.
         let mut batch = sled::Batch::default();
         for key in self.dag.iter().keys() {
             batch.remove(key.unwrap());
         }
-        batch.insert(genesis_event.id().as_bytes(), serialize_async(&genesis_event).await);
+        let (genesis_se, genesis_id) = genesis_event.serialize_with_id();
+        batch.insert(genesis_id.as_bytes(), genesis_se);
 
         debug!(target: "event_graph::dag_prune()", "Applying batch...");
         if let Err(e) = self.dag.apply_batch(batch) {
@@ -470,12 +792,14 @@ impl EventGraph {
 
         // Clear unreferenced tips and bcast ids
         *unreferenced_tips = BTreeMap::new();
-        unreferenced_tips.insert(0, HashSet::from([genesis_event.id()]));
+        unreferenced_tips.insert(0, HashSet::from([genesis_id]));
         *current_genesis = genesis_event;
         *broadcasted_ids = HashSet::new();
+        *tombstones = HashMap::new();
         drop(unreferenced_tips);
         drop(broadcasted_ids);
         drop(current_genesis);
+        drop(tombstones);
 
         debug!(target: "event_graph::dag_prune()", "DAG pruned successfully");
         Ok(())
@@ -499,6 +823,7 @@ impl EventGraph {
                 content: GENESIS_CONTENTS.to_vec(),
                 parents: [NULL_ID; N_EVENT_PARENTS],
                 layer: 0,
+                author: None,
             };
 
             // Sleep until it's time to rotate.
@@ -514,7 +839,11 @@ impl EventGraph {
     }
 
     /// Atomically insert given events into the DAG and return the event IDs.
-    /// All provided events must be valid. An overlay is used over the DAG tree,
+    /// All provided events must be valid. Events rejected by the application
+    /// event filter, along with the provided events building on them, are
+    /// skipped instead, and their IDs are not returned, since the filter
+    /// depends on local configuration rather than the event being malformed.
+    /// An overlay is used over the DAG tree,
     /// temporary writting each event in order. After all events have been
     /// validated and inserted successfully, we write the overlay to sled.
     /// This will append the new events into the unreferenced tips set, and
@@ -533,8 +862,12 @@ impl EventGraph {
         let mut unreferenced_tips = self.unreferenced_tips.write().await;
         let mut broadcasted_ids = self.broadcasted_ids.write().await;
 
-        // Here we keep the IDs to return
+        // Here we keep the IDs to return, along with the inserted events
         let mut ids = Vec::with_capacity(events.len());
+        let mut inserted = Vec::with_capacity(events.len());
+
+        // IDs of the events skipped due to the event filter
+        let mut filtered = HashSet::new();
 
         // Create an overlay over the DAG tree
         let mut overlay = SledTreeOverlay::new(&self.dag);
@@ -542,15 +875,42 @@ impl EventGraph {
         // Grab genesis timestamp
         let genesis_timestamp = self.current_genesis.read().await.timestamp;
 
+        // Grab the signature policy
+        let signature_policy = self.signature_policy.read().await;
+
+        // Grab the application event filter
+        let event_filter = self.event_filter.read().await;
+
         // Iterate over given events to validate them and
         // write them to the overlay
         for event in events {
-            let event_id = event.id();
+            // Encode the event and hash its ID in a single pass
+            let (event_se, event_id) = event.serialize_with_id();
             debug!(
                 target: "event_graph::dag_insert()",
                 "Inserting event {} into the DAG", event_id,
             );
 
+            if event.parents.iter().any(|parent_id| filtered.contains(parent_id)) {
+                debug!(
+                    target: "event_graph::dag_insert()",
+                    "Skipping event {} building on filtered events", event_id,
+                );
+                filtered.insert(event_id);
+                continue
+            }
+
+            if let Some(filter) = event_filter.as_ref() {
+                if !filter(event) {
+                    warn!(
+                        target: "event_graph::dag_insert()",
+                        "Event {} rejected by the event filter, skipping", event_id,
+                    );
+                    filtered.insert(event_id);
+                    continue
+                }
+            }
+
             if !event
                 .validate(&self.dag, genesis_timestamp, self.days_rotation, Some(&overlay))
                 .await?
@@ -559,7 +919,13 @@ impl EventGraph {
                 return Err(Error::EventIsInvalid)
             }
 
-            let event_se = serialize_async(event).await;
+            if let Err(topic) = signature_policy.check(event) {
+                error!(
+                    target: "event_graph::dag_insert()",
+                    "Event {} violates signature policy of topic: {}", event_id, topic,
+                );
+                return Err(Error::EventSignatureRequired(topic))
+            }
 
             // Add the event to the overlay
             overlay.insert(event_id.as_bytes(), &event_se)?;
@@ -569,6 +935,14 @@ impl EventGraph {
             }
             // Note down the event ID to return
             ids.push(event_id);
+            inserted.push(event);
+        }
+        drop(signature_policy);
+        drop(event_filter);
+
+        // Nothing to write if all events got filtered
+        if inserted.is_empty() {
+            return Ok(ids)
         }
 
         // Aggregate changes into a single batch
@@ -580,9 +954,18 @@ impl EventGraph {
             panic!("Failed applying dag_insert batch to sled: {}", e);
         }
 
-        // Iterate over given events to update references and
-        // send out notifications about them
-        for event in events {
+        // Index the inserted tombstones, so their targets resolve
+        // to the latest version right away.
+        let mut tombstones = self.tombstones.write().await;
+        for event in inserted.iter() {
+            if let Some(tombstone) = event.as_tombstone() {
+                tombstones.entry(tombstone.target).or_default().insert(event.id());
+            }
+        }
+        drop(tombstones);
+
+        // Iterate over inserted events to update references
+        for event in inserted.iter() {
             let event_id = event.id();
 
             // Update the unreferenced DAG tips set
@@ -624,9 +1007,23 @@ impl EventGraph {
                 layer_tips.insert(event_id);
                 unreferenced_tips.insert(event.layer, layer_tips);
             }
+        }
 
-            // Send out notifications about the new event
-            self.event_pub.notify(event.clone()).await;
+        // Send out notifications about the new events. Events already
+        // deleted by a tombstone, e.g. when both are received during
+        // sync, are skipped. The DAG time is read from the tips we
+        // hold, since resolving through the locked set would deadlock.
+        let dag_time = self.tips_time(&unreferenced_tips).await?;
+        for event in inserted {
+            if let EventVersion::Deleted(_) = self.resolve_event_at(event.clone(), dag_time).await?
+            {
+                debug!(
+                    target: "event_graph::dag_insert()",
+                    "Event {} is tombstoned, skipping notification", event.id(),
+                );
+                continue
+            }
+            self.event_pub.notify(event).await;
         }
 
         // Drop the exclusive locks
@@ -708,24 +1105,10 @@ impl EventGraph {
     async fn get_unreferenced_tips_sorted(&self) -> [blake3::Hash; N_EVENT_PARENTS] {
         let (_, tips) = self.get_next_layer_with_parents().await;
 
-        // Convert the hash to BigUint for sorting
-        let mut sorted: Vec<_> =
-            tips.iter().map(|x| BigUint::from_bytes_be(x.as_bytes())).collect();
-        sorted.sort_unstable();
-
-        // Convert back to blake3
-        let mut tips_sorted = [NULL_ID; N_EVENT_PARENTS];
-        for (i, id) in sorted.iter().enumerate() {
-            let mut bytes = id.to_bytes_be();
-
-            // Ensure we have 32 bytes
-            while bytes.len() < blake3::OUT_LEN {
-                bytes.insert(0, 0);
-            }
-
-            tips_sorted[i] = blake3::Hash::from_bytes(bytes.try_into().unwrap());
-        }
-
+        // Hashes are fixed-size big-endian numbers, so sorting their
+        // bytes sorts them numerically.
+        let mut tips_sorted = tips;
+        tips_sorted.sort_unstable_by(|a, b| a.as_bytes().cmp(b.as_bytes()));
         tips_sorted
     }
 
@@ -0,0 +1,110 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2025 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use std::{collections::HashMap, sync::Arc};
+
+use darkfi_sdk::crypto::PublicKey;
+
+use super::Event;
+
+/// Function classifying an [`Event`] into an application defined topic.
+/// Events not belonging to any topic should return `None`.
+pub type TopicFn = Arc<dyn Fn(&Event) -> Option<String> + Send + Sync>;
+
+/// Application defined filter deciding whether an [`Event`] is acceptable
+/// for insertion into the DAG, e.g. by checking anti-spam proofs carried
+/// in its content. Returning `false` rejects the event.
+pub type EventFilterFn = Arc<dyn Fn(&Event) -> bool + Send + Sync>;
+
+/// Per-peer rate limit of unsolicited new events. Peers relaying new
+/// events faster than `max_rate` events per second, averaged over the
+/// last `window` seconds, get greylisted for `greylist_time` seconds:
+/// their events are dropped and they are asked last during DAG sync.
+/// We keep relaying events to them, so an honest relay forwarding a
+/// burst can still catch up once the greylisting expires.
+#[derive(Copy, Clone, Debug)]
+pub struct EventRateLimit {
+    /// Maximum sustained rate of new events per second
+    pub max_rate: f64,
+    /// Window the rate is averaged over, in seconds
+    pub window: u64,
+    /// Time a peer stays greylisted for, in seconds
+    pub greylist_time: u64,
+}
+
+impl Default for EventRateLimit {
+    fn default() -> Self {
+        Self { max_rate: 5.0, window: 10, greylist_time: 300 }
+    }
+}
+
+impl EventRateLimit {
+    /// Check if receiving `count` new events within the window
+    /// exceeds the allowed rate.
+    pub fn exceeded(&self, count: usize) -> bool {
+        count as f64 > self.max_rate * self.window as f64
+    }
+}
+
+/// Per-topic signature requirements applications can enforce
+/// on events getting inserted into the DAG.
+#[derive(Default)]
+pub struct SignaturePolicy {
+    /// Application defined event topic classifier
+    classifier: Option<TopicFn>,
+    /// Topics requiring signed events, along with an optional
+    /// set of authors allowed to publish in them
+    topics: HashMap<String, Option<Vec<PublicKey>>>,
+}
+
+impl SignaturePolicy {
+    /// Set the event topic classifier.
+    pub fn set_classifier(&mut self, classifier: TopicFn) {
+        self.classifier = Some(classifier);
+    }
+
+    /// Require signed events for provided topic. If `authors` is set,
+    /// only events signed by one of them are accepted.
+    pub fn require(&mut self, topic: &str, authors: Option<Vec<PublicKey>>) {
+        self.topics.insert(topic.to_string(), authors);
+    }
+
+    /// Stop requiring signed events for provided topic.
+    pub fn release(&mut self, topic: &str) {
+        self.topics.remove(topic);
+    }
+
+    /// Check if provided [`Event`] satisfies the signature requirements
+    /// of its topic, returning the violated topic on failure.
+    /// Note: This does *NOT* verify the signature itself, which
+    /// is handled by the event validation.
+    pub fn check(&self, event: &Event) -> std::result::Result<(), String> {
+        let Some(classifier) = &self.classifier else { return Ok(()) };
+        let Some(topic) = classifier(event) else { return Ok(()) };
+        let Some(authors) = self.topics.get(&topic) else { return Ok(()) };
+
+        let Some(author) = event.author() else { return Err(topic) };
+        if let Some(authors) = authors {
+            if !authors.contains(author) {
+                return Err(topic)
+            }
+        }
+
+        Ok(())
+    }
+}
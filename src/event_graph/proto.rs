@@ -26,9 +26,9 @@ use std::{
 
 use darkfi_serial::{async_trait, SerialDecodable, SerialEncodable};
 use log::{debug, error, trace, warn};
-use smol::Executor;
+use smol::{channel::Receiver, future::FutureExt, lock::RwLock, Executor};
 
-use super::{Event, EventGraphPtr, NULL_ID};
+use super::{bloom::BloomFilter, Event, EventGraphPtr, NULL_ID};
 use crate::{impl_p2p_message, net::*, system::msleep, util::time::NanoTimestamp, Error, Result};
 
 /// Malicious behaviour threshold. If the threshold is reached, we will
@@ -49,6 +49,42 @@ const RATELIMIT_SAMPLE_IDX: usize = 10;
 /// Sleep for this amount of time when `count == RATE_LIMIT_SAMPLE_IDX`.
 const RATELIMIT_SAMPLE_SLEEP: usize = 1000;
 
+/// Maximum number of event IDs we reply with in a single `BloomRep`
+pub const BLOOM_REP_MAX_IDS: usize = 10_000;
+/// Rolling length of the `BloomReq` window
+const BLOOM_WINDOW_EXPIRY_TIME: NanoTimestamp = NanoTimestamp::from_secs(60);
+/// Limit of `BloomReq` messages per window. Each one costs us a full
+/// DAG scan, while an honest peer only sends one per sync.
+const BLOOM_WINDOW_MAXSIZE: usize = 3;
+
+/// Events with content up to this size are considered interactive
+/// (e.g. chat lines), bigger ones are relayed as bulk traffic.
+pub const INTERACTIVE_EVENT_MAX_SIZE: usize = 1024;
+
+/// Traffic class of a relayed event. Each class is queued separately,
+/// and interactive events are relayed ahead of bulk ones, so live chat
+/// isn't stuck behind a peer backfilling history. Both classes share
+/// the channel's relay rate-limit window.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum RelayClass {
+    /// Small live events
+    Interactive,
+    /// Big events, or events that arrived while backfilling their parents
+    Bulk,
+}
+
+impl RelayClass {
+    /// Classify an event by its content size, and by whether we had to
+    /// fetch its parents from the peer before inserting it.
+    pub fn of(event: &Event, backfilled: bool) -> Self {
+        if backfilled || event.content.len() > INTERACTIVE_EVENT_MAX_SIZE {
+            return Self::Bulk
+        }
+
+        Self::Interactive
+    }
+}
+
 struct MovingWindow {
     times: VecDeque<NanoTimestamp>,
     expiry_time: NanoTimestamp,
@@ -97,15 +133,26 @@ pub struct ProtocolEventGraph {
     tip_req_sub: MessageSubscription<TipReq>,
     /// `MessageSubscriber` for `TipRep`
     _tip_rep_sub: MessageSubscription<TipRep>,
+    /// `MessageSubscriber` for `BloomReq`
+    bloom_req_sub: MessageSubscription<BloomReq>,
+    /// `MessageSubscriber` for `BloomRep`
+    _bloom_rep_sub: MessageSubscription<BloomRep>,
+    /// Event IDs we replied with in our last `BloomRep`, which the
+    /// peer is expected to request from us
+    bloom_ids: RwLock<HashSet<blake3::Hash>>,
     /// Peer malicious message count
     malicious_count: AtomicUsize,
     /// P2P jobs manager pointer
     jobsman: ProtocolJobsManagerPtr,
     /// To apply the rate-limit, we don't broadcast directly but instead send into the
-    /// sending queue.
-    broadcaster_push: smol::channel::Sender<EventPut>,
-    /// Receive send requests and rate-limit broadcasting them.
-    broadcaster_pull: smol::channel::Receiver<EventPut>,
+    /// sending queue of the event's [`RelayClass`].
+    interactive_push: smol::channel::Sender<EventPut>,
+    /// Receive interactive send requests and rate-limit broadcasting them.
+    interactive_pull: smol::channel::Receiver<EventPut>,
+    /// Sending queue for bulk events
+    bulk_push: smol::channel::Sender<EventPut>,
+    /// Receive bulk send requests and rate-limit broadcasting them.
+    bulk_pull: smol::channel::Receiver<EventPut>,
 }
 
 /// A P2P message representing publishing an event on the network
@@ -133,6 +180,18 @@ impl_p2p_message!(TipReq, "EventGraph::TipReq");
 pub struct TipRep(pub BTreeMap<u64, HashSet<blake3::Hash>>);
 impl_p2p_message!(TipRep, "EventGraph::TipRep");
 
+/// A P2P message carrying a bloom filter of the event IDs the sender
+/// already has, asking the peer for the IDs it is missing.
+#[derive(Clone, SerialEncodable, SerialDecodable)]
+pub struct BloomReq(pub BloomFilter);
+impl_p2p_message!(BloomReq, "EventGraph::BloomReq");
+
+/// A P2P message representing a reply to `BloomReq`, containing the
+/// event IDs that were not found in the requester's bloom filter.
+#[derive(Clone, SerialEncodable, SerialDecodable)]
+pub struct BloomRep(pub Vec<blake3::Hash>);
+impl_p2p_message!(BloomRep, "EventGraph::BloomRep");
+
 #[async_trait]
 impl ProtocolBase for ProtocolEventGraph {
     async fn start(self: Arc<Self>, ex: Arc<Executor<'_>>) -> Result<()> {
@@ -140,6 +199,7 @@ impl ProtocolBase for ProtocolEventGraph {
         self.jobsman.clone().spawn(self.clone().handle_event_put(), ex.clone()).await;
         self.jobsman.clone().spawn(self.clone().handle_event_req(), ex.clone()).await;
         self.jobsman.clone().spawn(self.clone().handle_tip_req(), ex.clone()).await;
+        self.jobsman.clone().spawn(self.clone().handle_bloom_req(), ex.clone()).await;
         self.jobsman.clone().spawn(self.clone().broadcast_rate_limiter(), ex.clone()).await;
         Ok(())
     }
@@ -157,14 +217,19 @@ impl ProtocolEventGraph {
         msg_subsystem.add_dispatch::<EventRep>().await;
         msg_subsystem.add_dispatch::<TipReq>().await;
         msg_subsystem.add_dispatch::<TipRep>().await;
+        msg_subsystem.add_dispatch::<BloomReq>().await;
+        msg_subsystem.add_dispatch::<BloomRep>().await;
 
         let ev_put_sub = channel.subscribe_msg::<EventPut>().await?;
         let ev_req_sub = channel.subscribe_msg::<EventReq>().await?;
         let ev_rep_sub = channel.subscribe_msg::<EventRep>().await?;
         let tip_req_sub = channel.subscribe_msg::<TipReq>().await?;
         let _tip_rep_sub = channel.subscribe_msg::<TipRep>().await?;
+        let bloom_req_sub = channel.subscribe_msg::<BloomReq>().await?;
+        let _bloom_rep_sub = channel.subscribe_msg::<BloomRep>().await?;
 
-        let (broadcaster_push, broadcaster_pull) = smol::channel::unbounded();
+        let (interactive_push, interactive_pull) = smol::channel::unbounded();
+        let (bulk_push, bulk_pull) = smol::channel::unbounded();
 
         Ok(Arc::new(Self {
             channel: channel.clone(),
@@ -174,10 +239,15 @@ impl ProtocolEventGraph {
             ev_rep_sub,
             tip_req_sub,
             _tip_rep_sub,
+            bloom_req_sub,
+            _bloom_rep_sub,
+            bloom_ids: RwLock::new(HashSet::new()),
             malicious_count: AtomicUsize::new(0),
             jobsman: ProtocolJobsManager::new("ProtocolEventGraph", channel.clone()),
-            broadcaster_push,
-            broadcaster_pull,
+            interactive_push,
+            interactive_pull,
+            bulk_push,
+            bulk_pull,
         }))
     }
 
@@ -207,6 +277,8 @@ impl ProtocolEventGraph {
     async fn handle_event_put(self: Arc<Self>) -> Result<()> {
         // Rolling window of event timestamps on this channel
         let mut bantimes = MovingWindow::new(WINDOW_EXPIRY_TIME);
+        // Rolling window of event timestamps used for the rate limit
+        let mut ratetimes = MovingWindow::new(WINDOW_EXPIRY_TIME);
 
         loop {
             let event = match self.ev_put_sub.receive().await {
@@ -246,6 +318,24 @@ impl ProtocolEventGraph {
                 return Err(Error::MaliciousFlood)
             }
 
+            // Apply the configured rate limit. Peers exceeding it get
+            // greylisted, and their events dropped until it expires.
+            if let Some(rate_limit) = *self.event_graph.rate_limit.read().await {
+                ratetimes.expiry_time = NanoTimestamp::from_secs(rate_limit.window as u128);
+                ratetimes.ticktock();
+                if rate_limit.exceeded(ratetimes.count()) {
+                    self.event_graph.greylist_peer(self.channel.address()).await;
+                }
+
+                if self.event_graph.greylisted_peers().await.contains(self.channel.address()) {
+                    debug!(
+                        target: "event_graph::protocol::handle_event_put()",
+                        "Dropping event {} from greylisted peer {}", event_id, self.channel.address(),
+                    );
+                    continue
+                }
+            }
+
             // We received an event. Check if we already have it in our DAG.
             // Check event is not older that current genesis event timestamp.
             // Also check if we have the event's parents. In the case we do
@@ -298,13 +388,14 @@ impl ProtocolEventGraph {
             // If we have missing parents, then we have to attempt to
             // fetch them from this peer. Do this recursively until we
             // find all of them.
-            if !missing_parents.is_empty() {
-                // We track the received events mapped by their layer.
-                // If/when we get all of them, we need to insert them in order so
-                // the DAG state stays correct and unreferenced tips represent the
-                // actual thing they should. If we insert them out of order, then
-                // we might have wrong unreferenced tips.
-                let mut received_events: BTreeMap<u64, Vec<Event>> = BTreeMap::new();
+            // We track the received events mapped by their layer.
+            // If/when we get all of them, we need to insert them in order so
+            // the DAG state stays correct and unreferenced tips represent the
+            // actual thing they should. If we insert them out of order, then
+            // we might have wrong unreferenced tips.
+            let mut received_events: BTreeMap<u64, Vec<Event>> = BTreeMap::new();
+            let backfilled = !missing_parents.is_empty();
+            if backfilled {
                 let mut received_events_hashes = HashSet::new();
 
                 debug!(
@@ -392,34 +483,41 @@ impl ProtocolEventGraph {
                         }
                     }
                 } // <-- while !missing_parents.is_empty()
-
-                // At this point we should've got all the events.
-                // We should add them to the DAG.
-                let mut events = vec![];
-                for (_, tips) in received_events {
-                    for tip in tips {
-                        events.push(tip);
-                    }
-                }
-                if self.event_graph.dag_insert(&events).await.is_err() {
-                    self.clone().increase_malicious_count().await?;
-                    continue
-                }
             } // <-- !missing_parents.is_empty()
 
             // If we're here, we have all the parents, and we can now
-            // perform a full validation and add the actual event to
-            // the DAG.
+            // perform a full validation and add them along with the
+            // actual event to the DAG, parents first.
             debug!(
                 target: "event_graph::protocol::handle_event_put()",
                 "Got all parents necessary for insertion",
             );
-            if self.event_graph.dag_insert(&[event.clone()]).await.is_err() {
-                self.clone().increase_malicious_count().await?;
+            let mut events: Vec<Event> = received_events.into_values().flatten().collect();
+            events.push(event.clone());
+            let inserted = match self.event_graph.dag_insert(&events).await {
+                Ok(v) => v,
+                Err(_) => {
+                    self.clone().increase_malicious_count().await?;
+                    continue
+                }
+            };
+
+            // Events rejected by our event filter are dropped quietly,
+            // since the filter depends on our local configuration, so
+            // the peer is not at fault for relaying them.
+            if !inserted.contains(&event_id) {
+                debug!(
+                    target: "event_graph::protocol::handle_event_put()",
+                    "Event {} was filtered, not relaying it", event_id,
+                );
                 continue
             }
 
-            self.broadcaster_push.send(EventPut(event)).await.expect("push broadcaster closed");
+            let broadcaster_push = match RelayClass::of(&event, backfilled) {
+                RelayClass::Interactive => &self.interactive_push,
+                RelayClass::Bulk => &self.bulk_push,
+            };
+            broadcaster_push.send(EventPut(event)).await.expect("push broadcaster closed");
         }
     }
 
@@ -458,7 +556,9 @@ impl ProtocolEventGraph {
             // reading our db and steal our bandwidth.
             let mut events = vec![];
             for event_id in event_ids.iter() {
-                if !self.event_graph.broadcasted_ids.read().await.contains(event_id) {
+                if !self.event_graph.broadcasted_ids.read().await.contains(event_id) &&
+                    !self.bloom_ids.read().await.contains(event_id)
+                {
                     let malicious_count = self.malicious_count.fetch_add(1, SeqCst);
                     if malicious_count + 1 == MALICIOUS_THRESHOLD {
                         error!(
@@ -558,6 +658,73 @@ impl ProtocolEventGraph {
         }
     }
 
+    /// Protocol function handling `BloomReq`.
+    /// This is triggered when a syncing peer sends us a bloom filter of
+    /// the events it has. We reply with the IDs of the events in our DAG
+    /// that are not in the filter, so the peer only has to request what
+    /// it is actually missing.
+    async fn handle_bloom_req(self: Arc<Self>) -> Result<()> {
+        // Rolling window of bloom requests on this channel
+        let mut bloomtimes = MovingWindow::new(BLOOM_WINDOW_EXPIRY_TIME);
+
+        loop {
+            let filter = match self.bloom_req_sub.receive().await {
+                Ok(v) => v.0.clone(),
+                Err(_) => continue,
+            };
+            trace!(
+                target: "event_graph::protocol::handle_bloom_req()",
+                "Got BloomReq [{}]", self.channel.address(),
+            );
+
+            // Check if node has finished syncing its DAG
+            if !*self.event_graph.synced.read().await {
+                debug!(
+                    target: "event_graph::protocol::handle_bloom_req()",
+                    "DAG is still syncing, skipping..."
+                );
+                continue
+            }
+
+            // Make sure the filter isn't something we'd choke on
+            if !filter.is_valid() {
+                self.clone().increase_malicious_count().await?;
+                continue
+            }
+
+            // Every request costs us a full DAG scan, so peers asking
+            // more often than a sync needs are considered malicious.
+            bloomtimes.ticktock();
+            if bloomtimes.count() > BLOOM_WINDOW_MAXSIZE {
+                self.clone().increase_malicious_count().await?;
+                continue
+            }
+
+            // Find the events the peer doesn't have, and note them down
+            // so the follow-up `EventReq` from this peer is considered legit.
+            let mut missing = vec![];
+            for event_id in self.event_graph.dag.iter().keys() {
+                let event_id = event_id?;
+                let event_id = blake3::Hash::from_bytes((&event_id as &[u8]).try_into().unwrap());
+                if !filter.contains(&event_id) {
+                    missing.push(event_id);
+                    if missing.len() >= BLOOM_REP_MAX_IDS {
+                        break
+                    }
+                }
+            }
+
+            *self.bloom_ids.write().await = missing.iter().copied().collect();
+
+            debug!(
+                target: "event_graph::protocol::handle_bloom_req()",
+                "Peer {} is missing {} events", self.channel.address(), missing.len(),
+            );
+
+            self.channel.send(&BloomRep(missing)).await?;
+        }
+    }
+
     /// We need to rate limit message propagation so malicious nodes don't get us banned
     /// for flooding. We do that by aggregating messages here into a queue then apply
     /// rate limit logic before broadcasting.
@@ -583,11 +750,17 @@ impl ProtocolEventGraph {
     /// | 18    | 3000            |
     ///
     /// So we use the sample to calculate a straight line from RATELIMIT_MIN_COUNT.
+    ///
+    /// Both [`RelayClass`] queues go through the same window, so splitting
+    /// the traffic doesn't raise the channel's relay rate. The interactive
+    /// queue is just drained first.
     async fn broadcast_rate_limiter(self: Arc<Self>) -> Result<()> {
         let mut ratelimit = MovingWindow::new(RATELIMIT_EXPIRY_TIME);
 
         loop {
-            let event_put = self.broadcaster_pull.recv().await.expect("pull broadcaster closed");
+            let (event_put, class) = next_relay(&self.interactive_pull, &self.bulk_pull)
+                .await
+                .expect("pull broadcaster closed");
 
             ratelimit.ticktock();
             if ratelimit.count() > RATELIMIT_MIN_COUNT {
@@ -596,7 +769,7 @@ impl ProtocolEventGraph {
                         (RATELIMIT_SAMPLE_IDX - RATELIMIT_MIN_COUNT)) as u64;
                 debug!(
                     target: "event_graph::protocol::broadcast_rate_limiter",
-                    "Activated rate limit: sleeping {sleep_time} ms [count={}]",
+                    "Activated {class:?} rate limit: sleeping {sleep_time} ms [count={}]",
                     ratelimit.count()
                 );
                 // Apply the ratelimit
@@ -611,3 +784,89 @@ impl ProtocolEventGraph {
         }
     }
 }
+
+/// Wait for the next event to relay, taking queued interactive events
+/// before any bulk one.
+async fn next_relay<T>(
+    interactive: &Receiver<T>,
+    bulk: &Receiver<T>,
+) -> std::result::Result<(T, RelayClass), smol::channel::RecvError> {
+    if let Ok(v) = interactive.try_recv() {
+        return Ok((v, RelayClass::Interactive))
+    }
+
+    // `or` polls the interactive queue first, so it also wins when
+    // both become ready at once.
+    let interactive = async { interactive.recv().await.map(|v| (v, RelayClass::Interactive)) };
+    let bulk = async { bulk.recv().await.map(|v| (v, RelayClass::Bulk)) };
+    interactive.or(bulk).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::event_graph::{policy::EventRateLimit, N_EVENT_PARENTS};
+
+    #[test]
+    fn test_next_relay() {
+        smol::block_on(async {
+            let (interactive_push, interactive_pull) = smol::channel::unbounded();
+            let (bulk_push, bulk_pull) = smol::channel::unbounded();
+
+            // Queued interactive events go ahead of earlier bulk ones
+            bulk_push.send(1).await.unwrap();
+            bulk_push.send(2).await.unwrap();
+            interactive_push.send(3).await.unwrap();
+            interactive_push.send(4).await.unwrap();
+            let mut relayed = vec![];
+            for _ in 0..4 {
+                relayed.push(next_relay(&interactive_pull, &bulk_pull).await.unwrap());
+            }
+            assert_eq!(
+                relayed,
+                vec![
+                    (3, RelayClass::Interactive),
+                    (4, RelayClass::Interactive),
+                    (1, RelayClass::Bulk),
+                    (2, RelayClass::Bulk),
+                ]
+            );
+
+            // A waiting relayer picks up whichever queue gets an event
+            let task = smol::spawn(async move {
+                let first = next_relay(&interactive_pull, &bulk_pull).await.unwrap();
+                let second = next_relay(&interactive_pull, &bulk_pull).await.unwrap();
+                (first, second)
+            });
+            msleep(50).await;
+            bulk_push.send(5).await.unwrap();
+            msleep(50).await;
+            interactive_push.send(6).await.unwrap();
+            assert_eq!(task.await, ((5, RelayClass::Bulk), (6, RelayClass::Interactive)));
+        });
+    }
+
+    #[test]
+    fn test_relay_class() {
+        let mut event = Event {
+            timestamp: 0,
+            content: vec![0; INTERACTIVE_EVENT_MAX_SIZE],
+            parents: [NULL_ID; N_EVENT_PARENTS],
+            layer: 1,
+            author: None,
+        };
+        assert_eq!(RelayClass::of(&event, false), RelayClass::Interactive);
+        assert_eq!(RelayClass::of(&event, true), RelayClass::Bulk);
+
+        event.content.push(0);
+        assert_eq!(RelayClass::of(&event, false), RelayClass::Bulk);
+    }
+
+    #[test]
+    fn test_rate_limit_exceeded() {
+        let rate_limit = EventRateLimit { max_rate: 2.5, window: 10, greylist_time: 60 };
+        assert!(!rate_limit.exceeded(0));
+        assert!(!rate_limit.exceeded(25));
+        assert!(rate_limit.exceeded(26));
+    }
+}
@@ -0,0 +1,168 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2025 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use std::{
+    collections::{BTreeMap, HashSet},
+    fs::{File, OpenOptions},
+    io::{Read, Write},
+    path::Path,
+};
+
+use darkfi_serial::{
+    async_trait, deserialize_async, serialize_async, SerialDecodable, SerialEncodable,
+};
+use flate2::{read::GzDecoder, write::GzEncoder, Compression};
+use log::info;
+
+use super::{Event, EventGraph};
+use crate::{Error, Result};
+
+/// Current version of the DAG snapshot format
+pub const SNAPSHOT_VERSION: u8 = 1;
+
+/// Maximum decompressed size of an imported DAG snapshot, so a small
+/// crafted file can't exhaust our memory.
+pub const MAX_SNAPSHOT_SIZE: u64 = 256 * 1024 * 1024;
+
+/// A serialized dump of the DAG, used to bootstrap new nodes
+/// without fetching every event over P2P.
+#[derive(Debug, Clone, SerialEncodable, SerialDecodable)]
+pub struct DagSnapshot {
+    /// Snapshot format version
+    pub version: u8,
+    /// The genesis event of the DAG
+    pub genesis: Event,
+    /// All the other DAG events, sorted by their layer
+    pub events: Vec<Event>,
+    /// The unreferenced tips of the DAG, mapped by their layer
+    pub tips: BTreeMap<u64, HashSet<blake3::Hash>>,
+}
+
+impl EventGraph {
+    /// Create a snapshot of the current DAG state.
+    pub async fn snapshot(&self) -> Result<DagSnapshot> {
+        // Hold the tips lock so no events get inserted meanwhile
+        let tips = self.unreferenced_tips.read().await;
+        let genesis = self.current_genesis.read().await.clone();
+        let genesis_id = genesis.id();
+
+        let mut events = Vec::with_capacity(self.dag.len());
+        for iter_elem in self.dag.iter() {
+            let (id, val) = iter_elem?;
+            if id.as_ref() == genesis_id.as_bytes() {
+                continue
+            }
+            let event: Event = deserialize_async(&val).await?;
+            events.push(event);
+        }
+        events.sort_by_key(|e| e.layer);
+
+        Ok(DagSnapshot { version: SNAPSHOT_VERSION, genesis, events, tips: tips.clone() })
+    }
+
+    /// Export a compressed snapshot of the current DAG state into `path`.
+    /// Existing files are never overwritten.
+    /// Returns the number of exported events.
+    pub async fn export_snapshot(&self, path: &Path) -> Result<usize> {
+        let snapshot = self.snapshot().await?;
+        let bytes = serialize_async(&snapshot).await;
+
+        let path_ = path.to_path_buf();
+        smol::unblock(move || -> Result<()> {
+            let file = OpenOptions::new().write(true).create_new(true).open(path_)?;
+            let mut encoder = GzEncoder::new(file, Compression::default());
+            encoder.write_all(&bytes)?;
+            encoder.finish()?;
+            Ok(())
+        })
+        .await?;
+
+        info!(
+            target: "event_graph::export_snapshot()",
+            "[EVENTGRAPH] Exported {} events into {}", snapshot.events.len(), path.display(),
+        );
+        Ok(snapshot.events.len())
+    }
+
+    /// Import a compressed DAG snapshot from `path`, inserting the events
+    /// we are missing. Every event gets validated like the ones received
+    /// over P2P, so the snapshot source doesn't have to be trusted, and
+    /// only the delta since the snapshot has to be synced afterwards.
+    /// Returns the number of imported events.
+    pub async fn import_snapshot(&self, path: &Path) -> Result<usize> {
+        let path = path.to_path_buf();
+        let bytes = smol::unblock(move || read_snapshot(&path, MAX_SNAPSHOT_SIZE)).await?;
+        let snapshot: DagSnapshot = deserialize_async(&bytes).await?;
+        self.apply_snapshot(snapshot).await
+    }
+
+    /// Insert the events of provided snapshot we are missing into the DAG.
+    /// Returns the number of inserted events.
+    pub async fn apply_snapshot(&self, snapshot: DagSnapshot) -> Result<usize> {
+        if snapshot.version != SNAPSHOT_VERSION {
+            return Err(Error::DagSnapshotInvalid(format!(
+                "Unsupported version: {}",
+                snapshot.version
+            )))
+        }
+
+        // The snapshot must come from the same DAG rotation
+        if snapshot.genesis.id() != self.current_genesis.read().await.id() {
+            return Err(Error::DagSnapshotInvalid("Genesis event mismatch".to_string()))
+        }
+
+        // Its tips must be part of it
+        let mut snapshot_ids: HashSet<blake3::Hash> =
+            snapshot.events.iter().map(|e| e.id()).collect();
+        snapshot_ids.insert(snapshot.genesis.id());
+        for tip in snapshot.tips.values().flatten() {
+            if !snapshot_ids.contains(tip) {
+                return Err(Error::DagSnapshotInvalid(format!("Missing tip event: {tip}")))
+            }
+        }
+
+        // Sort the events by layer, so parents always precede their
+        // children and the batch can be validated in order.
+        let mut events = Vec::with_capacity(snapshot.events.len());
+        for event in snapshot.events {
+            if !self.dag.contains_key(event.id().as_bytes())? {
+                events.push(event);
+            }
+        }
+        events.sort_by_key(|e| e.layer);
+
+        let inserted = self.dag_insert(&events).await?.len();
+        info!(
+            target: "event_graph::apply_snapshot()",
+            "[EVENTGRAPH] Imported {} events from snapshot", inserted,
+        );
+        Ok(inserted)
+    }
+}
+
+/// Auxiliary function to decompress the snapshot file at `path`,
+/// rejecting it if it decompresses to more than `max_size` bytes.
+/// This is blocking, so async callers should use `smol::unblock`.
+pub(super) fn read_snapshot(path: &Path, max_size: u64) -> Result<Vec<u8>> {
+    let mut bytes = vec![];
+    GzDecoder::new(File::open(path)?).take(max_size + 1).read_to_end(&mut bytes)?;
+    if bytes.len() as u64 > max_size {
+        return Err(Error::DagSnapshotInvalid(format!("Exceeds {max_size} bytes")))
+    }
+    Ok(bytes)
+}
@@ -18,18 +18,28 @@
 
 // cargo +nightly test --release --features=event-graph --lib eventgraph_propagation -- --include-ignored
 
-use std::sync::Arc;
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Arc,
+};
 
+use darkfi_sdk::crypto::SecretKey;
 use log::{info, warn};
-use rand::{prelude::SliceRandom, rngs::ThreadRng};
+use rand::{
+    prelude::SliceRandom,
+    rngs::{OsRng, ThreadRng},
+};
 use sled_overlay::sled;
 use smol::{channel, future, Executor};
 use url::Url;
 
 use crate::{
     event_graph::{
+        policy::EventRateLimit,
         proto::{EventPut, ProtocolEventGraph},
-        Event, EventGraph,
+        snapshot::{read_snapshot, MAX_SNAPSHOT_SIZE},
+        util::generate_genesis,
+        Event, EventGraph, EventVersion, Tombstone,
     },
     net::{session::SESSION_DEFAULT, P2p, Settings},
     system::sleep,
@@ -452,3 +462,321 @@ async fn eventgraph_chaotic_propagation_real(ex: Arc<Executor<'static>>) {
         eg.p2p.clone().stop().await;
     }
 }
+
+#[test]
+fn eventgraph_snapshot() {
+    test_body!(eventgraph_snapshot_real);
+}
+
+async fn eventgraph_snapshot_real(ex: Arc<Executor<'static>>) {
+    let source = spawn_node(vec![], vec![], ex.clone()).await;
+    let target = spawn_node(vec![], vec![], ex.clone()).await;
+
+    // Create some events on the source node
+    for i in 0..5 {
+        let event = Event::new(vec![1, 2, 3, 4, i], &source).await;
+        source.dag_insert(&[event]).await.unwrap();
+    }
+
+    // Bootstrap the target node from the source snapshot
+    let path = std::env::temp_dir().join(format!("eventgraph_snapshot_{}", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+    assert_eq!(source.export_snapshot(&path).await.unwrap(), 5);
+    assert_eq!(target.import_snapshot(&path).await.unwrap(), 5);
+
+    // Existing files are not overwritten, and snapshots decompressing
+    // to more than the allowed size are rejected.
+    assert!(source.export_snapshot(&path).await.is_err());
+    let size = read_snapshot(&path, MAX_SNAPSHOT_SIZE).unwrap().len() as u64;
+    assert!(read_snapshot(&path, size).is_ok());
+    assert!(read_snapshot(&path, size - 1).is_err());
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(source.dag.len(), target.dag.len());
+    assert_eq!(*source.unreferenced_tips.read().await, *target.unreferenced_tips.read().await);
+
+    // Applying it again inserts nothing
+    let mut snapshot = source.snapshot().await.unwrap();
+    assert_eq!(target.apply_snapshot(snapshot.clone()).await.unwrap(), 0);
+
+    // Snapshots with tips outside of them are rejected
+    snapshot.tips.insert(6, HashSet::from([blake3::hash(b"unknown")]));
+    assert!(target.apply_snapshot(snapshot).await.is_err());
+}
+
+#[test]
+fn eventgraph_tombstones() {
+    test_body!(eventgraph_tombstones_real);
+}
+
+async fn eventgraph_tombstones_real(ex: Arc<Executor<'static>>) {
+    let eg = spawn_node(vec![], vec![], ex.clone()).await;
+    let secret = SecretKey::random(&mut OsRng);
+    let other = SecretKey::random(&mut OsRng);
+
+    // Insert a couple of signed events
+    let mut edited = Event::new(vec![1], &eg).await;
+    edited.sign(&secret);
+    let edited_id = eg.dag_insert(&[edited]).await.unwrap()[0];
+    let mut deleted = Event::new(vec![2], &eg).await;
+    deleted.sign(&secret);
+    let deleted_id = eg.dag_insert(&[deleted]).await.unwrap()[0];
+    let mut expiring = Event::new(vec![3], &eg).await;
+    expiring.sign(&secret);
+    let expiring_id = eg.dag_insert(&[expiring.clone()]).await.unwrap()[0];
+
+    // Tombstones by another author are ignored
+    let mut forged = Event::new(Tombstone::delete(edited_id).to_content(), &eg).await;
+    forged.sign(&other);
+    eg.dag_insert(&[forged]).await.unwrap();
+    assert!(!eg.is_tombstoned(&edited_id).await.unwrap());
+
+    // Edit, delete and schedule the expiry of the events
+    let mut edit = Event::new(Tombstone::edit(edited_id, vec![4]).to_content(), &eg).await;
+    edit.sign(&secret);
+    let edit_id = eg.dag_insert(&[edit]).await.unwrap()[0];
+    let mut delete = Event::new(Tombstone::delete(deleted_id).to_content(), &eg).await;
+    delete.sign(&secret);
+    let delete_id = eg.dag_insert(&[delete]).await.unwrap()[0];
+    let expiry = expiring.timestamp + 30_000;
+    let mut expire = Event::new(Tombstone::expire(expiring_id, expiry).to_content(), &eg).await;
+    expire.sign(&secret);
+    let expire_id = eg.dag_insert(&[expire]).await.unwrap()[0];
+
+    assert_eq!(
+        eg.dag_resolve(&edited_id).await.unwrap(),
+        EventVersion::Visible { content: vec![4], edited_by: Some(edit_id) }
+    );
+    assert_eq!(eg.dag_resolve(&deleted_id).await.unwrap(), EventVersion::Deleted(delete_id));
+    assert!(!eg.is_tombstoned(&expiring_id).await.unwrap());
+    assert!(matches!(eg.dag_resolve(&edit_id).await.unwrap(), EventVersion::Tombstone(_)));
+
+    // Only the visible events remain, with their latest content
+    let visible = eg.order_visible_events().await.unwrap();
+    let contents: Vec<_> = visible.iter().map(|(_, e)| e.content.clone()).collect();
+    assert!(contents.contains(&vec![4]));
+    assert!(contents.contains(&vec![3]));
+    assert!(!contents.contains(&vec![1]));
+    assert!(!contents.contains(&vec![2]));
+    assert!(visible.iter().all(|(_, e)| e.as_tombstone().is_none()));
+
+    // Expiry takes effect once the DAG time reaches it
+    let later = Event::with_timestamp(expiry, vec![5], &eg).await;
+    eg.dag_insert(&[later]).await.unwrap();
+    assert_eq!(eg.dag_time().await.unwrap(), expiry);
+    assert_eq!(eg.dag_resolve(&expiring_id).await.unwrap(), EventVersion::Deleted(expire_id));
+    let visible = eg.order_visible_events().await.unwrap();
+    assert!(visible.iter().all(|(_, e)| e.content != vec![3]));
+
+    // Pruning drops the tombstones along with their targets
+    eg.dag_prune(generate_genesis(1)).await.unwrap();
+    assert!(eg.tombstones.read().await.is_empty());
+    assert_eq!(eg.dag_resolve(&deleted_id).await.unwrap(), EventVersion::Missing);
+}
+
+#[test]
+#[ignore]
+fn eventgraph_tombstones_sync() {
+    test_body!(eventgraph_tombstones_sync_real);
+}
+
+async fn eventgraph_tombstones_sync_real(ex: Arc<Executor<'static>>) {
+    let source_addr = Url::parse("tcp://127.0.0.1:15200").unwrap();
+    let source = spawn_node(vec![source_addr.clone()], vec![], ex.clone()).await;
+    let secret = SecretKey::random(&mut OsRng);
+
+    // Create a kept and a deleted event on the source node
+    let mut kept = Event::new(vec![1], &source).await;
+    kept.sign(&secret);
+    let kept_id = source.dag_insert(&[kept]).await.unwrap()[0];
+    let mut deleted = Event::new(vec![2], &source).await;
+    deleted.sign(&secret);
+    let deleted_id = source.dag_insert(&[deleted]).await.unwrap()[0];
+    let mut delete = Event::new(Tombstone::delete(deleted_id).to_content(), &source).await;
+    delete.sign(&secret);
+    let delete_id = source.dag_insert(&[delete]).await.unwrap()[0];
+
+    // Sync a fresh node from it
+    let target = spawn_node(
+        vec![Url::parse("tcp://127.0.0.1:15201").unwrap()],
+        vec![source_addr],
+        ex.clone(),
+    )
+    .await;
+    let event_sub = target.event_pub.clone().subscribe().await;
+    source.p2p.clone().start().await.unwrap();
+    target.p2p.clone().start().await.unwrap();
+    info!("Waiting 5s until the nodes connect");
+    sleep(5).await;
+    target.dag_sync().await.unwrap();
+
+    // The deleted event is stored, since the tombstone references it,
+    // but it is neither notified nor visible.
+    assert_eq!(target.dag.len(), source.dag.len());
+    let mut notified = HashSet::new();
+    while let Some(event) = event_sub.try_receive() {
+        notified.insert(event.id());
+    }
+    assert!(notified.contains(&kept_id));
+    assert!(notified.contains(&delete_id));
+    assert!(!notified.contains(&deleted_id));
+    assert_eq!(target.dag_resolve(&deleted_id).await.unwrap(), EventVersion::Deleted(delete_id));
+    let visible = target.order_visible_events().await.unwrap();
+    assert!(visible.iter().all(|(id, _)| id != &deleted_id));
+
+    source.p2p.clone().stop().await;
+    target.p2p.clone().stop().await;
+}
+
+#[test]
+#[ignore]
+fn eventgraph_rate_limit() {
+    test_body!(eventgraph_rate_limit_real);
+}
+
+async fn eventgraph_rate_limit_real(ex: Arc<Executor<'static>>) {
+    // Line topology: flooder <- relay <- leaf
+    let flooder_addr = Url::parse("tcp://127.0.0.1:15300").unwrap();
+    let relay_addr = Url::parse("tcp://127.0.0.1:15301").unwrap();
+    let flooder = spawn_node(vec![flooder_addr.clone()], vec![], ex.clone()).await;
+    let relay = spawn_node(vec![relay_addr.clone()], vec![flooder_addr.clone()], ex.clone()).await;
+    let leaf = spawn_node(
+        vec![Url::parse("tcp://127.0.0.1:15302").unwrap()],
+        vec![relay_addr],
+        ex.clone(),
+    )
+    .await;
+
+    // The relay accepts a single new event per window from each peer
+    relay
+        .set_rate_limit(Some(EventRateLimit { max_rate: 0.1, window: 10, greylist_time: 60 }))
+        .await;
+
+    flooder.p2p.clone().start().await.unwrap();
+    relay.p2p.clone().start().await.unwrap();
+    leaf.p2p.clone().start().await.unwrap();
+    info!("Waiting 5s until the nodes connect");
+    sleep(5).await;
+
+    // =========================================================
+    // 1. Flood the relay, which greylists the flooder and drops
+    //    everything past the first event
+    // =========================================================
+    let mut flood_ids = vec![];
+    for i in 0..3 {
+        let event = Event::new(vec![i], &flooder).await;
+        flood_ids.push(flooder.dag_insert(&[event.clone()]).await.unwrap()[0]);
+        flooder.p2p.broadcast(&EventPut(event)).await;
+    }
+    info!("Waiting 5s for event propagation");
+    sleep(5).await;
+
+    assert!(relay.greylisted_peers().await.contains(&flooder_addr));
+    assert!(relay.dag.contains_key(flood_ids[0].as_bytes()).unwrap());
+    assert!(!relay.dag.contains_key(flood_ids[1].as_bytes()).unwrap());
+    assert!(!relay.dag.contains_key(flood_ids[2].as_bytes()).unwrap());
+    // The accepted event was relayed onwards
+    assert!(leaf.dag.contains_key(flood_ids[0].as_bytes()).unwrap());
+
+    // ===================================================
+    // 2. Events from other peers still get relayed to the
+    //    greylisted peer
+    // ===================================================
+    let event = Event::new(vec![3], &leaf).await;
+    let event_id = leaf.dag_insert(&[event.clone()]).await.unwrap()[0];
+    leaf.p2p.broadcast(&EventPut(event)).await;
+    info!("Waiting 5s for event propagation");
+    sleep(5).await;
+
+    assert!(relay.dag.contains_key(event_id.as_bytes()).unwrap());
+    assert!(flooder.dag.contains_key(event_id.as_bytes()).unwrap());
+
+    flooder.p2p.clone().stop().await;
+    relay.p2p.clone().stop().await;
+    leaf.p2p.clone().stop().await;
+}
+
+#[test]
+fn eventgraph_bloom_sync() {
+    test_body!(eventgraph_bloom_sync_real);
+}
+
+async fn eventgraph_bloom_sync_real(ex: Arc<Executor<'static>>) {
+    let source_addr = Url::parse("tcp://127.0.0.1:15400").unwrap();
+    let source = spawn_node(vec![source_addr.clone()], vec![], ex.clone()).await;
+
+    // Create a chain of events on the source node
+    let mut event_ids = vec![];
+    for i in 0..10 {
+        let event = Event::new(vec![i], &source).await;
+        event_ids.push(source.dag_insert(&[event]).await.unwrap()[0]);
+    }
+
+    // Sync a fresh node from it
+    let target = spawn_node(
+        vec![Url::parse("tcp://127.0.0.1:15401").unwrap()],
+        vec![source_addr],
+        ex.clone(),
+    )
+    .await;
+    *target.synced.write().await = false;
+    source.p2p.clone().start().await.unwrap();
+    target.p2p.clone().start().await.unwrap();
+    info!("Waiting 5s until the nodes connect");
+    sleep(5).await;
+    target.dag_sync().await.unwrap();
+
+    assert!(*target.synced.read().await);
+    assert_eq!(target.dag.len(), source.dag.len());
+    for event_id in event_ids.iter() {
+        assert!(target.dag.contains_key(event_id.as_bytes()).unwrap());
+    }
+    assert_eq!(*target.unreferenced_tips.read().await, *source.unreferenced_tips.read().await);
+
+    // A second sync has nothing left to fetch
+    target.dag_sync().await.unwrap();
+    assert_eq!(target.dag.len(), source.dag.len());
+
+    source.p2p.clone().stop().await;
+    target.p2p.clone().stop().await;
+}
+
+#[test]
+fn eventgraph_sync_ancestors() {
+    smol::block_on(async {
+        let ex = Arc::new(Executor::new());
+        let source = spawn_node(vec![], vec![], ex.clone()).await;
+        let target = spawn_node(vec![], vec![], ex.clone()).await;
+
+        // A chain of events the target doesn't have
+        let mut events = vec![];
+        for i in 0..3 {
+            let event = Event::new(vec![i], &source).await;
+            source.dag_insert(&[event.clone()]).await.unwrap();
+            events.push(event);
+        }
+        let tips: HashSet<_> = [events[2].id()].into_iter().collect();
+
+        // An event not descending into the tips
+        let mut unrelated = Event::new(vec![3], &source).await;
+        unrelated.parents[0] = blake3::hash(b"unrelated");
+
+        let mut candidates: HashMap<_, _> = events.iter().map(|e| (e.id(), e.clone())).collect();
+        candidates.insert(unrelated.id(), unrelated.clone());
+
+        // Only the ancestors of the tips are reached
+        let (reachable, unresolved) = target.dag_sync_ancestors(&tips, &candidates);
+        assert_eq!(reachable, events.iter().map(|e| e.id()).collect());
+        assert!(unresolved.is_empty());
+
+        // Missing links are reported as unresolved
+        candidates.remove(&events[1].id());
+        let (reachable, unresolved) = target.dag_sync_ancestors(&tips, &candidates);
+        assert_eq!(reachable, [events[2].id()].into_iter().collect());
+        assert_eq!(unresolved, [events[1].id()].into_iter().collect());
+
+        // Events we already have end the walk
+        let (reachable, unresolved) = source.dag_sync_ancestors(&tips, &candidates);
+        assert!(reachable.is_empty());
+        assert!(unresolved.is_empty());
+    });
+}
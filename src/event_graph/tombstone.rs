@@ -0,0 +1,237 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2025 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+use darkfi_serial::{
+    async_trait, deserialize, deserialize_async, serialize, SerialDecodable, SerialEncodable,
+};
+use log::debug;
+
+use super::{Event, EventGraph};
+use crate::Result;
+
+/// Content prefix marking an event as a tombstone
+pub const TOMBSTONE_MAGIC: &[u8] = &[0x54, 0x4f, 0x4d, 0x42, 0x53, 0x54, 0x4f, 0x4e, 0x45];
+
+/// A tombstone marks an earlier event as deleted or edited.
+/// It is carried as the content of a regular signed [`Event`], so the
+/// event format and IDs stay unchanged, and it only applies when both
+/// the tombstone and its target are signed by the same author.
+#[derive(Debug, Clone, PartialEq, SerialEncodable, SerialDecodable)]
+pub struct Tombstone {
+    /// ID of the event this tombstone applies to
+    pub target: blake3::Hash,
+    /// Replacement content for edits, `None` deletes the target
+    pub replacement: Option<Vec<u8>>,
+    /// Timestamp in millis from which the tombstone applies.
+    /// Zero means immediately, otherwise the target expires then.
+    pub effective: u64,
+}
+
+impl Tombstone {
+    /// Create a tombstone deleting the target event.
+    pub fn delete(target: blake3::Hash) -> Self {
+        Self { target, replacement: None, effective: 0 }
+    }
+
+    /// Create a tombstone replacing the target event content.
+    pub fn edit(target: blake3::Hash, content: Vec<u8>) -> Self {
+        Self { target, replacement: Some(content), effective: 0 }
+    }
+
+    /// Create a tombstone deleting the target event once provided
+    /// timestamp, in millis, has passed.
+    pub fn expire(target: blake3::Hash, timestamp: u64) -> Self {
+        Self { target, replacement: None, effective: timestamp }
+    }
+
+    /// Encode the tombstone into event content.
+    pub fn to_content(&self) -> Vec<u8> {
+        let mut content = TOMBSTONE_MAGIC.to_vec();
+        content.extend_from_slice(&serialize(self));
+        content
+    }
+
+    /// Decode a tombstone from event content, if it is one.
+    pub fn from_content(content: &[u8]) -> Option<Self> {
+        let bytes = content.strip_prefix(TOMBSTONE_MAGIC)?;
+        deserialize(bytes).ok()
+    }
+}
+
+/// The visible version of a DAG event, after applying its tombstones
+#[derive(Debug, Clone, PartialEq)]
+pub enum EventVersion {
+    /// The event is not in the DAG
+    Missing,
+    /// The event is a tombstone itself
+    Tombstone(Tombstone),
+    /// The event was deleted by the tombstone with given ID
+    Deleted(blake3::Hash),
+    /// The event is visible with its latest content, along with
+    /// the ID of the tombstone that last edited it, if any
+    Visible { content: Vec<u8>, edited_by: Option<blake3::Hash> },
+}
+
+impl EventGraph {
+    /// Scan the DAG for tombstones, mapping target event IDs to the
+    /// IDs of the tombstones referencing them.
+    pub(super) async fn find_tombstones(
+        &self,
+    ) -> Result<HashMap<blake3::Hash, HashSet<blake3::Hash>>> {
+        let mut map: HashMap<blake3::Hash, HashSet<blake3::Hash>> = HashMap::new();
+        for iter_elem in self.dag.iter() {
+            let (_, event) = iter_elem?;
+            let event: Event = deserialize_async(&event).await?;
+            if let Some(tombstone) = event.as_tombstone() {
+                map.entry(tombstone.target).or_default().insert(event.id());
+            }
+        }
+
+        Ok(map)
+    }
+
+    /// Return the DAG time, in millis, which is the latest timestamp of
+    /// the unreferenced tips. Tombstone expiry is resolved against it
+    /// instead of the local wall clock, so every node holding the same
+    /// DAG resolves its events the same way.
+    pub async fn dag_time(&self) -> Result<u64> {
+        let unreferenced_tips = self.unreferenced_tips.read().await;
+        self.tips_time(&unreferenced_tips).await
+    }
+
+    /// Return the latest timestamp of the provided DAG tips, in millis.
+    pub(super) async fn tips_time(
+        &self,
+        tips: &BTreeMap<u64, HashSet<blake3::Hash>>,
+    ) -> Result<u64> {
+        let mut time = 0;
+        for tip in tips.values().flatten() {
+            if let Some(event) = self.dag_get(tip).await? {
+                time = time.max(event.timestamp);
+            }
+        }
+
+        Ok(time)
+    }
+
+    /// Check if the event with given ID has been deleted or edited
+    /// by an applicable tombstone.
+    pub async fn is_tombstoned(&self, event_id: &blake3::Hash) -> Result<bool> {
+        Ok(match self.dag_resolve(event_id).await? {
+            EventVersion::Deleted(_) => true,
+            EventVersion::Visible { edited_by, .. } => edited_by.is_some(),
+            EventVersion::Missing | EventVersion::Tombstone(_) => false,
+        })
+    }
+
+    /// Resolve the latest visible version of the event with given ID.
+    pub async fn dag_resolve(&self, event_id: &blake3::Hash) -> Result<EventVersion> {
+        let Some(event) = self.dag_get(event_id).await? else { return Ok(EventVersion::Missing) };
+        self.resolve_event(event).await
+    }
+
+    /// Resolve the latest visible version of provided DAG event.
+    pub(super) async fn resolve_event(&self, event: Event) -> Result<EventVersion> {
+        let dag_time = self.dag_time().await?;
+        self.resolve_event_at(event, dag_time).await
+    }
+
+    /// Resolve the latest visible version of provided DAG event, with
+    /// the tombstones that took effect by the given DAG time.
+    pub(super) async fn resolve_event_at(
+        &self,
+        event: Event,
+        dag_time: u64,
+    ) -> Result<EventVersion> {
+        if let Some(tombstone) = event.as_tombstone() {
+            return Ok(EventVersion::Tombstone(tombstone))
+        }
+
+        let event_id = event.id();
+        let Some(tombstone_ids) = self.tombstones.read().await.get(&event_id).cloned() else {
+            return Ok(EventVersion::Visible { content: event.content, edited_by: None })
+        };
+
+        // Find the latest applicable tombstone. Tombstones are ordered
+        // by the time they take effect, with ties broken by their ID.
+        let mut latest: Option<(u64, blake3::Hash, Tombstone)> = None;
+        for tombstone_id in tombstone_ids {
+            let Some(tombstone_event) = self.dag_get(&tombstone_id).await? else { continue };
+            let Some(tombstone) = tombstone_event.as_tombstone() else { continue };
+
+            // Only the original author can tombstone their events
+            if event.author().is_none() || tombstone_event.author() != event.author() {
+                debug!(
+                    target: "event_graph::resolve_event()",
+                    "Ignoring unauthorized tombstone {} of event {}", tombstone_id, event_id,
+                );
+                continue
+            }
+
+            if tombstone_event.timestamp < event.timestamp {
+                continue
+            }
+
+            let effective = tombstone.effective.max(tombstone_event.timestamp);
+            if effective > dag_time {
+                continue
+            }
+
+            let newer = match &latest {
+                Some((l_effective, l_id, _)) => {
+                    (effective, tombstone_id.as_bytes()) > (*l_effective, l_id.as_bytes())
+                }
+                None => true,
+            };
+            if newer {
+                latest = Some((effective, tombstone_id, tombstone));
+            }
+        }
+
+        Ok(match latest {
+            Some((_, tombstone_id, tombstone)) => match tombstone.replacement {
+                Some(content) => EventVersion::Visible { content, edited_by: Some(tombstone_id) },
+                None => EventVersion::Deleted(tombstone_id),
+            },
+            None => EventVersion::Visible { content: event.content, edited_by: None },
+        })
+    }
+
+    /// Perform a topological sort of the DAG, keeping only the visible
+    /// events. Tombstones and deleted events are skipped, and edited
+    /// events carry their latest content. Since editing changes the
+    /// content, each event is returned along with its original ID.
+    pub async fn order_visible_events(&self) -> Result<Vec<(blake3::Hash, Event)>> {
+        let dag_time = self.dag_time().await?;
+        let mut visible = vec![];
+        for mut event in self.order_events().await {
+            let event_id = event.id();
+            match self.resolve_event_at(event.clone(), dag_time).await? {
+                EventVersion::Visible { content, .. } => {
+                    event.content = content;
+                    visible.push((event_id, event));
+                }
+                EventVersion::Missing | EventVersion::Tombstone(_) | EventVersion::Deleted(_) => {}
+            }
+        }
+
+        Ok(visible)
+    }
+}
@@ -130,6 +130,7 @@ pub fn generate_genesis(days_rotation: u64) -> Event {
         content: GENESIS_CONTENTS.to_vec(),
         parents: [NULL_ID; N_EVENT_PARENTS],
         layer: 0,
+        author: None,
     }
 }
 
@@ -0,0 +1,310 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2025 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Fetch progress checkpoints.
+//!
+//! [`Geode::get`] re-hashes every locally available chunk of a file,
+//! which for multi-hundred-GB files can take hours on spinning disks.
+//! While a file is being fetched, a bitmap of its verified chunks is
+//! periodically written under `checkpoints`. When the fetch is resumed,
+//! e.g. after a crash, chunks covered by the checkpoint are trusted
+//! without re-hashing, unless their files were modified after the
+//! checkpoint was written.
+//!
+//! Like the rest of Geode's state, checkpoints are plain files rather
+//! than database entries: a checkpoint is a single small bitmap that is
+//! replaced atomically with a rename, and keeping it next to the chunks
+//! it describes means it's dropped along with them on garbage collection.
+
+use std::{
+    collections::HashSet,
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+
+use log::debug;
+use smol::fs;
+
+use super::{ChunkedFile, Geode};
+use crate::Result;
+
+/// Verified chunks bitmap of a file being fetched
+pub(super) struct Checkpoint {
+    /// Bit `i` is set if the chunk at index `i` was verified
+    bitmap: Vec<u8>,
+    /// Time the checkpoint was written
+    written: SystemTime,
+}
+
+impl Checkpoint {
+    /// Build the bitmap of the chunks available in provided [`ChunkedFile`]
+    fn bitmap(chunked_file: &ChunkedFile) -> Vec<u8> {
+        let mut bitmap = vec![0u8; chunked_file.0.len().div_ceil(8)];
+        for (i, (_, path)) in chunked_file.iter().enumerate() {
+            if path.is_some() {
+                bitmap[i / 8] |= 1 << (i % 8);
+            }
+        }
+        bitmap
+    }
+
+    /// Check if the chunk at index `i`, stored at `chunk_path`, was verified
+    /// and has not been modified since the checkpoint was written.
+    pub(super) async fn covers(&self, i: usize, chunk_path: &Path) -> bool {
+        if self.bitmap[i / 8] & (1 << (i % 8)) == 0 {
+            return false
+        }
+
+        let Ok(metadata) = fs::metadata(chunk_path).await else { return false };
+        let Ok(modified) = metadata.modified() else { return false };
+        modified <= self.written
+    }
+}
+
+impl Geode {
+    /// Return the path to the fetch checkpoint of provided file.
+    pub(super) fn checkpoint_path(&self, file_hash: &blake3::Hash) -> PathBuf {
+        let mut checkpoint_path = self.checkpoints_path.clone();
+        checkpoint_path.push(file_hash.to_hex().as_str());
+        checkpoint_path
+    }
+
+    /// Read the fetch checkpoint of a file with `chunks` chunks, if any.
+    /// Checkpoints not matching the chunk count are ignored.
+    pub(super) async fn read_checkpoint(
+        &self,
+        file_hash: &blake3::Hash,
+        chunks: usize,
+    ) -> Option<Checkpoint> {
+        let checkpoint_path = self.checkpoint_path(file_hash);
+        let bitmap = fs::read(&checkpoint_path).await.ok()?;
+        if bitmap.len() != chunks.div_ceil(8) {
+            debug!(
+                target: "geode::read_checkpoint()",
+                "[Geode] Ignoring mismatched checkpoint of file {}", file_hash,
+            );
+            return None
+        }
+
+        let written = fs::metadata(&checkpoint_path).await.ok()?.modified().ok()?;
+        Some(Checkpoint { bitmap, written })
+    }
+
+    /// Fetch file metadata from Geode like [`Geode::get`] does, but trust
+    /// the chunks covered by the file's fetch checkpoint instead of
+    /// consistency checking them.
+    pub async fn get_checkpointed(&self, file_hash: &blake3::Hash) -> Result<ChunkedFile> {
+        debug!(
+            target: "geode::get_checkpointed()",
+            "[Geode] Getting file chunks for {} using its checkpoint", file_hash,
+        );
+        self.load_chunks(file_hash, true).await
+    }
+
+    /// Mark the provided chunks, which were just inserted, as available in
+    /// the [`ChunkedFile`] and record its verified chunks as the file's
+    /// fetch checkpoint.
+    pub async fn checkpoint(
+        &self,
+        file_hash: &blake3::Hash,
+        chunked_file: &mut ChunkedFile,
+        inserted: &[blake3::Hash],
+    ) -> Result<()> {
+        let inserted: HashSet<_> = inserted.iter().collect();
+        for (chunk_hash, chunk_path) in chunked_file.0.iter_mut() {
+            if chunk_path.is_none() && inserted.contains(chunk_hash) {
+                let mut c_path = self.chunks_path.clone();
+                c_path.push(chunk_hash.to_hex().as_str());
+                *chunk_path = Some(c_path);
+            }
+        }
+
+        // Write to a temporary file first, so a crash can't leave
+        // a partially written checkpoint behind.
+        let checkpoint_path = self.checkpoint_path(file_hash);
+        let tmp_path = checkpoint_path.with_extension("tmp");
+        fs::write(&tmp_path, Checkpoint::bitmap(chunked_file)).await?;
+        fs::rename(&tmp_path, &checkpoint_path).await?;
+
+        debug!(
+            target: "geode::checkpoint()",
+            "[Geode] Checkpointed {}/{} chunks of file {}",
+            chunked_file.iter().filter(|(_, p)| p.is_some()).count(),
+            chunked_file.0.len(),
+            file_hash,
+        );
+        Ok(())
+    }
+
+    /// Remove the fetch checkpoint of a file, once it's no longer needed.
+    pub async fn remove_checkpoint(&self, file_hash: &blake3::Hash) -> Result<()> {
+        match fs::remove_file(self.checkpoint_path(file_hash)).await {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e.into()),
+            _ => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{fs::File, io::Cursor, time::Duration};
+
+    use crate::geode::MAX_CHUNK_SIZE;
+
+    /// Instantiate a Geode in a fresh directory
+    async fn geode(name: &str) -> Geode {
+        let base_path = std::env::temp_dir().join(format!(
+            "darkfi_test_checkpoint_{}_{}",
+            name,
+            std::process::id()
+        ));
+        let _ = fs::remove_dir_all(&base_path).await;
+        Geode::new(&base_path).await.unwrap()
+    }
+
+    /// Set the modification time of the file at `path`
+    fn set_modified(path: &Path, time: SystemTime) {
+        File::options().write(true).open(path).unwrap().set_modified(time).unwrap();
+    }
+
+    #[test]
+    fn checkpoint_bitmap_roundtrip() {
+        smol::block_on(async {
+            let geode = geode("roundtrip").await;
+            let chunks: Vec<Vec<u8>> = (0..10u8).map(|i| vec![i; 16]).collect();
+            let chunk_hashes: Vec<_> = chunks.iter().map(|c| geode.hash_chunk(c)).collect();
+            let file_hash = blake3::hash(b"checkpointed file");
+            let mut chunked_file = ChunkedFile::new(&chunk_hashes);
+
+            let mut inserted = vec![];
+            for i in [0, 3, 9] {
+                inserted.push(geode.insert_chunk(&chunks[i]).await.unwrap());
+            }
+            geode.checkpoint(&file_hash, &mut chunked_file, &inserted).await.unwrap();
+
+            // Only the inserted chunks are marked as available
+            let available: Vec<usize> = chunked_file
+                .iter()
+                .enumerate()
+                .filter(|(_, (_, p))| p.is_some())
+                .map(|(i, _)| i)
+                .collect();
+            assert_eq!(available, vec![0, 3, 9]);
+
+            // The bitmap read back matches the file it was written for
+            let checkpoint = geode.read_checkpoint(&file_hash, 10).await.unwrap();
+            assert_eq!(checkpoint.bitmap, Checkpoint::bitmap(&chunked_file));
+            assert_eq!(checkpoint.bitmap, vec![0b0000_1001, 0b0000_0010]);
+
+            // Checkpoints are removed once no longer needed
+            geode.remove_checkpoint(&file_hash).await.unwrap();
+            assert!(geode.read_checkpoint(&file_hash, 10).await.is_none());
+            geode.remove_checkpoint(&file_hash).await.unwrap();
+
+            fs::remove_dir_all(geode.checkpoints_path.parent().unwrap()).await.unwrap();
+        })
+    }
+
+    #[test]
+    fn checkpoint_covers() {
+        smol::block_on(async {
+            let geode = geode("covers").await;
+            let chunks: Vec<Vec<u8>> = (0..3u8).map(|i| vec![i; 16]).collect();
+            let chunk_hashes: Vec<_> = chunks.iter().map(|c| geode.hash_chunk(c)).collect();
+            let file_hash = blake3::hash(b"covered file");
+            let mut chunked_file = ChunkedFile::new(&chunk_hashes);
+
+            let mut inserted = vec![];
+            for chunk in &chunks {
+                inserted.push(geode.insert_chunk(chunk).await.unwrap());
+            }
+            geode.checkpoint(&file_hash, &mut chunked_file, &inserted[..2]).await.unwrap();
+
+            let paths: Vec<PathBuf> =
+                chunk_hashes.iter().map(|h| geode.chunks_path.join(h.to_hex().as_str())).collect();
+            let checkpoint = geode.read_checkpoint(&file_hash, 3).await.unwrap();
+
+            // Chunks verified before the checkpoint are covered
+            assert!(checkpoint.covers(0, &paths[0]).await);
+            assert!(checkpoint.covers(1, &paths[1]).await);
+
+            // Chunks missing from the bitmap aren't, even if present
+            assert!(!checkpoint.covers(2, &paths[2]).await);
+
+            // Chunks modified after the checkpoint was written aren't
+            set_modified(&paths[1], checkpoint.written + Duration::from_secs(60));
+            assert!(!checkpoint.covers(1, &paths[1]).await);
+
+            // Neither are chunks that no longer exist
+            fs::remove_file(&paths[0]).await.unwrap();
+            assert!(!checkpoint.covers(0, &paths[0]).await);
+
+            fs::remove_dir_all(geode.checkpoints_path.parent().unwrap()).await.unwrap();
+        })
+    }
+
+    #[test]
+    fn checkpoint_mismatch() {
+        smol::block_on(async {
+            let geode = geode("mismatch").await;
+            let chunk_hashes: Vec<_> = (0..10u8).map(|i| geode.hash_chunk(&[i])).collect();
+            let file_hash = blake3::hash(b"mismatched file");
+            let mut chunked_file = ChunkedFile::new(&chunk_hashes);
+            geode.checkpoint(&file_hash, &mut chunked_file, &[]).await.unwrap();
+
+            // A checkpoint written for another chunk count is ignored
+            assert!(geode.read_checkpoint(&file_hash, 10).await.is_some());
+            assert!(geode.read_checkpoint(&file_hash, 20).await.is_none());
+            assert!(geode.read_checkpoint(&file_hash, 1).await.is_none());
+
+            // As is a missing one
+            let other_hash = blake3::hash(b"other file");
+            assert!(geode.read_checkpoint(&other_hash, 10).await.is_none());
+
+            fs::remove_dir_all(geode.checkpoints_path.parent().unwrap()).await.unwrap();
+        })
+    }
+
+    #[test]
+    fn checkpoint_skips_rehash() {
+        smol::block_on(async {
+            let geode = geode("rehash").await;
+            let data = vec![42u8; MAX_CHUNK_SIZE + 1];
+            let (file_hash, chunk_hashes) = geode.insert(Cursor::new(&data)).await.unwrap();
+            let mut chunked_file = geode.get(&file_hash).await.unwrap();
+            geode.checkpoint(&file_hash, &mut chunked_file, &chunk_hashes).await.unwrap();
+            let written = geode.read_checkpoint(&file_hash, 2).await.unwrap().written;
+
+            // Corrupt a chunk without touching its modification time, so
+            // only the consistency check can notice it
+            let path = geode.chunks_path.join(chunk_hashes[1].to_hex().as_str());
+            fs::write(&path, b"corrupted").await.unwrap();
+            set_modified(&path, written - Duration::from_secs(60));
+
+            assert!(!geode.get(&file_hash).await.unwrap().is_complete());
+            assert!(geode.get_checkpointed(&file_hash).await.unwrap().is_complete());
+
+            // Without a checkpoint, every chunk is consistency checked
+            geode.remove_checkpoint(&file_hash).await.unwrap();
+            assert!(!geode.get_checkpointed(&file_hash).await.unwrap().is_complete());
+
+            fs::remove_dir_all(geode.checkpoints_path.parent().unwrap()).await.unwrap();
+        })
+    }
+}
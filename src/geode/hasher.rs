@@ -0,0 +1,252 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2025 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Hash backends used to derive chunk and file IDs.
+//!
+//! By default Geode uses plain BLAKE3. Private swarms can use keyed BLAKE3,
+//! so IDs can't be derived without knowing the key, and SHA256 allows
+//! interop with existing content-addressed systems. All backends produce
+//! 32-byte digests, which are carried around as [`blake3::Hash`] values
+//! regardless of the backend that produced them.
+//!
+//! The identifier of the algorithm used is stored in the file metadata,
+//! and files hashed with a different algorithm than the one Geode was
+//! instantiated with are rejected on verification. Metadata of plain
+//! BLAKE3 files carries no identifier, so it stays readable by older
+//! versions, which would otherwise garbage collect it.
+//!
+//! Peers don't exchange the algorithm, so every node of a swarm has to
+//! be configured with the same backend. Chunks hashed otherwise simply
+//! fail verification.
+
+use std::{fmt, str::FromStr, sync::Arc};
+
+use sha2::Digest;
+
+use crate::{Error, Result};
+
+/// Prefix of the file metadata line holding the hash algorithm identifier
+pub const ALGORITHM_PREFIX: &str = "algo:";
+
+/// Supported hash algorithms
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum HashAlgorithm {
+    /// Plain BLAKE3
+    Blake3,
+    /// BLAKE3 in keyed mode
+    Blake3Keyed,
+    /// SHA256
+    Sha256,
+}
+
+impl HashAlgorithm {
+    /// Return the identifier of the algorithm, as stored in file metadata.
+    pub fn id(&self) -> &'static str {
+        match self {
+            Self::Blake3 => "blake3",
+            Self::Blake3Keyed => "blake3-keyed",
+            Self::Sha256 => "sha256",
+        }
+    }
+
+    /// Instantiate the backend implementing this algorithm. Keyed BLAKE3
+    /// requires a key, which the other algorithms don't take.
+    pub fn backend(&self, key: Option<[u8; 32]>) -> Result<Arc<dyn HashBackend>> {
+        match (self, key) {
+            (Self::Blake3, None) => Ok(Arc::new(Blake3Backend)),
+            (Self::Blake3Keyed, Some(key)) => Ok(Arc::new(KeyedBlake3Backend::new(key))),
+            (Self::Sha256, None) => Ok(Arc::new(Sha256Backend)),
+            (Self::Blake3Keyed, None) => Err(Error::ParseFailed("Keyed BLAKE3 requires a key")),
+            (_, Some(_)) => Err(Error::ParseFailed("Only keyed BLAKE3 takes a key")),
+        }
+    }
+}
+
+impl fmt::Display for HashAlgorithm {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.id())
+    }
+}
+
+impl FromStr for HashAlgorithm {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "blake3" => Ok(Self::Blake3),
+            "blake3-keyed" => Ok(Self::Blake3Keyed),
+            "sha256" => Ok(Self::Sha256),
+            _ => Err(Error::GeodeHashAlgorithmMismatch(format!("Unknown algorithm: {s}"))),
+        }
+    }
+}
+
+/// Incremental hasher state, used when hashing streamed data.
+pub trait IncrementalHasher: Send {
+    /// Feed more data into the hasher.
+    fn update(&mut self, data: &[u8]);
+
+    /// Return the digest of all the data fed so far.
+    fn finalize(&self) -> blake3::Hash;
+}
+
+impl IncrementalHasher for blake3::Hasher {
+    fn update(&mut self, data: &[u8]) {
+        blake3::Hasher::update(self, data);
+    }
+
+    fn finalize(&self) -> blake3::Hash {
+        blake3::Hasher::finalize(self)
+    }
+}
+
+impl IncrementalHasher for sha2::Sha256 {
+    fn update(&mut self, data: &[u8]) {
+        Digest::update(self, data);
+    }
+
+    fn finalize(&self) -> blake3::Hash {
+        let digest: [u8; 32] = Digest::finalize(self.clone()).into();
+        blake3::Hash::from(digest)
+    }
+}
+
+/// Hash backend used by Geode to derive chunk and file IDs.
+pub trait HashBackend: Send + Sync {
+    /// Return the algorithm this backend implements.
+    fn algorithm(&self) -> HashAlgorithm;
+
+    /// Hash the provided data in one go.
+    fn hash(&self, data: &[u8]) -> blake3::Hash;
+
+    /// Create a new incremental hasher.
+    fn hasher(&self) -> Box<dyn IncrementalHasher>;
+}
+
+/// Plain BLAKE3 backend
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Blake3Backend;
+
+impl HashBackend for Blake3Backend {
+    fn algorithm(&self) -> HashAlgorithm {
+        HashAlgorithm::Blake3
+    }
+
+    fn hash(&self, data: &[u8]) -> blake3::Hash {
+        blake3::hash(data)
+    }
+
+    fn hasher(&self) -> Box<dyn IncrementalHasher> {
+        Box::new(blake3::Hasher::new())
+    }
+}
+
+/// Keyed BLAKE3 backend, for private swarms sharing a secret key
+#[derive(Clone)]
+pub struct KeyedBlake3Backend {
+    key: [u8; 32],
+}
+
+impl KeyedBlake3Backend {
+    /// Create a new keyed BLAKE3 backend using provided key.
+    pub fn new(key: [u8; 32]) -> Self {
+        Self { key }
+    }
+}
+
+impl HashBackend for KeyedBlake3Backend {
+    fn algorithm(&self) -> HashAlgorithm {
+        HashAlgorithm::Blake3Keyed
+    }
+
+    fn hash(&self, data: &[u8]) -> blake3::Hash {
+        blake3::keyed_hash(&self.key, data)
+    }
+
+    fn hasher(&self) -> Box<dyn IncrementalHasher> {
+        Box::new(blake3::Hasher::new_keyed(&self.key))
+    }
+}
+
+/// SHA256 backend, for interop with other content-addressed systems
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Sha256Backend;
+
+impl HashBackend for Sha256Backend {
+    fn algorithm(&self) -> HashAlgorithm {
+        HashAlgorithm::Sha256
+    }
+
+    fn hash(&self, data: &[u8]) -> blake3::Hash {
+        let digest: [u8; 32] = sha2::Sha256::digest(data).into();
+        blake3::Hash::from(digest)
+    }
+
+    fn hasher(&self) -> Box<dyn IncrementalHasher> {
+        Box::new(sha2::Sha256::new())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hash_algorithm_ids() {
+        for algorithm in [HashAlgorithm::Blake3, HashAlgorithm::Blake3Keyed, HashAlgorithm::Sha256]
+        {
+            assert_eq!(algorithm.id().parse::<HashAlgorithm>().unwrap(), algorithm);
+        }
+        assert!("md5".parse::<HashAlgorithm>().is_err());
+    }
+
+    #[test]
+    fn hash_backends() {
+        let data = b"darkfi geode";
+        let key = [7u8; 32];
+
+        // Only keyed BLAKE3 takes a key
+        assert!(HashAlgorithm::Blake3.backend(Some(key)).is_err());
+        assert!(HashAlgorithm::Sha256.backend(Some(key)).is_err());
+        assert!(HashAlgorithm::Blake3Keyed.backend(None).is_err());
+
+        let blake3 = HashAlgorithm::Blake3.backend(None).unwrap();
+        let keyed = HashAlgorithm::Blake3Keyed.backend(Some(key)).unwrap();
+        let other_key = HashAlgorithm::Blake3Keyed.backend(Some([8u8; 32])).unwrap();
+        let sha256 = HashAlgorithm::Sha256.backend(None).unwrap();
+        assert_eq!(blake3.algorithm(), HashAlgorithm::Blake3);
+        assert_eq!(keyed.algorithm(), HashAlgorithm::Blake3Keyed);
+        assert_eq!(sha256.algorithm(), HashAlgorithm::Sha256);
+
+        assert_eq!(blake3.hash(data), blake3::hash(data));
+        assert_eq!(keyed.hash(data), blake3::keyed_hash(&key, data));
+        assert_ne!(keyed.hash(data), other_key.hash(data));
+        assert_eq!(
+            sha256.hash(b"abc").to_hex().as_str(),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+
+        // Incremental hashing matches one-shot hashing
+        for backend in [blake3, keyed, sha256] {
+            let mut hasher = backend.hasher();
+            hasher.update(&data[..6]);
+            hasher.update(&data[6..]);
+            assert_eq!(hasher.finalize(), backend.hash(data));
+        }
+    }
+}
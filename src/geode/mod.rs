@@ -51,12 +51,22 @@
 //! hashes found above. The contents of the files in `/chunks` are arbitrary
 //! data, and by concatenating them we can retrieve the original file.
 //!
+//! The hashing algorithm is pluggable, see [`hasher`]. BLAKE3 is used by
+//! default. The first line of a file's metadata holds the identifier of
+//! the algorithm its hashes were derived with, e.g. `algo:blake3`.
+//! Metadata without it is assumed to be BLAKE3.
+//!
+//! Additionally, a `scrub` directory keeps the last time each file had its
+//! chunks re-verified by the background integrity scrubber, and a
+//! `checkpoints` directory keeps the verified chunks bitmap of files that
+//! are being fetched, so an interrupted fetch can be resumed cheaply.
+//!
 //! It is important to note that multiple files can use the same chunks.
 //! This is some kind of naive deduplication, so we actually don't consider
 //! chunks to be specific to a single file and therefore when we do garbage
 //! collection, we keep chunks and files independent of each other.
 
-use std::{collections::HashSet, path::PathBuf};
+use std::{collections::HashSet, path::PathBuf, sync::Arc};
 
 use futures::AsyncRead;
 use log::{debug, info, warn};
@@ -67,7 +77,21 @@ use smol::{
     stream::StreamExt,
 };
 
-use crate::{Error, Result};
+use crate::{
+    system::{Publisher, PublisherPtr},
+    Error, Result,
+};
+
+/// Background integrity scrubbing
+pub mod scrub;
+use scrub::ScrubEvent;
+
+/// Fetch progress checkpoints
+pub mod checkpoint;
+
+/// Pluggable chunk and file ID hashing
+pub mod hasher;
+use hasher::{Blake3Backend, HashAlgorithm, HashBackend, ALGORITHM_PREFIX};
 
 /// Defined maximum size of a stored chunk (256 KiB)
 pub const MAX_CHUNK_SIZE: usize = 262_144;
@@ -76,6 +100,10 @@ pub const MAX_CHUNK_SIZE: usize = 262_144;
 const FILES_PATH: &str = "files";
 /// Path prefix where file chunks are stored
 const CHUNKS_PATH: &str = "chunks";
+/// Path prefix where file scrub timestamps are stored
+const SCRUB_PATH: &str = "scrub";
+/// Path prefix where file fetch checkpoints are stored
+const CHECKPOINTS_PATH: &str = "checkpoints";
 
 /// `ChunkedFile` is a representation of a file we're trying to
 /// retrieve from `Geode`.
@@ -109,39 +137,168 @@ pub struct Geode {
     files_path: PathBuf,
     /// Path to the filesystem directory where file chunks are stored
     chunks_path: PathBuf,
+    /// Path to the filesystem directory where file scrub timestamps are stored
+    scrub_path: PathBuf,
+    /// Path to the filesystem directory where file fetch checkpoints are stored
+    checkpoints_path: PathBuf,
+    /// Publisher for scrubber corruption events
+    scrub_pub: PublisherPtr<ScrubEvent>,
+    /// Hash backend used to derive chunk and file IDs
+    hash_backend: Arc<dyn HashBackend>,
 }
 
 impl Geode {
-    /// Instantiate a new [`Geode`] object.
+    /// Instantiate a new [`Geode`] object using the default BLAKE3 hash backend.
     /// `base_path` defines the root directory where Geode will store its
     /// file metadata and chunks.
     pub async fn new(base_path: &PathBuf) -> Result<Self> {
+        Self::with_hash_backend(base_path, Arc::new(Blake3Backend)).await
+    }
+
+    /// Instantiate a new [`Geode`] object using provided hash backend.
+    /// Files hashed with a different algorithm will be rejected.
+    pub async fn with_hash_backend(
+        base_path: &PathBuf,
+        hash_backend: Arc<dyn HashBackend>,
+    ) -> Result<Self> {
         let mut files_path: PathBuf = base_path.into();
         let mut chunks_path: PathBuf = base_path.into();
+        let mut scrub_path: PathBuf = base_path.into();
+        let mut checkpoints_path: PathBuf = base_path.into();
         files_path.push(FILES_PATH);
         chunks_path.push(CHUNKS_PATH);
+        scrub_path.push(SCRUB_PATH);
+        checkpoints_path.push(CHECKPOINTS_PATH);
 
         // Create necessary directory structure if needed
         fs::create_dir_all(&files_path).await?;
         fs::create_dir_all(&chunks_path).await?;
+        fs::create_dir_all(&scrub_path).await?;
+        fs::create_dir_all(&checkpoints_path).await?;
+
+        Ok(Self {
+            files_path,
+            chunks_path,
+            scrub_path,
+            checkpoints_path,
+            scrub_pub: Publisher::new(),
+            hash_backend,
+        })
+    }
+
+    /// Return the hash algorithm used to derive chunk and file IDs.
+    pub fn hash_algorithm(&self) -> HashAlgorithm {
+        self.hash_backend.algorithm()
+    }
+
+    /// Hash a chunk with the configured hash backend, returning its chunk ID.
+    pub fn hash_chunk(&self, chunk: &[u8]) -> blake3::Hash {
+        self.hash_backend.hash(chunk)
+    }
+
+    /// Return the path to the filesystem directory where file chunks are stored.
+    pub fn chunks_path(&self) -> PathBuf {
+        self.chunks_path.clone()
+    }
+
+    /// Return the hashes of all the files whose metadata is stored in Geode.
+    pub async fn list_files(&self) -> Result<Vec<blake3::Hash>> {
+        let mut files = vec![];
+        let mut file_paths = fs::read_dir(&self.files_path).await?;
+        while let Some(file) = file_paths.next().await {
+            let Ok(entry) = file else { continue };
+            let path = entry.path();
+
+            // Skip if we're not a plain file
+            if !path.is_file() {
+                continue
+            }
+
+            // Make sure that the filename is a BLAKE3 hash
+            let file_name = match path.file_name().and_then(|n| n.to_str()) {
+                Some(v) => v,
+                None => continue,
+            };
+            let file_hash = match blake3::Hash::from_hex(file_name) {
+                Ok(v) => v,
+                Err(_) => continue,
+            };
+
+            files.push(file_hash);
+        }
+
+        Ok(files)
+    }
+
+    /// Return the total size of the chunks stored in Geode, in bytes.
+    pub async fn storage_size(&self) -> Result<u64> {
+        let mut size = 0;
+        let mut chunk_paths = fs::read_dir(&self.chunks_path).await?;
+        while let Some(chunk) = chunk_paths.next().await {
+            let Ok(entry) = chunk else { continue };
+            let Ok(metadata) = entry.metadata().await else { continue };
+            if metadata.is_file() {
+                size += metadata.len();
+            }
+        }
 
-        Ok(Self { files_path, chunks_path })
+        Ok(size)
     }
 
     /// Attempt to read chunk hashes from a given file path and return
-    /// a `Vec` containing the hashes in order.
-    async fn read_metadata(path: &PathBuf) -> Result<Vec<blake3::Hash>> {
+    /// the hash algorithm they were derived with, along with a `Vec`
+    /// containing the hashes in order.
+    async fn read_metadata(path: &PathBuf) -> Result<(HashAlgorithm, Vec<blake3::Hash>)> {
         debug!(target: "geode::read_metadata()", "Reading chunks from {:?}", path);
         let fd = File::open(path).await?;
+        let mut algorithm = HashAlgorithm::Blake3;
         let mut read_chunks = vec![];
         let mut lines = BufReader::new(fd).lines();
         while let Some(line) = lines.next().await {
             let line = line?;
+            if let Some(id) = line.strip_prefix(ALGORITHM_PREFIX) {
+                // The algorithm identifier can only be on the first line
+                if !read_chunks.is_empty() {
+                    return Err(Error::GeodeNeedsGc)
+                }
+                algorithm = id.parse()?;
+                continue
+            }
             let chunk_hash = blake3::Hash::from_hex(line)?;
             read_chunks.push(chunk_hash);
         }
 
-        Ok(read_chunks)
+        Ok((algorithm, read_chunks))
+    }
+
+    /// Write the file metadata to a given file path, tagged with our
+    /// hash algorithm identifier unless it's the default one, so older
+    /// versions can still read it. Always overwrites any existing file.
+    async fn write_metadata(&self, path: &PathBuf, chunk_hashes: &[blake3::Hash]) -> Result<()> {
+        let mut file_fd = File::create(path).await?;
+        if self.hash_algorithm() != HashAlgorithm::Blake3 {
+            file_fd
+                .write_all(format!("{}{}\n", ALGORITHM_PREFIX, self.hash_algorithm()).as_bytes())
+                .await?;
+        }
+        for ch in chunk_hashes {
+            file_fd.write_all(format!("{}\n", ch.to_hex().as_str()).as_bytes()).await?;
+        }
+
+        Ok(())
+    }
+
+    /// Ensure provided hash algorithm matches the one we use.
+    fn verify_algorithm(&self, algorithm: HashAlgorithm) -> Result<()> {
+        if algorithm != self.hash_algorithm() {
+            return Err(Error::GeodeHashAlgorithmMismatch(format!(
+                "expected {}, found {}",
+                self.hash_algorithm(),
+                algorithm
+            )))
+        }
+
+        Ok(())
     }
 
     /// Perform garbage collection over the filesystem hierarchy.
@@ -191,7 +348,7 @@ impl Geode {
             };
 
             let chunk_slice = &buf[..bytes_read];
-            let hashed_chunk = blake3::hash(chunk_slice);
+            let hashed_chunk = self.hash_backend.hash(chunk_slice);
 
             // If the hash doesn't match the filename, remove it.
             if chunk_hash != hashed_chunk {
@@ -247,6 +404,12 @@ impl Geode {
                     );
                 }
 
+                // Drop its scrub record and fetch checkpoint as well
+                let mut scrub_path = self.scrub_path.clone();
+                scrub_path.push(file_hash.to_hex().as_str());
+                let _ = fs::remove_file(scrub_path).await;
+                let _ = fs::remove_file(self.checkpoint_path(&file_hash)).await;
+
                 deleted_files.insert(file_hash);
                 continue
             }
@@ -265,7 +428,7 @@ impl Geode {
         mut stream: impl AsyncRead + Unpin,
     ) -> Result<(blake3::Hash, Vec<blake3::Hash>)> {
         info!(target: "geode::insert()", "[Geode] Inserting file...");
-        let mut file_hasher = blake3::Hasher::new();
+        let mut file_hasher = self.hash_backend.hasher();
         let mut chunk_hashes = vec![];
         let mut buf = [0u8; MAX_CHUNK_SIZE];
 
@@ -275,7 +438,7 @@ impl Geode {
             }
 
             let chunk_slice = &buf[..bytes_read];
-            let chunk_hash = blake3::hash(chunk_slice);
+            let chunk_hash = self.hash_backend.hash(chunk_slice);
             file_hasher.update(chunk_slice);
             chunk_hashes.push(chunk_hash);
 
@@ -291,7 +454,7 @@ impl Geode {
             let mut fs_buf = [0u8; MAX_CHUNK_SIZE];
             let fs_bytes_read = chunk_fd.read(&mut fs_buf).await?;
             let fs_chunk_slice = &fs_buf[..fs_bytes_read];
-            let fs_chunk_hash = blake3::hash(fs_chunk_slice);
+            let fs_chunk_hash = self.hash_backend.hash(fs_chunk_slice);
 
             if fs_chunk_hash != chunk_hash {
                 debug!(
@@ -320,10 +483,7 @@ impl Geode {
         file_path.push(file_hash.to_hex().as_str());
 
         // We always overwrite the metadata.
-        let mut file_fd = File::create(&file_path).await?;
-        for ch in &chunk_hashes {
-            file_fd.write(format!("{}\n", ch.to_hex().as_str()).as_bytes()).await?;
-        }
+        self.write_metadata(&file_path, &chunk_hashes).await?;
 
         Ok((file_hash, chunk_hashes))
     }
@@ -339,13 +499,7 @@ impl Geode {
 
         let mut file_path = self.files_path.clone();
         file_path.push(file_hash.to_hex().as_str());
-        let mut file_fd = File::create(&file_path).await?;
-
-        for ch in chunk_hashes {
-            file_fd.write(format!("{}\n", ch.to_hex().as_str()).as_bytes()).await?;
-        }
-
-        Ok(())
+        self.write_metadata(&file_path, chunk_hashes).await
     }
 
     /// Create and insert a single chunk into Geode given a stream.
@@ -358,7 +512,7 @@ impl Geode {
 
         let bytes_read = cursor.read(&mut chunk).await?;
         let chunk_slice = &chunk[..bytes_read];
-        let chunk_hash = blake3::hash(chunk_slice);
+        let chunk_hash = self.hash_backend.hash(chunk_slice);
 
         let mut chunk_path = self.chunks_path.clone();
         chunk_path.push(chunk_hash.to_hex().as_str());
@@ -373,12 +527,23 @@ impl Geode {
     /// the read failed in any way (could also be the file does not exist).
     pub async fn get(&self, file_hash: &blake3::Hash) -> Result<ChunkedFile> {
         info!(target: "geode::get()", "[Geode] Getting file chunks for {}...", file_hash);
+        self.load_chunks(file_hash, false).await
+    }
+
+    /// Read the file metadata and find which of its chunks are available
+    /// locally. Chunks are consistency checked, unless `use_checkpoint` is
+    /// set and they are covered by the file's fetch checkpoint.
+    async fn load_chunks(
+        &self,
+        file_hash: &blake3::Hash,
+        use_checkpoint: bool,
+    ) -> Result<ChunkedFile> {
         let mut file_path = self.files_path.clone();
         file_path.push(file_hash.to_hex().as_str());
 
         // Try to read the file metadata. If it's corrupt, return an error signalling
         // that garbage collection needs to run.
-        let chunk_hashes = match Self::read_metadata(&file_path).await {
+        let (algorithm, chunk_hashes) = match Self::read_metadata(&file_path).await {
             Ok(v) => v,
             Err(e) => {
                 return match e {
@@ -390,11 +555,18 @@ impl Geode {
             }
         };
 
+        // Chunks hashed with a different algorithm can't be verified
+        self.verify_algorithm(algorithm)?;
+
         let mut chunked_file = ChunkedFile::new(&chunk_hashes);
+        let checkpoint = match use_checkpoint {
+            true => self.read_checkpoint(file_hash, chunk_hashes.len()).await,
+            false => None,
+        };
 
         // Iterate over chunks and find which chunks we have available locally.
         let mut buf = [0u8; MAX_CHUNK_SIZE];
-        for (chunk_hash, chunk_path) in chunked_file.0.iter_mut() {
+        for (i, (chunk_hash, chunk_path)) in chunked_file.0.iter_mut().enumerate() {
             let mut c_path = self.chunks_path.clone();
             c_path.push(chunk_hash.to_hex().as_str());
 
@@ -403,11 +575,20 @@ impl Geode {
                 continue
             }
 
+            // Skip the consistency check of chunks verified before
+            // the checkpoint was written.
+            if let Some(checkpoint) = &checkpoint {
+                if checkpoint.covers(i, &c_path).await {
+                    *chunk_path = Some(c_path);
+                    continue
+                }
+            }
+
             // Perform chunk consistency check
             let mut chunk_fd = File::open(&c_path).await?;
             let bytes_read = chunk_fd.read(&mut buf).await?;
             let chunk_slice = &buf[..bytes_read];
-            let hashed_chunk = blake3::hash(chunk_slice);
+            let hashed_chunk = self.hash_backend.hash(chunk_slice);
             if &hashed_chunk != chunk_hash {
                 // The chunk is corrupted/inconsistent. Garbage collection should run.
                 buf = [0u8; MAX_CHUNK_SIZE];
@@ -438,7 +619,7 @@ impl Geode {
         let mut chunk_fd = File::open(&chunk_path).await?;
         let bytes_read = chunk_fd.read(&mut buf).await?;
         let chunk_slice = &buf[..bytes_read];
-        let hashed_chunk = blake3::hash(chunk_slice);
+        let hashed_chunk = self.hash_backend.hash(chunk_slice);
         if &hashed_chunk != chunk_hash {
             // The chunk is corrupted
             return Err(Error::GeodeNeedsGc)
@@ -447,3 +628,82 @@ impl Geode {
         Ok(chunk_path)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Instantiate a Geode with the given algorithm in a fresh directory
+    async fn geode(name: &str, algorithm: HashAlgorithm, key: Option<[u8; 32]>) -> Geode {
+        let base_path =
+            std::env::temp_dir().join(format!("darkfi_test_geode_{}_{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&base_path).await;
+        Geode::with_hash_backend(&base_path, algorithm.backend(key).unwrap()).await.unwrap()
+    }
+
+    #[test]
+    fn geode_default_metadata() {
+        smol::block_on(async {
+            let geode = geode("default_metadata", HashAlgorithm::Blake3, None).await;
+            let data = vec![42u8; MAX_CHUNK_SIZE + 1];
+            let (file_hash, chunk_hashes) = geode.insert(Cursor::new(&data)).await.unwrap();
+            assert_eq!(chunk_hashes.len(), 2);
+            assert_eq!(chunk_hashes[0], blake3::hash(&data[..MAX_CHUNK_SIZE]));
+            assert_eq!(file_hash, blake3::hash(&data));
+
+            // Metadata stays in the format older versions understand
+            let path = geode.files_path.join(file_hash.to_hex().as_str());
+            let metadata = fs::read_to_string(&path).await.unwrap();
+            let expected: String =
+                chunk_hashes.iter().map(|h| format!("{}\n", h.to_hex().as_str())).collect();
+            assert_eq!(metadata, expected);
+
+            let chunked_file = geode.get(&file_hash).await.unwrap();
+            assert!(chunked_file.is_complete());
+            assert_eq!(geode.garbage_collect().await.unwrap(), (HashSet::new(), HashSet::new()));
+
+            fs::remove_dir_all(geode.files_path.parent().unwrap()).await.unwrap();
+        })
+    }
+
+    #[test]
+    fn geode_hash_backends() {
+        smol::block_on(async {
+            let key = [7u8; 32];
+            let data = b"private swarm content".to_vec();
+
+            let keyed = geode("keyed", HashAlgorithm::Blake3Keyed, Some(key)).await;
+            let (file_hash, chunk_hashes) = keyed.insert(Cursor::new(&data)).await.unwrap();
+            assert_eq!(chunk_hashes, vec![blake3::keyed_hash(&key, &data)]);
+            assert_eq!(keyed.hash_chunk(&data), chunk_hashes[0]);
+            assert!(keyed.get(&file_hash).await.unwrap().is_complete());
+
+            // Non-default metadata is tagged with the algorithm
+            let path = keyed.files_path.join(file_hash.to_hex().as_str());
+            let metadata = fs::read_to_string(&path).await.unwrap();
+            assert!(metadata.starts_with(&format!("{ALGORITHM_PREFIX}blake3-keyed\n")));
+
+            // A Geode using another algorithm rejects the metadata, but
+            // doesn't garbage collect it
+            let base_path = keyed.files_path.parent().unwrap().to_path_buf();
+            let sha256 =
+                Geode::with_hash_backend(&base_path, HashAlgorithm::Sha256.backend(None).unwrap())
+                    .await
+                    .unwrap();
+            assert!(matches!(
+                sha256.get(&file_hash).await,
+                Err(Error::GeodeHashAlgorithmMismatch(_))
+            ));
+            let (deleted_files, _) = sha256.garbage_collect().await.unwrap();
+            assert!(deleted_files.is_empty());
+            assert!(path.exists());
+
+            // Chunks are verified with the configured backend
+            let chunk_hash = sha256.insert_chunk(&data).await.unwrap();
+            assert_eq!(chunk_hash, sha256.hash_chunk(&data));
+            assert!(sha256.get_chunk(&chunk_hash).await.is_ok());
+
+            fs::remove_dir_all(&base_path).await.unwrap();
+        })
+    }
+}
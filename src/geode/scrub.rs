@@ -0,0 +1,219 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2025 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Background integrity scrubbing of stored chunks.
+//!
+//! Bit-rot on disk happens silently, so corrupted chunks would only get
+//! caught once a peer requests them. The scrubber re-hashes the locally
+//! available chunks of stored files against their metadata, limited to
+//! a configurable rate so it doesn't compete with regular disk IO.
+//! The last scrub timestamp of each file is stored under `scrub`, so
+//! files get scrubbed in least recently scrubbed order across restarts.
+
+use std::time::{Duration, SystemTime};
+
+use darkfi_serial::{deserialize, serialize};
+use log::{debug, warn};
+use smol::{fs, fs::File, io::AsyncReadExt, Timer};
+
+use super::{Geode, MAX_CHUNK_SIZE};
+use crate::{system::Subscription, Error, Result};
+
+/// Corruption event emitted by the scrubber
+#[derive(Clone, Debug)]
+pub enum ScrubEvent {
+    /// A stored chunk of the file doesn't match its hash
+    ChunkCorrupted { file_hash: blake3::Hash, chunk_hash: blake3::Hash },
+    /// The file metadata is unreadable
+    MetadataCorrupted { file_hash: blake3::Hash },
+}
+
+/// Outcome of a single file scrub
+#[derive(Clone, Debug, Default)]
+pub struct ScrubReport {
+    /// Number of locally available chunks that got verified
+    pub chunks: usize,
+    /// Total amount of bytes re-hashed
+    pub bytes: u64,
+    /// Chunks found to be corrupted
+    pub corrupted: Vec<blake3::Hash>,
+}
+
+impl Geode {
+    /// Subscribe to the scrubber corruption events.
+    pub async fn subscribe_scrub(&self) -> Subscription<ScrubEvent> {
+        self.scrub_pub.clone().subscribe().await
+    }
+
+    /// Return the last time provided file got scrubbed.
+    pub async fn last_scrub(&self, file_hash: &blake3::Hash) -> Option<SystemTime> {
+        let mut scrub_path = self.scrub_path.clone();
+        scrub_path.push(file_hash.to_hex().as_str());
+        let contents = fs::read(&scrub_path).await.ok()?;
+        deserialize(&contents).ok()
+    }
+
+    /// Return the stored files along with their last scrub timestamp, ordered
+    /// so that never and least recently scrubbed files come first.
+    pub async fn scrub_queue(&self) -> Result<Vec<(blake3::Hash, Option<SystemTime>)>> {
+        let mut queue = vec![];
+        for file_hash in self.list_files().await? {
+            queue.push((file_hash, self.last_scrub(&file_hash).await));
+        }
+
+        queue.sort_by_key(|(_, last_scrub)| *last_scrub);
+        Ok(queue)
+    }
+
+    /// Scrub a stored file, re-hashing its locally available chunks against its
+    /// metadata, limited to `rate` bytes per second (unlimited if zero).
+    /// Corrupted chunks are reported and published to the scrub subscribers,
+    /// but not removed, so the caller can decide how to handle them.
+    /// The scrub timestamp of the file is recorded on completion.
+    pub async fn scrub(&self, file_hash: &blake3::Hash, rate: u64) -> Result<ScrubReport> {
+        debug!(target: "geode::scrub()", "[Geode] Scrubbing file {}", file_hash);
+        let mut file_path = self.files_path.clone();
+        file_path.push(file_hash.to_hex().as_str());
+
+        let (algorithm, chunk_hashes) = match Self::read_metadata(&file_path).await {
+            Ok(v) => v,
+            Err(Error::Io(std::io::ErrorKind::NotFound)) => return Err(Error::GeodeFileNotFound),
+            Err(_) => {
+                warn!(target: "geode::scrub()", "[Geode] File {} metadata is corrupted", file_hash);
+                self.scrub_pub
+                    .notify(ScrubEvent::MetadataCorrupted { file_hash: *file_hash })
+                    .await;
+                return Err(Error::GeodeNeedsGc)
+            }
+        };
+        self.verify_algorithm(algorithm)?;
+
+        let mut report = ScrubReport::default();
+        let mut buf = vec![0u8; MAX_CHUNK_SIZE];
+        for chunk_hash in chunk_hashes {
+            let mut chunk_path = self.chunks_path.clone();
+            chunk_path.push(chunk_hash.to_hex().as_str());
+
+            // Skip chunks we don't have locally
+            let Ok(mut chunk_fd) = File::open(&chunk_path).await else { continue };
+
+            // Perform chunk consistency check. Unreadable chunks
+            // are considered corrupted as well.
+            let (bytes_read, consistent) = match chunk_fd.read(&mut buf).await {
+                Ok(n) => (n, self.hash_backend.hash(&buf[..n]) == chunk_hash),
+                Err(_) => (0, false),
+            };
+            report.chunks += 1;
+            report.bytes += bytes_read as u64;
+
+            if !consistent {
+                warn!(
+                    target: "geode::scrub()",
+                    "[Geode] Chunk {} of file {} is corrupted", chunk_hash, file_hash,
+                );
+                report.corrupted.push(chunk_hash);
+                self.scrub_pub
+                    .notify(ScrubEvent::ChunkCorrupted { file_hash: *file_hash, chunk_hash })
+                    .await;
+            }
+
+            // Throttle ourselves to the configured rate
+            if rate > 0 {
+                Timer::after(Duration::from_secs_f64(bytes_read as f64 / rate as f64)).await;
+            }
+        }
+
+        // Record the scrub timestamp
+        let mut scrub_path = self.scrub_path.clone();
+        scrub_path.push(file_hash.to_hex().as_str());
+        fs::write(&scrub_path, serialize(&SystemTime::now())).await?;
+
+        Ok(report)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use smol::io::Cursor;
+
+    use super::*;
+
+    #[test]
+    fn geode_scrub_corruption() {
+        smol::block_on(async {
+            let base_path = std::env::temp_dir()
+                .join(format!("darkfi_test_geode_scrub_{}", std::process::id()));
+            let _ = fs::remove_dir_all(&base_path).await;
+            let geode = Geode::new(&base_path).await.unwrap();
+            let subscription = geode.subscribe_scrub().await;
+
+            let data: Vec<u8> = (0..MAX_CHUNK_SIZE * 2 + 1337).map(|i| (i % 251) as u8).collect();
+            let (file_hash, chunk_hashes) = geode.insert(Cursor::new(&data)).await.unwrap();
+            assert!(geode.last_scrub(&file_hash).await.is_none());
+
+            // A healthy file scrubs clean and gets its timestamp recorded
+            let report = geode.scrub(&file_hash, 0).await.unwrap();
+            assert_eq!(report.chunks, 3);
+            assert_eq!(report.bytes, data.len() as u64);
+            assert!(report.corrupted.is_empty());
+            assert!(geode.last_scrub(&file_hash).await.is_some());
+
+            // Flip a byte of the second chunk behind Geode's back
+            let chunk_path = geode.chunks_path().join(chunk_hashes[1].to_hex().as_str());
+            let mut chunk = fs::read(&chunk_path).await.unwrap();
+            chunk[42] ^= 0xff;
+            fs::write(&chunk_path, &chunk).await.unwrap();
+
+            let report = geode.scrub(&file_hash, 0).await.unwrap();
+            assert_eq!(report.chunks, 3);
+            assert_eq!(report.corrupted, vec![chunk_hashes[1]]);
+            match subscription.receive().await {
+                ScrubEvent::ChunkCorrupted { file_hash: f, chunk_hash } => {
+                    assert_eq!(f, file_hash);
+                    assert_eq!(chunk_hash, chunk_hashes[1]);
+                }
+                e => panic!("Unexpected scrub event: {e:?}"),
+            }
+
+            // Corrupted chunks are left for the caller to garbage collect,
+            // after which they are not scrubbed anymore
+            assert!(chunk_path.exists());
+            let (_, deleted_chunks) = geode.garbage_collect().await.unwrap();
+            assert_eq!(deleted_chunks, HashSet::from([chunk_hashes[1]]));
+            let report = geode.scrub(&file_hash, 0).await.unwrap();
+            assert_eq!(report.chunks, 2);
+            assert!(report.corrupted.is_empty());
+
+            // Unreadable metadata is reported as well
+            let file_path = geode.files_path.join(file_hash.to_hex().as_str());
+            fs::write(&file_path, b"not a hash\n").await.unwrap();
+            assert!(matches!(geode.scrub(&file_hash, 0).await, Err(Error::GeodeNeedsGc)));
+            assert!(matches!(
+                subscription.receive().await,
+                ScrubEvent::MetadataCorrupted { file_hash: f } if f == file_hash
+            ));
+
+            let missing = blake3::hash(b"missing");
+            assert!(matches!(geode.scrub(&missing, 0).await, Err(Error::GeodeFileNotFound)));
+
+            fs::remove_dir_all(&base_path).await.unwrap();
+        })
+    }
+}
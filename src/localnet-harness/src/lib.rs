@@ -0,0 +1,354 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2025 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! In-process localnet harness for cross-daemon integration tests.
+//!
+//! Spins up `darkfid` nodes on a localnet, optionally paired with a
+//! `minerd` daemon each, and `drk` wallets connected to them, so features
+//! spanning daemons like mining, sync and wallet scanning can be tested
+//! end-to-end. Nodes run with a fixed PoW difficulty, and tests should
+//! synchronize on chain events through the [`wait`] hooks instead of
+//! sleeping for arbitrary amounts of time. All daemons listen on
+//! OS-assigned ports, so tests can run in parallel.
+
+use std::{
+    net::TcpListener,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use darkfi::{
+    blockchain::{BlockInfo, CONTRACT_STATE_QUOTA},
+    net::Settings,
+    rpc::{
+        client::RpcClient,
+        jsonrpc::{JsonNotification, JsonRequest},
+        settings::RpcSettings,
+    },
+    system::{ExecutorPtr, Subscription},
+    validator::ValidatorConfig,
+    Error, Result,
+};
+use darkfi_contract_test_harness::vks;
+use darkfi_sdk::crypto::{Keypair, PublicKey};
+use darkfid::{task::consensus::ConsensusInitTaskConfig, Darkfid, DarkfidPtr};
+use drk::Drk;
+use log::info;
+use minerd::{solutions::SolutionLog, Minerd, MinerdPtr};
+use num_bigint::BigUint;
+use rand::rngs::OsRng;
+use sled_overlay::sled;
+use tinyjson::JsonValue;
+use url::Url;
+
+/// Chain event wait hooks
+pub mod wait;
+
+/// In-memory `drk` wallets
+pub mod wallet;
+
+/// Localnet configuration
+#[derive(Clone, Debug)]
+pub struct LocalnetConfig {
+    /// Number of `darkfid` nodes
+    pub nodes: usize,
+    /// Number of nodes, starting from the first one, paired with a `minerd`
+    pub miners: usize,
+    /// Number of threads each `minerd` mines with
+    pub miner_threads: usize,
+    /// Mining rewards recipient, a random one is used if unset
+    pub recipient: Option<PublicKey>,
+    /// PoW block production target, in seconds
+    pub pow_target: u32,
+    /// Optional fixed PoW difficulty
+    pub pow_fixed_difficulty: Option<BigUint>,
+    /// Confirmation threshold for the best fork
+    pub confirmation_threshold: usize,
+    /// Verify transaction fees
+    pub verify_fees: bool,
+    /// Time to wait for each daemon JSON-RPC server to start listening
+    pub startup_timeout: Duration,
+}
+
+impl Default for LocalnetConfig {
+    fn default() -> Self {
+        Self {
+            nodes: 2,
+            miners: 1,
+            miner_threads: 1,
+            recipient: None,
+            pow_target: 10,
+            pow_fixed_difficulty: Some(BigUint::from(1u8)),
+            confirmation_threshold: 1,
+            verify_fees: false,
+            startup_timeout: Duration::from_secs(10),
+        }
+    }
+}
+
+/// A localnet `darkfid` node, along with its `minerd`
+pub struct LocalnetNode {
+    /// The `darkfid` daemon
+    pub daemon: DarkfidPtr,
+    /// The paired `minerd` daemon, if the node mines
+    pub miner: Option<MinerdPtr>,
+    /// P2P inbound URL
+    pub p2p_url: Url,
+    /// JSON-RPC listen URL
+    pub rpc_url: Url,
+    /// JSON-RPC client used by the harness to query the node
+    rpc_client: RpcClient,
+}
+
+impl LocalnetNode {
+    /// Execute a JSON-RPC request against the node.
+    pub async fn request(&self, method: &str, params: JsonValue) -> Result<JsonValue> {
+        self.rpc_client.request(JsonRequest::new(method, params)).await
+    }
+
+    /// Retrieve the node last confirmed block height and hash.
+    pub async fn last_confirmed_block(&self) -> Result<(u32, String)> {
+        let rep = self.request("blockchain.last_confirmed_block", JsonValue::Array(vec![])).await?;
+        let Some(rep) = rep.get::<Vec<JsonValue>>() else {
+            return Err(Error::ParseFailed("Invalid last confirmed block reply"))
+        };
+        let (Some(height), Some(hash)) =
+            (rep.first().and_then(|h| h.get::<f64>()), rep.get(1).and_then(|h| h.get::<String>()))
+        else {
+            return Err(Error::ParseFailed("Invalid last confirmed block reply"))
+        };
+
+        Ok((*height as u32, hash.clone()))
+    }
+
+    /// Subscribe to the node confirmed blocks notifications.
+    pub async fn subscribe_blocks(&self) -> Subscription<JsonNotification> {
+        self.daemon.subscriber("blocks").unwrap().publisher.subscribe().await
+    }
+}
+
+/// Auxiliary function to generate a localhost URL with the given scheme,
+/// on a port currently available, as assigned by the OS.
+fn available_url(scheme: &str) -> Result<Url> {
+    let listener = TcpListener::bind("127.0.0.1:0")?;
+    let port = listener.local_addr()?.port();
+    drop(listener);
+    Ok(Url::parse(&format!("{scheme}://127.0.0.1:{port}"))?)
+}
+
+/// In-process DarkFi localnet
+pub struct Localnet {
+    /// Localnet configuration
+    pub config: LocalnetConfig,
+    /// The localnet nodes
+    pub nodes: Vec<LocalnetNode>,
+    /// Executor the daemons run in
+    ex: ExecutorPtr,
+}
+
+impl Localnet {
+    /// Spin up a localnet for the provided configuration. Every node
+    /// connects to all the previous ones, and all nodes but the first
+    /// sync from them before starting their consensus tasks.
+    pub async fn new(config: LocalnetConfig, ex: &ExecutorPtr) -> Result<Self> {
+        if config.nodes == 0 || config.miners > config.nodes {
+            return Err(Error::ParseFailed("Invalid localnet nodes configuration"))
+        }
+
+        // Generate the genesis block, appending its producer
+        // transaction again so its added to the merkle tree
+        let mut genesis_block = BlockInfo::default();
+        let producer_tx = genesis_block.txs.pop().unwrap();
+        genesis_block.append_txs(vec![producer_tx]);
+        let bootstrap = genesis_block.header.timestamp.inner();
+
+        let validator_config = ValidatorConfig {
+            confirmation_threshold: config.confirmation_threshold,
+            pow_target: config.pow_target,
+            pow_fixed_difficulty: config.pow_fixed_difficulty.clone(),
+            genesis_block,
+            verify_fees: config.verify_fees,
+            admission_policies: vec![],
+            spend_extractor: None,
+            snapshot_interval: 0,
+            contract_state_quota: CONTRACT_STATE_QUOTA,
+        };
+
+        let recipient = match config.recipient {
+            Some(r) => r,
+            None => Keypair::random(&mut OsRng).public,
+        };
+
+        // Use the pregenerated vks
+        let (_, vks) = vks::get_cached_pks_and_vks()?;
+
+        let mut nodes: Vec<LocalnetNode> = Vec::with_capacity(config.nodes);
+        for index in 0..config.nodes {
+            let p2p_url = available_url("tcp+tls")?;
+            let rpc_url = available_url("tcp")?;
+            let minerd_url = available_url("tcp")?;
+            info!(target: "localnet_harness", "Spinning up node {index} on {p2p_url}");
+
+            // Start the paired miner first, so the node can reach it
+            let miner = if index < config.miners {
+                let miner_db = sled::Config::new().temporary(true).open()?;
+                let solutions = SolutionLog::new(&miner_db)?;
+                let miner = Minerd::init(config.miner_threads, None, solutions, None);
+                miner.start(ex, &minerd_url);
+                wait::wait_for_rpc(&minerd_url, ex, config.startup_timeout).await?.stop().await;
+                Some(miner)
+            } else {
+                None
+            };
+
+            let settings = Settings {
+                localnet: true,
+                inbound_connections: config.nodes,
+                inbound_addrs: vec![p2p_url.clone()],
+                peers: nodes.iter().map(|n| n.p2p_url.clone()).collect(),
+                ..Default::default()
+            };
+
+            let sled_db = sled::Config::new().temporary(true).open()?;
+            vks::inject(&sled_db, &vks)?;
+
+            let minerd_endpoint = miner.as_ref().map(|_| minerd_url);
+            let daemon = Darkfid::init(
+                &sled_db,
+                &validator_config,
+                &settings,
+                &minerd_endpoint,
+                &None,
+                false,
+                None,
+                10,
+                true,
+                ex,
+            )
+            .await?;
+
+            let consensus_config = ConsensusInitTaskConfig {
+                skip_sync: index == 0,
+                checkpoint_height: None,
+                checkpoint: None,
+                checkpoints: vec![],
+                checkpoint_depth: 0,
+                stale_tip_multiplier: 0,
+                snapshot_sync: false,
+                miner: miner.is_some(),
+                recipient: Some(recipient.to_string()),
+                spend_hook: None,
+                user_data: None,
+                bootstrap,
+            };
+            let rpc_settings = RpcSettings::new(rpc_url.clone());
+            daemon.start(ex, &rpc_settings, &None, &None, &consensus_config).await?;
+
+            let rpc_client = wait::wait_for_rpc(&rpc_url, ex, config.startup_timeout).await?;
+            nodes.push(LocalnetNode { daemon, miner, p2p_url, rpc_url, rpc_client });
+        }
+
+        Ok(Self { config, nodes, ex: ex.clone() })
+    }
+
+    /// Connect a `drk` wallet to the JSON-RPC of the given node.
+    pub async fn connect_wallet(&self, wallet: &mut Drk, node: usize) -> Result<()> {
+        if let Some(rpc_client) = wallet.rpc_client.take() {
+            rpc_client.stop().await;
+        }
+        wallet.rpc_client =
+            Some(RpcClient::new(self.nodes[node].rpc_url.clone(), self.ex.clone()).await?);
+        Ok(())
+    }
+
+    /// Wait until the given node has confirmed a block at `height`,
+    /// re-checking every time it notifies a new confirmed block.
+    pub async fn wait_for_height(&self, node: usize, height: u32, timeout: Duration) -> Result<()> {
+        let node = &self.nodes[node];
+        let subscription = node.subscribe_blocks().await;
+        let result = wait::wait_for_notification(timeout, &subscription, move || async move {
+            Ok(node.last_confirmed_block().await?.0 >= height)
+        })
+        .await;
+        subscription.unsubscribe().await;
+        result
+    }
+
+    /// Wait until all nodes have confirmed the same last block. Waits
+    /// for every node to reach the first node tip height, and starts
+    /// over if the first node confirmed more blocks in the meantime.
+    pub async fn wait_for_sync(&self, timeout: Duration) -> Result<()> {
+        let deadline = Instant::now() + timeout;
+        let remaining = || deadline.saturating_duration_since(Instant::now());
+        loop {
+            let tip = self.nodes[0].last_confirmed_block().await?;
+            for index in 1..self.nodes.len() {
+                self.wait_for_height(index, tip.0, remaining()).await?;
+            }
+
+            let mut synced = true;
+            for node in &self.nodes {
+                if node.last_confirmed_block().await? != tip {
+                    synced = false;
+                    break
+                }
+            }
+            if synced {
+                return Ok(())
+            }
+
+            // Wait for the next confirmed block before checking again
+            self.wait_for_height(0, tip.0 + 1, remaining()).await?;
+        }
+    }
+
+    /// Stop all the localnet daemons.
+    pub async fn stop(&self) -> Result<()> {
+        for node in self.nodes.iter().rev() {
+            node.rpc_client.stop().await;
+            node.daemon.stop().await?;
+            if let Some(miner) = &node.miner {
+                miner.stop().await?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Auxiliary function to share an executor among a fixed amount of threads
+/// while running the given localnet test to completion.
+pub fn run<F, Fut>(test: F) -> Result<()>
+where
+    F: FnOnce(ExecutorPtr) -> Fut,
+    Fut: std::future::Future<Output = Result<()>>,
+{
+    let ex = Arc::new(smol::Executor::new());
+    let (signal, shutdown) = smol::channel::unbounded::<()>();
+
+    let (_, result) = easy_parallel::Parallel::new()
+        .each(0..4, |_| smol::block_on(ex.run(shutdown.recv())))
+        .finish(|| {
+            smol::block_on(async {
+                let result = test(ex.clone()).await;
+                drop(signal);
+                result
+            })
+        });
+
+    result
+}
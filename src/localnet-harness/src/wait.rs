@@ -0,0 +1,107 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2025 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use std::{
+    future::Future,
+    time::{Duration, Instant},
+};
+
+use darkfi::{
+    rpc::{
+        client::RpcClient,
+        jsonrpc::{JsonNotification, JsonRequest},
+    },
+    system::{ExecutorPtr, Subscription},
+    Error, Result,
+};
+use smol::{future, Timer};
+use tinyjson::JsonValue;
+use url::Url;
+
+/// Interval between connection attempts while waiting for a daemon
+/// JSON-RPC endpoint to start listening.
+pub const CONNECT_RETRY_INTERVAL: Duration = Duration::from_millis(50);
+
+/// Auxiliary function to generate the error of a wait that timed out.
+fn timed_out(what: &str, timeout: Duration) -> Error {
+    Error::Custom(format!("Localnet {what} did not happen within {} seconds", timeout.as_secs()))
+}
+
+/// Wait until the provided async `condition` holds. The condition is
+/// checked once right away, and then again every time a notification
+/// is received on `subscription`, so callers must subscribe before
+/// the state they check can change. Errors out if it doesn't hold
+/// within `timeout`, or if checking it fails.
+pub async fn wait_for_notification<F, Fut>(
+    timeout: Duration,
+    subscription: &Subscription<JsonNotification>,
+    mut condition: F,
+) -> Result<()>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<bool>>,
+{
+    let deadline = Instant::now() + timeout;
+    loop {
+        if condition().await? {
+            return Ok(())
+        }
+
+        let notified = future::or(
+            async {
+                subscription.receive().await;
+                true
+            },
+            async {
+                Timer::at(deadline).await;
+                false
+            },
+        )
+        .await;
+
+        if !notified {
+            return Err(timed_out("condition", timeout))
+        }
+    }
+}
+
+/// Wait until the daemon JSON-RPC server at `endpoint` accepts
+/// connections and replies to a `ping`, returning the connected
+/// client.
+pub async fn wait_for_rpc(
+    endpoint: &Url,
+    ex: &ExecutorPtr,
+    timeout: Duration,
+) -> Result<RpcClient> {
+    let deadline = Instant::now() + timeout;
+    loop {
+        if let Ok(rpc_client) = RpcClient::new(endpoint.clone(), ex.clone()).await {
+            let req = JsonRequest::new("ping", JsonValue::Array(vec![]));
+            if rpc_client.request(req).await.is_ok() {
+                return Ok(rpc_client)
+            }
+            rpc_client.stop().await;
+        }
+
+        if Instant::now() >= deadline {
+            return Err(timed_out(&format!("JSON-RPC server {endpoint} startup"), timeout))
+        }
+
+        Timer::after(CONNECT_RETRY_INTERVAL).await;
+    }
+}
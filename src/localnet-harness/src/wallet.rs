@@ -0,0 +1,45 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2025 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use darkfi::{Error, Result};
+use darkfi_sdk::crypto::Keypair;
+use drk::{walletdb::WalletDb, Drk};
+
+/// Auxiliary function to map wallet database errors.
+fn wallet_err(e: impl std::fmt::Debug) -> Error {
+    Error::DatabaseError(format!("[localnet_harness::wallet] {e:?}"))
+}
+
+/// Create an initialized in-memory `drk` wallet, along with its first
+/// keypair. Use [`crate::Localnet::connect_wallet`] to connect it to a
+/// localnet node.
+pub async fn new_wallet() -> Result<(Drk, Keypair)> {
+    let wallet = WalletDb::new(None, None).map_err(wallet_err)?;
+    let drk = Drk::from_wallet(wallet, None, false, vec![]);
+
+    drk.initialize_wallet().await.map_err(wallet_err)?;
+    drk.initialize_money().await.map_err(wallet_err)?;
+    drk.initialize_dao().await.map_err(wallet_err)?;
+    drk.initialize_deployooor().map_err(wallet_err)?;
+    drk.initialize_darkname().map_err(wallet_err)?;
+    drk.initialize_auction().map_err(wallet_err)?;
+    drk.initialize_money_seed(None).await?;
+    let keypair = drk.money_keygen().await?;
+
+    Ok((drk, keypair))
+}
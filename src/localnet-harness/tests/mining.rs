@@ -0,0 +1,52 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2025 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use std::time::Duration;
+
+use darkfi::{Error, Result};
+use darkfi_contract_test_harness::init_logger;
+use darkfi_localnet_harness::{run, wallet::new_wallet, Localnet, LocalnetConfig};
+use darkfi_money_contract::model::DARK_TOKEN_ID;
+
+#[test]
+/// Mine blocks on a localnet node, sync them to a non-mining node,
+/// and scan the mining rewards into a wallet connected to the latter.
+fn localnet_mining_sync_and_scan() -> Result<()> {
+    run(|ex| async move {
+        init_logger();
+
+        let (mut wallet, keypair) = new_wallet().await?;
+        let config = LocalnetConfig { recipient: Some(keypair.public), ..Default::default() };
+        let localnet = Localnet::new(config, &ex).await?;
+
+        // Wait for blocks to get mined and propagated
+        localnet.wait_for_height(0, 3, Duration::from_secs(120)).await?;
+        localnet.wait_for_height(1, 3, Duration::from_secs(60)).await?;
+
+        // Scan the rewards using the non-mining node
+        localnet.connect_wallet(&mut wallet, 1).await?;
+        if let Err(e) = wallet.scan_blocks().await {
+            return Err(Error::DatabaseError(format!("Wallet scan failed: {e:?}")))
+        }
+        let balance = wallet.money_balance().await?;
+        assert!(balance.get(&DARK_TOKEN_ID.to_string()).is_some_and(|v| *v > 0));
+
+        wallet.stop_rpc_client().await?;
+        localnet.stop().await
+    })
+}
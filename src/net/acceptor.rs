@@ -17,16 +17,19 @@
  */
 
 use std::{
+    collections::{HashMap, VecDeque},
     io::ErrorKind,
+    net::IpAddr,
     sync::{
         atomic::{AtomicUsize, Ordering::SeqCst},
-        Arc,
+        Arc, Mutex,
     },
+    time::{Duration, Instant},
 };
 
-use log::{error, info, warn};
+use log::{debug, error, info, warn};
 use smol::Executor;
-use url::Url;
+use url::{Host, Url};
 
 use super::{
     channel::{Channel, ChannelPtr},
@@ -42,12 +45,90 @@ use crate::{
 /// Atomic pointer to Acceptor
 pub type AcceptorPtr = Arc<Acceptor>;
 
+/// Window over which new inbound connections are rate limited
+const INBOUND_RATE_WINDOW: Duration = Duration::from_secs(60);
+
+/// Number of tracked address classes after which stale ones get pruned
+const INBOUND_RATE_PRUNE_LEN: usize = 1024;
+
+/// Returns the address class of an inbound peer, used to rate limit new
+/// connections. IP addresses are grouped by their /24 (IPv4) or /48 (IPv6)
+/// prefix, so a single host can't bypass the limit by rotating addresses
+/// within its allocation. Other hosts form a class of their own.
+/// Returns `None` for anonymous peers, arriving from the loopback or a
+/// local socket, since every peer reaching us through a local proxy, like
+/// Tor onion services do, shows up with the same address.
+fn address_class(url: &Url) -> Option<String> {
+    // Non-special URL schemes don't get their IPv4 hosts parsed
+    let ip = match url.host() {
+        Some(Host::Ipv4(ip)) => IpAddr::V4(ip),
+        Some(Host::Ipv6(ip)) => IpAddr::V6(ip),
+        Some(Host::Domain(domain)) => match domain.parse() {
+            Ok(ip) => ip,
+            Err(_) if domain == "localhost" => return None,
+            Err(_) => return Some(domain.to_string()),
+        },
+        None => return None,
+    };
+
+    if ip.is_loopback() || ip.is_unspecified() {
+        return None
+    }
+
+    Some(match ip {
+        IpAddr::V4(ip) => {
+            let [a, b, c, _] = ip.octets();
+            format!("{}.{}.{}.0/24", a, b, c)
+        }
+        IpAddr::V6(ip) => {
+            let [a, b, c, ..] = ip.segments();
+            format!("{:x}:{:x}:{:x}::/48", a, b, c)
+        }
+    })
+}
+
+/// Tracks new inbound connections per address class
+#[derive(Default)]
+struct InboundThrottle {
+    accepted: Mutex<HashMap<String, VecDeque<Instant>>>,
+}
+
+impl InboundThrottle {
+    /// Check if a new connection from given address class is allowed,
+    /// recording it if so. A zero `limit` disables throttling.
+    fn allow(&self, class: String, limit: usize, now: Instant) -> bool {
+        if limit == 0 {
+            return true
+        }
+
+        let mut accepted = self.accepted.lock().unwrap();
+        if accepted.len() >= INBOUND_RATE_PRUNE_LEN {
+            accepted.retain(|_, times| {
+                times.back().is_some_and(|t| now.duration_since(*t) < INBOUND_RATE_WINDOW)
+            });
+        }
+
+        let times = accepted.entry(class).or_default();
+        while times.front().is_some_and(|t| now.duration_since(*t) >= INBOUND_RATE_WINDOW) {
+            times.pop_front();
+        }
+
+        if times.len() >= limit {
+            return false
+        }
+
+        times.push_back(now);
+        true
+    }
+}
+
 /// Create inbound socket connections
 pub struct Acceptor {
     channel_publisher: PublisherPtr<Result<ChannelPtr>>,
     task: StoppableTaskPtr,
     session: SessionWeakPtr,
     conn_count: AtomicUsize,
+    throttle: InboundThrottle,
 }
 
 impl Acceptor {
@@ -58,6 +139,7 @@ impl Acceptor {
             task: StoppableTask::new(),
             session,
             conn_count: AtomicUsize::new(0),
+            throttle: InboundThrottle::default(),
         })
     }
 
@@ -151,6 +233,11 @@ impl Acceptor {
                         continue
                     }
 
+                    // Check if this peer may take a slot right now
+                    if !self.admit(&url).await {
+                        continue
+                    }
+
                     // Create the new Channel.
                     let session = self.session.clone();
                     let channel = Channel::new(stream, None, url, session).await;
@@ -263,6 +350,50 @@ impl Acceptor {
         }
     }
 
+    /// Check if an accepted inbound peer may take a connection slot.
+    /// Known peers are always admitted while there are free slots. Other
+    /// peers can't take the slots reserved for known ones, and are rate
+    /// limited per address class, while anonymous peers share a separate
+    /// rate limit.
+    async fn admit(&self, url: &Url) -> bool {
+        let p2p = self.session.upgrade().unwrap().p2p();
+        let settings = p2p.settings().read().await;
+        let hosts = p2p.hosts();
+
+        // Known peers are matched by host, since inbound ports are ephemeral
+        let host = url.host_str();
+        let known = settings.peers.iter().any(|peer| peer.host_str() == host) ||
+            [HostColor::Gold, HostColor::White].into_iter().any(|color| {
+                hosts.container.fetch_all(color).iter().any(|(addr, _)| addr.host_str() == host)
+            });
+        if known {
+            return true
+        }
+
+        let unreserved =
+            settings.inbound_connections.saturating_sub(settings.inbound_reserved_slots);
+        if self.conn_count.load(SeqCst) >= unreserved {
+            debug!(target: "net::acceptor::admit()", "Rejecting {}: remaining slots are reserved for known peers", url);
+            return false
+        }
+
+        // Local test networks connect everything through the loopback
+        if settings.localnet && hosts.is_local_host(url) {
+            return true
+        }
+
+        let (class, limit) = match address_class(url) {
+            Some(class) => (class, settings.inbound_rate_limit),
+            None => (String::new(), settings.inbound_anon_rate_limit),
+        };
+        if !self.throttle.allow(class, limit, Instant::now()) {
+            warn!(target: "net::acceptor::admit()", "Rejecting {}: inbound rate limit exceeded", url);
+            return false
+        }
+
+        true
+    }
+
     /// Handles network errors. Panics if errors pass silently, otherwise broadcasts it
     /// to all channel publishers.
     async fn handle_stop(self: Arc<Self>, result: Result<()>) {
@@ -272,3 +403,47 @@ impl Acceptor {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_address_class() {
+        let class = |url: &str| address_class(&Url::parse(url).unwrap());
+        assert_eq!(class("tcp+tls://77.168.10.65:2222").unwrap(), "77.168.10.0/24");
+        assert_eq!(class("tcp://77.168.10.1:1111"), class("tcp://77.168.10.250:3333"));
+        assert_ne!(class("tcp://77.168.10.1"), class("tcp://77.168.11.1"));
+        assert_eq!(class("tcp://[2001:db8:1:2::1]:4444").unwrap(), "2001:db8:1::/48");
+        assert_eq!(class("tor://abcdef.onion:5555").unwrap(), "abcdef.onion");
+
+        // Peers arriving through a local proxy or socket are anonymous
+        assert!(class("tcp://127.0.0.1:41234").is_none());
+        assert!(class("tcp://127.0.0.53:41234").is_none());
+        assert!(class("tcp://[::1]:41234").is_none());
+        assert!(class("tcp://localhost:41234").is_none());
+        assert!(class("unix:///tmp/darkfi.sock").is_none());
+    }
+
+    #[test]
+    fn test_inbound_throttle() {
+        let throttle = InboundThrottle::default();
+        let start = Instant::now();
+        let class = || "77.168.10.0/24".to_string();
+
+        // Up to the limit within the window
+        assert!(throttle.allow(class(), 2, start));
+        assert!(throttle.allow(class(), 2, start + Duration::from_secs(1)));
+        assert!(!throttle.allow(class(), 2, start + Duration::from_secs(2)));
+
+        // Other classes are tracked separately
+        assert!(throttle.allow("77.168.11.0/24".to_string(), 2, start));
+
+        // Slots free up as the window moves
+        assert!(throttle.allow(class(), 2, start + INBOUND_RATE_WINDOW));
+        assert!(!throttle.allow(class(), 2, start + INBOUND_RATE_WINDOW));
+
+        // Zero disables throttling
+        assert!(throttle.allow(class(), 0, start));
+    }
+}
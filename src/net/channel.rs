@@ -22,16 +22,16 @@ use std::{
         atomic::{AtomicBool, Ordering::SeqCst},
         Arc,
     },
-    time::UNIX_EPOCH,
+    time::{SystemTime, UNIX_EPOCH},
 };
 
 use darkfi_serial::{
-    async_trait, AsyncDecodable, AsyncEncodable, SerialDecodable, SerialEncodable, VarInt,
+    async_trait, AsyncDecoder, AsyncEncodable, SerialDecodable, SerialEncodable, VarInt,
 };
 use log::{debug, error, info, trace, warn};
 use rand::{rngs::OsRng, Rng};
 use smol::{
-    io::{self, AsyncRead, AsyncReadExt, AsyncWriteExt, ReadHalf, WriteHalf},
+    io::{self, AsyncRead, AsyncWriteExt, ReadHalf, WriteHalf},
     lock::Mutex,
     Executor,
 };
@@ -47,6 +47,7 @@ use super::{
     session::{
         Session, SessionBitFlag, SessionWeakPtr, SESSION_ALL, SESSION_INBOUND, SESSION_REFINE,
     },
+    stream::{StreamMux, StreamPtr, STREAM_FEATURE},
     transport::PtStream,
 };
 use crate::{
@@ -56,6 +57,10 @@ use crate::{
     Error, Result,
 };
 
+/// Maximum size of the magic bytes or the command of a message header.
+/// Message payloads are read from the stream as is, and not bounded by it.
+pub const MAX_HEADER_SIZE: usize = 256;
+
 /// Atomic pointer to async channel
 pub type ChannelPtr = Arc<Channel>;
 
@@ -64,12 +69,12 @@ pub type ChannelPtr = Arc<Channel>;
 pub struct ChannelInfo {
     pub resolve_addr: Option<Url>,
     pub connect_addr: Url,
-    pub start_time: u64,
+    pub start_time: SystemTime,
     pub id: u32,
 }
 
 impl ChannelInfo {
-    fn new(resolve_addr: Option<Url>, connect_addr: Url, start_time: u64) -> Self {
+    fn new(resolve_addr: Option<Url>, connect_addr: Url, start_time: SystemTime) -> Self {
         Self { resolve_addr, connect_addr, start_time, id: OsRng.gen() }
     }
 }
@@ -77,7 +82,7 @@ impl ChannelInfo {
 /// Async channel for communication between nodes.
 pub struct Channel {
     /// The reading half of the transport stream
-    reader: Mutex<ReadHalf<Box<dyn PtStream>>>,
+    reader: Mutex<AsyncDecoder<ReadHalf<Box<dyn PtStream>>>>,
     /// The writing half of the transport stream
     writer: Mutex<WriteHalf<Box<dyn PtStream>>>,
     /// The message subsystem instance for this channel
@@ -88,6 +93,8 @@ pub struct Channel {
     receive_task: StoppableTaskPtr,
     /// A boolean marking if this channel is stopped
     stopped: AtomicBool,
+    /// Multiplexer of the streams opened over this channel
+    streams: StreamMux,
     /// Weak pointer to respective session
     pub(in crate::net) session: SessionWeakPtr,
     /// The version message of the node we are connected to.
@@ -109,15 +116,15 @@ impl Channel {
         session: SessionWeakPtr,
     ) -> Arc<Self> {
         let (reader, writer) = io::split(stream);
-        let reader = Mutex::new(reader);
+        let reader = Mutex::new(AsyncDecoder::with_max_buffer(reader, MAX_HEADER_SIZE));
         let writer = Mutex::new(writer);
 
         let message_subsystem = MessageSubsystem::new();
         Self::setup_dispatchers(&message_subsystem).await;
+        let streams = StreamMux::new(&message_subsystem).await;
 
         let version = Mutex::new(None);
-        let start_time = UNIX_EPOCH.elapsed().unwrap().as_secs();
-        let info = ChannelInfo::new(resolve_addr, connect_addr.clone(), start_time);
+        let info = ChannelInfo::new(resolve_addr, connect_addr.clone(), SystemTime::now());
 
         Arc::new(Self {
             reader,
@@ -126,6 +133,7 @@ impl Channel {
             stop_publisher: Publisher::new(),
             receive_task: StoppableTask::new(),
             stopped: AtomicBool::new(false),
+            streams,
             session,
             version,
             info,
@@ -152,9 +160,13 @@ impl Channel {
             self.clone().main_receive_loop(),
             |result| self_.handle_stop(result),
             Error::ChannelStopped,
-            executor,
+            executor.clone(),
         );
 
+        // Dispatch stream frames until the channel stops
+        let self_ = self.clone();
+        executor.spawn(async move { self_.streams.run(self_.clone()).await }).detach();
+
         debug!(target: "net::channel::start()", "END {:?}", self);
     }
 
@@ -264,20 +276,18 @@ impl Channel {
         Ok(())
     }
 
-    /// Returns a decoded Message command. We start by extracting the length
-    /// from the stream, then allocate the precise buffer for this length
-    /// using stream.take(). This manual deserialization provides a basic
-    /// DDOS protection, since it prevents nodes from sending an arbitarily
-    /// large payload.
+    /// Returns a decoded Message command. The header is read through the
+    /// channel's [`AsyncDecoder`], so reading it is cancellation safe, and
+    /// it can't exceed [`MAX_HEADER_SIZE`], which provides a basic DDOS
+    /// protection against nodes sending an arbitrarily large command.
     pub async fn read_command<R: AsyncRead + Unpin + Send + Sized>(
         &self,
-        stream: &mut R,
+        decoder: &mut AsyncDecoder<R>,
     ) -> Result<String> {
         // Messages should have a 4 byte header of magic digits.
         // This is used for network debugging.
-        let mut magic = [0u8; 4];
         trace!(target: "net::channel::read_command()", "Reading magic...");
-        stream.read_exact(&mut magic).await?;
+        let magic: [u8; 4] = decoder.decode().await?;
 
         trace!(target: "net::channel::read_command()", "Read magic {:?}", magic);
         let magic_bytes = self.p2p().settings().read().await.magic_bytes.0;
@@ -286,18 +296,7 @@ impl Channel {
             return Err(Error::MalformedPacket)
         }
 
-        // First extract the length from the stream
-        let cmd_len = VarInt::decode_async(stream).await?.0;
-
-        // Then extract precisely `cmd_len` items from the stream.
-        let mut take = stream.take(cmd_len);
-
-        // Deserialize into a vector of `cmd_len` size.
-        let mut bytes = vec![0; cmd_len.try_into().unwrap()];
-        take.read_exact(&mut bytes).await?;
-
-        let command = String::from_utf8(bytes)?;
-
+        let command = decoder.decode().await?;
         Ok(command)
     }
 
@@ -318,6 +317,33 @@ impl Channel {
         sub
     }
 
+    /// Open a new multiplexed stream for `protocol` on this channel.
+    /// Fails if the peer did not advertise stream support.
+    pub async fn open_stream(self: &Arc<Self>, protocol: &str) -> Result<StreamPtr> {
+        if self.is_stopped() {
+            return Err(Error::ChannelStopped)
+        }
+
+        if !self.supports_streams().await {
+            return Err(Error::StreamsUnsupported)
+        }
+
+        self.streams.open(self, protocol).await
+    }
+
+    /// Returns the stream multiplexer of this channel, used to listen
+    /// for streams opened by the peer.
+    pub fn streams(&self) -> &StreamMux {
+        &self.streams
+    }
+
+    /// Returns true if the peer advertised stream support in its
+    /// version message.
+    pub async fn supports_streams(&self) -> bool {
+        let Some(version) = self.version.lock().await.clone() else { return false };
+        version.features.iter().any(|(feature, _)| feature == STREAM_FEATURE)
+    }
+
     /// Handle network errors. Panic if error passes silently, otherwise
     /// broadcast the error.
     async fn handle_stop(self: Arc<Self>, result: Result<()>) {
@@ -66,6 +66,7 @@ impl Connector {
         let transports = settings.allowed_transports.clone();
         let transport_mixing = settings.transport_mixing;
         let datastore = settings.p2p_datastore.clone();
+        let tor_socks5_proxy = settings.tor_socks5_proxy.clone();
         let outbound_connect_timeout = settings.outbound_connect_timeout;
         drop(settings);
 
@@ -84,7 +85,17 @@ impl Connector {
             }
         }
 
-        let dialer = Dialer::new(endpoint.clone(), datastore).await?;
+        let p2p = self.session.upgrade().unwrap().p2p();
+        p2p.metrics().record_connect_attempt(&endpoint);
+
+        let dialer =
+            match Dialer::with_tor_proxy(endpoint.clone(), datastore, tor_socks5_proxy).await {
+                Ok(v) => v,
+                Err(e) => {
+                    p2p.metrics().record_connect_failure(&endpoint, &e);
+                    return Err(e)
+                }
+            };
         let timeout = Duration::from_secs(outbound_connect_timeout);
 
         let stop_fut = async {
@@ -108,15 +119,11 @@ impl Connector {
             }
 
             Either::Left((Err(e), _)) => {
+                p2p.metrics().record_connect_failure(&endpoint, &Error::Io(e.kind()));
+
                 // If we get ENETUNREACH, we don't have IPv6 connectivity so note it down.
                 if e.raw_os_error() == Some(libc::ENETUNREACH) {
-                    self.session
-                        .upgrade()
-                        .unwrap()
-                        .p2p()
-                        .hosts()
-                        .ipv6_available
-                        .store(false, Ordering::SeqCst);
+                    p2p.hosts().ipv6_available.store(false, Ordering::SeqCst);
                 }
                 Err(e.into())
             }
@@ -16,15 +16,17 @@
  * along with this program.  If not, see <https://www.gnu.org/licenses/>.
  */
 
+use std::{collections::VecDeque, sync::Mutex};
+
 use url::Url;
 
-use super::channel::ChannelInfo;
+use super::{channel::ChannelInfo, session::SessionBitFlag};
 use crate::util::time::NanoTimestamp;
 
 macro_rules! dnetev {
     ($self:expr, $event_name:ident, $($code:tt)*) => {
         {
-            if $self.p2p().dnet_enabled.load(std::sync::atomic::Ordering::SeqCst) {
+            if $self.p2p().dnet_active() {
                 let event = DnetEvent::$event_name(dnet::$event_name $($code)*);
                 $self.p2p().dnet_notify(event).await;
             }
@@ -83,6 +85,24 @@ pub struct OutboundPeerDiscovery {
     pub state: &'static str,
 }
 
+#[derive(Clone, Debug)]
+pub struct ChannelHandshake {
+    pub addr: Url,
+    pub session: SessionBitFlag,
+    pub duration_ms: u64,
+    pub err: Option<String>,
+}
+
+#[derive(Clone, Debug)]
+pub struct TorBootstrap {
+    /// Bootstrap progress, between 0 and 1
+    pub progress: f32,
+    /// Human-readable bootstrap status
+    pub status: String,
+    /// Whether the Tor client is ready for traffic
+    pub ready: bool,
+}
+
 #[derive(Clone, Debug)]
 pub enum DnetEvent {
     SendMessage(MessageInfo),
@@ -94,4 +114,131 @@ pub enum DnetEvent {
     OutboundSlotConnected(OutboundSlotConnected),
     OutboundSlotDisconnected(OutboundSlotDisconnected),
     OutboundPeerDiscovery(OutboundPeerDiscovery),
+    ChannelHandshake(ChannelHandshake),
+    TorBootstrap(TorBootstrap),
+}
+
+impl DnetEvent {
+    /// Name of the event kind, as exposed over JSON-RPC
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::SendMessage(_) => "send",
+            Self::RecvMessage(_) => "recv",
+            Self::InboundConnected(_) => "inbound_connected",
+            Self::InboundDisconnected(_) => "inbound_disconnected",
+            Self::OutboundSlotSleeping(_) => "outbound_slot_sleeping",
+            Self::OutboundSlotConnecting(_) => "outbound_slot_connecting",
+            Self::OutboundSlotConnected(_) => "outbound_slot_connected",
+            Self::OutboundSlotDisconnected(_) => "outbound_slot_disconnected",
+            Self::OutboundPeerDiscovery(_) => "outbound_peer_discovery",
+            Self::ChannelHandshake(_) => "channel_handshake",
+            Self::TorBootstrap(_) => "tor_bootstrap",
+        }
+    }
+}
+
+/// Filter applied when querying the [`DnetHistory`]
+#[derive(Clone, Debug, Default)]
+pub struct DnetFilter {
+    /// Event kind names to keep, all of them if empty
+    pub events: Vec<String>,
+    /// Only keep events recorded after this time
+    pub since: Option<NanoTimestamp>,
+    /// Only keep this many most recent events
+    pub limit: Option<usize>,
+}
+
+/// Ring buffer of recent dnet events, so they can be inspected after
+/// the fact by clients that weren't subscribed when they happened.
+pub struct DnetHistory {
+    /// Max amount of events kept, disabled if zero
+    capacity: usize,
+    /// Recorded events along with their recording time, oldest first
+    events: Mutex<VecDeque<(NanoTimestamp, DnetEvent)>>,
+}
+
+impl DnetHistory {
+    pub fn new(capacity: usize) -> Self {
+        Self { capacity, events: Mutex::new(VecDeque::with_capacity(capacity)) }
+    }
+
+    /// Whether events get recorded at all
+    pub fn is_enabled(&self) -> bool {
+        self.capacity > 0
+    }
+
+    /// Record an event, evicting the oldest one if the buffer is full
+    pub fn push(&self, event: DnetEvent) {
+        if !self.is_enabled() {
+            return
+        }
+
+        let mut events = self.events.lock().unwrap();
+        if events.len() == self.capacity {
+            events.pop_front();
+        }
+        events.push_back((NanoTimestamp::current_time(), event));
+    }
+
+    /// Retrieve the recorded events matching the filter, oldest first
+    pub fn query(&self, filter: &DnetFilter) -> Vec<(NanoTimestamp, DnetEvent)> {
+        let events = self.events.lock().unwrap();
+        let mut matched: Vec<_> = events
+            .iter()
+            .filter(|(time, _)| filter.since.is_none_or(|since| *time > since))
+            .filter(|(_, event)| {
+                filter.events.is_empty() || filter.events.iter().any(|e| e == event.name())
+            })
+            .cloned()
+            .collect();
+        drop(events);
+
+        if let Some(limit) = filter.limit {
+            let skip = matched.len().saturating_sub(limit);
+            matched.drain(..skip);
+        }
+
+        matched
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_dnet_history() {
+        let sleeping = |slot| DnetEvent::OutboundSlotSleeping(OutboundSlotSleeping { slot });
+        let discovery =
+            DnetEvent::OutboundPeerDiscovery(OutboundPeerDiscovery { attempt: 1, state: "wait" });
+
+        // Disabled history records nothing
+        let history = DnetHistory::new(0);
+        history.push(sleeping(0));
+        assert!(history.query(&DnetFilter::default()).is_empty());
+
+        // Oldest events get evicted
+        let history = DnetHistory::new(3);
+        for slot in 0..3 {
+            history.push(sleeping(slot));
+        }
+        history.push(discovery);
+        let events = history.query(&DnetFilter::default());
+        assert_eq!(events.len(), 3);
+        assert!(matches!(events[0].1, DnetEvent::OutboundSlotSleeping(ref e) if e.slot == 1));
+        assert_eq!(events[2].1.name(), "outbound_peer_discovery");
+
+        // Filtering by kind, time and limit
+        let filter =
+            DnetFilter { events: vec!["outbound_slot_sleeping".to_string()], ..Default::default() };
+        assert_eq!(history.query(&filter).len(), 2);
+
+        let filter = DnetFilter { since: Some(events[1].0), ..Default::default() };
+        assert!(history.query(&filter).iter().all(|(time, _)| *time > events[1].0));
+
+        let filter = DnetFilter { limit: Some(1), ..Default::default() };
+        let limited = history.query(&filter);
+        assert_eq!(limited.len(), 1);
+        assert_eq!(limited[0].1.name(), "outbound_peer_discovery");
+    }
 }
@@ -20,6 +20,7 @@ use std::{
     collections::HashMap,
     fmt, fs,
     fs::File,
+    path::{Component, Path, PathBuf},
     sync::{
         atomic::{AtomicBool, Ordering},
         Arc, Mutex, RwLock,
@@ -1363,6 +1364,105 @@ impl Hosts {
         ret
     }
 
+    /// Resolve the path of a hostlist exchange file in the `hostlists`
+    /// directory of the P2P datastore. Only plain file names are accepted,
+    /// so callers can't read or write arbitrary filesystem paths.
+    async fn exchange_path(&self, name: &str) -> Result<PathBuf> {
+        let mut components = Path::new(name).components();
+        if !matches!((components.next(), components.next()), (Some(Component::Normal(_)), None)) {
+            return Err(Error::Custom(format!("Invalid hostlist file name: {name}")))
+        }
+
+        let Some(datastore) = self.settings.read().await.p2p_datastore.clone() else {
+            return Err(Error::Custom("No P2P datastore configured".to_string()))
+        };
+
+        Ok(expand_path(&datastore)?.join("hostlists").join(name))
+    }
+
+    /// Export our Gold, White and Grey hostlists to a file, using the same
+    /// tab-separated `list\turl\tlast_seen` format as the hostlist file.
+    /// The file is written to the `hostlists` directory of the P2P datastore,
+    /// and can be shared out-of-band and imported by other nodes with
+    /// `import_hosts()`. Returns the number of exported hosts.
+    pub async fn export_hosts(&self, name: &str) -> Result<usize> {
+        let path = self.exchange_path(name).await?;
+
+        let mut tsv = String::new();
+        let mut exported = 0;
+        for (name, color) in
+            [("gold", HostColor::Gold), ("white", HostColor::White), ("grey", HostColor::Grey)]
+        {
+            for (url, last_seen) in self.container.fetch_all(color) {
+                tsv.push_str(&format!("{}\t{}\t{}\n", name, url, last_seen));
+                exported += 1;
+            }
+        }
+
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        info!(target: "net::hosts::export_hosts()", "Exporting {} hosts to: {:?}", exported, path);
+        save_file(&path, &tsv)?;
+
+        Ok(exported)
+    }
+
+    /// Import a curated hostlist from a file in the `hostlists` directory
+    /// of the P2P datastore. Accepts both the format written by
+    /// `export_hosts()` and plain files containing one URL per line.
+    /// Empty lines and lines starting with `#` are ignored.
+    ///
+    /// Imported hosts are inserted into our greylist regardless of the
+    /// list they were exported from, so they get probed by the refinery
+    /// before we make outbound connections to them. Returns the number of
+    /// parsed hosts.
+    pub async fn import_hosts(&self, name: &str) -> Result<usize> {
+        let path = self.exchange_path(name).await?;
+        let contents = load_file(&path)?;
+        let now = UNIX_EPOCH.elapsed().unwrap().as_secs();
+
+        let mut addrs = vec![];
+        for line in contents.lines() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue
+            }
+
+            let data: Vec<&str> = line.split('\t').collect();
+            let (url, last_seen) = match data[..] {
+                [url] => (url, now),
+                [_, url, last_seen] => match last_seen.parse::<u64>() {
+                    Ok(t) => (url, t),
+                    Err(e) => {
+                        debug!(target: "net::hosts::import_hosts()",
+                               "Skipping malformed last seen {}", e);
+                        continue
+                    }
+                },
+                _ => {
+                    debug!(target: "net::hosts::import_hosts()",
+                           "Skipping malformed line {}", line);
+                    continue
+                }
+            };
+
+            match Url::parse(url) {
+                Ok(url) => addrs.push((url, last_seen)),
+                Err(e) => {
+                    debug!(target: "net::hosts::import_hosts()", "Skipping malformed URL {}", e);
+                }
+            }
+        }
+
+        info!(target: "net::hosts::import_hosts()", "Importing {} hosts from: {:?}",
+              addrs.len(), path);
+        self.insert(HostColor::Grey, &addrs).await;
+
+        Ok(addrs.len())
+    }
+
     /// Method to fetch the last_seen field for a give address when we do
     /// not know what hostlist it is on.
     pub fn fetch_last_seen(&self, addr: &Url) -> Option<u64> {
@@ -1607,6 +1707,64 @@ mod tests {
         assert!(hosts.container.contains(HostColor::Gold as usize, &gold_hosts[2]));
     }
 
+    #[test]
+    fn test_export_import_hosts() {
+        smol::block_on(async {
+            let datastore = std::env::temp_dir().join(format!(
+                "darkfi_test_export_import_hosts_{}_{}",
+                std::process::id(),
+                OsRng.gen::<u64>()
+            ));
+            let p2p_datastore = Some(datastore.to_str().unwrap().to_string());
+            let path = datastore.join("hostlists").join("hosts.tsv");
+            let last_seen = UNIX_EPOCH.elapsed().unwrap().as_secs();
+
+            let settings = Settings { p2p_datastore: p2p_datastore.clone(), ..Default::default() };
+            let hosts = Hosts::new(Arc::new(AsyncRwLock::new(settings)));
+
+            let gold = Url::parse("tcp+tls://dark.fi:26661").unwrap();
+            let white = Url::parse("tcp+tls://dyne.org:26661").unwrap();
+            let grey = Url::parse("tcp+tls://agorism.xyz:26661").unwrap();
+            let black = Url::parse("tcp+tls://nietzsche.king:26661").unwrap();
+            hosts.container.store(HostColor::Gold as usize, gold.clone(), last_seen);
+            hosts.container.store(HostColor::White as usize, white.clone(), last_seen);
+            hosts.container.store(HostColor::Grey as usize, grey.clone(), last_seen);
+            hosts.container.store(HostColor::Black as usize, black.clone(), last_seen);
+
+            // Blacklisted hosts are never exported
+            assert_eq!(hosts.export_hosts("hosts.tsv").await.unwrap(), 3);
+
+            // Only plain file names inside the datastore are accepted
+            for name in ["", "..", "../hosts.tsv", "/tmp/hosts.tsv", "lists/hosts.tsv"] {
+                assert!(hosts.export_hosts(name).await.is_err());
+                assert!(hosts.import_hosts(name).await.is_err());
+            }
+
+            // Without a datastore there's nowhere to exchange files
+            let settings = Settings { ..Default::default() };
+            let no_datastore = Hosts::new(Arc::new(AsyncRwLock::new(settings)));
+            assert!(no_datastore.export_hosts("hosts.tsv").await.is_err());
+
+            // Append a plain URL line, as found in hand-curated files
+            let mut contents = load_file(&path).unwrap();
+            contents.push_str("# curated\ntcp+tls://lol.cat:26661\n");
+            save_file(&path, &contents).unwrap();
+
+            let settings = Settings { p2p_datastore, ..Default::default() };
+            let imported = Hosts::new(Arc::new(AsyncRwLock::new(settings)));
+            assert_eq!(imported.import_hosts("hosts.tsv").await.unwrap(), 4);
+
+            // Everything lands on the greylist to be refined
+            for addr in [&gold, &white, &grey, &Url::parse("tcp+tls://lol.cat:26661").unwrap()] {
+                assert!(imported.container.contains(HostColor::Grey as usize, addr));
+            }
+            assert!(imported.container.is_empty(HostColor::White));
+            assert!(imported.container.is_empty(HostColor::Gold));
+
+            fs::remove_dir_all(datastore).unwrap();
+        });
+    }
+
     #[test]
     fn test_refresh() {
         smol::block_on(async {
@@ -26,7 +26,7 @@ use smol::{io::AsyncReadExt, lock::Mutex};
 
 use super::message::Message;
 use crate::{net::transport::PtStream, system::timeout::timeout, Error, Result};
-use darkfi_serial::{AsyncDecodable, VarInt};
+use darkfi_serial::{AsyncDecodable, AsyncDecoder, VarInt};
 
 /// 64-bit identifier for message subscription.
 pub type MessageSubscriptionId = u64;
@@ -178,7 +178,7 @@ impl<M: Message> MessageSubscription<M> {
 /// Generic interface for the message dispatcher.
 #[async_trait]
 trait MessageDispatcherInterface: Send + Sync {
-    async fn trigger(&self, stream: &mut smol::io::ReadHalf<Box<dyn PtStream + 'static>>);
+    async fn trigger(&self, stream: &mut AsyncDecoder<smol::io::ReadHalf<Box<dyn PtStream>>>);
 
     async fn trigger_error(&self, err: Error);
 
@@ -190,11 +190,12 @@ trait MessageDispatcherInterface: Send + Sync {
 impl<M: Message> MessageDispatcherInterface for MessageDispatcher<M> {
     /// Internal function to deserialize data into a message type
     /// and dispatch it across subscriber channels. Reads directly
-    /// from an inbound stream.
+    /// from an inbound stream, through the channel's decoder so any
+    /// bytes it already buffered are read first.
     ///
     /// We extract the message length from the stream and use `take()`
     /// to allocate an appropiately sized buffer as a basic DDOS protection.
-    async fn trigger(&self, stream: &mut smol::io::ReadHalf<Box<dyn PtStream + 'static>>) {
+    async fn trigger(&self, stream: &mut AsyncDecoder<smol::io::ReadHalf<Box<dyn PtStream>>>) {
         match VarInt::decode_async(stream).await {
             Ok(int) => {
                 // TODO: check the message length does not exceed some bound.
@@ -287,7 +288,7 @@ impl MessageSubsystem {
     pub async fn notify(
         &self,
         command: &str,
-        reader: &mut smol::io::ReadHalf<Box<dyn PtStream + 'static>>,
+        reader: &mut AsyncDecoder<smol::io::ReadHalf<Box<dyn PtStream>>>,
     ) -> Result<()> {
         let Some(dispatcher) = self.dispatchers.lock().await.get(command).cloned() else {
             return Err(Error::MissingDispatcher)
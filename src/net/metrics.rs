@@ -0,0 +1,210 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2025 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Channel establishment metrics.
+//!
+//! Records outbound connection attempts, handshake durations and failure
+//! reasons, grouped by transport. These help diagnosing situations where
+//! e.g. seeds connect fine but outbound slots never fill up.
+
+use std::{collections::HashMap, sync::Mutex, time::Duration};
+
+use url::Url;
+
+use crate::Error;
+
+/// Upper bounds of the handshake duration histogram buckets, in milliseconds.
+/// Durations above the last bound are counted in an extra overflow bucket.
+pub const HANDSHAKE_BUCKETS_MS: [u64; 8] = [50, 100, 250, 500, 1000, 2500, 5000, 10000];
+
+/// Channel establishment statistics of a single transport
+#[derive(Clone, Debug, Default)]
+pub struct TransportStats {
+    /// Outbound connection attempts
+    pub connect_attempts: u64,
+    /// Outbound connection attempts that failed before handshaking
+    pub connect_failures: u64,
+    /// Successful handshakes, both inbound and outbound
+    pub handshake_successes: u64,
+    /// Failed handshakes, both inbound and outbound
+    pub handshake_failures: u64,
+    /// Handshake durations histogram, using [`HANDSHAKE_BUCKETS_MS`]
+    pub handshake_histogram: [u64; HANDSHAKE_BUCKETS_MS.len() + 1],
+    /// Sum of all handshake durations, in milliseconds
+    pub handshake_total_ms: u64,
+    /// Connection and handshake failure kinds, along with their counts
+    pub failure_reasons: HashMap<String, u64>,
+}
+
+impl TransportStats {
+    /// Ratio of channel establishments that resulted in a successful handshake
+    pub fn success_rate(&self) -> f64 {
+        let total = self.connect_failures + self.handshake_successes + self.handshake_failures;
+        if total == 0 {
+            return 0.0
+        }
+
+        self.handshake_successes as f64 / total as f64
+    }
+
+    /// Average handshake duration, in milliseconds
+    pub fn handshake_avg_ms(&self) -> f64 {
+        let total = self.handshake_successes + self.handshake_failures;
+        if total == 0 {
+            return 0.0
+        }
+
+        self.handshake_total_ms as f64 / total as f64
+    }
+
+    fn record_failure(&mut self, err: &Error) {
+        *self.failure_reasons.entry(failure_kind(err)).or_insert(0) += 1;
+    }
+}
+
+/// Name the kind of a failure, so failures get grouped regardless of the
+/// addresses or other details their messages carry. IO errors are named
+/// by their [`std::io::ErrorKind`], anything else by its [`Error`] variant.
+pub fn failure_kind(err: &Error) -> String {
+    if let Error::Io(kind) = err {
+        return format!("{kind:?}")
+    }
+
+    let name = format!("{err:?}");
+    match name.find(|c: char| !c.is_alphanumeric()) {
+        Some(end) => name[..end].to_string(),
+        None => name,
+    }
+}
+
+/// Registry of channel establishment metrics, keyed by transport
+#[derive(Default)]
+pub struct Metrics {
+    transports: Mutex<HashMap<String, TransportStats>>,
+}
+
+impl Metrics {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn with_transport(&self, addr: &Url, f: impl FnOnce(&mut TransportStats)) {
+        let mut transports = self.transports.lock().unwrap();
+        f(transports.entry(addr.scheme().to_string()).or_default())
+    }
+
+    /// Record an outbound connection attempt to the given address
+    pub fn record_connect_attempt(&self, addr: &Url) {
+        self.with_transport(addr, |stats| stats.connect_attempts += 1)
+    }
+
+    /// Record a failed outbound connection attempt to the given address
+    pub fn record_connect_failure(&self, addr: &Url, err: &Error) {
+        self.with_transport(addr, |stats| {
+            stats.connect_failures += 1;
+            stats.record_failure(err);
+        })
+    }
+
+    /// Record a finished handshake with the given address, along with its
+    /// duration and error, if any.
+    pub fn record_handshake(&self, addr: &Url, duration: Duration, err: Option<&Error>) {
+        let duration_ms = duration.as_millis() as u64;
+        let bucket = HANDSHAKE_BUCKETS_MS
+            .iter()
+            .position(|bound| duration_ms <= *bound)
+            .unwrap_or(HANDSHAKE_BUCKETS_MS.len());
+
+        self.with_transport(addr, |stats| {
+            stats.handshake_histogram[bucket] += 1;
+            stats.handshake_total_ms += duration_ms;
+            match err {
+                Some(err) => {
+                    stats.handshake_failures += 1;
+                    stats.record_failure(err);
+                }
+                None => stats.handshake_successes += 1,
+            }
+        })
+    }
+
+    /// Get a snapshot of all the recorded transport statistics
+    pub fn transport_stats(&self) -> HashMap<String, TransportStats> {
+        self.transports.lock().unwrap().clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::ErrorKind;
+
+    use super::*;
+
+    #[test]
+    fn metrics_failure_kind() {
+        assert_eq!(failure_kind(&Error::Io(ErrorKind::ConnectionRefused)), "ConnectionRefused");
+        assert_eq!(failure_kind(&Error::Io(ErrorKind::TimedOut)), "TimedOut");
+        assert_eq!(failure_kind(&Error::ChannelTimeout), "ChannelTimeout");
+        assert_eq!(failure_kind(&Error::ConnectFailed), "ConnectFailed");
+        assert_eq!(failure_kind(&Error::Custom("peer 127.0.0.1 failed".to_string())), "Custom");
+        assert_eq!(failure_kind(&Error::Custom("peer 127.0.0.2 failed".to_string())), "Custom");
+    }
+
+    #[test]
+    fn metrics_transport_stats() {
+        let metrics = Metrics::new();
+        let foo = Url::parse("tcp+tls://127.0.0.1:1111").unwrap();
+        let bar = Url::parse("tcp+tls://127.0.0.2:2222").unwrap();
+        let tor = Url::parse("tor://foo.onion:3333").unwrap();
+
+        // Failures with differing details are grouped by their kind
+        metrics.record_connect_attempt(&foo);
+        metrics.record_connect_attempt(&bar);
+        metrics.record_connect_attempt(&tor);
+        metrics.record_connect_failure(&foo, &Error::Io(ErrorKind::ConnectionRefused));
+        metrics.record_connect_failure(&bar, &Error::Io(ErrorKind::ConnectionRefused));
+        metrics.record_handshake(&foo, Duration::from_millis(40), None);
+        metrics.record_handshake(&bar, Duration::from_millis(300), None);
+        metrics.record_handshake(&foo, Duration::from_secs(20), Some(&Error::ChannelTimeout));
+
+        let stats = metrics.transport_stats();
+        assert_eq!(stats.len(), 2);
+        let tls = &stats["tcp+tls"];
+        assert_eq!(tls.connect_attempts, 2);
+        assert_eq!(tls.connect_failures, 2);
+        assert_eq!(tls.handshake_successes, 2);
+        assert_eq!(tls.handshake_failures, 1);
+        assert_eq!(tls.success_rate(), 0.4);
+        assert_eq!(tls.handshake_total_ms, 20340);
+        assert_eq!(tls.handshake_avg_ms(), 6780.0);
+        assert_eq!(tls.handshake_histogram, [1, 0, 0, 1, 0, 0, 0, 0, 1]);
+        assert_eq!(
+            tls.failure_reasons,
+            HashMap::from([
+                ("ConnectionRefused".to_string(), 2),
+                ("ChannelTimeout".to_string(), 1)
+            ])
+        );
+
+        // Transports without any finished establishment have no rates yet
+        let tor = &stats["tor"];
+        assert_eq!(tor.connect_attempts, 1);
+        assert_eq!(tor.success_rate(), 0.0);
+        assert_eq!(tor.handshake_avg_ms(), 0.0);
+    }
+}
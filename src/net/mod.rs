@@ -69,6 +69,12 @@ pub mod hosts;
 pub mod channel;
 pub use channel::ChannelPtr;
 
+/// Multiplexed streams over a single channel. Lets several protocol
+/// conversations with the same peer proceed concurrently, each with
+/// its own flow control window.
+pub mod stream;
+pub use stream::{Stream, StreamPtr};
+
 /// P2P provides all core functionality to interact with the P2P network.
 ///
 /// Used to create a network, to start and run it, to broadcast messages
@@ -124,6 +130,8 @@ pub mod connector;
 pub mod settings;
 pub use settings::{BanPolicy, Settings};
 
+pub mod metrics;
+
 /// Optional events based debug-notify subsystem. Off by default. Enabled in P2P instance,
 /// and then call `p2p.dnet_sub()` to start receiving events.
 #[macro_use]
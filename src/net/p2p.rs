@@ -21,6 +21,11 @@ use std::sync::{
     Arc,
 };
 
+#[cfg(feature = "p2p-tor")]
+use futures::{
+    future::{select, Either},
+    pin_mut,
+};
 use futures::{stream::FuturesUnordered, TryFutureExt};
 use futures_rustls::rustls::crypto::{ring, CryptoProvider};
 use log::{debug, error, info, warn};
@@ -29,9 +34,10 @@ use url::Url;
 
 use super::{
     channel::ChannelPtr,
-    dnet::DnetEvent,
+    dnet::{DnetEvent, DnetHistory},
     hosts::{Hosts, HostsPtr},
     message::{Message, SerializedMessage},
+    metrics::Metrics,
     protocol::{protocol_registry::ProtocolRegistry, register_default_protocols},
     session::{
         InboundSession, InboundSessionPtr, ManualSession, ManualSessionPtr, OutboundSession,
@@ -40,11 +46,19 @@ use super::{
     settings::Settings,
 };
 use crate::{
-    system::{ExecutorPtr, Publisher, PublisherPtr, Subscription},
+    system::{trace, ExecutorPtr, Publisher, PublisherPtr, Subscription},
     util::path::expand_path,
     Result,
 };
 
+#[cfg(feature = "p2p-tor")]
+use super::{dnet::TorBootstrap, transport::tor};
+#[cfg(feature = "p2p-tor")]
+use crate::{
+    system::{StoppableTask, StoppableTaskPtr},
+    Error,
+};
+
 #[cfg(target_family = "unix")]
 use smol::fs::unix::PermissionsExt;
 
@@ -75,6 +89,13 @@ pub struct P2p {
     pub dnet_enabled: AtomicBool,
     /// The publisher for which we can give dnet info over
     dnet_publisher: PublisherPtr<DnetEvent>,
+    /// Recent dnet events, kept for later inspection
+    dnet_history: DnetHistory,
+    /// Channel establishment metrics
+    metrics: Metrics,
+    /// Task bootstrapping the embedded Tor client and reporting its progress
+    #[cfg(feature = "p2p-tor")]
+    tor_bootstrap_task: std::sync::Mutex<Option<StoppableTaskPtr>>,
 }
 
 impl P2p {
@@ -99,6 +120,8 @@ impl P2p {
         // Register a CryptoProvider for rustls
         let _ = CryptoProvider::install_default(ring::default_provider());
 
+        let dnet_history = DnetHistory::new(settings.dnet_history);
+
         // Wrap the Settings into an Arc<RwLock>
         let settings = Arc::new(AsyncRwLock::new(settings));
 
@@ -114,6 +137,10 @@ impl P2p {
             session_seedsync: SeedSyncSession::new(p2p.clone()),
             dnet_enabled: AtomicBool::new(false),
             dnet_publisher: Publisher::new(),
+            dnet_history,
+            metrics: Metrics::new(),
+            #[cfg(feature = "p2p-tor")]
+            tor_bootstrap_task: std::sync::Mutex::new(None),
         });
 
         register_default_protocols(self_.clone()).await;
@@ -127,6 +154,32 @@ impl P2p {
                self.settings.read().await.magic_bytes.0);
         info!(target: "net::p2p::start", "[P2P] Starting P2P subsystem");
 
+        // Start bootstrapping the embedded Tor client right away, if it's
+        // going to be used, so its progress can be followed over dnet.
+        #[cfg(feature = "p2p-tor")]
+        {
+            let settings = self.settings.read().await;
+            if uses_embedded_tor(&settings) {
+                let datastore = settings.p2p_datastore.clone();
+                let task = StoppableTask::new();
+                task.clone().start(
+                    self.clone().tor_bootstrap(datastore),
+                    |res| async {
+                        match res {
+                            Ok(()) | Err(Error::DetachedTaskStopped) => { /* Do nothing */ }
+                            Err(e) => error!(
+                                target: "net::p2p::start",
+                                "[P2P] Failed bootstrapping Tor: {}", e,
+                            ),
+                        }
+                    },
+                    Error::DetachedTaskStopped,
+                    self.executor.clone(),
+                );
+                *self.tor_bootstrap_task.lock().unwrap() = Some(task);
+            }
+        }
+
         // Start the inbound session
         if let Err(err) = self.session_inbound().start().await {
             error!(target: "net::p2p::start", "Failed to start inbound session!: {}", err);
@@ -168,6 +221,54 @@ impl P2p {
         self.session_seedsync().stop().await;
         self.session_outbound().stop().await;
         self.session_refine().stop().await;
+
+        #[cfg(feature = "p2p-tor")]
+        {
+            let task = self.tor_bootstrap_task.lock().unwrap().take();
+            if let Some(task) = task {
+                task.stop().await;
+            }
+        }
+    }
+
+    /// Bootstrap the embedded Tor client, reporting its progress as
+    /// dnet events until it is ready for traffic.
+    #[cfg(feature = "p2p-tor")]
+    async fn tor_bootstrap(self: Arc<Self>, datastore: Option<String>) -> Result<()> {
+        let events = tor::bootstrap_events(&datastore).await?;
+        let report = async {
+            pin_mut!(events);
+            while let Some(info) = events.next().await {
+                self.tor_bootstrap_notify(info).await;
+            }
+        };
+
+        let bootstrap = tor::bootstrap(&datastore);
+        pin_mut!(bootstrap);
+        pin_mut!(report);
+
+        match select(bootstrap, report).await {
+            Either::Left((res, _)) => res?,
+            Either::Right((_, bootstrap)) => bootstrap.await?,
+        }
+
+        // The final status change may not have been reported yet
+        self.tor_bootstrap_notify(tor::bootstrap_status(&datastore).await?).await;
+        info!(target: "net::p2p::tor_bootstrap", "[P2P] Tor client bootstrapped");
+
+        Ok(())
+    }
+
+    /// Log a Tor bootstrap status change and notify it over dnet
+    #[cfg(feature = "p2p-tor")]
+    async fn tor_bootstrap_notify(&self, info: TorBootstrap) {
+        debug!(
+            target: "net::p2p::tor_bootstrap",
+            "[P2P] Tor bootstrap {:.0}%: {}", info.progress * 100.0, info.status,
+        );
+        if self.dnet_active() {
+            self.dnet_notify(DnetEvent::TorBootstrap(info)).await;
+        }
     }
 
     /// Broadcasts a message concurrently across all active peers.
@@ -195,6 +296,12 @@ impl P2p {
             return
         }
 
+        debug!(
+            target: "net::p2p::broadcast()",
+            "{}[P2P] Broadcasting {} to {} channels",
+            trace::log_prefix(), M::NAME, channel_list.len(),
+        );
+
         let message = SerializedMessage::new(message).await;
         let futures = FuturesUnordered::new();
 
@@ -202,8 +309,8 @@ impl P2p {
             futures.push(channel.send_serialized(&message).map_err(|e| {
                 error!(
                     target: "net::p2p::broadcast()",
-                    "[P2P] Broadcasting message to {} failed: {}",
-                    channel.address(), e
+                    "{}[P2P] Broadcasting message to {} failed: {}",
+                    trace::log_prefix(), channel.address(), e
                 );
                 // If the channel is stopped then it should automatically die
                 // and the session will remove it from p2p.
@@ -282,14 +389,34 @@ impl P2p {
         warn!("[P2P] Network debugging disabled!");
     }
 
+    /// Get a reference to the channel establishment metrics
+    pub fn metrics(&self) -> &Metrics {
+        &self.metrics
+    }
+
     /// Subscribe to dnet events
     pub async fn dnet_subscribe(&self) -> Subscription<DnetEvent> {
         self.dnet_publisher.clone().subscribe().await
     }
 
-    /// Send a dnet notification over the publisher
+    /// Whether dnet events should be produced, either for live
+    /// subscribers or for the recent events history
+    pub fn dnet_active(&self) -> bool {
+        self.dnet_enabled.load(Ordering::SeqCst) || self.dnet_history.is_enabled()
+    }
+
+    /// Get a reference to the recent dnet events history
+    pub fn dnet_history(&self) -> &DnetHistory {
+        &self.dnet_history
+    }
+
+    /// Record a dnet event and send a notification over the publisher
+    /// if dnet is enabled
     pub(super) async fn dnet_notify(&self, event: DnetEvent) {
-        self.dnet_publisher.notify(event).await;
+        if self.dnet_enabled.load(Ordering::SeqCst) {
+            self.dnet_publisher.notify(event.clone()).await;
+        }
+        self.dnet_history.push(event);
     }
 
     /// Grab the channel pointer of provided channel ID, if it exists.
@@ -297,3 +424,13 @@ impl P2p {
         self.hosts.get_channel(id)
     }
 }
+
+/// Whether the configured transports use the embedded Tor client, either
+/// for dialing Tor endpoints without an external proxy, or for listening
+/// on an onion service.
+#[cfg(feature = "p2p-tor")]
+fn uses_embedded_tor(settings: &Settings) -> bool {
+    let dials_tor = settings.tor_socks5_proxy.is_none() &&
+        settings.allowed_transports.iter().any(|t| t == "tor" || t == "tor+tls");
+    dials_tor || settings.inbound_addrs.iter().any(|addr| addr.scheme() == "tor")
+}
@@ -79,6 +79,15 @@ pub use protocol_seed::ProtocolSeed;
 /// or not we should propagate the message to rest nodes or skip it.
 pub mod protocol_generic;
 
+/// Reachability self-test protocol.
+///
+/// Nodes open a dial-back stream to some of their peers, asking them to
+/// connect to the node's external addresses. Each peer reports back
+/// whether the connection succeeded and how long it took, which lets a
+/// node verify it is reachable without relying on UPnP.
+pub mod protocol_dialback;
+pub use protocol_dialback::ProtocolDialback;
+
 /// Base trait for implementing P2P protocols
 pub mod protocol_base;
 /// Interface for registering arbitrary P2P protocols
@@ -89,5 +98,6 @@ pub async fn register_default_protocols(p2p: P2pPtr) {
     let registry = p2p.protocol_registry();
     registry.register(SESSION_DEFAULT | SESSION_SEED, ProtocolPing::init).await;
     registry.register(SESSION_DEFAULT, ProtocolAddress::init).await;
+    registry.register(SESSION_DEFAULT, ProtocolDialback::init).await;
     registry.register(SESSION_SEED, ProtocolSeed::init).await;
 }
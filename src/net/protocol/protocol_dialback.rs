@@ -0,0 +1,373 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2025 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use std::{
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use async_trait::async_trait;
+use darkfi_serial::{SerialDecodable, SerialEncodable};
+use futures::future::join_all;
+use log::{debug, warn};
+use rand::{rngs::OsRng, seq::SliceRandom};
+use smol::{channel::Receiver, lock::RwLock as AsyncRwLock, Executor};
+use url::Url;
+
+use super::{
+    super::{
+        channel::ChannelPtr,
+        hosts::{Hosts, HostsPtr},
+        message::Message,
+        p2p::P2pPtr,
+        settings::Settings,
+        stream::StreamPtr,
+        transport::Dialer,
+    },
+    protocol_base::{ProtocolBase, ProtocolBasePtr},
+    protocol_jobs_manager::{ProtocolJobsManager, ProtocolJobsManagerPtr},
+};
+use crate::{impl_p2p_message, system::timeout::timeout, Error, Result};
+
+/// Stream protocol name dial-back requests are sent on
+pub const DIALBACK_STREAM: &str = "dialback";
+/// Maximum number of addresses a peer may ask us to dial back at once
+pub const DIALBACK_MAX_ADDRS: usize = 8;
+/// Number of connected peers asked to dial us back in a reachability check
+pub const DIALBACK_PEERS: usize = 3;
+/// Seconds a peer has to wait between its dial-back requests
+const DIALBACK_INTERVAL: u64 = 60;
+
+/// Asks the peer to dial back the given addresses
+#[derive(Debug, Clone, SerialEncodable, SerialDecodable)]
+pub struct DialbackRequest {
+    pub addrs: Vec<Url>,
+}
+impl_p2p_message!(DialbackRequest, "dialbackreq");
+
+/// Outcome of dialing back a single address
+#[derive(Debug, Clone, SerialEncodable, SerialDecodable)]
+pub struct DialbackResult {
+    /// Address that was dialed
+    pub addr: Url,
+    /// Connect latency, `None` if the dial failed
+    pub latency: Option<Duration>,
+    /// Reason the dial failed, empty on success
+    pub error: String,
+}
+
+/// Reply to a [`DialbackRequest`], in the requested addresses order
+#[derive(Debug, Clone, SerialEncodable, SerialDecodable)]
+pub struct DialbackReply {
+    pub results: Vec<DialbackResult>,
+}
+impl_p2p_message!(DialbackReply, "dialbackrep");
+
+/// Dials back the addresses a peer asks for over a [`DIALBACK_STREAM`]
+/// stream, so it can find out whether its external addresses are
+/// reachable from the network.
+///
+/// Since we connect wherever we are told to, we must not be usable to
+/// probe arbitrary hosts, see [`check_dialback_target`]:
+/// * Clearnet addresses must point to the public host of the requesting
+///   peer. Loopback, private and other non-global hosts are refused.
+/// * Tor addresses must be `.onion` services, so the Tor exit nodes can't
+///   be used to reach clearnet hosts through us.
+/// * Requests over channels with a non-global address are refused, since
+///   we can't tell who is behind them, e.g. inbound Tor connections all
+///   appear as loopback ones.
+///
+/// Peers are also limited to one request every [`DIALBACK_INTERVAL`]
+/// seconds, with at most [`DIALBACK_MAX_ADDRS`] addresses each. Dialing
+/// only opens the transport connection, which is dropped right after,
+/// without performing a handshake.
+pub struct ProtocolDialback {
+    channel: ChannelPtr,
+    stream_listener: Receiver<StreamPtr>,
+    settings: Arc<AsyncRwLock<Settings>>,
+    hosts: HostsPtr,
+    jobsman: ProtocolJobsManagerPtr,
+}
+
+const PROTO_NAME: &str = "ProtocolDialback";
+
+impl ProtocolDialback {
+    /// Create a new dial-back protocol.
+    pub async fn init(channel: ChannelPtr, p2p: P2pPtr) -> ProtocolBasePtr {
+        let stream_listener = channel.streams().listen(DIALBACK_STREAM).await;
+
+        Arc::new(Self {
+            channel: channel.clone(),
+            stream_listener,
+            settings: p2p.settings(),
+            hosts: p2p.hosts(),
+            jobsman: ProtocolJobsManager::new(PROTO_NAME, channel),
+        })
+    }
+
+    /// Waits for dial-back streams opened by the peer, dials back the
+    /// requested addresses, and replies with the results.
+    async fn handle_dialback(self: Arc<Self>) -> Result<()> {
+        debug!(
+            target: "net::protocol_dialback::handle_dialback()",
+            "START => address={}", self.channel.address(),
+        );
+
+        let mut last_request: Option<Instant> = None;
+        loop {
+            let Ok(stream) = self.stream_listener.recv().await else {
+                return Err(Error::ChannelStopped)
+            };
+
+            let peer = self.channel.address();
+            if peer.host().is_none() || self.hosts.is_local_host(peer) {
+                warn!(
+                    target: "net::protocol_dialback::handle_dialback()",
+                    "[P2P] Refusing dial-back request from non-global peer {}", peer,
+                );
+                stream.close().await;
+                continue
+            }
+
+            if last_request.is_some_and(|t| t.elapsed() < Duration::from_secs(DIALBACK_INTERVAL)) {
+                warn!(
+                    target: "net::protocol_dialback::handle_dialback()",
+                    "[P2P] Refusing early dial-back request from {}", self.channel.address(),
+                );
+                stream.close().await;
+                continue
+            }
+            last_request = Some(Instant::now());
+
+            let connect_timeout =
+                Duration::from_secs(self.settings.read().await.outbound_connect_timeout);
+            let request = match timeout(connect_timeout, stream.receive::<DialbackRequest>()).await
+            {
+                Ok(Ok(v)) => v,
+                Ok(Err(e)) => {
+                    debug!(
+                        target: "net::protocol_dialback::handle_dialback()",
+                        "Invalid dial-back request from {}: {}", self.channel.address(), e,
+                    );
+                    stream.close().await;
+                    continue
+                }
+                Err(_) => {
+                    stream.close().await;
+                    continue
+                }
+            };
+
+            let mut results = Vec::with_capacity(request.addrs.len().min(DIALBACK_MAX_ADDRS));
+            for addr in request.addrs.into_iter().take(DIALBACK_MAX_ADDRS) {
+                results.push(self.dial_back(addr).await);
+            }
+
+            if let Err(e) = stream.send(&DialbackReply { results }).await {
+                debug!(
+                    target: "net::protocol_dialback::handle_dialback()",
+                    "Failed sending dial-back reply to {}: {}", self.channel.address(), e,
+                );
+            }
+            stream.close().await;
+        }
+    }
+
+    /// Try to open a transport connection to the given address,
+    /// measuring how long it took.
+    async fn dial_back(&self, addr: Url) -> DialbackResult {
+        let failed = |addr: Url, error: String| DialbackResult { addr, latency: None, error };
+
+        let settings = self.settings.read().await;
+        let allowed = settings.allowed_transports.contains(&addr.scheme().to_string());
+        let datastore = settings.p2p_datastore.clone();
+        let tor_socks5_proxy = settings.tor_socks5_proxy.clone();
+        let connect_timeout = Duration::from_secs(settings.outbound_connect_timeout);
+        drop(settings);
+
+        if !allowed {
+            return failed(addr, "Transport not supported".to_string())
+        }
+
+        if let Err(e) = check_dialback_target(&self.hosts, self.channel.address(), &addr) {
+            return failed(addr, e.to_string())
+        }
+
+        let dialer = match Dialer::with_tor_proxy(addr.clone(), datastore, tor_socks5_proxy).await {
+            Ok(v) => v,
+            Err(e) => return failed(addr, e.to_string()),
+        };
+
+        let start = Instant::now();
+        match dialer.dial(Some(connect_timeout)).await {
+            Ok(_) => DialbackResult { addr, latency: Some(start.elapsed()), error: String::new() },
+            Err(e) => failed(addr, e.to_string()),
+        }
+    }
+}
+
+/// Check whether the peer at `peer` may ask us to dial back `addr`.
+///
+/// `.onion` addresses can't be tied to the connection they are requested
+/// over, but are only reachable inside the Tor network. Every other
+/// address must point to the global host of the requesting peer.
+pub fn check_dialback_target(
+    hosts: &Hosts,
+    peer: &Url,
+    addr: &Url,
+) -> std::result::Result<(), &'static str> {
+    if addr.scheme().starts_with("tor") {
+        return match addr.host() {
+            Some(url::Host::Domain(d)) if d.ends_with(".onion") => Ok(()),
+            _ => Err("Tor addresses must be onion services"),
+        }
+    }
+
+    if addr.host().is_none() || hosts.is_local_host(addr) {
+        return Err("Address is not a global host")
+    }
+
+    if addr.host() != peer.host() {
+        return Err("Address does not belong to the requesting peer")
+    }
+
+    Ok(())
+}
+
+#[async_trait]
+impl ProtocolBase for ProtocolDialback {
+    async fn start(self: Arc<Self>, ex: Arc<Executor<'_>>) -> Result<()> {
+        debug!(
+            target: "net::protocol_dialback::start()",
+            "START => address={}", self.channel.address(),
+        );
+        self.jobsman.clone().start(ex.clone());
+        self.jobsman.clone().spawn(self.clone().handle_dialback(), ex).await;
+        debug!(
+            target: "net::protocol_dialback::start()",
+            "END => address={}", self.channel.address(),
+        );
+        Ok(())
+    }
+
+    fn name(&self) -> &'static str {
+        PROTO_NAME
+    }
+}
+
+/// Dial-back results of our external addresses, as reported by a peer
+pub struct PeerReachability {
+    /// Address of the peer that dialed us back
+    pub peer: Url,
+    /// Per address results, or the reason the peer didn't report any
+    pub results: Result<Vec<DialbackResult>>,
+}
+
+/// Ask up to [`DIALBACK_PEERS`] random connected peers supporting
+/// streams to dial back our external addresses, returning what each of
+/// them reported. Fails if we have no external addresses configured,
+/// or no connected peer to ask.
+pub async fn check_reachability(p2p: &P2pPtr) -> Result<Vec<PeerReachability>> {
+    let settings = p2p.settings().read().await.clone();
+    let addrs: Vec<Url> =
+        settings.external_addrs.iter().take(DIALBACK_MAX_ADDRS).cloned().collect();
+    if addrs.is_empty() {
+        return Err(Error::Custom("No external addresses configured".to_string()))
+    }
+
+    let mut peers = vec![];
+    for channel in p2p.hosts().peers() {
+        if channel.supports_streams().await {
+            peers.push(channel);
+        }
+    }
+    if peers.is_empty() {
+        return Err(Error::NetworkNotConnected)
+    }
+    peers.shuffle(&mut OsRng);
+    peers.truncate(DIALBACK_PEERS);
+
+    // The peer dials the addresses one after the other
+    let reply_timeout =
+        Duration::from_secs(settings.outbound_connect_timeout * (addrs.len() as u64 + 1));
+
+    let requests = peers.into_iter().map(|channel| {
+        let addrs = addrs.clone();
+        async move {
+            let peer = channel.address().clone();
+            let results: Result<Vec<DialbackResult>> = async {
+                let stream = channel.open_stream(DIALBACK_STREAM).await?;
+                stream.send(&DialbackRequest { addrs }).await?;
+                // Peers without the protocol, or that we asked too
+                // early, close the stream without replying.
+                let reply = match timeout(reply_timeout, stream.receive::<DialbackReply>()).await {
+                    Ok(Err(Error::StreamClosed)) => {
+                        Err(Error::Custom("Peer refused the dial-back request".to_string()))
+                    }
+                    Ok(reply) => reply,
+                    Err(_) => Err(Error::ConnectTimeout),
+                };
+                stream.close().await;
+                Ok(reply?.results)
+            }
+            .await;
+
+            PeerReachability { peer, results }
+        }
+    });
+
+    Ok(join_all(requests).await)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn dialback_targets() {
+        let hosts = Hosts::new(Arc::new(AsyncRwLock::new(Settings::default())));
+        let peer = Url::parse("tcp://93.184.216.34:43210").unwrap();
+        let check = |addr: &str| check_dialback_target(&hosts, &peer, &Url::parse(addr).unwrap());
+
+        assert!(check("tcp://93.184.216.34:26661").is_ok());
+        assert!(check("tcp+tls://93.184.216.34:26661").is_ok());
+        assert!(check(
+            "tor://abcdefghijklmnopqrstuvwxyz234567abcdefghijklmnopqrstuvwx.onion:26661"
+        )
+        .is_ok());
+
+        // Other hosts than the requesting peer
+        assert!(check("tcp://1.1.1.1:26661").is_err());
+        // Loopback and private hosts
+        assert!(check("tcp://127.0.0.1:26661").is_err());
+        assert!(check("tcp://localhost:26661").is_err());
+        assert!(check("tcp://192.168.1.1:26661").is_err());
+        assert!(check("tcp+tls://[::1]:26661").is_err());
+        // Clearnet hosts through Tor
+        assert!(check("tor://93.184.216.34:26661").is_err());
+        assert!(check("tor://example.com:26661").is_err());
+        assert!(check("tor+tls://127.0.0.1:26661").is_err());
+        // Hostless addresses
+        assert!(check("unix:///tmp/darkfi.sock").is_err());
+
+        // Non-global peers can't request clearnet dial-backs of themselves
+        let peer = Url::parse("tcp://127.0.0.1:43210").unwrap();
+        let addr = Url::parse("tcp://127.0.0.1:26661").unwrap();
+        assert!(check_dialback_target(&hosts, &peer, &addr).is_err());
+    }
+}
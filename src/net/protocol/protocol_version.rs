@@ -33,6 +33,7 @@ use super::super::{
     message::{VerackMessage, VersionMessage},
     message_publisher::MessageSubscription,
     settings::Settings,
+    stream::{STREAM_FEATURE, STREAM_VERSION},
 };
 use crate::{Error, Result};
 
@@ -166,7 +167,7 @@ impl ProtocolVersion {
             /* NOTE: `features` is a list of enabled features in the
             format Vec<(service, version)>. In the future, Protocols will
             add their own data to this field when they are attached.*/
-            features: vec![],
+            features: vec![(STREAM_FEATURE.to_string(), STREAM_VERSION)],
         };
         self.channel.send(&version).await?;
 
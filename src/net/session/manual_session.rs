@@ -85,6 +85,52 @@ impl ManualSession {
         while (futures.next().await).is_some() {}
     }
 
+    /// Add a peer to the manual session at runtime and start connecting to
+    /// it. The peer is also appended to the configured peers, so it is kept
+    /// out of our hostlists. Returns `false` if the peer is already configured.
+    pub async fn add_peer(self: Arc<Self>, addr: Url) -> bool {
+        let mut slots = self.slots.lock().await;
+        if slots.iter().any(|slot| slot.addr == addr) {
+            return false
+        }
+
+        info!(target: "net::manual_session", "[P2P] Adding manual peer [{}]", addr);
+        let settings = self.p2p().settings();
+        settings.write().await.peers.push(addr.clone());
+
+        let slot = Slot::new(Arc::downgrade(&self), addr, settings);
+        slot.clone().start().await;
+        slots.push(slot);
+
+        true
+    }
+
+    /// Remove a peer from the manual session at runtime, stopping any further
+    /// connection attempts and disconnecting from it if we are connected.
+    /// Returns `false` if the peer is not configured.
+    pub async fn remove_peer(&self, addr: &Url) -> bool {
+        let mut slots = self.slots.lock().await;
+        let Some(index) = slots.iter().position(|slot| &slot.addr == addr) else { return false };
+        let slot = slots.remove(index);
+        drop(slots);
+
+        info!(target: "net::manual_session", "[P2P] Removing manual peer [{}]", addr);
+        slot.stop().await;
+        self.p2p().settings().write().await.peers.retain(|peer| peer != addr);
+
+        for channel in self.p2p().hosts().channels() {
+            if channel.address() == addr && channel.session_type_id() & SESSION_MANUAL != 0 {
+                channel.stop().await;
+            }
+        }
+
+        // The slot might have been stopped mid-connection attempt,
+        // so free up this addr for future operations.
+        self.p2p().hosts().unregister(addr);
+
+        true
+    }
+
     /// Stops the manual session.
     pub async fn stop(&self) {
         let slots = &*self.slots.lock().await;
@@ -18,14 +18,20 @@
 
 use std::{
     sync::{Arc, Weak},
-    time::UNIX_EPOCH,
+    time::{Instant, UNIX_EPOCH},
 };
 
 use async_trait::async_trait;
 use log::{debug, trace};
 use smol::Executor;
 
-use super::{channel::ChannelPtr, hosts::HostColor, p2p::P2pPtr, protocol::ProtocolVersion};
+use super::{
+    channel::ChannelPtr,
+    dnet::{self, dnetev, DnetEvent},
+    hosts::HostColor,
+    p2p::P2pPtr,
+    protocol::ProtocolVersion,
+};
 use crate::{system::Subscription, Error, Result};
 
 pub mod inbound_session;
@@ -50,6 +56,18 @@ pub const SESSION_REFINE: SessionBitFlag = 0b10000;
 pub const SESSION_DEFAULT: SessionBitFlag = 0b00111;
 pub const SESSION_ALL: SessionBitFlag = 0b11111;
 
+/// Get the name of the given session type, as used in RPC and dnet output
+pub fn session_name(type_id: SessionBitFlag) -> &'static str {
+    match type_id {
+        SESSION_INBOUND => "inbound",
+        SESSION_OUTBOUND => "outbound",
+        SESSION_MANUAL => "manual",
+        SESSION_REFINE => "refine",
+        SESSION_SEED => "seed",
+        _ => "unknown",
+    }
+}
+
 pub type SessionWeakPtr = Weak<dyn Session + Send + Sync + 'static>;
 
 /// Removes channel from the list of connected channels when a stop signal
@@ -131,6 +149,7 @@ pub trait Session: Sync {
             "Performing handshake protocols {}", channel.clone().address(),
         );
 
+        let handshake_start = Instant::now();
         let handshake_task =
             self.perform_handshake_protocols(protocol_version, channel.clone(), executor.clone());
 
@@ -138,7 +157,21 @@ pub trait Session: Sync {
         channel.clone().start(executor.clone());
 
         // Wait for handshake to finish.
-        match handshake_task.await {
+        let handshake_result = handshake_task.await;
+        let handshake_duration = handshake_start.elapsed();
+        p2p.metrics().record_handshake(
+            channel.address(),
+            handshake_duration,
+            handshake_result.as_ref().err(),
+        );
+        dnetev!(self, ChannelHandshake, {
+            addr: channel.address().clone(),
+            session: self.type_id(),
+            duration_ms: handshake_duration.as_millis() as u64,
+            err: handshake_result.as_ref().err().map(|e| e.to_string()),
+        });
+
+        match handshake_result {
             Ok(()) => {
                 debug!(target: "net::session::register_channel()",
                 "Handshake successful {}", channel.clone().address());
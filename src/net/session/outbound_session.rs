@@ -27,9 +27,10 @@
 //! same time.
 
 use std::{
+    collections::HashMap,
     sync::{
         atomic::{AtomicU32, Ordering},
-        Arc, Weak,
+        Arc, Mutex as SyncMutex, Weak,
     },
     time::{Duration, Instant},
 };
@@ -37,6 +38,7 @@ use std::{
 use async_trait::async_trait;
 use futures::stream::{FuturesUnordered, StreamExt};
 use log::{debug, error, info, warn};
+use rand::{rngs::OsRng, Rng};
 use smol::lock::Mutex;
 use url::Url;
 
@@ -48,6 +50,7 @@ use super::{
         hosts::{HostColor, HostState},
         message::GetAddrsMessage,
         p2p::{P2p, P2pPtr},
+        settings::Settings,
     },
     Session, SessionBitFlag, SESSION_OUTBOUND,
 };
@@ -66,6 +69,8 @@ pub struct OutboundSession {
     slots: Mutex<Vec<Arc<Slot>>>,
     /// Peer discovery task
     peer_discovery: Arc<PeerDiscovery>,
+    /// Per-address connection attempts backoff
+    backoffs: AddrBackoffs,
 }
 
 impl OutboundSession {
@@ -75,6 +80,7 @@ impl OutboundSession {
             p2p,
             slots: Mutex::new(Vec::new()),
             peer_discovery: PeerDiscovery::new(session.clone()),
+            backoffs: AddrBackoffs::default(),
         })
     }
 
@@ -138,6 +144,88 @@ impl OutboundSession {
     }
 }
 
+/// Backoff state of an address we failed connecting to
+struct Backoff {
+    /// Consecutive failed connection attempts
+    failures: u32,
+    /// Time before which we don't try connecting again
+    retry_at: Instant,
+}
+
+/// Per-address exponential backoff of outbound connection attempts,
+/// with a circuit breaker pausing attempts to an address for a
+/// cool-down period after too many consecutive failures.
+#[derive(Default)]
+struct AddrBackoffs {
+    entries: SyncMutex<HashMap<Url, Backoff>>,
+}
+
+impl AddrBackoffs {
+    /// Drop the addresses we are currently backing off from
+    fn filter(&self, addrs: Vec<(Url, u64)>) -> Vec<(Url, u64)> {
+        let entries = self.entries.lock().unwrap();
+        let now = Instant::now();
+        addrs
+            .into_iter()
+            .filter(|(addr, _)| !entries.get(addr).is_some_and(|b| b.retry_at > now))
+            .collect()
+    }
+
+    /// Time left until the earliest address we back off from can be
+    /// retried, if any
+    fn next_retry(&self) -> Option<Duration> {
+        let entries = self.entries.lock().unwrap();
+        let now = Instant::now();
+        entries.values().filter(|b| b.retry_at > now).map(|b| b.retry_at - now).min()
+    }
+
+    /// Record a failed connection attempt to `addr`, returning the amount
+    /// of consecutive failures and whether its circuit breaker tripped.
+    fn failure(&self, addr: &Url, settings: &Settings) -> (u32, bool) {
+        let mut entries = self.entries.lock().unwrap();
+        let now = Instant::now();
+
+        // Forget addresses we haven't retried in a long while
+        let stale = Duration::from_secs(
+            settings.outbound_backoff_max.max(settings.outbound_circuit_breaker_cooldown),
+        );
+        entries.retain(|_, b| now.saturating_duration_since(b.retry_at) < stale);
+
+        let backoff = entries.entry(addr.clone()).or_insert(Backoff { failures: 0, retry_at: now });
+        backoff.failures = backoff.failures.saturating_add(1);
+
+        let threshold = settings.outbound_circuit_breaker_threshold;
+        let tripped = threshold > 0 && backoff.failures >= threshold;
+        let delay = if tripped {
+            Duration::from_secs(settings.outbound_circuit_breaker_cooldown)
+        } else {
+            backoff_delay(
+                backoff.failures,
+                settings.outbound_backoff_base,
+                settings.outbound_backoff_max,
+            )
+        };
+        backoff.retry_at = now + delay;
+
+        (backoff.failures, tripped)
+    }
+
+    /// Clear the backoff of an address we connected to
+    fn success(&self, addr: &Url) {
+        self.entries.lock().unwrap().remove(addr);
+    }
+}
+
+/// Exponential backoff after `failures` consecutive failures, capped at
+/// `max` seconds. Half of it is randomized, so peers failing at the same
+/// time don't retry in lockstep.
+fn backoff_delay(failures: u32, base: u64, max: u64) -> Duration {
+    let exp = failures.saturating_sub(1).min(32);
+    let delay = base.saturating_mul(1 << exp).min(max).saturating_mul(1000);
+    let half = delay / 2;
+    Duration::from_millis(half + OsRng.gen_range(0..=delay - half))
+}
+
 #[async_trait]
 impl Session for OutboundSession {
     fn p2p(&self) -> P2pPtr {
@@ -238,6 +326,9 @@ impl Slot {
             container.fetch(HostColor::Grey, &transports, transport_mixing)
         };
 
+        // Skip addresses we are backing off from
+        let addrs = self.session().backoffs.filter(addrs);
+
         hosts.check_addrs(addrs).await
     }
 
@@ -287,8 +378,14 @@ impl Slot {
                 self.wakeup_self.reset();
                 // Peer discovery
                 self.session().wakeup_peer_discovery();
-                // Wait to be woken up by peer discovery
-                self.wakeup_self.wait().await;
+                // Wait to be woken up by peer discovery, or until an
+                // address we are backing off from can be retried.
+                match self.session().backoffs.next_retry() {
+                    Some(retry) => {
+                        let _ = timeout(retry, self.wakeup_self.wait()).await;
+                    }
+                    None => self.wakeup_self.wait().await,
+                }
 
                 continue
             };
@@ -334,6 +431,8 @@ impl Slot {
                 slot, addr
             );
 
+            self.session().backoffs.success(&host);
+
             dnetev!(self, OutboundSlotConnected, {
                 slot: self.slot,
                 addr: addr.clone(),
@@ -396,12 +495,6 @@ impl Slot {
             Ok((addr_final, channel)) => Ok((addr_final, channel)),
 
             Err(err) => {
-                info!(
-                    target: "net::outbound_session::try_connect()",
-                    "[P2P] Unable to connect outbound slot #{} [{}]: {}",
-                    self.slot, addr, err
-                );
-
                 // Immediately return if the Connector has stopped.
                 // This indicates a shutdown of the P2P network and
                 // should not result in hostlist modifications.
@@ -409,6 +502,33 @@ impl Slot {
                     return Err(Error::ConnectFailed);
                 }
 
+                // Back off from this address. Only the first failure and
+                // the circuit breaker tripping are worth logging.
+                let settings = self.p2p().settings().read_arc().await;
+                let (failures, tripped) = self.session().backoffs.failure(&addr, &settings);
+                let cooldown = settings.outbound_circuit_breaker_cooldown;
+                drop(settings);
+
+                if tripped {
+                    warn!(
+                        target: "net::outbound_session::try_connect()",
+                        "[P2P] Unable to connect outbound slot #{} [{}] {} times, pausing for {}s: {}",
+                        self.slot, addr, failures, cooldown, err
+                    );
+                } else if failures == 1 {
+                    info!(
+                        target: "net::outbound_session::try_connect()",
+                        "[P2P] Unable to connect outbound slot #{} [{}]: {}",
+                        self.slot, addr, err
+                    );
+                } else {
+                    debug!(
+                        target: "net::outbound_session::try_connect()",
+                        "[P2P] Unable to connect outbound slot #{} [{}] ({} failures): {}",
+                        self.slot, addr, failures, err
+                    );
+                }
+
                 // At this point we failed to connect. We'll downgrade this peer now.
                 self.p2p().hosts().move_host(&addr, last_seen, HostColor::Grey)?;
 
@@ -654,3 +774,45 @@ impl PeerDiscoveryBase for PeerDiscovery {
         self.session().p2p()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn backoff_delay_bounds() {
+        for failures in 1..=4 {
+            let expected: u64 = 2 * (1 << (failures - 1)) * 1000;
+            let delay = backoff_delay(failures, 2, 300).as_millis() as u64;
+            assert!(delay >= expected / 2 && delay <= expected);
+        }
+
+        // Delays are capped, and don't overflow
+        assert!(backoff_delay(100, 2, 300) <= Duration::from_secs(300));
+        assert!(backoff_delay(u32::MAX, u64::MAX, u64::MAX) > Duration::ZERO);
+    }
+
+    #[test]
+    fn addr_backoffs_circuit_breaker() {
+        let settings = Settings { outbound_circuit_breaker_threshold: 3, ..Default::default() };
+        let backoffs = AddrBackoffs::default();
+        let addr = Url::parse("tcp://127.0.0.1:1234").unwrap();
+        let other = Url::parse("tcp://127.0.0.1:4321").unwrap();
+
+        // Failed addresses get filtered out until they can be retried
+        assert_eq!(backoffs.failure(&addr, &settings), (1, false));
+        let addrs = backoffs.filter(vec![(addr.clone(), 0), (other.clone(), 0)]);
+        assert_eq!(addrs, vec![(other, 0)]);
+        assert!(backoffs.next_retry().is_some());
+
+        // The circuit breaker trips after enough consecutive failures
+        assert_eq!(backoffs.failure(&addr, &settings), (2, false));
+        assert_eq!(backoffs.failure(&addr, &settings), (3, true));
+        assert!(backoffs.next_retry().unwrap() > Duration::from_secs(300));
+
+        // Connecting successfully clears the backoff
+        backoffs.success(&addr);
+        assert!(backoffs.next_retry().is_none());
+        assert_eq!(backoffs.filter(vec![(addr.clone(), 0)]), vec![(addr, 0)]);
+    }
+}
@@ -64,12 +64,25 @@ pub struct Settings {
     pub allowed_transports: Vec<String>,
     /// Allow transport mixing (e.g. Tor would be allowed to connect to `tcp://`)
     pub transport_mixing: bool,
+    /// External Tor SOCKS5 proxy used to dial `tor://` and `tor+tls://`
+    /// endpoints. The embedded Tor client is used if unset.
+    pub tor_socks5_proxy: Option<Url>,
     /// Outbound connection slots number, this many connections will be
     /// attempted. (This does not include manual connections)
     pub outbound_connections: usize,
     /// Inbound connection slots number, this many active listening connections
     /// will be allowed. (This does not include manual connections)
     pub inbound_connections: usize,
+    /// Inbound connection slots reserved for known peers (gold and whitelisted
+    /// hosts, and manual peers), so new inbound peers can't exhaust them
+    pub inbound_reserved_slots: usize,
+    /// Maximum number of new inbound connections accepted per minute from the
+    /// same address class (disabled if zero). Known peers are exempt.
+    pub inbound_rate_limit: usize,
+    /// Maximum number of new inbound connections accepted per minute from
+    /// anonymous peers, arriving through a local proxy or socket like Tor
+    /// onion services do, all sharing the same limit (disabled if zero).
+    pub inbound_anon_rate_limit: usize,
     /// Outbound connection timeout (in seconds)
     pub outbound_connect_timeout: u64,
     /// Exchange versions (handshake) timeout (in seconds)
@@ -82,6 +95,17 @@ pub struct Settings {
     pub outbound_peer_discovery_cooloff_time: u64,
     /// Time between peer discovery attempts
     pub outbound_peer_discovery_attempt_time: u64,
+    /// Initial backoff after a failed outbound connection to an
+    /// address, doubling on every consecutive failure (in seconds)
+    pub outbound_backoff_base: u64,
+    /// Maximum backoff between outbound connections to an address (in seconds)
+    pub outbound_backoff_max: u64,
+    /// Consecutive failed outbound connections to an address after which
+    /// attempts to it are paused (disabled if zero)
+    pub outbound_circuit_breaker_threshold: u32,
+    /// Time outbound connections to an address are paused for once its
+    /// circuit breaker trips (in seconds)
+    pub outbound_circuit_breaker_cooldown: u64,
     /// P2P datastore path
     pub p2p_datastore: Option<String>,
     /// Hostlist storage path
@@ -107,6 +131,9 @@ pub struct Settings {
     /// Do not ban nodes that send messages without dispatchers if set
     /// to `Relaxed`. For most uses, should be set to `Strict`.
     pub ban_policy: BanPolicy,
+    /// Amount of recent dnet events kept in memory for later inspection,
+    /// regardless of dnet being enabled (disabled if zero)
+    pub dnet_history: usize,
 }
 
 impl Default for Settings {
@@ -124,14 +151,22 @@ impl Default for Settings {
             app_version,
             allowed_transports: vec!["tcp+tls".to_string()],
             transport_mixing: true,
+            tor_socks5_proxy: None,
             outbound_connections: 8,
             inbound_connections: 8,
+            inbound_reserved_slots: 2,
+            inbound_rate_limit: 10,
+            inbound_anon_rate_limit: 60,
             outbound_connect_timeout: 15,
             channel_handshake_timeout: 10,
             channel_heartbeat_interval: 30,
             localnet: false,
             outbound_peer_discovery_cooloff_time: 30,
             outbound_peer_discovery_attempt_time: 5,
+            outbound_backoff_base: 2,
+            outbound_backoff_max: 300,
+            outbound_circuit_breaker_threshold: 5,
+            outbound_circuit_breaker_cooldown: 1800,
             p2p_datastore: None,
             hostlist: None,
             greylist_refinery_interval: 15,
@@ -141,6 +176,7 @@ impl Default for Settings {
             time_with_no_connections: 30,
             blacklist: vec![],
             ban_policy: BanPolicy::Strict,
+            dnet_history: 0,
         }
     }
 }
@@ -175,6 +211,18 @@ pub struct SettingsOpt {
     #[structopt(long = "inbound-slots")]
     pub inbound_connections: Option<usize>,
 
+    /// Inbound connection slots reserved for known peers
+    #[structopt(skip)]
+    pub inbound_reserved_slots: Option<usize>,
+
+    /// Maximum new inbound connections per minute from the same address class
+    #[structopt(skip)]
+    pub inbound_rate_limit: Option<usize>,
+
+    /// Maximum new inbound connections per minute from anonymous peers
+    #[structopt(skip)]
+    pub inbound_anon_rate_limit: Option<usize>,
+
     #[serde(default)]
     #[structopt(skip)]
     /// Magic bytes used to distinguish P2P distinct networks and
@@ -225,6 +273,12 @@ pub struct SettingsOpt {
     #[structopt(long)]
     pub transport_mixing: Option<bool>,
 
+    /// External Tor SOCKS5 proxy to dial Tor endpoints through
+    /// (e.g. socks5://127.0.0.1:9050), instead of the embedded Tor client
+    #[serde(default)]
+    #[structopt(long)]
+    pub tor_socks5_proxy: Option<Url>,
+
     /// If this is true, strictly follow the gold_connect_count and
     /// white_connect_percent settings. Otherwise, connect to greylist
     /// entries if we have no white or gold connections.
@@ -240,6 +294,25 @@ pub struct SettingsOpt {
     #[structopt(skip)]
     pub outbound_peer_discovery_attempt_time: Option<u64>,
 
+    /// Initial backoff after a failed outbound connection to an
+    /// address, doubling on every consecutive failure (in seconds)
+    #[structopt(skip)]
+    pub outbound_backoff_base: Option<u64>,
+
+    /// Maximum backoff between outbound connections to an address (in seconds)
+    #[structopt(skip)]
+    pub outbound_backoff_max: Option<u64>,
+
+    /// Consecutive failed outbound connections to an address after which
+    /// attempts to it are paused (disabled if zero)
+    #[structopt(skip)]
+    pub outbound_circuit_breaker_threshold: Option<u32>,
+
+    /// Time outbound connections to an address are paused for once its
+    /// circuit breaker trips (in seconds)
+    #[structopt(skip)]
+    pub outbound_circuit_breaker_cooldown: Option<u64>,
+
     /// P2P datastore path
     #[serde(default)]
     #[structopt(long)]
@@ -285,6 +358,10 @@ pub struct SettingsOpt {
     #[serde(default)]
     #[structopt(skip)]
     pub ban_policy: BanPolicy,
+
+    /// Amount of recent dnet events kept in memory for later inspection
+    #[structopt(skip)]
+    pub dnet_history: Option<usize>,
 }
 
 impl From<SettingsOpt> for Settings {
@@ -301,8 +378,16 @@ impl From<SettingsOpt> for Settings {
             app_version: def.app_version,
             allowed_transports: opt.allowed_transports.unwrap_or(def.allowed_transports),
             transport_mixing: opt.transport_mixing.unwrap_or(def.transport_mixing),
+            tor_socks5_proxy: opt.tor_socks5_proxy,
             outbound_connections: opt.outbound_connections.unwrap_or(def.outbound_connections),
             inbound_connections: opt.inbound_connections.unwrap_or(def.inbound_connections),
+            inbound_reserved_slots: opt
+                .inbound_reserved_slots
+                .unwrap_or(def.inbound_reserved_slots),
+            inbound_rate_limit: opt.inbound_rate_limit.unwrap_or(def.inbound_rate_limit),
+            inbound_anon_rate_limit: opt
+                .inbound_anon_rate_limit
+                .unwrap_or(def.inbound_anon_rate_limit),
             outbound_connect_timeout: opt
                 .outbound_connect_timeout
                 .unwrap_or(def.outbound_connect_timeout),
@@ -319,6 +404,14 @@ impl From<SettingsOpt> for Settings {
             outbound_peer_discovery_attempt_time: opt
                 .outbound_peer_discovery_attempt_time
                 .unwrap_or(def.outbound_peer_discovery_attempt_time),
+            outbound_backoff_base: opt.outbound_backoff_base.unwrap_or(def.outbound_backoff_base),
+            outbound_backoff_max: opt.outbound_backoff_max.unwrap_or(def.outbound_backoff_max),
+            outbound_circuit_breaker_threshold: opt
+                .outbound_circuit_breaker_threshold
+                .unwrap_or(def.outbound_circuit_breaker_threshold),
+            outbound_circuit_breaker_cooldown: opt
+                .outbound_circuit_breaker_cooldown
+                .unwrap_or(def.outbound_circuit_breaker_cooldown),
             p2p_datastore: opt.p2p_datastore,
             hostlist: opt.hostlist,
             greylist_refinery_interval: opt
@@ -332,6 +425,7 @@ impl From<SettingsOpt> for Settings {
                 .unwrap_or(def.time_with_no_connections),
             blacklist: opt.blacklist,
             ban_policy: opt.ban_policy,
+            dnet_history: opt.dnet_history.unwrap_or(def.dnet_history),
         }
     }
 }
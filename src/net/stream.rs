@@ -0,0 +1,422 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2025 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Stream multiplexing over a single [`Channel`].
+//!
+//! A stream is a lightweight, bidirectional conversation carried inside
+//! [`StreamFrame`] messages. Any number of streams can be open on a
+//! channel at the same time, so independent requests to the same peer
+//! don't have to share message subscriptions or open new connections.
+//!
+//! Streams are opened for a named protocol, and the remote end accepts
+//! them through [`StreamMux::listen`]. Each stream has its own flow
+//! control window counted in messages: a side may only have
+//! [`STREAM_WINDOW`] unacknowledged messages in flight, and the
+//! receiving side grants more credit as it consumes them. A slow
+//! stream therefore never stalls the other streams of its channel.
+//! A peer may only have [`MAX_PEER_STREAMS`] streams open on a channel
+//! at once, further ones are refused.
+
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicBool, AtomicU32, Ordering::SeqCst},
+        Arc, Weak,
+    },
+};
+
+use darkfi_serial::{
+    async_trait, deserialize_async, serialize_async, AsyncDecodable, AsyncEncodable,
+    SerialDecodable, SerialEncodable,
+};
+use log::{debug, warn};
+use smol::{
+    channel::{self, Receiver, Sender},
+    lock::Mutex,
+};
+
+use super::{
+    channel::Channel,
+    message::Message,
+    message_publisher::{MessageSubscription, MessageSubsystem},
+};
+use crate::{impl_p2p_message, Error, Result};
+
+/// Feature name advertised in the version exchange by nodes supporting streams
+pub const STREAM_FEATURE: &str = "stream";
+/// Version of the stream protocol advertised in the version exchange
+pub const STREAM_VERSION: u32 = 1;
+
+/// Number of messages a side may send on a stream before it has to
+/// wait for the receiver to grant more credit.
+pub const STREAM_WINDOW: u32 = 32;
+
+/// Maximum number of streams the peer may have open on a channel.
+pub const MAX_PEER_STREAMS: usize = 64;
+
+/// Frame opening a new stream. `command` holds the protocol name.
+const FRAME_OPEN: u8 = 0;
+/// Frame carrying a message on an open stream.
+const FRAME_DATA: u8 = 1;
+/// Frame granting `credit` more messages to the other side.
+const FRAME_CREDIT: u8 = 2;
+/// Frame closing a stream, or refusing to open it.
+const FRAME_CLOSE: u8 = 3;
+
+/// Message carrying a single stream frame over a channel.
+#[derive(Debug, Clone, SerialEncodable, SerialDecodable)]
+pub struct StreamFrame {
+    /// Stream ID, unique among the streams opened by the sender's side
+    pub id: u32,
+    /// Whether the stream was opened by the sender of this frame
+    pub opener: bool,
+    /// Frame kind
+    pub kind: u8,
+    /// Protocol name for open frames, message command for data frames
+    pub command: String,
+    /// Credit granted by credit frames
+    pub credit: u32,
+    /// Encoded message for data frames
+    pub payload: Vec<u8>,
+}
+impl_p2p_message!(StreamFrame, "streamframe");
+
+/// Streams are identified by their ID and by whether we opened them,
+/// since both sides allocate IDs independently.
+type StreamKey = (bool, u32);
+
+/// Atomic pointer to a stream
+pub type StreamPtr = Arc<Stream>;
+
+/// A single multiplexed conversation with the peer of a channel.
+pub struct Stream {
+    /// Stream ID
+    id: u32,
+    /// Whether we opened this stream
+    local: bool,
+    /// Protocol this stream was opened for
+    protocol: String,
+    /// Channel carrying this stream
+    channel: Weak<Channel>,
+    /// Messages received from the peer, waiting to be consumed
+    inbox_tx: Sender<(String, Vec<u8>)>,
+    inbox_rx: Receiver<(String, Vec<u8>)>,
+    /// Messages we are still allowed to send
+    send_credit: AtomicU32,
+    /// Wakes up senders waiting for credit
+    credit_tx: Sender<()>,
+    credit_rx: Receiver<()>,
+    /// Messages consumed since we last granted credit to the peer
+    consumed: AtomicU32,
+    /// Marks if this stream is closed
+    closed: AtomicBool,
+}
+
+impl Stream {
+    fn new(id: u32, local: bool, protocol: &str, channel: Weak<Channel>) -> StreamPtr {
+        let (inbox_tx, inbox_rx) = channel::unbounded();
+        let (credit_tx, credit_rx) = channel::bounded(1);
+        Arc::new(Self {
+            id,
+            local,
+            protocol: protocol.to_string(),
+            channel,
+            inbox_tx,
+            inbox_rx,
+            send_credit: AtomicU32::new(STREAM_WINDOW),
+            credit_tx,
+            credit_rx,
+            consumed: AtomicU32::new(0),
+            closed: AtomicBool::new(false),
+        })
+    }
+
+    fn key(&self) -> StreamKey {
+        (self.local, self.id)
+    }
+
+    /// Protocol this stream was opened for
+    pub fn protocol(&self) -> &str {
+        &self.protocol
+    }
+
+    /// Returns the channel carrying this stream, if it still exists
+    pub fn channel(&self) -> Option<Arc<Channel>> {
+        self.channel.upgrade()
+    }
+
+    pub fn is_closed(&self) -> bool {
+        self.closed.load(SeqCst)
+    }
+
+    /// Send a frame of this stream over the channel
+    async fn send_frame(
+        &self,
+        kind: u8,
+        command: String,
+        credit: u32,
+        payload: Vec<u8>,
+    ) -> Result<()> {
+        let Some(channel) = self.channel() else { return Err(Error::ChannelStopped) };
+        let frame = StreamFrame { id: self.id, opener: self.local, kind, command, credit, payload };
+        channel.send(&frame).await
+    }
+
+    /// Send a message on this stream. Waits until the peer has granted
+    /// enough credit.
+    pub async fn send<M: Message>(&self, message: &M) -> Result<()> {
+        loop {
+            if self.is_closed() {
+                return Err(Error::StreamClosed)
+            }
+
+            if self.send_credit.fetch_update(SeqCst, SeqCst, |c| c.checked_sub(1)).is_ok() {
+                break
+            }
+
+            // Woken up on new credit or on close
+            let _ = self.credit_rx.recv().await;
+        }
+
+        self.send_frame(FRAME_DATA, M::NAME.to_string(), 0, serialize_async(message).await).await
+    }
+
+    /// Receive the next message on this stream, returning its command
+    /// and encoded payload. Returns [`Error::StreamClosed`] once the
+    /// stream is closed and all received messages have been consumed.
+    pub async fn receive_raw(&self) -> Result<(String, Vec<u8>)> {
+        let Ok(message) = self.inbox_rx.recv().await else { return Err(Error::StreamClosed) };
+
+        // Grant the peer more credit once half the window is consumed
+        let consumed = self.consumed.fetch_add(1, SeqCst) + 1;
+        if consumed >= STREAM_WINDOW / 2 && !self.is_closed() {
+            self.consumed.fetch_sub(consumed, SeqCst);
+            self.send_frame(FRAME_CREDIT, String::new(), consumed, vec![]).await?;
+        }
+
+        Ok(message)
+    }
+
+    /// Receive the next message on this stream, decoded as `M`.
+    /// Returns [`Error::StreamUnexpectedMessage`] if the peer sent
+    /// another message.
+    pub async fn receive<M: Message>(&self) -> Result<M> {
+        let (command, payload) = self.receive_raw().await?;
+        if command != M::NAME {
+            return Err(Error::StreamUnexpectedMessage(command))
+        }
+
+        Ok(deserialize_async(&payload).await?)
+    }
+
+    /// Close this stream, notifying the peer.
+    pub async fn close(&self) {
+        if self.closed.swap(true, SeqCst) {
+            return
+        }
+
+        self.shutdown();
+        if let Some(channel) = self.channel() {
+            channel.streams().remove(&self.key()).await;
+        }
+        let _ = self.send_frame(FRAME_CLOSE, String::new(), 0, vec![]).await;
+    }
+
+    /// Mark the stream closed and wake up anything waiting on it.
+    /// Already received messages can still be consumed.
+    fn shutdown(&self) {
+        self.closed.store(true, SeqCst);
+        self.inbox_tx.close();
+        let _ = self.credit_tx.try_send(());
+    }
+}
+
+/// Multiplexes the streams of a channel.
+pub struct StreamMux {
+    /// Subscription to the stream frames received on the channel
+    frame_sub: MessageSubscription<StreamFrame>,
+    /// Currently open streams
+    streams: Mutex<HashMap<StreamKey, StreamPtr>>,
+    /// Queues of streams opened by the peer, by protocol name
+    listeners: Mutex<HashMap<String, Sender<StreamPtr>>>,
+    /// ID of the next stream we open
+    next_id: AtomicU32,
+}
+
+impl StreamMux {
+    pub(in crate::net) async fn new(subsystem: &MessageSubsystem) -> Self {
+        subsystem.add_dispatch::<StreamFrame>().await;
+        let frame_sub = subsystem.subscribe::<StreamFrame>().await.unwrap();
+
+        Self {
+            frame_sub,
+            streams: Mutex::new(HashMap::new()),
+            listeners: Mutex::new(HashMap::new()),
+            next_id: AtomicU32::new(0),
+        }
+    }
+
+    /// Open a new stream for `protocol` on the given channel.
+    /// The peer refuses it by closing the stream if it doesn't listen
+    /// for the protocol, in which case the stream's first receive fails.
+    pub(in crate::net) async fn open(
+        &self,
+        channel: &Arc<Channel>,
+        protocol: &str,
+    ) -> Result<StreamPtr> {
+        let id = self.next_id.fetch_add(1, SeqCst);
+        let stream = Stream::new(id, true, protocol, Arc::downgrade(channel));
+        self.streams.lock().await.insert(stream.key(), stream.clone());
+
+        debug!(
+            target: "net::stream::open()",
+            "Opening stream {} for protocol {} on {:?}", id, protocol, channel,
+        );
+
+        if let Err(e) = stream.send_frame(FRAME_OPEN, protocol.to_string(), 0, vec![]).await {
+            self.remove(&stream.key()).await;
+            return Err(e)
+        }
+
+        Ok(stream)
+    }
+
+    /// Start accepting streams the peer opens for `protocol`, returning
+    /// the queue they are delivered on. Replaces any existing listener.
+    pub async fn listen(&self, protocol: &str) -> Receiver<StreamPtr> {
+        let (tx, rx) = channel::unbounded();
+        self.listeners.lock().await.insert(protocol.to_string(), tx);
+        rx
+    }
+
+    /// Number of currently open streams, opened by either side
+    pub async fn open_streams(&self) -> usize {
+        self.streams.lock().await.len()
+    }
+
+    async fn remove(&self, key: &StreamKey) {
+        self.streams.lock().await.remove(key);
+    }
+
+    /// Dispatch received stream frames to their streams until the
+    /// channel stops.
+    pub(in crate::net) async fn run(&self, channel: Arc<Channel>) {
+        loop {
+            let frame = match self.frame_sub.receive().await {
+                Ok(v) => v,
+                Err(_) => break,
+            };
+
+            // Our own streams are the ones the peer did not open
+            let key = (!frame.opener, frame.id);
+
+            match frame.kind {
+                FRAME_OPEN => self.handle_open(&channel, key, &frame.command).await,
+
+                FRAME_DATA => {
+                    let Some(stream) = self.streams.lock().await.get(&key).cloned() else {
+                        debug!(
+                            target: "net::stream::run()",
+                            "Data for unknown stream {:?} on {:?}", key, channel,
+                        );
+                        continue
+                    };
+
+                    // The peer must respect the window we granted
+                    if stream.inbox_rx.len() >= STREAM_WINDOW as usize {
+                        warn!(
+                            target: "net::stream::run()",
+                            "Stream {:?} on {:?} exceeded its window, closing it", key, channel,
+                        );
+                        stream.close().await;
+                        continue
+                    }
+
+                    let _ =
+                        stream.inbox_tx.send((frame.command.clone(), frame.payload.clone())).await;
+                }
+
+                FRAME_CREDIT => {
+                    if let Some(stream) = self.streams.lock().await.get(&key) {
+                        let _ = stream.send_credit.fetch_update(SeqCst, SeqCst, |c| {
+                            Some(c.saturating_add(frame.credit).min(STREAM_WINDOW))
+                        });
+                        let _ = stream.credit_tx.try_send(());
+                    }
+                }
+
+                FRAME_CLOSE => {
+                    if let Some(stream) = self.streams.lock().await.remove(&key) {
+                        stream.shutdown();
+                    }
+                }
+
+                kind => {
+                    warn!(
+                        target: "net::stream::run()",
+                        "Unknown stream frame kind {} on {:?}", kind, channel,
+                    );
+                }
+            }
+        }
+
+        // Channel stopped, so close all its streams
+        for (_, stream) in self.streams.lock().await.drain() {
+            stream.shutdown();
+        }
+        self.listeners.lock().await.clear();
+    }
+
+    /// Handle the peer opening a stream, handing it to the protocol
+    /// listener or refusing it. Streams reusing the ID of an open one,
+    /// or exceeding [`MAX_PEER_STREAMS`], are refused.
+    async fn handle_open(&self, channel: &Arc<Channel>, key: StreamKey, protocol: &str) {
+        let stream = Stream::new(key.1, key.0, protocol, Arc::downgrade(channel));
+
+        let streams = self.streams.lock().await;
+        let allowed = !streams.contains_key(&key) &&
+            streams.keys().filter(|(local, _)| !local).count() < MAX_PEER_STREAMS;
+        drop(streams);
+
+        let mut listeners = self.listeners.lock().await;
+        let accepted = match listeners.get(protocol) {
+            Some(listener) if allowed => {
+                self.streams.lock().await.insert(key, stream.clone());
+                if listener.send(stream.clone()).await.is_ok() {
+                    true
+                } else {
+                    // The listener went away
+                    listeners.remove(protocol);
+                    self.remove(&key).await;
+                    false
+                }
+            }
+            _ => false,
+        };
+        drop(listeners);
+
+        if !accepted {
+            debug!(
+                target: "net::stream::handle_open()",
+                "Refusing stream {:?} for protocol {} on {:?}", key, protocol, channel,
+            );
+            stream.closed.store(true, SeqCst);
+            let _ = stream.send_frame(FRAME_CLOSE, String::new(), 0, vec![]).await;
+        }
+    }
+}
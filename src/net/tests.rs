@@ -26,7 +26,13 @@ use smol::{channel, future, Executor};
 use url::Url;
 
 use crate::{
-    net::{hosts::HostColor, P2p, Settings},
+    net::{
+        hosts::HostColor,
+        message::{PingMessage, PongMessage},
+        protocol::protocol_dialback,
+        stream::{MAX_PEER_STREAMS, STREAM_WINDOW},
+        ChannelPtr, P2p, Settings, StreamPtr,
+    },
     system::sleep,
 };
 
@@ -274,6 +280,92 @@ macro_rules! test_body {
     };
 }
 
+/// Echo every ping received on "echo" streams back as a pong.
+async fn echo_streams(channel: ChannelPtr, ex: Arc<Executor<'static>>) {
+    let listener = channel.streams().listen("echo").await;
+    while let Ok(stream) = listener.recv().await {
+        ex.spawn(async move {
+            while let Ok(ping) = stream.receive::<PingMessage>().await {
+                if stream.send(&PongMessage { nonce: ping.nonce }).await.is_err() {
+                    break
+                }
+            }
+        })
+        .detach();
+    }
+}
+
+async fn check_streams(instances: &[Arc<P2p>], ex: Arc<Executor<'static>>) {
+    for p2p in &instances[1..] {
+        for channel in p2p.hosts().peers() {
+            ex.spawn(echo_streams(channel, ex.clone())).detach();
+        }
+    }
+    sleep(1).await;
+
+    // Run concurrent streams over each channel, sending more messages
+    // than the flow control window so credit has to be granted.
+    let n_msgs = STREAM_WINDOW as u16 * 3;
+    let exchange = |stream: StreamPtr| async move {
+        let send = async {
+            for nonce in 0..n_msgs {
+                stream.send(&PingMessage { nonce }).await.unwrap();
+            }
+        };
+        let recv = async {
+            for nonce in 0..n_msgs {
+                assert_eq!(stream.receive::<PongMessage>().await.unwrap().nonce, nonce);
+            }
+        };
+        future::zip(send, recv).await;
+    };
+
+    for channel in instances[0].hosts().peers() {
+        let a = channel.open_stream("echo").await.unwrap();
+        let b = channel.open_stream("echo").await.unwrap();
+        future::zip(exchange(a.clone()), exchange(b.clone())).await;
+
+        a.close().await;
+        b.close().await;
+        assert!(a.send(&PingMessage { nonce: 0 }).await.is_err());
+
+        // Streams for protocols nobody listens for are refused
+        let refused = channel.open_stream("unknown").await.unwrap();
+        assert!(refused.receive::<PongMessage>().await.is_err());
+
+        // Streams exceeding the peer's quota are refused
+        let mut open = vec![];
+        for _ in 0..MAX_PEER_STREAMS {
+            open.push(channel.open_stream("echo").await.unwrap());
+        }
+        let refused = channel.open_stream("echo").await.unwrap();
+        assert!(refused.receive::<PongMessage>().await.is_err());
+        let last = open.last().unwrap();
+        last.send(&PingMessage { nonce: 0 }).await.unwrap();
+        assert_eq!(last.receive::<PongMessage>().await.unwrap().nonce, 0);
+        for stream in open {
+            stream.close().await;
+        }
+    }
+}
+
+async fn check_reachability(instances: &[Arc<P2p>]) {
+    for p2p in instances {
+        let external_addrs = p2p.settings().read().await.external_addrs.clone();
+        let reports = protocol_dialback::check_reachability(p2p).await.unwrap();
+        assert_eq!(reports.len(), protocol_dialback::DIALBACK_PEERS.min(N_CONNS * 2));
+
+        for report in reports {
+            let results = report.results.unwrap();
+            assert_eq!(results.len(), external_addrs.len());
+            for (result, addr) in results.iter().zip(&external_addrs) {
+                assert_eq!(&result.addr, addr);
+                assert!(result.latency.is_some(), "{}", result.error);
+            }
+        }
+    }
+}
+
 #[test]
 fn p2p_test() {
     test_body!(p2p_test_real);
@@ -463,6 +555,16 @@ async fn p2p_test_real(ex: Arc<Executor<'static>>) {
         assert!(peers.len() == N_CONNS * 2);
     }
 
+    info!("========================================================");
+    info!("Checking multiplexed streams between manual nodes...");
+    info!("========================================================");
+    check_streams(&manual_instances, ex.clone()).await;
+
+    info!("========================================================");
+    info!("Checking manual nodes are reachable through dial-backs...");
+    info!("========================================================");
+    check_reachability(&manual_instances).await;
+
     info!("========================================================");
     info!("Manual session successful! Shutting down manual test...");
     info!("========================================================");
@@ -78,6 +78,9 @@ pub enum DialerVariant {
 
     /// SOCKS5 proxy
     Socks5(socks5::Socks5Dialer),
+
+    /// SOCKS5 proxy with TLS
+    Socks5Tls(socks5::Socks5Dialer),
 }
 
 /// Listener variants
@@ -207,6 +210,41 @@ impl Dialer {
         }
     }
 
+    /// Instantiate a new [`Dialer`] like [`Dialer::new`], dialing `tor://`
+    /// and `tor+tls://` endpoints through the given external Tor SOCKS5
+    /// proxy instead of the embedded Tor client, if one is set.
+    pub async fn with_tor_proxy(
+        endpoint: Url,
+        datastore: Option<String>,
+        tor_socks5_proxy: Option<Url>,
+    ) -> io::Result<Self> {
+        let tls = match endpoint.scheme().to_lowercase().as_str() {
+            "tor" => false,
+            "tor+tls" => true,
+            _ => return Self::new(endpoint, datastore).await,
+        };
+
+        let Some(proxy) = tor_socks5_proxy else { return Self::new(endpoint, datastore).await };
+
+        enforce_hostport!(endpoint);
+        enforce_hostport!(proxy);
+
+        // Build a SOCKS5 dialer towards the Tor endpoint
+        let uri = Url::parse(&format!(
+            "socks5://{}:{}/{}:{}",
+            proxy.host_str().unwrap(),
+            proxy.port().unwrap(),
+            endpoint.host_str().unwrap(),
+            endpoint.port().unwrap(),
+        ))
+        .map_err(|_| io::Error::from(io::ErrorKind::InvalidInput))?;
+
+        let variant = socks5::Socks5Dialer::new(&uri).await?;
+        let variant =
+            if tls { DialerVariant::Socks5Tls(variant) } else { DialerVariant::Socks5(variant) };
+        Ok(Self { endpoint, variant })
+    }
+
     /// Dial an instantiated [`Dialer`]. This creates a connection and returns a stream.
     /// The Tor-based Dialer variants can panic: this is intended. There exists validation
     /// for hosts and ports in other parts of the codebase. A panic occurring here
@@ -268,7 +306,14 @@ impl Dialer {
             }
 
             DialerVariant::Socks5(dialer) => {
-                let stream = dialer.do_dial().await?;
+                let stream = dialer.do_dial(timeout).await?;
+                Ok(Box::new(stream))
+            }
+
+            DialerVariant::Socks5Tls(dialer) => {
+                let stream = dialer.do_dial(timeout).await?;
+                let tlsupgrade = tls::TlsUpgrade::new().await;
+                let stream = tlsupgrade.upgrade_dialer_tls(stream).await?;
                 Ok(Box::new(stream))
             }
         }
@@ -20,11 +20,15 @@ use std::{
     fmt::Debug,
     io,
     net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr, SocketAddrV4, SocketAddrV6},
+    time::Duration,
 };
 
-use futures::{AsyncReadExt, AsyncWriteExt};
+use futures::{
+    future::{select, Either},
+    pin_mut, AsyncReadExt, AsyncWriteExt,
+};
 use log::debug;
-use smol::net::TcpStream;
+use smol::{net::TcpStream, Timer};
 use url::Url;
 
 /// SOCKS5 dialer
@@ -61,13 +65,27 @@ impl Socks5Dialer {
     }
 
     /// Internal dial function
-    pub(crate) async fn do_dial(&self) -> io::Result<TcpStream> {
+    pub(crate) async fn do_dial(&self, timeout: Option<Duration>) -> io::Result<TcpStream> {
         debug!(
             target: "net::socks5::do_dial",
             "Dialing {:?} with SOCKS5...", self.endpoint,
         );
 
-        self.client.connect(self.endpoint.clone()).await
+        let connect = self.client.connect(self.endpoint.clone());
+
+        match timeout {
+            Some(t) => {
+                let timeout = Timer::after(t);
+                pin_mut!(timeout);
+                pin_mut!(connect);
+
+                match select(connect, timeout).await {
+                    Either::Left((res, _)) => res,
+                    Either::Right((_, _)) => Err(io::ErrorKind::TimedOut.into()),
+                }
+            }
+            None => connect.await,
+        }
     }
 }
 
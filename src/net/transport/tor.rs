@@ -25,6 +25,7 @@ use std::{
 
 use arti_client::{
     config::{onion_service::OnionServiceConfigBuilder, BoolOrAuto, TorClientConfigBuilder},
+    status::BootstrapStatus,
     DataStream, StreamPrefs, TorClient,
 };
 use async_trait::async_trait;
@@ -47,11 +48,87 @@ use tor_rtcompat::PreferredRuntime;
 use url::Url;
 
 use super::{PtListener, PtStream};
-use crate::util::path::expand_path;
+use crate::{net::dnet::TorBootstrap, util::path::expand_path};
 
 /// A static for `TorClient` reusability
 static TOR_CLIENT: OnceCell<TorClient<PreferredRuntime>> = OnceCell::new();
 
+/// Returns the embedded Tor client, creating it on first use.
+/// The client is not bootstrapped yet, see [`bootstrap`].
+async fn client(datastore: &Option<String>) -> io::Result<&'static TorClient<PreferredRuntime>> {
+    let client = TOR_CLIENT
+        .get_or_try_init(|| async {
+            let builder = TorClient::builder();
+            let builder = match datastore {
+                Some(datadir) => {
+                    let datadir = expand_path(datadir).unwrap();
+                    let config = TorClientConfigBuilder::from_directories(datadir.clone(), datadir)
+                        .build()
+                        .unwrap();
+                    builder.config(config)
+                }
+                None => builder,
+            };
+            builder.create_unbootstrapped()
+        })
+        .await;
+
+    match client {
+        Ok(client) => Ok(client),
+        Err(e) => {
+            warn!("{}", e.report());
+            Err(io::Error::new(ErrorKind::Other, "Internal Tor error, see logged warning"))
+        }
+    }
+}
+
+/// Bootstrap the embedded Tor client, creating it if needed. Returns
+/// immediately if it is already bootstrapped, and waits for the ongoing
+/// bootstrap otherwise.
+pub(crate) async fn bootstrap(datastore: &Option<String>) -> io::Result<()> {
+    let client = client(datastore).await?;
+    if client.bootstrap_status().ready_for_traffic() {
+        return Ok(())
+    }
+
+    debug!(target: "net::tor::bootstrap", "Bootstrapping...");
+    if let Err(e) = client.bootstrap().await {
+        warn!("{}", e.report());
+        return Err(io::Error::new(ErrorKind::Other, "Internal Tor error, see logged warning"))
+    }
+
+    Ok(())
+}
+
+/// Returns the embedded Tor client, bootstrapping it if needed
+async fn bootstrapped_client(
+    datastore: &Option<String>,
+) -> io::Result<&'static TorClient<PreferredRuntime>> {
+    bootstrap(datastore).await?;
+    client(datastore).await
+}
+
+/// Convert an arti bootstrap status into its dnet representation
+fn bootstrap_info(status: BootstrapStatus) -> TorBootstrap {
+    TorBootstrap {
+        progress: status.as_frac(),
+        status: status.to_string(),
+        ready: status.ready_for_traffic(),
+    }
+}
+
+/// Subscribe to the bootstrap status changes of the embedded Tor client
+pub(crate) async fn bootstrap_events(
+    datastore: &Option<String>,
+) -> io::Result<impl Stream<Item = TorBootstrap>> {
+    Ok(client(datastore).await?.bootstrap_events().map(bootstrap_info))
+}
+
+/// Current bootstrap status of the embedded Tor client
+pub(crate) async fn bootstrap_status(datastore: &Option<String>) -> io::Result<TorBootstrap> {
+    Ok(bootstrap_info(client(datastore).await?.bootstrap_status()))
+}
+
 /// Tor Dialer implementation
 #[derive(Debug, Clone)]
 pub struct TorDialer {
@@ -75,32 +152,7 @@ impl TorDialer {
 
         // Initialize or fetch the static TOR_CLIENT that should be reused in
         // the Tor dialer
-        let client = match TOR_CLIENT
-            .get_or_try_init(|| async {
-                debug!(target: "net::tor::do_dial", "Bootstrapping...");
-                if let Some(datadir) = &self.datastore {
-                    let datadir = expand_path(datadir).unwrap();
-
-                    let config = TorClientConfigBuilder::from_directories(datadir.clone(), datadir)
-                        .build()
-                        .unwrap();
-
-                    TorClient::create_bootstrapped(config).await
-                } else {
-                    TorClient::builder().create_bootstrapped().await
-                }
-            })
-            .await
-        {
-            Ok(client) => client,
-            Err(e) => {
-                warn!("{}", e.report());
-                return Err(io::Error::new(
-                    ErrorKind::Other,
-                    "Internal Tor error, see logged warning",
-                ))
-            }
-        };
+        let client = bootstrapped_client(&self.datastore).await?;
 
         let mut stream_prefs = StreamPrefs::new();
         stream_prefs.connect_to_onion_services(BoolOrAuto::Explicit(true));
@@ -167,32 +219,7 @@ impl TorListener {
     pub(crate) async fn do_listen(&self, port: u16) -> io::Result<TorListenerIntern> {
         // Initialize or fetch the static TOR_CLIENT that should be reused in
         // the Tor dialer
-        let client = match TOR_CLIENT
-            .get_or_try_init(|| async {
-                debug!(target: "net::tor::do_listen", "Bootstrapping...");
-                if let Some(datadir) = &self.datastore {
-                    let datadir = expand_path(datadir).unwrap();
-
-                    let config = TorClientConfigBuilder::from_directories(datadir.clone(), datadir)
-                        .build()
-                        .unwrap();
-
-                    TorClient::create_bootstrapped(config).await
-                } else {
-                    TorClient::builder().create_bootstrapped().await
-                }
-            })
-            .await
-        {
-            Ok(client) => client,
-            Err(e) => {
-                warn!("{}", e.report());
-                return Err(io::Error::new(
-                    ErrorKind::Other,
-                    "Internal Tor error, see logged warning",
-                ))
-            }
-        };
+        let client = bootstrapped_client(&self.datastore).await?;
 
         let hs_nick = HsNickname::new("darkfi_tor".to_string()).unwrap();
 
@@ -53,6 +53,8 @@ impl RpcClient {
     /// Instantiate a new JSON-RPC client that connects to the given endpoint.
     /// The function takes an `Executor` object, which is needed to start the
     /// `StoppableTask` which represents the client-server connection.
+    /// If the endpoint carries a password, it is used as the access token
+    /// to authenticate with the server right after connecting.
     pub async fn new(endpoint: Url, ex: Arc<Executor<'_>>) -> Result<Self> {
         // Instantiate communication channels
         let (req_send, req_recv) = channel::unbounded();
@@ -68,6 +70,13 @@ impl RpcClient {
         }
         let use_http = endpoint.scheme().starts_with("http+");
 
+        // Strip the access token, if any, so it doesn't reach the dialer
+        let auth_token = endpoint.password().map(|p| p.to_string());
+        if auth_token.is_some() {
+            let _ = dialer_url.set_username("");
+            let _ = dialer_url.set_password(None);
+        }
+
         // Instantiate Dialer and dial the server
         // TODO: Could add a timeout here
         let dialer = Dialer::new(dialer_url, None).await?;
@@ -89,7 +98,22 @@ impl RpcClient {
             ex.clone(),
         );
 
-        Ok(Self { req_send, rep_recv, task, req_skip_send })
+        let client = Self { req_send, rep_recv, task, req_skip_send };
+
+        // Authenticate with the server, see [`super::settings`]
+        if let Some(token) = auth_token {
+            let req =
+                JsonRequest::new("rpc.auth", JsonValue::Array(vec![JsonValue::String(token)]));
+            match client.request(req).await {
+                Ok(JsonValue::Boolean(true)) => {}
+                _ => {
+                    client.stop().await;
+                    return Err(Error::RpcUnauthorized)
+                }
+            }
+        }
+
+        Ok(client)
     }
 
     /// Stop the JSON-RPC client. This will trigger `stop()` on the inner
@@ -0,0 +1,137 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2025 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Graceful JSON-RPC server shutdown.
+//!
+//! Stopping the server connections right away aborts the requests being
+//! handled, so clients see truncated responses whenever the node gets
+//! restarted. With a drain in place, [`RequestHandler::stop_connections`]
+//! first refuses any new requests, waits for the in-flight ones to get
+//! replied and for the subscriptions to flush their queued notifications,
+//! up to the drain timeout, and only then closes the connections.
+//!
+//! [`RequestHandler::stop_connections`]: super::server::RequestHandler::stop_connections
+
+use std::{
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
+
+use log::{info, warn};
+use smol::channel;
+
+use crate::system::msleep;
+
+/// Interval at which the drain checks for in-flight requests
+const DRAIN_POLL_INTERVAL_MS: u64 = 50;
+
+/// Shared drain state, kept alive by the in-flight guards
+struct DrainState {
+    /// Flag indicating the server is draining
+    draining: AtomicBool,
+    /// Number of requests being handled and subscriptions being served
+    in_flight: AtomicUsize,
+    /// Closed once draining starts, waking up all the subscriptions
+    started_tx: channel::Sender<()>,
+    started_rx: channel::Receiver<()>,
+}
+
+/// Graceful shutdown state of a JSON-RPC server
+pub struct RpcDrain {
+    /// Maximum time to wait for in-flight requests to finish
+    timeout: Duration,
+    /// Shared drain state
+    state: Arc<DrainState>,
+}
+
+/// Guard marking a request or subscription as in-flight until dropped
+pub struct InFlight(Arc<DrainState>);
+
+impl Drop for InFlight {
+    fn drop(&mut self) {
+        self.0.in_flight.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+impl RpcDrain {
+    /// Create a new drain state, waiting up to `timeout` for in-flight
+    /// requests to finish on shutdown.
+    pub fn new(timeout: Duration) -> Self {
+        let (started_tx, started_rx) = channel::bounded(1);
+        let state = Arc::new(DrainState {
+            draining: AtomicBool::new(false),
+            in_flight: AtomicUsize::new(0),
+            started_tx,
+            started_rx,
+        });
+        Self { timeout, state }
+    }
+
+    /// Check if the server is draining
+    pub fn is_draining(&self) -> bool {
+        self.state.draining.load(Ordering::SeqCst)
+    }
+
+    /// Return the number of in-flight requests and subscriptions
+    pub fn in_flight(&self) -> usize {
+        self.state.in_flight.load(Ordering::SeqCst)
+    }
+
+    /// Mark a request or subscription as in-flight. Returns `None` if the
+    /// server is already draining, in which case it should be refused.
+    pub fn enter(&self) -> Option<InFlight> {
+        self.state.in_flight.fetch_add(1, Ordering::SeqCst);
+        let guard = InFlight(self.state.clone());
+        if self.is_draining() {
+            return None
+        }
+        Some(guard)
+    }
+
+    /// Wait until the server starts draining
+    pub async fn started(&self) {
+        // Nothing ever gets sent, so this only returns once closed
+        let _ = self.state.started_rx.recv().await;
+    }
+
+    /// Start draining, and wait up to the drain timeout for the in-flight
+    /// requests and subscriptions to finish. Returns `true` if everything
+    /// finished in time.
+    pub async fn drain(&self) -> bool {
+        self.state.draining.store(true, Ordering::SeqCst);
+        self.state.started_tx.close();
+
+        let start = Instant::now();
+        while self.in_flight() > 0 {
+            if start.elapsed() >= self.timeout {
+                warn!(
+                    target: "rpc::drain",
+                    "[RPC] Drain timed out with {} requests still in-flight", self.in_flight(),
+                );
+                return false
+            }
+            msleep(DRAIN_POLL_INTERVAL_MS).await;
+        }
+
+        info!(target: "rpc::drain", "[RPC] Drained in {:?}", start.elapsed());
+        true
+    }
+}
@@ -0,0 +1,304 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2025 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Crate-wide registry of JSON-RPC server error codes.
+//!
+//! Every daemon owns a range of error codes, and declares its errors as a
+//! typed enum inside that range using [`rpc_error_codes!`](crate::rpc_error_codes).
+//! Errors built from such an enum carry a structured `data` payload:
+//!
+//! ```json
+//! {"code": -32120, "message": "Blockchain is not synced",
+//!  "data": {"module": "darkfid", "kind": "NotSynced", "details": ...}}
+//! ```
+//!
+//! so clients can programmatically distinguish errors, either by their
+//! code or by `data.kind`, instead of parsing error messages.
+
+use std::collections::HashMap;
+
+use tinyjson::JsonValue;
+
+use super::jsonrpc::{ErrorCode, JsonError};
+
+/// A contiguous range of JSON-RPC server error codes owned by a module.
+/// Codes grow downwards from `start`, so the code with offset `n` is
+/// `start - n`.
+///
+/// Errors predating the registry keep the code they have always been
+/// returned with, and are declared with that (negative) code instead of
+/// an offset. Such legacy codes may fall outside the module's range, so
+/// clients should rely on `data.module` to tell them apart.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct ErrorCodeRange {
+    /// Module owning the range
+    pub module: &'static str,
+    /// First (highest) code of the range
+    pub start: i32,
+    /// Amount of codes in the range
+    pub len: i32,
+}
+
+impl ErrorCodeRange {
+    /// Return the error code with the given offset in this range.
+    /// Negative offsets are legacy codes and are returned as is.
+    pub const fn code(&self, offset: i32) -> i32 {
+        if offset < 0 {
+            return offset
+        }
+        self.start - offset
+    }
+
+    /// Check if the given error code belongs to this range
+    pub const fn contains(&self, code: i32) -> bool {
+        code <= self.start && code > self.start - self.len
+    }
+}
+
+/// darkfid error codes. Kept inside the range reserved by JSON-RPC for
+/// compatibility with the codes darkfid has always returned.
+pub const DARKFID: ErrorCodeRange = ErrorCodeRange { module: "darkfid", start: -32100, len: 250 };
+
+/// minerd error codes
+pub const MINERD: ErrorCodeRange = ErrorCodeRange { module: "minerd", start: -33000, len: 100 };
+
+/// fud error codes
+pub const FUD: ErrorCodeRange = ErrorCodeRange { module: "fud", start: -33100, len: 100 };
+
+/// taud error codes
+pub const TAUD: ErrorCodeRange = ErrorCodeRange { module: "taud", start: -33200, len: 100 };
+
+/// oracled error codes
+pub const ORACLED: ErrorCodeRange = ErrorCodeRange { module: "oracled", start: -33300, len: 100 };
+
+/// All registered error code ranges. Ranges must not overlap each other
+/// or the predefined [`ErrorCode`] values.
+pub const REGISTRY: &[ErrorCodeRange] = &[DARKFID, MINERD, FUD, TAUD, ORACLED];
+
+/// Find the module owning the given error code, if any.
+pub fn module_of(code: i32) -> Option<&'static str> {
+    REGISTRY.iter().find(|r| r.contains(code)).map(|r| r.module)
+}
+
+/// Typed RPC errors of a module, implemented by enums declared with
+/// [`rpc_error_codes!`](crate::rpc_error_codes).
+pub trait RpcErrorCode: Copy + Sized {
+    /// The error code range the errors belong to
+    const RANGE: ErrorCodeRange;
+
+    /// JSON-RPC error code
+    fn code(&self) -> i32;
+
+    /// Machine-readable error kind, used in the `data` payload
+    fn kind(&self) -> &'static str;
+
+    /// Default human-readable error message
+    fn message(&self) -> &'static str;
+
+    /// Look up the error with the given code
+    fn from_code(code: i32) -> Option<Self>;
+
+    /// Build a [`JsonError`] for this error, with an optional message
+    /// overriding the default one and optional `details` in the payload.
+    fn to_json_error(
+        &self,
+        id: u16,
+        message: Option<String>,
+        details: Option<JsonValue>,
+    ) -> JsonError {
+        let mut data = HashMap::from([
+            ("module".to_string(), JsonValue::String(Self::RANGE.module.to_string())),
+            ("kind".to_string(), JsonValue::String(self.kind().to_string())),
+        ]);
+
+        if let Some(details) = details {
+            data.insert("details".to_string(), details);
+        }
+
+        let message = message.unwrap_or_else(|| self.message().to_string());
+        JsonError::new(ErrorCode::ServerError(self.code()), Some(message), id)
+            .with_data(JsonValue::Object(data))
+    }
+}
+
+/// Declare a module's typed RPC errors inside its registered code range.
+/// Legacy errors keep their existing wire code by declaring it directly.
+///
+/// ```ignore
+/// rpc_error_codes! {
+///     /// Custom RPC errors available for darkfid.
+///     pub enum RpcError in DARKFID {
+///         NotSynced = 20 => "Blockchain is not synced",
+///         OldError = -32001 => "Error with a legacy code",
+///     }
+/// }
+/// ```
+#[macro_export]
+macro_rules! rpc_error_codes {
+    (
+        $(#[$meta:meta])*
+        $vis:vis enum $name:ident in $range:path {
+            $(
+                $(#[$vmeta:meta])*
+                $variant:ident = $offset:literal => $msg:literal,
+            )*
+        }
+    ) => {
+        $(#[$meta])*
+        #[derive(Copy, Clone, Debug, PartialEq, Eq)]
+        $vis enum $name {
+            $(
+                $(#[$vmeta])*
+                $variant,
+            )*
+        }
+
+        // Make sure all new codes fall inside the module's range
+        const _: () = { $(assert!($offset < 0 || $range.contains($range.code($offset)));)* };
+
+        impl $crate::rpc::error_code::RpcErrorCode for $name {
+            const RANGE: $crate::rpc::error_code::ErrorCodeRange = $range;
+
+            fn code(&self) -> i32 {
+                match self {
+                    $(Self::$variant => $range.code($offset),)*
+                }
+            }
+
+            fn kind(&self) -> &'static str {
+                match self {
+                    $(Self::$variant => stringify!($variant),)*
+                }
+            }
+
+            fn message(&self) -> &'static str {
+                match self {
+                    $(Self::$variant => $msg,)*
+                }
+            }
+
+            fn from_code(code: i32) -> Option<Self> {
+                $(
+                    if code == $range.code($offset) {
+                        return Some(Self::$variant)
+                    }
+                )*
+                None
+            }
+        }
+    };
+}
+
+/// Build a [`JsonResult`](crate::rpc::jsonrpc::JsonResult) from a typed
+/// RPC error inside an RPC handler.
+///
+/// ```ignore
+/// return rpc_error!(RpcError::NotSynced, id)
+/// return rpc_error!(RpcError::ParseError, id, "Invalid transaction")
+/// return rpc_error!(RpcError::ParseError, id, "Invalid transaction", details)
+/// ```
+#[macro_export]
+macro_rules! rpc_error {
+    ($err:expr, $id:expr) => {
+        $crate::rpc::jsonrpc::JsonResult::from(
+            $crate::rpc::error_code::RpcErrorCode::to_json_error(&$err, $id, None, None),
+        )
+    };
+    ($err:expr, $id:expr, $msg:expr) => {
+        $crate::rpc::jsonrpc::JsonResult::from(
+            $crate::rpc::error_code::RpcErrorCode::to_json_error(
+                &$err,
+                $id,
+                Some($msg.to_string()),
+                None,
+            ),
+        )
+    };
+    ($err:expr, $id:expr, $msg:expr, $details:expr) => {
+        $crate::rpc::jsonrpc::JsonResult::from(
+            $crate::rpc::error_code::RpcErrorCode::to_json_error(
+                &$err,
+                $id,
+                Some($msg.to_string()),
+                Some($details),
+            ),
+        )
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn registry_ranges_are_disjoint() {
+        let predefined = [
+            ErrorCode::ParseError,
+            ErrorCode::InvalidRequest,
+            ErrorCode::MethodNotFound,
+            ErrorCode::InvalidParams,
+            ErrorCode::InternalError,
+            ErrorCode::IdMismatch,
+            ErrorCode::InvalidReply,
+            ErrorCode::RateLimited,
+            ErrorCode::Unauthorized,
+            ErrorCode::ShuttingDown,
+        ];
+
+        for (i, a) in REGISTRY.iter().enumerate() {
+            assert!(a.len > 0);
+            for c in predefined {
+                assert!(!a.contains(c.code()), "{} range contains {:?}", a.module, c);
+            }
+            for b in &REGISTRY[i + 1..] {
+                assert!(
+                    !a.contains(b.start) && !b.contains(a.start),
+                    "{} overlaps {}",
+                    a.module,
+                    b.module
+                );
+            }
+        }
+    }
+
+    crate::rpc_error_codes! {
+        enum TestError in FUD {
+            Foo = 0 => "foo",
+            Bar = 42 => "bar",
+            Baz = -32101 => "baz",
+        }
+    }
+
+    #[test]
+    fn typed_error_payload() {
+        assert_eq!(TestError::Bar.code(), -33142);
+        assert_eq!(TestError::from_code(-33100), Some(TestError::Foo));
+        assert_eq!(TestError::from_code(-33101), None);
+        assert_eq!(module_of(TestError::Bar.code()), Some("fud"));
+        assert_eq!(TestError::Baz.code(), -32101);
+        assert_eq!(TestError::from_code(-32101), Some(TestError::Baz));
+
+        let err = TestError::Bar.to_json_error(1, None, Some(JsonValue::Boolean(true)));
+        let parsed = JsonError::try_from(&JsonValue::from(&err)).unwrap();
+        assert_eq!(parsed.error.code, -33142);
+        assert_eq!(parsed.error.message, "bar");
+        let data = parsed.error.data.unwrap();
+        assert_eq!(data["kind"], JsonValue::String("Bar".to_string()));
+        assert_eq!(data["details"], JsonValue::Boolean(true));
+    }
+}
@@ -95,6 +95,33 @@ impl From<net::dnet::OutboundPeerDiscovery> for JsonValue {
     }
 }
 
+#[cfg(feature = "net")]
+impl From<net::dnet::ChannelHandshake> for JsonValue {
+    fn from(info: net::dnet::ChannelHandshake) -> JsonValue {
+        let err = match info.err {
+            Some(err) => JsonStr(err),
+            None => JsonValue::Null,
+        };
+        json_map([
+            ("addr", JsonStr(info.addr.to_string())),
+            ("session", json_str(net::session::session_name(info.session))),
+            ("duration_ms", JsonNum(info.duration_ms as f64)),
+            ("err", err),
+        ])
+    }
+}
+
+#[cfg(feature = "net")]
+impl From<net::dnet::TorBootstrap> for JsonValue {
+    fn from(info: net::dnet::TorBootstrap) -> JsonValue {
+        json_map([
+            ("progress", JsonNum(info.progress.into())),
+            ("status", JsonStr(info.status)),
+            ("ready", JsonValue::Boolean(info.ready)),
+        ])
+    }
+}
+
 #[cfg(feature = "net")]
 impl From<net::dnet::DnetEvent> for JsonValue {
     fn from(event: net::dnet::DnetEvent) -> JsonValue {
@@ -126,6 +153,12 @@ impl From<net::dnet::DnetEvent> for JsonValue {
             net::dnet::DnetEvent::OutboundPeerDiscovery(info) => {
                 json_map([("event", json_str("outbound_peer_discovery")), ("info", info.into())])
             }
+            net::dnet::DnetEvent::ChannelHandshake(info) => {
+                json_map([("event", json_str("channel_handshake")), ("info", info.into())])
+            }
+            net::dnet::DnetEvent::TorBootstrap(info) => {
+                json_map([("event", json_str("tor_bootstrap")), ("info", info.into())])
+            }
         }
     }
 }
@@ -168,3 +201,14 @@ impl From<event_graph::deg::DegEvent> for JsonValue {
         }
     }
 }
+
+impl From<crate::system::logger::LogEvent> for JsonValue {
+    fn from(event: crate::system::logger::LogEvent) -> JsonValue {
+        json_map([
+            ("timestamp", JsonStr(event.timestamp.to_string())),
+            ("level", JsonStr(event.level.as_str().to_lowercase())),
+            ("target", JsonStr(event.target)),
+            ("message", JsonStr(event.message)),
+        ])
+    }
+}
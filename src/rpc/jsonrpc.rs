@@ -17,17 +17,26 @@
  */
 
 //! JSON-RPC 2.0 object definitions
-use std::collections::HashMap;
+use std::{collections::HashMap, fmt, sync::Arc};
 
 use rand::{rngs::OsRng, Rng};
 use tinyjson::JsonValue;
 
 use crate::{
     error::RpcError,
-    system::{Publisher, PublisherPtr},
+    system::{trace, Publisher, PublisherPtr},
     Result,
 };
 
+/// Extension member carrying the request correlation ID, used for
+/// tracing a request across multiple daemons.
+pub const CORRELATION_ID_FIELD: &str = "correlation_id";
+
+/// Parse the optional correlation ID extension member of a JSON object.
+fn parse_correlation_id(map: &HashMap<String, JsonValue>) -> Option<String> {
+    map.get(CORRELATION_ID_FIELD).and_then(|v| v.get::<String>()).cloned()
+}
+
 /// JSON-RPC error codes.
 /// The error codes `[-32768, -32000]` are reserved for predefined errors.
 #[derive(Copy, Clone, Debug)]
@@ -47,6 +56,12 @@ pub enum ErrorCode {
     IdMismatch,
     /// Invalid/Unexpected reply
     InvalidReply,
+    /// Client exceeded its request rate limit
+    RateLimited,
+    /// Client failed to authenticate
+    Unauthorized,
+    /// Server is shutting down and refuses new requests
+    ShuttingDown,
     /// Reserved for implementation-defined server-errors.
     ServerError(i32),
 }
@@ -61,6 +76,9 @@ impl ErrorCode {
             Self::InternalError => -32603,
             Self::IdMismatch => -32360,
             Self::InvalidReply => -32361,
+            Self::RateLimited => -32362,
+            Self::Unauthorized => -32363,
+            Self::ShuttingDown => -32364,
             Self::ServerError(c) => c,
         }
     }
@@ -74,6 +92,9 @@ impl ErrorCode {
             Self::InternalError => "internal error".to_string(),
             Self::IdMismatch => "id mismatch".to_string(),
             Self::InvalidReply => "invalid reply".to_string(),
+            Self::RateLimited => "rate limited".to_string(),
+            Self::Unauthorized => "unauthorized".to_string(),
+            Self::ShuttingDown => "server shutting down".to_string(),
             Self::ServerError(_) => "server error".to_string(),
         }
     }
@@ -112,6 +133,26 @@ impl JsonResult {
 
         Err(RpcError::InvalidJson("Invalid JSON Result".to_string()).into())
     }
+
+    /// Attach a correlation ID to the response or error this result
+    /// replies with. Other result types are returned untouched.
+    pub fn with_correlation_id(self, correlation_id: &str) -> Self {
+        match self {
+            Self::Response(mut rep) => {
+                rep.correlation_id = Some(correlation_id.to_string());
+                Self::Response(rep)
+            }
+            Self::Error(mut err) => {
+                err.correlation_id = Some(correlation_id.to_string());
+                Self::Error(err)
+            }
+            Self::SubscriberWithReply(sub, mut rep) => {
+                rep.correlation_id = Some(correlation_id.to_string());
+                Self::SubscriberWithReply(sub, rep)
+            }
+            other => other,
+        }
+    }
 }
 
 impl From<JsonResponse> for JsonResult {
@@ -156,15 +197,24 @@ pub struct JsonRequest {
     pub method: String,
     /// Request parameters
     pub params: JsonValue,
+    /// Optional correlation ID extension
+    pub correlation_id: Option<String>,
 }
 // ANCHOR_END: jsonrequest
 
 impl JsonRequest {
     /// Create a new [`JsonRequest`] object with the given method and parameters.
-    /// The request ID is chosen randomly.
+    /// The request ID is chosen randomly. When created while handling a traced
+    /// request, the correlation ID gets propagated.
     pub fn new(method: &str, params: JsonValue) -> Self {
         assert!(params.is_object() || params.is_array());
-        Self { jsonrpc: "2.0", id: OsRng::gen(&mut OsRng), method: method.to_string(), params }
+        Self {
+            jsonrpc: "2.0",
+            id: OsRng::gen(&mut OsRng),
+            method: method.to_string(),
+            params,
+            correlation_id: trace::current(),
+        }
     }
 
     /// Convert the object into a JSON string
@@ -176,12 +226,18 @@ impl JsonRequest {
 
 impl From<&JsonRequest> for JsonValue {
     fn from(req: &JsonRequest) -> JsonValue {
-        JsonValue::Object(HashMap::from([
+        let mut map = HashMap::from([
             ("jsonrpc".to_string(), JsonValue::String(req.jsonrpc.to_string())),
             ("id".to_string(), JsonValue::Number(req.id.into())),
             ("method".to_string(), JsonValue::String(req.method.clone())),
             ("params".to_string(), req.params.clone()),
-        ]))
+        ]);
+
+        if let Some(correlation_id) = &req.correlation_id {
+            map.insert(CORRELATION_ID_FIELD.to_string(), JsonValue::String(correlation_id.clone()));
+        }
+
+        JsonValue::Object(map)
     }
 }
 
@@ -233,6 +289,7 @@ impl TryFrom<&JsonValue> for JsonRequest {
             id: *map["id"].get::<f64>().unwrap() as u16,
             method: map["method"].get::<String>().unwrap().clone(),
             params: map["params"].clone(),
+            correlation_id: parse_correlation_id(map),
         })
     }
 }
@@ -326,13 +383,15 @@ pub struct JsonResponse {
     pub id: u16,
     /// Response result
     pub result: JsonValue,
+    /// Optional correlation ID extension
+    pub correlation_id: Option<String>,
 }
 
 impl JsonResponse {
     /// Create a new [`JsonResponse`] object with the given ID and result value.
     /// Creating a `JsonResponse` implies that the method call was successful.
     pub fn new(result: JsonValue, id: u16) -> Self {
-        Self { jsonrpc: "2.0", id, result }
+        Self { jsonrpc: "2.0", id, result, correlation_id: None }
     }
 
     /// Convert the object into a JSON string
@@ -344,11 +403,17 @@ impl JsonResponse {
 
 impl From<&JsonResponse> for JsonValue {
     fn from(rep: &JsonResponse) -> JsonValue {
-        JsonValue::Object(HashMap::from([
+        let mut map = HashMap::from([
             ("jsonrpc".to_string(), JsonValue::String(rep.jsonrpc.to_string())),
             ("id".to_string(), JsonValue::Number(rep.id.into())),
             ("result".to_string(), rep.result.clone()),
-        ]))
+        ]);
+
+        if let Some(correlation_id) = &rep.correlation_id {
+            map.insert(CORRELATION_ID_FIELD.to_string(), JsonValue::String(correlation_id.clone()));
+        }
+
+        JsonValue::Object(map)
     }
 }
 
@@ -387,6 +452,7 @@ impl TryFrom<&JsonValue> for JsonResponse {
             jsonrpc: "2.0",
             id: *map["id"].get::<f64>().unwrap() as u16,
             result: map["result"].clone(),
+            correlation_id: parse_correlation_id(map),
         })
     }
 }
@@ -400,15 +466,19 @@ pub struct JsonError {
     pub id: u16,
     /// JSON-RPC error (code and message)
     pub error: JsonErrorVal,
+    /// Optional correlation ID extension
+    pub correlation_id: Option<String>,
 }
 
-/// A JSON-RPC error value (code and message)
+/// A JSON-RPC error value (code, message and optional data)
 #[derive(Clone, Debug)]
 pub struct JsonErrorVal {
     /// Error code
     pub code: i32,
     /// Error message
     pub message: String,
+    /// Additional structured information about the error
+    pub data: Option<JsonValue>,
 }
 
 impl JsonError {
@@ -416,8 +486,15 @@ impl JsonError {
     /// message, and a response ID.
     /// Creating a `JsonError` implies that the method call was unsuccessful.
     pub fn new(c: ErrorCode, message: Option<String>, id: u16) -> Self {
-        let error = JsonErrorVal { code: c.code(), message: message.unwrap_or(c.message()) };
-        Self { jsonrpc: "2.0", id, error }
+        let error =
+            JsonErrorVal { code: c.code(), message: message.unwrap_or(c.message()), data: None };
+        Self { jsonrpc: "2.0", id, error, correlation_id: None }
+    }
+
+    /// Attach a structured `data` payload to the error.
+    pub fn with_data(mut self, data: JsonValue) -> Self {
+        self.error.data = Some(data);
+        self
     }
 
     /// Convert the object into a JSON string
@@ -429,16 +506,26 @@ impl JsonError {
 
 impl From<&JsonError> for JsonValue {
     fn from(err: &JsonError) -> JsonValue {
-        let errmap = JsonValue::Object(HashMap::from([
+        let mut errmap = HashMap::from([
             ("code".to_string(), JsonValue::Number(err.error.code.into())),
             ("message".to_string(), JsonValue::String(err.error.message.clone())),
-        ]));
+        ]);
 
-        JsonValue::Object(HashMap::from([
+        if let Some(data) = &err.error.data {
+            errmap.insert("data".to_string(), data.clone());
+        }
+
+        let mut map = HashMap::from([
             ("jsonrpc".to_string(), JsonValue::String(err.jsonrpc.to_string())),
             ("id".to_string(), JsonValue::Number(err.id.into())),
-            ("error".to_string(), errmap),
-        ]))
+            ("error".to_string(), JsonValue::Object(errmap)),
+        ]);
+
+        if let Some(correlation_id) = &err.correlation_id {
+            map.insert(CORRELATION_ID_FIELD.to_string(), JsonValue::String(correlation_id.clone()));
+        }
+
+        JsonValue::Object(map)
     }
 }
 
@@ -491,24 +578,61 @@ impl TryFrom<&JsonValue> for JsonError {
             error: JsonErrorVal {
                 code: *map["error"]["code"].get::<f64>().unwrap() as i32,
                 message: map["error"]["message"].get::<String>().unwrap().to_string(),
+                data: map["error"]
+                    .get::<HashMap<String, JsonValue>>()
+                    .unwrap()
+                    .get("data")
+                    .cloned(),
             },
+            correlation_id: parse_correlation_id(map),
         })
     }
 }
 
+/// Per-subscription filter applied to notifications before they are pushed
+/// to the client. Returning `None` skips the notification, otherwise the
+/// returned (possibly trimmed) notification is pushed instead.
+pub type JsonNotificationFilter =
+    Arc<dyn Fn(&JsonNotification) -> Option<JsonNotification> + Send + Sync>;
+
 /// A JSON-RPC subscriber for notifications
-#[derive(Clone, Debug)]
+#[derive(Clone)]
 pub struct JsonSubscriber {
     /// Notification method
     pub method: &'static str,
     /// Notification publisher
     pub publisher: PublisherPtr<JsonNotification>,
+    /// Optional filter of the notifications pushed to this subscription
+    pub filter: Option<JsonNotificationFilter>,
+}
+
+impl fmt::Debug for JsonSubscriber {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("JsonSubscriber")
+            .field("method", &self.method)
+            .field("filtered", &self.filter.is_some())
+            .finish()
+    }
 }
 
 impl JsonSubscriber {
     pub fn new(method: &'static str) -> Self {
         let publisher = Publisher::new();
-        Self { method, publisher }
+        Self { method, publisher, filter: None }
+    }
+
+    /// Create a subscription sharing this subscriber's publisher, which
+    /// only pushes the notifications passing the given filter.
+    pub fn with_filter(&self, filter: JsonNotificationFilter) -> Self {
+        Self { method: self.method, publisher: self.publisher.clone(), filter: Some(filter) }
+    }
+
+    /// Apply the subscription filter, if any, to the given notification
+    pub fn apply_filter(&self, notification: JsonNotification) -> Option<JsonNotification> {
+        match self.filter {
+            Some(ref filter) => filter(&notification),
+            None => Some(notification),
+        }
     }
 
     /// Send a notification to the publisher with the given JSON object
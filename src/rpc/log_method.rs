@@ -0,0 +1,193 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2025 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Runtime log level control and log event streaming over JSON-RPC,
+//! using the [`logger`](crate::system::logger) of `async_daemonize` daemons.
+//!
+//! Log records can leak sensitive information, so daemons reachable by
+//! untrusted clients should only serve these methods on an authenticated
+//! management listener, see [`RpcSettings`](super::settings::RpcSettings).
+
+use std::str::FromStr;
+
+use async_trait::async_trait;
+use log::LevelFilter;
+
+use super::{
+    jsonrpc::{ErrorCode, JsonError, JsonResponse, JsonResult, JsonSubscriber},
+    util::*,
+};
+use crate::{system::logger, Result};
+
+/// Target name used to address the default level of the runtime logger
+pub const DEFAULT_TARGET: &str = "*";
+
+/// Background task forwarding the runtime logger events as JSON-RPC
+/// notifications to the given subscriber. Events only get published
+/// once switched on through `log.switch`.
+pub async fn log_events_task(subscriber: JsonSubscriber) -> Result<()> {
+    let Some(logger) = logger::runtime() else { return Ok(()) };
+    let events = logger.subscribe();
+    while let Ok(event) = events.recv().await {
+        subscriber.notify(vec![event.into()].into()).await;
+    }
+    Ok(())
+}
+
+#[async_trait]
+pub trait HandlerLog: Sync + Send {
+    // RPCAPI:
+    // Returns the default log level, along with the level overrides of
+    // specific targets, and whether log events get streamed.
+    //
+    // --> {"jsonrpc": "2.0", "method": "log.get_levels", "params": [], "id": 1}
+    // <-- {"jsonrpc": "2.0", "result": {"default": "info", "targets": {"net::outbound_session": "debug"}, "events": false}, "id": 1}
+    async fn log_get_levels(&self, id: u16, params: JsonValue) -> JsonResult {
+        let Some(params) = params.get::<Vec<JsonValue>>() else {
+            return JsonError::new(ErrorCode::InvalidParams, None, id).into()
+        };
+        if !params.is_empty() {
+            return JsonError::new(ErrorCode::InvalidParams, None, id).into()
+        }
+
+        let Some(logger) = logger::runtime() else {
+            return JsonError::new(ErrorCode::InternalError, None, id).into()
+        };
+
+        let targets = logger
+            .target_levels()
+            .into_iter()
+            .map(|(target, level)| (target, JsonStr(level.as_str().to_lowercase())))
+            .collect();
+
+        let result = json_map([
+            ("default", JsonStr(logger.default_level().as_str().to_lowercase())),
+            ("targets", JsonObj(targets)),
+            ("events", JsonValue::Boolean(logger.events_enabled())),
+        ]);
+        JsonResponse::new(result, id).into()
+    }
+
+    // RPCAPI:
+    // Sets the log level of a target and everything under it, e.g. `net`
+    // also covers `net::outbound_session`. The most specific override wins.
+    // Use `*` as the target to set the default level instead.
+    // Levels are `off`, `error`, `warn`, `info`, `debug` and `trace`.
+    // Returns `true` on success.
+    //
+    // --> {"jsonrpc": "2.0", "method": "log.set_level", "params": ["net::outbound_session", "debug"], "id": 1}
+    // <-- {"jsonrpc": "2.0", "result": true, "id": 1}
+    async fn log_set_level(&self, id: u16, params: JsonValue) -> JsonResult {
+        let Some(params) = params.get::<Vec<JsonValue>>() else {
+            return JsonError::new(ErrorCode::InvalidParams, None, id).into()
+        };
+        if params.len() != 2 || !params[0].is_string() || !params[1].is_string() {
+            return JsonError::new(ErrorCode::InvalidParams, None, id).into()
+        }
+
+        let target = params[0].get::<String>().unwrap();
+        let Ok(level) = LevelFilter::from_str(params[1].get::<String>().unwrap()) else {
+            return JsonError::new(ErrorCode::InvalidParams, None, id).into()
+        };
+        if target.is_empty() {
+            return JsonError::new(ErrorCode::InvalidParams, None, id).into()
+        }
+
+        let Some(logger) = logger::runtime() else {
+            return JsonError::new(ErrorCode::InternalError, None, id).into()
+        };
+
+        if target == DEFAULT_TARGET {
+            logger.set_default_level(level);
+        } else {
+            logger.set_target_level(target, level);
+        }
+        JsonResponse::new(JsonValue::Boolean(true), id).into()
+    }
+
+    // RPCAPI:
+    // Removes the log level override of a target, so it falls back to the
+    // level of its closest overridden parent, or the default level.
+    // Returns `false` if the target had no override.
+    //
+    // --> {"jsonrpc": "2.0", "method": "log.reset_level", "params": ["net::outbound_session"], "id": 1}
+    // <-- {"jsonrpc": "2.0", "result": true, "id": 1}
+    async fn log_reset_level(&self, id: u16, params: JsonValue) -> JsonResult {
+        let Some(params) = params.get::<Vec<JsonValue>>() else {
+            return JsonError::new(ErrorCode::InvalidParams, None, id).into()
+        };
+        if params.len() != 1 || !params[0].is_string() {
+            return JsonError::new(ErrorCode::InvalidParams, None, id).into()
+        }
+
+        let Some(logger) = logger::runtime() else {
+            return JsonError::new(ErrorCode::InternalError, None, id).into()
+        };
+
+        let reset = logger.reset_target_level(params[0].get::<String>().unwrap());
+        JsonResponse::new(JsonValue::Boolean(reset), id).into()
+    }
+
+    // RPCAPI:
+    // Enable or disable streaming log events to `log.subscribe_events`
+    // subscribers. Only records passing the configured levels get streamed.
+    // Returns `true` on success.
+    //
+    // --> {"jsonrpc": "2.0", "method": "log.switch", "params": [true], "id": 1}
+    // <-- {"jsonrpc": "2.0", "result": true, "id": 1}
+    async fn log_switch(&self, id: u16, params: JsonValue) -> JsonResult {
+        let Some(params) = params.get::<Vec<JsonValue>>() else {
+            return JsonError::new(ErrorCode::InvalidParams, None, id).into()
+        };
+        if params.len() != 1 || !params[0].is_bool() {
+            return JsonError::new(ErrorCode::InvalidParams, None, id).into()
+        }
+
+        let Some(logger) = logger::runtime() else {
+            return JsonError::new(ErrorCode::InternalError, None, id).into()
+        };
+
+        logger.switch_events(*params[0].get::<bool>().unwrap());
+        JsonResponse::new(JsonValue::Boolean(true), id).into()
+    }
+
+    // RPCAPI:
+    // Initializes a subscription to log events, streamed once switched on
+    // through `log.switch`. `timestamp` is a `u64` (String) in milliseconds.
+    //
+    // --> {"jsonrpc": "2.0", "method": "log.subscribe_events", "params": [], "id": 1}
+    // <-- {"jsonrpc": "2.0", "method": "log.subscribe_events", "params": [{"timestamp": "1234", "level": "debug", "target": "net::outbound_session", "message": "..."}]}
+    async fn log_subscribe_events(&self, id: u16, params: JsonValue) -> JsonResult {
+        let Some(params) = params.get::<Vec<JsonValue>>() else {
+            return JsonError::new(ErrorCode::InvalidParams, None, id).into()
+        };
+        if !params.is_empty() {
+            return JsonError::new(ErrorCode::InvalidParams, None, id).into()
+        }
+
+        match self.log_subscriber() {
+            Some(subscriber) => subscriber.into(),
+            None => JsonError::new(ErrorCode::MethodNotFound, None, id).into(),
+        }
+    }
+
+    /// Subscriber fed by [`log_events_task`], if log events streaming is supported
+    fn log_subscriber(&self) -> Option<JsonSubscriber> {
+        None
+    }
+}
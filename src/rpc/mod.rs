@@ -22,6 +22,9 @@ mod common;
 /// JSON-RPC primitives
 pub mod jsonrpc;
 
+/// Registry of JSON-RPC server error codes and typed errors
+pub mod error_code;
+
 /// Client-side JSON-RPC implementation
 pub mod client;
 
@@ -37,5 +40,20 @@ pub mod from_impl;
 /// Provides optional `p2p.get_info()` method
 pub mod p2p_method;
 
+/// Provides optional `rpc.get_stats()` method and request statistics
+pub mod stats;
+
+/// Provides optional `log.*` methods for runtime log level control
+pub mod log_method;
+
+/// Per-connection client sessions, with optional `rpc.session()` methods
+pub mod session;
+
+/// JSON-RPC server listeners settings and authentication
+pub mod settings;
+
+/// Graceful server shutdown, draining in-flight requests
+pub mod drain;
+
 /// Json helper methods and types
 pub mod util;
@@ -16,27 +16,26 @@
  * along with this program.  If not, see <https://www.gnu.org/licenses/>.
  */
 
+use std::{collections::HashMap, time::Duration};
+
 use async_trait::async_trait;
+use url::Url;
 
 use super::{
-    jsonrpc::{JsonResponse, JsonResult},
+    jsonrpc::{ErrorCode, JsonError, JsonResponse, JsonResult},
     util::*,
 };
-use crate::net;
+use crate::{
+    net::{self, dnet::DnetFilter},
+    util::time::NanoTimestamp,
+};
 
 #[async_trait]
 pub trait HandlerP2p: Sync + Send {
     async fn p2p_get_info(&self, id: u16, _params: JsonValue) -> JsonResult {
         let mut channels = Vec::new();
         for channel in self.p2p().hosts().channels() {
-            let session = match channel.session_type_id() {
-                net::session::SESSION_INBOUND => "inbound",
-                net::session::SESSION_OUTBOUND => "outbound",
-                net::session::SESSION_MANUAL => "manual",
-                net::session::SESSION_REFINE => "refine",
-                net::session::SESSION_SEED => "seed",
-                _ => panic!("invalid result from channel.session_type_id()"),
-            };
+            let session = net::session::session_name(channel.session_type_id());
             channels.push(json_map([
                 ("url", JsonStr(channel.address().clone().into())),
                 ("session", json_str(session)),
@@ -54,5 +53,255 @@ pub trait HandlerP2p: Sync + Send {
         JsonResponse::new(result, id).into()
     }
 
+    // RPCAPI:
+    // Returns channel establishment statistics for every transport, consisting of
+    // outbound connection attempts and failures, handshake successes and failures,
+    // the handshake durations histogram in milliseconds, and the failure reasons.
+    //
+    // --> {"jsonrpc": "2.0", "method": "p2p.get_transport_stats", "params": [], "id": 1}
+    // <-- {"jsonrpc": "2.0", "result": {"tcp+tls": {"connect_attempts": 10, "connect_failures": 2, "handshake_successes": 7, "handshake_failures": 1, "success_rate": 0.7, "handshake_avg_ms": 312.5, "handshake_histogram": [{"le_ms": 50, "count": 0}, ..., {"le_ms": null, "count": 0}], "failure_reasons": {"ConnectionRefused": 2, ...}}, ...}, "id": 1}
+    async fn p2p_get_transport_stats(&self, id: u16, _params: JsonValue) -> JsonResult {
+        let mut transports = HashMap::new();
+        for (transport, stats) in self.p2p().metrics().transport_stats() {
+            let mut histogram = Vec::with_capacity(stats.handshake_histogram.len());
+            for (i, count) in stats.handshake_histogram.iter().enumerate() {
+                let le_ms = match net::metrics::HANDSHAKE_BUCKETS_MS.get(i) {
+                    Some(bound) => JsonNum(*bound as f64),
+                    None => JsonValue::Null,
+                };
+                histogram.push(json_map([("le_ms", le_ms), ("count", JsonNum(*count as f64))]));
+            }
+
+            let failure_reasons = stats
+                .failure_reasons
+                .iter()
+                .map(|(reason, count)| (reason.clone(), JsonNum(*count as f64)))
+                .collect();
+
+            transports.insert(
+                transport,
+                json_map([
+                    ("connect_attempts", JsonNum(stats.connect_attempts as f64)),
+                    ("connect_failures", JsonNum(stats.connect_failures as f64)),
+                    ("handshake_successes", JsonNum(stats.handshake_successes as f64)),
+                    ("handshake_failures", JsonNum(stats.handshake_failures as f64)),
+                    ("success_rate", JsonNum(stats.success_rate())),
+                    ("handshake_avg_ms", JsonNum(stats.handshake_avg_ms())),
+                    ("handshake_histogram", JsonArray(histogram)),
+                    ("failure_reasons", JsonObj(failure_reasons)),
+                ]),
+            );
+        }
+
+        JsonResponse::new(JsonObj(transports), id).into()
+    }
+
+    // RPCAPI:
+    // Asks a few random connected peers to dial back our external addresses,
+    // and returns what each of them reported, along with a per transport
+    // summary of how many peers reached us and the lowest latency observed.
+    // Peers running older versions refuse dial-back requests and report an error.
+    //
+    // --> {"jsonrpc": "2.0", "method": "p2p.check_reachability", "params": [], "id": 1}
+    // <-- {"jsonrpc": "2.0", "result": {"transports": {"tcp+tls": {"reachable": true, "confirmations": 2, "min_latency_ms": 84}, ...}, "peers": [{"peer": "tcp+tls://x.x.x.x:26661", "results": [{"addr": "tcp+tls://y.y.y.y:26661", "latency_ms": 84, "error": null}, ...]}, {"peer": "tor://abc.onion:26661", "error": "Connection timed out"}, ...]}, "id": 1}
+    async fn p2p_check_reachability(&self, id: u16, _params: JsonValue) -> JsonResult {
+        let reports = match net::protocol::protocol_dialback::check_reachability(&self.p2p()).await
+        {
+            Ok(v) => v,
+            Err(e) => {
+                return JsonError::new(ErrorCode::InternalError, Some(e.to_string()), id).into()
+            }
+        };
+
+        // transport => (confirmations, min latency)
+        let mut summary: HashMap<String, (usize, Option<Duration>)> = HashMap::new();
+        for addr in self.p2p().settings().read().await.external_addrs.iter() {
+            summary.entry(addr.scheme().to_string()).or_default();
+        }
+
+        let mut peers = Vec::with_capacity(reports.len());
+        for report in reports {
+            let peer = JsonStr(report.peer.into());
+            let results = match report.results {
+                Ok(v) => v,
+                Err(e) => {
+                    peers.push(json_map([("peer", peer), ("error", JsonStr(e.to_string()))]));
+                    continue
+                }
+            };
+
+            let mut entries = Vec::with_capacity(results.len());
+            for result in results {
+                let (latency_ms, error) = match result.latency {
+                    Some(latency) => {
+                        let entry = summary.entry(result.addr.scheme().to_string()).or_default();
+                        entry.0 += 1;
+                        entry.1 = Some(entry.1.map_or(latency, |min| min.min(latency)));
+                        (JsonNum(latency.as_millis() as f64), JsonValue::Null)
+                    }
+                    None => (JsonValue::Null, JsonStr(result.error)),
+                };
+
+                entries.push(json_map([
+                    ("addr", JsonStr(result.addr.into())),
+                    ("latency_ms", latency_ms),
+                    ("error", error),
+                ]));
+            }
+
+            peers.push(json_map([("peer", peer), ("results", JsonArray(entries))]));
+        }
+
+        let transports = summary
+            .into_iter()
+            .map(|(transport, (confirmations, min_latency))| {
+                let min_latency_ms = match min_latency {
+                    Some(latency) => JsonNum(latency.as_millis() as f64),
+                    None => JsonValue::Null,
+                };
+                let stats = json_map([
+                    ("reachable", JsonValue::Boolean(confirmations > 0)),
+                    ("confirmations", JsonNum(confirmations as f64)),
+                    ("min_latency_ms", min_latency_ms),
+                ]);
+                (transport, stats)
+            })
+            .collect();
+
+        let result = json_map([("transports", JsonObj(transports)), ("peers", JsonArray(peers))]);
+        JsonResponse::new(result, id).into()
+    }
+
+    // RPCAPI:
+    // Exports our gold, white and grey hostlists, along with each host's
+    // last seen timestamp, to the given file in the `hostlists` directory
+    // of the P2P datastore. The file can be shared out-of-band and imported
+    // by other nodes using `p2p.import_hosts`. Returns the number of
+    // exported hosts.
+    //
+    // --> {"jsonrpc": "2.0", "method": "p2p.export_hosts", "params": ["hosts.tsv"], "id": 1}
+    // <-- {"jsonrpc": "2.0", "result": 42, "id": 1}
+    async fn p2p_export_hosts(&self, id: u16, params: JsonValue) -> JsonResult {
+        let Some(name) = single_str_param(&params) else {
+            return JsonError::new(ErrorCode::InvalidParams, None, id).into()
+        };
+
+        match self.p2p().hosts().export_hosts(name).await {
+            Ok(exported) => JsonResponse::new(JsonNum(exported as f64), id).into(),
+            Err(e) => JsonError::new(ErrorCode::InternalError, Some(e.to_string()), id).into(),
+        }
+    }
+
+    // RPCAPI:
+    // Imports a curated hostlist from the given file in the `hostlists`
+    // directory of the P2P datastore, either exported by `p2p.export_hosts`
+    // or containing one peer URL per line. Imported hosts go through our
+    // greylist refinery before being connected to. Returns the number of
+    // parsed hosts.
+    //
+    // --> {"jsonrpc": "2.0", "method": "p2p.import_hosts", "params": ["hosts.tsv"], "id": 1}
+    // <-- {"jsonrpc": "2.0", "result": 42, "id": 1}
+    async fn p2p_import_hosts(&self, id: u16, params: JsonValue) -> JsonResult {
+        let Some(name) = single_str_param(&params) else {
+            return JsonError::new(ErrorCode::InvalidParams, None, id).into()
+        };
+
+        match self.p2p().hosts().import_hosts(name).await {
+            Ok(imported) => JsonResponse::new(JsonNum(imported as f64), id).into(),
+            Err(e) => JsonError::new(ErrorCode::InternalError, Some(e.to_string()), id).into(),
+        }
+    }
+
+    // RPCAPI:
+    // Adds a manual peer at runtime, which we will keep trying to connect to.
+    // Returns `false` if the peer is already configured.
+    //
+    // --> {"jsonrpc": "2.0", "method": "p2p.add_peer", "params": ["tcp+tls://foo.bar:26661"], "id": 1}
+    // <-- {"jsonrpc": "2.0", "result": true, "id": 1}
+    async fn p2p_add_peer(&self, id: u16, params: JsonValue) -> JsonResult {
+        let Some(Ok(addr)) = single_str_param(&params).map(Url::parse) else {
+            return JsonError::new(ErrorCode::InvalidParams, None, id).into()
+        };
+
+        let added = self.p2p().session_manual().add_peer(addr).await;
+        JsonResponse::new(JsonValue::Boolean(added), id).into()
+    }
+
+    // RPCAPI:
+    // Removes a manual peer at runtime, disconnecting from it if connected.
+    // Returns `false` if the peer is not configured.
+    //
+    // --> {"jsonrpc": "2.0", "method": "p2p.remove_peer", "params": ["tcp+tls://foo.bar:26661"], "id": 1}
+    // <-- {"jsonrpc": "2.0", "result": true, "id": 1}
+    async fn p2p_remove_peer(&self, id: u16, params: JsonValue) -> JsonResult {
+        let Some(Ok(addr)) = single_str_param(&params).map(Url::parse) else {
+            return JsonError::new(ErrorCode::InvalidParams, None, id).into()
+        };
+
+        let removed = self.p2p().session_manual().remove_peer(&addr).await;
+        JsonResponse::new(JsonValue::Boolean(removed), id).into()
+    }
+
+    // RPCAPI:
+    // Returns the recent dnet events kept in memory, oldest first, so they
+    // can be inspected without having been subscribed when they happened.
+    // The history size is set with the `dnet_history` P2P setting. An
+    // optional filter can restrict the event kinds, only return events
+    // recorded after a nanosecond timestamp, and/or limit the amount of
+    // returned events to the most recent ones.
+    //
+    // --> {"jsonrpc": "2.0", "method": "dnet.get_recent", "params": [{"events": ["channel_handshake"], "since": "1734000000000000000", "limit": 100}], "id": 1}
+    // <-- {"jsonrpc": "2.0", "result": [{"time": "1734000001000000000", "event": "channel_handshake", "info": {...}}, ...], "id": 1}
+    async fn dnet_get_recent(&self, id: u16, params: JsonValue) -> JsonResult {
+        let Some(filter) = dnet_filter_param(&params) else {
+            return JsonError::new(ErrorCode::InvalidParams, None, id).into()
+        };
+
+        let mut events = vec![];
+        for (time, event) in self.p2p().dnet_history().query(&filter) {
+            let mut event: JsonValue = event.into();
+            if let JsonObj(ref mut map) = event {
+                map.insert("time".to_string(), JsonStr(time.inner().to_string()));
+            }
+            events.push(event);
+        }
+
+        JsonResponse::new(JsonArray(events), id).into()
+    }
+
     fn p2p(&self) -> net::P2pPtr;
 }
+
+/// Parse request params consisting of an optional dnet history filter
+fn dnet_filter_param(params: &JsonValue) -> Option<DnetFilter> {
+    let mut filter = DnetFilter::default();
+    let map = match params.get::<Vec<JsonValue>>()?.as_slice() {
+        [] => return Some(filter),
+        [JsonObj(map)] => map,
+        _ => return None,
+    };
+
+    if let Some(events) = map.get("events") {
+        for event in events.get::<Vec<JsonValue>>()? {
+            filter.events.push(event.get::<String>()?.clone());
+        }
+    }
+
+    if let Some(since) = map.get("since") {
+        filter.since = Some(NanoTimestamp(since.get::<String>()?.parse().ok()?));
+    }
+
+    if let Some(limit) = map.get("limit") {
+        filter.limit = Some(*limit.get::<f64>()? as usize);
+    }
+
+    Some(filter)
+}
+
+/// Parse request params consisting of a single string
+fn single_str_param(params: &JsonValue) -> Option<&str> {
+    match params.get::<Vec<JsonValue>>()?.as_slice() {
+        [JsonStr(param)] => Some(param.as_str()),
+        _ => None,
+    }
+}
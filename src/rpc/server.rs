@@ -16,11 +16,17 @@
  * along with this program.  If not, see <https://www.gnu.org/licenses/>.
  */
 
-use std::{collections::HashSet, io::ErrorKind, sync::Arc};
+use std::{
+    collections::HashSet,
+    io::ErrorKind,
+    sync::Arc,
+    time::{Duration, Instant},
+};
 
 use async_trait::async_trait;
-use log::{debug, error, info};
+use log::{debug, error, info, warn};
 use smol::{
+    future,
     io::{BufReader, ReadHalf, WriteHalf},
     lock::{Mutex, MutexGuard},
 };
@@ -32,14 +38,21 @@ use super::{
         http_read_from_stream_request, http_write_to_stream, read_from_stream, write_to_stream,
         INIT_BUF_SIZE,
     },
+    drain::{InFlight, RpcDrain},
     jsonrpc::*,
+    session::{self, RpcSessions},
+    settings::RpcListenerSettings,
+    stats::RpcStats,
 };
 use crate::{
     net::transport::{Listener, PtListener, PtStream},
-    system::{StoppableTask, StoppableTaskPtr},
+    system::{timeout::timeout, trace, StoppableTask, StoppableTaskPtr, Subscription},
     Error, Result,
 };
 
+/// Time clients of an authenticated listener have to authenticate
+const AUTH_TIMEOUT: Duration = Duration::from_secs(10);
+
 /// Asynchronous trait implementing a handler for incoming JSON-RPC requests.
 #[async_trait]
 pub trait RequestHandler<T>: Sync + Send {
@@ -49,6 +62,34 @@ pub trait RequestHandler<T>: Sync + Send {
         JsonResponse::new(JsonValue::String("pong".to_string()), id).into()
     }
 
+    /// Request statistics registry. When set, every handled request's
+    /// method, latency and result get recorded into it.
+    fn stats(&self) -> Option<&RpcStats> {
+        None
+    }
+
+    /// Request tracing. When enabled, every handled request gets a
+    /// correlation ID (the client-provided one, or a fresh one), which
+    /// is exposed to the handler through [`trace::current()`], so that
+    /// downstream logs and outgoing requests carry it, and which gets
+    /// returned in the `correlation_id` extension member of the reply.
+    fn tracing(&self) -> bool {
+        false
+    }
+
+    /// Client sessions registry. When set, every connection gets its own
+    /// session, whose ID is exposed to the handler through
+    /// [`session::current()`], and whose rate limit gets enforced.
+    fn sessions(&self) -> Option<&RpcSessions> {
+        None
+    }
+
+    /// Graceful shutdown state. When set, [`RequestHandler::stop_connections`]
+    /// drains the server before closing the connections, see [`RpcDrain`].
+    fn drain(&self) -> Option<&RpcDrain> {
+        None
+    }
+
     async fn connections_mut(&self) -> MutexGuard<'life0, HashSet<StoppableTaskPtr>>;
 
     async fn connections(&self) -> Vec<StoppableTaskPtr> {
@@ -68,6 +109,11 @@ pub trait RequestHandler<T>: Sync + Send {
     }
 
     async fn stop_connections(&self) {
+        if let Some(drain) = self.drain() {
+            info!(target: "rpc::server", "[RPC] Server stopped, draining in-flight requests");
+            drain.drain().await;
+        }
+
         info!(target: "rpc::server", "[RPC] Server stopped, closing connections");
         for (i, task) in self.connections().await.iter().enumerate() {
             debug!(target: "rpc::server", "Stopping connection #{}", i);
@@ -76,7 +122,53 @@ pub trait RequestHandler<T>: Sync + Send {
     }
 }
 
+/// Closes a client session once its connection is gone.
+struct SessionGuard<'a> {
+    sessions: &'a RpcSessions,
+    id: u64,
+}
+
+impl Drop for SessionGuard<'_> {
+    fn drop(&mut self) {
+        self.sessions.close(self.id);
+    }
+}
+
+/// Pass a request to the [`RequestHandler`], exposing the ID of the
+/// session it belongs to.
+async fn handle_in_session<T>(
+    rh: &impl RequestHandler<T>,
+    session: Option<u64>,
+    req: JsonRequest,
+) -> JsonResult {
+    match session {
+        Some(id) => session::scoped(id, rh.handle_request(req)).await,
+        None => rh.handle_request(req).await,
+    }
+}
+
+/// Wait for the next notification of a subscription. Once the server
+/// starts draining, only the already queued notifications are returned,
+/// and `None` signals the subscription got flushed.
+async fn next_notification(
+    subscription: &Subscription<JsonNotification>,
+    drain: Option<&RpcDrain>,
+) -> Option<JsonNotification> {
+    let Some(drain) = drain else { return Some(subscription.receive().await) };
+    if drain.is_draining() {
+        return subscription.try_receive()
+    }
+
+    future::or(async { Some(subscription.receive().await) }, async {
+        drain.started().await;
+        subscription.try_receive()
+    })
+    .await
+}
+
 /// Auxiliary function to handle a request in the background.
+/// The in-flight guard, if any, is held until the reply gets written.
+#[allow(clippy::too_many_arguments)]
 async fn handle_request<T>(
     writer: Arc<Mutex<WriteHalf<Box<dyn PtStream>>>>,
     addr: Url,
@@ -84,9 +176,37 @@ async fn handle_request<T>(
     ex: Arc<smol::Executor<'_>>,
     tasks: Arc<Mutex<HashSet<Arc<StoppableTask>>>>,
     use_http: bool,
+    session: Option<u64>,
     req: JsonRequest,
+    _in_flight: Option<InFlight>,
 ) -> Result<()> {
-    let rep = rh.handle_request(req).await;
+    let method = req.method.clone();
+    let start = Instant::now();
+    let admitted = match (rh.sessions(), session) {
+        (Some(sessions), Some(id)) => sessions.admit(id),
+        _ => true,
+    };
+    let rep = if !admitted {
+        debug!(target: "rpc::server", "{} exceeded its rate limit, refusing {}", addr, method);
+        JsonError::new(ErrorCode::RateLimited, None, req.id).into()
+    } else if rh.tracing() {
+        let correlation_id = req.correlation_id.clone().unwrap_or_else(trace::new_correlation_id);
+        debug!(target: "rpc::server", "[{}] {} --> {}", correlation_id, addr, method);
+        let rep =
+            trace::traced(correlation_id.clone(), handle_in_session(rh.as_ref(), session, req))
+                .await;
+        debug!(
+            target: "rpc::server",
+            "[{}] {} handled in {:?}", correlation_id, method, start.elapsed(),
+        );
+        rep.with_correlation_id(&correlation_id)
+    } else {
+        handle_in_session(rh.as_ref(), session, req).await
+    };
+    if let Some(stats) = rh.stats() {
+        stats.record(&addr, &method, start.elapsed(), &rep);
+    }
+
     match rep {
         JsonResult::Subscriber(subscriber) => {
             let task = StoppableTask::new();
@@ -96,15 +216,23 @@ async fn handle_request<T>(
             let addr_ = addr.clone();
             let tasks_ = tasks.clone();
             let writer_ = writer.clone();
+            let rh_ = rh.clone();
 
             // Detach the subscriber so we can multiplex further requests
             task.clone().start(
                 async move {
                     // Subscribe to the inner method subscriber
-                    let subscription = subscriber.publisher.subscribe().await;
+                    let subscription = subscriber.publisher.clone().subscribe().await;
+                    let _in_flight = rh_.drain().and_then(|d| d.enter());
                     loop {
-                        // Listen for notifications
-                        let notification = subscription.receive().await;
+                        // Listen for notifications, until flushed on drain
+                        let Some(notification) = next_notification(&subscription, rh_.drain()).await else {
+                            subscription.unsubscribe().await;
+                            return Ok(())
+                        };
+
+                        // Skip notifications filtered out for this subscription
+                        let Some(notification) = subscriber.apply_filter(notification) else { continue };
 
                         // Push notification
                         debug!(target: "rpc::server", "{} <-- {}", addr_, notification.stringify().unwrap());
@@ -134,6 +262,9 @@ async fn handle_request<T>(
                         "Removing background task {} from map", task_.task_id,
                     );
                     tasks_.lock().await.remove(&task_);
+                    if let (Some(sessions), Some(id)) = (rh_.sessions(), session) {
+                        sessions.subscription_closed(id);
+                    }
                 },
                 Error::DetachedTaskStopped,
                 ex.clone(),
@@ -141,6 +272,9 @@ async fn handle_request<T>(
 
             debug!(target: "rpc::server", "Adding background task {} to map", task.task_id);
             tasks.lock().await.insert(task);
+            if let (Some(sessions), Some(id)) = (rh.sessions(), session) {
+                sessions.subscription_opened(id);
+            }
         }
 
         JsonResult::SubscriberWithReply(subscriber, reply) => {
@@ -160,15 +294,23 @@ async fn handle_request<T>(
             let addr_ = addr.clone();
             let tasks_ = tasks.clone();
             let writer_ = writer.clone();
+            let rh_ = rh.clone();
 
             // Detach the subscriber so we can multiplex further requests
             task.clone().start(
                 async move {
                     // Start the subscriber loop
-                    let subscription = subscriber.publisher.subscribe().await;
+                    let subscription = subscriber.publisher.clone().subscribe().await;
+                    let _in_flight = rh_.drain().and_then(|d| d.enter());
                     loop {
-                        // Listen for notifications
-                        let notification = subscription.receive().await;
+                        // Listen for notifications, until flushed on drain
+                        let Some(notification) = next_notification(&subscription, rh_.drain()).await else {
+                            subscription.unsubscribe().await;
+                            return Ok(())
+                        };
+
+                        // Skip notifications filtered out for this subscription
+                        let Some(notification) = subscriber.apply_filter(notification) else { continue };
 
                         // Push notification
                         debug!(target: "rpc::server", "{} <-- {}", addr_, notification.stringify().unwrap());
@@ -198,6 +340,9 @@ async fn handle_request<T>(
                         "Removing background task {} from map", task_.task_id,
                     );
                     tasks_.lock().await.remove(&task_);
+                    if let (Some(sessions), Some(id)) = (rh_.sessions(), session) {
+                        sessions.subscription_closed(id);
+                    }
                 },
                 Error::DetachedTaskStopped,
                 ex.clone(),
@@ -205,6 +350,9 @@ async fn handle_request<T>(
 
             debug!(target: "rpc::server", "Adding background task {} to map", task.task_id);
             tasks.lock().await.insert(task);
+            if let (Some(sessions), Some(id)) = (rh.sessions(), session) {
+                sessions.subscription_opened(id);
+            }
         }
 
         JsonResult::Request(_) | JsonResult::Notification(_) => {
@@ -261,9 +409,40 @@ pub async fn accept<'a, T: 'a>(
         }
     }
 
+    // Open a session for this connection, closed whenever we return
+    let session = rh.sessions().map(|sessions| SessionGuard { sessions, id: sessions.open(&addr) });
+    let session_id = session.as_ref().map(|s| s.id);
+
     // We'll hold our background tasks here
     let tasks = Arc::new(Mutex::new(HashSet::new()));
 
+    let result =
+        read_requests(reader, writer, addr, rh.clone(), use_http, session_id, tasks.clone(), ex)
+            .await;
+
+    // Tear down the subscriptions of this connection, so they don't
+    // outlive it until their next notification fails to get written.
+    let tasks: Vec<StoppableTaskPtr> = tasks.lock().await.iter().cloned().collect();
+    for task in tasks {
+        task.stop().await;
+    }
+
+    result
+}
+
+/// Read incoming JSON-RPC requests of a connection and pass them to the
+/// [`RequestHandler`] in the background, until the connection fails.
+#[allow(clippy::too_many_arguments, clippy::type_complexity)]
+async fn read_requests<'a, T: 'a>(
+    reader: Arc<Mutex<BufReader<ReadHalf<Box<dyn PtStream>>>>>,
+    writer: Arc<Mutex<WriteHalf<Box<dyn PtStream>>>>,
+    addr: Url,
+    rh: Arc<impl RequestHandler<T> + 'static>,
+    use_http: bool,
+    session: Option<u64>,
+    tasks: Arc<Mutex<HashSet<StoppableTaskPtr>>>,
+    ex: Arc<smol::Executor<'a>>,
+) -> Result<()> {
     loop {
         let mut buf = Vec::with_capacity(INIT_BUF_SIZE);
 
@@ -312,6 +491,26 @@ pub async fn accept<'a, T: 'a>(
 
         debug!(target: "rpc::server", "{} --> {}", addr, val.stringify()?);
 
+        // Refuse new requests while the server is draining
+        let in_flight = match rh.drain() {
+            Some(drain) => match drain.enter() {
+                Some(guard) => Some(guard),
+                None => {
+                    debug!(target: "rpc::server", "Server draining, refusing {}", req.method);
+                    let rep: JsonResult =
+                        JsonError::new(ErrorCode::ShuttingDown, None, req.id).into();
+                    let mut writer_lock = writer.lock().await;
+                    if use_http {
+                        http_write_to_stream(&mut writer_lock, &rep).await?;
+                    } else {
+                        write_to_stream(&mut writer_lock, &rep).await?;
+                    }
+                    continue
+                }
+            },
+            None => None,
+        };
+
         // Create a new task to handle request in the background
         let task = StoppableTask::new();
 
@@ -328,7 +527,9 @@ pub async fn accept<'a, T: 'a>(
                 ex.clone(),
                 tasks.clone(),
                 use_http,
+                session,
                 req,
+                in_flight,
             ),
             move |_| async move {
                 debug!(
@@ -346,12 +547,68 @@ pub async fn accept<'a, T: 'a>(
     }
 }
 
+/// Perform the `rpc.auth` handshake on a freshly accepted connection of
+/// an authenticated listener. The first request of the connection must
+/// carry the listener's access token, otherwise it gets refused.
+#[allow(clippy::type_complexity)]
+async fn authenticate(
+    reader: Arc<Mutex<BufReader<ReadHalf<Box<dyn PtStream>>>>>,
+    writer: Arc<Mutex<WriteHalf<Box<dyn PtStream>>>>,
+    addr: &Url,
+    token: &str,
+) -> Result<()> {
+    let mut buf = Vec::with_capacity(INIT_BUF_SIZE);
+    let mut reader_lock = reader.lock().await;
+    let Ok(read) = timeout(AUTH_TIMEOUT, read_from_stream(&mut reader_lock, &mut buf)).await else {
+        warn!(target: "rpc::server::authenticate()", "[RPC] {} didn't authenticate in time", addr);
+        return Err(Error::RpcUnauthorized)
+    };
+    read?;
+    drop(reader_lock);
+
+    let req = String::from_utf8(buf)
+        .ok()
+        .and_then(|line| line.trim().parse::<JsonValue>().ok())
+        .and_then(|val| JsonRequest::try_from(&val).ok());
+    let Some(req) = req else {
+        warn!(target: "rpc::server::authenticate()", "[RPC] {} sent an invalid request", addr);
+        return Err(Error::RpcUnauthorized)
+    };
+
+    // Compare the tokens in constant time
+    let authorized = req.method == "rpc.auth" &&
+        match req.params.get::<Vec<JsonValue>>().map(|p| p.as_slice()) {
+            Some([JsonValue::String(t)]) => {
+                t.len() == token.len() &&
+                    t.bytes().zip(token.bytes()).fold(0u8, |acc, (a, b)| acc | (a ^ b)) == 0
+            }
+            _ => false,
+        };
+
+    let rep: JsonResult = if authorized {
+        JsonResponse::new(JsonValue::Boolean(true), req.id).into()
+    } else {
+        JsonError::new(ErrorCode::Unauthorized, None, req.id).into()
+    };
+    write_to_stream(&mut *writer.lock().await, &rep).await?;
+
+    if !authorized {
+        warn!(target: "rpc::server::authenticate()", "[RPC] {} failed to authenticate", addr);
+        return Err(Error::RpcUnauthorized)
+    }
+
+    debug!(target: "rpc::server::authenticate()", "[RPC] {} authenticated", addr);
+    Ok(())
+}
+
 /// Wrapper function around [`accept()`] to take the incoming connection and
-/// pass it forward.
+/// pass it forward. If an access token is set, connections must first
+/// [`authenticate()`].
 async fn run_accept_loop<'a, T: 'a>(
     listener: Box<dyn PtListener>,
     rh: Arc<impl RequestHandler<T> + 'static>,
     conn_limit: Option<usize>,
+    auth_token: Option<Arc<String>>,
     use_http: bool,
     ex: Arc<smol::Executor<'a>>,
 ) -> Result<()> {
@@ -368,8 +625,16 @@ async fn run_accept_loop<'a, T: 'a>(
                 let task = StoppableTask::new();
                 let task_ = task.clone();
                 let ex_ = ex.clone();
+                let url_ = url.clone();
+                let rh_conn = rh.clone();
+                let auth_token = auth_token.clone();
                 task.clone().start(
-                    accept(reader, writer, url.clone(), rh.clone(), conn_limit, use_http, ex_),
+                    async move {
+                        if let Some(token) = auth_token {
+                            authenticate(reader.clone(), writer.clone(), &url_, &token).await?;
+                        }
+                        accept(reader, writer, url_, rh_conn, conn_limit, use_http, ex_).await
+                    },
                     |_| async move {
                         info!(target: "rpc::server", "[RPC] Closed conn from {}", url);
                         rh_.clone().unmark_connection(task_.clone()).await;
@@ -426,6 +691,31 @@ pub async fn listen_and_serve<'a, T: 'a>(
     rh: Arc<impl RequestHandler<T> + 'static>,
     conn_limit: Option<usize>,
     ex: Arc<smol::Executor<'a>>,
+) -> Result<()> {
+    serve(accept_url, rh, conn_limit, None, ex).await
+}
+
+/// Start a JSON-RPC server listener with the given [`RpcListenerSettings`]
+/// and use the given [`RequestHandler`] to handle incoming requests.
+/// Connections to listeners with an access token must authenticate
+/// before any of their requests get handled.
+pub async fn listen_and_serve_with<'a, T: 'a>(
+    settings: RpcListenerSettings,
+    rh: Arc<impl RequestHandler<T> + 'static>,
+    ex: Arc<smol::Executor<'a>>,
+) -> Result<()> {
+    settings.validate()?;
+    let auth_token = settings.auth_token.map(Arc::new);
+    serve(settings.listen, rh, settings.conn_limit, auth_token, ex).await
+}
+
+/// Bind a JSON-RPC server to the given accept URL.
+async fn serve<'a, T: 'a>(
+    accept_url: Url,
+    rh: Arc<impl RequestHandler<T> + 'static>,
+    conn_limit: Option<usize>,
+    auth_token: Option<Arc<String>>,
+    ex: Arc<smol::Executor<'a>>,
 ) -> Result<()> {
     // Figure out if we're using HTTP and rewrite the URL accordingly.
     let mut listen_url = accept_url.clone();
@@ -438,7 +728,7 @@ pub async fn listen_and_serve<'a, T: 'a>(
     let listener = Listener::new(listen_url, None).await?.listen().await?;
 
     let use_http = accept_url.scheme().starts_with("http+");
-    run_accept_loop(listener, rh, conn_limit, use_http, ex.clone()).await
+    run_accept_loop(listener, rh, conn_limit, auth_token, use_http, ex.clone()).await
 }
 
 #[cfg(test)]
@@ -465,6 +755,32 @@ mod tests {
         }
     }
 
+    struct SlowRpcServer {
+        rpc_connections: Mutex<HashSet<StoppableTaskPtr>>,
+        rpc_drain: RpcDrain,
+    }
+
+    #[async_trait]
+    impl RequestHandler<()> for SlowRpcServer {
+        async fn handle_request(&self, req: JsonRequest) -> JsonResult {
+            match req.method.as_str() {
+                "slow_ping" => {
+                    msleep(1000).await;
+                    self.pong(req.id, req.params).await
+                }
+                _ => panic!(),
+            }
+        }
+
+        fn drain(&self) -> Option<&RpcDrain> {
+            Some(&self.rpc_drain)
+        }
+
+        async fn connections_mut(&self) -> MutexGuard<'life0, HashSet<StoppableTaskPtr>> {
+            self.rpc_connections.lock().await
+        }
+    }
+
     #[test]
     fn conn_manager() -> Result<()> {
         let executor = Arc::new(Executor::new());
@@ -537,4 +853,104 @@ mod tests {
             Ok(())
         }))
     }
+
+    #[test]
+    fn authenticated_listener() -> Result<()> {
+        let executor = Arc::new(Executor::new());
+
+        smol::block_on(executor.run(async {
+            // Find an available port
+            let listener = TcpListener::bind("127.0.0.1:0").await?;
+            let sockaddr = listener.local_addr()?;
+            let endpoint = Url::parse(&format!("tcp://127.0.0.1:{}", sockaddr.port()))?;
+            drop(listener);
+
+            let rpc_server = Arc::new(RpcServer { rpc_connections: Mutex::new(HashSet::new()) });
+            let settings = RpcListenerSettings {
+                listen: endpoint.clone(),
+                auth_token: Some("s3cr3t".to_string()),
+                conn_limit: None,
+            };
+
+            let server_task = StoppableTask::new();
+            server_task.clone().start(
+                listen_and_serve_with(settings, rpc_server.clone(), executor.clone()),
+                |_| async move {},
+                Error::RpcServerStopped,
+                executor.clone(),
+            );
+
+            // Let the server spawn
+            msleep(500).await;
+
+            let ping = || JsonRequest::new("ping", JsonValue::Array(vec![]));
+
+            // Clients providing the token can use the server
+            let mut authenticated = endpoint.clone();
+            authenticated.set_password(Some("s3cr3t")).unwrap();
+            let rpc_client = RpcClient::new(authenticated, executor.clone()).await?;
+            assert_eq!(rpc_client.request(ping()).await?, JsonValue::String("pong".to_string()));
+            rpc_client.stop().await;
+
+            // Clients with a wrong token get refused
+            let mut wrong = endpoint.clone();
+            wrong.set_password(Some("guess")).unwrap();
+            assert!(RpcClient::new(wrong, executor.clone()).await.is_err());
+
+            // Clients without a token can't issue requests
+            let rpc_client = RpcClient::new(endpoint, executor.clone()).await?;
+            assert!(rpc_client.request(ping()).await.is_err());
+            rpc_client.stop().await;
+
+            server_task.stop().await;
+            Ok(())
+        }))
+    }
+
+    #[test]
+    fn graceful_drain() -> Result<()> {
+        let executor = Arc::new(Executor::new());
+
+        smol::block_on(executor.run(async {
+            // Find an available port
+            let listener = TcpListener::bind("127.0.0.1:0").await?;
+            let sockaddr = listener.local_addr()?;
+            let endpoint = Url::parse(&format!("tcp://127.0.0.1:{}", sockaddr.port()))?;
+            drop(listener);
+
+            let rpc_server = Arc::new(SlowRpcServer {
+                rpc_connections: Mutex::new(HashSet::new()),
+                rpc_drain: RpcDrain::new(Duration::from_secs(5)),
+            });
+            let rpc_server_ = rpc_server.clone();
+
+            let server_task = StoppableTask::new();
+            server_task.clone().start(
+                listen_and_serve(endpoint.clone(), rpc_server.clone(), None, executor.clone()),
+                |_| async move { rpc_server_.stop_connections().await },
+                Error::RpcServerStopped,
+                executor.clone(),
+            );
+
+            // Let the server spawn
+            msleep(500).await;
+
+            // Issue a slow request in the background
+            let rpc_client = Arc::new(RpcClient::new(endpoint, executor.clone()).await?);
+            let rpc_client_ = rpc_client.clone();
+            let request = executor.spawn(async move {
+                rpc_client_.request(JsonRequest::new("slow_ping", JsonValue::Array(vec![]))).await
+            });
+            msleep(200).await;
+
+            // Stopping the server waits for the in-flight request to get replied
+            server_task.stop().await;
+            assert!(rpc_server.rpc_drain.is_draining());
+            assert_eq!(rpc_server.rpc_drain.in_flight(), 0);
+            assert_eq!(request.await?, JsonValue::String("pong".to_string()));
+
+            rpc_client.stop().await;
+            Ok(())
+        }))
+    }
 }
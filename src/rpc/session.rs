@@ -0,0 +1,318 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2025 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! JSON-RPC client sessions.
+//!
+//! Every accepted connection gets its own session, identified by a
+//! server-unique ID. Sessions keep their request counts, their active
+//! subscriptions and an optional client-provided label, so operators of
+//! nodes shared between several wallets can tell the clients apart in
+//! `rpc.get_stats`. An optional per-session rate limit stops a single
+//! client from starving the others.
+//!
+//! The ID of the session a request belongs to is exposed to the handler
+//! through [`current()`] while the request is being handled.
+
+use std::{
+    cell::Cell,
+    collections::HashMap,
+    future::Future,
+    pin::Pin,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Mutex,
+    },
+    task::{Context, Poll},
+    time::{Duration, Instant},
+};
+
+use async_trait::async_trait;
+use pin_project_lite::pin_project;
+use url::Url;
+
+use super::{
+    jsonrpc::{ErrorCode, JsonError, JsonResponse, JsonResult},
+    util::*,
+};
+use crate::util::time::Timestamp;
+
+/// Window over which the per-session rate limit is enforced
+pub const RATE_LIMIT_WINDOW: Duration = Duration::from_secs(60);
+
+/// Maximum length of a session label
+pub const MAX_LABEL_LEN: usize = 64;
+
+thread_local! {
+    static CURRENT: Cell<Option<u64>> = const { Cell::new(None) };
+}
+
+/// Returns the ID of the session whose request is currently being
+/// handled on this thread, if any.
+pub fn current() -> Option<u64> {
+    CURRENT.with(|c| c.get())
+}
+
+/// Wrap a future so that [`current()`] returns `id` while it is polled.
+pub fn scoped<F: Future>(id: u64, future: F) -> SessionScoped<F> {
+    SessionScoped { id, future }
+}
+
+pin_project! {
+    /// A future exposing a session ID to everything it polls.
+    pub struct SessionScoped<F> {
+        id: u64,
+        #[pin]
+        future: F,
+    }
+}
+
+impl<F: Future> Future for SessionScoped<F> {
+    type Output = F::Output;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.project();
+        let previous = CURRENT.with(|c| c.replace(Some(*this.id)));
+        let result = this.future.poll(cx);
+        CURRENT.with(|c| c.set(previous));
+        result
+    }
+}
+
+/// State of a single client session
+#[derive(Clone, Debug)]
+pub struct Session {
+    /// Server-unique session ID
+    pub id: u64,
+    /// Client address
+    pub addr: Url,
+    /// Optional client-provided label
+    pub label: Option<String>,
+    /// Time the session was opened
+    pub connected: Timestamp,
+    /// Handled requests
+    pub requests: u64,
+    /// Requests refused by the rate limit
+    pub rate_limited: u64,
+    /// Currently active subscriptions
+    pub subscriptions: usize,
+    /// Start of the current rate limit window
+    window_start: Instant,
+    /// Requests admitted in the current rate limit window
+    window_requests: u32,
+}
+
+/// Registry of open JSON-RPC client sessions
+pub struct RpcSessions {
+    sessions: Mutex<HashMap<u64, Session>>,
+    next_id: AtomicU64,
+    /// Maximum requests per session in every [`RATE_LIMIT_WINDOW`]
+    rate_limit: Option<u32>,
+}
+
+impl RpcSessions {
+    pub fn new(rate_limit: Option<u32>) -> Self {
+        Self { sessions: Mutex::new(HashMap::new()), next_id: AtomicU64::new(1), rate_limit }
+    }
+
+    /// Open a new session for a client connecting from `addr`,
+    /// returning its ID.
+    pub fn open(&self, addr: &Url) -> u64 {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        let session = Session {
+            id,
+            addr: addr.clone(),
+            label: None,
+            connected: Timestamp::current_time(),
+            requests: 0,
+            rate_limited: 0,
+            subscriptions: 0,
+            window_start: Instant::now(),
+            window_requests: 0,
+        };
+        self.sessions.lock().unwrap().insert(id, session);
+        id
+    }
+
+    /// Close the given session.
+    pub fn close(&self, id: u64) {
+        self.sessions.lock().unwrap().remove(&id);
+    }
+
+    /// Account a new request of the given session. Returns `false` if
+    /// the session exceeded its rate limit and the request must be refused.
+    pub fn admit(&self, id: u64) -> bool {
+        let mut sessions = self.sessions.lock().unwrap();
+        let Some(session) = sessions.get_mut(&id) else { return true };
+
+        if let Some(limit) = self.rate_limit {
+            if session.window_start.elapsed() >= RATE_LIMIT_WINDOW {
+                session.window_start = Instant::now();
+                session.window_requests = 0;
+            }
+
+            if session.window_requests >= limit {
+                session.rate_limited += 1;
+                return false
+            }
+            session.window_requests += 1;
+        }
+
+        session.requests += 1;
+        true
+    }
+
+    /// Set or clear the label of the given session.
+    pub fn set_label(&self, id: u64, label: Option<String>) -> bool {
+        match self.sessions.lock().unwrap().get_mut(&id) {
+            Some(session) => {
+                session.label = label;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Account a subscription opened by the given session.
+    pub fn subscription_opened(&self, id: u64) {
+        if let Some(session) = self.sessions.lock().unwrap().get_mut(&id) {
+            session.subscriptions += 1;
+        }
+    }
+
+    /// Account a subscription of the given session getting closed.
+    pub fn subscription_closed(&self, id: u64) {
+        if let Some(session) = self.sessions.lock().unwrap().get_mut(&id) {
+            session.subscriptions = session.subscriptions.saturating_sub(1);
+        }
+    }
+
+    /// Get a snapshot of the given session
+    pub fn get(&self, id: u64) -> Option<Session> {
+        self.sessions.lock().unwrap().get(&id).cloned()
+    }
+
+    /// Get a snapshot of all the open sessions, ordered by ID
+    pub fn sessions(&self) -> Vec<Session> {
+        let mut sessions: Vec<Session> = self.sessions.lock().unwrap().values().cloned().collect();
+        sessions.sort_by_key(|s| s.id);
+        sessions
+    }
+}
+
+impl From<&Session> for JsonValue {
+    fn from(session: &Session) -> JsonValue {
+        let label = match &session.label {
+            Some(l) => JsonStr(l.clone()),
+            None => JsonValue::Null,
+        };
+
+        json_map([
+            ("id", JsonNum(session.id as f64)),
+            ("addr", JsonStr(session.addr.to_string())),
+            ("label", label),
+            ("connected", JsonStr(session.connected.inner().to_string())),
+            ("requests", JsonNum(session.requests as f64)),
+            ("rate_limited", JsonNum(session.rate_limited as f64)),
+            ("subscriptions", JsonNum(session.subscriptions as f64)),
+        ])
+    }
+}
+
+#[async_trait]
+pub trait HandlerSession: Sync + Send {
+    // RPCAPI:
+    // Returns the session of the calling connection: its ID, client
+    // address, label, `u64` (String) connection timestamp, handled and
+    // rate limited request counts, and active subscriptions.
+    //
+    // --> {"jsonrpc": "2.0", "method": "rpc.session", "params": [], "id": 1}
+    // <-- {"jsonrpc": "2.0", "result": {"id": 3, "addr": "tcp://127.0.0.1:51234", "label": "alice", "connected": "1234", "requests": 10, "rate_limited": 0, "subscriptions": 1}, "id": 1}
+    async fn rpc_session(&self, id: u16, _params: JsonValue) -> JsonResult {
+        let Some(session) = current().and_then(|s| self.rpc_session_registry().get(s)) else {
+            return JsonError::new(ErrorCode::InternalError, None, id).into()
+        };
+
+        JsonResponse::new((&session).into(), id).into()
+    }
+
+    // RPCAPI:
+    // Sets the label of the calling connection's session, shown in
+    // `rpc.get_stats`. An empty label clears it.
+    // Returns `true` on success.
+    //
+    // --> {"jsonrpc": "2.0", "method": "rpc.set_session_label", "params": ["alice"], "id": 1}
+    // <-- {"jsonrpc": "2.0", "result": true, "id": 1}
+    async fn rpc_set_session_label(&self, id: u16, params: JsonValue) -> JsonResult {
+        let Some(params) = params.get::<Vec<JsonValue>>() else {
+            return JsonError::new(ErrorCode::InvalidParams, None, id).into()
+        };
+        if params.len() != 1 || !params[0].is_string() {
+            return JsonError::new(ErrorCode::InvalidParams, None, id).into()
+        }
+
+        let label = params[0].get::<String>().unwrap();
+        if label.len() > MAX_LABEL_LEN || label.chars().any(|c| c.is_control()) {
+            return JsonError::new(ErrorCode::InvalidParams, None, id).into()
+        }
+        let label = if label.is_empty() { None } else { Some(label.clone()) };
+
+        let Some(session) = current() else {
+            return JsonError::new(ErrorCode::InternalError, None, id).into()
+        };
+
+        let set = self.rpc_session_registry().set_label(session, label);
+        JsonResponse::new(JsonValue::Boolean(set), id).into()
+    }
+
+    fn rpc_session_registry(&self) -> &RpcSessions;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rpc_sessions_rate_limit() {
+        let sessions = RpcSessions::new(Some(2));
+        let addr = Url::parse("tcp://127.0.0.1:4242").unwrap();
+        let a = sessions.open(&addr);
+        let b = sessions.open(&addr);
+        assert_ne!(a, b);
+
+        // Sessions are limited independently of each other
+        assert!(sessions.admit(a));
+        assert!(sessions.admit(a));
+        assert!(!sessions.admit(a));
+        assert!(sessions.admit(b));
+
+        let session = sessions.get(a).unwrap();
+        assert_eq!(session.requests, 2);
+        assert_eq!(session.rate_limited, 1);
+
+        sessions.subscription_opened(b);
+        assert!(sessions.set_label(b, Some("alice".to_string())));
+        let session = sessions.get(b).unwrap();
+        assert_eq!(session.subscriptions, 1);
+        assert_eq!(session.label.as_deref(), Some("alice"));
+
+        sessions.close(a);
+        assert!(sessions.get(a).is_none());
+        assert!(!sessions.set_label(a, None));
+        assert_eq!(sessions.sessions().len(), 1);
+    }
+}
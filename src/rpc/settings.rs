@@ -0,0 +1,136 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2025 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! JSON-RPC server settings.
+//!
+//! A server can run several listeners at once, each with its own
+//! settings, e.g. an unauthenticated one on localhost next to an
+//! authenticated one exposed as a Tor hidden service. Clients of an
+//! authenticated listener must open every connection with an
+//! `rpc.auth` request carrying the listener's access token:
+//!
+//! ```json
+//! --> {"jsonrpc": "2.0", "method": "rpc.auth", "params": ["s3cr3t"], "id": 1}
+//! <-- {"jsonrpc": "2.0", "result": true, "id": 1}
+//! ```
+//!
+//! [`RpcClient`](super::client::RpcClient) does so automatically when
+//! the endpoint URL carries the token as its password, for example
+//! `tor://:s3cr3t@abcd...xyz.onion:8340`.
+
+use url::Url;
+
+use crate::{Error, Result};
+
+/// Settings of a single JSON-RPC listener
+#[derive(Clone, Debug, PartialEq, Eq, serde::Deserialize)]
+pub struct RpcListenerSettings {
+    /// JSON-RPC listen URL
+    pub listen: Url,
+    /// Optional access token clients must authenticate with
+    #[serde(default)]
+    pub auth_token: Option<String>,
+    /// Optional connections limit of the request handler, after
+    /// which this listener refuses new connections
+    #[serde(default)]
+    pub conn_limit: Option<usize>,
+}
+
+impl RpcListenerSettings {
+    /// Create unauthenticated listener settings for the given URL.
+    pub fn new(listen: Url) -> Self {
+        Self { listen, auth_token: None, conn_limit: None }
+    }
+
+    /// Check the listener settings are usable.
+    pub fn validate(&self) -> Result<()> {
+        if let Some(token) = &self.auth_token {
+            if token.is_empty() {
+                return Err(Error::ParseFailed("JSON-RPC access token must not be empty"))
+            }
+
+            // HTTP clients can't perform the per-connection handshake
+            if self.listen.scheme().starts_with("http+") {
+                return Err(Error::ParseFailed("JSON-RPC authentication is not supported over HTTP"))
+            }
+        }
+
+        if self.conn_limit == Some(0) {
+            return Err(Error::ParseFailed("JSON-RPC connection limit must be greater than zero"))
+        }
+
+        Ok(())
+    }
+}
+
+/// JSON-RPC server settings, consisting of independent listeners
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct RpcSettings {
+    /// Listeners to serve JSON-RPC on
+    pub listeners: Vec<RpcListenerSettings>,
+    /// Optional authenticated listener serving the privileged management
+    /// methods, which are never exposed on the public listeners
+    pub management: Option<RpcListenerSettings>,
+}
+
+impl RpcSettings {
+    /// Create settings with a single unauthenticated listener.
+    pub fn new(listen: Url) -> Self {
+        Self { listeners: vec![RpcListenerSettings::new(listen)], management: None }
+    }
+
+    /// Append additional listeners to the settings.
+    pub fn with_listeners(mut self, listeners: Vec<RpcListenerSettings>) -> Self {
+        self.listeners.extend(listeners);
+        self
+    }
+
+    /// Set the management listener of the settings.
+    pub fn with_management(mut self, management: Option<RpcListenerSettings>) -> Self {
+        self.management = management;
+        self
+    }
+
+    /// Check all listeners are usable and none of them share a URL.
+    pub fn validate(&self) -> Result<()> {
+        if self.listeners.is_empty() {
+            return Err(Error::ParseFailed("No JSON-RPC listeners configured"))
+        }
+
+        for (i, listener) in self.listeners.iter().enumerate() {
+            listener.validate()?;
+            if self.listeners[i + 1..].iter().any(|l| l.listen == listener.listen) {
+                return Err(Error::ParseFailed("Duplicate JSON-RPC listen URL"))
+            }
+        }
+
+        if let Some(management) = &self.management {
+            management.validate()?;
+            if management.auth_token.is_none() {
+                return Err(Error::ParseFailed(
+                    "JSON-RPC management listener requires an access token",
+                ))
+            }
+            if self.listeners.iter().any(|l| l.listen == management.listen) {
+                return Err(Error::ParseFailed("Duplicate JSON-RPC listen URL"))
+            }
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,248 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2025 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! JSON-RPC request statistics.
+//!
+//! Records per-method call counts, error counts and latency histograms,
+//! along with per-client request counts, so operators can see which
+//! methods are slow and which consumers are hammering the node.
+
+use std::{collections::HashMap, sync::Mutex, time::Duration};
+
+use async_trait::async_trait;
+use url::Url;
+
+use super::{
+    jsonrpc::{ErrorCode, JsonResponse, JsonResult},
+    session::RpcSessions,
+    util::*,
+};
+
+/// Upper bounds of the request latency histogram buckets, in milliseconds.
+/// Latencies above the last bound are counted in an extra overflow bucket.
+pub const LATENCY_BUCKETS_MS: [u64; 9] = [1, 5, 10, 50, 100, 500, 1000, 5000, 10000];
+
+/// Maximum number of distinct clients tracked. Requests from any further
+/// clients get accounted under [`OTHER_CLIENTS`].
+pub const MAX_TRACKED_CLIENTS: usize = 1024;
+
+/// Key under which requests from untracked clients are accounted
+pub const OTHER_CLIENTS: &str = "other";
+
+/// Key under which requests to non-existing methods are accounted
+pub const UNKNOWN_METHOD: &str = "unknown";
+
+/// Request statistics of a single JSON-RPC method
+#[derive(Clone, Debug, Default)]
+pub struct MethodStats {
+    /// Handled requests
+    pub calls: u64,
+    /// Requests that resulted in an error
+    pub errors: u64,
+    /// Request latencies histogram, using [`LATENCY_BUCKETS_MS`]
+    pub latency_histogram: [u64; LATENCY_BUCKETS_MS.len() + 1],
+    /// Sum of all request latencies, in microseconds
+    pub latency_total_us: u64,
+    /// Highest request latency, in microseconds
+    pub latency_max_us: u64,
+    /// Returned error codes, along with their counts
+    pub error_codes: HashMap<i32, u64>,
+}
+
+impl MethodStats {
+    /// Average request latency, in milliseconds
+    pub fn latency_avg_ms(&self) -> f64 {
+        if self.calls == 0 {
+            return 0.0
+        }
+
+        self.latency_total_us as f64 / self.calls as f64 / 1000.0
+    }
+}
+
+/// Registry of JSON-RPC request statistics
+#[derive(Default)]
+pub struct RpcStats {
+    methods: Mutex<HashMap<String, MethodStats>>,
+    clients: Mutex<HashMap<String, u64>>,
+}
+
+impl RpcStats {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record a handled request of the given client and method, along
+    /// with its latency and result.
+    pub fn record(&self, client: &Url, method: &str, latency: Duration, result: &JsonResult) {
+        let error_code = match result {
+            JsonResult::Error(e) => Some(e.error.code),
+            _ => None,
+        };
+
+        // Don't let clients bloat the registry with made up methods
+        let method = if error_code == Some(ErrorCode::MethodNotFound.code()) {
+            UNKNOWN_METHOD
+        } else {
+            method
+        };
+
+        let latency_us = latency.as_micros() as u64;
+        let latency_ms = latency_us / 1000;
+        let bucket = LATENCY_BUCKETS_MS
+            .iter()
+            .position(|bound| latency_ms < *bound)
+            .unwrap_or(LATENCY_BUCKETS_MS.len());
+
+        let mut methods = self.methods.lock().unwrap();
+        let stats = methods.entry(method.to_string()).or_default();
+        stats.calls += 1;
+        stats.latency_histogram[bucket] += 1;
+        stats.latency_total_us += latency_us;
+        stats.latency_max_us = stats.latency_max_us.max(latency_us);
+        if let Some(code) = error_code {
+            stats.errors += 1;
+            *stats.error_codes.entry(code).or_insert(0) += 1;
+        }
+        drop(methods);
+
+        // Clients are tracked by host, since their ports change per connection
+        let client = client.host_str().unwrap_or(OTHER_CLIENTS).to_string();
+        let mut clients = self.clients.lock().unwrap();
+        let key = if clients.contains_key(&client) || clients.len() < MAX_TRACKED_CLIENTS {
+            client
+        } else {
+            OTHER_CLIENTS.to_string()
+        };
+        *clients.entry(key).or_insert(0) += 1;
+    }
+
+    /// Get a snapshot of all the recorded method statistics
+    pub fn method_stats(&self) -> HashMap<String, MethodStats> {
+        self.methods.lock().unwrap().clone()
+    }
+
+    /// Get a snapshot of the recorded request counts per client
+    pub fn client_stats(&self) -> HashMap<String, u64> {
+        self.clients.lock().unwrap().clone()
+    }
+}
+
+#[async_trait]
+pub trait HandlerStats: Sync + Send {
+    // RPCAPI:
+    // Returns the JSON-RPC request statistics: for every method, its calls
+    // and errors count, the returned error codes, the average and maximum
+    // latencies and the latency histogram in milliseconds, along with the
+    // request counts of every client host. If client sessions are enabled,
+    // the open sessions are returned as well, see `rpc.session`.
+    //
+    // --> {"jsonrpc": "2.0", "method": "rpc.get_stats", "params": [], "id": 1}
+    // <-- {"jsonrpc": "2.0", "result": {"methods": {"tx.simulate": {"calls": 10, "errors": 1, "error_codes": {"-32602": 1}, "latency_avg_ms": 12.5, "latency_max_ms": 40.1, "latency_histogram": [{"le_ms": 1, "count": 0}, ..., {"le_ms": null, "count": 0}]}, ...}, "clients": {"127.0.0.1": 10, ...}, "sessions": [{"id": 3, "addr": "tcp://127.0.0.1:51234", "label": "alice", ...}, ...]}, "id": 1}
+    async fn rpc_get_stats(&self, id: u16, _params: JsonValue) -> JsonResult {
+        let mut methods = HashMap::new();
+        for (method, stats) in self.rpc_stats().method_stats() {
+            let mut histogram = Vec::with_capacity(stats.latency_histogram.len());
+            for (i, count) in stats.latency_histogram.iter().enumerate() {
+                let le_ms = match LATENCY_BUCKETS_MS.get(i) {
+                    Some(bound) => JsonNum(*bound as f64),
+                    None => JsonValue::Null,
+                };
+                histogram.push(json_map([("le_ms", le_ms), ("count", JsonNum(*count as f64))]));
+            }
+
+            let error_codes = stats
+                .error_codes
+                .iter()
+                .map(|(code, count)| (code.to_string(), JsonNum(*count as f64)))
+                .collect();
+
+            methods.insert(
+                method,
+                json_map([
+                    ("calls", JsonNum(stats.calls as f64)),
+                    ("errors", JsonNum(stats.errors as f64)),
+                    ("error_codes", JsonObj(error_codes)),
+                    ("latency_avg_ms", JsonNum(stats.latency_avg_ms())),
+                    ("latency_max_ms", JsonNum(stats.latency_max_us as f64 / 1000.0)),
+                    ("latency_histogram", JsonArray(histogram)),
+                ]),
+            );
+        }
+
+        let clients = self
+            .rpc_stats()
+            .client_stats()
+            .into_iter()
+            .map(|(client, count)| (client, JsonNum(count as f64)))
+            .collect();
+
+        let mut result = HashMap::from([
+            ("methods".to_string(), JsonObj(methods)),
+            ("clients".to_string(), JsonObj(clients)),
+        ]);
+
+        if let Some(sessions) = self.rpc_stats_sessions() {
+            let sessions = sessions.sessions().iter().map(|s| s.into()).collect();
+            result.insert("sessions".to_string(), JsonArray(sessions));
+        }
+
+        JsonResponse::new(JsonObj(result), id).into()
+    }
+
+    fn rpc_stats(&self) -> &RpcStats;
+
+    /// Client sessions to include in `rpc.get_stats`, if any
+    fn rpc_stats_sessions(&self) -> Option<&RpcSessions> {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rpc::jsonrpc::JsonError;
+
+    #[test]
+    fn rpc_stats_record() {
+        let stats = RpcStats::new();
+        let client = Url::parse("tcp://127.0.0.1:4242").unwrap();
+        let ok: JsonResult = JsonResponse::new(JsonValue::Null, 1).into();
+        let err: JsonResult = JsonError::new(ErrorCode::InvalidParams, None, 1).into();
+        let not_found: JsonResult = JsonError::new(ErrorCode::MethodNotFound, None, 1).into();
+
+        stats.record(&client, "foo", Duration::from_micros(500), &ok);
+        stats.record(&client, "foo", Duration::from_millis(20), &err);
+        stats.record(&client, "made.up", Duration::from_micros(10), &not_found);
+
+        let methods = stats.method_stats();
+        assert_eq!(methods.len(), 2);
+
+        let foo = &methods["foo"];
+        assert_eq!(foo.calls, 2);
+        assert_eq!(foo.errors, 1);
+        assert_eq!(foo.error_codes[&ErrorCode::InvalidParams.code()], 1);
+        assert_eq!(foo.latency_histogram[0], 1);
+        assert_eq!(foo.latency_histogram[4], 1);
+        assert_eq!(foo.latency_max_us, 20000);
+        assert!((foo.latency_avg_ms() - 10.25).abs() < f64::EPSILON);
+
+        assert_eq!(methods[UNKNOWN_METHOD].calls, 1);
+        assert_eq!(stats.client_stats()["127.0.0.1"], 3);
+    }
+}
@@ -21,12 +21,12 @@ use std::io::Cursor;
 use darkfi_sdk::{crypto::ContractId, wasm};
 use darkfi_serial::{deserialize, serialize, Decodable};
 use log::{debug, error, info};
-use wasmer::{FunctionEnvMut, WasmPtr};
+use wasmer::{AsStoreMut, FunctionEnvMut, WasmPtr};
 
 use super::acl::acl_allow;
 use crate::{
     blockchain::contract_store::SMART_CONTRACT_ZKAS_DB_NAME,
-    runtime::vm_runtime::{ContractSection, Env},
+    runtime::vm_runtime::{ContractSection, Env, STATE_RENT_PER_BYTE},
     zk::{empty_witnesses, VerifyingKey, ZkCircuit},
     zkas::ZkBinary,
 };
@@ -44,6 +44,76 @@ impl DbHandle {
     }
 }
 
+/// Account for `new_len` bytes of contract state replacing `old_len` bytes.
+///
+/// Net growth is charged [`STATE_RENT_PER_BYTE`] gas and is refused if it
+/// would take the contract over its state quota. Shrinking state frees up
+/// quota, but the rent paid for it is not refunded.
+/// Every host function writing to a contract's state must go through here,
+/// so the accounted size always matches the records held, which is what
+/// [`ContractStore::backfill_state_sizes`] computes for existing state.
+/// Returns `SUCCESS` on success, otherwise returns an error value.
+///
+/// [`ContractStore::backfill_state_sizes`]: crate::blockchain::ContractStore::backfill_state_sizes
+pub(crate) fn account_state_write(
+    env: &mut Env,
+    store: &mut impl AsStoreMut,
+    old_len: u64,
+    new_len: u64,
+) -> i64 {
+    let cid = env.contract_id;
+    let blockchain = env.blockchain.lock().unwrap();
+
+    let size = match blockchain.contracts.get_state_size(&cid) {
+        Ok(v) => v,
+        Err(e) => {
+            error!(
+                target: "runtime::db::account_state_write",
+                "[WASM] [{}] account_state_write(): Failed to get state size: {}", cid, e,
+            );
+            return darkfi_sdk::error::INTERNAL_ERROR
+        }
+    };
+
+    let new_size = (size + new_len).saturating_sub(old_len);
+    if new_size > size {
+        if let Some(quota) = env.state_quota {
+            if new_size > quota {
+                error!(
+                    target: "runtime::db::account_state_write",
+                    "[WASM] [{}] account_state_write(): State quota exceeded: {} > {}",
+                    cid, new_size, quota,
+                );
+                return darkfi_sdk::error::STATE_QUOTA_EXCEEDED
+            }
+        }
+    }
+
+    if let Err(e) = blockchain.contracts.set_state_size(&cid, new_size) {
+        error!(
+            target: "runtime::db::account_state_write",
+            "[WASM] [{}] account_state_write(): Failed to set state size: {}", cid, e,
+        );
+        return darkfi_sdk::error::INTERNAL_ERROR
+    }
+    drop(blockchain);
+
+    // Subtract the rent for the newly held bytes
+    env.subtract_gas(store, new_size.saturating_sub(size) * STATE_RENT_PER_BYTE);
+
+    wasm::entrypoint::SUCCESS
+}
+
+/// Size in bytes of a record held in a contract's state tree, accounting
+/// both its key and value, or zero if it doesn't exist.
+pub(crate) fn state_record_len(
+    overlay: &sled_overlay::SledDbOverlay,
+    tree: &[u8],
+    key: &[u8],
+) -> Result<u64, sled_overlay::sled::Error> {
+    Ok(overlay.get(tree, key)?.map_or(0, |v| (key.len() + v.len()) as u64))
+}
+
 /// Create a new database instance for the calling contract.
 ///
 /// This function expects to receive a pointer from which a `ContractId`
@@ -436,17 +506,32 @@ pub(crate) fn db_set(mut ctx: FunctionEnvMut<Env>, ptr: WasmPtr<u8>, ptr_len: u3
         return darkfi_sdk::error::CALLER_ACCESS_DENIED
     }
 
+    let tree = db_handle.tree;
+    drop(db_handles);
+
+    // Account the state growth, replacing any existing record
+    let old_len = match state_record_len(
+        &env.blockchain.lock().unwrap().overlay.lock().unwrap(),
+        &tree,
+        &key,
+    ) {
+        Ok(v) => v,
+        Err(e) => {
+            error!(
+                target: "runtime::db::db_set",
+                "[WASM] [{}] db_set(): Internal error getting from tree: {}", cid, e,
+            );
+            return darkfi_sdk::error::DB_SET_FAILED
+        }
+    };
+
+    let ret = account_state_write(env, &mut store, old_len, (key.len() + value.len()) as u64);
+    if ret != wasm::entrypoint::SUCCESS {
+        return ret
+    }
+
     // Insert key-value pair into the database corresponding to this contract
-    if env
-        .blockchain
-        .lock()
-        .unwrap()
-        .overlay
-        .lock()
-        .unwrap()
-        .insert(&db_handle.tree, &key, &value)
-        .is_err()
-    {
+    if env.blockchain.lock().unwrap().overlay.lock().unwrap().insert(&tree, &key, &value).is_err() {
         error!(
             target: "runtime::db::db_set",
             "[WASM] [{}] db_set(): Couldn't insert to db_handle tree", cid,
@@ -556,9 +641,32 @@ pub(crate) fn db_del(mut ctx: FunctionEnvMut<Env>, ptr: WasmPtr<u8>, ptr_len: u3
         return darkfi_sdk::error::CALLER_ACCESS_DENIED
     }
 
+    let tree = db_handle.tree;
+    drop(db_handles);
+
+    // Release the state held by the removed record
+    let old_len = match state_record_len(
+        &env.blockchain.lock().unwrap().overlay.lock().unwrap(),
+        &tree,
+        &key,
+    ) {
+        Ok(v) => v,
+        Err(e) => {
+            error!(
+                target: "runtime::db::db_del",
+                "[WASM] [{}] db_del(): Internal error getting from tree: {}", cid, e,
+            );
+            return darkfi_sdk::error::DB_DEL_FAILED
+        }
+    };
+
+    let ret = account_state_write(env, &mut store, old_len, 0);
+    if ret != wasm::entrypoint::SUCCESS {
+        return ret
+    }
+
     // Remove key-value pair from the database corresponding to this contract
-    if env.blockchain.lock().unwrap().overlay.lock().unwrap().remove(&db_handle.tree, &key).is_err()
-    {
+    if env.blockchain.lock().unwrap().overlay.lock().unwrap().remove(&tree, &key).is_err() {
         error!(
             target: "runtime::db::db_del",
             "[WASM] [{}] db_del(): Couldn't remove key from db_handle tree", cid,
@@ -903,7 +1011,7 @@ pub(crate) fn zkas_db_set(mut ctx: FunctionEnvMut<Env>, ptr: WasmPtr<u8>, ptr_le
     // Check if there is existing bincode and compare it. Return DB_SUCCESS if
     // they're the same. The assumption should be that VerifyingKey was generated
     // already so we can skip things after this guard.
-    match env
+    let old_len = match env
         .blockchain
         .lock()
         .unwrap()
@@ -926,6 +1034,10 @@ pub(crate) fn zkas_db_set(mut ctx: FunctionEnvMut<Env>, ptr: WasmPtr<u8>, ptr_le
                     );
                     return wasm::entrypoint::SUCCESS
                 }
+
+                (serialize(&zkbin.namespace).len() + bytes.len()) as u64
+            } else {
+                0
             }
         }
         Err(e) => {
@@ -970,23 +1082,21 @@ pub(crate) fn zkas_db_set(mut ctx: FunctionEnvMut<Env>, ptr: WasmPtr<u8>, ptr_le
     // Insert the key-value pair into the database.
     let key = serialize(&zkbin.namespace);
     let value = serialize(&(zkbin_bytes, vk_buf));
-    if env
-        .blockchain
-        .lock()
-        .unwrap()
-        .overlay
-        .lock()
-        .unwrap()
-        .insert(&db_handle.tree, &key, &value)
-        .is_err()
-    {
+    let tree = db_handle.tree;
+    drop(db_handles);
+
+    let ret = account_state_write(env, &mut store, old_len, (key.len() + value.len()) as u64);
+    if ret != wasm::entrypoint::SUCCESS {
+        return ret
+    }
+
+    if env.blockchain.lock().unwrap().overlay.lock().unwrap().insert(&tree, &key, &value).is_err() {
         error!(
             target: "runtime::db::zkas_db_set",
             "[WASM] [{}] zkas_db_set(): Couldn't insert to db_handle tree", cid,
         );
         return darkfi_sdk::error::DB_SET_FAILED
     }
-    drop(db_handles);
 
     // Subtract used gas. Here we count the bytes written into the db.
     env.subtract_gas(&mut store, (key.len() + value.len()) as u64);
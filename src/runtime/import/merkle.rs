@@ -27,7 +27,10 @@ use darkfi_serial::{serialize, Decodable, Encodable, WriteExt};
 use log::{debug, error};
 use wasmer::{FunctionEnvMut, WasmPtr};
 
-use super::acl::acl_allow;
+use super::{
+    acl::acl_allow,
+    db::{account_state_write, state_record_len},
+};
 use crate::runtime::vm_runtime::{ContractSection, Env};
 
 /// Adds data to merkle tree. The tree, database connection, and new data to add is
@@ -150,7 +153,8 @@ pub(crate) fn merkle_add(mut ctx: FunctionEnvMut<Env>, ptr: WasmPtr<u8>, len: u3
         }
     };
 
-    // This `coin` represents the leaf we're adding to the Merkle tree
+    // These `coins` represent the leaves we're appending to the Merkle tree,
+    // so the tree is (de)serialized and its root written once per call.
     let coins: Vec<MerkleNode> = match Decodable::decode(&mut buf_reader) {
         Ok(v) => v,
         Err(e) => {
@@ -277,6 +281,21 @@ pub(crate) fn merkle_add(mut ctx: FunctionEnvMut<Env>, ptr: WasmPtr<u8>, len: u3
     env.call_idx.encode(&mut value_data).expect("Unable to serialize call_idx");
     assert_eq!(value_data.len(), 32 + 1);
 
+    // Grab the sizes of the root records we replace, for state accounting
+    let replaced_roots = match (
+        state_record_len(&overlay, &db_roots.tree, &latest_root_data),
+        state_record_len(&overlay, &db_info.tree, &root_key),
+    ) {
+        (Ok(a), Ok(b)) => a + b,
+        (Err(e), _) | (_, Err(e)) => {
+            error!(
+                target: "runtime::merkle::merkle_add",
+                "[WASM] [{}] merkle_add(): Internal error getting from tree: {}", cid, e,
+            );
+            return darkfi_sdk::error::INTERNAL_ERROR
+        }
+    };
+
     if overlay.insert(&db_roots.tree, &latest_root_data, &value_data).is_err() {
         error!(
             target: "runtime::merkle::merkle_add",
@@ -307,6 +326,20 @@ pub(crate) fn merkle_add(mut ctx: FunctionEnvMut<Env>, ptr: WasmPtr<u8>, len: u3
     drop(overlay);
     drop(lock);
     drop(db_handles);
+
+    // Account the state changes
+    let replaced = (tree_key.len() + return_data.len()) as u64 + replaced_roots;
+    let written = tree_key.len() +
+        tree_data.len() +
+        latest_root_data.len() +
+        value_data.len() +
+        root_key.len() +
+        latest_root_data.len();
+    let ret = account_state_write(env, &mut store, replaced, written as u64);
+    if ret != wasm::entrypoint::SUCCESS {
+        return ret
+    }
+
     let spent_gas = return_data.len() + tree_data.len() + (coins_len * 32);
     env.subtract_gas(&mut store, spent_gas as u64);
 
@@ -32,20 +32,48 @@ use log::{debug, error};
 use num_bigint::BigUint;
 use wasmer::{FunctionEnvMut, WasmPtr};
 
-use super::acl::acl_allow;
+use super::{
+    acl::acl_allow,
+    db::{account_state_write, state_record_len},
+};
 use crate::runtime::vm_runtime::{ContractSection, Env};
 
 /// An SMT adapter for sled overlay storage. Compatible with the WasmDb SMT adapter
 pub struct SledStorage<'a> {
     overlay: &'a mut sled_overlay::SledDbOverlay,
     tree_key: &'a [u8],
+    /// State bytes replaced and written, to be accounted once done
+    written: &'a mut (u64, u64),
+}
+
+impl SledStorage<'_> {
+    /// Size of the existing record of the given key, to account it as replaced
+    fn replaced_len(&self, key: &[u8]) -> Option<u64> {
+        match state_record_len(&*self.overlay, self.tree_key, key) {
+            Ok(v) => Some(v),
+            Err(e) => {
+                error!(
+                    target: "runtime::smt::SledStorage::replaced_len",
+                    "[WASM] SledStorage::replaced_len(): Fetching key {:?} from DB tree: {:?}: {}",
+                    key, self.tree_key, e,
+                );
+                None
+            }
+        }
+    }
 }
 
 impl StorageAdapter for SledStorage<'_> {
     type Value = pallas::Base;
 
     fn put(&mut self, key: BigUint, value: pallas::Base) -> ContractResult {
-        if let Err(e) = self.overlay.insert(self.tree_key, &key.to_bytes_le(), &value.to_repr()) {
+        let key_bytes = key.to_bytes_le();
+        let Some(replaced) = self.replaced_len(&key_bytes) else {
+            return Err(ContractError::SmtPutFailed)
+        };
+
+        let value_bytes = value.to_repr();
+        if let Err(e) = self.overlay.insert(self.tree_key, &key_bytes, &value_bytes) {
             error!(
                 target: "runtime::smt::SledStorage::put",
                 "[WASM] SledStorage::put(): inserting key {:?}, value {:?} into DB tree: {:?}: {}",
@@ -54,6 +82,8 @@ impl StorageAdapter for SledStorage<'_> {
             return Err(ContractError::SmtPutFailed)
         }
 
+        self.written.0 += replaced;
+        self.written.1 += (key_bytes.len() + value_bytes.len()) as u64;
         Ok(())
     }
 
@@ -78,7 +108,12 @@ impl StorageAdapter for SledStorage<'_> {
     }
 
     fn del(&mut self, key: &BigUint) -> ContractResult {
-        if let Err(e) = self.overlay.remove(self.tree_key, &key.to_bytes_le()) {
+        let key_bytes = key.to_bytes_le();
+        let Some(replaced) = self.replaced_len(&key_bytes) else {
+            return Err(ContractError::SmtDelFailed)
+        };
+
+        if let Err(e) = self.overlay.remove(self.tree_key, &key_bytes) {
             error!(
                 target: "runtime::smt::SledStorage::del",
                 "[WASM] SledStorage::del(): Removing key {:?} from DB tree: {:?}: {}",
@@ -87,6 +122,7 @@ impl StorageAdapter for SledStorage<'_> {
             return Err(ContractError::SmtDelFailed)
         }
 
+        self.written.0 += replaced;
         Ok(())
     }
 }
@@ -241,7 +277,9 @@ pub(crate) fn sparse_merkle_insert_batch(
     let hasher = PoseidonFp::new();
     let lock = env.blockchain.lock().unwrap();
     let mut overlay = lock.overlay.lock().unwrap();
-    let smt_store = SledStorage { overlay: &mut overlay, tree_key: &db_smt.tree };
+    let mut written = (0, 0);
+    let smt_store =
+        SledStorage { overlay: &mut overlay, tree_key: &db_smt.tree, written: &mut written };
     let mut smt = SparseMerkleTree::<
         SMT_FP_DEPTH,
         { SMT_FP_DEPTH + 1 },
@@ -315,6 +353,9 @@ pub(crate) fn sparse_merkle_insert_batch(
 
     // If the record exists, append the new value data,
     // otherwise create a new set with it.
+    if let Some(ref value_data_set) = root_value_data_set {
+        written.0 += (latest_root_data.len() + value_data_set.len()) as u64;
+    }
     let root_value_data_set = match root_value_data_set {
         Some(value_data_set) => {
             let mut value_data_set: Vec<Vec<u8>> = match deserialize(&value_data_set) {
@@ -342,20 +383,31 @@ pub(crate) fn sparse_merkle_insert_batch(
         target: "runtime::smt::sparse_merkle_insert_batch",
         "[WASM] [{}] sparse_merkle_insert_batch(): Appending SMT root to db: {:?}", cid, latest_root,
     );
-    if overlay.insert(&db_roots.tree, &latest_root_data, &serialize(&root_value_data_set)).is_err()
-    {
+    let root_value_data_set = serialize(&root_value_data_set);
+    if overlay.insert(&db_roots.tree, &latest_root_data, &root_value_data_set).is_err() {
         error!(
             target: "runtime::smt::sparse_merkle_insert_batch",
             "[WASM] [{}] sparse_merkle_insert_batch(): Couldn't insert to db_roots tree", cid,
         );
         return darkfi_sdk::error::INTERNAL_ERROR
     }
+    written.1 += (latest_root_data.len() + root_value_data_set.len()) as u64;
 
     // Update the pointer to the latest known root
     debug!(
         target: "runtime::smt::sparse_merkle_insert_batch",
         "[WASM] [{}] sparse_merkle_insert_batch(): Replacing latest SMT root pointer", cid,
     );
+    match state_record_len(&overlay, &db_info.tree, &root_key) {
+        Ok(v) => written.0 += v,
+        Err(e) => {
+            error!(
+                target: "runtime::smt::sparse_merkle_insert_batch",
+                "[WASM] [{}] sparse_merkle_insert_batch(): Internal error getting from db_info tree: {}", cid, e,
+            );
+            return darkfi_sdk::error::INTERNAL_ERROR
+        }
+    }
     if overlay.insert(&db_info.tree, &root_key, &latest_root_data).is_err() {
         error!(
             target: "runtime::smt::sparse_merkle_insert_batch",
@@ -370,6 +422,11 @@ pub(crate) fn sparse_merkle_insert_batch(
     drop(overlay);
     drop(lock);
     drop(db_handles);
+    written.1 += (root_key.len() + latest_root_data.len()) as u64;
+    let ret = account_state_write(env, &mut store, written.0, written.1);
+    if ret != wasm::entrypoint::SUCCESS {
+        return ret
+    }
     env.subtract_gas(&mut store, inserted_nullifiers as u64);
 
     wasm::entrypoint::SUCCESS
@@ -18,9 +18,16 @@
 
 use std::io::Cursor;
 
-use darkfi_sdk::wasm;
+use darkfi_sdk::{
+    crypto::{
+        mimc_vdf,
+        timelock::{TIMELOCK_MAX_VERIFY_STEPS, TIMELOCK_VERIFY_STEP_GAS},
+    },
+    wasm,
+};
 use darkfi_serial::Decodable;
 use log::{debug, error};
+use num_bigint::BigUint;
 use wasmer::{FunctionEnvMut, WasmPtr};
 
 use super::acl::acl_allow;
@@ -608,3 +615,90 @@ pub(crate) fn get_tx_location(mut ctx: FunctionEnvMut<Env>, ptr: WasmPtr<u8>) ->
     objects.push(return_data.to_vec());
     (objects.len() - 1) as i64
 }
+
+/// Verifies a time-lock VDF puzzle solution natively.
+///
+/// Reads the serialized puzzle seed, number of steps and witness.
+/// Returns `1` if the witness solves the puzzle, `0` if it doesn't.
+/// Otherwise, returns an error code.
+///
+/// Permissions: deploy, metadata, exec
+pub(crate) fn timelock_verify(mut ctx: FunctionEnvMut<Env>, ptr: WasmPtr<u8>, ptr_len: u32) -> i64 {
+    let (env, mut store) = ctx.data_and_store_mut();
+    let cid = env.contract_id;
+
+    if let Err(e) =
+        acl_allow(env, &[ContractSection::Deploy, ContractSection::Metadata, ContractSection::Exec])
+    {
+        error!(
+            target: "runtime::util::timelock_verify",
+            "[WASM] [{}] timelock_verify(): Called in unauthorized section: {}", cid, e,
+        );
+        return darkfi_sdk::error::CALLER_ACCESS_DENIED
+    }
+
+    // Subtract used gas. Here we count the length of the arguments.
+    env.subtract_gas(&mut store, ptr_len as u64);
+
+    // Ensure that it is possible to read memory
+    let memory_view = env.memory_view(&store);
+    let Ok(mem_slice) = ptr.slice(&memory_view, ptr_len) else {
+        error!(
+            target: "runtime::util::timelock_verify",
+            "[WASM] [{}] timelock_verify(): Failed to make slice from ptr", cid,
+        );
+        return darkfi_sdk::error::INTERNAL_ERROR
+    };
+
+    let mut buf = vec![0_u8; ptr_len as usize];
+    if let Err(e) = mem_slice.read_slice(&mut buf) {
+        error!(
+            target: "runtime::util::timelock_verify",
+            "[WASM] [{}] timelock_verify(): Failed to read from memory slice: {}", cid, e,
+        );
+        return darkfi_sdk::error::INTERNAL_ERROR
+    };
+
+    let mut buf_reader = Cursor::new(buf);
+
+    // Decode the puzzle seed, steps and witness
+    let (seed, steps, witness): ([u8; 32], u64, [u8; 32]) = match Decodable::decode(&mut buf_reader)
+    {
+        Ok(v) => v,
+        Err(e) => {
+            error!(
+                target: "runtime::util::timelock_verify",
+                "[WASM] [{}] timelock_verify(): Failed to decode arguments: {}", cid, e,
+            );
+            return darkfi_sdk::error::INTERNAL_ERROR
+        }
+    };
+
+    // Make sure there are no trailing bytes in the buffer. This means we've used all data that was
+    // supplied.
+    if buf_reader.position() != ptr_len as u64 {
+        error!(
+            target: "runtime::util::timelock_verify",
+            "[WASM] [{}] timelock_verify(): Trailing bytes in argument stream", cid,
+        );
+        return darkfi_sdk::error::INTERNAL_ERROR
+    }
+
+    // Bound the native work a contract can request
+    if steps > TIMELOCK_MAX_VERIFY_STEPS {
+        error!(
+            target: "runtime::util::timelock_verify",
+            "[WASM] [{}] timelock_verify(): Puzzle steps {} exceed maximum {}", cid, steps,
+            TIMELOCK_MAX_VERIFY_STEPS,
+        );
+        return darkfi_sdk::error::TIMELOCK_INVALID_PARAMS
+    }
+
+    // Subtract used gas. Here we count the verified VDF steps.
+    env.subtract_gas(&mut store, steps * TIMELOCK_VERIFY_STEP_GAS);
+
+    let valid =
+        mimc_vdf::verify(&BigUint::from_bytes_le(&seed), steps, &BigUint::from_bytes_le(&witness));
+
+    valid as i64
+}
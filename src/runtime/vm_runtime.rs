@@ -21,7 +21,14 @@ use std::{
     sync::Arc,
 };
 
-use darkfi_sdk::{crypto::ContractId, tx::TransactionHash, wasm, AsHex};
+use darkfi_sdk::{
+    crypto::{
+        ContractId, AUCTION_CONTRACT_ID, DAO_CONTRACT_ID, DARKNAME_CONTRACT_ID,
+        DEPLOYOOOR_CONTRACT_ID, MONEY_CONTRACT_ID, ORACLE_CONTRACT_ID,
+    },
+    tx::TransactionHash,
+    wasm, AsHex,
+};
 use darkfi_serial::serialize;
 use log::{debug, error, info};
 use wasmer::{
@@ -46,6 +53,9 @@ const MEMORY: &str = "memory";
 /// Gas limit for a single contract call (Single WASM instance)
 const GAS_LIMIT: u64 = 400_000_000;
 
+/// Gas charged per byte of net state growth, on top of the write cost
+pub const STATE_RENT_PER_BYTE: u64 = 10;
+
 // ANCHOR: contract-section
 #[derive(Clone, Copy, PartialEq)]
 pub enum ContractSection {
@@ -105,6 +115,9 @@ pub struct Env {
     pub call_idx: u8,
     /// Parent `Instance`
     pub instance: Option<Arc<Instance>>,
+    /// Maximum amount of state bytes the contract can hold.
+    /// Native contracts are not limited.
+    pub state_quota: Option<u64>,
 }
 
 impl Env {
@@ -187,6 +200,22 @@ impl Runtime {
         let db_handles = RefCell::new(vec![]);
         let logs = RefCell::new(vec![]);
 
+        // Native contracts hold the chain's own state, so only deployed
+        // contracts are bound by a state quota.
+        let native_contracts = [
+            *MONEY_CONTRACT_ID,
+            *DAO_CONTRACT_ID,
+            *DEPLOYOOOR_CONTRACT_ID,
+            *DARKNAME_CONTRACT_ID,
+            *AUCTION_CONTRACT_ID,
+            *ORACLE_CONTRACT_ID,
+        ];
+        let state_quota = if native_contracts.contains(&contract_id) {
+            None
+        } else {
+            Some(blockchain.lock().unwrap().state_quota)
+        };
+
         debug!(target: "runtime::vm_runtime", "Importing functions");
 
         let ctx = FunctionEnv::new(
@@ -206,6 +235,7 @@ impl Runtime {
                 tx_hash,
                 call_idx,
                 instance: None,
+                state_quota,
             },
         );
 
@@ -336,6 +366,12 @@ impl Runtime {
                     &ctx,
                     import::util::get_tx_location,
                 ),
+
+                "timelock_verify_" => Function::new_typed_with_env(
+                    &mut store,
+                    &ctx,
+                    import::util::timelock_verify,
+                ),
             }
         };
 
@@ -349,6 +385,11 @@ impl Runtime {
         Ok(Self { instance, store, ctx })
     }
 
+    /// Override the state quota of the contract. `None` lifts the limit.
+    pub fn set_state_quota(&mut self, quota: Option<u64>) {
+        self.ctx.as_mut(&mut self.store).state_quota = quota;
+    }
+
     /// Call a contract method defined by a [`ContractSection`] using a supplied
     /// payload. Returns a `Vec<u8>` corresponding to the result data of the call.
     /// For calls that do not return any data, an empty `Vec<u8>` is returned.
@@ -538,6 +579,12 @@ impl Runtime {
         Ok(())
     }
 
+    /// Returns `true` if the contract looked up any state database, so its
+    /// results might depend on the current state.
+    pub fn accessed_state(&self) -> bool {
+        !self.ctx.as_ref(&self.store).db_handles.borrow().is_empty()
+    }
+
     /// Prints the wasm contract logs.
     fn print_logs(&self) {
         let logs = self.ctx.as_ref(&self.store).logs.borrow();
@@ -27,6 +27,9 @@ pub use fixed_bases::{
 /// Domain prefix used for Schnorr signatures, with `hash_to_scalar`.
 pub const DRK_SCHNORR_DOMAIN: &[u8] = b"DarkFi:Schnorr";
 
+/// Domain prefix used for Schnorr batch verification weights, with `hash_to_scalar`.
+pub const DRK_SCHNORR_BATCH_DOMAIN: &[u8] = b"DarkFi:SchnBatch";
+
 /// Domain prefix used for block hashes, with `hash_to_curve`.
 pub const BLOCK_HASH_DOMAIN: &str = "DarkFi:Block";
 
@@ -22,7 +22,7 @@ use num_bigint::BigUint;
 use num_traits::Num;
 
 /// Modulus of prime field 2^256 - 2^32 * 351 + 1
-const MODULUS: &str =
+pub(crate) const MODULUS: &str =
     "115792089237316195423570985008687907853269984665640564039457584006405596119041";
 
 /// An exponent to perform inverse of x^3 on prime field based on Fermat's Little Theorem
@@ -79,6 +79,13 @@ pub fn eval(seed: &BigUint, num_steps: u64) -> BigUint {
     backward_mimc(num_steps, seed)
 }
 
+/// Computes the seed whose Eval() step results in the given `witness`.
+/// This runs in the fast direction, allowing to construct puzzles with
+/// a known solution.
+pub fn seed(witness: &BigUint, num_steps: u64) -> BigUint {
+    forward_mimc(num_steps, witness)
+}
+
 /// Performs a Verify() step for the MiMC-based VDF result
 pub fn verify(seed: &BigUint, num_steps: u64, witness: &BigUint) -> bool {
     forward_mimc(num_steps, witness) == *seed
@@ -0,0 +1,268 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2025 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! BIP39 mnemonic seed phrases.
+//!
+//! Implements phrase generation and recovery using the standard BIP39
+//! English wordlist and checksum, so phrases are interchangeable with
+//! other BIP39 tools. The seed is derived using PBKDF2-HMAC-SHA512 over
+//! the NFKD normalized phrase and passphrase as specified, and is meant
+//! to be fed into [`super::hd::ExtendedSecretKey`].
+
+use core::str::FromStr;
+
+use lazy_static::lazy_static;
+use rand_core::{CryptoRng, RngCore};
+use sha2::{Digest, Sha256, Sha512};
+use unicode_normalization::UnicodeNormalization;
+
+use crate::error::ContractError;
+
+/// BIP39 English wordlist
+const ENGLISH_WORDLIST: &str = include_str!("english.txt");
+
+/// PBKDF2 rounds used for seed derivation
+const PBKDF2_ROUNDS: u32 = 2048;
+
+/// Length of the derived seed in bytes
+pub const SEED_LEN: usize = 64;
+
+lazy_static! {
+    static ref WORDLIST: Vec<&'static str> = ENGLISH_WORDLIST.lines().collect();
+}
+
+/// Supported amounts of words in a phrase
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum WordCount {
+    Words12 = 12,
+    Words15 = 15,
+    Words18 = 18,
+    Words21 = 21,
+    Words24 = 24,
+}
+
+impl WordCount {
+    /// Entropy length in bytes for this amount of words
+    fn entropy_len(&self) -> usize {
+        (*self as usize) * 11 * 32 / 33 / 8
+    }
+}
+
+impl TryFrom<usize> for WordCount {
+    type Error = ContractError;
+
+    fn try_from(n: usize) -> Result<Self, Self::Error> {
+        match n {
+            12 => Ok(Self::Words12),
+            15 => Ok(Self::Words15),
+            18 => Ok(Self::Words18),
+            21 => Ok(Self::Words21),
+            24 => Ok(Self::Words24),
+            _ => Err(ContractError::IoError(format!("Invalid mnemonic word count: {n}"))),
+        }
+    }
+}
+
+/// A BIP39 mnemonic phrase, holding the entropy it encodes
+#[derive(Clone, PartialEq, Eq)]
+pub struct Mnemonic {
+    entropy: Vec<u8>,
+}
+
+impl core::fmt::Debug for Mnemonic {
+    /// Avoid leaking the phrase in logs
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        write!(f, "Mnemonic({} words)", self.entropy.len() * 8 * 33 / 32 / 11)
+    }
+}
+
+impl Mnemonic {
+    /// Generate a new random `Mnemonic` with the given amount of words
+    pub fn generate(rng: &mut (impl CryptoRng + RngCore), word_count: WordCount) -> Self {
+        let mut entropy = vec![0u8; word_count.entropy_len()];
+        rng.fill_bytes(&mut entropy);
+        Self { entropy }
+    }
+
+    /// Create a `Mnemonic` from raw entropy. The entropy length must
+    /// be one of 16, 20, 24, 28, or 32 bytes.
+    pub fn from_entropy(entropy: &[u8]) -> Result<Self, ContractError> {
+        if entropy.len() < 16 || entropy.len() > 32 || entropy.len() % 4 != 0 {
+            return Err(ContractError::IoError(format!(
+                "Invalid mnemonic entropy length: {}",
+                entropy.len()
+            )))
+        }
+
+        Ok(Self { entropy: entropy.to_vec() })
+    }
+
+    /// Get the entropy encoded by this `Mnemonic`
+    pub fn entropy(&self) -> &[u8] {
+        &self.entropy
+    }
+
+    /// Checksum bits for the given entropy, left aligned in a byte
+    fn checksum(entropy: &[u8]) -> u8 {
+        let n_bits = (entropy.len() / 4) as u32;
+        let hash = Sha256::digest(entropy);
+        hash[0] & !0xffu8.checked_shr(n_bits).unwrap_or(0)
+    }
+
+    /// Get the wordlist indexes of the phrase words
+    fn indexes(&self) -> Vec<usize> {
+        let mut bits = self.entropy.clone();
+        bits.push(Self::checksum(&self.entropy));
+
+        let n_words = (self.entropy.len() * 8 + self.entropy.len() / 4) / 11;
+        (0..n_words)
+            .map(|i| {
+                (0..11).fold(0, |acc, j| {
+                    let bit = i * 11 + j;
+                    let set = (bits[bit / 8] >> (7 - bit % 8)) & 1;
+                    (acc << 1) | set as usize
+                })
+            })
+            .collect()
+    }
+
+    /// Get the phrase words
+    pub fn words(&self) -> Vec<&'static str> {
+        self.indexes().into_iter().map(|i| WORDLIST[i]).collect()
+    }
+
+    /// Derive the 64 byte seed of this `Mnemonic` using an optional
+    /// `passphrase`. An empty passphrase should be used if none is set.
+    pub fn to_seed(&self, passphrase: &str) -> [u8; SEED_LEN] {
+        let phrase: String = self.to_string().nfkd().collect();
+        let salt: String = format!("mnemonic{passphrase}").nfkd().collect();
+
+        let mut seed = [0u8; SEED_LEN];
+        pbkdf2::pbkdf2_hmac::<Sha512>(phrase.as_bytes(), salt.as_bytes(), PBKDF2_ROUNDS, &mut seed);
+        seed
+    }
+}
+
+impl FromStr for Mnemonic {
+    type Err = ContractError;
+
+    /// Recover a `Mnemonic` from a whitespace separated phrase,
+    /// verifying its words and checksum.
+    fn from_str(phrase: &str) -> Result<Self, Self::Err> {
+        let phrase: String = phrase.nfkd().collect();
+        let words: Vec<String> = phrase.split_whitespace().map(|w| w.to_lowercase()).collect();
+        let word_count = WordCount::try_from(words.len())?;
+
+        let n_bits = words.len() * 11;
+        let mut bits = vec![0u8; n_bits.div_ceil(8)];
+        for (i, word) in words.iter().enumerate() {
+            let Ok(idx) = WORDLIST.binary_search(&word.as_str()) else {
+                return Err(ContractError::IoError(format!("Unknown mnemonic word: {word}")))
+            };
+
+            for j in 0..11 {
+                if (idx >> (10 - j)) & 1 == 1 {
+                    let bit = i * 11 + j;
+                    bits[bit / 8] |= 1 << (7 - bit % 8);
+                }
+            }
+        }
+
+        let entropy = bits[..word_count.entropy_len()].to_vec();
+        let checksum = bits[word_count.entropy_len()];
+        if checksum != Self::checksum(&entropy) {
+            return Err(ContractError::IoError("Invalid mnemonic checksum".to_string()))
+        }
+
+        Ok(Self { entropy })
+    }
+}
+
+impl core::fmt::Display for Mnemonic {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        write!(f, "{}", self.words().join(" "))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hex::AsHex;
+    use rand::rngs::OsRng;
+
+    #[test]
+    fn wordlist_sorted() {
+        assert_eq!(WORDLIST.len(), 2048);
+        assert!(WORDLIST.windows(2).all(|w| w[0] < w[1]));
+    }
+
+    #[test]
+    fn bip39_vectors() {
+        // From the reference BIP39 test vectors, using "TREZOR" as passphrase
+        let mnemonic = Mnemonic::from_entropy(&[0u8; 16]).unwrap();
+        assert_eq!(
+            mnemonic.to_string(),
+            "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about"
+        );
+
+        let seed = mnemonic.to_seed("TREZOR");
+        assert_eq!(
+            seed.hex(),
+            "c55257c360c07c72029aebc1b53c05ed0362ada38ead3e3e9efa3708e53495531f09a6987599d18264c1e1c92f2cf141630c7a3c4ab7c81b2f001698e7463b04"
+        );
+
+        let mnemonic = Mnemonic::from_entropy(&[0xff; 32]).unwrap();
+        assert_eq!(
+            mnemonic.to_string(),
+            "zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo vote"
+        );
+        assert_eq!(
+            mnemonic.to_seed("TREZOR").hex(),
+            "dd48c104698c30cfe2b6142103248622fb7bb0ff692eebb00089b32d22484e1613912f0a5b694407be899ffd31ed3992c456cdf60f5d4564b8ba3f05a69890ad"
+        );
+    }
+
+    #[test]
+    fn seed_normalization() {
+        // Composed and decomposed forms of the same passphrase must
+        // derive the same seed.
+        let mnemonic = Mnemonic::from_entropy(&[7u8; 16]).unwrap();
+        assert_eq!(mnemonic.to_seed("caf\u{e9}"), mnemonic.to_seed("cafe\u{301}"));
+        assert_ne!(mnemonic.to_seed("caf\u{e9}"), mnemonic.to_seed("cafe"));
+
+        // Phrases using fullwidth characters normalize to the wordlist
+        let phrase = mnemonic.to_string().replace('a', "\u{ff41}");
+        assert_eq!(Mnemonic::from_str(&phrase).unwrap(), mnemonic);
+    }
+
+    #[test]
+    fn mnemonic_roundtrip() {
+        for word_count in [WordCount::Words12, WordCount::Words18, WordCount::Words24] {
+            let mnemonic = Mnemonic::generate(&mut OsRng, word_count);
+            assert_eq!(mnemonic.words().len(), word_count as usize);
+
+            let recovered = Mnemonic::from_str(&mnemonic.to_string()).unwrap();
+            assert_eq!(mnemonic, recovered);
+        }
+
+        // Swapping words should break the checksum
+        let bad = "about abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon";
+        assert!(Mnemonic::from_str(bad).is_err());
+        assert!(Mnemonic::from_str("abandon notaword").is_err());
+    }
+}
@@ -34,9 +34,20 @@ pub use util::poseidon_hash;
 pub mod keypair;
 pub use keypair::{Keypair, PublicKey, SecretKey};
 
+/// BIP39 mnemonic seed phrases
+pub mod mnemonic;
+pub use mnemonic::Mnemonic;
+
+/// Hierarchical deterministic key derivation
+pub mod hd;
+pub use hd::ExtendedSecretKey;
+
 /// Contract ID definitions and methods
 pub mod contract_id;
-pub use contract_id::{ContractId, DAO_CONTRACT_ID, DEPLOYOOOR_CONTRACT_ID, MONEY_CONTRACT_ID};
+pub use contract_id::{
+    ContractId, AUCTION_CONTRACT_ID, DAO_CONTRACT_ID, DARKNAME_CONTRACT_ID, DEPLOYOOOR_CONTRACT_ID,
+    MONEY_CONTRACT_ID, ORACLE_CONTRACT_ID,
+};
 
 /// Function ID definitions and methods
 pub mod func_ref;
@@ -59,6 +70,10 @@ pub mod schnorr;
 /// MiMC VDF
 pub mod mimc_vdf;
 
+/// Time-lock encryption to a future block height
+pub mod timelock;
+pub use timelock::TimeLockedPayload;
+
 /// Elliptic curve VRF (Verifiable Random Function)
 pub mod ecvrf;
 
@@ -20,13 +20,17 @@
 use darkfi_serial::async_trait;
 use darkfi_serial::{SerialDecodable, SerialEncodable};
 use halo2_gadgets::ecc::chip::FixedPoint;
+use halo2_proofs::arithmetic::best_multiexp;
 use pasta_curves::{
-    group::{ff::PrimeField, Group, GroupEncoding},
+    group::{
+        ff::{Field, PrimeField},
+        Curve, Group, GroupEncoding,
+    },
     pallas,
 };
 
 use super::{
-    constants::{NullifierK, DRK_SCHNORR_DOMAIN},
+    constants::{NullifierK, DRK_SCHNORR_BATCH_DOMAIN, DRK_SCHNORR_DOMAIN},
     util::{fp_mod_fv, hash_to_scalar},
     PublicKey, SecretKey,
 };
@@ -87,6 +91,61 @@ impl SchnorrPublic for PublicKey {
     }
 }
 
+/// Verify a batch of `(public_key, message, signature)` tuples at once.
+///
+/// Each verification equation `s_i*G - c_i*P_i - R_i = 0` is weighted with
+/// a scalar `z_i` derived from the entire batch transcript, and the sum is
+/// checked with a single multi-scalar multiplication. The weights bind all
+/// signatures together, so an invalid signature can't be cancelled out by
+/// another one in the batch. Returns `true` only if every signature is valid.
+/// On failure, use [`SchnorrPublic::verify`] to find the offending entry.
+pub fn verify_batch(items: &[(&PublicKey, &[u8], &Signature)]) -> bool {
+    match items.len() {
+        0 => return true,
+        1 => return items[0].0.verify(items[0].1, items[0].2),
+        _ => {}
+    }
+
+    // Compute the per-signature challenges and the batch transcript
+    let mut challenges = Vec::with_capacity(items.len());
+    let mut hasher = blake3::Hasher::new();
+    for (pubkey, message, signature) in items {
+        let commit_bytes = signature.commit.to_bytes();
+        let pubkey_bytes = pubkey.to_bytes();
+        let challenge =
+            hash_to_scalar(DRK_SCHNORR_DOMAIN, &[&commit_bytes, &pubkey_bytes, *message]);
+
+        hasher.update(&commit_bytes);
+        hasher.update(&pubkey_bytes);
+        hasher.update(&signature.response.to_repr());
+        hasher.update(&challenge.to_repr());
+        challenges.push(challenge);
+    }
+    let seed = hasher.finalize();
+
+    // Sum over z_i*R_i + (z_i*c_i)*P_i - (z_i*s_i)*G
+    let mut coeffs = Vec::with_capacity(2 * items.len() + 1);
+    let mut points = Vec::with_capacity(2 * items.len() + 1);
+    let mut response_sum = pallas::Scalar::ZERO;
+    for (i, ((pubkey, _, signature), challenge)) in items.iter().zip(challenges).enumerate() {
+        let weight =
+            hash_to_scalar(DRK_SCHNORR_BATCH_DOMAIN, &[seed.as_bytes(), &(i as u64).to_le_bytes()]);
+
+        coeffs.push(weight);
+        points.push(signature.commit);
+        coeffs.push(weight * challenge);
+        points.push(pubkey.inner());
+        response_sum += weight * signature.response;
+    }
+    coeffs.push(-response_sum);
+    points.push(NullifierK.generator().into());
+
+    let mut bases = vec![pallas::Affine::default(); points.len()];
+    pallas::Point::batch_normalize(&points, &mut bases);
+
+    bool::from(best_multiexp(&coeffs, &bases).is_identity())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -106,4 +165,36 @@ mod tests {
         let de = deserialize(&ser).unwrap();
         assert!(public.verify(message, &de));
     }
+
+    #[test]
+    fn test_schnorr_batch_verification() {
+        let messages: Vec<Vec<u8>> = (0..8u8).map(|i| vec![i; 32]).collect();
+        let secrets: Vec<SecretKey> = (0..8).map(|_| SecretKey::random(&mut OsRng)).collect();
+        let publics: Vec<PublicKey> = secrets.iter().map(|s| PublicKey::from_secret(*s)).collect();
+        let mut signatures: Vec<Signature> =
+            secrets.iter().zip(&messages).map(|(s, m)| s.sign(m)).collect();
+
+        let batch = |sigs: &[Signature]| -> bool {
+            let items: Vec<_> = publics
+                .iter()
+                .zip(&messages)
+                .zip(sigs)
+                .map(|((p, m), s)| (p, m.as_slice(), s))
+                .collect();
+            verify_batch(&items)
+        };
+
+        assert!(verify_batch(&[]));
+        assert!(batch(&signatures));
+        assert!(batch(&signatures[..1]));
+
+        // Swapping two signatures must fail
+        signatures.swap(2, 5);
+        assert!(!batch(&signatures));
+        signatures.swap(2, 5);
+
+        // A tampered response must fail
+        signatures[7].response += pallas::Scalar::ONE;
+        assert!(!batch(&signatures));
+    }
 }
@@ -0,0 +1,229 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2025 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Time-lock encryption to a future block height.
+//!
+//! The sealer picks a random MiMC VDF witness, derives the puzzle seed
+//! in the fast direction and encrypts the payload with a key derived
+//! from the witness. Opening requires evaluating the VDF in the slow
+//! direction, for a number of sequential steps calibrated to the blocks
+//! remaining until the target height, so nobody (including validators)
+//! can read the payload early.
+//!
+//! Contracts enforce the target height against the verifying block
+//! height, and check a revealed witness against the puzzle seed through
+//! the `timelock_verify` host function. This enables e.g. sealed-bid
+//! auctions and fair-launch reveals.
+//!
+//! Note that verifying the MiMC VDF is only a few times faster than
+//! evaluating it, and verification runs on every validator, so puzzles
+//! are capped to [`TIMELOCK_MAX_VERIFY_STEPS`] steps and each verified
+//! step is charged its native cost in gas. This bounds how far ahead
+//! payloads can be locked, making the scheme suited for short horizons.
+
+use std::io::Cursor;
+
+use chacha20poly1305::{AeadInPlace, ChaCha20Poly1305, KeyInit};
+use darkfi_serial::{serialize, Decodable, Encodable, SerialDecodable, SerialEncodable};
+use num_bigint::BigUint;
+use num_traits::Num;
+use rand_core::{CryptoRng, RngCore};
+
+#[cfg(feature = "async")]
+use darkfi_serial::async_trait;
+
+use super::mimc_vdf;
+use crate::error::ContractError;
+
+/// Key derivation context of the payload encryption key
+const TIMELOCK_KDF_CONTEXT: &str = "DarkFi 2025 time-lock encryption key";
+
+/// Default number of VDF steps a puzzle requires per block until the
+/// target height, allowing payloads to be locked up to 32 blocks ahead.
+pub const TIMELOCK_DEFAULT_STEPS_PER_BLOCK: u64 = 1 << 8;
+
+/// Maximum number of VDF steps the runtime verifies for contracts.
+/// At [`TIMELOCK_VERIFY_STEP_GAS`], this uses about a quarter of the
+/// contract call gas limit.
+pub const TIMELOCK_MAX_VERIFY_STEPS: u64 = 1 << 13;
+
+/// Gas charged by the runtime per verified VDF step. A verification
+/// step takes around 13μs natively, while a gas unit accounts for a
+/// single WASM instruction.
+pub const TIMELOCK_VERIFY_STEP_GAS: u64 = 13_000;
+
+/// Encode a VDF field element into its fixed-size little-endian form
+fn encode_element(x: &BigUint) -> [u8; 32] {
+    let mut ret = [0u8; 32];
+    let bytes = x.to_bytes_le();
+    ret[..bytes.len()].copy_from_slice(&bytes);
+    ret
+}
+
+/// A payload encrypted until a target block height
+#[derive(Debug, Clone, PartialEq, Eq, SerialEncodable, SerialDecodable)]
+pub struct TimeLockedPayload {
+    /// Block height from which the payload is meant to be opened
+    pub target_height: u32,
+    /// Sequential VDF steps required to open the payload
+    pub steps: u64,
+    /// VDF puzzle seed
+    pub seed: [u8; 32],
+    /// Encrypted payload
+    pub ciphertext: Vec<u8>,
+}
+
+impl TimeLockedPayload {
+    /// Seal given payload until `target_height`, using `steps_per_block`
+    /// VDF steps for every block after `current_height`.
+    pub fn seal(
+        payload: &impl Encodable,
+        current_height: u32,
+        target_height: u32,
+        steps_per_block: u64,
+        rng: &mut (impl CryptoRng + RngCore),
+    ) -> Result<Self, ContractError> {
+        // Target height must be in the future
+        if target_height <= current_height {
+            return Err(ContractError::TimeLockInvalidParams)
+        }
+
+        // Contracts must be able to verify the puzzle solution
+        let Some(steps) = steps_per_block.checked_mul((target_height - current_height) as u64)
+        else {
+            return Err(ContractError::TimeLockInvalidParams)
+        };
+        if !(2..=TIMELOCK_MAX_VERIFY_STEPS).contains(&steps) {
+            return Err(ContractError::TimeLockInvalidParams)
+        }
+
+        // Sample a random witness in the VDF field
+        let mut bytes = [0u8; 64];
+        rng.fill_bytes(&mut bytes);
+        let modulus = BigUint::from_str_radix(mimc_vdf::MODULUS, 10).unwrap();
+        let witness = encode_element(&(BigUint::from_bytes_le(&bytes) % modulus));
+        let seed = encode_element(&mimc_vdf::seed(&BigUint::from_bytes_le(&witness), steps));
+
+        let mut ciphertext = serialize(payload);
+        let mut this = Self { target_height, steps, seed, ciphertext: vec![] };
+        let key = this.key(&witness);
+        ChaCha20Poly1305::new(key.as_ref().into())
+            .encrypt_in_place([0u8; 12][..].into(), &this.aad(), &mut ciphertext)
+            .unwrap();
+        this.ciphertext = ciphertext;
+
+        Ok(this)
+    }
+
+    /// Solve the VDF puzzle, returning the witness opening the payload.
+    /// This is the slow direction, taking `self.steps` sequential steps.
+    pub fn solve(&self) -> [u8; 32] {
+        encode_element(&mimc_vdf::eval(&BigUint::from_bytes_le(&self.seed), self.steps))
+    }
+
+    /// Verify that given witness solves the VDF puzzle.
+    pub fn verify(&self, witness: &[u8; 32]) -> bool {
+        mimc_vdf::verify(
+            &BigUint::from_bytes_le(&self.seed),
+            self.steps,
+            &BigUint::from_bytes_le(witness),
+        )
+    }
+
+    /// Decrypt the payload using the puzzle witness. The AEAD ensures a
+    /// wrong witness, or tampered metadata, fails decryption.
+    pub fn decrypt<D: Decodable>(&self, witness: &[u8; 32]) -> Result<D, ContractError> {
+        let key = self.key(witness);
+        let mut plaintext = self.ciphertext.clone();
+
+        match ChaCha20Poly1305::new(key.as_ref().into()).decrypt_in_place(
+            [0u8; 12][..].into(),
+            &self.aad(),
+            &mut plaintext,
+        ) {
+            Ok(()) => {
+                let mut cursor = Cursor::new(&plaintext[..]);
+                Ok(D::decode(&mut cursor)?)
+            }
+            Err(_) => Err(ContractError::TimeLockInvalidWitness),
+        }
+    }
+
+    /// Derive the payload encryption key from given witness
+    fn key(&self, witness: &[u8; 32]) -> [u8; 32] {
+        blake3::derive_key(TIMELOCK_KDF_CONTEXT, witness)
+    }
+
+    /// Associated data binding the ciphertext to the time-lock parameters
+    fn aad(&self) -> Vec<u8> {
+        let mut aad = Vec::with_capacity(44);
+        aad.extend_from_slice(&self.target_height.to_le_bytes());
+        aad.extend_from_slice(&self.steps.to_le_bytes());
+        aad.extend_from_slice(&self.seed);
+        aad
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crypto::note::AEAD_TAG_SIZE;
+    use rand::rngs::OsRng;
+
+    #[test]
+    fn timelock_seal_and_open() {
+        let payload = (42_u64, "sealed bid".to_string());
+        let sealed = TimeLockedPayload::seal(&payload, 10, 15, 200, &mut OsRng).unwrap();
+        assert_eq!(sealed.steps, 1000);
+        assert_eq!(sealed.ciphertext.len(), serialize(&payload).len() + AEAD_TAG_SIZE);
+
+        let witness = sealed.solve();
+        assert!(sealed.verify(&witness));
+        assert_eq!(sealed.decrypt::<(u64, String)>(&witness).unwrap(), payload);
+
+        // Wrong witness
+        let mut bad_witness = witness;
+        bad_witness[0] ^= 1;
+        assert!(!sealed.verify(&bad_witness));
+        assert!(sealed.decrypt::<(u64, String)>(&bad_witness).is_err());
+
+        // Tampered metadata
+        let mut tampered = sealed.clone();
+        tampered.target_height = 11;
+        assert!(tampered.decrypt::<(u64, String)>(&witness).is_err());
+
+        // Target height must be in the future
+        assert!(matches!(
+            TimeLockedPayload::seal(&payload, 15, 15, 200, &mut OsRng),
+            Err(ContractError::TimeLockInvalidParams)
+        ));
+
+        // Puzzles must be verifiable by contracts
+        let max_blocks = (TIMELOCK_MAX_VERIFY_STEPS / TIMELOCK_DEFAULT_STEPS_PER_BLOCK) as u32;
+        let steps = TIMELOCK_DEFAULT_STEPS_PER_BLOCK;
+        assert!(TimeLockedPayload::seal(&payload, 10, 10 + max_blocks, steps, &mut OsRng).is_ok());
+        assert!(matches!(
+            TimeLockedPayload::seal(&payload, 10, 11 + max_blocks, steps, &mut OsRng),
+            Err(ContractError::TimeLockInvalidParams)
+        ));
+        assert!(matches!(
+            TimeLockedPayload::seal(&payload, 10, 11, u64::MAX, &mut OsRng),
+            Err(ContractError::TimeLockInvalidParams)
+        ));
+    }
+}
@@ -850,6 +850,189 @@ pub fn dark_forest_leaf_vec_integrity_check<T: Clone + Send + Sync>(
     Ok(())
 }
 
+/// Structural change of a single leaf between two flattened
+/// [`DarkTree`] leaf vectors, as produced by [`dark_leaf_vec_diff`].
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum DarkLeafChange<T>
+where
+    T: Clone + Send + Sync,
+{
+    /// Leaf only present in the new tree, at given index
+    Added { index: usize, leaf: DarkLeaf<T> },
+    /// Leaf only present in the old tree, at given index
+    Removed { index: usize },
+    /// Leaf present in both trees, whose index or connected
+    /// nodes indexes changed, along with its new indexes
+    Moved { from: usize, to: usize, parent_index: Option<usize>, children_indexes: Vec<usize> },
+}
+
+/// Structural diff between two flattened [`DarkTree`] leaf vectors.
+/// Leafs not referenced by any change are kept as is, in the same
+/// index, so they are not included.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct DarkTreePatch<T>
+where
+    T: Clone + Send + Sync,
+{
+    /// Leafs count of the old tree
+    pub old_len: usize,
+    /// Leafs count of the new tree
+    pub new_len: usize,
+    /// Leafs changes, ordered by index
+    pub changes: Vec<DarkLeafChange<T>>,
+}
+
+impl<T: Clone + Send + Sync> DarkTreePatch<T> {
+    /// Check if the patch doesn't change anything.
+    pub fn is_empty(&self) -> bool {
+        self.old_len == self.new_len && self.changes.is_empty()
+    }
+}
+
+/// Auxiliary function to compute the structural diff between two
+/// flattened [`DarkTree`] leaf vectors. Leafs are matched by their
+/// data, preferring the same index, so unchanged leafs produce no
+/// changes. Leafs with modified data show up as removed and added.
+pub fn dark_leaf_vec_diff<T: Clone + Send + Sync + PartialEq>(
+    old: &[DarkLeaf<T>],
+    new: &[DarkLeaf<T>],
+) -> DarkTreePatch<T> {
+    let mut matched: Vec<Option<usize>> = vec![None; new.len()];
+    let mut used = vec![false; old.len()];
+
+    // First match leafs which kept their index
+    for (index, leaf) in new.iter().enumerate() {
+        if index < old.len() && old[index].data == leaf.data {
+            matched[index] = Some(index);
+            used[index] = true;
+        }
+    }
+
+    // Then match the rest with the first unused equal leaf
+    for (index, leaf) in new.iter().enumerate() {
+        if matched[index].is_some() {
+            continue
+        }
+
+        if let Some(old_index) = (0..old.len()).find(|i| !used[*i] && old[*i].data == leaf.data) {
+            matched[index] = Some(old_index);
+            used[old_index] = true;
+        }
+    }
+
+    let mut changes = vec![];
+    for (index, used) in used.iter().enumerate() {
+        if !used {
+            changes.push(DarkLeafChange::Removed { index });
+        }
+    }
+
+    for (index, leaf) in new.iter().enumerate() {
+        match matched[index] {
+            Some(from) => {
+                if from == index &&
+                    old[from].parent_index == leaf.parent_index &&
+                    old[from].children_indexes == leaf.children_indexes
+                {
+                    continue
+                }
+
+                changes.push(DarkLeafChange::Moved {
+                    from,
+                    to: index,
+                    parent_index: leaf.parent_index,
+                    children_indexes: leaf.children_indexes.clone(),
+                });
+            }
+            None => changes.push(DarkLeafChange::Added { index, leaf: leaf.clone() }),
+        }
+    }
+
+    DarkTreePatch { old_len: old.len(), new_len: new.len(), changes }
+}
+
+/// Auxiliary function to apply a [`DarkTreePatch`] over provided
+/// flattened [`DarkTree`] leaf vector, producing the patched leaf
+/// vector. The patch must reference the old leafs and fill every
+/// new leaf exactly once, and the resulting vector is verified using
+/// [`dark_leaf_vec_integrity_check`], with provided capacities.
+pub fn dark_leaf_vec_apply_patch<T: Clone + Send + Sync>(
+    old: &[DarkLeaf<T>],
+    patch: &DarkTreePatch<T>,
+    min_capacity: Option<usize>,
+    max_capacity: Option<usize>,
+) -> DarkTreeResult<Vec<DarkLeaf<T>>> {
+    if old.len() != patch.old_len {
+        return Err(DarkTreeError::InvalidPatch(format!(
+            "Patch is for {} leafs, found {}",
+            patch.old_len,
+            old.len()
+        )))
+    }
+
+    // Mark the old leafs the patch references
+    let mut referenced = vec![false; old.len()];
+    for change in &patch.changes {
+        let old_index = match change {
+            DarkLeafChange::Removed { index } => *index,
+            DarkLeafChange::Moved { from, .. } => *from,
+            DarkLeafChange::Added { .. } => continue,
+        };
+
+        if old_index >= old.len() || referenced[old_index] {
+            return Err(DarkTreeError::InvalidPatch(format!("Invalid old leaf index {old_index}")))
+        }
+        referenced[old_index] = true;
+    }
+
+    // Place the changed leafs
+    let mut new: Vec<Option<DarkLeaf<T>>> = vec![None; patch.new_len];
+    for change in &patch.changes {
+        let (index, leaf) = match change {
+            DarkLeafChange::Added { index, leaf } => (*index, leaf.clone()),
+            DarkLeafChange::Moved { from, to, parent_index, children_indexes } => {
+                let leaf = DarkLeaf {
+                    data: old[*from].data.clone(),
+                    parent_index: *parent_index,
+                    children_indexes: children_indexes.clone(),
+                };
+                (*to, leaf)
+            }
+            DarkLeafChange::Removed { .. } => continue,
+        };
+
+        if index >= new.len() || new[index].is_some() {
+            return Err(DarkTreeError::InvalidPatch(format!("Invalid new leaf index {index}")))
+        }
+        new[index] = Some(leaf);
+    }
+
+    // Keep the unreferenced leafs in place
+    for (index, leaf) in old.iter().enumerate() {
+        if referenced[index] {
+            continue
+        }
+
+        if index >= new.len() || new[index].is_some() {
+            return Err(DarkTreeError::InvalidPatch(format!("Unchanged leaf {index} overwritten")))
+        }
+        new[index] = Some(leaf.clone());
+    }
+
+    let mut leafs = Vec::with_capacity(new.len());
+    for (index, leaf) in new.into_iter().enumerate() {
+        let Some(leaf) = leaf else {
+            return Err(DarkTreeError::InvalidPatch(format!("Missing new leaf {index}")))
+        };
+        leafs.push(leaf);
+    }
+
+    // Re-check the patched tree integrity
+    dark_leaf_vec_integrity_check(&leafs, min_capacity, max_capacity, None)?;
+
+    Ok(leafs)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -1680,4 +1863,75 @@ mod tests {
         // Thanks for reading
         Ok(())
     }
+
+    #[test]
+    fn test_darktree_diff_patch() -> DarkTreeResult<()> {
+        let (mut tree, _) = generate_tree()?;
+        let old = tree.build_vec()?;
+
+        // Verify diffing identical trees produces an empty patch
+        let patch = dark_leaf_vec_diff(&old, &old);
+        assert!(patch.is_empty());
+        assert_eq!(dark_leaf_vec_apply_patch(&old, &patch, None, None)?, old);
+
+        // Generate a modified tree, where the third branch gets
+        // replaced and a new leaf is appended to the root.
+        //
+        // Tree structure:
+        //                   16
+        //           /        |        \
+        //          10       14        15
+        //      /  /  \   \  |  \
+        //     2   4   6   9 12  13
+        //    / \  |   |  / \ |
+        //   0  1  3   5 7   8 11
+        let (mut tree, _) = generate_tree()?;
+        tree.children.pop();
+        tree.append(DarkTree::new(23, vec![], None, None))?;
+        let new = tree.build_vec()?;
+
+        // Verify the patch describes the structural changes
+        let patch = dark_leaf_vec_diff(&old, &new);
+        assert_eq!(patch.old_len, 23);
+        assert_eq!(patch.new_len, 17);
+        let removed =
+            patch.changes.iter().filter(|c| matches!(c, DarkLeafChange::Removed { .. })).count();
+        assert_eq!(removed, 7);
+        assert!(patch.changes.contains(&DarkLeafChange::Added {
+            index: 15,
+            leaf: DarkLeaf { data: 23, parent_index: Some(16), children_indexes: vec![] },
+        }));
+        assert!(patch.changes.contains(&DarkLeafChange::Moved {
+            from: 22,
+            to: 16,
+            parent_index: None,
+            children_indexes: vec![10, 14, 15],
+        }));
+
+        // Verify applying the patch produces the new tree
+        assert_eq!(dark_leaf_vec_apply_patch(&old, &patch, None, None)?, new);
+
+        // Verify applying the patch on a different tree will fail
+        assert!(dark_leaf_vec_apply_patch(&new, &patch, None, None).is_err());
+
+        // Verify applying the patch will fail using different bounds
+        assert!(dark_leaf_vec_apply_patch(&old, &patch, None, Some(16)).is_err());
+
+        // Verify applying a patch breaking the tree integrity will fail
+        let mut broken = patch.clone();
+        for change in broken.changes.iter_mut() {
+            if let DarkLeafChange::Moved { children_indexes, .. } = change {
+                children_indexes.pop();
+            }
+        }
+        assert!(dark_leaf_vec_apply_patch(&old, &broken, None, None).is_err());
+
+        // Verify applying a patch not filling every leaf will fail
+        let mut broken = patch.clone();
+        broken.changes.retain(|c| !matches!(c, DarkLeafChange::Added { .. }));
+        assert!(dark_leaf_vec_apply_patch(&old, &broken, None, None).is_err());
+
+        // Thanks for reading
+        Ok(())
+    }
 }
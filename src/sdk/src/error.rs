@@ -95,6 +95,18 @@ pub enum ContractError {
 
     #[error("Hex string is not properly formatted")]
     HexFmtErr,
+
+    #[error("Contract state quota exceeded")]
+    StateQuotaExceeded,
+
+    #[error("Time-lock target height not reached")]
+    TimeLockTargetNotReached,
+
+    #[error("Invalid time-lock witness")]
+    TimeLockInvalidWitness,
+
+    #[error("Invalid time-lock parameters")]
+    TimeLockInvalidParams,
 }
 
 /// Builtin return values occupy the upper 32 bits
@@ -126,6 +138,10 @@ pub const SMT_DEL_FAILED: i64 = to_builtin!(19);
 pub const GET_SYSTEM_TIME_FAILED: i64 = to_builtin!(20);
 pub const DATA_TOO_LARGE: i64 = to_builtin!(21);
 pub const HEX_FMT_ERR: i64 = to_builtin!(22);
+pub const STATE_QUOTA_EXCEEDED: i64 = to_builtin!(23);
+pub const TIMELOCK_TARGET_NOT_REACHED: i64 = to_builtin!(24);
+pub const TIMELOCK_INVALID_WITNESS: i64 = to_builtin!(25);
+pub const TIMELOCK_INVALID_PARAMS: i64 = to_builtin!(26);
 
 impl From<ContractError> for i64 {
     fn from(err: ContractError) -> Self {
@@ -151,6 +167,10 @@ impl From<ContractError> for i64 {
             ContractError::GetSystemTimeFailed => GET_SYSTEM_TIME_FAILED,
             ContractError::DataTooLarge => DATA_TOO_LARGE,
             ContractError::HexFmtErr => HEX_FMT_ERR,
+            ContractError::StateQuotaExceeded => STATE_QUOTA_EXCEEDED,
+            ContractError::TimeLockTargetNotReached => TIMELOCK_TARGET_NOT_REACHED,
+            ContractError::TimeLockInvalidWitness => TIMELOCK_INVALID_WITNESS,
+            ContractError::TimeLockInvalidParams => TIMELOCK_INVALID_PARAMS,
             ContractError::Custom(error) => {
                 if error == 0 {
                     CUSTOM_ZERO
@@ -187,6 +207,10 @@ impl From<i64> for ContractError {
             GET_SYSTEM_TIME_FAILED => Self::GetSystemTimeFailed,
             DATA_TOO_LARGE => Self::DataTooLarge,
             HEX_FMT_ERR => Self::HexFmtErr,
+            STATE_QUOTA_EXCEEDED => Self::StateQuotaExceeded,
+            TIMELOCK_TARGET_NOT_REACHED => Self::TimeLockTargetNotReached,
+            TIMELOCK_INVALID_WITNESS => Self::TimeLockInvalidWitness,
+            TIMELOCK_INVALID_PARAMS => Self::TimeLockInvalidParams,
             _ => Self::Custom(error as u32),
         }
     }
@@ -230,4 +254,7 @@ pub enum DarkTreeError {
 
     #[error("DarkTree max capacity has been exceeded")]
     MaxCapacityExceeded,
+
+    #[error("Invalid DarkTree patch: {0}")]
+    InvalidPatch(String),
 }
@@ -25,5 +25,8 @@ pub mod entrypoint;
 /// Merkle
 pub mod merkle;
 
+/// Typed storage abstractions over the database functions
+pub mod storage;
+
 /// Utility functions
 pub mod util;
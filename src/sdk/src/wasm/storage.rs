@@ -0,0 +1,295 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2025 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Typed storage abstractions over the raw `db_*` functions.
+//!
+//! [`StorageMap`], [`StorageSet`] and [`StorageVec`] take care of
+//! serializing keys and values, so contracts don't have to hand-encode
+//! them on every access. Multiple collections can share a single
+//! database by giving each one a distinct namespace, which gets
+//! prepended to all of its keys.
+//!
+//! Non-namespaced maps and sets use the serialized key as is, so they
+//! are compatible with databases written using the raw functions.
+//!
+//! As with the raw functions, reads are available everywhere while
+//! writes are only permitted in `update()`.
+
+use core::marker::PhantomData;
+
+use darkfi_serial::{deserialize, serialize, Decodable, Encodable};
+
+use super::db::{db_contains_key, db_del, db_get, db_set, DbHandle};
+use crate::error::{ContractError, GenericResult};
+
+/// Key under which a [`StorageVec`] stores its length, after its namespace
+const VEC_LEN_KEY: u8 = 0x00;
+/// Key prefix under which a [`StorageVec`] stores its items, after its namespace
+const VEC_ITEM_KEY: u8 = 0x01;
+
+/// Serialize a namespace into a key prefix. The namespace is length
+/// prefixed, so distinct namespaces never produce overlapping keys.
+fn namespace_prefix(namespace: &str) -> Vec<u8> {
+    serialize(&namespace.to_string())
+}
+
+/// Build the database key of a map or set entry
+fn entry_key<K: Encodable>(prefix: &[u8], key: &K) -> Vec<u8> {
+    let mut buf = prefix.to_vec();
+    // Writing into a `Vec` can't fail
+    key.encode(&mut buf).unwrap();
+    buf
+}
+
+/// Build the database key holding a vector's length
+fn vec_len_key(prefix: &[u8]) -> Vec<u8> {
+    let mut buf = prefix.to_vec();
+    buf.push(VEC_LEN_KEY);
+    buf
+}
+
+/// Build the database key of a vector item. The index is big-endian
+/// encoded, so items keep their order in the underlying database.
+fn vec_item_key(prefix: &[u8], index: u64) -> Vec<u8> {
+    let mut buf = prefix.to_vec();
+    buf.push(VEC_ITEM_KEY);
+    buf.extend_from_slice(&index.to_be_bytes());
+    buf
+}
+
+/// Typed key-value map stored in a contract database
+pub struct StorageMap<K, V> {
+    db: DbHandle,
+    prefix: Vec<u8>,
+    _marker: PhantomData<(K, V)>,
+}
+
+impl<K: Encodable, V: Encodable + Decodable> StorageMap<K, V> {
+    /// Map over the whole database, using serialized keys as is
+    pub fn new(db: DbHandle) -> Self {
+        Self { db, prefix: vec![], _marker: PhantomData }
+    }
+
+    /// Map over the given namespace of the database
+    pub fn namespaced(db: DbHandle, namespace: &str) -> Self {
+        Self { db, prefix: namespace_prefix(namespace), _marker: PhantomData }
+    }
+
+    /// Retrieve the value of given key, if it exists
+    pub fn get(&self, key: &K) -> GenericResult<Option<V>> {
+        match db_get(self.db, &entry_key(&self.prefix, key))? {
+            Some(bytes) => Ok(Some(deserialize(&bytes)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Retrieve the value of given key, or the provided default if it
+    /// doesn't exist
+    pub fn get_or(&self, key: &K, default: V) -> GenericResult<V> {
+        Ok(self.get(key)?.unwrap_or(default))
+    }
+
+    /// Check if given key exists
+    pub fn contains(&self, key: &K) -> GenericResult<bool> {
+        db_contains_key(self.db, &entry_key(&self.prefix, key))
+    }
+
+    /// Set the value of given key, overwriting any existing one
+    pub fn insert(&self, key: &K, value: &V) -> GenericResult<()> {
+        db_set(self.db, &entry_key(&self.prefix, key), &serialize(value))
+    }
+
+    /// Remove given key
+    pub fn remove(&self, key: &K) -> GenericResult<()> {
+        db_del(self.db, &entry_key(&self.prefix, key))
+    }
+
+    /// Apply `f` to the value of given key, or to `default` if it
+    /// doesn't exist, and store the result
+    pub fn update(&self, key: &K, default: V, f: impl FnOnce(V) -> V) -> GenericResult<()> {
+        let value = f(self.get_or(key, default)?);
+        self.insert(key, &value)
+    }
+}
+
+/// Typed set stored in a contract database, as keys with empty values
+pub struct StorageSet<K> {
+    db: DbHandle,
+    prefix: Vec<u8>,
+    _marker: PhantomData<K>,
+}
+
+impl<K: Encodable> StorageSet<K> {
+    /// Set over the whole database, using serialized keys as is
+    pub fn new(db: DbHandle) -> Self {
+        Self { db, prefix: vec![], _marker: PhantomData }
+    }
+
+    /// Set over the given namespace of the database
+    pub fn namespaced(db: DbHandle, namespace: &str) -> Self {
+        Self { db, prefix: namespace_prefix(namespace), _marker: PhantomData }
+    }
+
+    /// Check if given key is in the set
+    pub fn contains(&self, key: &K) -> GenericResult<bool> {
+        db_contains_key(self.db, &entry_key(&self.prefix, key))
+    }
+
+    /// Add given key to the set
+    pub fn insert(&self, key: &K) -> GenericResult<()> {
+        db_set(self.db, &entry_key(&self.prefix, key), &[])
+    }
+
+    /// Remove given key from the set
+    pub fn remove(&self, key: &K) -> GenericResult<()> {
+        db_del(self.db, &entry_key(&self.prefix, key))
+    }
+}
+
+/// Typed vector stored in a contract database.
+/// Its length and each of its items are stored under separate keys,
+/// so accessing an item doesn't require loading the whole vector.
+pub struct StorageVec<T> {
+    db: DbHandle,
+    prefix: Vec<u8>,
+    _marker: PhantomData<T>,
+}
+
+impl<T: Encodable + Decodable> StorageVec<T> {
+    /// Vector stored in the given namespace of the database
+    pub fn new(db: DbHandle, namespace: &str) -> Self {
+        Self { db, prefix: namespace_prefix(namespace), _marker: PhantomData }
+    }
+
+    /// Number of items in the vector
+    pub fn len(&self) -> GenericResult<u64> {
+        match db_get(self.db, &vec_len_key(&self.prefix))? {
+            Some(bytes) => Ok(deserialize(&bytes)?),
+            None => Ok(0),
+        }
+    }
+
+    /// Check if the vector has no items
+    pub fn is_empty(&self) -> GenericResult<bool> {
+        Ok(self.len()? == 0)
+    }
+
+    /// Retrieve the item at given index, if it exists
+    pub fn get(&self, index: u64) -> GenericResult<Option<T>> {
+        if index >= self.len()? {
+            return Ok(None)
+        }
+
+        match db_get(self.db, &vec_item_key(&self.prefix, index))? {
+            Some(bytes) => Ok(Some(deserialize(&bytes)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Retrieve the last item, if the vector is not empty
+    pub fn last(&self) -> GenericResult<Option<T>> {
+        match self.len()? {
+            0 => Ok(None),
+            len => self.get(len - 1),
+        }
+    }
+
+    /// Append an item to the end of the vector, returning its index
+    pub fn push(&self, item: &T) -> GenericResult<u64> {
+        let index = self.len()?;
+        db_set(self.db, &vec_item_key(&self.prefix, index), &serialize(item))?;
+        db_set(self.db, &vec_len_key(&self.prefix), &serialize(&(index + 1)))?;
+        Ok(index)
+    }
+
+    /// Remove and return the last item, if the vector is not empty
+    pub fn pop(&self) -> GenericResult<Option<T>> {
+        let Some(item) = self.last()? else { return Ok(None) };
+        let index = self.len()? - 1;
+        db_del(self.db, &vec_item_key(&self.prefix, index))?;
+        db_set(self.db, &vec_len_key(&self.prefix), &serialize(&index))?;
+        Ok(Some(item))
+    }
+
+    /// Overwrite the item at given index. Returns `false` if the index
+    /// is out of bounds, in which case nothing gets written.
+    pub fn set(&self, index: u64, item: &T) -> GenericResult<bool> {
+        if index >= self.len()? {
+            return Ok(false)
+        }
+
+        db_set(self.db, &vec_item_key(&self.prefix, index), &serialize(item))?;
+        Ok(true)
+    }
+
+    /// Iterate over the vector items, in order. The length is read once,
+    /// when the iterator gets created.
+    pub fn iter(&self) -> GenericResult<StorageVecIter<'_, T>> {
+        Ok(StorageVecIter { vec: self, index: 0, len: self.len()? })
+    }
+}
+
+/// Iterator over the items of a [`StorageVec`]
+pub struct StorageVecIter<'a, T> {
+    vec: &'a StorageVec<T>,
+    index: u64,
+    len: u64,
+}
+
+impl<T: Encodable + Decodable> Iterator for StorageVecIter<'_, T> {
+    type Item = GenericResult<T>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.index >= self.len {
+            return None
+        }
+
+        let key = vec_item_key(&self.vec.prefix, self.index);
+        self.index += 1;
+
+        let item = match db_get(self.vec.db, &key) {
+            Ok(Some(bytes)) => deserialize(&bytes).map_err(Into::into),
+            Ok(None) => Err(ContractError::DbGetFailed),
+            Err(e) => Err(e),
+        };
+
+        Some(item)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn storage_keys() {
+        // Non-namespaced entries are compatible with raw serialized keys
+        assert_eq!(entry_key(&[], &42_u32), serialize(&42_u32));
+
+        // Namespaces never overlap, even when one prefixes the other
+        let a = namespace_prefix("coins");
+        let b = namespace_prefix("coinsx");
+        assert!(!entry_key(&b, &0_u8).starts_with(&a));
+        assert_ne!(entry_key(&a, &1_u8), entry_key(&b, &1_u8));
+
+        // Vector length and item keys are distinct, and items keep their order
+        let prefix = namespace_prefix("vec");
+        assert_ne!(vec_len_key(&prefix), vec_item_key(&prefix, 0));
+        assert!(vec_item_key(&prefix, 1) < vec_item_key(&prefix, 256));
+    }
+}
@@ -20,6 +20,7 @@ use darkfi_serial::{Decodable, Encodable};
 use std::io::Cursor;
 
 use crate::{
+    crypto::TimeLockedPayload,
     error::{ContractError, GenericResult},
     tx::TransactionHash,
 };
@@ -192,6 +193,56 @@ pub fn get_tx_location(hash: &TransactionHash) -> GenericResult<(u32, u16)> {
     Ok((Decodable::decode(&mut cursor)?, Decodable::decode(&mut cursor)?))
 }
 
+/// Everyone can call this. Will verify natively that `witness` solves
+/// the VDF puzzle of given time-locked payload. Puzzles requiring more
+/// than [`TIMELOCK_MAX_VERIFY_STEPS`] steps are rejected.
+///
+/// [`TIMELOCK_MAX_VERIFY_STEPS`]: crate::crypto::timelock::TIMELOCK_MAX_VERIFY_STEPS
+///
+/// ```
+/// valid = timelock_verify(&payload, &witness)?;
+/// ```
+pub fn timelock_verify(payload: &TimeLockedPayload, witness: &[u8; 32]) -> GenericResult<bool> {
+    let mut buf = vec![];
+    let mut len = 0;
+    len += payload.seed.encode(&mut buf)?;
+    len += payload.steps.encode(&mut buf)?;
+    len += witness.encode(&mut buf)?;
+
+    let ret = unsafe { timelock_verify_(buf.as_ptr(), len as u32) };
+    if ret < 0 {
+        return Err(ContractError::from(ret))
+    }
+
+    match ret {
+        0 => Ok(false),
+        1 => Ok(true),
+        _ => unreachable!(),
+    }
+}
+
+/// Everyone can call this. Will open given time-locked
+/// payload using the revealed `witness`, after ensuring its target block
+/// height has been reached and the witness solves its VDF puzzle.
+///
+/// ```
+/// bid: u64 = timelock_open(&payload, &witness)?;
+/// ```
+pub fn timelock_open<D: Decodable>(
+    payload: &TimeLockedPayload,
+    witness: &[u8; 32],
+) -> GenericResult<D> {
+    if get_verifying_block_height()? < payload.target_height {
+        return Err(ContractError::TimeLockTargetNotReached)
+    }
+
+    if !timelock_verify(payload, witness)? {
+        return Err(ContractError::TimeLockInvalidWitness)
+    }
+
+    payload.decrypt(witness)
+}
+
 extern "C" {
     fn set_return_data_(ptr: *const u8, len: u32) -> i64;
     fn get_object_bytes_(ptr: *const u8, len: u32) -> i64;
@@ -205,4 +256,5 @@ extern "C" {
     fn get_last_block_height_() -> i64;
     fn get_tx_(ptr: *const u8) -> i64;
     fn get_tx_location_(ptr: *const u8) -> i64;
+    fn timelock_verify_(ptr: *const u8, len: u32) -> i64;
 }
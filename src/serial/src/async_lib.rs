@@ -17,17 +17,23 @@
  */
 
 use std::{
+    any::{Any, TypeId},
     collections::VecDeque,
+    future::Future,
     io::{Error, ErrorKind, Result},
+    pin::Pin,
+    sync::{Arc, Mutex},
+    task::{Context, Poll},
 };
 
 pub use async_trait::async_trait;
+use futures_lite::future::poll_once;
 pub use futures_lite::{
     io::Cursor, AsyncRead, AsyncReadExt as FutAsyncReadExt, AsyncWrite,
     AsyncWriteExt as FutAsyncWriteExt,
 };
 
-use crate::{endian, VarInt};
+use crate::{endian, NetworkEndian, VarInt};
 
 /// Data which can asynchronously be encoded in a consensus-consistent way.
 #[async_trait]
@@ -75,6 +81,220 @@ pub async fn deserialize_async<T: AsyncDecodable>(data: &[u8]) -> Result<T> {
     Ok(rv)
 }
 
+/// Default maximum amount of bytes an [`AsyncDecoder`] will consume
+/// while decoding a single object.
+pub const ASYNC_DECODER_MAX_BUFFER: usize = 8 * 1024 * 1024;
+
+/// Size of the chunks an [`AsyncDecoder`] reads from its inner reader.
+const ASYNC_DECODER_CHUNK_SIZE: usize = 8 * 1024;
+
+/// Bytes read by an [`AsyncDecoder`], shared with its in-progress decode
+#[derive(Default)]
+struct DecoderInput {
+    /// Bytes read from the stream but not yet consumed
+    buffer: VecDeque<u8>,
+    /// Bytes consumed by the in-progress decode so far
+    consumed: usize,
+}
+
+impl DecoderInput {
+    /// Move as many buffered bytes as fit into `buf`
+    fn take(&mut self, buf: &mut [u8]) -> usize {
+        let n = buf.len().min(self.buffer.len());
+        for (dst, src) in buf.iter_mut().zip(self.buffer.drain(..n)) {
+            *dst = src;
+        }
+        n
+    }
+}
+
+/// Reader feeding the buffered bytes to an in-progress decode.
+///
+/// It pends without registering a waker once the buffer runs dry,
+/// which is fine since the [`AsyncDecoder`] polls the decode again
+/// itself after reading more bytes from the stream.
+struct DecoderInputReader(Arc<Mutex<DecoderInput>>);
+
+impl AsyncRead for DecoderInputReader {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        _cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<Result<usize>> {
+        let mut input = self.0.lock().unwrap();
+        if input.buffer.is_empty() && !buf.is_empty() {
+            return Poll::Pending
+        }
+
+        let n = input.take(buf);
+        input.consumed += n;
+        Poll::Ready(Ok(n))
+    }
+}
+
+/// Type-erased decoding future, suspended whenever it runs out of input
+type DecodeFuture = Pin<Box<dyn Future<Output = Result<Box<dyn Any + Send>>> + Send>>;
+
+/// Decode in progress of an [`AsyncDecoder`]
+struct PendingDecode {
+    /// Type being decoded
+    type_id: TypeId,
+    /// Decoding future
+    future: DecodeFuture,
+}
+
+/// Cancellation-safe incremental decoding adapter over an `AsyncRead`.
+///
+/// Decoding directly from a stream with [`AsyncDecodable::decode_async`]
+/// loses any bytes already consumed if the future is dropped mid-decode,
+/// e.g. when it loses a `select!` race, corrupting the stream framing.
+/// `AsyncDecoder` instead buffers the bytes read from the stream and
+/// keeps the decode in progress across calls, feeding it more bytes as
+/// they arrive, so each byte is only ever decoded once. Dropping a
+/// [`AsyncDecoder::decode`] future at any `.await` point is therefore
+/// safe, and the next call for the same type resumes where it left off.
+///
+/// The decoder also implements `AsyncRead`, handing out the buffered
+/// bytes first, so raw reads can be mixed in between decoded objects.
+pub struct AsyncDecoder<R> {
+    /// Inner stream we read from
+    reader: R,
+    /// Bytes read from the stream, shared with the pending decode
+    input: Arc<Mutex<DecoderInput>>,
+    /// Decode in progress, if any
+    pending: Option<PendingDecode>,
+    /// Maximum amount of bytes a single object may consume
+    max_buffer: usize,
+}
+
+impl<R: AsyncRead + Unpin + Send> AsyncDecoder<R> {
+    /// Create a new `AsyncDecoder` over the given reader, using
+    /// [`ASYNC_DECODER_MAX_BUFFER`] as the object size limit.
+    pub fn new(reader: R) -> Self {
+        Self::with_max_buffer(reader, ASYNC_DECODER_MAX_BUFFER)
+    }
+
+    /// Create a new `AsyncDecoder` over the given reader, with a custom
+    /// limit on the amount of bytes a single object may consume.
+    pub fn with_max_buffer(reader: R, max_buffer: usize) -> Self {
+        Self {
+            reader,
+            input: Arc::new(Mutex::new(DecoderInput::default())),
+            pending: None,
+            max_buffer,
+        }
+    }
+
+    /// Decode the next object from the stream.
+    ///
+    /// This is cancellation safe: if the returned future is dropped before
+    /// completion, no data is lost and a subsequent call for the same type
+    /// will resume decoding from where it stopped. Calling it for another
+    /// type while a decode is pending returns an error.
+    pub async fn decode<T: AsyncDecodable + Send + 'static>(&mut self) -> Result<T> {
+        let type_id = TypeId::of::<T>();
+        match &self.pending {
+            Some(pending) if pending.type_id != type_id => {
+                return Err(Error::new(
+                    ErrorKind::InvalidInput,
+                    "Decoder is in the middle of decoding another type",
+                ))
+            }
+            Some(_) => {}
+            None => {
+                self.input.lock().unwrap().consumed = 0;
+                let mut reader = DecoderInputReader(self.input.clone());
+                let future = Box::pin(async move {
+                    let rv: T = AsyncDecodable::decode_async(&mut reader).await?;
+                    Ok(Box::new(rv) as Box<dyn Any + Send>)
+                });
+                self.pending = Some(PendingDecode { type_id, future });
+            }
+        }
+
+        loop {
+            // Feed the pending decode with what we have buffered so far.
+            // It only pends once it has consumed all of it.
+            let future = &mut self.pending.as_mut().unwrap().future;
+            if let Some(rv) = poll_once(future).await {
+                self.pending = None;
+                return rv.map(|rv| *rv.downcast::<T>().unwrap())
+            }
+
+            if self.input.lock().unwrap().consumed >= self.max_buffer {
+                self.pending = None;
+                return Err(Error::new(ErrorKind::InvalidData, "Decoder buffer limit exceeded"))
+            }
+
+            // We need more data. Anything read here is immediately
+            // stored, so dropping the future at this await is safe.
+            if let Err(e) = self.fill_buffer().await {
+                self.pending = None;
+                return Err(e)
+            }
+        }
+    }
+
+    /// Read the next chunk of bytes from the stream into our buffer.
+    async fn fill_buffer(&mut self) -> Result<()> {
+        let mut chunk = [0u8; ASYNC_DECODER_CHUNK_SIZE];
+        let n = self.reader.read(&mut chunk).await?;
+        if n == 0 {
+            return Err(Error::new(ErrorKind::UnexpectedEof, "Stream ended mid-decode"))
+        }
+
+        self.input.lock().unwrap().buffer.extend(&chunk[..n]);
+        Ok(())
+    }
+
+    /// Returns the amount of bytes read from the stream that haven't
+    /// been consumed yet.
+    pub fn buffered(&self) -> usize {
+        self.input.lock().unwrap().buffer.len()
+    }
+
+    /// Returns a reference to the inner reader.
+    pub fn get_ref(&self) -> &R {
+        &self.reader
+    }
+
+    /// Returns a mutable reference to the inner reader. Reading from it
+    /// directly bypasses any buffered bytes.
+    pub fn get_mut(&mut self) -> &mut R {
+        &mut self.reader
+    }
+
+    /// Consume the decoder, returning the inner reader along with any
+    /// buffered bytes that weren't consumed yet. Bytes already consumed
+    /// by a pending decode are lost.
+    pub fn into_inner(self) -> (R, Vec<u8>) {
+        let buffer = std::mem::take(&mut self.input.lock().unwrap().buffer);
+        (self.reader, buffer.into())
+    }
+}
+
+impl<R: AsyncRead + Unpin> AsyncRead for AsyncDecoder<R> {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<Result<usize>> {
+        if self.pending.is_some() {
+            return Poll::Ready(Err(Error::new(
+                ErrorKind::InvalidInput,
+                "Decoder is in the middle of decoding an object",
+            )))
+        }
+
+        let n = self.input.lock().unwrap().take(buf);
+        if n > 0 {
+            return Poll::Ready(Ok(n))
+        }
+
+        Pin::new(&mut self.reader).poll_read(cx, buf)
+    }
+}
+
 /// Extensions of `AsyncWrite` to encode data as per Bitcoin consensus.
 #[async_trait]
 pub trait AsyncWriteExt {
@@ -385,6 +605,38 @@ tuple_encode!(T0, T1, T2, T3, T4, T5);
 tuple_encode!(T0, T1, T2, T3, T4, T5, T6);
 tuple_encode!(T0, T1, T2, T3, T4, T5, T6, T7);
 
+macro_rules! impl_network_endian {
+    ($type: ty, $byte_len: expr, $to_array: ident, $from_slice: ident) => {
+        #[async_trait]
+        impl AsyncEncodable for NetworkEndian<$type> {
+            #[inline]
+            async fn encode_async<S: AsyncWrite + Unpin + Send>(&self, s: &mut S) -> Result<usize> {
+                s.write_slice_async(&endian::$to_array(self.0)).await?;
+                Ok($byte_len)
+            }
+        }
+
+        #[async_trait]
+        impl AsyncDecodable for NetworkEndian<$type> {
+            #[inline]
+            async fn decode_async<D: AsyncRead + Unpin + Send>(d: &mut D) -> Result<Self> {
+                let mut val = [0; $byte_len];
+                d.read_slice_async(&mut val).await?;
+                Ok(Self(endian::$from_slice(&val)))
+            }
+        }
+    };
+}
+
+impl_network_endian!(u16, 2, u16_to_array_be, slice_to_u16_be);
+impl_network_endian!(u32, 4, u32_to_array_be, slice_to_u32_be);
+impl_network_endian!(u64, 8, u64_to_array_be, slice_to_u64_be);
+impl_network_endian!(u128, 16, u128_to_array_be, slice_to_u128_be);
+impl_network_endian!(i16, 2, i16_to_array_be, slice_to_i16_be);
+impl_network_endian!(i32, 4, i32_to_array_be, slice_to_i32_be);
+impl_network_endian!(i64, 8, i64_to_array_be, slice_to_i64_be);
+impl_network_endian!(i128, 16, i128_to_array_be, slice_to_i128_be);
+
 /// Asynchronously encode a dynamic set of arguments to a buffer.
 #[macro_export]
 macro_rules! encode_payload_async {
@@ -0,0 +1,378 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2025 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Fixed-point decimal numbers.
+//!
+//! [`Decimal`] represents `mantissa * 10^-scale` exactly, so it is suitable
+//! for financial values like token amounts and exchange rates, where
+//! floating point rounding is not acceptable. It is encoded as its
+//! mantissa (`i128`) followed by its scale (`u8`). The scale is kept as-is,
+//! so `1.0` and `1.00` compare equal, but encode differently.
+
+use std::{
+    cmp::Ordering,
+    fmt,
+    hash::{Hash, Hasher},
+    io::{Error, ErrorKind, Read, Result, Write},
+    str::FromStr,
+};
+
+#[cfg(feature = "async")]
+use crate::{AsyncDecodable, AsyncEncodable};
+#[cfg(feature = "async")]
+use async_trait::async_trait;
+#[cfg(feature = "async")]
+use futures_lite::{AsyncRead, AsyncWrite};
+
+use crate::{Decodable, Encodable};
+
+/// Maximum number of decimal places of a [`Decimal`]
+pub const MAX_SCALE: u8 = 38;
+
+/// Returns `10^exp`, for `exp <= MAX_SCALE`
+const fn pow10(exp: u8) -> i128 {
+    10i128.pow(exp as u32)
+}
+
+/// Fixed-point decimal number, equal to `mantissa * 10^-scale`
+#[derive(Copy, Clone, Debug, Default)]
+pub struct Decimal {
+    mantissa: i128,
+    scale: u8,
+}
+
+impl Decimal {
+    /// Zero, with no decimal places
+    pub const ZERO: Self = Self { mantissa: 0, scale: 0 };
+
+    /// Create a new `Decimal` equal to `mantissa * 10^-scale`.
+    /// Returns `None` if `scale` exceeds [`MAX_SCALE`].
+    pub const fn new(mantissa: i128, scale: u8) -> Option<Self> {
+        if scale > MAX_SCALE {
+            return None
+        }
+        Some(Self { mantissa, scale })
+    }
+
+    /// Create a `Decimal` out of an integer amount of base units,
+    /// e.g. a token amount with `decimals` decimal places.
+    /// Returns `None` if `decimals` exceeds [`MAX_SCALE`].
+    pub const fn from_units(units: u64, decimals: u8) -> Option<Self> {
+        Self::new(units as i128, decimals)
+    }
+
+    /// Convert into an integer amount of base units with `decimals` decimal
+    /// places. Returns `None` if the value is negative, doesn't fit, or has
+    /// more decimal places than `decimals`.
+    pub fn to_units(&self, decimals: u8) -> Option<u64> {
+        self.rescale(decimals)?.mantissa.try_into().ok()
+    }
+
+    /// The mantissa of this `Decimal`
+    pub const fn mantissa(&self) -> i128 {
+        self.mantissa
+    }
+
+    /// The number of decimal places of this `Decimal`
+    pub const fn scale(&self) -> u8 {
+        self.scale
+    }
+
+    /// Check if this `Decimal` is zero
+    pub const fn is_zero(&self) -> bool {
+        self.mantissa == 0
+    }
+
+    /// Check if this `Decimal` is negative
+    pub const fn is_negative(&self) -> bool {
+        self.mantissa < 0
+    }
+
+    /// Convert into the same value with `scale` decimal places.
+    /// Returns `None` if the mantissa overflows, if `scale` exceeds
+    /// [`MAX_SCALE`], or if precision would be lost.
+    pub fn rescale(&self, scale: u8) -> Option<Self> {
+        match scale.cmp(&self.scale) {
+            Ordering::Equal => Some(*self),
+            Ordering::Greater => {
+                if scale > MAX_SCALE {
+                    return None
+                }
+                let factor = pow10(scale - self.scale);
+                Some(Self { mantissa: self.mantissa.checked_mul(factor)?, scale })
+            }
+            Ordering::Less => {
+                let factor = pow10(self.scale - scale);
+                if self.mantissa % factor != 0 {
+                    return None
+                }
+                Some(Self { mantissa: self.mantissa / factor, scale })
+            }
+        }
+    }
+
+    /// Round to `scale` decimal places, with halves rounded away from zero.
+    /// Values that already have at most `scale` decimal places are returned
+    /// unchanged.
+    pub fn round(&self, scale: u8) -> Self {
+        if scale >= self.scale {
+            return *self
+        }
+
+        let factor = pow10(self.scale - scale);
+        let (quotient, remainder) = (self.mantissa / factor, self.mantissa % factor);
+        let mantissa = if remainder.unsigned_abs() * 2 >= factor as u128 {
+            quotient + self.mantissa.signum()
+        } else {
+            quotient
+        };
+
+        Self { mantissa, scale }
+    }
+
+    /// Strip trailing zero decimal places
+    pub fn normalize(&self) -> Self {
+        let mut normalized = *self;
+        while normalized.scale > 0 && normalized.mantissa % 10 == 0 {
+            normalized.mantissa /= 10;
+            normalized.scale -= 1;
+        }
+        normalized
+    }
+
+    /// Checked addition. Returns `None` on overflow.
+    pub fn checked_add(&self, other: &Self) -> Option<Self> {
+        let scale = self.scale.max(other.scale);
+        let (a, b) = (self.rescale(scale)?, other.rescale(scale)?);
+        Some(Self { mantissa: a.mantissa.checked_add(b.mantissa)?, scale })
+    }
+
+    /// Checked subtraction. Returns `None` on overflow.
+    pub fn checked_sub(&self, other: &Self) -> Option<Self> {
+        let scale = self.scale.max(other.scale);
+        let (a, b) = (self.rescale(scale)?, other.rescale(scale)?);
+        Some(Self { mantissa: a.mantissa.checked_sub(b.mantissa)?, scale })
+    }
+
+    /// Checked multiplication. The result has the sum of both scales, with
+    /// trailing zeros stripped if it exceeds [`MAX_SCALE`]. Returns `None`
+    /// on overflow, or if the exact result has more than [`MAX_SCALE`]
+    /// decimal places.
+    pub fn checked_mul(&self, other: &Self) -> Option<Self> {
+        let product = Self {
+            mantissa: self.mantissa.checked_mul(other.mantissa)?,
+            scale: self.scale.checked_add(other.scale)?,
+        };
+        if product.scale <= MAX_SCALE {
+            return Some(product)
+        }
+        let normalized = product.normalize();
+        (normalized.scale <= MAX_SCALE).then_some(normalized)
+    }
+
+    /// Split into the integer part and the fractional part mantissa
+    fn split(&self) -> (i128, i128) {
+        let factor = pow10(self.scale);
+        (self.mantissa / factor, self.mantissa % factor)
+    }
+}
+
+impl PartialEq for Decimal {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Decimal {}
+
+impl PartialOrd for Decimal {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Decimal {
+    fn cmp(&self, other: &Self) -> Ordering {
+        // Comparing the integer parts first, the fractional parts can
+        // always be brought to a common scale without overflowing.
+        let ((a_int, a_frac), (b_int, b_frac)) = (self.split(), other.split());
+        let scale = self.scale.max(other.scale);
+        let a_frac = a_frac * pow10(scale - self.scale);
+        let b_frac = b_frac * pow10(scale - other.scale);
+        a_int.cmp(&b_int).then(a_frac.cmp(&b_frac))
+    }
+}
+
+impl Hash for Decimal {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        let normalized = self.normalize();
+        normalized.mantissa.hash(state);
+        normalized.scale.hash(state);
+    }
+}
+
+impl fmt::Display for Decimal {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let sign = if self.is_negative() { "-" } else { "" };
+        let abs = self.mantissa.unsigned_abs();
+        if self.scale == 0 {
+            return write!(f, "{sign}{abs}")
+        }
+
+        let factor = pow10(self.scale) as u128;
+        let scale = self.scale as usize;
+        write!(f, "{sign}{}.{:0scale$}", abs / factor, abs % factor)
+    }
+}
+
+impl FromStr for Decimal {
+    type Err = Error;
+
+    /// Parse a decimal string like `-12.345`, keeping as many decimal
+    /// places as given.
+    fn from_str(s: &str) -> Result<Self> {
+        let invalid = || Error::new(ErrorKind::InvalidInput, "Invalid decimal string");
+
+        let (negative, digits) = match s.strip_prefix('-') {
+            Some(digits) => (true, digits),
+            None => (false, s.strip_prefix('+').unwrap_or(s)),
+        };
+        let (int, frac) = digits.split_once('.').unwrap_or((digits, ""));
+
+        if int.is_empty() && frac.is_empty() {
+            return Err(invalid())
+        }
+        if !int.bytes().chain(frac.bytes()).all(|b| b.is_ascii_digit()) {
+            return Err(invalid())
+        }
+        if frac.len() > MAX_SCALE as usize {
+            return Err(Error::new(ErrorKind::InvalidInput, "Too many decimal places"))
+        }
+
+        let mut mantissa: i128 = 0;
+        for digit in int.bytes().chain(frac.bytes()) {
+            mantissa = mantissa
+                .checked_mul(10)
+                .and_then(|m| m.checked_add((digit - b'0') as i128))
+                .ok_or_else(|| Error::new(ErrorKind::InvalidInput, "Decimal out of range"))?;
+        }
+        if negative {
+            mantissa = -mantissa;
+        }
+
+        Ok(Self { mantissa, scale: frac.len() as u8 })
+    }
+}
+
+impl Encodable for Decimal {
+    fn encode<S: Write>(&self, s: &mut S) -> Result<usize> {
+        let mut len = self.mantissa.encode(s)?;
+        len += self.scale.encode(s)?;
+        Ok(len)
+    }
+}
+
+#[cfg(feature = "async")]
+#[async_trait]
+impl AsyncEncodable for Decimal {
+    async fn encode_async<S: AsyncWrite + Unpin + Send>(&self, s: &mut S) -> Result<usize> {
+        let mut len = self.mantissa.encode_async(s).await?;
+        len += self.scale.encode_async(s).await?;
+        Ok(len)
+    }
+}
+
+impl Decodable for Decimal {
+    fn decode<D: Read>(d: &mut D) -> Result<Self> {
+        let mantissa: i128 = Decodable::decode(d)?;
+        let scale: u8 = Decodable::decode(d)?;
+        Self::new(mantissa, scale)
+            .ok_or_else(|| Error::new(ErrorKind::InvalidData, "Decimal scale out of range"))
+    }
+}
+
+#[cfg(feature = "async")]
+#[async_trait]
+impl AsyncDecodable for Decimal {
+    async fn decode_async<D: AsyncRead + Unpin + Send>(d: &mut D) -> Result<Self> {
+        let mantissa: i128 = AsyncDecodable::decode_async(d).await?;
+        let scale: u8 = AsyncDecodable::decode_async(d).await?;
+        Self::new(mantissa, scale)
+            .ok_or_else(|| Error::new(ErrorKind::InvalidData, "Decimal scale out of range"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{deserialize, serialize};
+
+    fn dec(s: &str) -> Decimal {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn parse_display_decimal() {
+        for s in ["0", "1", "-1", "0.5", "-0.05", "12.3400", "100000000.00000001"] {
+            assert_eq!(dec(s).to_string(), s);
+        }
+        assert_eq!(dec("+.5").to_string(), "0.5");
+        assert_eq!(dec("7.").to_string(), "7");
+
+        for s in ["", ".", "-", "1.2.3", "1e5", "--1", "0.000000000000000000000000000000000000001"]
+        {
+            assert!(s.parse::<Decimal>().is_err());
+        }
+    }
+
+    #[test]
+    fn decimal_arithmetic() {
+        assert_eq!(dec("1.0"), dec("1.00"));
+        assert!(dec("-1.5") < dec("-1.25"));
+        assert!(dec("2") > dec("1.99999"));
+
+        assert_eq!(dec("1.5").checked_add(&dec("2.25")).unwrap().to_string(), "3.75");
+        assert_eq!(dec("1.5").checked_sub(&dec("2.25")).unwrap().to_string(), "-0.75");
+        assert_eq!(dec("1.5").checked_mul(&dec("-2.25")).unwrap().to_string(), "-3.375");
+
+        assert_eq!(dec("2.345").round(2).to_string(), "2.35");
+        assert_eq!(dec("-2.345").round(2).to_string(), "-2.35");
+        assert_eq!(dec("2.344").round(2).to_string(), "2.34");
+        assert_eq!(dec("12.3400").normalize().to_string(), "12.34");
+
+        let amount = Decimal::from_units(150_000_000, 8).unwrap();
+        assert_eq!(amount.to_string(), "1.50000000");
+        assert_eq!(dec("1.5").to_units(8), Some(150_000_000));
+        assert_eq!(dec("1.000000001").to_units(8), None);
+        assert_eq!(dec("-1").to_units(8), None);
+    }
+
+    #[test]
+    fn serialize_deserialize_decimal() {
+        for s in ["0", "-0.05", "12.3400", "100000000.00000001"] {
+            let original = dec(s);
+            let deserialized: Decimal = deserialize(&serialize(&original)).unwrap();
+            assert_eq!(deserialized.mantissa(), original.mantissa());
+            assert_eq!(deserialized.scale(), original.scale());
+        }
+
+        let mut invalid = serialize(&0i128);
+        invalid.push(MAX_SCALE + 1);
+        assert!(deserialize::<Decimal>(&invalid).is_err());
+    }
+}
@@ -16,10 +16,24 @@
  * along with this program.  If not, see <https://www.gnu.org/licenses/>.
  */
 
+//! Endianness conversion helpers.
+//!
+//! DarkFi encodes integers in little-endian, while various external
+//! protocols (e.g. Bitcoin block headers fields or Monero structures
+//! used in merge mining) expect big-endian. This module provides both
+//! little-endian (`_le`) and big-endian (`_be`) conversions between
+//! primitive types and byte arrays, along with the [`NetworkEndian`]
+//! wrapper type, which gets encoded in big-endian.
+
+use std::io::{Error, Read, Write};
+
+use crate::{Decodable, Encodable};
+
 macro_rules! define_slice_to_be {
     ($name: ident, $type: ty) => {
+        #[doc = concat!("Convert a big-endian byte slice into `", stringify!($type), "`.")]
+        /// Panics if the slice length doesn't match the type size.
         #[inline]
-        #[allow(dead_code)]
         pub fn $name(slice: &[u8]) -> $type {
             assert_eq!(slice.len(), ::core::mem::size_of::<$type>());
             let mut res = 0;
@@ -33,6 +47,8 @@ macro_rules! define_slice_to_be {
 
 macro_rules! define_slice_to_le {
     ($name: ident, $type: ty) => {
+        #[doc = concat!("Convert a little-endian byte slice into `", stringify!($type), "`.")]
+        /// Panics if the slice length doesn't match the type size.
         #[inline]
         pub fn $name(slice: &[u8]) -> $type {
             assert_eq!(slice.len(), ::core::mem::size_of::<$type>());
@@ -47,8 +63,8 @@ macro_rules! define_slice_to_le {
 
 macro_rules! define_be_to_array {
     ($name: ident, $type: ty, $byte_len: expr) => {
+        #[doc = concat!("Convert `", stringify!($type), "` into a big-endian byte array.")]
         #[inline]
-        #[allow(dead_code)]
         pub fn $name(val: $type) -> [u8; $byte_len] {
             assert_eq!(::core::mem::size_of::<$type>(), $byte_len);
             let mut res = [0; $byte_len];
@@ -62,6 +78,7 @@ macro_rules! define_be_to_array {
 
 macro_rules! define_le_to_array {
     ($name: ident, $type: ty, $byte_len: expr) => {
+        #[doc = concat!("Convert `", stringify!($type), "` into a little-endian byte array.")]
         #[inline]
         pub fn $name(val: $type) -> [u8; $byte_len] {
             assert_eq!(::core::mem::size_of::<$type>(), $byte_len);
@@ -74,8 +91,29 @@ macro_rules! define_le_to_array {
     };
 }
 
+macro_rules! define_signed_conversions {
+    ($to_array: ident, $from_slice: ident, $type: ty, $utype: ty, $byte_len: expr,
+     $uto_array: ident, $ufrom_slice: ident) => {
+        /// Convert a signed integer into a byte array, using the
+        /// unsigned conversion of the same endianness.
+        #[inline]
+        pub fn $to_array(val: $type) -> [u8; $byte_len] {
+            $uto_array(val as $utype)
+        }
+
+        /// Convert a byte slice into a signed integer, using the
+        /// unsigned conversion of the same endianness.
+        #[inline]
+        pub fn $from_slice(slice: &[u8]) -> $type {
+            $ufrom_slice(slice) as $type
+        }
+    };
+}
+
+define_slice_to_be!(slice_to_u16_be, u16);
 define_slice_to_be!(slice_to_u32_be, u32);
-define_be_to_array!(u32_to_array_be, u32, 4);
+define_slice_to_be!(slice_to_u64_be, u64);
+define_slice_to_be!(slice_to_u128_be, u128);
 
 define_slice_to_le!(slice_to_u16_le, u16);
 define_slice_to_le!(slice_to_u32_le, u32);
@@ -84,6 +122,11 @@ define_slice_to_le!(slice_to_u128_le, u128);
 //define_slice_to_le!(slice_to_usize_le, usize);
 //define_slice_to_le!(slice_to_isize_le, isize);
 
+define_be_to_array!(u16_to_array_be, u16, 2);
+define_be_to_array!(u32_to_array_be, u32, 4);
+define_be_to_array!(u64_to_array_be, u64, 8);
+define_be_to_array!(u128_to_array_be, u128, 16);
+
 define_le_to_array!(u16_to_array_le, u16, 2);
 define_le_to_array!(u32_to_array_le, u32, 4);
 define_le_to_array!(u64_to_array_le, u64, 8);
@@ -91,71 +134,134 @@ define_le_to_array!(u128_to_array_le, u128, 16);
 //define_le_to_array!(usize_to_array_le, usize, usize::BITS as usize / 8);
 //define_le_to_array!(isize_to_array_le, isize, isize::BITS as usize / 8);
 
-#[inline]
-pub fn i16_to_array_le(val: i16) -> [u8; 2] {
-    u16_to_array_le(val as u16)
-}
-
-#[inline]
-pub fn i32_to_array_le(val: i32) -> [u8; 4] {
-    u32_to_array_le(val as u32)
-}
-
-#[inline]
-pub fn i64_to_array_le(val: i64) -> [u8; 8] {
-    u64_to_array_le(val as u64)
-}
-
-#[inline]
-pub fn i128_to_array_le(val: i128) -> [u8; 16] {
-    u128_to_array_le(val as u128)
-}
+define_signed_conversions!(
+    i16_to_array_le,
+    slice_to_i16_le,
+    i16,
+    u16,
+    2,
+    u16_to_array_le,
+    slice_to_u16_le
+);
+define_signed_conversions!(
+    i32_to_array_le,
+    slice_to_i32_le,
+    i32,
+    u32,
+    4,
+    u32_to_array_le,
+    slice_to_u32_le
+);
+define_signed_conversions!(
+    i64_to_array_le,
+    slice_to_i64_le,
+    i64,
+    u64,
+    8,
+    u64_to_array_le,
+    slice_to_u64_le
+);
+define_signed_conversions!(
+    i128_to_array_le,
+    slice_to_i128_le,
+    i128,
+    u128,
+    16,
+    u128_to_array_le,
+    slice_to_u128_le
+);
 
-#[inline]
-pub fn slice_to_i16_le(slice: &[u8]) -> i16 {
-    slice_to_u16_le(slice) as i16
-}
-
-#[inline]
-pub fn slice_to_i32_le(slice: &[u8]) -> i32 {
-    slice_to_u32_le(slice) as i32
-}
-
-#[inline]
-pub fn slice_to_i64_le(slice: &[u8]) -> i64 {
-    slice_to_u64_le(slice) as i64
-}
-
-#[inline]
-pub fn slice_to_i128_le(slice: &[u8]) -> i128 {
-    slice_to_u128_le(slice) as i128
-}
+define_signed_conversions!(
+    i16_to_array_be,
+    slice_to_i16_be,
+    i16,
+    u16,
+    2,
+    u16_to_array_be,
+    slice_to_u16_be
+);
+define_signed_conversions!(
+    i32_to_array_be,
+    slice_to_i32_be,
+    i32,
+    u32,
+    4,
+    u32_to_array_be,
+    slice_to_u32_be
+);
+define_signed_conversions!(
+    i64_to_array_be,
+    slice_to_i64_be,
+    i64,
+    u64,
+    8,
+    u64_to_array_be,
+    slice_to_u64_be
+);
+define_signed_conversions!(
+    i128_to_array_be,
+    slice_to_i128_be,
+    i128,
+    u128,
+    16,
+    u128_to_array_be,
+    slice_to_u128_be
+);
 
+/// Convert `f64` into a little-endian byte array.
 #[inline]
 pub fn f64_to_array_le(val: f64) -> [u8; 8] {
     assert_eq!(::core::mem::size_of::<f64>(), 8);
     val.to_le_bytes()
 }
+/// Convert a little-endian byte array into `f64`.
 #[inline]
 pub fn slice_to_f64_le(slice: &[u8; 8]) -> f64 {
     assert_eq!(slice.len(), ::core::mem::size_of::<f64>());
     f64::from_le_bytes(*slice)
 }
+/// Convert `f32` into a little-endian byte array.
 #[inline]
 pub fn f32_to_array_le(val: f32) -> [u8; 4] {
     assert_eq!(::core::mem::size_of::<f32>(), 4);
     val.to_le_bytes()
 }
+/// Convert a little-endian byte array into `f32`.
 #[inline]
 pub fn slice_to_f32_le(slice: &[u8; 4]) -> f32 {
     assert_eq!(slice.len(), ::core::mem::size_of::<f32>());
     f32::from_le_bytes(*slice)
 }
+/// Convert `f64` into a big-endian byte array.
+#[inline]
+pub fn f64_to_array_be(val: f64) -> [u8; 8] {
+    assert_eq!(::core::mem::size_of::<f64>(), 8);
+    val.to_be_bytes()
+}
+/// Convert a big-endian byte array into `f64`.
+#[inline]
+pub fn slice_to_f64_be(slice: &[u8; 8]) -> f64 {
+    assert_eq!(slice.len(), ::core::mem::size_of::<f64>());
+    f64::from_be_bytes(*slice)
+}
+/// Convert `f32` into a big-endian byte array.
+#[inline]
+pub fn f32_to_array_be(val: f32) -> [u8; 4] {
+    assert_eq!(::core::mem::size_of::<f32>(), 4);
+    val.to_be_bytes()
+}
+/// Convert a big-endian byte array into `f32`.
+#[inline]
+pub fn slice_to_f32_be(slice: &[u8; 4]) -> f32 {
+    assert_eq!(slice.len(), ::core::mem::size_of::<f32>());
+    f32::from_be_bytes(*slice)
+}
 
 macro_rules! define_chunk_slice_to_int {
     ($name: ident, $type: ty, $converter: ident) => {
+        #[doc = concat!("Convert a byte slice into a `", stringify!($type), "` slice.")]
+        /// Panics if the input length doesn't match the output size.
         #[inline]
-        #[allow(dead_code)]
         pub fn $name(inp: &[u8], outp: &mut [$type]) {
             //assert_eq!(inp.len(), outp.len() * ::core::mem::size_of::<$type>());
             assert_eq!(inp.len(), std::mem::size_of_val(outp));
@@ -169,6 +275,63 @@ macro_rules! define_chunk_slice_to_int {
 }
 
 define_chunk_slice_to_int!(bytes_to_u64_slice_le, u64, slice_to_u64_le);
+define_chunk_slice_to_int!(bytes_to_u64_slice_be, u64, slice_to_u64_be);
+
+/// Wrapper type for integers that get encoded in big-endian (network
+/// byte order), instead of the default little-endian, for interop with
+/// external protocols.
+///
+/// ```
+/// use darkfi_serial::{serialize, NetworkEndian};
+///
+/// assert_eq!(serialize(&NetworkEndian(0xdeadbeef_u32)), vec![0xde, 0xad, 0xbe, 0xef]);
+/// assert_eq!(serialize(&0xdeadbeef_u32), vec![0xef, 0xbe, 0xad, 0xde]);
+/// ```
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct NetworkEndian<T>(pub T);
+
+impl<T> NetworkEndian<T> {
+    /// Consume the wrapper, returning the inner value.
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+impl<T> From<T> for NetworkEndian<T> {
+    fn from(val: T) -> Self {
+        Self(val)
+    }
+}
+
+macro_rules! impl_network_endian {
+    ($type: ty, $byte_len: expr, $to_array: ident, $from_slice: ident) => {
+        impl Encodable for NetworkEndian<$type> {
+            #[inline]
+            fn encode<S: Write>(&self, s: &mut S) -> Result<usize, Error> {
+                s.write_all(&$to_array(self.0))?;
+                Ok($byte_len)
+            }
+        }
+
+        impl Decodable for NetworkEndian<$type> {
+            #[inline]
+            fn decode<D: Read>(d: &mut D) -> Result<Self, Error> {
+                let mut val = [0; $byte_len];
+                d.read_exact(&mut val)?;
+                Ok(Self($from_slice(&val)))
+            }
+        }
+    };
+}
+
+impl_network_endian!(u16, 2, u16_to_array_be, slice_to_u16_be);
+impl_network_endian!(u32, 4, u32_to_array_be, slice_to_u32_be);
+impl_network_endian!(u64, 8, u64_to_array_be, slice_to_u64_be);
+impl_network_endian!(u128, 16, u128_to_array_be, slice_to_u128_be);
+impl_network_endian!(i16, 2, i16_to_array_be, slice_to_i16_be);
+impl_network_endian!(i32, 4, i32_to_array_be, slice_to_i32_be);
+impl_network_endian!(i64, 8, i64_to_array_be, slice_to_i64_be);
+impl_network_endian!(i128, 16, i128_to_array_be, slice_to_i128_be);
 
 #[cfg(test)]
 mod tests {
@@ -178,6 +341,20 @@ mod tests {
     fn endianness_test() {
         assert_eq!(slice_to_u32_be(&[0xde, 0xad, 0xbe, 0xef]), 0xdeadbeef);
         assert_eq!(u32_to_array_be(0xdeadbeef), [0xde, 0xad, 0xbe, 0xef]);
+        assert_eq!(slice_to_u16_be(&[0xde, 0xad]), 0xdead);
+        assert_eq!(u16_to_array_be(0xdead), [0xde, 0xad]);
+        assert_eq!(
+            slice_to_u64_be(&[0x1b, 0xad, 0xca, 0xfe, 0xde, 0xad, 0xbe, 0xef]),
+            0x1badcafedeadbeef
+        );
+        assert_eq!(
+            u64_to_array_be(0x1badcafedeadbeef),
+            [0x1b, 0xad, 0xca, 0xfe, 0xde, 0xad, 0xbe, 0xef]
+        );
+        assert_eq!(slice_to_u128_be(&u128_to_array_be(u128::MAX - 1)), u128::MAX - 1);
+        assert_eq!(i32_to_array_be(-2), [0xff, 0xff, 0xff, 0xfe]);
+        assert_eq!(slice_to_i32_be(&[0xff, 0xff, 0xff, 0xfe]), -2);
+        assert_eq!(slice_to_f64_be(&f64_to_array_be(1.5)), 1.5);
 
         assert_eq!(slice_to_u16_le(&[0xad, 0xde]), 0xdead);
         assert_eq!(slice_to_u32_le(&[0xef, 0xbe, 0xad, 0xde]), 0xdeadbeef);
@@ -202,5 +379,24 @@ mod tests {
         let mut out = [0; 2];
         bytes_to_u64_slice_le(&inp, &mut out);
         assert_eq!(out, [0x1badcafedeadbeef, 0x0201face1badcafe]);
+        bytes_to_u64_slice_be(&inp, &mut out);
+        assert_eq!(out, [0xefbeaddefecaad1b, 0xfecaad1bcefa0102]);
+    }
+
+    #[test]
+    fn network_endian_test() {
+        use crate::{deserialize, serialize};
+
+        assert_eq!(serialize(&NetworkEndian(0xdeadu16)), vec![0xde, 0xad]);
+        assert_eq!(
+            serialize(&NetworkEndian(-2i64)),
+            vec![0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xfe]
+        );
+
+        let val = NetworkEndian(0x1badcafedeadbeef_u64);
+        let bytes = serialize(&val);
+        assert_eq!(bytes, u64_to_array_be(val.0));
+        assert_eq!(deserialize::<NetworkEndian<u64>>(&bytes).unwrap(), val);
+        assert_eq!(deserialize::<u64>(&bytes).unwrap(), val.into_inner().swap_bytes());
     }
 }
@@ -0,0 +1,243 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2025 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Adapters hashing data while it is being encoded or decoded.
+//!
+//! A common pattern is serializing an object into a buffer, and then
+//! hashing that buffer to derive the object's ID. [`HashedWriter`] and
+//! [`HashedReader`] wrap a writer or reader and feed exactly the bytes
+//! that passed through them into a [`StreamHasher`], so the canonical
+//! encoding is only produced once.
+
+use std::io::{Read, Result, Write};
+
+#[cfg(feature = "async")]
+use std::{
+    pin::Pin,
+    task::{ready, Context, Poll},
+};
+
+use crate::Encodable;
+#[cfg(feature = "async")]
+use crate::{AsyncEncodable, AsyncRead, AsyncWrite};
+
+/// Hash functions that can be incrementally fed with data
+pub trait StreamHasher {
+    /// Digest produced by the hash function
+    type Output;
+
+    /// Feed more data into the hasher
+    fn update(&mut self, data: &[u8]);
+
+    /// Produce the digest of all the data fed so far
+    fn finalize(&self) -> Self::Output;
+}
+
+#[cfg(feature = "hash")]
+impl StreamHasher for blake3::Hasher {
+    type Output = blake3::Hash;
+
+    fn update(&mut self, data: &[u8]) {
+        blake3::Hasher::update(self, data);
+    }
+
+    fn finalize(&self) -> Self::Output {
+        blake3::Hasher::finalize(self)
+    }
+}
+
+/// Writer adapter hashing all the bytes written to the inner writer
+pub struct HashedWriter<W, H> {
+    inner: W,
+    hasher: H,
+}
+
+impl<W, H: StreamHasher> HashedWriter<W, H> {
+    /// Wrap the given writer, feeding written bytes into `hasher`
+    pub fn new(inner: W, hasher: H) -> Self {
+        Self { inner, hasher }
+    }
+
+    /// Reference to the underlying writer
+    pub fn get_ref(&self) -> &W {
+        &self.inner
+    }
+
+    /// Digest of the bytes written so far
+    pub fn digest(&self) -> H::Output {
+        self.hasher.finalize()
+    }
+
+    /// Consume the adapter, returning the inner writer and the digest
+    /// of all the bytes written to it
+    pub fn finalize(self) -> (W, H::Output) {
+        let digest = self.hasher.finalize();
+        (self.inner, digest)
+    }
+}
+
+impl<W: Write, H: StreamHasher> Write for HashedWriter<W, H> {
+    fn write(&mut self, buf: &[u8]) -> Result<usize> {
+        // Only hash what the inner writer actually accepted
+        let n = self.inner.write(buf)?;
+        self.hasher.update(&buf[..n]);
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> Result<()> {
+        self.inner.flush()
+    }
+}
+
+#[cfg(feature = "async")]
+impl<W: AsyncWrite + Unpin, H: StreamHasher + Unpin> AsyncWrite for HashedWriter<W, H> {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<Result<usize>> {
+        let this = &mut *self;
+        let n = ready!(Pin::new(&mut this.inner).poll_write(cx, buf))?;
+        this.hasher.update(&buf[..n]);
+        Poll::Ready(Ok(n))
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<()>> {
+        Pin::new(&mut self.inner).poll_flush(cx)
+    }
+
+    fn poll_close(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<()>> {
+        Pin::new(&mut self.inner).poll_close(cx)
+    }
+}
+
+/// Reader adapter hashing all the bytes read from the inner reader
+pub struct HashedReader<R, H> {
+    inner: R,
+    hasher: H,
+}
+
+impl<R, H: StreamHasher> HashedReader<R, H> {
+    /// Wrap the given reader, feeding read bytes into `hasher`
+    pub fn new(inner: R, hasher: H) -> Self {
+        Self { inner, hasher }
+    }
+
+    /// Reference to the underlying reader
+    pub fn get_ref(&self) -> &R {
+        &self.inner
+    }
+
+    /// Digest of the bytes read so far
+    pub fn digest(&self) -> H::Output {
+        self.hasher.finalize()
+    }
+
+    /// Consume the adapter, returning the inner reader and the digest
+    /// of all the bytes read from it
+    pub fn finalize(self) -> (R, H::Output) {
+        let digest = self.hasher.finalize();
+        (self.inner, digest)
+    }
+}
+
+impl<R: Read, H: StreamHasher> Read for HashedReader<R, H> {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize> {
+        let n = self.inner.read(buf)?;
+        self.hasher.update(&buf[..n]);
+        Ok(n)
+    }
+}
+
+#[cfg(feature = "async")]
+impl<R: AsyncRead + Unpin, H: StreamHasher + Unpin> AsyncRead for HashedReader<R, H> {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<Result<usize>> {
+        let this = &mut *self;
+        let n = ready!(Pin::new(&mut this.inner).poll_read(cx, buf))?;
+        this.hasher.update(&buf[..n]);
+        Poll::Ready(Ok(n))
+    }
+}
+
+/// Encode an object into a vector, hashing the encoded bytes in the same pass.
+pub fn serialize_hashed<T: Encodable + ?Sized, H: StreamHasher + Default>(
+    data: &T,
+) -> (Vec<u8>, H::Output) {
+    let mut writer = HashedWriter::new(Vec::new(), H::default());
+    let len = data.encode(&mut writer).unwrap();
+    let (encoded, digest) = writer.finalize();
+    assert_eq!(len, encoded.len());
+    (encoded, digest)
+}
+
+/// Asynchronously encode an object into a vector, hashing the encoded
+/// bytes in the same pass.
+#[cfg(feature = "async")]
+pub async fn serialize_hashed_async<
+    T: AsyncEncodable + ?Sized,
+    H: StreamHasher + Default + Unpin + Send,
+>(
+    data: &T,
+) -> (Vec<u8>, H::Output) {
+    let mut writer = HashedWriter::new(Vec::new(), H::default());
+    let len = data.encode_async(&mut writer).await.unwrap();
+    let (encoded, digest) = writer.finalize();
+    assert_eq!(len, encoded.len());
+    (encoded, digest)
+}
+
+#[cfg(all(test, feature = "hash"))]
+mod tests {
+    use super::*;
+    use crate::{deserialize, serialize, Decodable};
+
+    #[test]
+    fn hashed_writer_matches_double_serialization() {
+        let data = (vec![1u64, 2, 3], String::from("darkfi"), Some(42u32));
+
+        let (encoded, digest) = serialize_hashed::<_, blake3::Hasher>(&data);
+        assert_eq!(encoded, serialize(&data));
+        assert_eq!(digest, blake3::hash(&serialize(&data)));
+    }
+
+    #[test]
+    fn hashed_reader_matches_encoding() {
+        let data = (vec![1u64, 2, 3], String::from("darkfi"), Some(42u32));
+        let encoded = serialize(&data);
+
+        let mut reader = HashedReader::new(&encoded[..], blake3::Hasher::new());
+        let decoded: (Vec<u64>, String, Option<u32>) = Decodable::decode(&mut reader).unwrap();
+        assert_eq!(decoded, deserialize(&encoded).unwrap());
+        assert_eq!(reader.digest(), blake3::hash(&encoded));
+    }
+
+    #[cfg(feature = "async")]
+    #[test]
+    fn hashed_writer_async() {
+        let data = (vec![1u64, 2, 3], String::from("darkfi"), Some(42u32));
+
+        let (encoded, digest) =
+            futures_lite::future::block_on(serialize_hashed_async::<_, blake3::Hasher>(&data));
+        assert_eq!(encoded, serialize(&data));
+        assert_eq!(digest, blake3::hash(&encoded));
+    }
+}
@@ -29,11 +29,24 @@ mod async_lib;
 #[cfg(feature = "async")]
 pub use async_lib::{
     async_trait, deserialize_async, deserialize_async_partial, serialize_async, AsyncDecodable,
-    AsyncEncodable, AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt, FutAsyncReadExt,
-    FutAsyncWriteExt,
+    AsyncDecoder, AsyncEncodable, AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt,
+    FutAsyncReadExt, FutAsyncWriteExt, ASYNC_DECODER_MAX_BUFFER,
 };
 
-mod endian;
+/// Endianness conversion helpers
+pub mod endian;
+pub use endian::NetworkEndian;
+
+/// Hashing adapters for encoding and decoding
+pub mod hashed;
+#[cfg(feature = "async")]
+pub use hashed::serialize_hashed_async;
+pub use hashed::{serialize_hashed, HashedReader, HashedWriter, StreamHasher};
+
+/// Fixed-point decimal numbers
+pub mod decimal;
+pub use decimal::Decimal;
+
 mod types;
 
 /// Data which can be encoded in a consensus-consistent way.
@@ -1008,4 +1021,99 @@ mod tests {
         assert_eq!(ts1, ts1_n);
         assert_eq!(ts1_n, TestStruct1(baz));
     }
+
+    /// Reader yielding a single byte per read, and pending on every other poll.
+    #[cfg(feature = "async")]
+    struct TrickleReader {
+        data: Vec<u8>,
+        pos: usize,
+        pending: bool,
+    }
+
+    #[cfg(feature = "async")]
+    impl AsyncRead for TrickleReader {
+        fn poll_read(
+            mut self: std::pin::Pin<&mut Self>,
+            cx: &mut std::task::Context<'_>,
+            buf: &mut [u8],
+        ) -> std::task::Poll<std::io::Result<usize>> {
+            self.pending = !self.pending;
+            if self.pending {
+                cx.waker().wake_by_ref();
+                return std::task::Poll::Pending
+            }
+
+            if self.pos == self.data.len() || buf.is_empty() {
+                return std::task::Poll::Ready(Ok(0))
+            }
+
+            buf[0] = self.data[self.pos];
+            self.pos += 1;
+            std::task::Poll::Ready(Ok(1))
+        }
+    }
+
+    #[cfg(feature = "async")]
+    #[test]
+    fn async_decoder_cancellation_safety() {
+        futures_lite::future::block_on(async {
+            let ts0 = TestStruct0 { foo: 44, bar: true, baz: String::from("foobarbaz") };
+            let ts1 = TestStruct1(String::from("quux"));
+
+            let mut data = serialize(&ts0);
+            data.extend_from_slice(&serialize(&ts1));
+            data.extend_from_slice(&serialize(&VarInt(0x10000)));
+            let reader = TrickleReader { data, pos: 0, pending: false };
+            let mut decoder = AsyncDecoder::new(reader);
+
+            // Drop the decode future after every single poll, which would
+            // lose data when decoding directly from the reader.
+            let ts0_n: TestStruct0 = loop {
+                if let Some(rv) = futures_lite::future::poll_once(decoder.decode()).await {
+                    break rv.unwrap()
+                }
+            };
+            assert_eq!(ts0_n, ts0);
+
+            let ts1_n: TestStruct1 = loop {
+                if let Some(rv) = futures_lite::future::poll_once(decoder.decode()).await {
+                    break rv.unwrap()
+                }
+            };
+            assert_eq!(ts1_n, ts1);
+
+            let varint: VarInt = decoder.decode().await.unwrap();
+            assert_eq!(varint, VarInt(0x10000));
+            assert_eq!(decoder.buffered(), 0);
+
+            // Stream is exhausted now
+            let err = decoder.decode::<u8>().await.unwrap_err();
+            assert_eq!(err.kind(), ErrorKind::UnexpectedEof);
+
+            // A pending decode can only be resumed for the same type,
+            // and blocks raw reads until it completes
+            let mut data = serialize(&ts1);
+            data.extend_from_slice(&[1, 2, 3]);
+            let reader = TrickleReader { data, pos: 0, pending: false };
+            let mut decoder = AsyncDecoder::new(reader);
+            assert!(futures_lite::future::poll_once(decoder.decode::<TestStruct1>())
+                .await
+                .is_none());
+            let err = decoder.decode::<TestStruct0>().await.unwrap_err();
+            assert_eq!(err.kind(), ErrorKind::InvalidInput);
+            let mut raw = [0u8; 3];
+            assert!(decoder.read_exact(&mut raw).await.is_err());
+            let ts1_n: TestStruct1 = decoder.decode().await.unwrap();
+            assert_eq!(ts1_n, ts1);
+            decoder.read_exact(&mut raw).await.unwrap();
+            assert_eq!(raw, [1, 2, 3]);
+
+            // Buffer limit is enforced
+            let data = serialize(&vec![0u8; 64]);
+            let reader = TrickleReader { data, pos: 0, pending: false };
+            let mut decoder = AsyncDecoder::with_max_buffer(reader, 32);
+            let err = decoder.decode::<Vec<u8>>().await.unwrap_err();
+            assert_eq!(err.kind(), ErrorKind::InvalidData);
+        });
+    }
 }
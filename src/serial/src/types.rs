@@ -16,7 +16,10 @@
  * along with this program.  If not, see <https://www.gnu.org/licenses/>.
  */
 
-//! Encodings for external crates
+//! Encodings for standard library types and external crates
+
+mod net;
+mod time;
 
 #[cfg(feature = "collections")]
 mod collections;
@@ -16,7 +16,15 @@
  * along with this program.  If not, see <https://www.gnu.org/licenses/>.
  */
 
-use std::io::{Read, Result, Write};
+//! Encodings for `num_bigint` types.
+//!
+//! Both `BigInt` and `BigUint` are encoded as a length-prefixed vector of
+//! their minimal big-endian bytes, two's complement for `BigInt`. Zero is
+//! encoded as a single zero byte. Decoding rejects any other encoding, so
+//! each value has exactly one valid encoding and can be safely hashed or
+//! compared in its serialized form.
+
+use std::io::{Error, ErrorKind, Read, Result, Write};
 
 #[cfg(feature = "async")]
 use crate::{AsyncDecodable, AsyncEncodable};
@@ -27,6 +35,28 @@ use futures_lite::{AsyncRead, AsyncWrite};
 
 use crate::{Decodable, Encodable};
 
+/// Build a `BigInt` out of its decoded bytes, rejecting non-minimal encodings
+fn bigint_from_bytes(bytes: &[u8]) -> Result<num_bigint::BigInt> {
+    let redundant = match bytes {
+        [] => true,
+        [0x00, next, ..] => *next < 0x80,
+        [0xff, next, ..] => *next >= 0x80,
+        _ => false,
+    };
+    if redundant {
+        return Err(Error::new(ErrorKind::InvalidData, "Non-canonical BigInt encoding"))
+    }
+    Ok(num_bigint::BigInt::from_signed_bytes_be(bytes))
+}
+
+/// Build a `BigUint` out of its decoded bytes, rejecting non-minimal encodings
+fn biguint_from_bytes(bytes: &[u8]) -> Result<num_bigint::BigUint> {
+    if bytes.is_empty() || (bytes.len() > 1 && bytes[0] == 0) {
+        return Err(Error::new(ErrorKind::InvalidData, "Non-canonical BigUint encoding"))
+    }
+    Ok(num_bigint::BigUint::from_bytes_be(bytes))
+}
+
 impl Encodable for num_bigint::BigInt {
     fn encode<S: Write>(&self, s: &mut S) -> Result<usize> {
         self.to_signed_bytes_be().encode(s)
@@ -44,7 +74,7 @@ impl AsyncEncodable for num_bigint::BigInt {
 impl Decodable for num_bigint::BigInt {
     fn decode<D: Read>(d: &mut D) -> Result<Self> {
         let vec: Vec<u8> = Decodable::decode(d)?;
-        Ok(Self::from_signed_bytes_be(&vec))
+        bigint_from_bytes(&vec)
     }
 }
 
@@ -53,7 +83,7 @@ impl Decodable for num_bigint::BigInt {
 impl AsyncDecodable for num_bigint::BigInt {
     async fn decode_async<D: AsyncRead + Unpin + Send>(d: &mut D) -> Result<Self> {
         let vec: Vec<u8> = AsyncDecodable::decode_async(d).await?;
-        Ok(Self::from_signed_bytes_be(&vec))
+        bigint_from_bytes(&vec)
     }
 }
 
@@ -74,7 +104,7 @@ impl AsyncEncodable for num_bigint::BigUint {
 impl Decodable for num_bigint::BigUint {
     fn decode<D: Read>(d: &mut D) -> Result<Self> {
         let vec: Vec<u8> = Decodable::decode(d)?;
-        Ok(Self::from_bytes_be(&vec))
+        biguint_from_bytes(&vec)
     }
 }
 
@@ -83,6 +113,55 @@ impl Decodable for num_bigint::BigUint {
 impl AsyncDecodable for num_bigint::BigUint {
     async fn decode_async<D: AsyncRead + Unpin + Send>(d: &mut D) -> Result<Self> {
         let vec: Vec<u8> = AsyncDecodable::decode_async(d).await?;
-        Ok(Self::from_bytes_be(&vec))
+        biguint_from_bytes(&vec)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use num_bigint::{BigInt, BigUint};
+
+    use crate::{deserialize, serialize};
+
+    #[test]
+    fn serialize_deserialize_bigint() {
+        let values = [
+            "0",
+            "1",
+            "-1",
+            "127",
+            "128",
+            "-128",
+            "-129",
+            "255",
+            "-256",
+            "1267650600228229401496703205376",
+        ];
+
+        for value in values {
+            let original: BigInt = value.parse().unwrap();
+            let serialized = serialize(&original);
+            assert_eq!(deserialize::<BigInt>(&serialized).unwrap(), original);
+
+            if let Some(original) = original.to_biguint() {
+                let serialized = serialize(&original);
+                assert_eq!(deserialize::<BigUint>(&serialized).unwrap(), original);
+            }
+        }
+    }
+
+    #[test]
+    fn reject_non_canonical_bigint() {
+        // Empty and zero-padded encodings
+        assert!(deserialize::<BigUint>(&[0]).is_err());
+        assert!(deserialize::<BigUint>(&[2, 0x00, 0x01]).is_err());
+        assert!(deserialize::<BigInt>(&[0]).is_err());
+        assert!(deserialize::<BigInt>(&[2, 0x00, 0x01]).is_err());
+        assert!(deserialize::<BigInt>(&[2, 0xff, 0xff]).is_err());
+
+        // Padding needed to keep the sign is fine
+        assert_eq!(deserialize::<BigUint>(&[1, 0x00]).unwrap(), BigUint::from(0u8));
+        assert_eq!(deserialize::<BigInt>(&[2, 0x00, 0x80]).unwrap(), BigInt::from(128));
+        assert_eq!(deserialize::<BigInt>(&[2, 0xff, 0x7f]).unwrap(), BigInt::from(-129));
     }
 }
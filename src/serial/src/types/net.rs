@@ -0,0 +1,268 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2025 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Encodings for `std::net` types.
+//!
+//! An `Ipv4Addr` is encoded as its 4 octets, and an `Ipv6Addr` as its 16
+//! octets. An `IpAddr` is prefixed with a `u8` tag, being `4` or `6`,
+//! followed by the corresponding address. A `SocketAddr` is encoded the
+//! same way, with the `u16` port following the address. IPv6 socket
+//! addresses additionally encode their `u32` flow info and scope ID.
+
+use std::{
+    io::{Error, ErrorKind, Read, Result, Write},
+    net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr, SocketAddrV4, SocketAddrV6},
+};
+
+#[cfg(feature = "async")]
+use crate::{AsyncDecodable, AsyncEncodable};
+#[cfg(feature = "async")]
+use async_trait::async_trait;
+#[cfg(feature = "async")]
+use futures_lite::{AsyncRead, AsyncWrite};
+
+use crate::{Decodable, Encodable};
+
+/// `IpAddr` and `SocketAddr` tag for IPv4 addresses
+const TAG_V4: u8 = 4;
+/// `IpAddr` and `SocketAddr` tag for IPv6 addresses
+const TAG_V6: u8 = 6;
+
+fn invalid_tag(tag: u8) -> Error {
+    Error::new(ErrorKind::InvalidData, format!("Invalid IP address tag: {}", tag))
+}
+
+impl Encodable for Ipv4Addr {
+    fn encode<S: Write>(&self, s: &mut S) -> Result<usize> {
+        self.octets().encode(s)
+    }
+}
+
+#[cfg(feature = "async")]
+#[async_trait]
+impl AsyncEncodable for Ipv4Addr {
+    async fn encode_async<S: AsyncWrite + Unpin + Send>(&self, s: &mut S) -> Result<usize> {
+        self.octets().encode_async(s).await
+    }
+}
+
+impl Decodable for Ipv4Addr {
+    fn decode<D: Read>(d: &mut D) -> Result<Self> {
+        let octets: [u8; 4] = Decodable::decode(d)?;
+        Ok(Self::from(octets))
+    }
+}
+
+#[cfg(feature = "async")]
+#[async_trait]
+impl AsyncDecodable for Ipv4Addr {
+    async fn decode_async<D: AsyncRead + Unpin + Send>(d: &mut D) -> Result<Self> {
+        let octets: [u8; 4] = AsyncDecodable::decode_async(d).await?;
+        Ok(Self::from(octets))
+    }
+}
+
+impl Encodable for Ipv6Addr {
+    fn encode<S: Write>(&self, s: &mut S) -> Result<usize> {
+        self.octets().encode(s)
+    }
+}
+
+#[cfg(feature = "async")]
+#[async_trait]
+impl AsyncEncodable for Ipv6Addr {
+    async fn encode_async<S: AsyncWrite + Unpin + Send>(&self, s: &mut S) -> Result<usize> {
+        self.octets().encode_async(s).await
+    }
+}
+
+impl Decodable for Ipv6Addr {
+    fn decode<D: Read>(d: &mut D) -> Result<Self> {
+        let octets: [u8; 16] = Decodable::decode(d)?;
+        Ok(Self::from(octets))
+    }
+}
+
+#[cfg(feature = "async")]
+#[async_trait]
+impl AsyncDecodable for Ipv6Addr {
+    async fn decode_async<D: AsyncRead + Unpin + Send>(d: &mut D) -> Result<Self> {
+        let octets: [u8; 16] = AsyncDecodable::decode_async(d).await?;
+        Ok(Self::from(octets))
+    }
+}
+
+impl Encodable for IpAddr {
+    fn encode<S: Write>(&self, s: &mut S) -> Result<usize> {
+        match self {
+            Self::V4(ip) => Ok(TAG_V4.encode(s)? + ip.encode(s)?),
+            Self::V6(ip) => Ok(TAG_V6.encode(s)? + ip.encode(s)?),
+        }
+    }
+}
+
+#[cfg(feature = "async")]
+#[async_trait]
+impl AsyncEncodable for IpAddr {
+    async fn encode_async<S: AsyncWrite + Unpin + Send>(&self, s: &mut S) -> Result<usize> {
+        match self {
+            Self::V4(ip) => Ok(TAG_V4.encode_async(s).await? + ip.encode_async(s).await?),
+            Self::V6(ip) => Ok(TAG_V6.encode_async(s).await? + ip.encode_async(s).await?),
+        }
+    }
+}
+
+impl Decodable for IpAddr {
+    fn decode<D: Read>(d: &mut D) -> Result<Self> {
+        match u8::decode(d)? {
+            TAG_V4 => Ok(Self::V4(Decodable::decode(d)?)),
+            TAG_V6 => Ok(Self::V6(Decodable::decode(d)?)),
+            tag => Err(invalid_tag(tag)),
+        }
+    }
+}
+
+#[cfg(feature = "async")]
+#[async_trait]
+impl AsyncDecodable for IpAddr {
+    async fn decode_async<D: AsyncRead + Unpin + Send>(d: &mut D) -> Result<Self> {
+        match u8::decode_async(d).await? {
+            TAG_V4 => Ok(Self::V4(AsyncDecodable::decode_async(d).await?)),
+            TAG_V6 => Ok(Self::V6(AsyncDecodable::decode_async(d).await?)),
+            tag => Err(invalid_tag(tag)),
+        }
+    }
+}
+
+impl Encodable for SocketAddr {
+    fn encode<S: Write>(&self, s: &mut S) -> Result<usize> {
+        match self {
+            Self::V4(addr) => {
+                let mut len = TAG_V4.encode(s)?;
+                len += addr.ip().encode(s)?;
+                len += addr.port().encode(s)?;
+                Ok(len)
+            }
+            Self::V6(addr) => {
+                let mut len = TAG_V6.encode(s)?;
+                len += addr.ip().encode(s)?;
+                len += addr.port().encode(s)?;
+                len += addr.flowinfo().encode(s)?;
+                len += addr.scope_id().encode(s)?;
+                Ok(len)
+            }
+        }
+    }
+}
+
+#[cfg(feature = "async")]
+#[async_trait]
+impl AsyncEncodable for SocketAddr {
+    async fn encode_async<S: AsyncWrite + Unpin + Send>(&self, s: &mut S) -> Result<usize> {
+        match self {
+            Self::V4(addr) => {
+                let mut len = TAG_V4.encode_async(s).await?;
+                len += addr.ip().encode_async(s).await?;
+                len += addr.port().encode_async(s).await?;
+                Ok(len)
+            }
+            Self::V6(addr) => {
+                let mut len = TAG_V6.encode_async(s).await?;
+                len += addr.ip().encode_async(s).await?;
+                len += addr.port().encode_async(s).await?;
+                len += addr.flowinfo().encode_async(s).await?;
+                len += addr.scope_id().encode_async(s).await?;
+                Ok(len)
+            }
+        }
+    }
+}
+
+impl Decodable for SocketAddr {
+    fn decode<D: Read>(d: &mut D) -> Result<Self> {
+        match u8::decode(d)? {
+            TAG_V4 => {
+                let ip = Ipv4Addr::decode(d)?;
+                let port = u16::decode(d)?;
+                Ok(Self::V4(SocketAddrV4::new(ip, port)))
+            }
+            TAG_V6 => {
+                let ip = Ipv6Addr::decode(d)?;
+                let port = u16::decode(d)?;
+                let flowinfo = u32::decode(d)?;
+                let scope_id = u32::decode(d)?;
+                Ok(Self::V6(SocketAddrV6::new(ip, port, flowinfo, scope_id)))
+            }
+            tag => Err(invalid_tag(tag)),
+        }
+    }
+}
+
+#[cfg(feature = "async")]
+#[async_trait]
+impl AsyncDecodable for SocketAddr {
+    async fn decode_async<D: AsyncRead + Unpin + Send>(d: &mut D) -> Result<Self> {
+        match u8::decode_async(d).await? {
+            TAG_V4 => {
+                let ip = Ipv4Addr::decode_async(d).await?;
+                let port = u16::decode_async(d).await?;
+                Ok(Self::V4(SocketAddrV4::new(ip, port)))
+            }
+            TAG_V6 => {
+                let ip = Ipv6Addr::decode_async(d).await?;
+                let port = u16::decode_async(d).await?;
+                let flowinfo = u32::decode_async(d).await?;
+                let scope_id = u32::decode_async(d).await?;
+                Ok(Self::V6(SocketAddrV6::new(ip, port, flowinfo, scope_id)))
+            }
+            tag => Err(invalid_tag(tag)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+
+    use crate::{deserialize, serialize};
+
+    #[test]
+    fn serialize_deserialize_ip_addr() {
+        let ip = IpAddr::V4(Ipv4Addr::new(10, 0, 0, 1));
+        assert_eq!(serialize(&ip), vec![4u8, 10, 0, 0, 1]);
+        assert_eq!(deserialize::<IpAddr>(&serialize(&ip)).unwrap(), ip);
+
+        let ip = IpAddr::V6(Ipv6Addr::LOCALHOST);
+        assert_eq!(serialize(&ip).len(), 17);
+        assert_eq!(deserialize::<IpAddr>(&serialize(&ip)).unwrap(), ip);
+
+        // Unknown tags are rejected
+        assert!(deserialize::<IpAddr>(&[5u8, 10, 0, 0, 1]).is_err());
+    }
+
+    #[test]
+    fn serialize_deserialize_socket_addr() {
+        let addr: SocketAddr = "10.0.0.1:26661".parse().unwrap();
+        assert_eq!(serialize(&addr), vec![4u8, 10, 0, 0, 1, 37, 104]);
+        assert_eq!(deserialize::<SocketAddr>(&serialize(&addr)).unwrap(), addr);
+
+        let addr: SocketAddr = "[::1%3]:26661".parse().unwrap();
+        assert_eq!(serialize(&addr).len(), 27);
+        assert_eq!(deserialize::<SocketAddr>(&serialize(&addr)).unwrap(), addr);
+    }
+}
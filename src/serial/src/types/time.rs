@@ -0,0 +1,153 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2025 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Encodings for `std::time` types.
+//!
+//! A `Duration` is encoded as its whole seconds (`u64`), followed by its
+//! subsecond nanoseconds (`u32`). A `SystemTime` is encoded as the
+//! `Duration` elapsed since `UNIX_EPOCH`, so times before the epoch
+//! can't be encoded.
+
+use std::{
+    io::{Error, ErrorKind, Read, Result, Write},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+#[cfg(feature = "async")]
+use crate::{AsyncDecodable, AsyncEncodable};
+#[cfg(feature = "async")]
+use async_trait::async_trait;
+#[cfg(feature = "async")]
+use futures_lite::{AsyncRead, AsyncWrite};
+
+use crate::{Decodable, Encodable};
+
+/// Build a `Duration` out of its decoded parts, rejecting invalid nanos
+fn duration_from_parts(secs: u64, nanos: u32) -> Result<Duration> {
+    if nanos >= 1_000_000_000 {
+        return Err(Error::new(ErrorKind::InvalidData, "Duration nanoseconds out of range"))
+    }
+    Ok(Duration::new(secs, nanos))
+}
+
+/// Retrieve the `Duration` since `UNIX_EPOCH` of a `SystemTime`
+fn since_epoch(time: &SystemTime) -> Result<Duration> {
+    time.duration_since(UNIX_EPOCH)
+        .map_err(|_| Error::new(ErrorKind::InvalidInput, "SystemTime is before UNIX_EPOCH"))
+}
+
+/// Build a `SystemTime` out of its `Duration` since `UNIX_EPOCH`
+fn from_epoch(duration: Duration) -> Result<SystemTime> {
+    UNIX_EPOCH
+        .checked_add(duration)
+        .ok_or_else(|| Error::new(ErrorKind::InvalidData, "SystemTime out of range"))
+}
+
+impl Encodable for Duration {
+    fn encode<S: Write>(&self, s: &mut S) -> Result<usize> {
+        let mut len = self.as_secs().encode(s)?;
+        len += self.subsec_nanos().encode(s)?;
+        Ok(len)
+    }
+}
+
+#[cfg(feature = "async")]
+#[async_trait]
+impl AsyncEncodable for Duration {
+    async fn encode_async<S: AsyncWrite + Unpin + Send>(&self, s: &mut S) -> Result<usize> {
+        let mut len = self.as_secs().encode_async(s).await?;
+        len += self.subsec_nanos().encode_async(s).await?;
+        Ok(len)
+    }
+}
+
+impl Decodable for Duration {
+    fn decode<D: Read>(d: &mut D) -> Result<Self> {
+        let secs: u64 = Decodable::decode(d)?;
+        let nanos: u32 = Decodable::decode(d)?;
+        duration_from_parts(secs, nanos)
+    }
+}
+
+#[cfg(feature = "async")]
+#[async_trait]
+impl AsyncDecodable for Duration {
+    async fn decode_async<D: AsyncRead + Unpin + Send>(d: &mut D) -> Result<Self> {
+        let secs: u64 = AsyncDecodable::decode_async(d).await?;
+        let nanos: u32 = AsyncDecodable::decode_async(d).await?;
+        duration_from_parts(secs, nanos)
+    }
+}
+
+impl Encodable for SystemTime {
+    fn encode<S: Write>(&self, s: &mut S) -> Result<usize> {
+        since_epoch(self)?.encode(s)
+    }
+}
+
+#[cfg(feature = "async")]
+#[async_trait]
+impl AsyncEncodable for SystemTime {
+    async fn encode_async<S: AsyncWrite + Unpin + Send>(&self, s: &mut S) -> Result<usize> {
+        since_epoch(self)?.encode_async(s).await
+    }
+}
+
+impl Decodable for SystemTime {
+    fn decode<D: Read>(d: &mut D) -> Result<Self> {
+        from_epoch(Decodable::decode(d)?)
+    }
+}
+
+#[cfg(feature = "async")]
+#[async_trait]
+impl AsyncDecodable for SystemTime {
+    async fn decode_async<D: AsyncRead + Unpin + Send>(d: &mut D) -> Result<Self> {
+        from_epoch(AsyncDecodable::decode_async(d).await?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+    use crate::{deserialize, serialize, Encodable};
+
+    #[test]
+    fn serialize_deserialize_duration() {
+        let duration = Duration::new(5000, 1);
+        assert_eq!(serialize(&duration), vec![136u8, 19, 0, 0, 0, 0, 0, 0, 1, 0, 0, 0]);
+        assert_eq!(deserialize::<Duration>(&serialize(&duration)).unwrap(), duration);
+
+        // Nanoseconds must be below a second
+        let invalid = [serialize(&0u64), serialize(&1_000_000_000u32)].concat();
+        assert!(deserialize::<Duration>(&invalid).is_err());
+    }
+
+    #[test]
+    fn serialize_deserialize_system_time() {
+        let duration = Duration::new(5000, 1);
+        let time = UNIX_EPOCH + duration;
+        assert_eq!(serialize(&time), serialize(&duration));
+        assert_eq!(deserialize::<SystemTime>(&serialize(&time)).unwrap(), time);
+
+        // Times before the epoch can't be encoded
+        let mut buf = vec![];
+        assert!((UNIX_EPOCH - duration).encode(&mut buf).is_err());
+    }
+}
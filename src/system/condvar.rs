@@ -21,8 +21,11 @@ use std::{
     pin::Pin,
     sync::Mutex,
     task::{Context, Poll, Waker},
+    time::Duration,
 };
 
+use super::timeout::timeout;
+
 /// Condition variables allow you to block a task while waiting for an event to occur.
 /// Condition variables are typically associated with a boolean predicate (a condition).
 /// ```rust
@@ -71,6 +74,13 @@ impl CondVar {
         CondVarWait { state: &self.state }
     }
 
+    /// Wait for a notification, giving up after the given duration.
+    /// Returns `true` if we were notified, or `false` if we timed out.
+    /// Like `wait()`, this does not reset the condition variable.
+    pub async fn wait_timeout(&self, dur: Duration) -> bool {
+        timeout(dur, self.wait()).await.is_ok()
+    }
+
     /// Reset self ready to wait() again.
     /// The reason this is separate from `wait()` is that usually
     /// on the first `wait()` we want to catch any `notify()` calls that
@@ -235,6 +245,28 @@ mod tests {
         }))
     }
 
+    #[test]
+    fn condvar_wait_timeout() {
+        let executor = Arc::new(Executor::new());
+        let executor_ = executor.clone();
+        smol::block_on(executor.run(async move {
+            let cv = Arc::new(CondVar::new());
+
+            // Nobody notifies us, so this should time out
+            assert!(!cv.wait_timeout(Duration::from_millis(10)).await);
+
+            let cv_ = cv.clone();
+            let task =
+                executor_.spawn(async move { cv_.wait_timeout(Duration::from_secs(10)).await });
+
+            cv.notify();
+            assert!(task.await);
+
+            // Without calling reset(), this returns instantly
+            assert!(cv.wait_timeout(Duration::from_millis(10)).await);
+        }))
+    }
+
     #[test]
     fn condvar_drop() {
         let executor = Arc::new(Executor::new());
@@ -0,0 +1,310 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2025 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Runtime-controllable logger.
+//!
+//! [`RuntimeLogger`] wraps the loggers a daemon writes to, and filters
+//! records using a default level along with per-target level overrides
+//! which can be changed while running, e.g. to get `debug` logs out of
+//! `net::outbound_session` only, without restarting the daemon.
+//!
+//! Target overrides apply to the target itself and everything under it,
+//! so `net` also covers `net::outbound_session`. The most specific
+//! override wins.
+//!
+//! When switched on, every logged record is additionally published as a
+//! [`LogEvent`] to the channels returned by [`RuntimeLogger::subscribe`].
+//! Records of the targets delivering those events (see
+//! [`EVENTS_SKIPPED_TARGETS`]) are never published, as every delivered
+//! event would otherwise log, and so publish, further events.
+
+use std::{
+    collections::BTreeMap,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Mutex, OnceLock, RwLock,
+    },
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use log::{Level, LevelFilter, Log, Metadata, Record, SetLoggerError};
+use smol::channel::{self, Receiver, Sender};
+
+/// Capacity of every log event subscription channel. Events published
+/// to a full channel are dropped, so slow consumers can't stall logging.
+pub const EVENTS_CHANNEL_CAP: usize = 1024;
+
+/// Targets whose records never get published as log events. These log
+/// while delivering the events themselves, so publishing them would feed
+/// the event stream back into itself.
+pub const EVENTS_SKIPPED_TARGETS: &[&str] = &["rpc::server", "system::publisher"];
+
+/// The global runtime logger, if initialized
+static LOGGER: OnceLock<&'static RuntimeLogger> = OnceLock::new();
+
+/// Structured representation of a logged record
+#[derive(Clone, Debug)]
+pub struct LogEvent {
+    /// UNIX timestamp of the record, in milliseconds
+    pub timestamp: u64,
+    /// Record level
+    pub level: Level,
+    /// Record target
+    pub target: String,
+    /// Formatted record message
+    pub message: String,
+}
+
+/// Logger with runtime-adjustable per-target levels
+pub struct RuntimeLogger {
+    /// Wrapped logger the records get written to
+    inner: Box<dyn Log>,
+    /// Level of targets without an override
+    default_level: RwLock<LevelFilter>,
+    /// Per-target level overrides
+    targets: RwLock<BTreeMap<String, LevelFilter>>,
+    /// Flag indicating log events get published
+    events: AtomicBool,
+    /// Log event subscription channels
+    subscribers: Mutex<Vec<Sender<LogEvent>>>,
+}
+
+/// Initialize the global logger, wrapping the given one, which should
+/// let through all records, since filtering happens here.
+/// Returns the runtime logger handle, also available through [`runtime()`].
+pub fn init(
+    inner: Box<dyn Log>,
+    default_level: LevelFilter,
+) -> Result<&'static RuntimeLogger, SetLoggerError> {
+    let logger: &'static RuntimeLogger = Box::leak(Box::new(RuntimeLogger {
+        inner,
+        default_level: RwLock::new(default_level),
+        targets: RwLock::new(BTreeMap::new()),
+        events: AtomicBool::new(false),
+        subscribers: Mutex::new(vec![]),
+    }));
+
+    log::set_logger(logger)?;
+    logger.update_max_level();
+    let _ = LOGGER.set(logger);
+    Ok(logger)
+}
+
+/// Returns the global runtime logger, if it was initialized using [`init()`].
+pub fn runtime() -> Option<&'static RuntimeLogger> {
+    LOGGER.get().copied()
+}
+
+/// Returns `true` if `target` is `prefix` itself or a sub-target of it.
+fn target_matches(target: &str, prefix: &str) -> bool {
+    target == prefix || target.strip_prefix(prefix).is_some_and(|rest| rest.starts_with("::"))
+}
+
+/// Returns `true` if records of the given target may be published as
+/// log events.
+fn publishable(target: &str) -> bool {
+    !EVENTS_SKIPPED_TARGETS.iter().any(|skipped| target_matches(target, skipped))
+}
+
+impl RuntimeLogger {
+    /// Level of targets without an override
+    pub fn default_level(&self) -> LevelFilter {
+        *self.default_level.read().unwrap()
+    }
+
+    /// Set the level of targets without an override.
+    pub fn set_default_level(&self, level: LevelFilter) {
+        *self.default_level.write().unwrap() = level;
+        self.update_max_level();
+    }
+
+    /// Current per-target level overrides
+    pub fn target_levels(&self) -> BTreeMap<String, LevelFilter> {
+        self.targets.read().unwrap().clone()
+    }
+
+    /// Override the level of the given target and its sub-targets.
+    pub fn set_target_level(&self, target: &str, level: LevelFilter) {
+        self.targets.write().unwrap().insert(target.to_string(), level);
+        self.update_max_level();
+    }
+
+    /// Remove the level override of the given target.
+    /// Returns `false` if the target had no override.
+    pub fn reset_target_level(&self, target: &str) -> bool {
+        let removed = self.targets.write().unwrap().remove(target).is_some();
+        self.update_max_level();
+        removed
+    }
+
+    /// Effective level of the given target, using its most specific override.
+    pub fn level_of(&self, target: &str) -> LevelFilter {
+        let targets = self.targets.read().unwrap();
+        targets
+            .iter()
+            .filter(|(prefix, _)| target_matches(target, prefix))
+            .max_by_key(|(prefix, _)| prefix.len())
+            .map(|(_, level)| *level)
+            .unwrap_or_else(|| self.default_level())
+    }
+
+    /// Enable or disable publishing log events to subscribers.
+    pub fn switch_events(&self, enabled: bool) {
+        self.events.store(enabled, Ordering::SeqCst);
+    }
+
+    /// Returns `true` if log events get published.
+    pub fn events_enabled(&self) -> bool {
+        self.events.load(Ordering::Relaxed)
+    }
+
+    /// Subscribe to log events, published while switched on.
+    pub fn subscribe(&self) -> Receiver<LogEvent> {
+        let (tx, rx) = channel::bounded(EVENTS_CHANNEL_CAP);
+        self.subscribers.lock().unwrap().push(tx);
+        rx
+    }
+
+    /// Let the `log` macros skip records no target could be interested in.
+    fn update_max_level(&self) {
+        let max = self
+            .targets
+            .read()
+            .unwrap()
+            .values()
+            .copied()
+            .fold(self.default_level(), |a, b| a.max(b));
+        log::set_max_level(max);
+    }
+
+    /// Publish a record to all live subscribers, dropping closed ones.
+    fn publish(&self, record: &Record) {
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_millis() as u64)
+            .unwrap_or_default();
+        let event = LogEvent {
+            timestamp,
+            level: record.level(),
+            target: record.target().to_string(),
+            message: record.args().to_string(),
+        };
+
+        let mut subscribers = self.subscribers.lock().unwrap();
+        subscribers.retain(|tx| !tx.is_closed());
+        for tx in subscribers.iter() {
+            let _ = tx.try_send(event.clone());
+        }
+    }
+}
+
+impl Log for RuntimeLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= self.level_of(metadata.target())
+    }
+
+    fn log(&self, record: &Record) {
+        if !self.enabled(record.metadata()) {
+            return
+        }
+
+        self.inner.log(record);
+        if self.events_enabled() && publishable(record.target()) {
+            self.publish(record);
+        }
+    }
+
+    fn flush(&self) {
+        self.inner.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn target_level_overrides() {
+        assert!(target_matches("net::outbound_session", "net"));
+        assert!(target_matches("net", "net"));
+        assert!(!target_matches("network", "net"));
+
+        let logger = RuntimeLogger {
+            inner: Box::new(NopLogger),
+            default_level: RwLock::new(LevelFilter::Info),
+            targets: RwLock::new(BTreeMap::new()),
+            events: AtomicBool::new(false),
+            subscribers: Mutex::new(vec![]),
+        };
+
+        logger.set_target_level("net", LevelFilter::Warn);
+        logger.set_target_level("net::outbound_session", LevelFilter::Trace);
+        assert_eq!(logger.level_of("fud::fetch_chunks"), LevelFilter::Info);
+        assert_eq!(logger.level_of("net::session"), LevelFilter::Warn);
+        assert_eq!(logger.level_of("net::outbound_session::connect"), LevelFilter::Trace);
+
+        assert!(logger.reset_target_level("net::outbound_session"));
+        assert!(!logger.reset_target_level("net::outbound_session"));
+        assert_eq!(logger.level_of("net::outbound_session"), LevelFilter::Warn);
+    }
+
+    #[test]
+    fn event_delivery_targets_not_published() {
+        let logger = RuntimeLogger {
+            inner: Box::new(NopLogger),
+            default_level: RwLock::new(LevelFilter::Trace),
+            targets: RwLock::new(BTreeMap::new()),
+            events: AtomicBool::new(true),
+            subscribers: Mutex::new(vec![]),
+        };
+        let rx = logger.subscribe();
+
+        for target in ["rpc::server::accept()", "system::publisher", "rpc::server"] {
+            logger.log(
+                &Record::builder()
+                    .level(Level::Info)
+                    .target(target)
+                    .args(format_args!("delivered"))
+                    .build(),
+            );
+        }
+        assert!(rx.try_recv().is_err());
+
+        logger.log(
+            &Record::builder()
+                .level(Level::Info)
+                .target("rpc::serverless")
+                .args(format_args!("published"))
+                .build(),
+        );
+        let event = rx.try_recv().unwrap();
+        assert_eq!(event.target, "rpc::serverless");
+        assert_eq!(event.message, "published");
+        assert!(rx.try_recv().is_err());
+    }
+
+    struct NopLogger;
+
+    impl Log for NopLogger {
+        fn enabled(&self, _: &Metadata) -> bool {
+            true
+        }
+        fn log(&self, _: &Record) {}
+        fn flush(&self) {}
+    }
+}
@@ -35,7 +35,18 @@ pub use publisher::{Publisher, PublisherPtr, Subscription};
 
 /// Async timeout implementations
 pub mod timeout;
-pub use timeout::io_timeout;
+pub use timeout::{io_timeout, timeout, TimeoutError};
+
+/// Async counting semaphore for bounding concurrent operations
+pub mod semaphore;
+pub use semaphore::{Semaphore, SemaphoreGuard, SemaphorePtr};
+
+/// Correlation ID propagation for request tracing
+pub mod trace;
+pub use trace::{traced, Traced};
+
+/// Logger with runtime-adjustable per-target levels
+pub mod logger;
 
 pub type ExecutorPtr = Arc<Executor<'static>>;
 
@@ -50,6 +50,11 @@ impl<T: Clone> Subscription<T> {
         }
     }
 
+    /// Receive an already queued message, without waiting for one.
+    pub fn try_receive(&self) -> Option<T> {
+        self.recv_queue.try_recv().ok()
+    }
+
     /// Must be called manually since async Drop is not possible in Rust
     pub async fn unsubscribe(&self) {
         self.parent.clone().unsubscribe(self.id).await
@@ -95,6 +100,11 @@ impl<T: Clone> Publisher<T> {
         self.subs.lock().await.remove(&sub_id);
     }
 
+    /// Number of currently active subscriptions.
+    pub async fn subscriptions(&self) -> usize {
+        self.subs.lock().await.len()
+    }
+
     /// Publish a message to all listening subscriptions.
     pub async fn notify(&self, message_result: T) {
         self.notify_with_exclude(message_result, &[]).await
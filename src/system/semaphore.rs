@@ -0,0 +1,146 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2025 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use std::{future::Future, sync::Arc, time::Duration};
+
+use smol::lock::{Semaphore as InnerSemaphore, SemaphoreGuardArc};
+
+use super::timeout::{timeout, TimeoutError};
+
+pub type SemaphorePtr = Arc<Semaphore>;
+
+/// RAII guard holding a [`Semaphore`] permit. The permit is released
+/// when the guard is dropped, so it can be moved into spawned tasks.
+pub type SemaphoreGuard = SemaphoreGuardArc;
+
+/// Async counting semaphore used to bound the amount of concurrent
+/// heavy operations, like proof generation or chunk IO.
+/// ```rust
+///    let semaphore = Semaphore::new(4);
+///
+///    for job in jobs {
+///        // Waits here until a permit is available
+///        let permit = semaphore.acquire().await;
+///        executor
+///            .spawn(async move {
+///                job.await;
+///                // Permit is released here
+///                drop(permit);
+///            })
+///            .detach();
+///    }
+/// ```
+pub struct Semaphore {
+    /// The underlying semaphore
+    inner: Arc<InnerSemaphore>,
+    /// Total number of permits
+    permits: usize,
+}
+
+impl Semaphore {
+    /// Create a new semaphore with the given number of permits.
+    pub fn new(permits: usize) -> SemaphorePtr {
+        Arc::new(Self { inner: Arc::new(InnerSemaphore::new(permits)), permits })
+    }
+
+    /// Total number of permits this semaphore was created with.
+    pub fn permits(&self) -> usize {
+        self.permits
+    }
+
+    /// Wait until a permit is available and acquire it.
+    pub async fn acquire(&self) -> SemaphoreGuard {
+        self.inner.acquire_arc().await
+    }
+
+    /// Attempt to acquire a permit without waiting.
+    pub fn try_acquire(&self) -> Option<SemaphoreGuard> {
+        self.inner.try_acquire_arc()
+    }
+
+    /// Wait until a permit is available and acquire it, giving up
+    /// after the given duration.
+    pub async fn acquire_timeout(&self, dur: Duration) -> Result<SemaphoreGuard, TimeoutError> {
+        timeout(dur, self.acquire()).await
+    }
+
+    /// Run the given future while holding a permit.
+    pub async fn run<F: Future>(&self, future: F) -> F::Output {
+        let _permit = self.acquire().await;
+        future.await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use smol::Executor;
+    use std::sync::atomic::{AtomicUsize, Ordering::SeqCst};
+
+    use crate::system::msleep;
+
+    #[test]
+    fn semaphore_bounds_concurrency() {
+        let executor = Arc::new(Executor::new());
+        let executor_ = executor.clone();
+        smol::block_on(executor.run(async move {
+            let semaphore = Semaphore::new(2);
+            let running = Arc::new(AtomicUsize::new(0));
+            let max_running = Arc::new(AtomicUsize::new(0));
+
+            let mut tasks = vec![];
+            for _ in 0..8 {
+                let semaphore_ = semaphore.clone();
+                let running_ = running.clone();
+                let max_running_ = max_running.clone();
+                tasks.push(executor_.spawn(async move {
+                    semaphore_
+                        .run(async {
+                            let n = running_.fetch_add(1, SeqCst) + 1;
+                            max_running_.fetch_max(n, SeqCst);
+                            msleep(5).await;
+                            running_.fetch_sub(1, SeqCst);
+                        })
+                        .await
+                }));
+            }
+
+            for task in tasks {
+                task.await;
+            }
+
+            assert_eq!(max_running.load(SeqCst), 2);
+        }))
+    }
+
+    #[test]
+    fn semaphore_try_acquire_and_timeout() {
+        smol::block_on(async {
+            let semaphore = Semaphore::new(1);
+            assert_eq!(semaphore.permits(), 1);
+
+            let permit = semaphore.try_acquire().unwrap();
+            assert!(semaphore.try_acquire().is_none());
+            assert!(semaphore.acquire_timeout(Duration::from_millis(10)).await.is_err());
+
+            // Releasing the permit makes it available again
+            drop(permit);
+            assert!(semaphore.acquire_timeout(Duration::from_millis(10)).await.is_ok());
+        })
+    }
+}
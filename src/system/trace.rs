@@ -0,0 +1,134 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2025 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Correlation ID propagation for tracing requests across async calls.
+//!
+//! A [`Traced`] future carries a correlation ID and exposes it through
+//! [`current()`] for as long as it is being polled, so any downstream
+//! code awaited by it (validator calls, p2p broadcasts, outgoing
+//! JSON-RPC requests) can tag its log lines with the ID of the request
+//! that triggered it. Tasks spawned onto an executor do not inherit
+//! the ID and must be wrapped explicitly.
+
+use std::{
+    cell::RefCell,
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use pin_project_lite::pin_project;
+use rand::{rngs::OsRng, Rng};
+
+thread_local! {
+    static CURRENT: RefCell<Option<String>> = const { RefCell::new(None) };
+}
+
+/// Generate a new random correlation ID.
+pub fn new_correlation_id() -> String {
+    format!("{:016x}", OsRng.gen::<u64>())
+}
+
+/// Returns the correlation ID of the [`Traced`] future currently being
+/// polled on this thread, if any.
+pub fn current() -> Option<String> {
+    CURRENT.with(|c| c.borrow().clone())
+}
+
+/// Returns a `"[<id>] "` prefix for log lines emitted inside a traced
+/// future, or an empty string outside of one.
+pub fn log_prefix() -> String {
+    match current() {
+        Some(id) => format!("[{}] ", id),
+        None => String::new(),
+    }
+}
+
+/// Wrap a future so that [`current()`] returns `id` while it is polled.
+pub fn traced<F: Future>(id: String, future: F) -> Traced<F> {
+    Traced { id, future }
+}
+
+pin_project! {
+    /// A future exposing a correlation ID to everything it polls.
+    pub struct Traced<F> {
+        id: String,
+        #[pin]
+        future: F,
+    }
+}
+
+impl<F> Traced<F> {
+    /// The correlation ID carried by this future
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+}
+
+impl<F: Future> Future for Traced<F> {
+    type Output = F::Output;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.project();
+
+        // Swap our ID in, and restore the previous one afterwards so
+        // nested traced futures unwind correctly.
+        let previous = CURRENT.with(|c| c.replace(Some(this.id.clone())));
+        let result = this.future.poll(cx);
+        CURRENT.with(|c| *c.borrow_mut() = previous);
+
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::system::msleep;
+
+    #[test]
+    fn traced_future_scoping() {
+        smol::block_on(async {
+            assert!(current().is_none());
+
+            let id = new_correlation_id();
+            assert_eq!(id.len(), 16);
+
+            let id_ = id.clone();
+            traced(id.clone(), async move {
+                assert_eq!(current(), Some(id_.clone()));
+
+                // The ID survives across yield points
+                msleep(5).await;
+                assert_eq!(log_prefix(), format!("[{}] ", id_));
+
+                // Nested traced futures shadow the outer ID and restore it
+                traced("inner".to_string(), async {
+                    assert_eq!(current(), Some("inner".to_string()));
+                })
+                .await;
+                assert_eq!(current(), Some(id_));
+            })
+            .await;
+
+            assert!(current().is_none());
+            assert!(log_prefix().is_empty());
+        })
+    }
+}
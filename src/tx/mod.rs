@@ -16,11 +16,14 @@
  * along with this program.  If not, see <https://www.gnu.org/licenses/>.
  */
 
-use std::collections::HashMap;
+use std::{
+    collections::HashMap,
+    io::{Error as IoError, ErrorKind, Read, Write},
+};
 
 use darkfi_sdk::{
     crypto::{
-        schnorr::{SchnorrPublic, SchnorrSecret, Signature},
+        schnorr::{verify_batch, SchnorrPublic, SchnorrSecret, Signature},
         PublicKey, SecretKey,
     },
     dark_tree::{dark_forest_leaf_vec_integrity_check, DarkForest, DarkLeaf, DarkTree},
@@ -31,9 +34,9 @@ use darkfi_sdk::{
 };
 
 #[cfg(feature = "async-serial")]
-use darkfi_serial::async_trait;
+use darkfi_serial::{async_trait, AsyncDecodable, AsyncEncodable, AsyncRead, AsyncWrite};
 
-use darkfi_serial::{Encodable, SerialDecodable, SerialEncodable};
+use darkfi_serial::{serialize_hashed, Decodable, Encodable, VarInt};
 use log::{debug, error};
 
 use crate::{
@@ -54,7 +57,10 @@ macro_rules! zip {
 /// along with corresponding ZK proofs and Schnorr signatures.
 ///
 /// `DarkLeaf` is used to map relations between contract calls in the transaction.
-#[derive(Clone, Default, Eq, PartialEq, SerialEncodable, SerialDecodable)]
+///
+/// The optional validity heights bound the block heights the transaction
+/// can be included at, and are covered by the signatures.
+#[derive(Clone, Default, Eq, PartialEq)]
 pub struct Transaction {
     /// Calls executed in this transaction
     pub calls: Vec<DarkLeaf<ContractCall>>,
@@ -62,9 +68,23 @@ pub struct Transaction {
     pub proofs: Vec<Vec<Proof>>,
     /// Attached Schnorr signatures
     pub signatures: Vec<Vec<Signature>>,
+    /// Block height before which the transaction is not valid
+    pub not_before_height: Option<u32>,
+    /// Block height after which the transaction is not valid
+    pub not_after_height: Option<u32>,
 }
 // ANCHOR_END: transaction
 
+/// Marker prefixing versioned transaction encodings. It takes the place
+/// of the calls vector length in the legacy encoding, which can never
+/// be this big, so both encodings can be told apart while decoding.
+const TX_VERSION_MARKER: u64 = u64::MAX;
+
+/// Current versioned transaction encoding.
+/// Transactions without validity heights keep using the legacy
+/// encoding, so their hashes and signatures remain unchanged.
+pub const TX_VERSION: u8 = 1;
+
 impl Transaction {
     /// Verify ZK proofs for the entire transaction.
     pub async fn verify_zkps(
@@ -120,13 +140,45 @@ impl Transaction {
         Ok(())
     }
 
-    /// Verify Schnorr signatures for the entire transaction.
-    pub fn verify_sigs(&self, pub_table: Vec<Vec<PublicKey>>) -> Result<()> {
-        // Hash the transaction without the signatures
+    /// Hash the transaction without the signatures, producing the data
+    /// the Schnorr signatures are created over.
+    fn sig_data_hash(&self) -> Result<blake3::Hash> {
         let mut hasher = blake3::Hasher::new();
         self.calls.encode(&mut hasher)?;
         self.proofs.encode(&mut hasher)?;
-        let data_hash = hasher.finalize();
+        if self.is_versioned() {
+            self.not_before_height.encode(&mut hasher)?;
+            self.not_after_height.encode(&mut hasher)?;
+        }
+        Ok(hasher.finalize())
+    }
+
+    /// Returns true if the transaction has validity heights set,
+    /// requiring the versioned encoding.
+    pub fn is_versioned(&self) -> bool {
+        self.not_before_height.is_some() || self.not_after_height.is_some()
+    }
+
+    /// Verify the transaction can be included in a block at provided height.
+    pub fn verify_validity_heights(&self, height: u32) -> Result<()> {
+        if let Some(not_before) = self.not_before_height {
+            if height < not_before {
+                return Err(TxVerifyFailed::NotYetValid(not_before).into())
+            }
+        }
+
+        if let Some(not_after) = self.not_after_height {
+            if height > not_after {
+                return Err(TxVerifyFailed::Expired(not_after).into())
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Verify Schnorr signatures for the entire transaction.
+    pub fn verify_sigs(&self, pub_table: Vec<Vec<PublicKey>>) -> Result<()> {
+        let data_hash = self.sig_data_hash()?;
 
         debug!(
             target: "tx::verify_sigs",
@@ -135,6 +187,21 @@ impl Transaction {
 
         assert_eq!(self.signatures.len(), pub_table.len());
 
+        // Verify all the signatures at once. Only if the batch fails do we
+        // go through them one by one to find the offending call.
+        let mut batch = vec![];
+        for (sigs, pubkeys) in self.signatures.iter().zip(pub_table.iter()) {
+            assert_eq!(sigs.len(), pubkeys.len());
+            for (pubkey, signature) in pubkeys.iter().zip(sigs) {
+                batch.push((pubkey, &data_hash.as_bytes()[..], signature));
+            }
+        }
+
+        if verify_batch(&batch) {
+            debug!(target: "tx::verify_sigs", "[TX] tx::verify_sigs batch of {} passed", batch.len());
+            return Ok(())
+        }
+
         for (i, (sigs, pubkeys)) in self.signatures.iter().zip(pub_table.iter()).enumerate() {
             assert_eq!(sigs.len(), pubkeys.len());
 
@@ -160,11 +227,7 @@ impl Transaction {
 
     /// Create Schnorr signatures for the entire transaction.
     pub fn create_sigs(&self, secret_keys: &[SecretKey]) -> Result<Vec<Signature>> {
-        // Hash the transaction without the signatures
-        let mut hasher = blake3::Hasher::new();
-        self.calls.encode(&mut hasher)?;
-        self.proofs.encode(&mut hasher)?;
-        let data_hash = hasher.finalize();
+        let data_hash = self.sig_data_hash()?;
 
         debug!(
             target: "tx::create_sigs",
@@ -194,6 +257,12 @@ impl Transaction {
         TransactionHash(hasher.finalize().into())
     }
 
+    /// Serialize the transaction, computing its hash in the same pass
+    pub fn serialize_with_hash(&self) -> (Vec<u8>, TransactionHash) {
+        let (encoded, hash) = serialize_hashed::<_, blake3::Hasher>(self);
+        (encoded, TransactionHash(hash.into()))
+    }
+
     /// Returns true if transaction is a PoW reward one.
     pub fn is_pow_reward(&self) -> bool {
         // PoW rewards must be single contract calls
@@ -210,6 +279,131 @@ impl Transaction {
     }
 }
 
+impl Encodable for Transaction {
+    fn encode<S: Write>(&self, s: &mut S) -> std::result::Result<usize, IoError> {
+        let mut len = 0;
+        if self.is_versioned() {
+            len += VarInt(TX_VERSION_MARKER).encode(s)?;
+            len += TX_VERSION.encode(s)?;
+        }
+        len += self.calls.encode(s)?;
+        len += self.proofs.encode(s)?;
+        len += self.signatures.encode(s)?;
+        if self.is_versioned() {
+            len += self.not_before_height.encode(s)?;
+            len += self.not_after_height.encode(s)?;
+        }
+        Ok(len)
+    }
+}
+
+impl Decodable for Transaction {
+    fn decode<D: Read>(d: &mut D) -> std::result::Result<Self, IoError> {
+        let prefix = VarInt::decode(d)?.0;
+        if prefix != TX_VERSION_MARKER {
+            // Legacy encoding, where the prefix is the calls vector length
+            let mut calls = Vec::new();
+            calls.try_reserve(prefix as usize).map_err(|_| ErrorKind::InvalidData)?;
+            for _ in 0..prefix {
+                calls.push(Decodable::decode(d)?);
+            }
+            let proofs = Decodable::decode(d)?;
+            let signatures = Decodable::decode(d)?;
+            return Ok(Self { calls, proofs, signatures, ..Default::default() })
+        }
+
+        let version: u8 = Decodable::decode(d)?;
+        if version != TX_VERSION {
+            return Err(IoError::new(ErrorKind::InvalidData, "Unknown transaction version"))
+        }
+
+        let tx = Self {
+            calls: Decodable::decode(d)?,
+            proofs: Decodable::decode(d)?,
+            signatures: Decodable::decode(d)?,
+            not_before_height: Decodable::decode(d)?,
+            not_after_height: Decodable::decode(d)?,
+        };
+
+        // Keep the encoding canonical
+        if !tx.is_versioned() {
+            return Err(IoError::new(
+                ErrorKind::InvalidData,
+                "Versioned transaction without heights",
+            ))
+        }
+
+        Ok(tx)
+    }
+}
+
+#[cfg(feature = "async-serial")]
+#[async_trait]
+impl AsyncEncodable for Transaction {
+    async fn encode_async<S: AsyncWrite + Unpin + Send>(
+        &self,
+        s: &mut S,
+    ) -> std::result::Result<usize, IoError> {
+        let mut len = 0;
+        if self.is_versioned() {
+            len += VarInt(TX_VERSION_MARKER).encode_async(s).await?;
+            len += TX_VERSION.encode_async(s).await?;
+        }
+        len += self.calls.encode_async(s).await?;
+        len += self.proofs.encode_async(s).await?;
+        len += self.signatures.encode_async(s).await?;
+        if self.is_versioned() {
+            len += self.not_before_height.encode_async(s).await?;
+            len += self.not_after_height.encode_async(s).await?;
+        }
+        Ok(len)
+    }
+}
+
+#[cfg(feature = "async-serial")]
+#[async_trait]
+impl AsyncDecodable for Transaction {
+    async fn decode_async<D: AsyncRead + Unpin + Send>(
+        d: &mut D,
+    ) -> std::result::Result<Self, IoError> {
+        let prefix = VarInt::decode_async(d).await?.0;
+        if prefix != TX_VERSION_MARKER {
+            // Legacy encoding, where the prefix is the calls vector length
+            let mut calls = Vec::new();
+            calls.try_reserve(prefix as usize).map_err(|_| ErrorKind::InvalidData)?;
+            for _ in 0..prefix {
+                calls.push(AsyncDecodable::decode_async(d).await?);
+            }
+            let proofs = AsyncDecodable::decode_async(d).await?;
+            let signatures = AsyncDecodable::decode_async(d).await?;
+            return Ok(Self { calls, proofs, signatures, ..Default::default() })
+        }
+
+        let version: u8 = AsyncDecodable::decode_async(d).await?;
+        if version != TX_VERSION {
+            return Err(IoError::new(ErrorKind::InvalidData, "Unknown transaction version"))
+        }
+
+        let tx = Self {
+            calls: AsyncDecodable::decode_async(d).await?,
+            proofs: AsyncDecodable::decode_async(d).await?,
+            signatures: AsyncDecodable::decode_async(d).await?,
+            not_before_height: AsyncDecodable::decode_async(d).await?,
+            not_after_height: AsyncDecodable::decode_async(d).await?,
+        };
+
+        // Keep the encoding canonical
+        if !tx.is_versioned() {
+            return Err(IoError::new(
+                ErrorKind::InvalidData,
+                "Versioned transaction without heights",
+            ))
+        }
+
+        Ok(tx)
+    }
+}
+
 // Avoid showing the proofs and sigs in the debug output since often they are very long.
 impl std::fmt::Debug for Transaction {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
@@ -225,6 +419,12 @@ impl std::fmt::Debug for Transaction {
             writeln!(f, "    children: {:?}", call.children_indexes)?;
             writeln!(f, "  }},")?;
         }
+        if let Some(height) = self.not_before_height {
+            writeln!(f, "  not_before_height: {}", height)?;
+        }
+        if let Some(height) = self.not_after_height {
+            writeln!(f, "  not_after_height: {}", height)?;
+        }
         writeln!(f, "}}")
     }
 }
@@ -305,6 +505,69 @@ impl TransactionBuilder {
             proofs.push(leaf.data.proofs);
         }
 
-        Ok(Transaction { calls, proofs, signatures: vec![] })
+        Ok(Transaction { calls, proofs, signatures: vec![], ..Default::default() })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use darkfi_sdk::crypto::MONEY_CONTRACT_ID;
+    use darkfi_serial::{deserialize, serialize};
+
+    use super::*;
+
+    fn tx() -> Transaction {
+        let call = ContractCall { contract_id: *MONEY_CONTRACT_ID, data: vec![0, 1, 2] };
+        let secret = SecretKey::random(&mut rand::rngs::OsRng);
+        let mut tx = Transaction {
+            calls: vec![DarkLeaf { data: call, parent_index: None, children_indexes: vec![] }],
+            proofs: vec![vec![]],
+            ..Default::default()
+        };
+        tx.signatures = vec![tx.create_sigs(&[secret]).unwrap()];
+        tx
+    }
+
+    #[test]
+    fn tx_encoding_versions() {
+        // Transactions without validity heights keep the legacy encoding
+        let legacy = tx();
+        let bytes = serialize(&legacy);
+        let mut expected = serialize(&legacy.calls);
+        expected.extend(serialize(&legacy.proofs));
+        expected.extend(serialize(&legacy.signatures));
+        assert_eq!(bytes, expected);
+        assert_eq!(deserialize::<Transaction>(&bytes).unwrap(), legacy);
+
+        // Validity heights switch to the versioned encoding
+        let mut versioned = tx();
+        versioned.not_after_height = Some(100);
+        let bytes = serialize(&versioned);
+        assert_eq!(deserialize::<Transaction>(&bytes).unwrap(), versioned);
+        assert_ne!(
+            versioned.hash(),
+            Transaction { not_after_height: None, ..versioned.clone() }.hash()
+        );
+
+        // Versioned encodings without heights are not canonical
+        let mut bytes = serialize(&VarInt(TX_VERSION_MARKER));
+        bytes.push(TX_VERSION);
+        bytes.extend(&serialize(&legacy)[..]);
+        bytes.extend(serialize(&None::<u32>));
+        bytes.extend(serialize(&None::<u32>));
+        assert!(deserialize::<Transaction>(&bytes).is_err());
+    }
+
+    #[test]
+    fn tx_validity_heights() {
+        let mut tx = tx();
+        assert!(tx.verify_validity_heights(0).is_ok());
+
+        tx.not_before_height = Some(10);
+        tx.not_after_height = Some(20);
+        assert!(tx.verify_validity_heights(9).is_err());
+        assert!(tx.verify_validity_heights(10).is_ok());
+        assert!(tx.verify_validity_heights(20).is_ok());
+        assert!(tx.verify_validity_heights(21).is_err());
     }
 }
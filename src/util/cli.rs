@@ -177,9 +177,10 @@ macro_rules! async_daemonize {
             let log_level = darkfi::util::cli::get_log_level(args.verbose);
             let log_config = darkfi::util::cli::get_log_config(args.verbose);
 
-            // Setup terminal logger
+            // Setup terminal logger. Levels get filtered by the runtime
+            // logger wrapping it, so they can be adjusted while running.
             let term_logger = simplelog::TermLogger::new(
-                log_level,
+                simplelog::LevelFilter::Trace,
                 log_config.clone(),
                 simplelog::TerminalMode::Mixed,
                 simplelog::ColorChoice::Auto,
@@ -187,17 +188,20 @@ macro_rules! async_daemonize {
 
             // If a log file has been configured, also create a write logger.
             // Otherwise, output to terminal logger only.
-            match args.log {
+            let loggers: Vec<Box<dyn simplelog::SharedLogger>> = match args.log {
                 Some(ref log_path) => {
                     let log_path = darkfi::util::path::expand_path(log_path)?;
                     let log_file = std::fs::File::create(log_path)?;
-                    let write_logger = simplelog::WriteLogger::new(log_level, log_config, log_file);
-                    simplelog::CombinedLogger::init(vec![term_logger, write_logger])?;
+                    let write_logger = simplelog::WriteLogger::new(
+                        simplelog::LevelFilter::Trace,
+                        log_config,
+                        log_file,
+                    );
+                    vec![term_logger, write_logger]
                 }
-                None => {
-                    simplelog::CombinedLogger::init(vec![term_logger])?;
-                }
-            }
+                None => vec![term_logger],
+            };
+            darkfi::system::logger::init(simplelog::CombinedLogger::new(loggers), log_level)?;
 
             // https://docs.rs/smol/latest/smol/struct.Executor.html#examples
             let n_threads = std::thread::available_parallelism().unwrap().get();
@@ -349,3 +353,52 @@ impl ProgressInc {
         eprint!("\r\x1b[2K\x1b[?25h");
     }
 }
+
+/// Progress bar over a known amount of work. Every update is drawn
+/// on its own line in stderr, so it can be interleaved with other output.
+pub struct ProgressBar {
+    total: u64,
+    label: String,
+    position: Mutex<u64>,
+    timer: Instant,
+}
+
+impl ProgressBar {
+    /// Width of the drawn bar, in characters
+    const WIDTH: u64 = 40;
+
+    pub fn new(total: u64, label: &str) -> Self {
+        Self { total, label: label.to_string(), position: Mutex::new(0), timer: Instant::now() }
+    }
+
+    pub fn inc(&self, n: u64) {
+        let mut position = self.position.lock().unwrap();
+        *position = (*position + n).min(self.total);
+        self.draw(*position);
+    }
+
+    pub fn position(&self) -> u64 {
+        *self.position.lock().unwrap()
+    }
+
+    fn draw(&self, position: u64) {
+        let filled =
+            if self.total == 0 { Self::WIDTH } else { position * Self::WIDTH / self.total };
+        let bar = format!(
+            "{}{}",
+            "#".repeat(filled as usize),
+            "-".repeat((Self::WIDTH - filled) as usize)
+        );
+
+        let elapsed = self.timer.elapsed().as_secs();
+        let rate = position as f64 / self.timer.elapsed().as_secs_f64().max(1.0);
+        eprintln!(
+            "[{:02}:{:02}:{:02}] [{bar}] {position}/{} {} ({rate:.1}/s)",
+            elapsed / 3600,
+            (elapsed / 60) % 60,
+            elapsed % 60,
+            self.total,
+            self.label,
+        );
+    }
+}
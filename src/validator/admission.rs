@@ -0,0 +1,271 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2025 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use std::{
+    collections::{HashMap, HashSet},
+    fmt,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use darkfi_sdk::crypto::ContractId;
+use darkfi_serial::serialize;
+
+use crate::{error::TxVerifyFailed, tx::Transaction, Result};
+
+/// Maximum number of sources tracked by [`RateLimit`] before expired
+/// windows get pruned.
+const RATE_LIMIT_MAX_SOURCES: usize = 4096;
+
+/// Origin of a transaction submitted for mempool admission
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum TxSource {
+    /// Submitted locally, i.e. through the node's JSON-RPC
+    Local,
+    /// Received from a P2P network peer, identified by its address
+    Peer(String),
+}
+
+impl fmt::Display for TxSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Local => write!(f, "local"),
+            Self::Peer(addr) => write!(f, "{addr}"),
+        }
+    }
+}
+
+/// A mempool admission policy, restricting which transactions a node
+/// accepts into its mempool, on top of their state transition validity.
+pub trait AdmissionPolicy: Send + Sync {
+    /// Policy name, used in rejection messages
+    fn name(&self) -> &'static str;
+
+    /// Check performed before the transaction's state transition gets
+    /// verified, so cheap checks can reject it early.
+    fn check_tx(&self, _tx: &Transaction, _source: &TxSource) -> Result<()> {
+        Ok(())
+    }
+
+    /// Check performed after the transaction's state transition got
+    /// verified, with its total gas used and fee paid.
+    fn check_verified(&self, _tx: &Transaction, _gas_used: u64, _fee_paid: u64) -> Result<()> {
+        Ok(())
+    }
+}
+
+/// Construct the admission rejection error of given policy
+fn rejected(policy: &dyn AdmissionPolicy, reason: String) -> crate::Error {
+    TxVerifyFailed::AdmissionRejected(format!("{}: {reason}", policy.name())).into()
+}
+
+/// Ordered set of [`AdmissionPolicy`] a transaction must pass to enter
+/// the mempool. An empty pipeline admits everything.
+#[derive(Clone, Default)]
+pub struct AdmissionPipeline {
+    policies: Vec<Arc<dyn AdmissionPolicy>>,
+}
+
+impl AdmissionPipeline {
+    pub fn new(policies: Vec<Arc<dyn AdmissionPolicy>>) -> Self {
+        Self { policies }
+    }
+
+    /// Run all the policies pre-verification checks, in order
+    pub fn check_tx(&self, tx: &Transaction, source: &TxSource) -> Result<()> {
+        for policy in &self.policies {
+            policy.check_tx(tx, source)?;
+        }
+
+        Ok(())
+    }
+
+    /// Run all the policies post-verification checks, in order
+    pub fn check_verified(&self, tx: &Transaction, gas_used: u64, fee_paid: u64) -> Result<()> {
+        for policy in &self.policies {
+            policy.check_verified(tx, gas_used, fee_paid)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Reject transactions exceeding a serialized size or contract calls count
+pub struct SizeLimit {
+    /// Maximum serialized transaction size, in bytes
+    pub max_bytes: usize,
+    /// Maximum number of contract calls
+    pub max_calls: Option<usize>,
+}
+
+impl AdmissionPolicy for SizeLimit {
+    fn name(&self) -> &'static str {
+        "size_limit"
+    }
+
+    fn check_tx(&self, tx: &Transaction, _source: &TxSource) -> Result<()> {
+        let size = serialize(tx).len();
+        if size > self.max_bytes {
+            return Err(rejected(self, format!("size {size} exceeds {} bytes", self.max_bytes)))
+        }
+
+        if let Some(max_calls) = self.max_calls {
+            if tx.calls.len() > max_calls {
+                return Err(rejected(self, format!("{} calls exceed {max_calls}", tx.calls.len())))
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Reject transactions paying less than a minimum fee
+pub struct FeeFloor {
+    /// Minimum fee a transaction must pay
+    pub min_fee: u64,
+}
+
+impl AdmissionPolicy for FeeFloor {
+    fn name(&self) -> &'static str {
+        "fee_floor"
+    }
+
+    fn check_verified(&self, _tx: &Transaction, _gas_used: u64, fee_paid: u64) -> Result<()> {
+        if fee_paid < self.min_fee {
+            return Err(rejected(self, format!("paid fee {fee_paid} below {}", self.min_fee)))
+        }
+
+        Ok(())
+    }
+}
+
+/// Reject transactions calling any contract outside an allowlist.
+/// Note that the Money contract must be allowed for transactions
+/// to be able to pay their fee.
+pub struct ContractAllowlist {
+    contracts: HashSet<[u8; 32]>,
+}
+
+impl ContractAllowlist {
+    pub fn new(contracts: &[ContractId]) -> Self {
+        Self { contracts: contracts.iter().map(|c| c.to_bytes()).collect() }
+    }
+}
+
+impl AdmissionPolicy for ContractAllowlist {
+    fn name(&self) -> &'static str {
+        "contract_allowlist"
+    }
+
+    fn check_tx(&self, tx: &Transaction, _source: &TxSource) -> Result<()> {
+        for call in &tx.calls {
+            if !self.contracts.contains(&call.data.contract_id.to_bytes()) {
+                return Err(rejected(
+                    self,
+                    format!("contract {} not allowed", call.data.contract_id),
+                ))
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Limit the number of transactions each peer can submit within a time
+/// window. Locally submitted transactions are not limited.
+pub struct RateLimit {
+    /// Maximum transactions per source within the window
+    pub max_txs: u32,
+    /// Window duration
+    pub window: Duration,
+    /// Each source's current window start along with its transactions count
+    sources: Mutex<HashMap<String, (Instant, u32)>>,
+}
+
+impl RateLimit {
+    pub fn new(max_txs: u32, window: Duration) -> Self {
+        Self { max_txs, window, sources: Mutex::new(HashMap::new()) }
+    }
+}
+
+impl AdmissionPolicy for RateLimit {
+    fn name(&self) -> &'static str {
+        "rate_limit"
+    }
+
+    fn check_tx(&self, _tx: &Transaction, source: &TxSource) -> Result<()> {
+        let TxSource::Peer(addr) = source else { return Ok(()) };
+
+        let now = Instant::now();
+        let mut sources = self.sources.lock().unwrap();
+        if sources.len() >= RATE_LIMIT_MAX_SOURCES && !sources.contains_key(addr) {
+            sources.retain(|_, (start, _)| now.duration_since(*start) < self.window);
+        }
+
+        let (start, count) = sources.entry(addr.clone()).or_insert((now, 0));
+        if now.duration_since(*start) >= self.window {
+            *start = now;
+            *count = 0;
+        }
+
+        if *count >= self.max_txs {
+            return Err(rejected(self, format!("{source} exceeded {} txs", self.max_txs)))
+        }
+        *count += 1;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn admission_pipeline() {
+        let tx = Transaction::default();
+        let peer = TxSource::Peer("127.0.0.1".to_string());
+
+        // An empty pipeline admits everything
+        let pipeline = AdmissionPipeline::default();
+        assert!(pipeline.check_tx(&tx, &peer).is_ok());
+        assert!(pipeline.check_verified(&tx, 0, 0).is_ok());
+
+        let pipeline = AdmissionPipeline::new(vec![
+            Arc::new(SizeLimit { max_bytes: serialize(&tx).len(), max_calls: Some(0) }),
+            Arc::new(RateLimit::new(2, Duration::from_secs(60))),
+            Arc::new(FeeFloor { min_fee: 100 }),
+        ]);
+
+        // Peers get rate limited, local submissions don't
+        assert!(pipeline.check_tx(&tx, &peer).is_ok());
+        assert!(pipeline.check_tx(&tx, &peer).is_ok());
+        assert!(pipeline.check_tx(&tx, &peer).is_err());
+        assert!(pipeline.check_tx(&tx, &TxSource::Peer("127.0.0.2".to_string())).is_ok());
+        for _ in 0..3 {
+            assert!(pipeline.check_tx(&tx, &TxSource::Local).is_ok());
+        }
+
+        assert!(pipeline.check_verified(&tx, 10, 99).is_err());
+        assert!(pipeline.check_verified(&tx, 10, 100).is_ok());
+
+        // Size limits
+        let policy = SizeLimit { max_bytes: serialize(&tx).len() - 1, max_calls: None };
+        assert!(policy.check_tx(&tx, &TxSource::Local).is_err());
+    }
+}
@@ -32,9 +32,11 @@ use crate::{
     },
     tx::Transaction,
     validator::{
+        parallel::TxScheduler,
         pow::PoWModule,
         utils::{best_fork_index, block_rank, find_extended_fork_index},
         verification::{verify_proposal, verify_transaction},
+        versionbits::VersionBits,
     },
     zk::VerifyingKey,
     Error, Result,
@@ -62,6 +64,13 @@ pub struct Consensus {
     pub module: RwLock<PoWModule>,
     /// Lock to restrict when proposals appends can happen
     pub append_lock: RwLock<()>,
+    /// Soft-fork deployments tracker
+    pub versionbits: VersionBits,
+    /// Block transactions parallel verification scheduler
+    pub scheduler: TxScheduler,
+    /// Manually invalidated blocks, mapped to the blocks dropped along
+    /// with them, so they can be reconsidered later
+    pub invalidated: RwLock<HashMap<HeaderHash, Vec<BlockInfo>>>,
 }
 
 impl Consensus {
@@ -80,7 +89,18 @@ impl Consensus {
             None,
         )?);
         let append_lock = RwLock::new(());
-        Ok(Self { blockchain, confirmation_threshold, forks, module, append_lock })
+        let versionbits = VersionBits::default();
+        let scheduler = TxScheduler::new();
+        Ok(Self {
+            blockchain,
+            confirmation_threshold,
+            forks,
+            module,
+            append_lock,
+            versionbits,
+            scheduler,
+            invalidated: RwLock::new(HashMap::new()),
+        })
     }
 
     /// Generate a new empty fork.
@@ -107,6 +127,12 @@ impl Consensus {
     pub async fn append_proposal(&self, proposal: &Proposal, verify_fees: bool) -> Result<()> {
         debug!(target: "validator::consensus::append_proposal", "Appending proposal {}", proposal.hash);
 
+        // Check if proposal has been manually invalidated
+        if self.invalidated.read().await.contains_key(&proposal.hash) {
+            debug!(target: "validator::consensus::append_proposal", "Proposal {} is invalidated", proposal.hash);
+            return Err(Error::BlockIsInvalid(proposal.hash.to_string()))
+        }
+
         // Check if proposal already exists
         let lock = self.forks.read().await;
         for fork in lock.iter() {
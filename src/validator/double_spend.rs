@@ -0,0 +1,381 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2025 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Double-spend detection and peer accountability.
+//!
+//! Every valid transaction the node sees, either through its mempool or
+//! inside an appended block proposal, gets its spent nullifiers indexed.
+//! When a different transaction spending an already indexed nullifier
+//! shows up, a compact [`DoubleSpendProof`] referencing both transactions
+//! is recorded, and the conflicting transaction is rejected from the
+//! mempool, while the rest of the peer's transactions are unaffected.
+//!
+//! Relaying a transaction that conflicts with one we saw first is not
+//! misbehaviour by itself, since gossip doesn't reach every node in the
+//! same order. Honest nodes never relay both spends though, as they reject
+//! the second one themselves, so a peer only gets penalized when it
+//! relayed both conflicting transactions. Penalties decay by one every
+//! [`EQUIVOCATION_DECAY`], and peers reaching [`EQUIVOCATION_THRESHOLD`]
+//! are reported as equivocating sources.
+
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use darkfi_sdk::tx::TransactionHash;
+
+use crate::{blockchain::HeaderHash, tx::Transaction, util::time::Timestamp};
+
+use super::admission::TxSource;
+
+/// Maximum number of spent nullifiers indexed. When exceeded, the
+/// oldest ones get evicted.
+pub const MAX_TRACKED_SPENDS: usize = 65536;
+
+/// Maximum number of double-spend proofs retained
+pub const MAX_DOUBLE_SPEND_PROOFS: usize = 1024;
+
+/// Number of double-spend proofs attributed to a peer after which it
+/// is considered an equivocating source
+pub const EQUIVOCATION_THRESHOLD: u32 = 3;
+
+/// Time after which a single penalty of a peer expires
+pub const EQUIVOCATION_DECAY: Duration = Duration::from_secs(3600);
+
+/// Extracts the nullifiers a transaction spends. Nullifier semantics
+/// are contract-specific, so the node provides the implementation.
+pub trait SpendExtractor: Send + Sync {
+    /// Returns the raw nullifiers spent by given transaction
+    fn nullifiers(&self, tx: &Transaction) -> Vec<[u8; 32]>;
+}
+
+/// Compact proof of two distinct transactions spending the same nullifier.
+/// Anyone holding both transactions can verify it by extracting their
+/// nullifiers.
+#[derive(Clone, Debug)]
+pub struct DoubleSpendProof {
+    /// The nullifier spent twice
+    pub nullifier: [u8; 32],
+    /// Transaction that spent the nullifier first
+    pub first_tx: TransactionHash,
+    /// Source the first transaction was received from
+    pub first_source: TxSource,
+    /// Conflicting transaction spending the nullifier again
+    pub second_tx: TransactionHash,
+    /// Source that relayed the conflicting transaction
+    pub second_source: TxSource,
+    /// Block proposal containing the conflicting transaction, if it
+    /// was detected in one
+    pub block: Option<HeaderHash>,
+    /// Detection timestamp
+    pub timestamp: Timestamp,
+}
+
+/// First known spend of a nullifier
+struct SpendRecord {
+    tx: TransactionHash,
+    source: TxSource,
+}
+
+/// Decaying penalty score of a peer
+struct PeerScore {
+    /// Score as of `updated`
+    score: u32,
+    /// Last time the score was brought up to date
+    updated: Instant,
+}
+
+impl PeerScore {
+    /// Apply the decay elapsed until `now`, returning the current score.
+    fn decay(&mut self, now: Instant) -> u32 {
+        let elapsed = now.saturating_duration_since(self.updated);
+        let decays = (elapsed.as_secs() / EQUIVOCATION_DECAY.as_secs()).min(u32::MAX as u64);
+        if decays > 0 {
+            self.score = self.score.saturating_sub(decays as u32);
+            self.updated += EQUIVOCATION_DECAY * decays as u32;
+        }
+        self.score
+    }
+}
+
+#[derive(Default)]
+struct MonitorState {
+    /// Indexed nullifiers along with their first known spend
+    spends: HashMap<[u8; 32], SpendRecord>,
+    /// Indexed nullifiers in insertion order, used for eviction
+    order: VecDeque<[u8; 32]>,
+    /// Recorded double-spend proofs, oldest first
+    proofs: VecDeque<DoubleSpendProof>,
+    /// Decaying number of double-spend proofs attributed to each peer
+    scores: HashMap<String, PeerScore>,
+}
+
+impl MonitorState {
+    /// Current score of given peer, dropping it once fully decayed.
+    fn score(&mut self, peer: &str, now: Instant) -> u32 {
+        let Some(entry) = self.scores.get_mut(peer) else { return 0 };
+        let score = entry.decay(now);
+        if score == 0 {
+            self.scores.remove(peer);
+        }
+        score
+    }
+
+    /// Penalize given peer for a single offence.
+    fn penalize(&mut self, peer: &str, now: Instant) {
+        let score = self.score(peer, now);
+        self.scores.insert(peer.to_string(), PeerScore { score: score + 1, updated: now });
+    }
+}
+
+/// Registry of spent nullifiers, double-spend proofs and peer penalties
+pub struct DoubleSpendMonitor {
+    extractor: Option<Arc<dyn SpendExtractor>>,
+    state: Mutex<MonitorState>,
+}
+
+impl DoubleSpendMonitor {
+    /// Create a new monitor. Without an extractor, nothing gets tracked.
+    pub fn new(extractor: Option<Arc<dyn SpendExtractor>>) -> Self {
+        Self { extractor, state: Mutex::new(MonitorState::default()) }
+    }
+
+    /// Index the nullifiers of a valid transaction received from given
+    /// source, optionally inside given block proposal. Returns the
+    /// double-spend proofs it produced, which also get recorded. A
+    /// conflicting transaction gets none of its nullifiers indexed, so
+    /// mempool transactions producing proofs must be rejected.
+    pub fn observe(
+        &self,
+        tx: &Transaction,
+        source: &TxSource,
+        block: Option<HeaderHash>,
+    ) -> Vec<DoubleSpendProof> {
+        self.observe_at(tx, source, block, Instant::now())
+    }
+
+    fn observe_at(
+        &self,
+        tx: &Transaction,
+        source: &TxSource,
+        block: Option<HeaderHash>,
+        now: Instant,
+    ) -> Vec<DoubleSpendProof> {
+        let Some(extractor) = &self.extractor else { return vec![] };
+        let nullifiers = extractor.nullifiers(tx);
+        if nullifiers.is_empty() {
+            return vec![]
+        }
+
+        let tx_hash = tx.hash();
+        let mut state = self.state.lock().unwrap();
+        let mut found = vec![];
+        let mut relayed_both = false;
+        for nullifier in &nullifiers {
+            let Some(record) = state.spends.get(nullifier) else { continue };
+            if record.tx == tx_hash {
+                continue
+            }
+
+            relayed_both |= record.source == *source;
+            found.push(DoubleSpendProof {
+                nullifier: *nullifier,
+                first_tx: record.tx,
+                first_source: record.source.clone(),
+                second_tx: tx_hash,
+                second_source: source.clone(),
+                block,
+                timestamp: Timestamp::current_time(),
+            });
+        }
+
+        if found.is_empty() {
+            for nullifier in nullifiers {
+                if state.spends.contains_key(&nullifier) {
+                    continue
+                }
+                state.spends.insert(nullifier, SpendRecord { tx: tx_hash, source: source.clone() });
+                state.order.push_back(nullifier);
+                if state.order.len() > MAX_TRACKED_SPENDS {
+                    let evicted = state.order.pop_front().unwrap();
+                    state.spends.remove(&evicted);
+                }
+            }
+            return found
+        }
+
+        // Only peers relaying both spends themselves get penalized, and a
+        // single transaction counts as one offence, regardless of how many
+        // of its nullifiers conflict. Peers relaying a proposal are not
+        // its producers, so proposals never penalize them.
+        if let TxSource::Peer(peer) = source {
+            if relayed_both && block.is_none() {
+                state.penalize(peer, now);
+            }
+        }
+
+        for proof in &found {
+            state.proofs.push_back(proof.clone());
+            if state.proofs.len() > MAX_DOUBLE_SPEND_PROOFS {
+                state.proofs.pop_front();
+            }
+        }
+
+        found
+    }
+
+    /// Returns the first known transaction the given one conflicts with,
+    /// without indexing or recording anything.
+    pub fn conflict(&self, tx: &Transaction) -> Option<TransactionHash> {
+        let extractor = self.extractor.as_ref()?;
+        let tx_hash = tx.hash();
+        let state = self.state.lock().unwrap();
+        extractor.nullifiers(tx).iter().find_map(|nullifier| {
+            state.spends.get(nullifier).map(|record| record.tx).filter(|first| *first != tx_hash)
+        })
+    }
+
+    /// Recorded double-spend proofs, oldest first
+    pub fn proofs(&self) -> Vec<DoubleSpendProof> {
+        self.state.lock().unwrap().proofs.iter().cloned().collect()
+    }
+
+    /// Current, decayed, number of double-spend proofs attributed to
+    /// each penalized peer
+    pub fn scores(&self) -> HashMap<String, u32> {
+        let now = Instant::now();
+        let mut state = self.state.lock().unwrap();
+        let peers: Vec<String> = state.scores.keys().cloned().collect();
+        peers
+            .into_iter()
+            .filter_map(|peer| {
+                let score = state.score(&peer, now);
+                (score > 0).then_some((peer, score))
+            })
+            .collect()
+    }
+
+    /// Current, decayed, number of double-spend proofs attributed to
+    /// given source. Local submissions are never penalized.
+    pub fn score(&self, source: &TxSource) -> u32 {
+        self.score_at(source, Instant::now())
+    }
+
+    fn score_at(&self, source: &TxSource, now: Instant) -> u32 {
+        match source {
+            TxSource::Local => 0,
+            TxSource::Peer(peer) => self.state.lock().unwrap().score(peer, now),
+        }
+    }
+
+    /// Check if given source is currently an equivocating one.
+    pub fn is_equivocating(&self, source: &TxSource) -> bool {
+        self.score(source) >= EQUIVOCATION_THRESHOLD
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use darkfi_sdk::{crypto::MONEY_CONTRACT_ID, dark_tree::DarkLeaf, tx::ContractCall};
+
+    use super::*;
+
+    /// Test extractor treating every call data byte as a nullifier
+    struct ByteExtractor;
+
+    impl SpendExtractor for ByteExtractor {
+        fn nullifiers(&self, tx: &Transaction) -> Vec<[u8; 32]> {
+            tx.calls.iter().flat_map(|c| c.data.data.iter().map(|b| [*b; 32])).collect()
+        }
+    }
+
+    fn tx(data: Vec<u8>) -> Transaction {
+        let call = ContractCall { contract_id: *MONEY_CONTRACT_ID, data };
+        Transaction {
+            calls: vec![DarkLeaf { data: call, parent_index: None, children_indexes: vec![] }],
+            proofs: vec![],
+            signatures: vec![],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn double_spend_detection() {
+        let monitor = DoubleSpendMonitor::new(Some(Arc::new(ByteExtractor)));
+        let peer_a = TxSource::Peer("127.0.0.1".to_string());
+        let peer_b = TxSource::Peer("127.0.0.2".to_string());
+
+        // Fresh spends and re-observing the same transaction are fine
+        let tx0 = tx(vec![0, 1]);
+        assert!(monitor.observe(&tx0, &TxSource::Local, None).is_empty());
+        assert!(monitor.observe(&tx0, &peer_a, None).is_empty());
+
+        // A conflicting transaction yields a proof per conflicting nullifier,
+        // but its relayer only saw one of the spends, so it isn't penalized
+        let tx1 = tx(vec![0, 1, 2]);
+        let proofs = monitor.observe(&tx1, &peer_b, None);
+        assert_eq!(proofs.len(), 2);
+        assert_eq!(proofs[0].first_tx, tx0.hash());
+        assert_eq!(proofs[0].second_tx, tx1.hash());
+        assert_eq!(monitor.score(&peer_b), 0);
+        assert_eq!(monitor.proofs().len(), 2);
+
+        // The conflicting transaction didn't get its other nullifiers indexed
+        assert_eq!(monitor.conflict(&tx1), Some(tx0.hash()));
+        assert_eq!(monitor.conflict(&tx(vec![2])), None);
+        assert!(monitor.observe(&tx(vec![2]), &peer_b, None).is_empty());
+
+        // Relaying both spends gets the relayer penalized, once per transaction
+        assert!(monitor.observe(&tx(vec![3, 4]), &peer_a, None).is_empty());
+        assert_eq!(monitor.observe(&tx(vec![3, 4, 5]), &peer_a, None).len(), 2);
+        assert_eq!(monitor.score(&peer_a), 1);
+
+        // Proposals never penalize the peer relaying them
+        let block = HeaderHash::new([7; 32]);
+        let proofs = monitor.observe(&tx(vec![3, 6]), &peer_a, Some(block));
+        assert_eq!(proofs.len(), 1);
+        assert_eq!(proofs[0].block, Some(block));
+        assert_eq!(monitor.score(&peer_a), 1);
+
+        // Peers get flagged after enough offences
+        assert!(!monitor.is_equivocating(&peer_a));
+        for i in 1..EQUIVOCATION_THRESHOLD as u8 {
+            assert_eq!(monitor.observe(&tx(vec![3, 10 + i]), &peer_a, None).len(), 1);
+        }
+        assert!(monitor.is_equivocating(&peer_a));
+        assert_eq!(monitor.scores().get("127.0.0.1"), Some(&EQUIVOCATION_THRESHOLD));
+
+        // Penalties decay over time
+        let now = Instant::now();
+        assert_eq!(monitor.score_at(&peer_a, now + EQUIVOCATION_DECAY), EQUIVOCATION_THRESHOLD - 1);
+        assert_eq!(monitor.score_at(&peer_a, now + EQUIVOCATION_DECAY * EQUIVOCATION_THRESHOLD), 0);
+        assert!(monitor.scores().is_empty());
+
+        // Local submissions never get penalized
+        assert_eq!(monitor.observe(&tx(vec![0, 30]), &TxSource::Local, None).len(), 1);
+        assert_eq!(monitor.observe(&tx(vec![0, 31]), &TxSource::Local, None).len(), 1);
+        assert!(!monitor.is_equivocating(&TxSource::Local));
+
+        // Without an extractor nothing gets tracked
+        let monitor = DoubleSpendMonitor::new(None);
+        monitor.observe(&tx0, &peer_a, None);
+        assert!(monitor.observe(&tx1, &peer_b, None).is_empty());
+    }
+}
@@ -16,9 +16,13 @@
  * along with this program.  If not, see <https://www.gnu.org/licenses/>.
  */
 
-use std::{collections::HashMap, sync::Arc};
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Arc,
+};
 
 use darkfi_sdk::crypto::MerkleTree;
+use darkfi_serial::serialize;
 use log::{debug, error, info, warn};
 use num_bigint::BigUint;
 use sled_overlay::sled;
@@ -30,6 +34,7 @@ use crate::{
         Blockchain, BlockchainOverlay, HeaderHash,
     },
     error::TxVerifyFailed,
+    system::trace,
     tx::Transaction,
     validator::fees::GasData,
     zk::VerifyingKey,
@@ -42,7 +47,7 @@ use consensus::{Consensus, Fork, Proposal};
 
 /// DarkFi PoW module
 pub mod pow;
-use pow::PoWModule;
+use pow::{difficulty_history, DifficultyHistory, PoWModule};
 
 /// Verification functions
 pub mod verification;
@@ -54,8 +59,27 @@ use verification::{
 /// Fee calculation helpers
 pub mod fees;
 
+/// Mempool admission policies
+pub mod admission;
+use admission::{AdmissionPipeline, AdmissionPolicy, TxSource};
+
+/// Double-spend detection and peer accountability
+pub mod double_spend;
+use double_spend::{DoubleSpendMonitor, SpendExtractor};
+
+/// Dependency-aware parallel transaction verification
+pub mod parallel;
+
 /// Helper utilities
 pub mod utils;
+
+/// Soft-fork feature signalling and activation
+pub mod versionbits;
+use versionbits::{Deployment, DeploymentState};
+
+/// Blockchain snapshots for fast bootstrap
+pub mod snapshot;
+use snapshot::{SnapshotStore, SNAPSHOT_REPLAY_BATCH};
 use utils::{best_fork_index, block_rank, deploy_native_contracts};
 
 /// Configuration for initializing [`Validator`]
@@ -71,6 +95,14 @@ pub struct ValidatorConfig {
     pub genesis_block: BlockInfo,
     /// Flag to enable tx fee verification
     pub verify_fees: bool,
+    /// Mempool admission policies, applied in order
+    pub admission_policies: Vec<Arc<dyn AdmissionPolicy>>,
+    /// Optional spent nullifiers extractor, enabling double-spend detection
+    pub spend_extractor: Option<Arc<dyn SpendExtractor>>,
+    /// Interval in blocks between blockchain snapshots (0 disables)
+    pub snapshot_interval: u32,
+    /// Maximum amount of state bytes a non-native contract can hold
+    pub contract_state_quota: u64,
 }
 
 /// Atomic pointer to validator.
@@ -86,6 +118,14 @@ pub struct Validator {
     pub synced: RwLock<bool>,
     /// Flag to enable tx fee verification
     pub verify_fees: bool,
+    /// Mempool admission policies pipeline
+    pub admission: AdmissionPipeline,
+    /// Double-spend proofs and peer penalties registry
+    pub double_spends: DoubleSpendMonitor,
+    /// Blockchain snapshots served to peers
+    pub snapshots: SnapshotStore,
+    /// Interval in blocks between blockchain snapshots (0 disables)
+    pub snapshot_interval: u32,
 }
 
 impl Validator {
@@ -93,7 +133,11 @@ impl Validator {
         info!(target: "validator::new", "Initializing Validator");
 
         info!(target: "validator::new", "Initializing Blockchain");
-        let blockchain = Blockchain::new(db)?;
+        let mut blockchain = Blockchain::new(db)?;
+        blockchain.contracts.state_quota = config.contract_state_quota;
+
+        // Account the state size of contracts deployed before it was tracked
+        blockchain.contracts.backfill_state_sizes(db)?;
 
         // Create an overlay over whole blockchain so we can write stuff
         let overlay = BlockchainOverlay::new(&blockchain)?;
@@ -110,6 +154,8 @@ impl Validator {
         // Write the changes to the actual chain db
         overlay.lock().unwrap().overlay.lock().unwrap().apply()?;
 
+        let snapshots = SnapshotStore::new(db)?;
+
         info!(target: "validator::new", "Initializing Consensus");
         let consensus = Consensus::new(
             blockchain.clone(),
@@ -124,6 +170,10 @@ impl Validator {
             consensus,
             synced: RwLock::new(false),
             verify_fees: config.verify_fees,
+            admission: AdmissionPipeline::new(config.admission_policies.clone()),
+            double_spends: DoubleSpendMonitor::new(config.spend_extractor.clone()),
+            snapshots,
+            snapshot_interval: config.snapshot_interval,
         });
 
         info!(target: "validator::new", "Finished initializing validator");
@@ -167,30 +217,39 @@ impl Validator {
         Ok(verify_result)
     }
 
-    /// The node retrieves a transaction, validates its state transition,
-    /// and appends it to the pending txs store.
-    pub async fn append_tx(&self, tx: &Transaction, write: bool) -> Result<()> {
+    /// The node retrieves a transaction, checks it against the configured
+    /// admission policies, validates its state transition, and appends it
+    /// to the pending txs store.
+    pub async fn append_tx(&self, tx: &Transaction, write: bool, source: &TxSource) -> Result<()> {
         let tx_hash = tx.hash();
+        let prefix = trace::log_prefix();
 
         // Check if we have already seen this tx
         let tx_in_txstore = self.blockchain.transactions.contains(&tx_hash)?;
         let tx_in_pending_txs_store = self.blockchain.transactions.contains_pending(&tx_hash)?;
 
         if tx_in_txstore || tx_in_pending_txs_store {
-            info!(target: "validator::append_tx", "We have already seen this tx");
+            info!(target: "validator::append_tx", "{}We have already seen this tx", prefix);
             return Err(TxVerifyFailed::AlreadySeenTx(tx_hash.as_string()).into())
         }
 
+        // Check admission policies before spending time on verification
+        if let Err(e) = self.admission.check_tx(tx, source) {
+            info!(target: "validator::append_tx",
+                "{}Transaction {} from {} rejected: {}", prefix, tx_hash, source, e);
+            return Err(e)
+        }
+
         // Verify state transition
-        info!(target: "validator::append_tx", "Starting state transition validation");
+        info!(target: "validator::append_tx", "{}Starting state transition validation", prefix);
         let tx_vec = [tx.clone()];
-        let mut valid = false;
+        let mut valid_forks = vec![];
 
         // Grab a lock over current consensus forks state
         let mut forks = self.consensus.forks.write().await;
 
         // Iterate over node forks to verify transaction validity in their overlays
-        for fork in forks.iter_mut() {
+        for (index, fork) in forks.iter().enumerate() {
             // Clone fork state
             let fork_clone = fork.full_clone()?;
 
@@ -212,28 +271,52 @@ impl Validator {
             fork_clone.overlay.lock().unwrap().overlay.lock().unwrap().purge_new_trees()?;
 
             // Handle response
-            match verify_result {
-                Ok(_) => {}
+            let (gas_used, fee_paid) = match verify_result {
+                Ok(v) => v,
                 Err(Error::TxVerifyFailed(TxVerifyFailed::ErroneousTxs(_))) => continue,
                 Err(e) => return Err(e),
+            };
+
+            // Check admission policies requiring the verification results
+            if let Err(e) = self.admission.check_verified(tx, gas_used, fee_paid) {
+                info!(target: "validator::append_tx",
+                    "{}Transaction {} from {} rejected: {}", prefix, tx_hash, source, e);
+                return Err(e)
             }
 
-            valid = true;
+            valid_forks.push(index);
+        }
+
+        // Return error if transaction is not valid for any fork
+        if valid_forks.is_empty() {
+            return Err(TxVerifyFailed::ErroneousTxs(tx_vec.to_vec()).into())
+        }
+
+        // Index its nullifiers, rejecting it if it conflicts with another
+        // valid transaction we have seen first. Transactions we don't keep
+        // are only checked, so they can't shadow later ones.
+        if write {
+            let proofs = self.double_spends.observe(tx, source, None);
+            if let Some(proof) = proofs.first() {
+                warn!(target: "validator::append_tx",
+                    "{}Transaction {} from {} double-spends a nullifier of {}",
+                    prefix, proof.second_tx, proof.second_source, proof.first_tx);
+                return Err(TxVerifyFailed::DoubleSpend(proof.first_tx.to_string()).into())
+            }
+        } else if let Some(first_tx) = self.double_spends.conflict(tx) {
+            return Err(TxVerifyFailed::DoubleSpend(first_tx.to_string()).into())
+        }
 
-            // Store transaction hash in forks' mempool
-            if write {
-                fork.mempool.push(tx_hash);
+        // Store transaction hash in forks' mempool
+        if write {
+            for index in valid_forks {
+                forks[index].mempool.push(tx_hash);
             }
         }
 
         // Drop forks lock
         drop(forks);
 
-        // Return error if transaction is not valid for any fork
-        if !valid {
-            return Err(TxVerifyFailed::ErroneousTxs(tx_vec.to_vec()).into())
-        }
-
         // Add transaction to pending txs store
         if write {
             self.blockchain.add_pending_txs(&tx_vec)?;
@@ -390,6 +473,16 @@ impl Validator {
             confirmed_txs.extend_from_slice(&confirmed_blocks[index].txs);
             state_diffs_heights.push(confirmed_blocks[index].header.height);
             state_diffs.push(diffs[index].clone());
+
+            // Snapshot the canonical blocks if we reached the configured interval.
+            // Confirmed diffs get applied one by one, so the canonical chain ends
+            // at this exact block height right now.
+            let height = confirmed_blocks[index].header.height;
+            if self.snapshot_interval > 0 && height > 0 && height % self.snapshot_interval == 0 {
+                if let Err(e) = self.snapshots.create(&self.blockchain) {
+                    error!(target: "validator::confirmation", "Failed creating blockchain snapshot at height {height}: {e}");
+                }
+            }
         }
         drop(module);
         drop(forks);
@@ -514,8 +607,8 @@ impl Validator {
     }
 
     /// Validate a set of [`BlockInfo`] in sequence and apply them if all are valid.
-    /// Note: this function should only be used in tests when we don't want to
-    /// perform consensus logic.
+    /// Note: this function should only be used in tests, or when replaying
+    /// snapshot blocks, when we don't want to perform consensus logic.
     pub async fn add_test_blocks(&self, blocks: &[BlockInfo]) -> Result<()> {
         debug!(target: "validator::add_test_blocks", "Instantiating BlockchainOverlay");
         let overlay = BlockchainOverlay::new(&self.blockchain)?;
@@ -541,7 +634,17 @@ impl Validator {
         // Validate and insert each block
         for block in blocks {
             // Verify block
-            match verify_block(&overlay, &module, block, previous, self.verify_fees).await {
+            match verify_block(
+                &overlay,
+                &module,
+                block,
+                previous,
+                &self.consensus.scheduler,
+                &self.consensus.versionbits,
+                self.verify_fees,
+            )
+            .await
+            {
                 Ok(()) => { /* Do nothing */ }
                 // Skip already existing block
                 Err(Error::BlockAlreadyExists(_)) => {
@@ -750,7 +853,18 @@ impl Validator {
         // Validate and insert each block
         for block in &blocks[1..] {
             // Verify block
-            if verify_block(&overlay, &module, block, previous, self.verify_fees).await.is_err() {
+            if verify_block(
+                &overlay,
+                &module,
+                block,
+                previous,
+                &self.consensus.scheduler,
+                &self.consensus.versionbits,
+                self.verify_fees,
+            )
+            .await
+            .is_err()
+            {
                 error!(target: "validator::validate_blockchain", "Erroneous block found in set");
                 overlay.lock().unwrap().overlay.lock().unwrap().purge_new_trees()?;
                 return Err(Error::BlockIsInvalid(block.hash().as_string()))
@@ -766,6 +880,165 @@ impl Validator {
         Ok(())
     }
 
+    /// Re-execute the canonical blocks in the provided inclusive height
+    /// range against a fresh in memory overlay and compare each produced
+    /// state diff with the one stored in the database, to detect state
+    /// corruption or nondeterministic execution between releases.
+    /// The in memory state up to `start` is bootstrapped using the stored
+    /// state diffs, so only the requested range gets re-executed.
+    /// Each provided checkpoint `(height, hash)` inside the range is also
+    /// enforced against the replayed block hashes.
+    /// Returns the heights whose re-executed state diff didn't match the
+    /// stored one.
+    pub async fn replay_range(
+        &self,
+        start: u32,
+        end: u32,
+        checkpoints: &[(u32, HeaderHash)],
+    ) -> Result<Vec<u32>> {
+        // Genesis block has no stored state diff so we can't replay it
+        let start = start.max(1);
+        let (last, _) = self.blockchain.last()?;
+        if start > end || end > last {
+            return Err(Error::DatabaseError(format!(
+                "Replay heights range is invalid: {start}..={end} (last: {last})"
+            )))
+        }
+        info!(target: "validator::replay_range", "Replaying blocks {start}..={end}");
+
+        // Create an in memory blockchain to replay the blocks
+        let sled_db = sled::Config::new().temporary(true).open()?;
+        let blockchain = Blockchain::new(&sled_db)?;
+        let overlay = BlockchainOverlay::new(&blockchain)?;
+
+        // Grab the configured PoW parameters
+        let (pow_target, pow_fixed_difficulty) = {
+            let module = self.consensus.module.read().await;
+            (module.target, module.fixed_difficulty.clone())
+        };
+
+        // Deploy native wasm contracts and append genesis block
+        let genesis = self.blockchain.genesis_block()?;
+        deploy_native_contracts(&overlay, pow_target).await?;
+        verify_genesis_block(&overlay, &genesis, pow_target).await?;
+        overlay.lock().unwrap().overlay.lock().unwrap().apply()?;
+
+        // Bootstrap the state until the requested start height using the
+        // stored diffs, retrieving them one at a time so we don't keep the
+        // whole history in memory.
+        if start > 1 {
+            let overlay = BlockchainOverlay::new(&blockchain)?;
+            let overlay_lock = overlay.lock().unwrap();
+            let mut lock = overlay_lock.overlay.lock().unwrap();
+            for height in 1..start {
+                // Since we used strict retrieval it's safe to unwrap here
+                let diff =
+                    self.blockchain.blocks.get_state_diff(&[height], true)?.remove(0).unwrap();
+                lock.add_diff(&diff)?;
+                lock.apply_diff(&diff)?;
+            }
+            drop(lock);
+            drop(overlay_lock);
+        }
+
+        // Retrieve last block difficulty to access current ranks
+        let last_difficulty = blockchain.last_block_difficulty()?;
+        let mut current_targets_rank = last_difficulty.ranks.targets_rank;
+        let mut current_hashes_rank = last_difficulty.ranks.hashes_rank;
+
+        // Create a PoW module to validate each block
+        let mut module =
+            PoWModule::new(blockchain.clone(), pow_target, pow_fixed_difficulty, None)?;
+
+        // Re-execute each block and compare its state diff
+        let mut previous = self.blockchain.get_blocks_by_heights(&[start - 1])?.remove(0);
+        let mut mismatches = vec![];
+        for height in start..=end {
+            let block = self.blockchain.get_blocks_by_heights(&[height])?.remove(0);
+            let stored_diff = self.blockchain.blocks.get_state_diff(&[height], true)?.remove(0);
+
+            // Verify block
+            let overlay = BlockchainOverlay::new(&blockchain)?;
+            if let Err(e) = verify_block(
+                &overlay,
+                &module,
+                &block,
+                &previous,
+                &self.consensus.scheduler,
+                &self.consensus.versionbits,
+                self.verify_fees,
+            )
+            .await
+            {
+                error!(target: "validator::replay_range", "Block {height} failed verification: {e}");
+                return Err(Error::BlockIsInvalid(block.hash().as_string()))
+            }
+
+            // Grab next mine target and difficulty
+            let (next_target, next_difficulty) = module.next_mine_target_and_difficulty()?;
+
+            // Calculate block rank
+            let (target_distance_sq, hash_distance_sq) = block_rank(&block, &next_target);
+
+            // Update current ranks
+            current_targets_rank += target_distance_sq.clone();
+            current_hashes_rank += hash_distance_sq.clone();
+
+            // Generate block difficulty and update PoW module
+            let cummulative_difficulty =
+                module.cummulative_difficulty.clone() + next_difficulty.clone();
+            let ranks = BlockRanks::new(
+                target_distance_sq,
+                current_targets_rank.clone(),
+                hash_distance_sq,
+                current_hashes_rank.clone(),
+            );
+            let block_difficulty = BlockDifficulty::new(
+                block.header.height,
+                block.header.timestamp,
+                next_difficulty,
+                cummulative_difficulty,
+                ranks,
+            );
+            module.append_difficulty(&overlay, block_difficulty)?;
+
+            // Compare the produced state diff with the stored one
+            let diff = overlay.lock().unwrap().overlay.lock().unwrap().diff(&[])?;
+            if stored_diff.map(|d| serialize(&d)) != Some(serialize(&diff)) {
+                warn!(target: "validator::replay_range", "State diff mismatch at height {height}");
+                mismatches.push(height);
+            }
+
+            // Write the changes to the in memory db
+            overlay.lock().unwrap().overlay.lock().unwrap().apply()?;
+
+            // Use current block as next iteration previous
+            previous = block;
+        }
+
+        // Verify provided checkpoints against the replayed blocks
+        for (height, hash) in checkpoints {
+            if *height < start || *height > end {
+                continue
+            }
+            let replayed = blockchain.blocks.get_order(&[*height], true)?[0].unwrap();
+            if &replayed != hash {
+                error!(
+                    target: "validator::replay_range",
+                    "Checkpoint mismatch at height {height}: expected {hash}, got {replayed}"
+                );
+                return Err(Error::BlockIsInvalid(replayed.as_string()))
+            }
+        }
+
+        info!(
+            target: "validator::replay_range",
+            "Replay finished, found {} state diff mismatches", mismatches.len()
+        );
+
+        Ok(mismatches)
+    }
+
     /// Auxiliary function to retrieve current best fork next block height.
     pub async fn best_fork_next_block_height(&self) -> Result<u32> {
         let forks = self.consensus.forks.read().await;
@@ -776,6 +1049,65 @@ impl Validator {
         Ok(next_block_height)
     }
 
+    /// Auxiliary function to retrieve the soft-fork deployments along with
+    /// their state for the current best fork next block height.
+    pub async fn deployment_states(&self) -> Result<(u32, Vec<(Deployment, DeploymentState)>)> {
+        let forks = self.consensus.forks.read().await;
+        let fork = &forks[best_fork_index(&forks)?];
+        let next_block_height = fork.get_next_block_height()?;
+        let states = self.consensus.versionbits.states(&fork.overlay, next_block_height)?;
+        drop(forks);
+
+        Ok((next_block_height, states))
+    }
+
+    /// Auxiliary function to retrieve the canonical blockchain difficulty
+    /// history of the last `window` blocks.
+    pub fn difficulty_history(&self, window: usize) -> Result<DifficultyHistory> {
+        difficulty_history(&self.blockchain, window)
+    }
+
+    /// Replay all the blocks of provided decoded snapshot on top of our
+    /// canonical blockchain, fully verifying each of them, so the state is
+    /// derived by ourselves instead of trusting the peers serving it.
+    /// Blocks are replayed in batches, so the snapshot is never loaded in
+    /// memory as a whole. Afterwards, the consensus forks get reset on top
+    /// of the snapshot tip, and blocks after it must be synced normally.
+    pub async fn replay_snapshot(&self, snapshot: &Blockchain) -> Result<()> {
+        let (height, hash) = snapshot.last()?;
+        info!(target: "validator::replay_snapshot", "Replaying blockchain snapshot: {height} - {hash}");
+
+        // Snapshot must extend our genesis block
+        if snapshot.genesis()? != self.blockchain.genesis()? {
+            return Err(Error::SnapshotInvalid(String::from("Genesis block mismatch")))
+        }
+
+        // Grab append lock so no new proposals can be appended while we replay
+        let append_lock = self.consensus.append_lock.write().await;
+
+        let mut next = self.blockchain.last()?.0 + 1;
+        while next <= height {
+            let end = height.min(next + SNAPSHOT_REPLAY_BATCH - 1);
+            let heights: Vec<u32> = (next..=end).collect();
+            let blocks = snapshot.get_blocks_by_heights(&heights)?;
+            self.add_test_blocks(&blocks).await?;
+            info!(target: "validator::replay_snapshot", "Snapshot blocks replayed: {end}/{height}");
+            next = end + 1;
+        }
+
+        // Reset forks on top of the replayed blocks
+        let module = self.consensus.module.read().await.clone();
+        *self.consensus.forks.write().await =
+            vec![Fork::new(self.blockchain.clone(), module).await?];
+
+        // Release append lock
+        drop(append_lock);
+
+        info!(target: "validator::replay_snapshot", "Blockchain snapshot replayed successfully!");
+
+        Ok(())
+    }
+
     /// Auxiliary function to reset the validator blockchain and consensus states
     /// to the provided block height.
     pub async fn reset_to_height(&self, height: u32) -> Result<()> {
@@ -799,4 +1131,140 @@ impl Validator {
 
         Ok(())
     }
+
+    /// Development helper to force a reorg, marking provided block as
+    /// invalid. A confirmed block gets rolled back along with all blocks
+    /// on top of it, returning their transactions to the mempool, while
+    /// an unconfirmed one gets dropped from the forks along with its
+    /// descendants. Invalidated blocks are rejected until reconsidered.
+    /// Returns the hashes of the dropped blocks.
+    pub async fn invalidate_block(&self, hash: &HeaderHash) -> Result<Vec<HeaderHash>> {
+        info!(target: "validator::invalidate_block", "Invalidating block: {hash}");
+        // Grab append lock so no new proposals can be appended while we invalidate
+        let append_lock = self.consensus.append_lock.write().await;
+
+        let dropped = match self.blockchain.get_blocks_by_hash(&[*hash]) {
+            Ok(blocks) => {
+                let height = blocks[0].header.height;
+                if height == 0 {
+                    return Err(Error::Custom("Genesis block can't be invalidated".to_string()))
+                }
+
+                // Grab the confirmed blocks we are going to roll back
+                let (last, _) = self.blockchain.last()?;
+                let dropped = self.blockchain.get_by_range(height, last + 1)?;
+
+                // Roll back canonical state, invalidating all forks
+                self.blockchain.reset_to_height(height - 1)?;
+                self.consensus.reset_pow_module().await?;
+                self.consensus.purge_forks().await?;
+
+                dropped
+            }
+            Err(_) => self.drop_unconfirmed(hash).await?,
+        };
+
+        let hashes: Vec<HeaderHash> = dropped.iter().map(|b| b.hash()).collect();
+        let dropped_txs: Vec<Transaction> = dropped
+            .iter()
+            .flat_map(|b| b.txs.iter())
+            .filter(|tx| !tx.is_pow_reward())
+            .cloned()
+            .collect();
+        self.consensus.invalidated.write().await.insert(*hash, dropped);
+
+        // Release append lock
+        drop(append_lock);
+
+        // Return the rolled back transactions to the mempool
+        for tx in dropped_txs {
+            if let Err(e) = self.append_tx(&tx, true, &TxSource::Local).await {
+                debug!(target: "validator::invalidate_block", "Dropped transaction {} not returned to mempool: {e}", tx.hash());
+            }
+        }
+
+        info!(target: "validator::invalidate_block", "Block {hash} invalidated, dropped {} blocks", hashes.len());
+
+        Ok(hashes)
+    }
+
+    /// Auxiliary function to drop an unconfirmed block and its descendants
+    /// from the forks containing it. Their ancestors get re-appended, so
+    /// the rest of those forks survive. Returns the dropped blocks of the
+    /// longest fork containing it.
+    async fn drop_unconfirmed(&self, hash: &HeaderHash) -> Result<Vec<BlockInfo>> {
+        let mut forks = self.consensus.forks.write().await;
+
+        let mut dropped = vec![];
+        let mut ancestors = vec![];
+        let mut seen = HashSet::new();
+        for fork in forks.iter() {
+            let Some(index) = fork.proposals.iter().position(|p| p == hash) else { continue };
+
+            let overlay = fork.overlay.lock().unwrap();
+            for block in overlay.get_blocks_by_hash(&fork.proposals[..index])? {
+                if seen.insert(block.hash()) {
+                    ancestors.push(block);
+                }
+            }
+            let blocks = overlay.get_blocks_by_hash(&fork.proposals[index..])?;
+            if blocks.len() > dropped.len() {
+                dropped = blocks;
+            }
+        }
+
+        if dropped.is_empty() {
+            return Err(Error::BlockNotFound(hash.to_string()))
+        }
+
+        forks.retain(|fork| !fork.proposals.contains(hash));
+        if forks.is_empty() {
+            let module = self.consensus.module.read().await.clone();
+            forks.push(Fork::new(self.blockchain.clone(), module).await?);
+        }
+        drop(forks);
+
+        // Re-append the surviving ancestors in height order
+        ancestors.sort_by_key(|b| b.header.height);
+        for block in ancestors {
+            match self.consensus.append_proposal(&Proposal::new(block), self.verify_fees).await {
+                Ok(()) | Err(Error::ProposalAlreadyExists) => {}
+                Err(e) => return Err(e),
+            }
+        }
+
+        Ok(dropped)
+    }
+
+    /// Development helper to undo [`Validator::invalidate_block`],
+    /// re-appending the dropped blocks as proposals. Returns the hashes
+    /// of the re-appended blocks.
+    pub async fn reconsider_block(&self, hash: &HeaderHash) -> Result<Vec<HeaderHash>> {
+        info!(target: "validator::reconsider_block", "Reconsidering block: {hash}");
+        let Some(blocks) = self.consensus.invalidated.write().await.remove(hash) else {
+            return Err(Error::BlockNotFound(hash.to_string()))
+        };
+
+        // Grab append lock so we restrict concurrent proposals appends
+        let append_lock = self.consensus.append_lock.write().await;
+
+        let mut appended = vec![];
+        for block in blocks {
+            let proposal = Proposal::new(block);
+            match self.consensus.append_proposal(&proposal, self.verify_fees).await {
+                Ok(()) | Err(Error::ProposalAlreadyExists) => appended.push(proposal.hash),
+                Err(e) => {
+                    warn!(target: "validator::reconsider_block", "Failed re-appending block {}: {e}", proposal.hash);
+                    break
+                }
+            }
+        }
+
+        // Release append lock
+        drop(append_lock);
+
+        info!(target: "validator::reconsider_block", "Block {hash} reconsidered, re-appended {} blocks", appended.len());
+
+        Ok(appended)
+    }
 }
@@ -0,0 +1,517 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2025 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Dependency-aware parallel transaction verification.
+//!
+//! Transactions declare the contract state they access through the
+//! `metadata` function of their calls: the public inputs of their ZK proofs
+//! are the nullifiers, coins, bullas and roots they read or create. Block
+//! transactions get split into waves of consecutive ones, where a transaction
+//! whose metadata looked up contract state can't share a wave with an earlier
+//! one declaring any of its inputs. The metadata of each wave's transactions,
+//! along with their signatures and ZK proofs, gets evaluated in parallel on
+//! the blocking thread pool, over the state left by the previous waves. The
+//! transactions are then executed and applied in block order, each one having
+//! to yield the same metadata its proofs were verified against.
+//!
+//! Parallel work only reads the overlay, so no snapshots are required.
+//! Whenever anything fails, the overlay gets reverted and the whole set is
+//! verified again in sequence, so the outcome is always the same as the
+//! sequential [`verify_transactions`] one.
+
+use std::{
+    collections::{HashMap, HashSet},
+    num::NonZeroUsize,
+    thread,
+};
+
+use darkfi_sdk::{
+    crypto::{MerkleTree, PublicKey},
+    pasta::{group::ff::PrimeField, pallas},
+};
+use darkfi_serial::{AsyncDecodable, AsyncEncodable};
+use log::{debug, warn};
+use smol::io::Cursor;
+
+use crate::{
+    blockchain::{block_store::append_tx_to_merkle_tree, BlockchainOverlayPtr},
+    error::TxVerifyFailed,
+    runtime::vm_runtime::Runtime,
+    tx::Transaction,
+    validator::{
+        consensus::GAS_LIMIT_UNPROPOSED_TXS,
+        verification::{
+            execute_transaction, verify_transaction, verify_transaction_proofs,
+            verify_transactions, TxMetadata,
+        },
+    },
+    zk::VerifyingKey,
+    Result,
+};
+
+/// Schedules the transactions of a block into waves of non-conflicting
+/// ones, and verifies each wave in parallel.
+pub struct TxScheduler {
+    /// Maximum number of transactions verified concurrently
+    workers: usize,
+}
+
+impl Default for TxScheduler {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl TxScheduler {
+    /// Generate a new `TxScheduler`, using all the available cores.
+    pub fn new() -> Self {
+        Self::with_workers(thread::available_parallelism().map(NonZeroUsize::get).unwrap_or(1))
+    }
+
+    /// Generate a new `TxScheduler`, verifying up to `workers` transactions concurrently.
+    pub fn with_workers(workers: usize) -> Self {
+        Self { workers: workers.max(1) }
+    }
+
+    /// Returns `true` if transactions can get verified in parallel.
+    pub fn is_parallel(&self) -> bool {
+        self.workers > 1
+    }
+
+    /// Split given transactions into waves of consecutive indexes, using the
+    /// access sets their calls metadata declare over the provided overlay.
+    pub async fn schedule(
+        &self,
+        overlay: &BlockchainOverlayPtr,
+        verifying_block_height: u32,
+        block_target: u32,
+        txs: &[Transaction],
+    ) -> Vec<Vec<usize>> {
+        let mut declared = Vec::with_capacity(txs.len());
+        for chunk in txs.chunks(self.workers) {
+            let tasks: Vec<_> = chunk
+                .iter()
+                .map(|tx| {
+                    let overlay = overlay.clone();
+                    let tx = tx.clone();
+                    smol::unblock(move || {
+                        smol::block_on(transaction_metadata(
+                            &overlay,
+                            verifying_block_height,
+                            block_target,
+                            &tx,
+                        ))
+                        .ok()
+                        .and_then(|(metadata, reads_state)| {
+                            Access::declared(&tx, &metadata, reads_state)
+                        })
+                    })
+                })
+                .collect();
+
+            for task in tasks {
+                declared.push(task.await);
+            }
+        }
+
+        waves(&declared)
+    }
+
+    /// Verify a set of [`Transaction`] and apply them if all are valid, verifying
+    /// non-conflicting ones in parallel. Semantics and returned values are the same
+    /// as [`verify_transactions`], which gets used as a fallback whenever parallel
+    /// verification is not possible or a conflict is detected.
+    pub async fn verify_transactions(
+        &self,
+        overlay: &BlockchainOverlayPtr,
+        verifying_block_height: u32,
+        block_target: u32,
+        txs: &[Transaction],
+        tree: &mut MerkleTree,
+        verify_fees: bool,
+    ) -> Result<(u64, u64)> {
+        if !self.is_parallel() || txs.len() < 2 {
+            return verify_transactions(
+                overlay,
+                verifying_block_height,
+                block_target,
+                txs,
+                tree,
+                verify_fees,
+            )
+            .await
+        }
+
+        let waves = self.schedule(overlay, verifying_block_height, block_target, txs).await;
+        if waves.len() == txs.len() {
+            debug!(target: "validator::parallel::verify_transactions", "No independent transactions found, verifying in sequence");
+            return verify_transactions(
+                overlay,
+                verifying_block_height,
+                block_target,
+                txs,
+                tree,
+                verify_fees,
+            )
+            .await
+        }
+        debug!(target: "validator::parallel::verify_transactions", "Verifying {} transactions in {} waves", txs.len(), waves.len());
+
+        overlay.lock().unwrap().checkpoint();
+        match self
+            .verify_waves(overlay, verifying_block_height, block_target, txs, &waves, verify_fees)
+            .await?
+        {
+            Some(gas) => {
+                for tx in txs {
+                    append_tx_to_merkle_tree(tree, tx);
+                }
+                Ok(gas)
+            }
+            None => {
+                warn!(target: "validator::parallel::verify_transactions", "Conflict detected, falling back to sequential verification");
+                overlay.lock().unwrap().revert_to_checkpoint()?;
+                verify_transactions(
+                    overlay,
+                    verifying_block_height,
+                    block_target,
+                    txs,
+                    tree,
+                    verify_fees,
+                )
+                .await
+            }
+        }
+    }
+
+    /// Verify and apply given waves in order. Returns `None` if any transaction
+    /// failed, its metadata changed, or the gas limit got exceeded, leaving the
+    /// overlay dirty.
+    async fn verify_waves(
+        &self,
+        overlay: &BlockchainOverlayPtr,
+        verifying_block_height: u32,
+        block_target: u32,
+        txs: &[Transaction],
+        waves: &[Vec<usize>],
+        verify_fees: bool,
+    ) -> Result<Option<(u64, u64)>> {
+        // Verifying keys are shared by the whole set, like the sequential
+        // verification does, so circuits get charged the same gas.
+        let mut vks: HashMap<[u8; 32], HashMap<String, VerifyingKey>> = HashMap::new();
+        for tx in txs {
+            for call in &tx.calls {
+                vks.insert(call.data.contract_id.to_bytes(), HashMap::new());
+            }
+        }
+
+        // Transactions hashes are appended by the caller on success
+        let mut scratch_tree = MerkleTree::new(1);
+        let mut total_gas_used = 0;
+        let mut total_gas_paid = 0;
+
+        for wave in waves {
+            // Single transaction waves are verified as usual
+            if wave.len() == 1 {
+                let tx = &txs[wave[0]];
+                let gas_data = match verify_transaction(
+                    overlay,
+                    verifying_block_height,
+                    block_target,
+                    tx,
+                    &mut scratch_tree,
+                    &mut vks,
+                    verify_fees,
+                )
+                .await
+                {
+                    Ok(gas_data) => gas_data,
+                    Err(e) => {
+                        debug!(target: "validator::parallel::verify_waves", "Transaction {} verification failed: {}", tx.hash(), e);
+                        return Ok(None)
+                    }
+                };
+
+                total_gas_used += gas_data.total_gas_used();
+                if total_gas_used > GAS_LIMIT_UNPROPOSED_TXS {
+                    return Ok(None)
+                }
+                total_gas_paid += gas_data.paid;
+                continue
+            }
+
+            for chunk in wave.chunks(self.workers) {
+                // Evaluate the metadata and verify the proofs of each transaction
+                // on the blocking thread pool, only reading the overlay.
+                let tasks: Vec<_> = chunk
+                    .iter()
+                    .map(|index| {
+                        let overlay = overlay.clone();
+                        let tx = txs[*index].clone();
+                        smol::unblock(move || {
+                            smol::block_on(speculate(
+                                &overlay,
+                                verifying_block_height,
+                                block_target,
+                                &tx,
+                            ))
+                        })
+                    })
+                    .collect();
+
+                // Wait for all of them before touching the overlay
+                let mut results = Vec::with_capacity(tasks.len());
+                for task in tasks {
+                    results.push(task.await);
+                }
+
+                // Execute and apply the transactions in block order
+                for (index, result) in chunk.iter().zip(results) {
+                    let tx = &txs[*index];
+                    let metadata = match result {
+                        Ok(metadata) => metadata,
+                        Err(e) => {
+                            debug!(target: "validator::parallel::verify_waves", "Transaction {} speculative verification failed: {}", tx.hash(), e);
+                            return Ok(None)
+                        }
+                    };
+
+                    let (gas_data, executed) = match execute_transaction(
+                        overlay,
+                        verifying_block_height,
+                        block_target,
+                        tx,
+                        &mut vks,
+                        verify_fees,
+                    )
+                    .await
+                    {
+                        Ok(v) => v,
+                        Err(e) => {
+                            debug!(target: "validator::parallel::verify_waves", "Transaction {} execution failed: {}", tx.hash(), e);
+                            return Ok(None)
+                        }
+                    };
+
+                    // Proofs were verified against the speculated metadata
+                    if executed != metadata {
+                        debug!(target: "validator::parallel::verify_waves", "Transaction {} metadata changed after speculation", tx.hash());
+                        return Ok(None)
+                    }
+
+                    // Check gas limit in block order, like the sequential verification does
+                    total_gas_used += gas_data.total_gas_used();
+                    if total_gas_used > GAS_LIMIT_UNPROPOSED_TXS {
+                        return Ok(None)
+                    }
+                    total_gas_paid += gas_data.paid;
+                }
+            }
+        }
+
+        Ok(Some((total_gas_used, total_gas_paid)))
+    }
+}
+
+/// State access a transaction declares through its calls metadata
+struct Access {
+    /// Hashes of the calls ZK proofs public inputs, bound to their contract
+    keys: Vec<[u8; 32]>,
+    /// Whether the metadata looked up contract state, so earlier
+    /// transactions can change it
+    reads_state: bool,
+}
+
+impl Access {
+    /// Derive the access a transaction declares from its calls metadata.
+    /// Returns `None` if it looked up contract state without declaring
+    /// any inputs, so its dependencies are unknown.
+    fn declared(tx: &Transaction, metadata: &TxMetadata, reads_state: bool) -> Option<Self> {
+        let mut keys = vec![];
+        for (call, zkp_pub) in tx.calls.iter().zip(&metadata.zkp_table) {
+            let contract_id = call.data.contract_id.to_bytes();
+            for (_, public_inputs) in zkp_pub {
+                for input in public_inputs {
+                    let mut hasher = blake3::Hasher::new();
+                    hasher.update(&contract_id);
+                    hasher.update(&input.to_repr());
+                    keys.push(*hasher.finalize().as_bytes());
+                }
+            }
+        }
+
+        if reads_state && keys.is_empty() {
+            return None
+        }
+
+        Some(Self { keys, reads_state })
+    }
+}
+
+/// Split transactions into waves of consecutive indexes, given their declared
+/// access. A transaction joins the current wave unless its metadata looked up
+/// state and it declares an input of the wave, while applying the waves in
+/// order retains the block order. Transactions with unknown access act as
+/// barriers, getting their own wave.
+fn waves(declared: &[Option<Access>]) -> Vec<Vec<usize>> {
+    let mut waves: Vec<Vec<usize>> = vec![];
+    // Keys declared by the current wave, or `None` after a barrier
+    let mut wave_keys: Option<HashSet<[u8; 32]>> = None;
+
+    for (index, access) in declared.iter().enumerate() {
+        let Some(access) = access else {
+            waves.push(vec![index]);
+            wave_keys = None;
+            continue
+        };
+
+        match wave_keys {
+            Some(ref mut keys)
+                if !access.reads_state || !access.keys.iter().any(|k| keys.contains(k)) =>
+            {
+                waves.last_mut().unwrap().push(index);
+                keys.extend(&access.keys);
+            }
+            _ => {
+                waves.push(vec![index]);
+                wave_keys = Some(access.keys.iter().copied().collect());
+            }
+        }
+    }
+
+    waves
+}
+
+/// Evaluate the `metadata` function of every call of given [`Transaction`]
+/// over the provided overlay, without executing them. The flag signals
+/// whether any of them looked up contract state.
+async fn transaction_metadata(
+    overlay: &BlockchainOverlayPtr,
+    verifying_block_height: u32,
+    block_target: u32,
+    tx: &Transaction,
+) -> Result<(TxMetadata, bool)> {
+    let tx_hash = tx.hash();
+
+    let mut payload = vec![];
+    tx.calls.encode_async(&mut payload).await?;
+
+    let mut zkp_table = Vec::with_capacity(tx.calls.len());
+    let mut sig_table = Vec::with_capacity(tx.calls.len());
+    let mut reads_state = false;
+    for (idx, call) in tx.calls.iter().enumerate() {
+        let wasm = overlay.lock().unwrap().contracts.get(call.data.contract_id)?;
+        let mut runtime = Runtime::new(
+            &wasm,
+            overlay.clone(),
+            call.data.contract_id,
+            verifying_block_height,
+            block_target,
+            tx_hash,
+            idx as u8,
+        )?;
+        let metadata = runtime.metadata(&payload)?;
+        reads_state |= runtime.accessed_state();
+
+        let mut decoder = Cursor::new(&metadata);
+        let zkp_pub: Vec<(String, Vec<pallas::Base>)> =
+            AsyncDecodable::decode_async(&mut decoder).await?;
+        let sig_pub: Vec<PublicKey> = AsyncDecodable::decode_async(&mut decoder).await?;
+        if decoder.position() != metadata.len() as u64 {
+            return Err(TxVerifyFailed::ErroneousTxs(vec![tx.clone()]).into())
+        }
+
+        zkp_table.push(zkp_pub);
+        sig_table.push(sig_pub);
+    }
+
+    Ok((TxMetadata { zkp_table, sig_table }, reads_state))
+}
+
+/// Speculatively verify given [`Transaction`] signatures and ZK proofs against
+/// the metadata its calls declare over the provided overlay, returning it.
+async fn speculate(
+    overlay: &BlockchainOverlayPtr,
+    verifying_block_height: u32,
+    block_target: u32,
+    tx: &Transaction,
+) -> Result<TxMetadata> {
+    let (metadata, _) =
+        transaction_metadata(overlay, verifying_block_height, block_target, tx).await?;
+
+    // Malformed proof vectors are left to the sequential verification
+    if tx.proofs.len() != tx.calls.len() ||
+        tx.proofs.iter().zip(&metadata.zkp_table).any(|(p, z)| p.len() != z.len())
+    {
+        return Err(TxVerifyFailed::InvalidZkProof.into())
+    }
+
+    let mut vks: HashMap<[u8; 32], HashMap<String, VerifyingKey>> = HashMap::new();
+    for (call, zkp_pub) in tx.calls.iter().zip(&metadata.zkp_table) {
+        let contract_vks = vks.entry(call.data.contract_id.to_bytes()).or_default();
+        for (zkas_ns, _) in zkp_pub {
+            if contract_vks.contains_key(zkas_ns) {
+                continue
+            }
+            let (_, vk) =
+                overlay.lock().unwrap().contracts.get_zkas(&call.data.contract_id, zkas_ns)?;
+            contract_vks.insert(zkas_ns.clone(), vk);
+        }
+    }
+
+    verify_transaction_proofs(tx, &vks, metadata.clone()).await?;
+
+    Ok(metadata)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tx_waves() {
+        let access = |bytes: &[u8], reads_state| {
+            Some(Access { keys: bytes.iter().map(|b| [*b; 32]).collect(), reads_state })
+        };
+        let declared = vec![
+            access(&[0, 1], true),
+            access(&[2], true),
+            access(&[1, 3], true),
+            access(&[4], false),
+            access(&[3], true),
+            None,
+            access(&[0], false),
+            access(&[0], false),
+            access(&[5], true),
+            access(&[0, 6], true),
+        ];
+
+        // Transactions looking up state start a new wave when they declare
+        // an input of the current one, state independent ones always join
+        // it, and unknown ones act as barriers
+        assert_eq!(
+            waves(&declared),
+            vec![vec![0, 1], vec![2, 3], vec![4], vec![5], vec![6, 7, 8], vec![9]]
+        );
+
+        // A single worker disables parallel verification
+        assert!(!TxScheduler::with_workers(1).is_parallel());
+        assert!(!TxScheduler::with_workers(0).is_parallel());
+        assert!(TxScheduler::with_workers(2).is_parallel());
+    }
+}
@@ -18,14 +18,14 @@
 
 use std::{
     sync::{
-        atomic::{AtomicBool, AtomicU64, Ordering},
+        atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering},
         Arc,
     },
     thread,
-    time::Instant,
+    time::{Duration, Instant},
 };
 
-use darkfi_sdk::num_traits::{One, Zero};
+use darkfi_sdk::num_traits::{One, ToPrimitive, Zero};
 use log::debug;
 use num_bigint::BigUint;
 use randomx::{RandomXCache, RandomXDataset, RandomXFlags, RandomXVM};
@@ -70,6 +70,8 @@ const CUT_END: usize = 660;
 const BLOCKCHAIN_TIMESTAMP_CHECK_WINDOW: usize = 60;
 /// Time limit in the future of what blocks can be
 const BLOCK_FUTURE_TIME_LIMIT: Timestamp = Timestamp::from_u64(60 * 60 * 2);
+/// Max amount of blocks a difficulty history can span
+pub const DIFFICULTY_HISTORY_MAX: usize = DIFFICULTY_WINDOW;
 
 /// This struct represents the information required by the PoW algorithm
 #[derive(Clone)]
@@ -334,12 +336,92 @@ impl std::fmt::Display for PoWModule {
     }
 }
 
+/// Difficulty of a single block, along with its solve time
+#[derive(Clone, Debug)]
+pub struct DifficultySample {
+    /// Block height number
+    pub height: u32,
+    /// Block creation timestamp
+    pub timestamp: Timestamp,
+    /// Height difficulty
+    pub difficulty: BigUint,
+    /// Seconds since previous block, if known
+    pub solve_time: Option<u64>,
+}
+
+/// Recent network difficulty history
+#[derive(Clone, Debug)]
+pub struct DifficultyHistory {
+    /// Per-block difficulties, in height order
+    pub samples: Vec<DifficultySample>,
+    /// Network hashrate estimate over the samples, in hashes per second
+    pub hashrate: f64,
+}
+
+/// Build the difficulty history of the last `window` blocks of provided
+/// blockchain, capped at [`DIFFICULTY_HISTORY_MAX`]. The hashrate estimate
+/// is the total work of the window divided by the time it took, which
+/// smooths out individual solve time variance.
+pub fn difficulty_history(blockchain: &Blockchain, window: usize) -> Result<DifficultyHistory> {
+    let window = window.min(DIFFICULTY_HISTORY_MAX);
+
+    // Grab an extra record so the first sample has a solve time too
+    let records = blockchain.blocks.get_last_n_difficulties(window + 1)?;
+    let skip = records.len().saturating_sub(window);
+
+    let mut samples = Vec::with_capacity(window);
+    let mut work = BigUint::zero();
+    let mut elapsed = 0;
+    for (i, record) in records.iter().enumerate().skip(skip) {
+        // Timestamps are only checked against the median of previous ones,
+        // so they are not guaranteed to be increasing.
+        let solve_time = match i {
+            0 => None,
+            _ => Some(record.timestamp.inner().saturating_sub(records[i - 1].timestamp.inner())),
+        };
+
+        if let Some(t) = solve_time {
+            work += &record.difficulty;
+            elapsed += t;
+        }
+
+        samples.push(DifficultySample {
+            height: record.height,
+            timestamp: record.timestamp,
+            difficulty: record.difficulty.clone(),
+            solve_time,
+        });
+    }
+
+    let hashrate = match elapsed {
+        0 => 0.0,
+        _ => work.to_f64().unwrap_or(f64::MAX) / elapsed as f64,
+    };
+
+    Ok(DifficultyHistory { samples, hashrate })
+}
+
 /// Mine provided block, based on provided PoW module next mine target.
 pub fn mine_block(
     target: &BigUint,
     miner_block: &mut BlockInfo,
     threads: usize,
     stop_signal: &Receiver<()>,
+) -> Result<()> {
+    let active_threads = Arc::new(AtomicUsize::new(threads));
+    mine_block_throttled(target, miner_block, threads, stop_signal, &active_threads)
+}
+
+/// Mine provided block, based on provided PoW module next mine target,
+/// allowing the amount of mining threads to be throttled while mining.
+/// Threads with an index greater or equal to `active_threads` pause
+/// until it gets increased again, so setting it to zero pauses mining.
+pub fn mine_block_throttled(
+    target: &BigUint,
+    miner_block: &mut BlockInfo,
+    threads: usize,
+    stop_signal: &Receiver<()>,
+    active_threads: &Arc<AtomicUsize>,
 ) -> Result<()> {
     let miner_setup = Instant::now();
 
@@ -365,6 +447,7 @@ pub fn mine_block(
         let found_nonce = Arc::clone(&found_nonce);
         let dataset = Arc::clone(&dataset);
         let stop_signal = stop_signal.clone();
+        let active_threads = Arc::clone(active_threads);
 
         handles.push(thread::spawn(move || {
             debug!(target: "validator::pow::mine_block", "[MINER] Initializing RandomX VM #{}...", t);
@@ -377,6 +460,15 @@ pub fn mine_block(
                     break
                 }
 
+                // Check if this thread is throttled
+                if t >= active_threads.load(Ordering::Relaxed) as u64 {
+                    if found_block.load(Ordering::SeqCst) {
+                        break
+                    }
+                    thread::sleep(Duration::from_millis(100));
+                    continue
+                }
+
                 block.header.nonce = miner_nonce;
                 if found_block.load(Ordering::SeqCst) {
                     debug!(target: "validator::pow::mine_block", "[MINER] Block found, thread #{} exiting", t);
@@ -431,11 +523,14 @@ mod tests {
     use sled_overlay::sled;
 
     use crate::{
-        blockchain::{BlockInfo, Blockchain},
+        blockchain::{
+            block_store::{BlockDifficulty, BlockRanks},
+            BlockInfo, Blockchain,
+        },
         Result,
     };
 
-    use super::PoWModule;
+    use super::{difficulty_history, PoWModule, DIFFICULTY_HISTORY_MAX};
 
     const DEFAULT_TEST_THREADS: usize = 2;
     const DEFAULT_TEST_DIFFICULTY_TARGET: u32 = 120;
@@ -495,4 +590,40 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn test_difficulty_history() -> Result<()> {
+        let sled_db = sled::Config::new().temporary(true).open()?;
+        let blockchain = Blockchain::new(&sled_db)?;
+
+        // Blocks every 100 seconds, at difficulty 1000 * height
+        let ranks = BlockRanks::new(0u8.into(), 0u8.into(), 0u8.into(), 0u8.into());
+        let mut records = vec![];
+        let mut cummulative = BigUint::from(0u8);
+        for height in 0..10u32 {
+            let difficulty = BigUint::from(1000 * height);
+            cummulative += &difficulty;
+            records.push(BlockDifficulty::new(
+                height,
+                (height as u64 * 100).into(),
+                difficulty,
+                cummulative.clone(),
+                ranks.clone(),
+            ));
+        }
+        blockchain.blocks.insert_difficulty(&records)?;
+
+        let history = difficulty_history(&blockchain, 4)?;
+        assert_eq!(history.samples.len(), 4);
+        assert_eq!(history.samples[0].height, 6);
+        assert!(history.samples.iter().all(|s| s.solve_time == Some(100)));
+        assert_eq!(history.hashrate, (6000 + 7000 + 8000 + 9000) as f64 / 400.0);
+
+        // The genesis block has no solve time
+        let history = difficulty_history(&blockchain, DIFFICULTY_HISTORY_MAX)?;
+        assert_eq!(history.samples.len(), 10);
+        assert_eq!(history.samples[0].solve_time, None);
+
+        Ok(())
+    }
 }
@@ -0,0 +1,467 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2025 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+//! Blockchain snapshots, used for fast bootstrap.
+//!
+//! A snapshot is a deterministic export of the sled trees holding the
+//! canonical blocks up to a given block height: headers, blocks, their
+//! order and transactions. The export is split into fixed size chunks,
+//! each identified by its hash, and described by a [`SnapshotManifest`],
+//! so nodes can download the chunks in bulk from multiple peers and
+//! verify each of them independently.
+//!
+//! Since headers don't commit to the contracts state, snapshots carry no
+//! state at all. The importing node replays all the snapshot blocks with
+//! full verification, deriving the state itself, so it never has to
+//! trust the peers serving it. Snapshots are streamed from and into
+//! the chunk stores, so they are never held in memory as a whole.
+
+use std::io::{Error as IoError, ErrorKind, Read, Result as IoResult, Write};
+
+use darkfi_serial::{
+    deserialize, serialize, Decodable, Encodable, SerialDecodable, SerialEncodable, VarInt,
+};
+use log::info;
+use sled_overlay::sled;
+
+use crate::{
+    blockchain::{
+        block_store::{SLED_BLOCK_ORDER_TREE, SLED_BLOCK_TREE},
+        header_store::SLED_HEADER_TREE,
+        tx_store::SLED_TX_TREE,
+        Blockchain, HeaderHash,
+    },
+    Error, Result,
+};
+
+/// Size of each snapshot chunk, in bytes
+pub const SNAPSHOT_CHUNK_SIZE: usize = 512 * 1024;
+
+/// Amount of snapshots a node keeps around to serve to its peers
+pub const SNAPSHOTS_KEPT: usize = 2;
+
+/// Sled tree storing the snapshot manifests, keyed by block height
+pub const SLED_SNAPSHOT_MANIFEST_TREE: &[u8] = b"_snapshot_manifests";
+
+/// Sled tree storing the snapshot chunks, keyed by their hash
+pub const SLED_SNAPSHOT_CHUNK_TREE: &[u8] = b"_snapshot_chunks";
+
+/// Amount of blocks replayed at once while importing a snapshot
+pub const SNAPSHOT_REPLAY_BATCH: u32 = 64;
+
+/// Amount of records written to a sled tree at once while decoding a snapshot
+const SNAPSHOT_DECODE_BATCH: usize = 1024;
+
+/// Fixed sled trees included in a snapshot, in export order
+const SNAPSHOT_TREES: &[&[u8]] =
+    &[SLED_HEADER_TREE, SLED_BLOCK_TREE, SLED_BLOCK_ORDER_TREE, SLED_TX_TREE];
+
+/// Structure describing a blockchain snapshot
+#[derive(Clone, Debug, PartialEq, Eq, SerialEncodable, SerialDecodable)]
+pub struct SnapshotManifest {
+    /// Block height the snapshot was taken at
+    pub height: u32,
+    /// Block hash the snapshot was taken at
+    pub block: HeaderHash,
+    /// Total size of the snapshot, in bytes
+    pub size: u64,
+    /// Hashes of the snapshot chunks, in order
+    pub chunks: Vec<blake3::Hash>,
+}
+
+impl SnapshotManifest {
+    /// Compute the manifest hash, committing to all its chunks.
+    pub fn hash(&self) -> blake3::Hash {
+        let mut hasher = blake3::Hasher::new();
+        // Blake3 hasher .update() method never fails.
+        self.encode(&mut hasher).expect("blake3 hasher");
+        hasher.finalize()
+    }
+
+    /// Verify provided chunk is the one at given index of the manifest.
+    pub fn verify_chunk(&self, index: usize, chunk: &[u8]) -> bool {
+        match self.chunks.get(index) {
+            Some(hash) => chunk.len() <= SNAPSHOT_CHUNK_SIZE && &blake3::hash(chunk) == hash,
+            None => false,
+        }
+    }
+}
+
+/// Auxiliary function to map a sled error into an IO one.
+fn io_error(e: sled::Error) -> IoError {
+    IoError::new(ErrorKind::Other, e)
+}
+
+/// Writer splitting a snapshot stream into chunks, storing each of them
+/// in a sled tree keyed by its hash, so only a single chunk is ever
+/// buffered in memory.
+struct ChunkWriter<'a> {
+    /// Tree the chunks get stored in
+    tree: &'a sled::Tree,
+    /// Current chunk buffer
+    buf: Vec<u8>,
+    /// Hashes of the stored chunks, in order
+    chunks: Vec<blake3::Hash>,
+    /// Total bytes written
+    size: u64,
+}
+
+impl<'a> ChunkWriter<'a> {
+    fn new(tree: &'a sled::Tree) -> Self {
+        Self { tree, buf: Vec::with_capacity(SNAPSHOT_CHUNK_SIZE), chunks: vec![], size: 0 }
+    }
+
+    /// Store the first `len` buffered bytes as the next chunk.
+    fn store_chunk(&mut self, len: usize) -> IoResult<()> {
+        let chunk: Vec<u8> = self.buf.drain(..len).collect();
+        let hash = blake3::hash(&chunk);
+        self.tree.insert(hash.as_bytes(), chunk).map_err(io_error)?;
+        self.chunks.push(hash);
+        Ok(())
+    }
+
+    /// Store any remaining buffered bytes, returning the chunks hashes
+    /// along with the total size of the stream.
+    fn finish(mut self) -> IoResult<(Vec<blake3::Hash>, u64)> {
+        if !self.buf.is_empty() {
+            self.store_chunk(self.buf.len())?;
+        }
+        Ok((self.chunks, self.size))
+    }
+}
+
+impl Write for ChunkWriter<'_> {
+    fn write(&mut self, data: &[u8]) -> IoResult<usize> {
+        self.buf.extend_from_slice(data);
+        self.size += data.len() as u64;
+        while self.buf.len() >= SNAPSHOT_CHUNK_SIZE {
+            self.store_chunk(SNAPSHOT_CHUNK_SIZE)?;
+        }
+        Ok(data.len())
+    }
+
+    fn flush(&mut self) -> IoResult<()> {
+        Ok(())
+    }
+}
+
+/// Reader streaming a snapshot back from its chunks stored in a sled
+/// tree, verifying each chunk against the manifest as it gets loaded.
+struct ChunkReader<'a> {
+    /// Manifest of the snapshot
+    manifest: &'a SnapshotManifest,
+    /// Tree the chunks are stored in
+    tree: &'a sled::Tree,
+    /// Index of the next chunk to load
+    index: usize,
+    /// Currently loaded chunk
+    chunk: Vec<u8>,
+    /// Read position in the loaded chunk
+    pos: usize,
+    /// Total bytes read
+    read: u64,
+}
+
+impl<'a> ChunkReader<'a> {
+    fn new(manifest: &'a SnapshotManifest, tree: &'a sled::Tree) -> Self {
+        Self { manifest, tree, index: 0, chunk: vec![], pos: 0, read: 0 }
+    }
+
+    /// Check the whole snapshot stream has been consumed.
+    fn is_exhausted(&self) -> bool {
+        self.index == self.manifest.chunks.len() &&
+            self.pos == self.chunk.len() &&
+            self.read == self.manifest.size
+    }
+}
+
+impl Read for ChunkReader<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> IoResult<usize> {
+        // Load the next non-empty chunk, if we consumed the current one
+        while self.pos == self.chunk.len() {
+            if self.index == self.manifest.chunks.len() {
+                return Ok(0)
+            }
+
+            let hash = &self.manifest.chunks[self.index];
+            let Some(chunk) = self.tree.get(hash.as_bytes()).map_err(io_error)? else {
+                return Err(IoError::new(ErrorKind::NotFound, "Snapshot chunk missing"))
+            };
+            if !self.manifest.verify_chunk(self.index, &chunk) {
+                return Err(IoError::new(ErrorKind::InvalidData, "Snapshot chunk hash mismatch"))
+            }
+
+            self.chunk = chunk.to_vec();
+            self.pos = 0;
+            self.index += 1;
+        }
+
+        let len = buf.len().min(self.chunk.len() - self.pos);
+        buf[..len].copy_from_slice(&self.chunk[self.pos..self.pos + len]);
+        self.pos += len;
+        self.read += len as u64;
+        Ok(len)
+    }
+}
+
+/// Auxiliary function to encode a byte slice the same way as a `Vec<u8>`,
+/// without copying it.
+fn encode_bytes<W: Write>(bytes: &[u8], w: &mut W) -> IoResult<()> {
+    VarInt(bytes.len() as u64).encode(w)?;
+    w.write_all(bytes)
+}
+
+/// Auxiliary function to decode a `Vec<u8>`, rejecting lengths over
+/// provided maximum before allocating it.
+fn decode_bytes<R: Read>(r: &mut R, max: u64) -> IoResult<Vec<u8>> {
+    let len = VarInt::decode(r)?.0;
+    if len > max {
+        return Err(IoError::new(ErrorKind::InvalidData, "Snapshot record too large"))
+    }
+    let mut bytes = vec![0u8; len as usize];
+    r.read_exact(&mut bytes)?;
+    Ok(bytes)
+}
+
+/// Export the canonical blocks of provided blockchain, streaming the
+/// snapshot chunks into given sled tree, and return its manifest.
+/// Note: caller must ensure no canonical writes happen concurrently.
+pub fn export_snapshot(blockchain: &Blockchain, chunks: &sled::Tree) -> Result<SnapshotManifest> {
+    let (height, block) = blockchain.last()?;
+
+    let mut writer = ChunkWriter::new(chunks);
+    VarInt(SNAPSHOT_TREES.len() as u64).encode(&mut writer)?;
+    for name in SNAPSHOT_TREES {
+        let tree = blockchain.sled_db.open_tree(name)?;
+        encode_bytes(name, &mut writer)?;
+        VarInt(tree.len() as u64).encode(&mut writer)?;
+        for record in tree.iter() {
+            let (key, value) = record?;
+            encode_bytes(&key, &mut writer)?;
+            encode_bytes(&value, &mut writer)?;
+        }
+    }
+    let (chunks, size) = writer.finish()?;
+
+    Ok(SnapshotManifest { height, block, size, chunks })
+}
+
+/// Decode the snapshot described by provided manifest, streaming its
+/// chunks from given sled tree into the blockchain trees of given
+/// scratch database. Each chunk is verified against the manifest as
+/// it's read. Returns the snapshot blockchain, whose blocks must then
+/// be replayed through [`crate::validator::Validator::replay_snapshot`],
+/// as nothing in it is verified yet.
+pub fn decode_snapshot(
+    manifest: &SnapshotManifest,
+    chunks: &sled::Tree,
+    db: &sled::Db,
+) -> Result<Blockchain> {
+    let mut reader = ChunkReader::new(manifest, chunks);
+
+    if VarInt::decode(&mut reader)?.0 != SNAPSHOT_TREES.len() as u64 {
+        return Err(Error::SnapshotInvalid(String::from("Trees count mismatch")))
+    }
+
+    for name in SNAPSHOT_TREES {
+        if decode_bytes(&mut reader, manifest.size)? != *name {
+            return Err(Error::SnapshotInvalid(String::from("Tree name mismatch")))
+        }
+
+        let tree = db.open_tree(name)?;
+        let records = VarInt::decode(&mut reader)?.0;
+        let mut batch = sled::Batch::default();
+        for record in 1..=records {
+            let key = decode_bytes(&mut reader, manifest.size)?;
+            let value = decode_bytes(&mut reader, manifest.size)?;
+            batch.insert(key, value);
+            if record as usize % SNAPSHOT_DECODE_BATCH == 0 {
+                tree.apply_batch(std::mem::take(&mut batch))?;
+            }
+        }
+        tree.apply_batch(batch)?;
+    }
+
+    if !reader.is_exhausted() {
+        return Err(Error::SnapshotInvalid(String::from("Snapshot size mismatch")))
+    }
+
+    // Verify the snapshot ends at the manifest block
+    let blockchain = Blockchain::new(db)?;
+    if blockchain.last()? != (manifest.height, manifest.block) {
+        return Err(Error::SnapshotInvalid(String::from(
+            "Snapshot tip doesn't match manifest block",
+        )))
+    }
+
+    Ok(blockchain)
+}
+
+/// Structure storing the blockchain snapshots a node serves to its peers.
+#[derive(Clone)]
+pub struct SnapshotStore {
+    /// The `sled` tree storing the snapshot manifests, keyed by block height
+    pub manifests: sled::Tree,
+    /// The `sled` tree storing the snapshot chunks, keyed by their hash
+    pub chunks: sled::Tree,
+}
+
+impl SnapshotStore {
+    /// Opens a new or existing `SnapshotStore` on the given sled database.
+    pub fn new(db: &sled::Db) -> Result<Self> {
+        let manifests = db.open_tree(SLED_SNAPSHOT_MANIFEST_TREE)?;
+        let chunks = db.open_tree(SLED_SNAPSHOT_CHUNK_TREE)?;
+        Ok(Self { manifests, chunks })
+    }
+
+    /// Export a snapshot of the current canonical blocks of provided blockchain
+    /// and store it, pruning older ones so only [`SNAPSHOTS_KEPT`] remain.
+    pub fn create(&self, blockchain: &Blockchain) -> Result<SnapshotManifest> {
+        let manifest = export_snapshot(blockchain, &self.chunks)?;
+        self.manifests.insert(manifest.height.to_be_bytes(), serialize(&manifest))?;
+
+        self.prune()?;
+
+        info!(target: "validator::snapshot::create", "Created blockchain snapshot: {} - {} ({} chunks)", manifest.height, manifest.block, manifest.chunks.len());
+        Ok(manifest)
+    }
+
+    /// Remove all but the last [`SNAPSHOTS_KEPT`] snapshots, along with
+    /// any chunks no longer referenced by them.
+    fn prune(&self) -> Result<()> {
+        let mut manifests = self.get_all()?;
+        if manifests.len() <= SNAPSHOTS_KEPT {
+            return Ok(())
+        }
+
+        let kept = manifests.split_off(manifests.len() - SNAPSHOTS_KEPT);
+        let referenced: HashSet<blake3::Hash> =
+            kept.iter().flat_map(|m| m.chunks.iter().copied()).collect();
+
+        for manifest in manifests {
+            for chunk in &manifest.chunks {
+                if !referenced.contains(chunk) {
+                    self.chunks.remove(chunk.as_bytes())?;
+                }
+            }
+            self.manifests.remove(manifest.height.to_be_bytes())?;
+        }
+
+        Ok(())
+    }
+
+    /// Retrieve all stored snapshot manifests, ordered by height.
+    pub fn get_all(&self) -> Result<Vec<SnapshotManifest>> {
+        let mut manifests = vec![];
+        for record in self.manifests.iter() {
+            let (_, manifest) = record?;
+            manifests.push(deserialize(&manifest)?);
+        }
+        Ok(manifests)
+    }
+
+    /// Retrieve the most recent stored snapshot manifest, if any.
+    pub fn latest(&self) -> Result<Option<SnapshotManifest>> {
+        match self.manifests.last()? {
+            Some((_, manifest)) => Ok(Some(deserialize(&manifest)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Retrieve a stored snapshot chunk by its hash, if it exists.
+    pub fn get_chunk(&self, hash: &blake3::Hash) -> Result<Option<Vec<u8>>> {
+        Ok(self.chunks.get(hash.as_bytes())?.map(|c| c.to_vec()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_snapshot_export_decode() -> Result<()> {
+        // Build a source blockchain with some canonical and local records
+        let source_db = sled::Config::new().temporary(true).open()?;
+        let source = Blockchain::new(&source_db)?;
+        let hashes = [HeaderHash::new([1; 32]), HeaderHash::new([2; 32])];
+        source.blocks.insert_order(&[0, 1], &hashes)?;
+        source.blocks.state_diff.insert(1_u32.to_be_bytes(), vec![0xff])?;
+
+        // Store a few snapshots so pruning kicks in
+        let store = SnapshotStore::new(&source_db)?;
+        for _ in 0..=SNAPSHOTS_KEPT {
+            store.create(&source)?;
+        }
+        let manifest = store.latest()?.unwrap();
+        assert_eq!(store.get_all()?.len(), 1);
+        assert_eq!(manifest.height, 1);
+        assert_eq!(manifest.block, hashes[1]);
+
+        // Decode it into a fresh database, leaving node local data out
+        let target_db = sled::Config::new().temporary(true).open()?;
+        let target = decode_snapshot(&manifest, &store.chunks, &target_db)?;
+        assert_eq!(target.last()?, (1, hashes[1]));
+        assert!(target.blocks.state_diff.is_empty());
+
+        // Same blocks produce the same manifest
+        let scratch_db = sled::Config::new().temporary(true).open()?;
+        let reexported = export_snapshot(&target, &scratch_db.open_tree("chunks")?)?;
+        assert_eq!(reexported.hash(), manifest.hash());
+
+        // Tampered chunks get rejected
+        let chunk = manifest.chunks[0];
+        let mut tampered = store.get_chunk(&chunk)?.unwrap();
+        tampered[0] ^= 1;
+        store.chunks.insert(chunk.as_bytes(), tampered)?;
+        let target_db = sled::Config::new().temporary(true).open()?;
+        assert!(decode_snapshot(&manifest, &store.chunks, &target_db).is_err());
+
+        // Missing chunks get rejected
+        store.chunks.remove(chunk.as_bytes())?;
+        let target_db = sled::Config::new().temporary(true).open()?;
+        assert!(decode_snapshot(&manifest, &store.chunks, &target_db).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_snapshot_chunking() -> Result<()> {
+        let db = sled::Config::new().temporary(true).open()?;
+        let tree = db.open_tree("chunks")?;
+
+        // Stream more than two chunks worth of data
+        let data: Vec<u8> = (0..2 * SNAPSHOT_CHUNK_SIZE + 42).map(|i| i as u8).collect();
+        let mut writer = ChunkWriter::new(&tree);
+        for piece in data.chunks(1000) {
+            writer.write_all(piece)?;
+        }
+        let (chunks, size) = writer.finish()?;
+        assert_eq!(chunks.len(), 3);
+        assert_eq!(size, data.len() as u64);
+
+        // Read it back
+        let manifest =
+            SnapshotManifest { height: 0, block: HeaderHash::new([0; 32]), size, chunks };
+        let mut reader = ChunkReader::new(&manifest, &tree);
+        let mut read = vec![];
+        reader.read_to_end(&mut read)?;
+        assert_eq!(read, data);
+        assert!(reader.is_exhausted());
+
+        Ok(())
+    }
+}
@@ -17,7 +17,10 @@
  */
 
 use darkfi_sdk::{
-    crypto::{DAO_CONTRACT_ID, DEPLOYOOOR_CONTRACT_ID, MONEY_CONTRACT_ID},
+    crypto::{
+        AUCTION_CONTRACT_ID, DAO_CONTRACT_ID, DARKNAME_CONTRACT_ID, DEPLOYOOOR_CONTRACT_ID,
+        MONEY_CONTRACT_ID, ORACLE_CONTRACT_ID,
+    },
     tx::TransactionHash,
 };
 use log::info;
@@ -57,6 +60,15 @@ pub async fn deploy_native_contracts(
     // The Deployooor contract uses an empty payload to deploy itself.
     let deployooor_contract_deploy_payload = vec![];
 
+    // The Darkname contract uses an empty payload to deploy itself.
+    let darkname_contract_deploy_payload = vec![];
+
+    // The Auction contract uses an empty payload to deploy itself.
+    let auction_contract_deploy_payload = vec![];
+
+    // The Oracle contract uses an empty payload to deploy itself.
+    let oracle_contract_deploy_payload = vec![];
+
     let native_contracts = vec![
         (
             "Money Contract",
@@ -76,6 +88,24 @@ pub async fn deploy_native_contracts(
             include_bytes!("../contract/deployooor/darkfi_deployooor_contract.wasm").to_vec(),
             deployooor_contract_deploy_payload,
         ),
+        (
+            "Darkname Contract",
+            *DARKNAME_CONTRACT_ID,
+            include_bytes!("../contract/darkname/darkfi_darkname_contract.wasm").to_vec(),
+            darkname_contract_deploy_payload,
+        ),
+        (
+            "Auction Contract",
+            *AUCTION_CONTRACT_ID,
+            include_bytes!("../contract/auction/darkfi_auction_contract.wasm").to_vec(),
+            auction_contract_deploy_payload,
+        ),
+        (
+            "Oracle Contract",
+            *ORACLE_CONTRACT_ID,
+            include_bytes!("../contract/oracle/darkfi_oracle_contract.wasm").to_vec(),
+            oracle_contract_deploy_payload,
+        ),
     ];
 
     // Grab last known block height to verify against next one.
@@ -41,7 +41,9 @@ use crate::{
     validator::{
         consensus::{Consensus, Fork, Proposal, GAS_LIMIT_UNPROPOSED_TXS},
         fees::{circuit_gas_use, GasData, PALLAS_SCHNORR_SIGNATURE_FEE},
+        parallel::TxScheduler,
         pow::PoWModule,
+        versionbits::{valid_block_version, VersionBits},
     },
     zk::VerifyingKey,
     Error, Result,
@@ -115,7 +117,7 @@ pub async fn verify_genesis_block(
 /// Validate provided block according to set rules.
 ///
 /// A block is considered valid when the following rules apply:
-///     1. Block version is correct for its height
+///     1. Block version is correct for its height, or signals soft-fork features on top of it
 ///     2. Parent hash is equal to the hash of the previous block
 ///     3. Block height increments previous block height by 1
 ///     4. Timestamp is valid based on PoWModule validation
@@ -123,7 +125,7 @@ pub async fn verify_genesis_block(
 /// Additional validity rules can be applied.
 pub fn validate_block(block: &BlockInfo, previous: &BlockInfo, module: &PoWModule) -> Result<()> {
     // Check block version (1)
-    if block.header.version != block_version(block.header.height) {
+    if !valid_block_version(block.header.version, block.header.height) {
         return Err(Error::BlockIsInvalid(block.hash().as_string()))
     }
 
@@ -177,6 +179,8 @@ pub async fn verify_block(
     module: &PoWModule,
     block: &BlockInfo,
     previous: &BlockInfo,
+    scheduler: &TxScheduler,
+    versionbits: &VersionBits,
     verify_fees: bool,
 ) -> Result<()> {
     let block_hash = block.hash();
@@ -190,6 +194,9 @@ pub async fn verify_block(
     // Validate block, using its previous
     validate_block(block, previous, module)?;
 
+    // Check block against the soft-fork deployments state
+    versionbits.check_block(overlay, block)?;
+
     // Verify transactions vector contains at least one(producers) transaction
     if block.txs.is_empty() {
         return Err(Error::BlockContainsNoTransactions(block_hash.as_string()))
@@ -198,15 +205,16 @@ pub async fn verify_block(
     // Verify transactions, exluding producer(last) one
     let mut tree = MerkleTree::new(1);
     let txs = &block.txs[..block.txs.len() - 1];
-    let e = verify_transactions(
-        overlay,
-        block.header.height,
-        module.target,
-        txs,
-        &mut tree,
-        verify_fees,
-    )
-    .await;
+    let e = scheduler
+        .verify_transactions(
+            overlay,
+            block.header.height,
+            module.target,
+            txs,
+            &mut tree,
+            verify_fees,
+        )
+        .await;
     if let Err(e) = e {
         warn!(
             target: "validator::verification::verify_block",
@@ -529,6 +537,16 @@ async fn apply_producer_transaction(
     Ok(signature_public_key)
 }
 
+/// Public inputs and keys a [`Transaction`] calls declared through their
+/// `metadata` function, used to verify its ZK proofs and signatures.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TxMetadata {
+    /// ZK proofs public inputs of each call, as `(zkas_ns, public_inputs)`
+    pub zkp_table: Vec<Vec<(String, Vec<pallas::Base>)>>,
+    /// Signature public keys of each call
+    pub sig_table: Vec<Vec<PublicKey>>,
+}
+
 /// Verify WASM execution, signatures, and ZK proofs for a given [`Transaction`],
 /// and apply it to the provided overlay. Additionally, append its hash to the
 /// provided Merkle tree.
@@ -544,6 +562,41 @@ pub async fn verify_transaction(
     let tx_hash = tx.hash();
     debug!(target: "validator::verification::verify_transaction", "Validating transaction {}", tx_hash);
 
+    let (gas_data, metadata) = execute_transaction(
+        overlay,
+        verifying_block_height,
+        block_target,
+        tx,
+        verifying_keys,
+        verify_fee,
+    )
+    .await?;
+
+    verify_transaction_proofs(tx, verifying_keys, metadata).await?;
+
+    // Append hash to merkle tree
+    append_tx_to_merkle_tree(tree, tx);
+
+    debug!(target: "validator::verification::verify_transaction", "The total gas used for transaction {}: {}", tx_hash, gas_data.total_gas_used());
+    debug!(target: "validator::verification::verify_transaction", "Transaction {} verified successfully", tx_hash);
+    Ok(gas_data)
+}
+
+/// Verify WASM execution and fees of a given [`Transaction`], and apply it to
+/// the provided overlay, without verifying its signatures and ZK proofs.
+/// Returns the transaction gas data, along with the metadata its proofs must
+/// be verified against. Verifying keys of the used circuits get inserted into
+/// the provided map.
+pub async fn execute_transaction(
+    overlay: &BlockchainOverlayPtr,
+    verifying_block_height: u32,
+    block_target: u32,
+    tx: &Transaction,
+    verifying_keys: &mut HashMap<[u8; 32], HashMap<String, VerifyingKey>>,
+    verify_fee: bool,
+) -> Result<(GasData, TxMetadata)> {
+    let tx_hash = tx.hash();
+
     // Create a FeeData instance to hold the calculated fee data
     let mut gas_data = GasData::default();
 
@@ -558,6 +611,16 @@ pub async fn verify_transaction(
         dark_forest_leaf_vec_integrity_check(&tx.calls, Some(MIN_TX_CALLS), Some(MAX_TX_CALLS))?;
     }
 
+    // Verify the transaction can be included at this height
+    if let Err(e) = tx.verify_validity_heights(verifying_block_height) {
+        error!(
+            target: "validator::verification::execute_transaction",
+            "[VALIDATOR] Transaction {} is not valid at height {}: {}",
+            tx_hash, verifying_block_height, e,
+        );
+        return Err(e)
+    }
+
     // Table of public inputs used for ZK proof verification
     let mut zkp_table = vec![];
     // Table of public keys used for signature verification
@@ -579,7 +642,7 @@ pub async fn verify_transaction(
 
         if !found_fee {
             error!(
-                target: "validator::verification::verify_transcation",
+                target: "validator::verification::execute_transaction",
                 "[VALIDATOR] Transaction {} does not contain fee payment call", tx_hash,
             );
             return Err(TxVerifyFailed::InvalidFee.into())
@@ -593,17 +656,17 @@ pub async fn verify_transaction(
     for (idx, call) in tx.calls.iter().enumerate() {
         // Transaction must not contain a Pow reward call
         if call.data.is_money_pow_reward() {
-            error!(target: "validator::verification::verify_transaction", "Reward transaction detected");
+            error!(target: "validator::verification::execute_transaction", "Reward transaction detected");
             return Err(TxVerifyFailed::ErroneousTxs(vec![tx.clone()]).into())
         }
 
-        debug!(target: "validator::verification::verify_transaction", "Executing contract call {}", idx);
+        debug!(target: "validator::verification::execute_transaction", "Executing contract call {}", idx);
 
         // Write the actual payload data
         let mut payload = vec![];
         tx.calls.encode_async(&mut payload).await?;
 
-        debug!(target: "validator::verification::verify_transaction", "Instantiating WASM runtime");
+        debug!(target: "validator::verification::execute_transaction", "Instantiating WASM runtime");
         let wasm = overlay.lock().unwrap().contracts.get(call.data.contract_id)?;
 
         let mut runtime = Runtime::new(
@@ -616,7 +679,7 @@ pub async fn verify_transaction(
             idx as u8,
         )?;
 
-        debug!(target: "validator::verification::verify_transaction", "Executing \"metadata\" call");
+        debug!(target: "validator::verification::execute_transaction", "Executing \"metadata\" call");
         let metadata = runtime.metadata(&payload)?;
 
         // Decode the metadata retrieved from the execution
@@ -629,17 +692,17 @@ pub async fn verify_transaction(
 
         if decoder.position() != metadata.len() as u64 {
             error!(
-                target: "validator::verification::verify_transaction",
+                target: "validator::verification::execute_transaction",
                 "[VALIDATOR] Failed decoding entire metadata buffer for {}:{}", tx_hash, idx,
             );
             return Err(TxVerifyFailed::ErroneousTxs(vec![tx.clone()]).into())
         }
 
-        debug!(target: "validator::verification::verify_transaction", "Successfully executed \"metadata\" call");
+        debug!(target: "validator::verification::execute_transaction", "Successfully executed \"metadata\" call");
 
         // Here we'll look up verifying keys and insert them into the per-contract map.
         // TODO: This vk map can potentially use a lot of RAM. Perhaps load keys on-demand at verification time?
-        debug!(target: "validator::verification::verify_transaction", "Performing VerifyingKey lookups from the sled db");
+        debug!(target: "validator::verification::execute_transaction", "Performing VerifyingKey lookups from the sled db");
         for (zkas_ns, _) in &zkp_pub {
             let inner_vk_map = verifying_keys.get_mut(&call.data.contract_id.to_bytes()).unwrap();
 
@@ -662,21 +725,21 @@ pub async fn verify_transaction(
 
         // After getting the metadata, we run the "exec" function with the same runtime
         // and the same payload.
-        debug!(target: "validator::verification::verify_transaction", "Executing \"exec\" call");
+        debug!(target: "validator::verification::execute_transaction", "Executing \"exec\" call");
         let state_update = runtime.exec(&payload)?;
-        debug!(target: "validator::verification::verify_transaction", "Successfully executed \"exec\" call");
+        debug!(target: "validator::verification::execute_transaction", "Successfully executed \"exec\" call");
 
         // If that was successful, we apply the state update in the ephemeral overlay.
-        debug!(target: "validator::verification::verify_transaction", "Executing \"apply\" call");
+        debug!(target: "validator::verification::execute_transaction", "Executing \"apply\" call");
         runtime.apply(&state_update)?;
-        debug!(target: "validator::verification::verify_transaction", "Successfully executed \"apply\" call");
+        debug!(target: "validator::verification::execute_transaction", "Successfully executed \"apply\" call");
 
         // If this call is supposed to deploy a new contract, we have to instantiate
         // a new `Runtime` and run its deploy function.
         if call.data.is_deployment()
         /* DeployV1 */
         {
-            debug!(target: "validator::verification::verify_transaction", "Deploying new contract");
+            debug!(target: "validator::verification::execute_transaction", "Deploying new contract");
             // Deserialize the deployment parameters
             let deploy_params: DeployParamsV1 = deserialize_async(&call.data.data[1..]).await?;
             let deploy_cid = ContractId::derive_public(deploy_params.public_key);
@@ -695,14 +758,14 @@ pub async fn verify_transaction(
             deploy_runtime.deploy(&deploy_params.ix)?;
 
             let deploy_gas_used = deploy_runtime.gas_used();
-            debug!(target: "validator::verification::verify_transaction", "The gas used for deployment call {:?} of transaction {}: {}", call, tx_hash, deploy_gas_used);
+            debug!(target: "validator::verification::execute_transaction", "The gas used for deployment call {:?} of transaction {}: {}", call, tx_hash, deploy_gas_used);
             gas_data.deployments += deploy_gas_used;
         }
 
         // At this point we're done with the call and move on to the next one.
         // Accumulate the WASM gas used.
         let wasm_gas_used = runtime.gas_used();
-        debug!(target: "validator::verification::verify_transaction", "The gas used for WASM call {:?} of transaction {}: {}", call, tx_hash, wasm_gas_used);
+        debug!(target: "validator::verification::execute_transaction", "The gas used for WASM call {:?} of transaction {}: {}", call, tx_hash, wasm_gas_used);
 
         // Append the used wasm gas
         gas_data.wasm += wasm_gas_used;
@@ -711,12 +774,12 @@ pub async fn verify_transaction(
     // The signature fee is tx_size + fixed_sig_fee * n_signatures
     gas_data.signatures = (PALLAS_SCHNORR_SIGNATURE_FEE * tx.signatures.len() as u64) +
         serialize_async(tx).await.len() as u64;
-    debug!(target: "validator::verification::verify_transaction", "The gas used for signature of transaction {}: {}", tx_hash, gas_data.signatures);
+    debug!(target: "validator::verification::execute_transaction", "The gas used for signature of transaction {}: {}", tx_hash, gas_data.signatures);
 
     // The ZK circuit fee is calculated using a function in validator/fees.rs
     for zkbin in circuits_to_verify.iter() {
         let zk_circuit_gas_used = circuit_gas_use(zkbin);
-        debug!(target: "validator::verification::verify_transaction", "The gas used for ZK circuit in namespace {} of transaction {}: {}", zkbin.namespace, tx_hash, zk_circuit_gas_used);
+        debug!(target: "validator::verification::execute_transaction", "The gas used for ZK circuit in namespace {} of transaction {}: {}", zkbin.namespace, tx_hash, zk_circuit_gas_used);
 
         // Append the used zk circuit gas
         gas_data.zk_circuits += zk_circuit_gas_used;
@@ -731,7 +794,7 @@ pub async fn verify_transaction(
             Ok(v) => v,
             Err(e) => {
                 error!(
-                    target: "validator::verification::verify_transaction",
+                    target: "validator::verification::execute_transaction",
                     "[VALIDATOR] Failed deserializing tx {} fee call: {}", tx_hash, e,
                 );
                 return Err(TxVerifyFailed::InvalidFee.into())
@@ -742,56 +805,61 @@ pub async fn verify_transaction(
         // Check that enough fee has been paid for the used gas in this transaction.
         if total_gas_used > fee {
             error!(
-                target: "validator::verification::verify_transaction",
+                target: "validator::verification::execute_transaction",
                 "[VALIDATOR] Transaction {} has insufficient fee. Required: {}, Paid: {}",
                 tx_hash, total_gas_used, fee,
             );
             return Err(TxVerifyFailed::InsufficientFee.into())
         }
-        debug!(target: "validator::verification::verify_transaction", "The gas paid for transaction {}: {}", tx_hash, gas_data.paid);
+        debug!(target: "validator::verification::execute_transaction", "The gas paid for transaction {}: {}", tx_hash, gas_data.paid);
 
         // Store paid fee
         gas_data.paid = fee;
     }
 
-    // When we're done looping and executing over the tx's contract calls and
-    // (optionally) made sure that enough fee was paid, we now move on with
-    // verification. First we verify the transaction signatures and then we
+    Ok((gas_data, TxMetadata { zkp_table, sig_table }))
+}
+
+/// Verify the signatures and ZK proofs of a given [`Transaction`] against the
+/// metadata its calls declared, using the provided verifying keys.
+pub async fn verify_transaction_proofs(
+    tx: &Transaction,
+    verifying_keys: &HashMap<[u8; 32], HashMap<String, VerifyingKey>>,
+    metadata: TxMetadata,
+) -> Result<()> {
+    let tx_hash = tx.hash();
+
+    // First we verify the transaction signatures and then we
     // verify any accompanying ZK proofs.
-    debug!(target: "validator::verification::verify_transaction", "Verifying signatures for transaction {}", tx_hash);
-    if sig_table.len() != tx.signatures.len() {
+    debug!(target: "validator::verification::verify_transaction_proofs", "Verifying signatures for transaction {}", tx_hash);
+    if metadata.sig_table.len() != tx.signatures.len() {
         error!(
-            target: "validator::verification::verify_transaction",
+            target: "validator::verification::verify_transaction_proofs",
             "[VALIDATOR] Incorrect number of signatures in tx {}", tx_hash,
         );
         return Err(TxVerifyFailed::MissingSignatures.into())
     }
 
-    if let Err(e) = tx.verify_sigs(sig_table) {
+    if let Err(e) = tx.verify_sigs(metadata.sig_table) {
         error!(
-            target: "validator::verification::verify_transaction",
+            target: "validator::verification::verify_transaction_proofs",
             "[VALIDATOR] Signature verification for tx {} failed: {}", tx_hash, e,
         );
         return Err(TxVerifyFailed::InvalidSignature.into())
     }
-    debug!(target: "validator::verification::verify_transaction", "Signature verification successful");
+    debug!(target: "validator::verification::verify_transaction_proofs", "Signature verification successful");
 
-    debug!(target: "validator::verification::verify_transaction", "Verifying ZK proofs for transaction {}", tx_hash);
-    if let Err(e) = tx.verify_zkps(verifying_keys, zkp_table).await {
+    debug!(target: "validator::verification::verify_transaction_proofs", "Verifying ZK proofs for transaction {}", tx_hash);
+    if let Err(e) = tx.verify_zkps(verifying_keys, metadata.zkp_table).await {
         error!(
-            target: "validator::verification::verify_transaction",
+            target: "validator::verification::verify_transaction_proofs",
             "[VALIDATOR] ZK proof verification for tx {} failed: {}", tx_hash, e,
         );
         return Err(TxVerifyFailed::InvalidZkProof.into())
     }
-    debug!(target: "validator::verification::verify_transaction", "ZK proof verification successful");
-
-    // Append hash to merkle tree
-    append_tx_to_merkle_tree(tree, tx);
+    debug!(target: "validator::verification::verify_transaction_proofs", "ZK proof verification successful");
 
-    debug!(target: "validator::verification::verify_transaction", "The total gas used for transaction {}: {}", tx_hash, total_gas_used);
-    debug!(target: "validator::verification::verify_transaction", "Transaction {} verified successfully", tx_hash);
-    Ok(gas_data)
+    Ok(())
 }
 
 /// Apply given [`Transaction`] to the provided overlay.
@@ -1018,9 +1086,17 @@ pub async fn verify_proposal(
     let previous = fork.overlay.lock().unwrap().last_block()?;
 
     // Verify proposal block (2)
-    if verify_block(&fork.overlay, &fork.module, &proposal.block, &previous, verify_fees)
-        .await
-        .is_err()
+    if verify_block(
+        &fork.overlay,
+        &fork.module,
+        &proposal.block,
+        &previous,
+        &consensus.scheduler,
+        &consensus.versionbits,
+        verify_fees,
+    )
+    .await
+    .is_err()
     {
         error!(target: "validator::verification::verify_proposal", "Erroneous proposal block found");
         fork.overlay.lock().unwrap().overlay.lock().unwrap().purge_new_trees()?;
@@ -1039,6 +1115,8 @@ pub async fn verify_proposal(
 pub async fn verify_fork_proposal(
     fork: &Fork,
     proposal: &Proposal,
+    scheduler: &TxScheduler,
+    versionbits: &VersionBits,
     verify_fees: bool,
 ) -> Result<()> {
     // Check if proposal hash matches actual one (1)
@@ -1055,9 +1133,17 @@ pub async fn verify_fork_proposal(
     let previous = fork.overlay.lock().unwrap().last_block()?;
 
     // Verify proposal block (2)
-    if verify_block(&fork.overlay, &fork.module, &proposal.block, &previous, verify_fees)
-        .await
-        .is_err()
+    if verify_block(
+        &fork.overlay,
+        &fork.module,
+        &proposal.block,
+        &previous,
+        scheduler,
+        versionbits,
+        verify_fees,
+    )
+    .await
+    .is_err()
     {
         error!(target: "validator::verification::verify_fork_proposal", "Erroneous proposal block found");
         fork.overlay.lock().unwrap().overlay.lock().unwrap().purge_new_trees()?;
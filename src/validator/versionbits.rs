@@ -0,0 +1,497 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2025 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use std::{collections::HashMap, sync::Mutex};
+
+use darkfi_sdk::blockchain::block_version;
+use log::debug;
+
+use crate::{
+    blockchain::{BlockInfo, BlockchainOverlayPtr, HeaderHash},
+    Error, Result,
+};
+
+/// Top bits of a header version signalling feature readiness.
+/// Headers with these top bits set carry the plain block version
+/// in their lowest bits, and a feature bit field above it.
+pub const VERSIONBITS_TOP_BITS: u8 = 0b0010_0000;
+/// Mask used to check a header version's top bits.
+pub const VERSIONBITS_TOP_MASK: u8 = 0b1110_0000;
+/// Mask of the plain block version carried by a signalling version.
+pub const VERSIONBITS_BASE_MASK: u8 = 0b0000_0011;
+/// Number of bits the plain block version takes in a signalling version.
+const VERSIONBITS_BASE_BITS: u8 = 2;
+/// Number of feature bits available for signalling.
+pub const VERSIONBITS_NUM_BITS: u8 = 3;
+/// Number of blocks in each signalling period. Deployment
+/// states only change at period boundaries.
+pub const VERSIONBITS_PERIOD: u32 = 1000;
+/// Number of signalling blocks in a period required to lock in a deployment.
+pub const VERSIONBITS_THRESHOLD: u32 = 900;
+
+/// A soft-fork deployment, signalled through a header version bit.
+#[derive(Copy, Clone, Debug)]
+pub struct Deployment {
+    /// Deployment name, used to check if it is active
+    pub name: &'static str,
+    /// Header version bit used for signalling, must be < `VERSIONBITS_NUM_BITS`
+    pub bit: u8,
+    /// Height from which signalling may start, at a period boundary
+    pub start_height: u32,
+    /// Height after which the deployment fails, if it hasn't locked in,
+    /// at a period boundary
+    pub timeout_height: u32,
+    /// Gated consensus rule, enforced on every block once the
+    /// deployment is active
+    pub rule: fn(&BlockInfo) -> Result<()>,
+}
+
+/// Currently known soft-fork deployments.
+/// Their gated rules are enforced by [`VersionBits::check_block`], so
+/// they switch on at activation height. Bits of deployments that finished
+/// (active or failed) can be reused once every node has upgraded past them.
+pub const DEPLOYMENTS: &[Deployment] = &[];
+
+/// State of a [`Deployment`], as defined for each signalling period.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum DeploymentState {
+    /// Deployment start height hasn't been reached yet
+    Defined,
+    /// Miners may signal readiness
+    Started,
+    /// Threshold was reached, activating on next period
+    LockedIn,
+    /// Gated rules are enforced
+    Active,
+    /// Deployment timed out without locking in
+    Failed,
+}
+
+impl DeploymentState {
+    /// Name of the state, as exposed over RPC
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Defined => "defined",
+            Self::Started => "started",
+            Self::LockedIn => "locked_in",
+            Self::Active => "active",
+            Self::Failed => "failed",
+        }
+    }
+}
+
+/// Check if given header version is a feature signalling version.
+pub fn is_signalling_version(version: u8) -> bool {
+    version & VERSIONBITS_TOP_MASK == VERSIONBITS_TOP_BITS
+}
+
+/// Check if given header version signals for the given bit.
+pub fn signals_bit(version: u8, bit: u8) -> bool {
+    is_signalling_version(version) &&
+        bit < VERSIONBITS_NUM_BITS &&
+        (version >> (bit + VERSIONBITS_BASE_BITS)) & 1 == 1
+}
+
+/// Grab the plain block version of given header version.
+pub fn base_version(version: u8) -> u8 {
+    if is_signalling_version(version) {
+        return version & VERSIONBITS_BASE_MASK
+    }
+    version
+}
+
+/// Build the header version signalling given feature bit field,
+/// on top of given plain block version.
+pub fn signalling_version(base: u8, bits: u8) -> u8 {
+    debug_assert!(base <= VERSIONBITS_BASE_MASK && bits < 1 << VERSIONBITS_NUM_BITS);
+    VERSIONBITS_TOP_BITS | bits << VERSIONBITS_BASE_BITS | base
+}
+
+/// Check if given header version is valid for given height. Either the
+/// plain block version of the height, or a feature signalling one on top
+/// of it, signalling only for known deployments inside their window.
+pub fn valid_block_version(version: u8, height: u32) -> bool {
+    valid_block_version_with(DEPLOYMENTS, version, height)
+}
+
+/// Check if given header version is valid for given height, using
+/// the given deployments.
+fn valid_block_version_with(deployments: &[Deployment], version: u8, height: u32) -> bool {
+    if !is_signalling_version(version) {
+        return version == block_version(height)
+    }
+
+    if base_version(version) != block_version(height) {
+        return false
+    }
+
+    // At least one bit must be signalled, each for a deployment
+    // whose signalling window contains the height.
+    let bits = (version & !VERSIONBITS_TOP_MASK) >> VERSIONBITS_BASE_BITS;
+    bits != 0 &&
+        (0..VERSIONBITS_NUM_BITS).filter(|bit| (bits >> bit) & 1 == 1).all(|bit| {
+            deployments
+                .iter()
+                .any(|d| d.bit == bit && d.start_height <= height && height < d.timeout_height)
+        })
+}
+
+/// Soft-fork deployments tracker, computing deployment states from the
+/// signalling of each period's headers.
+pub struct VersionBits {
+    /// Tracked deployments
+    deployments: Vec<Deployment>,
+    /// Number of blocks in each signalling period
+    period: u32,
+    /// Number of signalling blocks in a period required to lock in
+    threshold: u32,
+    /// Computed deployment states, keyed by deployment bit and the hash
+    /// of the last block before the period, so they are fork-safe.
+    cache: Mutex<HashMap<(u8, HeaderHash), DeploymentState>>,
+}
+
+impl Default for VersionBits {
+    fn default() -> Self {
+        Self::new(DEPLOYMENTS.to_vec(), VERSIONBITS_PERIOD, VERSIONBITS_THRESHOLD)
+    }
+}
+
+impl VersionBits {
+    /// Generate a new deployments tracker, using given parameters.
+    pub fn new(deployments: Vec<Deployment>, period: u32, threshold: u32) -> Self {
+        assert!(period > 0 && threshold <= period);
+        for deployment in &deployments {
+            assert!(deployment.bit < VERSIONBITS_NUM_BITS);
+            assert!(deployment.start_height % period == 0);
+            assert!(deployment.timeout_height % period == 0);
+        }
+        Self { deployments, period, threshold, cache: Mutex::new(HashMap::new()) }
+    }
+
+    /// Compute the state of a deployment for the block at given height.
+    /// `header` must return the hash and version of the header at given
+    /// height, of the chain the block is being appended to.
+    pub fn state_with<F>(
+        &self,
+        deployment: &Deployment,
+        height: u32,
+        header: F,
+    ) -> Result<DeploymentState>
+    where
+        F: Fn(u32) -> Result<(HeaderHash, u8)>,
+    {
+        // Walk back the periods until we find a known state
+        let mut period_start = height - height % self.period;
+        let mut to_compute = vec![];
+        let mut state = loop {
+            // Periods before the start height are always defined
+            if period_start == 0 || period_start < deployment.start_height {
+                break DeploymentState::Defined
+            }
+
+            let (previous_hash, _) = header(period_start - 1)?;
+            if let Some(state) = self.cache.lock().unwrap().get(&(deployment.bit, previous_hash)) {
+                break *state
+            }
+
+            to_compute.push((period_start, previous_hash));
+            period_start -= self.period;
+        };
+
+        // Now walk forward, computing each period state from its previous one
+        while let Some((period_start, previous_hash)) = to_compute.pop() {
+            state = match state {
+                DeploymentState::Defined => {
+                    if period_start >= deployment.timeout_height {
+                        DeploymentState::Failed
+                    } else if period_start >= deployment.start_height {
+                        DeploymentState::Started
+                    } else {
+                        DeploymentState::Defined
+                    }
+                }
+                DeploymentState::Started => {
+                    // Count signalling blocks in the previous period
+                    let mut count = 0;
+                    for h in period_start - self.period..period_start {
+                        let (_, version) = header(h)?;
+                        if signals_bit(version, deployment.bit) {
+                            count += 1;
+                        }
+                    }
+
+                    if count >= self.threshold {
+                        DeploymentState::LockedIn
+                    } else if period_start >= deployment.timeout_height {
+                        DeploymentState::Failed
+                    } else {
+                        DeploymentState::Started
+                    }
+                }
+                DeploymentState::LockedIn => DeploymentState::Active,
+                DeploymentState::Active => DeploymentState::Active,
+                DeploymentState::Failed => DeploymentState::Failed,
+            };
+
+            debug!(
+                target: "validator::versionbits::state_with",
+                "Deployment {} state at height {}: {:?}", deployment.name, period_start, state,
+            );
+            self.cache.lock().unwrap().insert((deployment.bit, previous_hash), state);
+        }
+
+        Ok(state)
+    }
+
+    /// Compute the state of a deployment for the block at given height,
+    /// appended to the chain of given overlay.
+    pub fn state(
+        &self,
+        overlay: &BlockchainOverlayPtr,
+        deployment: &Deployment,
+        height: u32,
+    ) -> Result<DeploymentState> {
+        let overlay = overlay.lock().unwrap();
+        self.state_with(deployment, height, |h| {
+            let hash = overlay.blocks.get_order(&[h], true)?[0].unwrap();
+            let header = overlay.headers.get(&[hash], true)?[0].clone().unwrap();
+            Ok((hash, header.version))
+        })
+    }
+
+    /// Check given block against the deployments state of the chain it
+    /// is appended to. `header` must return the hash and version of the
+    /// header at given height of that chain. Blocks may only signal for
+    /// started deployments, and must follow the gated rules of the active
+    /// ones.
+    pub fn check_block_with<F>(&self, block: &BlockInfo, header: F) -> Result<()>
+    where
+        F: Fn(u32) -> Result<(HeaderHash, u8)>,
+    {
+        let (version, height) = (block.header.version, block.header.height);
+        for bit in 0..VERSIONBITS_NUM_BITS {
+            if signals_bit(version, bit) && !self.deployments.iter().any(|d| d.bit == bit) {
+                return Err(Error::BlockIsInvalid(block.hash().as_string()))
+            }
+        }
+
+        for deployment in &self.deployments {
+            let state = self.state_with(deployment, height, &header)?;
+            if signals_bit(version, deployment.bit) && state != DeploymentState::Started {
+                return Err(Error::BlockIsInvalid(block.hash().as_string()))
+            }
+
+            if state == DeploymentState::Active {
+                (deployment.rule)(block)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Check given block against the deployments state of the chain
+    /// of given overlay, which the block is appended to.
+    pub fn check_block(&self, overlay: &BlockchainOverlayPtr, block: &BlockInfo) -> Result<()> {
+        let overlay = overlay.lock().unwrap();
+        self.check_block_with(block, |h| {
+            let hash = overlay.blocks.get_order(&[h], true)?[0].unwrap();
+            let header = overlay.headers.get(&[hash], true)?[0].clone().unwrap();
+            Ok((hash, header.version))
+        })
+    }
+
+    /// Compute the header version a block at given height, appended to
+    /// the chain of given overlay, should use. We signal readiness for
+    /// every deployment we know of that is started, on top of the plain
+    /// block version.
+    pub fn block_version(&self, overlay: &BlockchainOverlayPtr, height: u32) -> Result<u8> {
+        let mut bits = 0;
+        for deployment in &self.deployments {
+            if self.state(overlay, deployment, height)? == DeploymentState::Started {
+                bits |= 1 << deployment.bit;
+            }
+        }
+
+        if bits == 0 {
+            return Ok(block_version(height))
+        }
+
+        Ok(signalling_version(block_version(height), bits))
+    }
+
+    /// Returns the tracked deployments along with their state for the block
+    /// at given height, appended to the chain of given overlay.
+    pub fn states(
+        &self,
+        overlay: &BlockchainOverlayPtr,
+        height: u32,
+    ) -> Result<Vec<(Deployment, DeploymentState)>> {
+        let mut ret = Vec::with_capacity(self.deployments.len());
+        for deployment in &self.deployments {
+            ret.push((*deployment, self.state(overlay, deployment, height)?));
+        }
+
+        Ok(ret)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{blockchain::Header, util::time::Timestamp};
+
+    use super::*;
+
+    /// Test rule, rejecting blocks with an odd nonce
+    fn even_nonce_rule(block: &BlockInfo) -> Result<()> {
+        if block.header.nonce % 2 == 1 {
+            return Err(Error::BlockIsInvalid(block.hash().as_string()))
+        }
+        Ok(())
+    }
+
+    const TEST_DEPLOYMENT: Deployment = Deployment {
+        name: "test",
+        bit: 2,
+        start_height: 10,
+        timeout_height: 50,
+        rule: even_nonce_rule,
+    };
+
+    /// Build a chain of headers with given versions, returning a
+    /// lookup closure for `VersionBits::state_with`.
+    fn chain(versions: Vec<u8>) -> impl Fn(u32) -> Result<(HeaderHash, u8)> {
+        move |h| {
+            let hash = HeaderHash::new(blake3::hash(&h.to_le_bytes()).into());
+            Ok((hash, versions[h as usize]))
+        }
+    }
+
+    /// Build a block at given height, with given version and nonce
+    fn block(height: u32, version: u8, nonce: u64) -> BlockInfo {
+        let mut header =
+            Header::new(HeaderHash::new([0; 32]), height, Timestamp::current_time(), nonce);
+        header.version = version;
+        BlockInfo::new_empty(header)
+    }
+
+    #[test]
+    fn versionbits_version_checks() {
+        let deployments = [TEST_DEPLOYMENT];
+        let version = signalling_version(block_version(15), 1 << 2);
+        assert!(is_signalling_version(version));
+        assert!(signals_bit(version, 2));
+        assert!(!signals_bit(version, 1));
+        assert!(!signals_bit(block_version(0), 0));
+        assert_eq!(base_version(version), block_version(15));
+        assert_eq!(base_version(block_version(15)), block_version(15));
+
+        // Plain block versions must match the height one
+        assert!(valid_block_version_with(&deployments, block_version(1), 1));
+        assert!(!valid_block_version_with(&deployments, block_version(1) + 1, 1));
+        assert!(!valid_block_version_with(&deployments, 0b1000_0000, 1));
+
+        // Signalling is only valid inside the deployment window
+        assert!(!valid_block_version_with(&deployments, version, 9));
+        assert!(valid_block_version_with(&deployments, version, 10));
+        assert!(valid_block_version_with(&deployments, version, 49));
+        assert!(!valid_block_version_with(&deployments, version, 50));
+
+        // On top of the expected plain block version
+        let wrong_base = signalling_version(block_version(15) + 1, 1 << 2);
+        assert!(!valid_block_version_with(&deployments, wrong_base, 15));
+
+        // For known deployment bits only, and at least one of them
+        assert!(!valid_block_version_with(&deployments, signalling_version(1, 1 << 1), 15));
+        assert!(!valid_block_version_with(&deployments, signalling_version(1, 1 << 2 | 1), 15));
+        assert!(!valid_block_version_with(&deployments, signalling_version(1, 0), 15));
+
+        // No deployments are known on the network yet
+        assert!(!valid_block_version(version, 15));
+    }
+
+    #[test]
+    fn versionbits_activation() -> Result<()> {
+        let vb = VersionBits::new(vec![TEST_DEPLOYMENT], 10, 8);
+        let signal = signalling_version(1, 1 << TEST_DEPLOYMENT.bit);
+
+        // Signal in 8 out of 10 blocks of the first started period
+        let mut versions = vec![1; 40];
+        for v in versions.iter_mut().skip(10).take(8) {
+            *v = signal;
+        }
+        let header = chain(versions);
+
+        assert_eq!(vb.state_with(&TEST_DEPLOYMENT, 5, &header)?, DeploymentState::Defined);
+        assert_eq!(vb.state_with(&TEST_DEPLOYMENT, 15, &header)?, DeploymentState::Started);
+        assert_eq!(vb.state_with(&TEST_DEPLOYMENT, 25, &header)?, DeploymentState::LockedIn);
+        assert_eq!(vb.state_with(&TEST_DEPLOYMENT, 30, &header)?, DeploymentState::Active);
+        assert_eq!(vb.state_with(&TEST_DEPLOYMENT, 39, &header)?, DeploymentState::Active);
+
+        Ok(())
+    }
+
+    #[test]
+    fn versionbits_timeout() -> Result<()> {
+        let vb = VersionBits::new(vec![TEST_DEPLOYMENT], 10, 8);
+        let signal = signalling_version(1, 1 << TEST_DEPLOYMENT.bit);
+
+        // Signal in only 7 out of 10 blocks of every period
+        let mut versions = vec![1; 70];
+        for (h, v) in versions.iter_mut().enumerate() {
+            if h % 10 < 7 {
+                *v = signal;
+            }
+        }
+        let header = chain(versions);
+
+        assert_eq!(vb.state_with(&TEST_DEPLOYMENT, 45, &header)?, DeploymentState::Started);
+        assert_eq!(vb.state_with(&TEST_DEPLOYMENT, 55, &header)?, DeploymentState::Failed);
+        assert_eq!(vb.state_with(&TEST_DEPLOYMENT, 65, &header)?, DeploymentState::Failed);
+
+        Ok(())
+    }
+
+    #[test]
+    fn versionbits_check_block() -> Result<()> {
+        let vb = VersionBits::new(vec![TEST_DEPLOYMENT], 10, 8);
+        let signal = signalling_version(1, 1 << TEST_DEPLOYMENT.bit);
+
+        let mut versions = vec![1; 40];
+        for v in versions.iter_mut().skip(10).take(8) {
+            *v = signal;
+        }
+        let header = chain(versions);
+
+        // Signalling is only allowed while the deployment is started
+        assert!(vb.check_block_with(&block(5, signal, 0), &header).is_err());
+        assert!(vb.check_block_with(&block(15, signal, 0), &header).is_ok());
+        assert!(vb.check_block_with(&block(25, signal, 0), &header).is_err());
+        assert!(vb.check_block_with(&block(35, signal, 0), &header).is_err());
+        assert!(vb
+            .check_block_with(&block(15, signalling_version(1, 1 << 1), 0), &header)
+            .is_err());
+
+        // The gated rule switches on at activation height
+        assert!(vb.check_block_with(&block(25, 1, 1), &header).is_ok());
+        assert!(vb.check_block_with(&block(29, 1, 1), &header).is_ok());
+        assert!(vb.check_block_with(&block(30, 1, 1), &header).is_err());
+        assert!(vb.check_block_with(&block(30, 1, 2), &header).is_ok());
+
+        Ok(())
+    }
+}
@@ -31,6 +31,16 @@ pub mod gadget;
 pub mod proof;
 pub use proof::{Proof, ProvingKey, VerifyingKey};
 
+/// Prioritized proving thread pool
+#[cfg(feature = "smol")]
+pub mod prover;
+#[cfg(feature = "smol")]
+pub use prover::{ProofPriority, ProverJob, ProverPool, ProverProgress};
+
+/// Circuit cost statistics and regression tracking
+pub mod stats;
+pub use stats::{CircuitStats, CostEstimate};
+
 /// Trace computation of intermediate values in circuit
 mod tracer;
 pub use tracer::DebugOpValue;
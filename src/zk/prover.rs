@@ -0,0 +1,319 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2025 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use std::{
+    cmp::Ordering,
+    collections::BinaryHeap,
+    panic::{catch_unwind, AssertUnwindSafe},
+    sync::{Arc, Condvar, Mutex},
+    thread,
+};
+
+use darkfi_sdk::pasta::pallas;
+use halo2_proofs::plonk;
+use log::{debug, error};
+use rand::rngs::OsRng;
+use smol::channel::{bounded, Receiver};
+
+use super::{Proof, ProvingKey, ZkCircuit};
+use crate::{Error, Result};
+
+/// Priority of a job submitted to the [`ProverPool`].
+/// Higher priority jobs are always picked up first, while jobs of
+/// the same priority run in submission order.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Ord, PartialOrd)]
+pub enum ProofPriority {
+    /// Batch work nobody is actively waiting on
+    Background,
+    /// Work a user is waiting on, like building a wallet transaction
+    Interactive,
+}
+
+/// Snapshot of the [`ProverPool`] progress
+#[derive(Copy, Clone, Debug, Default)]
+pub struct ProverProgress {
+    /// Jobs waiting for a free proving thread
+    pub queued: usize,
+    /// Jobs currently being proven
+    pub running: usize,
+    /// Jobs finished since the pool was created
+    pub completed: u64,
+}
+
+struct Job {
+    priority: ProofPriority,
+    seq: u64,
+    run: Box<dyn FnOnce() + Send>,
+}
+
+impl Ord for Job {
+    fn cmp(&self, other: &Self) -> Ordering {
+        // Max-heap: higher priority first, then the oldest submission
+        self.priority.cmp(&other.priority).then_with(|| other.seq.cmp(&self.seq))
+    }
+}
+
+impl PartialOrd for Job {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for Job {
+    fn eq(&self, other: &Self) -> bool {
+        self.priority == other.priority && self.seq == other.seq
+    }
+}
+
+impl Eq for Job {}
+
+#[derive(Default)]
+struct PoolState {
+    queue: BinaryHeap<Job>,
+    next_seq: u64,
+    running: usize,
+    completed: u64,
+    stopped: bool,
+}
+
+#[derive(Default)]
+struct PoolShared {
+    state: Mutex<PoolState>,
+    cond: Condvar,
+}
+
+/// Stops the proving threads once the last [`ProverPool`] handle is dropped
+struct PoolHandle {
+    shared: Arc<PoolShared>,
+    threads: usize,
+}
+
+impl Drop for PoolHandle {
+    fn drop(&mut self) {
+        self.shared.state.lock().unwrap().stopped = true;
+        self.shared.cond.notify_all();
+    }
+}
+
+/// Bounded set of threads creating ZK proofs, or running any other
+/// CPU heavy job, in priority order.
+///
+/// Proving blocks the calling thread for a long time and uses all the
+/// cores it can get, so client tooling creating several proofs at once
+/// can easily freeze the machine. Submitting them to a shared pool
+/// caps the number of proofs created concurrently, keeps the async
+/// executor responsive, and lets interactive work jump ahead of
+/// background batches.
+///
+/// The pool is cheap to clone, with all clones sharing the same threads.
+/// Jobs already queued are still run after the last handle is dropped.
+#[derive(Clone)]
+pub struct ProverPool {
+    handle: Arc<PoolHandle>,
+}
+
+impl ProverPool {
+    /// Spawn a new pool using the given number of proving threads.
+    pub fn new(threads: usize) -> Self {
+        let threads = threads.max(1);
+        let shared = Arc::new(PoolShared::default());
+
+        for i in 0..threads {
+            let shared = shared.clone();
+            thread::Builder::new()
+                .name(format!("prover-{i}"))
+                .spawn(move || Self::worker(shared))
+                .expect("Failed spawning prover thread");
+        }
+
+        Self { handle: Arc::new(PoolHandle { shared, threads }) }
+    }
+
+    /// Default number of proving threads, leaving a core free for the
+    /// rest of the system.
+    pub fn default_threads() -> usize {
+        thread::available_parallelism().map(|n| n.get().saturating_sub(1)).unwrap_or(1).max(1)
+    }
+
+    /// Number of proving threads of this pool
+    pub fn threads(&self) -> usize {
+        self.handle.threads
+    }
+
+    /// Current progress of the pool
+    pub fn progress(&self) -> ProverProgress {
+        let state = self.handle.shared.state.lock().unwrap();
+        ProverProgress {
+            queued: state.queue.len(),
+            running: state.running,
+            completed: state.completed,
+        }
+    }
+
+    /// Queue a job with the given priority, returning a handle to
+    /// await its output.
+    pub fn submit<T, F>(&self, priority: ProofPriority, job: F) -> ProverJob<T>
+    where
+        T: Send + 'static,
+        F: FnOnce() -> T + Send + 'static,
+    {
+        let (sender, receiver) = bounded(1);
+        let run = Box::new(move || {
+            let _ = sender.try_send(job());
+        });
+
+        let mut state = self.handle.shared.state.lock().unwrap();
+        let seq = state.next_seq;
+        state.next_seq += 1;
+        state.queue.push(Job { priority, seq, run });
+        drop(state);
+        self.handle.shared.cond.notify_one();
+
+        ProverJob { receiver }
+    }
+
+    /// Queue a job with the given priority and wait for its output.
+    pub async fn run<T, F>(&self, priority: ProofPriority, job: F) -> Result<T>
+    where
+        T: Send + 'static,
+        F: FnOnce() -> T + Send + 'static,
+    {
+        self.submit(priority, job).wait().await
+    }
+
+    /// Queue the creation of a proof for the given circuit.
+    pub fn prove(
+        &self,
+        priority: ProofPriority,
+        pk: ProvingKey,
+        circuit: ZkCircuit,
+        public_inputs: Vec<pallas::Base>,
+    ) -> ProverJob<std::result::Result<Proof, plonk::Error>> {
+        self.submit(priority, move || Proof::create(&pk, &[circuit], &public_inputs, &mut OsRng))
+    }
+
+    fn worker(shared: Arc<PoolShared>) {
+        loop {
+            let mut state = shared.state.lock().unwrap();
+            let job = loop {
+                if let Some(job) = state.queue.pop() {
+                    break job
+                }
+                if state.stopped {
+                    return
+                }
+                state = shared.cond.wait(state).unwrap();
+            };
+            state.running += 1;
+            drop(state);
+
+            debug!(target: "zk::prover", "Running {:?} job {}", job.priority, job.seq);
+            // A panicking job drops its sender, so its waiter gets an error
+            if catch_unwind(AssertUnwindSafe(job.run)).is_err() {
+                error!(target: "zk::prover", "{:?} job {} panicked", job.priority, job.seq);
+            }
+
+            let mut state = shared.state.lock().unwrap();
+            state.running -= 1;
+            state.completed += 1;
+        }
+    }
+}
+
+impl Default for ProverPool {
+    fn default() -> Self {
+        Self::new(Self::default_threads())
+    }
+}
+
+/// Handle to a job queued in a [`ProverPool`]
+pub struct ProverJob<T> {
+    receiver: Receiver<T>,
+}
+
+impl<T> ProverJob<T> {
+    /// Check if the job has finished running
+    pub fn is_done(&self) -> bool {
+        !self.receiver.is_empty() || self.receiver.is_closed()
+    }
+
+    /// Wait for the job to finish, returning its output.
+    pub async fn wait(self) -> Result<T> {
+        match self.receiver.recv().await {
+            Ok(v) => Ok(v),
+            Err(_) => Err(Error::Custom("Prover job panicked".to_string())),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::mpsc;
+
+    use super::*;
+
+    #[test]
+    fn prover_pool_priorities() {
+        let pool = ProverPool::new(1);
+
+        // Keep the only thread busy until all jobs are queued
+        let (started_tx, started) = mpsc::channel::<()>();
+        let (unblock, blocked) = mpsc::channel::<()>();
+        let blocker = pool.submit(ProofPriority::Background, move || {
+            started_tx.send(()).unwrap();
+            blocked.recv().unwrap()
+        });
+        started.recv().unwrap();
+
+        let order = Arc::new(Mutex::new(vec![]));
+        let mut jobs = vec![];
+        for (i, priority) in [
+            ProofPriority::Background,
+            ProofPriority::Interactive,
+            ProofPriority::Background,
+            ProofPriority::Interactive,
+        ]
+        .into_iter()
+        .enumerate()
+        {
+            let order = order.clone();
+            jobs.push(pool.submit(priority, move || order.lock().unwrap().push(i)));
+        }
+
+        let progress = pool.progress();
+        assert_eq!(progress.queued, 4);
+        assert_eq!(progress.running, 1);
+        assert!(!jobs[0].is_done());
+
+        unblock.send(()).unwrap();
+        smol::block_on(async {
+            blocker.wait().await.unwrap();
+            for job in jobs {
+                job.wait().await.unwrap();
+            }
+        });
+
+        assert_eq!(*order.lock().unwrap(), vec![1, 3, 0, 2]);
+        assert_eq!(pool.progress().queued, 0);
+
+        // Panicking jobs don't take their thread down with them
+        let failed = pool.submit(ProofPriority::Interactive, || panic!("prover test"));
+        assert!(smol::block_on(failed.wait()).is_err());
+        assert_eq!(smol::block_on(pool.run(ProofPriority::Interactive, || 42)).unwrap(), 42);
+    }
+}
@@ -0,0 +1,456 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2025 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Circuit cost reporting.
+//!
+//! [`CircuitStats::measure`] configures and lays out a compiled zkas
+//! circuit without any witness values, and reports the constraint
+//! system shape, the number of rows the layout actually occupies and
+//! a rough estimate of what proving it costs at a given `k`.
+//!
+//! The estimates are not benchmarks. They model the dominant prover
+//! work (multi-scalar multiplications and FFTs over the extended
+//! domain) with fixed single-threaded constants, so they are meant to
+//! compare two versions of a circuit against each other rather than
+//! to predict wall clock time on a particular machine. Use
+//! [`CircuitStats::regressions`] to catch changes that balloon cost.
+
+use std::{collections::BTreeMap, fmt};
+
+use darkfi_sdk::pasta::pallas;
+use darkfi_serial::{SerialDecodable, SerialEncodable};
+use halo2_proofs::{
+    circuit::{floor_planner, FloorPlanner, Value},
+    plonk::{
+        Advice, Any, Assigned, Assignment, Circuit, Column, ConstraintSystem, Error as PlonkError,
+        Fixed, Instance, Selector,
+    },
+};
+
+use super::{empty_witnesses, ZkCircuit};
+use crate::{
+    zkas::{Opcode, ZkBinary},
+    Result,
+};
+
+/// Size in bytes of a single field element held by the prover
+const FIELD_ELEMENT_SIZE: u64 = 32;
+
+/// Reference cost of one point in a multi-scalar multiplication, in nanoseconds
+const MSM_NS_PER_POINT: u64 = 2_500;
+
+/// Reference cost of one FFT butterfly, in nanoseconds
+const FFT_NS_PER_BUTTERFLY: u64 = 40;
+
+/// Default tolerance in percent used by `zkas` when checking for regressions
+pub const DEFAULT_REGRESSION_TOLERANCE: u64 = 10;
+
+/// Estimated proving cost of a circuit for a given `k`
+#[derive(Clone, Debug, Default, PartialEq, Eq, SerialEncodable, SerialDecodable)]
+pub struct CostEstimate {
+    /// The `k` this estimate was made for
+    pub k: u32,
+    /// Total rows in the domain (`2^k`)
+    pub rows: u64,
+    /// Rows available to the circuit after blinding
+    pub usable_rows: u64,
+    /// Whether the laid out circuit fits into the usable rows
+    pub fits: bool,
+    /// Number of polynomials the prover commits to
+    pub committed_polys: u64,
+    /// Size of the extended evaluation domain
+    pub extended_rows: u64,
+    /// Estimated peak prover memory, in bytes
+    pub proving_memory: u64,
+    /// Estimated single-threaded proving time, in milliseconds
+    pub proving_time_ms: u64,
+}
+
+/// Cost statistics of a compiled zkas circuit
+#[derive(Clone, Debug, PartialEq, Eq, SerialEncodable, SerialDecodable)]
+pub struct CircuitStats {
+    /// Circuit namespace
+    pub namespace: String,
+    /// The `k` declared by the circuit
+    pub k: u32,
+    /// Number of witnesses
+    pub witnesses: u64,
+    /// Number of public inputs
+    pub public_inputs: u64,
+    /// Number of opcodes in the circuit
+    pub opcodes: u64,
+    /// Opcode usage, by opcode name
+    pub opcode_counts: Vec<(String, u64)>,
+    /// Number of advice columns
+    pub advice_columns: u64,
+    /// Number of fixed columns, not counting selectors
+    pub fixed_columns: u64,
+    /// Number of instance columns
+    pub instance_columns: u64,
+    /// Number of selectors
+    pub selectors: u64,
+    /// Number of custom gates
+    pub gates: u64,
+    /// Number of polynomial constraints across all gates
+    pub constraints: u64,
+    /// Number of lookup arguments
+    pub lookups: u64,
+    /// Number of columns taking part in the permutation argument
+    pub permutation_columns: u64,
+    /// Maximum degree of the constraint system
+    pub degree: u64,
+    /// Rows reserved for blinding factors
+    pub blinding_factors: u64,
+    /// Rows occupied by the circuit layout, including lookup tables
+    pub rows_used: u64,
+    /// Number of advice cells assigned
+    pub advice_cells: u64,
+    /// Number of fixed cells assigned, including lookup tables
+    pub fixed_cells: u64,
+    /// Number of copy constraints
+    pub copies: u64,
+    /// Smallest `k` the circuit fits into
+    pub min_k: u32,
+    /// Estimated proving cost at the declared `k`
+    pub cost: CostEstimate,
+}
+
+/// A metric that grew beyond the tolerated amount compared to a baseline
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct StatsRegression {
+    /// Name of the metric
+    pub metric: &'static str,
+    /// Value in the baseline
+    pub baseline: u64,
+    /// Value in the current stats
+    pub current: u64,
+}
+
+impl fmt::Display for StatsRegression {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {} -> {}", self.metric, self.baseline, self.current)?;
+        if self.baseline > 0 {
+            let pct = (self.current - self.baseline) * 100 / self.baseline;
+            write!(f, " (+{}%)", pct)?;
+        }
+        Ok(())
+    }
+}
+
+impl CircuitStats {
+    /// Measure the given compiled circuit.
+    pub fn measure(zkbin: &ZkBinary) -> Result<Self> {
+        let circuit = ZkCircuit::new(empty_witnesses(zkbin)?, zkbin);
+
+        let mut cs = ConstraintSystem::default();
+        let config = ZkCircuit::configure_with_params(&mut cs, circuit.params());
+
+        let mut layout = LayoutCounter::default();
+        floor_planner::V1::synthesize(&mut layout, &circuit, config, cs.constants().clone())?;
+
+        let mut opcode_counts = BTreeMap::new();
+        for (opcode, _) in &zkbin.opcodes {
+            *opcode_counts.entry(opcode.name().to_string()).or_insert(0) += 1;
+        }
+
+        let public_inputs =
+            zkbin.opcodes.iter().filter(|(op, _)| *op == Opcode::ConstrainInstance).count();
+
+        let blinding_factors = cs.blinding_factors() as u64;
+        let rows_used = layout.rows;
+
+        // The last usable row is followed by the blinding rows and one
+        // more row reserved by the permutation argument.
+        let mut min_k = 1;
+        while (1u64 << min_k) < rows_used + blinding_factors + 1 {
+            min_k += 1;
+        }
+
+        let mut stats = Self {
+            namespace: zkbin.namespace.clone(),
+            k: zkbin.k,
+            witnesses: zkbin.witnesses.len() as u64,
+            public_inputs: public_inputs as u64,
+            opcodes: zkbin.opcodes.len() as u64,
+            opcode_counts: opcode_counts.into_iter().collect(),
+            advice_columns: cs.num_advice_columns() as u64,
+            fixed_columns: cs.num_fixed_columns() as u64,
+            instance_columns: cs.num_instance_columns() as u64,
+            selectors: cs.num_selectors() as u64,
+            gates: cs.gates().len() as u64,
+            constraints: cs.gates().iter().map(|g| g.polynomials().len() as u64).sum(),
+            lookups: cs.lookups().len() as u64,
+            permutation_columns: cs.permutation().get_columns().len() as u64,
+            degree: cs.degree() as u64,
+            blinding_factors,
+            rows_used,
+            advice_cells: layout.advice_cells,
+            fixed_cells: layout.fixed_cells,
+            copies: layout.copies,
+            min_k,
+            cost: CostEstimate::default(),
+        };
+
+        stats.cost = stats.estimate(zkbin.k);
+        Ok(stats)
+    }
+
+    /// Estimate the proving cost of the circuit for the given `k`.
+    pub fn estimate(&self, k: u32) -> CostEstimate {
+        let rows = 1u64 << k;
+        let usable_rows = rows.saturating_sub(self.blinding_factors + 1);
+
+        // The quotient polynomial is evaluated over a domain extended
+        // by the smallest power of two covering `degree - 1`.
+        let degree = self.degree.max(3);
+        let mut extended_k = k;
+        while (1u64 << (extended_k - k)) < degree - 1 {
+            extended_k += 1;
+        }
+        let extended_rows = 1u64 << extended_k;
+
+        // Each grand product column covers `degree - 2` permuted columns.
+        let permutation_products = self.permutation_columns.div_ceil(degree - 2);
+
+        // Advice columns, permutation products, three committed polynomials
+        // per lookup (permuted input, permuted table and the product), and
+        // the quotient pieces.
+        let committed_polys =
+            self.advice_columns + permutation_products + self.lookups * 3 + (degree - 1);
+
+        // Polynomials the prover keeps around: the committed ones, and the
+        // fixed, selector and permutation polynomials from the proving key.
+        // Each is held in Lagrange and coefficient form, plus an extended
+        // evaluation.
+        let held_polys = committed_polys +
+            self.instance_columns +
+            self.fixed_columns +
+            self.selectors +
+            self.permutation_columns;
+        let proving_memory = held_polys * (2 * rows + extended_rows) * FIELD_ELEMENT_SIZE;
+
+        // One MSM of size `rows` per commitment, and an inverse FFT plus an
+        // extended FFT for every polynomial entering the quotient.
+        let msm_ns = committed_polys * rows * MSM_NS_PER_POINT;
+        let fft_ns = held_polys *
+            (rows * k as u64 / 2 + extended_rows * extended_k as u64 / 2) *
+            FFT_NS_PER_BUTTERFLY;
+        let proving_time_ms = (msm_ns + fft_ns) / 1_000_000;
+
+        CostEstimate {
+            k,
+            rows,
+            usable_rows,
+            fits: self.rows_used <= usable_rows,
+            committed_polys,
+            extended_rows,
+            proving_memory,
+            proving_time_ms,
+        }
+    }
+
+    /// Named metrics that are tracked for regressions.
+    pub fn metrics(&self) -> Vec<(&'static str, u64)> {
+        vec![
+            ("k", self.k as u64),
+            ("advice_columns", self.advice_columns),
+            ("fixed_columns", self.fixed_columns),
+            ("selectors", self.selectors),
+            ("constraints", self.constraints),
+            ("lookups", self.lookups),
+            ("degree", self.degree),
+            ("rows_used", self.rows_used),
+            ("advice_cells", self.advice_cells),
+            ("copies", self.copies),
+            ("min_k", self.min_k as u64),
+            ("proving_memory", self.cost.proving_memory),
+            ("proving_time_ms", self.cost.proving_time_ms),
+        ]
+    }
+
+    /// Compare against a baseline and return every metric that grew by
+    /// more than `tolerance` percent.
+    pub fn regressions(&self, baseline: &Self, tolerance: u64) -> Vec<StatsRegression> {
+        let mut ret = vec![];
+
+        for ((metric, current), (_, base)) in self.metrics().into_iter().zip(baseline.metrics()) {
+            if current <= base + base * tolerance / 100 {
+                continue
+            }
+
+            ret.push(StatsRegression { metric, baseline: base, current });
+        }
+
+        ret
+    }
+}
+
+impl fmt::Display for CircuitStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Circuit \"{}\" (k = {})", self.namespace, self.k)?;
+        writeln!(f, "  Witnesses:            {}", self.witnesses)?;
+        writeln!(f, "  Public inputs:        {}", self.public_inputs)?;
+        writeln!(f, "  Opcodes:              {}", self.opcodes)?;
+        for (name, count) in &self.opcode_counts {
+            writeln!(f, "    {:<24}{}", name, count)?;
+        }
+        writeln!(f, "  Columns:")?;
+        writeln!(f, "    advice              {}", self.advice_columns)?;
+        writeln!(f, "    fixed               {}", self.fixed_columns)?;
+        writeln!(f, "    instance            {}", self.instance_columns)?;
+        writeln!(f, "    selectors           {}", self.selectors)?;
+        writeln!(f, "    permutation         {}", self.permutation_columns)?;
+        writeln!(f, "  Gates:                {}", self.gates)?;
+        writeln!(f, "  Constraints:          {}", self.constraints)?;
+        writeln!(f, "  Lookups:              {}", self.lookups)?;
+        writeln!(f, "  Degree:               {}", self.degree)?;
+        writeln!(f, "  Rows used:            {} / {}", self.rows_used, self.cost.usable_rows)?;
+        writeln!(f, "  Advice cells:         {}", self.advice_cells)?;
+        writeln!(f, "  Fixed cells:          {}", self.fixed_cells)?;
+        writeln!(f, "  Copy constraints:     {}", self.copies)?;
+        writeln!(f, "  Minimum k:            {}", self.min_k)?;
+        write!(f, "{}", self.cost)
+    }
+}
+
+impl fmt::Display for CostEstimate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "  Estimated cost at k = {}:", self.k)?;
+        if !self.fits {
+            writeln!(f, "    circuit does not fit into {} usable rows", self.usable_rows)?;
+        }
+        writeln!(f, "    committed polys     {}", self.committed_polys)?;
+        writeln!(f, "    extended domain     {}", self.extended_rows)?;
+        writeln!(f, "    proving memory      {} MiB", self.proving_memory.div_ceil(1024 * 1024))?;
+        writeln!(f, "    proving time        {} ms", self.proving_time_ms)
+    }
+}
+
+/// [`Assignment`] backend that only records how much of the table a
+/// circuit layout touches.
+#[derive(Default)]
+struct LayoutCounter {
+    rows: u64,
+    advice_cells: u64,
+    fixed_cells: u64,
+    copies: u64,
+}
+
+impl LayoutCounter {
+    fn touch(&mut self, row: usize) {
+        self.rows = self.rows.max(row as u64 + 1);
+    }
+}
+
+impl Assignment<pallas::Base> for LayoutCounter {
+    fn enter_region<NR, N>(&mut self, _name_fn: N)
+    where
+        NR: Into<String>,
+        N: FnOnce() -> NR,
+    {
+    }
+
+    fn exit_region(&mut self) {}
+
+    fn enable_selector<A, AR>(
+        &mut self,
+        _annotation: A,
+        _selector: &Selector,
+        row: usize,
+    ) -> std::result::Result<(), PlonkError>
+    where
+        A: FnOnce() -> AR,
+        AR: Into<String>,
+    {
+        self.touch(row);
+        Ok(())
+    }
+
+    fn query_instance(
+        &self,
+        _column: Column<Instance>,
+        _row: usize,
+    ) -> std::result::Result<Value<pallas::Base>, PlonkError> {
+        Ok(Value::unknown())
+    }
+
+    fn assign_advice<V, VR, A, AR>(
+        &mut self,
+        _annotation: A,
+        _column: Column<Advice>,
+        row: usize,
+        _to: V,
+    ) -> std::result::Result<(), PlonkError>
+    where
+        V: FnOnce() -> Value<VR>,
+        VR: Into<Assigned<pallas::Base>>,
+        A: FnOnce() -> AR,
+        AR: Into<String>,
+    {
+        self.advice_cells += 1;
+        self.touch(row);
+        Ok(())
+    }
+
+    fn assign_fixed<V, VR, A, AR>(
+        &mut self,
+        _annotation: A,
+        _column: Column<Fixed>,
+        row: usize,
+        _to: V,
+    ) -> std::result::Result<(), PlonkError>
+    where
+        V: FnOnce() -> Value<VR>,
+        VR: Into<Assigned<pallas::Base>>,
+        A: FnOnce() -> AR,
+        AR: Into<String>,
+    {
+        self.fixed_cells += 1;
+        self.touch(row);
+        Ok(())
+    }
+
+    fn copy(
+        &mut self,
+        _left_column: Column<Any>,
+        _left_row: usize,
+        _right_column: Column<Any>,
+        _right_row: usize,
+    ) -> std::result::Result<(), PlonkError> {
+        self.copies += 1;
+        Ok(())
+    }
+
+    fn fill_from_row(
+        &mut self,
+        _column: Column<Fixed>,
+        _row: usize,
+        _to: Value<Assigned<pallas::Base>>,
+    ) -> std::result::Result<(), PlonkError> {
+        Ok(())
+    }
+
+    fn push_namespace<NR, N>(&mut self, _name_fn: N)
+    where
+        NR: Into<String>,
+        N: FnOnce() -> NR,
+    {
+    }
+
+    fn pop_namespace(&mut self, _gadget_name: Option<String>) {}
+}
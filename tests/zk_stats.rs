@@ -0,0 +1,67 @@
+/* This file is part of DarkFi (https://dark.fi)
+ *
+ * Copyright (C) 2020-2025 Dyne.org foundation
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as
+ * published by the Free Software Foundation, either version 3 of the
+ * License, or (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use darkfi::{
+    zk::{
+        stats::{CircuitStats, DEFAULT_REGRESSION_TOLERANCE},
+        vm::ZkCircuit,
+        vm_heap::empty_witnesses,
+        VerifyingKey,
+    },
+    zkas::ZkBinary,
+    Result,
+};
+use darkfi_serial::{deserialize, serialize};
+
+#[test]
+fn zk_stats() -> Result<()> {
+    let bincode = include_bytes!("../proof/opcodes.zk.bin");
+    let zkbin = ZkBinary::decode(bincode)?;
+
+    let stats = CircuitStats::measure(&zkbin)?;
+    assert_eq!(stats.k, zkbin.k);
+    assert_eq!(stats.opcodes, zkbin.opcodes.len() as u64);
+    assert_eq!(stats.opcode_counts.iter().map(|(_, c)| c).sum::<u64>(), stats.opcodes);
+    assert!(stats.rows_used > 0);
+    assert!(stats.cost.fits);
+    assert!(stats.min_k <= zkbin.k);
+
+    // The circuit must actually build at the reported minimum k.
+    let circuit = ZkCircuit::new(empty_witnesses(&zkbin)?, &zkbin);
+    let _ = VerifyingKey::build(stats.min_k, &circuit);
+
+    // One below the minimum must not fit, and cost grows with k.
+    assert!(!stats.estimate(stats.min_k - 1).fits);
+    let bigger = stats.estimate(zkbin.k + 1);
+    assert!(bigger.proving_memory > stats.cost.proving_memory);
+    assert!(bigger.proving_time_ms >= stats.cost.proving_time_ms);
+
+    // Baselines roundtrip, and only growth past the tolerance is flagged.
+    let baseline: CircuitStats = deserialize(&serialize(&stats))?;
+    assert_eq!(baseline, stats);
+    assert!(stats.regressions(&baseline, DEFAULT_REGRESSION_TOLERANCE).is_empty());
+
+    let mut shrunk = baseline.clone();
+    shrunk.rows_used /= 2;
+    shrunk.advice_cells = shrunk.advice_cells * 95 / 100;
+    let regressions = stats.regressions(&shrunk, DEFAULT_REGRESSION_TOLERANCE);
+    assert_eq!(regressions.len(), 1);
+    assert_eq!(regressions[0].metric, "rows_used");
+
+    Ok(())
+}